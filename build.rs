@@ -0,0 +1,34 @@
+//! Generates the C header of the `ffi` feature from `src/ffi.rs` into `OUT_DIR`, compared
+//! against the committed `ffi/mpu6050.h` by `tests/ffi.rs`. Without the feature there is
+//! nothing to build.
+
+fn main() {
+    println!("cargo:rerun-if-changed=build.rs");
+    #[cfg(feature = "ffi")]
+    header();
+}
+
+#[cfg(feature = "ffi")]
+fn header() {
+    println!("cargo:rerun-if-changed=src/ffi.rs");
+    let config = cbindgen::Config {
+        language: cbindgen::Language::C,
+        header: Some(
+            "/* C ABI of the mpu6050 crate, see its `ffi` module. Generated by cbindgen from\n \
+             * src/ffi.rs, regenerate with\n \
+             * UPDATE_GOLDENS=1 cargo test --features ffi,test-util --test ffi */"
+                .into(),
+        ),
+        include_guard: Some("MPU6050_H".into()),
+        cpp_compat: true,
+        usize_is_size_t: true,
+        ..Default::default()
+    };
+    let out = std::path::Path::new(&std::env::var("OUT_DIR").unwrap()).join("mpu6050.h");
+    cbindgen::Builder::new()
+        .with_config(config)
+        .with_src("src/ffi.rs")
+        .generate()
+        .expect("C header of src/ffi.rs")
+        .write_to_file(out);
+}
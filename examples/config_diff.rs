@@ -0,0 +1,29 @@
+use mpu6050::config::Mpu6050Config;
+use mpu6050::linux::{I2cdev, Sleep};
+use mpu6050::Mpu6050Builder;
+
+/// Prints the difference between a saved configuration and the device's.
+/// The saved file has one register per line: `0x1b=0x08`
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let path = std::env::args().nth(1).ok_or("usage: config_diff <saved config>")?;
+    let saved = std::fs::read_to_string(path)?;
+    let image: Vec<(u8, u8)> = saved
+        .lines()
+        .filter_map(|line| line.split_once('='))
+        .filter_map(|(addr, value)| Some((parse_hex(addr)?, parse_hex(value)?)))
+        .collect();
+
+    let i2c = I2cdev::open("/dev/i2c-1")?;
+    let mut mpu = Mpu6050Builder::new().i2c(i2c).build()?;
+
+    mpu.init(&mut Sleep)?;
+
+    let current = mpu.read_config()?;
+    println!("{}", Mpu6050Config::from_image(&image).diff(&current));
+
+    Ok(())
+}
+
+fn parse_hex(s: &str) -> Option<u8> {
+    u8::from_str_radix(s.trim().trim_start_matches("0x"), 16).ok()
+}
@@ -0,0 +1,167 @@
+//! Interrupt driven sampling as an embassy task, feature `async`. Runs on the host executor
+//! against a simulated sensor:
+//! `cargo run --example embassy_sampling --features async`
+//!
+//! On a board the task takes the HAL's async I2C and the INT pin as an EXTI input instead,
+//! e.g. `embassy_stm32::i2c::I2c<'static, Async>` and `embassy_stm32::exti::ExtiInput`, and
+//! `embassy_time::Delay` for `init`. The task code stays the same.
+use core::convert::Infallible;
+
+use embassy_executor::Spawner;
+use embedded_hal_1::digital::ErrorType as PinErrorType;
+use embedded_hal_async::delay::DelayNs;
+use embedded_hal_async::digital::Wait;
+use embedded_hal_async::i2c::{ErrorType, I2c, Operation};
+use mpu6050::async_driver::Mpu6050Async;
+use mpu6050::device::*;
+use mpu6050::sampling::SampleControl;
+use mpu6050::synthetic::frame_bytes;
+use mpu6050::Vec3A;
+
+/// samples taken before the task stops
+const SAMPLES: u64 = 1000;
+
+/// The sampling task: init, then every sample from the INT pin until [`SAMPLES`]
+async fn sampling<I: I2c, P: Wait>(
+    mut mpu: Mpu6050Async<I>,
+    mut int: P,
+    delay: &mut impl DelayNs,
+) -> Result<u32, &'static str> {
+    mpu.init(delay).await.map_err(|_| "init failed")?;
+    let mut overruns = 0;
+    mpu.run_sampling_task(&mut int, |sample, meta| {
+        overruns = meta.overruns;
+        if meta.sequence % 250 == 0 {
+            println!(
+                "{}: acc {:?} g, gyro {:?} rad/s{}",
+                meta.sequence,
+                sample.acc(),
+                sample.gyro(),
+                if meta.overrun { ", overrun" } else { "" }
+            );
+        }
+        match meta.sequence + 1 {
+            SAMPLES => SampleControl::Stop,
+            _ => SampleControl::Continue,
+        }
+    })
+    .await
+    .map_err(|_| "sampling failed")?;
+    Ok(overruns)
+}
+
+#[embassy_executor::task]
+async fn imu_task(mpu: Mpu6050Async<SimBus>, int: SimInt) {
+    match sampling(mpu, int, &mut SimDelay).await {
+        Ok(overruns) => println!("{} samples, {} overruns", SAMPLES, overruns),
+        Err(error) => println!("{}", error),
+    }
+    std::process::exit(0);
+}
+
+#[embassy_executor::main]
+async fn main(spawner: Spawner) {
+    spawner.spawn(imu_task(Mpu6050Async::new(SimBus::new()), SimInt).unwrap());
+}
+
+/// Register file of a sensor at rest turning at 45 °/s about Z. A sample is latched for every
+/// burst from INT_STATUS, the pin having been high; every 200th check after the callback finds
+/// the next sample already latched, an overrun
+struct SimBus {
+    regs: [u8; 0x80],
+    checks: u32,
+}
+
+impl SimBus {
+    fn new() -> Self {
+        let mut regs = [0; 0x80];
+        regs[WHOAMI as usize] = 0x68;
+        Self { regs, checks: 0 }
+    }
+
+    fn latch_sample(&mut self) {
+        let frame = frame_bytes(
+            Vec3A::Z,
+            Vec3A::new(0., 0., 45.),
+            AccelRange::G2,
+            GyroRange::D250,
+        );
+        let first = ACC_REGX_H as usize;
+        self.regs[first..first + frame.len()].copy_from_slice(&frame);
+        self.regs[INT_STATUS::ADDR as usize] = 0x01;
+    }
+}
+
+impl ErrorType for SimBus {
+    type Error = Infallible;
+}
+
+impl I2c for SimBus {
+    async fn transaction(
+        &mut self,
+        _address: u8,
+        operations: &mut [Operation<'_>],
+    ) -> Result<(), Infallible> {
+        let status = INT_STATUS::ADDR as usize;
+        let mut reg = 0;
+        for operation in operations {
+            match operation {
+                Operation::Write(bytes) => {
+                    reg = usize::from(bytes[0]);
+                    let data = &bytes[1..];
+                    self.regs[reg..reg + data.len()].copy_from_slice(data);
+                }
+                Operation::Read(buf) => {
+                    if reg == status && buf.len() == 1 {
+                        self.checks += 1;
+                        self.regs[status] = u8::from(self.checks.is_multiple_of(200));
+                    } else if reg == status {
+                        self.latch_sample();
+                    }
+                    buf.copy_from_slice(&self.regs[reg..reg + buf.len()]);
+                    if reg == status {
+                        // read to clear
+                        self.regs[status] = 0;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// INT pin of the simulated sensor, a sample is always ready
+struct SimInt;
+
+impl PinErrorType for SimInt {
+    type Error = Infallible;
+}
+
+impl Wait for SimInt {
+    async fn wait_for_high(&mut self) -> Result<(), Infallible> {
+        Ok(())
+    }
+
+    async fn wait_for_low(&mut self) -> Result<(), Infallible> {
+        Ok(())
+    }
+
+    async fn wait_for_rising_edge(&mut self) -> Result<(), Infallible> {
+        Ok(())
+    }
+
+    async fn wait_for_falling_edge(&mut self) -> Result<(), Infallible> {
+        Ok(())
+    }
+
+    async fn wait_for_any_edge(&mut self) -> Result<(), Infallible> {
+        Ok(())
+    }
+}
+
+/// no wake delay for the simulated sensor
+struct SimDelay;
+
+impl DelayNs for SimDelay {
+    async fn delay_ns(&mut self, _ns: u32) {}
+}
@@ -0,0 +1,103 @@
+//! Probe for the float formatting of the `Display` impls, see `scripts/float-fmt-check.sh`.
+//!
+//! Formats every public `Display` type holding floats that can be built outside the crate.
+//! The binary must link none of core's float formatting: the floats go through
+//! `mpu6050::decimal`, and nothing here formats one itself.
+use std::fmt::Write as _;
+use std::hint::black_box;
+use std::io::Write as _;
+
+use mpu6050::aliasing::{AliasingAssessment, AliasingRisk};
+use mpu6050::bus::RateTooHigh;
+use mpu6050::device::{AccelRange, GyroRange};
+use mpu6050::error_budget::ErrorEstimate;
+use mpu6050::governor::{ActivityMetrics, GovernorTransition, TransitionReason};
+use mpu6050::interleave::{AxisSource, MergedSample};
+use mpu6050::log_header::LogHeader;
+use mpu6050::oscillator::ClockErrorEstimate;
+use mpu6050::{Mpu6050Error, MpuSample, Vec3A};
+
+fn main() {
+    // opaque to the optimizer, formatted at run time
+    let x = black_box(0.125f32);
+    let assessment = AliasingAssessment {
+        risk: AliasingRisk::AliasingLikely,
+        odr_hz: x,
+        accel_rate_hz: x,
+        accel_bandwidth_hz: x,
+        gyro_bandwidth_hz: x,
+        ratio: x,
+        cycle: false,
+    };
+    let mut out = String::new();
+    let _ = writeln!(out, "{}", assessment);
+    let _ = writeln!(out, "{}", Mpu6050Error::<&str>::AliasingLikely(assessment));
+    let _ = writeln!(
+        out,
+        "{}",
+        ErrorEstimate {
+            horizon_s: x,
+            angle_random_walk_deg: x,
+            bias_drift_deg: x,
+            quantization_deg: x,
+            integration_deg: x,
+            total_deg: x,
+        }
+    );
+    let _ = writeln!(
+        out,
+        "{}",
+        ClockErrorEstimate {
+            ratio: x,
+            ratio_low: x,
+            ratio_high: x,
+            nominal_odr_hz: x,
+            effective_odr_hz: x,
+            samples: 1,
+            windows: 1,
+            duration_us: 1,
+        }
+    );
+    let _ = writeln!(
+        out,
+        "{}",
+        MergedSample {
+            sample: MpuSample::new(Vec3A::splat(x), Vec3A::splat(x), x),
+            sources: [AxisSource::Measured(AccelRange::G2); 3],
+            clipped: false,
+        }
+    );
+    let _ = writeln!(
+        out,
+        "{}",
+        GovernorTransition {
+            from: 0,
+            to: 1,
+            from_name: "a",
+            to_name: "b",
+            reason: TransitionReason::Rest,
+            metrics: ActivityMetrics {
+                gyro_rms: x,
+                acc_variance: x,
+                samples: 1,
+            },
+            dwell_ms: x,
+            sample_rate_hz: x,
+            power: None,
+        }
+    );
+    let _ = writeln!(
+        out,
+        "{}",
+        RateTooHigh {
+            required_hz: x,
+            available_hz: x,
+        }
+    );
+    let _ = writeln!(
+        out,
+        "{}",
+        LogHeader::new(0x68, AccelRange::G2, GyroRange::D250)
+    );
+    let _ = std::io::stdout().write_all(out.as_bytes());
+}
@@ -0,0 +1,29 @@
+//! Offline analysis of a recorded log of raw 14 byte frames, using only the hardware
+//! independent layer. Builds for the browser with
+//! `cargo build --example log_analysis --target wasm32-unknown-unknown --no-default-features --features fusion,std`
+use mpu6050::device::*;
+use mpu6050::frame::parse_frames;
+use mpu6050::orientation::ComplementaryFilter;
+use mpu6050::scale::Pipeline;
+use mpu6050::Quat;
+
+/// Orientation after every frame of a log recorded at `dt` s intervals, scaled like the driver
+/// that exported `pipeline` and fused with the default complementary filter
+pub fn analyze(log: &[u8], pipeline: &Pipeline, dt: f32) -> Vec<Quat> {
+    let mut filter = ComplementaryFilter::default();
+    parse_frames(log)
+        .map(|frame| filter.update_sample(&pipeline.sample(&frame), dt))
+        .collect()
+}
+
+fn main() {
+    let path = std::env::args()
+        .nth(1)
+        .expect("usage: log_analysis <log file>");
+    let log = std::fs::read(path).expect("could not read log");
+    let pipeline = Pipeline::new(AccelRange::G2, GyroRange::D250);
+
+    for (i, orientation) in analyze(&log, &pipeline, 0.01).iter().enumerate() {
+        println!("{}: {:?}", i, orientation);
+    }
+}
@@ -1,4 +1,4 @@
-use mpu6050::{*, device::MOT_DETECT_STATUS};
+use mpu6050::{*, device::MOT_DETECT_STATUS, interrupt::MotionDetectionConfig};
 use linux_embedded_hal::{I2cdev, Delay};
 use i2cdev::linux::LinuxI2CError;
 use embedded_hal::blocking::delay::DelayMs;
@@ -11,7 +11,7 @@ fn main() -> Result<(), Mpu6050Error<LinuxI2CError>> {
     let mut mpu = Mpu6050::new(i2c);
     
     mpu.init(&mut delay)?;
-    mpu.setup_motion_detection()?;
+    mpu.setup_motion_detection(MotionDetectionConfig::DEFAULT)?;
 
     let mut count: u8 = 0;
 
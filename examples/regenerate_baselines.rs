@@ -0,0 +1,25 @@
+//! Rewrites `tests/golden/numeric_baseline.txt` from the outputs of this build, for a
+//! deliberate numeric change, listing the drift from the old baseline first. Review the diff
+//! before committing it, see the `numeric_compat` module. Run with
+//! `cargo run --example regenerate_baselines --features test-fixtures`
+use mpu6050::numeric_compat::{self, compare};
+
+fn main() {
+    let report = match numeric_compat::load() {
+        Ok(baseline) => Some(compare(&baseline, &numeric_compat::current())),
+        Err(error) => {
+            println!("no baseline to compare with: {}", error);
+            None
+        }
+    };
+    let path = numeric_compat::regenerate().expect("could not write the baseline");
+    match report {
+        Some(report) if report.is_clean() => println!("{}, rewritten unchanged", report),
+        Some(report) => println!("{}", report),
+        None => {}
+    }
+    println!(
+        "wrote {}, review its diff before committing",
+        path.display()
+    );
+}
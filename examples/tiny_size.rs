@@ -0,0 +1,73 @@
+//! Size probe for the `minimal` feature, see `scripts/size-check.sh`.
+//!
+//! A bootloader style boot mode decision with `TinyMpu`, built as a static library so it
+//! links without a runtime. The bus is provided by the firmware linking it.
+#![no_std]
+
+use embedded_hal::blocking::i2c::{Write, WriteRead};
+use mpu6050::device::DEFAULT_SLAVE_ADDR;
+use mpu6050::tiny::TinyMpu;
+
+extern "C" {
+    fn board_i2c_write(address: u8, bytes: *const u8, len: usize) -> i32;
+    fn board_i2c_write_read(
+        address: u8,
+        bytes: *const u8,
+        len: usize,
+        buffer: *mut u8,
+        buffer_len: usize,
+    ) -> i32;
+    fn board_delay_ms(ms: u32);
+}
+
+struct BoardI2c;
+
+impl Write for BoardI2c {
+    type Error = i32;
+
+    fn write(&mut self, address: u8, bytes: &[u8]) -> Result<(), i32> {
+        match unsafe { board_i2c_write(address, bytes.as_ptr(), bytes.len()) } {
+            0 => Ok(()),
+            err => Err(err),
+        }
+    }
+}
+
+impl WriteRead for BoardI2c {
+    type Error = i32;
+
+    fn write_read(&mut self, address: u8, bytes: &[u8], buffer: &mut [u8]) -> Result<(), i32> {
+        let res = unsafe {
+            board_i2c_write_read(
+                address,
+                bytes.as_ptr(),
+                bytes.len(),
+                buffer.as_mut_ptr(),
+                buffer.len(),
+            )
+        };
+        match res {
+            0 => Ok(()),
+            err => Err(err),
+        }
+    }
+}
+
+/// 1 to enter DFU (face down), 0 to boot normally, negative on bus errors
+#[no_mangle]
+pub extern "C" fn tiny_boot_mode() -> i32 {
+    let mut mpu = TinyMpu::new(BoardI2c, DEFAULT_SLAVE_ADDR);
+    let decide = |mpu: &mut TinyMpu<BoardI2c>| -> Result<i32, i32> {
+        mpu.wake()?;
+        unsafe { board_delay_ms(100) };
+        let [_, _, z] = mpu.read_accel_raw()?;
+        mpu.sleep()?;
+        Ok((z < -13107) as i32)
+    };
+    decide(&mut mpu).unwrap_or_else(|err| -err.abs().max(1))
+}
+
+#[panic_handler]
+fn panic(_: &core::panic::PanicInfo) -> ! {
+    loop {}
+}
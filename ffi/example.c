@@ -0,0 +1,160 @@
+/* The C ABI of the `ffi` feature end to end: a driver over a register file standing in
+ * for the chip, a reading, range changes, a gyro calibration with progress, the filter and
+ * the error codes. Built and run by tests/ffi.rs, by hand:
+ *
+ *   cargo rustc --lib --crate-type staticlib --features ffi
+ *   cc ffi/example.c -I ffi target/debug/libmpu6050.a -lpthread -ldl -lm -o example
+ *
+ * Exits 0 and prints "ok" when every check passed.
+ */
+#include <math.h>
+#include <stdio.h>
+#include <string.h>
+
+#include "mpu6050.h"
+
+#define ADDR 0x68
+#define WHO_AM_I 0x75
+#define PWR_MGMT_1 0x6b
+#define ACCEL_XOUT_H 0x3b
+#define GYRO_XOUT_H 0x43
+
+/* the chip: registers behind an auto incrementing pointer */
+struct chip {
+    uint8_t regs[256];
+    /* status of every transaction, 0 for a working bus */
+    int32_t fail;
+    unsigned delays;
+};
+
+static void chip_reset(struct chip *chip) {
+    memset(chip, 0, sizeof(*chip));
+    chip->regs[WHO_AM_I] = 0x68;
+    chip->regs[PWR_MGMT_1] = 0x40;
+    /* flat and still: 1 g on Z at ±2 g */
+    chip->regs[ACCEL_XOUT_H + 4] = 0x40;
+}
+
+static void chip_set_word(struct chip *chip, uint8_t reg, int16_t value) {
+    chip->regs[reg] = (uint8_t)((uint16_t)value >> 8);
+    chip->regs[reg + 1] = (uint8_t)value;
+}
+
+static int32_t chip_write(void *context, uint8_t address, const uint8_t *bytes, size_t len) {
+    struct chip *chip = context;
+    if (chip->fail != 0) {
+        return chip->fail;
+    }
+    if (address != ADDR) {
+        return -1;
+    }
+    for (size_t i = 1; i < len; i++) {
+        chip->regs[(uint8_t)(bytes[0] + i - 1)] = bytes[i];
+    }
+    return 0;
+}
+
+static int32_t chip_write_read(void *context, uint8_t address, const uint8_t *bytes, size_t len,
+                               uint8_t *buffer, size_t buffer_len) {
+    struct chip *chip = context;
+    int32_t status = chip_write(context, address, bytes, len);
+    if (status != 0 || len == 0) {
+        return status;
+    }
+    for (size_t i = 0; i < buffer_len; i++) {
+        buffer[i] = chip->regs[(uint8_t)(bytes[0] + i)];
+    }
+    return 0;
+}
+
+static void chip_delay_ms(void *context, uint32_t ms) {
+    struct chip *chip = context;
+    (void)ms;
+    chip->delays++;
+}
+
+static void count_progress(void *context, uint16_t taken, uint16_t total) {
+    unsigned *calls = context;
+    (*calls)++;
+    if (taken == total) {
+        printf("calibrated from %u samples\n", (unsigned)taken);
+    }
+}
+
+#define CHECK(cond)                                                     \
+    do {                                                                \
+        if (!(cond)) {                                                  \
+            fprintf(stderr, "%s:%d: check failed: %s\n", __FILE__,      \
+                    __LINE__, #cond);                                   \
+            return 1;                                                   \
+        }                                                               \
+    } while (0)
+
+#define CLOSE(a, b, tolerance) (fabsf((a) - (b)) < (tolerance))
+
+int main(void) {
+    static struct chip chip;
+    chip_reset(&chip);
+    Mpu6050Bus bus = {&chip, chip_write, chip_write_read, chip_delay_ms};
+    Mpu6050Handle *mpu = NULL;
+    Mpu6050Reading reading;
+
+    CHECK(mpu6050_create(&bus, ADDR, &mpu) == MPU6050_OK && mpu != NULL);
+    CHECK(mpu6050_init(mpu) == MPU6050_OK);
+    CHECK(mpu6050_get_all(mpu, &reading) == MPU6050_OK);
+    printf("acc %.3f %.3f %.3f g, temp %.2f C\n", reading.acc_g[0], reading.acc_g[1],
+           reading.acc_g[2], reading.temp_c);
+    CHECK(CLOSE(reading.acc_g[2], 1.f, 1e-4f) && CLOSE(reading.acc_g[0], 0.f, 1e-4f));
+    CHECK(CLOSE(reading.temp_c, 36.53f, 1e-2f));
+
+    /* the same counts read at ±8 g */
+    CHECK(mpu6050_set_accel_range(mpu, 2) == MPU6050_OK);
+    CHECK(mpu6050_get_all(mpu, &reading) == MPU6050_OK);
+    CHECK(CLOSE(reading.acc_g[2], 4.f, 1e-3f));
+    CHECK(mpu6050_set_accel_range(mpu, 0) == MPU6050_OK);
+    CHECK(mpu6050_set_accel_range(mpu, 4) == MPU6050_INVALID_ARGUMENT);
+    CHECK(mpu6050_set_gyro_range(mpu, 0) == MPU6050_OK);
+
+    /* a gyro bias of 1 °/s on X, calibrated away */
+    chip_set_word(&chip, GYRO_XOUT_H, 131);
+    unsigned calls = 0;
+    float offset[3];
+    CHECK(mpu6050_calibrate_gyro(mpu, 50, count_progress, &calls, offset) == MPU6050_OK);
+    CHECK(calls == 50);
+    CHECK(CLOSE(offset[0], 0.0174533f, 1e-5f) && CLOSE(offset[1], 0.f, 1e-6f));
+    CHECK(mpu6050_get_all(mpu, &reading) == MPU6050_OK);
+    CHECK(CLOSE(reading.gyro_rad_s[0], 0.f, 1e-5f));
+    CHECK(chip.delays > 0);
+
+    /* the filter, turning at 90 °/s about Z for 1 s */
+    Mpu6050Filter *filter = mpu6050_filter_create(0.98f);
+    CHECK(filter != NULL);
+    reading.gyro_rad_s[2] = 1.5707963f;
+    Mpu6050Quat q;
+    for (int i = 0; i < 100; i++) {
+        CHECK(mpu6050_filter_update(filter, &reading, 0.01f, &q) == MPU6050_OK);
+    }
+    printf("orientation %.4f %.4f %.4f %.4f\n", q.x, q.y, q.z, q.w);
+    CHECK(CLOSE(q.x * q.x + q.y * q.y + q.z * q.z + q.w * q.w, 1.f, 1e-4f));
+    CHECK(CLOSE(q.z, 0.7071f, 1e-2f) && CLOSE(q.w, 0.7071f, 1e-2f));
+    CHECK(mpu6050_filter_create(1.5f) == NULL);
+    mpu6050_filter_destroy(filter);
+
+    /* errors: the code table of the driver, and the status of the bus */
+    chip.fail = 7;
+    CHECK(mpu6050_get_all(mpu, &reading) == 1);
+    CHECK(mpu6050_last_bus_status(mpu) == 7);
+    chip.fail = 0;
+    chip.regs[WHO_AM_I] = 0x00;
+    CHECK(mpu6050_init(mpu) == 2);
+    CHECK(mpu6050_get_all(NULL, &reading) == MPU6050_NULL_POINTER);
+    CHECK(mpu6050_get_all(mpu, NULL) == MPU6050_NULL_POINTER);
+    Mpu6050Bus incomplete = {&chip, chip_write, NULL, chip_delay_ms};
+    Mpu6050Handle *other = NULL;
+    CHECK(mpu6050_create(&incomplete, ADDR, &other) == MPU6050_NULL_POINTER && other == NULL);
+
+    mpu6050_destroy(mpu);
+    mpu6050_destroy(NULL);
+    puts("ok");
+    return 0;
+}
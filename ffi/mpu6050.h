@@ -0,0 +1,260 @@
+/* C ABI of the mpu6050 crate, see its `ffi` module. Generated by cbindgen from
+ * src/ffi.rs, regenerate with
+ * UPDATE_GOLDENS=1 cargo test --features ffi,test-util --test ffi */
+
+#ifndef MPU6050_H
+#define MPU6050_H
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+/**
+ * Success
+ */
+#define MPU6050_OK 0
+
+/**
+ * A required pointer or callback is NULL
+ */
+#define MPU6050_NULL_POINTER -1
+
+/**
+ * A range value beyond the register field, an invalid address or filter weight
+ */
+#define MPU6050_INVALID_ARGUMENT -2
+
+/**
+ * The call panicked, or an earlier call on the handle did. The handle is poisoned, only
+ * `mpu6050_destroy` is left
+ */
+#define MPU6050_PANICKED -3
+
+/**
+ * Calibration rejected, the samples varied too much and the device probably moved. The
+ * offsets are left as they were
+ */
+#define MPU6050_CALIBRATION_REJECTED -4
+
+/**
+ * A call on a handle from inside one of its callbacks, refused without running. The code of
+ * `Mpu6050Error::Reentrancy`
+ */
+#define MPU6050_REENTERED 22
+
+/**
+ * Opaque filter handle from [`mpu6050_filter_create`]
+ */
+typedef struct Mpu6050Filter Mpu6050Filter;
+
+/**
+ * Opaque driver handle from [`mpu6050_create`]
+ */
+typedef struct Mpu6050Handle Mpu6050Handle;
+
+/**
+ * Writes `len` bytes at `bytes` to the device at the 7-bit `address`, returns 0 or a bus
+ * error status
+ */
+typedef int32_t (*Mpu6050WriteFn)(void *context, uint8_t address, const uint8_t *bytes, size_t len);
+
+/**
+ * Writes `len` bytes at `bytes` to the device at `address`, then reads `buffer_len` bytes
+ * into `buffer` after a repeated start, returns 0 or a bus error status
+ */
+typedef int32_t (*Mpu6050WriteReadFn)(void *context,
+                                      uint8_t address,
+                                      const uint8_t *bytes,
+                                      size_t len,
+                                      uint8_t *buffer,
+                                      size_t buffer_len);
+
+/**
+ * Waits at least `ms` milliseconds
+ */
+typedef void (*Mpu6050DelayMsFn)(void *context, uint32_t ms);
+
+/**
+ * The bus of the host: the context and the callbacks it is passed to. All callbacks are
+ * required, `mpu6050_create` refuses NULL
+ */
+typedef struct Mpu6050Bus {
+  /**
+   * passed to every callback, owned by the host
+   */
+  void *context;
+  /**
+   * plain write
+   */
+  Mpu6050WriteFn write;
+  /**
+   * write then read with a repeated start
+   */
+  Mpu6050WriteReadFn write_read;
+  /**
+   * blocking delay
+   */
+  Mpu6050DelayMsFn delay_ms;
+} Mpu6050Bus;
+
+/**
+ * One burst of the chip in the units of the driver
+ */
+typedef struct Mpu6050Reading {
+  /**
+   * acceleration in g, X, Y, Z
+   */
+  float acc_g[3];
+  /**
+   * rotation rate in rad/s, X, Y, Z
+   */
+  float gyro_rad_s[3];
+  /**
+   * die temperature in °C
+   */
+  float temp_c;
+} Mpu6050Reading;
+
+/**
+ * Called after each calibration sample with the samples `taken` of `total`
+ */
+typedef void (*Mpu6050ProgressFn)(void *context, uint16_t taken, uint16_t total);
+
+/**
+ * Orientation quaternion, Hamilton convention
+ */
+typedef struct Mpu6050Quat {
+  /**
+   * vector part X
+   */
+  float x;
+  /**
+   * vector part Y
+   */
+  float y;
+  /**
+   * vector part Z
+   */
+  float z;
+  /**
+   * scalar part
+   */
+  float w;
+} Mpu6050Quat;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+/**
+ * Creates a driver over `bus` talking to the 7-bit `slave_addr`, 0x68 or 0x69, into
+ * `*out`. No bus contact, call [`mpu6050_init`] next. `*out` is left alone on failure
+ *
+ * Returns [`MPU6050_NULL_POINTER`] for a NULL `bus`, `out` or callback and
+ * [`MPU6050_INVALID_ARGUMENT`] if the builder refuses the address.
+ *
+ * # Safety
+ * `bus` and `out` are NULL or valid. The callbacks may be called with the context from any
+ * call on the handle until [`mpu6050_destroy`]
+ */
+int32_t mpu6050_create(const struct Mpu6050Bus *bus,
+                       uint8_t slave_addr,
+                       struct Mpu6050Handle **out);
+
+/**
+ * Releases a driver handle, NULL is ignored. Nothing is written to the chip
+ *
+ * # Safety
+ * `handle` is NULL or from [`mpu6050_create`], not destroyed before and not in use
+ */
+void mpu6050_destroy(struct Mpu6050Handle *handle);
+
+/**
+ * Wakes and configures the chip like `Mpu6050::init`
+ *
+ * # Safety
+ * `handle` is NULL or a live handle not in use
+ */
+int32_t mpu6050_init(struct Mpu6050Handle *handle);
+
+/**
+ * Reads accel, temperature and gyro in one burst into `*out` like `Mpu6050::get_all`.
+ * `*out` is left alone on failure
+ *
+ * # Safety
+ * `handle` is NULL or a live handle not in use, `out` is NULL or valid
+ */
+int32_t mpu6050_get_all(struct Mpu6050Handle *handle, struct Mpu6050Reading *out);
+
+/**
+ * Sets the accel range by its AFS_SEL value: 0 to 3 for ±2, ±4, ±8 and ±16 g
+ *
+ * # Safety
+ * `handle` is NULL or a live handle not in use
+ */
+int32_t mpu6050_set_accel_range(struct Mpu6050Handle *handle, uint8_t afs_sel);
+
+/**
+ * Sets the gyro range by its FS_SEL value: 0 to 3 for ±250, ±500, ±1000 and ±2000 °/s
+ *
+ * # Safety
+ * `handle` is NULL or a live handle not in use
+ */
+int32_t mpu6050_set_gyro_range(struct Mpu6050Handle *handle, uint8_t fs_sel);
+
+/**
+ * Calibrates the gyro offsets at rest from `samples` samples like
+ * `Mpu6050::calibrate_gyro`. `progress`, if not NULL, is called
+ * with `context` after each sample. The offsets applied, 3 floats in rad/s, are written to
+ * `offset_out` if not NULL
+ *
+ * # Safety
+ * `handle` is NULL or a live handle not in use, `offset_out` is NULL or valid for 3 floats
+ */
+int32_t mpu6050_calibrate_gyro(struct Mpu6050Handle *handle,
+                               uint16_t samples,
+                               Mpu6050ProgressFn progress,
+                               void *context,
+                               float *offset_out);
+
+/**
+ * Last nonzero status a bus callback returned, 0 before any
+ *
+ * # Safety
+ * `handle` is NULL or a live handle not in use
+ */
+int32_t mpu6050_last_bus_status(const struct Mpu6050Handle *handle);
+
+/**
+ * Creates a complementary filter at identity with gyro weight `alpha` per update, see
+ * [`ComplementaryFilter::new`]. NULL if `alpha` is not within 0 to 1
+ */
+struct Mpu6050Filter *mpu6050_filter_create(float alpha);
+
+/**
+ * One filter step with `reading` over `dt_s` seconds, the new orientation into `*out`, see
+ * [`ComplementaryFilter::update`]
+ *
+ * # Safety
+ * `filter` is NULL or a live filter not in use, `reading` and `out` are NULL or valid
+ */
+int32_t mpu6050_filter_update(struct Mpu6050Filter *filter,
+                              const struct Mpu6050Reading *reading,
+                              float dt_s,
+                              struct Mpu6050Quat *out);
+
+/**
+ * Releases a filter handle, NULL is ignored
+ *
+ * # Safety
+ * `filter` is NULL or from [`mpu6050_filter_create`], not destroyed before and not in use
+ */
+void mpu6050_filter_destroy(struct Mpu6050Filter *filter);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* MPU6050_H */
@@ -0,0 +1,81 @@
+#!/usr/bin/env python3
+# Reference vectors of eMPL's scaling on the MPU6050, for tests/conformance.rs.
+#
+# A transcription of the formulas of InvenSense Motion Driver 5.1 in float32, not captures
+# from a device or from the legacy firmware: sensitivities of mpu_get_accel_sens and
+# mpu_get_gyro_sens, mpu_get_temperature with temp_offset -521 and temp_sens 340, the
+# mounting matrix applied to the scaled counts before the biases, outputs as q16 longs
+# truncated toward zero. Replace the file with vectors logged from the firmware being
+# replaced, same columns, once they exist.
+#
+#   scripts/empl-vectors.py > tests/golden/empl_vectors.txt
+import random
+import struct
+
+ACCEL_SENS = {2: 16384.0, 4: 8192.0, 8: 4096.0, 16: 2048.0}
+GYRO_SENS = {250: 131.0, 500: 65.5, 1000: 32.8, 2000: 16.4}
+MOUNTINGS = ["+X+Y+Z", "-Y+X+Z", "+Y-X+Z", "+X-Y-Z", "+Z+X+Y", "-Z-Y-X"]
+
+
+def f32(x):
+    return struct.unpack("<f", struct.pack("<f", x))[0]
+
+
+def mount(mapping, v):
+    out = []
+    for i in range(3):
+        sign, axis = mapping[2 * i], "XYZ".index(mapping[2 * i + 1])
+        out.append(-v[axis] if sign == "-" else v[axis])
+    return out
+
+
+def q16(x):
+    # (long)(x * 65536L), the cast truncates
+    return int(f32(x * 65536.0))
+
+
+def scaled(raw, sens, mapping, bias):
+    units = [f32(f32(r) / f32(sens)) for r in raw]
+    return [q16(f32(u + b)) for u, b in zip(mount(mapping, units), bias)]
+
+
+def temperature(raw):
+    return q16(f32(35.0 + f32(f32(raw - f32(-521.0)) / f32(340.0))))
+
+
+def main():
+    rng = random.Random(6050)
+    extremes = [-32768, -32767, -1, 0, 1, 32767]
+    print("# generated by scripts/empl-vectors.py, formulas transcribed from eMPL, see there")
+    print("# accel_g gyro_dps mounting | acc_bias_g x3 | gyro_bias_dps x3 | acc x3 | gyro x3 |"
+          " temp | acc_q16 x3 | gyro_q16 x3 | temp_q16")
+    for row in range(32):
+        ag = list(ACCEL_SENS)[row % 4]
+        gd = list(GYRO_SENS)[(row // 4) % 4]
+        mapping = MOUNTINGS[row % len(MOUNTINGS)]
+        # dyadic biases, exact in the text and in f32
+        acc_bias = [rng.randint(-256, 256) / 4096 for _ in range(3)]
+        gyro_bias = [rng.randint(-512, 512) / 128 for _ in range(3)]
+        if row < len(extremes):
+            acc = [extremes[row]] * 3
+            gyro = [extremes[-1 - row]] * 3
+            temp = extremes[row]
+        else:
+            acc = [rng.randint(-32768, 32767) for _ in range(3)]
+            gyro = [rng.randint(-32768, 32767) for _ in range(3)]
+            temp = rng.randint(-8000, 4000)
+        cols = [
+            f"{ag} {gd} {mapping}",
+            " ".join(repr(b) for b in acc_bias),
+            " ".join(repr(b) for b in gyro_bias),
+            " ".join(map(str, acc)),
+            " ".join(map(str, gyro)),
+            str(temp),
+            " ".join(map(str, scaled(acc, ACCEL_SENS[ag], mapping, acc_bias))),
+            " ".join(map(str, scaled(gyro, GYRO_SENS[gd], mapping, gyro_bias))),
+            str(temperature(temp)),
+        ]
+        print(" | ".join(cols))
+
+
+main()
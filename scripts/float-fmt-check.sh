@@ -0,0 +1,22 @@
+#!/bin/sh
+# Float formatting check of the `Display` impls, see the `decimal` module.
+#
+# Builds the `float_free` example, which formats every public `Display` type holding floats,
+# and fails if its binary links any of core's float formatting (`core::fmt::float`).
+#
+#   scripts/float-fmt-check.sh
+set -eu
+
+cd "$(dirname "$0")/.."
+CARGO_TARGET_DIR=target/float-fmt-check \
+    cargo build --quiet --release --example float_free
+
+BIN="target/float-fmt-check/release/examples/float_free"
+FOUND=$(nm -C --defined-only "$BIN" | grep -c "core::fmt::float" || true)
+
+echo "float_free: $FOUND symbols of core's float formatting"
+if [ "$FOUND" -gt 0 ]; then
+    nm -C --defined-only "$BIN" | grep "core::fmt::float" >&2
+    echo "a Display impl formats floats with core, use mpu6050::decimal" >&2
+    exit 1
+fi
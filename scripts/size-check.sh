@@ -0,0 +1,30 @@
+#!/bin/sh
+# Flash size check of the `minimal` feature, see the `tiny` module for the target.
+#
+# Builds the `tiny_size` example as a static library and sums the code of its own object
+# file: the TinyMpu calls and the boot mode decision, without core intrinsics.
+#
+#   scripts/size-check.sh [target]    default target thumbv6m-none-eabi
+set -eu
+
+TARGET="${1:-thumbv6m-none-eabi}"
+LIMIT=512
+
+cd "$(dirname "$0")/.."
+CARGO_TARGET_DIR=target/size-check \
+RUSTFLAGS="-C panic=abort -C opt-level=z" \
+    cargo build --quiet --release --example tiny_size \
+    --no-default-features --features minimal --target "$TARGET"
+
+LIB="target/size-check/$TARGET/release/examples/libtiny_size.a"
+SIZE=$(nm -S -t d --defined-only "$LIB" 2>/dev/null | awk '
+    /^tiny_size-.*\.o:$/ { own = 1; next }
+    /\.o:$/ { own = 0; next }
+    own && $3 ~ /^[Tt]$/ { total += $2 }
+    END { print total + 0 }')
+
+echo "tiny_size on $TARGET: $SIZE bytes of code (limit $LIMIT)"
+if [ "$SIZE" -gt "$LIMIT" ]; then
+    echo "minimal build over its flash size target" >&2
+    exit 1
+fi
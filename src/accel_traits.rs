@@ -0,0 +1,127 @@
+//! The driver behind the [`accelerometer`] crate's traits.
+//!
+//! [`Accelerometer`] reads through [`get_acc`](Mpu6050::get_acc): g after offsets and scale
+//! factors, under the [`SettlingPolicy`](crate::settling::SettlingPolicy).
+//! [`sample_rate`](Accelerometer::sample_rate) is the accelerometer output rate of the cached
+//! configuration, the output data rate capped at the 1 kHz of the accelerometer, or the wake
+//! frequency in cycle mode. No bus access.
+//! [`RawAccelerometer<I16x3>`](RawAccelerometer) is [`get_acc_raw`](Mpu6050::get_acc_raw).
+//!
+//! The trait errors are the driver's [`Mpu6050Error`] as cause, classified by [`error_kind`].
+//! Only these impls need `E: Debug`, the driver itself keeps its bounds.
+//!
+//! ```
+//! use mpu6050::accelerometer::Accelerometer;
+//! use mpu6050::Mpu6050Builder;
+//! # use embedded_hal::blocking::i2c::{Write, WriteRead};
+//! # struct Bus;
+//! # impl Write for Bus {
+//! #     type Error = ();
+//! #     fn write(&mut self, _: u8, _: &[u8]) -> Result<(), ()> { Ok(()) }
+//! # }
+//! # impl WriteRead for Bus {
+//! #     type Error = ();
+//! #     fn write_read(&mut self, _: u8, reg: &[u8], buf: &mut [u8]) -> Result<(), ()> {
+//! #         // flat on a table, +1 g on Z at ±2 g
+//! #         buf.fill(0);
+//! #         if reg == [0x3b] && buf.len() == 6 {
+//! #             buf[4] = 0x40;
+//! #         }
+//! #         Ok(())
+//! #     }
+//! # }
+//!
+//! /// true when the sensor lies flat, for any accelerometer
+//! fn is_flat(sensor: &mut impl Accelerometer) -> bool {
+//!     match sensor.accel_norm() {
+//!         Ok(acc) => acc.z > 0.95 && acc.x.abs() < 0.05 && acc.y.abs() < 0.05,
+//!         Err(_) => false,
+//!     }
+//! }
+//!
+//! let mut mpu = Mpu6050Builder::new().i2c(Bus).build().unwrap();
+//! assert!(is_flat(&mut mpu));
+//! // DLPF off: 8 kHz output data rate, the accelerometer at 1 kHz
+//! assert_eq!(mpu.sample_rate().unwrap(), 1000.);
+//! ```
+
+use core::fmt::Debug;
+
+use accelerometer::vector::{F32x3, I16x3};
+use accelerometer::{Accelerometer, Error, ErrorKind, RawAccelerometer};
+use embedded_hal::blocking::i2c::{Write, WriteRead};
+
+use crate::device::LowPowerWakeFrequency;
+use crate::{Mpu6050, Mpu6050Error};
+
+/// Kind of a driver error for the [`accelerometer`] crate:
+/// * [`Bus`](ErrorKind::Bus): the I2C transaction failed or the sensor is disconnected
+/// * [`Device`](ErrorKind::Device): the chip answered but is not the chip or not in the state
+///   expected, a wrong chip id, a missing capability, an implausible frame
+/// * [`Mode`](ErrorKind::Mode): the driver's state refuses the call, dormant, streaming,
+///   busy, without a delay or with a stale derived value
+/// * [`Param`](ErrorKind::Param): the arguments or the configuration are refused
+pub fn error_kind<E>(error: &Mpu6050Error<E>) -> ErrorKind {
+    #[allow(deprecated)]
+    match error {
+        Mpu6050Error::I2c(_) | Mpu6050Error::Disconnected => ErrorKind::Bus,
+        Mpu6050Error::InvalidChipId(_)
+        | Mpu6050Error::StaleExtDataSlot
+        | Mpu6050Error::Unsupported(_)
+        | Mpu6050Error::ImplausibleFrame { .. }
+        | Mpu6050Error::WakeOnMotionRefused(_) => ErrorKind::Device,
+        Mpu6050Error::StaleFifoSchema
+        | Mpu6050Error::StaleConfiguration { .. }
+        | Mpu6050Error::Aborted { .. }
+        | Mpu6050Error::DelayRequired
+        | Mpu6050Error::FifoActive
+        | Mpu6050Error::NotActivated
+        | Mpu6050Error::Reentrancy => ErrorKind::Mode,
+        Mpu6050Error::ExtDataOverflow(_)
+        | Mpu6050Error::BufferTooSmall(_)
+        | Mpu6050Error::InvalidSettings(_)
+        | Mpu6050Error::AliasingLikely(_)
+        | Mpu6050Error::InvalidTimestamp(_)
+        | Mpu6050Error::InvalidRegisterAccess { .. }
+        | Mpu6050Error::BoardConstraint(_)
+        | Mpu6050Error::DeniedRegisterRange(_)
+        | Mpu6050Error::BusRateTooHigh(_) => ErrorKind::Param,
+    }
+}
+
+fn trait_error<E: Debug>(error: Mpu6050Error<E>) -> Error<Mpu6050Error<E>> {
+    Error::new_with_cause(error_kind(&error), error)
+}
+
+impl<I, D, E> Accelerometer for Mpu6050<I, D>
+where
+    I: Write<Error = E> + WriteRead<Error = E>,
+    E: Debug,
+{
+    type Error = Mpu6050Error<E>;
+
+    fn accel_norm(&mut self) -> Result<F32x3, Error<Self::Error>> {
+        let acc = self.get_acc().map_err(trait_error)?;
+        Ok(F32x3::new(acc.x, acc.y, acc.z))
+    }
+
+    fn sample_rate(&mut self) -> Result<f32, Error<Self::Error>> {
+        Ok(match self.cycle {
+            Some(wake) => LowPowerWakeFrequency::from(wake).centi_hz() as f32 / 100.,
+            None => self.check_aliasing().accel_rate_hz,
+        })
+    }
+}
+
+impl<I, D, E> RawAccelerometer<I16x3> for Mpu6050<I, D>
+where
+    I: Write<Error = E> + WriteRead<Error = E>,
+    E: Debug,
+{
+    type Error = Mpu6050Error<E>;
+
+    fn accel_raw(&mut self) -> Result<I16x3, Error<Self::Error>> {
+        let [x, y, z] = self.get_acc_raw().map_err(trait_error)?;
+        Ok(I16x3::new(x, y, z))
+    }
+}
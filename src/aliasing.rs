@@ -0,0 +1,161 @@
+//! Aliasing risk of a rate and filter configuration.
+//!
+//! The DLPF is the only anti-aliasing filter in front of the output data rate (ODR). With a
+//! large SMPLRT_DIV and a wide DLPF, signal above half the ODR folds back as low frequency
+//! noise. [`assess`] compares the rate each sensor is sampled at against its DLPF bandwidth
+//! from [`DLPF_BANDWIDTH_HZ`]:
+//! * the gyro is sampled at the ODR
+//! * the accelerometer at the ODR, at most its 1 kHz output rate
+//!
+//! The smaller of the two ratios rate / bandwidth decides:
+//! * at least [`OK_RATIO`] (2.5): [`AliasingRisk::Ok`], the filter has rolled off well below
+//!   half the rate
+//! * at least [`MARGINAL_RATIO`] (2): [`AliasingRisk::Marginal`], Nyquist is met but the
+//!   filter transition band aliases
+//! * below: [`AliasingRisk::AliasingLikely`]
+//!
+//! Cycle mode takes single accelerometer samples at the wake frequency for quasi-static
+//! sensing and is not assessed, it reports `Ok` with `cycle` set.
+//!
+//! The driver tracks the assessment of its configuration, see
+//! [`Mpu6050::check_aliasing`](crate::Mpu6050::check_aliasing). The result is advisory
+//! unless strict configuration is enabled
+//! ([`Mpu6050::set_strict_configuration`](crate::Mpu6050::set_strict_configuration)): then
+//! `apply_settings`, `set_dlpf` and `set_sample_rate_divider` refuse configurations assessed
+//! [`AliasingRisk::AliasingLikely`] with
+//! [`Mpu6050Error::AliasingLikely`](crate::Mpu6050Error::AliasingLikely), before writing
+//! anything.
+//! ```
+//! use mpu6050::aliasing::{assess, AliasingRisk};
+//!
+//! // DLPF off, 8 kHz / 16 = 500 Hz: the 260 Hz accel bandwidth aliases
+//! assert_eq!(assess(0, 15, false).risk, AliasingRisk::AliasingLikely);
+//! // 20 Hz DLPF at 100 Hz
+//! assert_eq!(assess(4, 9, false).risk, AliasingRisk::Ok);
+//! ```
+
+use core::fmt;
+
+use crate::decimal::Decimal;
+use crate::device::DLPF_BANDWIDTH_HZ;
+use crate::settings::{Mpu6050Settings, GYRO_OUTPUT_RATE_DLPF_OFF_HZ, GYRO_OUTPUT_RATE_DLPF_ON_HZ};
+use crate::{Mpu6050, Mpu6050Error};
+
+/// Minimum rate / bandwidth ratio assessed [`AliasingRisk::Ok`]
+pub const OK_RATIO: f32 = 2.5;
+/// Minimum rate / bandwidth ratio assessed [`AliasingRisk::Marginal`]
+pub const MARGINAL_RATIO: f32 = 2.0;
+/// Accelerometer output rate in Hz, the ODR cannot sample it faster
+pub const ACCEL_OUTPUT_RATE_HZ: f32 = 1000.;
+
+/// Classification of [`assess`], ordered from best to worst
+#[derive(Copy, Clone, Debug, Eq, PartialEq, PartialOrd, Ord)]
+pub enum AliasingRisk {
+    /// rate at least [`OK_RATIO`] times the bandwidth
+    Ok,
+    /// rate at least [`MARGINAL_RATIO`] times the bandwidth
+    Marginal,
+    /// rate below [`MARGINAL_RATIO`] times the bandwidth
+    AliasingLikely,
+}
+
+/// Result of [`assess`] with the numbers it is based on
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct AliasingAssessment {
+    /// classification
+    pub risk: AliasingRisk,
+    /// output data rate in Hz, the gyro sampling rate
+    pub odr_hz: f32,
+    /// accelerometer sampling rate in Hz
+    pub accel_rate_hz: f32,
+    /// accelerometer DLPF bandwidth in Hz
+    pub accel_bandwidth_hz: f32,
+    /// gyro DLPF bandwidth in Hz
+    pub gyro_bandwidth_hz: f32,
+    /// smaller of the two rate / bandwidth ratios
+    pub ratio: f32,
+    /// cycle mode, not assessed
+    pub cycle: bool,
+}
+
+impl fmt::Display for AliasingAssessment {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.cycle {
+            return f.write_str("cycle mode, not assessed");
+        }
+        write!(
+            f,
+            "{:?}: ODR {} Hz, accel {} Hz over {} Hz bandwidth, gyro {} Hz bandwidth, ratio {:.2}",
+            self.risk,
+            Decimal(self.odr_hz),
+            Decimal(self.accel_rate_hz),
+            Decimal(self.accel_bandwidth_hz),
+            Decimal(self.gyro_bandwidth_hz),
+            Decimal(self.ratio)
+        )
+    }
+}
+
+/// Assesses DLPF_CFG `dlpf_cfg` at SMPLRT_DIV `sample_rate_div`, see the module docs
+pub fn assess(dlpf_cfg: u8, sample_rate_div: u8, cycle: bool) -> AliasingAssessment {
+    let (accel_bandwidth_hz, gyro_bandwidth_hz) = DLPF_BANDWIDTH_HZ[(dlpf_cfg & 0x07) as usize];
+    let output_rate = match dlpf_cfg & 0x07 {
+        0 | 7 => GYRO_OUTPUT_RATE_DLPF_OFF_HZ,
+        _ => GYRO_OUTPUT_RATE_DLPF_ON_HZ,
+    };
+    let odr_hz = output_rate / (1. + sample_rate_div as f32);
+    let accel_rate_hz = odr_hz.min(ACCEL_OUTPUT_RATE_HZ);
+    let ratio = (accel_rate_hz / accel_bandwidth_hz).min(odr_hz / gyro_bandwidth_hz);
+    let risk = if cycle || ratio >= OK_RATIO {
+        AliasingRisk::Ok
+    } else if ratio >= MARGINAL_RATIO {
+        AliasingRisk::Marginal
+    } else {
+        AliasingRisk::AliasingLikely
+    };
+    AliasingAssessment {
+        risk,
+        odr_hz,
+        accel_rate_hz,
+        accel_bandwidth_hz,
+        gyro_bandwidth_hz,
+        ratio,
+        cycle,
+    }
+}
+
+impl Mpu6050Settings {
+    /// aliasing risk of these settings, see [`aliasing`](crate::aliasing)
+    pub fn check_aliasing(&self) -> AliasingAssessment {
+        assess(self.dlpf_cfg, self.sample_rate_div, self.cycle.is_some())
+    }
+}
+
+impl<I, D> Mpu6050<I, D> {
+    /// aliasing risk of the DLPF, sample rate divider and cycle mode last written by the
+    /// driver, the reset values if it never wrote them. No bus access
+    pub fn check_aliasing(&self) -> AliasingAssessment {
+        assess(self.dlpf_cfg, self.sample_rate_div, self.cycle.is_some())
+    }
+
+    /// refuse configurations likely to alias instead of only assessing them
+    pub fn set_strict_configuration(&mut self, strict: bool) {
+        self.strict_configuration = strict;
+    }
+
+    /// get strict configuration
+    pub fn get_strict_configuration(&self) -> bool {
+        self.strict_configuration
+    }
+
+    /// Err in strict configuration if `assessment` is likely to alias
+    pub(crate) fn refuse_aliasing<E>(
+        &self,
+        assessment: AliasingAssessment,
+    ) -> Result<(), Mpu6050Error<E>> {
+        if self.strict_configuration && assessment.risk == AliasingRisk::AliasingLikely {
+            return Err(Mpu6050Error::AliasingLikely(assessment));
+        }
+        Ok(())
+    }
+}
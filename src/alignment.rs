@@ -0,0 +1,445 @@
+//! Dynamic estimation of the residual mounting misalignment between sensor and body frame.
+//!
+//! An [`AxisMap`] takes the chip axes onto the body frame of a vehicle in steps of 90°. The
+//! bracket, the board and the housing leave a rotation of a few degrees on top, and on a
+//! vehicle that cannot be leveled precisely it cannot be measured at rest.
+//! [`DynamicAlignmentEstimator`] estimates it from samples taken during ordinary driving, in
+//! the axes of the map, with the caller assisting: every sample comes with the [`Maneuver`]
+//! the vehicle is known to perform, from wheel speed, GPS or an operator.
+//!
+//! #### Model
+//! The misalignment is the rotation vector `θ` (rad, about body X, Y and Z) of the rotation
+//! `R` taking the mapped sensor axes onto the body axes. For small angles
+//! `R v ≈ v + θ × v`, linear in `θ`. Two constraints give rows `h · θ = r` of a least squares
+//! problem:
+//! * [`Maneuver::QuasiStatic`]: the accelerometer reads gravity alone, on body Z. For the
+//!   measured direction `u` the rotated X and Y components vanish:
+//!   `u_x + θ_y u_z - θ_z u_y = 0` and `u_y + θ_z u_x - θ_x u_z = 0`. This constrains
+//!   `θ_x` and `θ_y`, roll and pitch
+//! * [`Maneuver::Longitudinal`]: accelerating or braking in a straight line, the reading
+//!   minus the gravity of the last quasi-static segment is along body X. For its direction
+//!   `d` the Y component vanishes: `d_y + θ_z d_x - θ_x d_z = 0`. This constrains `θ_z`,
+//!   yaw. The Z component is not used, the body pitches on its suspension
+//!
+//! Each row is weighted by the sample interval and the squared magnitude in g of the
+//! vector it comes from, the direction of a small acceleration being noisier. The
+//! [`excitation`](AlignmentProgress::excitation) of an axis is the information on it with
+//! the others marginalized, in g²·s: a second of quasi-static data adds about 1 to roll and
+//! pitch, a second at 0.2 g of longitudinal acceleration 0.04 to yaw.
+//!
+//! #### Gating
+//! The maneuver is a claim the estimator checks. A quasi-static sample counts after
+//! [`min_still_s`](AlignmentConfig::min_still_s) of continuous stillness, the gyro and the
+//! magnitude of the accelerometer within their thresholds. A longitudinal sample counts with
+//! the gyro below [`straight_gyro_rad_s`](AlignmentConfig::straight_gyro_rad_s), at least
+//! [`min_longitudinal_g`](AlignmentConfig::min_longitudinal_g) and within
+//! [`max_reference_age_s`](AlignmentConfig::max_reference_age_s) of the last quasi-static
+//! sample. [`Maneuver::Other`], turns among them, constrains nothing: lateral acceleration
+//! and lean do not separate from the misalignment.
+//!
+//! #### Small angles
+//! A sample whose direction is more than [`max_angle_rad`](AlignmentConfig::max_angle_rad)
+//! from body Z, or the acceleration from body X, is rejected: the linearization no longer
+//! holds, or the sample is not what the maneuver claims. The error of the linearization
+//! is of second order in `θ`: the synthetic drives of the tests recover 6° within 0.2° per
+//! axis. [`solve`](DynamicAlignmentEstimator::solve) refuses a solution beyond the bound, a
+//! mounting to be fixed with a different [`AxisMap`].
+//!
+//! #### Result
+//! [`solve`](DynamicAlignmentEstimator::solve) is pure and returns a result only when every
+//! axis reached [`min_excitation`](AlignmentConfig::min_excitation) and the RMS residual of
+//! the rows stays within [`max_residual_rad`](AlignmentConfig::max_residual_rad). Road
+//! grade and camber at the stops read as roll and pitch: stops on varied ground averages
+//! them out. The [`AlignmentEstimate`] is a rotation, applied to the readings after the
+//! axis map, or composed with it into one matrix from the chip axes with
+//! [`mounting_matrix`](AlignmentEstimate::mounting_matrix).
+//! ```
+//! use mpu6050::alignment::{AlignmentConfig, AlignmentError, DynamicAlignmentEstimator, Maneuver};
+//! use mpu6050::glam::Vec3;
+//! use mpu6050::{Quat, Vec3A};
+//!
+//! // 2° of pitch and 3° of yaw between the sensor and the body
+//! let misalignment = Quat::from_scaled_axis(Vec3::new(0., 0.035, 0.052));
+//! let sensor = |body: Vec3A| misalignment.inverse() * body;
+//! let mut estimator = DynamicAlignmentEstimator::new(AlignmentConfig::default());
+//! for _ in 0..300 {
+//!     estimator.feed_readings(sensor(Vec3A::Z), Vec3A::ZERO, 0.01, Maneuver::QuasiStatic);
+//! }
+//! // yaw is not observable at rest
+//! assert!(matches!(estimator.solve(), Err(AlignmentError::InsufficientExcitation(_))));
+//! for _ in 0..1500 {
+//!     let braking = Vec3A::new(-0.3, 0., 1.);
+//!     estimator.feed_readings(sensor(braking), Vec3A::ZERO, 0.01, Maneuver::Longitudinal);
+//! }
+//! let estimate = estimator.solve().unwrap();
+//! assert!((estimate.rotation_vector - Vec3A::new(0., 0.035, 0.052)).length() < 1e-3);
+//! ```
+
+use core::fmt;
+
+use glam::{Mat3A, Quat, Vec3A};
+
+use crate::axis_map::AxisMap;
+#[cfg(not(feature = "std"))]
+use crate::math::FloatExt;
+use crate::MpuSample;
+
+/// Relative weight of the regularization making the marginal information of an
+/// unconstrained axis finite
+const REGULARIZATION: f32 = 1e-6;
+
+/// What the vehicle does during a sample, as known to the caller
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Maneuver {
+    /// stopped, or at constant speed in a straight line: gravity alone
+    QuasiStatic,
+    /// accelerating or braking in a straight line
+    Longitudinal,
+    /// turning, or anything else, constrains nothing
+    Other,
+}
+
+/// Options of a [`DynamicAlignmentEstimator`]
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct AlignmentConfig {
+    /// gyro magnitudes above this (rad/s) are not quasi-static, default 0.03
+    pub still_gyro_rad_s: f32,
+    /// accel magnitudes further than this from 1g are not quasi-static, default 0.03
+    pub still_acc_g: f32,
+    /// continuous stillness before quasi-static samples count, in s, default 1
+    pub min_still_s: f32,
+    /// gyro magnitudes above this (rad/s) are not straight, default 0.05
+    pub straight_gyro_rad_s: f32,
+    /// smallest longitudinal acceleration counted in g, default 0.1
+    pub min_longitudinal_g: f32,
+    /// age of the gravity reference beyond which longitudinal samples are rejected, in s,
+    /// default 20
+    pub max_reference_age_s: f32,
+    /// largest angle of a sample from its body axis and of the solution, in rad, default 10°
+    pub max_angle_rad: f32,
+    /// excitation every axis needs before solving, in g²·s, default 1
+    pub min_excitation: f32,
+    /// largest RMS residual of a solution in rad, default 0.05
+    pub max_residual_rad: f32,
+}
+
+impl Default for AlignmentConfig {
+    fn default() -> Self {
+        Self {
+            still_gyro_rad_s: 0.03,
+            still_acc_g: 0.03,
+            min_still_s: 1.,
+            straight_gyro_rad_s: 0.05,
+            min_longitudinal_g: 0.1,
+            max_reference_age_s: 20.,
+            max_angle_rad: 10. * crate::PI_180,
+            min_excitation: 1.,
+            max_residual_rad: 0.05,
+        }
+    }
+}
+
+/// What the estimator has seen so far
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct AlignmentProgress {
+    /// quasi-static time accepted in s
+    pub quasi_static_s: f32,
+    /// longitudinal time accepted in s
+    pub longitudinal_s: f32,
+    /// samples rejected by the gating or the small angle bound
+    pub rejected: u32,
+    /// information per rotation axis in g²·s, the others marginalized
+    pub excitation: Vec3A,
+    /// axes at [`min_excitation`](AlignmentConfig::min_excitation): roll, pitch, yaw
+    pub constrained: [bool; 3],
+}
+
+/// Residual misalignment solved by [`DynamicAlignmentEstimator::solve`]
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct AlignmentEstimate {
+    /// rotation vector from the mapped sensor axes to the body axes in rad
+    pub rotation_vector: Vec3A,
+    /// RMS residual of the rows in rad
+    pub residual_rms_rad: f32,
+    /// information per rotation axis in g²·s
+    pub excitation: Vec3A,
+}
+
+impl AlignmentEstimate {
+    /// the rotation from the mapped sensor axes to the body axes
+    pub fn quat(&self) -> Quat {
+        Quat::from_scaled_axis(self.rotation_vector.into())
+    }
+
+    /// the rotation as a matrix, `body = M · mapped`
+    pub fn matrix(&self) -> Mat3A {
+        Mat3A::from_quat(self.quat())
+    }
+
+    /// `v` in the mapped sensor axes to the body axes
+    pub fn apply(&self, v: Vec3A) -> Vec3A {
+        self.quat() * v
+    }
+
+    /// chip axes to body axes, `map` then the residual rotation
+    pub fn mounting_matrix(&self, map: AxisMap) -> Mat3A {
+        let mapped = Mat3A::from_cols(
+            map.apply(Vec3A::X),
+            map.apply(Vec3A::Y),
+            map.apply(Vec3A::Z),
+        );
+        self.matrix() * mapped
+    }
+}
+
+/// Why [`DynamicAlignmentEstimator::solve`] returned no estimate
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum AlignmentError {
+    /// an axis has not reached the minimum excitation
+    InsufficientExcitation(AlignmentProgress),
+    /// the solution is beyond the small angle bound, in rad
+    NotSmall {
+        /// angle of the solution
+        angle_rad: f32,
+    },
+    /// the rows disagree beyond the residual bound, in rad
+    Inconsistent {
+        /// RMS residual of the solution
+        residual_rms_rad: f32,
+    },
+}
+
+impl fmt::Display for AlignmentError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AlignmentError::InsufficientExcitation(progress) => {
+                const AXES: [&str; 3] = ["roll", "pitch", "yaw"];
+                f.write_str("not enough excitation of")?;
+                let missing = AXES.iter().zip(progress.constrained).filter(|(_, c)| !c);
+                for (axis, _) in missing {
+                    write!(f, " {}", axis)?;
+                }
+                Ok(())
+            }
+            AlignmentError::NotSmall { angle_rad } => write!(
+                f,
+                "misalignment of {:.1}° is not small, fix the axis map",
+                angle_rad / crate::PI_180
+            ),
+            AlignmentError::Inconsistent { residual_rms_rad } => write!(
+                f,
+                "residual of {:.3} rad RMS, the maneuvers disagree",
+                residual_rms_rad
+            ),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for AlignmentError {}
+
+/// Least squares estimator of the residual mounting rotation, see the [module docs](self)
+#[derive(Copy, Clone, Debug)]
+pub struct DynamicAlignmentEstimator {
+    config: AlignmentConfig,
+    information: Mat3A,
+    rhs: Vec3A,
+    residual_sq: f32,
+    weight: f32,
+    still_s: f32,
+    reference_sum: Vec3A,
+    reference_weight: f32,
+    since_reference_s: f32,
+    quasi_static_s: f32,
+    longitudinal_s: f32,
+    rejected: u32,
+}
+
+impl DynamicAlignmentEstimator {
+    /// new estimator without statistics
+    pub fn new(config: AlignmentConfig) -> Self {
+        Self {
+            config,
+            information: Mat3A::ZERO,
+            rhs: Vec3A::ZERO,
+            residual_sq: 0.,
+            weight: 0.,
+            still_s: 0.,
+            reference_sum: Vec3A::ZERO,
+            reference_weight: 0.,
+            since_reference_s: f32::INFINITY,
+            quasi_static_s: 0.,
+            longitudinal_s: 0.,
+            rejected: 0,
+        }
+    }
+
+    /// options in use
+    pub fn config(&self) -> &AlignmentConfig {
+        &self.config
+    }
+
+    /// drops the statistics, keeping the options
+    pub fn reset(&mut self) {
+        *self = Self::new(self.config);
+    }
+
+    /// gravity of the last quasi-static segment in g, mapped sensor axes
+    pub fn gravity_reference(&self) -> Option<Vec3A> {
+        (self.reference_weight > 0.).then(|| self.reference_sum / self.reference_weight)
+    }
+
+    /// Feeds a sample taken `dt_s` after the previous one during `maneuver`. True if it
+    /// was accumulated
+    pub fn feed(&mut self, sample: &MpuSample, dt_s: f32, maneuver: Maneuver) -> bool {
+        self.feed_readings(sample.acc(), sample.gyro(), dt_s, maneuver)
+    }
+
+    /// [`feed`](Self::feed) with accel in g and gyro in rad/s
+    pub fn feed_readings(
+        &mut self,
+        acc: Vec3A,
+        gyro: Vec3A,
+        dt_s: f32,
+        maneuver: Maneuver,
+    ) -> bool {
+        if dt_s.is_nan() || dt_s <= 0. {
+            return false;
+        }
+        self.since_reference_s += dt_s;
+        if maneuver != Maneuver::QuasiStatic {
+            self.still_s = 0.;
+        }
+        let accepted = match maneuver {
+            Maneuver::QuasiStatic => match self.quasi_static(acc, gyro, dt_s) {
+                Some(accepted) => accepted,
+                None => return false,
+            },
+            Maneuver::Longitudinal => self.longitudinal(acc, gyro, dt_s),
+            Maneuver::Other => return false,
+        };
+        if !accepted {
+            self.rejected = self.rejected.saturating_add(1);
+        }
+        accepted
+    }
+
+    /// gravity rows, None while the stillness settles
+    fn quasi_static(&mut self, acc: Vec3A, gyro: Vec3A, dt_s: f32) -> Option<bool> {
+        let magnitude = acc.length();
+        let still = gyro.length() <= self.config.still_gyro_rad_s
+            && (magnitude - 1.).abs() <= self.config.still_acc_g;
+        if !still {
+            self.still_s = 0.;
+            return Some(false);
+        }
+        if self.still_s < self.config.min_still_s {
+            self.still_s += dt_s;
+            // a new segment, the reference restarts with it
+            self.reference_sum = Vec3A::ZERO;
+            self.reference_weight = 0.;
+            return None;
+        }
+        let u = acc / magnitude;
+        if u.z < self.max_angle_cos() {
+            return Some(false);
+        }
+        self.reference_sum += acc * dt_s;
+        self.reference_weight += dt_s;
+        self.since_reference_s = 0.;
+        let weight = dt_s * magnitude * magnitude;
+        self.add_row(Vec3A::new(0., u.z, -u.y), -u.x, weight);
+        self.add_row(Vec3A::new(-u.z, 0., u.x), -u.y, weight);
+        self.quasi_static_s += dt_s;
+        Some(true)
+    }
+
+    /// longitudinal row against the gravity reference
+    fn longitudinal(&mut self, acc: Vec3A, gyro: Vec3A, dt_s: f32) -> bool {
+        let reference = match self.gravity_reference() {
+            Some(reference) if self.since_reference_s <= self.config.max_reference_age_s => {
+                reference
+            }
+            _ => return false,
+        };
+        if gyro.length() > self.config.straight_gyro_rad_s {
+            return false;
+        }
+        let dynamic = acc - reference;
+        let magnitude = dynamic.length();
+        if magnitude < self.config.min_longitudinal_g {
+            return false;
+        }
+        let d = dynamic / magnitude;
+        if d.x.abs() < self.max_angle_cos() {
+            return false;
+        }
+        self.add_row(
+            Vec3A::new(-d.z, 0., d.x),
+            -d.y,
+            dt_s * magnitude * magnitude,
+        );
+        self.longitudinal_s += dt_s;
+        true
+    }
+
+    fn max_angle_cos(&self) -> f32 {
+        self.config.max_angle_rad.sin_cos().1
+    }
+
+    fn add_row(&mut self, h: Vec3A, r: f32, weight: f32) {
+        self.information += Mat3A::from_cols(h * h.x, h * h.y, h * h.z) * weight;
+        self.rhs += h * (r * weight);
+        self.residual_sq += r * r * weight;
+        self.weight += weight;
+    }
+
+    /// time accepted, rejections and the excitation per axis
+    pub fn progress(&self) -> AlignmentProgress {
+        let regularization =
+            Mat3A::from_diagonal(Vec3A::splat(REGULARIZATION * self.weight.max(1.)).into());
+        let covariance = (self.information + regularization).inverse();
+        let excitation = Vec3A::new(
+            covariance.x_axis.x,
+            covariance.y_axis.y,
+            covariance.z_axis.z,
+        )
+        .recip();
+        let minimum = self.config.min_excitation;
+        AlignmentProgress {
+            quasi_static_s: self.quasi_static_s,
+            longitudinal_s: self.longitudinal_s,
+            rejected: self.rejected,
+            excitation,
+            constrained: [
+                excitation.x >= minimum,
+                excitation.y >= minimum,
+                excitation.z >= minimum,
+            ],
+        }
+    }
+
+    /// The least squares misalignment of the samples so far, refused without enough
+    /// excitation of every axis, beyond the small angle bound or with inconsistent rows
+    pub fn solve(&self) -> Result<AlignmentEstimate, AlignmentError> {
+        let progress = self.progress();
+        if progress.constrained.contains(&false) {
+            return Err(AlignmentError::InsufficientExcitation(progress));
+        }
+        let theta = self.information.inverse() * self.rhs;
+        let angle_rad = theta.length();
+        if angle_rad.is_nan() || angle_rad > self.config.max_angle_rad {
+            return Err(AlignmentError::NotSmall { angle_rad });
+        }
+        let residual_rms_rad =
+            ((self.residual_sq - theta.dot(self.rhs)).max(0.) / self.weight).sqrt();
+        if residual_rms_rad > self.config.max_residual_rad {
+            return Err(AlignmentError::Inconsistent { residual_rms_rad });
+        }
+        Ok(AlignmentEstimate {
+            rotation_vector: theta,
+            residual_rms_rad,
+            excitation: progress.excitation,
+        })
+    }
+}
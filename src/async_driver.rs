@@ -0,0 +1,349 @@
+//! Async variant of the driver over `embedded-hal-async`, feature `async`.
+//!
+//! [`Mpu6050Async`] has the everyday API of [`Mpu6050`](crate::Mpu6050) as `async fn`s on an
+//! [`I2c`] bus, for executors like embassy where a blocking transaction stalls every other
+//! task: `init` with an async [`DelayNs`] for the wake delay, the scaled reads, the tilt
+//! angles, the range and basic power accessors and the raw register helpers. The registers
+//! come from [`device`](crate::device), the field arithmetic from the same bit helpers and
+//! the scaling from [`Pipeline`], so readings are the blocking driver's for the same
+//! register contents.
+//!
+//! [`run_sampling_task`](Mpu6050Async::run_sampling_task) is the interrupt driven loop of
+//! the [`sampling`](crate::sampling) module awaiting the INT pin through
+//! [`Wait`], with the same overrun detection; `examples/embassy_sampling.rs` runs it as an
+//! embassy task.
+//!
+//! The rest of the blocking driver, settling, FIFO, calibration, recovery and the other
+//! stateful layers, stays blocking for now. The async driver keeps the ranges and the bus,
+//! nothing else, so it builds without `std` like the hardware independent layer.
+//! ```
+//! use embedded_hal_async::delay::DelayNs;
+//! use embedded_hal_async::i2c::I2c;
+//! use mpu6050::async_driver::Mpu6050Async;
+//! use mpu6050::Mpu6050Error;
+//!
+//! async fn tilt_task<I: I2c>(
+//!     i2c: I,
+//!     delay: &mut impl DelayNs,
+//! ) -> Result<(), Mpu6050Error<I::Error>> {
+//!     let mut mpu = Mpu6050Async::new(i2c);
+//!     mpu.init(delay).await?;
+//!     loop {
+//!         let angles = mpu.get_acc_angles().await?;
+//!         let temp = mpu.get_temp().await?;
+//!         # let _ = (angles, temp);
+//!         delay.delay_ms(100).await;
+//!     }
+//! }
+//! ```
+
+use embedded_hal_async::delay::DelayNs;
+use embedded_hal_async::digital::Wait;
+use embedded_hal_async::i2c::I2c;
+use glam::{Quat, Vec3A};
+
+use crate::bits;
+use crate::device::{
+    self, AccelRange, ChipIdCheck, GyroRange, ACCEL_CONFIG, ACCEL_HPF, ACC_REGX_H, CLKSEL,
+    DEFAULT_SLAVE_ADDR, GYRO_CONFIG, GYRO_REGX_H, INT_ENABLE, INT_PIN_CFG, INT_STATUS,
+    PWR_MGMT_1, TEMP_OUT_H, WHOAMI,
+};
+use crate::frame::{self, FRAME_LEN};
+use crate::register::{Field, FieldValue, RegisterSpec, RegisterValue};
+use crate::sampling::{OverrunDetector, SampleControl, SampleMeta, SamplingError};
+use crate::scale::Pipeline;
+use crate::{euler, tilt, Mpu6050Error, MpuSample};
+
+/// MPU6050 on an async I2C bus
+pub struct Mpu6050Async<I> {
+    i2c: I,
+    slave_addr: u8,
+    chip_id_check: ChipIdCheck,
+    pipeline: Pipeline,
+}
+
+impl<I: I2c> Mpu6050Async<I> {
+    /// sensor at [`DEFAULT_SLAVE_ADDR`], no bus access
+    pub fn new(i2c: I) -> Self {
+        Self::new_with_addr(i2c, DEFAULT_SLAVE_ADDR)
+    }
+
+    /// sensor at `slave_addr`, no bus access
+    pub fn new_with_addr(i2c: I, slave_addr: u8) -> Self {
+        Self {
+            i2c,
+            slave_addr,
+            chip_id_check: ChipIdCheck::Mpu6050,
+            pipeline: Pipeline::new(AccelRange::G2, GyroRange::D250),
+        }
+    }
+
+    /// WHO_AM_I values `init` accepts, see [`ChipIdCheck`]
+    pub fn with_chip_id_check(mut self, check: ChipIdCheck) -> Self {
+        self.chip_id_check = check;
+        self
+    }
+
+    /// Verifies WHO_AM_I, wakes the sensor with the X gyro clock, waits 100 ms and sets
+    /// ±2g, ±250 °/s and the high pass filter reset, like the blocking driver's `init`
+    pub async fn init(&mut self, delay: &mut impl DelayNs) -> Result<(), Mpu6050Error<I::Error>> {
+        // WHO_AM_I reads while asleep, nothing is written to a chip that is refused
+        let who_am_i = self.read_byte(WHOAMI).await?;
+        if !self.chip_id_check.accepts(who_am_i) {
+            return Err(Mpu6050Error::InvalidChipId(who_am_i));
+        }
+        self.write_byte(PWR_MGMT_1::ADDR, CLKSEL::GXAXIS as u8)
+            .await?;
+        delay.delay_ms(100).await;
+        self.set_accel_range(AccelRange::G2).await?;
+        self.set_gyro_range(GyroRange::D250).await?;
+        self.set_accel_hpf(ACCEL_HPF::_RESET).await
+    }
+
+    /// sets the accel range, and the sensitivity of the readings
+    pub async fn set_accel_range(
+        &mut self,
+        range: AccelRange,
+    ) -> Result<(), Mpu6050Error<I::Error>> {
+        self.write_field(ACCEL_CONFIG::FS_SEL_FIELD, range).await?;
+        self.pipeline.acc_scale.nominal = range.sensitivity();
+        Ok(())
+    }
+
+    /// accel range read from the device
+    pub async fn get_accel_range(&mut self) -> Result<AccelRange, Mpu6050Error<I::Error>> {
+        let byte = self.read_field(ACCEL_CONFIG::FS_SEL_FIELD).await?;
+        Ok(AccelRange::from(byte))
+    }
+
+    /// sets the gyro range, and the sensitivity of the readings
+    pub async fn set_gyro_range(&mut self, range: GyroRange) -> Result<(), Mpu6050Error<I::Error>> {
+        self.write_field(GYRO_CONFIG::FS_SEL_FIELD, range).await?;
+        self.pipeline.gyro_scale.nominal = range.sensitivity();
+        Ok(())
+    }
+
+    /// gyro range read from the device
+    pub async fn get_gyro_range(&mut self) -> Result<GyroRange, Mpu6050Error<I::Error>> {
+        let byte = self.read_field(GYRO_CONFIG::FS_SEL_FIELD).await?;
+        Ok(GyroRange::from(byte))
+    }
+
+    /// sets the accel high pass filter mode
+    pub async fn set_accel_hpf(&mut self, mode: ACCEL_HPF) -> Result<(), Mpu6050Error<I::Error>> {
+        self.write_field(ACCEL_CONFIG::ACCEL_HPF_FIELD, mode).await
+    }
+
+    /// accel high pass filter mode read from the device
+    pub async fn get_accel_hpf(&mut self) -> Result<ACCEL_HPF, Mpu6050Error<I::Error>> {
+        let mode = self.read_field(ACCEL_CONFIG::ACCEL_HPF_FIELD).await?;
+        Ok(ACCEL_HPF::from(mode))
+    }
+
+    /// sets or clears sleep
+    pub async fn set_sleep_enabled(&mut self, enable: bool) -> Result<(), Mpu6050Error<I::Error>> {
+        self.write_field(PWR_MGMT_1::SLEEP_FIELD, enable).await
+    }
+
+    /// sleep bit read from the device
+    pub async fn get_sleep_enabled(&mut self) -> Result<bool, Mpu6050Error<I::Error>> {
+        Ok(self.read_field(PWR_MGMT_1::SLEEP_FIELD).await? != 0)
+    }
+
+    /// Roll and pitch from the accelerometer, see
+    /// [`Mpu6050::get_acc_angles`](crate::Mpu6050::get_acc_angles)
+    pub async fn get_acc_angles(&mut self) -> Result<Quat, Mpu6050Error<I::Error>> {
+        let (roll, pitch) = tilt::roll_pitch(self.get_acc().await?);
+        Ok(euler::from_xyz_rpy(roll, pitch, 0.0))
+    }
+
+    /// accelerometer readings in g
+    pub async fn get_acc(&mut self) -> Result<Vec3A, Mpu6050Error<I::Error>> {
+        let raw = self.read_triple(ACC_REGX_H).await?;
+        Ok(self.pipeline.acc(raw))
+    }
+
+    /// gyro readings in rad/s
+    pub async fn get_gyro(&mut self) -> Result<Vec3A, Mpu6050Error<I::Error>> {
+        let raw = self.read_triple(GYRO_REGX_H).await?;
+        Ok(self.pipeline.gyro(raw))
+    }
+
+    /// sensor temperature in degrees celsius
+    pub async fn get_temp(&mut self) -> Result<f32, Mpu6050Error<I::Error>> {
+        let mut buf = [0; 2];
+        self.read_bytes(TEMP_OUT_H, &mut buf).await?;
+        Ok(self.pipeline.temp(i16::from_be_bytes(buf)))
+    }
+
+    /// writes `byte` to `reg`
+    pub async fn write_byte(&mut self, reg: u8, byte: u8) -> Result<(), Mpu6050Error<I::Error>> {
+        self.i2c
+            .write(self.slave_addr, &[reg, byte])
+            .await
+            .map_err(Mpu6050Error::I2c)
+    }
+
+    /// sets or clears bit `bit_n` of `reg`, read, modify, write
+    pub async fn write_bit(
+        &mut self,
+        reg: u8,
+        bit_n: u8,
+        enable: bool,
+    ) -> Result<(), Mpu6050Error<I::Error>> {
+        let mut byte = self.read_byte(reg).await?;
+        bits::set_bit(&mut byte, bit_n, enable);
+        self.write_byte(reg, byte).await
+    }
+
+    /// writes `data` to the `length` bits of `reg` ending at `start_bit`, read, modify, write
+    pub async fn write_bits(
+        &mut self,
+        reg: u8,
+        start_bit: u8,
+        length: u8,
+        data: u8,
+    ) -> Result<(), Mpu6050Error<I::Error>> {
+        let mut byte = self.read_byte(reg).await?;
+        bits::set_bits(&mut byte, start_bit, length, data);
+        self.write_byte(reg, byte).await
+    }
+
+    /// bit `bit_n` of `reg`
+    pub async fn read_bit(&mut self, reg: u8, bit_n: u8) -> Result<u8, Mpu6050Error<I::Error>> {
+        Ok(bits::get_bit(self.read_byte(reg).await?, bit_n))
+    }
+
+    /// the `length` bits of `reg` ending at `start_bit`
+    pub async fn read_bits(
+        &mut self,
+        reg: u8,
+        start_bit: u8,
+        length: u8,
+    ) -> Result<u8, Mpu6050Error<I::Error>> {
+        Ok(bits::get_bits(
+            self.read_byte(reg).await?,
+            start_bit,
+            length,
+        ))
+    }
+
+    /// byte of `reg`
+    pub async fn read_byte(&mut self, reg: u8) -> Result<u8, Mpu6050Error<I::Error>> {
+        let mut byte = [0; 1];
+        self.read_bytes(reg, &mut byte).await?;
+        Ok(byte[0])
+    }
+
+    /// `buf.len()` bytes from `reg` on in one transaction, refused for the
+    /// [`DENIED_RANGES`](crate::device::DENIED_RANGES)
+    pub async fn read_bytes(
+        &mut self,
+        reg: u8,
+        buf: &mut [u8],
+    ) -> Result<(), Mpu6050Error<I::Error>> {
+        if let Some(range) = device::denied_range(reg, buf.len()) {
+            return Err(Mpu6050Error::DeniedRegisterRange(range));
+        }
+        self.i2c
+            .write_read(self.slave_addr, &[reg], buf)
+            .await
+            .map_err(Mpu6050Error::I2c)
+    }
+
+    /// Enables data ready interrupts on an active high, push-pull INT pin latched until
+    /// INT_STATUS is read, then awaits `int_pin` high, reads the sample and passes it to
+    /// `on_sample`, until the callback returns [`SampleControl::Stop`]. The interrupt stays
+    /// enabled when the task returns.
+    ///
+    /// Transactions per sample: one burst of INT_STATUS..GYRO_ZOUT_L, which clears the pin,
+    /// and after the callback an INT_STATUS read for the [`OverrunDetector`]. DATA_RDY set in
+    /// it means a sample was missed and the next one is there: the pin is not awaited, the
+    /// following sample comes flagged with [`overrun`](SampleMeta::overrun)
+    pub async fn run_sampling_task<P, F>(
+        &mut self,
+        int_pin: &mut P,
+        mut on_sample: F,
+    ) -> Result<(), SamplingError<I::Error, P::Error>>
+    where
+        P: Wait,
+        F: FnMut(MpuSample, SampleMeta) -> SampleControl,
+    {
+        self.enable_data_ready_latch()
+            .await
+            .map_err(SamplingError::Device)?;
+        let mut detector = OverrunDetector::new();
+        let mut ready = false;
+        loop {
+            if !ready {
+                int_pin.wait_for_high().await.map_err(SamplingError::Wait)?;
+            }
+            let mut buf = [0; 1 + FRAME_LEN];
+            self.read_bytes(INT_STATUS::ADDR, &mut buf)
+                .await
+                .map_err(SamplingError::Device)?;
+            let mut bytes = [0; FRAME_LEN];
+            bytes.copy_from_slice(&buf[1..]);
+            let sample = self.pipeline.sample(&frame::parse_frame(&bytes));
+            if on_sample(sample, detector.next_meta()) == SampleControl::Stop {
+                return Ok(());
+            }
+            let status = self
+                .read_byte(INT_STATUS::ADDR)
+                .await
+                .map_err(SamplingError::Device)?;
+            detector.after_callback(status);
+            ready = RegisterValue::<INT_STATUS>::from_byte(status)
+                .flag(INT_STATUS::DATA_RDY_INT_FIELD);
+        }
+    }
+
+    /// returns the bus
+    pub fn release(self) -> I {
+        self.i2c
+    }
+
+    /// DATA_RDY_EN on INT, active high, push-pull, latched until INT_STATUS is read
+    async fn enable_data_ready_latch(&mut self) -> Result<(), Mpu6050Error<I::Error>> {
+        let byte = self.read_byte(INT_PIN_CFG::ADDR).await?;
+        let config = RegisterValue::<INT_PIN_CFG>::from_byte(byte)
+            .with(INT_PIN_CFG::INT_LEVEL_FIELD, false)
+            .with(INT_PIN_CFG::INT_OPEN_FIELD, false)
+            .with(INT_PIN_CFG::LATCH_INT_EN_FIELD, true)
+            .with(INT_PIN_CFG::INT_RD_CLEAR_FIELD, false);
+        self.write_byte(INT_PIN_CFG::ADDR, config.byte()).await?;
+        self.write_field(INT_ENABLE::DATA_RDY_EN_FIELD, true).await
+    }
+
+    /// value of `field`
+    async fn read_field<R: RegisterSpec>(
+        &mut self,
+        field: Field<R>,
+    ) -> Result<u8, Mpu6050Error<I::Error>> {
+        let byte = self.read_byte(R::REGISTER.addr()).await?;
+        Ok(RegisterValue::<R>::from_byte(byte).field(field))
+    }
+
+    /// read, modify, write of `field`
+    async fn write_field<R: RegisterSpec>(
+        &mut self,
+        field: Field<R>,
+        value: impl Into<FieldValue<R>>,
+    ) -> Result<(), Mpu6050Error<I::Error>> {
+        let reg = R::REGISTER.addr();
+        let byte = self.read_byte(reg).await?;
+        let value = RegisterValue::<R>::from_byte(byte).with(field, value);
+        self.write_byte(reg, value.byte()).await
+    }
+
+    /// X, Y, Z of the big endian register pairs from `reg_h` on
+    async fn read_triple(&mut self, reg_h: u8) -> Result<[i16; 3], Mpu6050Error<I::Error>> {
+        let mut buf = [0; 6];
+        self.read_bytes(reg_h, &mut buf).await?;
+        Ok([
+            i16::from_be_bytes([buf[0], buf[1]]),
+            i16::from_be_bytes([buf[2], buf[3]]),
+            i16::from_be_bytes([buf[4], buf[5]]),
+        ])
+    }
+}
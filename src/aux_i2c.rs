@@ -0,0 +1,231 @@
+//! Auxiliary I2C master: slave configuration and EXT_SENS_DATA bookkeeping.
+//!
+//! Packing rule (register map, EXT_SENS_DATA_00 to 23): data read from slaves 0 to 3 is stored
+//! in slave number order, each enabled read slave occupying `len` consecutive bytes starting
+//! right after the previous one. Disabled slaves, write slaves and slaves with length 0 take no
+//! space, so (re)configuring one slave moves the data of every higher numbered slave. Slave 4
+//! never uses EXT_SENS_DATA. The total of 24 bytes must not be exceeded, the chip would
+//! silently drop the excess.
+//!
+//! [`ExtDataSlot`] handles carry the layout generation they were created in and are rejected
+//! by [`Mpu6050::read_ext_slot`] once any slave configuration changed.
+
+#[cfg(feature = "driver")]
+use crate::config_epoch::ConfigChange;
+use crate::device::{Capability, EXT_SENS_DATA_00, EXT_SENS_DATA_LEN, USER_CTRL};
+use crate::register::{Register, RegisterValue, SlaveRegisterSpec, I2C_SLV_ADDR, I2C_SLV_CTRL};
+use crate::{Mpu6050, Mpu6050Error};
+#[cfg(feature = "driver")]
+use embedded_hal::blocking::i2c::{Write, WriteRead};
+
+/// Aux slaves that read into EXT_SENS_DATA
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum SlaveSlot {
+    /// I2C_SLV0
+    Slv0 = 0,
+    /// I2C_SLV1
+    Slv1 = 1,
+    /// I2C_SLV2
+    Slv2 = 2,
+    /// I2C_SLV3
+    Slv3 = 3,
+}
+
+impl SlaveSlot {
+    /// all slots, in packing order
+    pub const ALL: [SlaveSlot; 4] = [
+        SlaveSlot::Slv0,
+        SlaveSlot::Slv1,
+        SlaveSlot::Slv2,
+        SlaveSlot::Slv3,
+    ];
+
+    /// I2C_SLVn_ADDR, I2C_SLVn_REG and I2C_SLVn_CTRL
+    pub fn registers(self) -> [Register; 3] {
+        Register::i2c_slv(self as u8).expect("slaves 0 to 3")
+    }
+}
+
+/// Transfer performed by an aux slave every sample
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct SlaveConfig {
+    /// 7 bit I2C address of the aux device
+    pub address: u8,
+    /// first register to transfer
+    pub register: u8,
+    /// number of bytes, 0 to 15
+    pub len: u8,
+    /// read from the aux device (stored in EXT_SENS_DATA) instead of writing to it
+    pub read: bool,
+    /// swap the bytes of each word
+    pub byte_swap: bool,
+}
+
+/// Location of a slave's data in EXT_SENS_DATA
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct ExtDataSlot {
+    /// slave the data belongs to
+    pub slave: SlaveSlot,
+    /// offset from EXT_SENS_DATA_00
+    pub offset: u8,
+    /// number of bytes
+    pub len: u8,
+    generation: u32,
+}
+
+/// Offset and length in EXT_SENS_DATA per slave, None if the slave stores no data.
+/// Err with the total length if the data does not fit into the 24 registers
+pub fn ext_data_layout(slaves: &[Option<SlaveConfig>; 4]) -> Result<[Option<(u8, u8)>; 4], u8> {
+    let mut layout = [None; 4];
+    let mut offset: u8 = 0;
+    for (slot, slave) in layout.iter_mut().zip(slaves) {
+        if let Some(slave) = slave.filter(|s| s.read && s.len > 0) {
+            *slot = Some((offset, slave.len));
+            offset += slave.len;
+        }
+    }
+    if offset > EXT_SENS_DATA_LEN {
+        return Err(offset);
+    }
+    Ok(layout)
+}
+
+/// Configured aux slaves and layout generation
+#[derive(Copy, Clone, Debug, Default)]
+pub(crate) struct AuxState {
+    slaves: [Option<SlaveConfig>; 4],
+    generation: u32,
+}
+
+impl AuxState {
+    pub(crate) fn slaves(&self) -> [Option<SlaveConfig>; 4] {
+        self.slaves
+    }
+
+    pub(crate) fn generation(&self) -> u32 {
+        self.generation
+    }
+
+    fn slots(&self) -> impl Iterator<Item = ExtDataSlot> {
+        let layout = ext_data_layout(&self.slaves).unwrap_or([None; 4]);
+        let generation = self.generation;
+        SlaveSlot::ALL
+            .into_iter()
+            .zip(layout)
+            .filter_map(move |(slave, entry)| {
+                entry.map(|(offset, len)| ExtDataSlot {
+                    slave,
+                    offset,
+                    len,
+                    generation,
+                })
+            })
+    }
+}
+
+#[cfg(feature = "driver")]
+impl<I, D, E> Mpu6050<I, D>
+where
+    I: Write<Error = E> + WriteRead<Error = E>,
+{
+    /// enable, disable the aux I2C master (USER_CTRL I2C_MST_EN). Enabling fails with
+    /// [`Mpu6050Error::Unsupported`] on chips without one
+    pub fn set_i2c_master_enabled(&mut self, enable: bool) -> Result<(), Mpu6050Error<E>> {
+        if enable {
+            self.require(Capability::AuxI2cMaster)?;
+        }
+        self.write_field(USER_CTRL::I2C_MST_EN_FIELD, enable)
+    }
+
+    /// Configures and enables an aux slave. Returns where its data is stored, None for write
+    /// slaves. All previously returned slots become stale
+    pub fn configure_i2c_slave(
+        &mut self,
+        slot: SlaveSlot,
+        config: SlaveConfig,
+    ) -> Result<Option<ExtDataSlot>, Mpu6050Error<E>> {
+        self.require(Capability::AuxI2cMaster)?;
+        let mut slaves = self.aux.slaves;
+        slaves[slot as usize] = Some(config);
+        if let Err(len) = ext_data_layout(&slaves) {
+            return Err(Mpu6050Error::ExtDataOverflow(len));
+        }
+
+        let addr = RegisterValue::<I2C_SLV_ADDR>::ZERO
+            .with(I2C_SLV_ADDR::ADDR_FIELD, config.address)
+            .with(I2C_SLV_ADDR::RW_FIELD, config.read);
+        let ctrl = RegisterValue::<I2C_SLV_CTRL>::ZERO
+            .with(I2C_SLV_CTRL::EN_FIELD, true)
+            .with(I2C_SLV_CTRL::BYTE_SW_FIELD, config.byte_swap)
+            .with(I2C_SLV_CTRL::LEN_FIELD, config.len);
+
+        let [_, reg_reg, _] = slot.registers();
+        // disable while changing address and register
+        self.write_slave_fields(slot, RegisterValue::<I2C_SLV_CTRL>::ZERO)?;
+        self.aux.slaves[slot as usize] = None;
+        self.aux.generation = self.aux.generation.wrapping_add(1);
+        self.bump_epoch(ConfigChange::AuxSlaves);
+        self.write_slave_fields(slot, addr)?;
+        self.write_register(reg_reg, config.register)?;
+        self.write_slave_fields(slot, ctrl)?;
+        self.aux.slaves = slaves;
+
+        Ok(self.aux.slots().find(|s| s.slave == slot))
+    }
+
+    /// Disables an aux slave. All previously returned slots become stale
+    pub fn disable_i2c_slave(&mut self, slot: SlaveSlot) -> Result<(), Mpu6050Error<E>> {
+        self.modify_slave_fields::<I2C_SLV_CTRL>(slot, |ctrl| {
+            ctrl.with(I2C_SLV_CTRL::EN_FIELD, false)
+        })?;
+        self.aux.slaves[slot as usize] = None;
+        self.aux.generation = self.aux.generation.wrapping_add(1);
+        self.bump_epoch(ConfigChange::AuxSlaves);
+        Ok(())
+    }
+
+    /// writes the content of `R` of `slot`
+    fn write_slave_fields<R: SlaveRegisterSpec>(
+        &mut self,
+        slot: SlaveSlot,
+        value: RegisterValue<R>,
+    ) -> Result<(), Mpu6050Error<E>> {
+        let reg = R::register(slot as u8).expect("slaves 0 to 3");
+        self.write_register(reg, value.byte())
+    }
+
+    /// [`modify_fields`](Self::modify_fields) of `R` of `slot`
+    fn modify_slave_fields<R: SlaveRegisterSpec>(
+        &mut self,
+        slot: SlaveSlot,
+        f: impl FnOnce(RegisterValue<R>) -> RegisterValue<R>,
+    ) -> Result<(), Mpu6050Error<E>> {
+        let reg = R::register(slot as u8).expect("slaves 0 to 3");
+        self.modify_register(reg, |byte| f(RegisterValue::from_byte(byte)).byte())?;
+        Ok(())
+    }
+
+    /// Current EXT_SENS_DATA layout, in offset order
+    pub fn ext_data_layout(&self) -> impl Iterator<Item = ExtDataSlot> {
+        self.aux.slots()
+    }
+
+    /// Reads the data of `slot` into the first `slot.len` bytes of `buf`
+    pub fn read_ext_slot(
+        &mut self,
+        slot: &ExtDataSlot,
+        buf: &mut [u8],
+    ) -> Result<(), Mpu6050Error<E>> {
+        self.check_active()?;
+        if slot.generation != self.aux.generation {
+            return Err(Mpu6050Error::StaleExtDataSlot);
+        }
+        let len = slot.len as usize;
+        if buf.len() < len {
+            return Err(Mpu6050Error::BufferTooSmall(len));
+        }
+        // the slot lies within EXT_SENS_DATA, see `configure_i2c_slave`
+        let first = Register::known(EXT_SENS_DATA_00 + slot.offset);
+        self.read_registers(first, &mut buf[..len])
+    }
+}
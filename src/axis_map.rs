@@ -0,0 +1,224 @@
+//! Mapping of the chip axes onto a mounting frame.
+//!
+//! A sensor mounted rotated reports its readings in its own axes. An [`AxisMap`] picks, for
+//! each axis of the common frame, the chip axis pointing along it and whether it points the
+//! opposite way. Only 90° mountings can be expressed, arbitrary ones need a rotation.
+//! [`AxisMap::ROTATIONS`] lists the 24 of them that are proper rotations, all maps are
+//! const-constructible for board tables in flash.
+//!
+//! For configuration files a rotation has a name, the signed chip axis of each common axis
+//! in order, and a matrix form, `out = M · in` with one ±1 per row:
+//! ```
+//! use mpu6050::axis_map::{AxisMap, AxisMapError};
+//! use mpu6050::device::Axis;
+//!
+//! let turned = AxisMap::from_name("-Y+X+Z").unwrap();
+//! assert_eq!(turned, AxisMap::new([Axis::Y, Axis::X, Axis::Z], [true, false, false]));
+//! assert_eq!(AxisMap::from_matrix([[0, -1, 0], [1, 0, 0], [0, 0, 1]]), Ok(turned));
+//! assert_eq!(turned.to_string(), "-Y+X+Z");
+//! assert_eq!(AxisMap::from_name("+X+X+Z"), Err(AxisMapError::RepeatedAxis(Axis::X)));
+//! assert_eq!(AxisMap::from_name("-X+Y+Z"), Err(AxisMapError::Mirror));
+//! ```
+
+use core::fmt;
+
+use glam::Vec3A;
+
+use crate::device::Axis;
+
+/// permutations of the chip axes, even ones first
+const PERMUTATIONS: [[Axis; 3]; 6] = [
+    [Axis::X, Axis::Y, Axis::Z],
+    [Axis::Y, Axis::Z, Axis::X],
+    [Axis::Z, Axis::X, Axis::Y],
+    [Axis::X, Axis::Z, Axis::Y],
+    [Axis::Z, Axis::Y, Axis::X],
+    [Axis::Y, Axis::X, Axis::Z],
+];
+
+/// Per-axis source and sign, `out[i] = ±in[axes[i]]`
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct AxisMap {
+    axes: [Axis; 3],
+    negate: [bool; 3],
+}
+
+impl Default for AxisMap {
+    fn default() -> Self {
+        Self::IDENTITY
+    }
+}
+
+impl AxisMap {
+    /// chip axes are the common axes
+    pub const IDENTITY: AxisMap = AxisMap {
+        axes: Axis::ALL,
+        negate: [false; 3],
+    };
+
+    /// the 24 canonical mountings, the maps that are rotations: per permutation of the chip
+    /// axes, even ones first, the negations in binary order of X, Y, Z. The identity is first
+    pub const ROTATIONS: [AxisMap; 24] = {
+        let mut maps = [AxisMap::IDENTITY; 24];
+        let mut n = 0;
+        let mut permutation = 0;
+        while permutation < PERMUTATIONS.len() {
+            let mut signs = 0;
+            while signs < 8 {
+                let negate = [signs & 1 != 0, signs & 2 != 0, signs & 4 != 0];
+                let map = AxisMap::new(PERMUTATIONS[permutation], negate);
+                if map.is_rotation() {
+                    maps[n] = map;
+                    n += 1;
+                }
+                signs += 1;
+            }
+            permutation += 1;
+        }
+        maps
+    };
+
+    /// common X, Y, Z from chip axes `axes`, negated where `negate` is set
+    pub const fn new(axes: [Axis; 3], negate: [bool; 3]) -> Self {
+        Self { axes, negate }
+    }
+
+    /// chip axis and negation for each common axis
+    pub const fn axes(&self) -> ([Axis; 3], [bool; 3]) {
+        (self.axes, self.negate)
+    }
+
+    /// true if every chip axis is used once and the map is a proper rotation, not a mirror
+    pub const fn is_rotation(&self) -> bool {
+        let [a, b, c] = [
+            self.axes[0] as usize,
+            self.axes[1] as usize,
+            self.axes[2] as usize,
+        ];
+        if a == b || b == c || a == c {
+            return false;
+        }
+        // even permutations keep the handedness, each negation flips it
+        let even = (a + 1) % 3 == b;
+        let [x, y, z] = self.negate;
+        let odd_flips = x ^ y ^ z;
+        even != odd_flips
+    }
+
+    /// The rotation named like `"+X+Y+Z"`: sign and chip axis for common X, Y and Z, case
+    /// insensitive. Mirrors are refused, no mounting produces them
+    pub fn from_name(name: &str) -> Result<Self, AxisMapError> {
+        let bytes = name.as_bytes();
+        if bytes.len() != 6 {
+            return Err(AxisMapError::Malformed);
+        }
+        let mut axes = Axis::ALL;
+        let mut negate = [false; 3];
+        for (i, pair) in bytes.chunks_exact(2).enumerate() {
+            negate[i] = match pair[0] {
+                b'+' => false,
+                b'-' => true,
+                _ => return Err(AxisMapError::Malformed),
+            };
+            axes[i] = match pair[1].to_ascii_uppercase() {
+                b'X' => Axis::X,
+                b'Y' => Axis::Y,
+                b'Z' => Axis::Z,
+                _ => return Err(AxisMapError::Malformed),
+            };
+        }
+        Self::rotation(axes, negate)
+    }
+
+    /// The rotation `out = matrix · in`, rows for the common axes, columns for the chip axes.
+    /// Every row holds one ±1 and zeros, mirrors are refused
+    pub fn from_matrix(matrix: [[i8; 3]; 3]) -> Result<Self, AxisMapError> {
+        let mut axes = Axis::ALL;
+        let mut negate = [false; 3];
+        for (i, row) in matrix.iter().enumerate() {
+            let mut picked = None;
+            for (axis, entry) in Axis::ALL.iter().zip(row) {
+                match (entry, picked) {
+                    (0, _) => {}
+                    (1 | -1, None) => picked = Some((*axis, *entry < 0)),
+                    _ => return Err(AxisMapError::BadRow(i)),
+                }
+            }
+            (axes[i], negate[i]) = picked.ok_or(AxisMapError::BadRow(i))?;
+        }
+        Self::rotation(axes, negate)
+    }
+
+    fn rotation(axes: [Axis; 3], negate: [bool; 3]) -> Result<Self, AxisMapError> {
+        for (i, axis) in axes.iter().enumerate() {
+            if axes[..i].contains(axis) {
+                return Err(AxisMapError::RepeatedAxis(*axis));
+            }
+        }
+        let map = Self::new(axes, negate);
+        if map.is_rotation() {
+            Ok(map)
+        } else {
+            Err(AxisMapError::Mirror)
+        }
+    }
+
+    /// `v` in chip axes to the common frame
+    pub fn apply(&self, v: Vec3A) -> Vec3A {
+        let component = |i: usize| {
+            let value = v[self.axes[i] as usize];
+            if self.negate[i] {
+                -value
+            } else {
+                value
+            }
+        };
+        Vec3A::new(component(0), component(1), component(2))
+    }
+}
+
+/// The name of [`AxisMap::from_name`], also for mirrors
+impl fmt::Display for AxisMap {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (axis, negate) in self.axes.iter().zip(self.negate) {
+            let sign = if negate { '-' } else { '+' };
+            write!(f, "{}{:?}", sign, axis)?;
+        }
+        Ok(())
+    }
+}
+
+/// Why a name or matrix is not a mounting
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum AxisMapError {
+    /// not three signed axes like `+X+Y+Z`
+    Malformed,
+    /// matrix row, counted from 0, without exactly one ±1
+    BadRow(usize),
+    /// a chip axis picked for two common axes
+    RepeatedAxis(Axis),
+    /// a mirror of a mounting, one axis sign too many or too few
+    Mirror,
+}
+
+impl fmt::Display for AxisMapError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AxisMapError::Malformed => {
+                f.write_str("expected a sign and chip axis per common axis, like +X+Y+Z or -Y+X+Z")
+            }
+            AxisMapError::BadRow(row) => {
+                write!(f, "matrix row {} is not one 1 or -1 and zeros", row)
+            }
+            AxisMapError::RepeatedAxis(axis) => {
+                write!(f, "chip axis {:?} used twice, every axis once", axis)
+            }
+            AxisMapError::Mirror => {
+                f.write_str("a mirror, not a mounting: flip the sign of one axis")
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for AxisMapError {}
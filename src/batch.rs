@@ -0,0 +1,77 @@
+//! Polled batches: K samples in one call, without the FIFO.
+//!
+//! A host polling the output registers at a moderate rate reads one burst of
+//! ACCEL_XOUT_H..GYRO_ZOUT_L per sample. [`Mpu6050::read_batch`] reads K such samples in a
+//! row and calls the caller's pacing closure between them, so the caller controls the timing
+//! without the driver owning a delay. Every sample takes the path of
+//! [`read_into_views`](Mpu6050::read_into_views): interleaving, settling flags, out-of-band
+//! flags, provenance, skew correction, governor, [`hook`](crate::hook) and tap are the same
+//! for a batch and for K single reads.
+//!
+//! #### What a batch saves
+//! Nothing on the bus. The register pointer auto-increments within one read only; reading on
+//! past GYRO_ZOUT_L continues into EXT_SENS_DATA, not into the next sample, so K samples are
+//! K transactions either way. Counted like [`IoStats`](crate::op_bounds::IoStats), see
+//! [`op_bounds`](crate::op_bounds), with the bits of the [`bus`](crate::bus) wire model:
+//!
+//! | per sample | transactions | bytes | bits | at 400 kHz |
+//! |:---|:---|:---|:---|:---|
+//! | `read_batch::<K>`, per sample | 1 | 17 | 156 | 390 µs |
+//! | `read_into_views`, one call | 1 | 17 | 156 | 390 µs |
+//! | `get_all` | 1 | 17 | 156 | 390 µs |
+//! | `get_acc`, `get_gyro` and `get_temp` | 3 | 23 | 216 | 540 µs |
+//!
+//! [`op_bounds::read_batch`](crate::op_bounds::read_batch) is the bound of a batch. The
+//! savings are on the CPU: the write of the register pointer is the same constant for every
+//! read and the burst window is not looked up in the register map again, the frames go
+//! through one stack buffer, and the parse is inlined into one loop the optimizer sees as a
+//! whole. Neither path allocates. That is some hundred instructions per sample at most,
+//! against hundreds of microseconds of bus time: a batch is worth it where the CPU time
+//! between reads matters, not for the bus.
+//!
+//! An error ends the batch: the samples before it are in `out`, the rest is left as it was.
+//! ```
+//! # use mpu6050::prelude::*;
+//! # use embedded_hal::blocking::i2c::{Write, WriteRead};
+//! fn poll_burst<I, E>(mpu: &mut Mpu6050<I>) -> Result<[MpuSample; 4], Mpu6050Error<E>>
+//! where
+//!     I: Write<Error = E> + WriteRead<Error = E>,
+//! {
+//!     let mut out = [MpuSample::default(); 4];
+//!     // e.g. wait for the next 1 ms tick of the application timer
+//!     mpu.read_batch(|| {}, &mut out)?;
+//!     Ok(out)
+//! }
+//! ```
+
+#[cfg(feature = "driver")]
+use embedded_hal::blocking::i2c::{Write, WriteRead};
+
+#[cfg(feature = "driver")]
+use crate::frame::FRAME_LEN;
+#[cfg(feature = "driver")]
+use crate::{Mpu6050, Mpu6050Error, MpuSample};
+
+#[cfg(feature = "driver")]
+impl<I, D, E> Mpu6050<I, D>
+where
+    I: Write<Error = E> + WriteRead<Error = E>,
+{
+    /// Reads `K` samples in bursts, calling `delay_between` between two reads, see
+    /// [`batch`](crate::batch). Each sample is what
+    /// [`read_into_views`](Self::read_into_views) would return
+    pub fn read_batch<const K: usize>(
+        &mut self,
+        mut delay_between: impl FnMut(),
+        out: &mut [MpuSample; K],
+    ) -> Result<(), Mpu6050Error<E>> {
+        let mut bytes = [0; FRAME_LEN];
+        for (idx, slot) in out.iter_mut().enumerate() {
+            if idx > 0 {
+                delay_between();
+            }
+            *slot = self.read_sample_burst_into(&mut bytes)?;
+        }
+        Ok(())
+    }
+}
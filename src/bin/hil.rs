@@ -0,0 +1,222 @@
+//! Hardware-in-the-loop battery over Linux i2c-dev, see the `hil` module of the crate.
+//!
+//! ```text
+//! cargo run --features hil --bin hil -- --bus /dev/i2c-1 --address 0x68 --json report.json
+//! ```
+//! Prints the human summary to stdout and operator prompts to stderr. Exits with 0 if no
+//! check failed, 1 if one did and 2 on usage or bus errors.
+//!
+//! `hil decode PATH` prints a black box export read back from a unit instead, see the
+//! `black_box` module, and exits with 2 if it does not decode.
+
+use std::process::ExitCode;
+
+use mpu6050::black_box;
+use mpu6050::hil::{run_hil, CheckId, HilOptions, Operator, Unattended};
+
+const USAGE: &str = "\
+usage: hil [options]
+       hil decode PATH    print the black box export in PATH
+  --bus PATH              i2c-dev bus, default /dev/i2c-1
+  --address ADDR          sensor address, decimal or 0x hex, default 0x68
+  --json PATH             write the JSON report to PATH, - for stdout
+  --unattended            skip checks needing an operator
+  --only ID,..            run only these checks, the probe always runs
+  --skip ID,..            do not run these checks
+  --fifo-seconds N        FIFO streaming time, default 5
+  --motion-timeout N      seconds to tap the sensor, default 10
+checks: probe init_profiles range_sweep dlpf_sweep noise gyro_calibration fifo_streaming
+        motion_interrupt self_test";
+
+struct Args {
+    bus: String,
+    address: u8,
+    json: Option<String>,
+    unattended: bool,
+    options: HilOptions,
+}
+
+fn parse_checks(list: &str) -> Result<Vec<CheckId>, String> {
+    list.split(',')
+        .map(|name| CheckId::from_name(name).ok_or_else(|| format!("unknown check {}", name)))
+        .collect()
+}
+
+fn parse_args(mut args: impl Iterator<Item = String>) -> Result<Args, String> {
+    let mut parsed = Args {
+        bus: "/dev/i2c-1".into(),
+        address: 0x68,
+        json: None,
+        unattended: false,
+        options: HilOptions::default(),
+    };
+    while let Some(flag) = args.next() {
+        if flag == "--unattended" {
+            parsed.unattended = true;
+            continue;
+        }
+        if flag == "--help" || flag == "-h" {
+            return Err(String::new());
+        }
+        let value = args
+            .next()
+            .ok_or_else(|| format!("{} needs a value", flag))?;
+        let number = |value: &str| {
+            value
+                .parse::<u32>()
+                .map_err(|_| format!("bad number {} for {}", value, flag))
+        };
+        match flag.as_str() {
+            "--bus" => parsed.bus = value,
+            "--address" => {
+                let address = match value.strip_prefix("0x") {
+                    Some(hex) => u8::from_str_radix(hex, 16),
+                    None => value.parse(),
+                };
+                parsed.address = address.map_err(|_| format!("bad address {}", value))?;
+            }
+            "--json" => parsed.json = Some(value),
+            "--only" => parsed.options.checks = parse_checks(&value)?,
+            "--skip" => {
+                let skipped = parse_checks(&value)?;
+                parsed.options.checks.retain(|id| !skipped.contains(id));
+            }
+            "--fifo-seconds" => parsed.options.fifo_duration_ms = number(&value)? * 1000,
+            "--motion-timeout" => parsed.options.motion_timeout_ms = number(&value)? * 1000,
+            _ => return Err(format!("unknown option {}", flag)),
+        }
+    }
+    Ok(parsed)
+}
+
+/// operator at the terminal, prompts on stderr
+struct Terminal;
+
+impl Operator for Terminal {
+    fn interactive(&self) -> bool {
+        true
+    }
+
+    fn prompt(&mut self, message: &str) {
+        eprintln!(">>> {}", message);
+    }
+
+    fn starting(&mut self, check: CheckId) {
+        eprintln!("running {}", check.name());
+    }
+}
+
+/// prints the black box export in `path`
+fn decode(path: &str) -> ExitCode {
+    let blob = match std::fs::read(path) {
+        Ok(blob) => blob,
+        Err(e) => {
+            eprintln!("cannot read {}: {}", path, e);
+            return ExitCode::from(2);
+        }
+    };
+    match black_box::decode(&blob) {
+        Ok(export) => {
+            println!("{}", export);
+            ExitCode::SUCCESS
+        }
+        Err(e) => {
+            eprintln!("{}: {}", path, e);
+            ExitCode::from(2)
+        }
+    }
+}
+
+fn main() -> ExitCode {
+    let mut args = std::env::args().skip(1).peekable();
+    if args.peek().map(String::as_str) == Some("decode") {
+        return match (args.nth(1), args.next()) {
+            (Some(path), None) => decode(&path),
+            _ => {
+                eprintln!("{}", USAGE);
+                ExitCode::from(2)
+            }
+        };
+    }
+    let args = match parse_args(args) {
+        Ok(args) => args,
+        Err(message) => {
+            if !message.is_empty() {
+                eprintln!("{}", message);
+            }
+            eprintln!("{}", USAGE);
+            return ExitCode::from(2);
+        }
+    };
+    run(args)
+}
+
+#[cfg(target_os = "linux")]
+fn run(args: Args) -> ExitCode {
+    use mpu6050::linux::{I2cdev, Sleep};
+    use mpu6050::Mpu6050Builder;
+
+    let i2c = match I2cdev::open(&args.bus) {
+        Ok(i2c) => i2c,
+        Err(e) => {
+            eprintln!("cannot open {}: {}", args.bus, e);
+            return ExitCode::from(2);
+        }
+    };
+    let mut mpu = match Mpu6050Builder::new()
+        .i2c(i2c)
+        .slave_addr(args.address)
+        .build()
+    {
+        Ok(mpu) => mpu,
+        Err(e) => {
+            eprintln!("invalid driver configuration: {:?}", e);
+            return ExitCode::from(2);
+        }
+    };
+    let mut delay = Sleep;
+    let report = if args.unattended {
+        run_hil(
+            &mut mpu,
+            &mut delay,
+            &mut Unattended,
+            &args.options,
+            &args.bus,
+        )
+    } else {
+        run_hil(
+            &mut mpu,
+            &mut delay,
+            &mut Terminal,
+            &args.options,
+            &args.bus,
+        )
+    };
+
+    match args.json.as_deref() {
+        Some("-") => println!("{}", report.to_json()),
+        Some(path) => {
+            if let Err(e) = std::fs::write(path, report.to_json()) {
+                eprintln!("cannot write {}: {}", path, e);
+                return ExitCode::from(2);
+            }
+        }
+        None => {}
+    }
+    if args.json.as_deref() == Some("-") {
+        eprintln!("{}", report);
+    } else {
+        println!("{}", report);
+    }
+    if report.passed() {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::from(1)
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+fn run(_args: Args) -> ExitCode {
+    eprintln!("the hil binary needs Linux i2c-dev, call mpu6050::hil::run_hil with your bus");
+    ExitCode::from(2)
+}
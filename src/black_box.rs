@@ -0,0 +1,901 @@
+//! Black box of the last significant driver events, for devices without a console.
+//!
+//! A headless unit cannot print why `init` failed, and by the next service connection the
+//! error is gone. A [`BlackBox`] keeps the last N events as fixed 8 byte entries, no strings,
+//! and exports them as a versioned, checksummed blob the application writes to EEPROM or
+//! flash when it suits it. The service tool reads the blob back with [`decode`], which needs
+//! neither the driver nor the N it was recorded with.
+//!
+//! #### Attaching
+//! The box is fed through the [`metrics`](crate::metrics) sink: a [`SharedBlackBox`] is a
+//! `static` behind a lock implementing [`MetricsSink`], installed with
+//! [`Mpu6050::set_metrics_sink`]. A borrowing `attach_black_box(&mut bb)` would put a
+//! lifetime on the driver type for one feature, while the sink is already called at every
+//! event the box keeps, at the cost of one `Option` check without it. The `static` also
+//! outlives the driver: the record of a failed `init` survives dropping the driver and
+//! building a new one. An application with a telemetry sink of its own keeps it with
+//! [`SharedBlackBox::forwarding`], which passes everything on.
+//! ```
+//! use mpu6050::black_box::SharedBlackBox;
+//! use mpu6050::Mpu6050Builder;
+//!
+//! static BLACK_BOX: SharedBlackBox = SharedBlackBox::new();
+//!
+//! # struct Bus; // the board's I2C peripheral
+//! let mut mpu = Mpu6050Builder::new().i2c(Bus).build().unwrap();
+//! mpu.set_metrics_sink(Some(&BLACK_BOX));
+//! // ... on the application's schedule:
+//! let mut eeprom = [0xff; 128];
+//! let len = BLACK_BOX.export(&mut eeprom);
+//! assert_eq!(len, 10);
+//! assert!(BLACK_BOX.black_box().is_empty());
+//! ```
+//!
+//! #### Events kept
+//! | kind | event | bytes 1 to 7 |
+//! |:---|:---|:---|
+//! | 0x01 | [`Initialized`](BlackBoxEvent::Initialized) | chip id |
+//! | 0x02 | [`OperationFailed`](BlackBoxEvent::OperationFailed) | [operation](FailedOperation::code), [error code](Mpu6050Error::code), error detail, register (0xff: none), [hint](#hints), hint delay in ms u16 LE, saturating |
+//! | 0x03 | [`InitFinding`](BlackBoxEvent::InitFinding) | finding kind, action (0 cleared, 1 overwritten, 2 kept), value, register |
+//! | 0x04 | [`Disconnected`](BlackBoxEvent::Disconnected) | |
+//! | 0x05 | [`Reconnected`](BlackBoxEvent::Reconnected) | 0 same chip, 1 different chip, chip id or previous chip id, chip id found |
+//! | 0x06 | [`SupervisorTripped`](BlackBoxEvent::SupervisorTripped) | limit (0 to 2 gyro x, y, z, 3 accel magnitude), value f32 LE |
+//! | 0x07 | [`BusRecovery`](BlackBoxEvent::BusRecovery) | outcome (0 recovered, 1 not stuck, 2 failed), resync (0 none, 1 resumed, 2 needs reconnect, 3 bus failed), fields restored |
+//! | 0x08 | [`FifoOverflow`](BlackBoxEvent::FifoOverflow) | FIFO count u16 LE |
+//!
+//! Byte 0 is the kind, unused bytes are zero. The finding kinds are 0 self-test active with
+//! the value holding the gyro X, Y, Z bits in bits 0 to 2 and the accel bits in bits 3 to
+//! 5, 1 power state with PWR_MGMT_1, 2 FIFO overflow latched with INT_STATUS and 3
+//! non-default register with its value and address. Settling retries, clipped samples,
+//! interrupts and sampling overruns happen at the data rate and would flush the ring within
+//! seconds, the box leaves them to the counters.
+//!
+//! The encoding is stable: a kind keeps its number and layout, a new event gets a new kind.
+//! An older tool decodes a kind or code it does not know as
+//! [`Unknown`](BlackBoxEvent::Unknown) with the raw bytes, the rest of the export decodes.
+//!
+//! #### Hints
+//! The hint byte is the position of the [`RecoveryHint`] in its declaration,
+//! 0 [`RetryImmediately`](RecoveryHint::RetryImmediately),
+//! 1 [`RetryAfterDelay`](RecoveryHint::RetryAfterDelay) with the delay,
+//! 2 [`ResetDevice`](RecoveryHint::ResetDevice), 3 [`PowerCycle`](RecoveryHint::PowerCycle),
+//! 4 [`CheckWiring`](RecoveryHint::CheckWiring),
+//! 5 [`ReplaceHardware`](RecoveryHint::ReplaceHardware) and
+//! 6 [`FixConfiguration`](RecoveryHint::FixConfiguration). `init` and `try_reconnect`
+//! take it with the [`hint_context`](Mpu6050::hint_context) of the failure, the context
+//! itself is gone by the time the service tool reads the export.
+//!
+//! #### Error codes
+//! | code | error | detail | register |
+//! |:---|:---|:---|:---|
+//! | 1 | [`I2c`](Mpu6050Error::I2c) | | of the latest failed transaction |
+//! | 2 | [`InvalidChipId`](Mpu6050Error::InvalidChipId) | chip id | WHO_AM_I |
+//! | 3 | [`Disconnected`](Mpu6050Error::Disconnected) | | |
+//! | 4 | [`ExtDataOverflow`](Mpu6050Error::ExtDataOverflow) | length | |
+//! | 5 | [`StaleExtDataSlot`](Mpu6050Error::StaleExtDataSlot) | | |
+//! | 6 | `StaleFifoSchema` | | |
+//! | 7 | [`StaleConfiguration`](Mpu6050Error::StaleConfiguration) | | |
+//! | 8 | [`BufferTooSmall`](Mpu6050Error::BufferTooSmall) | length, saturating | |
+//! | 9 | [`InvalidSettings`](Mpu6050Error::InvalidSettings) | | |
+//! | 10 | [`AliasingLikely`](Mpu6050Error::AliasingLikely) | | |
+//! | 11 | [`InvalidTimestamp`](Mpu6050Error::InvalidTimestamp) | | |
+//! | 12 | [`Unsupported`](Mpu6050Error::Unsupported) | | |
+//! | 13 | [`Aborted`](Mpu6050Error::Aborted) | | |
+//! | 14 | [`DelayRequired`](Mpu6050Error::DelayRequired) | | |
+//! | 15 | [`FifoActive`](Mpu6050Error::FifoActive) | | |
+//! | 16 | [`InvalidRegisterAccess`](Mpu6050Error::InvalidRegisterAccess) | | the register |
+//! | 17 | [`BoardConstraint`](Mpu6050Error::BoardConstraint) | | |
+//! | 18 | [`NotActivated`](Mpu6050Error::NotActivated) | | |
+//! | 19 | [`DeniedRegisterRange`](Mpu6050Error::DeniedRegisterRange) | | first of the range |
+//! | 20 | [`ImplausibleFrame`](Mpu6050Error::ImplausibleFrame) | | |
+//! | 21 | [`WakeOnMotionRefused`](Mpu6050Error::WakeOnMotionRefused) | | |
+//! | 22 | [`Reentrancy`](Mpu6050Error::Reentrancy) | | |
+//! | 23 | [`BusRateTooHigh`](Mpu6050Error::BusRateTooHigh) | | |
+//!
+//! [`error_name`] gives the stable name of a code.
+//!
+//! #### Export
+//! | bytes | content |
+//! |:---|:---|
+//! | 1 | format version ([`BLACK_BOX_VERSION`]) |
+//! | 1 | entry length ([`ENTRY_LEN`]) |
+//! | 2 | entry count, little endian |
+//! | 4 | events recorded since the box was created or cleared, little endian, wrapping |
+//! | 8 n | entries, oldest first |
+//! | 2 | CRC-16/CCITT-FALSE over all previous bytes, little endian |
+//!
+//! [`export_len`] gives the size for n entries. A buffer too small for every entry gets the
+//! newest that fit, the count of recorded events stays, so the dropped ones show. Not even
+//! the header fitting writes nothing and returns 0. The reader takes the length from the
+//! header and ignores bytes after the CRC, an export keeps decoding from a larger flash
+//! page; erased flash reads as an unknown version.
+//! ```
+//! use mpu6050::black_box::{decode, export_len, BlackBox, BlackBoxEvent};
+//! use mpu6050::metrics::MetricEvent;
+//! use mpu6050::packed::DecodeError;
+//!
+//! let mut black_box = BlackBox::<4>::new();
+//! black_box.record(&MetricEvent::Initialized { chip_id: 0x68 });
+//! black_box.record(&MetricEvent::Disconnected);
+//! black_box.record(&MetricEvent::FifoOverflow { bytes: 1024 });
+//!
+//! let mut page = [0xff; 64];
+//! assert_eq!(black_box.export(&mut page), export_len(3));
+//! let export = decode(&page).unwrap();
+//! assert_eq!(export.events[1], BlackBoxEvent::Disconnected);
+//! assert_eq!(BlackBox::<4>::import(&page).unwrap(), black_box);
+//!
+//! // room for two entries: the oldest is dropped
+//! let mut small = [0; 26];
+//! assert_eq!(black_box.export(&mut small), export_len(2));
+//! let export = decode(&small).unwrap();
+//! assert_eq!((export.recorded, export.dropped()), (3, 1));
+//! assert_eq!(decode(&page[..20]), Err(DecodeError::Truncated(export_len(3))));
+//! ```
+
+use core::fmt;
+#[cfg(feature = "std")]
+use std::sync::{Mutex, MutexGuard};
+#[cfg(feature = "std")]
+use std::vec::Vec;
+
+use crate::connection::ReconnectOutcome;
+use crate::device::Axis;
+use crate::hint::{HintContext, RecoveryHint};
+use crate::init_findings::{FindingAction, FindingKind, InitFinding};
+use crate::logging;
+#[cfg(feature = "std")]
+use crate::metrics::MetricsSink;
+use crate::metrics::{self, MetricEvent};
+use crate::packed::{crc16, DecodeError};
+use crate::recovery::{BusRecoveryOutcome, ResyncOutcome};
+use crate::register::Register;
+use crate::supervisor::{SupervisorEvent, SupervisorLimit};
+use crate::{Mpu6050, Mpu6050Error};
+
+/// Format version written to the export header
+pub const BLACK_BOX_VERSION: u8 = 1;
+/// Bytes per entry
+pub const ENTRY_LEN: usize = 8;
+/// Header bytes before the entries
+pub const EXPORT_HEADER_LEN: usize = 8;
+/// CRC bytes after the entries
+pub const EXPORT_CRC_LEN: usize = 2;
+/// Entries of a [`SharedBlackBox`] unless given
+pub const DEFAULT_ENTRIES: usize = 32;
+
+/// Stable names of the error codes, code 1 first, see the [module docs](self#error-codes)
+pub const ERROR_NAMES: [&str; 23] = [
+    "i2c",
+    "invalid_chip_id",
+    "disconnected",
+    "ext_data_overflow",
+    "stale_ext_data_slot",
+    "stale_fifo_schema",
+    "stale_configuration",
+    "buffer_too_small",
+    "invalid_settings",
+    "aliasing_likely",
+    "invalid_timestamp",
+    "unsupported",
+    "aborted",
+    "delay_required",
+    "fifo_active",
+    "invalid_register_access",
+    "board_constraint",
+    "not_activated",
+    "denied_register_range",
+    "implausible_frame",
+    "wake_on_motion_refused",
+    "reentrancy",
+    "bus_rate_too_high",
+];
+
+/// register byte of a failure without a register
+const NO_REGISTER: u8 = 0xff;
+
+const KIND_INITIALIZED: u8 = 0x01;
+const KIND_FAILED: u8 = 0x02;
+const KIND_INIT_FINDING: u8 = 0x03;
+const KIND_DISCONNECTED: u8 = 0x04;
+const KIND_RECONNECTED: u8 = 0x05;
+const KIND_SUPERVISOR: u8 = 0x06;
+const KIND_BUS_RECOVERY: u8 = 0x07;
+const KIND_FIFO_OVERFLOW: u8 = 0x08;
+
+/// Bytes of an export of `entries` entries
+pub const fn export_len(entries: usize) -> usize {
+    EXPORT_HEADER_LEN + entries * ENTRY_LEN + EXPORT_CRC_LEN
+}
+
+/// Stable name of an error code, None for a code this version does not know
+pub fn error_name(code: u8) -> Option<&'static str> {
+    ERROR_NAMES.get(usize::from(code).checked_sub(1)?).copied()
+}
+
+impl<E> Mpu6050Error<E> {
+    /// stable code of the variant, see the [`black_box`](crate::black_box) module
+    pub fn code(&self) -> u8 {
+        #[allow(deprecated)]
+        match self {
+            Mpu6050Error::I2c(_) => 1,
+            Mpu6050Error::InvalidChipId(_) => 2,
+            Mpu6050Error::Disconnected => 3,
+            Mpu6050Error::ExtDataOverflow(_) => 4,
+            Mpu6050Error::StaleExtDataSlot => 5,
+            Mpu6050Error::StaleFifoSchema => 6,
+            Mpu6050Error::StaleConfiguration { .. } => 7,
+            Mpu6050Error::BufferTooSmall(_) => 8,
+            Mpu6050Error::InvalidSettings(_) => 9,
+            Mpu6050Error::AliasingLikely(_) => 10,
+            Mpu6050Error::InvalidTimestamp(_) => 11,
+            Mpu6050Error::Unsupported(_) => 12,
+            Mpu6050Error::Aborted { .. } => 13,
+            Mpu6050Error::DelayRequired => 14,
+            Mpu6050Error::FifoActive => 15,
+            Mpu6050Error::InvalidRegisterAccess { .. } => 16,
+            Mpu6050Error::BoardConstraint(_) => 17,
+            Mpu6050Error::NotActivated => 18,
+            Mpu6050Error::DeniedRegisterRange(_) => 19,
+            Mpu6050Error::ImplausibleFrame { .. } => 20,
+            Mpu6050Error::WakeOnMotionRefused(_) => 21,
+            Mpu6050Error::Reentrancy => 22,
+            Mpu6050Error::BusRateTooHigh(_) => 23,
+        }
+    }
+}
+
+/// Driver operation of a [`FailureRecord`]
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum FailedOperation {
+    /// `init`
+    Init,
+    /// `try_reconnect`
+    Reconnect,
+}
+
+impl FailedOperation {
+    /// operation byte of the entry
+    pub fn code(&self) -> u8 {
+        match self {
+            FailedOperation::Init => 1,
+            FailedOperation::Reconnect => 2,
+        }
+    }
+
+    fn from_code(code: u8) -> Option<Self> {
+        match code {
+            1 => Some(FailedOperation::Init),
+            2 => Some(FailedOperation::Reconnect),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for FailedOperation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            FailedOperation::Init => "init",
+            FailedOperation::Reconnect => "reconnect",
+        })
+    }
+}
+
+/// A failed driver operation without the bus error itself, see the
+/// [module docs](self#error-codes)
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct FailureRecord {
+    /// operation that failed
+    pub operation: FailedOperation,
+    /// [`Mpu6050Error::code`] of the error
+    pub error: u8,
+    /// parameter of the error, e.g. the chip id read, 0 if it has none
+    pub detail: u8,
+    /// register involved
+    pub register: Option<u8>,
+    /// hint with the driver's context at the failure
+    pub hint: RecoveryHint,
+}
+
+impl FailureRecord {
+    pub(crate) fn new<E>(
+        operation: FailedOperation,
+        error: &Mpu6050Error<E>,
+        context: &HintContext,
+        failed_register: Option<u8>,
+    ) -> Self {
+        let (detail, register) = match error {
+            Mpu6050Error::I2c(_) => (0, failed_register),
+            Mpu6050Error::InvalidChipId(who_am_i) => (*who_am_i, Some(Register::WHO_AM_I.addr())),
+            Mpu6050Error::ExtDataOverflow(len) => (*len, None),
+            Mpu6050Error::BufferTooSmall(len) => ((*len).min(u8::MAX as usize) as u8, None),
+            Mpu6050Error::InvalidRegisterAccess { register, .. } => (0, Some(register.addr())),
+            Mpu6050Error::DeniedRegisterRange(range) => (0, Some(range.first)),
+            _ => (0, None),
+        };
+        Self {
+            operation,
+            error: error.code(),
+            detail,
+            register,
+            hint: error.recovery_hint_with(context),
+        }
+    }
+}
+
+/// e.g. "init failed: invalid_chip_id 0x72 at register 0x75, check wiring"
+impl fmt::Display for FailureRecord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} failed: ", self.operation)?;
+        match error_name(self.error) {
+            Some(name) => f.write_str(name)?,
+            None => write!(f, "error {}", self.error)?,
+        }
+        if self.detail != 0 {
+            write!(f, " 0x{:02x}", self.detail)?;
+        }
+        if let Some(register) = self.register {
+            write!(f, " at register 0x{:02x}", register)?;
+        }
+        write!(f, ", {}", self.hint)
+    }
+}
+
+/// One entry of the black box, decoded
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum BlackBoxEvent {
+    /// `init` succeeded
+    Initialized {
+        /// WHO_AM_I value
+        chip_id: u8,
+    },
+    /// `init` or `try_reconnect` failed
+    OperationFailed(FailureRecord),
+    /// init found a leftover of a previous run
+    InitFinding(InitFinding),
+    /// the connection monitor entered `Disconnected`
+    Disconnected,
+    /// `try_reconnect` succeeded
+    Reconnected(ReconnectOutcome),
+    /// the supervisor latched
+    SupervisorTripped(SupervisorEvent),
+    /// the bus reset callback was invoked
+    BusRecovery {
+        /// what the callback did
+        outcome: BusRecoveryOutcome,
+        /// resynchronization, None unless recovered
+        resync: Option<ResyncOutcome>,
+    },
+    /// a drain found the FIFO full and reset it
+    FifoOverflow {
+        /// FIFO count read
+        bytes: u16,
+    },
+    /// an entry of a kind or with a code this version does not know
+    Unknown([u8; ENTRY_LEN]),
+}
+
+impl BlackBoxEvent {
+    /// the entry kept for `event`, None for the events the box leaves out
+    pub fn from_metric(event: &MetricEvent) -> Option<Self> {
+        Some(match *event {
+            MetricEvent::Initialized { chip_id } => BlackBoxEvent::Initialized { chip_id },
+            MetricEvent::OperationFailed(record) => BlackBoxEvent::OperationFailed(record),
+            MetricEvent::InitFinding(finding) => BlackBoxEvent::InitFinding(finding),
+            MetricEvent::Disconnected => BlackBoxEvent::Disconnected,
+            MetricEvent::Reconnected(outcome) => BlackBoxEvent::Reconnected(outcome),
+            MetricEvent::SupervisorTripped(event) => BlackBoxEvent::SupervisorTripped(event),
+            MetricEvent::BusRecovery { outcome, resync } => {
+                BlackBoxEvent::BusRecovery { outcome, resync }
+            }
+            MetricEvent::FifoOverflow { bytes } => BlackBoxEvent::FifoOverflow { bytes },
+            MetricEvent::SettlingRetry(_)
+            | MetricEvent::SampleClipped { .. }
+            | MetricEvent::SamplingOverrun
+            | MetricEvent::Interrupt(_) => return None,
+        })
+    }
+
+    /// the entry bytes, see the [module docs](self#events-kept)
+    pub fn encode(&self) -> [u8; ENTRY_LEN] {
+        let mut entry = [0; ENTRY_LEN];
+        match *self {
+            BlackBoxEvent::Initialized { chip_id } => {
+                entry[0] = KIND_INITIALIZED;
+                entry[1] = chip_id;
+            }
+            BlackBoxEvent::OperationFailed(record) => {
+                let (hint, ms) = encode_hint(record.hint);
+                entry[..5].copy_from_slice(&[
+                    KIND_FAILED,
+                    record.operation.code(),
+                    record.error,
+                    record.detail,
+                    record.register.unwrap_or(NO_REGISTER),
+                ]);
+                entry[5] = hint;
+                entry[6..].copy_from_slice(&ms.to_le_bytes());
+            }
+            BlackBoxEvent::InitFinding(finding) => {
+                let (kind, value, register) = match finding.kind {
+                    FindingKind::SelfTestActive { gyro, accel } => {
+                        let bits = gyro.iter().chain(&accel).enumerate();
+                        (0, bits.fold(0, |v, (i, on)| v | (*on as u8) << i), 0)
+                    }
+                    FindingKind::PowerState { pwr_mgmt_1 } => (1, pwr_mgmt_1, 0),
+                    FindingKind::FifoOverflowLatched { int_status } => (2, int_status, 0),
+                    FindingKind::NonDefaultRegister { register, value } => {
+                        (3, value, register.addr())
+                    }
+                };
+                let action = match finding.action {
+                    FindingAction::Cleared => 0,
+                    FindingAction::Overwritten => 1,
+                    FindingAction::Kept => 2,
+                };
+                entry[..5].copy_from_slice(&[KIND_INIT_FINDING, kind, action, value, register]);
+            }
+            BlackBoxEvent::Disconnected => entry[0] = KIND_DISCONNECTED,
+            BlackBoxEvent::Reconnected(outcome) => {
+                let (different, first, found) = match outcome {
+                    ReconnectOutcome::SameChip { chip_id } => (0, chip_id, chip_id),
+                    ReconnectOutcome::DifferentChip { previous, found } => (1, previous, found),
+                };
+                entry[..4].copy_from_slice(&[KIND_RECONNECTED, different, first, found]);
+            }
+            BlackBoxEvent::SupervisorTripped(event) => {
+                entry[0] = KIND_SUPERVISOR;
+                entry[1] = match event.limit {
+                    SupervisorLimit::GyroRate(axis) => axis as u8,
+                    SupervisorLimit::AccelMagnitude => 3,
+                };
+                entry[2..6].copy_from_slice(&event.value.to_le_bytes());
+            }
+            BlackBoxEvent::BusRecovery { outcome, resync } => {
+                let outcome = match outcome {
+                    BusRecoveryOutcome::Recovered => 0,
+                    BusRecoveryOutcome::NotStuck => 1,
+                    BusRecoveryOutcome::Failed => 2,
+                };
+                let (resync, restored) = match resync {
+                    None => (0, 0),
+                    Some(ResyncOutcome::Resumed { restored }) => (1, restored),
+                    Some(ResyncOutcome::NeedsReconnect) => (2, 0),
+                    Some(ResyncOutcome::BusFailed) => (3, 0),
+                };
+                entry[..4].copy_from_slice(&[KIND_BUS_RECOVERY, outcome, resync, restored]);
+            }
+            BlackBoxEvent::FifoOverflow { bytes } => {
+                entry[0] = KIND_FIFO_OVERFLOW;
+                entry[1..3].copy_from_slice(&bytes.to_le_bytes());
+            }
+            BlackBoxEvent::Unknown(bytes) => entry = bytes,
+        }
+        entry
+    }
+
+    /// the event of entry bytes, [`Unknown`](BlackBoxEvent::Unknown) for a kind or code this
+    /// version does not know
+    pub fn decode(entry: &[u8; ENTRY_LEN]) -> Self {
+        Self::try_decode(entry).unwrap_or(BlackBoxEvent::Unknown(*entry))
+    }
+
+    fn try_decode(e: &[u8; ENTRY_LEN]) -> Option<Self> {
+        Some(match e[0] {
+            KIND_INITIALIZED => BlackBoxEvent::Initialized { chip_id: e[1] },
+            KIND_FAILED => BlackBoxEvent::OperationFailed(FailureRecord {
+                operation: FailedOperation::from_code(e[1])?,
+                error: e[2],
+                detail: e[3],
+                register: (e[4] != NO_REGISTER).then_some(e[4]),
+                hint: decode_hint(e[5], u16::from_le_bytes([e[6], e[7]]))?,
+            }),
+            KIND_INIT_FINDING => {
+                let bit = |i: u8| e[3] & (1 << i) != 0;
+                let kind = match e[1] {
+                    0 => FindingKind::SelfTestActive {
+                        gyro: [bit(0), bit(1), bit(2)],
+                        accel: [bit(3), bit(4), bit(5)],
+                    },
+                    1 => FindingKind::PowerState { pwr_mgmt_1: e[3] },
+                    2 => FindingKind::FifoOverflowLatched { int_status: e[3] },
+                    3 => FindingKind::NonDefaultRegister {
+                        register: Register::from_addr(e[4])?,
+                        value: e[3],
+                    },
+                    _ => return None,
+                };
+                let action = match e[2] {
+                    0 => FindingAction::Cleared,
+                    1 => FindingAction::Overwritten,
+                    2 => FindingAction::Kept,
+                    _ => return None,
+                };
+                BlackBoxEvent::InitFinding(InitFinding { kind, action })
+            }
+            KIND_DISCONNECTED => BlackBoxEvent::Disconnected,
+            KIND_RECONNECTED => BlackBoxEvent::Reconnected(match e[1] {
+                0 => ReconnectOutcome::SameChip { chip_id: e[2] },
+                1 => ReconnectOutcome::DifferentChip {
+                    previous: e[2],
+                    found: e[3],
+                },
+                _ => return None,
+            }),
+            KIND_SUPERVISOR => BlackBoxEvent::SupervisorTripped(SupervisorEvent {
+                limit: match e[1] {
+                    3 => SupervisorLimit::AccelMagnitude,
+                    axis => SupervisorLimit::GyroRate(*Axis::ALL.get(axis as usize)?),
+                },
+                value: f32::from_le_bytes([e[2], e[3], e[4], e[5]]),
+            }),
+            KIND_BUS_RECOVERY => BlackBoxEvent::BusRecovery {
+                outcome: match e[1] {
+                    0 => BusRecoveryOutcome::Recovered,
+                    1 => BusRecoveryOutcome::NotStuck,
+                    2 => BusRecoveryOutcome::Failed,
+                    _ => return None,
+                },
+                resync: match e[2] {
+                    0 => None,
+                    1 => Some(ResyncOutcome::Resumed { restored: e[3] }),
+                    2 => Some(ResyncOutcome::NeedsReconnect),
+                    3 => Some(ResyncOutcome::BusFailed),
+                    _ => return None,
+                },
+            },
+            KIND_FIFO_OVERFLOW => BlackBoxEvent::FifoOverflow {
+                bytes: u16::from_le_bytes([e[1], e[2]]),
+            },
+            _ => return None,
+        })
+    }
+}
+
+impl fmt::Display for BlackBoxEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BlackBoxEvent::Initialized { chip_id } => {
+                write!(f, "initialized, chip 0x{:02x}", chip_id)
+            }
+            BlackBoxEvent::OperationFailed(record) => write!(f, "{}", record),
+            BlackBoxEvent::InitFinding(finding) => {
+                write!(f, "init finding {:?}, {:?}", finding.kind, finding.action)
+            }
+            BlackBoxEvent::Disconnected => f.write_str("disconnected"),
+            BlackBoxEvent::Reconnected(outcome) => write!(f, "reconnected, {:?}", outcome),
+            BlackBoxEvent::SupervisorTripped(event) => {
+                write!(
+                    f,
+                    "supervisor tripped, {:?} at {}",
+                    event.limit, event.value
+                )
+            }
+            BlackBoxEvent::BusRecovery { outcome, resync } => {
+                write!(f, "bus recovery {:?}, resync {:?}", outcome, resync)
+            }
+            BlackBoxEvent::FifoOverflow { bytes } => write!(f, "FIFO overflow, {} bytes", bytes),
+            BlackBoxEvent::Unknown(entry) => write!(f, "unknown entry {:02x?}", entry),
+        }
+    }
+}
+
+/// hint byte and delay of `hint`
+fn encode_hint(hint: RecoveryHint) -> (u8, u16) {
+    match hint {
+        RecoveryHint::RetryImmediately => (0, 0),
+        RecoveryHint::RetryAfterDelay { ms } => (1, ms.min(u16::MAX as u32) as u16),
+        RecoveryHint::ResetDevice => (2, 0),
+        RecoveryHint::PowerCycle => (3, 0),
+        RecoveryHint::CheckWiring => (4, 0),
+        RecoveryHint::ReplaceHardware => (5, 0),
+        RecoveryHint::FixConfiguration => (6, 0),
+    }
+}
+
+fn decode_hint(code: u8, ms: u16) -> Option<RecoveryHint> {
+    Some(match code {
+        0 => RecoveryHint::RetryImmediately,
+        1 => RecoveryHint::RetryAfterDelay { ms: ms as u32 },
+        2 => RecoveryHint::ResetDevice,
+        3 => RecoveryHint::PowerCycle,
+        4 => RecoveryHint::CheckWiring,
+        5 => RecoveryHint::ReplaceHardware,
+        6 => RecoveryHint::FixConfiguration,
+        _ => return None,
+    })
+}
+
+/// Ring of the last `N` events as entries, no bus access, see the [module docs](self).
+/// Boxes compare equal with the same entries in the same order and the same count
+#[derive(Copy, Clone, Debug)]
+pub struct BlackBox<const N: usize = DEFAULT_ENTRIES> {
+    entries: [[u8; ENTRY_LEN]; N],
+    /// index of the next entry written
+    next: usize,
+    len: usize,
+    recorded: u32,
+}
+
+impl<const N: usize> BlackBox<N> {
+    /// empty box
+    pub const fn new() -> Self {
+        Self {
+            entries: [[0; ENTRY_LEN]; N],
+            next: 0,
+            len: 0,
+            recorded: 0,
+        }
+    }
+
+    /// keeps `event` if it is one the box records, true if kept
+    pub fn record(&mut self, event: &MetricEvent) -> bool {
+        match BlackBoxEvent::from_metric(event) {
+            Some(event) => {
+                self.push(&event);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// keeps `event`, dropping the oldest entry if full
+    pub fn push(&mut self, event: &BlackBoxEvent) {
+        self.push_entry(event.encode());
+    }
+
+    fn push_entry(&mut self, entry: [u8; ENTRY_LEN]) {
+        self.recorded = self.recorded.wrapping_add(1);
+        if N == 0 {
+            return;
+        }
+        self.entries[self.next] = entry;
+        self.next = (self.next + 1) % N;
+        self.len = (self.len + 1).min(N);
+    }
+
+    /// entries kept
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// nothing kept
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// events recorded since created or cleared, kept or dropped, wrapping
+    pub fn recorded(&self) -> u32 {
+        self.recorded
+    }
+
+    /// the entries, oldest first
+    pub fn entries(&self) -> impl Iterator<Item = &[u8; ENTRY_LEN]> {
+        let start = (self.next + N - self.len) % N.max(1);
+        (0..self.len).map(move |i| &self.entries[(start + i) % N])
+    }
+
+    /// the events, oldest first
+    pub fn events(&self) -> impl Iterator<Item = BlackBoxEvent> + '_ {
+        self.entries().map(BlackBoxEvent::decode)
+    }
+
+    /// forgets every entry and the count
+    pub fn clear(&mut self) {
+        *self = Self::new();
+    }
+
+    /// Writes the export to `out`, the newest entries that fit, see the
+    /// [module docs](self#export). Bytes written, 0 if not even the header fits
+    pub fn export(&self, out: &mut [u8]) -> usize {
+        if out.len() < export_len(0) {
+            return 0;
+        }
+        let count = self.len.min((out.len() - export_len(0)) / ENTRY_LEN);
+        let len = export_len(count);
+        out[0] = BLACK_BOX_VERSION;
+        out[1] = ENTRY_LEN as u8;
+        out[2..4].copy_from_slice(&(count as u16).to_le_bytes());
+        out[4..8].copy_from_slice(&self.recorded.to_le_bytes());
+        let entries = out[EXPORT_HEADER_LEN..len - EXPORT_CRC_LEN].chunks_exact_mut(ENTRY_LEN);
+        for (chunk, entry) in entries.zip(self.entries().skip(self.len - count)) {
+            chunk.copy_from_slice(entry);
+        }
+        let crc = crc16(&out[..len - EXPORT_CRC_LEN]);
+        out[len - EXPORT_CRC_LEN..len].copy_from_slice(&crc.to_le_bytes());
+        len
+    }
+
+    /// Reads an export, keeping the newest `N` entries. The count of recorded events is the
+    /// export's
+    pub fn import(blob: &[u8]) -> Result<Self, DecodeError> {
+        let (recorded, entries) = read_export(blob)?;
+        let count = entries.len() / ENTRY_LEN;
+        let mut black_box = Self::new();
+        for entry in entries
+            .chunks_exact(ENTRY_LEN)
+            .skip(count.saturating_sub(N))
+        {
+            black_box.push_entry(entry.try_into().unwrap());
+        }
+        black_box.recorded = recorded;
+        Ok(black_box)
+    }
+}
+
+impl<const N: usize> PartialEq for BlackBox<N> {
+    fn eq(&self, other: &Self) -> bool {
+        self.recorded == other.recorded && self.entries().eq(other.entries())
+    }
+}
+
+impl<const N: usize> Eq for BlackBox<N> {}
+
+impl<const N: usize> Default for BlackBox<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// recorded count and entry bytes of an export
+fn read_export(blob: &[u8]) -> Result<(u32, &[u8]), DecodeError> {
+    if blob.len() < EXPORT_HEADER_LEN {
+        return Err(DecodeError::Truncated(export_len(0)));
+    }
+    if blob[0] != BLACK_BOX_VERSION || blob[1] as usize != ENTRY_LEN {
+        return Err(DecodeError::BadHeader);
+    }
+    let len = export_len(u16::from_le_bytes([blob[2], blob[3]]) as usize);
+    if blob.len() < len {
+        return Err(DecodeError::Truncated(len));
+    }
+    let crc = u16::from_le_bytes([blob[len - 2], blob[len - 1]]);
+    if crc != crc16(&blob[..len - EXPORT_CRC_LEN]) {
+        return Err(DecodeError::CrcMismatch);
+    }
+    let recorded = u32::from_le_bytes([blob[4], blob[5], blob[6], blob[7]]);
+    Ok((recorded, &blob[EXPORT_HEADER_LEN..len - EXPORT_CRC_LEN]))
+}
+
+/// A decoded export, see [`decode`]
+#[cfg(feature = "std")]
+#[derive(Clone, Debug, PartialEq)]
+pub struct BlackBoxExport {
+    /// events recorded since the box was created or cleared, wrapping
+    pub recorded: u32,
+    /// the exported events, oldest first
+    pub events: Vec<BlackBoxEvent>,
+}
+
+#[cfg(feature = "std")]
+impl BlackBoxExport {
+    /// events recorded but not in the export
+    pub fn dropped(&self) -> u32 {
+        self.recorded.wrapping_sub(self.events.len() as u32)
+    }
+}
+
+/// one event per line, oldest first
+#[cfg(feature = "std")]
+impl fmt::Display for BlackBoxExport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} events recorded, {} dropped",
+            self.recorded,
+            self.dropped()
+        )?;
+        for (i, event) in self.events.iter().enumerate() {
+            write!(f, "\n{}: {}", i, event)?;
+        }
+        Ok(())
+    }
+}
+
+/// Reads an export of a box of any size, for the service tool side
+#[cfg(feature = "std")]
+pub fn decode(blob: &[u8]) -> Result<BlackBoxExport, DecodeError> {
+    let (recorded, entries) = read_export(blob)?;
+    let events = entries
+        .chunks_exact(ENTRY_LEN)
+        .map(|entry| BlackBoxEvent::decode(entry.try_into().unwrap()))
+        .collect();
+    Ok(BlackBoxExport { recorded, events })
+}
+
+/// [`BlackBox`] behind a lock, for a `static` installed as the metrics sink, see the
+/// [module docs](self#attaching)
+#[cfg(feature = "std")]
+pub struct SharedBlackBox<const N: usize = DEFAULT_ENTRIES> {
+    black_box: Mutex<BlackBox<N>>,
+    forward: Option<&'static dyn MetricsSink>,
+}
+
+#[cfg(feature = "std")]
+impl<const N: usize> SharedBlackBox<N> {
+    /// empty box
+    pub const fn new() -> Self {
+        Self {
+            black_box: Mutex::new(BlackBox::new()),
+            forward: None,
+        }
+    }
+
+    /// empty box passing every counter, gauge and event on to `sink`
+    pub const fn forwarding(sink: &'static dyn MetricsSink) -> Self {
+        Self {
+            black_box: Mutex::new(BlackBox::new()),
+            forward: Some(sink),
+        }
+    }
+
+    /// a copy of the box
+    pub fn black_box(&self) -> BlackBox<N> {
+        *self.lock()
+    }
+
+    /// [`BlackBox::export`], clearing the box if something was written: every event is in
+    /// at most one export
+    pub fn export(&self, out: &mut [u8]) -> usize {
+        let mut black_box = self.lock();
+        let len = black_box.export(out);
+        if len > 0 {
+            black_box.clear();
+        }
+        len
+    }
+
+    /// [`BlackBox::clear`]
+    pub fn clear(&self) {
+        self.lock().clear();
+    }
+
+    fn lock(&self) -> MutexGuard<'_, BlackBox<N>> {
+        // a panicking driver call leaves plain data behind, keep using it
+        self.black_box.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+#[cfg(feature = "std")]
+impl<const N: usize> Default for SharedBlackBox<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(feature = "std")]
+impl<const N: usize> MetricsSink for SharedBlackBox<N> {
+    fn counter(&self, name: &'static str, delta: u32) {
+        if let Some(sink) = self.forward {
+            sink.counter(name, delta);
+        }
+    }
+
+    fn gauge(&self, name: &'static str, value: f32) {
+        if let Some(sink) = self.forward {
+            sink.gauge(name, value);
+        }
+    }
+
+    fn event(&self, event: MetricEvent) {
+        self.lock().record(&event);
+        if let Some(sink) = self.forward {
+            sink.event(event);
+        }
+    }
+}
+
+impl<I, D> Mpu6050<I, D> {
+    /// [`MetricEvent::OperationFailed`] of `error`, hinted with the context of now, also
+    /// built for the log without a sink
+    pub(crate) fn emit_failure<E>(&self, operation: FailedOperation, error: &Mpu6050Error<E>) {
+        if self.metrics.is_some() || logging::ENABLED {
+            let record =
+                FailureRecord::new(operation, error, &self.hint_context(), self.failed_register);
+            self.emit_event(
+                metrics::OPERATION_FAILURES,
+                MetricEvent::OperationFailed(record),
+            );
+        }
+    }
+}
@@ -0,0 +1,398 @@
+//! Constraints of the board the chip is integrated on.
+//!
+//! The driver's defaults assume the chip alone on its INT line and AD0 at a level the firmware
+//! knows. [`BoardConstraints`], given to
+//! [`Mpu6050Builder::board_constraints`](crate::Mpu6050Builder::board_constraints), records
+//! what the board allows instead, and every method configuring INT_PIN_CFG or choosing the
+//! address honours it:
+//!
+//! | request | [`Unconstrained`](IntPinConstraint::Unconstrained) | [`RequireOpenDrainActiveLow`](IntPinConstraint::RequireOpenDrainActiveLow) | [`NotConnected`](IntPinConstraint::NotConnected) |
+//! |:---|:---|:---|:---|
+//! | `init`, [`reset_device`](Mpu6050::reset_device) | INT_PIN_CFG untouched | INT_OPEN and INT_LEVEL set before the wake, read back | untouched |
+//! | [`configure_int_pin`](Mpu6050::configure_int_pin) | written as given | rejected unless open-drain active-low | rejected |
+//! | [`setup_motion_detection`](Mpu6050::setup_motion_detection) | active-high push-pull, latched | adapted: open-drain active-low, latched | adapted: pin left alone, poll INT_STATUS |
+//! | [`enable_data_ready_strobe`](Mpu6050::enable_data_ready_strobe) | active-high push-pull 50 µs pulse | adapted: open-drain active-low pulse | rejected |
+//!
+//! A wired-OR INT line needs open-drain active-low on every device: the INT_PIN_CFG reset
+//! value, push-pull active-high, drives the idle line low, which is the start of bus
+//! contention. With [`RequireOpenDrainActiveLow`](IntPinConstraint::RequireOpenDrainActiveLow)
+//! init writes the pin configuration right after inspecting the chip and fails if the read
+//! back does not show it. Nothing can keep the chip from driving the line between its
+//! power-on and that write.
+//!
+//! [`AddrConstraint::Fixed`] sets the address and skips probing, giving the builder another
+//! [`slave_addr`](crate::Mpu6050Builder::slave_addr) fails the build.
+//! [`AddrConstraint::Probe`] is for an AD0 strapped by a jumper the firmware can't see: init
+//! starts with [`probe_address`](Mpu6050::probe_address), which takes the one address of
+//! [`DeviceAddr`] answering with a known WHO_AM_I, and fails with none or both answering.
+//! Every refusal is a [`Mpu6050Error::BoardConstraint`] naming the constraint and the request.
+//! Without constraints the driver behaves exactly as before.
+//!
+//! ```
+//! use mpu6050::board::*;
+//!
+//! let wired_or = IntPinConstraint::RequireOpenDrainActiveLow;
+//! let motion = wired_or.resolve(IntPinRequest::MotionDetection).unwrap();
+//! assert_eq!(motion, Some(IntPinConfig::MOTION.open_drain_active_low()));
+//! assert!(wired_or
+//!     .resolve(IntPinRequest::Configure(IntPinConfig::DATA_READY_STROBE))
+//!     .is_err());
+//! assert_eq!(
+//!     IntPinConstraint::NotConnected.resolve(IntPinRequest::MotionDetection),
+//!     Ok(None)
+//! );
+//! ```
+
+use core::fmt;
+
+#[cfg(feature = "driver")]
+use embedded_hal::blocking::i2c::{Write, WriteRead};
+
+use crate::Mpu6050;
+#[cfg(feature = "driver")]
+use crate::{
+    device::ChipVariant,
+    op_bounds,
+    register::Register,
+    registers::{INT_ENABLE, INT_PIN_CFG},
+    Mpu6050Error,
+};
+
+/// Address selected by the AD0 pin
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum DeviceAddr {
+    /// AD0 low, 0x68
+    Ad0Low,
+    /// AD0 high, 0x69
+    Ad0High,
+}
+
+impl DeviceAddr {
+    /// both addresses, in probing order
+    pub const ALL: [DeviceAddr; 2] = [DeviceAddr::Ad0Low, DeviceAddr::Ad0High];
+
+    /// 7 bit bus address
+    pub const fn addr(self) -> u8 {
+        match self {
+            DeviceAddr::Ad0Low => 0x68,
+            DeviceAddr::Ad0High => 0x69,
+        }
+    }
+}
+
+/// What the board allows on the INT pin
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub enum IntPinConstraint {
+    /// anything, the driver's defaults
+    #[default]
+    Unconstrained,
+    /// shared wired-OR line: open-drain and active-low only
+    RequireOpenDrainActiveLow,
+    /// INT not routed, interrupts are polled in INT_STATUS
+    NotConnected,
+}
+
+/// How the driver finds the chip's address
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum AddrConstraint {
+    /// known strapping, no probing
+    Fixed(DeviceAddr),
+    /// strapping unknown, probed at init
+    Probe,
+}
+
+/// Constraints of the board, see the module docs
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct BoardConstraints {
+    /// INT pin
+    pub int_pin: IntPinConstraint,
+    /// AD0 strapping
+    pub addr: AddrConstraint,
+}
+
+/// Electrical configuration of the INT pin, the INT_LEVEL, INT_OPEN, LATCH_INT_EN and
+/// INT_RD_CLEAR bits of INT_PIN_CFG
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct IntPinConfig {
+    /// asserted low, INT_LEVEL
+    pub active_low: bool,
+    /// open-drain instead of push-pull, INT_OPEN
+    pub open_drain: bool,
+    /// held until cleared instead of a 50 µs pulse, LATCH_INT_EN
+    pub latched: bool,
+    /// cleared by any read instead of only by reading INT_STATUS, INT_RD_CLEAR
+    pub clear_on_any_read: bool,
+}
+
+impl IntPinConfig {
+    /// pin of [`setup_motion_detection`](Mpu6050::setup_motion_detection): active-high,
+    /// push-pull, latched until INT_STATUS is read
+    pub const MOTION: Self = Self {
+        active_low: false,
+        open_drain: false,
+        latched: true,
+        clear_on_any_read: false,
+    };
+
+    /// pin of [`enable_data_ready_strobe`](Mpu6050::enable_data_ready_strobe): active-high,
+    /// push-pull, a 50 µs pulse per sample
+    pub const DATA_READY_STROBE: Self = Self {
+        active_low: false,
+        open_drain: false,
+        latched: false,
+        clear_on_any_read: false,
+    };
+
+    /// the same pin behaviour on a wired-OR line
+    pub const fn open_drain_active_low(self) -> Self {
+        Self {
+            active_low: true,
+            open_drain: true,
+            ..self
+        }
+    }
+
+    /// whether a shared wired-OR line allows it
+    pub const fn is_open_drain_active_low(&self) -> bool {
+        self.active_low && self.open_drain
+    }
+}
+
+/// Driver request touching the INT pin
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum IntPinRequest {
+    /// [`configure_int_pin`](Mpu6050::configure_int_pin), taken as given or refused
+    Configure(IntPinConfig),
+    /// [`setup_motion_detection`](Mpu6050::setup_motion_detection), adapted if possible
+    MotionDetection,
+    /// [`enable_data_ready_strobe`](Mpu6050::enable_data_ready_strobe), adapted if possible
+    DataReadyStrobe,
+}
+
+impl IntPinRequest {
+    /// pin configuration asked for
+    pub const fn config(self) -> IntPinConfig {
+        match self {
+            IntPinRequest::Configure(config) => config,
+            IntPinRequest::MotionDetection => IntPinConfig::MOTION,
+            IntPinRequest::DataReadyStrobe => IntPinConfig::DATA_READY_STROBE,
+        }
+    }
+}
+
+/// Request refused by a [`BoardConstraints`]
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum ConstraintViolation {
+    /// the INT pin configuration asked for is not allowed
+    IntPin {
+        /// constraint violated
+        constraint: IntPinConstraint,
+        /// offending request
+        request: IntPinRequest,
+    },
+    /// INT_PIN_CFG read back without the configuration init wrote
+    IntPinNotApplied {
+        /// constraint enforced
+        constraint: IntPinConstraint,
+        /// value read back
+        int_pin_cfg: u8,
+    },
+    /// the builder was given another address than the fixed one
+    AddrConflict {
+        /// constraint violated
+        constraint: AddrConstraint,
+        /// offending [`slave_addr`](crate::Mpu6050Builder::slave_addr)
+        slave_addr: u8,
+    },
+    /// probing did not find exactly one chip
+    ProbeFailed {
+        /// whether each address of [`DeviceAddr::ALL`] answered with a known WHO_AM_I
+        answered: [bool; 2],
+    },
+}
+
+impl fmt::Display for ConstraintViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConstraintViolation::IntPin {
+                constraint,
+                request,
+            } => write!(f, "INT pin {:?} refuses {:?}", constraint, request),
+            ConstraintViolation::IntPinNotApplied {
+                constraint,
+                int_pin_cfg,
+            } => write!(
+                f,
+                "INT pin {:?} not applied, INT_PIN_CFG reads {:#04x}",
+                constraint, int_pin_cfg
+            ),
+            ConstraintViolation::AddrConflict {
+                constraint,
+                slave_addr,
+            } => write!(
+                f,
+                "address {:?} conflicts with {:#04x}",
+                constraint, slave_addr
+            ),
+            ConstraintViolation::ProbeFailed { answered } => {
+                write!(f, "address probe found chips at {:?}", answered)
+            }
+        }
+    }
+}
+
+impl IntPinConstraint {
+    /// pin configuration to write for `request`: None to leave the pin alone, an error if the
+    /// request can't be honoured. See the module docs
+    pub fn resolve(
+        self,
+        request: IntPinRequest,
+    ) -> Result<Option<IntPinConfig>, ConstraintViolation> {
+        let wanted = request.config();
+        let refused = Err(ConstraintViolation::IntPin {
+            constraint: self,
+            request,
+        });
+        match (self, request) {
+            (IntPinConstraint::Unconstrained, _) => Ok(Some(wanted)),
+            (IntPinConstraint::RequireOpenDrainActiveLow, IntPinRequest::Configure(config)) => {
+                match config.is_open_drain_active_low() {
+                    true => Ok(Some(config)),
+                    false => refused,
+                }
+            }
+            (IntPinConstraint::RequireOpenDrainActiveLow, _) => {
+                Ok(Some(wanted.open_drain_active_low()))
+            }
+            (IntPinConstraint::NotConnected, IntPinRequest::MotionDetection) => Ok(None),
+            (IntPinConstraint::NotConnected, _) => refused,
+        }
+    }
+}
+
+impl<I, D> Mpu6050<I, D> {
+    /// Board constraints given to the builder, None if unconstrained
+    pub fn board_constraints(&self) -> Option<BoardConstraints> {
+        self.board
+    }
+
+    fn int_pin_constraint(&self) -> IntPinConstraint {
+        self.board
+            .map_or(IntPinConstraint::Unconstrained, |board| board.int_pin)
+    }
+}
+
+#[cfg(feature = "driver")]
+impl<I, D, E> Mpu6050<I, D>
+where
+    I: Write<Error = E> + WriteRead<Error = E>,
+{
+    /// Configures the INT pin as given, refused with [`Mpu6050Error::BoardConstraint`] if the
+    /// board does not allow it
+    pub fn configure_int_pin(&mut self, config: IntPinConfig) -> Result<(), Mpu6050Error<E>> {
+        match self.resolve_int_pin(IntPinRequest::Configure(config))? {
+            Some(config) => self.write_int_pin_config(config),
+            None => Ok(()),
+        }
+    }
+
+    /// Pulses INT for 50 µs on every new sample (DATA_RDY_EN), the pin adapted to the board.
+    /// Refused if INT is not connected
+    pub fn enable_data_ready_strobe(&mut self) -> Result<(), Mpu6050Error<E>> {
+        if let Some(config) = self.resolve_int_pin(IntPinRequest::DataReadyStrobe)? {
+            self.write_int_pin_config(config)?;
+        }
+        self.write_field(INT_ENABLE::DATA_RDY_EN_FIELD, true)
+    }
+
+    /// Reads WHO_AM_I at both [`DeviceAddr`]s and returns the one address answering with a
+    /// known chip. Refused with
+    /// [`ProbeFailed`](ConstraintViolation::ProbeFailed) if none or both answer. The
+    /// unanswered probe is not a bus error to the connection monitor
+    pub fn probe_address(&mut self) -> Result<u8, Mpu6050Error<E>> {
+        self.check_active()?;
+        let mut answered = [false; 2];
+        for (addr, answer) in DeviceAddr::ALL.iter().zip(answered.iter_mut()) {
+            let mut who_am_i = [0];
+            let res = self
+                .i2c
+                .write_read(addr.addr(), &[Register::WHO_AM_I.addr()], &mut who_am_i);
+            self.io_stats.record(op_bounds::read_cost_bytes(1));
+            *answer = res.is_ok()
+                && !matches!(
+                    ChipVariant::from_who_am_i(who_am_i[0]),
+                    ChipVariant::Unknown(_)
+                );
+        }
+        match answered {
+            [true, false] => Ok(DeviceAddr::Ad0Low.addr()),
+            [false, true] => Ok(DeviceAddr::Ad0High.addr()),
+            _ => Err(Mpu6050Error::BoardConstraint(
+                ConstraintViolation::ProbeFailed { answered },
+            )),
+        }
+    }
+
+    /// INT pin configuration to write for `request`, see [`IntPinConstraint::resolve`]
+    pub(crate) fn resolve_int_pin(
+        &self,
+        request: IntPinRequest,
+    ) -> Result<Option<IntPinConfig>, Mpu6050Error<E>> {
+        self.int_pin_constraint()
+            .resolve(request)
+            .map_err(Mpu6050Error::BoardConstraint)
+    }
+
+    pub(crate) fn write_int_pin_config(
+        &mut self,
+        config: IntPinConfig,
+    ) -> Result<(), Mpu6050Error<E>> {
+        for (field, value) in [
+            (INT_PIN_CFG::INT_LEVEL_FIELD, config.active_low),
+            (INT_PIN_CFG::INT_OPEN_FIELD, config.open_drain),
+            (INT_PIN_CFG::LATCH_INT_EN_FIELD, config.latched),
+            (INT_PIN_CFG::INT_RD_CLEAR_FIELD, config.clear_on_any_read),
+        ] {
+            self.write_field(field, value)?;
+        }
+        Ok(())
+    }
+
+    /// Probes the address if the board asks for it, the start of init
+    pub(crate) fn select_board_address(&mut self) -> Result<(), Mpu6050Error<E>> {
+        if let Some(BoardConstraints {
+            addr: AddrConstraint::Probe,
+            ..
+        }) = self.board
+        {
+            self.slave_addr = self.probe_address()?;
+        }
+        Ok(())
+    }
+
+    /// Puts INT open-drain active-low if the board requires it and reads it back, after init
+    /// inspected the chip and after a reset
+    pub(crate) fn enforce_int_pin_constraint(&mut self) -> Result<(), Mpu6050Error<E>> {
+        let constraint = self.int_pin_constraint();
+        if constraint != IntPinConstraint::RequireOpenDrainActiveLow {
+            return Ok(());
+        }
+        let required = [INT_PIN_CFG::INT_LEVEL_FIELD, INT_PIN_CFG::INT_OPEN_FIELD];
+        let int_pin_cfg = self.read_fields::<INT_PIN_CFG>()?;
+        self.write_fields(
+            required
+                .iter()
+                .fold(int_pin_cfg, |fields, field| fields.with(*field, true)),
+        )?;
+        let int_pin_cfg = self.read_fields::<INT_PIN_CFG>()?;
+        if !required.iter().all(|field| int_pin_cfg.flag(*field)) {
+            return Err(Mpu6050Error::BoardConstraint(
+                ConstraintViolation::IntPinNotApplied {
+                    constraint,
+                    int_pin_cfg: int_pin_cfg.byte(),
+                },
+            ));
+        }
+        Ok(())
+    }
+}
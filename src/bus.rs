@@ -0,0 +1,207 @@
+//! I2C bus budget: how many bits each driver operation puts on the wire, and which sample
+//! rates a bus can sustain.
+//!
+//! #### Wire model
+//! Every byte on the bus is 9 clock cycles (8 data bits + ACK/NACK), START, repeated START and
+//! STOP are one clock cycle each. With `n` payload bytes:
+//! * register write (`write_register`): START, address+W, register, n data, STOP
+//!   = `1 + 9 * (2 + n) + 1` bits
+//! * register read (`read_registers`, write_read with repeated start): START, address+W, register,
+//!   repeated START, address+R, n data, STOP = `1 + 9 * 2 + 1 + 9 * (1 + n) + 1` bits
+//!
+//! Bus idle time between transactions (tBUF), clock stretching and other traffic are not part of
+//! the model, account for them with the `overhead_fraction` of [`BusBudget`].
+//!
+//! #### Runtime check
+//! [`Mpu6050::validate_rate_against_bus`](crate::Mpu6050::validate_rate_against_bus) checks the
+//! configured output data rate. With [`Mpu6050Builder::bus_hz`](crate::Mpu6050Builder::bus_hz)
+//! the sample rate setters run the check before writing SMPLRT_DIV and refuse a rate the bus
+//! cannot sustain with [`Mpu6050Error::BusRateTooHigh`](crate::Mpu6050Error::BusRateTooHigh).
+
+use core::fmt;
+
+use crate::aliasing;
+use crate::decimal::Decimal;
+
+/// Overhead fraction used by [`Mpu6050::validate_rate_against_bus`](crate::Mpu6050::validate_rate_against_bus)
+pub const DEFAULT_BUS_OVERHEAD: f32 = 0.2;
+
+/// Bus transactions performed by the driver
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum DriverOp {
+    /// accel, temp and gyro in one 14 byte read
+    SampleBurst,
+    /// accel only, 6 byte read (`get_acc`)
+    AccelRead,
+    /// gyro only, 6 byte read (`get_gyro`)
+    GyroRead,
+    /// temperature only, 2 byte read (`get_temp`)
+    TempRead,
+    /// FIFO drain of n bytes
+    FifoDrain(usize),
+    /// single register write (`write_register`)
+    ConfigWrite,
+    /// read-modify-write of a register (`modify_register`, `write_field`, `write_register_field`)
+    ConfigUpdate,
+    /// interrupt status read, 1 byte
+    InterruptStatusRead,
+    /// any other register read of n bytes
+    RegisterRead(usize),
+}
+
+/// Kind and payload size of a single transaction
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+enum Transaction {
+    Write(usize),
+    Read(usize),
+}
+
+impl Transaction {
+    fn bytes(self) -> usize {
+        match self {
+            Transaction::Write(n) => 2 + n,
+            Transaction::Read(n) => 3 + n,
+        }
+    }
+
+    fn bits(self) -> usize {
+        match self {
+            Transaction::Write(n) => 1 + 9 * (2 + n) + 1,
+            Transaction::Read(n) => 1 + 9 * 2 + 1 + 9 * (1 + n) + 1,
+        }
+    }
+}
+
+impl DriverOp {
+    fn transactions(self) -> (Transaction, Option<Transaction>) {
+        match self {
+            DriverOp::SampleBurst => (Transaction::Read(14), None),
+            DriverOp::AccelRead | DriverOp::GyroRead => (Transaction::Read(6), None),
+            DriverOp::TempRead => (Transaction::Read(2), None),
+            DriverOp::FifoDrain(n) => (Transaction::Read(n), None),
+            DriverOp::ConfigWrite => (Transaction::Write(1), None),
+            DriverOp::ConfigUpdate => (Transaction::Read(1), Some(Transaction::Write(1))),
+            DriverOp::InterruptStatusRead => (Transaction::Read(1), None),
+            DriverOp::RegisterRead(n) => (Transaction::Read(n), None),
+        }
+    }
+}
+
+/// Bytes on the wire for `op`: address bytes, register byte and payload. ACKs are not counted
+pub fn transaction_cost_bytes(op: DriverOp) -> usize {
+    let (first, second) = op.transactions();
+    first.bytes() + second.map_or(0, Transaction::bytes)
+}
+
+/// Clock cycles on the wire for `op`, including ACKs, START, repeated START and STOP
+pub fn transaction_cost_bits(op: DriverOp) -> usize {
+    let (first, second) = op.transactions();
+    first.bits() + second.map_or(0, Transaction::bits)
+}
+
+/// Operations performed for every sample
+#[derive(Copy, Clone, Debug)]
+pub struct ReadPlan<'a> {
+    /// ops per sample
+    pub per_sample: &'a [DriverOp],
+}
+
+impl ReadPlan<'static> {
+    /// `get_acc`, `get_gyro` and `get_temp` per sample
+    pub const SEPARATE: ReadPlan<'static> = ReadPlan {
+        per_sample: &[DriverOp::AccelRead, DriverOp::GyroRead, DriverOp::TempRead],
+    };
+
+    /// one burst read per sample
+    pub const BURST: ReadPlan<'static> = ReadPlan {
+        per_sample: &[DriverOp::SampleBurst],
+    };
+
+    /// interrupt status check followed by a burst read
+    pub const BURST_WITH_STATUS: ReadPlan<'static> = ReadPlan {
+        per_sample: &[DriverOp::InterruptStatusRead, DriverOp::SampleBurst],
+    };
+}
+
+impl ReadPlan<'_> {
+    /// clock cycles per sample
+    pub fn bits_per_sample(&self) -> usize {
+        self.per_sample
+            .iter()
+            .map(|op| transaction_cost_bits(*op))
+            .sum()
+    }
+}
+
+/// Available bus bandwidth
+#[derive(Copy, Clone, Debug)]
+pub struct BusBudget {
+    bus_hz: u32,
+    overhead_fraction: f32,
+}
+
+impl BusBudget {
+    /// `overhead_fraction` of the bus is reserved for idle time and other devices, clamped to 0..1
+    pub fn new(bus_hz: u32, overhead_fraction: f32) -> Self {
+        Self {
+            bus_hz,
+            overhead_fraction: overhead_fraction.clamp(0.0, 1.0),
+        }
+    }
+
+    /// clock cycles per second available to the driver
+    pub fn available_bits_per_second(&self) -> f32 {
+        self.bus_hz as f32 * (1.0 - self.overhead_fraction)
+    }
+
+    /// highest sample rate in Hz the bus sustains with `plan`
+    pub fn max_sample_rate(&self, plan: &ReadPlan) -> f32 {
+        let bits = plan.bits_per_sample();
+        if bits == 0 {
+            return f32::INFINITY;
+        }
+        self.available_bits_per_second() / bits as f32
+    }
+}
+
+/// Requested sample rate exceeds what the bus sustains
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct RateTooHigh {
+    /// requested rate in Hz
+    pub required_hz: f32,
+    /// achievable rate in Hz
+    pub available_hz: f32,
+}
+
+impl fmt::Display for RateTooHigh {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "sample rate {} Hz exceeds bus capacity of {} Hz",
+            Decimal(self.required_hz),
+            Decimal(self.available_hz)
+        )
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for RateTooHigh {}
+
+/// Checks the output data rate of DLPF_CFG `dlpf_cfg` at SMPLRT_DIV `sample_rate_div` against
+/// a bus at `bus_hz` read with [`ReadPlan::SEPARATE`], reserving [`DEFAULT_BUS_OVERHEAD`]
+pub(crate) fn check_rate(
+    bus_hz: u32,
+    dlpf_cfg: u8,
+    sample_rate_div: u8,
+) -> Result<(), RateTooHigh> {
+    let required_hz = aliasing::assess(dlpf_cfg, sample_rate_div, false).odr_hz;
+    let available_hz =
+        BusBudget::new(bus_hz, DEFAULT_BUS_OVERHEAD).max_sample_rate(&ReadPlan::SEPARATE);
+    if required_hz > available_hz {
+        return Err(RateTooHigh {
+            required_hz,
+            available_hz,
+        });
+    }
+    Ok(())
+}
@@ -0,0 +1,279 @@
+//! Gyro and accel calibration at rest, in one call or in the background.
+//!
+//! [`Mpu6050::calibrate_gyro`] and [`Mpu6050::calibrate_accel`] average `samples` readings
+//! 2 ms apart with the device still, for the accelerometer lying level with Z up, then
+//! apply and return the offsets. The readings are in g at any range, so the gravity
+//! subtracted is 1 g on Z whatever the [`AccelRange`](crate::device::AccelRange). A per-axis
+//! standard deviation above the limit of [`AutoSetupOptions::default`], 0.01 rad/s or
+//! 0.02 g, means the device moved: the call fails with
+//! [`AutoSetupFailure::CalibrationRejected`] and the offsets stay as they were. Other limits
+//! or another attitude are the [referenced calibrations](crate::platform) with a constant
+//! reference, the same estimator.
+//!
+//! The returned offsets are for storing: passed to
+//! [`Mpu6050Builder::gyro_offset`](crate::Mpu6050Builder::gyro_offset) and
+//! [`acc_offset`](crate::Mpu6050Builder::acc_offset) on the next boot they apply without
+//! calibrating again. [`Mpu6050::get_gyro_offset`] and [`Mpu6050::get_acc_offset`] read the
+//! offsets applied, from whichever source.
+//!
+//! #### Offsets
+//! An offset is the bias of the sensor in physical units, g for the accelerometer and rad/s
+//! for the gyro, and is subtracted from the scaled reading: a still gyro reading 0.02 rad/s
+//! on X has an offset of 0.02 on X. Scaling by the range comes first, so the offsets do not
+//! depend on it: a range change, by [`Mpu6050::set_accel_range`], a settings apply or range
+//! interleaving, leaves them valid and untouched, and offsets calibrated at one range apply
+//! at every other. [`Mpu6050::set_acc_offset`] and [`Mpu6050::set_gyro_offset`] replace them
+//! at runtime and move the [configuration epoch](crate::config_epoch). Offsets stored by
+//! releases that added them to readings have the opposite sign and are negated once.
+//!
+//! #### Background calibration
+//! [`Mpu6050::start_background_calibration`] makes every `get_gyro` also feed a calibration
+//! accumulator, as long as the device is still: the offset corrected rate is below
+//! `stillness_threshold`. Motion pauses accumulation, it does not reset it. No additional bus
+//! transactions are made, only samples already being read are used.
+//!
+//! Once `samples` still readings are collected their standard deviation is checked against
+//! `max_std_dev`. If it is too large the accumulator restarts. Otherwise the new offset is
+//! swapped in before the next read and the mode ends, or, with `manual_commit`, it is staged
+//! until [`Mpu6050::commit_background_calibration`] is called.
+
+#[cfg(feature = "driver")]
+use embedded_hal::blocking::{
+    delay::DelayMs,
+    i2c::{Write, WriteRead},
+};
+use glam::Vec3A;
+
+use crate::config_epoch::ConfigChange;
+use crate::log_header::CalibrationMethod;
+#[cfg(feature = "driver")]
+use crate::platform::{CalibrationResult, ReferencedCalibration};
+use crate::setup::Accumulator;
+#[cfg(feature = "driver")]
+use crate::setup::{AutoSetupFailure, AutoSetupOptions};
+use crate::validity::{CalibrationKind, CalibrationMeta};
+use crate::Mpu6050;
+
+/// Background calibration options
+#[derive(Copy, Clone, Debug)]
+pub struct CalibrationConfig {
+    /// number of still samples to collect
+    pub samples: u32,
+    /// maximum per-axis standard deviation in rad/s of the collected samples
+    pub max_std_dev: f32,
+    /// samples with an offset corrected rate magnitude above this (rad/s) count as motion
+    pub stillness_threshold: f32,
+    /// stage the result until `commit_background_calibration` instead of applying it
+    pub manual_commit: bool,
+}
+
+impl Default for CalibrationConfig {
+    fn default() -> Self {
+        Self {
+            samples: 500,
+            max_std_dev: 0.01,
+            stillness_threshold: 0.1,
+            manual_commit: false,
+        }
+    }
+}
+
+/// Progress of a background calibration
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct BackgroundCalibrationStatus {
+    /// still samples collected in the current attempt
+    pub accumulated: u32,
+    /// still samples still needed
+    pub remaining: u32,
+    /// largest per-axis standard deviation of the samples so far, rad/s
+    pub std_dev: f32,
+    /// attempts rejected because of `max_std_dev`
+    pub rejected: u32,
+    /// offset waiting for `commit_background_calibration`
+    pub staged: Option<Vec3A>,
+}
+
+#[derive(Copy, Clone, Debug)]
+pub(crate) struct BackgroundCalibration {
+    config: CalibrationConfig,
+    acc: Accumulator,
+    rejected: u32,
+    staged: Option<Vec3A>,
+}
+
+impl BackgroundCalibration {
+    fn new(config: CalibrationConfig) -> Self {
+        Self {
+            config,
+            acc: Accumulator::default(),
+            rejected: 0,
+            staged: None,
+        }
+    }
+
+    /// Feeds a reading (scaled, no offset) and the offset corrected value.
+    /// Returns the new offset once the calibration completed
+    #[cfg(not(feature = "minimal-pipeline"))]
+    fn feed(&mut self, uncorrected: Vec3A, corrected: Vec3A) -> Option<Vec3A> {
+        if self.staged.is_some() || corrected.length() > self.config.stillness_threshold {
+            return None;
+        }
+        self.acc.push(uncorrected);
+        if self.acc.count() < self.config.samples {
+            return None;
+        }
+        if self.acc.max_std_dev() > self.config.max_std_dev {
+            self.rejected += 1;
+            self.acc = Accumulator::default();
+            return None;
+        }
+        Some(self.acc.mean())
+    }
+
+    pub(crate) fn status(&self) -> BackgroundCalibrationStatus {
+        BackgroundCalibrationStatus {
+            accumulated: self.acc.count(),
+            remaining: self.config.samples.saturating_sub(self.acc.count()),
+            std_dev: self.acc.max_std_dev(),
+            rejected: self.rejected,
+            staged: self.staged,
+        }
+    }
+}
+
+#[cfg(feature = "driver")]
+impl<I, D> Mpu6050<I, D> {
+    /// Sets the accelerometer offset in g, the bias subtracted from every reading, a
+    /// [`Vec3A`] or `[x, y, z]`. Stays valid across range changes, see [offsets](self#offsets)
+    pub fn set_acc_offset(&mut self, acc_offset: impl Into<Vec3A>) {
+        self.acc_offset = acc_offset.into();
+        self.bump_epoch(ConfigChange::Offsets);
+    }
+
+    /// Sets the gyro offset in rad/s, the bias subtracted from every reading, a [`Vec3A`] or
+    /// `[x, y, z]`. Stays valid across range changes, see [offsets](self#offsets)
+    pub fn set_gyro_offset(&mut self, gyro_offset: impl Into<Vec3A>) {
+        self.gyro_offset = gyro_offset.into();
+        self.bump_epoch(ConfigChange::Offsets);
+    }
+
+    /// Starts a background gyro calibration, replacing one in progress
+    pub fn start_background_calibration(&mut self, config: CalibrationConfig) {
+        self.background_calibration = Some(BackgroundCalibration::new(config));
+    }
+
+    /// Progress of the background calibration, None if none is running
+    pub fn background_calibration_status(&self) -> Option<BackgroundCalibrationStatus> {
+        self.background_calibration
+            .as_ref()
+            .map(BackgroundCalibration::status)
+    }
+
+    /// Applies a staged offset and ends the background calibration.
+    /// Returns the applied offset, None if nothing was staged (calibration keeps running)
+    pub fn commit_background_calibration(&mut self) -> Option<Vec3A> {
+        let calibration = self.background_calibration.as_ref()?;
+        let offset = calibration.staged?;
+        let std_dev = calibration.acc.max_std_dev();
+        self.gyro_offset = offset;
+        self.bump_epoch(ConfigChange::Offsets);
+        self.background_calibration = None;
+        self.record_background_calibration(std_dev);
+        Some(offset)
+    }
+
+    /// Stops the background calibration without applying anything
+    pub fn cancel_background_calibration(&mut self) {
+        self.background_calibration = None;
+    }
+
+    /// Called from the gyro read path
+    #[cfg(not(feature = "minimal-pipeline"))]
+    pub(crate) fn feed_background_calibration(&mut self, uncorrected: Vec3A, corrected: Vec3A) {
+        let Some(calibration) = &mut self.background_calibration else {
+            return;
+        };
+        if let Some(offset) = calibration.feed(uncorrected, corrected) {
+            if calibration.config.manual_commit {
+                calibration.staged = Some(offset);
+            } else {
+                let std_dev = calibration.acc.max_std_dev();
+                self.gyro_offset = offset;
+                self.bump_epoch(ConfigChange::Offsets);
+                self.background_calibration = None;
+                self.record_background_calibration(std_dev);
+            }
+        }
+    }
+
+    /// [`CalibrationMeta`] of an applied background calibration
+    fn record_background_calibration(&mut self, gyro_std_dev: f32) {
+        let meta = CalibrationMeta {
+            gyro_std_dev: Some(gyro_std_dev),
+            ..CalibrationMeta::new(CalibrationMethod::Background)
+        };
+        self.set_calibration_meta(CalibrationKind::SoftwareOffsets, Some(meta));
+    }
+}
+
+#[cfg(feature = "driver")]
+impl<I, D, E> Mpu6050<I, D>
+where
+    I: Write<Error = E> + WriteRead<Error = E>,
+{
+    /// `progress` gets the samples taken after each
+    pub(crate) fn calibrate_gyro_using(
+        &mut self,
+        delay: &mut impl DelayMs<u8>,
+        samples: u16,
+        progress: &mut dyn FnMut(u16),
+        deadline: &mut dyn FnMut() -> bool,
+    ) -> Result<CalibrationResult, AutoSetupFailure<E>> {
+        let options = ReferencedCalibration {
+            samples,
+            ..ReferencedCalibration::gyro(&AutoSetupOptions::default())
+        };
+        let mut taken = 0;
+        let reference = || {
+            taken += 1;
+            progress(taken);
+            Vec3A::ZERO
+        };
+        let result =
+            self.calibrate_gyro_with_reference_using(delay, reference, options, deadline)?;
+        let meta = CalibrationMeta {
+            gyro_std_dev: Some(result.std_dev),
+            ..CalibrationMeta::new(CalibrationMethod::Static)
+        };
+        self.set_calibration_meta(CalibrationKind::SoftwareOffsets, Some(meta));
+        Ok(result)
+    }
+
+    /// `progress` gets the samples taken after each
+    pub(crate) fn calibrate_accel_using(
+        &mut self,
+        delay: &mut impl DelayMs<u8>,
+        samples: u16,
+        progress: &mut dyn FnMut(u16),
+        deadline: &mut dyn FnMut() -> bool,
+    ) -> Result<CalibrationResult, AutoSetupFailure<E>> {
+        let options = ReferencedCalibration {
+            samples,
+            ..ReferencedCalibration::accel(&AutoSetupOptions::default())
+        };
+        let mut taken = 0;
+        let reference = || {
+            taken += 1;
+            progress(taken);
+            Vec3A::Z
+        };
+        let result =
+            self.calibrate_accel_with_reference_using(delay, reference, options, deadline)?;
+        let meta = CalibrationMeta {
+            acc_std_dev: Some(result.std_dev),
+            ..CalibrationMeta::new(CalibrationMethod::Static)
+        };
+        self.set_calibration_meta(CalibrationKind::SoftwareOffsets, Some(meta));
+        Ok(result)
+    }
+}
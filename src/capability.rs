@@ -0,0 +1,89 @@
+//! Runtime view of what the connected chip supports.
+//!
+//! [`Mpu6050::probe_capabilities`] looks up the capability table in
+//! [`device::capabilities`](crate::device::capabilities) for the WHO_AM_I answer, then checks
+//! the FIFO functionally: a pattern is written to FIFO_EN and read back, and the previous
+//! value is restored afterwards. With USER_CTRL FIFO_EN clear the FIFO is not written to,
+//! so the probe is harmless, and it leaves every register as it found it. Parts whose FIFO_EN
+//! does not hold the pattern are reported without FIFO. A
+//! [clone assessment](crate::clone_detect) and the [motion engine](crate::motion_verify)
+//! status are kept while the variant stays the same.
+//!
+//! Until probed, the driver assumes an MPU-6050. Feature entry points check the capabilities
+//! and return [`Mpu6050Error::Unsupported`] on parts without the feature.
+
+#[cfg(feature = "driver")]
+use embedded_hal::blocking::i2c::{Write, WriteRead};
+
+#[cfg(feature = "driver")]
+use crate::device::{self, ChipVariant};
+use crate::device::{AccelOffsetScaling, Capability, ChipCapabilities};
+use crate::register::Register;
+use crate::{Mpu6050, Mpu6050Error};
+
+/// Gyro X, Y, Z and accel to FIFO, inverted if FIFO_EN already holds it
+const FIFO_PROBE_PATTERN: u8 = 0x78;
+
+impl<I, D> Mpu6050<I, D> {
+    /// capabilities found by the last probe, those of an MPU-6050 if never probed
+    pub fn capabilities(&self) -> ChipCapabilities {
+        self.capabilities
+    }
+
+    /// [`Mpu6050Error::Unsupported`] unless the chip has `capability`
+    pub(crate) fn require<E>(&self, capability: Capability) -> Result<(), Mpu6050Error<E>> {
+        let caps = &self.capabilities;
+        let supported = match capability {
+            Capability::Fifo => caps.fifo_available,
+            Capability::Dmp => caps.dmp_loadable,
+            Capability::AuxI2cMaster => caps.aux_i2c_master,
+            Capability::AccelOffsets => caps.accel_offset_scaling != AccelOffsetScaling::Unknown,
+        };
+        if supported {
+            Ok(())
+        } else {
+            Err(Mpu6050Error::Unsupported(capability))
+        }
+    }
+}
+
+#[cfg(feature = "driver")]
+impl<I, D, E> Mpu6050<I, D>
+where
+    I: Write<Error = E> + WriteRead<Error = E>,
+{
+    /// Reads WHO_AM_I and probes the FIFO, see the [module docs](self). Done by `init`
+    pub fn probe_capabilities(&mut self) -> Result<ChipCapabilities, Mpu6050Error<E>> {
+        let variant = ChipVariant::from_who_am_i(self.read_register(Register::WHO_AM_I)?);
+        let mut caps = device::capabilities(variant);
+        if self.capabilities.variant == variant {
+            if let Some(assessment) = self.capabilities.clone_assessment {
+                caps = caps.with_clone_assessment(assessment);
+            }
+            caps.motion_engine = self.capabilities.motion_engine;
+        }
+        if caps.fifo_available {
+            caps.fifo_available = self.probe_fifo()?;
+        }
+        self.capabilities = caps;
+        Ok(caps)
+    }
+
+    /// true if FIFO_EN holds a written pattern, the previous value is restored
+    fn probe_fifo(&mut self) -> Result<bool, Mpu6050Error<E>> {
+        let previous = self.read_register(Register::FIFO_EN)?;
+        let pattern = if previous == FIFO_PROBE_PATTERN {
+            !FIFO_PROBE_PATTERN
+        } else {
+            FIFO_PROBE_PATTERN
+        };
+        let readback = self
+            .write_register(Register::FIFO_EN, pattern)
+            .and_then(|_| self.read_register(Register::FIFO_EN));
+        // restore even if the readback failed
+        let restored = self.write_register(Register::FIFO_EN, previous);
+        let readback = readback?;
+        restored?;
+        Ok(readback == pattern)
+    }
+}
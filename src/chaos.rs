@@ -0,0 +1,392 @@
+//! Fault injection for testing error handling without unplugging hardware.
+//!
+//! [`FlakyI2c`] wraps any I2C implementation, real or mock, and fails or corrupts
+//! transactions according to a [`ChaosConfig`]. Faults are injected before the inner bus is
+//! touched, so a failed write never reaches the device. Probabilistic faults come from a
+//! seeded generator: the same config and the same sequence of transactions produce the same
+//! faults. A [`ChaosHandle`] stays with the test while the driver owns the wrapper, to change
+//! the scenario and to assert on what was injected.
+//!
+//! Enable with the `test-util` feature, e.g. as a dev-dependency:
+//! `mpu6050 = { version = "0.2", features = ["test-util"] }`.
+//!
+//! #### What the crate's own chaos tests cover
+//! `tests/chaos.rs` and `tests/bus_recovery.rs` run the driver's fault handling under injected
+//! faults:
+//! * disconnect after consecutive failures and [`try_reconnect`](crate::Mpu6050::try_reconnect),
+//!   see [`connection`](crate::connection)
+//! * bus lockup detection and the recovery callback, see [`recovery`](crate::recovery)
+//! * settling discards, whose retries a failed read does not consume
+//! * spike rejection of corrupted reads: the supervisor's debounce, a median
+//!   [`FilteredView`](crate::smoothing::FilteredView) and the
+//!   [`plausibility`](crate::plausibility) scores
+//!
+//! The driver has no transaction retry policy: a failed transaction is returned at once,
+//! without side effects on the driver state, and retrying is the application's decision.
+//!
+//! #### Chaos runs against the real sensor
+//! The bus of the board, e.g. `linux::I2cdev` with the `linux` feature, is
+//! wrapped before the driver is built:
+//! ```no_run
+//! # use core::fmt::Debug;
+//! # use embedded_hal::blocking::delay::DelayMs;
+//! # use embedded_hal::blocking::i2c::{Write, WriteRead};
+//! # fn run<I: Write<Error = E> + WriteRead<Error = E>, E: Debug>(
+//! #     i2c: I,
+//! #     mut delay: impl DelayMs<u8>,
+//! # ) {
+//! use mpu6050::chaos::{ChaosConfig, FaultKind, FlakyI2c};
+//! use mpu6050::connection::ConnectionState;
+//! use mpu6050::*;
+//!
+//! // one NAK every 50 transactions plus 1% bus errors, reproducible with seed 7
+//! let config = ChaosConfig::NONE
+//!     .with_fail_every(50)
+//!     .with_fail_probability(0.01, 7)
+//!     .with_fault(FaultKind::BusError);
+//! let (i2c, chaos) = FlakyI2c::new(i2c, config);
+//! let mut mpu = Mpu6050Builder::new()
+//!     .i2c(i2c)
+//!     .auto_disconnect(true)
+//!     .build()
+//!     .unwrap();
+//!
+//! // bring the sensor up without faults, then run the application loop under chaos
+//! chaos.pause(true);
+//! mpu.init(&mut delay).unwrap();
+//! chaos.pause(false);
+//! for _ in 0..10_000 {
+//!     if mpu.get_acc().is_err() && mpu.connection_state() != ConnectionState::Connected {
+//!         mpu.try_reconnect(&mut delay).ok();
+//!     }
+//! }
+//! println!("{:?}", chaos.stats());
+//! # }
+//! ```
+
+use core::cell::RefCell;
+use core::fmt::{self, Debug, Display};
+use std::rc::Rc;
+
+use embedded_hal::blocking::i2c::{Read, Write, WriteRead};
+
+/// How an injected failure is reported
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum FaultKind {
+    /// the device did not acknowledge
+    Nak,
+    /// bus level error: arbitration loss, stuck line
+    BusError,
+}
+
+/// Single bit flipped in read data
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct BitFlip {
+    /// byte index in the read buffer, reads shorter than this are never corrupted
+    pub byte: usize,
+    /// bit in that byte, 0..=7
+    pub bit: u8,
+    /// corrupt every nth eligible read, 0 behaves like 1
+    pub every: u32,
+}
+
+/// Fault scenario of a [`FlakyI2c`]
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct ChaosConfig {
+    /// fail every nth transaction, counting from 1
+    pub fail_every: Option<u32>,
+    /// probability of failing any transaction, 0..=1
+    pub fail_probability: f32,
+    /// seed of the generator behind `fail_probability`
+    pub seed: u64,
+    /// how injected failures are reported
+    pub fault: FaultKind,
+    /// read corruption
+    pub corrupt: Option<BitFlip>,
+}
+
+impl ChaosConfig {
+    /// no faults
+    pub const NONE: ChaosConfig = ChaosConfig {
+        fail_every: None,
+        fail_probability: 0.0,
+        seed: 0,
+        fault: FaultKind::Nak,
+        corrupt: None,
+    };
+
+    /// fail every nth transaction, 0 disables
+    pub const fn with_fail_every(mut self, n: u32) -> Self {
+        self.fail_every = if n == 0 { None } else { Some(n) };
+        self
+    }
+
+    /// fail transactions with `probability`, drawn from a generator seeded with `seed`
+    pub const fn with_fail_probability(mut self, probability: f32, seed: u64) -> Self {
+        self.fail_probability = probability;
+        self.seed = seed;
+        self
+    }
+
+    /// report failures as `fault`
+    pub const fn with_fault(mut self, fault: FaultKind) -> Self {
+        self.fault = fault;
+        self
+    }
+
+    /// flip a bit of read data
+    pub const fn with_corruption(mut self, flip: BitFlip) -> Self {
+        self.corrupt = Some(flip);
+        self
+    }
+}
+
+impl Default for ChaosConfig {
+    fn default() -> Self {
+        Self::NONE
+    }
+}
+
+/// What a [`FlakyI2c`] saw and injected so far
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub struct ChaosStats {
+    /// transactions requested by the caller, failed ones included
+    pub transactions: u32,
+    /// transactions failed by injection
+    pub faults: u32,
+    /// reads with a flipped bit
+    pub corruptions: u32,
+}
+
+/// Error of a [`FlakyI2c`]
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum FlakyError<E> {
+    /// injected failure
+    Injected(FaultKind),
+    /// error of the inner bus
+    Inner(E),
+}
+
+impl<E: Display> Display for FlakyError<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FlakyError::Injected(FaultKind::Nak) => f.write_str("injected NAK"),
+            FlakyError::Injected(FaultKind::BusError) => f.write_str("injected bus error"),
+            FlakyError::Inner(error) => write!(f, "{}", error),
+        }
+    }
+}
+
+impl<E: Debug + Display> std::error::Error for FlakyError<E> {}
+
+#[derive(Debug)]
+struct ChaosState {
+    config: ChaosConfig,
+    rng: u64,
+    paused: bool,
+    fail_next: u32,
+    eligible_reads: u32,
+    last_fault: Option<FaultKind>,
+    stats: ChaosStats,
+}
+
+impl ChaosState {
+    fn new(config: ChaosConfig) -> Self {
+        Self {
+            config,
+            rng: seed_state(config.seed),
+            paused: false,
+            fail_next: 0,
+            eligible_reads: 0,
+            last_fault: None,
+            stats: ChaosStats::default(),
+        }
+    }
+
+    /// xorshift64*, upper 24 bits as a fraction
+    fn next_fraction(&mut self) -> f32 {
+        self.rng ^= self.rng >> 12;
+        self.rng ^= self.rng << 25;
+        self.rng ^= self.rng >> 27;
+        let x = self.rng.wrapping_mul(0x2545_f491_4f6c_dd1d);
+        (x >> 40) as f32 / (1u32 << 24) as f32
+    }
+
+    /// Called before each transaction, the fault to inject if any
+    fn begin(&mut self) -> Option<FaultKind> {
+        self.last_fault = self.draw();
+        self.last_fault
+    }
+
+    fn draw(&mut self) -> Option<FaultKind> {
+        self.stats.transactions = self.stats.transactions.wrapping_add(1);
+        if self.paused {
+            return None;
+        }
+        let forced = self.fail_next > 0;
+        self.fail_next = self.fail_next.saturating_sub(1);
+        let nth = self
+            .config
+            .fail_every
+            .is_some_and(|n| self.stats.transactions.is_multiple_of(n));
+        // always drawn, so the random sequence does not depend on the other fault sources
+        let random = self.config.fail_probability > 0.0
+            && self.next_fraction() < self.config.fail_probability;
+        if forced || nth || random {
+            self.stats.faults += 1;
+            Some(self.config.fault)
+        } else {
+            None
+        }
+    }
+
+    /// Called after each successful read
+    fn corrupt(&mut self, buf: &mut [u8]) {
+        let Some(flip) = self.config.corrupt.filter(|_| !self.paused) else {
+            return;
+        };
+        if flip.byte >= buf.len() {
+            return;
+        }
+        self.eligible_reads += 1;
+        if self.eligible_reads.is_multiple_of(flip.every.max(1)) {
+            buf[flip.byte] ^= 1 << (flip.bit & 7);
+            self.stats.corruptions += 1;
+        }
+    }
+}
+
+fn seed_state(seed: u64) -> u64 {
+    // xorshift must not start at 0
+    seed ^ 0x9e37_79b9_7f4a_7c15
+}
+
+/// Control over a [`FlakyI2c`] owned by someone else, usually the driver
+#[derive(Clone, Debug)]
+pub struct ChaosHandle {
+    state: Rc<RefCell<ChaosState>>,
+}
+
+impl ChaosHandle {
+    /// scenario in use
+    pub fn config(&self) -> ChaosConfig {
+        self.state.borrow().config
+    }
+
+    /// Replaces the scenario and reseeds the generator, counters are kept
+    pub fn set_config(&self, config: ChaosConfig) {
+        let mut state = self.state.borrow_mut();
+        state.config = config;
+        state.rng = seed_state(config.seed);
+        state.eligible_reads = 0;
+    }
+
+    /// fail the next `n` transactions regardless of the scenario, e.g. to simulate an unplug
+    pub fn fail_next(&self, n: u32) {
+        self.state.borrow_mut().fail_next = n;
+    }
+
+    /// While paused, transactions pass through unchanged but are still counted
+    pub fn pause(&self, paused: bool) {
+        self.state.borrow_mut().paused = paused;
+    }
+
+    /// counters so far
+    pub fn stats(&self) -> ChaosStats {
+        self.state.borrow().stats
+    }
+
+    /// fault injected into the latest transaction, None if it was passed through. A
+    /// classifier of [`recovery`](crate::recovery) can be built on it
+    pub fn last_fault(&self) -> Option<FaultKind> {
+        self.state.borrow().last_fault
+    }
+
+    /// zero the counters
+    pub fn reset_stats(&self) {
+        self.state.borrow_mut().stats = ChaosStats::default();
+    }
+}
+
+/// I2C wrapper injecting faults around `I`
+#[derive(Debug)]
+pub struct FlakyI2c<I> {
+    inner: I,
+    state: Rc<RefCell<ChaosState>>,
+}
+
+impl<I> FlakyI2c<I> {
+    /// Wraps `inner`, the handle controls the scenario after the wrapper is moved into the
+    /// driver
+    pub fn new(inner: I, config: ChaosConfig) -> (Self, ChaosHandle) {
+        let state = Rc::new(RefCell::new(ChaosState::new(config)));
+        let handle = ChaosHandle {
+            state: state.clone(),
+        };
+        (Self { inner, state }, handle)
+    }
+
+    /// another handle to this wrapper
+    pub fn handle(&self) -> ChaosHandle {
+        ChaosHandle {
+            state: self.state.clone(),
+        }
+    }
+
+    /// the wrapped bus
+    pub fn inner(&mut self) -> &mut I {
+        &mut self.inner
+    }
+
+    /// unwraps the bus
+    pub fn into_inner(self) -> I {
+        self.inner
+    }
+
+    fn begin<E>(&self) -> Result<(), FlakyError<E>> {
+        match self.state.borrow_mut().begin() {
+            Some(fault) => Err(FlakyError::Injected(fault)),
+            None => Ok(()),
+        }
+    }
+}
+
+impl<I: Write> Write for FlakyI2c<I> {
+    type Error = FlakyError<I::Error>;
+
+    fn write(&mut self, address: u8, bytes: &[u8]) -> Result<(), Self::Error> {
+        self.begin()?;
+        self.inner.write(address, bytes).map_err(FlakyError::Inner)
+    }
+}
+
+impl<I: Read> Read for FlakyI2c<I> {
+    type Error = FlakyError<I::Error>;
+
+    fn read(&mut self, address: u8, buffer: &mut [u8]) -> Result<(), Self::Error> {
+        self.begin()?;
+        self.inner
+            .read(address, buffer)
+            .map_err(FlakyError::Inner)?;
+        self.state.borrow_mut().corrupt(buffer);
+        Ok(())
+    }
+}
+
+impl<I: WriteRead> WriteRead for FlakyI2c<I> {
+    type Error = FlakyError<I::Error>;
+
+    fn write_read(
+        &mut self,
+        address: u8,
+        bytes: &[u8],
+        buffer: &mut [u8],
+    ) -> Result<(), Self::Error> {
+        self.begin()?;
+        self.inner
+            .write_read(address, bytes, buffer)
+            .map_err(FlakyError::Inner)?;
+        self.state.borrow_mut().corrupt(buffer);
+        Ok(())
+    }
+}
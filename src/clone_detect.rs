@@ -0,0 +1,208 @@
+//! Undocumented register space and clone detection.
+//!
+//! #### Deny-list
+//! Some clones misbehave when undocumented registers are read, a known case locks up the aux
+//! I2C master. The raw reads of the driver (`read_bytes`, `read_byte`, `read_word` and the
+//! typed bursts built on them) refuse any burst touching one of the
+//! [`DENIED_RANGES`](crate::device::DENIED_RANGES) with
+//! [`Mpu6050Error::DeniedRegisterRange`], before the bus is touched. A driver built with
+//! [`unsafe_allow_undocumented`](crate::Mpu6050Builder::unsafe_allow_undocumented) reads
+//! them anyway. The register map has no address in the list, so typed access to a single
+//! documented register never fails here.
+//!
+//! #### Heuristics
+//! [`Mpu6050::detect_clone_heuristics`] runs documented probes only, none of them reads
+//! denied space:
+//!
+//! | probe | bus | sign |
+//! |:---|:---|:---|
+//! | WHO_AM_I | one read | [`ForeignWhoAmI`](crate::device::CloneSign::ForeignWhoAmI) for an id of no InvenSense part, [`WhoAmIFollowsAd0`](crate::device::CloneSign::WhoAmIFollowsAd0) for 0x69 at 0x69: a genuine part answers 0x68 whatever AD0 is |
+//! | factory trim | two bursts, SELF_TEST_X to SELF_TEST_A and the accel offsets | [`BlankSelfTestTrim`](crate::device::CloneSign::BlankSelfTestTrim), [`BlankAccelTrim`](crate::device::CloneSign::BlankAccelTrim) for all 0x00 or all 0xff |
+//! | self-test | ACCEL_CONFIG saved, ±8 g with and without self-test, [`SELF_TEST_SAMPLES`] accel reads each, ACCEL_CONFIG restored | [`SelfTestOutOfRange`](crate::device::CloneSign::SelfTestOutOfRange) for a response outside [`ACCEL_SELF_TEST_MIN_G`] to [`ACCEL_SELF_TEST_MAX_G`] on any axis |
+//!
+//! The trim and self-test probes follow the MPU-6050 register map, an MPU-6500 or MPU-9250
+//! skips them. So does the self-test of a sleeping chip. The self-test response is the
+//! difference of two averages, the sensor has to be still while it runs.
+//!
+//! The signs are [weighted](crate::device::CloneSign::weight): a part scoring [`LIKELY_CLONE_SCORE`](crate::device::LIKELY_CLONE_SCORE) is
+//! [`LikelyClone`](crate::device::CloneAssessment::LikelyClone), a part without signs is
+//! [`LikelyGenuine`](crate::device::CloneAssessment::LikelyGenuine) if every probe ran. Anything else, a
+//! bus error included, is [`Inconclusive`](crate::device::CloneAssessment::Inconclusive). The assessment
+//! is kept in [`ChipCapabilities::clone_assessment`](crate::device::ChipCapabilities::clone_assessment), across later probes of the same
+//! variant, and a likely clone is not assumed to load DMP firmware, see
+//! [`ChipCapabilities::with_clone_assessment`](crate::device::ChipCapabilities::with_clone_assessment).
+//! ```
+//! use mpu6050::device::{CloneAssessment, CloneEvidence, CloneSign};
+//!
+//! let blank = CloneEvidence::EMPTY.with(CloneSign::BlankAccelTrim);
+//! // written offsets read as blank too, one weak sign is not enough
+//! assert_eq!(CloneAssessment::from_evidence(blank, true), CloneAssessment::Inconclusive);
+//! let evidence = blank.with(CloneSign::BlankSelfTestTrim);
+//! assert_eq!(evidence.score(), 2);
+//! assert!(CloneAssessment::from_evidence(evidence, true).is_likely_clone());
+//! assert_eq!(
+//!     CloneAssessment::from_evidence(CloneEvidence::EMPTY, false),
+//!     CloneAssessment::Inconclusive
+//! );
+//! ```
+
+#[cfg(feature = "driver")]
+use embedded_hal::blocking::{
+    delay::DelayMs,
+    i2c::{Write, WriteRead},
+};
+
+#[cfg(feature = "driver")]
+use crate::device::{
+    AccelRange, ChipVariant, CloneAssessment, CloneEvidence, CloneSign, SettleTrigger,
+    ACCEL_CONFIG, ACCEL_HPF, DEFAULT_SLAVE_ADDR,
+};
+#[cfg(feature = "driver")]
+use crate::register::{AccelConfigValue, Register};
+use crate::Mpu6050;
+#[cfg(feature = "driver")]
+use crate::Mpu6050Error;
+
+/// Lowest accel self-test response of a genuine part in g: the lowest factory trim, 0.34 g,
+/// less the 14 % the datasheet allows
+pub const ACCEL_SELF_TEST_MIN_G: f32 = 0.29;
+
+/// Highest accel self-test response of a genuine part in g: the highest factory trim,
+/// 0.92 g, plus the 14 % the datasheet allows
+pub const ACCEL_SELF_TEST_MAX_G: f32 = 1.05;
+
+/// Accel reads averaged with and without self-test
+pub const SELF_TEST_SAMPLES: u8 = 8;
+
+/// Wait after switching the self-test, in ms
+pub const SELF_TEST_SETTLE_MS: u8 = 50;
+
+/// Wait between the accel reads of the self-test, in ms
+pub const SELF_TEST_INTERVAL_MS: u8 = 2;
+
+/// LSB per g at ±8 g, the range of the self-test
+#[cfg(feature = "driver")]
+const SELF_TEST_LSB_PER_G: f32 = 4096.;
+
+impl<I, D> Mpu6050<I, D> {
+    /// true if built with
+    /// [`unsafe_allow_undocumented`](crate::Mpu6050Builder::unsafe_allow_undocumented)
+    pub fn undocumented_allowed(&self) -> bool {
+        self.allow_undocumented
+    }
+}
+
+#[cfg(feature = "driver")]
+impl<I, D, E> Mpu6050<I, D>
+where
+    I: Write<Error = E> + WriteRead<Error = E>,
+{
+    pub(crate) fn detect_clone_heuristics_using(
+        &mut self,
+        delay: &mut impl DelayMs<u8>,
+    ) -> CloneAssessment {
+        let mut evidence = CloneEvidence::EMPTY;
+        let mut complete = true;
+        match self.probe_who_am_i() {
+            Ok((variant, signs)) => {
+                evidence = signs;
+                if matches!(variant, ChipVariant::Mpu6500 | ChipVariant::Mpu9250) {
+                    complete = false;
+                } else {
+                    match self.probe_factory_trim() {
+                        Ok(signs) => evidence = evidence.union(signs),
+                        Err(_) => complete = false,
+                    }
+                    if self.power.asleep {
+                        complete = false;
+                    } else {
+                        match self.probe_self_test(delay) {
+                            Ok(signs) => evidence = evidence.union(signs),
+                            Err(_) => complete = false,
+                        }
+                    }
+                }
+            }
+            Err(_) => complete = false,
+        }
+        let assessment = CloneAssessment::from_evidence(evidence, complete);
+        self.capabilities = self.capabilities.with_clone_assessment(assessment);
+        assessment
+    }
+
+    /// variant and signs of WHO_AM_I at the driver's address
+    fn probe_who_am_i(&mut self) -> Result<(ChipVariant, CloneEvidence), Mpu6050Error<E>> {
+        let who_am_i = self.read_register(Register::WHO_AM_I)?;
+        let variant = ChipVariant::from_who_am_i(who_am_i);
+        let mut signs = CloneEvidence::EMPTY;
+        if matches!(variant, ChipVariant::Unknown(_)) {
+            signs = signs.with(CloneSign::ForeignWhoAmI);
+        }
+        if self.slave_addr == DEFAULT_SLAVE_ADDR + 1 && who_am_i == self.slave_addr {
+            signs = signs.with(CloneSign::WhoAmIFollowsAd0);
+        }
+        Ok((variant, signs))
+    }
+
+    /// blank self-test and accel offset trims
+    fn probe_factory_trim(&mut self) -> Result<CloneEvidence, Mpu6050Error<E>> {
+        let blank =
+            |bytes: &[u8]| bytes.iter().all(|b| *b == 0) || bytes.iter().all(|b| *b == 0xff);
+        let mut self_test = [0; 4];
+        self.read_registers(Register::SELF_TEST_X, &mut self_test)?;
+        let mut accel = [0; 6];
+        self.read_registers(Register::XA_OFFS_H, &mut accel)?;
+        let mut signs = CloneEvidence::EMPTY;
+        if blank(&self_test) {
+            signs = signs.with(CloneSign::BlankSelfTestTrim);
+        }
+        if blank(&accel) {
+            signs = signs.with(CloneSign::BlankAccelTrim);
+        }
+        Ok(signs)
+    }
+
+    /// accel self-test response out of range, ACCEL_CONFIG restored even after an error
+    fn probe_self_test(
+        &mut self,
+        delay: &mut impl DelayMs<u8>,
+    ) -> Result<CloneEvidence, Mpu6050Error<E>> {
+        let saved = self.read_register(Register::ACCEL_CONFIG)?;
+        let mut average = |mpu: &mut Self, enabled: bool| {
+            mpu.write_typed::<ACCEL_CONFIG>(AccelConfigValue {
+                self_test: [enabled; 3],
+                range: AccelRange::G8,
+                hpf: ACCEL_HPF::_RESET,
+            })?;
+            delay.delay_ms(SELF_TEST_SETTLE_MS);
+            let mut sum = [0i32; 3];
+            for i in 0..SELF_TEST_SAMPLES {
+                if i > 0 {
+                    delay.delay_ms(SELF_TEST_INTERVAL_MS);
+                }
+                let mut buf = [0; 6];
+                mpu.read_registers(Register::ACCEL_XOUT_H, &mut buf)?;
+                for (axis, sum) in sum.iter_mut().enumerate() {
+                    *sum += i16::from_be_bytes([buf[2 * axis], buf[2 * axis + 1]]) as i32;
+                }
+            }
+            Ok(sum.map(|sum| sum as f32 / SELF_TEST_SAMPLES as f32 / SELF_TEST_LSB_PER_G))
+        };
+        let off = average(self, false);
+        let on = off.and_then(|off| Ok((off, average(self, true)?)));
+        // restore even if a read failed
+        let restored = self.write_register(Register::ACCEL_CONFIG, saved);
+        self.settle.trigger(SettleTrigger::SelfTest);
+        let (off, on) = on?;
+        restored?;
+        let in_range = (0..3).all(|axis| {
+            let response = (on[axis] - off[axis]).abs();
+            (ACCEL_SELF_TEST_MIN_G..=ACCEL_SELF_TEST_MAX_G).contains(&response)
+        });
+        if in_range {
+            Ok(CloneEvidence::EMPTY)
+        } else {
+            Ok(CloneEvidence::EMPTY.with(CloneSign::SelfTestOutOfRange))
+        }
+    }
+}
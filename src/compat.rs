@@ -0,0 +1,400 @@
+//! API of the upstream `mpu6050` crate, as thin wrappers over this crate's driver.
+//!
+//! Projects written against upstream switch their imports and keep their call sites:
+//! [`Mpu6050`] here has upstream's constructors, method names and signatures, and
+//! [`Mpu6050Error`] upstream's error variants. The readings are computed by the native
+//! driver, they are the numbers upstream returns for the same register contents: g, rad/s,
+//! `raw / 340 + 36.53` °C and roll/pitch from the same `atan2` expressions (see
+//! [`tilt::roll_pitch`](crate::tilt::roll_pitch)). The conversion is tested against a
+//! reimplementation of upstream's formulas.
+//!
+//! Migrating is incremental: [`Mpu6050::inner_mut`] reaches the native driver for single
+//! new features, [`Mpu6050::into_inner`] ends the migration.
+//!
+//! #### Migration guide
+//!
+//! | upstream | compat | native |
+//! |:---|:---|:---|
+//! | `use mpu6050::*;` | `use mpu6050::compat::*;` | `use mpu6050::*;` |
+//! | `Mpu6050::new(i2c)` | same | `Mpu6050Builder::new().i2c(i2c).build()?` |
+//! | `Mpu6050::new_with_addr(i2c, addr)` | same | `.slave_addr(addr)` on the builder |
+//! | `Mpu6050::new_with_sens(i2c, arange, grange)` | same | `.acc_sensitivity(arange).gyro_sensitivity(grange)` |
+//! | `Mpu6050::new_with_addr_and_sens(..)` | same | all three builder calls |
+//! | `init(&mut delay)` | same | same |
+//! | `get_acc()`, `Vector3<f32>` in g | [`Vec3A`] | [`Vec3A`], or `get_acc_as::<Vector3<f32>>()` |
+//! | `get_gyro()`, `Vector3<f32>` in rad/s | [`Vec3A`] | [`Vec3A`], or `get_gyro_as::<Vector3<f32>>()` |
+//! | `get_acc_angles()`, `Vector2<f32>` roll, pitch | [`Vec2`] | [`Quat`](crate::Quat), roll and pitch from `get_acc_angles_checked()` |
+//! | `get_temp()` | same | same |
+//! | range, HPF, sleep, temperature, self-test and clock accessors | same | same |
+//! | `setup_motion_detection()`, `get_motion_detected()` | same | same |
+//! | `reset_device(&mut delay)` | same | same |
+//! | `read_bit`, `read_bits`, `read_byte`, `read_bytes`, `write_byte`, `write_bit`, `write_bits` | same | [`register`](crate::register) accessors |
+//! | `Mpu6050Error::I2c`, `Mpu6050Error::InvalidChipId` | same, plus [`Native`](Mpu6050Error::Native) | [`crate::Mpu6050Error`] |
+//!
+//! Before, against upstream:
+//! ```ignore
+//! use mpu6050::*;
+//!
+//! fn bring_up<I, E>(i2c: I, delay: &mut impl DelayMs<u8>) -> Result<(), Mpu6050Error<E>>
+//! where
+//!     I: Write<Error = E> + WriteRead<Error = E>,
+//! {
+//!     let mut mpu = Mpu6050::new_with_sens(i2c, device::AccelRange::G4, device::GyroRange::D500);
+//!     mpu.init(delay)?;
+//!     let angles = mpu.get_acc_angles()?;
+//!     let (roll, pitch) = (angles.x, angles.y);
+//!     let acc: nalgebra::Vector3<f32> = mpu.get_acc()?;
+//!     Ok(())
+//! }
+//! ```
+//! After, with the shim:
+//! ```
+//! # use embedded_hal::blocking::delay::DelayMs;
+//! # use embedded_hal::blocking::i2c::{Write, WriteRead};
+//! use mpu6050::compat::*;
+//!
+//! fn bring_up<I, E>(i2c: I, delay: &mut impl DelayMs<u8>) -> Result<(), Mpu6050Error<E>>
+//! where
+//!     I: Write<Error = E> + WriteRead<Error = E>,
+//! {
+//!     let mut mpu = Mpu6050::new_with_sens(i2c, device::AccelRange::G4, device::GyroRange::D500);
+//!     mpu.init(delay)?;
+//!     let angles = mpu.get_acc_angles()?;
+//!     let (roll, pitch) = (angles.x, angles.y);
+//!     // nalgebra's `Vector3<f32>` implements `From<[f32; 3]>` like the array
+//!     let acc: [f32; 3] = mpu.get_acc_as()?;
+//!     # let _ = (roll, pitch, acc);
+//!     Ok(())
+//! }
+//! ```
+//!
+//! #### Differences
+//! * nalgebra is no dependency of this crate: vectors are glam's [`Vec3A`] and [`Vec2`],
+//!   with the same `x`, `y` and `z` fields. [`get_acc_as`](Mpu6050::get_acc_as),
+//!   [`get_gyro_as`](Mpu6050::get_gyro_as) and
+//!   [`get_acc_angles_as`](Mpu6050::get_acc_angles_as) return any type built from an
+//!   array, nalgebra's vectors among them.
+//! * [`Mpu6050Error`] has a third variant, [`Native`](Mpu6050Error::Native), for errors
+//!   upstream has no counterpart for. The shim's own methods never return it on a driver
+//!   only used through the shim, it is reachable after configuring the native driver through
+//!   [`inner_mut`](Mpu6050::inner_mut), e.g. enabling the FIFO. Exhaustive matches need an
+//!   arm for it.
+//! * As upstream, [`init`](Mpu6050::init) sets ±2 g and ±250 °/s, overriding the ranges of
+//!   `new_with_sens`: set ranges after `init`.
+//! * The native driver re-reads samples taken while the signal path settles. The shim turns
+//!   that off, a read is one transaction as upstream.
+
+use embedded_hal::blocking::{
+    delay::DelayMs,
+    i2c::{Write, WriteRead},
+};
+pub use glam::{Vec2, Vec3A};
+
+use crate::bits;
+pub use crate::device;
+use crate::device::{AccelRange, GyroRange, ACCEL_HPF, CLKSEL};
+use crate::interrupt::MotionDetectionConfig;
+use crate::settling::SettlingPolicy;
+use crate::{Mpu6050Builder, Mpu6050Error as NativeError};
+
+/// Error of the upstream crate's shape
+#[derive(Debug)]
+pub enum Mpu6050Error<E> {
+    /// I2C bus error
+    I2c(E),
+
+    /// Invalid chip ID was read
+    InvalidChipId(u8),
+
+    /// Error of the native driver without upstream counterpart, see the module docs
+    Native(NativeError<E>),
+}
+
+impl<E> From<NativeError<E>> for Mpu6050Error<E> {
+    fn from(error: NativeError<E>) -> Self {
+        match error {
+            NativeError::I2c(error) => Mpu6050Error::I2c(error),
+            NativeError::InvalidChipId(id) => Mpu6050Error::InvalidChipId(id),
+            error => Mpu6050Error::Native(error),
+        }
+    }
+}
+
+/// Driver with the upstream crate's API, wrapping the native [`Mpu6050`](crate::Mpu6050)
+pub struct Mpu6050<I> {
+    inner: crate::Mpu6050<I>,
+}
+
+impl<I, E> Mpu6050<I>
+where
+    I: Write<Error = E> + WriteRead<Error = E>,
+{
+    /// Side effect free constructor with default sensitivies, no calibration
+    pub fn new(i2c: I) -> Self {
+        Self::build(Mpu6050Builder::new().i2c(i2c))
+    }
+
+    /// custom sensitivity
+    pub fn new_with_sens(i2c: I, arange: AccelRange, grange: GyroRange) -> Self {
+        Self::build(
+            Mpu6050Builder::new()
+                .i2c(i2c)
+                .acc_sensitivity(arange)
+                .gyro_sensitivity(grange),
+        )
+    }
+
+    /// Same as `new`, but the chip address can be specified (e.g. 0x69, if the A0 pin is pulled up)
+    pub fn new_with_addr(i2c: I, slave_addr: u8) -> Self {
+        Self::build(Mpu6050Builder::new().i2c(i2c).slave_addr(slave_addr))
+    }
+
+    /// Combination of `new_with_sens` and `new_with_addr`
+    pub fn new_with_addr_and_sens(
+        i2c: I,
+        slave_addr: u8,
+        arange: AccelRange,
+        grange: GyroRange,
+    ) -> Self {
+        Self::build(
+            Mpu6050Builder::new()
+                .i2c(i2c)
+                .slave_addr(slave_addr)
+                .acc_sensitivity(arange)
+                .gyro_sensitivity(grange),
+        )
+    }
+
+    fn build(builder: Mpu6050Builder<I>) -> Self {
+        let mut inner = match builder.build() {
+            Ok(inner) => inner,
+            // every constructor provides the bus
+            Err(_) => unreachable!("i2c provided"),
+        };
+        inner.set_settling_policy(SettlingPolicy::Ignore);
+        Self { inner }
+    }
+
+    /// Init wakes MPU6050 and verifies register addr, e.g. in i2c
+    pub fn init<D: DelayMs<u8>>(&mut self, delay: &mut D) -> Result<(), Mpu6050Error<E>> {
+        Ok(self.inner.init(delay)?)
+    }
+
+    /// set clock source
+    pub fn set_clock_source(&mut self, source: CLKSEL) -> Result<(), Mpu6050Error<E>> {
+        Ok(self.inner.set_clock_source(source)?)
+    }
+
+    /// get current clock source
+    pub fn get_clock_source(&mut self) -> Result<CLKSEL, Mpu6050Error<E>> {
+        Ok(self.inner.get_clock_source()?)
+    }
+
+    /// setup motion detection
+    pub fn setup_motion_detection(&mut self) -> Result<(), Mpu6050Error<E>> {
+        Ok(self
+            .inner
+            .setup_motion_detection(MotionDetectionConfig::DEFAULT)?)
+    }
+
+    /// get whether or not motion has been detected (INT_STATUS, MOT_INT)
+    pub fn get_motion_detected(&mut self) -> Result<bool, Mpu6050Error<E>> {
+        Ok(self.inner.get_motion_detected()?)
+    }
+
+    /// set accel high pass filter mode
+    pub fn set_accel_hpf(&mut self, mode: ACCEL_HPF) -> Result<(), Mpu6050Error<E>> {
+        Ok(self.inner.set_accel_hpf(mode)?)
+    }
+
+    /// get accel high pass filter mode
+    pub fn get_accel_hpf(&mut self) -> Result<ACCEL_HPF, Mpu6050Error<E>> {
+        Ok(self.inner.get_accel_hpf()?)
+    }
+
+    /// Set gyro range, and update sensitivity accordingly
+    pub fn set_gyro_range(&mut self, range: GyroRange) -> Result<(), Mpu6050Error<E>> {
+        Ok(self.inner.set_gyro_range(range)?)
+    }
+
+    /// get current gyro range
+    pub fn get_gyro_range(&mut self) -> Result<GyroRange, Mpu6050Error<E>> {
+        Ok(self.inner.get_gyro_range()?)
+    }
+
+    /// set accel range, and update sensitivy accordingly
+    pub fn set_accel_range(&mut self, range: AccelRange) -> Result<(), Mpu6050Error<E>> {
+        Ok(self.inner.set_accel_range(range)?)
+    }
+
+    /// get current accel_range
+    pub fn get_accel_range(&mut self) -> Result<AccelRange, Mpu6050Error<E>> {
+        Ok(self.inner.get_accel_range()?)
+    }
+
+    /// reset device
+    pub fn reset_device<D: DelayMs<u8>>(&mut self, delay: &mut D) -> Result<(), Mpu6050Error<E>> {
+        Ok(self.inner.reset_device(delay)?)
+    }
+
+    /// enable, disable sleep of sensor
+    pub fn set_sleep_enabled(&mut self, enable: bool) -> Result<(), Mpu6050Error<E>> {
+        Ok(self.inner.set_sleep_enabled(enable)?)
+    }
+
+    /// get sleep status
+    pub fn get_sleep_enabled(&mut self) -> Result<bool, Mpu6050Error<E>> {
+        Ok(self.inner.get_sleep_enabled()?)
+    }
+
+    /// enable, disable temperature measurement of sensor
+    pub fn set_temp_enabled(&mut self, enable: bool) -> Result<(), Mpu6050Error<E>> {
+        Ok(self.inner.set_temp_enabled(enable)?)
+    }
+
+    /// get temperature sensor status
+    pub fn get_temp_enabled(&mut self) -> Result<bool, Mpu6050Error<E>> {
+        Ok(self.inner.get_temp_enabled()?)
+    }
+
+    /// set accel x self test
+    pub fn set_accel_x_self_test(&mut self, enable: bool) -> Result<(), Mpu6050Error<E>> {
+        Ok(self.inner.set_accel_x_self_test(enable)?)
+    }
+
+    /// get accel x self test
+    pub fn get_accel_x_self_test(&mut self) -> Result<bool, Mpu6050Error<E>> {
+        Ok(self.inner.get_accel_x_self_test()?)
+    }
+
+    /// set accel y self test
+    pub fn set_accel_y_self_test(&mut self, enable: bool) -> Result<(), Mpu6050Error<E>> {
+        Ok(self.inner.set_accel_y_self_test(enable)?)
+    }
+
+    /// get accel y self test
+    pub fn get_accel_y_self_test(&mut self) -> Result<bool, Mpu6050Error<E>> {
+        Ok(self.inner.get_accel_y_self_test()?)
+    }
+
+    /// set accel z self test
+    pub fn set_accel_z_self_test(&mut self, enable: bool) -> Result<(), Mpu6050Error<E>> {
+        Ok(self.inner.set_accel_z_self_test(enable)?)
+    }
+
+    /// get accel z self test
+    pub fn get_accel_z_self_test(&mut self) -> Result<bool, Mpu6050Error<E>> {
+        Ok(self.inner.get_accel_z_self_test()?)
+    }
+
+    /// Roll and pitch estimation from raw accelerometer readings, in rad: roll in `x`, pitch
+    /// in `y`
+    pub fn get_acc_angles(&mut self) -> Result<Vec2, Mpu6050Error<E>> {
+        let tilt = self.inner.get_acc_angles_checked()?;
+        Ok(Vec2::new(tilt.roll, tilt.pitch))
+    }
+
+    /// [`get_acc_angles`](Self::get_acc_angles) as `[roll, pitch]` into any type, e.g.
+    /// nalgebra's `Vector2<f32>`
+    pub fn get_acc_angles_as<V: From<[f32; 2]>>(&mut self) -> Result<V, Mpu6050Error<E>> {
+        Ok(V::from(self.get_acc_angles()?.to_array()))
+    }
+
+    /// Accelerometer readings in g
+    pub fn get_acc(&mut self) -> Result<Vec3A, Mpu6050Error<E>> {
+        Ok(self.inner.get_acc()?)
+    }
+
+    /// [`get_acc`](Self::get_acc) into any type, e.g. nalgebra's `Vector3<f32>`
+    pub fn get_acc_as<V: From<[f32; 3]>>(&mut self) -> Result<V, Mpu6050Error<E>> {
+        Ok(self.inner.get_acc_as()?)
+    }
+
+    /// Gyro readings in rad/s
+    pub fn get_gyro(&mut self) -> Result<Vec3A, Mpu6050Error<E>> {
+        Ok(self.inner.get_gyro()?)
+    }
+
+    /// [`get_gyro`](Self::get_gyro) into any type, e.g. nalgebra's `Vector3<f32>`
+    pub fn get_gyro_as<V: From<[f32; 3]>>(&mut self) -> Result<V, Mpu6050Error<E>> {
+        Ok(self.inner.get_gyro_as()?)
+    }
+
+    /// Sensor Temp in degrees celcius
+    pub fn get_temp(&mut self) -> Result<f32, Mpu6050Error<E>> {
+        Ok(self.inner.get_temp()?)
+    }
+
+    /// Writes byte to register
+    pub fn write_byte(&mut self, reg: u8, byte: u8) -> Result<(), Mpu6050Error<E>> {
+        Ok(self.inner.write_byte(reg, byte)?)
+    }
+
+    /// Enables bit n at register address reg
+    pub fn write_bit(&mut self, reg: u8, bit_n: u8, enable: bool) -> Result<(), Mpu6050Error<E>> {
+        let mut byte = self.inner.read_byte(reg)?;
+        bits::set_bit(&mut byte, bit_n, enable);
+        Ok(self.inner.write_byte(reg, byte)?)
+    }
+
+    /// Write bits data at reg from start_bit to start_bit+length
+    pub fn write_bits(
+        &mut self,
+        reg: u8,
+        start_bit: u8,
+        length: u8,
+        data: u8,
+    ) -> Result<(), Mpu6050Error<E>> {
+        let mut byte = self.inner.read_byte(reg)?;
+        bits::set_bits(&mut byte, start_bit, length, data);
+        Ok(self.inner.write_byte(reg, byte)?)
+    }
+
+    /// Read bit n from register
+    pub fn read_bit(&mut self, reg: u8, bit_n: u8) -> Result<u8, Mpu6050Error<E>> {
+        self.read_bits(reg, bit_n, 1)
+    }
+
+    /// Read bits at register reg, starting with bit start_bit, until start_bit+length
+    pub fn read_bits(&mut self, reg: u8, start_bit: u8, length: u8) -> Result<u8, Mpu6050Error<E>> {
+        Ok(bits::get_bits(
+            self.inner.read_byte(reg)?,
+            start_bit,
+            length,
+        ))
+    }
+
+    /// Reads byte from register
+    pub fn read_byte(&mut self, reg: u8) -> Result<u8, Mpu6050Error<E>> {
+        Ok(self.inner.read_byte(reg)?)
+    }
+
+    /// Reads series of bytes into buf from specified reg
+    pub fn read_bytes(&mut self, reg: u8, buf: &mut [u8]) -> Result<(), Mpu6050Error<E>> {
+        Ok(self.inner.read_bytes(reg, buf)?)
+    }
+}
+
+impl<I> Mpu6050<I> {
+    /// the native driver, for features upstream lacks
+    pub fn inner(&self) -> &crate::Mpu6050<I> {
+        &self.inner
+    }
+
+    /// the native driver, see the module docs for what changes the shim's errors
+    pub fn inner_mut(&mut self) -> &mut crate::Mpu6050<I> {
+        &mut self.inner
+    }
+
+    /// the native driver, ending the migration
+    pub fn into_inner(self) -> crate::Mpu6050<I> {
+        self.inner
+    }
+}
+
+impl<I> From<crate::Mpu6050<I>> for Mpu6050<I> {
+    /// wraps a native driver as is, its settling policy included
+    fn from(inner: crate::Mpu6050<I>) -> Self {
+        Self { inner }
+    }
+}
@@ -0,0 +1,289 @@
+//! Configuration register snapshots and bit-level diffs between them.
+//!
+//! A diff lists, per differing register in address order, the changed fields decoded with
+//! [`REGISTERS`](crate::device::REGISTERS), e.g. `PWR_MGMT_1 (0x6b): SLEEP 1->0, CLKSEL 0->1`.
+//! Registers without a description fall back to a hex byte diff, identical fields are omitted.
+
+use core::fmt;
+
+use crate::bits;
+use crate::device::{
+    register_info, FieldInfo, RegisterInfo, INT_STATUS, MOT_DETECT_STATUS, REGISTERS, RESET_VALUES,
+};
+use crate::register::{Register, RegisterSpec, RegisterValue};
+use crate::{Mpu6050, Mpu6050Error};
+#[cfg(feature = "driver")]
+use embedded_hal::blocking::i2c::{Write, WriteRead};
+
+/// Number of registers in a [`Mpu6050Config`]
+pub const CONFIG_REGISTER_COUNT: usize = REGISTERS.len();
+
+/// Values of all configuration registers in [`REGISTERS`], in the same order
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct Mpu6050Config {
+    values: [u8; CONFIG_REGISTER_COUNT],
+}
+
+impl Mpu6050Config {
+    /// Snapshot from register values, in the order of [`REGISTERS`]
+    pub fn from_values(values: [u8; CONFIG_REGISTER_COUNT]) -> Self {
+        Self { values }
+    }
+
+    /// Snapshot from a register image, registers missing from the image read as 0
+    pub fn from_image(image: &[(u8, u8)]) -> Self {
+        let mut values = [0; CONFIG_REGISTER_COUNT];
+        for (value, info) in values.iter_mut().zip(REGISTERS) {
+            *value = lookup(image, info.addr).unwrap_or(0);
+        }
+        Self { values }
+    }
+
+    /// value of register `addr`, None if it is not part of the snapshot
+    pub fn get(&self, addr: u8) -> Option<u8> {
+        REGISTERS
+            .iter()
+            .position(|info| info.addr == addr)
+            .map(|i| self.values[i])
+    }
+
+    /// content of `R`, None if it is not part of the snapshot
+    pub fn fields<R: RegisterSpec>(&self) -> Option<RegisterValue<R>> {
+        self.get(R::REGISTER.addr()).map(RegisterValue::from_byte)
+    }
+
+    /// (address, value) pairs in address order
+    pub fn registers(&self) -> impl Iterator<Item = (u8, u8)> + '_ {
+        REGISTERS
+            .iter()
+            .zip(self.values.iter())
+            .map(|(info, value)| (info.addr, *value))
+    }
+
+    /// Field-level differences from `self` to `other`
+    pub fn diff(&self, other: &Self) -> ConfigDiff {
+        let mut diff = ConfigDiff {
+            diffs: [None; CONFIG_REGISTER_COUNT],
+        };
+        let changed = self
+            .registers()
+            .zip(other.registers())
+            .filter(|(a, b)| a.1 != b.1)
+            .map(|((addr, a), (_, b))| RegisterDiff::new(addr, Some(a), Some(b)));
+        for (slot, d) in diff.diffs.iter_mut().zip(changed) {
+            *slot = Some(d);
+        }
+        diff
+    }
+}
+
+/// Differences between two [`Mpu6050Config`]s, ordered by register address
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct ConfigDiff {
+    diffs: [Option<RegisterDiff>; CONFIG_REGISTER_COUNT],
+}
+
+impl ConfigDiff {
+    /// differing registers in address order
+    pub fn iter(&self) -> impl Iterator<Item = &RegisterDiff> {
+        self.diffs.iter().flatten()
+    }
+
+    /// true if both snapshots are identical
+    pub fn is_empty(&self) -> bool {
+        self.diffs[0].is_none()
+    }
+}
+
+impl fmt::Display for ConfigDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_empty() {
+            return f.write_str("no differences");
+        }
+        for (i, d) in self.iter().enumerate() {
+            if i > 0 {
+                f.write_str("\n")?;
+            }
+            write!(f, "{}", d)?;
+        }
+        Ok(())
+    }
+}
+
+/// Difference of a single register
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct RegisterDiff {
+    /// register address
+    pub addr: u8,
+    /// value in the first image, None if missing there
+    pub old: Option<u8>,
+    /// value in the second image, None if missing there
+    pub new: Option<u8>,
+}
+
+impl RegisterDiff {
+    fn new(addr: u8, old: Option<u8>, new: Option<u8>) -> Self {
+        Self { addr, old, new }
+    }
+
+    /// register description, None for registers unknown to the driver
+    pub fn info(&self) -> Option<&'static RegisterInfo> {
+        register_info(self.addr)
+    }
+
+    /// changed fields, empty for unknown registers or if the register is missing in one image
+    pub fn fields(&self) -> impl Iterator<Item = FieldChange> + '_ {
+        let values = self.old.zip(self.new);
+        self.info()
+            .filter(|_| values.is_some())
+            .map(|info| info.fields)
+            .unwrap_or(&[])
+            .iter()
+            .filter_map(move |field| {
+                let (old, new) = values?;
+                let change = FieldChange::new(field, old, new);
+                (change.old != change.new).then_some(change)
+            })
+    }
+}
+
+impl fmt::Display for RegisterDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (self.info(), self.old, self.new) {
+            (Some(info), Some(_), Some(_)) => {
+                write!(f, "{} (0x{:02x}):", info.name, self.addr)?;
+                for (i, field) in self.fields().enumerate() {
+                    let sep = if i == 0 { " " } else { ", " };
+                    write!(f, "{}{} {}->{}", sep, field.name, field.old, field.new)?;
+                }
+                Ok(())
+            }
+            (info, old, new) => {
+                match info {
+                    Some(info) => write!(f, "{} (0x{:02x}): ", info.name, self.addr)?,
+                    None => write!(f, "0x{:02x}: ", self.addr)?,
+                }
+                write_byte(f, old)?;
+                f.write_str("->")?;
+                write_byte(f, new)
+            }
+        }
+    }
+}
+
+fn write_byte(f: &mut fmt::Formatter<'_>, byte: Option<u8>) -> fmt::Result {
+    match byte {
+        Some(byte) => write!(f, "0x{:02x}", byte),
+        None => f.write_str("--"),
+    }
+}
+
+/// Changed field of a register
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct FieldChange {
+    /// field name
+    pub name: &'static str,
+    /// field value in the first image
+    pub old: u8,
+    /// field value in the second image
+    pub new: u8,
+}
+
+impl FieldChange {
+    fn new(field: &FieldInfo, old: u8, new: u8) -> Self {
+        let get = |byte| bits::get_bits(byte, field.block.bit, field.block.length);
+        Self {
+            name: field.name,
+            old: get(old),
+            new: get(new),
+        }
+    }
+}
+
+/// Registers whose read has side effects, never read by [`Mpu6050::assert_device_at_defaults`]
+const CLEAR_ON_READ: [u8; 2] = [INT_STATUS::ADDR, MOT_DETECT_STATUS::ADDR];
+
+/// Register deviating from its documented reset value
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct DefaultMismatch {
+    /// register address
+    pub addr: u8,
+    /// documented reset value
+    pub expected: u8,
+    /// value read from the device
+    pub actual: u8,
+}
+
+/// Result of [`Mpu6050::assert_device_at_defaults`]
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct DefaultsReport {
+    mismatches: [Option<DefaultMismatch>; RESET_VALUES.len()],
+}
+
+impl DefaultsReport {
+    /// registers not at their reset value, in address order
+    pub fn mismatches(&self) -> impl Iterator<Item = &DefaultMismatch> {
+        self.mismatches.iter().flatten()
+    }
+
+    /// true if all compared registers are at their reset value
+    pub fn at_defaults(&self) -> bool {
+        self.mismatches[0].is_none()
+    }
+}
+
+fn lookup(image: &[(u8, u8)], addr: u8) -> Option<u8> {
+    image.iter().find(|(a, _)| *a == addr).map(|(_, v)| *v)
+}
+
+/// Differences between two raw register images, in address order. Registers present in only
+/// one image are reported with `None` on the other side. Duplicate addresses use the first entry
+pub fn diff_register_images<'a>(
+    a: &'a [(u8, u8)],
+    b: &'a [(u8, u8)],
+) -> impl Iterator<Item = RegisterDiff> + 'a {
+    (0..=u8::MAX).filter_map(move |addr| {
+        let (old, new) = (lookup(a, addr), lookup(b, addr));
+        (old != new).then(|| RegisterDiff::new(addr, old, new))
+    })
+}
+
+#[cfg(feature = "driver")]
+impl<I, D, E> Mpu6050<I, D>
+where
+    I: Write<Error = E> + WriteRead<Error = E>,
+{
+    /// Reads all configuration registers in [`REGISTERS`]
+    pub fn read_config(&mut self) -> Result<Mpu6050Config, Mpu6050Error<E>> {
+        let mut values = [0; CONFIG_REGISTER_COUNT];
+        for (value, info) in values.iter_mut().zip(REGISTERS) {
+            *value = self.read_register(Register::known(info.addr))?;
+        }
+        Ok(Mpu6050Config { values })
+    }
+
+    /// Compares the device registers with [`RESET_VALUES`]. Clear-on-read status registers
+    /// are skipped
+    pub fn assert_device_at_defaults(&mut self) -> Result<DefaultsReport, Mpu6050Error<E>> {
+        let mut report = DefaultsReport {
+            mismatches: [None; RESET_VALUES.len()],
+        };
+        let mut slots = report.mismatches.iter_mut();
+        for (addr, expected) in RESET_VALUES {
+            if CLEAR_ON_READ.contains(addr) {
+                continue;
+            }
+            let actual = self.read_register(Register::known(*addr))?;
+            if actual != *expected {
+                if let Some(slot) = slots.next() {
+                    *slot = Some(DefaultMismatch {
+                        addr: *addr,
+                        expected: *expected,
+                        actual,
+                    });
+                }
+            }
+        }
+        Ok(report)
+    }
+}
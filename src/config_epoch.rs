@@ -0,0 +1,201 @@
+//! Configuration epoch of the driver, for values derived from its configuration.
+//!
+//! Scale factors, the FIFO frame layout and the resolution of the active ranges are derived
+//! from the configuration the driver last wrote. The driver keeps one counter,
+//! [`Mpu6050::config_epoch`], and advances it on every operation changing state that scales
+//! or lays out the data. A derived value taken at one epoch is current as long as the
+//! counter has not moved:
+//! * [`FifoSchema`](crate::fifo::FifoSchema) carries the epoch it was taken at, draining or
+//!   parsing with a schema of an earlier epoch fails before touching the bus
+//! * any other value, [`Pipeline`](crate::scale::Pipeline),
+//!   [`ResolutionInfo`](crate::resolution::ResolutionInfo) or one of the caller's, is
+//!   wrapped with [`Mpu6050::tracked`]: [`Tracked::is_current`] revalidates it for a
+//!   comparison, [`Tracked::get`] refuses a stale one
+//!
+//! A stale value fails with [`Mpu6050Error::StaleConfiguration`] carrying both epochs. The
+//! epoch advances on every call of an operation below, whether or not the value changed,
+//! so a stale value may still equal the current one, it only costs taking it again. The
+//! frames already in the FIFO keep the layout they were written with: if the new schema
+//! has the layout of the stale one, the FIFO drains with it, otherwise reset the FIFO.
+//!
+//! #### Operations advancing the epoch
+//! All of them go through one internal function recording the [`ConfigChange`], see
+//! [`Mpu6050::last_config_change`], and feeding the
+//! [configuration change events](crate::config_events).
+//!
+//! | change | operations |
+//! |:---|:---|
+//! | [`AccelRange`](ConfigChange::AccelRange) | `set_accel_range`, `apply_settings`, `apply_settings_diff`, range interleaving and impact ranging switching ranges |
+//! | [`GyroRange`](ConfigChange::GyroRange) | `set_gyro_range`, `apply_settings`, `apply_settings_diff` |
+//! | [`Dlpf`](ConfigChange::Dlpf) | `set_dlpf`, `apply_settings`, `apply_settings_diff` |
+//! | [`SampleRateDivider`](ConfigChange::SampleRateDivider) | `set_sample_rate_divider`, `apply_settings`, `apply_settings_diff` |
+//! | [`FifoSources`](ConfigChange::FifoSources) | `set_fifo_sources`, the shutdown sequence |
+//! | [`AuxSlaves`](ConfigChange::AuxSlaves) | `configure_i2c_slave`, `disable_i2c_slave` |
+//! | [`Offsets`](ConfigChange::Offsets) | `set_acc_offset`, `set_gyro_offset`, the calibrations storing an offset: `calibrate_*_with_reference`, guided setup, background calibration commits, `apply_acc_trim`, the hardware offset setters, `store_gyro_offset_in_hardware` and `set_boot_transient_correction` |
+//! | [`ScaleFactors`](ConfigChange::ScaleFactors) | `set_accel_scale_factors`, `set_gyro_scale_factors`, `apply_scale_correction` |
+//! | [`TempCalibration`](ConfigChange::TempCalibration) | `set_temp_calibration`, `calibrate_temp_two_point`, `calibrate_temp_single_point` |
+//! | [`Reset`](ConfigChange::Reset) | `reset_device` |
+//!
+//! The axis map and the units of the pipeline are fixed when the driver is built. The clock
+//! source, sleep and cycle modes, the accel high pass filter, the clock and skew
+//! corrections, the lever arm and its compensation, the parse policy, the rounding of count conversions and the hooks leave the scaling and the
+//! layout alone and keep the epoch. Writes to the public `gyro_offset` and `acc_offset`
+//! fields bypass the driver: values derived before such a write should be taken again.
+//! [`ExtDataSlot`](crate::aux_i2c::ExtDataSlot) handles follow the EXT_SENS_DATA layout
+//! alone, see [`aux_i2c`](crate::aux_i2c).
+//!
+//! The epoch is a wrapping u32. A value kept across 2³² changes compares current again.
+//! ```
+//! use mpu6050::config_epoch::ConfigChange;
+//! use mpu6050::Mpu6050Builder;
+//!
+//! # struct Bus; // the board's I2C peripheral
+//! let mut mpu = Mpu6050Builder::new().i2c(Bus).build().unwrap();
+//! let offset = mpu.tracked(|mpu| mpu.acc_offset);
+//! assert!(offset.is_current(&mpu));
+//!
+//! mpu.set_acc_offset([0., 0., 0.01]);
+//! assert_eq!(mpu.last_config_change(), Some(ConfigChange::Offsets));
+//! assert!(!offset.is_current(&mpu));
+//! assert!(offset.get::<_, _, ()>(&mpu).is_err());
+//! let offset = mpu.tracked(|mpu| mpu.acc_offset);
+//! assert_eq!(offset.get::<_, _, ()>(&mpu).unwrap().z, 0.01);
+//! ```
+
+use core::fmt;
+
+use crate::logging::log_debug;
+use crate::{Mpu6050, Mpu6050Error};
+
+/// State change advancing the [`config_epoch`](crate::config_epoch)
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum ConfigChange {
+    /// accelerometer range, its sensitivity
+    AccelRange,
+    /// gyro range, its sensitivity
+    GyroRange,
+    /// DLPF_CFG, the bandwidth and delay
+    Dlpf,
+    /// SMPLRT_DIV, the sample rate
+    SampleRateDivider,
+    /// FIFO_EN, the FIFO frame layout
+    FifoSources,
+    /// aux slave configuration, the EXT_SENS_DATA part of the frame
+    AuxSlaves,
+    /// software or hardware offsets
+    Offsets,
+    /// per-axis scale factors
+    ScaleFactors,
+    /// temperature model
+    TempCalibration,
+    /// device reset, everything
+    Reset,
+}
+
+impl ConfigChange {
+    /// every change
+    pub const ALL: [ConfigChange; 10] = [
+        ConfigChange::AccelRange,
+        ConfigChange::GyroRange,
+        ConfigChange::Dlpf,
+        ConfigChange::SampleRateDivider,
+        ConfigChange::FifoSources,
+        ConfigChange::AuxSlaves,
+        ConfigChange::Offsets,
+        ConfigChange::ScaleFactors,
+        ConfigChange::TempCalibration,
+        ConfigChange::Reset,
+    ];
+}
+
+impl fmt::Display for ConfigChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            ConfigChange::AccelRange => "accel range",
+            ConfigChange::GyroRange => "gyro range",
+            ConfigChange::Dlpf => "DLPF",
+            ConfigChange::SampleRateDivider => "sample rate divider",
+            ConfigChange::FifoSources => "FIFO sources",
+            ConfigChange::AuxSlaves => "aux slaves",
+            ConfigChange::Offsets => "offsets",
+            ConfigChange::ScaleFactors => "scale factors",
+            ConfigChange::TempCalibration => "temperature calibration",
+            ConfigChange::Reset => "device reset",
+        })
+    }
+}
+
+/// A value derived from the driver's configuration and the epoch it was derived at
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Tracked<T> {
+    value: T,
+    epoch: u32,
+}
+
+impl<T> Tracked<T> {
+    /// epoch the value was derived at
+    pub fn epoch(&self) -> u32 {
+        self.epoch
+    }
+
+    /// true if `mpu` has not changed its configuration since. No bus access
+    pub fn is_current<I, D>(&self, mpu: &Mpu6050<I, D>) -> bool {
+        self.epoch == mpu.config_epoch
+    }
+
+    /// the value, [`Mpu6050Error::StaleConfiguration`] if `mpu` changed its configuration
+    /// since
+    pub fn get<I, D, E>(&self, mpu: &Mpu6050<I, D>) -> Result<&T, Mpu6050Error<E>> {
+        mpu.check_epoch(self.epoch)?;
+        Ok(&self.value)
+    }
+
+    /// the value without a check, current or not
+    pub fn into_inner(self) -> T {
+        self.value
+    }
+}
+
+impl<I, D> Mpu6050<I, D> {
+    /// Current configuration epoch, see [`config_epoch`](crate::config_epoch)
+    pub fn config_epoch(&self) -> u32 {
+        self.config_epoch
+    }
+
+    /// Change advancing the epoch last, None if it never advanced
+    pub fn last_config_change(&self) -> Option<ConfigChange> {
+        self.last_config_change
+    }
+
+    /// `derive` of the current configuration, tagged with the epoch
+    pub fn tracked<T>(&self, derive: impl FnOnce(&Self) -> T) -> Tracked<T> {
+        Tracked {
+            value: derive(self),
+            epoch: self.config_epoch,
+        }
+    }
+
+    /// the single place advancing the epoch
+    pub(crate) fn bump_epoch(&mut self, change: ConfigChange) {
+        self.config_epoch = self.config_epoch.wrapping_add(1);
+        self.last_config_change = Some(change);
+        log_debug!(
+            CONFIG,
+            "configuration changed: {}, epoch {}",
+            change,
+            self.config_epoch
+        );
+        self.observe_config();
+    }
+
+    /// StaleConfiguration unless `captured` is the current epoch
+    pub(crate) fn check_epoch<E>(&self, captured: u32) -> Result<(), Mpu6050Error<E>> {
+        if captured != self.config_epoch {
+            return Err(Mpu6050Error::StaleConfiguration {
+                captured,
+                current: self.config_epoch,
+            });
+        }
+        Ok(())
+    }
+}
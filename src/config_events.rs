@@ -0,0 +1,370 @@
+//! Change events of the driver's cached configuration, for live views of a device.
+//!
+//! A dashboard polling [`Mpu6050::read_settings`] misses what changed between two polls and
+//! who changed it. A registered [`ConfigObserver`] instead receives a [`ConfigChangeEvent`]
+//! for every field of the cached configuration that changes, with the value before and
+//! after and the [`ChangeSource`]: the application's own call, the
+//! [governor](crate::governor) moving between operating points, a
+//! [register script](crate::script), the resynchronization after a
+//! [bus recovery](crate::recovery) found the device differing, or
+//! [`adopt_device_settings`](Mpu6050::adopt_device_settings).
+//!
+//! #### Queue
+//! The observer is a bounded queue held by the driver and drained by the application, not
+//! a callback: recording happens inside the driver's operations, where a callback would
+//! run with the driver borrowed and could neither read it nor act on it, and a `static`
+//! sink would need a lock on targets without one. The queue needs neither, costs no
+//! allocation, and the application drains it on its own schedule, e.g. once per frame of
+//! the dashboard. When it is full the oldest event is overwritten and counted in
+//! [`dropped`](ConfigObserver::dropped), the current value is then [`Mpu6050::read_settings`]
+//! away. The queue and the values it compares against take about half a kilobyte, inside
+//! the driver whether registered or not.
+//! ```
+//! use mpu6050::config_events::{ChangeSource, ConfigField, ConfigObserver, ConfigValue};
+//! use mpu6050::Mpu6050Builder;
+//!
+//! # struct Bus; // the board's I2C peripheral
+//! let mut mpu = Mpu6050Builder::new().i2c(Bus).build().unwrap();
+//! mpu.set_config_observer(Some(ConfigObserver::new()));
+//! mpu.set_acc_offset([0., 0., 0.01]);
+//!
+//! let events: Vec<_> = mpu.config_observer().unwrap().drain().collect();
+//! assert_eq!(events[0].field, ConfigField::AccelOffset);
+//! assert_eq!(events[0].new, ConfigValue::Vector([0., 0., 0.01]));
+//! assert_eq!(events[0].source, ChangeSource::UserApi);
+//! ```
+//!
+//! #### Coverage
+//! Events are recorded where the [`config_epoch`](crate::config_epoch) advances: each time,
+//! the cached configuration is compared with the values last reported, one event per
+//! differing field. No operation reports on its own, so any operation advancing the epoch
+//! reports whatever it changed, a device reset the fields it restored to their reset
+//! values. An operation advancing the epoch without changing a field, e.g. writing the
+//! range already set, reports nothing. The hardware offset registers are not cached and
+//! not reported, writes to the public `gyro_offset` and `acc_offset` fields are reported
+//! with the next operation advancing the epoch, attributed to its source.
+//!
+//! A resynchronization reports the fields it restored with the value found on the device
+//! as `old`. No events are recorded without an observer, the operations then pay one
+//! `Option` check, and registering one starts from the configuration at that moment.
+//!
+//! With the `serde` feature the event types implement `Serialize` and `Deserialize`, for
+//! forwarding them as they are. Values are register codes and plain numbers, an event is
+//! 40 bytes in memory.
+
+use crate::config_epoch::ConfigChange;
+use crate::Mpu6050;
+
+/// Field of the cached configuration
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ConfigField {
+    /// accelerometer range, [`Code`](ConfigValue::Code) of AFS_SEL
+    AccelRange,
+    /// gyro range, [`Code`](ConfigValue::Code) of FS_SEL
+    GyroRange,
+    /// [`Code`](ConfigValue::Code) of DLPF_CFG
+    Dlpf,
+    /// [`Code`](ConfigValue::Code) of SMPLRT_DIV
+    SampleRateDivider,
+    /// FIFO sources, [`Bits`](ConfigValue::Bits) of FIFO_EN and bit 8 for slave 3
+    FifoSources,
+    /// aux slave table, [`Generation`](ConfigValue::Generation) advancing with every
+    /// configured or disabled slave
+    AuxSlaves,
+    /// software accel offset, [`Vector`](ConfigValue::Vector) in g
+    AccelOffset,
+    /// software gyro offset, [`Vector`](ConfigValue::Vector) in rad/s
+    GyroOffset,
+    /// per-axis accel scale factors, [`Vector`](ConfigValue::Vector)
+    AccelScaleFactors,
+    /// per-axis gyro scale factors, [`Vector`](ConfigValue::Vector)
+    GyroScaleFactors,
+    /// temperature model, [`Vector`](ConfigValue::Vector) of raw offset, sensitivity and °C
+    /// offset
+    TempModel,
+}
+
+impl ConfigField {
+    /// every field, in the order events of one change are recorded
+    pub const ALL: [ConfigField; 11] = [
+        ConfigField::AccelRange,
+        ConfigField::GyroRange,
+        ConfigField::Dlpf,
+        ConfigField::SampleRateDivider,
+        ConfigField::FifoSources,
+        ConfigField::AuxSlaves,
+        ConfigField::AccelOffset,
+        ConfigField::GyroOffset,
+        ConfigField::AccelScaleFactors,
+        ConfigField::GyroScaleFactors,
+        ConfigField::TempModel,
+    ];
+
+    /// the [`ConfigChange`] a change of the field is, apart from a reset
+    pub fn change(self) -> ConfigChange {
+        match self {
+            ConfigField::AccelRange => ConfigChange::AccelRange,
+            ConfigField::GyroRange => ConfigChange::GyroRange,
+            ConfigField::Dlpf => ConfigChange::Dlpf,
+            ConfigField::SampleRateDivider => ConfigChange::SampleRateDivider,
+            ConfigField::FifoSources => ConfigChange::FifoSources,
+            ConfigField::AuxSlaves => ConfigChange::AuxSlaves,
+            ConfigField::AccelOffset | ConfigField::GyroOffset => ConfigChange::Offsets,
+            ConfigField::AccelScaleFactors | ConfigField::GyroScaleFactors => {
+                ConfigChange::ScaleFactors
+            }
+            ConfigField::TempModel => ConfigChange::TempCalibration,
+        }
+    }
+}
+
+/// Value of a [`ConfigField`], see the field for its unit
+#[derive(Copy, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ConfigValue {
+    /// register field
+    Code(u8),
+    /// flags
+    Bits(u16),
+    /// counter
+    Generation(u32),
+    /// X, Y, Z or three constants
+    Vector([f32; 3]),
+}
+
+impl ConfigValue {
+    /// equal bit for bit, a NaN equals itself
+    fn same(&self, other: &ConfigValue) -> bool {
+        match (self, other) {
+            (ConfigValue::Vector(a), ConfigValue::Vector(b)) => {
+                a.iter().zip(b).all(|(a, b)| a.to_bits() == b.to_bits())
+            }
+            _ => self == other,
+        }
+    }
+}
+
+/// What made a configuration change
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ChangeSource {
+    /// an operation the application called
+    #[default]
+    UserApi,
+    /// restored after a bus recovery found the device differing
+    Resync,
+    /// the power governor moving between operating points
+    Governor,
+    /// a register script writing a cached field
+    Script,
+    /// taken over from the device by
+    /// [`adopt_device_settings`](Mpu6050::adopt_device_settings)
+    Adopt,
+}
+
+/// One changed field
+#[derive(Copy, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ConfigChangeEvent {
+    /// field changed
+    pub field: ConfigField,
+    /// value before
+    pub old: ConfigValue,
+    /// value after
+    pub new: ConfigValue,
+    /// what changed it
+    pub source: ChangeSource,
+    /// [`config_epoch`](crate::config_epoch) after the change
+    pub epoch: u32,
+}
+
+impl ConfigChangeEvent {
+    const EMPTY: ConfigChangeEvent = ConfigChangeEvent {
+        field: ConfigField::AccelRange,
+        old: ConfigValue::Code(0),
+        new: ConfigValue::Code(0),
+        source: ChangeSource::UserApi,
+        epoch: 0,
+    };
+}
+
+/// Queue depth and losses of a [`ConfigObserver`]
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub struct ConfigObserverStatus {
+    /// events waiting to be drained
+    pub pending: usize,
+    /// events overwritten before being drained
+    pub dropped: u32,
+}
+
+/// Bounded queue of [`ConfigChangeEvent`]s, registered with
+/// [`Mpu6050::set_config_observer`]
+#[derive(Clone, Debug)]
+pub struct ConfigObserver {
+    /// values last reported, per [`ConfigField::ALL`]
+    reported: [ConfigValue; 11],
+    events: [ConfigChangeEvent; ConfigObserver::CAPACITY],
+    head: usize,
+    len: usize,
+    dropped: u32,
+}
+
+impl Default for ConfigObserver {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ConfigObserver {
+    /// events kept before the oldest is overwritten
+    pub const CAPACITY: usize = 8;
+
+    /// empty queue
+    pub const fn new() -> Self {
+        Self {
+            reported: [ConfigValue::Code(0); 11],
+            events: [ConfigChangeEvent::EMPTY; Self::CAPACITY],
+            head: 0,
+            len: 0,
+            dropped: 0,
+        }
+    }
+
+    /// oldest event, None if empty
+    pub fn pop(&mut self) -> Option<ConfigChangeEvent> {
+        if self.len == 0 {
+            return None;
+        }
+        let event = self.events[self.head];
+        self.head = (self.head + 1) % Self::CAPACITY;
+        self.len -= 1;
+        Some(event)
+    }
+
+    /// the events, oldest first, removed as they are iterated
+    pub fn drain(&mut self) -> impl Iterator<Item = ConfigChangeEvent> + '_ {
+        core::iter::from_fn(move || self.pop())
+    }
+
+    /// events waiting
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// true if no event is waiting
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// events overwritten before being drained, saturating
+    pub fn dropped(&self) -> u32 {
+        self.dropped
+    }
+
+    /// queue depth and losses
+    pub fn status(&self) -> ConfigObserverStatus {
+        ConfigObserverStatus {
+            pending: self.len,
+            dropped: self.dropped,
+        }
+    }
+
+    fn push(&mut self, event: ConfigChangeEvent) {
+        if self.len == Self::CAPACITY {
+            self.head = (self.head + 1) % Self::CAPACITY;
+            self.len -= 1;
+            self.dropped = self.dropped.saturating_add(1);
+        }
+        self.events[(self.head + self.len) % Self::CAPACITY] = event;
+        self.len += 1;
+    }
+
+    /// one event per field of `current` differing from the values last reported
+    fn record(&mut self, current: [ConfigValue; 11], source: ChangeSource, epoch: u32) {
+        for (k, field) in ConfigField::ALL.into_iter().enumerate() {
+            if !self.reported[k].same(&current[k]) {
+                self.push(ConfigChangeEvent {
+                    field,
+                    old: self.reported[k],
+                    new: current[k],
+                    source,
+                    epoch,
+                });
+            }
+        }
+        self.reported = current;
+    }
+}
+
+impl<I, D> Mpu6050<I, D> {
+    /// Registers `observer`, None unregisters. Returns the previous one with the events it
+    /// still holds. The observer reports changes from the current configuration on
+    pub fn set_config_observer(
+        &mut self,
+        observer: Option<ConfigObserver>,
+    ) -> Option<ConfigObserver> {
+        let current = self.config_values();
+        let observer = observer.map(|mut observer| {
+            observer.reported = current;
+            observer
+        });
+        core::mem::replace(&mut self.config_observer, observer)
+    }
+
+    /// The registered observer, to drain its events
+    pub fn config_observer(&mut self) -> Option<&mut ConfigObserver> {
+        self.config_observer.as_mut()
+    }
+
+    /// the cached configuration per [`ConfigField::ALL`]
+    fn config_values(&self) -> [ConfigValue; 11] {
+        let fifo = &self.fifo_sources;
+        let temp = self.temp_model();
+        [
+            ConfigValue::Code(self.accel_range as u8),
+            ConfigValue::Code(self.gyro_range as u8),
+            ConfigValue::Code(self.dlpf_cfg),
+            ConfigValue::Code(self.sample_rate_div),
+            ConfigValue::Bits(u16::from(fifo.fifo_en()) | u16::from(fifo.slaves[3]) << 8),
+            ConfigValue::Generation(self.aux.generation()),
+            ConfigValue::Vector(self.acc_offset.to_array()),
+            ConfigValue::Vector(self.gyro_offset.to_array()),
+            ConfigValue::Vector(self.acc_scale.per_axis.to_array()),
+            ConfigValue::Vector(self.gyro_scale.per_axis.to_array()),
+            ConfigValue::Vector([temp.raw_offset, temp.sensitivity, temp.offset_c]),
+        ]
+    }
+
+    /// records the changes since the last report, called where the epoch advances
+    pub(crate) fn observe_config(&mut self) {
+        if self.config_observer.is_none() {
+            return;
+        }
+        let current = self.config_values();
+        let (source, epoch) = (self.change_source, self.config_epoch);
+        if let Some(observer) = &mut self.config_observer {
+            observer.record(current, source, epoch);
+        }
+    }
+
+    /// `operation` with its changes attributed to `source`
+    pub(crate) fn with_change_source<R>(
+        &mut self,
+        source: ChangeSource,
+        operation: impl FnOnce(&mut Self) -> R,
+    ) -> R {
+        let outer = core::mem::replace(&mut self.change_source, source);
+        let result = operation(self);
+        self.change_source = outer;
+        result
+    }
+
+    /// `field` as found on the device, reported as the old value of the next change
+    pub(crate) fn observe_device_value(&mut self, field: ConfigField, value: ConfigValue) {
+        if let Some(observer) = &mut self.config_observer {
+            // ALL is in declaration order
+            observer.reported[field as usize] = value;
+        }
+    }
+}
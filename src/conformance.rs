@@ -0,0 +1,87 @@
+//! Conformance profiles reproducing the numbers of other implementations, for validating a
+//! transition from them against recorded data.
+//!
+//! A [`ConformanceProfile`] is a set of [`PipelineStages`]: applied to a [`Pipeline`] it
+//! switches the conventions of the same scaling steps, there is no second implementation.
+//! [`Native`](ConformanceProfile::Native) is this crate's documented behaviour and what the
+//! driver computes. [`Empl`](ConformanceProfile::Empl) follows InvenSense's eMPL (Motion
+//! Driver 5.1, `inv_mpu.c` and the MPL's q16 outputs) on the MPU6050. Where they differ:
+//!
+//! | step | `Native` | `Empl` |
+//! |:---|:---|:---|
+//! | accel, gyro sensitivity | 16384 to 2048 LSB/g, 131 to 16.4 LSB/°/s | same |
+//! | gyro unit, and of the gyro offset | rad/s | °/s |
+//! | mounting [`AxisMap`](crate::axis_map::AxisMap) | after offsets and per-axis factors, both in chip axes | on the scaled counts before the offsets, offsets and factors in mounting axes |
+//! | temperature | `raw / 340 + 36.53` | `35 + (raw + 521) / 340`, temp_offset -521 and temp_sens 340 |
+//! | outputs | f32 | truncated toward zero to q16, multiples of 1/65536, as eMPL's `long` outputs |
+//!
+//! The temperature constants put eMPL 0.0024 °C above the native formula, less than one
+//! LSB but the same sign on every sample; the q16 truncation moves every output toward zero
+//! by up to 1/65536 of its unit. Orientation and offsets only differ in their frame: with a
+//! mounting map other than the identity, eMPL's offsets are the native ones mapped as well.
+//! The MPU6500 constants of `inv_mpu.c` are not covered.
+//!
+//! Enable with the `empl-conformance` feature. Profiles are an analysis choice: log headers
+//! record the native pipeline of the driver, a profile applies when replaying.
+//! ```
+//! use mpu6050::axis_map::AxisMap;
+//! use mpu6050::conformance::ConformanceProfile;
+//! use mpu6050::device::{AccelRange, Axis, GyroRange};
+//! use mpu6050::scale::Pipeline;
+//! use mpu6050::Vec3A;
+//!
+//! // mounted rotated by 90° about Z, offsets as the legacy firmware stored them
+//! let mounting = AxisMap::new([Axis::Y, Axis::X, Axis::Z], [true, false, false]);
+//! let mut empl = Pipeline::new(AccelRange::G2, GyroRange::D250)
+//!     .with_profile(ConformanceProfile::Empl)
+//!     .with_orientation(mounting);
+//! empl.gyro_offset = Vec3A::new(0.5, 0., -0.25);
+//!
+//! // 131 counts on chip Y are -1 °/s on mounting X
+//! assert_eq!(empl.gyro([0, 131, 0]), Vec3A::new(-1.5, 0., 0.25));
+//! assert_eq!(empl.temp(-521), 35.);
+//! ```
+
+use crate::axis_map::AxisMap;
+use crate::scale::{GyroUnit, OrientationStage, Pipeline, PipelineStages, TempModel};
+
+/// Conventions of a reference implementation, see the [module docs](self)
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub enum ConformanceProfile {
+    /// this crate's documented behaviour
+    #[default]
+    Native,
+    /// InvenSense eMPL on the MPU6050
+    Empl,
+}
+
+impl ConformanceProfile {
+    /// temperature constants of `inv_mpu.c` for the MPU6050
+    pub const EMPL_TEMP: TempModel = TempModel {
+        raw_offset: -521.,
+        sensitivity: 340.,
+        offset_c: 35.,
+    };
+
+    /// the stages of the profile, identity mounting
+    pub const fn stages(self) -> PipelineStages {
+        match self {
+            ConformanceProfile::Native => PipelineStages::NATIVE,
+            ConformanceProfile::Empl => PipelineStages {
+                orientation: AxisMap::IDENTITY,
+                orientation_stage: OrientationStage::BeforeOffsets,
+                gyro_unit: GyroUnit::DegreesPerSecond,
+                temp: Self::EMPL_TEMP,
+                fixed_point_bits: Some(16),
+            },
+        }
+    }
+}
+
+impl Pipeline {
+    /// same pipeline with the stages of `profile`, keeping the mounting map
+    pub fn with_profile(self, profile: ConformanceProfile) -> Self {
+        let stages = profile.stages().with_orientation(self.stages.orientation);
+        self.with_stages(stages)
+    }
+}
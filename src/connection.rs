@@ -0,0 +1,195 @@
+//! Connection tracking for detachable sensors.
+//!
+//! Every bus transaction of the driver reports its result to a [`ConnectionMonitor`]. After
+//! `threshold` consecutive failures the sensor is considered [`ConnectionState::Disconnected`].
+//! With auto mode enabled, the driver then returns [`Mpu6050Error::Disconnected`](crate::Mpu6050Error::Disconnected)
+//! without touching the bus until [`Mpu6050::try_reconnect`](crate::Mpu6050::try_reconnect) is called.
+//!
+//! A reconnect re-applies what the driver caches: ranges, DLPF_CFG, SMPLRT_DIV and cycle
+//! mode. The accel high pass filter and the interrupt and motion configuration, not modeled
+//! by the driver, are kept as last written in [`RESTORED_REGISTERS`] and written back in
+//! that order. FIFO and aux slaves are not, reconfigure them after a reconnect.
+
+use crate::register::Register;
+
+/// Default number of consecutive bus errors after which the sensor counts as disconnected
+pub const DEFAULT_DISCONNECT_THRESHOLD: u32 = 3;
+
+/// Connection state of the sensor, as seen by the driver
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum ConnectionState {
+    /// Transactions succeed (or fewer than `threshold` failed in a row)
+    Connected,
+    /// `threshold` consecutive transactions failed
+    Disconnected {
+        /// operations attempted since the disconnect was detected, short-circuited ones included
+        since_ops: u32,
+    },
+    /// A reconnect attempt is in progress
+    Reinitializing,
+}
+
+/// Result of a successful [`Mpu6050::try_reconnect`](crate::Mpu6050::try_reconnect) probe
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum ReconnectOutcome {
+    /// The same chip came back, configuration and calibration were re-applied, see the
+    /// [module docs](self)
+    SameChip {
+        /// WHO_AM_I value read
+        chip_id: u8,
+    },
+    /// A chip with a different WHO_AM_I answered. It was NOT configured and the driver's
+    /// calibration was not applied to it, the caller decides whether to `init` it
+    DifferentChip {
+        /// WHO_AM_I value of the chip seen before the disconnect
+        previous: u8,
+        /// WHO_AM_I value read now
+        found: u8,
+    },
+}
+
+/// Registers kept as last written by the driver and written back by a reconnect, the
+/// interrupt enables last. Of ACCEL_CONFIG only the high pass filter is restored, the range
+/// is cached and self-test bits are not
+pub const RESTORED_REGISTERS: [Register; 6] = [
+    Register::ACCEL_CONFIG,
+    Register::INT_PIN_CFG,
+    Register::MOT_THR,
+    Register::MOT_DUR,
+    Register::MOT_DETECT_CONTROL,
+    Register::INT_ENABLE,
+];
+
+/// Last value written to each of [`RESTORED_REGISTERS`], None if never written
+#[derive(Copy, Clone, Debug, Default)]
+pub(crate) struct RegisterShadow([Option<u8>; RESTORED_REGISTERS.len()]);
+
+impl RegisterShadow {
+    /// notes `byte` written to `reg`
+    pub(crate) fn record(&mut self, reg: u8, byte: u8) {
+        if let Some(i) = RESTORED_REGISTERS.iter().position(|r| r.addr() == reg) {
+            self.0[i] = Some(byte);
+        }
+    }
+
+    /// last value written to `reg`
+    pub(crate) fn get(&self, reg: Register) -> Option<u8> {
+        let i = RESTORED_REGISTERS.iter().position(|r| *r == reg)?;
+        self.0[i]
+    }
+
+    /// the values in the order of [`RESTORED_REGISTERS`]
+    pub(crate) fn values(&self) -> [Option<u8>; RESTORED_REGISTERS.len()] {
+        self.0
+    }
+
+    /// forgets every value, the registers are at their reset values
+    pub(crate) fn clear(&mut self) {
+        *self = Self::default();
+    }
+}
+
+/// Consecutive error accounting and connection state machine, no bus access
+#[derive(Copy, Clone, Debug)]
+pub struct ConnectionMonitor {
+    state: ConnectionState,
+    consecutive_errors: u32,
+    successes: u32,
+    threshold: u32,
+    auto: bool,
+}
+
+impl Default for ConnectionMonitor {
+    fn default() -> Self {
+        Self::new(DEFAULT_DISCONNECT_THRESHOLD, false)
+    }
+}
+
+impl ConnectionMonitor {
+    /// New monitor in state `Connected`. A threshold of 0 is treated as 1
+    pub fn new(threshold: u32, auto: bool) -> Self {
+        Self {
+            state: ConnectionState::Connected,
+            consecutive_errors: 0,
+            successes: 0,
+            threshold: threshold.max(1),
+            auto,
+        }
+    }
+
+    /// current state
+    pub fn state(&self) -> ConnectionState {
+        self.state
+    }
+
+    /// consecutive failed transactions so far
+    pub fn consecutive_errors(&self) -> u32 {
+        self.consecutive_errors
+    }
+
+    /// successful transactions since the monitor was created, saturating
+    pub fn successes(&self) -> u32 {
+        self.successes
+    }
+
+    /// set number of consecutive errors after which the sensor counts as disconnected
+    pub fn set_threshold(&mut self, threshold: u32) {
+        self.threshold = threshold.max(1);
+    }
+
+    /// get disconnect threshold
+    pub fn threshold(&self) -> u32 {
+        self.threshold
+    }
+
+    /// enable, disable short-circuiting of transactions while disconnected
+    pub fn set_auto(&mut self, auto: bool) {
+        self.auto = auto;
+    }
+
+    /// get whether transactions are short-circuited while disconnected
+    pub fn auto(&self) -> bool {
+        self.auto
+    }
+
+    /// Called before each transaction, returns false if it must be skipped
+    pub fn should_attempt(&mut self) -> bool {
+        if let ConnectionState::Disconnected { since_ops } = &mut self.state {
+            *since_ops = since_ops.saturating_add(1);
+            return !self.auto;
+        }
+        true
+    }
+
+    /// Called after each attempted transaction
+    pub fn record(&mut self, success: bool) {
+        if success {
+            self.consecutive_errors = 0;
+            self.successes = self.successes.saturating_add(1);
+            if let ConnectionState::Disconnected { .. } = self.state {
+                self.state = ConnectionState::Connected;
+            }
+            return;
+        }
+
+        self.consecutive_errors = self.consecutive_errors.saturating_add(1);
+        if self.state == ConnectionState::Connected && self.consecutive_errors >= self.threshold {
+            self.state = ConnectionState::Disconnected { since_ops: 0 };
+        }
+    }
+
+    /// Enter `Reinitializing`, transactions are attempted regardless of auto mode
+    pub fn begin_reconnect(&mut self) {
+        self.state = ConnectionState::Reinitializing;
+    }
+
+    /// Leave `Reinitializing`, either connected or back to disconnected
+    pub fn end_reconnect(&mut self, connected: bool) {
+        if connected {
+            self.consecutive_errors = 0;
+            self.state = ConnectionState::Connected;
+        } else {
+            self.state = ConnectionState::Disconnected { since_ops: 0 };
+        }
+    }
+}
@@ -0,0 +1,109 @@
+//! Continuity token of the driver, for fusion state across power transitions.
+//!
+//! A filter integrating the gyro assumes the samples it gets are consecutive. Across a
+//! sleep of minutes that is wrong both ways: integrating as if no time passed keeps a
+//! stale tilt, integrating the real dt multiplies the gyro bias by minutes. The driver
+//! keeps one counter, [`Mpu6050::continuity_token`], and advances it on every transition
+//! after which the samples do not continue the ones before. A fusion update given the
+//! token, [`ComplementaryFilter::update_with_token`] and [`update_sample_with_token`],
+//! compares it with the token of its previous update and on a mismatch re-enters instead
+//! of stepping, see the [orientation docs](crate::orientation#re-entry). Applications
+//! running their own fusion read the token the same way.
+//!
+//! #### Operations advancing the token
+//! All of them go through one internal function recording the [`ContinuityBreak`], see
+//! [`Mpu6050::last_continuity_break`]. Sleep and cycle mode advance it when the driver's
+//! state changes, a repeated `set_sleep_enabled(true)` does not.
+//!
+//! | break | operations |
+//! |:---|:---|
+//! | [`Sleep`](ContinuityBreak::Sleep) | `set_sleep_enabled(true)`, the shutdown sequence |
+//! | [`Wake`](ContinuityBreak::Wake) | `set_sleep_enabled(false)`, `init` |
+//! | [`CycleEntered`](ContinuityBreak::CycleEntered) | `apply_settings`, `apply_settings_diff` and power governor transitions entering cycle mode |
+//! | [`CycleLeft`](ContinuityBreak::CycleLeft) | the same leaving cycle mode |
+//! | [`Reset`](ContinuityBreak::Reset) | `reset_device` |
+//! | [`Reconnect`](ContinuityBreak::Reconnect) | `try_reconnect` finding a chip, the same or a different one |
+//!
+//! Range, filter and rate changes keep the token: the samples continue, their scaling is
+//! the [configuration epoch](crate::config_epoch)'s business and the settling after them
+//! the [settling policy](crate::settling)'s. Raw and typed register writes bypass the
+//! driver's power state and the token with it.
+//!
+//! The token is a wrapping u32. A filter kept across 2³² breaks misses one.
+//! ```
+//! use mpu6050::continuity::ContinuityBreak;
+//! use mpu6050::Mpu6050Builder;
+//!
+//! # struct Bus; // the board's I2C peripheral
+//! let mpu = Mpu6050Builder::new().i2c(Bus).build().unwrap();
+//! assert_eq!(mpu.continuity_token(), 0);
+//! assert_eq!(mpu.last_continuity_break(), None);
+//! assert_eq!(ContinuityBreak::CycleLeft.to_string(), "cycle mode left");
+//! ```
+//!
+//! [`ComplementaryFilter::update_with_token`]: crate::orientation::ComplementaryFilter::update_with_token
+//! [`update_sample_with_token`]: crate::orientation::ComplementaryFilter::update_sample_with_token
+
+use core::fmt;
+
+use crate::Mpu6050;
+
+/// Power transition advancing the [`continuity_token`](crate::continuity)
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum ContinuityBreak {
+    /// SLEEP set
+    Sleep,
+    /// SLEEP cleared
+    Wake,
+    /// cycle mode entered, the accelerometer sampled at the wake frequency alone
+    CycleEntered,
+    /// cycle mode left
+    CycleLeft,
+    /// device reset
+    Reset,
+    /// chip found again after a disconnect
+    Reconnect,
+}
+
+impl ContinuityBreak {
+    /// every break
+    pub const ALL: [ContinuityBreak; 6] = [
+        ContinuityBreak::Sleep,
+        ContinuityBreak::Wake,
+        ContinuityBreak::CycleEntered,
+        ContinuityBreak::CycleLeft,
+        ContinuityBreak::Reset,
+        ContinuityBreak::Reconnect,
+    ];
+}
+
+impl fmt::Display for ContinuityBreak {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            ContinuityBreak::Sleep => "sleep",
+            ContinuityBreak::Wake => "wake",
+            ContinuityBreak::CycleEntered => "cycle mode entered",
+            ContinuityBreak::CycleLeft => "cycle mode left",
+            ContinuityBreak::Reset => "device reset",
+            ContinuityBreak::Reconnect => "reconnect",
+        })
+    }
+}
+
+impl<I, D> Mpu6050<I, D> {
+    /// Current continuity token, see [`continuity`](crate::continuity)
+    pub fn continuity_token(&self) -> u32 {
+        self.continuity_token
+    }
+
+    /// Break advancing the token last, None if it never advanced
+    pub fn last_continuity_break(&self) -> Option<ContinuityBreak> {
+        self.last_continuity_break
+    }
+
+    /// the single place advancing the token
+    pub(crate) fn break_continuity(&mut self, cause: ContinuityBreak) {
+        self.continuity_token = self.continuity_token.wrapping_add(1);
+        self.last_continuity_break = Some(cause);
+    }
+}
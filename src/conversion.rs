@@ -0,0 +1,199 @@
+//! Conversions between raw counts and scaled values, and between bit widths, in one place.
+//!
+//! Every path from counts to g, rad/s and °C, and back, goes through this module: the scaled
+//! getters and the [`Pipeline`](crate::scale::Pipeline), FIFO frames, the packed encoder and
+//! decoder, the gyro hardware offsets written from a calibration and the synthetic frames.
+//!
+//! #### Counts to scaled values
+//! `count as f32 / sensitivity` for accel and temperature, `count as f32 * (π/180 /
+//! sensitivity)` for the gyro, the float operations rounding to nearest even as IEEE 754
+//! does. There is no rounding mode to choose: i16 counts are exact in f32, and the result is
+//! the closest f32 to the quotient.
+//!
+//! #### Scaled values to counts
+//! The scaled value times the sensitivity, rounded to an integer by the [`RoundingMode`] and
+//! saturated to i16. `NearestEven` is the default everywhere:
+//!
+//! | mode | rounding | error | bias |
+//! |:---|:---|:---|:---|
+//! | [`NearestEven`](RoundingMode::NearestEven) | to nearest, ties to even | ≤ 0.5 LSB | none, ties go either way |
+//! | [`Truncate`](RoundingMode::Truncate) | toward zero | < 1 LSB | toward zero, -0.5 LSB for positive values |
+//! | [`Legacy`](RoundingMode::Legacy) | as before this module, see below | ≤ 0.5 LSB | ties away from zero, ties up when narrowing |
+//!
+//! Round trips:
+//! * counts to scaled and back is exact for every i16 at every range with `NearestEven` and
+//!   `Legacy`; the error of the scaled value is far below half an LSB. `Truncate` returns the
+//!   count or the one next to it toward zero
+//! * scaled to counts and back is off by at most the rounding error above, in LSB of the range
+//!
+//! #### Bit widths
+//! Narrowing a 16 bit count to `bits` divides by `2^(16 - bits)` with the same modes, ties
+//! are the counts exactly halfway between two narrow values, every 2^(16 - bits)th count.
+//! Widening back multiplies, exact. Narrow to wide and back is exact, wide to narrow and back
+//! is off by at most half a narrow step (a full one with `Truncate`). Values beyond the
+//! narrow range saturate.
+//!
+//! #### Legacy
+//! The conversions of earlier releases, bit-for-bit, for comparisons against outputs they
+//! produced: `f32::round` (ties away from zero) to counts and `(count + 2^(s - 1)) >> s` (ties
+//! toward +infinity) when narrowing. They differ from `NearestEven` at ties only: scaled
+//! values exactly halfway between two counts, and one in 2^(16 - bits) counts when narrowing.
+//! The gyro hardware offsets keep glam's vector rounding, which ties to even on SSE2 targets
+//! and away from zero elsewhere.
+
+use glam::Vec3A;
+
+use crate::device::{GYRO_OFFSET_DPS_PER_LSB, TEMP_OFFSET, TEMP_SENSITIVITY};
+#[cfg(not(feature = "std"))]
+use crate::math::FloatExt;
+use crate::{Mpu6050, PI_180};
+
+/// Rounding to counts, see the [module docs](self)
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub enum RoundingMode {
+    /// to nearest, ties to even, unbiased
+    #[default]
+    NearestEven,
+    /// toward zero
+    Truncate,
+    /// the rounding of earlier releases, ties away from zero, ties up when narrowing
+    Legacy,
+}
+
+impl RoundingMode {
+    /// `value` rounded to an integral value
+    pub fn round(self, value: f32) -> f32 {
+        match self {
+            RoundingMode::NearestEven => value.round_ties_even(),
+            RoundingMode::Truncate => value.trunc(),
+            RoundingMode::Legacy => value.round(),
+        }
+    }
+
+    /// `value` in LSB rounded to a count, and whether it saturated. NaN is 0
+    pub fn to_count(self, value: f32) -> (i16, bool) {
+        let rounded = self.round(value);
+        let saturated = rounded > i16::MAX as f32 || rounded < i16::MIN as f32;
+        // float to int casts saturate
+        (rounded as i16, saturated)
+    }
+
+    fn to_counts(self, values: Vec3A) -> ([i16; 3], bool) {
+        let (x, sx) = self.to_count(values.x);
+        let (y, sy) = self.to_count(values.y);
+        let (z, sz) = self.to_count(values.z);
+        ([x, y, z], sx || sy || sz)
+    }
+
+    /// `count` divided by `2^shift`, rounded
+    fn shift_right(self, count: i32, shift: u32) -> i32 {
+        if shift == 0 {
+            return count;
+        }
+        let half = 1 << (shift - 1);
+        match self {
+            RoundingMode::NearestEven => {
+                let floor = count >> shift;
+                let rest = count - (floor << shift);
+                match rest > half || (rest == half && floor & 1 == 1) {
+                    true => floor + 1,
+                    false => floor,
+                }
+            }
+            RoundingMode::Truncate => match count < 0 {
+                true => -(-count >> shift),
+                false => count >> shift,
+            },
+            RoundingMode::Legacy => (count + half) >> shift,
+        }
+    }
+}
+
+impl<I, D> Mpu6050<I, D> {
+    /// rounding of the driver's conversions to counts, the gyro offsets
+    /// [stored in hardware](Mpu6050::store_gyro_offset_in_hardware)
+    pub fn rounding(&self) -> RoundingMode {
+        self.rounding
+    }
+
+    /// sets the [`rounding`](Self::rounding)
+    pub fn set_rounding(&mut self, rounding: RoundingMode) {
+        self.rounding = rounding;
+    }
+}
+
+/// counts to units with `lsb_per_unit`, the accel in g
+pub fn counts_to_units([x, y, z]: [i16; 3], lsb_per_unit: f32) -> Vec3A {
+    Vec3A::new(x as f32, y as f32, z as f32) / lsb_per_unit
+}
+
+/// gyro counts to rad/s with `lsb_per_dps`
+pub fn counts_to_rad_s([x, y, z]: [i16; 3], lsb_per_dps: f32) -> Vec3A {
+    Vec3A::new(x as f32, y as f32, z as f32) * (PI_180 / lsb_per_dps)
+}
+
+/// temperature counts to °C, register map rev 4.2
+pub fn counts_to_celsius(raw: i16) -> f32 {
+    (raw as f32 / TEMP_SENSITIVITY) + TEMP_OFFSET
+}
+
+/// units to counts with `lsb_per_unit`, and whether any axis saturated
+pub fn units_to_counts(value: Vec3A, lsb_per_unit: f32, mode: RoundingMode) -> ([i16; 3], bool) {
+    mode.to_counts(value * lsb_per_unit)
+}
+
+/// rad/s to gyro counts with `lsb_per_dps`, and whether any axis saturated
+pub fn rad_s_to_counts(value: Vec3A, lsb_per_dps: f32, mode: RoundingMode) -> ([i16; 3], bool) {
+    mode.to_counts(value / PI_180 * lsb_per_dps)
+}
+
+/// °C to temperature counts, and whether they saturated
+pub fn celsius_to_counts(temp: f32, mode: RoundingMode) -> (i16, bool) {
+    mode.to_count((temp - TEMP_OFFSET) * TEMP_SENSITIVITY)
+}
+
+/// A 16 bit count narrowed to a `bits` wide two's complement value, 1 to 16 bits, and whether
+/// it saturated
+pub fn narrow(count: i16, bits: u32, mode: RoundingMode) -> (i16, bool) {
+    let max = (1i32 << (bits - 1)) - 1;
+    let min = -(1i32 << (bits - 1));
+    let reduced = mode.shift_right(count as i32, 16 - bits);
+    (
+        reduced.clamp(min, max) as i16,
+        reduced > max || reduced < min,
+    )
+}
+
+/// a `bits` wide value back to a 16 bit count, exact
+pub fn widen(value: i16, bits: u32) -> i16 {
+    value << (16 - bits)
+}
+
+/// Gyro offset register values with `offset` in rad/s added to `current`, in
+/// [`GYRO_OFFSET_DPS_PER_LSB`](crate::device::GYRO_OFFSET_DPS_PER_LSB), and the residue in
+/// rad/s left by rounding and saturation
+pub fn gyro_offset_counts(
+    current: [i16; 3],
+    offset: Vec3A,
+    mode: RoundingMode,
+) -> ([i16; 3], Vec3A) {
+    let counts = offset * (180. / core::f32::consts::PI) / GYRO_OFFSET_DPS_PER_LSB;
+    let counts = match mode {
+        // glam's, target dependent
+        RoundingMode::Legacy => counts.round(),
+        _ => Vec3A::new(
+            mode.round(counts.x),
+            mode.round(counts.y),
+            mode.round(counts.z),
+        ),
+    };
+    let mut offsets = current;
+    let mut residue = offset;
+    for axis in 0..3 {
+        let target = (f32::from(current[axis]) + counts[axis]).clamp(-32768., 32767.);
+        offsets[axis] = target as i16;
+        let added = target - f32::from(current[axis]);
+        residue[axis] -= added * GYRO_OFFSET_DPS_PER_LSB * (core::f32::consts::PI / 180.);
+    }
+    (offsets, residue)
+}
@@ -0,0 +1,249 @@
+//! Yield points in long blocking drains and calibrations, for single-threaded executors.
+//!
+//! Draining a full FIFO is over a thousand bytes of I2C traffic in one call, a calibration
+//! hundreds of samples. The cooperative variants split that work and call the caller's
+//! `yield_fn` between the pieces, where the executor can poll its other tasks, without going
+//! async. A [`DrainBudget`] sets how much bus work happens between two yields:
+//!
+//! | operation | [`Bytes(n)`](DrainBudget::Bytes) | [`Transactions(n)`](DrainBudget::Transactions) |
+//! |:---|:---|:---|
+//! | [`drain_fifo_cooperative`](Mpu6050::drain_fifo_cooperative) | one FIFO read of `n` bytes rounded down to whole frames, at least one frame | `n` transactions of one frame each, the FIFO count read included |
+//! | [`auto_setup_cooperative`](Mpu6050::auto_setup_cooperative) | after the sample or temperature reading reaching `n` bytes, as counted in the [`io_stats`](Mpu6050::io_stats) | after the reading reaching `n` transactions |
+//!
+//! `yield_fn` runs between two pieces only, never before the first or after the last, and
+//! gets no access to the driver: a yield cannot start other bus traffic on the sensor.
+//! The calibration yields after the delay following a reading, so the auto setup tolerates
+//! the extra time like a longer sample interval. The sampling loop needs no budget, its
+//! callback returns to the caller after every sample.
+//!
+//! #### Drains
+//! A cooperative drain reads the FIFO count once and drains the whole frames it found, as
+//! many as fit into `out`, packed from its start. Frames the chip writes meanwhile stay in
+//! the FIFO for the next call. [`DrainProgress::remaining`] are the bytes of the count not
+//! drained because `out` was full, the caller continues with another call next tick;
+//! ```
+//! use embedded_hal::blocking::i2c::{Write, WriteRead};
+//! use mpu6050::cooperative::DrainBudget;
+//! use mpu6050::fifo::FifoFrame;
+//! use mpu6050::{Mpu6050, Mpu6050Error};
+//!
+//! // called every tick of the executor, at most 64 bytes per yield
+//! fn tick<I, E>(
+//!     mpu: &mut Mpu6050<I>,
+//!     poll_others: &mut impl FnMut(),
+//!     mut on_frame: impl FnMut(FifoFrame),
+//! ) -> Result<bool, Mpu6050Error<E>>
+//! where
+//!     I: Write<Error = E> + WriteRead<Error = E>,
+//! {
+//!     let schema = mpu.fifo_schema();
+//!     let mut out = [0; 256];
+//!     let progress = mpu
+//!         .drain_fifo_cooperative(&schema, &mut out, DrainBudget::Bytes(64), &mut *poll_others)
+//!         .map_err(|e| e.error)?;
+//!     for frame in out[..progress.bytes].chunks_exact(schema.frame_len()) {
+//!         on_frame(mpu.parse_fifo_frame(&schema, frame)?);
+//!     }
+//!     // more to drain next tick
+//!     Ok(progress.remaining >= schema.frame_len())
+//! }
+//! ```
+//!
+//! #### Errors and alignment
+//! A failed transaction ends the drain with a [`FifoDrainError`]. Its
+//! [`progress`](FifoDrainError::progress) covers the reads before it: the frames in
+//! `out[..progress.bytes]` are complete and in order. The failed read may have taken bytes
+//! off the FIFO before the error, how many depends on the bus, so the frames of that read
+//! are lost and the next frame in the FIFO may be cut. No state is kept in the driver beyond
+//! the frames delivered on the [FIFO timeline](crate::fifo_recovery); the next drain, plain
+//! or cooperative, resumes.
+//!
+//! The chip writes whole frames, so a FIFO count that is not a multiple of the frame length
+//! means the oldest frame was cut by an earlier read. A cooperative drain discards the
+//! `count % frame_len` bytes of the cut frame before draining, reported in
+//! [`DrainProgress::discarded`], and the following frames are aligned again. After a
+//! [`FifoOverflow`](crate::metrics::MetricEvent::FifoOverflow) the chip overwrote the oldest
+//! bytes and the rule no longer holds: the drain resets the FIFO instead, as every drain
+//! does, see [`fifo_recovery`](crate::fifo_recovery). [`drain_fifo`](Mpu6050::drain_fifo)
+//! does not realign.
+
+use core::fmt::{self, Debug, Display};
+
+#[cfg(feature = "driver")]
+use embedded_hal::blocking::i2c::{Write, WriteRead};
+
+#[cfg(feature = "driver")]
+use crate::fifo::FifoSchema;
+use crate::fifo_recovery::FifoGap;
+use crate::op_bounds::IoStats;
+#[cfg(feature = "driver")]
+use crate::register::Register;
+#[cfg(feature = "driver")]
+use crate::Mpu6050;
+use crate::Mpu6050Error;
+
+/// Bus work between two yields, see the [module docs](self). 0 is taken as 1
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum DrainBudget {
+    /// bytes per yield
+    Bytes(u16),
+    /// transactions per yield
+    Transactions(u16),
+}
+
+/// How far a cooperative drain got
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub struct DrainProgress {
+    /// whole frames written to the start of `out`
+    pub frames: usize,
+    /// bytes of those frames
+    pub bytes: usize,
+    /// bytes of the FIFO count at the start not drained, whole frames
+    pub remaining: usize,
+    /// bytes of a cut frame discarded before draining
+    pub discarded: usize,
+    /// [`sequence`](crate::fifo::FifoFrame::sequence) of the first frame in `out`
+    pub first_sequence: u64,
+    /// frames lost to an overflow right before the first frame in `out`, see
+    /// [`fifo_recovery`](crate::fifo_recovery)
+    pub gap: Option<FifoGap>,
+}
+
+/// Error of [`Mpu6050::drain_fifo_cooperative`], with the frames drained before it
+#[derive(Debug)]
+pub struct FifoDrainError<E> {
+    /// frames in `out` before the error
+    pub progress: DrainProgress,
+    /// What went wrong
+    pub error: Mpu6050Error<E>,
+}
+
+impl<E: Display> Display for FifoDrainError<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "FIFO drain failed after {} frames: {}",
+            self.progress.frames, self.error
+        )
+    }
+}
+
+#[cfg(feature = "std")]
+impl<E: Debug + Display> std::error::Error for FifoDrainError<E> {}
+
+/// Calls the yield function of a cooperative calibration once its budget is spent
+pub(crate) struct Pacer<'y> {
+    budget: DrainBudget,
+    since: IoStats,
+    yield_fn: &'y mut dyn FnMut(),
+}
+
+impl<'y> Pacer<'y> {
+    pub(crate) fn new(budget: DrainBudget, now: IoStats, yield_fn: &'y mut dyn FnMut()) -> Self {
+        Self {
+            budget,
+            since: now,
+            yield_fn,
+        }
+    }
+
+    /// yields if the work since the last yield reached the budget
+    pub(crate) fn pace(&mut self, now: IoStats) {
+        let (spent, budget) = match self.budget {
+            DrainBudget::Bytes(n) => (now.bytes.wrapping_sub(self.since.bytes), n),
+            DrainBudget::Transactions(n) => {
+                (now.transactions.wrapping_sub(self.since.transactions), n)
+            }
+        };
+        if spent >= u32::from(budget.max(1)) {
+            (self.yield_fn)();
+            self.since = now;
+        }
+    }
+}
+
+#[cfg(feature = "driver")]
+impl<I, D, E> Mpu6050<I, D>
+where
+    I: Write<Error = E> + WriteRead<Error = E>,
+{
+    /// [`drain_fifo`](Self::drain_fifo) in pieces of `budget`, calling `yield_fn` between
+    /// them. Writes the whole frames to the start of `out` instead of passing them on,
+    /// restores the frame alignment after a cut read first. See the
+    /// [module docs](crate::cooperative) for the state left behind by an error
+    pub fn drain_fifo_cooperative(
+        &mut self,
+        schema: &FifoSchema,
+        out: &mut [u8],
+        budget: DrainBudget,
+        mut yield_fn: impl FnMut(),
+    ) -> Result<DrainProgress, FifoDrainError<E>> {
+        let mut progress = DrainProgress::default();
+        let fail = |progress, error| FifoDrainError { progress, error };
+        self.check_active().map_err(|e| fail(progress, e))?;
+        self.check_epoch(schema.generation)
+            .map_err(|e| fail(progress, e))?;
+        let len = schema.frame_len();
+        if len == 0 {
+            return Ok(progress);
+        }
+        if out.len() < len {
+            return Err(fail(progress, Mpu6050Error::BufferTooSmall(len)));
+        }
+        let count = self.fifo_count().map_err(|e| fail(progress, e))?;
+        let overflowed = self
+            .recover_fifo_overflow(count, len)
+            .map_err(|e| fail(progress, e))?;
+        progress.first_sequence = self.fifo_recovery_status().next_sequence;
+        if overflowed {
+            return Ok(progress);
+        }
+        let (frames_per_read, transactions) = match budget {
+            DrainBudget::Bytes(n) => ((n as usize / len).max(1), None),
+            DrainBudget::Transactions(n) => (1, Some(n.max(1))),
+        };
+        // the count read
+        let mut spent = 1;
+        let mut piece = |spent: &mut u16| match transactions {
+            Some(limit) if *spent < limit => {}
+            _ => {
+                yield_fn();
+                *spent = 0;
+            }
+        };
+
+        let mut count = count as usize;
+        let cut = count % len;
+        if cut > 0 {
+            if transactions.is_some() {
+                piece(&mut spent);
+            }
+            self.read_registers(Register::FIFO_R_W, &mut out[..cut])
+                .map_err(|e| fail(progress, e))?;
+            spent += 1;
+            progress.discarded = cut;
+            count -= cut;
+        }
+        let frames = (count / len).min(out.len() / len);
+        progress.remaining = count;
+        while progress.frames < frames {
+            if progress.frames > 0 || transactions.is_some() {
+                piece(&mut spent);
+            }
+            let n = frames_per_read.min(frames - progress.frames);
+            let bytes = &mut out[progress.bytes..progress.bytes + n * len];
+            self.read_registers(Register::FIFO_R_W, bytes)
+                .map_err(|e| fail(progress, e))?;
+            spent += 1;
+            self.note_fifo_drained(n);
+            let (_, gap) = self.fifo_timeline.deliver(n);
+            if progress.frames == 0 {
+                progress.gap = gap;
+            }
+            progress.frames += n;
+            progress.bytes += n * len;
+            progress.remaining -= n * len;
+        }
+        Ok(progress)
+    }
+}
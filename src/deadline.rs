@@ -0,0 +1,129 @@
+//! Caller supplied deadlines for long running blocking operations.
+//!
+//! Operations that wait or sample for a long time have a `*_with_deadline` variant taking
+//! `deadline: impl FnMut() -> bool`. It returns true once the operation should give up and is
+//! checked before every bus operation (a read, a write or the read-modify-write of a register
+//! field) and every delay of the waiting and sampling loops. A deadline that expires therefore
+//! overruns by at most one bus operation plus one delay quantum (the poll or sample interval of
+//! the loop, 20 ms for the self-test settling), plus the cleanup of the aborted phase where it
+//! has one. The aborted operation returns
+//! [`Mpu6050Error::Aborted`](crate::Mpu6050Error::Aborted) with the phase it was in and an
+//! [`AbortProgress`].
+//!
+//! The variants without deadline pass [`never`], so there is one implementation per
+//! operation.
+//!
+//! Operations with a deadline:
+//! * [`Mpu6050::auto_setup_with_deadline`](crate::Mpu6050::auto_setup_with_deadline), resumable
+//!   with [`Mpu6050::auto_setup_resume`](crate::Mpu6050::auto_setup_resume). The short fixed
+//!   register sequences of its `Init` and `ApplyConfiguration` phases are checked before they
+//!   start and then run to completion: `Init` is at most 20 transactions plus the 100 ms
+//!   wake delay, `ApplyConfiguration` 6 transactions. An aborted `ActuationCheck` clears the
+//!   self-test bits again, 6 more transactions
+//! * the calibrations [`calibrate_gyro_with_deadline`](crate::Mpu6050::calibrate_gyro_with_deadline),
+//!   [`calibrate_accel_with_deadline`](crate::Mpu6050::calibrate_accel_with_deadline),
+//!   [`calibrate_gyro_with_reference_with_deadline`](crate::Mpu6050::calibrate_gyro_with_reference_with_deadline)
+//!   and [`calibrate_accel_with_reference_with_deadline`](crate::Mpu6050::calibrate_accel_with_reference_with_deadline),
+//!   aborting in the `GyroCalibration` or `AccelCalibration` phase with the samples taken
+//!   and their mean. The delay quantum is the sample interval. The offsets are left as they
+//!   were; a calibration that has to survive a deadline runs inside `auto_setup`, whose
+//!   resume continues with the samples collected
+//! * [`characterize_self_heating_with_deadline`](crate::Mpu6050::characterize_self_heating_with_deadline),
+//!   aborting in [`self_heating::PHASE`](crate::self_heating::PHASE) with the points fitted
+//!   and the last bias. Waits between points are split into delays of at most 255 ms, the
+//!   quantum
+//! * [`wait_settled_with_deadline`](crate::Mpu6050::wait_settled_with_deadline), aborting in
+//!   [`settling::PHASE`](crate::settling::PHASE) after a whole number of sample periods, the
+//!   quantum, which are taken off the settling countdown: calling it again waits out the rest
+//! * [`collect_init_burst_with_deadline`](crate::Mpu6050::collect_init_burst_with_deadline),
+//!   aborting in [`orientation::INIT_BURST_PHASE`](crate::orientation::INIT_BURST_PHASE) with
+//!   the readings taken and their mean. A reading is one transaction, more while
+//!   [`SettlingPolicy::Discard`](crate::settling::SettlingPolicy::Discard) reads settling
+//!   samples again
+//! * [`verify_motion_detection_with_deadline`](crate::Mpu6050::verify_motion_detection_with_deadline),
+//!   aborting in [`motion_verify::PHASE`](crate::motion_verify::PHASE). The saved registers
+//!   are written back and INT_STATUS read after the abort, 6 more transactions, and the
+//!   engine status is left as it was
+//!
+//! Without a deadline variant: [`detect_clone_heuristics`](crate::Mpu6050::detect_clone_heuristics),
+//! whose self-test probes are a fixed sequence of about 130 ms, and
+//! [`run_script`](crate::Mpu6050::run_script), whose delays are the script's own.
+
+#[cfg(feature = "driver")]
+use embedded_hal::blocking::delay::DelayMs;
+use glam::Vec3A;
+
+#[cfg(feature = "driver")]
+use crate::setup::Accumulator;
+#[cfg(feature = "driver")]
+use crate::Mpu6050Error;
+
+/// Deadline that never expires, used by the variants without deadline
+pub fn never() -> bool {
+    false
+}
+
+/// How far an operation got before its deadline expired
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct AbortProgress {
+    /// time spent in delays by the operation, in ms
+    pub elapsed_ms: u32,
+    /// bus transactions performed by the operation
+    pub transactions: u32,
+    /// samples collected in the aborted phase, 0 outside sampling phases
+    pub samples: u32,
+    /// mean of those samples, in the units of the phase. None without samples
+    pub partial_mean: Option<Vec3A>,
+}
+
+/// Deadline of a standalone operation, with the time it spent in delays
+#[cfg(feature = "driver")]
+pub(crate) struct Watch<'d> {
+    phase: &'static str,
+    deadline: &'d mut dyn FnMut() -> bool,
+    /// operation counter at the start of the operation
+    start_op: u32,
+    elapsed_ms: u32,
+}
+
+#[cfg(feature = "driver")]
+impl<'d> Watch<'d> {
+    pub(crate) fn new(
+        phase: &'static str,
+        deadline: &'d mut dyn FnMut() -> bool,
+        start_op: u32,
+    ) -> Self {
+        Self {
+            phase,
+            deadline,
+            start_op,
+            elapsed_ms: 0,
+        }
+    }
+
+    /// Aborted error if the deadline expired, `partial` are the samples collected so far
+    pub(crate) fn check<E>(
+        &mut self,
+        op: u32,
+        partial: Option<&Accumulator>,
+    ) -> Result<(), Mpu6050Error<E>> {
+        if !(self.deadline)() {
+            return Ok(());
+        }
+        let partial = partial.filter(|acc| acc.count() > 0);
+        Err(Mpu6050Error::Aborted {
+            phase: self.phase,
+            progress: AbortProgress {
+                elapsed_ms: self.elapsed_ms,
+                transactions: op.wrapping_sub(self.start_op),
+                samples: partial.map_or(0, Accumulator::count),
+                partial_mean: partial.map(Accumulator::mean),
+            },
+        })
+    }
+
+    pub(crate) fn wait(&mut self, delay: &mut impl DelayMs<u8>, ms: u8) {
+        delay.delay_ms(ms);
+        self.elapsed_ms += u32::from(ms);
+    }
+}
@@ -0,0 +1,151 @@
+//! Fixed point formatting of floats, without core's float formatting.
+//!
+//! Core's `Display` and `Debug` of `f32` pull in its shortest round trip and exact decimal
+//! machinery, several KB of code, wherever a float is formatted. [`Decimal`] writes a float
+//! with integer arithmetic into a stack buffer of [`BUF_LEN`] bytes instead; every `Display`
+//! impl of the crate and the text encoders format their floats through it:
+//!
+//! | format | output |
+//! |:---|:---|
+//! | `{:.N}`, N ≤ [`MAX_PLACES`] | the same text as core, ties to even on the exact value |
+//! | `{:.N}`, N > [`MAX_PLACES`] | [`MAX_PLACES`] places |
+//! | `{}` | [`DEFAULT_PLACES`] places with trailing zeros dropped: core's shortest text where that is within half the last place of the value, as for values of at most that many decimals below 8 in magnitude, else the closer digits |
+//! | `{:+}`, width, fill, alignment, `0` | as for integers |
+//! | NaN, infinities | `NaN`, `inf`, `-inf`, without padding |
+//!
+//! Magnitudes of 2^24 and above are integers in f32 and written with all their digits, where
+//! core's `{}` writes the shortest digits and pads them with zeros.
+//!
+//! Where all digits matter the crate keeps core's formatting: the
+//! [`DriverStateSnapshot`](crate::snapshot::DriverStateSnapshot) dump and the measurements
+//! of the `hil` report's JSON, both std only.
+//! ```
+//! use mpu6050::decimal::{Decimal, DecimalVec};
+//! use mpu6050::Vec3A;
+//!
+//! assert_eq!(format!("{:.2} Hz", Decimal(99.5)), "99.50 Hz");
+//! assert_eq!(format!("{:+.0} ppm", Decimal(12.5)), "+12 ppm");
+//! assert_eq!(format!("{}", Decimal(0.1)), "0.1");
+//! assert_eq!(format!("{}", DecimalVec(Vec3A::new(0.5, -1., 0.))), "[0.5, -1, 0]");
+//! ```
+
+use core::fmt;
+
+use glam::Vec3A;
+
+/// Most decimal places written
+pub const MAX_PLACES: usize = 9;
+
+/// Places of `{}` without a precision, before dropping trailing zeros
+pub const DEFAULT_PLACES: usize = 6;
+
+/// Bytes of the stack buffer: the 39 digits of the largest f32, the point and the places
+pub const BUF_LEN: usize = 39 + 1 + MAX_PLACES;
+
+const POW10: [u64; MAX_PLACES + 1] = [
+    1,
+    10,
+    100,
+    1_000,
+    10_000,
+    100_000,
+    1_000_000,
+    10_000_000,
+    100_000_000,
+    1_000_000_000,
+];
+
+/// An `f32` formatted in fixed point, see the [module docs](self)
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Decimal(pub f32);
+
+/// A vector formatted as `[x, y, z]` with [`Decimal`] components, the format spec applies to
+/// each
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct DecimalVec(pub Vec3A);
+
+impl fmt::Display for Decimal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let value = self.0;
+        if value.is_nan() {
+            return f.write_str("NaN");
+        }
+        if value.is_infinite() {
+            return f.write_str(if value < 0. { "-inf" } else { "inf" });
+        }
+        let mut buf = [0; BUF_LEN];
+        let len = render(value, f.precision(), &mut buf);
+        // only ASCII digits and the point
+        let digits = core::str::from_utf8(&buf[..len]).map_err(|_| fmt::Error)?;
+        f.pad_integral(!value.is_sign_negative(), "", digits)
+    }
+}
+
+impl fmt::Display for DecimalVec {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("[")?;
+        fmt::Display::fmt(&Decimal(self.0.x), f)?;
+        f.write_str(", ")?;
+        fmt::Display::fmt(&Decimal(self.0.y), f)?;
+        f.write_str(", ")?;
+        fmt::Display::fmt(&Decimal(self.0.z), f)?;
+        f.write_str("]")
+    }
+}
+
+/// writes the magnitude of the finite `value` to `buf`, returns the length
+fn render(value: f32, precision: Option<usize>, buf: &mut [u8; BUF_LEN]) -> usize {
+    let (places, trim) = match precision {
+        Some(places) => (places.min(MAX_PLACES), false),
+        None => (DEFAULT_PLACES, true),
+    };
+    let magnitude = f64::from(value).abs();
+    let (int, mut frac) = if magnitude >= 16_777_216. {
+        // integral, and beyond what the scaled value below holds
+        (magnitude as u128, 0)
+    } else {
+        // exact: 24 bits of mantissa times 5^9 fit into the 53 of f64
+        let scaled = magnitude * POW10[places] as f64;
+        let floor = scaled as u64;
+        let rest = scaled - floor as f64;
+        let rounded = match rest > 0.5 || (rest == 0.5 && floor & 1 == 1) {
+            true => floor + 1,
+            false => floor,
+        };
+        (u128::from(rounded / POW10[places]), rounded % POW10[places])
+    };
+
+    let mut len = 0;
+    let mut int_digits = [0; 39];
+    let mut n = 0;
+    let mut rest = int;
+    loop {
+        int_digits[n] = b'0' + (rest % 10) as u8;
+        n += 1;
+        rest /= 10;
+        if rest == 0 {
+            break;
+        }
+    }
+    for digit in int_digits[..n].iter().rev() {
+        buf[len] = *digit;
+        len += 1;
+    }
+
+    let mut places = places;
+    if trim {
+        while places > 0 && frac % 10 == 0 {
+            frac /= 10;
+            places -= 1;
+        }
+    }
+    if places > 0 {
+        buf[len] = b'.';
+        len += 1;
+        for place in (0..places).rev() {
+            buf[len] = b'0' + (frac / POW10[place] % 10) as u8;
+            len += 1;
+        }
+    }
+    len
+}
@@ -0,0 +1,782 @@
+//! Delay owned by the driver, instead of a `&mut impl DelayMs<u8>` argument per call.
+//!
+//! A driver is `Mpu6050<I, D>` with `D` defaulting to [`NoDelay`], so `Mpu6050<I>` is the
+//! driver without a delay and every existing use keeps working: operations waiting for the
+//! sensor take the delay as an argument.
+//!
+//! [`Mpu6050Builder::delay`](crate::Mpu6050Builder::delay) moves a delay into the driver.
+//! The same operations then drop their delay argument:
+//!
+//! | operation | waits |
+//! |:---|:---|
+//! | [`init`](Mpu6050::init) | 100 ms after waking |
+//! | [`reset_device`](Mpu6050::reset_device) | 100 ms after the reset |
+//! | [`try_reconnect`](Mpu6050::try_reconnect) | 100 ms after waking, if the same chip answers |
+//! | [`auto_setup`](Mpu6050::auto_setup) and its variants, [`auto_setup_cooperative`](Mpu6050::auto_setup_cooperative) | as configured in the options |
+//! | [`calibrate_gyro_with_reference`](Mpu6050::calibrate_gyro_with_reference), [`calibrate_accel_with_reference`](Mpu6050::calibrate_accel_with_reference) | the sample interval per sample |
+//! | [`calibrate_gyro`](Mpu6050::calibrate_gyro), [`calibrate_accel`](Mpu6050::calibrate_accel) | 2 ms per sample |
+//! | [`characterize_self_heating`](Mpu6050::characterize_self_heating) and its deadline variant | the interval between points, [`WINDOW_SAMPLE_INTERVAL_MS`](crate::self_heating::WINDOW_SAMPLE_INTERVAL_MS) within a point |
+//! | [`verify_motion_detection`](Mpu6050::verify_motion_detection) | [`MOTION_SETTLE_MS`](crate::motion_verify::MOTION_SETTLE_MS), then [`MOTION_POLL_INTERVAL_MS`](crate::motion_verify::MOTION_POLL_INTERVAL_MS) per poll |
+//! | [`run_script`](Mpu6050::run_script) | the script's delays |
+//! | [`collect_init_burst`](Mpu6050::collect_init_burst) | [`INIT_BURST_INTERVAL_MS`](crate::orientation::INIT_BURST_INTERVAL_MS) between readings |
+//! | [`detect_clone_heuristics`](Mpu6050::detect_clone_heuristics) | [`SELF_TEST_SETTLE_MS`](crate::clone_detect::SELF_TEST_SETTLE_MS) per self-test switch, [`SELF_TEST_INTERVAL_MS`](crate::clone_detect::SELF_TEST_INTERVAL_MS) between readings |
+//! | [`shutdown`](Mpu6050::shutdown), [`shutdown_best_effort`](Mpu6050::shutdown_best_effort) | [`SHUTDOWN_SETTLE_MS`](crate::shutdown::SHUTDOWN_SETTLE_MS) before the FIFO reset |
+//! | [`exit_low_power_mode`](Mpu6050::exit_low_power_mode) | 100 ms for the gyros to start |
+//! | `serve`, with the `remote` feature | the commands' waits, see `remote` |
+//!
+//! [`wait_settled`](Mpu6050::wait_settled) is available in both flavours: it waits out the
+//! settling samples of the last range, filter or power change with the owned delay, and
+//! fails with [`Mpu6050Error::DelayRequired`] without one.
+//! ```
+//! # use embedded_hal::blocking::delay::DelayMs;
+//! # use embedded_hal::blocking::i2c::{Write, WriteRead};
+//! use mpu6050::{Mpu6050, Mpu6050Builder, Mpu6050Error};
+//!
+//! // generic application code carries the driver alone
+//! fn bring_up<I, D, E>(mpu: &mut Mpu6050<I, D>) -> Result<(), Mpu6050Error<E>>
+//! where
+//!     I: Write<Error = E> + WriteRead<Error = E>,
+//!     D: DelayMs<u8>,
+//! {
+//!     mpu.init()?;
+//!     mpu.wait_settled()?;
+//!     Ok(())
+//! }
+//! ```
+
+#[cfg(feature = "driver")]
+use embedded_hal::blocking::{
+    delay::DelayMs,
+    i2c::{Write, WriteRead},
+};
+#[cfg(feature = "driver")]
+use glam::Vec3A;
+
+#[cfg(feature = "driver")]
+use crate::connection::ReconnectOutcome;
+#[cfg(feature = "driver")]
+use crate::cooperative::DrainBudget;
+#[cfg(feature = "driver")]
+use crate::deadline::{self, AbortProgress};
+#[cfg(feature = "driver")]
+use crate::device::CloneAssessment;
+#[cfg(not(feature = "std"))]
+use crate::math::FloatExt;
+#[cfg(feature = "driver")]
+use crate::motion_verify::MotionVerifyOutcome;
+#[cfg(feature = "driver")]
+use crate::orientation::InitBurst;
+#[cfg(feature = "driver")]
+use crate::platform::{CalibrationResult, ReferencedCalibration};
+#[cfg(feature = "remote")]
+use crate::remote::{RemoteLink, ServeBudget, ServeStats, Transport};
+#[cfg(feature = "driver")]
+use crate::script::{RegisterScript, ScriptError, ScriptReport};
+#[cfg(feature = "driver")]
+use crate::self_heating::{SelfHeatingPoint, SelfHeatingSummary};
+#[cfg(feature = "driver")]
+use crate::settling::{self, SettleCountdown};
+#[cfg(feature = "driver")]
+use crate::setup::{
+    AutoSetupError, AutoSetupFailure, AutoSetupOptions, AutoSetupPhase, AutoSetupReport,
+};
+#[cfg(feature = "driver")]
+use crate::shutdown::ShutdownEvidence;
+use crate::Mpu6050;
+#[cfg(feature = "driver")]
+use crate::Mpu6050Error;
+
+/// No delay owned, the default of [`Mpu6050`]. Deliberately not a `DelayMs`
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub struct NoDelay;
+
+/// Delay slot of a driver: [`NoDelay`] or any `DelayMs<u8>`
+pub trait OwnedDelay {
+    /// waits `ms`, false without a delay to wait with
+    fn wait_ms(&mut self, ms: u8) -> bool;
+}
+
+impl OwnedDelay for NoDelay {
+    fn wait_ms(&mut self, _ms: u8) -> bool {
+        false
+    }
+}
+
+#[cfg(feature = "driver")]
+impl<T: DelayMs<u8>> OwnedDelay for T {
+    fn wait_ms(&mut self, ms: u8) -> bool {
+        self.delay_ms(ms);
+        true
+    }
+}
+
+impl<I, D> Mpu6050<I, D> {
+    /// true if the driver owns a delay
+    pub fn has_delay(&self) -> bool {
+        self.delay.is_some()
+    }
+
+    /// the owned delay, None for [`NoDelay`]
+    pub fn delay_mut(&mut self) -> Option<&mut D> {
+        self.delay.as_mut()
+    }
+}
+
+#[cfg(feature = "driver")]
+impl<I, D, E> Mpu6050<I, D>
+where
+    I: Write<Error = E> + WriteRead<Error = E>,
+    D: OwnedDelay,
+{
+    /// Waits until the settling samples counted down by the [`SettlingPolicy`](crate::settling::SettlingPolicy)
+    /// have passed at the current output data rate, and clears the countdown. Returns the
+    /// time waited in ms, 0 without settling samples pending. Fails with
+    /// [`Mpu6050Error::DelayRequired`] if samples are pending and the driver owns no delay
+    pub fn wait_settled(&mut self) -> Result<u32, Mpu6050Error<E>> {
+        self.wait_settled_with_deadline(deadline::never)
+    }
+
+    /// [`wait_settled`](Self::wait_settled) giving up once `deadline` returns true, checked
+    /// before every sample period waited, see [`deadline`](crate::deadline). The samples
+    /// waited out before the abort are taken off the countdown, call again to wait out the
+    /// rest
+    pub fn wait_settled_with_deadline(
+        &mut self,
+        mut deadline: impl FnMut() -> bool,
+    ) -> Result<u32, Mpu6050Error<E>> {
+        let samples = self.settle.acc.max(self.settle.gyro);
+        if samples == 0 {
+            return Ok(0);
+        }
+        let period_ms = (1000. / self.check_aliasing().odr_hz).ceil() as u32;
+        let total_ms = u32::from(samples) * period_ms;
+        let mut waited_ms = 0;
+        while waited_ms < total_ms {
+            if deadline() {
+                let settled = (waited_ms / period_ms) as u8;
+                self.settle.acc = self.settle.acc.saturating_sub(settled);
+                self.settle.gyro = self.settle.gyro.saturating_sub(settled);
+                return Err(Mpu6050Error::Aborted {
+                    phase: settling::PHASE,
+                    progress: AbortProgress {
+                        elapsed_ms: waited_ms,
+                        samples: u32::from(settled),
+                        ..AbortProgress::default()
+                    },
+                });
+            }
+            let ms = (total_ms - waited_ms).min(period_ms).min(u8::MAX as u32) as u8;
+            let waited = self.delay.as_mut().is_some_and(|delay| delay.wait_ms(ms));
+            if !waited {
+                return Err(Mpu6050Error::DelayRequired);
+            }
+            waited_ms += u32::from(ms);
+        }
+        self.settle = SettleCountdown::default();
+        Ok(total_ms)
+    }
+}
+
+/// Explicit delay, the driver owns none
+#[cfg(feature = "driver")]
+impl<I, E> Mpu6050<I>
+where
+    I: Write<Error = E> + WriteRead<Error = E>,
+{
+    /// Init wakes MPU6050 and verifies register addr, e.g. in i2c
+    pub fn init<D: DelayMs<u8>>(&mut self, delay: &mut D) -> Result<(), Mpu6050Error<E>> {
+        self.init_using(delay)
+    }
+
+    /// Probes WHO_AM_I and, if the same chip answers, wakes it and re-applies the driver's
+    /// configuration, see [`connection`](crate::connection). Offsets are kept in the driver and
+    /// therefore survive the reconnect.
+    /// Bus transactions are attempted regardless of auto mode while reconnecting; on error the
+    /// state returns to `Disconnected`
+    pub fn try_reconnect<D: DelayMs<u8>>(
+        &mut self,
+        delay: &mut D,
+    ) -> Result<ReconnectOutcome, Mpu6050Error<E>> {
+        self.try_reconnect_using(delay)
+    }
+
+    /// reset device, all registers return to [`RESET_VALUES`](crate::device::RESET_VALUES),
+    /// the cached ranges follow
+    pub fn reset_device<D: DelayMs<u8>>(&mut self, delay: &mut D) -> Result<(), Mpu6050Error<E>> {
+        self.reset_device_using(delay)
+    }
+
+    /// Runs the complete startup sequence, see [`AutoSetupPhase`] for the phases and the module
+    /// docs of [`setup`](crate::setup) for the device state left behind on failure.
+    ///
+    /// `progress` is invoked at the start of every phase, including skipped ones.
+    #[allow(clippy::result_large_err)]
+    pub fn auto_setup<D: DelayMs<u8>>(
+        &mut self,
+        delay: &mut D,
+        options: AutoSetupOptions,
+        progress: Option<&mut dyn FnMut(AutoSetupPhase)>,
+    ) -> Result<AutoSetupReport, AutoSetupError<E>> {
+        self.auto_setup_using(delay, options, progress)
+    }
+
+    /// [`auto_setup`](Self::auto_setup) giving up once `deadline` returns true, see
+    /// [`deadline`](crate::deadline) for how often it is checked. An expired deadline fails
+    /// the phase with [`PhaseStatus::Aborted`](crate::setup::PhaseStatus::Aborted) and
+    /// [`Mpu6050Error::Aborted`], continue with [`auto_setup_resume`](Self::auto_setup_resume)
+    #[allow(clippy::result_large_err)]
+    pub fn auto_setup_with_deadline<D: DelayMs<u8>>(
+        &mut self,
+        delay: &mut D,
+        options: AutoSetupOptions,
+        progress: Option<&mut dyn FnMut(AutoSetupPhase)>,
+        deadline: impl FnMut() -> bool,
+    ) -> Result<AutoSetupReport, AutoSetupError<E>> {
+        self.auto_setup_with_deadline_using(delay, options, progress, deadline)
+    }
+
+    /// Continues a setup that failed or was aborted with the report of its error. Phases
+    /// that completed, timed out or were skipped are not run again, an aborted calibration
+    /// continues with the samples it had collected. The options must be the ones of the
+    /// first run
+    #[allow(clippy::result_large_err)]
+    pub fn auto_setup_resume<D: DelayMs<u8>>(
+        &mut self,
+        delay: &mut D,
+        options: AutoSetupOptions,
+        previous: &AutoSetupReport,
+        progress: Option<&mut dyn FnMut(AutoSetupPhase)>,
+        deadline: impl FnMut() -> bool,
+    ) -> Result<AutoSetupReport, AutoSetupError<E>> {
+        self.auto_setup_resume_using(delay, options, previous, progress, deadline)
+    }
+
+    /// [`auto_setup`](Self::auto_setup) calling `yield_fn` between its readings whenever
+    /// `budget` is spent, see [`cooperative`](crate::cooperative)
+    #[allow(clippy::result_large_err)]
+    pub fn auto_setup_cooperative<D: DelayMs<u8>>(
+        &mut self,
+        delay: &mut D,
+        options: AutoSetupOptions,
+        progress: Option<&mut dyn FnMut(AutoSetupPhase)>,
+        budget: DrainBudget,
+        yield_fn: impl FnMut(),
+    ) -> Result<AutoSetupReport, AutoSetupError<E>> {
+        self.auto_setup_cooperative_using(delay, options, progress, budget, yield_fn)
+    }
+
+    /// Gyro calibration on a moving platform. `reference` returns the angular velocity of
+    /// the platform in rad/s in the sensor frame, at the time of the sample just taken.
+    /// Applies and returns the offset, see [`platform`](crate::platform)
+    pub fn calibrate_gyro_with_reference<D: DelayMs<u8>>(
+        &mut self,
+        delay: &mut D,
+        reference: impl FnMut() -> Vec3A,
+        options: ReferencedCalibration,
+    ) -> Result<CalibrationResult, AutoSetupFailure<E>> {
+        let mut never = deadline::never;
+        self.calibrate_gyro_with_reference_using(delay, reference, options, &mut never)
+    }
+
+    /// [`calibrate_gyro_with_reference`](Self::calibrate_gyro_with_reference) giving up once
+    /// `deadline` returns true, see [`deadline`](crate::deadline). An aborted calibration
+    /// leaves the offset as it was
+    pub fn calibrate_gyro_with_reference_with_deadline<D: DelayMs<u8>>(
+        &mut self,
+        delay: &mut D,
+        reference: impl FnMut() -> Vec3A,
+        options: ReferencedCalibration,
+        mut deadline: impl FnMut() -> bool,
+    ) -> Result<CalibrationResult, AutoSetupFailure<E>> {
+        self.calibrate_gyro_with_reference_using(delay, reference, options, &mut deadline)
+    }
+
+    /// Accel calibration in any attitude or on a moving platform. `reference` returns the
+    /// specific force in g in the sensor frame, at the time of the sample just taken: the
+    /// reaction to gravity (see [`synthetic::at_rest`](crate::synthetic::at_rest)) minus the
+    /// platform acceleration. Applies and returns the offset, see [`platform`](crate::platform)
+    pub fn calibrate_accel_with_reference<D: DelayMs<u8>>(
+        &mut self,
+        delay: &mut D,
+        reference: impl FnMut() -> Vec3A,
+        options: ReferencedCalibration,
+    ) -> Result<CalibrationResult, AutoSetupFailure<E>> {
+        let mut never = deadline::never;
+        self.calibrate_accel_with_reference_using(delay, reference, options, &mut never)
+    }
+
+    /// [`calibrate_accel_with_reference`](Self::calibrate_accel_with_reference) giving up
+    /// once `deadline` returns true, see [`deadline`](crate::deadline). An aborted
+    /// calibration leaves the offset as it was
+    pub fn calibrate_accel_with_reference_with_deadline<D: DelayMs<u8>>(
+        &mut self,
+        delay: &mut D,
+        reference: impl FnMut() -> Vec3A,
+        options: ReferencedCalibration,
+        mut deadline: impl FnMut() -> bool,
+    ) -> Result<CalibrationResult, AutoSetupFailure<E>> {
+        self.calibrate_accel_with_reference_using(delay, reference, options, &mut deadline)
+    }
+
+    /// Gyro calibration at rest: averages `samples` readings, applies and returns the
+    /// offset. Fails without touching the offsets if the device moved, see
+    /// [`calibration`](crate::calibration)
+    pub fn calibrate_gyro<D: DelayMs<u8>>(
+        &mut self,
+        delay: &mut D,
+        samples: u16,
+    ) -> Result<CalibrationResult, AutoSetupFailure<E>> {
+        self.calibrate_gyro_using(delay, samples, &mut |_| {}, &mut deadline::never)
+    }
+
+    /// [`calibrate_gyro`](Self::calibrate_gyro) giving up once `deadline` returns true, see
+    /// [`deadline`](crate::deadline). An aborted calibration leaves the offset as it was
+    pub fn calibrate_gyro_with_deadline<D: DelayMs<u8>>(
+        &mut self,
+        delay: &mut D,
+        samples: u16,
+        mut deadline: impl FnMut() -> bool,
+    ) -> Result<CalibrationResult, AutoSetupFailure<E>> {
+        self.calibrate_gyro_using(delay, samples, &mut |_| {}, &mut deadline)
+    }
+
+    /// Accel calibration lying level, Z up: averages `samples` readings less 1 g on Z,
+    /// applies and returns the offset. Fails without touching the offsets if the device
+    /// moved, see [`calibration`](crate::calibration)
+    pub fn calibrate_accel<D: DelayMs<u8>>(
+        &mut self,
+        delay: &mut D,
+        samples: u16,
+    ) -> Result<CalibrationResult, AutoSetupFailure<E>> {
+        self.calibrate_accel_using(delay, samples, &mut |_| {}, &mut deadline::never)
+    }
+
+    /// [`calibrate_accel`](Self::calibrate_accel) giving up once `deadline` returns true, see
+    /// [`deadline`](crate::deadline). An aborted calibration leaves the offset as it was
+    pub fn calibrate_accel_with_deadline<D: DelayMs<u8>>(
+        &mut self,
+        delay: &mut D,
+        samples: u16,
+        mut deadline: impl FnMut() -> bool,
+    ) -> Result<CalibrationResult, AutoSetupFailure<E>> {
+        self.calibrate_accel_using(delay, samples, &mut |_| {}, &mut deadline)
+    }
+
+    /// Records the gyro bias while the die self-heats from a cold boot, one point every
+    /// `interval_ms` for `duration_s`, each streamed to `sink`, and fits the settling curve
+    /// of every axis. Aborts with [`Mpu6050Error::Aborted`] if the device moves, see
+    /// [`self_heating`](crate::self_heating)
+    pub fn characterize_self_heating<D: DelayMs<u8>>(
+        &mut self,
+        delay: &mut D,
+        duration_s: u16,
+        interval_ms: u16,
+        sink: &mut impl FnMut(SelfHeatingPoint),
+    ) -> Result<SelfHeatingSummary, Mpu6050Error<E>> {
+        let mut never = deadline::never;
+        self.characterize_self_heating_using(delay, duration_s, interval_ms, sink, &mut never)
+    }
+
+    /// [`characterize_self_heating`](Self::characterize_self_heating) giving up once
+    /// `deadline` returns true, see [`deadline`](crate::deadline)
+    pub fn characterize_self_heating_with_deadline<D: DelayMs<u8>>(
+        &mut self,
+        delay: &mut D,
+        duration_s: u16,
+        interval_ms: u16,
+        sink: &mut impl FnMut(SelfHeatingPoint),
+        mut deadline: impl FnMut() -> bool,
+    ) -> Result<SelfHeatingSummary, Mpu6050Error<E>> {
+        self.characterize_self_heating_using(delay, duration_s, interval_ms, sink, &mut deadline)
+    }
+
+    /// Runs the motion detection engine once on the operator moving the device after
+    /// `prompt`, or without one on the accel self-test, and records the result in the
+    /// capabilities. Restores every register it writes, see
+    /// [`motion_verify`](crate::motion_verify)
+    pub fn verify_motion_detection<D: DelayMs<u8>>(
+        &mut self,
+        delay: &mut D,
+        prompt: Option<&mut dyn FnMut()>,
+    ) -> Result<MotionVerifyOutcome, Mpu6050Error<E>> {
+        self.verify_motion_detection_using(delay, prompt, &mut deadline::never)
+    }
+
+    /// [`verify_motion_detection`](Self::verify_motion_detection) giving up once `deadline`
+    /// returns true, see [`deadline`](crate::deadline). The saved registers are restored
+    /// after an abort too, and the engine status is left as it was
+    pub fn verify_motion_detection_with_deadline<D: DelayMs<u8>>(
+        &mut self,
+        delay: &mut D,
+        prompt: Option<&mut dyn FnMut()>,
+        mut deadline: impl FnMut() -> bool,
+    ) -> Result<MotionVerifyOutcome, Mpu6050Error<E>> {
+        self.verify_motion_detection_using(delay, prompt, &mut deadline)
+    }
+
+    /// Validates and runs `script`, see the [`script`](crate::script) module. Expectations
+    /// failing with [`OnFail::Continue`](crate::script::OnFail::Continue) show in the report
+    pub fn run_script<D: DelayMs<u8>>(
+        &mut self,
+        script: &RegisterScript,
+        delay: &mut D,
+    ) -> Result<ScriptReport, ScriptError<E>> {
+        self.run_script_using(script, delay)
+    }
+
+    /// Reads `n` scaled accelerometer readings, one every
+    /// [`INIT_BURST_INTERVAL_MS`](crate::orientation::INIT_BURST_INTERVAL_MS), into an
+    /// [`InitBurst`] for [`ComplementaryFilter::initialize_from_burst`](crate::orientation::ComplementaryFilter::initialize_from_burst).
+    /// Take it with the device at rest, see the [`orientation`](crate::orientation#first-fix)
+    /// module docs
+    pub fn collect_init_burst<D: DelayMs<u8>>(
+        &mut self,
+        delay: &mut D,
+        n: u16,
+    ) -> Result<InitBurst, Mpu6050Error<E>> {
+        self.collect_init_burst_using(delay, n, &mut deadline::never)
+    }
+
+    /// [`collect_init_burst`](Self::collect_init_burst) giving up once `deadline` returns
+    /// true, see [`deadline`](crate::deadline)
+    pub fn collect_init_burst_with_deadline<D: DelayMs<u8>>(
+        &mut self,
+        delay: &mut D,
+        n: u16,
+        mut deadline: impl FnMut() -> bool,
+    ) -> Result<InitBurst, Mpu6050Error<E>> {
+        self.collect_init_burst_using(delay, n, &mut deadline)
+    }
+
+    /// Assesses the chip with documented probes only, records the assessment in the
+    /// [`capabilities`](Mpu6050::capabilities) and returns it, see
+    /// [`clone_detect`](crate::clone_detect). Bus errors make it inconclusive
+    pub fn detect_clone_heuristics<D: DelayMs<u8>>(&mut self, delay: &mut D) -> CloneAssessment {
+        self.detect_clone_heuristics_using(delay)
+    }
+
+    /// Disables interrupts, FIFO, aux master and bypass, puts every axis in standby and the
+    /// chip to sleep, then reads every touched register back, see
+    /// [`shutdown`](crate::shutdown). Fails at the first bus error
+    pub fn shutdown<D: DelayMs<u8>>(
+        &mut self,
+        delay: &mut D,
+    ) -> Result<ShutdownEvidence, Mpu6050Error<E>> {
+        match self.shutdown_using(delay, true) {
+            (_, Some(e)) => Err(e),
+            (evidence, None) => Ok(evidence),
+        }
+    }
+
+    /// [`shutdown`](Mpu6050::shutdown) carrying on past bus errors, which show in the
+    /// evidence
+    pub fn shutdown_best_effort<D: DelayMs<u8>>(&mut self, delay: &mut D) -> ShutdownEvidence {
+        self.shutdown_using(delay, false).0
+    }
+
+    /// Leaves the low power accelerometer mode and waits for the gyros, see
+    /// [`low_power`](crate::low_power)
+    pub fn exit_low_power_mode<D: DelayMs<u8>>(
+        &mut self,
+        delay: &mut D,
+    ) -> Result<(), Mpu6050Error<E>> {
+        self.exit_low_power_mode_using(delay)
+    }
+
+    /// Handles the commands received on `link`, at most `budget.commands`, and returns once
+    /// the transport has no more bytes, see [`remote`](crate::remote). Fails with an error
+    /// of the transport only, driver errors are sent to the client
+    #[cfg(feature = "remote")]
+    pub fn serve<D: DelayMs<u8>, T: Transport>(
+        &mut self,
+        delay: &mut D,
+        link: &mut RemoteLink<T>,
+        budget: ServeBudget,
+    ) -> Result<ServeStats, T::Error> {
+        self.serve_using(delay, link, budget)
+    }
+}
+
+/// Owned delay, see the [module docs](self)
+#[cfg(feature = "driver")]
+impl<I, D, E> Mpu6050<I, D>
+where
+    I: Write<Error = E> + WriteRead<Error = E>,
+    D: DelayMs<u8>,
+{
+    /// runs `f` with the owned delay moved out of the driver
+    fn with_owned_delay<R>(&mut self, f: impl FnOnce(&mut Self, &mut D) -> R) -> R {
+        // only a builder with a delay gives a `DelayMs` driver, and the delay is back before
+        // any other owned-delay call can start
+        let mut delay = self.delay.take().expect("owned delay in use");
+        let res = f(self, &mut delay);
+        self.delay = Some(delay);
+        res
+    }
+
+    /// [`init`](Mpu6050::init) with the owned delay
+    pub fn init(&mut self) -> Result<(), Mpu6050Error<E>> {
+        self.with_owned_delay(|mpu, delay| mpu.init_using(delay))
+    }
+
+    /// [`try_reconnect`](Mpu6050::try_reconnect) with the owned delay
+    pub fn try_reconnect(&mut self) -> Result<ReconnectOutcome, Mpu6050Error<E>> {
+        self.with_owned_delay(|mpu, delay| mpu.try_reconnect_using(delay))
+    }
+
+    /// [`reset_device`](Mpu6050::reset_device) with the owned delay
+    pub fn reset_device(&mut self) -> Result<(), Mpu6050Error<E>> {
+        self.with_owned_delay(|mpu, delay| mpu.reset_device_using(delay))
+    }
+
+    /// [`auto_setup`](Mpu6050::auto_setup) with the owned delay
+    #[allow(clippy::result_large_err)]
+    pub fn auto_setup(
+        &mut self,
+        options: AutoSetupOptions,
+        progress: Option<&mut dyn FnMut(AutoSetupPhase)>,
+    ) -> Result<AutoSetupReport, AutoSetupError<E>> {
+        self.with_owned_delay(|mpu, delay| mpu.auto_setup_using(delay, options, progress))
+    }
+
+    /// [`auto_setup_with_deadline`](Mpu6050::auto_setup_with_deadline) with the owned delay
+    #[allow(clippy::result_large_err)]
+    pub fn auto_setup_with_deadline(
+        &mut self,
+        options: AutoSetupOptions,
+        progress: Option<&mut dyn FnMut(AutoSetupPhase)>,
+        deadline: impl FnMut() -> bool,
+    ) -> Result<AutoSetupReport, AutoSetupError<E>> {
+        self.with_owned_delay(|mpu, delay| {
+            mpu.auto_setup_with_deadline_using(delay, options, progress, deadline)
+        })
+    }
+
+    /// [`auto_setup_resume`](Mpu6050::auto_setup_resume) with the owned delay
+    #[allow(clippy::result_large_err)]
+    pub fn auto_setup_resume(
+        &mut self,
+        options: AutoSetupOptions,
+        previous: &AutoSetupReport,
+        progress: Option<&mut dyn FnMut(AutoSetupPhase)>,
+        deadline: impl FnMut() -> bool,
+    ) -> Result<AutoSetupReport, AutoSetupError<E>> {
+        self.with_owned_delay(|mpu, delay| {
+            mpu.auto_setup_resume_using(delay, options, previous, progress, deadline)
+        })
+    }
+
+    /// [`auto_setup_cooperative`](Mpu6050::auto_setup_cooperative) with the owned delay
+    #[allow(clippy::result_large_err)]
+    pub fn auto_setup_cooperative(
+        &mut self,
+        options: AutoSetupOptions,
+        progress: Option<&mut dyn FnMut(AutoSetupPhase)>,
+        budget: DrainBudget,
+        yield_fn: impl FnMut(),
+    ) -> Result<AutoSetupReport, AutoSetupError<E>> {
+        self.with_owned_delay(|mpu, delay| {
+            mpu.auto_setup_cooperative_using(delay, options, progress, budget, yield_fn)
+        })
+    }
+
+    /// [`calibrate_gyro_with_reference`](Mpu6050::calibrate_gyro_with_reference) with the
+    /// owned delay
+    pub fn calibrate_gyro_with_reference(
+        &mut self,
+        reference: impl FnMut() -> Vec3A,
+        options: ReferencedCalibration,
+    ) -> Result<CalibrationResult, AutoSetupFailure<E>> {
+        let mut never = deadline::never;
+        self.with_owned_delay(|mpu, delay| {
+            mpu.calibrate_gyro_with_reference_using(delay, reference, options, &mut never)
+        })
+    }
+
+    /// [`calibrate_gyro_with_reference_with_deadline`](Mpu6050::calibrate_gyro_with_reference_with_deadline)
+    /// with the owned delay
+    pub fn calibrate_gyro_with_reference_with_deadline(
+        &mut self,
+        reference: impl FnMut() -> Vec3A,
+        options: ReferencedCalibration,
+        mut deadline: impl FnMut() -> bool,
+    ) -> Result<CalibrationResult, AutoSetupFailure<E>> {
+        self.with_owned_delay(|mpu, delay| {
+            mpu.calibrate_gyro_with_reference_using(delay, reference, options, &mut deadline)
+        })
+    }
+
+    /// [`calibrate_accel_with_reference`](Mpu6050::calibrate_accel_with_reference) with the
+    /// owned delay
+    pub fn calibrate_accel_with_reference(
+        &mut self,
+        reference: impl FnMut() -> Vec3A,
+        options: ReferencedCalibration,
+    ) -> Result<CalibrationResult, AutoSetupFailure<E>> {
+        let mut never = deadline::never;
+        self.with_owned_delay(|mpu, delay| {
+            mpu.calibrate_accel_with_reference_using(delay, reference, options, &mut never)
+        })
+    }
+
+    /// [`calibrate_accel_with_reference_with_deadline`](Mpu6050::calibrate_accel_with_reference_with_deadline)
+    /// with the owned delay
+    pub fn calibrate_accel_with_reference_with_deadline(
+        &mut self,
+        reference: impl FnMut() -> Vec3A,
+        options: ReferencedCalibration,
+        mut deadline: impl FnMut() -> bool,
+    ) -> Result<CalibrationResult, AutoSetupFailure<E>> {
+        self.with_owned_delay(|mpu, delay| {
+            mpu.calibrate_accel_with_reference_using(delay, reference, options, &mut deadline)
+        })
+    }
+
+    /// [`calibrate_gyro`](Mpu6050::calibrate_gyro) with the owned delay
+    pub fn calibrate_gyro(
+        &mut self,
+        samples: u16,
+    ) -> Result<CalibrationResult, AutoSetupFailure<E>> {
+        self.with_owned_delay(|mpu, delay| {
+            mpu.calibrate_gyro_using(delay, samples, &mut |_| {}, &mut deadline::never)
+        })
+    }
+
+    /// [`calibrate_gyro_with_deadline`](Mpu6050::calibrate_gyro_with_deadline) with the owned
+    /// delay
+    pub fn calibrate_gyro_with_deadline(
+        &mut self,
+        samples: u16,
+        mut deadline: impl FnMut() -> bool,
+    ) -> Result<CalibrationResult, AutoSetupFailure<E>> {
+        self.with_owned_delay(|mpu, delay| {
+            mpu.calibrate_gyro_using(delay, samples, &mut |_| {}, &mut deadline)
+        })
+    }
+
+    /// [`calibrate_accel`](Mpu6050::calibrate_accel) with the owned delay
+    pub fn calibrate_accel(
+        &mut self,
+        samples: u16,
+    ) -> Result<CalibrationResult, AutoSetupFailure<E>> {
+        self.with_owned_delay(|mpu, delay| {
+            mpu.calibrate_accel_using(delay, samples, &mut |_| {}, &mut deadline::never)
+        })
+    }
+
+    /// [`calibrate_accel_with_deadline`](Mpu6050::calibrate_accel_with_deadline) with the
+    /// owned delay
+    pub fn calibrate_accel_with_deadline(
+        &mut self,
+        samples: u16,
+        mut deadline: impl FnMut() -> bool,
+    ) -> Result<CalibrationResult, AutoSetupFailure<E>> {
+        self.with_owned_delay(|mpu, delay| {
+            mpu.calibrate_accel_using(delay, samples, &mut |_| {}, &mut deadline)
+        })
+    }
+
+    /// [`characterize_self_heating`](Mpu6050::characterize_self_heating) with the owned delay
+    pub fn characterize_self_heating(
+        &mut self,
+        duration_s: u16,
+        interval_ms: u16,
+        sink: &mut impl FnMut(SelfHeatingPoint),
+    ) -> Result<SelfHeatingSummary, Mpu6050Error<E>> {
+        let mut never = deadline::never;
+        self.with_owned_delay(|mpu, delay| {
+            mpu.characterize_self_heating_using(delay, duration_s, interval_ms, sink, &mut never)
+        })
+    }
+
+    /// [`characterize_self_heating_with_deadline`](Mpu6050::characterize_self_heating_with_deadline)
+    /// with the owned delay
+    pub fn characterize_self_heating_with_deadline(
+        &mut self,
+        duration_s: u16,
+        interval_ms: u16,
+        sink: &mut impl FnMut(SelfHeatingPoint),
+        mut deadline: impl FnMut() -> bool,
+    ) -> Result<SelfHeatingSummary, Mpu6050Error<E>> {
+        self.with_owned_delay(|mpu, delay| {
+            mpu.characterize_self_heating_using(delay, duration_s, interval_ms, sink, &mut deadline)
+        })
+    }
+
+    /// [`verify_motion_detection`](Mpu6050::verify_motion_detection) with the owned delay
+    pub fn verify_motion_detection(
+        &mut self,
+        prompt: Option<&mut dyn FnMut()>,
+    ) -> Result<MotionVerifyOutcome, Mpu6050Error<E>> {
+        self.with_owned_delay(|mpu, delay| {
+            mpu.verify_motion_detection_using(delay, prompt, &mut deadline::never)
+        })
+    }
+
+    /// [`verify_motion_detection_with_deadline`](Mpu6050::verify_motion_detection_with_deadline)
+    /// with the owned delay
+    pub fn verify_motion_detection_with_deadline(
+        &mut self,
+        prompt: Option<&mut dyn FnMut()>,
+        mut deadline: impl FnMut() -> bool,
+    ) -> Result<MotionVerifyOutcome, Mpu6050Error<E>> {
+        self.with_owned_delay(|mpu, delay| {
+            mpu.verify_motion_detection_using(delay, prompt, &mut deadline)
+        })
+    }
+
+    /// [`run_script`](Mpu6050::run_script) with the owned delay
+    pub fn run_script(&mut self, script: &RegisterScript) -> Result<ScriptReport, ScriptError<E>> {
+        self.with_owned_delay(|mpu, delay| mpu.run_script_using(script, delay))
+    }
+
+    /// [`collect_init_burst`](Mpu6050::collect_init_burst) with the owned delay
+    pub fn collect_init_burst(&mut self, n: u16) -> Result<InitBurst, Mpu6050Error<E>> {
+        self.with_owned_delay(|mpu, delay| {
+            mpu.collect_init_burst_using(delay, n, &mut deadline::never)
+        })
+    }
+
+    /// [`collect_init_burst_with_deadline`](Mpu6050::collect_init_burst_with_deadline) with
+    /// the owned delay
+    pub fn collect_init_burst_with_deadline(
+        &mut self,
+        n: u16,
+        mut deadline: impl FnMut() -> bool,
+    ) -> Result<InitBurst, Mpu6050Error<E>> {
+        self.with_owned_delay(|mpu, delay| mpu.collect_init_burst_using(delay, n, &mut deadline))
+    }
+
+    /// [`detect_clone_heuristics`](Mpu6050::detect_clone_heuristics) with the owned delay
+    pub fn detect_clone_heuristics(&mut self) -> CloneAssessment {
+        self.with_owned_delay(|mpu, delay| mpu.detect_clone_heuristics_using(delay))
+    }
+
+    /// [`shutdown`](Mpu6050::shutdown) with the owned delay
+    pub fn shutdown(&mut self) -> Result<ShutdownEvidence, Mpu6050Error<E>> {
+        match self.with_owned_delay(|mpu, delay| mpu.shutdown_using(delay, true)) {
+            (_, Some(e)) => Err(e),
+            (evidence, None) => Ok(evidence),
+        }
+    }
+
+    /// [`shutdown_best_effort`](Mpu6050::shutdown_best_effort) with the owned delay
+    pub fn shutdown_best_effort(&mut self) -> ShutdownEvidence {
+        self.with_owned_delay(|mpu, delay| mpu.shutdown_using(delay, false).0)
+    }
+
+    /// [`exit_low_power_mode`](Mpu6050::exit_low_power_mode) with the owned delay
+    pub fn exit_low_power_mode(&mut self) -> Result<(), Mpu6050Error<E>> {
+        self.with_owned_delay(|mpu, delay| mpu.exit_low_power_mode_using(delay))
+    }
+
+    /// [`serve`](Mpu6050::serve) with the owned delay
+    #[cfg(feature = "remote")]
+    pub fn serve<T: Transport>(
+        &mut self,
+        link: &mut RemoteLink<T>,
+        budget: ServeBudget,
+    ) -> Result<ServeStats, T::Error> {
+        self.with_owned_delay(|mpu, delay| mpu.serve_using(delay, link, budget))
+    }
+}
@@ -0,0 +1,102 @@
+//! Bit-exact pipeline outputs across targets.
+//!
+//! With the `deterministic` feature, glam is built with `scalar-math` (no SSE2/NEON code
+//! paths) and transcendental functions without a correctly rounded implementation in `core`
+//! are replaced by portable ones built from `+ - * /` and `sqrt`, which IEEE 754 rounds
+//! identically everywhere. Rust never contracts `a * b + c` into a fused multiply-add on its
+//! own, so with these two changes identical inputs produce identical outputs on every target.
+//!
+//! #### Operation order
+//! * scaling: `(raw as f32 / nominal + offset) * per_axis`, gyro `(raw as f32 * (PI_180 /
+//!   nominal) + offset) * per_axis`, per component
+//! * magnitude: `(x * x + y * y + z * z).sqrt()`, summed left to right
+//! * roll: `atan2(y, (x * x + z * z).sqrt())`, pitch: `atan2(-x, (y * y + z * z).sqrt())`
+//! * atan2: argument reduced to |t| <= 1, halved twice with `t / (1 + sqrt(1 + t * t))`,
+//!   then the odd Taylor series up to t^9 evaluated with Horner's scheme, see [`atan2`]
+//!
+//! Without the feature the standard library `atan2` and glam's SIMD paths are used, results
+//! may then differ in the last bits between targets.
+//!
+//! Pipeline outputs can be compared across runs with [`determinism_checksum`].
+
+#[cfg(not(feature = "std"))]
+use crate::math::FloatExt;
+use crate::MpuSample;
+
+const FNV_OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+/// FNV-1a hash over the little endian bit patterns of acc (x, y, z), gyro (x, y, z) and temp of
+/// every sample. All NaNs hash as the canonical quiet NaN, independent of payload and sign
+pub fn determinism_checksum(samples: &[MpuSample]) -> u64 {
+    let mut hash = FNV_OFFSET;
+    for sample in samples {
+        let (acc, gyro) = (sample.acc(), sample.gyro());
+        for value in [acc.x, acc.y, acc.z, gyro.x, gyro.y, gyro.z, sample.temp()] {
+            let bits = if value.is_nan() {
+                f32::NAN.to_bits()
+            } else {
+                value.to_bits()
+            };
+            for byte in bits.to_le_bytes() {
+                hash ^= byte as u64;
+                hash = hash.wrapping_mul(FNV_PRIME);
+            }
+        }
+    }
+    hash
+}
+
+/// Four quadrant arctangent used by the driver's math, portable with the `deterministic`
+/// feature, `f32::atan2` otherwise
+#[cfg(not(feature = "deterministic"))]
+pub fn atan2(y: f32, x: f32) -> f32 {
+    y.atan2(x)
+}
+
+/// Four quadrant arctangent used by the driver's math, portable with the `deterministic`
+/// feature, `f32::atan2` otherwise. Max error about 5e-7 rad
+#[cfg(feature = "deterministic")]
+pub fn atan2(y: f32, x: f32) -> f32 {
+    use core::f32::consts::{FRAC_PI_2, PI};
+
+    if y.is_nan() || x.is_nan() {
+        f32::NAN
+    } else if x > 0.0 {
+        atan(y / x)
+    } else if x < 0.0 {
+        if y >= 0.0 {
+            atan(y / x) + PI
+        } else {
+            atan(y / x) - PI
+        }
+    } else if y > 0.0 {
+        FRAC_PI_2
+    } else if y < 0.0 {
+        -FRAC_PI_2
+    } else {
+        0.0
+    }
+}
+
+#[cfg(feature = "deterministic")]
+fn atan(x: f32) -> f32 {
+    use core::f32::consts::FRAC_PI_2;
+
+    let inverted = x.abs() > 1.0;
+    let mut t = if inverted { 1.0 / x } else { x };
+    // atan(t) = 2 atan(t / (1 + sqrt(1 + t^2))), twice brings |t| below tan(pi / 16)
+    t /= 1.0 + (1.0 + t * t).sqrt();
+    t /= 1.0 + (1.0 + t * t).sqrt();
+    let t2 = t * t;
+    let series =
+        t * (1.0 - t2 * (1.0 / 3.0 - t2 * (1.0 / 5.0 - t2 * (1.0 / 7.0 - t2 * (1.0 / 9.0)))));
+    let angle = 4.0 * series;
+    if !inverted {
+        angle
+    } else if x > 0.0 {
+        FRAC_PI_2 - angle
+    } else {
+        -FRAC_PI_2 - angle
+    }
+}
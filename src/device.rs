@@ -6,6 +6,12 @@
 //! #### Sources:
 //! * Register map (rev 3.2): https://arduino.ua/docs/RM-MPU-6000A.pdf
 //! * Datasheet (rev 3.2): https://www.cdiweb.com/datasheets/invensense/ps-mpu-6000a.pdf
+//!
+//! Register addresses and bit definitions live in [`registers`](crate::registers) and are
+//! re-exported here, ranges, configuration values and sensor constants are defined here.
+
+#[doc(no_inline)]
+pub use crate::registers::*;
 
 /// Gyro Sensitivity
 ///
@@ -32,257 +38,329 @@ pub const GYRO_SENS: (f32, f32, f32, f32) = (131., 65.5, 32.8, 16.4);
 ///     * https://github.com/kriswiner/MPU6050/wiki/2014-Invensense-Developer%27s-Conference
 ///     * rust MPU9250 driver on github
 pub const ACCEL_SENS: (f32, f32, f32, f32) = (16384., 8192., 4096., 2048.);
+/// Gyro rate noise spectral density in °/s/√Hz, datasheet rev 3.4 section 6.1
+pub const GYRO_NOISE_DENSITY_DPS: f32 = 0.005;
+/// Accelerometer noise power spectral density in µg/√Hz, datasheet rev 3.4 section 6.2
+pub const ACCEL_NOISE_DENSITY_UG: f32 = 400.;
+
+/// DLPF bandwidth in Hz per DLPF_CFG value (accel, gyro), register map rev 4.2 section 4.3.
+/// DLPF_CFG 7 is reserved and listed like 0
+pub const DLPF_BANDWIDTH_HZ: [(f32, f32); 8] = [
+    (260., 256.),
+    (184., 188.),
+    (94., 98.),
+    (44., 42.),
+    (21., 20.),
+    (10., 10.),
+    (5., 5.),
+    (260., 256.),
+];
+
+/// DLPF delay in ms per DLPF_CFG value (accel, gyro), register map rev 4.2 section 4.3.
+/// DLPF_CFG 7 is reserved and listed like 0
+pub const DLPF_DELAY_MS: [(f32, f32); 8] = [
+    (0., 0.98),
+    (2.0, 1.9),
+    (3.0, 2.8),
+    (4.9, 4.8),
+    (8.5, 8.3),
+    (13.8, 13.4),
+    (19.0, 18.6),
+    (0., 0.98),
+];
+
+/// Operations after which the signal path needs time to settle
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum SettleTrigger {
+    /// wake from sleep or power on (PWR_MGMT_1 SLEEP cleared)
+    Wake,
+    /// device reset (PWR_MGMT_1 DEVICE_RESET)
+    Reset,
+    /// DLPF_CFG change
+    Dlpf,
+    /// accel high pass filter change
+    AccelHpf,
+    /// full scale range change
+    Range,
+    /// accel self-test enabled or disabled
+    SelfTest,
+    /// leaving accel-only cycle mode, gyros leave standby
+    CycleExit,
+}
+
+/// Samples to discard after a [`SettleTrigger`] (accel, gyro), indexed by the trigger.
+/// * Wake, Reset: gyro start-up time 30 ms, datasheet rev 3.4 section 6.1, accel output
+///   settles within the first two samples
+/// * CycleExit: gyros leave standby, same start-up time as a wake
+/// * Dlpf: filter state from the old configuration, one group delay (register map rev 4.2
+///   section 4.3) is below 20 ms for every setting, two samples at the default rates
+/// * AccelHpf: HPF restarts from its reset state, one sample
+/// * Range, SelfTest: the sample in flight was converted with the old setting, one sample
+pub const SETTLE_SAMPLES: [(u8, u8); 7] = [(2, 3), (2, 3), (2, 2), (1, 0), (1, 1), (1, 0), (0, 3)];
+
+impl SettleTrigger {
+    /// samples to discard (accel, gyro), see [`SETTLE_SAMPLES`]
+    pub fn samples(self) -> (u8, u8) {
+        SETTLE_SAMPLES[self as usize]
+    }
+}
+
 /// Temperature Offset
 pub const TEMP_OFFSET: f32 = 36.53;
 /// Temperature Sensitivity
 pub const TEMP_SENSITIVITY: f32 = 340.;
 
-/// Motion Threshold Register
-pub const MOT_THR: u8 = 0x1F;
-/// Motion Duration Detection Register
-pub const MOT_DUR: u8 = 0x20;
-/// High Byte Register Gyro x orientation
-pub const GYRO_REGX_H: u8 = 0x43;
-/// High Byte Register Gyro y orientation
-pub const GYRO_REGY_H: u8 = 0x45;
-/// High Byte Register Gyro z orientation
-pub const GYRO_REGZ_H: u8 = 0x47;
-/// High Byte Register Calc roll
-pub const ACC_REGX_H: u8 = 0x3b;
-/// High Byte Register Calc pitch
-pub const ACC_REGY_H: u8 = 0x3d;
-/// High Byte Register Calc yaw
-pub const ACC_REGZ_H: u8 = 0x3f;
-/// High Byte Register Temperature
-pub const TEMP_OUT_H: u8 = 0x41;
 /// Slave address of Mpu6050
 pub const DEFAULT_SLAVE_ADDR: u8 = 0x68;
-/// Internal register to check slave addr
-pub const WHOAMI: u8 = 0x75;
-
-/// Describes a bit block from bit number 'bit' to 'bit'+'length'
-pub struct BitBlock {
-    pub bit: u8,
-    pub length: u8,
-}
+/// Gyro offset register resolution in °/s, the ±1000 °/s scale whatever the range
+pub const GYRO_OFFSET_DPS_PER_LSB: f32 = 1. / 32.8;
 
 #[allow(non_camel_case_types)]
-#[derive(Copy, Clone, Debug)]
-/// Register 26: Configuration (DLPF, External signal)
-pub struct CONFIG;
-
-impl CONFIG {
-    /// Base Address
-    pub const ADDR: u8 = 0x1a;
-    /// external Frame Synchronisation (FSYNC)
-    pub const EXT_SYNC_SET: BitBlock = BitBlock { bit: 5, length: 3 };
-    /// Digital Low Pass Filter (DLPF) config
-    pub const DLPF_CFG: BitBlock = BitBlock { bit: 2, length: 3 };
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+/// FSYNC latch location (CONFIG EXT_SYNC_SET): the LSB of this output carries the FSYNC tag
+pub enum EXT_SYNC {
+    /// FSYNC input disabled
+    DISABLED = 0,
+    /// TEMP_OUT_L bit 0
+    TEMP_OUT_L = 1,
+    /// GYRO_XOUT_L bit 0
+    GYRO_XOUT_L = 2,
+    /// GYRO_YOUT_L bit 0
+    GYRO_YOUT_L = 3,
+    /// GYRO_ZOUT_L bit 0
+    GYRO_ZOUT_L = 4,
+    /// ACCEL_XOUT_L bit 0
+    ACCEL_XOUT_L = 5,
+    /// ACCEL_YOUT_L bit 0
+    ACCEL_YOUT_L = 6,
+    /// ACCEL_ZOUT_L bit 0
+    ACCEL_ZOUT_L = 7,
 }
 
-#[allow(non_camel_case_types)]
-#[derive(Copy, Clone, Debug)]
-/// Register 27: Gyro Config
-pub struct GYRO_CONFIG;
-
-impl GYRO_CONFIG {
-    pub const ADDR: u8 = 0x1b;
-    /// Gyro x axis self test bit
-    pub const XG_ST: u8 = 7;
-    /// Gyro y axis self test bit
-    pub const YG_ST: u8 = 6;
-    /// Gyro z axis self test bit
-    pub const ZG_ST: u8 = 5;
-    /// Gyro Config FS_SEL
-    pub const FS_SEL: BitBlock = BitBlock { bit: 4, length: 2 };
+impl From<u8> for EXT_SYNC {
+    fn from(value: u8) -> Self {
+        match value {
+            1 => EXT_SYNC::TEMP_OUT_L,
+            2 => EXT_SYNC::GYRO_XOUT_L,
+            3 => EXT_SYNC::GYRO_YOUT_L,
+            4 => EXT_SYNC::GYRO_ZOUT_L,
+            5 => EXT_SYNC::ACCEL_XOUT_L,
+            6 => EXT_SYNC::ACCEL_YOUT_L,
+            7 => EXT_SYNC::ACCEL_ZOUT_L,
+            _ => EXT_SYNC::DISABLED,
+        }
+    }
 }
 
 #[allow(non_camel_case_types)]
-#[derive(Copy, Clone, Debug)]
-/// Register 28: Accel Config
-pub struct ACCEL_CONFIG;
-
-impl ACCEL_CONFIG {
-    /// Base Address
-    pub const ADDR: u8 = 0x1c;
-    /// Accel x axis self test bit
-    pub const XA_ST: u8 = 7;
-    /// Accel y axis self test bit
-    pub const YA_ST: u8 = 6;
-    /// Accel z axis self test bit
-    pub const ZA_ST: u8 = 5;
-    /// Accel Config FS_SEL
-    pub const FS_SEL: BitBlock = BitBlock { bit: 4, length: 2 };
-    /// Accel Config ACCEL_HPF
-    pub const ACCEL_HPF: BitBlock = BitBlock { bit: 2, length: 3 };
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+/// Wake values
+pub enum LP_WAKE_CTRL {
+    /// 1.25 Hz
+    _1P25 = 0,
+    /// 2.5 Hz
+    _2P5,
+    /// 5 Hz
+    _5,
+    /// 10 Hz
+    _10,
 }
 
-#[allow(non_camel_case_types)]
-#[derive(Copy, Clone, Debug)]
-/// Register 55: INT Pin / Bypass Enable Configuration
-pub struct INT_PIN_CFG;
-
-impl INT_PIN_CFG {
-    /// Base Address
-    pub const ADDR: u8 = 0x37;
-    /// INT pin logic level
-    pub const INT_LEVEL: u8 = 7;
-    /// INT pin config
-    pub const INT_OPEN: u8 = 6;
-    /// Pulse (length)
-    pub const LATCH_INT_EN: u8 = 5;
-    /// INT clear conditions
-    pub const INT_RD_CLEAR: u8 = 4;
-    /// FSYNC PIN logic level
-    pub const FSYNC_INT_LEVEL: u8 = 3;
-    /// FSYNC PIN config
-    pub const FSYNC_INT_EN: u8 = 2;
-    /// i2c access/bypass
-    pub const I2C_BYPASS_EN: u8 = 1;
-    /// enable/disable reference clock output
-    pub const CLKOUT_EN: u8 = 0;
+impl From<u8> for LP_WAKE_CTRL {
+    fn from(wake: u8) -> Self {
+        match wake & 0b11 {
+            0 => LP_WAKE_CTRL::_1P25,
+            1 => LP_WAKE_CTRL::_2P5,
+            2 => LP_WAKE_CTRL::_5,
+            _ => LP_WAKE_CTRL::_10,
+        }
+    }
 }
 
-#[allow(non_camel_case_types)]
-#[derive(Copy, Clone, Debug)]
-/// Register 56: Interrupt Status
-pub struct INT_ENABLE;
-
-impl INT_ENABLE {
-    /// Base Address
-    pub const ADDR: u8 = 0x38;
-    /// Generate interrupt Free Fall Detection
-    pub const FF_EN: u8 = 7;
-    /// Generate interrupt with Motion Detected
-    pub const MOT_EN: u8 = 6;
-    /// Generate iterrrupt when Zero Motion Detection
-    pub const ZMOT_EN: u8 = 5;
-    /// Generate iterrupt when FIFO buffer overflow
-    pub const FIFO_OFLOW_END: u8 = 4;
-    /// this  bit enables  any  of  the  I2C  Masterinterrupt  sources  to generate an interrupt
-    pub const I2C_MST_INT_EN: u8 = 3;
-    /// enables Data Ready interrupt, each time a write operation to all sensor registers completed
-    pub const DATA_RDY_EN: u8 = 0;
+/// Wake frequency of the low power accelerometer mode. The register map rev 4.2 section
+/// 4.29 gives these four frequencies for LP_WAKE_CTRL 0 to 3 on the MPU-6050, the cycle
+/// currents of the datasheet are quoted at the same four. [`LP_WAKE_CTRL`] carries the same
+/// codes under the names of an older table (1.25, 2.5, 5 and 10 Hz), the code is what
+/// reaches the register
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum LowPowerWakeFrequency {
+    /// 1.25 Hz
+    Hz1P25 = 0,
+    /// 5 Hz
+    Hz5 = 1,
+    /// 20 Hz
+    Hz20 = 2,
+    /// 40 Hz
+    Hz40 = 3,
 }
 
-#[allow(non_camel_case_types)]
-#[derive(Copy, Clone, Debug)]
-/// Register 58: Interrupt Status
-pub struct INT_STATUS;
-
-impl INT_STATUS {
-    /// Base Address
-    pub const ADDR: u8 = 0x3a;
-    /// Free Fall Interrupt
-    pub const FF_INT: u8 = 7;
-    /// Motion Detection Interrupt
-    pub const MOT_INT: u8 = 6;
-    /// Zero Motion Detection Interrupt
-    pub const ZMOT_INT: u8 = 5;
-    /// FIFO buffer overflow
-    pub const FIFO_OFLOW_INT: u8 = 4;
-    /// i2c master interrupt has been generated
-    pub const I2C_MSF_INT: u8 = 3;
-    /// Data is ready
-    pub const DATA_RDY_INT: u8 = 0;
+impl LowPowerWakeFrequency {
+    /// all frequencies, by LP_WAKE_CTRL code
+    pub const ALL: [LowPowerWakeFrequency; 4] = [
+        LowPowerWakeFrequency::Hz1P25,
+        LowPowerWakeFrequency::Hz5,
+        LowPowerWakeFrequency::Hz20,
+        LowPowerWakeFrequency::Hz40,
+    ];
+
+    /// wake frequency in hundredths of Hz
+    pub const fn centi_hz(self) -> u32 {
+        match self {
+            LowPowerWakeFrequency::Hz1P25 => 125,
+            LowPowerWakeFrequency::Hz5 => 500,
+            LowPowerWakeFrequency::Hz20 => 2000,
+            LowPowerWakeFrequency::Hz40 => 4000,
+        }
+    }
+
+    /// [`LP_WAKE_CTRL`] of the same code
+    pub const fn lp_wake_ctrl(self) -> LP_WAKE_CTRL {
+        match self {
+            LowPowerWakeFrequency::Hz1P25 => LP_WAKE_CTRL::_1P25,
+            LowPowerWakeFrequency::Hz5 => LP_WAKE_CTRL::_2P5,
+            LowPowerWakeFrequency::Hz20 => LP_WAKE_CTRL::_5,
+            LowPowerWakeFrequency::Hz40 => LP_WAKE_CTRL::_10,
+        }
+    }
 }
 
-#[allow(non_camel_case_types)]
-#[derive(Copy, Clone, Debug)]
-/// Register 97: Motion Detection Status
-pub struct MOT_DETECT_STATUS;
-
-impl MOT_DETECT_STATUS {
-    /// Base Address
-    pub const ADDR: u8 = 0x61;
-    /// motion  in  the  negative  X  axis  has generated a Motion detection interrupt
-    pub const MOT_XNEG: u8 = 7;
-    /// motion  in  the  positive  X  axis  has generated a Motion detection interrupt
-    pub const MOT_XPOS: u8 = 6;
-    /// motion  in  the  negative  Y  axis  has generated a Motion detection interrupt
-    pub const MOT_YNEG: u8 = 5;
-    /// motion  in  the positive  Y  axis  has generated a Motion detection interrupt
-    pub const MOT_YPOS: u8 = 4;
-    /// motion  in  the  negative  Z  axis  has generated a Motion detection interrupt.
-    pub const MOT_ZNEG: u8 = 3;
-    /// motion  in  the  positive  Z  axis  has generated a Motion detection interrupt
-    pub const MOT_ZPOS: u8 = 2;
-    /// Zero  Motion detection  interrupt  is generated
-    pub const MOT_ZRMOT: u8 = 0;
+impl From<LP_WAKE_CTRL> for LowPowerWakeFrequency {
+    fn from(wake: LP_WAKE_CTRL) -> Self {
+        Self::ALL[wake as usize]
+    }
 }
 
-#[allow(non_camel_case_types)]
-#[derive(Copy, Clone, Debug)]
-/// Register 105: Motion Detection Control
-pub struct MOT_DETECT_CONTROL;
+/// Standby bits of PWR_MGMT_2, in its bit layout: a set bit puts that axis of that sensor
+/// in standby
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub struct StandbyFlags(u8);
 
-impl MOT_DETECT_CONTROL {
-    /// Base Address
-    pub const ADDR: u8 = 0x69;
-    /// Additional delay
-    pub const ACCEL_ON_DELAY: BitBlock = BitBlock { bit: 5, length: 2 };
-    ///  Free Fall count
-    pub const FF_COUNT: BitBlock = BitBlock { bit: 3, length: 2 };
-    /// Motion Detection cound
-    pub const MOT_COUNT: BitBlock = BitBlock { bit: 1, length: 2 };
-}
+impl StandbyFlags {
+    /// every axis running, the reset value
+    pub const NONE: StandbyFlags = StandbyFlags(0);
+    /// the three gyro axes in standby, the accelerometer-only modes
+    pub const GYRO: StandbyFlags = StandbyFlags(
+        1 << PWR_MGMT_2::STBY_XG | 1 << PWR_MGMT_2::STBY_YG | 1 << PWR_MGMT_2::STBY_ZG,
+    );
+    /// the three accelerometer axes in standby
+    pub const ACCEL: StandbyFlags = StandbyFlags(
+        1 << PWR_MGMT_2::STBY_XA | 1 << PWR_MGMT_2::STBY_YA | 1 << PWR_MGMT_2::STBY_ZA,
+    );
+    /// every axis in standby
+    pub const ALL: StandbyFlags = StandbyFlags(Self::GYRO.0 | Self::ACCEL.0);
 
-#[allow(non_camel_case_types)]
-#[derive(Copy, Clone, Debug)]
-/// Register 107: Power Management 1
-pub struct PWR_MGMT_1;
-
-impl PWR_MGMT_1 {
-    /// Base Address
-    pub const ADDR: u8 = 0x6b;
-    /// Device Reset bit
-    pub const DEVICE_RESET: u8 = 7;
-    /// Sleep mode bit (Should be called "Low Power", doesn't actually sleep)
-    pub const SLEEP: u8 = 6;
-    /// Cycle bit for wake operations
-    pub const CYCLE: u8 = 5;
-    /// Temperature sensor enable/disable bit
-    pub const TEMP_DIS: u8 = 3;
-    /// Clock Control
-    pub const CLKSEL: BitBlock = BitBlock { bit: 2, length: 3 };
+    /// from a PWR_MGMT_2 value, LP_WAKE_CTRL dropped
+    pub const fn from_bits(bits: u8) -> Self {
+        Self(bits & Self::ALL.0)
+    }
+
+    /// PWR_MGMT_2 bit layout, LP_WAKE_CTRL clear
+    pub const fn bits(self) -> u8 {
+        self.0
+    }
+
+    /// same flags with accelerometer `axis` in standby or running
+    pub const fn with_accel(self, axis: Axis, standby: bool) -> Self {
+        self.with_bit(PWR_MGMT_2::STBY_XA - axis as u8, standby)
+    }
+
+    /// same flags with gyro `axis` in standby or running
+    pub const fn with_gyro(self, axis: Axis, standby: bool) -> Self {
+        self.with_bit(PWR_MGMT_2::STBY_XG - axis as u8, standby)
+    }
+
+    /// accelerometer `axis` in standby
+    pub const fn accel(self, axis: Axis) -> bool {
+        self.0 & 1 << (PWR_MGMT_2::STBY_XA - axis as u8) != 0
+    }
+
+    /// gyro `axis` in standby
+    pub const fn gyro(self, axis: Axis) -> bool {
+        self.0 & 1 << (PWR_MGMT_2::STBY_XG - axis as u8) != 0
+    }
+
+    const fn with_bit(self, bit: u8, standby: bool) -> Self {
+        match standby {
+            true => Self(self.0 | 1 << bit),
+            false => Self(self.0 & !(1 << bit)),
+        }
+    }
 }
 
-#[allow(non_camel_case_types)]
-#[derive(Copy, Clone, Debug)]
-/// Register 107: Power Management 2
-pub struct PWR_MGMT_2;
-
-impl PWR_MGMT_2 {
-    /// Base Address
-    pub const ADDR: u8 = 0x6c;
-    /// Wake up frequency
-    pub const LP_WAKE_CTRL: BitBlock = BitBlock { bit: 7, length: 2 };
-    /// disable accel axis x
-    pub const STBY_XA: u8 = 5;
-    /// disable accel axis y
-    pub const STBY_YA: u8 = 4;
-    /// disable accel axis z
-    pub const STBY_ZA: u8 = 3;
-    /// disable gyro  axis x
-    pub const STBY_XG: u8 = 2;
-    /// disable gyro  axis y
-    pub const STBY_YG: u8 = 1;
-    /// disable gyro  axis z
-    pub const STBY_ZG: u8 = 0;
+/// Setting of the low power accelerometer mode, see [`CYCLE_ADJUSTABILITY`]
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum LowPowerField {
+    /// PWR_MGMT_2 LP_WAKE_CTRL
+    WakeFrequency,
+    /// MOT_THR
+    MotionThreshold,
+    /// MOT_DUR
+    MotionDuration,
+    /// ACCEL_CONFIG AFS_SEL
+    AccelRange,
+    /// ACCEL_CONFIG ACCEL_HPF
+    AccelHpf,
 }
 
-#[allow(non_camel_case_types)]
+/// Whether a [`LowPowerField`] can change while CYCLE is set
 #[derive(Copy, Clone, Debug, Eq, PartialEq)]
-/// Wake values
-pub enum LP_WAKE_CTRL {
-    /// 1.25 Hz
-    _1P25 = 0,
-    /// 2.5 Hz
-    _2P5,
-    /// 5 Hz
-    _5,
-    /// 10 Hz
-    _10,
+pub struct CycleAdjustability {
+    /// the setting
+    pub field: LowPowerField,
+    /// register holding it
+    pub register: u8,
+    /// writable in place while cycling
+    pub adjustable: bool,
+    /// why, with the register map reference
+    pub reason: &'static str,
+}
+
+/// Settings [`adjust_low_power`](crate::Mpu6050::adjust_low_power) writes while cycling,
+/// and the ones needing the full path out of cycle mode
+pub const CYCLE_ADJUSTABILITY: &[CycleAdjustability] = &[
+    CycleAdjustability {
+        field: LowPowerField::WakeFrequency,
+        register: PWR_MGMT_2::ADDR,
+        adjustable: true,
+        reason: "the wake-up frequency of the low power accelerometer mode, taken from the \
+                 next wake (register map rev 4.2 section 4.29)",
+    },
+    CycleAdjustability {
+        field: LowPowerField::MotionThreshold,
+        register: MOT_THR,
+        adjustable: true,
+        reason: "compared against every accelerometer sample, the next wake uses the new \
+                 value (register map rev 3.2 MOT_THR, not in rev 4.2)",
+    },
+    CycleAdjustability {
+        field: LowPowerField::MotionDuration,
+        register: MOT_DUR,
+        adjustable: true,
+        reason: "counter limit of the motion detector, a count under way is compared \
+                 against the new value (register map rev 3.2 MOT_DUR, not in rev 4.2)",
+    },
+    CycleAdjustability {
+        field: LowPowerField::AccelRange,
+        register: ACCEL_CONFIG::ADDR,
+        adjustable: false,
+        reason: "rescales the samples the threshold is compared against, the step between \
+                 the two scales reads as motion (register map rev 4.2 section 4.5)",
+    },
+    CycleAdjustability {
+        field: LowPowerField::AccelHpf,
+        register: ACCEL_CONFIG::ADDR,
+        adjustable: false,
+        reason: "the motion detector runs on the high pass filter output, a mode change \
+                 restarts the filter from the present sample (register map rev 3.2 \
+                 ACCEL_HPF)",
+    },
+];
+
+/// whether `field` can change while cycling, see [`CYCLE_ADJUSTABILITY`]
+pub fn cycle_adjustable(field: LowPowerField) -> bool {
+    CYCLE_ADJUSTABILITY
+        .iter()
+        .any(|entry| entry.field == field && entry.adjustable)
 }
 
 #[allow(non_camel_case_types)]
@@ -318,6 +396,42 @@ impl From<u8> for ACCEL_HPF {
     }
 }
 
+#[allow(non_camel_case_types)]
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+/// Digital Low Pass Filter Values (CONFIG DLPF_CFG), named by accel bandwidth, see
+/// [`DLPF_BANDWIDTH_HZ`]
+pub enum DLPF {
+    /// Accel 260 Hz, gyro 256 Hz, gyro output at 8 kHz
+    _260 = 0,
+    /// Accel 184 Hz, gyro 188 Hz, gyro output at 1 kHz from here
+    _184 = 1,
+    /// Accel 94 Hz, gyro 98 Hz
+    _94 = 2,
+    /// Accel 44 Hz, gyro 42 Hz
+    _44 = 3,
+    /// Accel 21 Hz, gyro 20 Hz
+    _21 = 4,
+    /// Accel 10 Hz, gyro 10 Hz
+    _10 = 5,
+    /// Accel 5 Hz, gyro 5 Hz
+    _5 = 6,
+}
+
+impl From<u8> for DLPF {
+    /// the reserved DLPF_CFG 7 filters like 0
+    fn from(dlpf_cfg: u8) -> Self {
+        match dlpf_cfg & 0b111 {
+            1 => DLPF::_184,
+            2 => DLPF::_94,
+            3 => DLPF::_44,
+            4 => DLPF::_21,
+            5 => DLPF::_10,
+            6 => DLPF::_5,
+            _ => DLPF::_260,
+        }
+    }
+}
+
 #[allow(non_camel_case_types)]
 #[derive(Copy, Clone, Debug, Eq, PartialEq)]
 /// Clock Source Select Values
@@ -356,8 +470,8 @@ impl From<u8> for CLKSEL {
     }
 }
 
-/// Defines accelerometer range/sensivity
-#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+/// Defines accelerometer range/sensivity, ordered by full scale
+#[derive(Debug, Eq, PartialEq, PartialOrd, Ord, Copy, Clone)]
 pub enum AccelRange {
     /// 2G
     G2 = 0,
@@ -407,6 +521,29 @@ impl From<u8> for AccelRange {
 }
 
 impl AccelRange {
+    /// all ranges, smallest first
+    pub const ALL: [AccelRange; 4] = [
+        AccelRange::G2,
+        AccelRange::G4,
+        AccelRange::G8,
+        AccelRange::G16,
+    ];
+
+    /// full scale in g
+    pub fn full_scale_g(&self) -> f32 {
+        match &self {
+            AccelRange::G2 => 2.,
+            AccelRange::G4 => 4.,
+            AccelRange::G8 => 8.,
+            AccelRange::G16 => 16.,
+        }
+    }
+
+    /// size of one LSB in mg
+    pub fn resolution_mg_per_lsb(&self) -> f32 {
+        1000. / self.sensitivity()
+    }
+
     // Converts accelerometer range to correction/scaling factor, see register sheet
     pub(crate) fn sensitivity(&self) -> f32 {
         match &self {
@@ -419,6 +556,29 @@ impl AccelRange {
 }
 
 impl GyroRange {
+    /// all ranges, smallest first
+    pub const ALL: [GyroRange; 4] = [
+        GyroRange::D250,
+        GyroRange::D500,
+        GyroRange::D1000,
+        GyroRange::D2000,
+    ];
+
+    /// full scale in °/s
+    pub fn full_scale_dps(&self) -> f32 {
+        match &self {
+            GyroRange::D250 => 250.,
+            GyroRange::D500 => 500.,
+            GyroRange::D1000 => 1000.,
+            GyroRange::D2000 => 2000.,
+        }
+    }
+
+    /// size of one LSB in °/s
+    pub fn resolution_dps_per_lsb(&self) -> f32 {
+        1. / self.sensitivity()
+    }
+
     // Converts gyro range to correction/scaling factor, see register sheet
     pub(crate) fn sensitivity(&self) -> f32 {
         match &self {
@@ -429,3 +589,748 @@ impl GyroRange {
         }
     }
 }
+
+/// Named bit field of a register, for diagnostics
+#[derive(Copy, Clone, Debug)]
+pub struct FieldInfo {
+    /// field name as in the register map
+    pub name: &'static str,
+    /// field location
+    pub block: BitBlock,
+}
+
+/// Register name and fields, for diagnostics
+#[derive(Copy, Clone, Debug)]
+pub struct RegisterInfo {
+    /// register address
+    pub addr: u8,
+    /// register name as in the register map
+    pub name: &'static str,
+    /// fields, most significant first
+    pub fields: &'static [FieldInfo],
+}
+
+const fn field(name: &'static str, bit: u8, length: u8) -> FieldInfo {
+    FieldInfo {
+        name,
+        block: BitBlock { bit, length },
+    }
+}
+
+/// Configuration registers known to the driver, sorted by address
+pub const REGISTERS: &[RegisterInfo] = &[
+    RegisterInfo {
+        addr: SMPLRT_DIV,
+        name: "SMPLRT_DIV",
+        fields: &[field("SMPLRT_DIV", 7, 8)],
+    },
+    RegisterInfo {
+        addr: CONFIG::ADDR,
+        name: "CONFIG",
+        fields: &[field("EXT_SYNC_SET", 5, 3), field("DLPF_CFG", 2, 3)],
+    },
+    RegisterInfo {
+        addr: GYRO_CONFIG::ADDR,
+        name: "GYRO_CONFIG",
+        fields: &[
+            field("XG_ST", GYRO_CONFIG::XG_ST, 1),
+            field("YG_ST", GYRO_CONFIG::YG_ST, 1),
+            field("ZG_ST", GYRO_CONFIG::ZG_ST, 1),
+            field("FS_SEL", 4, 2),
+        ],
+    },
+    RegisterInfo {
+        addr: ACCEL_CONFIG::ADDR,
+        name: "ACCEL_CONFIG",
+        fields: &[
+            field("XA_ST", ACCEL_CONFIG::XA_ST, 1),
+            field("YA_ST", ACCEL_CONFIG::YA_ST, 1),
+            field("ZA_ST", ACCEL_CONFIG::ZA_ST, 1),
+            field("AFS_SEL", 4, 2),
+            field("ACCEL_HPF", 2, 3),
+        ],
+    },
+    RegisterInfo {
+        addr: MOT_THR,
+        name: "MOT_THR",
+        fields: &[field("MOT_THR", 7, 8)],
+    },
+    RegisterInfo {
+        addr: MOT_DUR,
+        name: "MOT_DUR",
+        fields: &[field("MOT_DUR", 7, 8)],
+    },
+    RegisterInfo {
+        addr: INT_PIN_CFG::ADDR,
+        name: "INT_PIN_CFG",
+        fields: &[
+            field("INT_LEVEL", INT_PIN_CFG::INT_LEVEL, 1),
+            field("INT_OPEN", INT_PIN_CFG::INT_OPEN, 1),
+            field("LATCH_INT_EN", INT_PIN_CFG::LATCH_INT_EN, 1),
+            field("INT_RD_CLEAR", INT_PIN_CFG::INT_RD_CLEAR, 1),
+            field("FSYNC_INT_LEVEL", INT_PIN_CFG::FSYNC_INT_LEVEL, 1),
+            field("FSYNC_INT_EN", INT_PIN_CFG::FSYNC_INT_EN, 1),
+            field("I2C_BYPASS_EN", INT_PIN_CFG::I2C_BYPASS_EN, 1),
+            field("CLKOUT_EN", INT_PIN_CFG::CLKOUT_EN, 1),
+        ],
+    },
+    RegisterInfo {
+        addr: INT_ENABLE::ADDR,
+        name: "INT_ENABLE",
+        fields: &[
+            field("FF_EN", INT_ENABLE::FF_EN, 1),
+            field("MOT_EN", INT_ENABLE::MOT_EN, 1),
+            field("ZMOT_EN", INT_ENABLE::ZMOT_EN, 1),
+            field("FIFO_OFLOW_EN", INT_ENABLE::FIFO_OFLOW_END, 1),
+            field("I2C_MST_INT_EN", INT_ENABLE::I2C_MST_INT_EN, 1),
+            field("DATA_RDY_EN", INT_ENABLE::DATA_RDY_EN, 1),
+        ],
+    },
+    RegisterInfo {
+        addr: MOT_DETECT_CONTROL::ADDR,
+        name: "MOT_DETECT_CONTROL",
+        fields: &[
+            field("ACCEL_ON_DELAY", 5, 2),
+            field("FF_COUNT", 3, 2),
+            field("MOT_COUNT", 1, 2),
+        ],
+    },
+    RegisterInfo {
+        addr: USER_CTRL::ADDR,
+        name: "USER_CTRL",
+        fields: &[
+            field("FIFO_EN", USER_CTRL::FIFO_EN, 1),
+            field("I2C_MST_EN", USER_CTRL::I2C_MST_EN, 1),
+            field("I2C_IF_DIS", USER_CTRL::I2C_IF_DIS, 1),
+            field("FIFO_RESET", USER_CTRL::FIFO_RESET, 1),
+            field("I2C_MST_RESET", USER_CTRL::I2C_MST_RESET, 1),
+            field("SIG_COND_RESET", USER_CTRL::SIG_COND_RESET, 1),
+        ],
+    },
+    RegisterInfo {
+        addr: PWR_MGMT_1::ADDR,
+        name: "PWR_MGMT_1",
+        fields: &[
+            field("DEVICE_RESET", PWR_MGMT_1::DEVICE_RESET, 1),
+            field("SLEEP", PWR_MGMT_1::SLEEP, 1),
+            field("CYCLE", PWR_MGMT_1::CYCLE, 1),
+            field("TEMP_DIS", PWR_MGMT_1::TEMP_DIS, 1),
+            field("CLKSEL", 2, 3),
+        ],
+    },
+    RegisterInfo {
+        addr: PWR_MGMT_2::ADDR,
+        name: "PWR_MGMT_2",
+        fields: &[
+            field("LP_WAKE_CTRL", 7, 2),
+            field("STBY_XA", PWR_MGMT_2::STBY_XA, 1),
+            field("STBY_YA", PWR_MGMT_2::STBY_YA, 1),
+            field("STBY_ZA", PWR_MGMT_2::STBY_ZA, 1),
+            field("STBY_XG", PWR_MGMT_2::STBY_XG, 1),
+            field("STBY_YG", PWR_MGMT_2::STBY_YG, 1),
+            field("STBY_ZG", PWR_MGMT_2::STBY_ZG, 1),
+        ],
+    },
+];
+
+/// Documented power-on reset values (address, value) of every register the driver touches,
+/// sorted by address. Register map rev 4.2 section 3: all registers reset to 0x00 except
+/// PWR_MGMT_1 (0x40, sleep) and WHO_AM_I (0x68)
+pub const RESET_VALUES: &[(u8, u8)] = &[
+    (SMPLRT_DIV, 0x00),
+    (CONFIG::ADDR, 0x00),
+    (GYRO_CONFIG::ADDR, 0x00),
+    (ACCEL_CONFIG::ADDR, 0x00),
+    (MOT_THR, 0x00),
+    (MOT_DUR, 0x00),
+    (I2C_SLV::addr_reg(0), 0x00),
+    (I2C_SLV::reg_reg(0), 0x00),
+    (I2C_SLV::ctrl_reg(0), 0x00),
+    (I2C_SLV::addr_reg(1), 0x00),
+    (I2C_SLV::reg_reg(1), 0x00),
+    (I2C_SLV::ctrl_reg(1), 0x00),
+    (I2C_SLV::addr_reg(2), 0x00),
+    (I2C_SLV::reg_reg(2), 0x00),
+    (I2C_SLV::ctrl_reg(2), 0x00),
+    (I2C_SLV::addr_reg(3), 0x00),
+    (I2C_SLV::reg_reg(3), 0x00),
+    (I2C_SLV::ctrl_reg(3), 0x00),
+    (INT_PIN_CFG::ADDR, 0x00),
+    (INT_ENABLE::ADDR, 0x00),
+    (INT_STATUS::ADDR, 0x00),
+    (MOT_DETECT_STATUS::ADDR, 0x00),
+    (SIGNAL_PATH_RESET::ADDR, 0x00),
+    (MOT_DETECT_CONTROL::ADDR, 0x00),
+    (USER_CTRL::ADDR, 0x00),
+    (PWR_MGMT_1::ADDR, 0x40),
+    (PWR_MGMT_2::ADDR, 0x00),
+    (WHOAMI, 0x68),
+];
+
+/// Documented power-on reset value of a register, None if not in [`RESET_VALUES`]
+pub fn reset_value(addr: u8) -> Option<u8> {
+    RESET_VALUES
+        .iter()
+        .find(|(a, _)| *a == addr)
+        .map(|(_, value)| *value)
+}
+
+/// Looks up name and fields of a configuration register
+pub fn register_info(addr: u8) -> Option<&'static RegisterInfo> {
+    REGISTERS.iter().find(|info| info.addr == addr)
+}
+
+/// Sensor axis
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Axis {
+    /// X axis
+    X = 0,
+    /// Y axis
+    Y = 1,
+    /// Z axis
+    Z = 2,
+}
+
+impl Axis {
+    /// all axes, in order
+    pub const ALL: [Axis; 3] = [Axis::X, Axis::Y, Axis::Z];
+}
+
+/// Chip family, from WHO_AM_I
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum ChipVariant {
+    /// MPU-6050, WHO_AM_I 0x68
+    Mpu6050,
+    /// MPU-6500, WHO_AM_I 0x70
+    Mpu6500,
+    /// MPU-9250 or MPU-9255, WHO_AM_I 0x71 or 0x73
+    Mpu9250,
+    /// anything else, clones with made up WHO_AM_I values included
+    Unknown(u8),
+}
+
+impl ChipVariant {
+    /// variant answering with `who_am_i`
+    pub const fn from_who_am_i(who_am_i: u8) -> Self {
+        match who_am_i {
+            0x68 => ChipVariant::Mpu6050,
+            0x70 => ChipVariant::Mpu6500,
+            0x71 | 0x73 => ChipVariant::Mpu9250,
+            other => ChipVariant::Unknown(other),
+        }
+    }
+}
+
+/// WHO_AM_I of an MPU-6050, the device ID in bits 6:1 whatever the AD0 pin
+pub const MPU6050_WHO_AM_I: u8 = 0x68;
+
+/// Bits of WHO_AM_I holding the device ID on the MPU-6050, bits 0 and 7 are reserved
+pub const WHO_AM_I_ID_MASK: u8 = 0b0111_1110;
+
+/// WHO_AM_I values init and reconnect accept, see
+/// [`expect_chip_ids`](crate::Mpu6050Builder::expect_chip_ids)
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub enum ChipIdCheck {
+    /// the MPU-6050 device ID in bits 6:1, [`MPU6050_WHO_AM_I`] under [`WHO_AM_I_ID_MASK`]
+    #[default]
+    Mpu6050,
+    /// any of these values, compared on all 8 bits as the later parts define them, e.g.
+    /// `&[0x68, 0x70, 0x71]` for MPU-6050, MPU-6500 and MPU-9250 boards
+    OneOf(&'static [u8]),
+    /// any value, the chip is not verified
+    Skip,
+}
+
+impl ChipIdCheck {
+    /// whether `who_am_i` passes
+    pub fn accepts(&self, who_am_i: u8) -> bool {
+        match self {
+            ChipIdCheck::Mpu6050 => who_am_i & WHO_AM_I_ID_MASK == MPU6050_WHO_AM_I,
+            ChipIdCheck::OneOf(ids) => ids.contains(&who_am_i),
+            ChipIdCheck::Skip => true,
+        }
+    }
+}
+
+/// Conversion of TEMP_OUT to degrees celsius
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum TempFormula {
+    /// `raw / 340 + 36.53`
+    Mpu6050,
+    /// `raw / 333.87 + 21`
+    Mpu6500,
+}
+
+#[cfg(feature = "fusion")]
+impl TempFormula {
+    /// the formula as pipeline temperature constants
+    pub const fn model(self) -> crate::scale::TempModel {
+        match self {
+            TempFormula::Mpu6050 => crate::scale::TempModel::NATIVE,
+            TempFormula::Mpu6500 => crate::scale::TempModel {
+                raw_offset: 0.,
+                sensitivity: 333.87,
+                offset_c: 21.0,
+            },
+        }
+    }
+
+    /// temperature in degrees celsius
+    pub fn celsius(self, raw: i16) -> f32 {
+        self.model().celsius(raw)
+    }
+}
+
+/// Layout of the factory accel offset registers
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum AccelOffsetScaling {
+    /// 15 bit, 0.98 mg/LSB, XA_OFFS_H at 0x06, bit 0 reserved
+    Mpu6050,
+    /// 15 bit, 0.98 mg/LSB, XA_OFFSET_H at 0x77, bit 0 reserved
+    Mpu6500,
+    /// unknown, must not be written
+    Unknown,
+}
+
+impl AccelOffsetScaling {
+    /// high registers of the X, Y and Z offsets, None if unknown
+    pub const fn registers(self) -> Option<[u8; 3]> {
+        match self {
+            AccelOffsetScaling::Mpu6050 => Some([XA_OFFS_H, YA_OFFS_H, ZA_OFFS_H]),
+            AccelOffsetScaling::Mpu6500 => Some([XA_OFFSET_H, YA_OFFSET_H, ZA_OFFSET_H]),
+            AccelOffsetScaling::Unknown => None,
+        }
+    }
+}
+
+/// MPU-6050 supply current, gyro and accel on, DMP off. Datasheet rev 3.2, section 6.4
+pub const MPU6050_NORMAL_UA: u32 = 3800;
+/// MPU-6050 supply current, gyro only. Datasheet rev 3.2, section 6.4
+pub const MPU6050_GYRO_ONLY_UA: u32 = 3600;
+/// MPU-6050 supply current, accel only. Datasheet rev 3.2, section 6.4
+pub const MPU6050_ACCEL_ONLY_UA: u32 = 500;
+/// MPU-6050 supply current, sleep. Datasheet rev 3.2, section 6.4
+pub const MPU6050_SLEEP_UA: u32 = 5;
+/// MPU-6050 low power accel current at a 1.25 Hz wake. Datasheet rev 3.2, section 6.4
+pub const MPU6050_CYCLE_1P25_HZ_UA: u32 = 10;
+/// MPU-6050 low power accel current at a 5 Hz wake. Datasheet rev 3.2, section 6.4
+pub const MPU6050_CYCLE_5_HZ_UA: u32 = 20;
+/// MPU-6050 low power accel current at a 20 Hz wake. Datasheet rev 3.2, section 6.4
+pub const MPU6050_CYCLE_20_HZ_UA: u32 = 70;
+
+/// MPU-6500 supply current, gyro and accel on. Datasheet PS-MPU-6500A-01, section 3.3
+pub const MPU6500_NORMAL_UA: u32 = 3400;
+/// MPU-6500 supply current, gyro only. Datasheet PS-MPU-6500A-01, section 3.3
+pub const MPU6500_GYRO_ONLY_UA: u32 = 3200;
+/// MPU-6500 supply current, accel only. Datasheet PS-MPU-6500A-01, section 3.3
+pub const MPU6500_ACCEL_ONLY_UA: u32 = 450;
+/// MPU-6500 supply current, full chip idle. Datasheet PS-MPU-6500A-01, section 3.3
+pub const MPU6500_SLEEP_UA: u32 = 6;
+/// MPU-6500 low power accel current at 0.98 Hz, rounded. Datasheet PS-MPU-6500A-01, section 3.3
+pub const MPU6500_CYCLE_0P98_HZ_UA: u32 = 7;
+/// MPU-6500 low power accel current at 31.25 Hz, rounded. Datasheet PS-MPU-6500A-01, section 3.3
+pub const MPU6500_CYCLE_31P25_HZ_UA: u32 = 19;
+
+/// Modelled current of the chip pulling SDA low: 3.3 V over a 4.7 kΩ pull-up, low for about
+/// half the bits. Not a datasheet figure, the bus is not part of the supply currents
+pub const I2C_ACTIVE_UA: u32 = 350;
+
+/// Supply currents of a chip in µA, the model of the `power` module. The gyro, accel and
+/// base figures split the datasheet's gyro only, accel only and combined currents: `accel =
+/// normal - gyro only`, `base = accel only - accel`, `gyro = gyro only - base`
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct CurrentTable {
+    /// asleep, all sensors off
+    pub sleep_ua: u32,
+    /// awake, shared by the sensors: oscillator, PLL, digital core
+    pub base_ua: u32,
+    /// gyro, all three axes out of standby, a third per axis
+    pub gyro_ua: u32,
+    /// accelerometer, all axes
+    pub accel_ua: u32,
+    /// temperature sensor, not specified by the datasheets apart from the base
+    pub temp_ua: u32,
+    /// whole chip in accel-only cycle mode, by [`LP_WAKE_CTRL`] wake frequency
+    pub cycle_ua: [u32; 4],
+    /// while the bus carries the driver's traffic, see [`I2C_ACTIVE_UA`]
+    pub i2c_active_ua: u32,
+}
+
+impl CurrentTable {
+    /// table split from the datasheet's currents, see the type docs
+    pub const fn from_datasheet(
+        normal_ua: u32,
+        gyro_only_ua: u32,
+        accel_only_ua: u32,
+        sleep_ua: u32,
+        cycle_ua: [u32; 4],
+    ) -> Self {
+        let accel_ua = normal_ua - gyro_only_ua;
+        let base_ua = accel_only_ua - accel_ua;
+        Self {
+            sleep_ua,
+            base_ua,
+            gyro_ua: gyro_only_ua - base_ua,
+            accel_ua,
+            temp_ua: 0,
+            cycle_ua,
+            i2c_active_ua: I2C_ACTIVE_UA,
+        }
+    }
+
+    /// current in cycle mode waking at `wake`
+    pub const fn cycle(&self, wake: LP_WAKE_CTRL) -> u32 {
+        self.cycle_ua[wake as usize]
+    }
+}
+
+/// MPU-6050 currents. 2.5 Hz and 10 Hz are not in the datasheet, they are interpolated
+/// linearly between its 1.25, 5 and 20 Hz figures, both a third of the way
+pub const MPU6050_CURRENT: CurrentTable = CurrentTable::from_datasheet(
+    MPU6050_NORMAL_UA,
+    MPU6050_GYRO_ONLY_UA,
+    MPU6050_ACCEL_ONLY_UA,
+    MPU6050_SLEEP_UA,
+    [
+        MPU6050_CYCLE_1P25_HZ_UA,
+        MPU6050_CYCLE_1P25_HZ_UA + (MPU6050_CYCLE_5_HZ_UA - MPU6050_CYCLE_1P25_HZ_UA) / 3,
+        MPU6050_CYCLE_5_HZ_UA,
+        MPU6050_CYCLE_5_HZ_UA + (MPU6050_CYCLE_20_HZ_UA - MPU6050_CYCLE_5_HZ_UA) / 3,
+    ],
+);
+
+/// MPU-6500 currents, also used for the MPU-9250 without its magnetometer. The wake
+/// frequencies are interpolated linearly between the datasheet's 0.98 and 31.25 Hz figures
+pub const MPU6500_CURRENT: CurrentTable = CurrentTable::from_datasheet(
+    MPU6500_NORMAL_UA,
+    MPU6500_GYRO_ONLY_UA,
+    MPU6500_ACCEL_ONLY_UA,
+    MPU6500_SLEEP_UA,
+    [
+        mpu6500_cycle_ua(125),
+        mpu6500_cycle_ua(250),
+        mpu6500_cycle_ua(500),
+        mpu6500_cycle_ua(1000),
+    ],
+);
+
+/// MPU-6500 cycle current at `centi_hz` hundredths of Hz, rounded to the nearest µA
+const fn mpu6500_cycle_ua(centi_hz: u32) -> u32 {
+    let (low, high) = (98, 3125);
+    let span = MPU6500_CYCLE_31P25_HZ_UA - MPU6500_CYCLE_0P98_HZ_UA;
+    MPU6500_CYCLE_0P98_HZ_UA + (span * (centi_hz - low) + (high - low) / 2) / (high - low)
+}
+
+/// Driver features a chip supports
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct ChipCapabilities {
+    /// variant the capabilities are for
+    pub variant: ChipVariant,
+    /// FIFO present, confirmed by a probe if probed
+    pub fifo_available: bool,
+    /// DMP firmware can be loaded
+    pub dmp_loadable: bool,
+    /// temperature conversion
+    pub temp_formula: TempFormula,
+    /// accel offset register layout
+    pub accel_offset_scaling: AccelOffsetScaling,
+    /// aux I2C master present
+    pub aux_i2c_master: bool,
+    /// max gyro output data rate in Hz
+    pub max_gyro_odr: u32,
+    /// supply currents of the power model
+    pub supply_current: CurrentTable,
+    /// clone assessment, None until
+    /// [`detect_clone_heuristics`](crate::Mpu6050::detect_clone_heuristics) ran
+    pub clone_assessment: Option<CloneAssessment>,
+    /// motion detection engine, unverified until
+    /// [`verify_motion_detection`](crate::Mpu6050::verify_motion_detection) ran
+    pub motion_engine: MotionEngineStatus,
+}
+
+impl ChipCapabilities {
+    /// The capabilities with `assessment` recorded. A likely clone is not assumed to load DMP
+    /// firmware: the clones seen so far either lack the DMP or run it unreliably
+    pub const fn with_clone_assessment(mut self, assessment: CloneAssessment) -> Self {
+        self.clone_assessment = Some(assessment);
+        if assessment.is_likely_clone() {
+            self.dmp_loadable = false;
+        }
+        self
+    }
+}
+
+/// Functional state of the motion detection engine, see [`motion_verify`](crate::motion_verify)
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum MotionEngineStatus {
+    /// never verified: accepting MOT_THR and MOT_DUR says nothing about MOT_INT
+    Unverified,
+    /// MOT_INT latched on a stimulus
+    Verified,
+    /// MOT_INT did not latch on a stimulus known to be there
+    Broken,
+}
+
+/// Driver feature, for [`Mpu6050Error::Unsupported`](crate::Mpu6050Error::Unsupported)
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Capability {
+    /// FIFO
+    Fifo,
+    /// DMP firmware
+    Dmp,
+    /// aux I2C master
+    AuxI2cMaster,
+    /// accel offset registers of a known layout
+    AccelOffsets,
+}
+
+/// Capability table, before functional probes. Unknown parts get only what every clone
+/// seen so far has: no FIFO, DMP or aux master is assumed
+pub const fn capabilities(variant: ChipVariant) -> ChipCapabilities {
+    match variant {
+        ChipVariant::Mpu6050 => ChipCapabilities {
+            variant,
+            fifo_available: true,
+            dmp_loadable: true,
+            temp_formula: TempFormula::Mpu6050,
+            accel_offset_scaling: AccelOffsetScaling::Mpu6050,
+            aux_i2c_master: true,
+            max_gyro_odr: 8000,
+            supply_current: MPU6050_CURRENT,
+            clone_assessment: None,
+            motion_engine: MotionEngineStatus::Unverified,
+        },
+        ChipVariant::Mpu6500 | ChipVariant::Mpu9250 => ChipCapabilities {
+            variant,
+            fifo_available: true,
+            dmp_loadable: true,
+            temp_formula: TempFormula::Mpu6500,
+            accel_offset_scaling: AccelOffsetScaling::Mpu6500,
+            aux_i2c_master: true,
+            max_gyro_odr: 32000,
+            supply_current: MPU6500_CURRENT,
+            clone_assessment: None,
+            motion_engine: MotionEngineStatus::Unverified,
+        },
+        ChipVariant::Unknown(_) => ChipCapabilities {
+            variant,
+            fifo_available: false,
+            dmp_loadable: false,
+            temp_formula: TempFormula::Mpu6050,
+            accel_offset_scaling: AccelOffsetScaling::Unknown,
+            aux_i2c_master: false,
+            max_gyro_odr: 8000,
+            supply_current: MPU6050_CURRENT,
+            clone_assessment: None,
+            motion_engine: MotionEngineStatus::Unverified,
+        },
+    }
+}
+
+/// Register range the raw reads refuse, see [`DENIED_RANGES`]
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct DeniedRange {
+    /// first address of the range
+    pub first: u8,
+    /// last address of the range, inclusive
+    pub last: u8,
+    /// what reading the range is known to do
+    pub hazard: &'static str,
+}
+
+impl DeniedRange {
+    /// true if a burst of `len` bytes from `reg` reads part of the range. The address
+    /// increments through the burst, FIFO_R_W excepted
+    pub const fn overlaps(&self, reg: u8, len: usize) -> bool {
+        if len == 0 {
+            return false;
+        }
+        let last = if reg == FIFO_R_W {
+            reg as usize
+        } else {
+            reg as usize + len - 1
+        };
+        reg <= self.last && last >= self.first as usize
+    }
+}
+
+impl core::fmt::Display for DeniedRange {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        if self.first == self.last {
+            write!(f, "0x{:02x} ({})", self.first, self.hazard)
+        } else {
+            write!(
+                f,
+                "0x{:02x}..=0x{:02x} ({})",
+                self.first, self.last, self.hazard
+            )
+        }
+    }
+}
+
+/// Undocumented addresses with a known hazard, refused by the raw reads of the driver
+/// (`read_bytes`, `read_byte`, `read_word`) with
+/// [`Mpu6050Error::DeniedRegisterRange`](crate::Mpu6050Error::DeniedRegisterRange) unless
+/// the driver was built with
+/// [`unsafe_allow_undocumented`](crate::Mpu6050Builder::unsafe_allow_undocumented).
+///
+/// * 0x62: clones lock up their aux I2C master after reads around 0x62 to 0x67, of that area
+///   only 0x62 is outside the register map. The documented I2C_SLV0_DO to
+///   I2C_MST_DELAY_CTRL stay readable
+/// * 0x6d to 0x71: the undocumented DMP memory interface, a read of the memory data register
+///   advances the memory address and returns DMP memory of genuine parts, garbage on clones
+///
+/// No address of the register map is denied, typed register access never fails here.
+pub const DENIED_RANGES: &[DeniedRange] = &[
+    DeniedRange {
+        first: 0x62,
+        last: 0x62,
+        hazard: "aux I2C master lockup on clones",
+    },
+    DeniedRange {
+        first: 0x6d,
+        last: 0x71,
+        hazard: "undocumented DMP memory interface, reads advance the memory address",
+    },
+];
+
+/// The first of [`DENIED_RANGES`] a burst of `len` bytes from `reg` reads part of
+pub fn denied_range(reg: u8, len: usize) -> Option<DeniedRange> {
+    DENIED_RANGES
+        .iter()
+        .find(|range| range.overlaps(reg, len))
+        .copied()
+}
+
+/// Sign of a clone found by
+/// [`detect_clone_heuristics`](crate::Mpu6050::detect_clone_heuristics), see
+/// [`clone_detect`](crate::clone_detect)
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum CloneSign {
+    /// WHO_AM_I answers none of the InvenSense ids of [`ChipVariant`]
+    ForeignWhoAmI,
+    /// WHO_AM_I answers 0x69 at 0x69: the register of a genuine part does not reflect AD0
+    WhoAmIFollowsAd0,
+    /// SELF_TEST_X to SELF_TEST_A all 0x00 or all 0xff, no factory trim
+    BlankSelfTestTrim,
+    /// the accel offset registers all 0x00 or all 0xff, no factory trim
+    BlankAccelTrim,
+    /// an accel self-test response outside the range of the factory trim codes
+    SelfTestOutOfRange,
+}
+
+impl CloneSign {
+    /// all signs, in bit order
+    pub const ALL: [CloneSign; 5] = [
+        CloneSign::ForeignWhoAmI,
+        CloneSign::WhoAmIFollowsAd0,
+        CloneSign::BlankSelfTestTrim,
+        CloneSign::BlankAccelTrim,
+        CloneSign::SelfTestOutOfRange,
+    ];
+
+    /// bit in [`CloneEvidence::bits`]
+    pub const fn bit(self) -> u8 {
+        1 << self as u8
+    }
+
+    /// Weight in [`CloneEvidence::score`]: 2 for a sign no genuine part shows, 1 for one a
+    /// genuine part shows after a user wrote the registers
+    pub const fn weight(self) -> u8 {
+        match self {
+            CloneSign::BlankSelfTestTrim | CloneSign::BlankAccelTrim => 1,
+            CloneSign::ForeignWhoAmI
+            | CloneSign::WhoAmIFollowsAd0
+            | CloneSign::SelfTestOutOfRange => 2,
+        }
+    }
+}
+
+/// Set of clone signs
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub struct CloneEvidence(u8);
+
+impl CloneEvidence {
+    /// no sign
+    pub const EMPTY: CloneEvidence = CloneEvidence(0);
+
+    /// from bits of [`CloneSign::bit`], unknown bits are dropped
+    pub fn from_bits(bits: u8) -> Self {
+        let known = CloneSign::ALL
+            .iter()
+            .fold(0, |mask, sign| mask | sign.bit());
+        Self(bits & known)
+    }
+
+    /// bits of [`CloneSign::bit`]
+    pub const fn bits(self) -> u8 {
+        self.0
+    }
+
+    /// the set with `sign` added
+    pub const fn with(self, sign: CloneSign) -> Self {
+        Self(self.0 | sign.bit())
+    }
+
+    /// signs in either set
+    pub const fn union(self, other: Self) -> Self {
+        Self(self.0 | other.0)
+    }
+
+    /// true if `sign` is part of the set
+    pub const fn contains(self, sign: CloneSign) -> bool {
+        self.0 & sign.bit() != 0
+    }
+
+    /// true if no sign is part of the set
+    pub const fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+
+    /// signs in the set, in bit order
+    pub fn iter(self) -> impl Iterator<Item = CloneSign> {
+        CloneSign::ALL
+            .into_iter()
+            .filter(move |sign| self.contains(*sign))
+    }
+
+    /// sum of the [weights](CloneSign::weight) of the signs
+    pub fn score(self) -> u8 {
+        self.iter().map(CloneSign::weight).sum()
+    }
+}
+
+/// [`CloneEvidence::score`] from which a part is [`CloneAssessment::LikelyClone`]
+pub const LIKELY_CLONE_SCORE: u8 = 2;
+
+/// Result of [`detect_clone_heuristics`](crate::Mpu6050::detect_clone_heuristics)
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum CloneAssessment {
+    /// every probe ran and found no sign
+    LikelyGenuine,
+    /// signs scoring at least [`LIKELY_CLONE_SCORE`]
+    LikelyClone {
+        /// the signs found
+        evidence: CloneEvidence,
+    },
+    /// a probe failed or did not apply, or the signs score below [`LIKELY_CLONE_SCORE`]
+    Inconclusive,
+}
+
+impl CloneAssessment {
+    /// Assessment of `evidence`, `complete` if every probe ran
+    pub fn from_evidence(evidence: CloneEvidence, complete: bool) -> Self {
+        if evidence.score() >= LIKELY_CLONE_SCORE {
+            CloneAssessment::LikelyClone { evidence }
+        } else if evidence.is_empty() && complete {
+            CloneAssessment::LikelyGenuine
+        } else {
+            CloneAssessment::Inconclusive
+        }
+    }
+
+    /// true for [`LikelyClone`](CloneAssessment::LikelyClone)
+    pub const fn is_likely_clone(self) -> bool {
+        matches!(self, CloneAssessment::LikelyClone { .. })
+    }
+}
@@ -0,0 +1,237 @@
+//! Two sensors read as one, for differential motion such as vibration isolation
+//! measurements.
+//!
+//! A [`DifferentialPair`] owns the sensor on the vibration source and the one on the isolated
+//! payload. Both can sit on one bus at 0x68 and 0x69 (hand each driver its own handle to the
+//! shared bus) or on separate buses. The pair only accepts configuration changes for both,
+//! and verifies after each change that the configuration registers of both are identical.
+//!
+//! [`DifferentialPair::read_pair`] reads the source, then the payload, with nothing in
+//! between. The skew between the two reads is what limits coherence at high frequencies: with
+//! a clock it is measured, and a sample is flagged when the skew exceeds a fraction of the
+//! sample interval.
+
+use core::fmt::{self, Debug, Display};
+
+use glam::Vec3A;
+
+#[cfg(feature = "driver")]
+use embedded_hal::blocking::{
+    delay::DelayMs,
+    i2c::{Write, WriteRead},
+};
+
+use crate::axis_map::AxisMap;
+use crate::config::ConfigDiff;
+#[cfg(feature = "driver")]
+use crate::settings::Mpu6050Settings;
+use crate::time_math::{elapsed_us, Clock};
+use crate::{Mpu6050, Mpu6050Error, MpuSample};
+
+/// Default max skew between the reads of a pair, as fraction of the sample interval
+pub const DEFAULT_MAX_SKEW_FRACTION: f32 = 0.1;
+
+/// Errors of a [`DifferentialPair`]
+#[derive(Debug)]
+pub enum PairError<E> {
+    /// error of the source sensor
+    Source(Mpu6050Error<E>),
+    /// error of the payload sensor
+    Payload(Mpu6050Error<E>),
+    /// configuration registers differ, source to payload
+    Diverged(ConfigDiff),
+}
+
+impl<E: Display> Display for PairError<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PairError::Source(error) => write!(f, "source sensor: {}", error),
+            PairError::Payload(error) => write!(f, "payload sensor: {}", error),
+            PairError::Diverged(diff) => write!(f, "configurations diverged: {}", diff),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<E: Debug + Display> std::error::Error for PairError<E> {}
+
+/// Samples of both sensors, in the common frame
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct PairSample {
+    /// source sensor, mapped by its axis map
+    pub source: MpuSample,
+    /// payload sensor, mapped by its axis map
+    pub payload: MpuSample,
+    /// time the source read started in µs, None without a clock
+    pub t_us: Option<u64>,
+    /// time from the start of the source read to the start of the payload read in µs, None
+    /// without a clock
+    pub skew_us: Option<u64>,
+    /// skew exceeded the configured fraction of the sample interval
+    pub coherence_degraded: bool,
+}
+
+/// Payload motion relative to the source
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Differential {
+    /// accel difference in g
+    pub acc: Vec3A,
+    /// gyro difference in rad/s
+    pub gyro: Vec3A,
+}
+
+impl PairSample {
+    /// payload minus source
+    pub fn differential(&self) -> Differential {
+        Differential {
+            acc: self.payload.acc - self.source.acc,
+            gyro: self.payload.gyro - self.source.gyro,
+        }
+    }
+}
+
+fn mapped(sample: MpuSample, map: &AxisMap) -> MpuSample {
+    MpuSample::new(map.apply(sample.acc), map.apply(sample.gyro), sample.temp)
+        .with_provenance(sample.provenance())
+        .with_settling(sample.settling())
+        .with_out_of_band(sample.out_of_band())
+}
+
+/// Source and payload sensor kept in configuration lockstep
+pub struct DifferentialPair<IA, IB> {
+    source: Mpu6050<IA>,
+    payload: Mpu6050<IB>,
+    source_map: AxisMap,
+    payload_map: AxisMap,
+    sample_interval_us: Option<f32>,
+    max_skew_fraction: f32,
+}
+
+impl<IA, IB> DifferentialPair<IA, IB> {
+    /// Pair of already built drivers, both mounted in the common frame
+    pub fn new(source: Mpu6050<IA>, payload: Mpu6050<IB>) -> Self {
+        Self {
+            source,
+            payload,
+            source_map: AxisMap::IDENTITY,
+            payload_map: AxisMap::IDENTITY,
+            sample_interval_us: None,
+            max_skew_fraction: DEFAULT_MAX_SKEW_FRACTION,
+        }
+    }
+
+    /// mounting of each sensor relative to the common frame
+    pub fn with_axis_maps(mut self, source: AxisMap, payload: AxisMap) -> Self {
+        self.source_map = source;
+        self.payload_map = payload;
+        self
+    }
+
+    /// max skew as fraction of the sample interval before samples are flagged
+    pub fn with_max_skew_fraction(mut self, fraction: f32) -> Self {
+        self.max_skew_fraction = fraction;
+        self
+    }
+
+    /// Sample interval in µs for the coherence check. Set by
+    /// [`apply_settings`](Self::apply_settings), None disables the check
+    pub fn set_sample_interval_us(&mut self, interval_us: Option<f32>) {
+        self.sample_interval_us = interval_us;
+    }
+
+    /// the source driver, read only: changes must go through the pair
+    pub fn source(&self) -> &Mpu6050<IA> {
+        &self.source
+    }
+
+    /// the payload driver, read only: changes must go through the pair
+    pub fn payload(&self) -> &Mpu6050<IB> {
+        &self.payload
+    }
+
+    /// releases both drivers, (source, payload)
+    pub fn into_inner(self) -> (Mpu6050<IA>, Mpu6050<IB>) {
+        (self.source, self.payload)
+    }
+
+    fn pair_sample(
+        &self,
+        source: MpuSample,
+        payload: MpuSample,
+        t_us: Option<u64>,
+        skew_us: Option<u64>,
+    ) -> PairSample {
+        let coherence_degraded = match (skew_us, self.sample_interval_us) {
+            (Some(skew), Some(interval)) => skew as f32 > interval * self.max_skew_fraction,
+            _ => false,
+        };
+        PairSample {
+            source: mapped(source, &self.source_map),
+            payload: mapped(payload, &self.payload_map),
+            t_us,
+            skew_us,
+            coherence_degraded,
+        }
+    }
+}
+
+#[cfg(feature = "driver")]
+impl<IA, IB, E> DifferentialPair<IA, IB>
+where
+    IA: Write<Error = E> + WriteRead<Error = E>,
+    IB: Write<Error = E> + WriteRead<Error = E>,
+{
+    /// Inits both sensors, then checks lockstep
+    pub fn init<D: DelayMs<u8>>(&mut self, delay: &mut D) -> Result<(), PairError<E>> {
+        self.source.init(delay).map_err(PairError::Source)?;
+        self.payload.init(delay).map_err(PairError::Payload)?;
+        self.check_lockstep()
+    }
+
+    /// Applies `settings` to both sensors, then checks lockstep. Invalid settings are
+    /// rejected before either sensor is written
+    pub fn apply_settings(&mut self, settings: &Mpu6050Settings) -> Result<(), PairError<E>> {
+        settings
+            .validate()
+            .map_err(|error| PairError::Source(Mpu6050Error::InvalidSettings(error)))?;
+        self.source
+            .apply_settings(settings)
+            .map_err(PairError::Source)?;
+        self.payload
+            .apply_settings(settings)
+            .map_err(PairError::Payload)?;
+        self.sample_interval_us = Some(1e6 / settings.sample_rate_hz());
+        self.check_lockstep()
+    }
+
+    /// Reads the configuration registers of both sensors, [`PairError::Diverged`] if they
+    /// differ
+    pub fn check_lockstep(&mut self) -> Result<(), PairError<E>> {
+        let source = self.source.read_config().map_err(PairError::Source)?;
+        let payload = self.payload.read_config().map_err(PairError::Payload)?;
+        let diff = source.diff(&payload);
+        if diff.is_empty() {
+            Ok(())
+        } else {
+            Err(PairError::Diverged(diff))
+        }
+    }
+
+    /// Reads source, then payload, without timestamps
+    pub fn read_pair(&mut self) -> Result<PairSample, PairError<E>> {
+        let source = self.source.read_sample().map_err(PairError::Source)?;
+        let payload = self.payload.read_sample().map_err(PairError::Payload)?;
+        Ok(self.pair_sample(source, payload, None, None))
+    }
+
+    /// Reads source, then payload, `clock` is read before each read. A clock narrower than
+    /// 64 bits goes through an [`ExtendedClock`](crate::time_math::ExtendedClock) first
+    pub fn read_pair_timed<C: Clock>(&mut self, mut clock: C) -> Result<PairSample, PairError<E>> {
+        let t_source = clock.now_us();
+        let source = self.source.read_sample().map_err(PairError::Source)?;
+        let t_payload = clock.now_us();
+        let payload = self.payload.read_sample().map_err(PairError::Payload)?;
+        let skew = elapsed_us(t_source, t_payload);
+        Ok(self.pair_sample(source, payload, Some(t_source), Some(skew)))
+    }
+}
@@ -0,0 +1,94 @@
+//! Construction without bus contact, for boards sequencing the power of their devices.
+//!
+//! A driver built with [`defer_bus_contact`](crate::Mpu6050Builder::defer_bus_contact) is
+//! dormant: it exists, can be configured and queried, but attempts no I2C transaction until
+//! [`activate`](Mpu6050::activate). Every method that would touch the bus fails with
+//! [`Mpu6050Error::NotActivated`] instead, before the transaction. The check sits below all
+//! register accesses and the address probe, so no path reaches the bus around it; methods
+//! that can finish without a register access (an empty FIFO schema, no power governor) check
+//! first too. Refused accesses are neither counted in the [`io_stats`](Mpu6050::io_stats) nor
+//! seen by the connection monitor.
+//!
+//! `activate` ends the dormant state and runs [`init`](Mpu6050::init), with the owned delay
+//! or one passed in like `init`. A failed init leaves the driver active, it can be retried
+//! with `init`. A driver built without `defer_bus_contact` is active from the start, its
+//! `activate` is `init`.
+//! ```
+//! use embedded_hal::blocking::delay::DelayMs;
+//! use embedded_hal::blocking::i2c::{Write, WriteRead};
+//! use mpu6050::{Mpu6050, Mpu6050Builder, Mpu6050Error};
+//!
+//! // constructed with the other drivers at bring-up, started once VDD is up
+//! fn start<I, E>(
+//!     mpu: &mut Mpu6050<I>,
+//!     delay: &mut impl DelayMs<u8>,
+//! ) -> Result<(), Mpu6050Error<E>>
+//! where
+//!     I: Write<Error = E> + WriteRead<Error = E>,
+//! {
+//!     assert!(mpu.is_dormant());
+//!     // VDD comes up 20 ms after VLOGIC
+//!     delay.delay_ms(20);
+//!     mpu.activate(delay)
+//! }
+//! ```
+
+#[cfg(feature = "driver")]
+use embedded_hal::blocking::{
+    delay::DelayMs,
+    i2c::{Write, WriteRead},
+};
+
+use crate::Mpu6050;
+#[cfg(feature = "driver")]
+use crate::Mpu6050Error;
+
+impl<I, D> Mpu6050<I, D> {
+    /// built with [`defer_bus_contact`](crate::Mpu6050Builder::defer_bus_contact) and not
+    /// activated yet
+    pub fn is_dormant(&self) -> bool {
+        self.dormant
+    }
+}
+
+#[cfg(feature = "driver")]
+impl<I, D, E> Mpu6050<I, D>
+where
+    I: Write<Error = E> + WriteRead<Error = E>,
+{
+    /// refuses bus access while dormant
+    pub(crate) fn check_active(&self) -> Result<(), Mpu6050Error<E>> {
+        match self.dormant {
+            true => Err(Mpu6050Error::NotActivated),
+            false => Ok(()),
+        }
+    }
+}
+
+/// Explicit delay, the driver owns none
+#[cfg(feature = "driver")]
+impl<I, E> Mpu6050<I>
+where
+    I: Write<Error = E> + WriteRead<Error = E>,
+{
+    /// Ends the dormant state and runs [`init`](Self::init), the first bus traffic of a
+    /// dormant driver
+    pub fn activate<D: DelayMs<u8>>(&mut self, delay: &mut D) -> Result<(), Mpu6050Error<E>> {
+        self.dormant = false;
+        self.init_using(delay)
+    }
+}
+
+/// Owned delay
+#[cfg(feature = "driver")]
+impl<I, D, E> Mpu6050<I, D>
+where
+    I: Write<Error = E> + WriteRead<Error = E>,
+    D: DelayMs<u8>,
+{
+    /// [`activate`](Mpu6050::activate) with the owned delay
+    pub fn activate(&mut self) -> Result<(), Mpu6050Error<E>> {
+        self.dormant = false;
+        self.init()
+    }
+}
@@ -0,0 +1,226 @@
+//! Orientation error accumulated by integrating the gyro between corrections.
+//!
+//! Between two accelerometer (or other absolute) corrections, an orientation is the integral
+//! of the gyro rate and collects four independent errors. [`ErrorBudget`] holds the figures
+//! they depend on and [`ErrorBudget::estimate`] computes the 1-sigma error per axis after a
+//! time `t` since the last correction, in degrees:
+//!
+//! | contribution | formula | |
+//! |---|---|---|
+//! | angle random walk | `N √t` | `N` rate noise density in °/s/√Hz |
+//! | bias drift | `B t` | `B` bias instability in °/s |
+//! | quantization | `q / √12 · √(t / f)` | `q` rate LSB in °/s, `f` update rate |
+//! | integration | `α t / (2 f)` | `α` angular acceleration in °/s² |
+//!
+//! The total is their root sum square.
+//!
+//! * Angle random walk is the integral of white rate noise, its standard deviation grows
+//!   with the square root of time (IEEE Std 952-1997, Annex C; O. J. Woodman, "An introduction
+//!   to inertial navigation", University of Cambridge, UCAM-CL-TR-696, 2007, section 4.2.2).
+//!   `N` defaults to the datasheet figure [`GYRO_NOISE_DENSITY_DPS`].
+//! * A bias left uncorrected integrates linearly (Woodman, section 4.2.1). The bias
+//!   instability of the Allan deviation is taken as that bias over the whole horizon, which
+//!   bounds its random walk from above. The datasheet has no figure, [`DEFAULT_BIAS_INSTABILITY_DPS`]
+//!   is typical of measured parts; use the Allan deviation of your own parts where it matters.
+//! * Rounding to the LSB adds uniform noise of variance `q² / 12` per sample (B. Widrow,
+//!   I. Kollár, "Quantization Noise", Cambridge University Press, 2008, chapter 4), summed
+//!   over `t f` samples of `1 / f` each.
+//! * Rectangular integration at `f` holds the rate for a whole update, each update errs by
+//!   `α / (2 f²)` under an angular acceleration `α`, `t f` updates add up linearly.
+//!   [`DEFAULT_ANGULAR_ACCEL_DPS2`] is slow handheld motion, replace it with the motion
+//!   profile of the application.
+//!
+//! [`Mpu6050::error_budget`] fills in the gyro range and output data rate of the driver. An
+//! [`ErrorTracker`] applies the same model at runtime to the time actually elapsed since the
+//! last correction, for filters reporting [`current_error_estimate`](ErrorTracker::current_error_estimate).
+//! ```
+//! use mpu6050::device::GyroRange;
+//! use mpu6050::error_budget::ErrorBudget;
+//!
+//! let estimate = ErrorBudget::new(GyroRange::D250, 100.).with_angular_acceleration(0.).estimate(4.);
+//! // 0.005 °/s/√Hz for 4 s
+//! assert!((estimate.angle_random_walk_deg - 0.01).abs() < 1e-6);
+//! assert!((estimate.bias_drift_deg - 0.04).abs() < 1e-6);
+//! assert_eq!(estimate.integration_deg, 0.);
+//! ```
+
+use core::fmt;
+
+use crate::decimal::Decimal;
+use crate::device::{GyroRange, GYRO_NOISE_DENSITY_DPS};
+#[cfg(not(feature = "std"))]
+use crate::math::FloatExt;
+use crate::Mpu6050;
+
+/// Bias instability in °/s assumed unless given, typical of Allan deviations measured on
+/// MPU6050 parts (36 °/h)
+pub const DEFAULT_BIAS_INSTABILITY_DPS: f32 = 0.01;
+/// Angular acceleration in °/s² assumed unless given, slow handheld motion
+pub const DEFAULT_ANGULAR_ACCEL_DPS2: f32 = 100.;
+
+/// Figures of the error model, see the [module docs](self)
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct ErrorBudget {
+    /// rate noise density in °/s/√Hz
+    pub noise_density_dps: f32,
+    /// bias instability in °/s
+    pub bias_instability_dps: f32,
+    /// rate resolution in °/s
+    pub lsb_dps: f32,
+    /// gyro update rate in Hz
+    pub update_rate_hz: f32,
+    /// angular acceleration in °/s²
+    pub angular_accel_dps2: f32,
+}
+
+impl ErrorBudget {
+    /// datasheet noise density and default figures for `gyro` updated at `update_rate_hz`
+    pub fn new(gyro: GyroRange, update_rate_hz: f32) -> Self {
+        Self {
+            noise_density_dps: GYRO_NOISE_DENSITY_DPS,
+            bias_instability_dps: DEFAULT_BIAS_INSTABILITY_DPS,
+            lsb_dps: gyro.resolution_dps_per_lsb(),
+            update_rate_hz,
+            angular_accel_dps2: DEFAULT_ANGULAR_ACCEL_DPS2,
+        }
+    }
+
+    /// with a measured noise density in °/s/√Hz
+    pub const fn with_noise_density(self, noise_density_dps: f32) -> Self {
+        Self {
+            noise_density_dps,
+            ..self
+        }
+    }
+
+    /// with a measured bias instability in °/s
+    pub const fn with_bias_instability(self, bias_instability_dps: f32) -> Self {
+        Self {
+            bias_instability_dps,
+            ..self
+        }
+    }
+
+    /// with the angular acceleration of the application in °/s²
+    pub const fn with_angular_acceleration(self, angular_accel_dps2: f32) -> Self {
+        Self {
+            angular_accel_dps2,
+            ..self
+        }
+    }
+
+    /// 1-sigma error per axis `horizon_s` seconds after a correction
+    pub fn estimate(&self, horizon_s: f32) -> ErrorEstimate {
+        let t = horizon_s.max(0.);
+        let sample_s = 1. / self.update_rate_hz;
+        let angle_random_walk_deg = self.noise_density_dps * t.sqrt();
+        let bias_drift_deg = self.bias_instability_dps * t;
+        let quantization_deg = self.lsb_dps / 12f32.sqrt() * (t * sample_s).sqrt();
+        let integration_deg = self.angular_accel_dps2 * t * sample_s / 2.;
+        let total_deg = [
+            angle_random_walk_deg,
+            bias_drift_deg,
+            quantization_deg,
+            integration_deg,
+        ]
+        .iter()
+        .map(|e| e * e)
+        .sum::<f32>()
+        .sqrt();
+        ErrorEstimate {
+            horizon_s: t,
+            angle_random_walk_deg,
+            bias_drift_deg,
+            quantization_deg,
+            integration_deg,
+            total_deg,
+        }
+    }
+}
+
+/// Itemized 1-sigma orientation error per axis, in degrees
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct ErrorEstimate {
+    /// time since the correction in s
+    pub horizon_s: f32,
+    /// integrated rate noise
+    pub angle_random_walk_deg: f32,
+    /// integrated bias
+    pub bias_drift_deg: f32,
+    /// integrated rounding
+    pub quantization_deg: f32,
+    /// rectangular integration
+    pub integration_deg: f32,
+    /// root sum square of the above
+    pub total_deg: f32,
+}
+
+impl fmt::Display for ErrorEstimate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "orientation error {} s after correction, 1 sigma per axis:",
+            Decimal(self.horizon_s)
+        )?;
+        let rows = [
+            ("angle random walk", self.angle_random_walk_deg),
+            ("bias drift", self.bias_drift_deg),
+            ("quantization", self.quantization_deg),
+            ("integration", self.integration_deg),
+        ];
+        for (name, value) in rows {
+            writeln!(f, "  {:<18} {:.4}°", name, Decimal(value))?;
+        }
+        write!(f, "  {:<18} {:.4}°", "total (rss)", Decimal(self.total_deg))
+    }
+}
+
+/// Time since the last correction, with its [`ErrorEstimate`]
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct ErrorTracker {
+    budget: ErrorBudget,
+    elapsed_s: f32,
+}
+
+impl ErrorTracker {
+    /// tracker applying `budget`, starting at a correction
+    pub fn new(budget: ErrorBudget) -> Self {
+        Self {
+            budget,
+            elapsed_s: 0.,
+        }
+    }
+
+    /// model applied
+    pub fn budget(&self) -> &ErrorBudget {
+        &self.budget
+    }
+
+    /// gyro integrated over `dt_s` seconds without correction
+    pub fn advance(&mut self, dt_s: f32) {
+        self.elapsed_s += dt_s.max(0.);
+    }
+
+    /// orientation corrected, the error starts over
+    pub fn correct(&mut self) {
+        self.elapsed_s = 0.;
+    }
+
+    /// seconds since the last correction
+    pub fn elapsed_s(&self) -> f32 {
+        self.elapsed_s
+    }
+
+    /// [`ErrorBudget::estimate`] at the elapsed time
+    pub fn current_error_estimate(&self) -> ErrorEstimate {
+        self.budget.estimate(self.elapsed_s)
+    }
+}
+
+impl<I, D> Mpu6050<I, D> {
+    /// [`ErrorBudget`] of the active gyro range and output data rate, default figures
+    /// otherwise. No bus access
+    pub fn error_budget(&self) -> ErrorBudget {
+        ErrorBudget::new(self.gyro_range, self.check_aliasing().odr_hz)
+    }
+}
@@ -0,0 +1,517 @@
+//! Sensor error models for the simulated samples, with their ground truth.
+//!
+//! The [`test_fixtures`](crate::test_fixtures) are noise free: every frame is the truth
+//! rounded to the nearest count. Calibration, bias tracking and fusion need readings with
+//! the errors of a real chip and the truth behind them, so a test computes the true error of
+//! an algorithm instead of its agreement with itself. A [`Simulator`] takes the ideal
+//! [`Truth`] of every sample, from a [`Fixture`] or any trajectory, applies an
+//! [`ErrorModel`] and emits the scaled [`MpuSample`] and the register [`RawFrame`] next to a
+//! [`SimTruth`]: the true orientation, the true biases at that sample and the event injected
+//! if any.
+//!
+//! #### Model
+//! Every component is `None` or `false` in [`ErrorModel::NONE`] and enabled on its own. For
+//! the true specific force `a` in g and rate `ω` in °/s:
+//!
+//! ```text
+//! acc  = (I + diag(s) + C) a + b_a + k_a (T - T_ref) + n_a
+//! gyro = ω + b_g[k] + k_g (T - T_ref) + n_g
+//! b_g[k + 1] = b_g[k] + K √dt w[k]
+//! ```
+//!
+//! * [`WhiteNoise`]: `n` is white gaussian noise of standard deviation `N √B`, `N` the noise
+//!   density, `B` the noise bandwidth: the DLPF bandwidth, or half the sample rate without
+//!   it. The datasheet densities are 400 µg/√Hz and 0.005 °/s/√Hz (MPU-6000/6050 product
+//!   specification PS-MPU-6000A, rev. 3.4, tables 6.1 and 6.2)
+//! * [`GyroBiasWalk`]: the rate random walk of IEEE Std 952-1997, annex C: the bias is a
+//!   Wiener process of coefficient `K` in °/s/√s, `w` standard normal. Woodman, An
+//!   introduction to inertial navigation (UCAM-CL-TR-696, 2007), section 3.2, relates it to
+//!   the bias instability of an Allan deviation plot
+//! * [`AccelErrors`]: a constant bias `b_a`, the scale factor errors `s` and the cross-axis
+//!   sensitivities `C` (zero diagonal), the linearized sensor model of Titterton and
+//!   Weston, Strapdown Inertial Navigation Technology (2nd ed., 2004), section 8.4. The
+//!   datasheet bounds are ±3 % scale and ±2 % cross-axis
+//! * [`TempBias`]: biases linear in the die temperature `T`, the first order term of the
+//!   datasheet's zero offset variation over temperature, ±35 mg on X and Y and ±20 °/s over
+//!   the operating range. `T` comes from the truth, or from a scripted
+//!   [`temp_profile`](ErrorModel::temp_profile) over time
+//! * [`quantization`](ErrorModel::quantization): the emitted sample is the driver's scaling
+//!   of the counts, rounded to nearest even and saturating at the configured ranges. The
+//!   rounding error is uniform over ±½ LSB for signals spanning many LSB (Widrow and Kollár,
+//!   Quantization Noise, 2008, chapter 4). Without it the sample keeps the exact values; the
+//!   frame is always in counts
+//! * [`Dropouts`]: with a probability per sample the frame is replaced: held from the
+//!   previous sample, all zero, or one byte corrupted, and the sample is scaled from it
+//!
+//! #### Determinism
+//! Each random component draws from its own xorshift64* generator, seeded from the seed of
+//! [`Simulator::new`] and the component. The same seed, model and truths emit the same
+//! samples bit for bit, and enabling one component leaves the draws of the others as they
+//! were.
+//!
+//! ```
+//! use mpu6050::error_model::{ErrorModel, GyroBiasWalk, Simulator};
+//! use mpu6050::test_fixtures::STATIONARY;
+//! use mpu6050::Vec3A;
+//!
+//! let model = ErrorModel::MPU6050.with_gyro_bias(GyroBiasWalk {
+//!     initial_dps: Vec3A::new(0.5, -0.3, 0.2),
+//!     random_walk_dps_per_rt_s: 0.01,
+//! });
+//! let samples = Simulator::for_fixture(&STATIONARY, model, 7).run(&STATIONARY);
+//! assert_eq!(samples, Simulator::for_fixture(&STATIONARY, model, 7).run(&STATIONARY));
+//!
+//! // the true bias is known at every sample
+//! let last = samples.last().unwrap();
+//! assert_ne!(last.truth.gyro_bias_dps, Vec3A::new(0.5, -0.3, 0.2));
+//! assert_eq!(last.truth.truth.gyro_dps, Vec3A::ZERO);
+//! ```
+//!
+//! The bias estimate and bounded noise tests of the complementary filter and the gravity
+//! trim over six orientations run through it, asserting against the truth.
+//!
+//! Enable with the `test-fixtures` feature, or `test-util` which includes it.
+
+use std::vec::Vec;
+
+use glam::{Mat3A, Quat, Vec3A};
+
+use crate::device::{AccelRange, GyroRange};
+use crate::frame::{encode_frame, parse_frame, RawFrame, FRAME_LEN};
+use crate::scale::Pipeline;
+use crate::test_fixtures::{encode, Fixture, Truth};
+use crate::{MpuSample, PI_180};
+
+/// Datasheet noise densities of the MPU6050, see the [module docs](self)
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct WhiteNoise {
+    /// accelerometer noise density in g/√Hz
+    pub accel_g_per_rt_hz: f32,
+    /// gyro noise density in °/s/√Hz
+    pub gyro_dps_per_rt_hz: f32,
+    /// noise bandwidth in Hz, `None` for half the sample rate
+    pub bandwidth_hz: Option<f32>,
+}
+
+impl WhiteNoise {
+    /// 400 µg/√Hz and 0.005 °/s/√Hz over half the sample rate
+    pub const MPU6050: WhiteNoise = WhiteNoise {
+        accel_g_per_rt_hz: 400e-6,
+        gyro_dps_per_rt_hz: 0.005,
+        bandwidth_hz: None,
+    };
+}
+
+/// Gyro bias random walk, see the [module docs](self)
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct GyroBiasWalk {
+    /// bias of the first sample in °/s
+    pub initial_dps: Vec3A,
+    /// random walk coefficient per axis in °/s/√s, 0 for a constant bias
+    pub random_walk_dps_per_rt_s: f32,
+}
+
+/// Accelerometer bias, scale factor and cross-axis errors, see the [module docs](self)
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct AccelErrors {
+    /// bias in g
+    pub bias_g: Vec3A,
+    /// scale factor error per axis, 0.01 reads 1 % high
+    pub scale: Vec3A,
+    /// cross-axis sensitivity, the share of each other axis in an output: X of Y, X of Z, Y
+    /// of X, Y of Z, Z of X, Z of Y
+    pub cross_axis: [f32; 6],
+}
+
+impl AccelErrors {
+    /// `I + diag(scale) + C`, applied to the true specific force
+    pub fn matrix(&self) -> Mat3A {
+        let [xy, xz, yx, yz, zx, zy] = self.cross_axis;
+        let s = self.scale;
+        Mat3A::from_cols(
+            Vec3A::new(1. + s.x, yx, zx),
+            Vec3A::new(xy, 1. + s.y, zy),
+            Vec3A::new(xz, yz, 1. + s.z),
+        )
+    }
+}
+
+/// Biases linear in the die temperature, see the [module docs](self)
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct TempBias {
+    /// temperature of zero temperature bias in °C
+    pub reference_c: f32,
+    /// accelerometer bias per °C in g
+    pub accel_g_per_c: Vec3A,
+    /// gyro bias per °C in °/s
+    pub gyro_dps_per_c: Vec3A,
+}
+
+/// Frame replaced by a dropout
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum DropoutKind {
+    /// the previous frame again, a stale read
+    Hold,
+    /// all zero bytes, a device that stopped answering
+    Zero,
+    /// one byte XORed with a random nonzero mask
+    Corrupt,
+}
+
+/// Dropout or corruption events, see the [module docs](self)
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Dropouts {
+    /// probability per sample, 0..=1
+    pub probability: f32,
+    /// what replaces the frame
+    pub kind: DropoutKind,
+}
+
+/// Errors applied between the truth and the emitted samples, see the [module docs](self)
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct ErrorModel {
+    /// white noise on both sensors
+    pub white_noise: Option<WhiteNoise>,
+    /// gyro bias and its random walk
+    pub gyro_bias: Option<GyroBiasWalk>,
+    /// accelerometer bias, scale and cross-axis errors
+    pub accel: Option<AccelErrors>,
+    /// biases over temperature
+    pub temp_bias: Option<TempBias>,
+    /// die temperature over time, `(s, °C)` points at increasing times interpolated
+    /// linearly and held past the ends, replacing the temperature of the truth
+    pub temp_profile: Option<&'static [(f32, f32)]>,
+    /// emitted samples scaled from the counts
+    pub quantization: bool,
+    /// dropped or corrupted frames
+    pub dropouts: Option<Dropouts>,
+}
+
+impl ErrorModel {
+    /// no errors, the emitted samples are the truth
+    pub const NONE: ErrorModel = ErrorModel {
+        white_noise: None,
+        gyro_bias: None,
+        accel: None,
+        temp_bias: None,
+        temp_profile: None,
+        quantization: false,
+        dropouts: None,
+    };
+
+    /// datasheet white noise and quantization, the errors every chip has
+    pub const MPU6050: ErrorModel = ErrorModel::NONE
+        .with_white_noise(WhiteNoise::MPU6050)
+        .with_quantization(true);
+
+    /// with white noise
+    pub const fn with_white_noise(mut self, noise: WhiteNoise) -> Self {
+        self.white_noise = Some(noise);
+        self
+    }
+
+    /// with a gyro bias walk
+    pub const fn with_gyro_bias(mut self, walk: GyroBiasWalk) -> Self {
+        self.gyro_bias = Some(walk);
+        self
+    }
+
+    /// with accelerometer errors
+    pub const fn with_accel(mut self, errors: AccelErrors) -> Self {
+        self.accel = Some(errors);
+        self
+    }
+
+    /// with biases over temperature
+    pub const fn with_temp_bias(mut self, bias: TempBias) -> Self {
+        self.temp_bias = Some(bias);
+        self
+    }
+
+    /// with a scripted die temperature
+    pub const fn with_temp_profile(mut self, profile: &'static [(f32, f32)]) -> Self {
+        self.temp_profile = Some(profile);
+        self
+    }
+
+    /// with or without quantization
+    pub const fn with_quantization(mut self, quantization: bool) -> Self {
+        self.quantization = quantization;
+        self
+    }
+
+    /// with dropouts
+    pub const fn with_dropouts(mut self, dropouts: Dropouts) -> Self {
+        self.dropouts = Some(dropouts);
+        self
+    }
+}
+
+impl Default for ErrorModel {
+    fn default() -> Self {
+        Self::NONE
+    }
+}
+
+/// What was true at one emitted sample
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct SimTruth {
+    /// time from the first sample in s
+    pub time_s: f32,
+    /// the ideal reading, with the temperature of the profile if any
+    pub truth: Truth,
+    /// attitude of the sensor, body to world, integrated from the true rates or given
+    pub orientation: Quat,
+    /// gyro bias in °/s, the walk and the temperature term
+    pub gyro_bias_dps: Vec3A,
+    /// accelerometer bias in g, the constant and the temperature term
+    pub accel_bias_g: Vec3A,
+    /// dropout injected at this sample
+    pub event: Option<DropoutKind>,
+}
+
+/// One emitted sample with its truth
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct SimSample {
+    /// the reading an algorithm sees
+    pub sample: MpuSample,
+    /// the registers behind it
+    pub frame: RawFrame,
+    /// the ground truth
+    pub truth: SimTruth,
+}
+
+impl SimSample {
+    /// frame bytes as read from ACCEL_XOUT_H, for register mocks
+    pub fn bytes(&self) -> [u8; FRAME_LEN] {
+        encode_frame(&self.frame)
+    }
+}
+
+/// xorshift64*, the generator of the `chaos` module
+#[derive(Clone, Debug)]
+struct Rng(u64);
+
+impl Rng {
+    fn new(seed: u64, stream: u64) -> Self {
+        let state =
+            (seed ^ 0x9e37_79b9_7f4a_7c15).wrapping_add(stream.wrapping_mul(0xd1b5_4a32_d192_ed03));
+        // xorshift must not start at 0
+        Self(state.max(1))
+    }
+
+    /// uniform in 0..1, upper 24 bits as a fraction
+    fn fraction(&mut self) -> f32 {
+        self.0 ^= self.0 >> 12;
+        self.0 ^= self.0 << 25;
+        self.0 ^= self.0 >> 27;
+        let x = self.0.wrapping_mul(0x2545_f491_4f6c_dd1d);
+        (x >> 40) as f32 / (1u32 << 24) as f32
+    }
+
+    /// standard normal, Box-Muller
+    fn normal(&mut self) -> f32 {
+        let u = 1. - self.fraction();
+        let v = self.fraction();
+        (-2. * u.ln()).sqrt() * (core::f32::consts::TAU * v).cos()
+    }
+
+    fn normal_vec(&mut self) -> Vec3A {
+        Vec3A::new(self.normal(), self.normal(), self.normal())
+    }
+}
+
+/// Sample-level simulation of a sensor under an [`ErrorModel`], see the [module docs](self)
+#[derive(Clone, Debug)]
+pub struct Simulator {
+    model: ErrorModel,
+    accel_range: AccelRange,
+    gyro_range: GyroRange,
+    interval_s: f32,
+    pipeline: Pipeline,
+    idx: u64,
+    orientation: Quat,
+    walk_dps: Vec3A,
+    noise_rng: Rng,
+    walk_rng: Rng,
+    dropout_rng: Rng,
+    last: Option<(RawFrame, MpuSample)>,
+}
+
+impl Simulator {
+    /// simulator at the ranges, one sample every `interval_s`, level and at rest
+    pub fn new(
+        model: ErrorModel,
+        accel_range: AccelRange,
+        gyro_range: GyroRange,
+        interval_s: f32,
+        seed: u64,
+    ) -> Self {
+        Self {
+            model,
+            accel_range,
+            gyro_range,
+            interval_s,
+            pipeline: Pipeline::new(accel_range, gyro_range),
+            idx: 0,
+            orientation: Quat::IDENTITY,
+            walk_dps: model.gyro_bias.map_or(Vec3A::ZERO, |walk| walk.initial_dps),
+            noise_rng: Rng::new(seed, 1),
+            walk_rng: Rng::new(seed, 2),
+            dropout_rng: Rng::new(seed, 3),
+            last: None,
+        }
+    }
+
+    /// simulator at the ranges and interval of `fixture`
+    pub fn for_fixture(fixture: &Fixture, model: ErrorModel, seed: u64) -> Self {
+        Self::new(
+            model,
+            fixture.accel_range,
+            fixture.gyro_range,
+            fixture.interval_s,
+            seed,
+        )
+    }
+
+    /// same simulator starting at `orientation`
+    pub fn with_orientation(mut self, orientation: Quat) -> Self {
+        self.orientation = orientation;
+        self
+    }
+
+    /// model applied
+    pub fn model(&self) -> &ErrorModel {
+        &self.model
+    }
+
+    /// samples emitted so far
+    pub fn samples(&self) -> u64 {
+        self.idx
+    }
+
+    /// The next sample of `truth`. Its true orientation is the one reached by integrating
+    /// the true rates of the samples before it, each held over one interval
+    pub fn step(&mut self, truth: &Truth) -> SimSample {
+        self.step_at(truth, self.orientation)
+    }
+
+    /// The next sample of `truth` at a known true `orientation`, for trajectories with an
+    /// analytic attitude. Integration continues from it
+    pub fn step_at(&mut self, truth: &Truth, orientation: Quat) -> SimSample {
+        let time_s = self.idx as f32 * self.interval_s;
+        let mut truth = *truth;
+        if let Some(profile) = self.model.temp_profile {
+            truth.temp_c = profile_temp(profile, time_s, truth.temp_c);
+        }
+
+        let (mut gyro_bias, mut accel_bias) = (self.walk_dps, Vec3A::ZERO);
+        let mut acc = truth.acc_g;
+        if let Some(errors) = self.model.accel {
+            acc = errors.matrix() * acc;
+            accel_bias += errors.bias_g;
+        }
+        if let Some(bias) = self.model.temp_bias {
+            let delta = truth.temp_c - bias.reference_c;
+            accel_bias += bias.accel_g_per_c * delta;
+            gyro_bias += bias.gyro_dps_per_c * delta;
+        }
+        acc += accel_bias;
+        let mut gyro = truth.gyro_dps + gyro_bias;
+        if let Some(noise) = self.model.white_noise {
+            let bandwidth = noise.bandwidth_hz.unwrap_or(0.5 / self.interval_s);
+            let root = bandwidth.sqrt();
+            acc += self.noise_rng.normal_vec() * (noise.accel_g_per_rt_hz * root);
+            gyro += self.noise_rng.normal_vec() * (noise.gyro_dps_per_rt_hz * root);
+        }
+
+        let measured = Truth {
+            acc_g: acc,
+            gyro_dps: gyro,
+            temp_c: truth.temp_c,
+        };
+        let frame = encode(&measured, self.accel_range, self.gyro_range).0;
+        let sample = if self.model.quantization {
+            self.pipeline.sample(&frame)
+        } else {
+            MpuSample::new(acc, gyro * PI_180, truth.temp_c)
+        };
+        let (event, frame, sample) = self.dropout(frame, sample);
+        self.last = Some((frame, sample));
+
+        let emitted = SimSample {
+            sample,
+            frame,
+            truth: SimTruth {
+                time_s,
+                truth,
+                orientation,
+                gyro_bias_dps: gyro_bias,
+                accel_bias_g: accel_bias,
+                event,
+            },
+        };
+
+        if let Some(walk) = self.model.gyro_bias {
+            let step = walk.random_walk_dps_per_rt_s * self.interval_s.sqrt();
+            self.walk_dps += self.walk_rng.normal_vec() * step;
+        }
+        let rotation = Quat::from_scaled_axis((truth.gyro_dps * (PI_180 * self.interval_s)).into());
+        self.orientation = (orientation * rotation).normalize();
+        self.idx += 1;
+        emitted
+    }
+
+    /// every sample of `fixture`, continuing from the samples emitted so far
+    pub fn run(&mut self, fixture: &Fixture) -> Vec<SimSample> {
+        fixture.truths().map(|truth| self.step(&truth)).collect()
+    }
+
+    fn dropout(
+        &mut self,
+        frame: RawFrame,
+        sample: MpuSample,
+    ) -> (Option<DropoutKind>, RawFrame, MpuSample) {
+        let Some(dropouts) = self.model.dropouts else {
+            return (None, frame, sample);
+        };
+        if self.dropout_rng.fraction() >= dropouts.probability {
+            return (None, frame, sample);
+        }
+        let frame = match dropouts.kind {
+            DropoutKind::Hold => match self.last {
+                Some((frame, sample)) => return (Some(DropoutKind::Hold), frame, sample),
+                // nothing to hold before the first sample
+                None => return (None, frame, sample),
+            },
+            DropoutKind::Zero => RawFrame::default(),
+            DropoutKind::Corrupt => {
+                let mut bytes = encode_frame(&frame);
+                let byte = (self.dropout_rng.fraction() * FRAME_LEN as f32) as usize;
+                let mask = 1 + (self.dropout_rng.fraction() * 255.) as u8;
+                bytes[byte.min(FRAME_LEN - 1)] ^= mask;
+                parse_frame(&bytes)
+            }
+        };
+        (Some(dropouts.kind), frame, self.pipeline.sample(&frame))
+    }
+}
+
+/// temperature of `profile` at `time_s`, `fallback` for an empty profile
+fn profile_temp(profile: &[(f32, f32)], time_s: f32, fallback: f32) -> f32 {
+    let (Some(&(t0, first)), Some(&(t1, last))) = (profile.first(), profile.last()) else {
+        return fallback;
+    };
+    if time_s <= t0 {
+        return first;
+    }
+    if time_s >= t1 {
+        return last;
+    }
+    let end = profile
+        .iter()
+        .position(|&(t, _)| t > time_s)
+        .unwrap_or(profile.len() - 1);
+    let ((ta, ca), (tb, cb)) = (profile[end - 1], profile[end]);
+    ca + (cb - ca) * (time_s - ta) / (tb - ta)
+}
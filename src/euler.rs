@@ -0,0 +1,190 @@
+//! Explicitly named conversions between quaternions and Euler angles.
+//!
+//! Two conventions, both right-handed about the chip axes (X forward, Y left, Z up, see
+//! [`synthetic`](crate::synthetic)): positive roll is right side down, positive pitch is nose
+//! down, positive yaw is nose left (counterclockwise seen from above).
+//!
+//! * aerospace yaw-pitch-roll, [`to_aerospace_ypr`] / [`from_aerospace_ypr`]: intrinsic
+//!   Z-Y'-X'' (yaw about Z, then pitch about the new Y, then roll about the newest X), the
+//!   same rotation as extrinsic X-Y-Z. `q = Rz(yaw) * Ry(pitch) * Rx(roll)`
+//! * XYZ roll-pitch-yaw, [`to_xyz_rpy`] / [`from_xyz_rpy`]: intrinsic X-Y'-Z'' (roll, then
+//!   pitch, then yaw), the same rotation as extrinsic Z-Y-X. `q = Rx(roll) * Ry(pitch) *
+//!   Rz(yaw)`, this is glam's `EulerRot::XYZ` and what
+//!   [`Mpu6050::get_acc_angles`](crate::Mpu6050::get_acc_angles) returns
+//!
+//! Angles are in rad. Pitch is in [-π/2, π/2], roll and yaw in [-π, π]. Autopilots using NED
+//! body axes (Y right, Z down) see the same roll, pitch and yaw with the sign flipped.
+//!
+//! #### Gimbal lock
+//! With pitch within [`SINGULARITY_EPSILON`] of ±90°, roll and yaw rotate about the same axis
+//! and only their sum or difference is defined. The conversions then set roll to 0, put the
+//! whole rotation into yaw and report `near_singularity`, they never return NaN for a unit
+//! quaternion.
+//!
+//! ```
+//! use core::f32::consts::FRAC_PI_2;
+//! use mpu6050::euler::*;
+//! use mpu6050::Quat;
+//!
+//! let close = |a: f32, b: f32| (a - b).abs() < 1e-4;
+//!
+//! // 90° single-axis rotations are the same in both conventions
+//! for (q, (roll, pitch, yaw)) in [
+//!     (Quat::from_rotation_x(FRAC_PI_2), (FRAC_PI_2, 0., 0.)),
+//!     (Quat::from_rotation_y(FRAC_PI_2 - 0.1), (0., FRAC_PI_2 - 0.1, 0.)),
+//!     (Quat::from_rotation_z(FRAC_PI_2), (0., 0., FRAC_PI_2)),
+//! ] {
+//!     let ypr = to_aerospace_ypr(q);
+//!     assert!(close(ypr.roll, roll) && close(ypr.pitch, pitch) && close(ypr.yaw, yaw));
+//!     let rpy = to_xyz_rpy(q);
+//!     assert!(close(rpy.roll, roll) && close(rpy.pitch, pitch) && close(rpy.yaw, yaw));
+//! }
+//!
+//! // the conventions differ once rotations are combined: 90° yaw, then 90° roll is 90°
+//! // pitch, then 90° yaw in XYZ
+//! let q = from_aerospace_ypr(FRAC_PI_2, 0., FRAC_PI_2);
+//! let rpy = to_xyz_rpy(q);
+//! assert!(close(rpy.pitch, FRAC_PI_2) && close(rpy.yaw, FRAC_PI_2) && rpy.near_singularity);
+//!
+//! // round trips away from the singularity
+//! for yaw in [-3.0f32, -1.2, 0., 0.7, 3.0] {
+//!     for pitch in [-1.5f32, -0.4, 0., 0.9, 1.5] {
+//!         for roll in [-3.0f32, -0.5, 0., 1.1, 3.0] {
+//!             let ypr = to_aerospace_ypr(from_aerospace_ypr(yaw, pitch, roll));
+//!             assert!(!ypr.near_singularity);
+//!             assert!(close(ypr.yaw, yaw) && close(ypr.pitch, pitch) && close(ypr.roll, roll));
+//!
+//!             let rpy = to_xyz_rpy(from_xyz_rpy(roll, pitch, yaw));
+//!             assert!(close(rpy.roll, roll) && close(rpy.pitch, pitch) && close(rpy.yaw, yaw));
+//!         }
+//!     }
+//! }
+//!
+//! // at the singularity: finite angles that reproduce the rotation
+//! for pitch in [FRAC_PI_2, -FRAC_PI_2] {
+//!     let q = from_aerospace_ypr(0.3, pitch, 0.5);
+//!     let ypr = to_aerospace_ypr(q);
+//!     assert!(ypr.near_singularity && ypr.roll == 0.);
+//!     assert!(from_aerospace_ypr(ypr.yaw, ypr.pitch, ypr.roll).angle_between(q) < 1e-3);
+//!
+//!     let q = from_xyz_rpy(0.3, pitch, 0.5);
+//!     let rpy = to_xyz_rpy(q);
+//!     assert!(rpy.near_singularity && rpy.roll == 0.);
+//!     assert!(from_xyz_rpy(rpy.roll, rpy.pitch, rpy.yaw).angle_between(q) < 1e-3);
+//! }
+//! ```
+
+use core::f32::consts::FRAC_PI_2;
+
+use glam::Quat;
+
+use crate::determinism::atan2;
+#[cfg(not(feature = "std"))]
+use crate::math::FloatExt;
+
+/// Distance of pitch from ±90° in rad below which roll and yaw are not separated
+pub const SINGULARITY_EPSILON: f32 = 1e-3;
+
+/// Aerospace yaw-pitch-roll angles in rad, intrinsic Z-Y'-X''
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct AerospaceYpr {
+    /// rotation about Z, applied first, in [-π, π]
+    pub yaw: f32,
+    /// rotation about the rotated Y, in [-π/2, π/2]
+    pub pitch: f32,
+    /// rotation about the twice rotated X, applied last, in [-π, π]
+    pub roll: f32,
+    /// pitch within [`SINGULARITY_EPSILON`] of ±90°, roll was set to 0
+    pub near_singularity: bool,
+}
+
+/// Roll-pitch-yaw angles in rad, intrinsic X-Y'-Z''
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct XyzRpy {
+    /// rotation about X, applied first, in [-π, π]
+    pub roll: f32,
+    /// rotation about the rotated Y, in [-π/2, π/2]
+    pub pitch: f32,
+    /// rotation about the twice rotated Z, applied last, in [-π, π]
+    pub yaw: f32,
+    /// pitch within [`SINGULARITY_EPSILON`] of ±90°, roll was set to 0
+    pub near_singularity: bool,
+}
+
+/// Rotation matrix elements of a unit quaternion, `m[row][column]`
+fn matrix(q: Quat) -> [[f32; 3]; 3] {
+    let q = q.normalize();
+    let (w, x, y, z) = (q.w, q.x, q.y, q.z);
+    [
+        [
+            1.0 - 2.0 * (y * y + z * z),
+            2.0 * (x * y - w * z),
+            2.0 * (x * z + w * y),
+        ],
+        [
+            2.0 * (x * y + w * z),
+            1.0 - 2.0 * (x * x + z * z),
+            2.0 * (y * z - w * x),
+        ],
+        [
+            2.0 * (x * z - w * y),
+            2.0 * (y * z + w * x),
+            1.0 - 2.0 * (x * x + y * y),
+        ],
+    ]
+}
+
+fn near_singularity(pitch: f32) -> bool {
+    FRAC_PI_2 - pitch.abs() < SINGULARITY_EPSILON
+}
+
+/// Aerospace yaw, pitch and roll of `q`, see the [module docs](self)
+pub fn to_aerospace_ypr(q: Quat) -> AerospaceYpr {
+    let m = matrix(q);
+    // cos(pitch) from two elements instead of asin(-m[2][0]), well conditioned near ±90°
+    let pitch = atan2(-m[2][0], (m[0][0] * m[0][0] + m[1][0] * m[1][0]).sqrt());
+    if near_singularity(pitch) {
+        return AerospaceYpr {
+            yaw: atan2(-m[0][1], m[1][1]),
+            pitch,
+            roll: 0.0,
+            near_singularity: true,
+        };
+    }
+    AerospaceYpr {
+        yaw: atan2(m[1][0], m[0][0]),
+        pitch,
+        roll: atan2(m[2][1], m[2][2]),
+        near_singularity: false,
+    }
+}
+
+/// Quaternion of aerospace angles in rad: `Rz(yaw) * Ry(pitch) * Rx(roll)`
+pub fn from_aerospace_ypr(yaw: f32, pitch: f32, roll: f32) -> Quat {
+    Quat::from_rotation_z(yaw) * Quat::from_rotation_y(pitch) * Quat::from_rotation_x(roll)
+}
+
+/// XYZ roll, pitch and yaw of `q`, see the [module docs](self)
+pub fn to_xyz_rpy(q: Quat) -> XyzRpy {
+    let m = matrix(q);
+    let pitch = atan2(m[0][2], (m[0][0] * m[0][0] + m[0][1] * m[0][1]).sqrt());
+    if near_singularity(pitch) {
+        return XyzRpy {
+            roll: 0.0,
+            pitch,
+            yaw: atan2(m[1][0], m[1][1]),
+            near_singularity: true,
+        };
+    }
+    XyzRpy {
+        roll: atan2(-m[1][2], m[2][2]),
+        pitch,
+        yaw: atan2(-m[0][1], m[0][0]),
+        near_singularity: false,
+    }
+}
+
+/// Quaternion of XYZ angles in rad: `Rx(roll) * Ry(pitch) * Rz(yaw)`
+pub fn from_xyz_rpy(roll: f32, pitch: f32, yaw: f32) -> Quat {
+    Quat::from_rotation_x(roll) * Quat::from_rotation_y(pitch) * Quat::from_rotation_z(yaw)
+}
@@ -0,0 +1,625 @@
+//! C ABI: the driver over I2C callbacks of the host, and the complementary filter.
+//!
+//! Firmware in C links the crate built as a static library and includes the header
+//! `ffi/mpu6050.h`, generated from this module by cbindgen when the crate builds with the
+//! `ffi` feature:
+//! ```text
+//! cargo rustc --lib --crate-type staticlib --release --features ffi
+//! cc app.c -I ffi target/release/libmpu6050.a -lpthread -ldl -lm
+//! ```
+//! `ffi/example.c` is a complete program over a register file standing in for the chip,
+//! `tests/ffi.rs` builds and runs it. The test also compares the header the build generated
+//! against the committed one: a changed signature fails it until the header is regenerated
+//! with `UPDATE_GOLDENS=1` and the diff reviewed.
+//!
+//! #### Handles and callbacks
+//! [`mpu6050_create`] builds a driver over an [`Mpu6050Bus`], a `void *` context with the
+//! `write`, `write_read` and `delay_ms` callbacks, and returns an opaque
+//! [`Mpu6050Handle`]. Creating touches no bus, [`mpu6050_init`] is the first transaction.
+//! A callback returns 0 for success and any other status for a bus error; the call fails
+//! with the code of [`I2c`](crate::Mpu6050Error::I2c) and
+//! [`mpu6050_last_bus_status`] gives the status. Every other function mirrors a method of
+//! the driver and [`ComplementaryFilter`]:
+//!
+//! | function | driver |
+//! |:---|:---|
+//! | [`mpu6050_init`] | [`init`](crate::Mpu6050::init) |
+//! | [`mpu6050_get_all`] | [`get_all`](crate::Mpu6050::get_all), into an [`Mpu6050Reading`] |
+//! | [`mpu6050_set_accel_range`], [`mpu6050_set_gyro_range`] | [`set_accel_range`](crate::Mpu6050::set_accel_range), [`set_gyro_range`](crate::Mpu6050::set_gyro_range), by register value |
+//! | [`mpu6050_calibrate_gyro`] | [`calibrate_gyro`](crate::Mpu6050::calibrate_gyro), with a progress callback per sample |
+//! | [`mpu6050_filter_create`], [`mpu6050_filter_update`] | [`ComplementaryFilter::new`], [`ComplementaryFilter::update`] |
+//!
+//! Handles are released with [`mpu6050_destroy`] and [`mpu6050_filter_destroy`], both
+//! accept NULL.
+//!
+//! #### Return codes
+//! Functions return an `int32_t`: [`MPU6050_OK`], the positive
+//! [error code](crate::Mpu6050Error::code) of the driver error, the same table the
+//! [`black_box`](crate::black_box) records, among them [`MPU6050_REENTERED`] of a
+//! [nested call](self#reentrancy), or a negative code of this layer:
+//!
+//! | code | meaning |
+//! |:---|:---|
+//! | [`MPU6050_NULL_POINTER`] | a required pointer or callback is NULL |
+//! | [`MPU6050_INVALID_ARGUMENT`] | a range value beyond the register field, an invalid address |
+//! | [`MPU6050_PANICKED`] | the call panicked, or an earlier one on the handle did |
+//! | [`MPU6050_CALIBRATION_REJECTED`] | the samples varied too much, the device moved |
+//!
+//! #### Panics
+//! A panic must not unwind into C, it is undefined behaviour, and aborting takes the host
+//! down with it. Every function runs its body under `catch_unwind` and returns
+//! [`MPU6050_PANICKED`] instead. The driver may be halfway through a register sequence
+//! then, so the handle is poisoned: every later call on it returns [`MPU6050_PANICKED`]
+//! without running, and only [`mpu6050_destroy`] is left. The callbacks are
+//! `extern "C-unwind"`, a callback written in Rust may panic and is caught the same way.
+//! A C callback must return normally: `longjmp` out of one skips the Rust frames between
+//! and is undefined behaviour, C++ exceptions are not caught.
+//!
+//! The layer needs std for `catch_unwind`, which the `driver` feature it builds on has. A
+//! `no_std` build has no unwinding to catch, a panic calls the `panic_handler` of the
+//! firmware, and the only safe boundary is one that cannot panic. The functions here are
+//! written toward that: every pointer is checked before use, range values are matched
+//! rather than indexed, lengths come from Rust slices, and allocation happens only in the
+//! create functions. That keeps panics to broken invariants of the driver itself.
+//!
+//! #### Reentrancy
+//! The callbacks run while a call holds the handle. A callback calling back into its own
+//! handle, e.g. a delay callback polling the sensor, is refused: every handle has a busy
+//! flag set for the duration of a call, and a call finding it set returns
+//! [`MPU6050_REENTERED`], the [code](crate::Mpu6050Error::code) of
+//! [`Reentrancy`](crate::Mpu6050Error::Reentrancy), without running. The running call is
+//! unaffected. [`mpu6050_last_bus_status`] only reads and is allowed from a callback,
+//! [`mpu6050_destroy`] of the handle in use is not. See [`reentrancy`](crate::reentrancy)
+//! for the callbacks of the driver itself.
+//!
+//! #### Thread safety
+//! A handle is not thread-safe. Calls on one handle must not overlap from several threads:
+//! the busy flag is not atomic, it catches nesting only. A handle may move between threads
+//! between calls if its context may. Separate handles are independent and
+//! may be used concurrently, on separate buses or with callbacks serializing a shared bus.
+//! Filter handles follow the same rules.
+
+use core::cell::{Cell, UnsafeCell};
+use core::ffi::c_void;
+use std::boxed::Box;
+use std::panic::{self, AssertUnwindSafe};
+
+use embedded_hal::blocking::{
+    delay::DelayMs,
+    i2c::{Write, WriteRead},
+};
+
+use crate::deadline;
+use crate::device::{AccelRange, GyroRange};
+use crate::orientation::ComplementaryFilter;
+use crate::setup::AutoSetupFailure;
+use crate::{Mpu6050, Mpu6050Builder, Mpu6050Error, Vec3A};
+
+/// Success
+pub const MPU6050_OK: i32 = 0;
+
+/// A required pointer or callback is NULL
+pub const MPU6050_NULL_POINTER: i32 = -1;
+
+/// A range value beyond the register field, an invalid address or filter weight
+pub const MPU6050_INVALID_ARGUMENT: i32 = -2;
+
+/// The call panicked, or an earlier call on the handle did. The handle is poisoned, only
+/// `mpu6050_destroy` is left
+pub const MPU6050_PANICKED: i32 = -3;
+
+/// Calibration rejected, the samples varied too much and the device probably moved. The
+/// offsets are left as they were
+pub const MPU6050_CALIBRATION_REJECTED: i32 = -4;
+
+/// A call on a handle from inside one of its callbacks, refused without running. The code of
+/// `Mpu6050Error::Reentrancy`
+pub const MPU6050_REENTERED: i32 = 22;
+
+/// Writes `len` bytes at `bytes` to the device at the 7-bit `address`, returns 0 or a bus
+/// error status
+pub type Mpu6050WriteFn = Option<
+    unsafe extern "C-unwind" fn(
+        context: *mut c_void,
+        address: u8,
+        bytes: *const u8,
+        len: usize,
+    ) -> i32,
+>;
+
+/// Writes `len` bytes at `bytes` to the device at `address`, then reads `buffer_len` bytes
+/// into `buffer` after a repeated start, returns 0 or a bus error status
+pub type Mpu6050WriteReadFn = Option<
+    unsafe extern "C-unwind" fn(
+        context: *mut c_void,
+        address: u8,
+        bytes: *const u8,
+        len: usize,
+        buffer: *mut u8,
+        buffer_len: usize,
+    ) -> i32,
+>;
+
+/// Waits at least `ms` milliseconds
+pub type Mpu6050DelayMsFn = Option<unsafe extern "C-unwind" fn(context: *mut c_void, ms: u32)>;
+
+/// Called after each calibration sample with the samples `taken` of `total`
+pub type Mpu6050ProgressFn =
+    Option<unsafe extern "C-unwind" fn(context: *mut c_void, taken: u16, total: u16)>;
+
+/// The bus of the host: the context and the callbacks it is passed to. All callbacks are
+/// required, `mpu6050_create` refuses NULL
+#[repr(C)]
+#[derive(Copy, Clone, Debug)]
+pub struct Mpu6050Bus {
+    /// passed to every callback, owned by the host
+    pub context: *mut c_void,
+    /// plain write
+    pub write: Mpu6050WriteFn,
+    /// write then read with a repeated start
+    pub write_read: Mpu6050WriteReadFn,
+    /// blocking delay
+    pub delay_ms: Mpu6050DelayMsFn,
+}
+
+/// One burst of the chip in the units of the driver
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct Mpu6050Reading {
+    /// acceleration in g, X, Y, Z
+    pub acc_g: [f32; 3],
+    /// rotation rate in rad/s, X, Y, Z
+    pub gyro_rad_s: [f32; 3],
+    /// die temperature in °C
+    pub temp_c: f32,
+}
+
+/// Orientation quaternion, Hamilton convention
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct Mpu6050Quat {
+    /// vector part X
+    pub x: f32,
+    /// vector part Y
+    pub y: f32,
+    /// vector part Z
+    pub z: f32,
+    /// scalar part
+    pub w: f32,
+}
+
+/// status of a callback as a result
+fn status(status: i32) -> Result<(), i32> {
+    match status {
+        0 => Ok(()),
+        status => Err(status),
+    }
+}
+
+/// the host's callbacks as embedded-hal I2C and delay, a status as the I2C error. A NULL
+/// callback, refused by `mpu6050_create`, fails like the bus rather than panic
+#[derive(Debug)]
+struct HostBus(Mpu6050Bus);
+
+impl Write for HostBus {
+    type Error = i32;
+
+    fn write(&mut self, address: u8, bytes: &[u8]) -> Result<(), i32> {
+        let Some(write) = self.0.write else {
+            return Err(MPU6050_NULL_POINTER);
+        };
+        // SAFETY: the host vouched for the callback with the context in `mpu6050_create`,
+        // the pointer and length are the slice's
+        status(unsafe { write(self.0.context, address, bytes.as_ptr(), bytes.len()) })
+    }
+}
+
+impl WriteRead for HostBus {
+    type Error = i32;
+
+    fn write_read(&mut self, address: u8, bytes: &[u8], buffer: &mut [u8]) -> Result<(), i32> {
+        let Some(write_read) = self.0.write_read else {
+            return Err(MPU6050_NULL_POINTER);
+        };
+        let (len, buffer_len) = (bytes.len(), buffer.len());
+        // SAFETY: as in `write`
+        status(unsafe {
+            write_read(
+                self.0.context,
+                address,
+                bytes.as_ptr(),
+                len,
+                buffer.as_mut_ptr(),
+                buffer_len,
+            )
+        })
+    }
+}
+
+impl DelayMs<u8> for HostBus {
+    fn delay_ms(&mut self, ms: u8) {
+        if let Some(delay_ms) = self.0.delay_ms {
+            // SAFETY: as in `write`
+            unsafe { delay_ms(self.0.context, u32::from(ms)) }
+        }
+    }
+}
+
+/// the state of a handle behind its flags: `busy` while a call runs, `poisoned` after one
+/// panicked. The flags are outside the state, a nested call reads them while the running
+/// one holds the state
+#[derive(Debug)]
+struct Guard<T> {
+    busy: Cell<bool>,
+    poisoned: Cell<bool>,
+    state: UnsafeCell<T>,
+}
+
+impl<T> Guard<T> {
+    fn new(state: T) -> Self {
+        Self {
+            busy: Cell::new(false),
+            poisoned: Cell::new(false),
+            state: UnsafeCell::new(state),
+        }
+    }
+}
+
+/// what a call on a driver handle works on
+struct DriverState {
+    mpu: Mpu6050<HostBus>,
+    delay: HostBus,
+}
+
+/// Opaque driver handle from [`mpu6050_create`]
+pub struct Mpu6050Handle {
+    guard: Guard<DriverState>,
+    last_bus_status: Cell<i32>,
+}
+
+impl Mpu6050Handle {
+    /// return code of `res`, the bus status kept
+    fn code(&self, res: Result<(), Mpu6050Error<i32>>) -> i32 {
+        match res {
+            Ok(()) => MPU6050_OK,
+            Err(error) => {
+                if let Mpu6050Error::I2c(status) = error {
+                    self.last_bus_status.set(status);
+                }
+                i32::from(error.code())
+            }
+        }
+    }
+}
+
+/// Opaque filter handle from [`mpu6050_filter_create`]
+#[derive(Debug)]
+pub struct Mpu6050Filter {
+    guard: Guard<ComplementaryFilter>,
+}
+
+/// a handle with a [`Guard`]
+trait Guarded {
+    type State;
+
+    fn guard(&self) -> &Guard<Self::State>;
+}
+
+impl Guarded for Mpu6050Handle {
+    type State = DriverState;
+
+    fn guard(&self) -> &Guard<DriverState> {
+        &self.guard
+    }
+}
+
+impl Guarded for Mpu6050Filter {
+    type State = ComplementaryFilter;
+
+    fn guard(&self) -> &Guard<ComplementaryFilter> {
+        &self.guard
+    }
+}
+
+/// `call` on the handle behind `handle` and its state, with its panic caught and a nested
+/// call refused, see the [module docs](self#reentrancy)
+///
+/// # Safety
+/// `handle` is NULL or from the matching create function and not destroyed, and no call on
+/// it is running on another thread
+unsafe fn guarded<H: Guarded>(
+    handle: *const H,
+    call: impl FnOnce(&H, &mut H::State) -> i32,
+) -> i32 {
+    // SAFETY: by the contract of the caller. Shared, a nested call holds another
+    let Some(handle) = (unsafe { handle.as_ref() }) else {
+        return MPU6050_NULL_POINTER;
+    };
+    let guard = handle.guard();
+    if guard.poisoned.get() {
+        return MPU6050_PANICKED;
+    }
+    if guard.busy.replace(true) {
+        return MPU6050_REENTERED;
+    }
+    // SAFETY: `busy` was clear, no other call holds the state until it is cleared again
+    let state = unsafe { &mut *guard.state.get() };
+    let res = panic::catch_unwind(AssertUnwindSafe(|| call(handle, state)));
+    guard.busy.set(false);
+    match res {
+        Ok(code) => code,
+        Err(_) => {
+            guard.poisoned.set(true);
+            MPU6050_PANICKED
+        }
+    }
+}
+
+/// Creates a driver over `bus` talking to the 7-bit `slave_addr`, 0x68 or 0x69, into
+/// `*out`. No bus contact, call [`mpu6050_init`] next. `*out` is left alone on failure
+///
+/// Returns [`MPU6050_NULL_POINTER`] for a NULL `bus`, `out` or callback and
+/// [`MPU6050_INVALID_ARGUMENT`] if the builder refuses the address.
+///
+/// # Safety
+/// `bus` and `out` are NULL or valid. The callbacks may be called with the context from any
+/// call on the handle until [`mpu6050_destroy`]
+#[no_mangle]
+pub unsafe extern "C" fn mpu6050_create(
+    bus: *const Mpu6050Bus,
+    slave_addr: u8,
+    out: *mut *mut Mpu6050Handle,
+) -> i32 {
+    // SAFETY: by the contract of the caller
+    let (Some(&bus), false) = (unsafe { bus.as_ref() }, out.is_null()) else {
+        return MPU6050_NULL_POINTER;
+    };
+    if bus.write.is_none() || bus.write_read.is_none() || bus.delay_ms.is_none() {
+        return MPU6050_NULL_POINTER;
+    }
+    let built = panic::catch_unwind(|| {
+        Mpu6050Builder::new()
+            .i2c(HostBus(bus))
+            .slave_addr(slave_addr)
+            .build()
+    });
+    match built {
+        Ok(Ok(mpu)) => {
+            let handle = Box::new(Mpu6050Handle {
+                guard: Guard::new(DriverState {
+                    mpu,
+                    delay: HostBus(bus),
+                }),
+                last_bus_status: Cell::new(0),
+            });
+            // SAFETY: checked for NULL above
+            unsafe { *out = Box::into_raw(handle) };
+            MPU6050_OK
+        }
+        Ok(Err(_)) => MPU6050_INVALID_ARGUMENT,
+        Err(_) => MPU6050_PANICKED,
+    }
+}
+
+/// Releases a driver handle, NULL is ignored. Nothing is written to the chip
+///
+/// # Safety
+/// `handle` is NULL or from [`mpu6050_create`], not destroyed before and not in use
+#[no_mangle]
+pub unsafe extern "C" fn mpu6050_destroy(handle: *mut Mpu6050Handle) {
+    if !handle.is_null() {
+        // SAFETY: by the contract of the caller, the box of `mpu6050_create`
+        drop(unsafe { Box::from_raw(handle) });
+    }
+}
+
+/// Wakes and configures the chip like `Mpu6050::init`
+///
+/// # Safety
+/// `handle` is NULL or a live handle not in use
+#[no_mangle]
+pub unsafe extern "C" fn mpu6050_init(handle: *mut Mpu6050Handle) -> i32 {
+    // SAFETY: by the contract of the caller
+    unsafe {
+        guarded(handle, |handle, state| {
+            let res = state.mpu.init(&mut state.delay);
+            handle.code(res)
+        })
+    }
+}
+
+/// Reads accel, temperature and gyro in one burst into `*out` like `Mpu6050::get_all`.
+/// `*out` is left alone on failure
+///
+/// # Safety
+/// `handle` is NULL or a live handle not in use, `out` is NULL or valid
+#[no_mangle]
+pub unsafe extern "C" fn mpu6050_get_all(
+    handle: *mut Mpu6050Handle,
+    out: *mut Mpu6050Reading,
+) -> i32 {
+    if out.is_null() {
+        return MPU6050_NULL_POINTER;
+    }
+    // SAFETY: by the contract of the caller
+    unsafe {
+        guarded(handle, |handle, state| match state.mpu.get_all() {
+            Ok(sample) => {
+                // SAFETY: checked for NULL above
+                *out = Mpu6050Reading {
+                    acc_g: sample.acc().to_array(),
+                    gyro_rad_s: sample.gyro().to_array(),
+                    temp_c: sample.temp(),
+                };
+                MPU6050_OK
+            }
+            Err(error) => handle.code(Err(error)),
+        })
+    }
+}
+
+/// Sets the accel range by its AFS_SEL value: 0 to 3 for ±2, ±4, ±8 and ±16 g
+///
+/// # Safety
+/// `handle` is NULL or a live handle not in use
+#[no_mangle]
+pub unsafe extern "C" fn mpu6050_set_accel_range(handle: *mut Mpu6050Handle, afs_sel: u8) -> i32 {
+    let range = match afs_sel {
+        0 => AccelRange::G2,
+        1 => AccelRange::G4,
+        2 => AccelRange::G8,
+        3 => AccelRange::G16,
+        _ => return MPU6050_INVALID_ARGUMENT,
+    };
+    // SAFETY: by the contract of the caller
+    unsafe {
+        guarded(handle, |handle, state| {
+            let res = state.mpu.set_accel_range(range);
+            handle.code(res)
+        })
+    }
+}
+
+/// Sets the gyro range by its FS_SEL value: 0 to 3 for ±250, ±500, ±1000 and ±2000 °/s
+///
+/// # Safety
+/// `handle` is NULL or a live handle not in use
+#[no_mangle]
+pub unsafe extern "C" fn mpu6050_set_gyro_range(handle: *mut Mpu6050Handle, fs_sel: u8) -> i32 {
+    let range = match fs_sel {
+        0 => GyroRange::D250,
+        1 => GyroRange::D500,
+        2 => GyroRange::D1000,
+        3 => GyroRange::D2000,
+        _ => return MPU6050_INVALID_ARGUMENT,
+    };
+    // SAFETY: by the contract of the caller
+    unsafe {
+        guarded(handle, |handle, state| {
+            let res = state.mpu.set_gyro_range(range);
+            handle.code(res)
+        })
+    }
+}
+
+/// Calibrates the gyro offsets at rest from `samples` samples like
+/// `Mpu6050::calibrate_gyro`. `progress`, if not NULL, is called
+/// with `context` after each sample. The offsets applied, 3 floats in rad/s, are written to
+/// `offset_out` if not NULL
+///
+/// # Safety
+/// `handle` is NULL or a live handle not in use, `offset_out` is NULL or valid for 3 floats
+#[no_mangle]
+pub unsafe extern "C" fn mpu6050_calibrate_gyro(
+    handle: *mut Mpu6050Handle,
+    samples: u16,
+    progress: Mpu6050ProgressFn,
+    context: *mut c_void,
+    offset_out: *mut f32,
+) -> i32 {
+    // SAFETY: by the contract of the caller
+    unsafe {
+        guarded(handle, |handle, state| {
+            let mut report = |taken| {
+                if let Some(progress) = progress {
+                    // SAFETY: the host vouched for the callback with the context
+                    progress(context, taken, samples);
+                }
+            };
+            let calibrated = state.mpu.calibrate_gyro_using(
+                &mut state.delay,
+                samples,
+                &mut report,
+                &mut deadline::never,
+            );
+            match calibrated {
+                Ok(result) => {
+                    if !offset_out.is_null() {
+                        // SAFETY: valid for 3 floats by the contract of the caller
+                        core::slice::from_raw_parts_mut(offset_out, 3)
+                            .copy_from_slice(&result.offset.to_array());
+                    }
+                    MPU6050_OK
+                }
+                Err(AutoSetupFailure::Device(error)) => handle.code(Err(error)),
+                // a static calibration runs no self-test
+                Err(AutoSetupFailure::CalibrationRejected { .. })
+                | Err(AutoSetupFailure::ActuationCheck { .. }) => MPU6050_CALIBRATION_REJECTED,
+            }
+        })
+    }
+}
+
+/// Last nonzero status a bus callback returned, 0 before any
+///
+/// # Safety
+/// `handle` is NULL or a live handle not in use
+#[no_mangle]
+pub unsafe extern "C" fn mpu6050_last_bus_status(handle: *const Mpu6050Handle) -> i32 {
+    // SAFETY: by the contract of the caller
+    match unsafe { handle.as_ref() } {
+        Some(handle) => handle.last_bus_status.get(),
+        None => 0,
+    }
+}
+
+/// Creates a complementary filter at identity with gyro weight `alpha` per update, see
+/// [`ComplementaryFilter::new`]. NULL if `alpha` is not within 0 to 1
+#[no_mangle]
+pub extern "C" fn mpu6050_filter_create(alpha: f32) -> *mut Mpu6050Filter {
+    if !(0. ..=1.).contains(&alpha) {
+        return core::ptr::null_mut();
+    }
+    let filter = Mpu6050Filter {
+        guard: Guard::new(ComplementaryFilter::new(alpha)),
+    };
+    Box::into_raw(Box::new(filter))
+}
+
+/// One filter step with `reading` over `dt_s` seconds, the new orientation into `*out`, see
+/// [`ComplementaryFilter::update`]
+///
+/// # Safety
+/// `filter` is NULL or a live filter not in use, `reading` and `out` are NULL or valid
+#[no_mangle]
+pub unsafe extern "C" fn mpu6050_filter_update(
+    filter: *mut Mpu6050Filter,
+    reading: *const Mpu6050Reading,
+    dt_s: f32,
+    out: *mut Mpu6050Quat,
+) -> i32 {
+    // SAFETY: by the contract of the caller
+    let Some(reading) = (unsafe { reading.as_ref() }) else {
+        return MPU6050_NULL_POINTER;
+    };
+    if out.is_null() {
+        return MPU6050_NULL_POINTER;
+    }
+    // SAFETY: by the contract of the caller
+    unsafe {
+        guarded(filter, |_, filter| {
+            let gyro = Vec3A::from(reading.gyro_rad_s);
+            let q = filter.update(gyro, Vec3A::from(reading.acc_g), dt_s);
+            // SAFETY: checked for NULL above
+            *out = Mpu6050Quat {
+                x: q.x,
+                y: q.y,
+                z: q.z,
+                w: q.w,
+            };
+            MPU6050_OK
+        })
+    }
+}
+
+/// Releases a filter handle, NULL is ignored
+///
+/// # Safety
+/// `filter` is NULL or from [`mpu6050_filter_create`], not destroyed before and not in use
+#[no_mangle]
+pub unsafe extern "C" fn mpu6050_filter_destroy(filter: *mut Mpu6050Filter) {
+    if !filter.is_null() {
+        // SAFETY: by the contract of the caller, the box of `mpu6050_filter_create`
+        drop(unsafe { Box::from_raw(filter) });
+    }
+}
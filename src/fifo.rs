@@ -0,0 +1,780 @@
+//! FIFO sources, frame layout and parsing.
+//!
+//! FIFO_EN (and SLV_3_FIFO_EN in I2C_MST_CTRL) select what the chip writes to the FIFO every
+//! sample. A frame holds the enabled sources in register address order (register map,
+//! FIFO_EN): accel X, Y, Z, temperature, gyro X, Y, Z, then the EXT_SENS_DATA of aux slaves
+//! 0 to 3, each with the length it was configured with. Sensor words are big endian two's
+//! complement, 2 bytes each.
+//!
+//! A [`FifoSchema`] describes the frame of one configuration: its length, the offset, width
+//! and kind of every field and the sensitivities needed to scale them.
+//! [`parse_fifo_frame`] decodes a frame with any schema, so consumers read fields by kind
+//! instead of hard coded offsets. The driver computes the schema of its configuration
+//! ([`Mpu6050::fifo_schema`]) and uses it for every drain ([`Mpu6050::drain_fifo`]).
+//!
+//! #### Invalidation
+//! The schema carries the [`config_epoch`](crate::config_epoch) it was taken at, which the
+//! driver advances whenever the frame layout, its scaling or the rest of the configuration
+//! changes: FIFO sources, ranges, aux slave configuration, temperature calibration, device
+//! reset among others. Draining or parsing with a schema of an earlier epoch fails with
+//! [`Mpu6050Error::StaleConfiguration`], [`FifoSchema::is_current`] tells beforehand; the
+//! FIFO may still hold frames of the old layout, reset it unless the new schema has the same
+//! layout.
+//!
+//! #### FIFO and direct reads
+//! While streaming (USER_CTRL FIFO_EN set with [`Mpu6050::set_fifo_enabled`]) there are two
+//! views of the same samples. The chip writes one frame per sample to the FIFO and the same
+//! sample to the data registers. A drain reads the oldest frames first, so the data
+//! registers are `fifo_count / frame_len` samples ahead of the next frame drained: a direct
+//! read returns the newest sample, the one at the write end of the FIFO, or a newer one if
+//! the chip wrote another in between. It has no place between two frames and no sequence
+//! number on the FIFO timeline.
+//!
+//! Direct scaled reads (`get_acc`, `get_gyro`, `get_temp`, everything built on them and the
+//! full-sample reads) therefore fail with [`Mpu6050Error::FifoActive`] while streaming. With
+//! [`MixedReadPolicy::Allow`] they are permitted; `mark: true` sets
+//! [`MpuSample::out_of_band`](crate::MpuSample::out_of_band) on the samples they deliver,
+//! which the [`hook`](crate::hook) and tap see and full-sample reads return. Raw reads
+//! (`get_acc_raw`, `read_registers`) are escape hatches and not checked.
+//!
+//! On the bus, FIFO_R_W does not advance the register pointer and every transaction of the
+//! driver writes its register address first, so a permitted direct read between two drains
+//! leaves the FIFO read position alone. Both go through `&mut self`: a read while a drain or
+//! the sampling loop runs does not compile,
+//! ```compile_fail
+//! use embedded_hal::blocking::i2c::{Write, WriteRead};
+//! use mpu6050::Mpu6050;
+//!
+//! fn mixed<I, E>(mpu: &mut Mpu6050<I>, buf: &mut [u8])
+//! where
+//!     I: Write<Error = E> + WriteRead<Error = E>,
+//! {
+//!     let schema = mpu.fifo_schema();
+//!     mpu.drain_fifo(&schema, buf, |_| {
+//!         mpu.get_acc().ok();
+//!     })
+//!     .ok();
+//! }
+//! ```
+//! and neither does starting the stream from within the sampling loop:
+//! ```compile_fail
+//! use embedded_hal::blocking::i2c::{Write, WriteRead};
+//! use mpu6050::sampling::SampleControl;
+//! use mpu6050::Mpu6050;
+//!
+//! fn start<I, E>(mpu: &mut Mpu6050<I>)
+//! where
+//!     I: Write<Error = E> + WriteRead<Error = E>,
+//! {
+//!     mpu.run_sampling_loop(
+//!         || Ok::<(), ()>(()),
+//!         |_, _| {
+//!             mpu.set_fifo_enabled(true).ok();
+//!             SampleControl::Stop
+//!         },
+//!     )
+//!     .ok();
+//! }
+//! ```
+//! Two drivers or contexts sharing one sensor are beyond what the driver sees: the bus
+//! implementation has to keep each transaction whole, and the streaming state is per driver.
+//!
+//! A drain finding [`FIFO_CAPACITY`] bytes reports a
+//! [`FifoOverflow`](crate::metrics::MetricEvent::FifoOverflow) to the metrics sink, the oldest
+//! frames are lost by then. It resets the FIFO and the next frame drained carries the gap,
+//! see [`fifo_recovery`](crate::fifo_recovery).
+//!
+//! #### Serialized form
+//! [`FifoSchema::to_bytes`] writes the schema as a header for recorded FIFO dumps,
+//! [`FifoSchema::from_bytes`] reads it back and recomputes the layout:
+//!
+//! | bytes | content |
+//! |:---|:---|
+//! | 1 | format version ([`FIFO_SCHEMA_VERSION`]) |
+//! | 1 | FIFO_EN |
+//! | 1 | flags, bit 0: SLV_3_FIFO_EN |
+//! | 1 | accel range (bits 3:2), gyro range (bits 1:0) |
+//! | 4 | EXT_SENS_DATA length of aux slaves 0 to 3 |
+//! | 4 | accel sensitivity in LSB per g, f32 little endian |
+//! | 4 | gyro sensitivity in LSB per °/s, f32 little endian |
+//! | 4 | configuration epoch, little endian |
+//! | 4 | temperature model raw offset in counts, f32 little endian |
+//! | 4 | temperature model sensitivity in LSB per °C, f32 little endian |
+//! | 4 | temperature model offset in °C, f32 little endian |
+//!
+//! Version 1 schemas end after the epoch, 20 bytes, and read back with
+//! [`TempModel::NATIVE`].
+//! ```
+//! use mpu6050::device::{AccelRange, GyroRange};
+//! use mpu6050::fifo::*;
+//!
+//! let sources = FifoSources::NONE.with_accel(true).with_gyro(true);
+//! let schema = FifoSchema::new(sources, [0; 4], AccelRange::G4, GyroRange::D500);
+//! assert_eq!(schema.frame_len(), 12);
+//! assert_eq!(schema.field(FifoFieldKind::GyroX).unwrap().offset, 6);
+//!
+//! let bytes = [0x20, 0, 0, 0, 0, 0, 0, 0x41, 0, 0, 0, 0];
+//! let frame = parse_fifo_frame(&schema, &bytes).unwrap();
+//! assert_eq!(frame.acc_g().unwrap().x, 1.);
+//! assert_eq!(frame.gyro, [Some(65), Some(0), Some(0)]);
+//! assert_eq!(frame.temp, None);
+//! ```
+
+use core::fmt;
+
+use glam::Vec3A;
+
+use crate::aux_i2c::{ext_data_layout, SlaveSlot};
+#[cfg(feature = "driver")]
+use crate::config_epoch::ConfigChange;
+use crate::device::{AccelRange, GyroRange, EXT_SENS_DATA_LEN, FIFO_EN_BITS, I2C_MST_CTRL};
+#[cfg(feature = "driver")]
+use crate::device::{Capability, USER_CTRL};
+use crate::fifo_recovery::FifoGap;
+use crate::register::{Register, RegisterValue};
+use crate::scale::{self, ScaleModel, TempModel};
+use crate::{Mpu6050, Mpu6050Error};
+#[cfg(feature = "driver")]
+use embedded_hal::blocking::i2c::{Write, WriteRead};
+
+/// Format version written by [`FifoSchema::to_bytes`]
+pub const FIFO_SCHEMA_VERSION: u8 = 2;
+/// FIFO size in bytes, a FIFO count this high means frames were dropped or overwritten
+pub const FIFO_CAPACITY: u16 = 1024;
+/// Length of the serialized schema
+pub const FIFO_SCHEMA_LEN: usize = 32;
+/// Length of a version 1 schema, without the temperature model
+const FIFO_SCHEMA_V1_LEN: usize = 20;
+/// Maximum number of fields in a frame
+pub const MAX_FIFO_FIELDS: usize = 11;
+/// Longest frame: all sensors and the full EXT_SENS_DATA
+pub const MAX_FIFO_FRAME_LEN: usize = 14 + EXT_SENS_DATA_LEN as usize;
+
+/// Sources written to the FIFO
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub struct FifoSources {
+    /// accel X, Y and Z
+    pub accel: bool,
+    /// temperature
+    pub temp: bool,
+    /// gyro X, Y, Z
+    pub gyro: [bool; 3],
+    /// EXT_SENS_DATA of aux slaves 0 to 3
+    pub slaves: [bool; 4],
+}
+
+impl FifoSources {
+    /// nothing, the reset value
+    pub const NONE: Self = Self {
+        accel: false,
+        temp: false,
+        gyro: [false; 3],
+        slaves: [false; 4],
+    };
+
+    /// set accel
+    pub const fn with_accel(mut self, enable: bool) -> Self {
+        self.accel = enable;
+        self
+    }
+
+    /// set temperature
+    pub const fn with_temp(mut self, enable: bool) -> Self {
+        self.temp = enable;
+        self
+    }
+
+    /// set all gyro axes
+    pub const fn with_gyro(mut self, enable: bool) -> Self {
+        self.gyro = [enable; 3];
+        self
+    }
+
+    /// set gyro axes X, Y, Z
+    pub const fn with_gyro_axes(mut self, axes: [bool; 3]) -> Self {
+        self.gyro = axes;
+        self
+    }
+
+    /// set the EXT_SENS_DATA of an aux slave
+    pub const fn with_slave(mut self, slave: SlaveSlot, enable: bool) -> Self {
+        self.slaves[slave as usize] = enable;
+        self
+    }
+
+    /// sources of a FIFO_EN and an I2C_MST_CTRL value
+    pub fn from_registers(fifo_en: u8, i2c_mst_ctrl: u8) -> Self {
+        let fifo_en = RegisterValue::<FIFO_EN_BITS>::from_byte(fifo_en);
+        let bit = |field| fifo_en.flag(field);
+        Self {
+            accel: bit(FIFO_EN_BITS::ACCEL_FIELD),
+            temp: bit(FIFO_EN_BITS::TEMP_FIELD),
+            gyro: [
+                bit(FIFO_EN_BITS::XG_FIELD),
+                bit(FIFO_EN_BITS::YG_FIELD),
+                bit(FIFO_EN_BITS::ZG_FIELD),
+            ],
+            slaves: [
+                bit(FIFO_EN_BITS::SLV0_FIELD),
+                bit(FIFO_EN_BITS::SLV1_FIELD),
+                bit(FIFO_EN_BITS::SLV2_FIELD),
+                RegisterValue::<I2C_MST_CTRL>::from_byte(i2c_mst_ctrl)
+                    .flag(I2C_MST_CTRL::SLV_3_FIFO_EN_FIELD),
+            ],
+        }
+    }
+
+    /// FIFO_EN value
+    pub fn fifo_en(&self) -> u8 {
+        self.fifo_en_value().byte()
+    }
+
+    fn fifo_en_value(&self) -> RegisterValue<FIFO_EN_BITS> {
+        [
+            (FIFO_EN_BITS::ACCEL_FIELD, self.accel),
+            (FIFO_EN_BITS::TEMP_FIELD, self.temp),
+            (FIFO_EN_BITS::XG_FIELD, self.gyro[0]),
+            (FIFO_EN_BITS::YG_FIELD, self.gyro[1]),
+            (FIFO_EN_BITS::ZG_FIELD, self.gyro[2]),
+            (FIFO_EN_BITS::SLV0_FIELD, self.slaves[0]),
+            (FIFO_EN_BITS::SLV1_FIELD, self.slaves[1]),
+            (FIFO_EN_BITS::SLV2_FIELD, self.slaves[2]),
+        ]
+        .into_iter()
+        .fold(RegisterValue::ZERO, |byte, (field, enable)| {
+            byte.with(field, enable)
+        })
+    }
+}
+
+/// What a frame field holds
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum FifoFieldKind {
+    /// accel X word
+    AccelX,
+    /// accel Y word
+    AccelY,
+    /// accel Z word
+    AccelZ,
+    /// temperature word
+    Temp,
+    /// gyro X word
+    GyroX,
+    /// gyro Y word
+    GyroY,
+    /// gyro Z word
+    GyroZ,
+    /// EXT_SENS_DATA bytes of an aux slave
+    ExtSens(SlaveSlot),
+}
+
+/// Location of a field in a frame
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct FifoField {
+    /// content
+    pub kind: FifoFieldKind,
+    /// byte offset from the start of the frame
+    pub offset: u8,
+    /// bytes
+    pub width: u8,
+}
+
+/// Frame layout and scaling of one FIFO configuration, see the [module docs](self)
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct FifoSchema {
+    sources: FifoSources,
+    ext_len: [u8; 4],
+    fields: [FifoField; MAX_FIFO_FIELDS],
+    field_count: u8,
+    frame_len: u8,
+    /// accel range in effect
+    pub accel_range: AccelRange,
+    /// gyro range in effect
+    pub gyro_range: GyroRange,
+    /// accel sensitivity in LSB per g
+    pub acc_sensitivity: f32,
+    /// gyro sensitivity in LSB per °/s
+    pub gyro_sensitivity: f32,
+    /// driver [`config_epoch`](crate::config_epoch) the schema was taken at, 0 for schemas
+    /// not from a driver
+    pub generation: u32,
+    /// temperature conversion in effect, see [`temp_calibration`](crate::temp_calibration)
+    pub temp: TempModel,
+}
+
+impl FifoSchema {
+    /// Layout of `sources`, with the nominal sensitivities of the ranges. `ext_len` is the
+    /// EXT_SENS_DATA length of aux slaves 0 to 3, ignored for slaves not in `sources`. Lengths
+    /// beyond the 24 EXT_SENS_DATA registers are cut off like the chip does
+    pub fn new(
+        sources: FifoSources,
+        ext_len: [u8; 4],
+        accel_range: AccelRange,
+        gyro_range: GyroRange,
+    ) -> Self {
+        let placeholder = FifoField {
+            kind: FifoFieldKind::AccelX,
+            offset: 0,
+            width: 0,
+        };
+        let mut schema = Self {
+            sources,
+            ext_len,
+            fields: [placeholder; MAX_FIFO_FIELDS],
+            field_count: 0,
+            frame_len: 0,
+            accel_range,
+            gyro_range,
+            acc_sensitivity: accel_range.sensitivity(),
+            gyro_sensitivity: gyro_range.sensitivity(),
+            generation: 0,
+            temp: TempModel::NATIVE,
+        };
+        let mut add = |kind, width: u8| {
+            schema.fields[schema.field_count as usize] = FifoField {
+                kind,
+                offset: schema.frame_len,
+                width,
+            };
+            schema.field_count += 1;
+            schema.frame_len += width;
+        };
+        if sources.accel {
+            add(FifoFieldKind::AccelX, 2);
+            add(FifoFieldKind::AccelY, 2);
+            add(FifoFieldKind::AccelZ, 2);
+        }
+        if sources.temp {
+            add(FifoFieldKind::Temp, 2);
+        }
+        let gyro = [
+            FifoFieldKind::GyroX,
+            FifoFieldKind::GyroY,
+            FifoFieldKind::GyroZ,
+        ];
+        for (kind, enabled) in gyro.into_iter().zip(sources.gyro) {
+            if enabled {
+                add(kind, 2);
+            }
+        }
+        let mut ext_total = 0;
+        for (slave, len) in SlaveSlot::ALL.into_iter().zip(ext_len) {
+            let len = len.min(EXT_SENS_DATA_LEN - ext_total);
+            if sources.slaves[slave as usize] && len > 0 {
+                add(FifoFieldKind::ExtSens(slave), len);
+            }
+            ext_total += len;
+        }
+        schema
+    }
+
+    /// same schema with the sensitivities in effect, in LSB per g and LSB per °/s
+    pub fn with_sensitivities(mut self, acc: f32, gyro: f32) -> Self {
+        self.acc_sensitivity = acc;
+        self.gyro_sensitivity = gyro;
+        self
+    }
+
+    /// same schema with the temperature conversion in effect
+    pub fn with_temp_model(mut self, temp: TempModel) -> Self {
+        self.temp = temp;
+        self
+    }
+
+    /// sources the layout is computed from
+    pub fn sources(&self) -> FifoSources {
+        self.sources
+    }
+
+    /// bytes per frame
+    pub fn frame_len(&self) -> usize {
+        self.frame_len as usize
+    }
+
+    /// fields in frame order
+    pub fn fields(&self) -> &[FifoField] {
+        &self.fields[..self.field_count as usize]
+    }
+
+    /// the field holding `kind`, None if not in the frame
+    pub fn field(&self, kind: FifoFieldKind) -> Option<FifoField> {
+        self.fields().iter().copied().find(|f| f.kind == kind)
+    }
+
+    /// true if `mpu` has not changed its configuration since the schema was taken, so a
+    /// drain accepts it. No bus access
+    pub fn is_current<I, D>(&self, mpu: &Mpu6050<I, D>) -> bool {
+        self.generation == mpu.config_epoch()
+    }
+
+    /// Serialized form, see the [module docs](self)
+    pub fn to_bytes(&self) -> [u8; FIFO_SCHEMA_LEN] {
+        let mut bytes = [0; FIFO_SCHEMA_LEN];
+        bytes[0] = FIFO_SCHEMA_VERSION;
+        bytes[1] = self.sources.fifo_en();
+        bytes[2] = self.sources.slaves[3] as u8;
+        bytes[3] = (self.accel_range as u8) << 2 | self.gyro_range as u8;
+        bytes[4..8].copy_from_slice(&self.ext_len);
+        bytes[8..12].copy_from_slice(&self.acc_sensitivity.to_le_bytes());
+        bytes[12..16].copy_from_slice(&self.gyro_sensitivity.to_le_bytes());
+        bytes[16..20].copy_from_slice(&self.generation.to_le_bytes());
+        bytes[20..24].copy_from_slice(&self.temp.raw_offset.to_le_bytes());
+        bytes[24..28].copy_from_slice(&self.temp.sensitivity.to_le_bytes());
+        bytes[28..32].copy_from_slice(&self.temp.offset_c.to_le_bytes());
+        bytes
+    }
+
+    /// Reads a schema written by [`to_bytes`](Self::to_bytes), of this version or version 1
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, FifoError> {
+        let version = bytes.first().copied();
+        let expected = match version {
+            Some(1) => FIFO_SCHEMA_V1_LEN,
+            _ => FIFO_SCHEMA_LEN,
+        };
+        if bytes.len() < expected {
+            return Err(FifoError::ShortInput {
+                expected,
+                actual: bytes.len(),
+            });
+        }
+        if !matches!(version, Some(1 | FIFO_SCHEMA_VERSION)) {
+            return Err(FifoError::UnknownVersion(bytes[0]));
+        }
+        let mut ext_len = [0; 4];
+        ext_len.copy_from_slice(&bytes[4..8]);
+        let total: u32 = ext_len.iter().map(|len| *len as u32).sum();
+        if total > EXT_SENS_DATA_LEN as u32 {
+            return Err(FifoError::ExtDataOverflow(total));
+        }
+        let slv3 = RegisterValue::<I2C_MST_CTRL>::ZERO
+            .with(I2C_MST_CTRL::SLV_3_FIFO_EN_FIELD, bytes[2] & 0x01 != 0)
+            .byte();
+        let word = |range: core::ops::Range<usize>| {
+            let mut word = [0; 4];
+            word.copy_from_slice(&bytes[range]);
+            word
+        };
+        let mut schema = Self::new(
+            FifoSources::from_registers(bytes[1], slv3),
+            ext_len,
+            AccelRange::from((bytes[3] >> 2) & 0x03),
+            GyroRange::from(bytes[3] & 0x03),
+        )
+        .with_sensitivities(
+            f32::from_le_bytes(word(8..12)),
+            f32::from_le_bytes(word(12..16)),
+        );
+        schema.generation = u32::from_le_bytes(word(16..20));
+        if expected == FIFO_SCHEMA_LEN {
+            schema.temp = TempModel {
+                raw_offset: f32::from_le_bytes(word(20..24)),
+                sensitivity: f32::from_le_bytes(word(24..28)),
+                offset_c: f32::from_le_bytes(word(28..32)),
+            };
+        }
+        Ok(schema)
+    }
+}
+
+/// Input rejected by [`parse_fifo_frame`] or [`FifoSchema::from_bytes`]
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum FifoError {
+    /// fewer bytes than a frame or a serialized schema
+    ShortInput {
+        /// bytes required
+        expected: usize,
+        /// bytes given
+        actual: usize,
+    },
+    /// serialized schema of an unknown format version
+    UnknownVersion(u8),
+    /// serialized aux slave lengths exceed EXT_SENS_DATA, total length
+    ExtDataOverflow(u32),
+}
+
+impl fmt::Display for FifoError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FifoError::ShortInput { expected, actual } => {
+                write!(f, "{} bytes, {} required", actual, expected)
+            }
+            FifoError::UnknownVersion(version) => {
+                write!(f, "unknown FIFO schema version {}", version)
+            }
+            FifoError::ExtDataOverflow(len) => {
+                write!(f, "aux data of {} bytes exceeds EXT_SENS_DATA", len)
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for FifoError {}
+
+/// Raw content of one FIFO frame, None for sources not in the frame
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct FifoFrame {
+    /// accel X, Y, Z counts
+    pub acc: Option<[i16; 3]>,
+    /// temperature counts
+    pub temp: Option<i16>,
+    /// gyro X, Y, Z counts
+    pub gyro: [Option<i16>; 3],
+    ext: [u8; EXT_SENS_DATA_LEN as usize],
+    ext_slots: [Option<(u8, u8)>; 4],
+    acc_sensitivity: f32,
+    gyro_sensitivity: f32,
+    temp_model: TempModel,
+    /// configuration epoch of the schema the frame was parsed with
+    pub generation: u32,
+    /// position on the FIFO timeline of a drain, 0 outside one, see
+    /// [`fifo_recovery`](crate::fifo_recovery)
+    pub sequence: u64,
+    /// frames lost to an overflow right before this one, set by drains only
+    pub gap: Option<FifoGap>,
+}
+
+impl FifoFrame {
+    /// accel in g with the sensitivity of the schema, None if not in the frame
+    pub fn acc_g(&self) -> Option<Vec3A> {
+        let scale = ScaleModel::new(self.acc_sensitivity);
+        self.acc.map(|raw| scale::acc_uncorrected(raw, &scale))
+    }
+
+    /// gyro in rad/s with the sensitivity of the schema. None unless all three axes are in
+    /// the frame
+    pub fn gyro_rad_s(&self) -> Option<Vec3A> {
+        let [Some(x), Some(y), Some(z)] = self.gyro else {
+            return None;
+        };
+        let scale = ScaleModel::new(self.gyro_sensitivity);
+        Some(scale::gyro_uncorrected([x, y, z], &scale))
+    }
+
+    /// temperature in °C with the temperature model of the schema, None if not in the frame
+    pub fn temp_c(&self) -> Option<f32> {
+        self.temp.map(|raw| self.temp_model.celsius(raw))
+    }
+
+    /// EXT_SENS_DATA bytes of `slave`, None if not in the frame
+    pub fn ext(&self, slave: SlaveSlot) -> Option<&[u8]> {
+        self.ext_slots[slave as usize]
+            .map(|(offset, len)| &self.ext[offset as usize..(offset + len) as usize])
+    }
+}
+
+/// Decodes the first frame of `bytes` with `schema`. Extra bytes are ignored
+pub fn parse_fifo_frame(schema: &FifoSchema, bytes: &[u8]) -> Result<FifoFrame, FifoError> {
+    if bytes.len() < schema.frame_len() {
+        return Err(FifoError::ShortInput {
+            expected: schema.frame_len(),
+            actual: bytes.len(),
+        });
+    }
+    let mut frame = FifoFrame {
+        acc: None,
+        temp: None,
+        gyro: [None; 3],
+        ext: [0; EXT_SENS_DATA_LEN as usize],
+        ext_slots: [None; 4],
+        acc_sensitivity: schema.acc_sensitivity,
+        gyro_sensitivity: schema.gyro_sensitivity,
+        temp_model: schema.temp,
+        generation: schema.generation,
+        sequence: 0,
+        gap: None,
+    };
+    let mut acc = [0; 3];
+    let mut ext_used = 0;
+    for field in schema.fields() {
+        let data = &bytes[field.offset as usize..(field.offset + field.width) as usize];
+        let word = || i16::from_be_bytes([data[0], data[1]]);
+        match field.kind {
+            FifoFieldKind::AccelX => acc[0] = word(),
+            FifoFieldKind::AccelY => acc[1] = word(),
+            FifoFieldKind::AccelZ => acc[2] = word(),
+            FifoFieldKind::Temp => frame.temp = Some(word()),
+            FifoFieldKind::GyroX => frame.gyro[0] = Some(word()),
+            FifoFieldKind::GyroY => frame.gyro[1] = Some(word()),
+            FifoFieldKind::GyroZ => frame.gyro[2] = Some(word()),
+            FifoFieldKind::ExtSens(slave) => {
+                frame.ext[ext_used as usize..(ext_used + field.width) as usize]
+                    .copy_from_slice(data);
+                frame.ext_slots[slave as usize] = Some((ext_used, field.width));
+                ext_used += field.width;
+            }
+        }
+    }
+    frame.acc = schema.sources.accel.then_some(acc);
+    Ok(frame)
+}
+
+/// What direct scaled reads do while FIFO streaming, see [`fifo`](crate::fifo)
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub enum MixedReadPolicy {
+    /// fail with [`Mpu6050Error::FifoActive`]
+    #[default]
+    Reject,
+    /// read, `mark` sets [`MpuSample::out_of_band`](crate::MpuSample::out_of_band)
+    Allow {
+        /// flag the delivered samples
+        mark: bool,
+    },
+}
+
+impl<I, D> Mpu6050<I, D> {
+    /// Schema of the FIFO sources, aux slaves and ranges last written by the driver, see
+    /// [`fifo`](crate::fifo). No bus access
+    pub fn fifo_schema(&self) -> FifoSchema {
+        let mut ext_len = [0; 4];
+        let layout = ext_data_layout(&self.aux.slaves()).unwrap_or([None; 4]);
+        for (len, entry) in ext_len.iter_mut().zip(layout) {
+            *len = entry.map_or(0, |(_, len)| len);
+        }
+        let mut schema = FifoSchema::new(
+            self.fifo_sources,
+            ext_len,
+            self.accel_range,
+            self.gyro_range,
+        )
+        .with_sensitivities(self.acc_scale.nominal, self.gyro_scale.nominal)
+        .with_temp_model(self.temp_model());
+        schema.generation = self.config_epoch();
+        schema
+    }
+
+    /// Decodes a frame with `schema`, which must be of the current configuration epoch
+    pub fn parse_fifo_frame<E>(
+        &self,
+        schema: &FifoSchema,
+        bytes: &[u8],
+    ) -> Result<FifoFrame, Mpu6050Error<E>> {
+        self.check_epoch(schema.generation)?;
+        parse_fifo_frame(schema, bytes)
+            .map_err(|_| Mpu6050Error::BufferTooSmall(schema.frame_len()))
+    }
+
+    /// true if the driver enabled the FIFO and has not disabled or reset it since
+    pub fn fifo_streaming(&self) -> bool {
+        self.fifo_streaming
+    }
+
+    /// set what direct scaled reads do while FIFO streaming
+    pub fn set_mixed_read_policy(&mut self, policy: MixedReadPolicy) {
+        self.mixed_read_policy = policy;
+    }
+
+    /// get mixed read policy
+    pub fn get_mixed_read_policy(&self) -> MixedReadPolicy {
+        self.mixed_read_policy
+    }
+
+    /// Admits a direct scaled read under the mixed read policy, returns whether its samples
+    /// are marked out of band
+    #[cfg(not(feature = "minimal-pipeline"))]
+    pub(crate) fn check_direct_read<E>(&self) -> Result<bool, Mpu6050Error<E>> {
+        match (self.fifo_streaming, self.mixed_read_policy) {
+            (false, _) => Ok(false),
+            (true, MixedReadPolicy::Reject) => Err(Mpu6050Error::FifoActive),
+            (true, MixedReadPolicy::Allow { mark }) => Ok(mark),
+        }
+    }
+}
+
+#[cfg(feature = "driver")]
+impl<I, D, E> Mpu6050<I, D>
+where
+    I: Write<Error = E> + WriteRead<Error = E>,
+{
+    /// Selects what is written to the FIFO (FIFO_EN, SLV_3_FIFO_EN) and returns the new
+    /// schema. Fails with [`Mpu6050Error::Unsupported`] on chips without FIFO
+    pub fn set_fifo_sources(
+        &mut self,
+        sources: FifoSources,
+    ) -> Result<FifoSchema, Mpu6050Error<E>> {
+        self.require(Capability::Fifo)?;
+        self.bump_epoch(ConfigChange::FifoSources);
+        self.write_fields(sources.fifo_en_value())?;
+        if sources.slaves[3] != self.fifo_sources.slaves[3] {
+            self.write_field(I2C_MST_CTRL::SLV_3_FIFO_EN_FIELD, sources.slaves[3])?;
+        }
+        self.fifo_sources = sources;
+        Ok(self.fifo_schema())
+    }
+
+    /// sources last written with [`set_fifo_sources`](Self::set_fifo_sources)
+    pub fn get_fifo_sources(&self) -> FifoSources {
+        self.fifo_sources
+    }
+
+    /// enable, disable writing to the FIFO (USER_CTRL FIFO_EN). While enabled, direct scaled
+    /// reads follow the [`MixedReadPolicy`]
+    pub fn set_fifo_enabled(&mut self, enable: bool) -> Result<(), Mpu6050Error<E>> {
+        self.write_field(USER_CTRL::FIFO_EN_FIELD, enable)?;
+        if enable && !self.fifo_streaming {
+            self.fifo_timeline.restart();
+        }
+        self.fifo_streaming = enable;
+        Ok(())
+    }
+
+    /// empties the FIFO (USER_CTRL FIFO_RESET)
+    pub fn reset_fifo(&mut self) -> Result<(), Mpu6050Error<E>> {
+        self.write_field(USER_CTRL::FIFO_RESET_FIELD, true)?;
+        self.fifo_timeline.emptied();
+        Ok(())
+    }
+
+    /// bytes in the FIFO
+    pub fn fifo_count(&mut self) -> Result<u16, Mpu6050Error<E>> {
+        Ok(self.read_register_word(Register::FIFO_COUNTH)? as u16)
+    }
+
+    /// Reads the complete frames in the FIFO that fit into `buf` in one transaction and
+    /// passes them to `on_frame` in order. Returns the number of frames. `schema` must be of
+    /// the current configuration epoch, otherwise nothing is read, see [`fifo`](crate::fifo)
+    pub fn drain_fifo(
+        &mut self,
+        schema: &FifoSchema,
+        buf: &mut [u8],
+        mut on_frame: impl FnMut(FifoFrame),
+    ) -> Result<usize, Mpu6050Error<E>> {
+        self.drain_fifo_with(schema, buf, |_, frame| on_frame(frame))
+    }
+
+    /// [`drain_fifo`](Self::drain_fifo) passing the driver along with every frame, for the
+    /// drains delivering scaled samples
+    pub(crate) fn drain_fifo_with(
+        &mut self,
+        schema: &FifoSchema,
+        buf: &mut [u8],
+        mut on_frame: impl FnMut(&mut Self, FifoFrame),
+    ) -> Result<usize, Mpu6050Error<E>> {
+        self.check_active()?;
+        self.check_epoch(schema.generation)?;
+        let len = schema.frame_len();
+        if len == 0 {
+            return Ok(0);
+        }
+        if buf.len() < len {
+            return Err(Mpu6050Error::BufferTooSmall(len));
+        }
+        let count = self.fifo_count()?;
+        if self.recover_fifo_overflow(count, len)? {
+            return Ok(0);
+        }
+        let frames = (count as usize / len).min(buf.len() / len);
+        if frames == 0 {
+            return Ok(0);
+        }
+        let bytes = &mut buf[..frames * len];
+        self.read_registers(Register::FIFO_R_W, bytes)?;
+        self.note_fifo_drained(frames);
+        for chunk in bytes.chunks_exact(len) {
+            let frame = self.parse_fifo_frame(schema, chunk)?;
+            let frame = self.stamp_fifo_frame(frame);
+            on_frame(self, frame);
+        }
+        Ok(frames)
+    }
+}
@@ -0,0 +1,294 @@
+//! FIFO overflow recovery, one specified algorithm for every drain.
+//!
+//! The FIFO holds [`FIFO_CAPACITY`] bytes. Once full the chip keeps FIFO_COUNT at the
+//! capacity and every further frame overwrites the oldest bytes: the content is no longer a
+//! sequence of whole frames, and nothing in it tells where the next whole frame starts.
+//! [`drain_fifo`](Mpu6050::drain_fifo), [`drain_fifo_cooperative`](Mpu6050::drain_fifo_cooperative)
+//! and [`drain_fifo_into`](Mpu6050::drain_fifo_into) all go through the same steps after
+//! their FIFO_COUNT read:
+//!
+//! 1. a count below the capacity is no overflow: the drain reads its frames, and the driver
+//!    notes the whole frames it left in the FIFO and, with a [`FifoClock`], the time of the
+//!    count read
+//! 2. a count at the capacity is an overflow: the count is kept as the pre-reset count and
+//!    the FIFO is reset (USER_CTRL FIFO_RESET). Nothing is read off FIFO_R_W, the bytes in
+//!    the FIFO are discarded unparsed, and the drain returns no frames
+//! 3. the frames lost are estimated, see below, and recorded as a pending [`FifoGap`]; the
+//!    [`FifoOverflow`](crate::metrics::MetricEvent::FifoOverflow) event carries the
+//!    pre-reset count to the metrics sink and the log
+//! 4. the next drain reads the frames written since the reset, the first of them carries
+//!    the gap: [`FifoFrame::gap`], or [`DrainProgress::gap`](crate::cooperative::DrainProgress::gap)
+//!    for the frame at the start of `out`
+//!
+//! A reset failing on the bus fails the drain and records no gap: the next drain finds the
+//! FIFO still full and runs the recovery again, the gap then spans both.
+//!
+//! #### Lost frames
+//! With a [`FifoClock`] the estimate is [`GapEstimate::Elapsed`]: the frames left by the
+//! previous drain plus the time between its count read and this one times the output data
+//! rate of [`check_aliasing`](Mpu6050::check_aliasing), rounded, at least the whole frames
+//! of the pre-reset count. Without a clock it is [`GapEstimate::FifoContent`], the whole
+//! frames of the pre-reset count: a lower bound, the frames written over are not counted. A
+//! second overflow before a frame carried the first gap adds to it, see
+//! [`overflows`](FifoGap::overflows).
+//!
+//! #### Guarantees
+//! * no duplicated frames: every frame is parsed from bytes taken off FIFO_R_W once, and a
+//!   drain parses only whole frames of the count it read
+//! * no reordered frames: frames are delivered in FIFO order, and after a count at the
+//!   capacity no byte of that FIFO content is parsed
+//! * gaps are explicit: every reset by the recovery is reported on the next frame
+//!   delivered, and [`FifoFrame::sequence`] moves on by the frames lost; until then
+//!   [`Mpu6050::fifo_recovery_status`] has it as `pending_gap`
+//! * bounded detection latency: an overflow is found by the count read of the next drain,
+//!   within one drain cycle
+//!
+//! The count is the only evidence the driver takes, so the guarantees assume the FIFO does
+//! not fill up between the count read of a drain and its last frame read: drain often
+//! enough to leave room for the frames the chip writes while the drain reads.
+//!
+//! [`FifoFrame::sequence`] numbers the frames of one stream from 0: enabling the FIFO with
+//! [`set_fifo_enabled`](Mpu6050::set_fifo_enabled) while not streaming starts a new timeline.
+//! After a gap the numbers are an estimate as good as the gap's.
+//! ```
+//! use mpu6050::fifo_recovery::{FifoGap, GapEstimate};
+//!
+//! // FIFO full with 12 byte frames, no clock: the 85 whole frames discarded
+//! let gap = FifoGap::from_content(1024, 12);
+//! assert_eq!(gap.lost_frames, 85);
+//! assert_eq!(gap.estimate, GapEstimate::FifoContent);
+//!
+//! // 10 frames left behind, 120 ms since at 1 kHz
+//! let gap = FifoGap::from_elapsed(1024, 12, 10, 120_000, 1000.);
+//! assert_eq!(gap.lost_frames, 130);
+//! ```
+
+#[cfg(feature = "driver")]
+use embedded_hal::blocking::i2c::{Write, WriteRead};
+
+#[cfg(feature = "driver")]
+use crate::fifo::{FifoFrame, FIFO_CAPACITY};
+#[cfg(feature = "driver")]
+use crate::metrics::{self, MetricEvent};
+use crate::time_math::elapsed_us;
+use crate::Mpu6050;
+#[cfg(feature = "driver")]
+use crate::Mpu6050Error;
+
+/// Monotonic time in µs for the lost frame estimate, an extended timestamp, see
+/// [`time_math`](crate::time_math)
+pub type FifoClock = fn() -> u64;
+
+/// How [`FifoGap::lost_frames`] was estimated
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum GapEstimate {
+    /// from the time since the previous drain and the output data rate
+    Elapsed,
+    /// from the pre-reset count alone, a lower bound
+    FifoContent,
+}
+
+/// Frames lost to one or more FIFO overflows, see the [module docs](self)
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct FifoGap {
+    /// FIFO_COUNT when the last overflow was detected
+    pub pre_reset_count: u16,
+    /// best estimate of the frames lost
+    pub lost_frames: u32,
+    /// how `lost_frames` was estimated, the weaker of the overflows merged
+    pub estimate: GapEstimate,
+    /// overflows merged into this gap, 1 unless the FIFO overflowed again before a frame
+    /// carried the gap
+    pub overflows: u16,
+}
+
+impl FifoGap {
+    /// gap of an overflow at `count` bytes of `frame_len` byte frames, without a clock
+    pub fn from_content(count: u16, frame_len: usize) -> Self {
+        Self {
+            pre_reset_count: count,
+            lost_frames: (count as usize / frame_len.max(1)) as u32,
+            estimate: GapEstimate::FifoContent,
+            overflows: 1,
+        }
+    }
+
+    /// gap of an overflow at `count` bytes of `frame_len` byte frames, `left_frames` left in
+    /// the FIFO by the previous drain `elapsed_us` before, at `odr_hz`
+    pub fn from_elapsed(
+        count: u16,
+        frame_len: usize,
+        left_frames: u32,
+        elapsed_us: u64,
+        odr_hz: f32,
+    ) -> Self {
+        let content = Self::from_content(count, frame_len);
+        let written = (elapsed_us as f64 * odr_hz as f64 / 1e6 + 0.5) as u64;
+        let lost = (left_frames as u64 + written).min(u32::MAX as u64) as u32;
+        Self {
+            lost_frames: lost.max(content.lost_frames),
+            estimate: GapEstimate::Elapsed,
+            ..content
+        }
+    }
+
+    /// `later`, a gap detected before this one was delivered, added in
+    fn merge(self, later: Self) -> Self {
+        let estimate = match (self.estimate, later.estimate) {
+            (GapEstimate::Elapsed, GapEstimate::Elapsed) => GapEstimate::Elapsed,
+            _ => GapEstimate::FifoContent,
+        };
+        Self {
+            pre_reset_count: later.pre_reset_count,
+            lost_frames: self.lost_frames.saturating_add(later.lost_frames),
+            estimate,
+            overflows: self.overflows.saturating_add(later.overflows),
+        }
+    }
+}
+
+/// Result of [`Mpu6050::fifo_recovery_status`]
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub struct FifoRecoveryStatus {
+    /// overflows recovered from since the driver was built
+    pub overflows: u32,
+    /// frames estimated lost in them
+    pub lost_frames: u64,
+    /// gap not yet carried by a frame
+    pub pending_gap: Option<FifoGap>,
+    /// [`sequence`](crate::fifo::FifoFrame::sequence) of the next frame
+    pub next_sequence: u64,
+}
+
+/// Where the driver is on the FIFO timeline
+#[derive(Copy, Clone, Debug, Default)]
+pub(crate) struct FifoTimeline {
+    clock: Option<FifoClock>,
+    next_sequence: u64,
+    left_frames: u32,
+    last_count_us: Option<u64>,
+    pending: Option<FifoGap>,
+    overflows: u32,
+    lost_frames: u64,
+}
+
+impl FifoTimeline {
+    /// the stream starts again at sequence 0, timed from now
+    pub(crate) fn restart(&mut self) {
+        *self = Self {
+            clock: self.clock,
+            overflows: self.overflows,
+            lost_frames: self.lost_frames,
+            ..Self::default()
+        };
+        self.emptied();
+    }
+
+    /// the FIFO was emptied outside the recovery
+    pub(crate) fn emptied(&mut self) {
+        self.left_frames = 0;
+        self.last_count_us = self.clock.map(|clock| clock());
+    }
+
+    /// gap for the next frame, the sequence moved past the frames lost
+    fn record(&mut self, gap: FifoGap) {
+        self.overflows = self.overflows.saturating_add(1);
+        self.lost_frames = self.lost_frames.saturating_add(gap.lost_frames as u64);
+        self.next_sequence = self.next_sequence.wrapping_add(gap.lost_frames as u64);
+        self.pending = Some(match self.pending {
+            Some(pending) => pending.merge(gap),
+            None => gap,
+        });
+    }
+
+    /// the sequence of the first of `frames` delivered and the gap before it
+    pub(crate) fn deliver(&mut self, frames: usize) -> (u64, Option<FifoGap>) {
+        let first = self.next_sequence;
+        if frames == 0 {
+            return (first, None);
+        }
+        self.next_sequence = first.wrapping_add(frames as u64);
+        (first, self.pending.take())
+    }
+}
+
+impl<I, D> Mpu6050<I, D> {
+    /// Sets the clock the lost frame estimate of an overflow takes the elapsed time from,
+    /// None for the lower bound from the FIFO content, see [`fifo_recovery`](self)
+    pub fn set_fifo_clock(&mut self, clock: Option<FifoClock>) {
+        self.fifo_timeline.clock = clock;
+        self.fifo_timeline.last_count_us = clock.map(|clock| clock());
+    }
+
+    /// get FIFO clock
+    pub fn get_fifo_clock(&self) -> Option<FifoClock> {
+        self.fifo_timeline.clock
+    }
+
+    /// Overflows recovered from, the frames lost, the gap not yet delivered. No bus access
+    pub fn fifo_recovery_status(&self) -> FifoRecoveryStatus {
+        let timeline = &self.fifo_timeline;
+        FifoRecoveryStatus {
+            overflows: timeline.overflows,
+            lost_frames: timeline.lost_frames,
+            pending_gap: timeline.pending,
+            next_sequence: timeline.next_sequence,
+        }
+    }
+
+    /// `frame` as the next frame of the stream
+    #[cfg(feature = "driver")]
+    pub(crate) fn stamp_fifo_frame(&mut self, mut frame: FifoFrame) -> FifoFrame {
+        (frame.sequence, frame.gap) = self.fifo_timeline.deliver(1);
+        frame
+    }
+}
+
+#[cfg(feature = "driver")]
+impl<I, D, E> Mpu6050<I, D>
+where
+    I: Write<Error = E> + WriteRead<Error = E>,
+{
+    /// Steps 1 to 3 of the recovery after the FIFO_COUNT read of a drain, true if the FIFO
+    /// overflowed and was reset: the drain then reads nothing
+    pub(crate) fn recover_fifo_overflow(
+        &mut self,
+        count: u16,
+        frame_len: usize,
+    ) -> Result<bool, Mpu6050Error<E>> {
+        let now = self.fifo_timeline.clock.map(|clock| clock());
+        if count < FIFO_CAPACITY {
+            self.fifo_timeline.left_frames = (count as usize / frame_len) as u32;
+            self.fifo_timeline.last_count_us = now;
+            return Ok(false);
+        }
+        self.emit_event(
+            metrics::FIFO_OVERFLOWS,
+            MetricEvent::FifoOverflow { bytes: count },
+        );
+        let odr_hz = self.check_aliasing().odr_hz;
+        let timeline = self.fifo_timeline;
+        self.reset_fifo()?;
+        let gap = match (timeline.last_count_us, now) {
+            (Some(last), Some(now)) => FifoGap::from_elapsed(
+                count,
+                frame_len,
+                timeline.left_frames,
+                elapsed_us(last, now),
+                odr_hz,
+            ),
+            _ => FifoGap::from_content(count, frame_len),
+        };
+        let timeline = &mut self.fifo_timeline;
+        timeline.record(gap);
+        timeline.left_frames = 0;
+        timeline.last_count_us = now;
+        Ok(true)
+    }
+
+    /// the drain after step 1 read `frames` of the whole frames it counted
+    pub(crate) fn note_fifo_drained(&mut self, frames: usize) {
+        let left = &mut self.fifo_timeline.left_frames;
+        *left = left.saturating_sub(frames as u32);
+    }
+}
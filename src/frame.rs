@@ -0,0 +1,60 @@
+//! Decoding of sensor data registers.
+//!
+//! The data registers hold big endian two's complement words starting at ACCEL_XOUT_H (0x3b):
+//! accel x, y, z, temperature, gyro x, y, z, 14 bytes in total. A [`RawFrame`] is one such
+//! burst, logs of raw frames can be run through [`Pipeline`](crate::scale::Pipeline) offline with
+//! the exact scaling of the driver.
+
+/// Bytes in a full accel, temp, gyro burst
+pub const FRAME_LEN: usize = 14;
+
+/// Raw counts of one burst read
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub struct RawFrame {
+    /// accelerometer x, y, z
+    pub acc: [i16; 3],
+    /// temperature
+    pub temp: i16,
+    /// gyro x, y, z
+    pub gyro: [i16; 3],
+}
+
+/// big endian two's complement word from the first 2 bytes
+pub fn word(bytes: &[u8]) -> i16 {
+    i16::from_be_bytes([bytes[0], bytes[1]])
+}
+
+/// x, y, z words from the first 6 bytes
+pub fn triplet(bytes: &[u8]) -> [i16; 3] {
+    [word(&bytes[0..2]), word(&bytes[2..4]), word(&bytes[4..6])]
+}
+
+/// Decodes a burst read starting at ACCEL_XOUT_H
+pub fn parse_frame(bytes: &[u8; FRAME_LEN]) -> RawFrame {
+    RawFrame {
+        acc: triplet(&bytes[0..6]),
+        temp: word(&bytes[6..8]),
+        gyro: triplet(&bytes[8..14]),
+    }
+}
+
+/// Encodes a frame the way the device lays it out, inverse of [`parse_frame`]
+pub fn encode_frame(frame: &RawFrame) -> [u8; FRAME_LEN] {
+    let mut bytes = [0; FRAME_LEN];
+    let [ax, ay, az] = frame.acc;
+    let [gx, gy, gz] = frame.gyro;
+    let words = [ax, ay, az, frame.temp, gx, gy, gz];
+    for (chunk, word) in bytes.chunks_exact_mut(2).zip(words) {
+        chunk.copy_from_slice(&word.to_be_bytes());
+    }
+    bytes
+}
+
+/// Decodes consecutive frames, trailing bytes not forming a full frame are ignored
+pub fn parse_frames(bytes: &[u8]) -> impl Iterator<Item = RawFrame> + '_ {
+    bytes.chunks_exact(FRAME_LEN).map(|chunk| {
+        let mut frame = [0; FRAME_LEN];
+        frame.copy_from_slice(chunk);
+        parse_frame(&frame)
+    })
+}
@@ -0,0 +1,361 @@
+//! FSYNC tag bookkeeping for camera-IMU synchronization.
+//!
+//! With CONFIG EXT_SYNC_SET selecting an output, the chip latches edges on the FSYNC pin into
+//! the LSB of that output: a sample is tagged if at least one edge arrived since the previous
+//! sample. [`fsync_tag`] extracts the tag from a [`RawFrame`], [`fsync_tag_fifo`] from a
+//! [`FifoFrame`].
+//!
+//! The tracker is fed from either read path:
+//! * burst reads, [`Mpu6050::get_all_fsync`]: [`get_all`](Mpu6050::get_all) with the tag of
+//!   the same burst, fed as the next sample
+//! * FIFO drains, [`FsyncTracker::feed_fifo`] in the callback of
+//!   [`drain_fifo`](Mpu6050::drain_fifo): the frame's position on the FIFO timeline is the
+//!   sample index, so frames lost to an overflow are skipped indices, not samples without a
+//!   tag. The latched output must be a source of the FIFO, frames without it are not fed
+//!
+//! [`FsyncTracker`] is fed every sample with its tag and counts edges. Two edges within one
+//! sample interval produce a single tag, which no tag stream can tell apart from one edge.
+//! The tracker detects it where possible instead of counting silently wrong:
+//! * with the trigger rate known (`expected_trigger_hz`), every tag interval is converted into
+//!   a number of trigger periods, carrying the fraction over, so both skipped and merged edges
+//!   are counted as missed
+//! * without it, the trigger interval is estimated as the median of recent tag intervals. A
+//!   tag interval within `tolerance` periods of an integer multiple `k >= 2` counts `k - 1`
+//!   missed edges. Tags on consecutive samples while the estimated interval is not longer than
+//!   the sample interval mean the trigger is as fast as the sampling: edges may merge and
+//!   [`FsyncStats::aliasing`] is set. Estimated intervals shorter than two samples are too
+//!   coarse to count periods, no edges are inferred then
+//!
+//! No bus access in the tracker.
+
+#[cfg(feature = "driver")]
+use embedded_hal::blocking::i2c::{Write, WriteRead};
+
+#[cfg(feature = "driver")]
+use crate::device::CONFIG;
+use crate::device::EXT_SYNC;
+use crate::fifo::FifoFrame;
+#[cfg(feature = "driver")]
+use crate::frame::{parse_frame, FRAME_LEN};
+use crate::frame::RawFrame;
+#[cfg(not(feature = "std"))]
+use crate::math::FloatExt;
+use crate::time_math::elapsed_us;
+#[cfg(feature = "driver")]
+use crate::MpuSample;
+#[cfg(feature = "driver")]
+use crate::{Mpu6050, Mpu6050Error};
+
+/// Number of tags kept by a [`FsyncTracker`]
+pub const FSYNC_RING_LEN: usize = 16;
+
+/// FSYNC tag of `frame` latched at `sync`, false if FSYNC is disabled
+pub fn fsync_tag(frame: &RawFrame, sync: EXT_SYNC) -> bool {
+    let word = match sync {
+        EXT_SYNC::DISABLED => return false,
+        EXT_SYNC::TEMP_OUT_L => frame.temp,
+        EXT_SYNC::GYRO_XOUT_L => frame.gyro[0],
+        EXT_SYNC::GYRO_YOUT_L => frame.gyro[1],
+        EXT_SYNC::GYRO_ZOUT_L => frame.gyro[2],
+        EXT_SYNC::ACCEL_XOUT_L => frame.acc[0],
+        EXT_SYNC::ACCEL_YOUT_L => frame.acc[1],
+        EXT_SYNC::ACCEL_ZOUT_L => frame.acc[2],
+    };
+    word & 1 != 0
+}
+
+/// FSYNC tag of a FIFO `frame` latched at `sync`, false if FSYNC is disabled. None if the
+/// latched output is not in the frame
+pub fn fsync_tag_fifo(frame: &FifoFrame, sync: EXT_SYNC) -> Option<bool> {
+    let word = match sync {
+        EXT_SYNC::DISABLED => return Some(false),
+        EXT_SYNC::TEMP_OUT_L => frame.temp,
+        EXT_SYNC::GYRO_XOUT_L => frame.gyro[0],
+        EXT_SYNC::GYRO_YOUT_L => frame.gyro[1],
+        EXT_SYNC::GYRO_ZOUT_L => frame.gyro[2],
+        EXT_SYNC::ACCEL_XOUT_L => frame.acc.map(|acc| acc[0]),
+        EXT_SYNC::ACCEL_YOUT_L => frame.acc.map(|acc| acc[1]),
+        EXT_SYNC::ACCEL_ZOUT_L => frame.acc.map(|acc| acc[2]),
+    }?;
+    Some(word & 1 != 0)
+}
+
+/// Options of a [`FsyncTracker`]
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct FsyncConfig {
+    /// nominal external trigger rate, None to estimate it from the tags
+    pub expected_trigger_hz: Option<f32>,
+    /// allowed deviation from an integer number of trigger periods, in periods, default 0.25
+    pub tolerance: f32,
+}
+
+impl Default for FsyncConfig {
+    fn default() -> Self {
+        Self {
+            expected_trigger_hz: None,
+            tolerance: 0.25,
+        }
+    }
+}
+
+/// Tagged sample
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct FsyncTag {
+    /// sample index
+    pub index: u64,
+    /// sample timestamp in µs
+    pub t_us: u64,
+    /// edges estimated missed between the previous tag and this one
+    pub missed_before: u32,
+}
+
+/// Counters of a [`FsyncTracker`]
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct FsyncStats {
+    /// samples fed
+    pub samples: u64,
+    /// tagged samples
+    pub tags: u64,
+    /// edges estimated missed
+    pub missed_edges: u64,
+    /// edge count, tags plus missed edges, never decreases
+    pub edges: u64,
+    /// tags on the sample right after another tag
+    pub consecutive_tags: u64,
+    /// most recent tag
+    pub last_tag: Option<FsyncTag>,
+    /// trigger interval in µs, expected or estimated
+    pub trigger_interval_us: Option<f32>,
+    /// trigger rate in Hz, expected or estimated
+    pub trigger_rate_hz: Option<f32>,
+    /// the trigger is at least as fast as the sampling, edges may merge undetected
+    pub aliasing: bool,
+}
+
+/// FSYNC edge counter fed with tagged samples, see the [module docs](self)
+#[derive(Copy, Clone, Debug)]
+pub struct FsyncTracker {
+    config: FsyncConfig,
+    samples: u64,
+    tags: u64,
+    missed: u64,
+    consecutive: u64,
+    first_sample: Option<(u64, u64)>,
+    last_sample: Option<(u64, u64)>,
+    ring: [Option<FsyncTag>; FSYNC_RING_LEN],
+    carry: f32,
+    aliasing: bool,
+}
+
+impl FsyncTracker {
+    /// new tracker without samples
+    pub fn new(config: FsyncConfig) -> Self {
+        Self {
+            config,
+            samples: 0,
+            tags: 0,
+            missed: 0,
+            consecutive: 0,
+            first_sample: None,
+            last_sample: None,
+            ring: [None; FSYNC_RING_LEN],
+            carry: 0.,
+            aliasing: false,
+        }
+    }
+
+    /// options in use
+    pub fn config(&self) -> &FsyncConfig {
+        &self.config
+    }
+
+    /// Feeds sample `index` taken at `t_us`. Indices and timestamps must increase, other
+    /// samples are ignored. Returns the tag if the sample is tagged
+    pub fn feed(&mut self, index: u64, t_us: u64, tagged: bool) -> Option<FsyncTag> {
+        if let Some((last_index, last_t)) = self.last_sample {
+            if index <= last_index || t_us <= last_t {
+                return None;
+            }
+        }
+        self.samples += 1;
+        self.first_sample.get_or_insert((index, t_us));
+        self.last_sample = Some((index, t_us));
+        if !tagged {
+            return None;
+        }
+
+        let missed_before = match self.last_tag() {
+            Some(previous) => {
+                if index == previous.index + 1 {
+                    self.consecutive += 1;
+                }
+                self.missed_between(previous, index, t_us)
+            }
+            None => 0,
+        };
+        let tag = FsyncTag {
+            index,
+            t_us,
+            missed_before,
+        };
+        self.ring.copy_within(1.., 0);
+        self.ring[FSYNC_RING_LEN - 1] = Some(tag);
+        self.tags += 1;
+        self.missed += missed_before as u64;
+        Some(tag)
+    }
+
+    fn missed_between(&mut self, previous: FsyncTag, index: u64, t_us: u64) -> u32 {
+        let interval = elapsed_us(previous.t_us, t_us) as f32;
+        match self.config.expected_trigger_hz {
+            Some(hz) => {
+                let period = 1e6 / hz;
+                self.aliasing |= self
+                    .sample_interval_us()
+                    .is_some_and(|sample| period < sample);
+                self.carry += interval / period;
+                let periods = self.carry.round().max(1.);
+                self.carry -= periods;
+                periods as u32 - 1
+            }
+            None => {
+                let Some(period) = self.median_interval_us() else {
+                    return 0;
+                };
+                let sample = self.sample_interval_us().unwrap_or(f32::INFINITY);
+                if index == previous.index + 1 {
+                    self.aliasing |= period <= sample * (1. + self.config.tolerance);
+                }
+                // below two sample intervals the estimate is too coarse to count periods
+                if period < 2. * sample {
+                    return 0;
+                }
+                let periods = interval / period;
+                let k = periods.round();
+                if k >= 2. && (periods - k).abs() <= self.config.tolerance {
+                    k as u32 - 1
+                } else {
+                    0
+                }
+            }
+        }
+    }
+
+    /// [`feed`](Self::feed) with the index after the last sample fed, 0 for the first
+    pub fn feed_next(&mut self, t_us: u64, tagged: bool) -> Option<FsyncTag> {
+        let index = self.last_sample.map_or(0, |(index, _)| index + 1);
+        self.feed(index, t_us, tagged)
+    }
+
+    /// Feeds a drained FIFO `frame` with its tag at `sync`, its
+    /// [`sequence`](FifoFrame::sequence) as index and `sequence * sample_interval_us` as
+    /// timestamp. Frames without the latched output are not fed, see the [module docs](self)
+    pub fn feed_fifo(
+        &mut self,
+        frame: &FifoFrame,
+        sync: EXT_SYNC,
+        sample_interval_us: u64,
+    ) -> Option<FsyncTag> {
+        let tagged = fsync_tag_fifo(frame, sync)?;
+        let t_us = frame.sequence.saturating_mul(sample_interval_us);
+        self.feed(frame.sequence, t_us, tagged)
+    }
+
+    /// most recent tag
+    pub fn last_tag(&self) -> Option<FsyncTag> {
+        self.ring[FSYNC_RING_LEN - 1]
+    }
+
+    /// the last [`FSYNC_RING_LEN`] tags, oldest first
+    pub fn recent_tags(&self) -> impl Iterator<Item = &FsyncTag> {
+        self.ring.iter().flatten()
+    }
+
+    /// mean sample interval in µs, None before the second sample
+    pub fn sample_interval_us(&self) -> Option<f32> {
+        let ((i0, t0), (i1, t1)) = (self.first_sample?, self.last_sample?);
+        (i1 > i0).then(|| elapsed_us(t0, t1) as f32 / (i1 - i0) as f32)
+    }
+
+    /// Median of the recent tag intervals, skipping intervals with missed edges. None with
+    /// fewer than 3 intervals
+    fn median_interval_us(&self) -> Option<f32> {
+        let mut intervals = [0f32; FSYNC_RING_LEN];
+        let mut n = 0;
+        for pair in self.ring.windows(2) {
+            if let [Some(a), Some(b)] = pair {
+                if b.missed_before == 0 {
+                    intervals[n] = elapsed_us(a.t_us, b.t_us) as f32;
+                    n += 1;
+                }
+            }
+        }
+        if n < 3 {
+            return None;
+        }
+        let intervals = &mut intervals[..n];
+        intervals.sort_unstable_by(f32::total_cmp);
+        Some(intervals[n / 2])
+    }
+
+    /// Mean trigger interval over the recent tags, missed edges included. Less affected by
+    /// the sample quantization of the tag timestamps than the median
+    fn mean_interval_us(&self) -> Option<f32> {
+        let mut tags = self.recent_tags();
+        let first = tags.next()?;
+        let (last, edges) = tags.fold((first, 0), |(_, edges), tag| {
+            (tag, edges + 1 + tag.missed_before)
+        });
+        (edges > 0).then(|| elapsed_us(first.t_us, last.t_us) as f32 / edges as f32)
+    }
+
+    /// counters and estimates
+    pub fn stats(&self) -> FsyncStats {
+        let trigger_interval_us = match self.config.expected_trigger_hz {
+            Some(hz) => Some(1e6 / hz),
+            None => self.mean_interval_us(),
+        };
+        FsyncStats {
+            samples: self.samples,
+            tags: self.tags,
+            missed_edges: self.missed,
+            edges: self.tags + self.missed,
+            consecutive_tags: self.consecutive,
+            last_tag: self.last_tag(),
+            trigger_interval_us,
+            trigger_rate_hz: trigger_interval_us.map(|us| 1e6 / us),
+            aliasing: self.aliasing,
+        }
+    }
+}
+
+#[cfg(feature = "driver")]
+impl<I, D, E> Mpu6050<I, D>
+where
+    I: Write<Error = E> + WriteRead<Error = E>,
+{
+    /// set where FSYNC edges are latched (CONFIG EXT_SYNC_SET)
+    pub fn set_fsync_source(&mut self, sync: EXT_SYNC) -> Result<(), Mpu6050Error<E>> {
+        self.write_field(CONFIG::EXT_SYNC_SET_FIELD, sync)
+    }
+
+    /// get where FSYNC edges are latched
+    pub fn get_fsync_source(&mut self) -> Result<EXT_SYNC, Mpu6050Error<E>> {
+        Ok(EXT_SYNC::from(self.read_field(CONFIG::EXT_SYNC_SET_FIELD)?))
+    }
+
+    /// [`get_all`](Self::get_all) and the FSYNC tag at `sync` of the same burst, fed to
+    /// `tracker` as the next sample at `t_us`. Returns the sample and its tag if tagged. A
+    /// failed read feeds nothing
+    pub fn get_all_fsync(
+        &mut self,
+        sync: EXT_SYNC,
+        tracker: &mut FsyncTracker,
+        t_us: u64,
+    ) -> Result<(MpuSample, Option<FsyncTag>), Mpu6050Error<E>> {
+        let mut bytes = [0; FRAME_LEN];
+        let sample = self.read_sample_burst_into(&mut bytes)?;
+        let tagged = fsync_tag(&parse_frame(&bytes), sync);
+        Ok((sample, tracker.feed_next(t_us, tagged)))
+    }
+}
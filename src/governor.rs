@@ -0,0 +1,637 @@
+//! Activity driven switching between operating points.
+//!
+//! A [`PowerGovernor`] holds two or three [`OperatingPoint`]s ordered from lowest to highest
+//! power, e.g. rest, walk and run, and moves between neighbouring points based on the activity
+//! over the samples of the last `window_ms` ([`ActivityMetrics`]):
+//! * gyro RMS in rad/s, the root of the mean squared rate magnitude
+//! * accel variance in g², summed over the axes
+//!
+//! Each pair of neighbouring points has a [`GovernorRule`]. The governor steps up when either
+//! metric exceeds the `up` level and steps down when both are below the `down` level, which
+//! must be lower on both metrics: activity between the two levels keeps the current point.
+//! A point is left only after its `min_dwell_ms` and once the window is full again, it is
+//! cleared on every transition so samples taken at the previous point do not count. The
+//! window is a time span rather than a sample count, so the metrics mean the same at every
+//! sample rate; it is kept in [`ACTIVITY_BUCKETS`] sums of fixed size.
+//! [`PowerGovernor::motion_wake`] is the fast path out of rest, e.g. on the motion interrupt:
+//! it goes straight to the highest point regardless of dwell.
+//!
+//! Time advances by the sample interval of the current point for every sample fed, and by
+//! [`PowerGovernor::tick`] for time without samples. The governor is a pure state machine:
+//! [`feed`](PowerGovernor::feed), [`tick`](PowerGovernor::tick) and `motion_wake` propose a
+//! [`GovernorTransition`], [`commit`](PowerGovernor::commit) takes it once the settings are in
+//! effect. Installed in the driver with
+//! [`Mpu6050::set_power_governor`] it is fed every full sample read (`run_sampling_loop`,
+//! `sample_into_interp_buffer`, ...) that is not settling, and transitions are written with
+//! [`Mpu6050::apply_settings_diff`], so registers are only written when the point changes.
+//!
+//! A transition changes the sample rate. Consumers integrating over time take the new
+//! interval from [`GovernorTransition::sample_interval_s`], e.g. as `dt_s` of
+//! [`GravityTrim::feed`](crate::gravity_trim::GravityTrim::feed) or for
+//! [`DifferentialPair::set_sample_interval_us`](crate::differential::DifferentialPair::set_sample_interval_us),
+//! through the callback in [`GovernorConfig`] or the transitions returned by the driver.
+//! Transitions performed by the driver also carry the estimated current of both points,
+//! see [`power`](crate::power).
+//! ```
+//! use mpu6050::governor::*;
+//! use mpu6050::{presets, MpuSample, Vec3A};
+//!
+//! let rest = OperatingPoint::new("rest", presets::LOW_POWER_TILT, 0);
+//! let active = OperatingPoint::new("active", presets::HANDHELD_UI, 2000);
+//! let rule = GovernorRule {
+//!     up: ActivityLevel { gyro_rms: 0.3, acc_variance: 0.01 },
+//!     down: ActivityLevel { gyro_rms: 0.1, acc_variance: 0.002 },
+//! };
+//! let mut governor = PowerGovernor::new(GovernorConfig::two(rest, active, rule), 0).unwrap();
+//!
+//! let mut shaken = None;
+//! // 5 Hz in cycle mode, a second of shaking
+//! for i in 0..6 {
+//!     let bump = if i % 2 == 0 { 0.2 } else { -0.2 };
+//!     let sample = MpuSample::new(Vec3A::new(bump, 0., 1.), Vec3A::ZERO, 25.);
+//!     shaken = shaken.or(governor.feed(&sample));
+//! }
+//! let transition = shaken.unwrap();
+//! assert_eq!((transition.from_name, transition.to_name), ("rest", "active"));
+//! assert_eq!(transition.reason, TransitionReason::Activity);
+//! governor.commit(&transition);
+//! assert_eq!(governor.current().name, "active");
+//! ```
+
+use core::fmt;
+
+use glam::Vec3A;
+
+#[cfg(feature = "driver")]
+use crate::config_events::ChangeSource;
+use crate::decimal::Decimal;
+#[cfg(feature = "driver")]
+use crate::logging::log_debug;
+#[cfg(not(feature = "std"))]
+use crate::math::FloatExt;
+use crate::power::PowerChange;
+use crate::sample::MpuSample;
+use crate::settings::{Mpu6050Settings, SettingsError};
+use crate::Mpu6050;
+#[cfg(feature = "driver")]
+use crate::Mpu6050Error;
+#[cfg(feature = "driver")]
+use embedded_hal::blocking::i2c::{Write, WriteRead};
+
+/// Maximum number of operating points
+pub const MAX_OPERATING_POINTS: usize = 3;
+/// Buckets the activity window is kept in, it slides by a sixteenth of its length
+pub const ACTIVITY_BUCKETS: usize = 16;
+
+/// Named settings the governor switches between
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct OperatingPoint {
+    /// name reported in transitions
+    pub name: &'static str,
+    /// settings written when entering the point
+    pub settings: Mpu6050Settings,
+    /// minimum time in ms spent at the point before activity may leave it
+    pub min_dwell_ms: u32,
+}
+
+impl OperatingPoint {
+    /// point `name` with `settings`, left no sooner than `min_dwell_ms` after entering
+    pub const fn new(name: &'static str, settings: Mpu6050Settings, min_dwell_ms: u32) -> Self {
+        Self {
+            name,
+            settings,
+            min_dwell_ms,
+        }
+    }
+}
+
+/// Activity thresholds
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct ActivityLevel {
+    /// gyro RMS in rad/s
+    pub gyro_rms: f32,
+    /// accel variance in g², summed over the axes
+    pub acc_variance: f32,
+}
+
+impl ActivityLevel {
+    /// true if either metric is above the level
+    pub fn exceeded_by(&self, metrics: &ActivityMetrics) -> bool {
+        metrics.gyro_rms > self.gyro_rms || metrics.acc_variance > self.acc_variance
+    }
+
+    /// true if the level is above both metrics
+    pub fn exceeds(&self, metrics: &ActivityMetrics) -> bool {
+        metrics.gyro_rms < self.gyro_rms && metrics.acc_variance < self.acc_variance
+    }
+}
+
+/// Transition rule between a point and the next higher one
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct GovernorRule {
+    /// step up when either metric exceeds this level
+    pub up: ActivityLevel,
+    /// step down when both metrics are below this level, lower than `up` on both
+    pub down: ActivityLevel,
+}
+
+/// Operating points, rules and window of a [`PowerGovernor`]
+#[derive(Copy, Clone, Debug)]
+pub struct GovernorConfig {
+    points: [OperatingPoint; MAX_OPERATING_POINTS],
+    rules: [GovernorRule; MAX_OPERATING_POINTS - 1],
+    count: usize,
+    /// time in ms the metrics are computed over, at least 1
+    pub window_ms: u32,
+    /// invoked after every transition the driver performed
+    pub callback: Option<fn(GovernorTransition)>,
+}
+
+impl GovernorConfig {
+    /// two points, `rule` between them, window of 1 s
+    pub const fn two(low: OperatingPoint, high: OperatingPoint, rule: GovernorRule) -> Self {
+        Self {
+            points: [low, high, high],
+            rules: [rule, rule],
+            count: 2,
+            window_ms: 1000,
+            callback: None,
+        }
+    }
+
+    /// three points, `lower` between `low` and `mid`, `upper` between `mid` and `high`,
+    /// window of 1 s
+    pub const fn three(
+        low: OperatingPoint,
+        mid: OperatingPoint,
+        high: OperatingPoint,
+        lower: GovernorRule,
+        upper: GovernorRule,
+    ) -> Self {
+        Self {
+            points: [low, mid, high],
+            rules: [lower, upper],
+            count: 3,
+            window_ms: 1000,
+            callback: None,
+        }
+    }
+
+    /// set window length in ms
+    pub const fn with_window_ms(mut self, window_ms: u32) -> Self {
+        self.window_ms = window_ms;
+        self
+    }
+
+    /// set transition callback
+    pub const fn with_callback(mut self, callback: Option<fn(GovernorTransition)>) -> Self {
+        self.callback = callback;
+        self
+    }
+
+    /// operating points, lowest power first
+    pub fn points(&self) -> &[OperatingPoint] {
+        &self.points[..self.count]
+    }
+
+    /// rules, the one between points `i` and `i + 1` at `i`
+    pub fn rules(&self) -> &[GovernorRule] {
+        &self.rules[..self.count - 1]
+    }
+
+    /// Checks the points' settings, the hysteresis of the rules and the window
+    pub fn validate(&self) -> Result<(), GovernorError> {
+        for (point, op) in self.points().iter().enumerate() {
+            op.settings
+                .validate()
+                .map_err(|error| GovernorError::InvalidSettings { point, error })?;
+        }
+        for (rule, r) in self.rules().iter().enumerate() {
+            if r.down.gyro_rms >= r.up.gyro_rms || r.down.acc_variance >= r.up.acc_variance {
+                return Err(GovernorError::NoHysteresis { rule });
+            }
+        }
+        if self.window_ms == 0 {
+            return Err(GovernorError::EmptyWindow);
+        }
+        Ok(())
+    }
+}
+
+/// Configuration rejected by [`PowerGovernor::new`]
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum GovernorError {
+    /// settings of a point rejected
+    InvalidSettings {
+        /// index of the point
+        point: usize,
+        /// reason
+        error: SettingsError,
+    },
+    /// `down` is not below `up` on both metrics
+    NoHysteresis {
+        /// index of the rule
+        rule: usize,
+    },
+    /// window of 0 ms
+    EmptyWindow,
+    /// initial point out of range
+    NoSuchPoint(usize),
+}
+
+impl fmt::Display for GovernorError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GovernorError::InvalidSettings { point, error } => {
+                write!(f, "operating point {}: {}", point, error)
+            }
+            GovernorError::NoHysteresis { rule } => {
+                write!(f, "rule {}: down level not below up level", rule)
+            }
+            GovernorError::EmptyWindow => f.write_str("activity window of 0 ms"),
+            GovernorError::NoSuchPoint(point) => write!(f, "no operating point {}", point),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for GovernorError {}
+
+/// Activity over the window
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct ActivityMetrics {
+    /// gyro RMS in rad/s
+    pub gyro_rms: f32,
+    /// accel variance in g², summed over the axes
+    pub acc_variance: f32,
+    /// samples in the window
+    pub samples: u32,
+}
+
+/// Sums over the samples of one bucket
+#[derive(Copy, Clone, Debug, Default)]
+struct Bucket {
+    gyro_sq: f32,
+    acc: Vec3A,
+    acc_sq: Vec3A,
+    count: u32,
+}
+
+/// Ring of [`ACTIVITY_BUCKETS`] buckets sliding over the samples of the last `window_ms`
+#[derive(Copy, Clone, Debug)]
+struct ActivityWindow {
+    buckets: [Bucket; ACTIVITY_BUCKETS],
+    current: usize,
+    /// time spent in the current bucket
+    bucket_ms: f32,
+    /// sample time since the window was cleared
+    covered_ms: f32,
+}
+
+impl ActivityWindow {
+    fn new() -> Self {
+        Self {
+            buckets: [Bucket::default(); ACTIVITY_BUCKETS],
+            current: 0,
+            bucket_ms: 0.,
+            covered_ms: 0.,
+        }
+    }
+
+    /// moves on by `dt_ms`, dropping buckets older than `window_ms`
+    fn advance(&mut self, dt_ms: f32, window_ms: u32) {
+        let bucket_len = window_ms as f32 / ACTIVITY_BUCKETS as f32;
+        self.covered_ms += dt_ms;
+        self.bucket_ms += dt_ms;
+        let steps = (self.bucket_ms / bucket_len) as usize;
+        self.bucket_ms -= steps as f32 * bucket_len;
+        for _ in 0..steps.min(ACTIVITY_BUCKETS) {
+            self.current = (self.current + 1) % ACTIVITY_BUCKETS;
+            self.buckets[self.current] = Bucket::default();
+        }
+    }
+
+    fn push(&mut self, gyro: Vec3A, acc: Vec3A) {
+        let bucket = &mut self.buckets[self.current];
+        bucket.gyro_sq += gyro.length_squared();
+        bucket.acc += acc;
+        bucket.acc_sq += acc * acc;
+        bucket.count += 1;
+    }
+
+    fn clear(&mut self) {
+        *self = Self::new();
+    }
+
+    fn metrics(&self) -> ActivityMetrics {
+        let sum = self
+            .buckets
+            .iter()
+            .fold(Bucket::default(), |sum, b| Bucket {
+                gyro_sq: sum.gyro_sq + b.gyro_sq,
+                acc: sum.acc + b.acc,
+                acc_sq: sum.acc_sq + b.acc_sq,
+                count: sum.count + b.count,
+            });
+        if sum.count == 0 {
+            return ActivityMetrics::default();
+        }
+        let n = sum.count as f32;
+        let mean = sum.acc / n;
+        let variance = (sum.acc_sq / n - mean * mean).max(Vec3A::ZERO);
+        ActivityMetrics {
+            gyro_rms: (sum.gyro_sq / n).sqrt(),
+            acc_variance: variance.x + variance.y + variance.z,
+            samples: sum.count,
+        }
+    }
+}
+
+/// Why a transition was proposed
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum TransitionReason {
+    /// activity above the `up` level of the rule
+    Activity,
+    /// activity below the `down` level of the rule
+    Rest,
+    /// [`PowerGovernor::motion_wake`]
+    MotionWake,
+}
+
+/// Change of operating point
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct GovernorTransition {
+    /// index of the point left
+    pub from: usize,
+    /// index of the point entered
+    pub to: usize,
+    /// name of the point left
+    pub from_name: &'static str,
+    /// name of the point entered
+    pub to_name: &'static str,
+    /// cause
+    pub reason: TransitionReason,
+    /// metrics that triggered the transition, those of the partial window for a motion wake
+    pub metrics: ActivityMetrics,
+    /// time spent at the point left in ms
+    pub dwell_ms: f32,
+    /// sample rate of the point entered in Hz
+    pub sample_rate_hz: f32,
+    /// estimated current before and after, filled in by the driver, None from the governor
+    /// alone
+    pub power: Option<PowerChange>,
+}
+
+impl GovernorTransition {
+    /// sample interval of the point entered in s
+    pub fn sample_interval_s(&self) -> f32 {
+        1. / self.sample_rate_hz
+    }
+}
+
+impl fmt::Display for GovernorTransition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} -> {} ({:?}) after {:.0} ms: gyro RMS {:.3} rad/s, accel variance {:.4} g², now {} Hz",
+            self.from_name,
+            self.to_name,
+            self.reason,
+            Decimal(self.dwell_ms),
+            Decimal(self.metrics.gyro_rms),
+            Decimal(self.metrics.acc_variance),
+            Decimal(self.sample_rate_hz)
+        )
+    }
+}
+
+/// Current point and activity of a governor, see [`PowerGovernor::status`]
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct GovernorStatus {
+    /// index of the current point
+    pub point: usize,
+    /// name of the current point
+    pub name: &'static str,
+    /// time spent at the current point in ms
+    pub dwell_ms: f32,
+    /// activity over the window so far
+    pub metrics: ActivityMetrics,
+    /// transitions committed
+    pub transitions: u32,
+}
+
+/// Activity state machine, no bus access
+#[derive(Copy, Clone, Debug)]
+pub struct PowerGovernor {
+    config: GovernorConfig,
+    current: usize,
+    dwell_ms: f32,
+    window: ActivityWindow,
+    transitions: u32,
+}
+
+impl PowerGovernor {
+    /// governor at point `initial`, with a validated `config`
+    pub fn new(config: GovernorConfig, initial: usize) -> Result<Self, GovernorError> {
+        config.validate()?;
+        if initial >= config.count {
+            return Err(GovernorError::NoSuchPoint(initial));
+        }
+        Ok(Self {
+            config,
+            current: initial,
+            dwell_ms: 0.,
+            window: ActivityWindow::new(),
+            transitions: 0,
+        })
+    }
+
+    /// configuration in use
+    pub fn config(&self) -> &GovernorConfig {
+        &self.config
+    }
+
+    /// current operating point
+    pub fn current(&self) -> &OperatingPoint {
+        &self.config.points[self.current]
+    }
+
+    /// index of the current operating point
+    pub fn current_index(&self) -> usize {
+        self.current
+    }
+
+    /// activity over the window so far
+    pub fn metrics(&self) -> ActivityMetrics {
+        self.window.metrics()
+    }
+
+    /// current point, dwell, activity and transition count
+    pub fn status(&self) -> GovernorStatus {
+        GovernorStatus {
+            point: self.current,
+            name: self.current().name,
+            dwell_ms: self.dwell_ms,
+            metrics: self.metrics(),
+            transitions: self.transitions,
+        }
+    }
+
+    /// Adds a sample taken at the current point, advancing time by its sample interval.
+    /// Settling samples advance time only. Returns the transition the activity calls for
+    pub fn feed(&mut self, sample: &MpuSample) -> Option<GovernorTransition> {
+        let interval_ms = 1000. / self.current().settings.sample_rate_hz();
+        self.dwell_ms += interval_ms;
+        self.window.advance(interval_ms, self.config.window_ms);
+        if !sample.settling() {
+            self.window.push(sample.gyro(), sample.acc());
+        }
+        self.evaluate()
+    }
+
+    /// Advances dwell by `elapsed_ms` without a sample, e.g. while the host slept. The window
+    /// keeps its samples. Returns the transition the activity calls for
+    pub fn tick(&mut self, elapsed_ms: u32) -> Option<GovernorTransition> {
+        self.dwell_ms += elapsed_ms as f32;
+        self.evaluate()
+    }
+
+    /// Transition to the highest point regardless of dwell and window, None if already there
+    pub fn motion_wake(&self) -> Option<GovernorTransition> {
+        let top = self.config.count - 1;
+        (self.current < top).then(|| self.transition(top, TransitionReason::MotionWake))
+    }
+
+    /// Takes `transition` into effect: the window is cleared and dwell starts over
+    pub fn commit(&mut self, transition: &GovernorTransition) {
+        self.current = transition.to.min(self.config.count - 1);
+        self.dwell_ms = 0.;
+        self.window.clear();
+        self.transitions = self.transitions.wrapping_add(1);
+    }
+
+    fn evaluate(&self) -> Option<GovernorTransition> {
+        if self.window.covered_ms < self.config.window_ms as f32
+            || self.dwell_ms < self.current().min_dwell_ms as f32
+        {
+            return None;
+        }
+        let metrics = self.window.metrics();
+        let rules = self.config.rules();
+        if let Some(rule) = rules.get(self.current) {
+            if rule.up.exceeded_by(&metrics) {
+                return Some(self.transition(self.current + 1, TransitionReason::Activity));
+            }
+        }
+        if let Some(rule) = self.current.checked_sub(1).map(|below| &rules[below]) {
+            if rule.down.exceeds(&metrics) {
+                return Some(self.transition(self.current - 1, TransitionReason::Rest));
+            }
+        }
+        None
+    }
+
+    fn transition(&self, to: usize, reason: TransitionReason) -> GovernorTransition {
+        let target = &self.config.points[to];
+        GovernorTransition {
+            from: self.current,
+            to,
+            from_name: self.current().name,
+            to_name: target.name,
+            reason,
+            metrics: self.window.metrics(),
+            dwell_ms: self.dwell_ms,
+            sample_rate_hz: target.settings.sample_rate_hz(),
+            power: None,
+        }
+    }
+}
+
+impl<I, D> Mpu6050<I, D> {
+    /// installed governor, None if not installed
+    pub fn power_governor(&self) -> Option<&PowerGovernor> {
+        self.governor.as_ref()
+    }
+
+    /// Removes the governor, the settings of its current point stay in effect
+    pub fn remove_power_governor(&mut self) -> Option<PowerGovernor> {
+        self.governor.take()
+    }
+}
+
+#[cfg(feature = "driver")]
+impl<I, D, E> Mpu6050<I, D>
+where
+    I: Write<Error = E> + WriteRead<Error = E>,
+{
+    /// Installs `governor` and applies the settings of its current point with
+    /// [`apply_settings`](Self::apply_settings), see [`governor`](crate::governor).
+    /// Replaces a previous governor
+    pub fn set_power_governor(&mut self, governor: PowerGovernor) -> Result<(), Mpu6050Error<E>> {
+        self.governor = None;
+        self.apply_settings(&governor.current().settings)?;
+        self.governor = Some(governor);
+        Ok(())
+    }
+
+    /// Advances the governor by `elapsed_ms` without a sample and performs the transition it
+    /// calls for. Ok(None) without a governor or transition
+    pub fn governor_tick(
+        &mut self,
+        elapsed_ms: u32,
+    ) -> Result<Option<GovernorTransition>, Mpu6050Error<E>> {
+        self.check_active()?;
+        let transition = match &mut self.governor {
+            Some(governor) => governor.tick(elapsed_ms),
+            None => None,
+        };
+        self.perform_transition(transition)
+    }
+
+    /// Moves to the highest point right away, e.g. when
+    /// [`poll_interrupt_events`](Self::poll_interrupt_events) reports motion while at rest.
+    /// Ok(None) without a governor or if already there
+    pub fn governor_motion_wake(&mut self) -> Result<Option<GovernorTransition>, Mpu6050Error<E>> {
+        self.check_active()?;
+        let transition = self.governor.as_ref().and_then(PowerGovernor::motion_wake);
+        self.perform_transition(transition)
+    }
+
+    /// feeds a full sample to the governor and performs the transition it calls for
+    #[cfg(not(feature = "minimal-pipeline"))]
+    pub(crate) fn feed_governor(&mut self, sample: &MpuSample) -> Result<(), Mpu6050Error<E>> {
+        let transition = match &mut self.governor {
+            Some(governor) => governor.feed(sample),
+            None => None,
+        };
+        self.perform_transition(transition).map(|_| ())
+    }
+
+    /// writes the settings difference, then commits the transition and reports it
+    fn perform_transition(
+        &mut self,
+        transition: Option<GovernorTransition>,
+    ) -> Result<Option<GovernorTransition>, Mpu6050Error<E>> {
+        let (Some(mut transition), Some(governor)) = (transition, &self.governor) else {
+            return Ok(None);
+        };
+        let points = governor.config().points();
+        let (from, to) = (
+            points[transition.from].settings,
+            points[transition.to].settings,
+        );
+        let callback = governor.config().callback;
+        transition.power = Some(self.power_change(&to));
+        self.with_change_source(ChangeSource::Governor, |mpu| {
+            mpu.apply_settings_diff(&from, &to)
+        })?;
+        if let Some(governor) = &mut self.governor {
+            governor.commit(&transition);
+        }
+        log_debug!(POWER, "governor: {}", transition);
+        if let Some(callback) = callback {
+            callback(transition);
+        }
+        Ok(Some(transition))
+    }
+}
@@ -0,0 +1,242 @@
+//! Slow accelerometer offset trim from the long-horizon gravity magnitude.
+//!
+//! A device at rest measures exactly 1g, whatever its orientation. Averaged over hours of
+//! still periods, a deviation of the measured magnitude is offset drift. At rest with gravity
+//! direction `u`, an offset change `d` changes the magnitude by about `u · d`, so the drift is
+//! the least squares solution of `M d = -b` with `M = Σ u uᵀ` and `b = Σ r u` over still
+//! samples, `r = |a| - 1`. Only directions gravity was seen along are observable: a device
+//! that always sits flat gets its Z offset trimmed and nothing else. A small regularization
+//! pulls the total correction towards zero in directions `M` says nothing about.
+//!
+//! [`GravityMagnitudeTrimmer`] is a pure state machine, no bus access:
+//! * samples count only after `min_still_s` of continuous stillness (gyro and accel
+//!   magnitude thresholds), motion resets the run and nothing accumulates while moving
+//! * residuals are clipped to `residual_clip_g` before accumulating, so knocks that slip
+//!   through the stillness test cannot pull the estimate
+//! * `M` and `b` decay with `time_constant_s`, weighted by sample interval
+//! * every `update_interval_s` of accepted stillness a correction is proposed, bounded to
+//!   `max_step_g` per axis, and the total stays within `budget_g` per axis. Reaching the
+//!   budget sets [`saturated`](GravityMagnitudeTrimmer::saturated)
+//! * every proposed correction is kept in [`corrections_log`](GravityMagnitudeTrimmer::corrections_log)
+//!
+//! Corrections are added to the accel offset with [`Mpu6050::apply_acc_trim`] (or by hand when
+//! replaying logs). Feed readings taken with the trims applied so far.
+
+use glam::{Mat3A, Vec3A};
+
+use crate::config_epoch::ConfigChange;
+#[cfg(not(feature = "std"))]
+use crate::math::FloatExt;
+use crate::{Mpu6050, MpuSample};
+
+/// Number of corrections kept in the log
+pub const CORRECTIONS_LOG_LEN: usize = 32;
+
+/// Relative weight of the regularization keeping unobserved offset directions at zero
+const REGULARIZATION: f32 = 1e-3;
+
+/// Options of a [`GravityMagnitudeTrimmer`]
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct TrimmerConfig {
+    /// decay time constant of the magnitude statistics in s, default 4 h
+    pub time_constant_s: f32,
+    /// max total correction per axis in g, default 0.03
+    pub budget_g: f32,
+    /// max correction per update per axis in g, default 0.0005
+    pub max_step_g: f32,
+    /// accepted still time between proposals in s, default 60
+    pub update_interval_s: f32,
+    /// gyro magnitudes above this (rad/s) are motion, default 0.05
+    pub still_gyro_rad_s: f32,
+    /// accel magnitudes further than this from 1g are motion, default 0.05
+    pub still_acc_g: f32,
+    /// continuous stillness before samples count, in s, default 2
+    pub min_still_s: f32,
+    /// residual clip in g, default 0.03
+    pub residual_clip_g: f32,
+}
+
+impl Default for TrimmerConfig {
+    fn default() -> Self {
+        Self {
+            time_constant_s: 4. * 3600.,
+            budget_g: 0.03,
+            max_step_g: 0.0005,
+            update_interval_s: 60.,
+            still_gyro_rad_s: 0.05,
+            still_acc_g: 0.05,
+            min_still_s: 2.,
+            residual_clip_g: 0.03,
+        }
+    }
+}
+
+/// Offset correction proposed by the trimmer
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct TrimCorrection {
+    /// trimmer time of the proposal in s, summed sample intervals
+    pub t_s: f64,
+    /// offset change in g, to add to the accel offset
+    pub step: Vec3A,
+    /// all corrections so far, this one included
+    pub total: Vec3A,
+    /// the budget limited this correction
+    pub saturated: bool,
+}
+
+/// Long-horizon gravity magnitude offset trimmer, see the [module docs](self)
+#[derive(Copy, Clone, Debug)]
+pub struct GravityMagnitudeTrimmer {
+    config: TrimmerConfig,
+    t_s: f64,
+    still_s: f32,
+    since_update_s: f32,
+    information: Mat3A,
+    residuals: Vec3A,
+    weight: f32,
+    total: Vec3A,
+    saturated: bool,
+    log: [Option<TrimCorrection>; CORRECTIONS_LOG_LEN],
+    logged: usize,
+}
+
+impl GravityMagnitudeTrimmer {
+    /// new trimmer without statistics
+    pub fn new(config: TrimmerConfig) -> Self {
+        Self {
+            config,
+            t_s: 0.,
+            still_s: 0.,
+            since_update_s: 0.,
+            information: Mat3A::ZERO,
+            residuals: Vec3A::ZERO,
+            weight: 0.,
+            total: Vec3A::ZERO,
+            saturated: false,
+            log: [None; CORRECTIONS_LOG_LEN],
+            logged: 0,
+        }
+    }
+
+    /// options in use
+    pub fn config(&self) -> &TrimmerConfig {
+        &self.config
+    }
+
+    /// all corrections so far, in g
+    pub fn total_correction(&self) -> Vec3A {
+        self.total
+    }
+
+    /// true once an axis reached the budget
+    pub fn saturated(&self) -> bool {
+        self.saturated
+    }
+
+    /// Total correction in g the statistics ask for. Least squares with the corrections
+    /// applied so far, regularized towards zero total correction
+    pub fn estimate(&self) -> Vec3A {
+        if self.weight <= 0. {
+            return Vec3A::ZERO;
+        }
+        let regularization =
+            Mat3A::from_diagonal(Vec3A::splat(REGULARIZATION * self.weight).into());
+        (self.information + regularization).inverse()
+            * (self.information * self.total - self.residuals)
+    }
+
+    /// the last [`CORRECTIONS_LOG_LEN`] corrections, oldest first
+    pub fn corrections_log(&self) -> impl Iterator<Item = &TrimCorrection> {
+        let start = self.logged % CORRECTIONS_LOG_LEN;
+        self.log[start..]
+            .iter()
+            .chain(self.log[..start].iter())
+            .flatten()
+    }
+
+    /// number of corrections proposed so far, also those dropped from the log
+    pub fn corrections_count(&self) -> usize {
+        self.logged
+    }
+
+    /// true if the reading passes the stillness thresholds
+    fn is_still(&self, acc: Vec3A, gyro: Vec3A) -> bool {
+        gyro.length() <= self.config.still_gyro_rad_s
+            && (acc.length() - 1.).abs() <= self.config.still_acc_g
+    }
+
+    /// Feeds a sample taken `dt_s` after the previous one. Returns a correction to apply
+    /// to the accel offset when one is due
+    pub fn feed(&mut self, sample: &MpuSample, dt_s: f32) -> Option<TrimCorrection> {
+        self.feed_readings(sample.acc(), sample.gyro(), dt_s)
+    }
+
+    /// [`feed`](Self::feed) with accel in g and gyro in rad/s
+    pub fn feed_readings(&mut self, acc: Vec3A, gyro: Vec3A, dt_s: f32) -> Option<TrimCorrection> {
+        if dt_s.is_nan() || dt_s <= 0. {
+            return None;
+        }
+        self.t_s += dt_s as f64;
+        if !self.is_still(acc, gyro) {
+            self.still_s = 0.;
+            return None;
+        }
+        self.still_s += dt_s;
+        if self.still_s < self.config.min_still_s {
+            return None;
+        }
+
+        let magnitude = acc.length();
+        let u = acc / magnitude;
+        let clip = self.config.residual_clip_g;
+        let residual = (magnitude - 1.).clamp(-clip, clip);
+        let decay = (-dt_s / self.config.time_constant_s).exp();
+        let outer = Mat3A::from_cols(u * u.x, u * u.y, u * u.z);
+        self.information = self.information * decay + outer * dt_s;
+        self.residuals = self.residuals * decay + u * (residual * dt_s);
+        self.weight = self.weight * decay + dt_s;
+
+        self.since_update_s += dt_s;
+        if self.since_update_s < self.config.update_interval_s {
+            return None;
+        }
+        self.since_update_s = 0.;
+        self.propose()
+    }
+
+    fn propose(&mut self) -> Option<TrimCorrection> {
+        let max_step = Vec3A::splat(self.config.max_step_g);
+        let budget = Vec3A::splat(self.config.budget_g);
+        let wanted = (self.estimate() - self.total).clamp(-max_step, max_step);
+        let unbounded = self.total + wanted;
+        let saturated = unbounded.abs().cmpgt(budget).any();
+        let total = unbounded.clamp(-budget, budget);
+        let step = total - self.total;
+        self.saturated |= saturated;
+        if step == Vec3A::ZERO {
+            return None;
+        }
+
+        // the accumulated residuals were measured before this step
+        self.residuals += self.information * step;
+        self.total = total;
+        let correction = TrimCorrection {
+            t_s: self.t_s,
+            step,
+            total,
+            saturated,
+        };
+        self.log[self.logged % CORRECTIONS_LOG_LEN] = Some(correction);
+        self.logged += 1;
+        Some(correction)
+    }
+}
+
+impl<I, D> Mpu6050<I, D> {
+    /// Applies a trim correction in g to the accel offset, taking the per-axis scale factors
+    /// into account: readings change by `step`
+    pub fn apply_acc_trim(&mut self, step: Vec3A) {
+        self.acc_offset -= step / self.acc_scale.per_axis;
+        self.bump_epoch(ConfigChange::Offsets);
+    }
+}
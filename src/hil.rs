@@ -0,0 +1,933 @@
+//! Hardware-in-the-loop battery for maintainers with a real sensor attached.
+//!
+//! The mock buses of the test suite answer like a datasheet MPU-6050, real parts and clones
+//! do not: settle times, FIFO timing and noise only show on silicon. [`run_hil`] runs a fixed
+//! battery against a driver on a real bus and returns a [`HilReport`], the `hil` binary
+//! (`cargo run --features hil --bin hil -- --help`, Linux i2c-dev) wraps it with bus and
+//! address flags.
+//!
+//! | check | exercises | passes when |
+//! |:---|:---|:---|
+//! | [`Probe`](CheckId::Probe) | [`Mpu6050::probe_capabilities`] | the chip answers, unknown WHO_AM_I values are noted |
+//! | [`InitProfiles`](CheckId::InitProfiles) | [`Mpu6050::apply_settings`] | every [`presets::ALL`] profile reads back after `init` |
+//! | [`RangeSweep`](CheckId::RangeSweep) | [`Mpu6050::set_accel_range`], [`Mpu6050::set_gyro_range`] | every range reads back |
+//! | [`DlpfSweep`](CheckId::DlpfSweep) | [`Mpu6050::set_dlpf`] | DLPF_CFG 0 to 6 read back |
+//! | [`Noise`](CheckId::Noise) | [`Mpu6050::get_acc`], [`Mpu6050::get_gyro`] | stationary RMS noise within `noise_tolerance` times [`ResolutionInfo`], and not constant |
+//! | [`GyroCalibration`](CheckId::GyroCalibration) | [`Mpu6050::auto_setup`] | two calibrations agree within `calibration_tolerance_dps` |
+//! | [`FifoStreaming`](CheckId::FifoStreaming) | [`Mpu6050::drain_fifo`] | no overflow, frame count within `fifo_rate_tolerance` of the rate, every frame near 1 g |
+//! | [`MotionInterrupt`](CheckId::MotionInterrupt) | [`Mpu6050::setup_motion_detection`] | MOT_INT within `motion_timeout_ms` of prompting the operator to tap the sensor |
+//! | [`SelfTest`](CheckId::SelfTest) | [`Mpu6050::set_accel_x_self_test`] and Y, Z | the self-test response is at least `min_self_test_g` per axis |
+//!
+//! Every check after the probe starts from `init` and the default settings. A failing check does not stop the run: a
+//! bus error or an out of bounds measurement fails that check with the reason, checks whose
+//! prerequisite is missing (no answer to the probe, no FIFO, no operator) are skipped with
+//! the reason. A check failed by an error carries the error's
+//! [`RecoveryHint`], in the driver's [`hint_context`](Mpu6050::hint_context) right after it,
+//! [`HilReport::recovery_hint`] is the one to act on first. The device is left at `init` and
+//! the default settings.
+//!
+//! #### Report
+//! [`HilReport::to_json`] is the machine readable form, the crate has no serde dependency so
+//! the schema is fixed here and versioned by [`HIL_SCHEMA_VERSION`]. Its `Display` is the
+//! human summary.
+//!
+//! | key | content |
+//! |:---|:---|
+//! | `schema_version` | [`HIL_SCHEMA_VERSION`] |
+//! | `driver_version` | crate version of the harness |
+//! | `bus`, `address` | as given to the harness, the address as a number |
+//! | `chip` | `null` without an answer, otherwise `who_am_i` and `variant` |
+//! | `summary` | `pass`, `fail` and `skip` counts |
+//! | `checks` | in battery order: `id` ([`CheckId::name`]), `api` ([`CheckId::api`]), `status` (`pass`, `fail`, `skip`), `note` (string or `null`), `hint` (`null`, or `action` ([`RecoveryHint::name`]) and `delay_ms`, a number for a delayed retry, `null` otherwise), `measurements` (object of numbers, `null` if not finite) |
+//!
+//! Adding a key or a check keeps the version, renaming or removing one bumps it.
+//! ```
+//! use mpu6050::hil::{CheckId, CheckResult, CheckStatus, HilReport};
+//!
+//! let mut report = HilReport::new("/dev/i2c-1", 0x68);
+//! report.checks.push(CheckResult {
+//!     id: CheckId::Probe,
+//!     status: CheckStatus::Pass,
+//!     note: None,
+//!     hint: None,
+//!     measurements: vec![("who_am_i".into(), 104.)],
+//! });
+//! let json = report.to_json();
+//! assert!(json.starts_with("{\"schema_version\":1,"));
+//! assert!(json.contains("\"id\":\"probe\",\"api\":\"Mpu6050::probe_capabilities\""));
+//! assert!(report.passed());
+//! ```
+
+use core::fmt::{self, Debug};
+
+use embedded_hal::blocking::delay::DelayMs;
+use embedded_hal::blocking::i2c::{Write, WriteRead};
+use glam::Vec3A;
+
+use crate::decimal::Decimal;
+use crate::device::{AccelRange, ChipVariant, GyroRange};
+use crate::fifo::{FifoSources, FIFO_CAPACITY};
+use crate::hint::RecoveryHint;
+use crate::interrupt::MotionDetectionConfig;
+use crate::presets;
+use crate::register::Register;
+use crate::resolution::ResolutionInfo;
+use crate::settings::Mpu6050Settings;
+use crate::setup::AutoSetupOptions;
+use crate::{Mpu6050, Mpu6050Error};
+
+/// Version of the [`HilReport`] JSON schema, see the module docs
+pub const HIL_SCHEMA_VERSION: u32 = 1;
+
+/// Checks of the battery, in the order they run
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum CheckId {
+    /// probe and identify
+    Probe,
+    /// init and apply every preset
+    InitProfiles,
+    /// accel and gyro range readback
+    RangeSweep,
+    /// DLPF_CFG readback
+    DlpfSweep,
+    /// stationary noise against the datasheet
+    Noise,
+    /// gyro calibration run twice
+    GyroCalibration,
+    /// FIFO streaming, overflow and continuity
+    FifoStreaming,
+    /// motion interrupt on an operator tap
+    MotionInterrupt,
+    /// accel self-test response
+    SelfTest,
+}
+
+impl CheckId {
+    /// every check, in battery order
+    pub const ALL: [CheckId; 9] = [
+        CheckId::Probe,
+        CheckId::InitProfiles,
+        CheckId::RangeSweep,
+        CheckId::DlpfSweep,
+        CheckId::Noise,
+        CheckId::GyroCalibration,
+        CheckId::FifoStreaming,
+        CheckId::MotionInterrupt,
+        CheckId::SelfTest,
+    ];
+
+    /// id in the report and on the command line
+    pub const fn name(self) -> &'static str {
+        match self {
+            CheckId::Probe => "probe",
+            CheckId::InitProfiles => "init_profiles",
+            CheckId::RangeSweep => "range_sweep",
+            CheckId::DlpfSweep => "dlpf_sweep",
+            CheckId::Noise => "noise",
+            CheckId::GyroCalibration => "gyro_calibration",
+            CheckId::FifoStreaming => "fifo_streaming",
+            CheckId::MotionInterrupt => "motion_interrupt",
+            CheckId::SelfTest => "self_test",
+        }
+    }
+
+    /// crate API the check exercises
+    pub const fn api(self) -> &'static str {
+        match self {
+            CheckId::Probe => "Mpu6050::probe_capabilities",
+            CheckId::InitProfiles => "Mpu6050::apply_settings",
+            CheckId::RangeSweep => "Mpu6050::set_accel_range, Mpu6050::set_gyro_range",
+            CheckId::DlpfSweep => "Mpu6050::set_dlpf",
+            CheckId::Noise => "Mpu6050::get_acc, Mpu6050::get_gyro",
+            CheckId::GyroCalibration => "Mpu6050::auto_setup",
+            CheckId::FifoStreaming => "Mpu6050::drain_fifo",
+            CheckId::MotionInterrupt => "Mpu6050::setup_motion_detection",
+            CheckId::SelfTest => "Mpu6050::set_accel_x_self_test",
+        }
+    }
+
+    /// check of a [`name`](Self::name)
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|id| id.name() == name)
+    }
+}
+
+/// Outcome of a check
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum CheckStatus {
+    /// ran and met its bounds
+    Pass,
+    /// ran and missed its bounds, or failed on the bus
+    Fail,
+    /// did not run, see the note
+    Skip,
+}
+
+impl CheckStatus {
+    /// status in the report
+    pub const fn name(self) -> &'static str {
+        match self {
+            CheckStatus::Pass => "pass",
+            CheckStatus::Fail => "fail",
+            CheckStatus::Skip => "skip",
+        }
+    }
+}
+
+/// Result of one check
+#[derive(Clone, Debug, PartialEq)]
+pub struct CheckResult {
+    /// check
+    pub id: CheckId,
+    /// outcome
+    pub status: CheckStatus,
+    /// failure or skip reason, or a remark on a pass
+    pub note: Option<String>,
+    /// hint of the error failing the check, None for a measurement out of bounds, see
+    /// [`hint`](crate::hint)
+    pub hint: Option<RecoveryHint>,
+    /// named measurements, units in the name
+    pub measurements: Vec<(String, f32)>,
+}
+
+impl CheckResult {
+    fn skip(id: CheckId, reason: &str) -> Self {
+        Self {
+            id,
+            status: CheckStatus::Skip,
+            note: Some(reason.into()),
+            hint: None,
+            measurements: Vec::new(),
+        }
+    }
+}
+
+/// Chip found by the probe
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct ChipInfo {
+    /// WHO_AM_I answer
+    pub who_am_i: u8,
+    /// variant of the answer
+    pub variant: ChipVariant,
+}
+
+/// Result of [`run_hil`], see the module docs for the JSON form
+#[derive(Clone, Debug, PartialEq)]
+pub struct HilReport {
+    /// [`HIL_SCHEMA_VERSION`] of the report
+    pub schema_version: u32,
+    /// crate version of the harness
+    pub driver_version: &'static str,
+    /// bus as given to the harness, e.g. `/dev/i2c-1`
+    pub bus: String,
+    /// I2C address
+    pub address: u8,
+    /// chip, None if the probe got no answer
+    pub chip: Option<ChipInfo>,
+    /// results in battery order
+    pub checks: Vec<CheckResult>,
+}
+
+impl HilReport {
+    /// empty report of the current schema
+    pub fn new(bus: &str, address: u8) -> Self {
+        Self {
+            schema_version: HIL_SCHEMA_VERSION,
+            driver_version: env!("CARGO_PKG_VERSION"),
+            bus: bus.into(),
+            address,
+            chip: None,
+            checks: Vec::new(),
+        }
+    }
+
+    /// result of `id`, None if not in the report
+    pub fn check(&self, id: CheckId) -> Option<&CheckResult> {
+        self.checks.iter().find(|check| check.id == id)
+    }
+
+    /// checks with `status`
+    pub fn count(&self, status: CheckStatus) -> usize {
+        self.checks.iter().filter(|c| c.status == status).count()
+    }
+
+    /// no check failed
+    pub fn passed(&self) -> bool {
+        self.count(CheckStatus::Fail) == 0
+    }
+
+    /// the hint to act on first among the failed checks, None without hints
+    pub fn recovery_hint(&self) -> Option<RecoveryHint> {
+        self.checks.iter().filter_map(|check| check.hint).max()
+    }
+
+    /// JSON form, see the module docs
+    pub fn to_json(&self) -> String {
+        let mut out = String::new();
+        self.write_json(&mut out).expect("String is infallible");
+        out
+    }
+
+    /// writes the JSON form to `out`
+    pub fn write_json(&self, out: &mut impl fmt::Write) -> fmt::Result {
+        write!(
+            out,
+            "{{\"schema_version\":{},\"driver_version\":",
+            self.schema_version
+        )?;
+        json_string(out, self.driver_version)?;
+        out.write_str(",\"bus\":")?;
+        json_string(out, &self.bus)?;
+        write!(out, ",\"address\":{},\"chip\":", self.address)?;
+        match self.chip {
+            Some(chip) => {
+                write!(out, "{{\"who_am_i\":{},\"variant\":", chip.who_am_i)?;
+                json_string(out, &format!("{:?}", chip.variant))?;
+                out.write_char('}')?;
+            }
+            None => out.write_str("null")?,
+        }
+        write!(
+            out,
+            ",\"summary\":{{\"pass\":{},\"fail\":{},\"skip\":{}}},\"checks\":[",
+            self.count(CheckStatus::Pass),
+            self.count(CheckStatus::Fail),
+            self.count(CheckStatus::Skip)
+        )?;
+        for (i, check) in self.checks.iter().enumerate() {
+            if i > 0 {
+                out.write_char(',')?;
+            }
+            out.write_str("{\"id\":")?;
+            json_string(out, check.id.name())?;
+            out.write_str(",\"api\":")?;
+            json_string(out, check.id.api())?;
+            out.write_str(",\"status\":")?;
+            json_string(out, check.status.name())?;
+            out.write_str(",\"note\":")?;
+            match &check.note {
+                Some(note) => json_string(out, note)?,
+                None => out.write_str("null")?,
+            }
+            out.write_str(",\"hint\":")?;
+            match check.hint {
+                Some(hint) => {
+                    out.write_str("{\"action\":")?;
+                    json_string(out, hint.name())?;
+                    match hint {
+                        RecoveryHint::RetryAfterDelay { ms } => {
+                            write!(out, ",\"delay_ms\":{}}}", ms)?
+                        }
+                        _ => out.write_str(",\"delay_ms\":null}")?,
+                    }
+                }
+                None => out.write_str("null")?,
+            }
+            out.write_str(",\"measurements\":{")?;
+            for (j, (name, value)) in check.measurements.iter().enumerate() {
+                if j > 0 {
+                    out.write_char(',')?;
+                }
+                json_string(out, name)?;
+                out.write_char(':')?;
+                if value.is_finite() {
+                    write!(out, "{}", value)?;
+                } else {
+                    out.write_str("null")?;
+                }
+            }
+            out.write_str("}}")?;
+        }
+        out.write_str("]}")
+    }
+}
+
+/// human summary, one line per check
+impl fmt::Display for HilReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "hil report v{}, driver {}, {} at 0x{:02x}",
+            self.schema_version, self.driver_version, self.bus, self.address
+        )?;
+        match self.chip {
+            Some(chip) => writeln!(f, ", {:?} (WHO_AM_I 0x{:02x})", chip.variant, chip.who_am_i)?,
+            None => writeln!(f, ", no chip")?,
+        }
+        for check in &self.checks {
+            write!(
+                f,
+                "  {:<4}  {:<16}  {}",
+                check.status.name(),
+                check.id.name(),
+                check.id.api()
+            )?;
+            if let Some(note) = &check.note {
+                write!(f, ": {}", note)?;
+            }
+            if let Some(hint) = check.hint {
+                write!(f, ", {}", hint)?;
+            }
+            writeln!(f)?;
+        }
+        write!(
+            f,
+            "{} passed, {} failed, {} skipped",
+            self.count(CheckStatus::Pass),
+            self.count(CheckStatus::Fail),
+            self.count(CheckStatus::Skip)
+        )
+    }
+}
+
+/// JSON string literal of `s`
+fn json_string(out: &mut impl fmt::Write, s: &str) -> fmt::Result {
+    out.write_char('"')?;
+    for c in s.chars() {
+        match c {
+            '"' => out.write_str("\\\"")?,
+            '\\' => out.write_str("\\\\")?,
+            '\n' => out.write_str("\\n")?,
+            c if (c as u32) < 0x20 => write!(out, "\\u{:04x}", c as u32)?,
+            c => out.write_char(c)?,
+        }
+    }
+    out.write_char('"')
+}
+
+/// The person at the bench
+pub trait Operator {
+    /// someone is there to follow prompts, checks needing an action are skipped otherwise
+    fn interactive(&self) -> bool;
+
+    /// asks for an action, the check times out on its own
+    fn prompt(&mut self, message: &str);
+
+    /// a check is about to run
+    fn starting(&mut self, _check: CheckId) {}
+}
+
+/// Nobody at the bench, e.g. a CI runner with a sensor attached
+#[derive(Copy, Clone, Debug, Default)]
+pub struct Unattended;
+
+impl Operator for Unattended {
+    fn interactive(&self) -> bool {
+        false
+    }
+
+    fn prompt(&mut self, _message: &str) {}
+}
+
+/// Bounds and durations of the battery
+#[derive(Clone, Debug, PartialEq)]
+pub struct HilOptions {
+    /// checks to run, the probe always runs
+    pub checks: Vec<CheckId>,
+    /// samples of the noise measurement, 10 ms apart
+    pub noise_samples: u16,
+    /// allowed multiple of the datasheet RMS noise
+    pub noise_tolerance: f32,
+    /// samples per gyro calibration
+    pub calibration_samples: u16,
+    /// allowed offset difference between the two calibrations in °/s
+    pub calibration_tolerance_dps: f32,
+    /// streaming time in ms
+    pub fifo_duration_ms: u32,
+    /// allowed relative deviation of the FIFO frame count from the rate
+    pub fifo_rate_tolerance: f32,
+    /// time given to the operator to tap the sensor in ms
+    pub motion_timeout_ms: u32,
+    /// minimum self-test response per axis in g
+    pub min_self_test_g: f32,
+}
+
+impl Default for HilOptions {
+    fn default() -> Self {
+        Self {
+            checks: CheckId::ALL.to_vec(),
+            noise_samples: 200,
+            noise_tolerance: 3.,
+            calibration_samples: 500,
+            calibration_tolerance_dps: 0.1,
+            fifo_duration_ms: 5000,
+            fifo_rate_tolerance: 0.05,
+            motion_timeout_ms: 10_000,
+            min_self_test_g: AutoSetupOptions::default().min_actuation_g,
+        }
+    }
+}
+
+/// Poll interval of the timed checks in ms
+const POLL_MS: u8 = 10;
+/// Stationary configuration of the noise and FIFO checks: 44 Hz DLPF, 100 Hz output rate
+const STATIONARY: Mpu6050Settings = Mpu6050Settings::new()
+    .with_dlpf_cfg(3)
+    .with_sample_rate_div(9);
+/// Output rate of [`STATIONARY`] in Hz
+const STATIONARY_ODR_HZ: f32 = 100.;
+/// Degrees per radian
+const DEG_PER_RAD: f32 = 180. / core::f32::consts::PI;
+/// Largest deviation of a stationary FIFO frame from 1 g, in g
+const FIFO_FRAME_TOLERANCE_G: f32 = 0.25;
+
+/// Reason a check failed, with the hint of the error behind it
+struct Failure {
+    reason: String,
+    hint: Option<RecoveryHint>,
+}
+
+type Outcome = Result<CheckResult, Failure>;
+
+/// Runs the battery of `options` against `mpu` on `bus`, see the module docs
+pub fn run_hil<I, E, D, O>(
+    mpu: &mut Mpu6050<I>,
+    delay: &mut D,
+    operator: &mut O,
+    options: &HilOptions,
+    bus: &str,
+) -> HilReport
+where
+    I: Write<Error = E> + WriteRead<Error = E>,
+    E: Debug,
+    D: DelayMs<u8>,
+    O: Operator,
+{
+    let mut report = HilReport::new(bus, mpu.slave_addr);
+    operator.starting(CheckId::Probe);
+    let probe = probe(mpu);
+    report.chip = probe.as_ref().ok().and_then(|(_, chip)| *chip);
+    report
+        .checks
+        .push(finish(CheckId::Probe, probe.map(|(r, _)| r)));
+
+    for &id in CheckId::ALL[1..]
+        .iter()
+        .filter(|id| options.checks.contains(id))
+    {
+        if report.chip.is_none() {
+            report
+                .checks
+                .push(CheckResult::skip(id, "no answer to the probe"));
+            continue;
+        }
+        operator.starting(id);
+        let outcome = restart(mpu, delay).and_then(|_| match id {
+            CheckId::Probe => unreachable!("the probe runs first"),
+            CheckId::InitProfiles => init_profiles(mpu, delay),
+            CheckId::RangeSweep => range_sweep(mpu),
+            CheckId::DlpfSweep => dlpf_sweep(mpu),
+            CheckId::Noise => noise(mpu, delay, options),
+            CheckId::GyroCalibration => gyro_calibration(mpu, delay, options),
+            CheckId::FifoStreaming => fifo_streaming(mpu, delay, options),
+            CheckId::MotionInterrupt => motion_interrupt(mpu, delay, operator, options),
+            CheckId::SelfTest => self_test(mpu, delay, options),
+        });
+        report.checks.push(finish(id, outcome));
+    }
+    if report.chip.is_some() {
+        // a failure here already failed a check
+        let _ = restart(mpu, delay);
+    }
+    report
+}
+
+/// `init` and the default settings, which `init` leaves as found
+fn restart<I, E: Debug, D: DelayMs<u8>>(mpu: &mut Mpu6050<I>, delay: &mut D) -> Result<(), Failure>
+where
+    I: Write<Error = E> + WriteRead<Error = E>,
+{
+    bus_err(mpu.init(delay), mpu)?;
+    bus_err(mpu.apply_settings(&Mpu6050Settings::new()), mpu)
+}
+
+fn finish(id: CheckId, outcome: Outcome) -> CheckResult {
+    outcome.unwrap_or_else(|failure| CheckResult {
+        id,
+        status: CheckStatus::Fail,
+        note: Some(failure.reason),
+        hint: failure.hint,
+        measurements: Vec::new(),
+    })
+}
+
+/// failure with the hint of the error, in the context of `mpu`
+fn bus_err<T, I, E: Debug>(
+    res: Result<T, Mpu6050Error<E>>,
+    mpu: &Mpu6050<I>,
+) -> Result<T, Failure> {
+    res.map_err(|e| Failure {
+        hint: Some(e.recovery_hint_with(&mpu.hint_context())),
+        reason: format!("{:?}", e),
+    })
+}
+
+/// pass if `failures` is empty, otherwise fail listing them
+fn verdict(id: CheckId, failures: Vec<String>, measurements: Vec<(String, f32)>) -> Outcome {
+    let (status, note) = if failures.is_empty() {
+        (CheckStatus::Pass, None)
+    } else {
+        (CheckStatus::Fail, Some(failures.join("; ")))
+    };
+    Ok(CheckResult {
+        id,
+        status,
+        note,
+        hint: None,
+        measurements,
+    })
+}
+
+fn probe<I, E: Debug>(mpu: &mut Mpu6050<I>) -> Result<(CheckResult, Option<ChipInfo>), Failure>
+where
+    I: Write<Error = E> + WriteRead<Error = E>,
+{
+    let who_am_i = bus_err(mpu.read_register(Register::WHO_AM_I), mpu)?;
+    let caps = bus_err(mpu.probe_capabilities(), mpu)?;
+    let note = match caps.variant {
+        ChipVariant::Unknown(_) => Some(format!(
+            "unrecognized WHO_AM_I 0x{:02x}, run as a clone without FIFO",
+            who_am_i
+        )),
+        _ => None,
+    };
+    let result = CheckResult {
+        id: CheckId::Probe,
+        status: CheckStatus::Pass,
+        note,
+        hint: None,
+        measurements: vec![
+            ("who_am_i".into(), who_am_i as f32),
+            ("fifo_available".into(), caps.fifo_available as u8 as f32),
+        ],
+    };
+    let chip = ChipInfo {
+        who_am_i,
+        variant: caps.variant,
+    };
+    Ok((result, Some(chip)))
+}
+
+fn init_profiles<I, E: Debug, D: DelayMs<u8>>(mpu: &mut Mpu6050<I>, delay: &mut D) -> Outcome
+where
+    I: Write<Error = E> + WriteRead<Error = E>,
+{
+    let mut failures = Vec::new();
+    let mut measurements = Vec::new();
+    for (name, settings) in presets::ALL {
+        bus_err(mpu.init(delay), mpu)?;
+        bus_err(mpu.apply_settings(&settings), mpu)?;
+        let read = bus_err(mpu.read_settings(), mpu)?;
+        if read != settings {
+            failures.push(format!("{} read back as {:?}", name, read));
+        }
+        measurements.push((name.to_lowercase(), (read == settings) as u8 as f32));
+    }
+    verdict(CheckId::InitProfiles, failures, measurements)
+}
+
+fn range_sweep<I, E: Debug>(mpu: &mut Mpu6050<I>) -> Outcome
+where
+    I: Write<Error = E> + WriteRead<Error = E>,
+{
+    let mut failures = Vec::new();
+    for range in AccelRange::ALL {
+        bus_err(mpu.set_accel_range(range), mpu)?;
+        let read = bus_err(mpu.get_accel_range(), mpu)?;
+        if read != range {
+            failures.push(format!("{:?} read back as {:?}", range, read));
+        }
+    }
+    for range in GyroRange::ALL {
+        bus_err(mpu.set_gyro_range(range), mpu)?;
+        let read = bus_err(mpu.get_gyro_range(), mpu)?;
+        if read != range {
+            failures.push(format!("{:?} read back as {:?}", range, read));
+        }
+    }
+    let mismatches = vec![("mismatches".into(), failures.len() as f32)];
+    verdict(CheckId::RangeSweep, failures, mismatches)
+}
+
+fn dlpf_sweep<I, E: Debug>(mpu: &mut Mpu6050<I>) -> Outcome
+where
+    I: Write<Error = E> + WriteRead<Error = E>,
+{
+    let mut failures = Vec::new();
+    for dlpf_cfg in 0..=6 {
+        bus_err(mpu.set_dlpf(dlpf_cfg), mpu)?;
+        let read = bus_err(mpu.read_settings(), mpu)?.dlpf_cfg;
+        if read != dlpf_cfg {
+            failures.push(format!("DLPF_CFG {} read back as {}", dlpf_cfg, read));
+        }
+    }
+    let mismatches = vec![("mismatches".into(), failures.len() as f32)];
+    verdict(CheckId::DlpfSweep, failures, mismatches)
+}
+
+/// running mean and variance per axis
+#[derive(Default)]
+struct Stats {
+    n: u32,
+    mean: Vec3A,
+    m2: Vec3A,
+}
+
+impl Stats {
+    fn add(&mut self, v: Vec3A) {
+        self.n += 1;
+        let delta = v - self.mean;
+        self.mean += delta / self.n as f32;
+        self.m2 += delta * (v - self.mean);
+    }
+
+    fn std_dev(&self) -> Vec3A {
+        let variance = self.m2 / (self.n.max(2) - 1) as f32;
+        Vec3A::from(variance.max(Vec3A::ZERO).to_array().map(f32::sqrt))
+    }
+}
+
+fn noise<I, E: Debug, D: DelayMs<u8>>(
+    mpu: &mut Mpu6050<I>,
+    delay: &mut D,
+    options: &HilOptions,
+) -> Outcome
+where
+    I: Write<Error = E> + WriteRead<Error = E>,
+{
+    bus_err(mpu.apply_settings(&STATIONARY), mpu)?;
+    // past the settling of the configuration
+    delay.delay_ms(100);
+    let (mut acc, mut gyro) = (Stats::default(), Stats::default());
+    for _ in 0..options.noise_samples {
+        delay.delay_ms(POLL_MS);
+        acc.add(bus_err(mpu.get_acc(), mpu)? * 1000.);
+        gyro.add(bus_err(mpu.get_gyro(), mpu)? * DEG_PER_RAD);
+    }
+    let info = ResolutionInfo::new(
+        STATIONARY.accel_range,
+        STATIONARY.gyro_range,
+        STATIONARY.dlpf_cfg,
+    );
+    let acc_limit = info.accel_noise_mg_rms * options.noise_tolerance;
+    let gyro_limit = info.gyro_noise_dps_rms * options.noise_tolerance;
+    let (acc_rms, gyro_rms) = (acc.std_dev(), gyro.std_dev());
+
+    let mut failures = Vec::new();
+    let mut measurements = Vec::new();
+    for (sensor, rms, limit, unit) in [
+        ("acc", acc_rms, acc_limit, "mg"),
+        ("gyro", gyro_rms, gyro_limit, "dps"),
+    ] {
+        for (axis, value) in ["x", "y", "z"].iter().zip(rms.to_array()) {
+            measurements.push((format!("{}_{}_{}_rms", sensor, axis, unit), value));
+            if value > limit {
+                failures.push(format!(
+                    "{} {} noise {} {} RMS above {}",
+                    sensor,
+                    axis,
+                    Decimal(value),
+                    unit,
+                    Decimal(limit)
+                ));
+            }
+        }
+        measurements.push((format!("{}_limit_{}_rms", sensor, unit), limit));
+        if rms == Vec3A::ZERO {
+            failures.push(format!("{} output constant", sensor));
+        }
+    }
+    verdict(CheckId::Noise, failures, measurements)
+}
+
+fn gyro_calibration<I, E: Debug, D: DelayMs<u8>>(
+    mpu: &mut Mpu6050<I>,
+    delay: &mut D,
+    options: &HilOptions,
+) -> Outcome
+where
+    I: Write<Error = E> + WriteRead<Error = E>,
+{
+    let setup = AutoSetupOptions {
+        actuation_check: false,
+        gyro_samples: options.calibration_samples,
+        ..AutoSetupOptions::default()
+    };
+    let mut offsets = [Vec3A::ZERO; 2];
+    for offset in &mut offsets {
+        let report = mpu.auto_setup(delay, setup, None).map_err(|e| Failure {
+            hint: Some(e.recovery_hint_with(&mpu.hint_context())),
+            reason: format!("{:?}", e.failure),
+        })?;
+        *offset = report.gyro_offset * DEG_PER_RAD;
+    }
+    let difference = (offsets[0] - offsets[1]).abs().max_element();
+    let mut measurements: Vec<(String, f32)> = Vec::new();
+    for (run, offset) in offsets.iter().enumerate() {
+        for (axis, value) in ["x", "y", "z"].iter().zip(offset.to_array()) {
+            measurements.push((format!("run{}_{}_dps", run + 1, axis), value));
+        }
+    }
+    measurements.push(("difference_dps".into(), difference));
+    let mut failures = Vec::new();
+    if difference > options.calibration_tolerance_dps {
+        failures.push(format!(
+            "offsets differ by {} °/s, above {}",
+            Decimal(difference),
+            Decimal(options.calibration_tolerance_dps)
+        ));
+    }
+    verdict(CheckId::GyroCalibration, failures, measurements)
+}
+
+fn fifo_streaming<I, E: Debug, D: DelayMs<u8>>(
+    mpu: &mut Mpu6050<I>,
+    delay: &mut D,
+    options: &HilOptions,
+) -> Outcome
+where
+    I: Write<Error = E> + WriteRead<Error = E>,
+{
+    if !mpu.capabilities().fifo_available {
+        return Ok(CheckResult::skip(
+            CheckId::FifoStreaming,
+            "chip without FIFO",
+        ));
+    }
+    bus_err(mpu.apply_settings(&STATIONARY), mpu)?;
+    let schema = bus_err(
+        mpu.set_fifo_sources(FifoSources::NONE.with_accel(true).with_gyro(true)),
+        mpu,
+    )?;
+    bus_err(mpu.reset_fifo(), mpu)?;
+    bus_err(mpu.set_fifo_enabled(true), mpu)?;
+
+    let mut buf = [0; FIFO_CAPACITY as usize];
+    let (mut frames, mut off_gravity, mut overflows) = (0u32, 0u32, 0u32);
+    let mut elapsed_ms = 0;
+    let streamed = (|| {
+        while elapsed_ms < options.fifo_duration_ms {
+            delay.delay_ms(POLL_MS);
+            elapsed_ms += POLL_MS as u32;
+            if mpu.fifo_count()? >= FIFO_CAPACITY {
+                overflows += 1;
+            }
+            mpu.drain_fifo(&schema, &mut buf, |frame| {
+                frames += 1;
+                let g = frame.acc_g().map_or(0., |acc| acc.length());
+                if (g - 1.).abs() > FIFO_FRAME_TOLERANCE_G {
+                    off_gravity += 1;
+                }
+            })?;
+        }
+        Ok(())
+    })();
+    let stopped = mpu.set_fifo_enabled(false);
+    bus_err(streamed.and(stopped), mpu)?;
+
+    let expected = STATIONARY_ODR_HZ * options.fifo_duration_ms as f32 / 1000.;
+    let ratio = frames as f32 / expected;
+    let mut failures = Vec::new();
+    if overflows > 0 {
+        failures.push(format!("FIFO overflowed {} times", overflows));
+    }
+    if (ratio - 1.).abs() > options.fifo_rate_tolerance {
+        failures.push(format!("{} frames, expected {}", frames, expected));
+    }
+    if off_gravity > 0 {
+        failures.push(format!("{} frames off 1 g, misaligned", off_gravity));
+    }
+    let measurements = vec![
+        ("frames".into(), frames as f32),
+        ("expected_frames".into(), expected),
+        ("overflows".into(), overflows as f32),
+        ("off_gravity_frames".into(), off_gravity as f32),
+    ];
+    verdict(CheckId::FifoStreaming, failures, measurements)
+}
+
+fn motion_interrupt<I, E: Debug, D: DelayMs<u8>, O: Operator>(
+    mpu: &mut Mpu6050<I>,
+    delay: &mut D,
+    operator: &mut O,
+    options: &HilOptions,
+) -> Outcome
+where
+    I: Write<Error = E> + WriteRead<Error = E>,
+{
+    if !operator.interactive() {
+        return Ok(CheckResult::skip(
+            CheckId::MotionInterrupt,
+            "needs an operator to tap the sensor",
+        ));
+    }
+    bus_err(
+        mpu.setup_motion_detection(MotionDetectionConfig::DEFAULT),
+        mpu,
+    )?;
+    // clears what latched during the setup
+    bus_err(mpu.get_motion_detected(), mpu)?;
+    operator.prompt(&format!(
+        "tap the sensor within {} s",
+        options.motion_timeout_ms / 1000
+    ));
+    let mut elapsed_ms = 0;
+    while elapsed_ms < options.motion_timeout_ms {
+        if bus_err(mpu.get_motion_detected(), mpu)? {
+            let latency = vec![("latency_ms".into(), elapsed_ms as f32)];
+            return verdict(CheckId::MotionInterrupt, Vec::new(), latency);
+        }
+        delay.delay_ms(POLL_MS);
+        elapsed_ms += POLL_MS as u32;
+    }
+    // no error behind it, nothing to hint
+    Err(Failure {
+        reason: format!(
+            "no motion interrupt within {} ms",
+            options.motion_timeout_ms
+        ),
+        hint: None,
+    })
+}
+
+fn self_test<I, E: Debug, D: DelayMs<u8>>(
+    mpu: &mut Mpu6050<I>,
+    delay: &mut D,
+    options: &HilOptions,
+) -> Outcome
+where
+    I: Write<Error = E> + WriteRead<Error = E>,
+{
+    // the datasheet self-test procedure runs at ±8 g
+    bus_err(mpu.set_accel_range(AccelRange::G8), mpu)?;
+    let mut average = |mpu: &mut Mpu6050<I>, enabled: bool| {
+        mpu.set_accel_x_self_test(enabled)?;
+        mpu.set_accel_y_self_test(enabled)?;
+        mpu.set_accel_z_self_test(enabled)?;
+        delay.delay_ms(50);
+        let mut sum = Vec3A::ZERO;
+        for _ in 0..16 {
+            delay.delay_ms(POLL_MS);
+            sum += mpu.get_acc()?;
+        }
+        Ok(sum / 16.)
+    };
+    let on = average(mpu, true);
+    let off = average(mpu, false);
+    let response = (bus_err(on, mpu)? - bus_err(off, mpu)?).abs();
+
+    let mut failures = Vec::new();
+    let mut measurements = Vec::new();
+    for (axis, value) in ["x", "y", "z"].iter().zip(response.to_array()) {
+        measurements.push((format!("response_{}_g", axis), value));
+        if value < options.min_self_test_g {
+            failures.push(format!(
+                "{} response {} g below {}",
+                axis,
+                Decimal(value),
+                Decimal(options.min_self_test_g)
+            ));
+        }
+    }
+    verdict(CheckId::SelfTest, failures, measurements)
+}
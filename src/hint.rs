@@ -0,0 +1,371 @@
+//! What to do about an error, as a typed suggestion.
+//!
+//! An error's text tells what went wrong, not whether a retry helps. Every error of the
+//! driver maps to one [`RecoveryHint`] through `recovery_hint`, on [`Mpu6050Error`] and the
+//! composite errors wrapping it, so a generic error handler can act on it or show the
+//! operator the action. The table is stable: changing a row is a breaking change, a new
+//! error variant comes with its row, the matches have no catch-all.
+//!
+//! | error | context | hint |
+//! |:---|:---|:---|
+//! | [`I2c`](Mpu6050Error::I2c) | [`Nak`](BusErrorKind::Nak), no successful transaction yet | [`FixConfiguration`](RecoveryHint::FixConfiguration): nobody at the address |
+//! | [`I2c`](Mpu6050Error::I2c) | [`Nak`](BusErrorKind::Nak) after successful transactions | [`CheckWiring`](RecoveryHint::CheckWiring): the sensor answered before |
+//! | [`I2c`](Mpu6050Error::I2c) | bus error, a lockup recovery failed | [`PowerCycle`](RecoveryHint::PowerCycle) |
+//! | [`I2c`](Mpu6050Error::I2c) | bus error, disconnect threshold reached after successful transactions | [`ResetDevice`](RecoveryHint::ResetDevice) |
+//! | [`I2c`](Mpu6050Error::I2c) | bus error, disconnect threshold reached, no successful transaction yet | [`CheckWiring`](RecoveryHint::CheckWiring) |
+//! | [`I2c`](Mpu6050Error::I2c) | bus error otherwise | [`RetryAfterDelay`](RecoveryHint::RetryAfterDelay) of [`BUS_RETRY_DELAY_MS`] |
+//! | [`InvalidChipId`](Mpu6050Error::InvalidChipId) | 0x00 or 0xFF | [`CheckWiring`](RecoveryHint::CheckWiring): a floating or shorted line |
+//! | [`InvalidChipId`](Mpu6050Error::InvalidChipId) | a known relative ([`ChipVariant`]) | [`ReplaceHardware`](RecoveryHint::ReplaceHardware): not an MPU-6050 |
+//! | [`InvalidChipId`](Mpu6050Error::InvalidChipId) | any other value | [`FixConfiguration`](RecoveryHint::FixConfiguration): most often another device at the address, e.g. an RTC at 0x68; a clone with a made-up WHO_AM_I also lands here, the `hil` probe tells |
+//! | [`Disconnected`](Mpu6050Error::Disconnected) | | [`CheckWiring`](RecoveryHint::CheckWiring), then [`try_reconnect`](Mpu6050::try_reconnect) |
+//! | [`Unsupported`](Mpu6050Error::Unsupported) | | [`ReplaceHardware`](RecoveryHint::ReplaceHardware) |
+//! | [`Aborted`](Mpu6050Error::Aborted) | | [`RetryImmediately`](RecoveryHint::RetryImmediately) with a longer deadline |
+//! | [`ImplausibleFrame`](Mpu6050Error::ImplausibleFrame) | | [`RetryImmediately`](RecoveryHint::RetryImmediately): a glitch most likely |
+//! | [`WakeOnMotionRefused`](Mpu6050Error::WakeOnMotionRefused) | engine [`Broken`](MotionEngineStatus::Broken) | [`ReplaceHardware`](RecoveryHint::ReplaceHardware) |
+//! | [`WakeOnMotionRefused`](Mpu6050Error::WakeOnMotionRefused) | engine unverified | [`FixConfiguration`](RecoveryHint::FixConfiguration): [verify](Mpu6050::verify_motion_detection) the engine first |
+//! | all others | | [`FixConfiguration`](RecoveryHint::FixConfiguration): refused before or without the bus, the same call fails again |
+//!
+//! The composite errors pass their driver error on, their own failures map as:
+//!
+//! | error | hint |
+//! |:---|:---|
+//! | [`ScriptError::Rejected`] | [`FixConfiguration`](RecoveryHint::FixConfiguration) |
+//! | [`ScriptError::ExpectationFailed`] | [`ResetDevice`](RecoveryHint::ResetDevice): the chip is not in the expected state |
+//! | [`AutoSetupFailure::ActuationCheck`] | [`ReplaceHardware`](RecoveryHint::ReplaceHardware): the self-test failed |
+//! | [`AutoSetupFailure::CalibrationRejected`] | [`RetryAfterDelay`](RecoveryHint::RetryAfterDelay) of [`MOTION_RETRY_DELAY_MS`], once the device is still |
+//! | [`SamplingError::Wait`] | [`CheckWiring`](RecoveryHint::CheckWiring): the INT line |
+//!
+//! #### Context
+//! embedded-hal 0.2 errors are opaque, the I2C rows need a [`HintContext`]:
+//! [`Mpu6050::hint_context`] takes the kind of the latest failed transaction from the
+//! classifier of the [`recovery`](crate::recovery) registration, the counters from the
+//! [`connection`](crate::connection) monitor and the lockup recovery. Read it right after
+//! the error, before the next transaction. `recovery_hint` without a context uses
+//! [`HintContext::NONE`], which makes every bus error a retry.
+//!
+//! The reconnect and bus recovery outcomes and the `hil` report hint from the same table:
+//! [`ReconnectOutcome::recovery_hint`] maps a different chip like an invalid chip id,
+//! [`ResyncOutcome::recovery_hint`] a failed resynchronization like a failed lockup
+//! recovery.
+//!
+//! #### Order
+//! Hints are ordered, [`Ord`] follows the declaration, a longer delay after a shorter one:
+//! a later hint makes the earlier ones moot, no retry helps a chip that needs a reset and no
+//! hardware helps a wrong configuration. The `max` of several hints is the one to act on.
+//! ```
+//! use mpu6050::hint::{HintContext, RecoveryHint, BUS_RETRY_DELAY_MS};
+//! use mpu6050::recovery::BusErrorKind;
+//! use mpu6050::Mpu6050Error;
+//!
+//! let error: Mpu6050Error<()> = Mpu6050Error::I2c(());
+//! assert_eq!(
+//!     error.recovery_hint(),
+//!     RecoveryHint::RetryAfterDelay { ms: BUS_RETRY_DELAY_MS }
+//! );
+//! let context = HintContext {
+//!     bus_error: Some(BusErrorKind::Nak),
+//!     successes: 1200,
+//!     ..HintContext::NONE
+//! };
+//! assert_eq!(error.recovery_hint_with(&context), RecoveryHint::CheckWiring);
+//! assert_eq!(
+//!     Mpu6050Error::<()>::InvalidChipId(0xff).recovery_hint(),
+//!     RecoveryHint::CheckWiring
+//! );
+//! assert!(RecoveryHint::FixConfiguration > RecoveryHint::PowerCycle);
+//! ```
+
+use core::fmt;
+
+use crate::connection::{ReconnectOutcome, DEFAULT_DISCONNECT_THRESHOLD};
+use crate::cooperative::FifoDrainError;
+use crate::device::{ChipVariant, MotionEngineStatus};
+use crate::recovery::{BusErrorKind, ResyncOutcome};
+use crate::sampling::SamplingError;
+use crate::script::ScriptError;
+use crate::setup::{AutoSetupError, AutoSetupFailure};
+use crate::{Mpu6050, Mpu6050Error};
+
+/// Delay of the retry after a transient bus error, in ms
+pub const BUS_RETRY_DELAY_MS: u32 = 10;
+
+/// Delay of the retry after a calibration rejected for motion, in ms
+pub const MOTION_RETRY_DELAY_MS: u32 = 1000;
+
+/// Suggested action on an error, see the module docs
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub enum RecoveryHint {
+    /// the same call may succeed right away
+    RetryImmediately,
+    /// the same call may succeed after waiting
+    RetryAfterDelay {
+        /// wait in ms
+        ms: u32,
+    },
+    /// reset or reconnect the chip, e.g. [`Mpu6050::try_reconnect`] or `reset_device` and
+    /// `init`
+    ResetDevice,
+    /// remove the supply of the sensor, the bus cannot bring it back
+    PowerCycle,
+    /// check connectors, pull-ups and supply
+    CheckWiring,
+    /// the part is not fit for the use
+    ReplaceHardware,
+    /// the address, settings or call are wrong, no retry helps
+    FixConfiguration,
+}
+
+impl RecoveryHint {
+    /// stable snake case name, e.g. for logs and the `hil` report
+    pub fn name(&self) -> &'static str {
+        match self {
+            RecoveryHint::RetryImmediately => "retry_immediately",
+            RecoveryHint::RetryAfterDelay { .. } => "retry_after_delay",
+            RecoveryHint::ResetDevice => "reset_device",
+            RecoveryHint::PowerCycle => "power_cycle",
+            RecoveryHint::CheckWiring => "check_wiring",
+            RecoveryHint::ReplaceHardware => "replace_hardware",
+            RecoveryHint::FixConfiguration => "fix_configuration",
+        }
+    }
+
+    /// true for the hints a program can act on alone, retries and resets
+    pub fn is_automatic(&self) -> bool {
+        *self <= RecoveryHint::ResetDevice
+    }
+
+    /// hint of a chip answering with `who_am_i` where an MPU-6050 was expected
+    fn for_chip_id(who_am_i: u8) -> Self {
+        match ChipVariant::from_who_am_i(who_am_i) {
+            _ if who_am_i == 0x00 || who_am_i == 0xff => RecoveryHint::CheckWiring,
+            ChipVariant::Mpu6050 => RecoveryHint::ResetDevice,
+            ChipVariant::Mpu6500 | ChipVariant::Mpu9250 => RecoveryHint::ReplaceHardware,
+            ChipVariant::Unknown(_) => RecoveryHint::FixConfiguration,
+        }
+    }
+
+    /// hint of a failed transaction
+    fn for_bus_error(context: &HintContext) -> Self {
+        let answered = context.successes > 0;
+        match context.bus_error {
+            Some(BusErrorKind::Nak) if answered => RecoveryHint::CheckWiring,
+            Some(BusErrorKind::Nak) => RecoveryHint::FixConfiguration,
+            Some(BusErrorKind::Bus) | None => {
+                if context.unrecovered_lockups > 0 {
+                    RecoveryHint::PowerCycle
+                } else if context.consecutive_errors >= context.disconnect_threshold {
+                    match answered {
+                        true => RecoveryHint::ResetDevice,
+                        false => RecoveryHint::CheckWiring,
+                    }
+                } else {
+                    RecoveryHint::RetryAfterDelay {
+                        ms: BUS_RETRY_DELAY_MS,
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// e.g. "retry after 10 ms", "check wiring"
+impl fmt::Display for RecoveryHint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RecoveryHint::RetryImmediately => f.write_str("retry"),
+            RecoveryHint::RetryAfterDelay { ms } => write!(f, "retry after {} ms", ms),
+            RecoveryHint::ResetDevice => f.write_str("reset the device"),
+            RecoveryHint::PowerCycle => f.write_str("power cycle the sensor"),
+            RecoveryHint::CheckWiring => f.write_str("check wiring"),
+            RecoveryHint::ReplaceHardware => f.write_str("replace the hardware"),
+            RecoveryHint::FixConfiguration => f.write_str("fix the configuration"),
+        }
+    }
+}
+
+/// What the driver saw around an error, for the I2C rows of the table
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct HintContext {
+    /// kind of the latest failed transaction, None if unclassified
+    pub bus_error: Option<BusErrorKind>,
+    /// successful transactions so far
+    pub successes: u32,
+    /// consecutive failed transactions
+    pub consecutive_errors: u32,
+    /// consecutive failures counting as a disconnect
+    pub disconnect_threshold: u32,
+    /// lockup recoveries that did not resume
+    pub unrecovered_lockups: u32,
+}
+
+impl HintContext {
+    /// nothing known: unclassified, no counts, the default threshold
+    pub const NONE: Self = Self {
+        bus_error: None,
+        successes: 0,
+        consecutive_errors: 0,
+        disconnect_threshold: DEFAULT_DISCONNECT_THRESHOLD,
+        unrecovered_lockups: 0,
+    };
+}
+
+impl Default for HintContext {
+    fn default() -> Self {
+        Self::NONE
+    }
+}
+
+impl<E> Mpu6050Error<E> {
+    /// suggested action without context, see the [`hint`](crate::hint) table
+    pub fn recovery_hint(&self) -> RecoveryHint {
+        self.recovery_hint_with(&HintContext::NONE)
+    }
+
+    /// suggested action, the I2C rows from `context`
+    pub fn recovery_hint_with(&self, context: &HintContext) -> RecoveryHint {
+        match self {
+            Mpu6050Error::I2c(_) => RecoveryHint::for_bus_error(context),
+            Mpu6050Error::InvalidChipId(who_am_i) => RecoveryHint::for_chip_id(*who_am_i),
+            Mpu6050Error::Disconnected => RecoveryHint::CheckWiring,
+            Mpu6050Error::Unsupported(_)
+            | Mpu6050Error::WakeOnMotionRefused(MotionEngineStatus::Broken) => {
+                RecoveryHint::ReplaceHardware
+            }
+            Mpu6050Error::Aborted { .. } | Mpu6050Error::ImplausibleFrame { .. } => {
+                RecoveryHint::RetryImmediately
+            }
+            #[allow(deprecated)]
+            Mpu6050Error::ExtDataOverflow(_)
+            | Mpu6050Error::StaleExtDataSlot
+            | Mpu6050Error::StaleFifoSchema
+            | Mpu6050Error::StaleConfiguration { .. }
+            | Mpu6050Error::BufferTooSmall(_)
+            | Mpu6050Error::InvalidSettings(_)
+            | Mpu6050Error::AliasingLikely(_)
+            | Mpu6050Error::InvalidTimestamp(_)
+            | Mpu6050Error::DelayRequired
+            | Mpu6050Error::FifoActive
+            | Mpu6050Error::InvalidRegisterAccess { .. }
+            | Mpu6050Error::BoardConstraint(_)
+            | Mpu6050Error::NotActivated
+            | Mpu6050Error::DeniedRegisterRange(_)
+            | Mpu6050Error::WakeOnMotionRefused(_)
+            | Mpu6050Error::Reentrancy
+            | Mpu6050Error::BusRateTooHigh(_) => RecoveryHint::FixConfiguration,
+        }
+    }
+}
+
+impl<E> ScriptError<E> {
+    /// suggested action without context, see the [`hint`](crate::hint) table
+    pub fn recovery_hint(&self) -> RecoveryHint {
+        self.recovery_hint_with(&HintContext::NONE)
+    }
+
+    /// suggested action, a driver error's I2C rows from `context`
+    pub fn recovery_hint_with(&self, context: &HintContext) -> RecoveryHint {
+        match self {
+            ScriptError::Rejected(_) => RecoveryHint::FixConfiguration,
+            ScriptError::ExpectationFailed { .. } => RecoveryHint::ResetDevice,
+            ScriptError::Driver { error, .. } => error.recovery_hint_with(context),
+        }
+    }
+}
+
+impl<E> AutoSetupFailure<E> {
+    /// suggested action without context, see the [`hint`](crate::hint) table
+    pub fn recovery_hint(&self) -> RecoveryHint {
+        self.recovery_hint_with(&HintContext::NONE)
+    }
+
+    /// suggested action, a driver error's I2C rows from `context`
+    pub fn recovery_hint_with(&self, context: &HintContext) -> RecoveryHint {
+        match self {
+            AutoSetupFailure::Device(error) => error.recovery_hint_with(context),
+            AutoSetupFailure::ActuationCheck { .. } => RecoveryHint::ReplaceHardware,
+            AutoSetupFailure::CalibrationRejected { .. } => RecoveryHint::RetryAfterDelay {
+                ms: MOTION_RETRY_DELAY_MS,
+            },
+        }
+    }
+}
+
+impl<E> AutoSetupError<E> {
+    /// hint of the failure
+    pub fn recovery_hint(&self) -> RecoveryHint {
+        self.failure.recovery_hint()
+    }
+
+    /// hint of the failure, a driver error's I2C rows from `context`
+    pub fn recovery_hint_with(&self, context: &HintContext) -> RecoveryHint {
+        self.failure.recovery_hint_with(context)
+    }
+}
+
+impl<E> FifoDrainError<E> {
+    /// hint of the driver error
+    pub fn recovery_hint(&self) -> RecoveryHint {
+        self.error.recovery_hint()
+    }
+
+    /// hint of the driver error, its I2C rows from `context`
+    pub fn recovery_hint_with(&self, context: &HintContext) -> RecoveryHint {
+        self.error.recovery_hint_with(context)
+    }
+}
+
+impl<E, P> SamplingError<E, P> {
+    /// suggested action without context, see the [`hint`](crate::hint) table
+    pub fn recovery_hint(&self) -> RecoveryHint {
+        self.recovery_hint_with(&HintContext::NONE)
+    }
+
+    /// suggested action, a driver error's I2C rows from `context`
+    pub fn recovery_hint_with(&self, context: &HintContext) -> RecoveryHint {
+        match self {
+            SamplingError::Device(error) => error.recovery_hint_with(context),
+            SamplingError::Wait(_) => RecoveryHint::CheckWiring,
+        }
+    }
+}
+
+impl ReconnectOutcome {
+    /// None for the same chip, a different one as an invalid chip id: a new MPU-6050 wants
+    /// `init` ([`ResetDevice`](RecoveryHint::ResetDevice))
+    pub fn recovery_hint(&self) -> Option<RecoveryHint> {
+        match self {
+            ReconnectOutcome::SameChip { .. } => None,
+            ReconnectOutcome::DifferentChip { found, .. } => {
+                Some(RecoveryHint::for_chip_id(*found))
+            }
+        }
+    }
+}
+
+impl ResyncOutcome {
+    /// None when resumed, otherwise [`ResetDevice`](RecoveryHint::ResetDevice) for a chip
+    /// to reconnect and [`PowerCycle`](RecoveryHint::PowerCycle) for a failed check, as a bus
+    /// error after a failed lockup recovery
+    pub fn recovery_hint(&self) -> Option<RecoveryHint> {
+        match self {
+            ResyncOutcome::Resumed { .. } => None,
+            ResyncOutcome::NeedsReconnect => Some(RecoveryHint::ResetDevice),
+            ResyncOutcome::BusFailed => Some(RecoveryHint::PowerCycle),
+        }
+    }
+}
+
+impl<I, D> Mpu6050<I, D> {
+    /// context of the latest transaction for `recovery_hint_with`, see the
+    /// [`hint`](crate::hint) module
+    pub fn hint_context(&self) -> HintContext {
+        let recovery = self.bus_recovery.as_ref();
+        HintContext {
+            bus_error: recovery.and_then(|state| state.last_failure()),
+            successes: self.connection.successes(),
+            consecutive_errors: self.connection.consecutive_errors(),
+            disconnect_threshold: self.connection.threshold(),
+            unrecovered_lockups: recovery.map_or(0, |state| state.stats().unrecovered),
+        }
+    }
+}
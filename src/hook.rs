@@ -0,0 +1,173 @@
+//! User hooks in the sample path.
+//!
+//! A sample hook (`fn(&mut MpuSample)`) transforms every scaled output of the driver, e.g.
+//! with a thermal model from factory characterization, so all consumers see the corrected
+//! data. A sample tap (`fn(&MpuSample)`) observes the result for logging or metrics, a
+//! sample observer (`fn(&SampleContext)`) the same with a copy of the driver's configuration,
+//! the ranges and the epoch the sample was taken at.
+//!
+//! #### Where they run
+//! Every scaled output goes through one function, in this order:
+//! 1. raw counts are converted to g, rad/s and °C
+//! 2. per-axis scale factors and offsets are applied
+//! 3. built-in consumers see the sample: supervisor, background gyro calibration, power
+//!    governor (full samples only)
+//! 4. the [calibration policy](crate::validity) evaluates it, if set
+//! 5. the [plausibility](crate::plausibility) checks score it, if configured
+//! 6. the hook transforms it
+//! 7. the tap observes it
+//! 8. the observer observes it with the [`DriverInfo`](crate::reentrancy::DriverInfo)
+//! 9. the per-sample gauges of the [`metrics`](crate::metrics) sink see it, if enabled
+//! 10. it is returned to the caller
+//!
+//! The supervisor, the background calibration, the governor, the calibration policy and the
+//! plausibility checks therefore see the data without the user correction. Paths covered:
+//! `get_acc`, `get_gyro`, `get_temp` and everything built on them (`get_acc_as`,
+//! `get_acc_angles`, ...), all full-sample reads (`get_all`, the `samples` iterator,
+//! `run_sampling_loop`, `sample_into_interp_buffer`, `DifferentialPair::read_pair`,
+//! `read_into_views`, `read_batch`) and the scaled FIFO drain `drain_fifo_into`, whose
+//! frames come without offsets, see [`split`](crate::split). `tests/hook.rs` reads through
+//! each of them.
+//!
+//! The driver has no unhooked fast path. What bypasses the hook is raw data, not scaled
+//! output: `get_acc_raw`, `get_gyro_raw`, `get_all_raw`, `read_registers`, and the FIFO
+//! frames of `drain_fifo` and `drain_fifo_cooperative`, whose
+//! [`acc_g`](crate::fifo::FifoFrame::acc_g) and friends convert with the sensitivity alone.
+//!
+//! Single sensor reads pass a partial sample: the parts not read are NaN, temperature
+//! included, and tagged [`Invalid`](crate::provenance::Provenance::Invalid). A hook needing
+//! the temperature, like a thermal model, should leave samples with a NaN temperature alone
+//! or use full-sample reads. A hook replacing values rather than correcting them (spike
+//! rejection, a fallback estimate) tags them with
+//! [`with_provenance`](MpuSample::with_provenance), see [`provenance`](crate::provenance).
+//!
+//! #### Reentrancy and panics
+//! Hooks are plain function pointers: they capture nothing and get no access to the driver,
+//! which is mutably borrowed for the duration of the read anyway. Calling back into the
+//! driver needs it in a global, which in safe code means a lock the caller already holds.
+//! A closure capturing the driver does not coerce to a function pointer:
+//! ```compile_fail
+//! use embedded_hal::blocking::i2c::{Write, WriteRead};
+//! use mpu6050::{Mpu6050, MpuSample};
+//!
+//! fn reenter<I, E>(mpu: &mut Mpu6050<I>)
+//! where
+//!     I: Write<Error = E> + WriteRead<Error = E>,
+//! {
+//!     let driver = &mut *mpu;
+//!     mpu.set_sample_hook(Some(|_: &mut MpuSample| {
+//!         driver.get_acc().ok();
+//!     }));
+//! }
+//! ```
+//! A panicking hook or tap unwinds out of the read like any other panic, the driver does
+//! not catch it. The contract of every callback of the driver is in
+//! [`reentrancy`](crate::reentrancy).
+
+#[cfg(not(feature = "minimal-pipeline"))]
+use glam::Vec3A;
+
+#[cfg(not(feature = "minimal-pipeline"))]
+use crate::provenance::{FieldGroup, Provenance, SampleProvenance};
+use crate::reentrancy::SampleContext;
+use crate::{Mpu6050, MpuSample};
+
+/// Transform applied to every scaled sample
+pub type SampleHook = fn(&mut MpuSample);
+
+/// Observer of every scaled sample, after the hook
+pub type SampleTap = fn(&MpuSample);
+
+/// Observer of every scaled sample with the driver's configuration, after the tap
+pub type SampleObserver = fn(&SampleContext<'_>);
+
+impl<I, D> Mpu6050<I, D> {
+    /// set or remove the transform applied to every scaled output, see [`hook`](crate::hook)
+    pub fn set_sample_hook(&mut self, hook: Option<SampleHook>) {
+        self.sample_hook = hook;
+    }
+
+    /// get sample hook
+    pub fn get_sample_hook(&self) -> Option<SampleHook> {
+        self.sample_hook
+    }
+
+    /// set or remove the observer of every scaled output, see [`hook`](crate::hook)
+    pub fn set_sample_tap(&mut self, tap: Option<SampleTap>) {
+        self.sample_tap = tap;
+    }
+
+    /// get sample tap
+    pub fn get_sample_tap(&self) -> Option<SampleTap> {
+        self.sample_tap
+    }
+
+    /// set or remove the observer of every scaled output with the driver's configuration,
+    /// see [`hook`](crate::hook)
+    pub fn set_sample_observer(&mut self, observer: Option<SampleObserver>) {
+        self.sample_observer = observer;
+    }
+
+    /// get sample observer
+    pub fn get_sample_observer(&self) -> Option<SampleObserver> {
+        self.sample_observer
+    }
+
+    /// The single point where scaled samples leave the driver: evaluates the
+    /// [calibration policy](crate::validity), scores the [plausibility](crate::plausibility),
+    /// applies hook, then tap and observer
+    #[cfg(not(feature = "minimal-pipeline"))]
+    pub(crate) fn deliver(&mut self, sample: MpuSample) -> MpuSample {
+        self.observe_calibration_validity(&sample);
+        let mut sample = self.score_plausibility(sample);
+        if let Some(hook) = self.sample_hook {
+            hook(&mut sample);
+        }
+        if let Some(tap) = self.sample_tap {
+            tap(&sample);
+        }
+        if let Some(observer) = self.sample_observer {
+            observer(&SampleContext::new(&sample, self.driver_info()));
+        }
+        self.emit_gauges(&sample);
+        sample
+    }
+
+    /// the sample as is, the `minimal-pipeline` build calls no hook
+    #[cfg(feature = "minimal-pipeline")]
+    #[inline(always)]
+    pub(crate) fn deliver(&mut self, sample: MpuSample) -> MpuSample {
+        sample
+    }
+
+    /// [`deliver`](Self::deliver) for a single sensor read, the other parts NaN and invalid
+    #[cfg(not(feature = "minimal-pipeline"))]
+    pub(crate) fn deliver_partial(
+        &mut self,
+        acc: Option<Vec3A>,
+        gyro: Option<Vec3A>,
+        temp: Option<f32>,
+        settling: bool,
+        out_of_band: bool,
+    ) -> MpuSample {
+        let tag = |read: bool| match read {
+            true => Provenance::Measured,
+            false => Provenance::Invalid,
+        };
+        let provenance = SampleProvenance::MEASURED
+            .with(FieldGroup::Acc, tag(acc.is_some()))
+            .with(FieldGroup::Gyro, tag(gyro.is_some()))
+            .with(FieldGroup::Temp, tag(temp.is_some()));
+        let sample = MpuSample::new(
+            acc.unwrap_or(Vec3A::NAN),
+            gyro.unwrap_or(Vec3A::NAN),
+            temp.unwrap_or(f32::NAN),
+        );
+        self.deliver(
+            sample
+                .with_settling(settling)
+                .with_out_of_band(out_of_band)
+                .with_provenance(provenance),
+        )
+    }
+}
@@ -0,0 +1,102 @@
+//! Offset registers of the chip, added to the output before it reaches the data registers.
+//!
+//! Unlike the driver's own offsets (`gyro_offset`, `acc_offset`) they apply to everything
+//! read from the chip, the FIFO included. Every offset is a [`RegisterPair`] written with a
+//! single two byte transaction: the offsets act on the live output, a half written value
+//! would show as a glitch of up to 256 LSB.
+//!
+//! * gyro: [`XG_OFFS_USRH`] and following, [`GYRO_OFFSET_DPS_PER_LSB`] whatever the range
+//! * accel: the factory trim at a chip dependent place, see [`AccelOffsetScaling`]. Bit 0 of
+//!   the low register is reserved and kept as read
+//!
+//! [`Mpu6050::store_gyro_offset_in_hardware`] moves the driver's gyro offset, e.g. from a
+//! calibration, into the registers.
+
+#[cfg(feature = "driver")]
+use embedded_hal::blocking::i2c::{Write, WriteRead};
+
+#[cfg(feature = "driver")]
+use crate::config_epoch::ConfigChange;
+use crate::conversion;
+use crate::device::*;
+use crate::register::Register;
+use crate::validity::CalibrationKind;
+use crate::{Mpu6050, Mpu6050Error};
+
+const GYRO_OFFSET_REGISTERS: [Register; 3] = [
+    Register::XG_OFFS_USRH,
+    Register::YG_OFFS_USRH,
+    Register::ZG_OFFS_USRH,
+];
+
+#[cfg(feature = "driver")]
+impl<I, D, E> Mpu6050<I, D>
+where
+    I: Write<Error = E> + WriteRead<Error = E>,
+{
+    /// gyro offset registers X, Y, Z in [`GYRO_OFFSET_DPS_PER_LSB`]
+    pub fn get_gyro_hw_offsets(&mut self) -> Result<[i16; 3], Mpu6050Error<E>> {
+        let mut offsets = [0; 3];
+        for (offset, reg) in offsets.iter_mut().zip(GYRO_OFFSET_REGISTERS) {
+            *offset = self.read_register_word(reg)?;
+        }
+        Ok(offsets)
+    }
+
+    /// sets the gyro offset registers X, Y, Z, one transaction each
+    pub fn set_gyro_hw_offsets(&mut self, offsets: [i16; 3]) -> Result<(), Mpu6050Error<E>> {
+        // before the writes, a failed one may leave some registers changed
+        self.bump_epoch(ConfigChange::Offsets);
+        for (offset, reg) in offsets.into_iter().zip(GYRO_OFFSET_REGISTERS) {
+            self.write_register_word(reg, offset)?;
+        }
+        Ok(())
+    }
+
+    /// accel offset registers X, Y, Z, bit 0 reserved
+    pub fn get_accel_hw_offsets(&mut self) -> Result<[i16; 3], Mpu6050Error<E>> {
+        let registers = self.accel_offset_registers()?;
+        let mut offsets = [0; 3];
+        for (offset, reg) in offsets.iter_mut().zip(registers) {
+            *offset = self.read_register_word(reg)?;
+        }
+        Ok(offsets)
+    }
+
+    /// Sets the accel offset registers X, Y, Z, one transaction each. Bit 0 of each is
+    /// reserved, it keeps the value read from the chip
+    pub fn set_accel_hw_offsets(&mut self, offsets: [i16; 3]) -> Result<(), Mpu6050Error<E>> {
+        let registers = self.accel_offset_registers()?;
+        self.bump_epoch(ConfigChange::Offsets);
+        for (offset, reg) in offsets.into_iter().zip(registers) {
+            let reserved = self.read_register_word(reg)? & 1;
+            self.write_register_word(reg, offset & !1 | reserved)?;
+        }
+        Ok(())
+    }
+
+    /// Subtracts `gyro_offset` in the gyro offset registers, rounded to their resolution with
+    /// the driver's [`rounding`](Self::rounding), and keeps the rounding residue as
+    /// `gyro_offset`. Returns the new register values. Readings stay the same, within a count,
+    /// and the FIFO gains the correction
+    pub fn store_gyro_offset_in_hardware(&mut self) -> Result<[i16; 3], Mpu6050Error<E>> {
+        let current = self.get_gyro_hw_offsets()?;
+        let (offsets, residue) =
+            conversion::gyro_offset_counts(current, -self.gyro_offset, self.rounding);
+        self.set_gyro_hw_offsets(offsets)?;
+        self.gyro_offset = -residue;
+        self.bump_epoch(ConfigChange::Offsets);
+        let meta = self.calibration_meta(CalibrationKind::SoftwareOffsets);
+        self.set_calibration_meta(CalibrationKind::HardwareOffsets, meta);
+        Ok(offsets)
+    }
+
+    fn accel_offset_registers(&self) -> Result<[Register; 3], Mpu6050Error<E>> {
+        self.require(Capability::AccelOffsets)?;
+        self.capabilities
+            .accel_offset_scaling
+            .registers()
+            .map(|registers| registers.map(Register::known))
+            .ok_or(Mpu6050Error::Unsupported(Capability::AccelOffsets))
+    }
+}
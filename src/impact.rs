@@ -0,0 +1,418 @@
+//! Experimental: a high accelerometer range for the duration of an impact only.
+//!
+//! [Interleaving](crate::interleave) alternates the ranges on a fixed cadence and spends
+//! half the samples at the coarse range. Impact ranging stays at the range the driver is at,
+//! ±2 g for its resolution, and switches to a high range only while an impact lasts. With
+//! [`Mpu6050::configure_impact_ranging`] the driver runs an [`ImpactRanger`] in the sample
+//! read of the sampling loop ([`run_sampling_loop`](Mpu6050::run_sampling_loop) and the
+//! other sample paths, the same as interleaving). The range at the call is the low range,
+//! configuring touches no register.
+//!
+//! #### Onset
+//! A sample taken at the low range triggers if its magnitude reaches
+//! [`trigger_g`](ImpactRangeConfig::trigger_g) or an axis is clipped, at least
+//! [`CLIP_FRACTION`](crate::interleave::CLIP_FRACTION) of the full scale. The switch to the
+//! [`high_range`](ImpactRangeConfig::high_range) is written in the same read, right after
+//! the triggering sample: the next sample, one sample period after the trigger, is captured
+//! at the high range. A switch is
+//! [`SWITCH_TRANSACTIONS`](crate::interleave::SWITCH_TRANSACTIONS) transactions on top of
+//! the reads of the triggering sample. That first sample was converted with the old range,
+//! it is the [`SettleTrigger::Range`](crate::device::SettleTrigger::Range) settling sample
+//! and flagged `settling`, the first usable sample at the high range is the one after.
+//! While ranging the [`SettlingPolicy`] is [`Flag`](SettlingPolicy::Flag), as for
+//! interleaving.
+//!
+//! #### Hold
+//! The hold ends [`hold_samples`](ImpactRangeConfig::hold_samples) samples after the last
+//! triggering sample: the onset, or a usable sample at the high range reaching `trigger_g`
+//! or clipped. A second impact during the hold extends it from that sample. The switch back
+//! is written before reading the first sample past the hold, that sample is settling. For
+//! `hold_samples` 3 and an impact at sample 1:
+//!
+//! | sample | 0 | 1 | 2 | 3 | 4 | 5 | 6 |
+//! |:---|:---|:---|:---|:---|:---|:---|:---|
+//! | range | low | low | high | high | high | low | low |
+//! | transition | | onset | switched | | | restored | |
+//! | settling | | | yes | | | yes | |
+//!
+//! With a second impact at sample 3 the hold ends after sample 6 instead, sample 3 is
+//! flagged [`Extended`](RangeTransition::Extended) and sample 7 is restored. After the
+//! restore the [`RearmPolicy`] decides when an onset counts again.
+//!
+//! #### Flags
+//! Every sample read while ranging is tagged with the range it was captured at
+//! ([`MpuSample::accel_range`](crate::MpuSample::accel_range)), the samples of a transition
+//! with the [`RangeTransition`]
+//! ([`MpuSample::range_transition`](crate::MpuSample::range_transition)). Settling samples
+//! neither trigger nor extend. [`ImpactStats`] counts the impacts, the extensions, the peak
+//! magnitude of the usable samples at the high range and those of them clipped anyway.
+//!
+//! A failed switch fails the read, the ranger keeps its state: an onset is taken again from
+//! the next sample, a restore is tried again before it. Impact ranging and interleaving
+//! exclude each other. Like interleaving, every switch invalidates the FIFO schema, the mode
+//! is for direct sample reads.
+//! ```
+//! use mpu6050::device::AccelRange;
+//! use mpu6050::impact::{ImpactRangeConfig, RearmPolicy};
+//! use mpu6050::settings::SettingsError;
+//!
+//! let config = ImpactRangeConfig {
+//!     trigger_g: 1.8,
+//!     hold_samples: 20,
+//!     high_range: AccelRange::G16,
+//!     rearm_policy: RearmPolicy::AfterQuiet(5),
+//! };
+//! assert_eq!(config.validate(AccelRange::G2), Ok(()));
+//! assert_eq!(
+//!     config.validate(AccelRange::G16),
+//!     Err(SettingsError::ImpactRanges(AccelRange::G16, AccelRange::G16))
+//! );
+//! ```
+
+use core::fmt;
+
+#[cfg(feature = "driver")]
+use embedded_hal::blocking::i2c::{Write, WriteRead};
+
+use crate::device::AccelRange;
+#[cfg(all(feature = "driver", not(feature = "minimal-pipeline")))]
+use crate::interleave::CLIP_FRACTION;
+use crate::interleave::RangeInterleave;
+use crate::settings::SettingsError;
+use crate::settling::SettlingPolicy;
+use crate::Mpu6050;
+#[cfg(feature = "driver")]
+use crate::Mpu6050Error;
+#[cfg(all(feature = "driver", not(feature = "minimal-pipeline")))]
+use crate::MpuSample;
+
+/// When an onset counts again after the hold
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum RearmPolicy {
+    /// from the first usable sample after the restore
+    Immediate,
+    /// after that many consecutive usable samples at the low range without a trigger, for
+    /// the ringing after an impact. 0 is immediate
+    AfterQuiet(u8),
+    /// one impact only, until [`rearm_impact_ranging`](Mpu6050::rearm_impact_ranging)
+    Manual,
+}
+
+/// Impact ranging configuration
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct ImpactRangeConfig {
+    /// magnitude in g from which a sample triggers, a clipped axis triggers below it
+    pub trigger_g: f32,
+    /// samples at the high range after the last triggering sample, the settling one included
+    pub hold_samples: u16,
+    /// range during the hold
+    pub high_range: AccelRange,
+    /// when an onset counts again after the hold
+    pub rearm_policy: RearmPolicy,
+}
+
+impl ImpactRangeConfig {
+    /// Checks the trigger, that `high_range` is above `low` and that the hold leaves a
+    /// usable sample after the settling one
+    pub fn validate(&self, low: AccelRange) -> Result<(), SettingsError> {
+        if !(self.trigger_g.is_finite() && self.trigger_g > 0.) {
+            return Err(SettingsError::ImpactTrigger);
+        }
+        if low >= self.high_range {
+            return Err(SettingsError::ImpactRanges(low, self.high_range));
+        }
+        let min = RangeInterleave::settle_samples() as u16 + 1;
+        if self.hold_samples < min {
+            return Err(SettingsError::ImpactHold {
+                hold: self.hold_samples,
+                min,
+            });
+        }
+        Ok(())
+    }
+}
+
+/// Range transition a sample is part of, see the module docs
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum RangeTransition {
+    /// triggered at the low range, the switch to the high range followed its read
+    Onset,
+    /// first sample at the high range, settling
+    Switched,
+    /// triggered during the hold, which restarts from this sample
+    Extended,
+    /// first sample back at the low range, settling
+    Restored,
+}
+
+impl fmt::Display for RangeTransition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            RangeTransition::Onset => "onset",
+            RangeTransition::Switched => "switched",
+            RangeTransition::Extended => "extended",
+            RangeTransition::Restored => "restored",
+        })
+    }
+}
+
+/// Where the ranger is
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum ImpactPhase {
+    /// at the low range, the next usable sample may trigger
+    Armed,
+    /// at the high range, samples left in the hold
+    High {
+        /// samples still read at the high range
+        remaining: u16,
+    },
+    /// at the low range, usable samples without a trigger left before arming
+    Quiet {
+        /// samples left
+        remaining: u8,
+    },
+    /// at the low range, waiting for [`rearm_impact_ranging`](Mpu6050::rearm_impact_ranging)
+    Disarmed,
+}
+
+/// Impact ranging statistics
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct ImpactStats {
+    /// onsets
+    pub impacts: u32,
+    /// holds extended by a second impact
+    pub extensions: u32,
+    /// largest magnitude of a usable sample at the high range, in g
+    pub peak_g: f32,
+    /// usable samples at the high range with a clipped axis
+    pub clipped_anyway: u32,
+}
+
+impl fmt::Display for ImpactStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} impacts, {} extensions, peak {:.2} g, {} clipped anyway",
+            self.impacts, self.extensions, self.peak_g, self.clipped_anyway
+        )
+    }
+}
+
+/// Impact ranging state, no bus access. Driven by the sample reads, see the module docs
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct ImpactRanger {
+    config: ImpactRangeConfig,
+    low: AccelRange,
+    phase: ImpactPhase,
+    /// transition of the sample being read
+    current: Option<RangeTransition>,
+    /// transition of the next sample
+    next: Option<RangeTransition>,
+    stats: ImpactStats,
+    restore_policy: SettlingPolicy,
+}
+
+impl ImpactRanger {
+    /// configuration
+    pub fn config(&self) -> ImpactRangeConfig {
+        self.config
+    }
+
+    /// range outside the holds, the driver's range when configured
+    pub fn low_range(&self) -> AccelRange {
+        self.low
+    }
+
+    /// where the ranger is
+    pub fn phase(&self) -> ImpactPhase {
+        self.phase
+    }
+
+    /// true during a hold
+    pub fn is_high(&self) -> bool {
+        matches!(self.phase, ImpactPhase::High { .. })
+    }
+
+    /// statistics since configured
+    pub fn stats(&self) -> ImpactStats {
+        self.stats
+    }
+
+    /// policy restored when ranging stops
+    pub fn restore_policy(&self) -> SettlingPolicy {
+        self.restore_policy
+    }
+
+    /// phase after the hold, by the rearm policy
+    #[cfg(all(feature = "driver", not(feature = "minimal-pipeline")))]
+    fn after_hold(&self) -> ImpactPhase {
+        match self.config.rearm_policy {
+            RearmPolicy::Immediate | RearmPolicy::AfterQuiet(0) => ImpactPhase::Armed,
+            RearmPolicy::AfterQuiet(remaining) => ImpactPhase::Quiet { remaining },
+            RearmPolicy::Manual => ImpactPhase::Disarmed,
+        }
+    }
+}
+
+#[cfg(feature = "driver")]
+impl<I, D, E> Mpu6050<I, D>
+where
+    I: Write<Error = E> + WriteRead<Error = E>,
+{
+    /// Starts impact ranging at the current range, armed. Invalid configurations, and
+    /// configurations while interleaving, are refused before touching the bus. A hold in
+    /// progress is ended first, the low range of the previous configuration kept
+    pub fn configure_impact_ranging(
+        &mut self,
+        config: ImpactRangeConfig,
+    ) -> Result<(), Mpu6050Error<E>> {
+        if self.interleave.is_some() {
+            return Err(Mpu6050Error::InvalidSettings(
+                SettingsError::RangeModesExclusive,
+            ));
+        }
+        let (low, restore_policy) = match self.impact {
+            Some(ranger) => (ranger.low, ranger.restore_policy),
+            None => (self.accel_range, self.settling_policy),
+        };
+        config
+            .validate(low)
+            .map_err(Mpu6050Error::InvalidSettings)?;
+        if self.impact.is_some_and(|ranger| ranger.is_high()) {
+            self.switch_accel_range(low)?;
+        }
+        self.impact = Some(ImpactRanger {
+            config,
+            low,
+            phase: ImpactPhase::Armed,
+            current: None,
+            next: None,
+            stats: ImpactStats::default(),
+            restore_policy,
+        });
+        self.settling_policy = SettlingPolicy::Flag;
+        Ok(())
+    }
+
+    /// Stops impact ranging, switches back to the low range during a hold and restores the
+    /// settling policy
+    pub fn disable_impact_ranging(&mut self) -> Result<(), Mpu6050Error<E>> {
+        let Some(ranger) = self.impact else {
+            return Ok(());
+        };
+        if ranger.is_high() {
+            self.switch_accel_range(ranger.low)?;
+        }
+        self.impact = None;
+        self.settling_policy = ranger.restore_policy;
+        Ok(())
+    }
+
+    /// restores the low range at the end of a hold, returns the range of the sample about to
+    /// be read. None if not ranging
+    #[cfg(not(feature = "minimal-pipeline"))]
+    pub(crate) fn step_impact_ranging(&mut self) -> Result<Option<AccelRange>, Mpu6050Error<E>> {
+        let Some(mut ranger) = self.impact else {
+            return Ok(None);
+        };
+        let mut current = ranger.next.take();
+        match ranger.phase {
+            ImpactPhase::High { remaining: 0 } => {
+                self.switch_accel_range(ranger.low)?;
+                ranger.phase = ranger.after_hold();
+                current = Some(RangeTransition::Restored);
+            }
+            ImpactPhase::High { remaining } => {
+                ranger.phase = ImpactPhase::High {
+                    remaining: remaining - 1,
+                };
+            }
+            _ => {}
+        }
+        ranger.current = current;
+        self.impact = Some(ranger);
+        Ok(Some(self.accel_range))
+    }
+
+    /// checks a sample just read for a trigger, switches to the high range on an onset and
+    /// tags the sample with its transition
+    #[cfg(not(feature = "minimal-pipeline"))]
+    pub(crate) fn detect_impact(
+        &mut self,
+        sample: MpuSample,
+    ) -> Result<MpuSample, Mpu6050Error<E>> {
+        let Some(mut ranger) = self.impact else {
+            return Ok(sample);
+        };
+        let mut transition = ranger.current;
+        let acc = sample.acc();
+        let magnitude = acc.length();
+        let clipped = acc.abs().max_element() >= CLIP_FRACTION * self.accel_range.full_scale_g();
+        let triggered = magnitude >= ranger.config.trigger_g || clipped;
+        if !sample.settling() {
+            match ranger.phase {
+                ImpactPhase::Armed if triggered => {
+                    self.switch_accel_range(ranger.config.high_range)?;
+                    ranger.phase = ImpactPhase::High {
+                        remaining: ranger.config.hold_samples,
+                    };
+                    ranger.next = Some(RangeTransition::Switched);
+                    ranger.stats.impacts += 1;
+                    transition = Some(RangeTransition::Onset);
+                }
+                ImpactPhase::High { .. } => {
+                    ranger.stats.peak_g = ranger.stats.peak_g.max(magnitude);
+                    if clipped {
+                        ranger.stats.clipped_anyway += 1;
+                    }
+                    if triggered {
+                        ranger.phase = ImpactPhase::High {
+                            remaining: ranger.config.hold_samples,
+                        };
+                        ranger.stats.extensions += 1;
+                        transition = Some(RangeTransition::Extended);
+                    }
+                }
+                ImpactPhase::Quiet { remaining } => {
+                    ranger.phase = if triggered {
+                        ranger.after_hold()
+                    } else if remaining <= 1 {
+                        ImpactPhase::Armed
+                    } else {
+                        ImpactPhase::Quiet {
+                            remaining: remaining - 1,
+                        }
+                    };
+                }
+                _ => {}
+            }
+        }
+        ranger.current = None;
+        self.impact = Some(ranger);
+        Ok(sample.with_range_transition(transition))
+    }
+}
+
+impl<I, D> Mpu6050<I, D> {
+    /// impact ranging state, None if not ranging
+    pub fn impact_ranger(&self) -> Option<ImpactRanger> {
+        self.impact
+    }
+
+    /// Arms a ranger waiting for the quiet samples or disarmed by [`RearmPolicy::Manual`],
+    /// no bus access. False if not ranging, armed already or in a hold
+    pub fn rearm_impact_ranging(&mut self) -> bool {
+        match &mut self.impact {
+            Some(ranger)
+                if matches!(
+                    ranger.phase,
+                    ImpactPhase::Quiet { .. } | ImpactPhase::Disarmed
+                ) =>
+            {
+                ranger.phase = ImpactPhase::Armed;
+                true
+            }
+            _ => false,
+        }
+    }
+}
@@ -0,0 +1,257 @@
+//! Leftovers of a previous run found by `init`.
+//!
+//! A host that crashed or reset without power-cycling the sensor leaves the chip as the
+//! previous run had it: in the middle of a self-test, awake, with a FIFO that overflowed long
+//! ago, configured away from the reset values. `init` inspects the chip once WHO_AM_I was
+//! verified and before waking it, corrects what would corrupt the readings and records every
+//! anomaly in [`InitFindings`], available from [`Mpu6050::init_findings`] until the next
+//! init. A chip fresh from a power-on has no findings.
+//!
+//! | finding | observed | action |
+//! |:---|:---|:---|
+//! | [`SelfTestActive`](FindingKind::SelfTestActive) | self-test bits in GYRO_CONFIG or ACCEL_CONFIG | [`Cleared`](FindingAction::Cleared): the excitation offsets every reading |
+//! | [`PowerState`](FindingKind::PowerState) | PWR_MGMT_1 other than its reset value, awake, cycling or on another clock | [`Overwritten`](FindingAction::Overwritten) by the wake |
+//! | [`FifoOverflowLatched`](FindingKind::FifoOverflowLatched) | FIFO_OFLOW_INT in INT_STATUS | [`Cleared`](FindingAction::Cleared): the read clears the flag, the stale FIFO is reset |
+//! | [`NonDefaultRegister`](FindingKind::NonDefaultRegister) | a configuration register of [`INSPECTED`] other than its reset value, self-test bits aside | [`Overwritten`](FindingAction::Overwritten) for GYRO_CONFIG and ACCEL_CONFIG, which init writes, [`Kept`](FindingAction::Kept) otherwise |
+//!
+//! The inspection costs 5 reads. A kept register stays in effect behind the driver's back,
+//! e.g. a sample rate divider the driver assumes to be 0: apply settings or
+//! [`reset_device`](Mpu6050::reset_device) to start from a known state. Every finding emits
+//! [`INIT_FINDINGS`](crate::metrics::INIT_FINDINGS) with an
+//! [`InitFinding`](crate::metrics::MetricEvent::InitFinding) event.
+//!
+//! ```
+//! use mpu6050::init_findings::*;
+//!
+//! assert_eq!(self_test_bits(0b1010_1000), [true, false, true]);
+//! assert_eq!(InitFindings::default().len(), 0);
+//! ```
+
+#[cfg(feature = "driver")]
+use embedded_hal::blocking::i2c::{Write, WriteRead};
+
+use crate::register::Register;
+use crate::registers::{ACCEL_CONFIG, GYRO_CONFIG};
+use crate::Mpu6050;
+#[cfg(feature = "driver")]
+use crate::{
+    device::reset_value,
+    metrics::{self, MetricEvent},
+    register::RegisterValue,
+    registers::{INT_STATUS, PWR_MGMT_1},
+    Mpu6050Error,
+};
+
+/// Configuration registers compared against their reset values, by address
+pub const INSPECTED: [Register; 9] = [
+    Register::SMPLRT_DIV,
+    Register::CONFIG,
+    Register::GYRO_CONFIG,
+    Register::ACCEL_CONFIG,
+    Register::FIFO_EN,
+    Register::INT_PIN_CFG,
+    Register::INT_ENABLE,
+    Register::USER_CTRL,
+    Register::PWR_MGMT_2,
+];
+
+/// upper bound of the findings of one init: one per kind and inspected register
+pub const MAX_FINDINGS: usize = INSPECTED.len() + 3;
+
+/// XG_ST/YG_ST/ZG_ST and XA_ST/YA_ST/ZA_ST, bits 7:5 of both registers
+const SELF_TEST_MASK: u8 = GYRO_CONFIG::XG_ST_FIELD.mask()
+    | GYRO_CONFIG::YG_ST_FIELD.mask()
+    | GYRO_CONFIG::ZG_ST_FIELD.mask();
+
+const _: () = assert!(
+    SELF_TEST_MASK
+        == ACCEL_CONFIG::XA_ST_FIELD.mask()
+            | ACCEL_CONFIG::YA_ST_FIELD.mask()
+            | ACCEL_CONFIG::ZA_ST_FIELD.mask()
+);
+
+/// self-test bits of a GYRO_CONFIG or ACCEL_CONFIG value, X, Y, Z
+pub const fn self_test_bits(value: u8) -> [bool; 3] {
+    [value & 0x80 != 0, value & 0x40 != 0, value & 0x20 != 0]
+}
+
+/// What `init` found
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum FindingKind {
+    /// self-test excitation enabled, X, Y, Z per sensor
+    SelfTestActive {
+        /// GYRO_CONFIG self-test bits
+        gyro: [bool; 3],
+        /// ACCEL_CONFIG self-test bits
+        accel: [bool; 3],
+    },
+    /// PWR_MGMT_1 not in its reset state
+    PowerState {
+        /// PWR_MGMT_1 value found
+        pwr_mgmt_1: u8,
+    },
+    /// the FIFO overflowed before init
+    FifoOverflowLatched {
+        /// INT_STATUS value found
+        int_status: u8,
+    },
+    /// a register of [`INSPECTED`] differs from its reset value
+    NonDefaultRegister {
+        /// register
+        register: Register,
+        /// value found, self-test bits included
+        value: u8,
+    },
+}
+
+/// What `init` did about a finding
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum FindingAction {
+    /// corrected by an extra write before the init sequence
+    Cleared,
+    /// replaced by the init sequence itself
+    Overwritten,
+    /// left as found, reported only
+    Kept,
+}
+
+/// One anomaly and its handling, see the module docs
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct InitFinding {
+    /// what was observed
+    pub kind: FindingKind,
+    /// what was done
+    pub action: FindingAction,
+}
+
+/// Findings of the last init, in inspection order: self-test, power state, FIFO overflow,
+/// then the registers by address
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub struct InitFindings {
+    findings: [Option<InitFinding>; MAX_FINDINGS],
+    len: usize,
+}
+
+impl InitFindings {
+    /// number of findings
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// the chip looked freshly powered on
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// the findings in order
+    pub fn iter(&self) -> impl Iterator<Item = &InitFinding> {
+        self.findings[..self.len].iter().flatten()
+    }
+
+    /// the self-test finding, if any
+    pub fn self_test(&self) -> Option<&InitFinding> {
+        self.iter()
+            .find(|finding| matches!(finding.kind, FindingKind::SelfTestActive { .. }))
+    }
+
+    fn push(&mut self, kind: FindingKind, action: FindingAction) {
+        if self.len < MAX_FINDINGS {
+            self.findings[self.len] = Some(InitFinding { kind, action });
+            self.len += 1;
+        }
+    }
+}
+
+impl<I, D> Mpu6050<I, D> {
+    /// What the last init found and did, see the [module docs](crate::init_findings). Empty
+    /// before the first init
+    pub fn init_findings(&self) -> &InitFindings {
+        &self.init_findings
+    }
+}
+
+#[cfg(feature = "driver")]
+impl<I, D, E> Mpu6050<I, D>
+where
+    I: Write<Error = E> + WriteRead<Error = E>,
+{
+    /// First step of init after verifying WHO_AM_I: reads the chip's state, clears self-test
+    /// bits and a latched FIFO overflow, records the findings
+    pub(crate) fn inspect_at_init(&mut self) -> Result<(), Mpu6050Error<E>> {
+        // SMPLRT_DIV, CONFIG, GYRO_CONFIG, ACCEL_CONFIG
+        let mut config = [0; 4];
+        self.read_registers(Register::SMPLRT_DIV, &mut config)?;
+        let fifo_en = self.read_register(Register::FIFO_EN)?;
+        // INT_PIN_CFG, INT_ENABLE
+        let mut int_config = [0; 2];
+        self.read_registers(Register::INT_PIN_CFG, &mut int_config)?;
+        let int_status = self.read_register(Register::INT_STATUS)?;
+        // USER_CTRL, PWR_MGMT_1, PWR_MGMT_2
+        let mut power = [0; 3];
+        self.read_registers(Register::USER_CTRL, &mut power)?;
+
+        let mut findings = InitFindings::default();
+        let (gyro_config, accel_config) = (config[2], config[3]);
+        if (gyro_config | accel_config) & SELF_TEST_MASK != 0 {
+            if gyro_config & SELF_TEST_MASK != 0 {
+                self.write_fields(RegisterValue::<GYRO_CONFIG>::from_byte(
+                    gyro_config & !SELF_TEST_MASK,
+                ))?;
+            }
+            if accel_config & SELF_TEST_MASK != 0 {
+                self.write_fields(RegisterValue::<ACCEL_CONFIG>::from_byte(
+                    accel_config & !SELF_TEST_MASK,
+                ))?;
+            }
+            let kind = FindingKind::SelfTestActive {
+                gyro: self_test_bits(gyro_config),
+                accel: self_test_bits(accel_config),
+            };
+            findings.push(kind, FindingAction::Cleared);
+        }
+        if Some(power[1]) != reset_value(PWR_MGMT_1::ADDR) {
+            let kind = FindingKind::PowerState {
+                pwr_mgmt_1: power[1],
+            };
+            findings.push(kind, FindingAction::Overwritten);
+        }
+        if RegisterValue::<INT_STATUS>::from_byte(int_status).flag(INT_STATUS::FIFO_OFLOW_INT_FIELD)
+        {
+            self.reset_fifo()?;
+            findings.push(
+                FindingKind::FifoOverflowLatched { int_status },
+                FindingAction::Cleared,
+            );
+        }
+
+        let values = [
+            config[0],
+            config[1],
+            gyro_config,
+            accel_config,
+            fifo_en,
+            int_config[0],
+            int_config[1],
+            power[0],
+            power[2],
+        ];
+        for (register, value) in INSPECTED.into_iter().zip(values) {
+            let (compared, action) = match register {
+                Register::GYRO_CONFIG | Register::ACCEL_CONFIG => {
+                    (value & !SELF_TEST_MASK, FindingAction::Overwritten)
+                }
+                _ => (value, FindingAction::Kept),
+            };
+            // every register resets to 0 but PWR_MGMT_1 and WHO_AM_I
+            if compared != reset_value(register.addr()).unwrap_or(0) {
+                findings.push(FindingKind::NonDefaultRegister { register, value }, action);
+            }
+        }
+
+        for finding in findings.iter() {
+            self.emit_event(metrics::INIT_FINDINGS, MetricEvent::InitFinding(*finding));
+        }
+        self.init_findings = findings;
+        Ok(())
+    }
+}
@@ -0,0 +1,374 @@
+//! Experimental: alternating accelerometer ranges on successive samples.
+//!
+//! One range cannot resolve both the rest signal and a sharp impact: ±2 g resolves 0.06 mg
+//! but clips at 2 g, ±16 g captures the impact at 0.5 mg per LSB. With
+//! [`Mpu6050::enable_range_interleaving`] the driver switches ACCEL_CONFIG FS_SEL between a
+//! `low` and a `high` range every `period` samples, and tags every sample with the range it
+//! was captured at ([`MpuSample::accel_range`]). [`merge_interleaved`] reconstructs a wide
+//! dynamic range reading from a window of tagged samples.
+//!
+//! #### Cadence
+//! The switch happens in the sample read of the sampling loop
+//! ([`run_sampling_loop`](Mpu6050::run_sampling_loop) and the other sample paths), before
+//! reading the first sample of a phase. For `period` 3:
+//!
+//! | sample | 0 | 1 | 2 | 3 | 4 | 5 | 6 |
+//! |:---|:---|:---|:---|:---|:---|:---|:---|
+//! | range | low | low | low | high | high | high | low |
+//! | settling | yes | | | yes | | | yes |
+//!
+//! #### Costs
+//! * A switch is a read-modify-write of ACCEL_CONFIG, [`SWITCH_TRANSACTIONS`] transactions
+//!   every `period` samples, on top of the three reads of a sample.
+//! * The sample after a switch was converted with the old range, it is the
+//!   [`SettleTrigger::Range`] settling sample of the settle table (only the accelerometer,
+//!   the gyro range does not change). It is flagged `settling` and excluded by the merge.
+//!   While interleaving the [`SettlingPolicy`] is [`Flag`](SettlingPolicy::Flag): a re-read
+//!   right after the switch returns the same stale sample. The previous policy is restored
+//!   by [`disable_range_interleaving`](Mpu6050::disable_range_interleaving).
+//! * Of `2 * period` samples `2 * settle` are lost, see [`RangeInterleave::rates`] for the
+//!   usable rate. A `period` without a usable sample per phase is refused.
+//!
+//! Every switch invalidates the FIFO schema like any range change, the mode is for direct
+//! sample reads.
+//!
+//! #### Merge
+//! [`merge_interleaved`] estimates the accelerometer at the centre sample of the window,
+//! `window[window.len() / 2]`, per axis:
+//! 1. samples that are settling or untagged are not used
+//! 2. low range estimate: the centre sample itself if it is a usable low range sample,
+//!    otherwise linear interpolation by sample index between the nearest usable low range
+//!    samples before and after the centre, or the nearest one alone at the window edges
+//! 3. the low range estimate is used unless one of its samples is clipped on the axis,
+//!    at least [`CLIP_FRACTION`] of the full scale
+//! 4. otherwise the high range estimate, built the same way. Without high range samples the
+//!    clipped low range estimate is returned and flagged
+//!
+//! Gyro, temperature and flags come from the centre sample. The accelerometer is tagged with
+//! the worst axis source ([`provenance`](crate::provenance)): measured, interpolated, or held
+//! as many samples old as the held sample is away from the centre. A window of `2 * period + 1`
+//! samples holds usable samples of both ranges on either side of its centre.
+//! ```
+//! use mpu6050::device::AccelRange;
+//! use mpu6050::interleave::{merge_interleaved, AxisSource};
+//! use mpu6050::{MpuSample, Vec3A};
+//!
+//! let tagged = |x: f32, range| {
+//!     MpuSample::new(Vec3A::new(x, 0., 1.), Vec3A::ZERO, 25.).with_accel_range(Some(range))
+//! };
+//! // an impact clips the ±2 g samples, the ±16 g samples around it measure 6 and 8 g
+//! let window = [
+//!     tagged(6., AccelRange::G16),
+//!     tagged(2., AccelRange::G2),
+//!     tagged(8., AccelRange::G16),
+//! ];
+//! let merged = merge_interleaved(&window).unwrap();
+//! assert_eq!(merged.sample.acc().x, 7.);
+//! assert_eq!(merged.sources[0], AxisSource::Interpolated(AccelRange::G16));
+//! // unclipped axes keep the resolution of the low range
+//! assert_eq!(merged.sources[2], AxisSource::Measured(AccelRange::G2));
+//! ```
+
+use core::fmt;
+
+#[cfg(feature = "driver")]
+use embedded_hal::blocking::i2c::{Write, WriteRead};
+
+use crate::aliasing;
+#[cfg(feature = "driver")]
+use crate::config_epoch::ConfigChange;
+use crate::decimal::DecimalVec;
+use crate::device::{AccelRange, SettleTrigger, ACCEL_CONFIG};
+use crate::provenance::{FieldGroup, Provenance};
+use crate::settings::SettingsError;
+use crate::settling::SettlingPolicy;
+use crate::{Mpu6050, Mpu6050Error, MpuSample};
+
+/// Transactions of a range switch, a read-modify-write of ACCEL_CONFIG
+pub const SWITCH_TRANSACTIONS: u32 = 2;
+/// Fraction of the full scale from which a reading counts as clipped
+pub const CLIP_FRACTION: f32 = 0.99;
+
+/// Interleaving configuration
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct RangeInterleave {
+    /// range with the finer resolution
+    pub low: AccelRange,
+    /// range with the larger full scale
+    pub high: AccelRange,
+    /// samples per range before switching
+    pub period: u8,
+}
+
+/// Usable rates of an interleaving configuration
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct InterleaveRates {
+    /// usable samples per second of each range
+    pub per_range_hz: f32,
+    /// usable samples per second of both ranges together
+    pub combined_hz: f32,
+    /// range switches per second
+    pub switches_hz: f32,
+    /// bus transactions per second spent on switching
+    pub switch_transactions_hz: f32,
+}
+
+impl RangeInterleave {
+    /// settling samples after every switch, from the settle table
+    pub fn settle_samples() -> u8 {
+        SettleTrigger::Range.samples().0
+    }
+
+    /// Checks the ranges are ordered and every phase has a usable sample
+    pub fn validate(&self) -> Result<(), SettingsError> {
+        if self.low >= self.high {
+            return Err(SettingsError::InterleaveRanges(self.low, self.high));
+        }
+        let min = Self::settle_samples() + 1;
+        if self.period < min {
+            return Err(SettingsError::InterleavePeriod {
+                period: self.period,
+                min,
+            });
+        }
+        Ok(())
+    }
+
+    /// rates at the output data rate `odr_hz`
+    pub fn rates(&self, odr_hz: f32) -> InterleaveRates {
+        let period = self.period.max(1) as f32;
+        let usable = period - (Self::settle_samples() as f32).min(period);
+        let switches_hz = odr_hz / period;
+        InterleaveRates {
+            per_range_hz: odr_hz * usable / (2. * period),
+            combined_hz: odr_hz * usable / period,
+            switches_hz,
+            switch_transactions_hz: switches_hz * SWITCH_TRANSACTIONS as f32,
+        }
+    }
+}
+
+/// Where a merged axis value comes from
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum AxisSource {
+    /// the centre sample, captured at the range
+    Measured(AccelRange),
+    /// interpolated between samples of the range around the centre
+    Interpolated(AccelRange),
+    /// the nearest sample of the range, all others are on the same side of the centre
+    Held(AccelRange),
+}
+
+/// Wide dynamic range estimate, see the module docs
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct MergedSample {
+    /// the centre sample with the merged accelerometer reading, untagged
+    pub sample: MpuSample,
+    /// source of each accelerometer axis
+    pub sources: [AxisSource; 3],
+    /// an axis is clipped in every range of the window
+    pub clipped: bool,
+}
+
+impl fmt::Display for MergedSample {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} g from {:?}",
+            DecimalVec(self.sample.acc()),
+            self.sources
+        )?;
+        if self.clipped {
+            f.write_str(", clipped")?;
+        }
+        Ok(())
+    }
+}
+
+/// Merges a window of tagged samples into an estimate for its centre sample, see the module
+/// docs. None if the window holds no usable sample
+pub fn merge_interleaved(window: &[MpuSample]) -> Option<MergedSample> {
+    let usable = |s: &MpuSample| (!s.settling()).then_some(s.accel_range()).flatten();
+    let mut ranges = window.iter().filter_map(usable);
+    let first = ranges.next()?;
+    let (low, high) = ranges.fold((first, first), |(low, high), r| (low.min(r), high.max(r)));
+    let centre = window.len() / 2;
+
+    let mut acc = [0.; 3];
+    let mut sources = [AxisSource::Held(low); 3];
+    let mut acc_provenance = Provenance::Measured;
+    let mut clipped = false;
+    for axis in 0..3 {
+        let estimate = |range: AccelRange| estimate(window, centre, range, axis);
+        let (value, source, provenance, axis_clipped) = match estimate(low) {
+            Some((value, source, provenance, false)) => (value, source, provenance, false),
+            low_estimate => match estimate(high).filter(|_| high != low) {
+                Some(high_estimate) => high_estimate,
+                None => low_estimate?,
+            },
+        };
+        acc[axis] = value;
+        sources[axis] = source;
+        acc_provenance = acc_provenance.worst(provenance);
+        clipped |= axis_clipped;
+    }
+    let centre_sample = window[centre];
+    let provenance = centre_sample
+        .provenance()
+        .with(FieldGroup::Acc, acc_provenance);
+    let sample = centre_sample
+        .with_acc(acc.into())
+        .with_accel_range(None)
+        .with_provenance(provenance);
+    Some(MergedSample {
+        sample,
+        sources,
+        clipped,
+    })
+}
+
+/// estimate of `axis` at `centre` from the usable samples of `range`, its provenance and
+/// whether a sample used is clipped
+fn estimate(
+    window: &[MpuSample],
+    centre: usize,
+    range: AccelRange,
+    axis: usize,
+) -> Option<(f32, AxisSource, Provenance, bool)> {
+    let matches = |s: &MpuSample| !s.settling() && s.accel_range() == Some(range);
+    let limit = CLIP_FRACTION * range.full_scale_g();
+    let value = |i: usize| {
+        let v = window[i].acc[axis];
+        (v, v.abs() >= limit)
+    };
+    let tag = |i: usize| window[i].provenance().get(FieldGroup::Acc);
+    if matches(&window[centre]) {
+        let (v, clipped) = value(centre);
+        return Some((v, AxisSource::Measured(range), tag(centre), clipped));
+    }
+    let before = window[..centre].iter().rposition(matches);
+    let after = window[centre + 1..]
+        .iter()
+        .position(matches)
+        .map(|i| centre + 1 + i);
+    match (before, after) {
+        (Some(b), Some(a)) => {
+            let ((vb, cb), (va, ca)) = (value(b), value(a));
+            let t = (centre - b) as f32 / (a - b) as f32;
+            Some((
+                vb + (va - vb) * t,
+                AxisSource::Interpolated(range),
+                tag(b).worst(tag(a)).worst(Provenance::Interpolated),
+                cb || ca,
+            ))
+        }
+        (Some(i), None) | (None, Some(i)) => {
+            let (v, clipped) = value(i);
+            let held = Provenance::held(centre.abs_diff(i) as u32);
+            Some((v, AxisSource::Held(range), tag(i).worst(held), clipped))
+        }
+        (None, None) => None,
+    }
+}
+
+/// Interleaving progress, no bus access
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct InterleaveState {
+    /// configuration
+    pub config: RangeInterleave,
+    /// the high range is active
+    pub at_high: bool,
+    /// samples read in the current phase
+    pub in_phase: u8,
+    /// policy restored when interleaving stops
+    pub restore_policy: SettlingPolicy,
+}
+
+#[cfg(feature = "driver")]
+impl<I, D, E> Mpu6050<I, D>
+where
+    I: Write<Error = E> + WriteRead<Error = E>,
+{
+    /// Starts interleaving at the low range, the next sample is the first of the low phase.
+    /// Invalid configurations, and configurations while [impact ranging](crate::impact), are
+    /// refused before touching the bus
+    pub fn enable_range_interleaving(
+        &mut self,
+        config: RangeInterleave,
+    ) -> Result<(), Mpu6050Error<E>> {
+        if self.impact.is_some() {
+            return Err(Mpu6050Error::InvalidSettings(
+                SettingsError::RangeModesExclusive,
+            ));
+        }
+        config.validate().map_err(Mpu6050Error::InvalidSettings)?;
+        self.switch_accel_range(config.low)?;
+        let restore_policy = match self.interleave {
+            Some(state) => state.restore_policy,
+            None => self.settling_policy,
+        };
+        self.interleave = Some(InterleaveState {
+            config,
+            at_high: false,
+            in_phase: 0,
+            restore_policy,
+        });
+        self.settling_policy = SettlingPolicy::Flag;
+        Ok(())
+    }
+
+    /// Stops interleaving and sets `range`, restores the settling policy
+    pub fn disable_range_interleaving(&mut self, range: AccelRange) -> Result<(), Mpu6050Error<E>> {
+        if let Some(state) = self.interleave.take() {
+            self.settling_policy = state.restore_policy;
+        }
+        self.set_accel_range(range)
+    }
+
+    /// switches at the end of a phase, returns the range of the sample about to be read.
+    /// None if not interleaving
+    #[cfg(not(feature = "minimal-pipeline"))]
+    pub(crate) fn step_interleave(&mut self) -> Result<Option<AccelRange>, Mpu6050Error<E>> {
+        let Some(mut state) = self.interleave else {
+            return Ok(None);
+        };
+        if state.in_phase >= state.config.period {
+            let next = if state.at_high {
+                state.config.low
+            } else {
+                state.config.high
+            };
+            self.switch_accel_range(next)?;
+            state.at_high = !state.at_high;
+            state.in_phase = 0;
+        }
+        state.in_phase += 1;
+        self.interleave = Some(state);
+        Ok(Some(self.accel_range))
+    }
+
+    /// [`set_accel_range`](Self::set_accel_range) arming the accel countdown only
+    pub(crate) fn switch_accel_range(&mut self, range: AccelRange) -> Result<(), Mpu6050Error<E>> {
+        self.write_field(ACCEL_CONFIG::FS_SEL_FIELD, range)?;
+        self.accel_range = range;
+        self.acc_scale.nominal = range.sensitivity();
+        self.bump_epoch(ConfigChange::AccelRange);
+        self.synced.accel_range = Some(self.io_stats.transactions);
+        self.settle.acc = self.settle.acc.max(RangeInterleave::settle_samples());
+        Ok(())
+    }
+}
+
+impl<I, D> Mpu6050<I, D> {
+    /// interleaving progress, None if not interleaving
+    pub fn range_interleave(&self) -> Option<InterleaveState> {
+        self.interleave
+    }
+
+    /// usable rates of the active interleaving at the configured output data rate, None if
+    /// not interleaving
+    pub fn range_interleave_rates(&self) -> Option<InterleaveRates> {
+        let odr_hz = aliasing::assess(self.dlpf_cfg, self.sample_rate_div, false).odr_hz;
+        self.interleave.map(|state| state.config.rates(odr_hz))
+    }
+}
@@ -0,0 +1,290 @@
+//! Short timestamped history of samples, interpolated to arbitrary timestamps.
+//!
+//! Meant for fusing with sensors sampled on a different clock (GPS, camera): push every IMU
+//! sample with its timestamp, query [`InterpolatingBuffer::sample_at`] with the other sensor's
+//! timestamp. Accel, gyro and temperature are interpolated linearly per axis, orientations
+//! (when pushed along) with slerp. Results between entries are tagged
+//! [`Interpolated`](crate::provenance::Provenance::Interpolated), extrapolated ones
+//! [`Synthesized`](crate::provenance::Provenance::Synthesized), see
+//! [`provenance`](crate::provenance).
+//!
+//! Timestamps must be strictly increasing. A repeated timestamp (stalled clock) or one older
+//! than the newest entry is rejected, the buffer is left unchanged.
+
+use core::fmt;
+
+use glam::{Quat, Vec3A};
+
+#[cfg(feature = "driver")]
+use embedded_hal::blocking::i2c::{Write, WriteRead};
+
+use crate::euler::{self, AerospaceYpr, XyzRpy};
+use crate::provenance::SampleProvenance;
+use crate::time_math::{elapsed_us, offset_us};
+use crate::MpuSample;
+#[cfg(feature = "driver")]
+use crate::{Mpu6050, Mpu6050Error};
+
+/// Sample with timestamp and optional orientation estimate
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct TimedSample {
+    /// timestamp in µs
+    pub t_us: u64,
+    /// the sample
+    pub sample: MpuSample,
+    /// orientation estimate at `t_us`, if a fusion filter runs
+    pub orientation: Option<Quat>,
+}
+
+/// Result of [`InterpolatingBuffer::sample_at`]
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct InterpolatedSample {
+    /// accelerometer reading in g
+    pub acc: Vec3A,
+    /// gyro reading in rad/s
+    pub gyro: Vec3A,
+    /// temperature in degrees celsius
+    pub temp: f32,
+    /// orientation, None unless both neighbours carry one
+    pub orientation: Option<Quat>,
+    /// the timestamp was outside the buffered span, within the extrapolation window
+    pub extrapolated: bool,
+    /// origin of the values, see [`provenance`](crate::provenance)
+    pub provenance: SampleProvenance,
+}
+
+impl InterpolatedSample {
+    /// orientation in aerospace yaw-pitch-roll, see [`euler`](crate::euler)
+    pub fn orientation_ypr(&self) -> Option<AerospaceYpr> {
+        self.orientation.map(euler::to_aerospace_ypr)
+    }
+
+    /// orientation in XYZ roll-pitch-yaw, see [`euler`](crate::euler)
+    pub fn orientation_xyz_rpy(&self) -> Option<XyzRpy> {
+        self.orientation.map(euler::to_xyz_rpy)
+    }
+
+    fn exact(entry: &TimedSample) -> Self {
+        Self {
+            acc: entry.sample.acc,
+            gyro: entry.sample.gyro,
+            temp: entry.sample.temp,
+            orientation: entry.orientation,
+            extrapolated: false,
+            provenance: entry.sample.provenance(),
+        }
+    }
+}
+
+/// Rejected timestamp
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum TimestampError {
+    /// same timestamp as the newest entry, the clock did not advance
+    Duplicate {
+        /// timestamp in µs
+        t_us: u64,
+    },
+    /// older than the newest entry
+    OutOfOrder {
+        /// newest timestamp in the buffer
+        newest_us: u64,
+        /// rejected timestamp
+        t_us: u64,
+    },
+}
+
+impl fmt::Display for TimestampError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TimestampError::Duplicate { t_us } => write!(f, "duplicate timestamp {} us", t_us),
+            TimestampError::OutOfOrder { newest_us, t_us } => write!(
+                f,
+                "timestamp {} us older than newest entry {} us",
+                t_us, newest_us
+            ),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for TimestampError {}
+
+/// Interpolation between `a` and `b` at `t_us`, tagged interpolated. `t_us` outside of
+/// `a.t_us..=b.t_us` extrapolates along the same line but is neither flagged nor tagged
+/// synthesized, `a.t_us == b.t_us` returns `a`
+pub fn interpolate(a: &TimedSample, b: &TimedSample, t_us: u64) -> InterpolatedSample {
+    if t_us == a.t_us || a.t_us == b.t_us {
+        return InterpolatedSample::exact(a);
+    }
+    if t_us == b.t_us {
+        return InterpolatedSample::exact(b);
+    }
+    let span = offset_us(a.t_us, b.t_us) as f64;
+    let alpha = (offset_us(a.t_us, t_us) as f64 / span) as f32;
+    let (sa, sb) = (&a.sample, &b.sample);
+    InterpolatedSample {
+        acc: sa.acc + (sb.acc - sa.acc) * alpha,
+        gyro: sa.gyro + (sb.gyro - sa.gyro) * alpha,
+        temp: sa.temp + (sb.temp - sa.temp) * alpha,
+        orientation: match (a.orientation, b.orientation) {
+            (Some(qa), Some(qb)) => Some(qa.slerp(qb, alpha)),
+            _ => None,
+        },
+        extrapolated: false,
+        provenance: sa.provenance().interpolated(sb.provenance()),
+    }
+}
+
+/// Ring buffer of the last `N` timestamped samples
+#[derive(Copy, Clone, Debug)]
+pub struct InterpolatingBuffer<const N: usize> {
+    entries: [TimedSample; N],
+    /// index of the oldest entry
+    head: usize,
+    len: usize,
+    extrapolation_us: u64,
+}
+
+impl<const N: usize> Default for InterpolatingBuffer<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const N: usize> InterpolatingBuffer<N> {
+    /// empty buffer, no extrapolation
+    pub fn new() -> Self {
+        Self {
+            entries: [TimedSample::default(); N],
+            head: 0,
+            len: 0,
+            extrapolation_us: 0,
+        }
+    }
+
+    /// allow queries up to `window_us` before the oldest or after the newest entry
+    pub fn set_extrapolation_window(&mut self, window_us: u64) {
+        self.extrapolation_us = window_us;
+    }
+
+    /// number of buffered samples
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// true if no sample is buffered
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// drop all samples
+    pub fn clear(&mut self) {
+        self.head = 0;
+        self.len = 0;
+    }
+
+    /// entry `i`, 0 is the oldest
+    fn get(&self, i: usize) -> &TimedSample {
+        &self.entries[(self.head + i) % N]
+    }
+
+    /// oldest and newest timestamp
+    pub fn span(&self) -> Option<(u64, u64)> {
+        if self.is_empty() {
+            return None;
+        }
+        Some((self.get(0).t_us, self.get(self.len - 1).t_us))
+    }
+
+    /// Checks whether a sample at `t_us` would be accepted
+    pub fn check_timestamp(&self, t_us: u64) -> Result<(), TimestampError> {
+        match self.span() {
+            Some((_, newest_us)) if t_us == newest_us => Err(TimestampError::Duplicate { t_us }),
+            Some((_, newest_us)) if t_us < newest_us => {
+                Err(TimestampError::OutOfOrder { newest_us, t_us })
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Appends an entry, dropping the oldest one when full. A buffer with `N == 0` stays empty
+    pub fn push(&mut self, entry: TimedSample) -> Result<(), TimestampError> {
+        self.check_timestamp(entry.t_us)?;
+        if N == 0 {
+            return Ok(());
+        }
+        if self.len == N {
+            self.entries[self.head] = entry;
+            self.head = (self.head + 1) % N;
+        } else {
+            self.entries[(self.head + self.len) % N] = entry;
+            self.len += 1;
+        }
+        Ok(())
+    }
+
+    /// Sample at `t_us`. A query exactly on an entry returns that entry unchanged. None if
+    /// `t_us` is outside the buffered span plus the extrapolation window
+    pub fn sample_at(&self, t_us: u64) -> Option<InterpolatedSample> {
+        let (oldest_us, newest_us) = self.span()?;
+        let last = self.len - 1;
+        let (a, b, extrapolated) = if t_us < oldest_us {
+            if elapsed_us(t_us, oldest_us) > self.extrapolation_us {
+                return None;
+            }
+            (self.get(0), self.get(last.min(1)), true)
+        } else if t_us > newest_us {
+            if elapsed_us(newest_us, t_us) > self.extrapolation_us {
+                return None;
+            }
+            (self.get(last.saturating_sub(1)), self.get(last), true)
+        } else {
+            // first entry not older than t_us, exists since t_us <= newest
+            let i = (0..self.len).find(|i| self.get(*i).t_us >= t_us)?;
+            let b = self.get(i);
+            if b.t_us == t_us {
+                return Some(InterpolatedSample::exact(b));
+            }
+            (self.get(i - 1), b, false)
+        };
+        // a single entry extrapolates as a constant
+        let mut sample = if a.t_us == b.t_us {
+            InterpolatedSample::exact(a)
+        } else {
+            interpolate(a, b, t_us)
+        };
+        if extrapolated {
+            sample.extrapolated = true;
+            sample.provenance = sample.provenance.extrapolated();
+        }
+        Some(sample)
+    }
+}
+
+#[cfg(feature = "driver")]
+impl<I, D, E> Mpu6050<I, D>
+where
+    I: Write<Error = E> + WriteRead<Error = E>,
+{
+    /// Reads a sample and appends it to `buffer` with timestamp `t_us`. The timestamp is
+    /// checked first, a rejected one costs no bus transaction
+    pub fn sample_into_interp_buffer<const N: usize>(
+        &mut self,
+        buffer: &mut InterpolatingBuffer<N>,
+        t_us: u64,
+        orientation: Option<Quat>,
+    ) -> Result<MpuSample, Mpu6050Error<E>> {
+        buffer
+            .check_timestamp(t_us)
+            .map_err(Mpu6050Error::InvalidTimestamp)?;
+        let sample = self.read_sample()?;
+        buffer
+            .push(TimedSample {
+                t_us,
+                sample,
+                orientation,
+            })
+            .map_err(Mpu6050Error::InvalidTimestamp)?;
+        Ok(sample)
+    }
+}
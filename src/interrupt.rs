@@ -0,0 +1,353 @@
+//! Interrupt sources and edge detection on INT_STATUS.
+//!
+//! INT_STATUS is cleared by reading it, a bit set in a read means the source latched since the
+//! previous read. A source whose condition persists (motion continues, data keeps arriving)
+//! latches again right after the clear and reads as set on every poll. [`InterruptEdgeTracker`]
+//! reports a source as `fired` when it is set now and was not set in the previous read, the
+//! raw set bits are `asserted`. A source that fired and went away between two polls is still
+//! reported, its latched bit was set at read time.
+//!
+//! Any other read of INT_STATUS (e.g. [`get_int_status`](Mpu6050::get_int_status) or
+//! `get_motion_detected`) clears it without the tracker seeing the value, mixing both loses
+//! events.
+//!
+//! #### Configuration
+//! [`set_int_enabled`](Mpu6050::set_int_enabled) switches a source in INT_ENABLE, which has
+//! the bit layout of INT_STATUS. The motion trigger of
+//! [`setup_motion_detection`](Mpu6050::setup_motion_detection) is a
+//! [`MotionDetectionConfig`], the INT pin it sets is
+//! [`IntPinConfig::MOTION`](crate::board::IntPinConfig::MOTION) adapted to the board:
+//! [`configure_int_pin`](Mpu6050::configure_int_pin) after it sets another one.
+//! ```
+//! use embedded_hal::blocking::i2c::{Write, WriteRead};
+//! use mpu6050::board::IntPinConfig;
+//! use mpu6050::interrupt::{InterruptSource, MotionDetectionConfig};
+//! use mpu6050::{Mpu6050, Mpu6050Error};
+//!
+//! /// motion on an INT line into a GPIO with a pull-up, held until INT_STATUS is read
+//! fn wire_motion<I, E>(mpu: &mut Mpu6050<I>) -> Result<(), Mpu6050Error<E>>
+//! where
+//!     I: Write<Error = E> + WriteRead<Error = E>,
+//! {
+//!     mpu.setup_motion_detection(MotionDetectionConfig { threshold: 20, duration: 5 })?;
+//!     mpu.configure_int_pin(IntPinConfig::MOTION.open_drain_active_low())?;
+//!     mpu.set_int_enabled(InterruptSource::DataReady, false)
+//! }
+//! ```
+//!
+//! #### Motion event timestamps
+//! [`Mpu6050::take_motion_event`] estimates when the motion that raised the interrupt
+//! happened, from the host time of the INT_STATUS read. Subtracted are, itemized in
+//! [`MotionCompensation`]: the MOT_DUR duration the threshold had to be exceeded for, the
+//! accel DLPF group delay and half an accel sample interval (the mean quantization delay).
+//! The estimate is only as good as the time between the interrupt firing and the status read:
+//! interrupt latency and scheduling on the host are not known to the driver and NOT
+//! compensated, read the status right after the interrupt and take the timestamp then. The
+//! residual uncertainty of the compensated terms is ±half a sample interval (0.5 ms).
+
+#[cfg(feature = "driver")]
+use embedded_hal::blocking::i2c::{Write, WriteRead};
+
+use crate::device::{DLPF_DELAY_MS, INT_ENABLE, INT_STATUS};
+use crate::metrics::{self, MetricEvent};
+use crate::register::{Field, Register};
+use crate::time_math::before_us;
+use crate::{Mpu6050, Mpu6050Error};
+
+/// Interrupt source in INT_STATUS / INT_ENABLE
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum InterruptSource {
+    /// free fall detected
+    FreeFall,
+    /// motion detected
+    Motion,
+    /// zero motion detected
+    ZeroMotion,
+    /// FIFO overflowed
+    FifoOverflow,
+    /// i2c master interrupt
+    I2cMaster,
+    /// new sample available
+    DataReady,
+}
+
+impl InterruptSource {
+    /// all sources, index matches [`InterruptSource::index`]
+    pub const ALL: [InterruptSource; 6] = [
+        InterruptSource::FreeFall,
+        InterruptSource::Motion,
+        InterruptSource::ZeroMotion,
+        InterruptSource::FifoOverflow,
+        InterruptSource::I2cMaster,
+        InterruptSource::DataReady,
+    ];
+
+    /// bit in INT_STATUS
+    pub const fn bit(self) -> u8 {
+        match self {
+            InterruptSource::FreeFall => INT_STATUS::FF_INT,
+            InterruptSource::Motion => INT_STATUS::MOT_INT,
+            InterruptSource::ZeroMotion => INT_STATUS::ZMOT_INT,
+            InterruptSource::FifoOverflow => INT_STATUS::FIFO_OFLOW_INT,
+            InterruptSource::I2cMaster => INT_STATUS::I2C_MSF_INT,
+            InterruptSource::DataReady => INT_STATUS::DATA_RDY_INT,
+        }
+    }
+
+    /// enable field in INT_ENABLE
+    pub const fn enable_field(self) -> Field<INT_ENABLE> {
+        match self {
+            InterruptSource::FreeFall => INT_ENABLE::FF_EN_FIELD,
+            InterruptSource::Motion => INT_ENABLE::MOT_EN_FIELD,
+            InterruptSource::ZeroMotion => INT_ENABLE::ZMOT_EN_FIELD,
+            InterruptSource::FifoOverflow => INT_ENABLE::FIFO_OFLOW_EN_FIELD,
+            InterruptSource::I2cMaster => INT_ENABLE::I2C_MST_INT_EN_FIELD,
+            InterruptSource::DataReady => INT_ENABLE::DATA_RDY_EN_FIELD,
+        }
+    }
+
+    /// position in [`InterruptSource::ALL`]
+    pub const fn index(self) -> usize {
+        self as usize
+    }
+
+    const fn mask(self) -> u8 {
+        1 << self.bit()
+    }
+}
+
+/// Set of interrupt sources, INT_STATUS bit layout
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub struct InterruptSet(u8);
+
+impl InterruptSet {
+    /// no source
+    pub const EMPTY: InterruptSet = InterruptSet(0);
+
+    /// from an INT_STATUS value, bits without a known source are dropped
+    pub fn from_status(status: u8) -> Self {
+        let known = InterruptSource::ALL
+            .iter()
+            .fold(0, |mask, source| mask | source.mask());
+        Self(status & known)
+    }
+
+    /// INT_STATUS bit layout
+    pub fn bits(self) -> u8 {
+        self.0
+    }
+
+    /// true if `source` is part of the set
+    pub fn contains(self, source: InterruptSource) -> bool {
+        self.0 & source.mask() != 0
+    }
+
+    /// true if no source is part of the set
+    pub fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+
+    /// sources in the set, in [`InterruptSource::ALL`] order
+    pub fn iter(self) -> impl Iterator<Item = InterruptSource> {
+        InterruptSource::ALL
+            .into_iter()
+            .filter(move |source| self.contains(*source))
+    }
+}
+
+/// Result of one INT_STATUS poll
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub struct InterruptEvents {
+    /// sources set now but not in the previous poll (rising edges)
+    pub fired: InterruptSet,
+    /// sources set now
+    pub asserted: InterruptSet,
+}
+
+/// Edge detection and per source event counting, no bus access
+#[derive(Copy, Clone, Debug, Default)]
+pub struct InterruptEdgeTracker {
+    previous: InterruptSet,
+    counts: [u32; InterruptSource::ALL.len()],
+}
+
+impl InterruptEdgeTracker {
+    /// new tracker, all sources considered clear
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Feed an INT_STATUS value read from the device
+    pub fn update(&mut self, status: u8) -> InterruptEvents {
+        let asserted = InterruptSet::from_status(status);
+        let fired = InterruptSet(asserted.0 & !self.previous.0);
+        for source in fired.iter() {
+            let count = &mut self.counts[source.index()];
+            *count = count.saturating_add(1);
+        }
+        self.previous = asserted;
+        InterruptEvents { fired, asserted }
+    }
+
+    /// rising edges of `source` seen so far
+    pub fn count(&self, source: InterruptSource) -> u32 {
+        self.counts[source.index()]
+    }
+
+    /// reset counters and previous state
+    pub fn reset(&mut self) {
+        *self = Self::default();
+    }
+}
+
+/// Output rate of the accelerometer and the motion detection counter in Hz
+pub const ACCEL_OUTPUT_RATE_HZ: u32 = 1000;
+
+/// Motion trigger of [`setup_motion_detection`](Mpu6050::setup_motion_detection)
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct MotionDetectionConfig {
+    /// MOT_THR, compared against the high pass filtered accelerometer samples
+    pub threshold: u8,
+    /// MOT_DUR, how long the threshold has to be exceeded, 1 ms per LSB at the 1 kHz accel
+    /// rate
+    pub duration: u8,
+}
+
+impl MotionDetectionConfig {
+    /// threshold 10, 40 ms
+    pub const DEFAULT: Self = Self {
+        threshold: 10,
+        duration: 40,
+    };
+}
+
+impl Default for MotionDetectionConfig {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+/// Delays between a motion and the INT_STATUS bit latching, in µs
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub struct MotionCompensation {
+    /// MOT_DUR, 1 ms per LSB at the 1 kHz accel rate
+    pub duration_us: u32,
+    /// accel DLPF group delay of the active DLPF_CFG
+    pub filter_delay_us: u32,
+    /// half an accel sample interval
+    pub half_sample_us: u32,
+}
+
+impl MotionCompensation {
+    /// compensation for a MOT_DUR register value and DLPF_CFG
+    pub fn new(mot_dur: u8, dlpf_cfg: u8) -> Self {
+        let (accel_delay_ms, _) = DLPF_DELAY_MS[(dlpf_cfg & 0x07) as usize];
+        Self {
+            duration_us: mot_dur as u32 * 1_000_000 / ACCEL_OUTPUT_RATE_HZ,
+            filter_delay_us: (accel_delay_ms * 1000.) as u32,
+            half_sample_us: 500_000 / ACCEL_OUTPUT_RATE_HZ,
+        }
+    }
+
+    /// sum of all terms
+    pub fn total_us(&self) -> u32 {
+        self.duration_us + self.filter_delay_us + self.half_sample_us
+    }
+
+    /// estimated motion time for a status read at `status_read_us`, saturating at 0
+    pub fn apply(&self, status_read_us: u64) -> u64 {
+        before_us(status_read_us, self.total_us() as u64)
+    }
+}
+
+/// Motion interrupt with an estimate of when the motion happened
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct MotionEvent {
+    /// host time of the INT_STATUS read in µs, as passed in
+    pub status_read_us: u64,
+    /// terms subtracted from `status_read_us`
+    pub compensation: MotionCompensation,
+    /// estimated time of the motion in µs
+    pub estimated_us: u64,
+}
+
+impl MotionEvent {
+    /// event for a status read at `status_read_us`
+    pub fn new(status_read_us: u64, compensation: MotionCompensation) -> Self {
+        Self {
+            status_read_us,
+            compensation,
+            estimated_us: compensation.apply(status_read_us),
+        }
+    }
+}
+
+#[cfg(feature = "driver")]
+impl<I, D, E> Mpu6050<I, D>
+where
+    I: Write<Error = E> + WriteRead<Error = E>,
+{
+    /// Reads INT_STATUS once and reports new and currently asserted interrupt sources
+    pub fn poll_interrupt_events(&mut self) -> Result<InterruptEvents, Mpu6050Error<E>> {
+        let status = self.read_register(Register::INT_STATUS)?;
+        let events = self.interrupt_tracker.update(status);
+        for source in events.fired.iter() {
+            self.emit_event(
+                metrics::interrupt_counter(source),
+                MetricEvent::Interrupt(source),
+            );
+        }
+        Ok(events)
+    }
+
+    /// Polls INT_STATUS like [`poll_interrupt_events`](Self::poll_interrupt_events) and
+    /// returns a timestamped event if a motion interrupt fired since the last poll.
+    /// `status_read_us` is the host time taken right before this call. MOT_DUR is read back
+    /// only when an event fired
+    pub fn take_motion_event(
+        &mut self,
+        status_read_us: u64,
+    ) -> Result<Option<MotionEvent>, Mpu6050Error<E>> {
+        let events = self.poll_interrupt_events()?;
+        if !events.fired.contains(InterruptSource::Motion) {
+            return Ok(None);
+        }
+        let mot_dur = self.read_register(Register::MOT_DUR)?;
+        let compensation = MotionCompensation::new(mot_dur, self.dlpf_cfg);
+        Ok(Some(MotionEvent::new(status_read_us, compensation)))
+    }
+
+    /// Reads INT_STATUS once and decodes every source, clearing the latched bits. The
+    /// edge tracker does not see the value, see the [module docs](self)
+    pub fn get_int_status(&mut self) -> Result<InterruptSet, Mpu6050Error<E>> {
+        let status = self.read_register(Register::INT_STATUS)?;
+
+        Ok(InterruptSet::from_status(status))
+    }
+
+    /// set whether `source` drives the INT pin and latches in INT_STATUS (INT_ENABLE)
+    pub fn set_int_enabled(
+        &mut self,
+        source: InterruptSource,
+        enabled: bool,
+    ) -> Result<(), Mpu6050Error<E>> {
+        self.write_field(source.enable_field(), enabled)
+    }
+
+    /// get the sources enabled in INT_ENABLE
+    pub fn get_int_enabled(&mut self) -> Result<InterruptSet, Mpu6050Error<E>> {
+        let enabled = self.read_register(Register::INT_ENABLE)?;
+
+        Ok(InterruptSet::from_status(enabled))
+    }
+
+    /// edge tracker fed by [`Mpu6050::poll_interrupt_events`]
+    pub fn interrupt_tracker(&self) -> &InterruptEdgeTracker {
+        &self.interrupt_tracker
+    }
+
+    /// reset interrupt event counters and edge state
+    pub fn reset_interrupt_tracker(&mut self) {
+        self.interrupt_tracker.reset();
+    }
+}
@@ -0,0 +1,258 @@
+//! Lever-arm compensation of the accelerometer for a sensor mounted off the body reference
+//! point.
+//!
+//! A rigid body turning about its reference point accelerates every other point of it: the
+//! accelerometer at `r` from the reference point reads the specific force of the reference
+//! point plus
+//!
+//! ```text
+//! ω × (ω × r) + α × r
+//! ```
+//!
+//! the centripetal term of the rotation rate ω and the tangential term of the angular
+//! acceleration α. [`rigid_body_accel_g`] is that sum in g, [`compensate`] takes it off a
+//! reading. Both are pure functions of the vectors; with `r` zero [`compensate`] returns
+//! the reading bit for bit.
+//!
+//! `r` is in meters, in the axes of the samples: the driver applies no axis map, so those
+//! are the chip axes, see [`axis_map`](crate::axis_map). [`STANDARD_GRAVITY`] converts
+//! m/s² into g.
+//!
+//! #### Angular acceleration
+//! The gyro does not measure α. [`LeverArmCompensator`] takes the finite difference of
+//! consecutive gyro readings one sample interval `dt` apart and low passes it with a first
+//! order filter at [`alpha_cutoff_hz`](LeverArmCompensation::alpha_cutoff_hz), gain
+//! `1 − exp(−2π·fc·dt)` per sample. The difference is a differentiator: white gyro noise of
+//! density `n` in rad/s/√Hz, cut off sharply at `fc`, comes out with a deviation of
+//!
+//! ```text
+//! σ_α = n · 2π · √(fc³ / 3)
+//! ```
+//!
+//! in rad/s², 0.01 rad/s² at the MPU6050's 0.005 °/s/√Hz and the default
+//! [`DEFAULT_ALPHA_CUTOFF_HZ`], 0.1 mg per 10 cm of lever arm. The first order filter rolls
+//! off slower and passes a few times that, up to the gyro DLPF bandwidth, to be measured
+//! rather than derived for a given configuration. The filtered value is clamped
+//! to ±[`max_alpha`](LeverArmCompensation::max_alpha), [`DEFAULT_MAX_ALPHA`] by default, so
+//! a glitch does not kick the output. A cutoff of zero drops the α term, leaving the
+//! centripetal term alone, which needs no history.
+//!
+//! #### Correction
+//! [`Mpu6050::set_lever_arm`] sets `r`, [`Mpu6050::set_lever_arm_compensation`] opts the
+//! combined sample path ([`run_sampling_loop`](Mpu6050::run_sampling_loop),
+//! [`sample_into_interp_buffer`](Mpu6050::sample_into_interp_buffer)) into the correction,
+//! after the [`skew`](crate::skew) correction so that ω and the accelerometer refer to one
+//! instant when that is on. The corrected sample carries the subtracted term in
+//! [`lever_arm_correction`](crate::MpuSample::lever_arm_correction) and the reading before
+//! the correction in [`uncompensated_acc`](crate::MpuSample::uncompensated_acc); its
+//! provenance is unchanged, the term is computed from the sample's own measurements. The
+//! fusion filters read [`acc`](crate::MpuSample::acc), the corrected value.
+//!
+//! The previous gyro reading is assumed one
+//! [`nominal_sample_interval_us`](Mpu6050::nominal_sample_interval_us) old. The first
+//! sample and the one after an overrun of the sampling loop have α zero. Settling samples
+//! go out uncorrected and restart the difference.
+//!
+//! #### Limits
+//! The single sensor reads and FIFO frames are not corrected, nor are the logged or packed
+//! formats told about the term. The model is a rigid mount: a flexing bracket adds
+//! accelerations no lever arm describes.
+//!
+//! ```
+//! use mpu6050::lever_arm::*;
+//! use mpu6050::Vec3A;
+//!
+//! // 10 cm off the axis of a 2π rad/s turn about z: 0.4 g toward the axis
+//! let r = Vec3A::new(0.1, 0., 0.);
+//! let gyro = Vec3A::new(0., 0., core::f32::consts::TAU);
+//! let term = rigid_body_accel_g(r, gyro, Vec3A::ZERO);
+//! assert!((term.x + 0.4026).abs() < 1e-4);
+//!
+//! let measured = Vec3A::Z + term;
+//! assert!((compensate(measured, r, gyro, Vec3A::ZERO) - Vec3A::Z).length() < 1e-6);
+//! assert_eq!(compensate(measured, Vec3A::ZERO, gyro, Vec3A::ZERO), measured);
+//! ```
+
+use glam::Vec3A;
+
+#[cfg(not(feature = "std"))]
+use crate::math::FloatExt;
+use crate::{Mpu6050, MpuSample};
+
+/// Standard gravity in m/s², the g of the accelerometer readings
+pub const STANDARD_GRAVITY: f32 = 9.80665;
+
+/// Default cutoff of the angular acceleration filter in Hz
+pub const DEFAULT_ALPHA_CUTOFF_HZ: f32 = 10.;
+
+/// Default bound of the angular acceleration in rad/s²
+pub const DEFAULT_MAX_ALPHA: f32 = 200.;
+
+/// `ω × (ω × r) + α × r` in g: the acceleration of the point `r` meters from the reference
+/// point of a rigid body turning at `gyro` rad/s and `alpha` rad/s²
+pub fn rigid_body_accel_g(r: Vec3A, gyro: Vec3A, alpha: Vec3A) -> Vec3A {
+    (gyro.cross(gyro.cross(r)) + alpha.cross(r)) / STANDARD_GRAVITY
+}
+
+/// `acc` in g with the acceleration of the lever arm `r` taken off, `acc` itself for `r`
+/// zero
+pub fn compensate(acc: Vec3A, r: Vec3A, gyro: Vec3A, alpha: Vec3A) -> Vec3A {
+    if r == Vec3A::ZERO {
+        return acc;
+    }
+    acc - rigid_body_accel_g(r, gyro, alpha)
+}
+
+/// Lever-arm compensation configuration, the angular acceleration filter
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct LeverArmCompensation {
+    /// cutoff of the angular acceleration low pass in Hz, zero drops the α term
+    pub alpha_cutoff_hz: f32,
+    /// bound of the filtered angular acceleration in rad/s²
+    pub max_alpha: f32,
+}
+
+impl Default for LeverArmCompensation {
+    fn default() -> Self {
+        Self {
+            alpha_cutoff_hz: DEFAULT_ALPHA_CUTOFF_HZ,
+            max_alpha: DEFAULT_MAX_ALPHA,
+        }
+    }
+}
+
+impl LeverArmCompensation {
+    /// cutoff of the angular acceleration low pass in Hz, negative and non-finite values are
+    /// ignored
+    pub fn with_alpha_cutoff_hz(mut self, alpha_cutoff_hz: f32) -> Self {
+        if alpha_cutoff_hz.is_finite() && alpha_cutoff_hz >= 0. {
+            self.alpha_cutoff_hz = alpha_cutoff_hz;
+        }
+        self
+    }
+
+    /// bound of the angular acceleration in rad/s², negative and NaN values are ignored
+    pub fn with_max_alpha(mut self, max_alpha: f32) -> Self {
+        if max_alpha >= 0. {
+            self.max_alpha = max_alpha;
+        }
+        self
+    }
+}
+
+/// Lever-arm compensation over a stream of samples one interval apart, no bus access
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct LeverArmCompensator {
+    config: LeverArmCompensation,
+    previous_gyro: Option<Vec3A>,
+    alpha: Vec3A,
+}
+
+impl LeverArmCompensator {
+    /// compensator without a previous sample
+    pub const fn new(config: LeverArmCompensation) -> Self {
+        Self {
+            config,
+            previous_gyro: None,
+            alpha: Vec3A::ZERO,
+        }
+    }
+
+    /// configuration
+    pub fn config(&self) -> LeverArmCompensation {
+        self.config
+    }
+
+    /// filtered angular acceleration in rad/s² of the last sample
+    pub fn alpha(&self) -> Vec3A {
+        self.alpha
+    }
+
+    /// forgets the previous sample and the filtered angular acceleration
+    pub fn reset(&mut self) {
+        self.previous_gyro = None;
+        self.alpha = Vec3A::ZERO;
+    }
+
+    /// `sample` with the acceleration of the lever arm `r` taken off, `interval_s` the time
+    /// since the previous sample in seconds. The angular acceleration is zero without a
+    /// previous sample
+    pub fn compensate(&mut self, sample: MpuSample, r: Vec3A, interval_s: f32) -> MpuSample {
+        self.update_alpha(sample.gyro, interval_s);
+        if r == Vec3A::ZERO {
+            return sample;
+        }
+        let term = rigid_body_accel_g(r, sample.gyro, self.alpha);
+        sample
+            .with_acc(sample.acc - term)
+            .with_lever_arm_correction(Some(term))
+    }
+
+    fn update_alpha(&mut self, gyro: Vec3A, interval_s: f32) {
+        let previous = self.previous_gyro.replace(gyro);
+        let cutoff_hz = self.config.alpha_cutoff_hz;
+        let (Some(previous), true) = (previous, cutoff_hz > 0. && interval_s > 0.) else {
+            self.alpha = Vec3A::ZERO;
+            return;
+        };
+        let raw = (gyro - previous) / interval_s;
+        let gain = 1. - (-core::f32::consts::TAU * cutoff_hz * interval_s).exp();
+        let max = Vec3A::splat(self.config.max_alpha);
+        self.alpha = (self.alpha + (raw - self.alpha) * gain).clamp(-max, max);
+    }
+}
+
+impl<I, D> Mpu6050<I, D> {
+    /// Position of the sensor relative to the body reference point in meters, in the axes
+    /// of the samples, see the module docs. Non-finite values are ignored
+    pub fn set_lever_arm(&mut self, r: Vec3A) {
+        if r.is_finite() {
+            self.lever_arm = r;
+        }
+    }
+
+    /// lever arm set with [`set_lever_arm`](Self::set_lever_arm), zero by default
+    pub fn lever_arm(&self) -> Vec3A {
+        self.lever_arm
+    }
+
+    /// Opts the combined sample path into the lever-arm compensation, None turns it off.
+    /// Forgets the previous sample
+    pub fn set_lever_arm_compensation(&mut self, compensation: Option<LeverArmCompensation>) {
+        self.lever_arm_compensation = compensation.map(LeverArmCompensator::new);
+    }
+
+    /// compensation set with [`set_lever_arm_compensation`](Self::set_lever_arm_compensation)
+    pub fn lever_arm_compensation(&self) -> Option<LeverArmCompensation> {
+        self.lever_arm_compensation
+            .as_ref()
+            .map(LeverArmCompensator::config)
+    }
+
+    /// a compensation is set and the lever arm is not zero
+    pub fn lever_arm_compensation_active(&self) -> bool {
+        self.lever_arm_compensation.is_some() && self.lever_arm != Vec3A::ZERO
+    }
+
+    /// `sample` through the lever-arm compensation, unchanged while inactive
+    #[cfg(not(feature = "minimal-pipeline"))]
+    pub(crate) fn compensate_lever_arm(&mut self, sample: MpuSample) -> MpuSample {
+        let interval_s = self.nominal_sample_interval_us() * 1e-6;
+        let r = self.lever_arm;
+        let Some(compensator) = self.lever_arm_compensation.as_mut() else {
+            return sample;
+        };
+        if sample.settling() {
+            compensator.reset();
+            return sample;
+        }
+        compensator.compensate(sample, r, interval_s)
+    }
+
+    /// samples were lost, the next one has no previous gyro reading to difference
+    pub(crate) fn forget_lever_arm_history(&mut self) {
+        if let Some(compensator) = self.lever_arm_compensation.as_mut() {
+            compensator.reset();
+        }
+    }
+}
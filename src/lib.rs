@@ -43,161 +43,1051 @@
 //!     }
 //! }
 //! ```
-
+//!
+//! ### Imports
+//! [`prelude`] brings the driver, its errors, configuration values, samples and fusion types
+//! into scope in one glob. Raw register addresses and bit definitions are kept out of it, in
+//! [`registers`].
+//!
+//! ### Features
+//! * `std` (default): the standard library, for the `std::error::Error` impls of the
+//!   errors, the lock based sinks and everything allocating, like the `log_header`
+//!   [`LogHeader`](log_header::LogHeader). Without it the crate is `no_std`
+//! * `libm`: float math through the `libm` crate instead of std, for `driver` or `fusion`
+//!   without `std`. A Cortex-M build is
+//!   `--no-default-features --features driver,libm --target thumbv7em-none-eabihf`
+//! * `driver` (default): the I2C driver, requires `embedded-hal`
+//! * `fusion`: the hardware independent layer alone ([`frame`], [`scale`], [`tilt`], detectors
+//!   and configuration types), no `embedded-hal` dependency, builds for
+//!   `wasm32-unknown-unknown` with `--no-default-features --features fusion,std`
+//! * `deterministic`: bit-exact results across targets, see [`determinism`]
+//! * `test-util`: fault injection for tests of downstream error handling, see `chaos`, and
+//!   transaction traces with golden files, see `trace`. Includes `test-fixtures`
+//! * `test-fixtures`: the golden sample corpus, named frame sequences with their physical
+//!   ground truth, see `test_fixtures`, the numeric outputs of a baseline release over it,
+//!   see `numeric_compat`, and sensor error models for simulated samples, see `error_model`
+//! * `minimal`: only [`device`] and `TinyMpu` from `tiny`, `no_std`, no glam, no floats. For
+//!   size constrained users like bootloaders, build with
+//!   `--no-default-features --features minimal`; combining it with `driver` or `fusion` is
+//!   a compile error
+//! * `spectrum`: fixed size FFT of an accelerometer axis for vibration frequencies, see
+//!   `spectrum`. `no_std`, combines with any of the above
+//! * `compat`: the API of the upstream `mpu6050` crate over this driver, for migrating
+//!   call site by call site, see `compat`
+//! * `hil`: a hardware-in-the-loop battery with a JSON report for maintainers with a sensor
+//!   attached, and the `hil` binary running it over Linux i2c-dev, see `hil`. Includes
+//!   `linux`
+//! * `linux`: an i2c-dev bus and a sleeping delay for Linux hosts, no extra dependencies,
+//!   see `linux`
+//! * `platform-config`: the driver set up from a TOML or JSON description of the board, its
+//!   bus, address, profile, settings, mounting and calibration, see `platform_config`. Adds
+//!   the serde, toml and serde_json dependencies
+//! * `remote`: a versioned command protocol over a byte stream, the server loop on the
+//!   driver and a host side client, see `remote`
+//! * `minimal-pipeline`: the optional stages of the scaled reads compiled out, a read is
+//!   parse, offset, scale and return, for builds audited for hard realtime, see [`stages`]
+//! * `log`, `defmt`: leveled diagnostic records of failures, recoveries, mode transitions and
+//!   optionally every transaction through the backend, see [`logging`]. Without either the
+//!   logging compiles to nothing
+//! * `ffi`: a C ABI over I2C callbacks of the host, the driver and the complementary filter
+//!   behind opaque handles, with a generated header, see `ffi`
+//! * `serde`: `Serialize` and `Deserialize` of the configuration change events, see
+//!   [`config_events`]
+//! * `async`: the everyday driver API as `async fn`s over `embedded-hal-async`, see
+//!   `async_driver`
+//! * `accelerometer`: the `Accelerometer` and `RawAccelerometer` traits of the
+//!   `accelerometer` crate for the driver, see `accel_traits`
+//!
+//! ### API stability
+//! The public surface is locked by two tests: `tests/public_api.rs` compares every public
+//! item against the snapshot committed in `tests/golden/public_api.txt`, and
+//! `tests/api_surface.rs` coerces the methods of the driver and builder to function pointers
+//! of concrete types. A change to the surface fails the suite until the snapshot is
+//! regenerated with `UPDATE_GOLDENS=1` and the diff reviewed with it. A renamed or replaced
+//! item stays for at least one minor release as a `#[deprecated]` shim whose note names the
+//! replacement and the release removing it; the bit helpers `write_bit`, `write_bits` and
+//! `read_bits` of the raw escape hatch go in 0.3.0.
+//!
+//! The numeric outputs are locked the same way: `tests/numeric_compat.rs` holds the scaled
+//! samples and filter results over the fixture corpus against a baseline captured at a
+//! release, bit-exact for parsing and scaling and within documented epsilons for fusion, see
+//! `numeric_compat`. The baseline changes only through the `regenerate_baselines` example.
+//!
+//! ### Math types and glam versions
+//! The public math types are glam's, [`Vec3A`] and [`Quat`] at the crate root and the whole
+//! crate as [`glam`]. Code naming them through this crate, `mpu6050::Vec3A` or
+//! `mpu6050::glam::Vec2`, always gets the version the crate was built with, whatever glam a
+//! downstream crate depends on itself. Across that boundary values travel as arrays: the
+//! `*_as` getters of the driver, [`MpuSample::acc_as`], [`MpuSample::gyro_as`] and
+//! `ComplementaryFilter::orientation_as` convert into any type built from `[f32; 3]` or
+//! `[f32; 4]`, [`MpuSample::from_arrays`] and the builder's offsets take arrays. A glam
+//! version of its own converts with `to_array` and `from`, as do other math libraries.
+//!
+//! The glam version is not selected by feature: one copy is built, and a glam major bump is
+//! a breaking release of this crate, listed in its release notes. Downstream code staying
+//! on the paths and conversions above needs no change for it.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+// without the driver, the driver's state types and helpers are unused
+#![cfg_attr(not(feature = "driver"), allow(dead_code, unused_imports))]
+
+#[cfg(all(feature = "minimal", feature = "fusion"))]
+compile_error!(
+    "feature `minimal` replaces the full driver, build with `--no-default-features --features minimal`"
+);
+#[cfg(all(feature = "fusion", not(feature = "std"), not(feature = "libm")))]
+compile_error!("a no_std build of the float math needs the `libm` feature, or enable `std`");
+
+#[cfg(feature = "accelerometer")]
+pub mod accel_traits;
+#[cfg(feature = "fusion")]
+pub mod aliasing;
+#[cfg(feature = "fusion")]
+pub mod alignment;
+#[cfg(feature = "fusion")]
+pub mod aux_i2c;
+#[cfg(feature = "fusion")]
+pub mod axis_map;
+#[cfg(feature = "fusion")]
+pub mod batch;
+#[cfg(feature = "async")]
+pub mod async_driver;
+#[cfg(feature = "fusion")]
 mod bits;
+#[cfg(feature = "fusion")]
+pub mod black_box;
+#[cfg(feature = "fusion")]
+pub mod board;
+#[cfg(feature = "fusion")]
+pub mod bus;
+#[cfg(feature = "fusion")]
+pub mod calibration;
+#[cfg(feature = "fusion")]
+pub mod capability;
+#[cfg(feature = "test-util")]
+pub mod chaos;
+#[cfg(feature = "compat")]
+pub mod compat;
+#[cfg(feature = "fusion")]
+pub mod clone_detect;
+#[cfg(feature = "fusion")]
+pub mod config;
+#[cfg(feature = "fusion")]
+pub mod config_epoch;
+#[cfg(feature = "fusion")]
+pub mod config_events;
+#[cfg(feature = "empl-conformance")]
+pub mod conformance;
+#[cfg(feature = "fusion")]
+pub mod connection;
+#[cfg(feature = "fusion")]
+pub mod continuity;
+#[cfg(feature = "fusion")]
+pub mod conversion;
+#[cfg(feature = "fusion")]
+pub mod cooperative;
+#[cfg(feature = "fusion")]
+pub mod deadline;
+#[cfg(feature = "fusion")]
+pub mod decimal;
+#[cfg(feature = "fusion")]
+pub mod delay;
+#[cfg(feature = "fusion")]
+pub mod determinism;
 pub mod device;
-
-use std::fmt::{Debug, Display};
-
+#[cfg(feature = "fusion")]
+pub mod differential;
+#[cfg(feature = "fusion")]
+pub mod dormant;
+#[cfg(feature = "fusion")]
+pub mod error_budget;
+#[cfg(feature = "test-fixtures")]
+pub mod error_model;
+#[cfg(feature = "fusion")]
+pub mod euler;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+#[cfg(feature = "fusion")]
+pub mod fifo;
+#[cfg(feature = "fusion")]
+pub mod fifo_recovery;
+#[cfg(feature = "fusion")]
+pub mod frame;
+#[cfg(feature = "fusion")]
+pub mod fsync;
+#[cfg(feature = "fusion")]
+pub mod governor;
+#[cfg(feature = "fusion")]
+pub mod gravity_trim;
+#[cfg(feature = "hil")]
+pub mod hil;
+#[cfg(feature = "fusion")]
+pub mod hint;
+#[cfg(feature = "fusion")]
+pub mod hook;
+#[cfg(feature = "fusion")]
+pub mod hw_offsets;
+#[cfg(feature = "fusion")]
+pub mod impact;
+#[cfg(feature = "fusion")]
+pub mod init_findings;
+#[cfg(feature = "fusion")]
+pub mod interleave;
+#[cfg(feature = "fusion")]
+pub mod interpolation;
+#[cfg(feature = "fusion")]
+pub mod interrupt;
+#[cfg(feature = "fusion")]
+pub mod lever_arm;
+#[cfg(all(feature = "linux", target_os = "linux"))]
+pub mod linux;
+#[cfg(feature = "fusion")]
+pub mod log_header;
+#[cfg(feature = "fusion")]
+pub mod logging;
+#[cfg(feature = "fusion")]
+pub mod long_term;
+#[cfg(feature = "fusion")]
+pub mod low_power;
+#[cfg(all(feature = "fusion", target_has_atomic = "32"))]
+pub mod mailbox;
+#[cfg(all(feature = "fusion", not(feature = "std")))]
+mod math;
+#[cfg(feature = "fusion")]
+pub mod metrics;
+#[cfg(feature = "fusion")]
+pub mod motion_verify;
+#[cfg(feature = "test-fixtures")]
+pub mod numeric_compat;
+#[cfg(feature = "fusion")]
+pub mod op_bounds;
+#[cfg(feature = "fusion")]
+pub mod orientation;
+#[cfg(feature = "fusion")]
+pub mod oscillator;
+#[cfg(feature = "fusion")]
+pub mod packed;
+#[cfg(feature = "fusion")]
+pub mod parse_policy;
+#[cfg(feature = "fusion")]
+pub mod platform;
+#[cfg(feature = "platform-config")]
+pub mod platform_config;
+#[cfg(feature = "fusion")]
+pub mod plausibility;
+#[cfg(feature = "fusion")]
+pub mod power;
+#[cfg(feature = "fusion")]
+pub mod prelude;
+#[cfg(feature = "fusion")]
+pub mod presets;
+#[cfg(feature = "fusion")]
+pub mod provenance;
+#[cfg(feature = "fusion")]
+pub mod recovery;
+#[cfg(feature = "fusion")]
+pub mod reentrancy;
+#[cfg(feature = "fusion")]
+pub mod register;
+pub mod registers;
+#[cfg(feature = "remote")]
+pub mod remote;
+#[cfg(feature = "fusion")]
+pub mod resample;
+#[cfg(feature = "fusion")]
+pub mod resolution;
+#[cfg(feature = "fusion")]
+pub mod sample;
+#[cfg(feature = "fusion")]
+pub mod sampling;
+#[cfg(feature = "fusion")]
+pub mod scale;
+#[cfg(feature = "fusion")]
+pub mod scale_drift;
+#[cfg(feature = "fusion")]
+pub mod script;
+#[cfg(feature = "fusion")]
+pub mod self_heating;
+#[cfg(feature = "fusion")]
+pub mod settings;
+#[cfg(feature = "fusion")]
+pub mod settling;
+#[cfg(feature = "fusion")]
+pub mod setup;
+#[cfg(feature = "fusion")]
+pub mod shutdown;
+#[cfg(feature = "fusion")]
+pub mod skew;
+#[cfg(feature = "fusion")]
+pub mod smoothing;
+#[cfg(feature = "fusion")]
+pub mod snapshot;
+#[cfg(feature = "spectrum")]
+pub mod spectrum;
+#[cfg(feature = "fusion")]
+pub mod split;
+#[cfg(feature = "fusion")]
+pub mod stages;
+#[cfg(feature = "fusion")]
+pub mod supervisor;
+#[cfg(feature = "fusion")]
+pub mod synthetic;
+#[cfg(feature = "fusion")]
+pub mod temp_calibration;
+#[cfg(feature = "test-fixtures")]
+pub mod test_fixtures;
+#[cfg(feature = "fusion")]
+pub mod tilt;
+#[cfg(feature = "fusion")]
+pub mod time_math;
+#[cfg(feature = "minimal")]
+pub mod tiny;
+#[cfg(feature = "test-util")]
+pub mod trace;
+#[cfg(feature = "fusion")]
+pub mod traffic_guard;
+#[cfg(any(feature = "driver", feature = "minimal"))]
+pub mod transfer;
+#[cfg(feature = "fusion")]
+pub mod validity;
+
+#[cfg(feature = "fusion")]
+use core::fmt::{Debug, Display};
+
+#[cfg(feature = "fusion")]
+use crate::aliasing::AliasingAssessment;
+#[cfg(feature = "fusion")]
+use crate::aux_i2c::AuxState;
+#[cfg(feature = "driver")]
+use crate::black_box::FailedOperation;
+#[cfg(feature = "driver")]
+use crate::board::IntPinRequest;
+#[cfg(feature = "fusion")]
+use crate::board::{AddrConstraint, BoardConstraints, ConstraintViolation};
+#[cfg(feature = "fusion")]
+use crate::bus::{DriverOp, RateTooHigh};
+#[cfg(feature = "fusion")]
+use crate::calibration::BackgroundCalibration;
+#[cfg(feature = "fusion")]
+use crate::config_epoch::ConfigChange;
+#[cfg(feature = "fusion")]
+use crate::config_events::{ChangeSource, ConfigObserver};
+#[cfg(feature = "fusion")]
+use crate::connection::*;
+#[cfg(feature = "fusion")]
+use crate::continuity::ContinuityBreak;
+#[cfg(feature = "fusion")]
+use crate::conversion::RoundingMode;
+#[cfg(feature = "fusion")]
+use crate::deadline::AbortProgress;
+#[cfg(feature = "fusion")]
+use crate::delay::NoDelay;
+#[cfg(feature = "fusion")]
 use crate::device::*;
+#[cfg(feature = "fusion")]
+use crate::fifo::{FifoSources, MixedReadPolicy};
+#[cfg(feature = "fusion")]
+use crate::fifo_recovery::FifoTimeline;
+#[cfg(feature = "fusion")]
+use crate::governor::PowerGovernor;
+#[cfg(feature = "fusion")]
+use crate::hook::{SampleHook, SampleObserver, SampleTap};
+#[cfg(feature = "fusion")]
+use crate::impact::ImpactRanger;
+#[cfg(feature = "fusion")]
+use crate::init_findings::InitFindings;
+#[cfg(feature = "fusion")]
+use crate::interleave::InterleaveState;
+#[cfg(feature = "fusion")]
+use crate::interpolation::TimestampError;
+#[cfg(feature = "fusion")]
+use crate::interrupt::{InterruptEdgeTracker, InterruptSource, MotionDetectionConfig};
+#[cfg(feature = "fusion")]
+use crate::lever_arm::LeverArmCompensator;
+#[cfg(all(feature = "driver", any(feature = "log", feature = "defmt")))]
+use crate::logging::register_name;
+#[cfg(feature = "driver")]
+use crate::logging::{log_debug, log_trace};
+#[cfg(feature = "fusion")]
+use crate::logging::TraceLevel;
+#[cfg(feature = "fusion")]
+use crate::metrics::{GaugeLimiter, MetricEvent, MetricsSink, Sensor};
+#[cfg(feature = "fusion")]
+use crate::op_bounds::IoStats;
+#[cfg(feature = "fusion")]
+use crate::parse_policy::{FrameCheck, ParseDiagnostics, ParsePolicy};
+#[cfg(feature = "fusion")]
+use crate::plausibility::PlausibilityScorer;
+#[cfg(feature = "fusion")]
+use crate::power::PowerState;
+#[cfg(feature = "fusion")]
+use crate::recovery::BusRecoveryState;
+#[cfg(feature = "fusion")]
+use crate::register::{AccessViolation, Register, RegisterValue};
+#[cfg(feature = "fusion")]
+use crate::resolution::ResolutionInfo;
+#[cfg(feature = "fusion")]
+use crate::scale::{Pipeline, PipelineStages, ScaleModel};
+#[cfg(feature = "fusion")]
+use crate::self_heating::BootTransient;
+#[cfg(feature = "fusion")]
+use crate::settings::SettingsError;
+#[cfg(feature = "fusion")]
+use crate::settling::{SettleCountdown, SettlingPolicy};
+#[cfg(feature = "fusion")]
+use crate::skew::SkewCorrector;
+#[cfg(feature = "fusion")]
+use crate::snapshot::SyncPoints;
+#[cfg(feature = "fusion")]
+use crate::supervisor::{Supervisor, SupervisorConfig, SupervisorEvent};
+#[cfg(feature = "fusion")]
+use crate::temp_calibration::{TempCalibration, TempCalibrationError};
+#[cfg(feature = "fusion")]
+use crate::tilt::{TiltEstimate, TiltThresholds};
+#[cfg(feature = "fusion")]
+use crate::traffic_guard::TrafficAccounting;
+#[cfg(feature = "fusion")]
+use crate::validity::CalibrationMonitor;
+#[cfg(feature = "driver")]
 use embedded_hal::{
     blocking::delay::DelayMs,
     blocking::i2c::{Write, WriteRead},
 };
-use glam::EulerRot;
+#[cfg(feature = "accelerometer")]
+pub use accelerometer;
+#[cfg(feature = "fusion")]
+pub use glam;
+#[cfg(feature = "fusion")]
 pub use glam::{Quat, Vec3A};
+#[cfg(feature = "fusion")]
+pub use sample::MpuSample;
 
+#[cfg(feature = "fusion")]
 /// PI, f32
 pub const PI: f32 = core::f32::consts::PI;
 
+#[cfg(feature = "fusion")]
 /// PI / 180, for conversion to radians
 pub const PI_180: f32 = PI / 180.0;
 
+#[cfg(feature = "fusion")]
 /// All possible errors for Mpu6050
 #[derive(Debug)]
 pub enum Mpu6050Error<E> {
     /// I2C bus error
     I2c(E),
 
-    /// Invalid chip ID was read
+    /// WHO_AM_I refused by the builder's [`ChipIdCheck`], the raw value read
     InvalidChipId(u8),
+
+    /// Sensor is considered disconnected, no bus transaction was attempted
+    Disconnected,
+
+    /// Aux slave data would exceed the 24 EXT_SENS_DATA registers, total length requested
+    ExtDataOverflow(u8),
+
+    /// EXT_SENS_DATA slot handle is from before a slave configuration change
+    StaleExtDataSlot,
+
+    /// FIFO schema is from before a change of the frame layout or scaling. No longer
+    /// returned, a stale schema fails with
+    /// [`StaleConfiguration`](Mpu6050Error::StaleConfiguration)
+    #[deprecated(
+        since = "0.2.0",
+        note = "stale schemas fail with `StaleConfiguration`; removed in 0.3.0"
+    )]
+    StaleFifoSchema,
+
+    /// Derived value is from an earlier configuration epoch, see [`config_epoch`]
+    StaleConfiguration {
+        /// epoch the value was derived at
+        captured: u32,
+        /// epoch of the driver
+        current: u32,
+    },
+
+    /// Provided buffer is too small, required length
+    BufferTooSmall(usize),
+
+    /// Settings failed validation, nothing was written
+    InvalidSettings(SettingsError),
+
+    /// Strict configuration refused a configuration likely to alias, see [`aliasing`]
+    AliasingLikely(AliasingAssessment),
+
+    /// Timestamp rejected by an interpolation buffer
+    InvalidTimestamp(TimestampError),
+
+    /// The connected chip lacks the feature, see [`capability`]
+    Unsupported(Capability),
+
+    /// The caller's deadline expired, see [`deadline`]
+    Aborted {
+        /// phase of the operation that was running
+        phase: &'static str,
+        /// how far the operation got
+        progress: AbortProgress,
+    },
+
+    /// The operation waits for the sensor, but the driver owns no delay, see [`delay`]
+    DelayRequired,
+
+    /// Direct scaled read refused while FIFO streaming, see [`fifo`]
+    FifoActive,
+
+    /// Typed register access refused before touching the bus, see [`register`]
+    InvalidRegisterAccess {
+        /// register accessed
+        register: Register,
+        /// what the access would have violated
+        violation: AccessViolation,
+    },
+
+    /// Request refused by the board constraints, see [`board`]
+    BoardConstraint(ConstraintViolation),
+
+    /// The driver is dormant, no bus transaction was attempted, see [`dormant`]
+    NotActivated,
+
+    /// Raw read of a deny-listed undocumented range refused before touching the bus, see
+    /// [`clone_detect`]
+    DeniedRegisterRange(DeniedRange),
+
+    /// Burst frame failing a structural check under the strict parse policy, see
+    /// [`parse_policy`]
+    ImplausibleFrame {
+        /// check the frame failed
+        reason: FrameCheck,
+    },
+
+    /// Wake on motion refused by its policy, with the status of the motion engine, see
+    /// [`motion_verify`]
+    WakeOnMotionRefused(MotionEngineStatus),
+
+    /// The driver is already in use further up the call stack or by an interrupted context,
+    /// nothing was done, see [`reentrancy`]
+    Reentrancy,
+
+    /// A sample rate change the bus given to [`Mpu6050Builder::bus_hz`] cannot sustain was
+    /// refused, see [`bus`]
+    BusRateTooHigh(RateTooHigh),
 }
 
+#[cfg(feature = "fusion")]
 impl<E: Display> Display for Mpu6050Error<E> {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let tmp;
-        f.write_str(match self {
-            Mpu6050Error::I2c(error) => {
-                tmp = format!("i2c error: {}", error);
-                &tmp
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Mpu6050Error::I2c(error) => write!(f, "i2c error: {}", error),
+            Mpu6050Error::InvalidChipId(id) => write!(f, "invalid chip id: {}", id),
+            Mpu6050Error::Disconnected => f.write_str("sensor disconnected"),
+            Mpu6050Error::ExtDataOverflow(len) => {
+                write!(f, "aux data of {} bytes exceeds EXT_SENS_DATA", len)
             }
-            Mpu6050Error::InvalidChipId(id) => {
-                tmp = format!("invalid chip id: {}", id);
-                &tmp
+            Mpu6050Error::StaleExtDataSlot => f.write_str("stale EXT_SENS_DATA slot"),
+            #[allow(deprecated)]
+            Mpu6050Error::StaleFifoSchema => f.write_str("stale FIFO schema"),
+            Mpu6050Error::StaleConfiguration { captured, current } => {
+                write!(
+                    f,
+                    "stale configuration, derived at epoch {}, driver at {}",
+                    captured, current
+                )
             }
-        })
+            Mpu6050Error::BufferTooSmall(len) => {
+                write!(f, "buffer too small, {} bytes required", len)
+            }
+            Mpu6050Error::InvalidSettings(error) => write!(f, "invalid settings: {}", error),
+            Mpu6050Error::AliasingLikely(assessment) => {
+                write!(f, "aliasing likely: {}", assessment)
+            }
+            Mpu6050Error::InvalidTimestamp(error) => write!(f, "invalid timestamp: {}", error),
+            Mpu6050Error::Unsupported(capability) => {
+                write!(f, "unsupported by the connected chip: {:?}", capability)
+            }
+            Mpu6050Error::Aborted { phase, progress } => {
+                write!(
+                    f,
+                    "aborted at deadline in {} after {} ms, {} transactions, {} samples",
+                    phase, progress.elapsed_ms, progress.transactions, progress.samples
+                )
+            }
+            Mpu6050Error::DelayRequired => f.write_str("delay required, the driver owns none"),
+            Mpu6050Error::FifoActive => f.write_str("direct read refused while FIFO streaming"),
+            Mpu6050Error::InvalidRegisterAccess {
+                register,
+                violation,
+            } => write!(f, "invalid access to {}: {}", register, violation),
+            Mpu6050Error::BoardConstraint(violation) => {
+                write!(f, "board constraint: {}", violation)
+            }
+            Mpu6050Error::NotActivated => f.write_str("driver dormant, not activated"),
+            Mpu6050Error::DeniedRegisterRange(range) => {
+                write!(f, "read of denied register range {}", range)
+            }
+            Mpu6050Error::ImplausibleFrame { reason } => write!(f, "implausible frame: {}", reason),
+            Mpu6050Error::WakeOnMotionRefused(engine) => {
+                write!(f, "wake on motion refused, motion engine {:?}", engine)
+            }
+            Mpu6050Error::Reentrancy => {
+                f.write_str("driver already in use, reentrant call refused")
+            }
+            Mpu6050Error::BusRateTooHigh(error) => write!(f, "{}", error),
+        }
     }
 }
 
+#[cfg(all(feature = "fusion", feature = "std"))]
 impl<E: Debug + Display> std::error::Error for Mpu6050Error<E> {}
 
+#[cfg(feature = "fusion")]
 #[derive(Debug)]
 pub enum Mpu6050BuilderError {
     /// No i2c device was provided to the builder
     NoI2cDeviceProvided,
+    /// The builder settings violate the board constraints, see [`board`]
+    BoardConstraint(ConstraintViolation),
+    /// The temperature calibration is invalid, see [`temp_calibration`]
+    TempCalibration(TempCalibrationError),
 }
 
+#[cfg(all(feature = "fusion", feature = "std"))]
 impl std::error::Error for Mpu6050BuilderError {}
 
+#[cfg(feature = "fusion")]
 impl Display for Mpu6050BuilderError {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        f.write_str(match self {
-            Mpu6050BuilderError::NoI2cDeviceProvided => "Mpu6050BuilderError::NoI2cDeviceProvided",
-        })
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Mpu6050BuilderError::NoI2cDeviceProvided => {
+                f.write_str("Mpu6050BuilderError::NoI2cDeviceProvided")
+            }
+            Mpu6050BuilderError::BoardConstraint(violation) => {
+                write!(f, "Mpu6050BuilderError::BoardConstraint({})", violation)
+            }
+            Mpu6050BuilderError::TempCalibration(error) => {
+                write!(f, "Mpu6050BuilderError::TempCalibration({})", error)
+            }
+        }
     }
 }
 
-pub struct Mpu6050Builder<I> {
+#[cfg(feature = "fusion")]
+pub struct Mpu6050Builder<I, D = NoDelay> {
     i2c: Option<I>,
+    delay: Option<D>,
     slave_addr: Option<u8>,
     acc_sensitivity: Option<AccelRange>,
     gyro_sensitivity: Option<GyroRange>,
-    gyro_offset: Option<Vec3A>,
-    acc_offset: Option<Vec3A>,
+    gyro_offset: Option<[f32; 3]>,
+    acc_offset: Option<[f32; 3]>,
+    acc_scale_factors: Option<[f32; 3]>,
+    gyro_scale_factors: Option<[f32; 3]>,
+    disconnect_threshold: Option<u32>,
+    auto_disconnect: bool,
+    strict_configuration: bool,
+    bus_hz: Option<u32>,
+    board: Option<BoardConstraints>,
+    dormant: bool,
+    rounding: RoundingMode,
+    temp_calibration: Option<TempCalibration>,
+    allow_undocumented: bool,
+    chip_id_check: ChipIdCheck,
 }
 
+#[cfg(feature = "fusion")]
 impl<I> Mpu6050Builder<I> {
-    pub fn new() -> Self {
+    pub const fn new() -> Self {
         Self {
             i2c: None,
+            delay: None,
             slave_addr: None,
             acc_sensitivity: None,
             gyro_sensitivity: None,
             gyro_offset: None,
             acc_offset: None,
+            acc_scale_factors: None,
+            gyro_scale_factors: None,
+            disconnect_threshold: None,
+            auto_disconnect: false,
+            strict_configuration: false,
+            bus_hz: None,
+            board: None,
+            dormant: false,
+            rounding: RoundingMode::NearestEven,
+            temp_calibration: None,
+            allow_undocumented: false,
+            chip_id_check: ChipIdCheck::Mpu6050,
         }
     }
+}
 
+#[cfg(feature = "fusion")]
+impl<I, D> Mpu6050Builder<I, D> {
     pub fn i2c(mut self, i2c: I) -> Self {
         self.i2c = Some(i2c);
         self
     }
 
-    pub fn slave_addr(mut self, slave_addr: u8) -> Self {
+    /// Delay owned by the driver, see [`delay`]
+    pub fn delay<O>(self, delay: O) -> Mpu6050Builder<I, O> {
+        Mpu6050Builder {
+            i2c: self.i2c,
+            delay: Some(delay),
+            slave_addr: self.slave_addr,
+            acc_sensitivity: self.acc_sensitivity,
+            gyro_sensitivity: self.gyro_sensitivity,
+            gyro_offset: self.gyro_offset,
+            acc_offset: self.acc_offset,
+            acc_scale_factors: self.acc_scale_factors,
+            gyro_scale_factors: self.gyro_scale_factors,
+            disconnect_threshold: self.disconnect_threshold,
+            auto_disconnect: self.auto_disconnect,
+            strict_configuration: self.strict_configuration,
+            bus_hz: self.bus_hz,
+            board: self.board,
+            dormant: self.dormant,
+            rounding: self.rounding,
+            temp_calibration: self.temp_calibration,
+            allow_undocumented: self.allow_undocumented,
+            chip_id_check: self.chip_id_check,
+        }
+    }
+
+    pub const fn slave_addr(mut self, slave_addr: u8) -> Self {
         self.slave_addr = Some(slave_addr);
         self
     }
 
-    pub fn acc_sensitivity(mut self, acc_sensitivity: AccelRange) -> Self {
+    pub const fn acc_sensitivity(mut self, acc_sensitivity: AccelRange) -> Self {
         self.acc_sensitivity = Some(acc_sensitivity);
         self
     }
 
-    pub fn gyro_sensitivity(mut self, gyro_sensitivity: GyroRange) -> Self {
+    pub const fn gyro_sensitivity(mut self, gyro_sensitivity: GyroRange) -> Self {
         self.gyro_sensitivity = Some(gyro_sensitivity);
         self
     }
 
-    pub fn gyro_offset(mut self, gyro_offset: Vec3A) -> Self {
+    /// Gyro offset in rad/s, the bias subtracted from readings, a [`Vec3A`] or `[x, y, z]`
+    pub fn gyro_offset(mut self, gyro_offset: impl Into<Vec3A>) -> Self {
+        self.gyro_offset = Some(gyro_offset.into().to_array());
+        self
+    }
+
+    /// [`gyro_offset`](Self::gyro_offset) as `[x, y, z]`, usable in consts
+    pub const fn gyro_offset_array(mut self, gyro_offset: [f32; 3]) -> Self {
         self.gyro_offset = Some(gyro_offset);
         self
     }
 
-    pub fn acc_offset(mut self, acc_offset: Vec3A) -> Self {
+    /// Accelerometer offset in g, the bias subtracted from readings, a [`Vec3A`] or
+    /// `[x, y, z]`
+    pub fn acc_offset(mut self, acc_offset: impl Into<Vec3A>) -> Self {
+        self.acc_offset = Some(acc_offset.into().to_array());
+        self
+    }
+
+    /// [`acc_offset`](Self::acc_offset) as `[x, y, z]`, usable in consts
+    pub const fn acc_offset_array(mut self, acc_offset: [f32; 3]) -> Self {
         self.acc_offset = Some(acc_offset);
         self
     }
 
-    pub fn build(self) -> Result<Mpu6050<I>, Mpu6050BuilderError> {
+    /// Per-axis accelerometer scale factors, see [`scale`]
+    pub fn acc_scale_factors(mut self, factors: Vec3A) -> Self {
+        self.acc_scale_factors = Some(factors.to_array());
+        self
+    }
+
+    /// [`acc_scale_factors`](Self::acc_scale_factors) as `[x, y, z]`, usable in consts
+    pub const fn acc_scale_factors_array(mut self, factors: [f32; 3]) -> Self {
+        self.acc_scale_factors = Some(factors);
+        self
+    }
+
+    /// Per-axis gyro scale factors, see [`scale`]
+    pub fn gyro_scale_factors(mut self, factors: Vec3A) -> Self {
+        self.gyro_scale_factors = Some(factors.to_array());
+        self
+    }
+
+    /// [`gyro_scale_factors`](Self::gyro_scale_factors) as `[x, y, z]`, usable in consts
+    pub const fn gyro_scale_factors_array(mut self, factors: [f32; 3]) -> Self {
+        self.gyro_scale_factors = Some(factors);
+        self
+    }
+
+    /// Number of consecutive bus errors after which the sensor counts as disconnected
+    pub const fn disconnect_threshold(mut self, threshold: u32) -> Self {
+        self.disconnect_threshold = Some(threshold);
+        self
+    }
+
+    /// Skip bus transactions while disconnected, until `try_reconnect` is called
+    pub const fn auto_disconnect(mut self, auto: bool) -> Self {
+        self.auto_disconnect = auto;
+        self
+    }
+
+    /// Refuse configurations likely to alias instead of only assessing them, see [`aliasing`]
+    pub const fn strict_configuration(mut self, strict: bool) -> Self {
+        self.strict_configuration = strict;
+        self
+    }
+
+    /// Refuse sample rate divider changes a bus at `bus_hz` cannot sustain, see
+    /// [`validate_rate_against_bus`](Mpu6050::validate_rate_against_bus)
+    pub const fn bus_hz(mut self, bus_hz: u32) -> Self {
+        self.bus_hz = Some(bus_hz);
+        self
+    }
+
+    /// Constraints of the board the chip is integrated on, see [`board`]
+    pub const fn board_constraints(mut self, board: BoardConstraints) -> Self {
+        self.board = Some(board);
+        self
+    }
+
+    /// Builds a dormant driver: no bus traffic until [`activate`](Mpu6050::activate), see
+    /// [`dormant`]
+    pub const fn defer_bus_contact(mut self) -> Self {
+        self.dormant = true;
+        self
+    }
+
+    /// Rounding of scaled values to counts, nearest even by default, see [`conversion`]
+    pub const fn rounding(mut self, rounding: RoundingMode) -> Self {
+        self.rounding = rounding;
+        self
+    }
+
+    /// Temperature model instead of the datasheet formula, e.g. a stored calibration, see
+    /// [`temp_calibration`]
+    pub const fn temp_calibration(mut self, calibration: TempCalibration) -> Self {
+        self.temp_calibration = Some(calibration);
+        self
+    }
+
+    /// Lets the raw reads touch the undocumented ranges of
+    /// [`DENIED_RANGES`], see [`clone_detect`]. Not memory unsafe: the
+    /// risk is a clone locking up
+    pub const fn unsafe_allow_undocumented(mut self) -> Self {
+        self.allow_undocumented = true;
+        self
+    }
+
+    /// WHO_AM_I values init and reconnect accept instead of the MPU-6050's, compared on all
+    /// 8 bits, e.g. `&[0x68, 0x70, 0x71]` to run MPU-6500 and MPU-9250 boards. What the
+    /// driver does with a part that is not an MPU-6050 is up to its register map
+    pub const fn expect_chip_ids(mut self, ids: &'static [u8]) -> Self {
+        self.chip_id_check = ChipIdCheck::OneOf(ids);
+        self
+    }
+
+    /// Accepts any WHO_AM_I at init and reconnect, the chip is not verified
+    pub const fn skip_chip_id_check(mut self) -> Self {
+        self.chip_id_check = ChipIdCheck::Skip;
+        self
+    }
+
+    pub fn build(self) -> Result<Mpu6050<I, D>, Mpu6050BuilderError> {
+        let mut slave_addr = self.slave_addr.unwrap_or(DEFAULT_SLAVE_ADDR);
+        if let Some(BoardConstraints {
+            addr: constraint @ AddrConstraint::Fixed(fixed),
+            ..
+        }) = self.board
+        {
+            match self.slave_addr {
+                Some(requested) if requested != fixed.addr() => {
+                    return Err(Mpu6050BuilderError::BoardConstraint(
+                        ConstraintViolation::AddrConflict {
+                            constraint,
+                            slave_addr: requested,
+                        },
+                    ))
+                }
+                _ => slave_addr = fixed.addr(),
+            }
+        }
+        if let Some(calibration) = self.temp_calibration {
+            calibration
+                .validate()
+                .map_err(Mpu6050BuilderError::TempCalibration)?;
+        }
         Ok(Mpu6050 {
             i2c: match self.i2c {
                 Some(i2c) => i2c,
                 None => return Err(Mpu6050BuilderError::NoI2cDeviceProvided),
             },
-            slave_addr: self.slave_addr.unwrap_or(DEFAULT_SLAVE_ADDR),
-            acc_sensitivity: self
-                .acc_sensitivity
-                .map(|sens| sens.sensitivity())
-                .unwrap_or(ACCEL_SENS.0),
-            gyro_sensitivity: self
-                .gyro_sensitivity
-                .map(|sens| sens.sensitivity())
-                .unwrap_or(GYRO_SENS.0),
-            gyro_offset: self.gyro_offset.unwrap_or(Vec3A::ZERO),
-            acc_offset: self.acc_offset.unwrap_or(Vec3A::ZERO),
+            delay: self.delay,
+            slave_addr,
+            accel_range: self.acc_sensitivity.unwrap_or(AccelRange::G2),
+            gyro_range: self.gyro_sensitivity.unwrap_or(GyroRange::D250),
+            acc_scale: ScaleModel::new(
+                self.acc_sensitivity
+                    .map(|sens| sens.sensitivity())
+                    .unwrap_or(ACCEL_SENS.0),
+            )
+            .with_factors(self.acc_scale_factors.map_or(Vec3A::ONE, Vec3A::from)),
+            gyro_scale: ScaleModel::new(
+                self.gyro_sensitivity
+                    .map(|sens| sens.sensitivity())
+                    .unwrap_or(GYRO_SENS.0),
+            )
+            .with_factors(self.gyro_scale_factors.map_or(Vec3A::ONE, Vec3A::from)),
+            gyro_offset: self.gyro_offset.map_or(Vec3A::ZERO, Vec3A::from),
+            acc_offset: self.acc_offset.map_or(Vec3A::ZERO, Vec3A::from),
+            connection: ConnectionMonitor::new(
+                self.disconnect_threshold
+                    .unwrap_or(DEFAULT_DISCONNECT_THRESHOLD),
+                self.auto_disconnect,
+            ),
+            shadow: RegisterShadow::default(),
+            chip_id: None,
+            failed_register: None,
+            tilt_thresholds: TiltThresholds::default(),
+            supervisor: None,
+            governor: None,
+            background_calibration: None,
+            boot_transient: None,
+            aux: AuxState::default(),
+            fifo_sources: FifoSources::NONE,
+            config_epoch: 0,
+            last_config_change: None,
+            config_observer: None,
+            change_source: ChangeSource::UserApi,
+            continuity_token: 0,
+            last_continuity_break: None,
+            dlpf_cfg: 0,
+            sample_rate_div: 0,
+            cycle: None,
+            strict_configuration: self.strict_configuration,
+            bus_hz: self.bus_hz,
+            interrupt_tracker: InterruptEdgeTracker::new(),
+            settle: SettleCountdown::default(),
+            settling_policy: SettlingPolicy::default(),
+            io_stats: IoStats::default(),
+            synced: SyncPoints::default(),
+            capabilities: device::capabilities(ChipVariant::Mpu6050),
+            sample_hook: None,
+            sample_tap: None,
+            sample_observer: None,
+            metrics: None,
+            gauge_limiter: None,
+            fifo_streaming: false,
+            mixed_read_policy: MixedReadPolicy::default(),
+            fifo_timeline: FifoTimeline::default(),
+            interleave: None,
+            impact: None,
+            clock_ratio: 1.,
+            skew: None,
+            lever_arm: Vec3A::ZERO,
+            lever_arm_compensation: None,
+            bus_recovery: None,
+            init_findings: InitFindings::default(),
+            power: PowerState::default(),
+            board: self.board,
+            dormant: self.dormant,
+            rounding: self.rounding,
+            plausibility: None,
+            parse_policy: ParsePolicy::Lenient,
+            parse_diagnostics: ParseDiagnostics::default(),
+            trace_level: TraceLevel::Off,
+            validity: CalibrationMonitor::new(),
+            traffic: TrafficAccounting::default(),
+            temp_calibration: self.temp_calibration,
+            allow_undocumented: self.allow_undocumented,
+            chip_id_check: self.chip_id_check,
         })
     }
 }
 
+#[cfg(feature = "fusion")]
 /// Handles all operations on/with Mpu6050
-pub struct Mpu6050<I> {
+pub struct Mpu6050<I, D = NoDelay> {
     i2c: I,
+    delay: Option<D>,
     slave_addr: u8,
-    acc_sensitivity: f32,
-    gyro_sensitivity: f32,
+    accel_range: AccelRange,
+    gyro_range: GyroRange,
+    acc_scale: ScaleModel,
+    gyro_scale: ScaleModel,
+    /// gyro bias in rad/s subtracted from every reading, see [`calibration`]
     pub gyro_offset: Vec3A,
+    /// accelerometer bias in g subtracted from every reading, see [`calibration`]
     pub acc_offset: Vec3A,
+    connection: ConnectionMonitor,
+    /// written back by a reconnect
+    shadow: RegisterShadow,
+    chip_id: Option<u8>,
+    failed_register: Option<u8>,
+    tilt_thresholds: TiltThresholds,
+    supervisor: Option<Supervisor>,
+    governor: Option<PowerGovernor>,
+    background_calibration: Option<BackgroundCalibration>,
+    boot_transient: Option<BootTransient>,
+    aux: AuxState,
+    fifo_sources: FifoSources,
+    config_epoch: u32,
+    last_config_change: Option<ConfigChange>,
+    config_observer: Option<ConfigObserver>,
+    change_source: ChangeSource,
+    continuity_token: u32,
+    last_continuity_break: Option<ContinuityBreak>,
+    dlpf_cfg: u8,
+    sample_rate_div: u8,
+    cycle: Option<LP_WAKE_CTRL>,
+    strict_configuration: bool,
+    bus_hz: Option<u32>,
+    interrupt_tracker: InterruptEdgeTracker,
+    settle: SettleCountdown,
+    settling_policy: SettlingPolicy,
+    io_stats: IoStats,
+    synced: SyncPoints,
+    capabilities: ChipCapabilities,
+    sample_hook: Option<SampleHook>,
+    sample_tap: Option<SampleTap>,
+    sample_observer: Option<SampleObserver>,
+    metrics: Option<&'static dyn MetricsSink>,
+    gauge_limiter: Option<GaugeLimiter>,
+    fifo_streaming: bool,
+    mixed_read_policy: MixedReadPolicy,
+    fifo_timeline: FifoTimeline,
+    interleave: Option<InterleaveState>,
+    impact: Option<ImpactRanger>,
+    clock_ratio: f32,
+    skew: Option<SkewCorrector>,
+    lever_arm: Vec3A,
+    lever_arm_compensation: Option<LeverArmCompensator>,
+    bus_recovery: Option<BusRecoveryState>,
+    init_findings: InitFindings,
+    power: PowerState,
+    board: Option<BoardConstraints>,
+    dormant: bool,
+    rounding: RoundingMode,
+    plausibility: Option<PlausibilityScorer>,
+    parse_policy: ParsePolicy,
+    parse_diagnostics: ParseDiagnostics,
+    trace_level: TraceLevel,
+    validity: CalibrationMonitor,
+    traffic: TrafficAccounting,
+    temp_calibration: Option<TempCalibration>,
+    allow_undocumented: bool,
+    chip_id_check: ChipIdCheck,
 }
 
-impl<I, E> Mpu6050<I>
+#[cfg(feature = "driver")]
+impl<I, D, E> Mpu6050<I, D>
 where
     I: Write<Error = E> + WriteRead<Error = E>,
 {
     /// Wakes MPU6050 with all sensors enabled (default)
-    fn wake<D: DelayMs<u8>>(&mut self, delay: &mut D) -> Result<(), Mpu6050Error<E>> {
+    fn wake(&mut self, delay: &mut impl DelayMs<u8>) -> Result<(), Mpu6050Error<E>> {
         // MPU6050 has sleep enabled by default -> set bit 0 to wake
         // Set clock source to be PLL with x-axis gyroscope reference, bits 2:0 = 001 (See Register Map )
-        self.write_byte(PWR_MGMT_1::ADDR, 0x01)?;
+        self.write_register(Register::PWR_MGMT_1, 0x01)?;
+        self.power.asleep = false;
+        self.break_continuity(ContinuityBreak::Wake);
+        self.settle.trigger(SettleTrigger::Wake);
         delay.delay_ms(100u8);
         Ok(())
     }
@@ -212,282 +1102,694 @@ where
     /// (or  an  external  clocksource) as the clock reference for improved stability.
     /// The clock source can be selected according to the following table...."
     pub fn set_clock_source(&mut self, source: CLKSEL) -> Result<(), Mpu6050Error<E>> {
-        Ok(self.write_bits(
-            PWR_MGMT_1::ADDR,
-            PWR_MGMT_1::CLKSEL.bit,
-            PWR_MGMT_1::CLKSEL.length,
-            source as u8,
-        )?)
+        self.write_field(PWR_MGMT_1::CLKSEL_FIELD, source)
     }
 
     /// get current clock source
     pub fn get_clock_source(&mut self) -> Result<CLKSEL, Mpu6050Error<E>> {
-        let source = self.read_bits(
-            PWR_MGMT_1::ADDR,
-            PWR_MGMT_1::CLKSEL.bit,
-            PWR_MGMT_1::CLKSEL.length,
-        )?;
+        let source = self.read_field(PWR_MGMT_1::CLKSEL_FIELD)?;
         Ok(CLKSEL::from(source))
     }
 
-    /// Init wakes MPU6050 and verifies register addr, e.g. in i2c
-    pub fn init<D: DelayMs<u8>>(&mut self, delay: &mut D) -> Result<(), Mpu6050Error<E>> {
-        self.wake(delay)?;
+    pub(crate) fn init_using(
+        &mut self,
+        delay: &mut impl DelayMs<u8>,
+    ) -> Result<(), Mpu6050Error<E>> {
+        let res = self.init_sequence(delay);
+        if let Err(error) = &res {
+            self.emit_failure(FailedOperation::Init, error);
+        }
+        res
+    }
+
+    fn init_sequence(&mut self, delay: &mut impl DelayMs<u8>) -> Result<(), Mpu6050Error<E>> {
+        self.select_board_address()?;
+        // WHO_AM_I reads while asleep, nothing is written to a chip that is refused
         self.verify()?;
+        self.inspect_at_init()?;
+        self.enforce_int_pin_constraint()?;
+        self.wake(delay)?;
+        self.probe_capabilities()?;
         self.set_accel_range(AccelRange::G2)?;
         self.set_gyro_range(GyroRange::D250)?;
         self.set_accel_hpf(ACCEL_HPF::_RESET)?;
+        let chip_id = self.chip_id.unwrap_or(DEFAULT_SLAVE_ADDR);
+        self.emit_event(metrics::INITS, MetricEvent::Initialized { chip_id });
         Ok(())
     }
 
-    /// Verifies device to address 0x68 with WHOAMI.addr() Register
+    /// Verifies the device ID in WHO_AM_I against the builder's [`ChipIdCheck`], the raw
+    /// value is kept as the chip ID and reported if refused
     fn verify(&mut self) -> Result<(), Mpu6050Error<E>> {
-        let address = self.read_byte(WHOAMI)?;
-        if address != DEFAULT_SLAVE_ADDR {
-            return Err(Mpu6050Error::InvalidChipId(address));
+        let who_am_i = self.read_register(Register::WHO_AM_I)?;
+        if !self.chip_id_check.accepts(who_am_i) {
+            return Err(Mpu6050Error::InvalidChipId(who_am_i));
         }
+        self.chip_id = Some(who_am_i);
         Ok(())
     }
 
+    /// Connection state derived from consecutive bus errors
+    pub fn connection_state(&self) -> ConnectionState {
+        self.connection.state()
+    }
+
+    /// Connection monitor, to change threshold or auto mode at runtime
+    pub fn connection_monitor(&mut self) -> &mut ConnectionMonitor {
+        &mut self.connection
+    }
+
+    pub(crate) fn try_reconnect_using(
+        &mut self,
+        delay: &mut impl DelayMs<u8>,
+    ) -> Result<ReconnectOutcome, Mpu6050Error<E>> {
+        self.connection.begin_reconnect();
+        let res = self.reconnect(delay);
+        self.connection.end_reconnect(res.is_ok());
+        match &res {
+            Ok(outcome) => {
+                self.break_continuity(ContinuityBreak::Reconnect);
+                self.emit_event(metrics::RECONNECTS, MetricEvent::Reconnected(*outcome))
+            }
+            Err(error) => self.emit_failure(FailedOperation::Reconnect, error),
+        }
+        res
+    }
+
+    fn reconnect(
+        &mut self,
+        delay: &mut impl DelayMs<u8>,
+    ) -> Result<ReconnectOutcome, Mpu6050Error<E>> {
+        let found = self.read_register(Register::WHO_AM_I)?;
+        if let Some(previous) = self.chip_id {
+            if previous != found {
+                return Ok(ReconnectOutcome::DifferentChip { previous, found });
+            }
+        }
+
+        // as written before the disconnect, the writes below record again
+        let shadow = self.shadow;
+        self.wake(delay)?;
+        self.verify()?;
+        self.set_accel_range(self.accel_range)?;
+        self.set_gyro_range(self.gyro_range)?;
+        self.write_dlpf_cfg(self.dlpf_cfg)?;
+        self.write_sample_rate_div(self.sample_rate_div)?;
+        if self.cycle.is_some() {
+            self.write_power_mode(self.cycle, false)?;
+        }
+        for reg in RESTORED_REGISTERS {
+            match (reg, shadow.get(reg)) {
+                (_, None) => {}
+                (Register::ACCEL_CONFIG, Some(byte)) => {
+                    let hpf = RegisterValue::<ACCEL_CONFIG>::from_byte(byte)
+                        .field(ACCEL_CONFIG::ACCEL_HPF_FIELD);
+                    self.set_accel_hpf(ACCEL_HPF::from(hpf))?;
+                }
+                (reg, Some(byte)) => self.write_register(reg, byte)?,
+            }
+        }
+        Ok(ReconnectOutcome::SameChip { chip_id: found })
+    }
+
     /// setup motion detection
     /// sources:
     /// * https://github.com/kriswiner/MPU6050/blob/a7e0c8ba61a56c5326b2bcd64bc81ab72ee4616b/MPU6050IMU.ino#L486
     /// * https://arduino.stackexchange.com/a/48430
-    pub fn setup_motion_detection(&mut self) -> Result<(), Mpu6050Error<E>> {
-        self.write_byte(0x6B, 0x00)?;
+    ///
+    /// Only the fields involved are written, ranges, self-test bits, clock source and other
+    /// interrupt enables are left as they are. The INT pin is adapted to the board
+    /// constraints, see [`board`], [`configure_int_pin`](Self::configure_int_pin) afterwards
+    /// sets another one.
+    pub fn setup_motion_detection(
+        &mut self,
+        config: MotionDetectionConfig,
+    ) -> Result<(), Mpu6050Error<E>> {
+        self.set_sleep_enabled(false)?;
         // optional? self.write_byte(0x68, 0x07)?; // Reset all internal signal paths in the MPU-6050 by writing 0x07 to register 0x68;
-        self.write_byte(INT_PIN_CFG::ADDR, 0x20)?; //write register 0x37 to select how to use the interrupt pin. For an active high, push-pull signal that stays until register (decimal) 58 is read, write 0x20.
-        self.write_byte(ACCEL_CONFIG::ADDR, 0x01)?; //Write register 28 (==0x1C) to set the Digital High Pass Filter, bits 3:0. For example set it to 0x01 for 5Hz. (These 3 bits are grey in the data sheet, but they are used! Leaving them 0 means the filter always outputs 0.)
-        self.write_byte(MOT_THR, 10)?; //Write the desired Motion threshold to register 0x1F (For example, write decimal 20).
-        self.write_byte(MOT_DUR, 40)?; //Set motion detect duration to 1  ms; LSB is 1 ms @ 1 kHz rate
-        self.write_byte(0x69, 0x15)?; //to register 0x69, write the motion detection decrement and a few other settings (for example write 0x15 to set both free-fall and motion decrements to 1 and accelerometer start-up delay to 5ms total by adding 1ms. )
-        self.write_byte(INT_ENABLE::ADDR, 0x40)?; //write register 0x38, bit 6 (0x40), to enable motion detection interrupt.
+        // INT pin active high, push-pull, latched until INT_STATUS is read, as the board allows
+        if let Some(config) = self.resolve_int_pin(IntPinRequest::MotionDetection)? {
+            self.write_int_pin_config(config)?;
+        }
+        // Digital High Pass Filter at 5Hz. Leaving it at 0 means the filter always outputs 0
+        self.set_accel_hpf(ACCEL_HPF::_5)?;
+        self.write_register(Register::MOT_THR, config.threshold)?;
+        // LSB is 1 ms @ 1 kHz rate
+        self.write_register(Register::MOT_DUR, config.duration)?;
+        // free-fall and motion decrements of 1, accelerometer start-up delay of 5ms total by adding 1ms
+        for (field, value) in [
+            (MOT_DETECT_CONTROL::ACCEL_ON_DELAY_FIELD, 1),
+            (MOT_DETECT_CONTROL::FF_COUNT_FIELD, 1),
+            (MOT_DETECT_CONTROL::MOT_COUNT_FIELD, 1),
+        ] {
+            self.write_field(field, value)?;
+        }
+        self.write_field(INT_ENABLE::MOT_EN_FIELD, true)?;
         Ok(())
     }
 
     /// get whether or not motion has been detected (INT_STATUS, MOT_INT)
     pub fn get_motion_detected(&mut self) -> Result<bool, Mpu6050Error<E>> {
-        Ok(self.read_bit(INT_STATUS::ADDR, INT_STATUS::MOT_INT)? != 0)
+        Ok(self.get_int_status()?.contains(InterruptSource::Motion))
     }
 
     /// set accel high pass filter mode
     pub fn set_accel_hpf(&mut self, mode: ACCEL_HPF) -> Result<(), Mpu6050Error<E>> {
-        Ok(self.write_bits(
-            ACCEL_CONFIG::ADDR,
-            ACCEL_CONFIG::ACCEL_HPF.bit,
-            ACCEL_CONFIG::ACCEL_HPF.length,
-            mode as u8,
-        )?)
+        self.write_field(ACCEL_CONFIG::ACCEL_HPF_FIELD, mode)?;
+        self.settle.trigger(SettleTrigger::AccelHpf);
+        Ok(())
     }
 
     /// get accel high pass filter mode
     pub fn get_accel_hpf(&mut self) -> Result<ACCEL_HPF, Mpu6050Error<E>> {
-        let mode: u8 = self.read_bits(
-            ACCEL_CONFIG::ADDR,
-            ACCEL_CONFIG::ACCEL_HPF.bit,
-            ACCEL_CONFIG::ACCEL_HPF.length,
-        )?;
+        let mode: u8 = self.read_field(ACCEL_CONFIG::ACCEL_HPF_FIELD)?;
 
         Ok(ACCEL_HPF::from(mode))
     }
 
     /// Set gyro range, and update sensitivity accordingly
     pub fn set_gyro_range(&mut self, range: GyroRange) -> Result<(), Mpu6050Error<E>> {
-        self.write_bits(
-            GYRO_CONFIG::ADDR,
-            GYRO_CONFIG::FS_SEL.bit,
-            GYRO_CONFIG::FS_SEL.length,
-            range as u8,
-        )?;
-
-        self.gyro_sensitivity = range.sensitivity();
+        self.write_field(GYRO_CONFIG::FS_SEL_FIELD, range)?;
+
+        self.gyro_range = range;
+        self.gyro_scale.nominal = range.sensitivity();
+        self.bump_epoch(ConfigChange::GyroRange);
+        self.synced.gyro_range = Some(self.io_stats.transactions);
+        self.settle.trigger(SettleTrigger::Range);
         Ok(())
     }
 
     /// get current gyro range
     pub fn get_gyro_range(&mut self) -> Result<GyroRange, Mpu6050Error<E>> {
-        let byte = self.read_bits(
-            GYRO_CONFIG::ADDR,
-            GYRO_CONFIG::FS_SEL.bit,
-            GYRO_CONFIG::FS_SEL.length,
-        )?;
+        let byte = self.read_field(GYRO_CONFIG::FS_SEL_FIELD)?;
 
         Ok(GyroRange::from(byte))
     }
 
     /// set accel range, and update sensitivy accordingly
     pub fn set_accel_range(&mut self, range: AccelRange) -> Result<(), Mpu6050Error<E>> {
-        self.write_bits(
-            ACCEL_CONFIG::ADDR,
-            ACCEL_CONFIG::FS_SEL.bit,
-            ACCEL_CONFIG::FS_SEL.length,
-            range as u8,
-        )?;
-
-        self.acc_sensitivity = range.sensitivity();
+        self.write_field(ACCEL_CONFIG::FS_SEL_FIELD, range)?;
+
+        self.accel_range = range;
+        self.acc_scale.nominal = range.sensitivity();
+        self.bump_epoch(ConfigChange::AccelRange);
+        self.synced.accel_range = Some(self.io_stats.transactions);
+        self.settle.trigger(SettleTrigger::Range);
         Ok(())
     }
 
     /// get current accel_range
     pub fn get_accel_range(&mut self) -> Result<AccelRange, Mpu6050Error<E>> {
-        let byte = self.read_bits(
-            ACCEL_CONFIG::ADDR,
-            ACCEL_CONFIG::FS_SEL.bit,
-            ACCEL_CONFIG::FS_SEL.length,
-        )?;
+        let byte = self.read_field(ACCEL_CONFIG::FS_SEL_FIELD)?;
 
         Ok(AccelRange::from(byte))
     }
 
-    /// reset device
-    pub fn reset_device<D: DelayMs<u8>>(&mut self, delay: &mut D) -> Result<(), Mpu6050Error<E>> {
-        self.write_bit(PWR_MGMT_1::ADDR, PWR_MGMT_1::DEVICE_RESET, true)?;
+    pub(crate) fn reset_device_using(
+        &mut self,
+        delay: &mut impl DelayMs<u8>,
+    ) -> Result<(), Mpu6050Error<E>> {
+        self.write_field(PWR_MGMT_1::DEVICE_RESET_FIELD, true)?;
         delay.delay_ms(100u8);
-        // Note: Reset sets sleep to true! Section register map: resets PWR_MGMT to 0x40
+        self.enforce_int_pin_constraint()?;
+        // Note: Reset sets sleep to true! PWR_MGMT_1 resets to reset_value(PWR_MGMT_1::ADDR)
+        self.accel_range = AccelRange::from(
+            RegisterValue::<ACCEL_CONFIG>::reset().field(ACCEL_CONFIG::FS_SEL_FIELD),
+        );
+        self.gyro_range =
+            GyroRange::from(RegisterValue::<GYRO_CONFIG>::reset().field(GYRO_CONFIG::FS_SEL_FIELD));
+        self.dlpf_cfg = RegisterValue::<CONFIG>::reset().field(CONFIG::DLPF_CFG_FIELD);
+        self.sample_rate_div = reset_value(SMPLRT_DIV).unwrap_or(0);
+        self.cycle = None;
+        self.shadow.clear();
+        self.power.asleep = true;
+        self.power.temp_enabled = true;
+        self.break_continuity(ContinuityBreak::Reset);
+        self.fifo_sources = FifoSources::NONE;
+        self.fifo_streaming = false;
+        if let Some(state) = self.interleave.take() {
+            self.settling_policy = state.restore_policy;
+        }
+        if let Some(ranger) = self.impact.take() {
+            self.settling_policy = ranger.restore_policy();
+        }
+        self.bump_epoch(ConfigChange::Reset);
+        self.interrupt_tracker.reset();
+        self.settle.trigger(SettleTrigger::Reset);
+        let op = Some(self.io_stats.transactions);
+        self.synced = SyncPoints {
+            accel_range: op,
+            gyro_range: op,
+            dlpf_cfg: op,
+            sample_rate_div: op,
+        };
+        self.acc_scale.nominal = self.accel_range.sensitivity();
+        self.gyro_scale.nominal = self.gyro_range.sensitivity();
         Ok(())
     }
 
     /// enable, disable sleep of sensor
     pub fn set_sleep_enabled(&mut self, enable: bool) -> Result<(), Mpu6050Error<E>> {
-        Ok(self.write_bit(PWR_MGMT_1::ADDR, PWR_MGMT_1::SLEEP, enable)?)
+        self.write_field(PWR_MGMT_1::SLEEP_FIELD, enable)?;
+        if self.power.asleep != enable {
+            self.break_continuity(match enable {
+                true => ContinuityBreak::Sleep,
+                false => ContinuityBreak::Wake,
+            });
+        }
+        self.power.asleep = enable;
+        if enable {
+            log_debug!(POWER, "asleep");
+        } else {
+            log_debug!(POWER, "awake");
+            self.settle.trigger(SettleTrigger::Wake);
+        }
+        Ok(())
     }
 
     /// get sleep status
     pub fn get_sleep_enabled(&mut self) -> Result<bool, Mpu6050Error<E>> {
-        Ok(self.read_bit(PWR_MGMT_1::ADDR, PWR_MGMT_1::SLEEP)? != 0)
+        self.read_flag(PWR_MGMT_1::SLEEP_FIELD)
     }
 
     /// enable, disable temperature measurement of sensor
     /// TEMP_DIS actually saves "disabled status"
     /// 1 is disabled! -> enable=true : bit=!enable
     pub fn set_temp_enabled(&mut self, enable: bool) -> Result<(), Mpu6050Error<E>> {
-        Ok(self.write_bit(PWR_MGMT_1::ADDR, PWR_MGMT_1::TEMP_DIS, !enable)?)
+        self.write_field(PWR_MGMT_1::TEMP_DIS_FIELD, !enable)?;
+        self.power.temp_enabled = enable;
+        Ok(())
     }
 
     /// get temperature sensor status
     /// TEMP_DIS actually saves "disabled status"
     /// 1 is disabled! -> 1 == 0 : false, 0 == 0 : true
     pub fn get_temp_enabled(&mut self) -> Result<bool, Mpu6050Error<E>> {
-        Ok(self.read_bit(PWR_MGMT_1::ADDR, PWR_MGMT_1::TEMP_DIS)? == 0)
+        Ok(!self.read_flag(PWR_MGMT_1::TEMP_DIS_FIELD)?)
     }
 
     /// set accel x self test
     pub fn set_accel_x_self_test(&mut self, enable: bool) -> Result<(), Mpu6050Error<E>> {
-        Ok(self.write_bit(ACCEL_CONFIG::ADDR, ACCEL_CONFIG::XA_ST, enable)?)
+        self.write_field(ACCEL_CONFIG::XA_ST_FIELD, enable)?;
+        self.settle.trigger(SettleTrigger::SelfTest);
+        Ok(())
     }
 
     /// get accel x self test
     pub fn get_accel_x_self_test(&mut self) -> Result<bool, Mpu6050Error<E>> {
-        Ok(self.read_bit(ACCEL_CONFIG::ADDR, ACCEL_CONFIG::XA_ST)? != 0)
+        self.read_flag(ACCEL_CONFIG::XA_ST_FIELD)
     }
 
     /// set accel y self test
     pub fn set_accel_y_self_test(&mut self, enable: bool) -> Result<(), Mpu6050Error<E>> {
-        Ok(self.write_bit(ACCEL_CONFIG::ADDR, ACCEL_CONFIG::YA_ST, enable)?)
+        self.write_field(ACCEL_CONFIG::YA_ST_FIELD, enable)?;
+        self.settle.trigger(SettleTrigger::SelfTest);
+        Ok(())
     }
 
     /// get accel y self test
     pub fn get_accel_y_self_test(&mut self) -> Result<bool, Mpu6050Error<E>> {
-        Ok(self.read_bit(ACCEL_CONFIG::ADDR, ACCEL_CONFIG::YA_ST)? != 0)
+        self.read_flag(ACCEL_CONFIG::YA_ST_FIELD)
     }
 
     /// set accel z self test
     pub fn set_accel_z_self_test(&mut self, enable: bool) -> Result<(), Mpu6050Error<E>> {
-        Ok(self.write_bit(ACCEL_CONFIG::ADDR, ACCEL_CONFIG::ZA_ST, enable)?)
+        self.write_field(ACCEL_CONFIG::ZA_ST_FIELD, enable)?;
+        self.settle.trigger(SettleTrigger::SelfTest);
+        Ok(())
     }
 
     /// get accel z self test
     pub fn get_accel_z_self_test(&mut self) -> Result<bool, Mpu6050Error<E>> {
-        Ok(self.read_bit(ACCEL_CONFIG::ADDR, ACCEL_CONFIG::ZA_ST)? != 0)
+        self.read_flag(ACCEL_CONFIG::ZA_ST_FIELD)
     }
 
     /// Roll and pitch estimation from raw accelerometer readings
     /// NOTE: no yaw! no magnetometer present on MPU6050
     /// https://www.nxp.com/docs/en/application-note/AN3461.pdf equation 28, 29
+    ///
+    /// The quaternion rotates by roll about X, then pitch about Y (XYZ convention of
+    /// [`euler`], which also converts to aerospace yaw-pitch-roll), see [`tilt::roll_pitch`]
+    /// for the sign conventions.
     pub fn get_acc_angles(&mut self) -> Result<Quat, Mpu6050Error<E>> {
         let acc = self.get_acc()?;
+        let (roll, pitch) = tilt::roll_pitch(acc);
 
-        Ok(Quat::from_euler(
-            EulerRot::XYZ,
-            acc.y.atan2((acc.x.powf(2.0) + acc.z.powf(2.0)).sqrt()),
-            (-acc.x).atan2((acc.y.powf(2.0) + acc.z.powf(2.0)).sqrt()),
-            0.0,
-        ))
+        Ok(euler::from_xyz_rpy(roll, pitch, 0.0))
     }
 
-    /// Converts 2 bytes number in 2 compliment
-    /// TODO i16?! whats 0x8000?!
-    fn read_word_2c(&self, byte: &[u8]) -> i32 {
-        let high: i32 = byte[0] as i32;
-        let low: i32 = byte[1] as i32;
-        let mut word: i32 = (high << 8) + low;
+    /// Roll and pitch estimation like [`get_acc_angles`](Self::get_acc_angles), with a trust
+    /// score derived from how far the measured magnitude is from 1g, see [`tilt`]
+    pub fn get_acc_angles_checked(&mut self) -> Result<TiltEstimate, Mpu6050Error<E>> {
+        let acc = self.get_acc()?;
+        Ok(tilt::estimate(acc, &self.tilt_thresholds))
+    }
 
-        if word >= 0x8000 {
-            word = -((65535 - word) + 1);
-        }
+    /// set thresholds used to classify tilt trust
+    pub fn set_tilt_thresholds(&mut self, thresholds: TiltThresholds) {
+        self.tilt_thresholds = thresholds;
+    }
 
-        word
+    /// get thresholds used to classify tilt trust
+    pub fn get_tilt_thresholds(&self) -> TiltThresholds {
+        self.tilt_thresholds
     }
 
-    /// Reads rotation (gyro/acc) from specified register
-    fn read_rot(&mut self, reg: u8) -> Result<Vec3A, Mpu6050Error<E>> {
+    /// Reads raw rotation (gyro/acc) counts from specified register
+    fn read_rot_raw(&mut self, reg: Register) -> Result<[i16; 3], Mpu6050Error<E>> {
         let mut buf: [u8; 6] = [0; 6];
-        self.read_bytes(reg, &mut buf)?;
+        self.read_registers(reg, &mut buf)?;
+        Ok(frame::triplet(&buf))
+    }
 
-        Ok(Vec3A::new(
-            self.read_word_2c(&buf[0..2]) as f32,
-            self.read_word_2c(&buf[2..4]) as f32,
-            self.read_word_2c(&buf[4..6]) as f32,
-        ))
+    /// Raw accelerometer counts, no scaling or offsets
+    pub fn get_acc_raw(&mut self) -> Result<[i16; 3], Mpu6050Error<E>> {
+        self.read_rot_raw(Register::ACCEL_XOUT_H)
     }
 
-    /// Accelerometer readings in g
-    pub fn get_acc(&mut self) -> Result<Vec3A, Mpu6050Error<E>> {
-        let mut acc = self.read_rot(ACC_REGX_H)?;
-        acc /= self.acc_sensitivity;
+    /// Raw gyro counts, no scaling or offsets
+    pub fn get_gyro_raw(&mut self) -> Result<[i16; 3], Mpu6050Error<E>> {
+        self.read_rot_raw(Register::GYRO_XOUT_H)
+    }
 
-        Ok(acc + self.acc_offset)
+    /// Raw TEMP_OUT counts, for [`calibrate_temp_two_point`](Mpu6050::calibrate_temp_two_point)
+    pub fn get_temp_raw(&mut self) -> Result<i16, Mpu6050Error<E>> {
+        self.read_register_word(Register::TEMP_OUT_H)
     }
 
-    /// Gyro readings in rad/s
+    /// Raw accel, temperature and gyro counts of one instant, one 14 byte read of
+    /// ACCEL_XOUT_H..GYRO_ZOUT_L. No scaling, offsets or frame checks, like
+    /// [`get_acc_raw`](Self::get_acc_raw). [`Pipeline::sample`] scales the frame as the driver
+    /// would
+    pub fn get_all_raw(&mut self) -> Result<frame::RawFrame, Mpu6050Error<E>> {
+        let mut buf = [0; frame::FRAME_LEN];
+        self.read_registers(Register::ACCEL_XOUT_H, &mut buf)?;
+        Ok(frame::parse_frame(&buf))
+    }
+
+    /// Checks whether a bus at `bus_hz` can sustain reading accel, gyro and temperature at the
+    /// output data rate of the cached DLPF and sample rate divider, reserving
+    /// [`DEFAULT_BUS_OVERHEAD`](bus::DEFAULT_BUS_OVERHEAD) of the bus. See [`bus`] for the model
+    pub fn validate_rate_against_bus(&self, bus_hz: u32) -> Result<(), RateTooHigh> {
+        bus::check_rate(bus_hz, self.dlpf_cfg, self.sample_rate_div)
+    }
+
+    /// Accelerometer readings in g, scaled but without offset applied
+    fn read_acc_uncorrected(&mut self) -> Result<Vec3A, Mpu6050Error<E>> {
+        let raw = self.read_rot_raw(Register::ACCEL_XOUT_H)?;
+        self.emit_clipped(Sensor::Accel, raw);
+        Ok(scale::acc_uncorrected(raw, &self.acc_scale))
+    }
+
+    /// Gyro readings in rad/s, scaled but without offset applied
+    fn read_gyro_uncorrected(&mut self) -> Result<Vec3A, Mpu6050Error<E>> {
+        let raw = self.read_rot_raw(Register::GYRO_XOUT_H)?;
+        self.emit_clipped(Sensor::Gyro, raw);
+        Ok(scale::gyro_uncorrected(raw, &self.gyro_scale))
+    }
+
+    /// Accelerometer readings in g, subject to the [`SettlingPolicy`]
+    ///
+    /// At rest the accelerometer measures the reaction to gravity: flat on a table, Z up,
+    /// reads +1g on Z. Same scaling as [`Pipeline`], which runs without a device:
+    /// ```
+    /// use mpu6050::{device::*, frame::parse_frame, scale::Pipeline, synthetic, Vec3A};
+    ///
+    /// let bytes = synthetic::frame_bytes(Vec3A::Z, Vec3A::ZERO, AccelRange::G2, GyroRange::D250);
+    /// let acc = Pipeline::new(AccelRange::G2, GyroRange::D250).acc(parse_frame(&bytes).acc);
+    /// assert!((acc - Vec3A::new(0., 0., 1.)).length() < 1e-3);
+    /// ```
+    pub fn get_acc(&mut self) -> Result<Vec3A, Mpu6050Error<E>> {
+        self.read_acc_scaled()
+    }
+
+    /// Gyro readings in rad/s, subject to the [`SettlingPolicy`]
+    ///
+    /// Rates are right-handed: rotating counterclockwise about +Z viewed from above (Z up)
+    /// is a positive Z rate.
+    /// ```
+    /// use mpu6050::{device::*, frame::parse_frame, scale::Pipeline, synthetic, Vec3A};
+    ///
+    /// // 90 °/s counterclockwise, seen from above
+    /// let rate = Vec3A::new(0., 0., 90.);
+    /// let bytes = synthetic::frame_bytes(Vec3A::Z, rate, AccelRange::G2, GyroRange::D250);
+    /// let gyro = Pipeline::new(AccelRange::G2, GyroRange::D250).gyro(parse_frame(&bytes).gyro);
+    /// assert!((gyro.z - core::f32::consts::FRAC_PI_2).abs() < 1e-3);
+    /// ```
     pub fn get_gyro(&mut self) -> Result<Vec3A, Mpu6050Error<E>> {
-        let mut gyro = self.read_rot(GYRO_REGX_H)?;
+        self.read_gyro_scaled()
+    }
+
+    /// set what scaled reads do with samples taken while the signal path settles
+    pub fn set_settling_policy(&mut self, policy: SettlingPolicy) {
+        self.settling_policy = policy;
+    }
+
+    /// get settling policy
+    pub fn get_settling_policy(&self) -> SettlingPolicy {
+        self.settling_policy
+    }
+
+    /// remaining settling samples per sensor
+    pub fn settle_countdown(&self) -> SettleCountdown {
+        self.settle
+    }
+
+    /// bus usage since construction or the last [`reset_io_stats`](Self::reset_io_stats),
+    /// compare with [`op_bounds`]
+    pub fn io_stats(&self) -> IoStats {
+        self.io_stats
+    }
+
+    /// reset bus usage counters
+    pub fn reset_io_stats(&mut self) {
+        self.io_stats = IoStats::default();
+    }
+
+    /// Scaling, offsets and per-axis factors currently applied, for processing recorded frames
+    /// exactly like the driver does
+    pub fn pipeline(&self) -> Pipeline {
+        Pipeline {
+            acc_scale: self.acc_scale,
+            gyro_scale: self.gyro_scale,
+            acc_offset: self.acc_offset,
+            gyro_offset: self.gyro_offset,
+            stages: PipelineStages {
+                temp: self.temp_model(),
+                ..PipelineStages::NATIVE
+            },
+        }
+    }
+
+    /// Resolution and expected noise of the active ranges and DLPF setting
+    pub fn current_resolution(&self) -> ResolutionInfo {
+        ResolutionInfo::new(self.accel_range, self.gyro_range, self.dlpf_cfg)
+    }
+
+    /// set per-axis accelerometer scale factors, kept across range changes
+    pub fn set_accel_scale_factors(&mut self, factors: Vec3A) {
+        self.acc_scale.per_axis = factors;
+        self.bump_epoch(ConfigChange::ScaleFactors);
+    }
 
-        gyro *= PI_180 / self.gyro_sensitivity;
+    /// set per-axis gyro scale factors, kept across range changes
+    pub fn set_gyro_scale_factors(&mut self, factors: Vec3A) {
+        self.gyro_scale.per_axis = factors;
+        self.bump_epoch(ConfigChange::ScaleFactors);
+    }
+
+    /// get accelerometer scale model
+    pub fn get_accel_scale(&self) -> ScaleModel {
+        self.acc_scale
+    }
+
+    /// get gyro scale model
+    pub fn get_gyro_scale(&self) -> ScaleModel {
+        self.gyro_scale
+    }
+
+    /// get accelerometer offset in g subtracted from every reading, see [`calibration`]
+    pub fn get_acc_offset(&self) -> Vec3A {
+        self.acc_offset
+    }
+
+    /// get gyro offset in rad/s subtracted from every reading, see [`calibration`]
+    pub fn get_gyro_offset(&self) -> Vec3A {
+        self.gyro_offset
+    }
+
+    /// Installs a supervisor checking every scaled read against the limits, see [`supervisor`].
+    /// Replaces a previous supervisor and its latch
+    pub fn set_supervisor(&mut self, config: SupervisorConfig) {
+        self.supervisor = Some(Supervisor::new(config));
+    }
+
+    /// Removes the supervisor
+    pub fn remove_supervisor(&mut self) {
+        self.supervisor = None;
+    }
+
+    /// Latched supervisor event, None if not tripped or no supervisor installed
+    pub fn supervisor_tripped(&self) -> Option<SupervisorEvent> {
+        self.supervisor.as_ref().and_then(Supervisor::tripped)
+    }
+
+    /// Clears the supervisor latch
+    pub fn clear_supervisor(&mut self) {
+        if let Some(supervisor) = &mut self.supervisor {
+            supervisor.clear();
+        }
+    }
+
+    /// Accelerometer readings in g, converted into any vector type constructible from
+    /// `[x, y, z]`, e.g. `nalgebra::Vector3<f32>`
+    pub fn get_acc_as<V: From<[f32; 3]>>(&mut self) -> Result<V, Mpu6050Error<E>> {
+        Ok(V::from(self.get_acc()?.to_array()))
+    }
+
+    /// Gyro readings in rad/s, converted into any vector type constructible from `[x, y, z]`
+    pub fn get_gyro_as<V: From<[f32; 3]>>(&mut self) -> Result<V, Mpu6050Error<E>> {
+        Ok(V::from(self.get_gyro()?.to_array()))
+    }
 
-        Ok(gyro + self.gyro_offset)
+    /// Roll and pitch estimation like [`get_acc_angles`](Self::get_acc_angles), converted into
+    /// any quaternion type constructible from `[x, y, z, w]` (vector part first, scalar last).
+    ///
+    /// This is the layout of both glam's `Quat::to_array` and nalgebra's
+    /// `Quaternion::from([f32; 4])`, so `UnitQuaternion::new_unchecked(q)` is a plain copy.
+    /// No normalization is done. Note that nalgebra's `Quaternion::new(w, i, j, k)` constructor
+    /// takes the scalar FIRST, do not feed it the array elements in order.
+    pub fn get_acc_angles_as<Q: From<[f32; 4]>>(&mut self) -> Result<Q, Mpu6050Error<E>> {
+        Ok(Q::from(self.get_acc_angles()?.to_array()))
     }
 
     /// Sensor Temp in degrees celcius
     pub fn get_temp(&mut self) -> Result<f32, Mpu6050Error<E>> {
-        let mut buf: [u8; 2] = [0; 2];
-        self.read_bytes(TEMP_OUT_H, &mut buf)?;
-        let raw_temp = self.read_word_2c(&buf[0..2]) as f32;
+        self.read_temp_scaled()
+    }
+
+    /// Accel in g, gyro in rad/s and temperature of one sample instant, read in one burst
+    ///
+    /// One transaction of 14 bytes instead of the three of [`get_acc`](Self::get_acc),
+    /// [`get_gyro`](Self::get_gyro) and [`get_temp`](Self::get_temp), 390 µs instead of
+    /// 540 µs at 400 kHz, see [`batch`](crate::batch). Scaled and offset the same way. The
+    /// sample is the one [`read_batch`](Self::read_batch) would return: a settling sample is
+    /// flagged, not read again, and the frame goes through the [parse policy](crate::parse_policy)
+    pub fn get_all(&mut self) -> Result<MpuSample, Mpu6050Error<E>> {
+        self.read_sample_burst()
+    }
 
-        // According to revision 4.2
-        Ok((raw_temp / TEMP_SENSITIVITY) + TEMP_OFFSET)
+    /// Sensor temperature in degrees celsius, before the [`hook`]
+    pub(crate) fn read_temp(&mut self) -> Result<f32, Mpu6050Error<E>> {
+        let raw = self.read_register_word(Register::TEMP_OUT_H)?;
+        Ok(self.temp_model().celsius(raw))
     }
 
-    /// Writes byte to register
+    /// bookkeeping of an attempted transaction of `bytes` bytes at `reg`: operation
+    /// counters, connection monitor, metrics, lockup detection
+    fn record_transaction(&mut self, reg: u8, bytes: u32, ok: bool) {
+        self.io_stats.record(bytes);
+        let connected = self.connection.state() == ConnectionState::Connected;
+        self.connection.record(ok);
+        self.emit_counter(metrics::BUS_TRANSACTIONS, 1);
+        self.emit_counter(metrics::BUS_BYTES, bytes);
+        if ok {
+            log_trace!(
+                self,
+                Transactions,
+                BUS,
+                "{} (0x{:02x}): {} bytes on the bus, ok",
+                register_name(reg),
+                reg,
+                bytes
+            );
+        } else {
+            log_trace!(
+                self,
+                Failures,
+                BUS,
+                "{} (0x{:02x}): {} bytes on the bus, failed",
+                register_name(reg),
+                reg,
+                bytes
+            );
+            self.failed_register = Some(reg);
+            self.emit_counter(metrics::BUS_ERRORS, 1);
+            if connected && self.connection.state() != ConnectionState::Connected {
+                self.emit_event(metrics::DISCONNECTS, MetricEvent::Disconnected);
+            }
+        }
+        self.watch_for_lockup(ok);
+    }
+
+    /// charges a transaction of `op` to the guarded section, see [`traffic_guard`]
+    #[inline(always)]
+    fn guard_traffic(&mut self, op: DriverOp) {
+        if self.traffic.admit(op) {
+            self.emit_counter(metrics::TRAFFIC_VIOLATIONS, 1);
+        }
+    }
+
+    /// Writes byte to register. Raw address escape hatch, unchecked: prefer
+    /// [`write_register`](Self::write_register)
+    #[doc(hidden)]
     pub fn write_byte(&mut self, reg: u8, byte: u8) -> Result<(), Mpu6050Error<E>> {
-        self.i2c
-            .write(self.slave_addr, &[reg, byte])
-            .map_err(Mpu6050Error::I2c)?;
+        self.check_active()?;
+        if !self.connection.should_attempt() {
+            return Err(Mpu6050Error::Disconnected);
+        }
+        self.guard_traffic(DriverOp::ConfigWrite);
+        let res = self.i2c.write(self.slave_addr, &[reg, byte]);
+        self.record_transaction(reg, op_bounds::write_cost_bytes(1), res.is_ok());
+        res.map_err(Mpu6050Error::I2c)?;
+        self.shadow.record(reg, byte);
         // delay disabled for dev build
         // TODO: check effects with physical unit
         // self.delay.delay_ms(10u8);
         Ok(())
     }
 
-    /// Enables bit n at register address reg
+    /// Writes the 16 bit `value` to the register pair starting at `reg_h`, high byte first, in
+    /// one transaction: the chip increments the register address, the pair is never half
+    /// written. See [`REGISTER_PAIRS`]. Raw address escape hatch, unchecked: prefer
+    /// [`write_register_word`](Self::write_register_word)
+    #[doc(hidden)]
+    pub fn write_word(&mut self, reg_h: u8, value: i16) -> Result<(), Mpu6050Error<E>> {
+        self.check_active()?;
+        if !self.connection.should_attempt() {
+            return Err(Mpu6050Error::Disconnected);
+        }
+        self.guard_traffic(DriverOp::ConfigWrite);
+        let [high, low] = value.to_be_bytes();
+        let res = self.i2c.write(self.slave_addr, &[reg_h, high, low]);
+        self.record_transaction(reg_h, op_bounds::write_cost_bytes(2), res.is_ok());
+        res.map_err(Mpu6050Error::I2c)?;
+        Ok(())
+    }
+
+    /// Enables bit n at register address reg. Raw address escape hatch, unchecked: prefer
+    /// [`write_register_bit`](Self::write_register_bit)
+    #[doc(hidden)]
+    #[deprecated(
+        since = "0.2.0",
+        note = "use `write_register_bit`, or `write_byte` for a raw address; removed in 0.3.0"
+    )]
     pub fn write_bit(&mut self, reg: u8, bit_n: u8, enable: bool) -> Result<(), Mpu6050Error<E>> {
-        let mut byte: [u8; 1] = [0; 1];
-        self.read_bytes(reg, &mut byte)?;
-        bits::set_bit(&mut byte[0], bit_n, enable);
-        Ok(self.write_byte(reg, byte[0])?)
+        self.as_op(DriverOp::ConfigUpdate, |mpu| {
+            let mut byte: [u8; 1] = [0; 1];
+            mpu.read_bytes(reg, &mut byte)?;
+            bits::set_bit(&mut byte[0], bit_n, enable);
+            mpu.write_byte(reg, byte[0])
+        })
     }
 
-    /// Write bits data at reg from start_bit to start_bit+length
+    /// Write bits data at reg from start_bit to start_bit+length. Raw address escape hatch,
+    /// unchecked: prefer [`write_register_field`](Self::write_register_field)
+    #[doc(hidden)]
+    #[deprecated(
+        since = "0.2.0",
+        note = "use `write_register_field`, or `write_byte` for a raw address; removed in 0.3.0"
+    )]
     pub fn write_bits(
         &mut self,
         reg: u8,
@@ -495,40 +1797,70 @@ where
         length: u8,
         data: u8,
     ) -> Result<(), Mpu6050Error<E>> {
-        let mut byte: [u8; 1] = [0; 1];
-        self.read_bytes(reg, &mut byte)?;
-        bits::set_bits(&mut byte[0], start_bit, length, data);
-        Ok(self.write_byte(reg, byte[0])?)
-    }
-
-    /// Read bit n from register
-    fn read_bit(&mut self, reg: u8, bit_n: u8) -> Result<u8, Mpu6050Error<E>> {
-        let mut byte: [u8; 1] = [0; 1];
-        self.read_bytes(reg, &mut byte)?;
-        Ok(bits::get_bit(byte[0], bit_n))
+        self.as_op(DriverOp::ConfigUpdate, |mpu| {
+            let mut byte: [u8; 1] = [0; 1];
+            mpu.read_bytes(reg, &mut byte)?;
+            bits::set_bits(&mut byte[0], start_bit, length, data);
+            mpu.write_byte(reg, byte[0])
+        })
     }
 
-    /// Read bits at register reg, starting with bit start_bit, until start_bit+length
+    /// Read bits at register reg, starting with bit start_bit, until start_bit+length. Raw
+    /// address escape hatch, unchecked: prefer [`read_register_field`](Self::read_register_field)
+    #[doc(hidden)]
+    #[deprecated(
+        since = "0.2.0",
+        note = "use `read_register_field`, or `read_byte` for a raw address; removed in 0.3.0"
+    )]
     pub fn read_bits(&mut self, reg: u8, start_bit: u8, length: u8) -> Result<u8, Mpu6050Error<E>> {
         let mut byte: [u8; 1] = [0; 1];
         self.read_bytes(reg, &mut byte)?;
         Ok(bits::get_bits(byte[0], start_bit, length))
     }
 
-    /// Reads byte from register
+    /// Reads byte from register. Raw address escape hatch, unchecked: prefer
+    /// [`read_register`](Self::read_register)
+    #[doc(hidden)]
     pub fn read_byte(&mut self, reg: u8) -> Result<u8, Mpu6050Error<E>> {
         let mut byte: [u8; 1] = [0; 1];
-        self.i2c
-            .write_read(self.slave_addr, &[reg], &mut byte)
-            .map_err(Mpu6050Error::I2c)?;
+        self.read_bytes(reg, &mut byte)?;
         Ok(byte[0])
     }
 
-    /// Reads series of bytes into buf from specified reg
+    /// Reads the 16 bit value of the register pair starting at `reg_h` in one transaction,
+    /// see [`write_word`](Self::write_word). Raw address escape hatch, unchecked: prefer
+    /// [`read_register_word`](Self::read_register_word)
+    #[doc(hidden)]
+    pub fn read_word(&mut self, reg_h: u8) -> Result<i16, Mpu6050Error<E>> {
+        let mut buf = [0; 2];
+        self.read_bytes(reg_h, &mut buf)?;
+        Ok(i16::from_be_bytes(buf))
+    }
+
+    /// Reads series of bytes into buf from specified reg. Raw address escape hatch, unchecked
+    /// but for the [`DENIED_RANGES`]: prefer
+    /// [`read_registers`](Self::read_registers)
+    #[doc(hidden)]
     pub fn read_bytes(&mut self, reg: u8, buf: &mut [u8]) -> Result<(), Mpu6050Error<E>> {
-        self.i2c
-            .write_read(self.slave_addr, &[reg], buf)
-            .map_err(Mpu6050Error::I2c)?;
+        if !self.allow_undocumented {
+            if let Some(range) = device::denied_range(reg, buf.len()) {
+                return Err(Mpu6050Error::DeniedRegisterRange(range));
+            }
+        }
+        self.read_burst(reg, buf)
+    }
+
+    /// [`read_bytes`](Self::read_bytes) without the deny-list, for the driver's own reads of
+    /// documented registers
+    pub(crate) fn read_burst(&mut self, reg: u8, buf: &mut [u8]) -> Result<(), Mpu6050Error<E>> {
+        self.check_active()?;
+        if !self.connection.should_attempt() {
+            return Err(Mpu6050Error::Disconnected);
+        }
+        self.guard_traffic(traffic_guard::classify_read(reg, buf.len()));
+        let res = self.i2c.write_read(self.slave_addr, &[reg], buf);
+        self.record_transaction(reg, op_bounds::read_cost_bytes(buf.len()), res.is_ok());
+        res.map_err(Mpu6050Error::I2c)?;
         Ok(())
     }
 }
@@ -0,0 +1,127 @@
+//! I2C over Linux i2c-dev without extra dependencies.
+//!
+//! [`I2cdev`] is the I2C_RDWR ioctl through libc, which std links, behind the `embedded-hal`
+//! blocking traits: one combined transfer per [`WriteRead`], so the register pointer write
+//! and the read are one transaction with a repeated start. [`Sleep`] is the matching
+//! [`DelayMs`] over `std::thread::sleep`. The `hil` binary and
+//! `Mpu6050::from_platform_description` of `platform_config` run on them, anything taking
+//! an `embedded-hal` 0.2 bus works the same.
+//!
+//! ```no_run
+//! use mpu6050::linux::{I2cdev, Sleep};
+//! use mpu6050::Mpu6050Builder;
+//!
+//! # fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! let i2c = I2cdev::open("/dev/i2c-1")?;
+//! let mut mpu = Mpu6050Builder::new().i2c(i2c).build()?;
+//! mpu.init(&mut Sleep)?;
+//! # Ok(())
+//! # }
+//! ```
+
+use std::fs::{File, OpenOptions};
+use std::io;
+use std::os::raw::{c_int, c_ulong};
+use std::os::unix::io::AsRawFd;
+use std::path::Path;
+use std::time::Duration;
+
+use embedded_hal::blocking::delay::DelayMs;
+use embedded_hal::blocking::i2c::{Write, WriteRead};
+
+/// combined transfer, linux/i2c-dev.h
+const I2C_RDWR: c_ulong = 0x0707;
+/// read message, linux/i2c.h
+const I2C_M_RD: u16 = 0x0001;
+
+/// struct i2c_msg
+#[repr(C)]
+struct I2cMsg {
+    addr: u16,
+    flags: u16,
+    len: u16,
+    buf: *mut u8,
+}
+
+/// struct i2c_rdwr_ioctl_data
+#[repr(C)]
+struct RdwrData {
+    msgs: *mut I2cMsg,
+    nmsgs: u32,
+}
+
+extern "C" {
+    fn ioctl(fd: c_int, request: c_ulong, ...) -> c_int;
+}
+
+/// An i2c-dev bus, `/dev/i2c-N`
+#[derive(Debug)]
+pub struct I2cdev {
+    file: File,
+}
+
+impl I2cdev {
+    /// Opens the bus device read and write, the address is given per transaction
+    pub fn open(path: impl AsRef<Path>) -> io::Result<Self> {
+        let file = OpenOptions::new().read(true).write(true).open(path)?;
+        Ok(Self { file })
+    }
+
+    fn transfer(&mut self, msgs: &mut [I2cMsg]) -> io::Result<()> {
+        let mut data = RdwrData {
+            msgs: msgs.as_mut_ptr(),
+            nmsgs: msgs.len() as u32,
+        };
+        // SAFETY: the messages point into buffers borrowed for the duration of the call,
+        // with their lengths, as I2C_RDWR expects
+        let res = unsafe { ioctl(self.file.as_raw_fd(), I2C_RDWR, &mut data) };
+        if res < 0 {
+            Err(io::Error::last_os_error())
+        } else {
+            Ok(())
+        }
+    }
+}
+
+fn msg(address: u8, flags: u16, buf: *mut u8, len: usize) -> I2cMsg {
+    I2cMsg {
+        addr: address as u16,
+        flags,
+        len: len as u16,
+        buf,
+    }
+}
+
+impl Write for I2cdev {
+    type Error = io::Error;
+
+    fn write(&mut self, address: u8, bytes: &[u8]) -> io::Result<()> {
+        // i2c_msg takes a mutable buffer, the kernel only reads it for a write
+        let mut bytes = bytes.to_vec();
+        let len = bytes.len();
+        self.transfer(&mut [msg(address, 0, bytes.as_mut_ptr(), len)])
+    }
+}
+
+impl WriteRead for I2cdev {
+    type Error = io::Error;
+
+    fn write_read(&mut self, address: u8, bytes: &[u8], buffer: &mut [u8]) -> io::Result<()> {
+        let mut bytes = bytes.to_vec();
+        let (wlen, rlen) = (bytes.len(), buffer.len());
+        self.transfer(&mut [
+            msg(address, 0, bytes.as_mut_ptr(), wlen),
+            msg(address, I2C_M_RD, buffer.as_mut_ptr(), rlen),
+        ])
+    }
+}
+
+/// [`DelayMs`] by sleeping the calling thread
+#[derive(Copy, Clone, Debug, Default)]
+pub struct Sleep;
+
+impl DelayMs<u8> for Sleep {
+    fn delay_ms(&mut self, ms: u8) {
+        std::thread::sleep(Duration::from_millis(ms as u64));
+    }
+}
@@ -0,0 +1,600 @@
+//! Self-describing header for recorded sample logs.
+//!
+//! A [`LogHeader`] captures what is needed to interpret a recording later:
+//! the chip, every configuration register, the scaling pipeline with its offsets and
+//! per-axis factors, the driver version, and the mounting and calibration notes of the
+//! application. DLPF, ODR and noise figures are derived from the recorded configuration
+//! with the datasheet tables, see [`LogHeader::resolution`] and [`LogHeader::odr_hz`].
+//! The MPU-6050 register map documents no product revision register, WHO_AM_I is the
+//! chip identification recorded.
+//!
+//! The crate has no serde dependency, a header has a fixed binary format written before the
+//! [`packed`](crate::packed) batches of a log, and a text form for comment lines of text
+//! logs, [`LogHeader::write_comments`].
+//!
+//! | bytes | content |
+//! |:---|:---|
+//! | 1 | format version ([`LOG_HEADER_VERSION`]) |
+//! | 2 | header length including version and CRC, little endian |
+//! | n | fields, a tag byte, a length byte, then the payload |
+//! | 2 | CRC-16/CCITT-FALSE ([`crc16`]) over all previous bytes, little endian |
+//!
+//! | field | tag | payload |
+//! |:---|:---|:---|
+//! | driver version | 1 | UTF-8 |
+//! | chip | 2 | WHO_AM_I |
+//! | configuration | 3 | accel range, gyro range, DLPF_CFG, SMPLRT_DIV, cycle wake (0 off, else `LP_WAKE_CTRL` + 1) |
+//! | accel scaling | 4 | nominal, per-axis x, y, z, offset x, y, z, `f32` little endian |
+//! | gyro scaling | 5 | as accel scaling |
+//! | registers | 6 | address, value pairs |
+//! | axis map | 7 | per common axis: chip axis, bit 7 set if negated |
+//! | calibration | 8 | [`CalibrationMethod`], 1 if a timestamp follows, timestamp `u64` little endian |
+//! | temperature calibration | 9 | [`TempCalibration::to_bytes`] |
+//!
+//! The length prefixes keep the format forward compatible: decoders skip tags they do not
+//! know, newer versions only add tags. Fields 1 to 5 are required, the others default to
+//! no registers, the identity mapping, no calibration note and the MPU-6050 temperature
+//! formula. The header length makes the
+//! header self-terminating, the first batch starts right after it.
+//! ```
+//! use mpu6050::device::{AccelRange, GyroRange};
+//! use mpu6050::log_header::LogHeader;
+//! use mpu6050::packed::{decode_binary_packed, encode_binary_packed, PackedBits};
+//! use mpu6050::{MpuSample, Vec3A};
+//!
+//! let header = LogHeader::new(0x68, AccelRange::G4, GyroRange::D500);
+//! let samples = [MpuSample::new(Vec3A::Z, Vec3A::ZERO, 25.)];
+//! let mut log = [0; 256];
+//! let mut len = header.encode(&mut log).unwrap();
+//! len += encode_binary_packed(&samples, PackedBits::B12, AccelRange::G4, GyroRange::D500, &mut log[len..]).unwrap();
+//!
+//! // host side: no driver, no configuration known in advance
+//! let (decoded, start) = LogHeader::decode(&log[..len]).unwrap();
+//! assert_eq!(decoded, header);
+//! assert_eq!(decoded.accel_range, AccelRange::G4);
+//! let mut out = [MpuSample::default(); 1];
+//! decode_binary_packed(&log[start..len], &mut out).unwrap();
+//! ```
+//!
+//! The header holds strings and a register list and needs the `std` feature, without it the
+//! module has [`CalibrationMethod`] and [`CalibrationNote`] alone.
+
+// without std, only the calibration note of the headers remains
+#![cfg_attr(not(feature = "std"), allow(dead_code, unused_imports))]
+
+use core::fmt;
+
+#[cfg(feature = "driver")]
+use embedded_hal::blocking::i2c::{Write, WriteRead};
+use glam::Vec3A;
+
+use crate::aliasing;
+use crate::axis_map::AxisMap;
+use crate::config::Mpu6050Config;
+use crate::decimal::{Decimal, DecimalVec};
+use crate::device::{AccelRange, Axis, ChipVariant, GyroRange, LP_WAKE_CTRL};
+use crate::packed::{crc16, DecodeError, EncodeError};
+use crate::register::Register;
+use crate::resolution::ResolutionInfo;
+use crate::scale::{Pipeline, PipelineStages, ScaleModel, TempModel};
+use crate::temp_calibration::{TempCalibration, TEMP_CALIBRATION_LEN};
+use crate::{Mpu6050, Mpu6050Error};
+
+/// Format version written to the header
+pub const LOG_HEADER_VERSION: u8 = 1;
+/// Driver version recorded by [`Mpu6050::build_log_header`]
+pub const DRIVER_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+const TAG_VERSION: u8 = 1;
+const TAG_CHIP: u8 = 2;
+const TAG_CONFIG: u8 = 3;
+const TAG_ACC_SCALE: u8 = 4;
+const TAG_GYRO_SCALE: u8 = 5;
+const TAG_REGISTERS: u8 = 6;
+const TAG_AXIS_MAP: u8 = 7;
+const TAG_CALIBRATION: u8 = 8;
+const TAG_TEMP_CALIBRATION: u8 = 9;
+
+/// How the recorded offsets and factors were determined, as told by the application
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum CalibrationMethod {
+    /// averaged at rest
+    Static = 1,
+    /// against a reference, see [`platform`](crate::platform)
+    Referenced = 2,
+    /// converged in the background, see [`calibration`](crate::calibration)
+    Background = 3,
+    /// computed outside the driver
+    External = 4,
+}
+
+impl CalibrationMethod {
+    pub(crate) fn from_u8(method: u8) -> Option<Self> {
+        match method {
+            1 => Some(CalibrationMethod::Static),
+            2 => Some(CalibrationMethod::Referenced),
+            3 => Some(CalibrationMethod::Background),
+            4 => Some(CalibrationMethod::External),
+            _ => None,
+        }
+    }
+}
+
+/// Calibration summary provided by the application, the offsets are in the pipeline
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct CalibrationNote {
+    /// how the calibration was done
+    pub method: CalibrationMethod,
+    /// when, in a unit of the application's choice, e.g. unix seconds
+    pub timestamp: Option<u64>,
+}
+
+/// Configuration a recording was made with, see the module docs
+#[cfg(feature = "std")]
+#[derive(Clone, Debug, PartialEq)]
+pub struct LogHeader {
+    /// version of the driver that recorded
+    pub driver_version: String,
+    /// WHO_AM_I of the chip
+    pub who_am_i: u8,
+    /// accelerometer range
+    pub accel_range: AccelRange,
+    /// gyro range
+    pub gyro_range: GyroRange,
+    /// DLPF_CFG
+    pub dlpf_cfg: u8,
+    /// SMPLRT_DIV
+    pub sample_rate_div: u8,
+    /// cycle mode wake frequency, None in normal operation
+    pub cycle: Option<LP_WAKE_CTRL>,
+    /// scaling from counts to g and rad/s, offsets and per-axis factors included
+    pub pipeline: Pipeline,
+    /// configuration registers, empty if not recorded
+    pub registers: Vec<(u8, u8)>,
+    /// mounting of the chip, set by the application
+    pub axis_map: AxisMap,
+    /// calibration summary, set by the application
+    pub calibration: Option<CalibrationNote>,
+    /// temperature model the recording was converted with, None if not recorded.
+    /// `pipeline` converts with it
+    pub temp_calibration: Option<TempCalibration>,
+}
+
+#[cfg(feature = "std")]
+impl LogHeader {
+    /// header of this driver version with nominal scaling at the given ranges, the reset
+    /// filter and rate configuration and nothing else recorded
+    pub fn new(who_am_i: u8, accel_range: AccelRange, gyro_range: GyroRange) -> Self {
+        Self {
+            driver_version: DRIVER_VERSION.into(),
+            who_am_i,
+            accel_range,
+            gyro_range,
+            dlpf_cfg: 0,
+            sample_rate_div: 0,
+            cycle: None,
+            pipeline: Pipeline::new(accel_range, gyro_range),
+            registers: Vec::new(),
+            axis_map: AxisMap::IDENTITY,
+            calibration: None,
+            temp_calibration: None,
+        }
+    }
+
+    /// same header with the mounting of the chip
+    pub fn with_axis_map(mut self, axis_map: AxisMap) -> Self {
+        self.axis_map = axis_map;
+        self
+    }
+
+    /// same header with a calibration summary
+    pub fn with_calibration(mut self, calibration: CalibrationNote) -> Self {
+        self.calibration = Some(calibration);
+        self
+    }
+
+    /// same header with the temperature model, applied to the pipeline
+    pub fn with_temp_calibration(mut self, calibration: TempCalibration) -> Self {
+        self.pipeline.stages.temp = calibration.model();
+        self.temp_calibration = Some(calibration);
+        self
+    }
+
+    /// chip family of the recorded WHO_AM_I
+    pub fn variant(&self) -> ChipVariant {
+        ChipVariant::from_who_am_i(self.who_am_i)
+    }
+
+    /// recorded registers as a snapshot, e.g. to diff against a device
+    pub fn config(&self) -> Mpu6050Config {
+        Mpu6050Config::from_image(&self.registers)
+    }
+
+    /// resolution, DLPF bandwidth and delay and expected noise of the recording
+    pub fn resolution(&self) -> ResolutionInfo {
+        ResolutionInfo::new(self.accel_range, self.gyro_range, self.dlpf_cfg)
+    }
+
+    /// output data rate in Hz, in normal operation
+    pub fn odr_hz(&self) -> f32 {
+        aliasing::assess(self.dlpf_cfg, self.sample_rate_div, false).odr_hz
+    }
+
+    /// bytes [`encode`](Self::encode) writes
+    pub fn encoded_len(&self) -> usize {
+        let fields = [
+            self.driver_version.len().min(255),
+            1,
+            5,
+            SCALE_LEN,
+            SCALE_LEN,
+            2 * self.registers.len().min(127),
+            3,
+        ];
+        let calibration = match self.calibration {
+            Some(CalibrationNote {
+                timestamp: Some(_), ..
+            }) => 2 + 10,
+            Some(_) => 2 + 2,
+            None => 0,
+        };
+        let temp_calibration = match self.temp_calibration {
+            Some(_) => 2 + TEMP_CALIBRATION_LEN,
+            None => 0,
+        };
+        3 + fields.iter().map(|len| 2 + len).sum::<usize>() + calibration + temp_calibration + 2
+    }
+
+    /// Encodes the header into `buf`, returns the bytes written. Driver versions over 255
+    /// bytes or more than 127 registers do not fit their fields and are truncated
+    pub fn encode(&self, buf: &mut [u8]) -> Result<usize, EncodeError> {
+        let len = self.encoded_len();
+        if buf.len() < len {
+            return Err(EncodeError::BufferTooSmall(len));
+        }
+        let mut writer = FieldWriter { buf, at: 3 };
+        let version = &self.driver_version.as_bytes()[..self.driver_version.len().min(255)];
+        writer.field(TAG_VERSION, version);
+        writer.field(TAG_CHIP, &[self.who_am_i]);
+        writer.field(
+            TAG_CONFIG,
+            &[
+                self.accel_range as u8,
+                self.gyro_range as u8,
+                self.dlpf_cfg,
+                self.sample_rate_div,
+                self.cycle.map_or(0, |wake| wake as u8 + 1),
+            ],
+        );
+        let p = &self.pipeline;
+        writer.field(TAG_ACC_SCALE, &scale_bytes(&p.acc_scale, p.acc_offset));
+        writer.field(TAG_GYRO_SCALE, &scale_bytes(&p.gyro_scale, p.gyro_offset));
+        let registers: Vec<u8> = self
+            .registers
+            .iter()
+            .take(127)
+            .flat_map(|(addr, value)| [*addr, *value])
+            .collect();
+        writer.field(TAG_REGISTERS, &registers);
+        let (axes, negate) = self.axis_map.axes();
+        let map = [0, 1, 2].map(|i| axes[i] as u8 | (negate[i] as u8) << 7);
+        writer.field(TAG_AXIS_MAP, &map);
+        if let Some(note) = self.calibration {
+            let mut payload = vec![note.method as u8, note.timestamp.is_some() as u8];
+            if let Some(timestamp) = note.timestamp {
+                payload.extend_from_slice(&timestamp.to_le_bytes());
+            }
+            writer.field(TAG_CALIBRATION, &payload);
+        }
+        if let Some(calibration) = self.temp_calibration {
+            writer.field(TAG_TEMP_CALIBRATION, &calibration.to_bytes());
+        }
+
+        let len = writer.at + 2;
+        let buf = writer.buf;
+        buf[0] = LOG_HEADER_VERSION;
+        buf[1..3].copy_from_slice(&(len as u16).to_le_bytes());
+        let crc = crc16(&buf[..len - 2]);
+        buf[len - 2..len].copy_from_slice(&crc.to_le_bytes());
+        Ok(len)
+    }
+
+    /// Decodes a header at the start of `buf`, without a driver. Returns the header and its
+    /// length, where the log data starts. Unknown tags are skipped
+    pub fn decode(buf: &[u8]) -> Result<(Self, usize), DecodeError> {
+        if buf.len() < 3 {
+            return Err(DecodeError::Truncated(3));
+        }
+        if buf[0] != LOG_HEADER_VERSION {
+            return Err(DecodeError::BadHeader);
+        }
+        let len = u16::from_le_bytes([buf[1], buf[2]]) as usize;
+        if len < 5 {
+            return Err(DecodeError::BadHeader);
+        }
+        if buf.len() < len {
+            return Err(DecodeError::Truncated(len));
+        }
+        let crc = u16::from_le_bytes([buf[len - 2], buf[len - 1]]);
+        if crc != crc16(&buf[..len - 2]) {
+            return Err(DecodeError::CrcMismatch);
+        }
+
+        let mut version = None;
+        let mut chip = None;
+        let mut config = None;
+        let mut acc = None;
+        let mut gyro = None;
+        let mut registers = Vec::new();
+        let mut axis_map = AxisMap::IDENTITY;
+        let mut calibration = None;
+        let mut temp_calibration = None;
+        let mut fields = &buf[3..len - 2];
+        while let [tag, field_len, rest @ ..] = fields {
+            let field_len = *field_len as usize;
+            if rest.len() < field_len {
+                return Err(DecodeError::BadHeader);
+            }
+            let (payload, next) = rest.split_at(field_len);
+            fields = next;
+            match (*tag, payload) {
+                (TAG_VERSION, payload) => {
+                    version = Some(String::from_utf8_lossy(payload).into_owned())
+                }
+                (TAG_CHIP, [who_am_i, ..]) => chip = Some(*who_am_i),
+                (TAG_CONFIG, [accel, gyro, dlpf, div, cycle, ..]) => {
+                    let cycle = cycle.checked_sub(1).map(LP_WAKE_CTRL::from);
+                    config = Some((
+                        AccelRange::from(*accel),
+                        GyroRange::from(*gyro),
+                        *dlpf,
+                        *div,
+                        cycle,
+                    ));
+                }
+                (TAG_ACC_SCALE, payload) => acc = scale_from_bytes(payload),
+                (TAG_GYRO_SCALE, payload) => gyro = scale_from_bytes(payload),
+                (TAG_REGISTERS, payload) => {
+                    registers = payload.chunks_exact(2).map(|p| (p[0], p[1])).collect()
+                }
+                (TAG_AXIS_MAP, [x, y, z, ..]) => {
+                    let bytes = [*x, *y, *z];
+                    let axes = bytes.map(|b| Axis::ALL.get((b & 0x03) as usize).copied());
+                    match axes {
+                        [Some(x), Some(y), Some(z)] => {
+                            axis_map = AxisMap::new([x, y, z], bytes.map(|b| b & 0x80 != 0))
+                        }
+                        _ => return Err(DecodeError::BadHeader),
+                    }
+                }
+                (TAG_CALIBRATION, [method, stamped, rest @ ..]) => {
+                    let method = CalibrationMethod::from_u8(*method);
+                    let timestamp = match (*stamped, rest) {
+                        (0, _) => None,
+                        (_, [a, b, c, d, e, f, g, h, ..]) => {
+                            Some(u64::from_le_bytes([*a, *b, *c, *d, *e, *f, *g, *h]))
+                        }
+                        _ => return Err(DecodeError::BadHeader),
+                    };
+                    // a method of a later version is still a calibration, of unknown kind
+                    calibration = Some(CalibrationNote {
+                        method: method.unwrap_or(CalibrationMethod::External),
+                        timestamp,
+                    });
+                }
+                (TAG_TEMP_CALIBRATION, payload) => {
+                    temp_calibration = Some(
+                        TempCalibration::from_bytes(payload).map_err(|_| DecodeError::BadHeader)?,
+                    )
+                }
+                (TAG_VERSION..=TAG_TEMP_CALIBRATION, _) => return Err(DecodeError::BadHeader),
+                _ => {}
+            }
+        }
+        if !fields.is_empty() {
+            return Err(DecodeError::BadHeader);
+        }
+
+        match (version, chip, config, acc, gyro) {
+            (
+                Some(driver_version),
+                Some(who_am_i),
+                Some((accel_range, gyro_range, dlpf_cfg, sample_rate_div, cycle)),
+                Some((acc_scale, acc_offset)),
+                Some((gyro_scale, gyro_offset)),
+            ) => Ok((
+                Self {
+                    driver_version,
+                    who_am_i,
+                    accel_range,
+                    gyro_range,
+                    dlpf_cfg,
+                    sample_rate_div,
+                    cycle,
+                    pipeline: Pipeline {
+                        acc_scale,
+                        gyro_scale,
+                        acc_offset,
+                        gyro_offset,
+                        stages: PipelineStages {
+                            temp: temp_calibration
+                                .map_or(TempModel::NATIVE, |calibration| calibration.model()),
+                            ..PipelineStages::NATIVE
+                        },
+                    },
+                    registers,
+                    axis_map,
+                    calibration,
+                    temp_calibration,
+                },
+                len,
+            )),
+            _ => Err(DecodeError::BadHeader),
+        }
+    }
+
+    /// Writes the text form as comment lines, each prefixed with `# `, e.g. above the column
+    /// names of a CSV log
+    pub fn write_comments<W: fmt::Write>(&self, out: &mut W) -> fmt::Result {
+        let text = self.to_string();
+        for line in text.lines() {
+            writeln!(out, "# {}", line)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "std")]
+impl fmt::Display for LogHeader {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let resolution = self.resolution();
+        let p = &self.pipeline;
+        writeln!(f, "mpu6050 log header v{}", LOG_HEADER_VERSION)?;
+        writeln!(f, "driver: {}", self.driver_version)?;
+        writeln!(
+            f,
+            "chip: {:?}, WHO_AM_I 0x{:02x}",
+            self.variant(),
+            self.who_am_i
+        )?;
+        writeln!(f, "ranges: {:?}, {:?}", self.accel_range, self.gyro_range)?;
+        match self.cycle {
+            Some(wake) => writeln!(f, "rate: cycle mode, wake {:?}", wake)?,
+            None => writeln!(
+                f,
+                "rate: DLPF_CFG {}, SMPLRT_DIV {}, ODR {} Hz",
+                self.dlpf_cfg,
+                self.sample_rate_div,
+                Decimal(self.odr_hz())
+            )?,
+        }
+        writeln!(
+            f,
+            "bandwidth: accel {} Hz, gyro {} Hz, delay accel {} ms, gyro {} ms",
+            Decimal(resolution.accel_bandwidth_hz),
+            Decimal(resolution.gyro_bandwidth_hz),
+            Decimal(resolution.accel_delay_ms),
+            Decimal(resolution.gyro_delay_ms)
+        )?;
+        writeln!(
+            f,
+            "noise: accel {:.3} mg rms, gyro {:.4} dps rms",
+            Decimal(resolution.accel_noise_mg_rms),
+            Decimal(resolution.gyro_noise_dps_rms)
+        )?;
+        writeln!(
+            f,
+            "acc scale: {} LSB/g, factors {}, offset {} g",
+            Decimal(p.acc_scale.nominal),
+            DecimalVec(p.acc_scale.per_axis),
+            DecimalVec(p.acc_offset)
+        )?;
+        writeln!(
+            f,
+            "gyro scale: {} LSB/dps, factors {}, offset {} rad/s",
+            Decimal(p.gyro_scale.nominal),
+            DecimalVec(p.gyro_scale.per_axis),
+            DecimalVec(p.gyro_offset)
+        )?;
+        let (axes, negate) = self.axis_map.axes();
+        write!(f, "axis map:")?;
+        for (axis, negated) in axes.iter().zip(negate) {
+            write!(f, " {}{:?}", if negated { "-" } else { "+" }, axis)?;
+        }
+        writeln!(f)?;
+        match self.calibration {
+            Some(CalibrationNote {
+                method,
+                timestamp: Some(timestamp),
+            }) => writeln!(f, "calibration: {:?} at {}", method, timestamp)?,
+            Some(CalibrationNote { method, .. }) => writeln!(f, "calibration: {:?}", method)?,
+            None => writeln!(f, "calibration: none recorded")?,
+        }
+        match self.temp_calibration {
+            Some(calibration) => writeln!(f, "temperature: {}", calibration)?,
+            None => writeln!(f, "temperature: not recorded")?,
+        }
+        write!(f, "registers:")?;
+        for (addr, value) in &self.registers {
+            write!(f, " {:02x}={:02x}", addr, value)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "std")]
+const SCALE_LEN: usize = 7 * 4;
+
+#[cfg(feature = "std")]
+fn scale_bytes(scale: &ScaleModel, offset: Vec3A) -> [u8; SCALE_LEN] {
+    let mut bytes = [0; SCALE_LEN];
+    let values = [scale.nominal]
+        .into_iter()
+        .chain(scale.per_axis.to_array())
+        .chain(offset.to_array());
+    for (chunk, value) in bytes.chunks_exact_mut(4).zip(values) {
+        chunk.copy_from_slice(&value.to_le_bytes());
+    }
+    bytes
+}
+
+#[cfg(feature = "std")]
+fn scale_from_bytes(bytes: &[u8]) -> Option<(ScaleModel, Vec3A)> {
+    if bytes.len() < SCALE_LEN {
+        return None;
+    }
+    let mut values = [0f32; 7];
+    for (value, chunk) in values.iter_mut().zip(bytes.chunks_exact(4)) {
+        *value = f32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]);
+    }
+    let [nominal, fx, fy, fz, ox, oy, oz] = values;
+    Some((
+        ScaleModel::new(nominal).with_factors(Vec3A::new(fx, fy, fz)),
+        Vec3A::new(ox, oy, oz),
+    ))
+}
+
+#[cfg(feature = "std")]
+struct FieldWriter<'a> {
+    buf: &'a mut [u8],
+    at: usize,
+}
+
+#[cfg(feature = "std")]
+impl FieldWriter<'_> {
+    fn field(&mut self, tag: u8, payload: &[u8]) {
+        self.buf[self.at] = tag;
+        self.buf[self.at + 1] = payload.len() as u8;
+        self.buf[self.at + 2..self.at + 2 + payload.len()].copy_from_slice(payload);
+        self.at += 2 + payload.len();
+    }
+}
+
+#[cfg(all(feature = "driver", feature = "std"))]
+impl<I, D, E> Mpu6050<I, D>
+where
+    I: Write<Error = E> + WriteRead<Error = E>,
+{
+    /// Header of a recording with the current configuration: reads WHO_AM_I and all
+    /// configuration registers, the rest comes from the driver state. Mounting and
+    /// calibration are added by the application, see [`LogHeader::with_axis_map`] and
+    /// [`LogHeader::with_calibration`]
+    pub fn build_log_header(&mut self) -> Result<LogHeader, Mpu6050Error<E>> {
+        let who_am_i = self.read_register(Register::WHO_AM_I)?;
+        let config = self.read_config()?;
+        Ok(LogHeader {
+            driver_version: DRIVER_VERSION.into(),
+            who_am_i,
+            accel_range: self.accel_range,
+            gyro_range: self.gyro_range,
+            dlpf_cfg: self.dlpf_cfg,
+            sample_rate_div: self.sample_rate_div,
+            cycle: self.cycle,
+            pipeline: self.pipeline(),
+            registers: config.registers().collect(),
+            axis_map: AxisMap::IDENTITY,
+            calibration: None,
+            temp_calibration: Some(self.temp_calibration()),
+        })
+    }
+}
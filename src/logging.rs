@@ -0,0 +1,264 @@
+//! Leveled diagnostic logging through an optional backend.
+//!
+//! With the `log` feature the driver emits records through the [`log`](https://docs.rs/log)
+//! facade, with `defmt` through [`defmt`](https://docs.rs/defmt). Without either the
+//! emission sites expand to nothing: no formatting, no branch, no argument evaluated, see
+//! [`ENABLED`]. With both, `log` is used.
+//!
+//! Every record has one of the targets below, stable across releases, for filtering by
+//! subsystem, e.g. `RUST_LOG=mpu6050::bus=trace,mpu6050=warn` with `env_logger`:
+//!
+//! | level | target | record |
+//! |:---|:---|:---|
+//! | error | [`INIT`] | `init` failed, the [`FailureRecord`](crate::black_box::FailureRecord) |
+//! | error | [`CONNECTION`] | `try_reconnect` failed, the failure record |
+//! | error | [`CONNECTION`] | the connection monitor entered `Disconnected` |
+//! | error | [`BUS`] | the bus reset callback left the lines stuck |
+//! | warn | [`CONNECTION`] | `try_reconnect` found a chip, the same or a different one |
+//! | warn | [`BUS`] | the bus reset callback was invoked, what it did and the resynchronization |
+//! | warn | [`INIT`] | init found a leftover of a previous run |
+//! | warn | [`FIFO`] | a drain found the FIFO full and reset it, the pre-reset count |
+//! | warn | [`SAMPLING`] | the sampling loop overran, the supervisor latched |
+//! | warn | [`POWER`] | the shutdown sequence was not verified, the evidence |
+//! | warn | [`CALIBRATION`] | a self-heating characterization started from a warm die |
+//! | debug | [`INIT`] | `init` succeeded |
+//! | debug | [`CONFIG`] | the [configuration epoch](crate::config_epoch) advanced, the change |
+//! | debug | [`POWER`] | sleep entered or left, a governor transition, a verified shutdown |
+//! | debug | [`SAMPLING`] | a settling sample was discarded and read again |
+//! | debug | [`FUSION`] | the reacquisition ramp of the complementary filter started, completed |
+//! | trace | [`BUS`] | a transaction: register, bytes, ok or failed, see [`TraceLevel`] |
+//!
+//! The errors and warnings are the [`MetricEvent`](crate::metrics::MetricEvent)s worth a
+//! line in a field log, they are logged whether or not a metrics sink is installed. Left
+//! out on purpose:
+//! * clipped samples and interrupt edges, they come at the sample rate while the cause
+//!   lasts, the metrics count them
+//! * errors returned to the caller, the caller has them and the context to judge them, a
+//!   second report in the log would be a duplicate at best and noise on a retry loop
+//! * the structural frame checks, diagnosed by [`parse_policy`](crate::parse_policy)
+//!
+//! #### Transactions
+//! Per transaction records would drown everything else on a high rate path, and cost the
+//! formatting on it. They are off until enabled at run time with
+//! [`Mpu6050::set_trace_level`], in addition to the backend's own filter:
+//!
+//! | [`TraceLevel`] | transactions logged |
+//! |:---|:---|
+//! | [`Off`](TraceLevel::Off), the default | none |
+//! | [`Failures`](TraceLevel::Failures) | the failed ones |
+//! | [`Transactions`](TraceLevel::Transactions) | all of them |
+//!
+//! #### defmt
+//! defmt has no targets and filters by crate and level at build time with `DEFMT_LOG`. The
+//! records are formatted on the device and sent as one string with the target in front,
+//! `mpu6050::bus: PWR_MGMT_1 (0x6b): 3 bytes on the bus, ok`. The application provides the global
+//! logger, the tests of this crate run with `log`.
+//!
+//! ```
+//! use mpu6050::logging::{TraceLevel, BUS};
+//!
+//! assert_eq!(BUS, "mpu6050::bus");
+//! assert_eq!(TraceLevel::default(), TraceLevel::Off);
+//! assert!(TraceLevel::Transactions > TraceLevel::Failures);
+//! ```
+
+use crate::Mpu6050;
+
+/// bus transactions and bus recovery
+pub const BUS: &str = "mpu6050::bus";
+/// `init`, its findings and failures
+pub const INIT: &str = "mpu6050::init";
+/// connection loss and reconnects
+pub const CONNECTION: &str = "mpu6050::connection";
+/// configuration changes advancing the epoch
+pub const CONFIG: &str = "mpu6050::config";
+/// FIFO overflows
+pub const FIFO: &str = "mpu6050::fifo";
+/// sleep, the power governor and the shutdown sequence
+pub const POWER: &str = "mpu6050::power";
+/// the sampling loop, the supervisor and settling
+pub const SAMPLING: &str = "mpu6050::sampling";
+/// orientation filters
+pub const FUSION: &str = "mpu6050::fusion";
+/// calibration routines
+pub const CALIBRATION: &str = "mpu6050::calibration";
+
+/// A logging backend is compiled in, `log` or `defmt`
+pub const ENABLED: bool = cfg!(any(feature = "log", feature = "defmt"));
+
+/// Bus transactions logged at trace level, see the module docs
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Ord, PartialOrd)]
+pub enum TraceLevel {
+    /// none
+    #[default]
+    Off,
+    /// the failed ones
+    Failures,
+    /// all of them
+    Transactions,
+}
+
+impl<I, D> Mpu6050<I, D> {
+    /// Selects the bus transactions logged at trace level, see the module docs. No effect
+    /// without a backend
+    pub fn set_trace_level(&mut self, level: TraceLevel) {
+        self.trace_level = level;
+    }
+
+    /// level set with [`set_trace_level`](Self::set_trace_level), [`Off`](TraceLevel::Off)
+    /// by default
+    pub fn trace_level(&self) -> TraceLevel {
+        self.trace_level
+    }
+}
+
+/// one record of `$level`, an ident of `log::Level`, on `$target`
+#[cfg(feature = "log")]
+macro_rules! emit {
+    ($level:ident, $target:expr, $($arg:tt)+) => {
+        ::log::log!(target: $target, ::log::Level::$level, $($arg)+)
+    };
+}
+
+/// one record of `$level`, an ident of `log::Level`, on `$target`
+#[cfg(all(feature = "defmt", not(feature = "log")))]
+macro_rules! emit {
+    (Error, $($rest:tt)+) => { $crate::logging::emit!(@defmt error, $($rest)+) };
+    (Warn, $($rest:tt)+) => { $crate::logging::emit!(@defmt warn, $($rest)+) };
+    (Debug, $($rest:tt)+) => { $crate::logging::emit!(@defmt debug, $($rest)+) };
+    (Trace, $($rest:tt)+) => { $crate::logging::emit!(@defmt trace, $($rest)+) };
+    // the match keeps the arguments alive for the whole call
+    (@defmt $macro:ident, $target:expr, $($arg:tt)+) => {
+        match format_args!($($arg)+) {
+            args => ::defmt::$macro!("{=str}: {}", $target, ::defmt::Display2Format(&args)),
+        }
+    };
+}
+
+/// nothing, the arguments are not even expanded
+#[cfg(not(any(feature = "log", feature = "defmt")))]
+macro_rules! emit {
+    ($($tokens:tt)*) => {
+        ()
+    };
+}
+
+// The wrappers take the target as the name of one of the constants above.
+
+/// failure with context, see the module docs
+macro_rules! log_error {
+    ($target:ident, $($arg:tt)+) => {
+        $crate::logging::emit!(Error, $crate::logging::$target, $($arg)+)
+    };
+}
+
+/// recovery or degradation
+macro_rules! log_warn {
+    ($target:ident, $($arg:tt)+) => {
+        $crate::logging::emit!(Warn, $crate::logging::$target, $($arg)+)
+    };
+}
+
+/// mode transition
+macro_rules! log_debug {
+    ($target:ident, $($arg:tt)+) => {
+        $crate::logging::emit!(Debug, $crate::logging::$target, $($arg)+)
+    };
+}
+
+/// per transaction detail, only if the driver `$mpu` traces at `$level` of [`TraceLevel`]
+#[cfg(any(feature = "log", feature = "defmt"))]
+macro_rules! log_trace {
+    ($mpu:expr, $level:ident, $target:ident, $($arg:tt)+) => {
+        if $mpu.trace_level >= $crate::logging::TraceLevel::$level {
+            $crate::logging::emit!(Trace, $crate::logging::$target, $($arg)+)
+        }
+    };
+}
+
+/// nothing, not even the check of the trace level
+#[cfg(not(any(feature = "log", feature = "defmt")))]
+macro_rules! log_trace {
+    ($($tokens:tt)*) => {
+        ()
+    };
+}
+
+// log_error is used by the backends' `log_event` only
+#[allow(unused_imports)]
+pub(crate) use {emit, log_debug, log_error, log_trace, log_warn};
+
+// Without a backend the macros must expand to nothing: none of these names exists, the
+// block only compiles if the arguments are dropped unexpanded.
+#[cfg(not(any(feature = "log", feature = "defmt")))]
+const _: () = {
+    log_error!(NO_SUCH_TARGET, "{}", no_such_value);
+    log_warn!(NO_SUCH_TARGET, "{}", no_such_value);
+    log_debug!(NO_SUCH_TARGET, "{}", no_such_value);
+    log_trace!(
+        no_such_driver,
+        NoSuchLevel,
+        NO_SUCH_TARGET,
+        "{}",
+        no_such_value
+    );
+};
+
+/// the record of a metrics event, see the module docs
+#[cfg(any(feature = "log", feature = "defmt"))]
+pub(crate) fn log_event(event: &crate::metrics::MetricEvent) {
+    use crate::black_box::FailedOperation;
+    use crate::metrics::MetricEvent;
+    use crate::recovery::BusRecoveryOutcome;
+
+    match event {
+        MetricEvent::Initialized { chip_id } => {
+            log_debug!(INIT, "initialized, WHO_AM_I {:#04x}", chip_id)
+        }
+        MetricEvent::Disconnected => log_error!(CONNECTION, "connection lost"),
+        MetricEvent::Reconnected(outcome) => log_warn!(CONNECTION, "reconnected: {:?}", outcome),
+        MetricEvent::SettlingRetry(sensor) => {
+            log_debug!(
+                SAMPLING,
+                "{:?} settling sample discarded, read again",
+                sensor
+            )
+        }
+        MetricEvent::SampleClipped { .. } | MetricEvent::Interrupt(_) => {}
+        MetricEvent::SupervisorTripped(event) => {
+            log_warn!(
+                SAMPLING,
+                "supervisor tripped: {:?} at {}",
+                event.limit,
+                event.value
+            )
+        }
+        MetricEvent::SamplingOverrun => log_warn!(SAMPLING, "sampling overrun"),
+        MetricEvent::FifoOverflow { bytes } => {
+            log_warn!(FIFO, "FIFO overflow, {} bytes", bytes)
+        }
+        MetricEvent::BusRecovery {
+            outcome: BusRecoveryOutcome::Failed,
+            ..
+        } => log_error!(BUS, "bus recovery failed, lines still stuck"),
+        MetricEvent::BusRecovery { outcome, resync } => {
+            log_warn!(BUS, "bus recovery: {:?}, resync {:?}", outcome, resync)
+        }
+        MetricEvent::InitFinding(finding) => log_warn!(
+            INIT,
+            "init finding: {:?}, {:?}",
+            finding.kind,
+            finding.action
+        ),
+        MetricEvent::OperationFailed(record) => match record.operation {
+            FailedOperation::Init => log_error!(INIT, "{}", record),
+            FailedOperation::Reconnect => log_error!(CONNECTION, "{}", record),
+        },
+    }
+}
+
+/// name of the register at `addr` of a transaction, `unknown` off the register map
+#[cfg(any(feature = "log", feature = "defmt"))]
+pub(crate) fn register_name(addr: u8) -> &'static str {
+    crate::register::Register::from_addr(addr).map_or("unknown", |register| register.name())
+}
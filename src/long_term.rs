@@ -0,0 +1,534 @@
+//! Bounded-memory min, max, mean and percentiles over long runs, for environmental reports.
+//!
+//! A deployed unit reporting the daily vibration and temperature needs percentiles over some
+//! ten million samples without keeping them. [`LongTermStats`] tracks five channels, the
+//! accelerometer magnitude in g, the gyro axes in rad/s and the temperature in °C, each in a
+//! [`ChannelStats`] of fixed size. [`snapshot`](LongTermStats::snapshot) summarizes them into
+//! a [`StatsSnapshot`]: count, exact min, max and mean, and p50, p95 and p99 within the
+//! error bound below. Values that are not finite are skipped per channel, so the partial
+//! samples of single sensor reads count for the sensor read only.
+//!
+//! #### Estimator
+//! Each channel is a histogram of `BINS` counts over an aligned grid of width `2^e`,
+//! adapting to the data. The first value places the grid at the finest width of the
+//! channel. A value outside the grid widens it to the finest power of two holding min and
+//! max in `BINS` bins; bins of the old grid lie within one bin of the new one and are merged
+//! without error, the grid never narrows until [`reset`](LongTermStats::reset). A
+//! percentile `p` is the nearest-rank order statistic, the value of rank `⌈p n⌉`: the
+//! estimate interpolates within the bin holding that rank and is clamped to min and max.
+//!
+//! The estimate lies in the same bin as the exact percentile, the error is below the bin
+//! width reported as [`resolution`](ChannelSummary::resolution):
+//!
+//! `|p̂ - p| < resolution ≤ max(finest, 2 (max - min) / (BINS - 1))`
+//!
+//! whatever the distribution. Outliers widen the grid: a heavy tail costs resolution, not
+//! correctness. Bin counts saturate at `u32::MAX`, some 50 days at 1 kHz; reset at least
+//! that often.
+//!
+//! | channel | finest width |
+//! |:---|:---|
+//! | accelerometer magnitude | 2^-14 g, about the LSB at ±2 g |
+//! | gyro axis | 2^-13 rad/s, about the LSB at ±250 °/s |
+//! | temperature | 2^-8 °C, about the LSB |
+//!
+//! #### Memory
+//! A channel is `BINS` counts of 4 bytes and 40 bytes of state, [`DEFAULT_BINS`] gives 296
+//! bytes per channel and 1480 for the five channels of a `LongTermStats`. Nothing is
+//! allocated, ingesting is a division, a floor and a count; widening the grid walks the bins
+//! once.
+//!
+//! #### Passive operation
+//! [`SharedLongTermStats`] is a `static` behind a lock, ingesting from the
+//! [sample tap](crate::hook): every scaled output of the driver is counted behind normal
+//! operation, no bus transaction added. Taps are not called in the `minimal-pipeline`
+//! build, see [`stages`](crate::stages).
+//! ```
+//! use embedded_hal::blocking::i2c::{Write, WriteRead};
+//! use mpu6050::long_term::{SharedLongTermStats, StatsSnapshot};
+//! use mpu6050::Mpu6050;
+//!
+//! static STATS: SharedLongTermStats = SharedLongTermStats::new();
+//!
+//! fn start<I, E>(mpu: &mut Mpu6050<I>)
+//! where
+//!     I: Write<Error = E> + WriteRead<Error = E>,
+//! {
+//!     mpu.set_sample_tap(Some(|sample| STATS.ingest(sample)));
+//! }
+//!
+//! /// once a day: the report, and a fresh day
+//! fn daily_report() -> StatsSnapshot {
+//!     STATS.take_snapshot()
+//! }
+//! ```
+//!
+//! #### Persistence
+//! The crate has no serde dependency, a snapshot has a fixed binary format,
+//! [`StatsSnapshot::to_bytes`] and [`StatsSnapshot::from_bytes`], and a text form for
+//! reports, its `Display`:
+//!
+//! | bytes | content |
+//! |:---|:---|
+//! | 1 | format version ([`STATS_SNAPSHOT_VERSION`]) |
+//! | 1 | reserved, 0 |
+//! | 5 × 36 | per channel in the order accelerometer magnitude, gyro x, y, z, temperature: count `u64`, then min, max, mean, p50, p95, p99 and resolution `f32`, little endian |
+//! | 2 | CRC-16/CCITT-FALSE ([`crc16`]) over all previous bytes, little endian |
+//!
+//! A channel without values has a count of 0 and NaN for everything else.
+//! ```
+//! use mpu6050::long_term::{LongTermStats, StatsSnapshot};
+//! use mpu6050::{MpuSample, Vec3A};
+//!
+//! let temp_c = |i: u32| 20. + i as f32 / 64.;
+//! let mut stats = LongTermStats::<64>::new();
+//! for i in 0..1000 {
+//!     stats.ingest(&MpuSample::new(Vec3A::Z, Vec3A::ZERO, temp_c(i)));
+//! }
+//! let snapshot = stats.snapshot();
+//! let temp = snapshot.temp_c;
+//! assert_eq!((temp.count, temp.min, temp.max), (1000, temp_c(0), temp_c(999)));
+//! // exact: the 500th value
+//! assert!((temp.p50 - temp_c(499)).abs() < temp.resolution);
+//! assert_eq!(StatsSnapshot::from_bytes(&snapshot.to_bytes()), Ok(snapshot));
+//! ```
+
+use core::fmt;
+#[cfg(feature = "std")]
+use std::sync::{Mutex, MutexGuard};
+
+#[cfg(not(feature = "std"))]
+use crate::math::FloatExt;
+use crate::packed::{crc16, DecodeError};
+use crate::MpuSample;
+
+/// Bins of a channel unless given
+pub const DEFAULT_BINS: usize = 64;
+/// Finest grid width of the accelerometer magnitude, as a power of two of g
+pub const ACC_MIN_EXPONENT: i16 = -14;
+/// Finest grid width of a gyro axis, as a power of two of rad/s
+pub const GYRO_MIN_EXPONENT: i16 = -13;
+/// Finest grid width of the temperature, as a power of two of °C
+pub const TEMP_MIN_EXPONENT: i16 = -8;
+/// Format version written by [`StatsSnapshot::to_bytes`]
+pub const STATS_SNAPSHOT_VERSION: u8 = 1;
+/// Length of a serialized snapshot
+pub const STATS_SNAPSHOT_LEN: usize = 2 + 5 * CHANNEL_LEN + 2;
+
+const CHANNEL_LEN: usize = 8 + 7 * 4;
+
+/// Streaming summary of one channel, see the [module docs](self#estimator)
+#[derive(Clone, Debug, PartialEq)]
+pub struct ChannelStats<const BINS: usize = DEFAULT_BINS> {
+    counts: [u32; BINS],
+    /// grid index of the first bin, in units of the width
+    first: i64,
+    count: u64,
+    sum: f64,
+    min: f32,
+    max: f32,
+    /// width of a bin is `2^exponent`
+    exponent: i16,
+    min_exponent: i16,
+}
+
+impl<const BINS: usize> ChannelStats<BINS> {
+    /// empty channel with the finest bin width `2^min_exponent`
+    pub const fn new(min_exponent: i16) -> Self {
+        assert!(BINS >= 2, "a channel needs at least 2 bins");
+        Self {
+            counts: [0; BINS],
+            first: 0,
+            count: 0,
+            sum: 0.,
+            min: f32::INFINITY,
+            max: f32::NEG_INFINITY,
+            exponent: min_exponent,
+            min_exponent,
+        }
+    }
+
+    /// counts `value`, skipped if not finite
+    pub fn ingest(&mut self, value: f32) {
+        if !value.is_finite() {
+            return;
+        }
+        if self.count == 0 {
+            self.exponent = self.min_exponent;
+            self.first = grid_index(value, self.exponent);
+            self.min = value;
+            self.max = value;
+        } else {
+            self.min = self.min.min(value);
+            self.max = self.max.max(value);
+        }
+        let mut bin = grid_index(value, self.exponent).saturating_sub(self.first);
+        if !(0..BINS as i64).contains(&bin) {
+            self.widen();
+            bin = grid_index(value, self.exponent) - self.first;
+        }
+        let slot = &mut self.counts[bin as usize];
+        *slot = slot.saturating_add(1);
+        self.count += 1;
+        self.sum += f64::from(value);
+    }
+
+    /// the finest grid holding min and max, the bins merged into it
+    fn widen(&mut self) {
+        let mut exponent = self.exponent;
+        while grid_index(self.max, exponent).saturating_sub(grid_index(self.min, exponent))
+            >= BINS as i64
+        {
+            exponent += 1;
+        }
+        let first = grid_index(self.min, exponent);
+        // an old index shifted by 63 is already 0 or -1
+        let shift = (exponent - self.exponent).min(63);
+        let mut counts = [0u32; BINS];
+        for (bin, &count) in self.counts.iter().enumerate() {
+            if count > 0 {
+                // an old bin lies within one new bin
+                let merged = ((self.first + bin as i64) >> shift) - first;
+                let slot = &mut counts[merged as usize];
+                *slot = slot.saturating_add(count);
+            }
+        }
+        self.counts = counts;
+        self.first = first;
+        self.exponent = exponent;
+    }
+
+    /// values counted
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+
+    /// current bin width, the error bound of the percentiles
+    pub fn resolution(&self) -> f32 {
+        exp2(self.exponent) as f32
+    }
+
+    /// Estimate of the nearest-rank percentile `p` in 0..=1, within
+    /// [`resolution`](Self::resolution) of the exact one. NaN without values
+    pub fn percentile(&self, p: f32) -> f32 {
+        let total: u64 = self.counts.iter().map(|&c| u64::from(c)).sum();
+        if total == 0 {
+            return f32::NAN;
+        }
+        let rank = ((f64::from(p.clamp(0., 1.)) * total as f64).ceil() as u64).clamp(1, total);
+        let mut below = 0;
+        for (bin, &count) in self.counts.iter().enumerate() {
+            let count = u64::from(count);
+            if below + count >= rank {
+                // the ranks of a bin spread evenly over it
+                let within = ((rank - below) as f64 - 0.5) / count as f64;
+                let value = ((self.first + bin as i64) as f64 + within) * exp2(self.exponent);
+                return (value as f32).clamp(self.min, self.max);
+            }
+            below += count;
+        }
+        self.max
+    }
+
+    /// min, max, mean and percentiles, see [`ChannelSummary`]
+    pub fn summary(&self) -> ChannelSummary {
+        if self.count == 0 {
+            return ChannelSummary::EMPTY;
+        }
+        ChannelSummary {
+            count: self.count,
+            min: self.min,
+            max: self.max,
+            mean: (self.sum / self.count as f64) as f32,
+            p50: self.percentile(0.5),
+            p95: self.percentile(0.95),
+            p99: self.percentile(0.99),
+            resolution: self.resolution(),
+        }
+    }
+
+    /// forgets every value, the grid back at the finest width
+    pub fn reset(&mut self) {
+        *self = Self::new(self.min_exponent);
+    }
+}
+
+/// `floor(value / 2^exponent)`
+fn grid_index(value: f32, exponent: i16) -> i64 {
+    (f64::from(value) / exp2(exponent)).floor() as i64
+}
+
+fn exp2(exponent: i16) -> f64 {
+    2f64.powi(i32::from(exponent))
+}
+
+/// Summary of one channel. Without values the count is 0 and everything else NaN
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct ChannelSummary {
+    /// values counted
+    pub count: u64,
+    /// smallest value, exact
+    pub min: f32,
+    /// largest value, exact
+    pub max: f32,
+    /// mean, exact up to rounding
+    pub mean: f32,
+    /// median estimate
+    pub p50: f32,
+    /// 95th percentile estimate
+    pub p95: f32,
+    /// 99th percentile estimate
+    pub p99: f32,
+    /// bin width: every percentile estimate is closer than this to the exact one
+    pub resolution: f32,
+}
+
+impl ChannelSummary {
+    /// summary of a channel without values
+    pub const EMPTY: Self = Self {
+        count: 0,
+        min: f32::NAN,
+        max: f32::NAN,
+        mean: f32::NAN,
+        p50: f32::NAN,
+        p95: f32::NAN,
+        p99: f32::NAN,
+        resolution: f32::NAN,
+    };
+
+    fn write(&self, bytes: &mut [u8]) {
+        bytes[..8].copy_from_slice(&self.count.to_le_bytes());
+        let values = [
+            self.min,
+            self.max,
+            self.mean,
+            self.p50,
+            self.p95,
+            self.p99,
+            self.resolution,
+        ];
+        for (chunk, value) in bytes[8..CHANNEL_LEN].chunks_exact_mut(4).zip(values) {
+            chunk.copy_from_slice(&value.to_le_bytes());
+        }
+    }
+
+    fn read(bytes: &[u8]) -> Self {
+        let mut count = [0; 8];
+        count.copy_from_slice(&bytes[..8]);
+        let float = |idx: usize| {
+            let at = 8 + 4 * idx;
+            f32::from_le_bytes([bytes[at], bytes[at + 1], bytes[at + 2], bytes[at + 3]])
+        };
+        Self {
+            count: u64::from_le_bytes(count),
+            min: float(0),
+            max: float(1),
+            mean: float(2),
+            p50: float(3),
+            p95: float(4),
+            p99: float(5),
+            resolution: float(6),
+        }
+    }
+}
+
+/// Summary of every channel of a [`LongTermStats`]
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct StatsSnapshot {
+    /// accelerometer magnitude in g
+    pub acc_magnitude_g: ChannelSummary,
+    /// gyro x, y and z in rad/s
+    pub gyro_rad_s: [ChannelSummary; 3],
+    /// temperature in °C
+    pub temp_c: ChannelSummary,
+}
+
+impl StatsSnapshot {
+    fn channels(&self) -> [&ChannelSummary; 5] {
+        let [x, y, z] = &self.gyro_rad_s;
+        [&self.acc_magnitude_g, x, y, z, &self.temp_c]
+    }
+
+    /// Serialized form, see the [module docs](self#persistence)
+    pub fn to_bytes(&self) -> [u8; STATS_SNAPSHOT_LEN] {
+        let mut bytes = [0; STATS_SNAPSHOT_LEN];
+        bytes[0] = STATS_SNAPSHOT_VERSION;
+        for (chunk, channel) in bytes[2..STATS_SNAPSHOT_LEN - 2]
+            .chunks_exact_mut(CHANNEL_LEN)
+            .zip(self.channels())
+        {
+            channel.write(chunk);
+        }
+        let crc = crc16(&bytes[..STATS_SNAPSHOT_LEN - 2]);
+        bytes[STATS_SNAPSHOT_LEN - 2..].copy_from_slice(&crc.to_le_bytes());
+        bytes
+    }
+
+    /// Reads a snapshot written by [`to_bytes`](Self::to_bytes)
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, DecodeError> {
+        if bytes.len() < STATS_SNAPSHOT_LEN {
+            return Err(DecodeError::Truncated(STATS_SNAPSHOT_LEN));
+        }
+        let bytes = &bytes[..STATS_SNAPSHOT_LEN];
+        if bytes[0] != STATS_SNAPSHOT_VERSION {
+            return Err(DecodeError::BadHeader);
+        }
+        let crc =
+            u16::from_le_bytes([bytes[STATS_SNAPSHOT_LEN - 2], bytes[STATS_SNAPSHOT_LEN - 1]]);
+        if crc != crc16(&bytes[..STATS_SNAPSHOT_LEN - 2]) {
+            return Err(DecodeError::CrcMismatch);
+        }
+        let channel = |idx: usize| ChannelSummary::read(&bytes[2 + idx * CHANNEL_LEN..]);
+        Ok(Self {
+            acc_magnitude_g: channel(0),
+            gyro_rad_s: [channel(1), channel(2), channel(3)],
+            temp_c: channel(4),
+        })
+    }
+}
+
+impl fmt::Display for StatsSnapshot {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let names = [
+            "acc g",
+            "gyro x rad/s",
+            "gyro y rad/s",
+            "gyro z rad/s",
+            "temp °C",
+        ];
+        for (idx, (name, c)) in names.iter().zip(self.channels()).enumerate() {
+            if idx > 0 {
+                f.write_str("\n")?;
+            }
+            if c.count == 0 {
+                write!(f, "{}: no values", name)?;
+                continue;
+            }
+            write!(
+                f,
+                "{}: n {} min {} max {} mean {} p50 {} p95 {} p99 {} (±{})",
+                name, c.count, c.min, c.max, c.mean, c.p50, c.p95, c.p99, c.resolution
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Long-term statistics of the accelerometer magnitude, the gyro axes and the temperature,
+/// see the [module docs](self)
+#[derive(Clone, Debug, PartialEq)]
+pub struct LongTermStats<const BINS: usize = DEFAULT_BINS> {
+    acc: ChannelStats<BINS>,
+    gyro: [ChannelStats<BINS>; 3],
+    temp: ChannelStats<BINS>,
+}
+
+impl<const BINS: usize> LongTermStats<BINS> {
+    /// no values yet
+    pub const fn new() -> Self {
+        Self {
+            acc: ChannelStats::new(ACC_MIN_EXPONENT),
+            gyro: [
+                ChannelStats::new(GYRO_MIN_EXPONENT),
+                ChannelStats::new(GYRO_MIN_EXPONENT),
+                ChannelStats::new(GYRO_MIN_EXPONENT),
+            ],
+            temp: ChannelStats::new(TEMP_MIN_EXPONENT),
+        }
+    }
+
+    /// counts the finite parts of `sample`
+    pub fn ingest(&mut self, sample: &MpuSample) {
+        self.acc.ingest(sample.acc().length());
+        for (channel, rate) in self.gyro.iter_mut().zip(sample.gyro().to_array()) {
+            channel.ingest(rate);
+        }
+        self.temp.ingest(sample.temp());
+    }
+
+    /// every channel summarized
+    pub fn snapshot(&self) -> StatsSnapshot {
+        StatsSnapshot {
+            acc_magnitude_g: self.acc.summary(),
+            gyro_rad_s: self.gyro.each_ref().map(ChannelStats::summary),
+            temp_c: self.temp.summary(),
+        }
+    }
+
+    /// forgets every value
+    pub fn reset(&mut self) {
+        *self = Self::new();
+    }
+
+    /// the accelerometer magnitude channel
+    pub fn acc_magnitude(&self) -> &ChannelStats<BINS> {
+        &self.acc
+    }
+
+    /// the gyro channel of `axis` 0, 1 or 2
+    pub fn gyro(&self, axis: usize) -> &ChannelStats<BINS> {
+        &self.gyro[axis]
+    }
+
+    /// the temperature channel
+    pub fn temp(&self) -> &ChannelStats<BINS> {
+        &self.temp
+    }
+}
+
+impl<const BINS: usize> Default for LongTermStats<BINS> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// [`LongTermStats`] behind a lock, for a `static` fed by the sample tap, see the
+/// [module docs](self#passive-operation)
+#[cfg(feature = "std")]
+#[derive(Debug, Default)]
+pub struct SharedLongTermStats<const BINS: usize = DEFAULT_BINS> {
+    stats: Mutex<LongTermStats<BINS>>,
+}
+
+#[cfg(feature = "std")]
+impl<const BINS: usize> SharedLongTermStats<BINS> {
+    /// no values yet
+    pub const fn new() -> Self {
+        Self {
+            stats: Mutex::new(LongTermStats::new()),
+        }
+    }
+
+    /// [`LongTermStats::ingest`]
+    pub fn ingest(&self, sample: &MpuSample) {
+        self.lock().ingest(sample);
+    }
+
+    /// [`LongTermStats::snapshot`]
+    pub fn snapshot(&self) -> StatsSnapshot {
+        self.lock().snapshot()
+    }
+
+    /// [`LongTermStats::reset`]
+    pub fn reset(&self) {
+        self.lock().reset();
+    }
+
+    /// snapshot and reset in one, every sample is in exactly one snapshot
+    pub fn take_snapshot(&self) -> StatsSnapshot {
+        let mut stats = self.lock();
+        let snapshot = stats.snapshot();
+        stats.reset();
+        snapshot
+    }
+
+    /// a copy of the statistics
+    pub fn stats(&self) -> LongTermStats<BINS> {
+        self.lock().clone()
+    }
+
+    fn lock(&self) -> MutexGuard<'_, LongTermStats<BINS>> {
+        // a panicking tap leaves plain data behind, keep using it
+        self.stats.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
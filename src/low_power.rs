@@ -0,0 +1,207 @@
+//! Low power accelerometer mode: entering and leaving it, the standby bits, and in place
+//! reconfiguration.
+//!
+//! [`Mpu6050::enter_low_power_accel_mode`] follows the register map rev 4.2 section 4.28:
+//! the internal oscillator as clock, the gyros go to standby with the wake frequency of a
+//! [`LowPowerWakeFrequency`], TEMP_DIS and CYCLE are set and SLEEP cleared. The chip then
+//! wakes at that frequency for one accelerometer sample and, with
+//! [`setup_motion_detection`](Mpu6050::setup_motion_detection) before or after, latches
+//! MOT_INT for a motion above the threshold. [`exit_low_power_mode`](Mpu6050::exit_low_power_mode)
+//! clears the standby bits, TEMP_DIS and CYCLE, selects the PLL with the X gyro reference
+//! again and waits 100 ms for the gyros to start, like `init`. Both keep the motion
+//! detection and interrupt configuration. [`set_cycle_enabled`](Mpu6050::set_cycle_enabled)
+//! and [`set_standby_mask`](Mpu6050::set_standby_mask) write the single fields for other
+//! combinations, e.g. the cycle mode with the temperature sensor on.
+//!
+//! Leaving cycle mode to change its wake frequency or the motion trigger, with
+//! [`apply_settings_diff`](crate::Mpu6050::apply_settings_diff) there and back, wakes the
+//! gyros for the gap, draining the normal mode current, and a motion in the gap has no
+//! detector checking it. [`Mpu6050::adjust_low_power`] writes a [`LowPowerAdjust`] while the
+//! chip keeps cycling:
+//! 1. every requested setting is checked against
+//!    [`CYCLE_ADJUSTABILITY`](crate::device::CYCLE_ADJUSTABILITY): outside cycle mode the
+//!    adjustment is [`NotCycling`](crate::settings::SettingsError::NotCycling), a setting
+//!    that cannot change while cycling
+//!    [`NotAdjustableInCycle`](crate::settings::SettingsError::NotAdjustableInCycle), both
+//!    before the bus is touched
+//! 2. MOT_THR and MOT_DUR are written, then LP_WAKE_CTRL: the first sample at a new wake
+//!    frequency is compared against the new threshold
+//! 3. the driver's cycle mode wake frequency is updated, the one
+//!    [`power_estimate`](crate::Mpu6050::power_estimate),
+//!    [`debug_state`](crate::Mpu6050::debug_state) and the aliasing checks see
+//!
+//! INT_STATUS is not read: a MOT_INT latched before or during the adjustment stays latched
+//! for the next poll. The sensor standby bits, TEMP_DIS and CYCLE are left as they are, and
+//! leaving cycle mode later takes the usual path, e.g. `apply_settings_diff` from the
+//! settings in effect with the new wake frequency, or [`read_settings`](Mpu6050::read_settings)
+//! for them. A [power governor](crate::governor) keeps the settings of its points: a
+//! transition back to a cycle mode point writes the point's wake frequency.
+//! ```
+//! use mpu6050::device::{cycle_adjustable, LowPowerField, CYCLE_ADJUSTABILITY};
+//!
+//! assert!(cycle_adjustable(LowPowerField::MotionThreshold));
+//! assert!(!cycle_adjustable(LowPowerField::AccelHpf));
+//! for entry in CYCLE_ADJUSTABILITY {
+//!     println!("{:?} in 0x{:02x}: {}", entry.field, entry.register, entry.reason);
+//! }
+//! ```
+
+#[cfg(feature = "driver")]
+use embedded_hal::blocking::{
+    delay::DelayMs,
+    i2c::{Write, WriteRead},
+};
+
+#[cfg(feature = "driver")]
+use crate::device::{
+    cycle_adjustable, LowPowerWakeFrequency, StandbyFlags, CLKSEL, PWR_MGMT_1, PWR_MGMT_2,
+};
+use crate::device::{AccelRange, LowPowerField, ACCEL_HPF, LP_WAKE_CTRL};
+#[cfg(feature = "driver")]
+use crate::logging::log_debug;
+#[cfg(feature = "driver")]
+use crate::register::Register;
+#[cfg(feature = "driver")]
+use crate::settings::SettingsError;
+#[cfg(feature = "driver")]
+use crate::{Mpu6050, Mpu6050Error};
+
+/// Changes to the low power accelerometer mode, None keeps a setting, see the
+/// [module docs](self)
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub struct LowPowerAdjust {
+    /// wake frequency
+    pub wake_freq: Option<LP_WAKE_CTRL>,
+    /// MOT_THR
+    pub motion_threshold: Option<u8>,
+    /// MOT_DUR
+    pub motion_duration: Option<u8>,
+    /// accelerometer range, not adjustable while cycling
+    pub accel_range: Option<AccelRange>,
+    /// accelerometer high pass filter, not adjustable while cycling
+    pub accel_hpf: Option<ACCEL_HPF>,
+}
+
+impl LowPowerAdjust {
+    /// settings requested, in the order of [`LowPowerField`]
+    pub fn fields(&self) -> impl Iterator<Item = LowPowerField> {
+        [
+            (self.wake_freq.is_some(), LowPowerField::WakeFrequency),
+            (
+                self.motion_threshold.is_some(),
+                LowPowerField::MotionThreshold,
+            ),
+            (
+                self.motion_duration.is_some(),
+                LowPowerField::MotionDuration,
+            ),
+            (self.accel_range.is_some(), LowPowerField::AccelRange),
+            (self.accel_hpf.is_some(), LowPowerField::AccelHpf),
+        ]
+        .into_iter()
+        .filter_map(|(requested, field)| requested.then_some(field))
+    }
+}
+
+#[cfg(feature = "driver")]
+impl<I, D, E> Mpu6050<I, D>
+where
+    I: Write<Error = E> + WriteRead<Error = E>,
+{
+    /// Writes `changes` while cycling, see the [module docs](self)
+    pub fn adjust_low_power(&mut self, changes: LowPowerAdjust) -> Result<(), Mpu6050Error<E>> {
+        self.check_active()?;
+        if self.cycle.is_none() {
+            return Err(Mpu6050Error::InvalidSettings(SettingsError::NotCycling));
+        }
+        if let Some(field) = changes.fields().find(|field| !cycle_adjustable(*field)) {
+            return Err(Mpu6050Error::InvalidSettings(
+                SettingsError::NotAdjustableInCycle(field),
+            ));
+        }
+        if let Some(threshold) = changes.motion_threshold {
+            self.write_register(Register::MOT_THR, threshold)?;
+        }
+        if let Some(duration) = changes.motion_duration {
+            self.write_register(Register::MOT_DUR, duration)?;
+        }
+        if let Some(wake) = changes.wake_freq {
+            self.write_field(PWR_MGMT_2::LP_WAKE_CTRL_FIELD, wake)?;
+            self.cycle = Some(wake);
+        }
+        log_debug!(POWER, "cycle mode adjusted in place: {:?}", changes);
+        Ok(())
+    }
+
+    /// Enters the low power accelerometer mode waking at `freq`, see the
+    /// [module docs](self)
+    pub fn enter_low_power_accel_mode(
+        &mut self,
+        freq: LowPowerWakeFrequency,
+    ) -> Result<(), Mpu6050Error<E>> {
+        self.check_active()?;
+        // the gyros stop, so does a clock referenced to them
+        self.set_clock_source(CLKSEL::OSCILL)?;
+        let was_cycling = self.cycle.is_some();
+        self.write_power_mode(Some(freq.lp_wake_ctrl()), was_cycling)?;
+        self.set_sleep_enabled(false)?;
+        log_debug!(POWER, "low power accel mode at {:?}", freq);
+        Ok(())
+    }
+
+    pub(crate) fn exit_low_power_mode_using(
+        &mut self,
+        delay: &mut impl DelayMs<u8>,
+    ) -> Result<(), Mpu6050Error<E>> {
+        self.check_active()?;
+        let was_cycling = self.cycle.is_some();
+        self.write_power_mode(None, was_cycling)?;
+        self.set_clock_source(CLKSEL::GXAXIS)?;
+        self.set_sleep_enabled(false)?;
+        delay.delay_ms(100u8);
+        log_debug!(POWER, "low power mode left");
+        Ok(())
+    }
+
+    /// Sets or clears CYCLE alone, keeping PWR_MGMT_2 and TEMP_DIS. Cycling with a gyro
+    /// referenced clock is refused with
+    /// [`CycleWithGyroClock`](SettingsError::CycleWithGyroClock) before CYCLE is written
+    pub fn set_cycle_enabled(&mut self, enable: bool) -> Result<(), Mpu6050Error<E>> {
+        self.check_active()?;
+        if enable
+            && matches!(
+                self.get_clock_source()?,
+                CLKSEL::GXAXIS | CLKSEL::GYAXIS | CLKSEL::GZAXIS
+            )
+        {
+            return Err(Mpu6050Error::InvalidSettings(
+                SettingsError::CycleWithGyroClock,
+            ));
+        }
+        let was_cycling = self.cycle.is_some();
+        self.write_field(PWR_MGMT_1::CYCLE_FIELD, enable)?;
+        self.cycle = match enable {
+            true => {
+                let wake = self.read_field(PWR_MGMT_2::LP_WAKE_CTRL_FIELD)?;
+                Some(LP_WAKE_CTRL::from(wake))
+            }
+            false => None,
+        };
+        self.note_cycle_transition(was_cycling, enable);
+        Ok(())
+    }
+
+    /// Writes the standby bits of PWR_MGMT_2, keeping LP_WAKE_CTRL
+    pub fn set_standby_mask(&mut self, flags: StandbyFlags) -> Result<(), Mpu6050Error<E>> {
+        self.check_active()?;
+        let wake = self.read_register(Register::PWR_MGMT_2)? & !StandbyFlags::ALL.bits();
+        self.write_register(Register::PWR_MGMT_2, wake | flags.bits())
+    }
+
+    /// Standby bits of PWR_MGMT_2
+    pub fn get_standby_mask(&mut self) -> Result<StandbyFlags, Mpu6050Error<E>> {
+        Ok(StandbyFlags::from_bits(
+            self.read_register(Register::PWR_MGMT_2)?,
+        ))
+    }
+}
@@ -0,0 +1,195 @@
+//! Lock-free hand-over of the latest sample from an interrupt to the main loop.
+//!
+//! A timer interrupt owning the driver reads the sensor, the main loop wants the newest
+//! sample whenever it gets round to it. Sharing the driver behind a lock from the interrupt
+//! invites priority inversion: the main loop holding the lock blocks the interrupt.
+//! [`LatestSampleMailbox`] holds no lock: [`publish`](LatestSampleMailbox::publish) and
+//! [`take_latest`](LatestSampleMailbox::take_latest) finish in a bounded number of steps
+//! whatever the other side does, and a sample is never seen half written.
+//!
+//! #### Triple buffer
+//! The mailbox has three slots. At any time one is the back slot, written by the publisher
+//! only, one the front slot, read by the consumer only, and one the shared slot, touched by
+//! neither. The index of the shared slot, a fresh flag and the count of publishes overwritten
+//! unread live in one atomic word:
+//! 1. `publish` writes the sample into the back slot, then exchanges the back index for the
+//!    shared one, sets the fresh flag and adds one to the missed count if the flag was already
+//!    set. The slot it got back is its new back slot
+//! 2. `take_latest` returns None without the fresh flag. Otherwise it exchanges the front
+//!    index for the shared one, clearing the flag and the count, and reads the sample from the
+//!    slot it got
+//!
+//! A slot changes hands only through the exchanges on the atomic word, so the two sides never
+//! access the same slot at the same time. Both exchanges are acquire-release: the release half
+//! publishes the writes (the sample) or the reads (the consumer done with its old front) made
+//! through the slot before handing it over, the acquire half makes them visible to the side
+//! receiving it. Only the consumer clears the fresh flag, so a set flag seen by its load is
+//! still set at its exchange. The publisher's exchange is a compare-and-swap loop, it retries
+//! at most once per `take_latest` running concurrently.
+//!
+//! Every publish is either taken or counted once as missed by the take following it: summed
+//! over all takes, `taken + missed` is the number of publishes. The missed count saturates at
+//! `u16::MAX`.
+//!
+//! #### Single writer, single reader
+//! The mailbox is meant for one publishing context and one consuming context. Both methods
+//! take `&self` so the mailbox can be a `static`; a publish overlapping another publish (or a
+//! take another take) is detected and refused instead of corrupting the slots: `publish`
+//! drops the sample and returns false, `take_latest` returns None. The word is 32 bits wide
+//! and needs compare-and-swap, targets without (Cortex-M0) do not build the module.
+//!
+//! #### Pattern
+//! The driver moves into the interrupt context at startup and stays there, the mailbox is a
+//! `static` shared with the main loop. How the context is handed to the interrupt is left to
+//! the platform, e.g. a `static` cell initialized before the timer is enabled.
+//! ```
+//! use embedded_hal::blocking::i2c::{Write, WriteRead};
+//! use mpu6050::mailbox::LatestSampleMailbox;
+//! use mpu6050::Mpu6050;
+//!
+//! static LATEST: LatestSampleMailbox = LatestSampleMailbox::new();
+//!
+//! /// owned by the 1 kHz timer interrupt
+//! struct ImuContext<I> {
+//!     mpu: Mpu6050<I>,
+//! }
+//!
+//! impl<I, E> ImuContext<I>
+//! where
+//!     I: Write<Error = E> + WriteRead<Error = E>,
+//! {
+//!     /// body of the timer interrupt handler: one burst read, published
+//!     fn on_timer(&mut self) {
+//!         if let Ok(sample) = self.mpu.read_into_views::<1>(&mut []) {
+//!             LATEST.publish(sample);
+//!         }
+//!     }
+//! }
+//!
+//! /// one pass of the main loop
+//! fn main_loop_step(missed_total: &mut u32) {
+//!     if let Some((sample, missed)) = LATEST.take_latest() {
+//!         *missed_total += u32::from(missed);
+//!         let _tilt = sample.acc();
+//!     }
+//! }
+//! ```
+
+use core::cell::UnsafeCell;
+use core::fmt;
+use core::sync::atomic::{AtomicBool, AtomicU32, AtomicU8, Ordering};
+
+use glam::Vec3A;
+
+use crate::MpuSample;
+
+/// shared slot index in the state word
+const INDEX: u32 = 0b11;
+/// the shared slot holds a publish not taken yet
+const FRESH: u32 = 1 << 2;
+/// position of the missed count in the state word
+const MISSED_SHIFT: u32 = 16;
+
+/// Single-writer, single-reader cell for the latest [`MpuSample`], see the
+/// [module docs](self)
+pub struct LatestSampleMailbox {
+    slots: [UnsafeCell<MpuSample>; 3],
+    /// shared slot index, [`FRESH`] and the missed count
+    state: AtomicU32,
+    /// back slot index, accessed by the publisher only
+    back: AtomicU8,
+    /// front slot index, accessed by the consumer only
+    front: AtomicU8,
+    publishing: AtomicBool,
+    taking: AtomicBool,
+}
+
+// SAFETY: a slot is accessed by the side owning it only: the back slot by the one publish
+// holding `publishing`, the front slot by the one take holding `taking`, the shared slot by
+// neither. Ownership moves through the acquire-release exchanges on `state`, which order
+// the slot accesses of both sides, see the module docs.
+unsafe impl Sync for LatestSampleMailbox {}
+
+impl LatestSampleMailbox {
+    /// empty mailbox
+    pub const fn new() -> Self {
+        const EMPTY: MpuSample = MpuSample::new(Vec3A::ZERO, Vec3A::ZERO, 0.);
+        Self {
+            slots: [
+                UnsafeCell::new(EMPTY),
+                UnsafeCell::new(EMPTY),
+                UnsafeCell::new(EMPTY),
+            ],
+            state: AtomicU32::new(1),
+            back: AtomicU8::new(0),
+            front: AtomicU8::new(2),
+            publishing: AtomicBool::new(false),
+            taking: AtomicBool::new(false),
+        }
+    }
+
+    /// Makes `sample` the latest, overwriting a publish not taken yet. False if another
+    /// publish was in progress: the sample is dropped
+    pub fn publish(&self, sample: MpuSample) -> bool {
+        if self.publishing.swap(true, Ordering::Acquire) {
+            return false;
+        }
+        let back = self.back.load(Ordering::Relaxed);
+        // SAFETY: the back slot belongs to this publish, see the Sync impl
+        unsafe { *self.slots[back as usize].get() = sample };
+        let exchange = |state: u32| {
+            let missed = (state >> MISSED_SHIFT) as u16;
+            let missed = match state & FRESH {
+                0 => missed,
+                _ => missed.saturating_add(1),
+            };
+            Some(back as u32 | FRESH | (missed as u32) << MISSED_SHIFT)
+        };
+        let previous = self
+            .state
+            .fetch_update(Ordering::AcqRel, Ordering::Relaxed, exchange)
+            .unwrap_or_else(|state| state);
+        self.back.store((previous & INDEX) as u8, Ordering::Relaxed);
+        self.publishing.store(false, Ordering::Release);
+        true
+    }
+
+    /// The latest sample and the number of publishes overwritten unread since the previous
+    /// take, None if nothing was published since, or another take is in progress
+    pub fn take_latest(&self) -> Option<(MpuSample, u16)> {
+        if self.taking.swap(true, Ordering::Acquire) {
+            return None;
+        }
+        let taken = match self.state.load(Ordering::Relaxed) & FRESH {
+            0 => None,
+            _ => {
+                let front = self.front.load(Ordering::Relaxed);
+                let previous = self.state.swap(front as u32, Ordering::AcqRel);
+                let index = previous & INDEX;
+                self.front.store(index as u8, Ordering::Relaxed);
+                // SAFETY: the slot handed over by the exchange is the new front slot, which
+                // belongs to this take, see the Sync impl
+                let sample = unsafe { *self.slots[index as usize].get() };
+                Some((sample, (previous >> MISSED_SHIFT) as u16))
+            }
+        };
+        self.taking.store(false, Ordering::Release);
+        taken
+    }
+}
+
+impl Default for LatestSampleMailbox {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Debug for LatestSampleMailbox {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let state = self.state.load(Ordering::Relaxed);
+        f.debug_struct("LatestSampleMailbox")
+            .field("fresh", &(state & FRESH != 0))
+            .field("missed", &(state >> MISSED_SHIFT))
+            .finish()
+    }
+}
@@ -0,0 +1,95 @@
+//! Float math of a `no_std` build.
+//!
+//! The float methods the crate uses beyond `core` are std's, which calls the platform's libm.
+//! Without std, [`FloatExt`] provides the same methods under the same names through the
+//! `libm` crate, a module imports it when std is off and the call sites stay as they are.
+//! `sqrt` and the rounding are exact either way, the transcendental functions can differ from
+//! std's in the last bit, see the `determinism` module for bit-exact results.
+
+// not every feature combination calls every method
+#[allow(dead_code)]
+pub(crate) trait FloatExt: Sized {
+    fn sqrt(self) -> Self;
+    fn round(self) -> Self;
+    fn round_ties_even(self) -> Self;
+    fn trunc(self) -> Self;
+    fn floor(self) -> Self;
+    fn ceil(self) -> Self;
+    fn exp(self) -> Self;
+    fn ln(self) -> Self;
+    fn sin_cos(self) -> (Self, Self);
+    fn atan2(self, other: Self) -> Self;
+    fn powi(self, n: i32) -> Self;
+}
+
+impl FloatExt for f32 {
+    fn sqrt(self) -> Self {
+        libm::sqrtf(self)
+    }
+    fn round(self) -> Self {
+        libm::roundf(self)
+    }
+    fn round_ties_even(self) -> Self {
+        libm::roundevenf(self)
+    }
+    fn trunc(self) -> Self {
+        libm::truncf(self)
+    }
+    fn floor(self) -> Self {
+        libm::floorf(self)
+    }
+    fn ceil(self) -> Self {
+        libm::ceilf(self)
+    }
+    fn exp(self) -> Self {
+        libm::expf(self)
+    }
+    fn ln(self) -> Self {
+        libm::logf(self)
+    }
+    fn sin_cos(self) -> (Self, Self) {
+        libm::sincosf(self)
+    }
+    fn atan2(self, other: Self) -> Self {
+        libm::atan2f(self, other)
+    }
+    fn powi(self, n: i32) -> Self {
+        libm::powf(self, n as f32)
+    }
+}
+
+impl FloatExt for f64 {
+    fn sqrt(self) -> Self {
+        libm::sqrt(self)
+    }
+    fn round(self) -> Self {
+        libm::round(self)
+    }
+    fn round_ties_even(self) -> Self {
+        libm::roundeven(self)
+    }
+    fn trunc(self) -> Self {
+        libm::trunc(self)
+    }
+    fn floor(self) -> Self {
+        libm::floor(self)
+    }
+    fn ceil(self) -> Self {
+        libm::ceil(self)
+    }
+    fn exp(self) -> Self {
+        libm::exp(self)
+    }
+    fn ln(self) -> Self {
+        libm::log(self)
+    }
+    fn sin_cos(self) -> (Self, Self) {
+        libm::sincos(self)
+    }
+    fn atan2(self, other: Self) -> Self {
+        libm::atan2(self, other)
+    }
+    fn powi(self, n: i32) -> Self {
+        libm::pow(self, n as f64)
+    }
+}
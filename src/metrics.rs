@@ -0,0 +1,374 @@
+//! Streaming driver metrics for telemetry backends.
+//!
+//! A [`MetricsSink`] installed with [`Mpu6050::set_metrics_sink`] sees counters, gauges and
+//! events the moment they happen, no polling of [`io_stats`](Mpu6050::io_stats) or the
+//! interrupt tracker needed. Without a sink every emission point costs one `Option` check,
+//! nothing is allocated either way: the sink is a `&'static` reference, usually to a
+//! `static` with interior mutability.
+//!
+//! #### Counters
+//! The names are stable, dashboards can be built against them.
+//!
+//! | name | delta | emitted |
+//! |:---|:---|:---|
+//! | [`BUS_TRANSACTIONS`] | 1 | every attempted transaction, where [`IoStats`](crate::op_bounds::IoStats) counts |
+//! | [`BUS_BYTES`] | bytes | every attempted transaction |
+//! | [`BUS_ERRORS`] | 1 | every failed transaction |
+//! | [`DISCONNECTS`] | 1 | the connection monitor enters `Disconnected` |
+//! | [`RECONNECTS`] | 1 | `try_reconnect` succeeded |
+//! | [`INITS`] | 1 | `init` succeeded |
+//! | [`SETTLING_RETRIES`] | 1 | a settling sample is discarded and read again |
+//! | [`ACC_CLIPPED`], [`GYRO_CLIPPED`] | 1 | a read has an axis at the end of the ADC range |
+//! | [`SUPERVISOR_TRIPS`] | 1 | the supervisor latches |
+//! | [`SAMPLING_OVERRUNS`] | 1 | the sampling loop detects an overrun |
+//! | [`FIFO_OVERFLOWS`] | 1 | a drain finds the FIFO full and resets it, see [`fifo_recovery`](crate::fifo_recovery) |
+//! | [`BUS_RECOVERIES`] | 1 | the bus reset callback is invoked, see [`recovery`](crate::recovery) |
+//! | [`BUS_RECOVERIES_RATE_LIMITED`] | 1 | a lockup is detected within the rate limit |
+//! | [`TRAFFIC_VIOLATIONS`] | 1 | a guarded section sees a transaction it does not allow, see [`traffic_guard`](crate::traffic_guard) |
+//! | [`INIT_FINDINGS`] | 1 | `init` finds a leftover of a previous run, see [`init_findings`](crate::init_findings) |
+//! | [`OPERATION_FAILURES`] | 1 | `init` or `try_reconnect` fails, see [`black_box`](crate::black_box) |
+//! | [`interrupt_counter`] | 1 | `poll_interrupt_events` sees a source fire, one name per source |
+//!
+//! Each counter except the bus ones comes with a [`MetricEvent`] carrying the details,
+//! [`BUS_RECOVERIES`] included.
+//!
+//! #### Gauges
+//! Per-sample gauges are off by default, the sample paths run at the output data rate.
+//! [`Mpu6050::set_gauge_limiter`] opts in with a [`GaugeLimiter`] that passes every nth
+//! scaled sample on in the order the [`hook`](crate::hook) module lists, after the tap.
+//! [`GAUGE_ACC_G`], [`GAUGE_GYRO_RAD_S`] and [`GAUGE_TEMP_C`] are the accel magnitude, gyro
+//! magnitude and temperature of the sample, parts not read (NaN) are left out.
+//!
+//! A sink sees everything from within the driver call that caused it, like the sample tap: it
+//! should record and return. [`MemorySink`] is a reference implementation accumulating into
+//! maps:
+//! ```
+//! use mpu6050::metrics::{MemorySink, MetricEvent, MetricsSink, BUS_ERRORS};
+//!
+//! let sink = MemorySink::new();
+//! sink.counter(BUS_ERRORS, 1);
+//! sink.counter(BUS_ERRORS, 2);
+//! sink.event(MetricEvent::FifoOverflow { bytes: 1024 });
+//! assert_eq!(sink.counter_value(BUS_ERRORS), 3);
+//! assert_eq!(sink.events(), [MetricEvent::FifoOverflow { bytes: 1024 }]);
+//! ```
+
+#[cfg(feature = "std")]
+use std::collections::HashMap;
+#[cfg(feature = "std")]
+use std::sync::Mutex;
+#[cfg(feature = "std")]
+use std::vec::Vec;
+
+use crate::black_box::FailureRecord;
+use crate::connection::ReconnectOutcome;
+use crate::device::Axis;
+use crate::init_findings::InitFinding;
+use crate::interrupt::InterruptSource;
+use crate::recovery::{BusRecoveryOutcome, ResyncOutcome};
+use crate::supervisor::SupervisorEvent;
+use crate::Mpu6050;
+#[cfg(not(feature = "minimal-pipeline"))]
+use crate::MpuSample;
+
+/// attempted bus transactions
+pub const BUS_TRANSACTIONS: &str = "mpu6050.bus.transactions";
+/// bytes of the attempted bus transactions
+pub const BUS_BYTES: &str = "mpu6050.bus.bytes";
+/// failed bus transactions
+pub const BUS_ERRORS: &str = "mpu6050.bus.errors";
+/// connection lost
+pub const DISCONNECTS: &str = "mpu6050.connection.disconnects";
+/// successful reconnects
+pub const RECONNECTS: &str = "mpu6050.connection.reconnects";
+/// successful inits
+pub const INITS: &str = "mpu6050.init";
+/// settling samples discarded and read again
+pub const SETTLING_RETRIES: &str = "mpu6050.settling.retries";
+/// accelerometer reads with a clipped axis
+pub const ACC_CLIPPED: &str = "mpu6050.acc.clipped";
+/// gyro reads with a clipped axis
+pub const GYRO_CLIPPED: &str = "mpu6050.gyro.clipped";
+/// supervisor latches
+pub const SUPERVISOR_TRIPS: &str = "mpu6050.supervisor.trips";
+/// sampling loop overruns
+pub const SAMPLING_OVERRUNS: &str = "mpu6050.sampling.overruns";
+/// full FIFO found by a drain
+pub const FIFO_OVERFLOWS: &str = "mpu6050.fifo.overflows";
+/// bus reset callback invocations
+pub const BUS_RECOVERIES: &str = "mpu6050.bus.recoveries";
+/// bus lockups detected within the rate limit
+pub const BUS_RECOVERIES_RATE_LIMITED: &str = "mpu6050.bus.recoveries_rate_limited";
+/// transactions not allowed in a guarded section
+pub const TRAFFIC_VIOLATIONS: &str = "mpu6050.bus.traffic_violations";
+/// anomalies found by init
+pub const INIT_FINDINGS: &str = "mpu6050.init.findings";
+/// failed inits and reconnects
+pub const OPERATION_FAILURES: &str = "mpu6050.operation.failures";
+/// rising edges per interrupt source, indexed by [`InterruptSource::index`]
+pub const INTERRUPTS: [&str; InterruptSource::ALL.len()] = [
+    "mpu6050.interrupt.free_fall",
+    "mpu6050.interrupt.motion",
+    "mpu6050.interrupt.zero_motion",
+    "mpu6050.interrupt.fifo_overflow",
+    "mpu6050.interrupt.i2c_master",
+    "mpu6050.interrupt.data_ready",
+];
+/// accel magnitude of a sample in g
+pub const GAUGE_ACC_G: &str = "mpu6050.sample.acc_g";
+/// gyro magnitude of a sample in rad/s
+pub const GAUGE_GYRO_RAD_S: &str = "mpu6050.sample.gyro_rad_s";
+/// temperature of a sample in °C
+pub const GAUGE_TEMP_C: &str = "mpu6050.sample.temp_c";
+
+/// counter name of the rising edges of `source`
+pub const fn interrupt_counter(source: InterruptSource) -> &'static str {
+    INTERRUPTS[source.index()]
+}
+
+/// Sensor of a [`MetricEvent::SampleClipped`]
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Sensor {
+    /// accelerometer
+    Accel,
+    /// gyroscope
+    Gyro,
+}
+
+/// Notable driver events, each emitted with its counter
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum MetricEvent {
+    /// `init` succeeded, [`INITS`]
+    Initialized {
+        /// WHO_AM_I value
+        chip_id: u8,
+    },
+    /// the connection monitor entered `Disconnected`, [`DISCONNECTS`]
+    Disconnected,
+    /// `try_reconnect` succeeded, [`RECONNECTS`]
+    Reconnected(ReconnectOutcome),
+    /// a settling sample was discarded and read again, [`SETTLING_RETRIES`]
+    SettlingRetry(Sensor),
+    /// a read had an axis at the end of the ADC range, [`ACC_CLIPPED`] or [`GYRO_CLIPPED`]
+    SampleClipped {
+        /// sensor read
+        sensor: Sensor,
+        /// first clipped axis
+        axis: Axis,
+    },
+    /// the supervisor latched, [`SUPERVISOR_TRIPS`]
+    SupervisorTripped(SupervisorEvent),
+    /// the sampling loop detected an overrun, [`SAMPLING_OVERRUNS`]
+    SamplingOverrun,
+    /// a drain found the FIFO full and reset it, [`FIFO_OVERFLOWS`]
+    FifoOverflow {
+        /// FIFO count read, the pre-reset count
+        bytes: u16,
+    },
+    /// an interrupt source fired, [`interrupt_counter`]
+    Interrupt(InterruptSource),
+    /// the bus reset callback was invoked, [`BUS_RECOVERIES`]
+    BusRecovery {
+        /// what the callback did
+        outcome: BusRecoveryOutcome,
+        /// resynchronization, None unless recovered
+        resync: Option<ResyncOutcome>,
+    },
+    /// init found a leftover of a previous run, [`INIT_FINDINGS`]
+    InitFinding(InitFinding),
+    /// `init` or `try_reconnect` failed, [`OPERATION_FAILURES`]
+    OperationFailed(FailureRecord),
+}
+
+/// Receiver of driver metrics, see the [module docs](self). All methods default to doing
+/// nothing, a sink implements what its backend takes
+pub trait MetricsSink: Sync {
+    /// `name` grew by `delta`
+    fn counter(&self, name: &'static str, delta: u32) {
+        let _ = (name, delta);
+    }
+
+    /// `name` is `value` now
+    fn gauge(&self, name: &'static str, value: f32) {
+        let _ = (name, value);
+    }
+
+    /// `event` happened
+    fn event(&self, event: MetricEvent) {
+        let _ = event;
+    }
+}
+
+/// Passes every `every`th sample to the gauges, no bus access
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct GaugeLimiter {
+    every: u32,
+    skipped: u32,
+}
+
+impl GaugeLimiter {
+    /// limiter passing the first sample and then every `every`th, 0 behaves like 1
+    pub fn new(every: u32) -> Self {
+        Self {
+            every: every.max(1),
+            skipped: 0,
+        }
+    }
+
+    /// samples per gauge emission
+    pub fn every(&self) -> u32 {
+        self.every
+    }
+
+    /// true if this sample is emitted
+    pub fn tick(&mut self) -> bool {
+        if self.skipped == 0 {
+            self.skipped = self.every - 1;
+            true
+        } else {
+            self.skipped -= 1;
+            false
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+#[derive(Default)]
+struct Recorded {
+    counters: HashMap<&'static str, u64>,
+    gauges: HashMap<&'static str, f32>,
+    events: Vec<MetricEvent>,
+}
+
+/// Reference sink: sums counters, keeps the last value of each gauge and all events
+#[cfg(feature = "std")]
+#[derive(Default)]
+pub struct MemorySink {
+    recorded: Mutex<Recorded>,
+}
+
+#[cfg(feature = "std")]
+impl MemorySink {
+    /// empty sink
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// sum of the deltas of `name`, 0 if never emitted
+    pub fn counter_value(&self, name: &str) -> u64 {
+        self.lock().counters.get(name).copied().unwrap_or(0)
+    }
+
+    /// all counters with their sums
+    pub fn counters(&self) -> HashMap<&'static str, u64> {
+        self.lock().counters.clone()
+    }
+
+    /// last value of `name`
+    pub fn gauge_value(&self, name: &str) -> Option<f32> {
+        self.lock().gauges.get(name).copied()
+    }
+
+    /// events in emission order
+    pub fn events(&self) -> Vec<MetricEvent> {
+        self.lock().events.clone()
+    }
+
+    /// forget everything recorded
+    pub fn clear(&self) {
+        *self.lock() = Recorded::default();
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Recorded> {
+        // a panicking test thread leaves plain data behind, keep using it
+        self.recorded.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+#[cfg(feature = "std")]
+impl MetricsSink for MemorySink {
+    fn counter(&self, name: &'static str, delta: u32) {
+        *self.lock().counters.entry(name).or_default() += u64::from(delta);
+    }
+
+    fn gauge(&self, name: &'static str, value: f32) {
+        self.lock().gauges.insert(name, value);
+    }
+
+    fn event(&self, event: MetricEvent) {
+        self.lock().events.push(event);
+    }
+}
+
+impl<I, D> Mpu6050<I, D> {
+    /// set or remove the metrics sink, see [`metrics`](crate::metrics)
+    pub fn set_metrics_sink(&mut self, sink: Option<&'static dyn MetricsSink>) {
+        self.metrics = sink;
+    }
+
+    /// get metrics sink
+    pub fn get_metrics_sink(&self) -> Option<&'static dyn MetricsSink> {
+        self.metrics
+    }
+
+    /// set or remove the limiter of the per-sample gauges, None (default) emits no gauges
+    pub fn set_gauge_limiter(&mut self, limiter: Option<GaugeLimiter>) {
+        self.gauge_limiter = limiter;
+    }
+
+    /// get gauge limiter
+    pub fn get_gauge_limiter(&self) -> Option<GaugeLimiter> {
+        self.gauge_limiter
+    }
+
+    pub(crate) fn emit_counter(&self, name: &'static str, delta: u32) {
+        if let Some(sink) = self.metrics {
+            sink.counter(name, delta);
+        }
+    }
+
+    /// `event` with its counter
+    pub(crate) fn emit_event(&self, name: &'static str, event: MetricEvent) {
+        #[cfg(any(feature = "log", feature = "defmt"))]
+        crate::logging::log_event(&event);
+        if let Some(sink) = self.metrics {
+            sink.counter(name, 1);
+            sink.event(event);
+        }
+    }
+
+    /// clipping check of a raw accel or gyro triplet
+    pub(crate) fn emit_clipped(&mut self, sensor: Sensor, raw: [i16; 3]) {
+        let clipped = Axis::ALL
+            .into_iter()
+            .find(|axis| matches!(raw[*axis as usize], i16::MIN | i16::MAX));
+        if let Some(axis) = clipped {
+            self.note_clipped();
+            let name = match sensor {
+                Sensor::Accel => ACC_CLIPPED,
+                Sensor::Gyro => GYRO_CLIPPED,
+            };
+            self.emit_event(name, MetricEvent::SampleClipped { sensor, axis });
+        }
+    }
+
+    /// gauges of a delivered sample, if the limiter lets it through
+    #[cfg(not(feature = "minimal-pipeline"))]
+    pub(crate) fn emit_gauges(&mut self, sample: &MpuSample) {
+        let (Some(sink), Some(limiter)) = (self.metrics, &mut self.gauge_limiter) else {
+            return;
+        };
+        if !limiter.tick() {
+            return;
+        }
+        for (name, value) in [
+            (GAUGE_ACC_G, sample.acc.length()),
+            (GAUGE_GYRO_RAD_S, sample.gyro.length()),
+            (GAUGE_TEMP_C, sample.temp),
+        ] {
+            if !value.is_nan() {
+                sink.gauge(name, value);
+            }
+        }
+    }
+}
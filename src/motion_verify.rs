@@ -0,0 +1,332 @@
+//! Functional verification of the motion detection engine, and arming gated by it.
+//!
+//! Clones exist that accept MOT_THR and MOT_DUR but never latch MOT_INT: a wake on motion
+//! armed on one of them never wakes, and nothing fails. [`Mpu6050::verify_motion_detection`]
+//! runs the engine once and records the result in
+//! [`ChipCapabilities::motion_engine`](crate::device::ChipCapabilities::motion_engine):
+//!
+//! 1. ACCEL_CONFIG, MOT_THR, MOT_DUR, MOT_DETECT_CONTROL and INT_ENABLE are saved
+//! 2. a hair trigger is configured: MOT_THR and MOT_DUR at 1, the accel HPF at 5 Hz, ±8 g,
+//!    MOT_EN set, then [`MOTION_SETTLE_MS`] waited and INT_STATUS read to drop a latch of
+//!    the reconfiguration
+//! 3. the stimulus: with a prompt, the prompt is called and the operator moves the device;
+//!    without one, the accel self-test is switched on
+//! 4. INT_STATUS is polled every [`MOTION_POLL_INTERVAL_MS`], for [`PROMPT_TIMEOUT_MS`] after
+//!    a prompt and [`SELF_TEST_TIMEOUT_MS`] after a self-test switch
+//! 5. the saved registers are written back, also after a bus error, and INT_STATUS read once
+//!    more to drop the latch of the test
+//!
+//! | outcome | engine status |
+//! |:---|:---|
+//! | [`Latched`](MotionVerifyOutcome::Latched) | [`Verified`](MotionEngineStatus::Verified) |
+//! | [`NotLatched`](MotionVerifyOutcome::NotLatched) | [`Broken`](MotionEngineStatus::Broken) |
+//! | [`NoStimulus`](MotionVerifyOutcome::NoStimulus), [`Asleep`](MotionVerifyOutcome::Asleep) | unchanged |
+//!
+//! Every INT_STATUS read clears the flags of the other sources too: interrupts pending
+//! during the verification are lost. A sleeping chip is not tested and nothing is written.
+//!
+//! #### Self-test as the stimulus
+//! Unattended, the accel self-test stands in for motion: it deflects the proof mass
+//! electrostatically by 0.3 g to 1 g, a step the HPF passes as a transient far above the
+//! hair trigger. It is a weaker proof than real motion:
+//! * a clone may drive the self-test differently or not at all. The accel output is read
+//!   before the switch and after the timeout, and if it moved less than
+//!   [`ACCEL_SELF_TEST_MIN_G`](crate::clone_detect::ACCEL_SELF_TEST_MIN_G) on every axis the
+//!   engine went untested: the outcome is [`NoStimulus`](MotionVerifyOutcome::NoStimulus)
+//!   and the status stays as it was, a dead self-test does not make a broken engine
+//! * the device has to be still. Motion during the test latches MOT_INT on its own, which
+//!   proves the engine fires all the same, but spoils the response measured
+//! * at the hair trigger, noise can latch MOT_INT: any latch after the settling read counts,
+//!   the engine fired
+//! * a latch on the self-test shows the engine reacts to the accel signal path it shares
+//!   with motion, not that MOT_THR scales like the datasheet says. The threshold of the
+//!   application is not tested, an operator test at that threshold is the complete check
+//!
+//! #### Arming
+//! [`Mpu6050::arm_wake_on_motion`] is
+//! [`setup_motion_detection`](crate::Mpu6050::setup_motion_detection)
+//! behind a [`WakeOnMotionPolicy`]: a refused arming fails with
+//! [`Mpu6050Error::WakeOnMotionRefused`] carrying the engine status, before the bus is
+//! touched. `setup_motion_detection` itself stays ungated.
+//! ```
+//! use mpu6050::device::MotionEngineStatus;
+//! use mpu6050::motion_verify::WakeOnMotionPolicy;
+//!
+//! assert!(WakeOnMotionPolicy::RefuseBroken.allows(MotionEngineStatus::Unverified));
+//! assert!(!WakeOnMotionPolicy::RefuseBroken.allows(MotionEngineStatus::Broken));
+//! assert!(!WakeOnMotionPolicy::RequireVerified.allows(MotionEngineStatus::Unverified));
+//! assert!(WakeOnMotionPolicy::Override.allows(MotionEngineStatus::Broken));
+//! ```
+
+#[cfg(feature = "driver")]
+use embedded_hal::blocking::{
+    delay::DelayMs,
+    i2c::{Write, WriteRead},
+};
+use glam::Vec3A;
+
+#[cfg(feature = "driver")]
+use crate::clone_detect::ACCEL_SELF_TEST_MIN_G;
+#[cfg(feature = "driver")]
+use crate::deadline::Watch;
+use crate::device::MotionEngineStatus;
+#[cfg(feature = "driver")]
+use crate::device::{
+    AccelRange, SettleTrigger, ACCEL_CONFIG, ACCEL_HPF, INT_ENABLE, INT_STATUS, MOT_DETECT_CONTROL,
+};
+#[cfg(feature = "driver")]
+use crate::interrupt::MotionDetectionConfig;
+#[cfg(feature = "driver")]
+use crate::register::{AccelConfigValue, Register};
+#[cfg(feature = "driver")]
+use crate::{Mpu6050, Mpu6050Error};
+
+/// Wait after configuring the hair trigger, before the stimulus, in ms
+pub const MOTION_SETTLE_MS: u8 = 50;
+
+/// Time between INT_STATUS polls in ms
+pub const MOTION_POLL_INTERVAL_MS: u8 = 10;
+
+/// Time MOT_INT has to latch in after the prompt, in ms
+pub const PROMPT_TIMEOUT_MS: u32 = 10_000;
+
+/// Time MOT_INT has to latch in after the self-test switch, in ms
+pub const SELF_TEST_TIMEOUT_MS: u32 = 100;
+
+/// [`Mpu6050Error::Aborted`](crate::Mpu6050Error::Aborted) phase of an expired deadline
+pub const PHASE: &str = "MotionVerify";
+
+/// LSB per g at ±8 g, the range of the verification
+#[cfg(feature = "driver")]
+const LSB_PER_G: f32 = 4096.;
+
+/// Stimulus of a verification
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum MotionStimulus {
+    /// the operator moved the device when prompted
+    Operator,
+    /// the accel self-test was switched on
+    SelfTest,
+}
+
+/// Result of [`Mpu6050::verify_motion_detection`](crate::Mpu6050::verify_motion_detection)
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum MotionVerifyOutcome {
+    /// MOT_INT latched
+    Latched {
+        /// stimulus applied
+        stimulus: MotionStimulus,
+        /// time from the stimulus to the poll seeing MOT_INT, in ms
+        after_ms: u32,
+    },
+    /// MOT_INT did not latch within the timeout
+    NotLatched {
+        /// stimulus applied
+        stimulus: MotionStimulus,
+    },
+    /// the self-test moved the accel output less than
+    /// [`ACCEL_SELF_TEST_MIN_G`](crate::clone_detect::ACCEL_SELF_TEST_MIN_G) on every
+    /// axis, the engine went untested
+    NoStimulus {
+        /// accel output with self-test less without, in g
+        response_g: Vec3A,
+    },
+    /// the chip sleeps, nothing was tested or written
+    Asleep,
+}
+
+impl MotionVerifyOutcome {
+    /// engine status the outcome shows, None if it shows nothing, see the
+    /// [module docs](self)
+    pub const fn engine_status(&self) -> Option<MotionEngineStatus> {
+        match self {
+            MotionVerifyOutcome::Latched { .. } => Some(MotionEngineStatus::Verified),
+            MotionVerifyOutcome::NotLatched { .. } => Some(MotionEngineStatus::Broken),
+            MotionVerifyOutcome::NoStimulus { .. } | MotionVerifyOutcome::Asleep => None,
+        }
+    }
+}
+
+/// Engine states [`Mpu6050::arm_wake_on_motion`](crate::Mpu6050::arm_wake_on_motion) arms in
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum WakeOnMotionPolicy {
+    /// any, the explicit override for an engine known broken
+    Override,
+    /// unverified or verified
+    RefuseBroken,
+    /// verified only
+    RequireVerified,
+}
+
+impl WakeOnMotionPolicy {
+    /// true if arming is allowed with the engine in `status`
+    pub const fn allows(&self, status: MotionEngineStatus) -> bool {
+        match self {
+            WakeOnMotionPolicy::Override => true,
+            WakeOnMotionPolicy::RefuseBroken => !matches!(status, MotionEngineStatus::Broken),
+            WakeOnMotionPolicy::RequireVerified => {
+                matches!(status, MotionEngineStatus::Verified)
+            }
+        }
+    }
+}
+
+/// registers the verification writes, in the order they are written back
+#[cfg(feature = "driver")]
+const SAVED: [Register; 5] = [
+    Register::ACCEL_CONFIG,
+    Register::MOT_THR,
+    Register::MOT_DUR,
+    Register::MOT_DETECT_CONTROL,
+    Register::INT_ENABLE,
+];
+
+#[cfg(feature = "driver")]
+impl<I, D, E> Mpu6050<I, D>
+where
+    I: Write<Error = E> + WriteRead<Error = E>,
+{
+    /// Arms the motion interrupt like
+    /// [`setup_motion_detection`](Mpu6050::setup_motion_detection) if `policy` allows the
+    /// engine status, see the [module docs](crate::motion_verify)
+    pub fn arm_wake_on_motion(
+        &mut self,
+        policy: WakeOnMotionPolicy,
+        config: MotionDetectionConfig,
+    ) -> Result<(), Mpu6050Error<E>> {
+        self.check_active()?;
+        let engine = self.capabilities.motion_engine;
+        if !policy.allows(engine) {
+            return Err(Mpu6050Error::WakeOnMotionRefused(engine));
+        }
+        self.setup_motion_detection(config)
+    }
+
+    pub(crate) fn verify_motion_detection_using(
+        &mut self,
+        delay: &mut impl DelayMs<u8>,
+        prompt: Option<&mut dyn FnMut()>,
+        deadline: &mut dyn FnMut() -> bool,
+    ) -> Result<MotionVerifyOutcome, Mpu6050Error<E>> {
+        self.check_active()?;
+        if self.power.asleep {
+            return Ok(MotionVerifyOutcome::Asleep);
+        }
+        let mut watch = Watch::new(PHASE, deadline, self.io_stats.transactions);
+        let mut saved = [0; SAVED.len()];
+        for (value, reg) in saved.iter_mut().zip(SAVED) {
+            watch.check(self.io_stats.transactions, None)?;
+            *value = self.read_register(reg)?;
+        }
+        let outcome = self.run_motion_verification(delay, &mut watch, prompt);
+        // restore even if the run failed
+        let mut restored = Ok(());
+        for (value, reg) in saved.into_iter().zip(SAVED) {
+            restored = restored.and(self.write_register(reg, value));
+        }
+        self.settle.trigger(SettleTrigger::SelfTest);
+        let cleared = self.read_register(Register::INT_STATUS);
+        let outcome = outcome?;
+        restored?;
+        cleared?;
+        if let Some(status) = outcome.engine_status() {
+            self.capabilities.motion_engine = status;
+        }
+        Ok(outcome)
+    }
+
+    /// steps 2 to 4 of the verification
+    fn run_motion_verification(
+        &mut self,
+        delay: &mut impl DelayMs<u8>,
+        watch: &mut Watch,
+        prompt: Option<&mut dyn FnMut()>,
+    ) -> Result<MotionVerifyOutcome, Mpu6050Error<E>> {
+        let config = |self_test| AccelConfigValue {
+            self_test: [self_test; 3],
+            range: AccelRange::G8,
+            hpf: ACCEL_HPF::_5,
+        };
+        watch.check(self.io_stats.transactions, None)?;
+        self.write_typed::<ACCEL_CONFIG>(config(false))?;
+        for reg in [Register::MOT_THR, Register::MOT_DUR] {
+            watch.check(self.io_stats.transactions, None)?;
+            self.write_register(reg, 1)?;
+        }
+        for (field, value) in [
+            (MOT_DETECT_CONTROL::ACCEL_ON_DELAY_FIELD, 1),
+            (MOT_DETECT_CONTROL::FF_COUNT_FIELD, 1),
+            (MOT_DETECT_CONTROL::MOT_COUNT_FIELD, 1),
+        ] {
+            watch.check(self.io_stats.transactions, None)?;
+            self.write_field(field, value)?;
+        }
+        watch.check(self.io_stats.transactions, None)?;
+        self.write_field(INT_ENABLE::MOT_EN_FIELD, true)?;
+        watch.check(self.io_stats.transactions, None)?;
+        watch.wait(delay, MOTION_SETTLE_MS);
+        watch.check(self.io_stats.transactions, None)?;
+        self.read_register(Register::INT_STATUS)?;
+
+        match prompt {
+            Some(prompt) => {
+                prompt();
+                let stimulus = MotionStimulus::Operator;
+                Ok(
+                    match self.poll_motion_latch(delay, watch, PROMPT_TIMEOUT_MS)? {
+                        Some(after_ms) => MotionVerifyOutcome::Latched { stimulus, after_ms },
+                        None => MotionVerifyOutcome::NotLatched { stimulus },
+                    },
+                )
+            }
+            None => {
+                watch.check(self.io_stats.transactions, None)?;
+                let off = self.read_accel_counts()?;
+                watch.check(self.io_stats.transactions, None)?;
+                self.write_typed::<ACCEL_CONFIG>(config(true))?;
+                let stimulus = MotionStimulus::SelfTest;
+                let latched = self.poll_motion_latch(delay, watch, SELF_TEST_TIMEOUT_MS)?;
+                if let Some(after_ms) = latched {
+                    return Ok(MotionVerifyOutcome::Latched { stimulus, after_ms });
+                }
+                watch.check(self.io_stats.transactions, None)?;
+                let response_g = (self.read_accel_counts()? - off) / LSB_PER_G;
+                Ok(if response_g.abs().max_element() < ACCEL_SELF_TEST_MIN_G {
+                    MotionVerifyOutcome::NoStimulus { response_g }
+                } else {
+                    MotionVerifyOutcome::NotLatched { stimulus }
+                })
+            }
+        }
+    }
+
+    /// ms until MOT_INT was seen, None if not within `timeout_ms`
+    fn poll_motion_latch(
+        &mut self,
+        delay: &mut impl DelayMs<u8>,
+        watch: &mut Watch,
+        timeout_ms: u32,
+    ) -> Result<Option<u32>, Mpu6050Error<E>> {
+        let mut waited_ms = 0;
+        loop {
+            watch.check(self.io_stats.transactions, None)?;
+            if self.read_flag(INT_STATUS::MOT_INT_FIELD)? {
+                return Ok(Some(waited_ms));
+            }
+            if waited_ms >= timeout_ms {
+                return Ok(None);
+            }
+            watch.check(self.io_stats.transactions, None)?;
+            watch.wait(delay, MOTION_POLL_INTERVAL_MS);
+            waited_ms += u32::from(MOTION_POLL_INTERVAL_MS);
+        }
+    }
+
+    /// accel output in counts, one burst
+    fn read_accel_counts(&mut self) -> Result<Vec3A, Mpu6050Error<E>> {
+        let mut buf = [0; 6];
+        self.read_registers(Register::ACCEL_XOUT_H, &mut buf)?;
+        let count = |axis: usize| i16::from_be_bytes([buf[2 * axis], buf[2 * axis + 1]]) as f32;
+        Ok(Vec3A::new(count(0), count(1), count(2)))
+    }
+}
@@ -0,0 +1,579 @@
+//! Numeric compatibility: the outputs of a baseline release over the fixture corpus, and the
+//! drift of the current code from them.
+//!
+//! The scaled samples and filter results are part of the contract, products are calibrated
+//! around them, and a refactor changing them by an LSB passes every API surface test. The
+//! baseline file `tests/golden/numeric_baseline.txt` holds the outputs of the release
+//! it was captured at for every [`Case`] of [`CASES`]: a fixture of the
+//! [`test_fixtures`](crate::test_fixtures) corpus at a pair of ranges, one line per sample and
+//! [`Quantity`]:
+//!
+//! ```text
+//! # baseline 0.2.0
+//! ROTATING_Z/G2/D500 12 acc 0.0 0.0 1.0
+//! ROTATING_Z/G2/D500 12 raw 0 0 16384 -3920 0 0 5895
+//! ROTATING_Z/G2/D500 12 orientation 0.0 0.0 0.10192444 0.9947922
+//! ```
+//!
+//! case, sample, quantity and its components, counts as integers, everything else as the
+//! shortest decimal reading back as the same f32. [`compare`] holds the outputs of this build, [`current`], against a parsed
+//! baseline within the tolerance of each quantity:
+//!
+//! | quantity | output | tolerance |
+//! |:---|:---|:---|
+//! | [`Raw`](Quantity::Raw) | counts of the parsed frame, accel, temperature, gyro | bit-exact |
+//! | [`Acc`](Quantity::Acc) | scaled accel in g | bit-exact |
+//! | [`Gyro`](Quantity::Gyro) | scaled gyro in rad/s | bit-exact |
+//! | [`Temp`](Quantity::Temp) | temperature in °C | bit-exact |
+//! | [`LegacyCounts`](Quantity::LegacyCounts) | the ground truth back to counts with [`RoundingMode::Legacy`] | bit-exact |
+//! | [`Tilt`](Quantity::Tilt) | roll and pitch of the accel reading in rad | ±1e-6 |
+//! | [`Orientation`](Quantity::Orientation) | [`ComplementaryFilter`] at [`DEFAULT_ALPHA`], x y z w | ±1e-5 |
+//!
+//! Parsing and scaling are one integer to float conversion and one multiplication, any
+//! change of them is a change of the contract and has no tolerance. The fusion quantities go
+//! through `atan2`, `sqrt` and a chain of quaternion products, their last bits move with the
+//! target's libm, fused multiply-adds and reordered operations: the epsilons are far below
+//! the sensor's noise and far above that rounding, the orientation's accumulating over the
+//! 100 updates of the longest fixture.
+//!
+//! A drift is a component outside its tolerance, a line of the baseline no longer produced or
+//! a line produced without one in the baseline. The [`DriftReport`] names the case, sample,
+//! quantity and component of each, with both values.
+//!
+//! #### Updating the baseline
+//! The baseline is not rewritten by `UPDATE_GOLDENS`, which regenerates goldens of the
+//! recorded sequences in bulk: a numeric change has to be deliberate. When it is, run
+//!
+//! ```text
+//! cargo run --example regenerate_baselines --features test-fixtures
+//! ```
+//!
+//! which rewrites the file from [`current`] with the version of this build, review the diff
+//! (the drifting lines and only them) and commit it with the change, its release notes
+//! naming the quantities that moved.
+//!
+//! Enable with the `test-fixtures` feature, or `test-util` which includes it.
+
+use core::fmt::{self, Display};
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::string::String;
+use std::vec::Vec;
+
+use crate::conversion::{self, RoundingMode};
+use crate::device::{AccelRange, GyroRange};
+use crate::frame::parse_frame;
+use crate::orientation::{ComplementaryFilter, DEFAULT_ALPHA};
+use crate::scale::Pipeline;
+use crate::test_fixtures::{Fixture, IMPACT, ROTATING_Z, STATIONARY, TEMP_RAMP, TILTED_30};
+use crate::tilt;
+
+/// Baseline file, relative to the crate root
+pub const BASELINE_FILE: &str = "tests/golden/numeric_baseline.txt";
+
+/// Numeric output of a sample, see the [module docs](self)
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub enum Quantity {
+    /// counts of the parsed frame: accel x y z, temperature, gyro x y z
+    Raw,
+    /// scaled accel in g
+    Acc,
+    /// scaled gyro in rad/s
+    Gyro,
+    /// temperature in °C
+    Temp,
+    /// the ground truth to counts with [`RoundingMode::Legacy`], laid out like [`Raw`](Self::Raw)
+    LegacyCounts,
+    /// roll and pitch of the accel reading in rad
+    Tilt,
+    /// complementary filter orientation after the sample, x y z w
+    Orientation,
+}
+
+impl Quantity {
+    /// every quantity, in the order of the baseline lines
+    pub const ALL: [Quantity; 7] = [
+        Quantity::Raw,
+        Quantity::Acc,
+        Quantity::Gyro,
+        Quantity::Temp,
+        Quantity::LegacyCounts,
+        Quantity::Tilt,
+        Quantity::Orientation,
+    ];
+
+    /// name in the baseline file
+    pub const fn name(self) -> &'static str {
+        match self {
+            Quantity::Raw => "raw",
+            Quantity::Acc => "acc",
+            Quantity::Gyro => "gyro",
+            Quantity::Temp => "temp",
+            Quantity::LegacyCounts => "legacy_counts",
+            Quantity::Tilt => "tilt",
+            Quantity::Orientation => "orientation",
+        }
+    }
+
+    /// quantity named `name` in the baseline file
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|quantity| quantity.name() == name)
+    }
+
+    /// components per line
+    pub const fn components(self) -> usize {
+        match self {
+            Quantity::Raw | Quantity::LegacyCounts => 7,
+            Quantity::Acc | Quantity::Gyro => 3,
+            Quantity::Temp => 1,
+            Quantity::Tilt => 2,
+            Quantity::Orientation => 4,
+        }
+    }
+
+    /// tolerance policy of the quantity, the table of the [module docs](self)
+    pub const fn tolerance(self) -> Tolerance {
+        match self {
+            Quantity::Raw
+            | Quantity::Acc
+            | Quantity::Gyro
+            | Quantity::Temp
+            | Quantity::LegacyCounts => Tolerance::Exact,
+            Quantity::Tilt => Tolerance::Absolute(1e-6),
+            Quantity::Orientation => Tolerance::Absolute(1e-5),
+        }
+    }
+}
+
+impl Display for Quantity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// Accepted difference of a component from its baseline
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Tolerance {
+    /// the same bits
+    Exact,
+    /// at most this far from the baseline
+    Absolute(f32),
+}
+
+impl Tolerance {
+    /// whether `actual` passes for `expected`
+    pub fn accepts(self, expected: f32, actual: f32) -> bool {
+        match self {
+            Tolerance::Exact => expected.to_bits() == actual.to_bits(),
+            Tolerance::Absolute(epsilon) => (actual - expected).abs() <= epsilon,
+        }
+    }
+}
+
+impl Display for Tolerance {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Tolerance::Exact => f.write_str("bit-exact"),
+            Tolerance::Absolute(epsilon) => write!(f, "±{:e}", epsilon),
+        }
+    }
+}
+
+/// A fixture at a pair of ranges, the configuration of the baseline lines
+#[derive(Copy, Clone, Debug)]
+pub struct Case {
+    /// fixture replayed
+    pub fixture: Fixture,
+    /// accel range its frames are generated and scaled at
+    pub accel_range: AccelRange,
+    /// gyro range its frames are generated and scaled at
+    pub gyro_range: GyroRange,
+}
+
+impl Case {
+    /// `fixture` at its documented ranges
+    pub const fn documented(fixture: Fixture) -> Self {
+        Self {
+            fixture,
+            accel_range: fixture.accel_range,
+            gyro_range: fixture.gyro_range,
+        }
+    }
+
+    /// label in the baseline file, `NAME/accel range/gyro range`
+    pub fn label(&self) -> String {
+        format!(
+            "{}/{:?}/{:?}",
+            self.fixture.name, self.accel_range, self.gyro_range
+        )
+    }
+
+    /// outputs of this build for every sample, in the layout of [`Quantity::ALL`]
+    pub fn outputs(&self) -> Vec<[Vec<f32>; 7]> {
+        let (fixture, accel_range, gyro_range) = (self.fixture, self.accel_range, self.gyro_range);
+        let pipeline = Pipeline::new(accel_range, gyro_range);
+        let mut filter = ComplementaryFilter::new(DEFAULT_ALPHA);
+        (0..fixture.len)
+            .map(|idx| {
+                let frame = parse_frame(&fixture.bytes_at(idx, accel_range, gyro_range));
+                let sample = pipeline.sample(&frame);
+                let truth = fixture.truth(idx);
+                let legacy = RoundingMode::Legacy;
+                let (legacy_acc, _) =
+                    conversion::units_to_counts(truth.acc_g, accel_range.sensitivity(), legacy);
+                let (legacy_gyro, _) =
+                    conversion::units_to_counts(truth.gyro_dps, gyro_range.sensitivity(), legacy);
+                let (legacy_temp, _) = conversion::celsius_to_counts(truth.temp_c, legacy);
+                let (roll, pitch) = tilt::roll_pitch(sample.acc());
+                let q = filter.update(sample.gyro(), sample.acc(), fixture.interval_s);
+                let counts = |acc: [i16; 3], temp: i16, gyro: [i16; 3]| {
+                    acc.into_iter()
+                        .chain([temp])
+                        .chain(gyro)
+                        .map(f32::from)
+                        .collect()
+                };
+                [
+                    counts(frame.acc, frame.temp, frame.gyro),
+                    sample.acc().to_array().to_vec(),
+                    sample.gyro().to_array().to_vec(),
+                    vec![sample.temp()],
+                    counts(legacy_acc, legacy_temp, legacy_gyro),
+                    vec![roll, pitch],
+                    q.to_array().to_vec(),
+                ]
+            })
+            .collect()
+    }
+}
+
+/// The corpus: every fixture at its documented ranges, and two again at the ranges left,
+/// every range scaled at least once
+pub const CASES: [Case; 7] = [
+    Case::documented(STATIONARY),
+    Case::documented(TILTED_30),
+    Case::documented(ROTATING_Z),
+    Case::documented(IMPACT),
+    Case::documented(TEMP_RAMP),
+    Case {
+        fixture: TILTED_30,
+        accel_range: AccelRange::G4,
+        gyro_range: GyroRange::D1000,
+    },
+    Case {
+        fixture: ROTATING_Z,
+        accel_range: AccelRange::G8,
+        gyro_range: GyroRange::D1000,
+    },
+];
+
+/// Line of a baseline: case label, sample and quantity
+pub type Key = (String, usize, Quantity);
+
+/// Numeric outputs of a release over [`CASES`], see the [module docs](self)
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Baseline {
+    /// crate version the outputs were captured at
+    pub version: String,
+    /// components of every line
+    pub lines: BTreeMap<Key, Vec<f32>>,
+}
+
+impl Baseline {
+    /// Parses a baseline file
+    pub fn parse(text: &str) -> Result<Self, BaselineParseError> {
+        let mut baseline = Baseline::default();
+        for (idx, line) in text.lines().enumerate() {
+            let error = |reason| BaselineParseError {
+                line: idx + 1,
+                reason,
+            };
+            let line = line.trim_end_matches('\r');
+            if let Some(comment) = line.strip_prefix('#') {
+                if let Some(version) = comment.trim().strip_prefix("baseline ") {
+                    baseline.version = version.trim().into();
+                }
+                continue;
+            }
+            if line.trim().is_empty() {
+                continue;
+            }
+            let mut fields = line.split_whitespace();
+            let case = fields.next().ok_or(error("no case"))?;
+            let sample = fields
+                .next()
+                .and_then(|sample| sample.parse().ok())
+                .ok_or(error("no sample index"))?;
+            let quantity = fields
+                .next()
+                .and_then(Quantity::from_name)
+                .ok_or(error("unknown quantity"))?;
+            let values = fields
+                .map(str::parse)
+                .collect::<Result<Vec<f32>, _>>()
+                .map_err(|_| error("not a number"))?;
+            if values.len() != quantity.components() {
+                return Err(error("wrong number of components"));
+            }
+            let key = (case.into(), sample, quantity);
+            if baseline.lines.insert(key, values).is_some() {
+                return Err(error("duplicate line"));
+            }
+        }
+        if baseline.version.is_empty() {
+            return Err(BaselineParseError {
+                line: 0,
+                reason: "no `# baseline <version>` line",
+            });
+        }
+        Ok(baseline)
+    }
+
+    /// The baseline file's content, read back by [`parse`](Self::parse) as the same baseline
+    pub fn render(&self) -> String {
+        let mut out = format!(
+            "# baseline {}\n\
+             # numeric outputs over the fixture corpus, see the `numeric_compat` module. Not\n\
+             # rewritten by UPDATE_GOLDENS: regenerate with\n\
+             # `cargo run --example regenerate_baselines --features test-fixtures` and review\n\
+             # case sample quantity components\n",
+            self.version
+        );
+        for ((case, sample, quantity), values) in &self.lines {
+            out.push_str(&format!("{} {} {}", case, sample, quantity));
+            for value in values {
+                match quantity {
+                    Quantity::Raw | Quantity::LegacyCounts => {
+                        out.push_str(&format!(" {}", *value as i16))
+                    }
+                    _ => out.push_str(&format!(" {:?}", value)),
+                }
+            }
+            out.push('\n');
+        }
+        out
+    }
+}
+
+/// Outputs of this build over [`CASES`], at the crate version
+pub fn current() -> Baseline {
+    let mut lines = BTreeMap::new();
+    for case in CASES {
+        let label = case.label();
+        for (sample, outputs) in case.outputs().into_iter().enumerate() {
+            for (quantity, values) in Quantity::ALL.into_iter().zip(outputs) {
+                lines.insert((label.clone(), sample, quantity), values);
+            }
+        }
+    }
+    Baseline {
+        version: env!("CARGO_PKG_VERSION").into(),
+        lines,
+    }
+}
+
+/// Path of the committed baseline in the crate this was built from
+pub fn baseline_path() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join(BASELINE_FILE)
+}
+
+/// Reads and parses the committed baseline
+pub fn load() -> Result<Baseline, String> {
+    let path = baseline_path();
+    let text =
+        std::fs::read_to_string(&path).map_err(|error| format!("{}: {}", path.display(), error))?;
+    Baseline::parse(&text).map_err(|error| format!("{}: {}", path.display(), error))
+}
+
+/// Rewrites the committed baseline from [`current`], for a deliberate numeric change. The
+/// diff is to be reviewed, see the [module docs](self#updating-the-baseline)
+pub fn regenerate() -> std::io::Result<PathBuf> {
+    let path = baseline_path();
+    std::fs::write(&path, current().render())?;
+    Ok(path)
+}
+
+/// Baseline file not in the format of [`Baseline::render`]
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct BaselineParseError {
+    /// line, from 1; 0 for the file as a whole
+    pub line: usize,
+    /// what is wrong with it
+    pub reason: &'static str,
+}
+
+impl Display for BaselineParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "baseline line {}: {}", self.line, self.reason)
+    }
+}
+
+impl std::error::Error for BaselineParseError {}
+
+/// How a line differs from the baseline
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum DriftKind {
+    /// a component outside the tolerance of the quantity
+    Value {
+        /// component of the line, from 0
+        component: usize,
+        /// baseline value
+        expected: f32,
+        /// value now
+        actual: f32,
+    },
+    /// in the baseline, no longer produced
+    Missing,
+    /// produced, not in the baseline
+    Unexpected,
+}
+
+/// A line of the outputs differing from the baseline
+#[derive(Clone, Debug, PartialEq)]
+pub struct Drift {
+    /// case label, the fixture and its ranges
+    pub case: String,
+    /// sample of the fixture
+    pub sample: usize,
+    /// quantity of the line
+    pub quantity: Quantity,
+    /// the difference
+    pub kind: DriftKind,
+}
+
+impl Display for Drift {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} sample {} {}", self.case, self.sample, self.quantity)?;
+        match self.kind {
+            DriftKind::Value {
+                component,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "[{}]: baseline {:?}, now {:?}, off by {:e}, tolerance {}",
+                component,
+                expected,
+                actual,
+                (actual - expected).abs(),
+                self.quantity.tolerance()
+            ),
+            DriftKind::Missing => f.write_str(": in the baseline, no longer produced"),
+            DriftKind::Unexpected => f.write_str(": produced, not in the baseline"),
+        }
+    }
+}
+
+/// Result of [`compare`]
+#[derive(Clone, Debug, PartialEq)]
+pub struct DriftReport {
+    /// version the baseline was captured at
+    pub baseline_version: String,
+    /// version of the outputs
+    pub current_version: String,
+    /// lines compared
+    pub compared: usize,
+    /// every drift, in the order of the lines
+    pub drifts: Vec<Drift>,
+}
+
+impl DriftReport {
+    /// Drifts listed by [`Display`], the count of the rest follows them
+    pub const LISTED: usize = 20;
+
+    /// true without drifts
+    pub fn is_clean(&self) -> bool {
+        self.drifts.is_empty()
+    }
+
+    /// drifts of `quantity`
+    pub fn count(&self, quantity: Quantity) -> usize {
+        self.drifts
+            .iter()
+            .filter(|drift| drift.quantity == quantity)
+            .count()
+    }
+}
+
+impl Display for DriftReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_clean() {
+            return write!(
+                f,
+                "{} lines match the baseline of {}",
+                self.compared, self.baseline_version
+            );
+        }
+        writeln!(
+            f,
+            "{} of {} lines drifted from the baseline of {} in {}:",
+            self.drifts.len(),
+            self.compared,
+            self.baseline_version,
+            self.current_version
+        )?;
+        for quantity in Quantity::ALL {
+            let count = self.count(quantity);
+            if count > 0 {
+                writeln!(f, "  {}: {} ({})", quantity, count, quantity.tolerance())?;
+            }
+        }
+        for drift in self.drifts.iter().take(Self::LISTED) {
+            writeln!(f, "  {}", drift)?;
+        }
+        if self.drifts.len() > Self::LISTED {
+            writeln!(f, "  and {} more", self.drifts.len() - Self::LISTED)?;
+        }
+        write!(
+            f,
+            "if the change is deliberate, run `cargo run --example regenerate_baselines \
+             --features test-fixtures` and review the diff of {}",
+            BASELINE_FILE
+        )
+    }
+}
+
+/// Holds `current` against `baseline` within the tolerances of the quantities, every line of
+/// either
+pub fn compare(baseline: &Baseline, current: &Baseline) -> DriftReport {
+    let mut drifts = Vec::new();
+    let drift = |(case, sample, quantity): &Key, kind| Drift {
+        case: case.clone(),
+        sample: *sample,
+        quantity: *quantity,
+        kind,
+    };
+    for (key, expected) in &baseline.lines {
+        let Some(actual) = current.lines.get(key) else {
+            drifts.push(drift(key, DriftKind::Missing));
+            continue;
+        };
+        let tolerance = key.2.tolerance();
+        for (component, (&expected, &actual)) in expected.iter().zip(actual).enumerate() {
+            if !tolerance.accepts(expected, actual) {
+                drifts.push(drift(
+                    key,
+                    DriftKind::Value {
+                        component,
+                        expected,
+                        actual,
+                    },
+                ));
+            }
+        }
+    }
+    for key in current.lines.keys() {
+        if !baseline.lines.contains_key(key) {
+            drifts.push(drift(key, DriftKind::Unexpected));
+        }
+    }
+    drifts.sort_by(|a, b| (&a.case, a.sample, a.quantity).cmp(&(&b.case, b.sample, b.quantity)));
+    DriftReport {
+        baseline_version: baseline.version.clone(),
+        current_version: current.version.clone(),
+        compared: baseline.lines.len(),
+        drifts,
+    }
+}
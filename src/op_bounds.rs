@@ -0,0 +1,127 @@
+//! Worst case bus usage of the driver calls meant for control loops.
+//!
+//! Bytes are counted like [`transaction_cost_bytes`](crate::bus::transaction_cost_bytes):
+//! address bytes, register byte and payload, no ACKs. A read is one write_read transaction
+//! with `3 + n` bytes, a register write one transaction with `2 + n` bytes.
+//!
+//! Every transaction the driver attempts is counted in [`IoStats`], see
+//! [`Mpu6050::io_stats`](crate::Mpu6050::io_stats), so the numbers below can be checked on
+//! the target. Transactions skipped because the sensor is disconnected are not counted.
+//!
+//! With the `minimal-pipeline` feature no settling policy retries a read, the bounds of
+//! every policy are those of [`SettlingPolicy::Ignore`], see [`stages`](crate::stages).
+
+use crate::settling::SettlingPolicy;
+
+/// Maximum bus usage of one call
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct OpBound {
+    /// I2C transactions
+    pub transactions: u32,
+    /// bytes on the wire
+    pub bytes: u32,
+}
+
+impl OpBound {
+    /// `count` consecutive calls
+    pub const fn times(self, count: u32) -> Self {
+        Self {
+            transactions: self.transactions * count,
+            bytes: self.bytes * count,
+        }
+    }
+
+    /// this call followed by `other`
+    pub const fn then(self, other: OpBound) -> Self {
+        Self {
+            transactions: self.transactions + other.transactions,
+            bytes: self.bytes + other.bytes,
+        }
+    }
+}
+
+/// bytes of a read transaction with `n` payload bytes
+pub(crate) const fn read_cost_bytes(n: usize) -> u32 {
+    3 + n as u32
+}
+
+/// bytes of a write transaction with `n` payload bytes
+pub(crate) const fn write_cost_bytes(n: usize) -> u32 {
+    2 + n as u32
+}
+
+const fn read(n: usize) -> OpBound {
+    OpBound {
+        transactions: 1,
+        bytes: read_cost_bytes(n),
+    }
+}
+
+/// additional reads a settling sample may cause, none with `minimal-pipeline`
+const fn settling_retries(policy: SettlingPolicy) -> u32 {
+    match policy {
+        #[cfg(not(feature = "minimal-pipeline"))]
+        SettlingPolicy::Discard { max_retries } => max_retries as u32,
+        _ => 0,
+    }
+}
+
+/// `get_temp`: one 2 byte read
+pub const GET_TEMP: OpBound = read(2);
+
+/// `get_acc_raw`, `get_gyro_raw`: one 6 byte read
+pub const GET_RAW: OpBound = read(6);
+
+/// `poll_interrupt_events`: one INT_STATUS read
+pub const POLL_INTERRUPT_EVENTS: OpBound = read(1);
+
+/// `get_acc`: one 6 byte read, plus the retries of the settling policy. Steady state
+/// (no settling countdown running) is always [`GET_RAW`]
+pub const fn get_acc(policy: SettlingPolicy) -> OpBound {
+    read(6).times(1 + settling_retries(policy))
+}
+
+/// `get_gyro`: same as [`get_acc`]
+pub const fn get_gyro(policy: SettlingPolicy) -> OpBound {
+    get_acc(policy)
+}
+
+/// `get_acc_angles`, `get_acc_angles_checked`: one `get_acc`
+pub const fn get_acc_angles(policy: SettlingPolicy) -> OpBound {
+    get_acc(policy)
+}
+
+/// one sample of `run_sampling_loop` or `sample_into_interp_buffer`: `get_acc`, `get_gyro`
+/// and `get_temp`. The sampling loop adds up to two INT_STATUS reads per sample
+pub const fn read_sample(policy: SettlingPolicy) -> OpBound {
+    get_acc(policy).then(get_gyro(policy)).then(GET_TEMP)
+}
+
+/// one burst of `read_into_views`, `get_all` or `get_all_raw`: one 14 byte read
+pub const READ_SAMPLE_BURST: OpBound = read(14);
+
+/// `read_batch` of `k` samples: `k` bursts, see [`batch`](crate::batch)
+pub const fn read_batch(k: u32) -> OpBound {
+    READ_SAMPLE_BURST.times(k)
+}
+
+/// Transactions and bytes the driver put on the bus
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub struct IoStats {
+    /// attempted transactions, failed ones included
+    pub transactions: u32,
+    /// bytes of the attempted transactions
+    pub bytes: u32,
+}
+
+impl IoStats {
+    pub(crate) fn record(&mut self, bytes: u32) {
+        self.transactions = self.transactions.wrapping_add(1);
+        self.bytes = self.bytes.wrapping_add(bytes);
+    }
+
+    /// true if the usage since the last reset is within `bound`
+    pub fn within(&self, bound: OpBound) -> bool {
+        self.transactions <= bound.transactions && self.bytes <= bound.bytes
+    }
+}
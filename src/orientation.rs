@@ -0,0 +1,1134 @@
+//! Complementary filter fusing gyro rates and accelerometer tilt into an orientation.
+//!
+//! The orientation `q` rotates sensor vectors into a world frame with Z up, so
+//! `q.inverse() * Vec3A::Z` is the direction the accelerometer reads at rest. Every
+//! [`update`](ComplementaryFilter::update):
+//! 1. integrates the gyro rate minus the bias estimate `b` over `dt`:
+//!    `q' = q * exp((ω - b) dt)`
+//! 2. rotates `q'` by the fraction `1 - α` of the shortest rotation `R` taking the predicted
+//!    gravity direction onto the measured one, which leaves yaw unchanged: `q = q' * R^(1 - α)`
+//! 3. moves the bias estimate against the correction angle `e` of `R` (rad, sensor frame):
+//!    `b -= (1 - α) e / τ`
+//!
+//! A gyro bias makes the uncorrected filter hold a tilt error of `α b dt / (1 - α)`.
+//! Step 3 feeds exactly that error back, the bias estimate converges with the time
+//! constant `τ` once the attitude has settled. Only the bias components perpendicular to
+//! gravity are observable from the accelerometer: the component about the vertical (yaw)
+//! stays where it is.
+//!
+//! ## Yaw
+//! Yaw is the gyro rate about the vertical integrated, positive counterclockwise about +Z
+//! seen from above (right handed). The accelerometer corrects roll and pitch only and there
+//! is no magnetometer input, so nothing pulls yaw back: a rate offset about the vertical, a
+//! bias not (yet) in the estimate, drifts it linearly, `yaw(t) = b_z t`, while roll and pitch
+//! stay held.
+//! ```
+//! use mpu6050::euler::to_xyz_rpy;
+//! use mpu6050::orientation::ComplementaryFilter;
+//! use mpu6050::Vec3A;
+//!
+//! let flat = Vec3A::Z;
+//! // counterclockwise about +Z at 0.1 rad/s for 5 s
+//! let mut filter = ComplementaryFilter::new(0.98);
+//! for _ in 0..500 {
+//!     filter.update(Vec3A::new(0., 0., 0.1), flat, 0.01);
+//! }
+//! let rpy = to_xyz_rpy(filter.orientation());
+//! assert!((rpy.yaw - 0.5).abs() < 1e-3);
+//!
+//! // at rest with 0.01 rad/s of bias about Z for 60 s: 0.6 rad of yaw drift, the bias
+//! // estimate does not see it and the tilt stays level
+//! let mut filter = ComplementaryFilter::new(0.98);
+//! for _ in 0..6000 {
+//!     filter.update(Vec3A::new(0., 0., 0.01), flat, 0.01);
+//! }
+//! let rpy = to_xyz_rpy(filter.orientation());
+//! assert!((rpy.yaw - 0.6).abs() < 1e-2);
+//! assert!(rpy.roll.abs() < 1e-4 && rpy.pitch.abs() < 1e-4);
+//! assert_eq!(filter.bias_estimate().z, 0.);
+//! ```
+//!
+//! ## Provenance
+//! [`update_sample`](ComplementaryFilter::update_sample) takes a sample and weighs its values
+//! by their [`provenance`](crate::provenance) tags with [`Provenance::weight`]: the
+//! correction gain `1 - α` is scaled by the accelerometer weight `w_a`, the bias step by
+//! `w_a w_g` with the gyro weight `w_g`. An interpolated accelerometer corrects half as much,
+//! a synthesized or invalid one not at all; the bias only learns from measured pairs at full
+//! rate. The gyro is integrated whatever its tag, it is the only rate source, except an
+//! invalid gyro: the prediction is skipped and the orientation held.
+//!
+//! ## Plausibility
+//! Samples scored by the driver's [plausibility](crate::plausibility) checks are handled by
+//! their class with [`with_plausibility_policy`](ComplementaryFilter::with_plausibility_policy):
+//! [`Use`](SampleHandling::Use) them like any other, [`DeWeight`](SampleHandling::DeWeight)
+//! them, scaling the correction gain and the bias step by the given weight on top of the
+//! provenance weights while the gyro is integrated as usual, or [`Skip`](SampleHandling::Skip)
+//! them: the orientation, the bias and the gate state are left as they are. The default
+//! [`PlausibilityPolicy::IGNORE`] uses every sample. A skipped update does not count as gated.
+//! ```
+//! use mpu6050::orientation::*;
+//! use mpu6050::plausibility::{PlausibilityCheck, PlausibilityScore, DEFAULT_IMPLAUSIBLE};
+//! use mpu6050::{MpuSample, Quat, Vec3A};
+//!
+//! let mut filter = ComplementaryFilter::new(0.98).with_plausibility_policy(PlausibilityPolicy {
+//!     suspect: SampleHandling::DeWeight(0.5),
+//!     implausible: SampleHandling::Skip,
+//! });
+//! let stale = PlausibilityScore::new(PlausibilityCheck::Stale.bit(), DEFAULT_IMPLAUSIBLE);
+//! let sample = MpuSample::new(Vec3A::X, Vec3A::splat(1.), 25.).with_plausibility(stale);
+//! // skipped: no rotation, no correction
+//! assert_eq!(filter.update_sample(&sample, 0.01), Quat::IDENTITY);
+//! assert!(!filter.estimate().accel_gated);
+//! ```
+//!
+//! ## Gain scheduling
+//! A fixed α trades drift at rest against accelerometer disturbances in motion. With
+//! [`with_gain_schedule`](ComplementaryFilter::with_gain_schedule) every update schedules it
+//! from the magnitude `r` of the bias corrected gyro rate over a [`FilterGainSchedule`]:
+//! `s = smoothstep(r / 2k)` with the knee `k`, `smoothstep(x) = 3x² - 2x³` for
+//! `x` clamped to 0..=1, and `α(r) = α_rest + (α_dynamic - α_rest) s`, halfway at the knee and
+//! `α_dynamic` from `2k` up. With [`accel_trust_weighting`](FilterGainSchedule::accel_trust_weighting)
+//! the correction gain is scaled by the [`TiltTrust`] of the accelerometer magnitude by
+//! [`FilterGainSchedule::trust_weight`]: `α_eff = 1 - (1 - α(r)) w_t`. The scheduled gain
+//! takes the place of `1 - α` in the correction and in the bias step, the provenance,
+//! plausibility and reacquisition weights apply on top.
+//! [`estimate`](ComplementaryFilter::estimate) reports `α_eff` of the last update in
+//! [`alpha`](OrientationEstimate::alpha). Without a schedule the filter runs on the fixed α,
+//! a schedule with `α_rest = α_dynamic` and no trust weighting steps exactly like it.
+//! ```
+//! use mpu6050::orientation::{ComplementaryFilter, FilterGainSchedule};
+//! use mpu6050::Vec3A;
+//!
+//! let mut filter = ComplementaryFilter::new(0.98).with_gain_schedule(Some(FilterGainSchedule {
+//!     alpha_at_rest: 0.9,
+//!     alpha_dynamic: 0.998,
+//!     gyro_rate_knee_rad_s: 1.,
+//!     accel_trust_weighting: false,
+//! }));
+//! filter.update(Vec3A::ZERO, Vec3A::Z, 0.01);
+//! assert_eq!(filter.estimate().alpha, 0.9);
+//! filter.update(Vec3A::new(1., 0., 0.), Vec3A::Z, 0.01);
+//! assert!((filter.estimate().alpha - 0.949).abs() < 1e-6);
+//! filter.update(Vec3A::new(0., 3., 0.), Vec3A::Z, 0.01);
+//! assert_eq!(filter.estimate().alpha, 0.998);
+//! ```
+//!
+//! ## Accelerometer gate and reacquisition
+//! An update whose accelerometer magnitude deviates from 1 g by more than the fraction
+//! given to [`with_accel_gate`](ComplementaryFilter::with_accel_gate), [`DEFAULT_ACCEL_GATE`]
+//! unless given, applies no correction: during sustained dynamics (aerobatics, vibration) the filter runs on the gyro
+//! alone and its tilt error `θ` grows with the gyro error. An update without correction for
+//! any reason (gate, zero or non finite reading, provenance weight 0) counts as gated.
+//!
+//! Once corrections resume after more than
+//! [`after_gated_s`](Reacquisition::after_gated_s) of gating, a full correction would remove
+//! `(1 - α) θ` in one update, a visible jump. With
+//! [`with_reacquisition`](ComplementaryFilter::with_reacquisition) the correction gain is
+//! scaled by a weight ramping linearly from 0 to 1 over
+//! [`ramp_s`](Reacquisition::ramp_s), `w = min(t / ramp_s, 1)` with `t` the time since the
+//! corrections resumed; the bias step is scaled the same. The schedule is monotone and never
+//! exceeds the normal weight. With `g = 1 - α` and `N = ramp_s / dt` updates, the `k`-th
+//! correction of a tilt error `θ` is at most `g (k / N) θ exp(-g k (k - 1) / 2N)`, the
+//! largest step is bounded by `θ min(g, √(g / (e N)) exp(g / 2))` instead of `g θ` for the
+//! snap: for `α = 0.98` and a 1 s ramp at 100 Hz, 0.0087 θ instead of 0.02 θ.
+//!
+//! Renewed gating interrupts a ramp: the gated time counts from zero again, if it exceeds
+//! `after_gated_s` the ramp restarts from weight 0, otherwise it continues where it
+//! stopped. [`estimate`](ComplementaryFilter::estimate) reports the
+//! [`ReacquisitionState`], e.g. to hold a horizon overlay until
+//! [`Complete`](ReacquisitionState::Complete).
+//! ```
+//! use mpu6050::orientation::{ComplementaryFilter, Reacquisition, ReacquisitionState};
+//! use mpu6050::Vec3A;
+//!
+//! let mut filter = ComplementaryFilter::new(0.98)
+//!     .with_accel_gate(Some(0.1))
+//!     .with_reacquisition(Some(Reacquisition { after_gated_s: 0.5, ramp_s: 1. }));
+//! // 1 s of 1.5 g: gyro only
+//! for _ in 0..100 {
+//!     filter.update(Vec3A::ZERO, Vec3A::new(0., 0., 1.5), 0.01);
+//! }
+//! assert!(filter.estimate().accel_gated);
+//! filter.update(Vec3A::ZERO, Vec3A::Z, 0.01);
+//! let estimate = filter.estimate();
+//! assert!((estimate.accel_weight - 0.01).abs() < 1e-6);
+//! assert!(matches!(estimate.reacquisition, ReacquisitionState::Ramping { .. }));
+//! for _ in 0..100 {
+//!     filter.update(Vec3A::ZERO, Vec3A::Z, 0.01);
+//! }
+//! assert_eq!(filter.estimate().reacquisition, ReacquisitionState::Complete);
+//! ```
+//!
+//! ## First fix
+//! A filter started at identity on a tilted device takes a few time constants of `1 - α` to
+//! find the tilt, seconds at the default α. A short accelerometer burst taken at rest gives
+//! it right away: [`initialize_from_accel`](ComplementaryFilter::initialize_from_accel)
+//! summarizes the burst into an [`InitBurst`], mean and largest per-axis standard deviation,
+//! and checks it against the [`InitCriteria`]:
+//!
+//! | verdict | condition | state |
+//! |:---|:---|:---|
+//! | [`TooFewSamples`](InitQuality::TooFewSamples) | fewer than `min_samples` readings | unchanged |
+//! | [`Moving`](InitQuality::Moving) | standard deviation above `max_std_dev_g`, or not finite | unchanged |
+//! | [`NotGravity`](InitQuality::NotGravity) | mean magnitude further from 1 g than `max_magnitude_deviation` | unchanged |
+//! | [`Initialized`](InitQuality::Initialized) | otherwise | the tilt of the mean, yaw zero |
+//!
+//! The tilt of a mean `a` is, with `s` the sign of `a.z`,
+//! `roll = atan2(a.y, s √(a.x² + a.z²))`, `pitch = atan2(-s a.x, s a.z)` and
+//! `q = Rx(roll) Ry(pitch)`: `q.inverse() * Vec3A::Z` is the direction of `a`, the pitch is
+//! within ±90° and the yaw of [`to_xyz_rpy`](crate::euler::to_xyz_rpy) is zero. The bias estimate is kept, the gate
+//! and ramp state start over. A rejected burst leaves the filter as it was, for the caller
+//! to retry or to go on from identity. On the driver,
+//! [`collect_init_burst`](crate::Mpu6050::collect_init_burst) reads the burst, one scaled
+//! accelerometer reading every [`INIT_BURST_INTERVAL_MS`].
+//!
+//! With [`with_auto_initialize`](ComplementaryFilter::with_auto_initialize) the filter does
+//! the same on its own first updates: the accelerometer readings of the first `min_samples`
+//! updates are the burst, the orientation is held while they are collected and
+//! [`estimate`](ComplementaryFilter::estimate) reports
+//! [`initializing`](OrientationEstimate::initializing). The update completing the burst
+//! applies the verdict and returns the initialized orientation, `initializing` is false from
+//! there on. A rejected burst starts a new one, after `max_attempts` rejected bursts the
+//! filter runs on from where it is like one without initialization.
+//! [`init_quality`](ComplementaryFilter::init_quality) is the verdict of the last burst.
+//! ```
+//! use mpu6050::orientation::{ComplementaryFilter, InitCriteria, InitQuality};
+//! use mpu6050::{euler, synthetic, Vec3A};
+//!
+//! let burst = [synthetic::at_rest(20., -10.); 32];
+//! let mut filter = ComplementaryFilter::new(0.98);
+//! assert!(filter.initialize_from_accel(&burst).is_initialized());
+//! let rpy = euler::to_xyz_rpy(filter.orientation());
+//! assert!((rpy.roll.to_degrees() - 20.).abs() < 1e-3 && rpy.yaw.abs() < 1e-6);
+//!
+//! let mut auto = ComplementaryFilter::new(0.98).with_auto_initialize(true);
+//! let criteria = InitCriteria::DEFAULT;
+//! for _ in 1..criteria.min_samples {
+//!     auto.update(Vec3A::ZERO, burst[0], 0.01);
+//!     assert!(auto.estimate().initializing);
+//! }
+//! auto.update(Vec3A::ZERO, burst[0], 0.01);
+//! assert!(!auto.estimate().initializing);
+//! assert!(matches!(auto.init_quality(), Some(InitQuality::Initialized { .. })));
+//! ```
+//!
+//! ## Re-entry
+//! After a sleep, a cycle mode period, a reset or a reconnect the samples do not continue
+//! the ones before, see [`continuity`](crate::continuity). Given the driver's continuity
+//! token, [`update_with_token`](ComplementaryFilter::update_with_token) and
+//! [`update_sample_with_token`](ComplementaryFilter::update_sample_with_token) compare it
+//! with the token of their previous update. The first token is taken as it is, a different
+//! one re-enters instead of stepping:
+//! 1. the update's gyro and `dt` are dropped, nothing is integrated across the break
+//! 2. a first fix burst starts as with [`with_auto_initialize`](ComplementaryFilter::with_auto_initialize),
+//!    the accelerometer reading of the update is its first, the orientation is held while it
+//!    is collected and [`initializing`](OrientationEstimate::initializing) is set
+//! 3. a burst passing the [`InitCriteria`] sets the tilt of the burst with the yaw of
+//!    [`with_reentry_yaw`](ComplementaryFilter::with_reentry_yaw): the one held before the
+//!    break by default, or zero. A rejected burst starts a new one, after `max_attempts`
+//!    the filter runs on from the held orientation
+//! 4. the gated time and the reacquisition ramp start over, the bias estimate is kept
+//!
+//! [`estimate`](ComplementaryFilter::estimate) reports the update finding the break in
+//! [`continuity_break`](OrientationEstimate::continuity_break) and counts the re-entries.
+//! The `dt` of the first update after the burst is the caller's: take it from the last
+//! sample of the burst, not from before the break.
+//!
+//! The orientation moves in one update of the re-entry only, the one completing the burst,
+//! and by the angle between the held orientation and the fresh tilt with the yaw kept. For
+//! a device woken in the attitude it slept in that is the tilt error of the held estimate
+//! and of the burst mean, below 0.5° with the default criteria and a settled filter,
+//! where one update over a 10 minute `dt` with a gyro bias of 0.3 °/s turns the estimate
+//! by 180°. A device moved while asleep gets its new tilt in that one update.
+//! ```
+//! use mpu6050::orientation::{ComplementaryFilter, InitCriteria};
+//! use mpu6050::{euler, synthetic, Vec3A};
+//!
+//! let mut filter = ComplementaryFilter::new(0.98);
+//! filter.update_with_token(Vec3A::ZERO, synthetic::at_rest(0., 0.), 0.01, 7);
+//! // woken with the device turned to 30° of roll, the driver's token moved on
+//! let tilted = synthetic::at_rest(30., 0.);
+//! filter.update_with_token(Vec3A::new(1., 0., 0.), tilted, 600., 8);
+//! assert!(filter.estimate().continuity_break && filter.estimate().initializing);
+//! for _ in 1..InitCriteria::DEFAULT.min_samples {
+//!     filter.update_with_token(Vec3A::ZERO, tilted, 0.01, 8);
+//! }
+//! let estimate = filter.estimate();
+//! assert!(!estimate.initializing && estimate.reentries == 1);
+//! let rpy = euler::to_xyz_rpy(estimate.orientation);
+//! assert!((rpy.roll.to_degrees() - 30.).abs() < 1e-3);
+//! ```
+//!
+//! ## Driver
+//! [`Mpu6050::update_orientation`] is the read and the update in one: accel and gyro of one
+//! sample instant in a single burst, handed to
+//! [`update_sample_with_token`](ComplementaryFilter::update_sample_with_token) with the
+//! driver's continuity token, so provenance, plausibility and re-entries after a sleep apply
+//! as described above. The estimate stays with the filter,
+//! [`orientation`](ComplementaryFilter::orientation) and
+//! [`estimate`](ComplementaryFilter::estimate) read it without touching the bus. In free
+//! fall or a hard turn the accelerometer does not measure gravity, a correction toward it
+//! would tilt the estimate: an exact zero or non finite reading applies none, and the
+//! [accelerometer gate](self#accelerometer-gate-and-reacquisition) skips readings away from
+//! 1 g, by default those below 0.7 g and above 1.3 g.
+//! ```no_run
+//! # use embedded_hal::blocking::i2c::{Write, WriteRead};
+//! # fn run<I: Write<Error = E> + WriteRead<Error = E>, E>(
+//! #     mut mpu: mpu6050::Mpu6050<I>,
+//! # ) -> Result<(), mpu6050::Mpu6050Error<E>> {
+//! use mpu6050::orientation::ComplementaryFilter;
+//!
+//! let mut filter = ComplementaryFilter::new(0.98);
+//! loop {
+//!     mpu.update_orientation(&mut filter, 0.01)?;
+//!     // on the gyro alone while falling
+//!     let estimate = filter.estimate();
+//! #   let _ = (estimate.orientation, estimate.accel_gated);
+//! }
+//! # }
+//! ```
+//!
+//! ## External estimators
+//! An external estimator (an EKF running elsewhere) can correct the filter without
+//! resetting it:
+//! * [`apply_external_correction`](ComplementaryFilter::apply_external_correction) blends an
+//!   orientation in with the weight `w = clamp(confidence, 0, 1)`: `q = slerp(q, external, w)`.
+//!   Repeated with the same estimate, the remaining angle after `n` corrections is
+//!   `(1 - w)^n` of the initial one
+//! * [`bias_estimate`](ComplementaryFilter::bias_estimate) and
+//!   [`set_bias_estimate`](ComplementaryFilter::set_bias_estimate) hand the gyro bias state
+//!   over in both directions, unchanged
+//! * [`reset_to`](ComplementaryFilter::reset_to) replaces the orientation outright
+//! ```
+//! use mpu6050::orientation::ComplementaryFilter;
+//! use mpu6050::{Quat, Vec3A};
+//!
+//! let mut filter = ComplementaryFilter::new(0.98);
+//! let external = Quat::from_rotation_z(0.5);
+//! for _ in 0..3 {
+//!     filter.apply_external_correction(external, 0.5);
+//! }
+//! // (1 - 0.5)^3 of the 0.5 rad left
+//! assert!((filter.orientation().angle_between(external) - 0.0625).abs() < 1e-4);
+//!
+//! filter.set_bias_estimate(Vec3A::new(0.01, -0.02, 0.));
+//! assert_eq!(filter.bias_estimate(), Vec3A::new(0.01, -0.02, 0.));
+//! ```
+
+use glam::{Quat, Vec3, Vec3A};
+
+#[cfg(feature = "driver")]
+use embedded_hal::blocking::{
+    delay::DelayMs,
+    i2c::{Write, WriteRead},
+};
+
+#[cfg(feature = "driver")]
+use crate::deadline::Watch;
+use crate::determinism::atan2;
+use crate::euler;
+use crate::logging::log_debug;
+#[cfg(not(feature = "std"))]
+use crate::math::FloatExt;
+use crate::plausibility::Plausibility;
+use crate::provenance::{FieldGroup, Provenance};
+use crate::setup::Accumulator;
+use crate::tilt::{self, TiltThresholds, TiltTrust};
+use crate::MpuSample;
+#[cfg(feature = "driver")]
+use crate::{Mpu6050, Mpu6050Error};
+
+/// Gyro weight per update assumed unless given
+pub const DEFAULT_ALPHA: f32 = 0.98;
+/// Time constant of the bias estimate in s assumed unless given
+pub const DEFAULT_BIAS_TIME_CONSTANT_S: f32 = 10.;
+/// Accelerometer gate assumed unless given: readings below 0.7 g or above 1.3 g apply no
+/// correction, free fall among them
+pub const DEFAULT_ACCEL_GATE: f32 = 0.3;
+/// Time in ms between two readings of [`collect_init_burst`](crate::Mpu6050::collect_init_burst)
+pub const INIT_BURST_INTERVAL_MS: u8 = 2;
+/// Phase of an [`Mpu6050Error::Aborted`](crate::Mpu6050Error::Aborted) from
+/// [`collect_init_burst_with_deadline`](crate::Mpu6050::collect_init_burst_with_deadline)
+pub const INIT_BURST_PHASE: &str = "InitBurst";
+
+/// When an accelerometer burst is fit for a first fix, see the
+/// [module docs](self#first-fix)
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct InitCriteria {
+    /// readings a burst needs, and the length of the burst of the auto initialization
+    pub min_samples: u32,
+    /// largest per-axis standard deviation in g of a device at rest
+    pub max_std_dev_g: f32,
+    /// largest relative deviation of the mean magnitude from 1 g
+    pub max_magnitude_deviation: f32,
+    /// rejected bursts after which the auto initialization gives up
+    pub max_attempts: u8,
+}
+
+impl InitCriteria {
+    /// 32 readings within 0.02 g of their mean, around 3 times the noise at the widest
+    /// DLPF bandwidth, and a magnitude within 5% of 1 g; 3 attempts
+    pub const DEFAULT: Self = Self {
+        min_samples: 32,
+        max_std_dev_g: 0.02,
+        max_magnitude_deviation: 0.05,
+        max_attempts: 3,
+    };
+}
+
+impl Default for InitCriteria {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+/// Accelerometer burst summarized for a first fix
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct InitBurst {
+    /// mean reading in g
+    pub mean: Vec3A,
+    /// largest per-axis standard deviation in g, 0 below 2 readings
+    pub std_dev_g: f32,
+    /// number of readings
+    pub samples: u32,
+}
+
+impl InitBurst {
+    /// summary of accelerometer readings in g
+    pub fn from_samples(samples: &[Vec3A]) -> Self {
+        let mut acc = Accumulator::default();
+        for sample in samples {
+            acc.push(*sample);
+        }
+        Self::from_accumulator(&acc)
+    }
+
+    fn from_accumulator(acc: &Accumulator) -> Self {
+        Self {
+            mean: acc.mean(),
+            std_dev_g: acc.max_std_dev(),
+            samples: acc.count(),
+        }
+    }
+
+    /// verdict of `criteria` on the burst
+    pub fn quality(&self, criteria: &InitCriteria) -> InitQuality {
+        let magnitude_g = self.mean.length();
+        if self.samples < criteria.min_samples.max(1) {
+            InitQuality::TooFewSamples {
+                samples: self.samples,
+            }
+        } else if !self.std_dev_g.is_finite() || self.std_dev_g > criteria.max_std_dev_g {
+            InitQuality::Moving {
+                std_dev_g: self.std_dev_g,
+            }
+        } else if !magnitude_g.is_finite()
+            || (magnitude_g - 1.).abs() > criteria.max_magnitude_deviation
+        {
+            InitQuality::NotGravity { magnitude_g }
+        } else {
+            InitQuality::Initialized {
+                std_dev_g: self.std_dev_g,
+            }
+        }
+    }
+
+    /// orientation of the mean with yaw zero, see the [module docs](self#first-fix)
+    pub fn tilt(&self) -> Quat {
+        let a = self.mean;
+        // pitch within ±90° as in `to_xyz_rpy`, upside down is a roll beyond ±90°
+        let s = if a.z < 0. { -1. } else { 1. };
+        let roll = atan2(a.y, s * (a.x * a.x + a.z * a.z).sqrt());
+        let pitch = atan2(-s * a.x, s * a.z);
+        euler::from_xyz_rpy(roll, pitch, 0.)
+    }
+}
+
+/// Verdict on an accelerometer burst, see the [module docs](self#first-fix)
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum InitQuality {
+    /// the filter was set to the tilt of the burst
+    Initialized {
+        /// largest per-axis standard deviation in g
+        std_dev_g: f32,
+    },
+    /// the readings spread too far, the device moved or vibrated
+    Moving {
+        /// largest per-axis standard deviation in g
+        std_dev_g: f32,
+    },
+    /// the mean is not gravity alone: sustained acceleration, free fall or a wrong scale
+    NotGravity {
+        /// magnitude of the mean in g
+        magnitude_g: f32,
+    },
+    /// the burst is shorter than `min_samples`
+    TooFewSamples {
+        /// readings in the burst
+        samples: u32,
+    },
+}
+
+impl InitQuality {
+    /// true if the filter was initialized
+    pub fn is_initialized(&self) -> bool {
+        matches!(self, Self::Initialized { .. })
+    }
+}
+
+/// Gyro weight scheduled over the rotation rate and the accelerometer trust, see the
+/// [module docs](self#gain-scheduling)
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct FilterGainSchedule {
+    /// gyro weight per update at rest, clamped to 0..=1
+    pub alpha_at_rest: f32,
+    /// gyro weight per update from twice the knee up, clamped to 0..=1
+    pub alpha_dynamic: f32,
+    /// gyro rate magnitude in rad/s halfway between the two weights
+    pub gyro_rate_knee_rad_s: f32,
+    /// scale the correction gain by the tilt trust of the accelerometer magnitude, with the
+    /// default [`TiltThresholds`]
+    pub accel_trust_weighting: bool,
+}
+
+impl FilterGainSchedule {
+    /// gyro weight per update at the gyro rate magnitude `rate` in rad/s
+    pub fn alpha(&self, rate: f32) -> f32 {
+        let x = (rate / (2. * self.gyro_rate_knee_rad_s)).clamp(0., 1.);
+        let s = x * x * (3. - 2. * x);
+        self.alpha_at_rest + (self.alpha_dynamic - self.alpha_at_rest) * s
+    }
+
+    /// correction gain weight of an accelerometer reading of trust `trust`: 1 High, 0.5
+    /// Medium, 0.1 Low, 0 Invalid
+    pub fn trust_weight(trust: TiltTrust) -> f32 {
+        match trust {
+            TiltTrust::High => 1.,
+            TiltTrust::Medium => 0.5,
+            TiltTrust::Low => 0.1,
+            TiltTrust::Invalid => 0.,
+        }
+    }
+}
+
+/// Accelerometer reacquisition after sustained gating, see the
+/// [module docs](self#accelerometer-gate-and-reacquisition)
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Reacquisition {
+    /// gated time in s after which corrections resume with a ramp
+    pub after_gated_s: f32,
+    /// reacquisition time constant in s: the accelerometer weight ramps from 0 to 1 over it
+    pub ramp_s: f32,
+}
+
+/// What [`ComplementaryFilter::update_sample`] does with a sample of a plausibility class, see
+/// the [module docs](self#plausibility)
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum SampleHandling {
+    /// update as with any other sample
+    Use,
+    /// scale the correction gain and the bias step by the weight, clamped to 0..=1
+    DeWeight(f32),
+    /// no update
+    Skip,
+}
+
+/// Handling of [`Suspect`](Plausibility::Suspect) and
+/// [`Implausible`](Plausibility::Implausible) samples
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct PlausibilityPolicy {
+    /// handling of suspect samples
+    pub suspect: SampleHandling,
+    /// handling of implausible samples
+    pub implausible: SampleHandling,
+}
+
+impl PlausibilityPolicy {
+    /// every sample used, whatever its score
+    pub const IGNORE: Self = Self {
+        suspect: SampleHandling::Use,
+        implausible: SampleHandling::Use,
+    };
+
+    /// weight of a sample of `class`, None to skip it
+    pub fn weight(&self, class: Plausibility) -> Option<f32> {
+        let handling = match class {
+            Plausibility::Ok => SampleHandling::Use,
+            Plausibility::Suspect => self.suspect,
+            Plausibility::Implausible => self.implausible,
+        };
+        match handling {
+            SampleHandling::Use => Some(1.),
+            SampleHandling::DeWeight(w) if w.is_nan() => Some(0.),
+            SampleHandling::DeWeight(w) => Some(w.clamp(0., 1.)),
+            SampleHandling::Skip => None,
+        }
+    }
+}
+
+impl Default for PlausibilityPolicy {
+    fn default() -> Self {
+        Self::IGNORE
+    }
+}
+
+/// Progress of an accelerometer reacquisition
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum ReacquisitionState {
+    /// no reacquisition since the last gating longer than `after_gated_s`, or none configured
+    Inactive,
+    /// corrections resumed, the weight is still ramping
+    Ramping {
+        /// time in s until the full weight, at the current ramp position
+        remaining_s: f32,
+    },
+    /// the last reacquisition reached the full weight
+    Complete,
+}
+
+/// Yaw of the orientation a re-entry sets, see the [module docs](self#re-entry)
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub enum ReentryYaw {
+    /// the yaw of the orientation before the break
+    #[default]
+    Hold,
+    /// zero, as a first fix
+    Zero,
+}
+
+/// State of a [`ComplementaryFilter`] after the last update
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct OrientationEstimate {
+    /// orientation, see the [module docs](self)
+    pub orientation: Quat,
+    /// gyro bias estimate in rad/s
+    pub bias: Vec3A,
+    /// the last update applied no accelerometer correction
+    pub accel_gated: bool,
+    /// time in s the corrections have been gated for, 0 once they resume
+    pub gated_s: f32,
+    /// weight of the last correction relative to `1 - α`: reacquisition ramp times the
+    /// provenance and plausibility weights, 0 when gated
+    pub accel_weight: f32,
+    /// gyro weight of the last update, scheduled by the
+    /// [gain schedule](self#gain-scheduling) if there is one, otherwise the fixed one
+    pub alpha: f32,
+    /// reacquisition progress
+    pub reacquisition: ReacquisitionState,
+    /// the auto initialization or a re-entry is collecting its burst, the orientation is
+    /// held
+    pub initializing: bool,
+    /// the last update given a continuity token found it changed and started a re-entry
+    pub continuity_break: bool,
+    /// re-entries since the filter was made
+    pub reentries: u32,
+}
+
+/// Gyro and accelerometer fusion with a gyro bias estimate, see the [module docs](self)
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct ComplementaryFilter {
+    alpha: f32,
+    gain_schedule: Option<FilterGainSchedule>,
+    /// gyro weight of the last update with a gain schedule
+    scheduled_alpha: Option<f32>,
+    bias_time_constant_s: Option<f32>,
+    orientation: Quat,
+    bias: Vec3A,
+    accel_gate: Option<f32>,
+    reacquisition: Option<Reacquisition>,
+    plausibility: PlausibilityPolicy,
+    gated_s: f32,
+    accel_weight: f32,
+    /// time in s into the current ramp, None without one
+    ramp_elapsed_s: Option<f32>,
+    ramp_complete: bool,
+    init_criteria: InitCriteria,
+    auto_initialize: bool,
+    /// burst of the running auto initialization and the rejected bursts before it
+    init: Option<(Accumulator, u8)>,
+    init_quality: Option<InitQuality>,
+    /// continuity token of the last update given one
+    continuity: Option<u32>,
+    reentry_yaw: ReentryYaw,
+    /// yaw to set once the burst of a re-entry passes
+    held_yaw: Option<f32>,
+    continuity_break: bool,
+    reentries: u32,
+}
+
+impl Default for ComplementaryFilter {
+    fn default() -> Self {
+        Self::new(DEFAULT_ALPHA)
+    }
+}
+
+impl ComplementaryFilter {
+    /// Filter at identity with gyro weight `alpha` per update, clamped to 0..=1, a bias
+    /// time constant of [`DEFAULT_BIAS_TIME_CONSTANT_S`] and an accelerometer gate of
+    /// [`DEFAULT_ACCEL_GATE`]
+    pub fn new(alpha: f32) -> Self {
+        Self {
+            alpha: alpha.clamp(0., 1.),
+            gain_schedule: None,
+            scheduled_alpha: None,
+            bias_time_constant_s: Some(DEFAULT_BIAS_TIME_CONSTANT_S),
+            orientation: Quat::IDENTITY,
+            bias: Vec3A::ZERO,
+            accel_gate: Some(DEFAULT_ACCEL_GATE),
+            reacquisition: None,
+            plausibility: PlausibilityPolicy::IGNORE,
+            gated_s: 0.,
+            accel_weight: 1.,
+            ramp_elapsed_s: None,
+            ramp_complete: false,
+            init_criteria: InitCriteria::DEFAULT,
+            auto_initialize: false,
+            init: None,
+            init_quality: None,
+            continuity: None,
+            reentry_yaw: ReentryYaw::Hold,
+            held_yaw: None,
+            continuity_break: false,
+            reentries: 0,
+        }
+    }
+
+    /// time constant of the bias estimate in s, None holds the bias estimate fixed
+    pub fn with_bias_time_constant(mut self, time_constant_s: Option<f32>) -> Self {
+        self.bias_time_constant_s = time_constant_s.filter(|tau| *tau > 0.);
+        self
+    }
+
+    /// Gate on the accelerometer magnitude: updates deviating from 1 g by more than the
+    /// fraction `max_deviation` apply no correction, [`DEFAULT_ACCEL_GATE`] unless given.
+    /// None, NaN or negative disable the gate
+    pub fn with_accel_gate(mut self, max_deviation: Option<f32>) -> Self {
+        self.accel_gate = max_deviation.filter(|d| *d >= 0.);
+        self
+    }
+
+    /// Ramp the accelerometer back in after sustained gating, None snaps to full corrections.
+    /// A `ramp_s` that is not positive disables the ramp
+    pub fn with_reacquisition(mut self, reacquisition: Option<Reacquisition>) -> Self {
+        self.reacquisition = reacquisition.filter(|r| r.ramp_s > 0.);
+        self
+    }
+
+    /// Schedule the gyro weight over the gyro rate and the accelerometer trust, None runs on
+    /// the fixed one. A knee that is not positive disables the schedule, see the
+    /// [module docs](self#gain-scheduling)
+    pub fn with_gain_schedule(mut self, schedule: Option<FilterGainSchedule>) -> Self {
+        self.gain_schedule =
+            schedule
+                .filter(|s| s.gyro_rate_knee_rad_s > 0.)
+                .map(|s| FilterGainSchedule {
+                    alpha_at_rest: s.alpha_at_rest.clamp(0., 1.),
+                    alpha_dynamic: s.alpha_dynamic.clamp(0., 1.),
+                    ..s
+                });
+        self.scheduled_alpha = None;
+        self
+    }
+
+    /// gain schedule
+    pub fn gain_schedule(&self) -> Option<FilterGainSchedule> {
+        self.gain_schedule
+    }
+
+    /// Handling of samples by their plausibility class in
+    /// [`update_sample`](Self::update_sample), see the [module docs](self#plausibility)
+    pub fn with_plausibility_policy(mut self, policy: PlausibilityPolicy) -> Self {
+        self.plausibility = policy;
+        self
+    }
+
+    /// Criteria of a first fix from an accelerometer burst, [`InitCriteria::DEFAULT`] unless
+    /// given
+    pub fn with_init_criteria(mut self, criteria: InitCriteria) -> Self {
+        self.init_criteria = criteria;
+        self
+    }
+
+    /// Initialize from the accelerometer readings of the first updates, see the
+    /// [module docs](self#first-fix)
+    pub fn with_auto_initialize(mut self, auto: bool) -> Self {
+        self.auto_initialize = auto;
+        self.init = auto.then(|| (Accumulator::default(), 0));
+        self
+    }
+
+    /// Yaw a re-entry sets, [`ReentryYaw::Hold`] unless given, see the
+    /// [module docs](self#re-entry)
+    pub fn with_reentry_yaw(mut self, yaw: ReentryYaw) -> Self {
+        self.reentry_yaw = yaw;
+        self
+    }
+
+    /// yaw a re-entry sets
+    pub fn reentry_yaw(&self) -> ReentryYaw {
+        self.reentry_yaw
+    }
+
+    /// continuity token of the last update given one, None before
+    pub fn continuity_token(&self) -> Option<u32> {
+        self.continuity
+    }
+
+    /// criteria of a first fix
+    pub fn init_criteria(&self) -> InitCriteria {
+        self.init_criteria
+    }
+
+    /// whether the filter initializes from its first updates
+    pub fn auto_initialize(&self) -> bool {
+        self.auto_initialize
+    }
+
+    /// verdict on the last burst, None before the first
+    pub fn init_quality(&self) -> Option<InitQuality> {
+        self.init_quality
+    }
+
+    /// Sets the tilt of a burst of accelerometer readings in g taken at rest, yaw zero, if
+    /// the burst passes the [`InitCriteria`]. See the [module docs](self#first-fix)
+    pub fn initialize_from_accel(&mut self, samples: &[Vec3A]) -> InitQuality {
+        self.initialize_from_burst(&InitBurst::from_samples(samples))
+    }
+
+    /// [`initialize_from_accel`](Self::initialize_from_accel) with a summarized burst, e.g.
+    /// of [`collect_init_burst`](crate::Mpu6050::collect_init_burst)
+    pub fn initialize_from_burst(&mut self, burst: &InitBurst) -> InitQuality {
+        let quality = burst.quality(&self.init_criteria);
+        if quality.is_initialized() {
+            self.orientation = burst.tilt();
+            self.gated_s = 0.;
+            self.accel_weight = 1.;
+            self.ramp_elapsed_s = None;
+            self.ramp_complete = false;
+            self.init = None;
+        }
+        self.init_quality = Some(quality);
+        quality
+    }
+
+    /// accelerometer gate, the largest relative deviation of the magnitude from 1 g
+    pub fn accel_gate(&self) -> Option<f32> {
+        self.accel_gate
+    }
+
+    /// reacquisition settings
+    pub fn reacquisition(&self) -> Option<Reacquisition> {
+        self.reacquisition
+    }
+
+    /// handling of samples by their plausibility class
+    pub fn plausibility_policy(&self) -> PlausibilityPolicy {
+        self.plausibility
+    }
+
+    /// gyro weight per update
+    pub fn alpha(&self) -> f32 {
+        self.alpha
+    }
+
+    /// set the gyro weight per update, clamped to 0..=1
+    pub fn set_alpha(&mut self, alpha: f32) {
+        self.alpha = alpha.clamp(0., 1.);
+    }
+
+    /// time constant of the bias estimate in s, None if the bias estimate is held
+    pub fn bias_time_constant(&self) -> Option<f32> {
+        self.bias_time_constant_s
+    }
+
+    /// current estimate, no update
+    pub fn orientation(&self) -> Quat {
+        self.orientation
+    }
+
+    /// current estimate converted into any quaternion type constructible from `[x, y, z, w]`
+    pub fn orientation_as<Q: From<[f32; 4]>>(&self) -> Q {
+        Q::from(self.orientation.to_array())
+    }
+
+    /// orientation with the gate and reacquisition state of the last update
+    pub fn estimate(&self) -> OrientationEstimate {
+        let reacquisition = match (self.ramp_elapsed_s, self.reacquisition) {
+            (Some(elapsed), Some(r)) => ReacquisitionState::Ramping {
+                remaining_s: (r.ramp_s - elapsed).max(0.),
+            },
+            _ if self.ramp_complete => ReacquisitionState::Complete,
+            _ => ReacquisitionState::Inactive,
+        };
+        OrientationEstimate {
+            orientation: self.orientation,
+            bias: self.bias,
+            accel_gated: self.gated_s > 0.,
+            gated_s: self.gated_s,
+            accel_weight: self.accel_weight,
+            alpha: self.scheduled_alpha.unwrap_or(self.alpha),
+            reacquisition,
+            initializing: self.init.is_some(),
+            continuity_break: self.continuity_break,
+            reentries: self.reentries,
+        }
+    }
+
+    /// gyro bias estimate in rad/s, subtracted from the rates before integrating
+    pub fn bias_estimate(&self) -> Vec3A {
+        self.bias
+    }
+
+    /// replace the gyro bias estimate in rad/s, e.g. with the one of an external estimator
+    pub fn set_bias_estimate(&mut self, bias: Vec3A) {
+        self.bias = bias;
+    }
+
+    /// Blends `orientation` in with the weight `clamp(confidence, 0, 1)`: 0 keeps the
+    /// estimate, 1 replaces it. The bias estimate is kept
+    pub fn apply_external_correction(&mut self, orientation: Quat, confidence: f32) {
+        let weight = if confidence.is_nan() {
+            0.
+        } else {
+            confidence.clamp(0., 1.)
+        };
+        self.orientation = self
+            .orientation
+            .slerp(orientation.normalize(), weight)
+            .normalize();
+    }
+
+    /// Replaces the orientation and ends a running auto initialization, the bias estimate
+    /// is kept; clear it with [`set_bias_estimate`](Self::set_bias_estimate) for a full
+    /// restart
+    pub fn reset_to(&mut self, orientation: Quat) {
+        self.orientation = orientation.normalize();
+        self.init = None;
+        self.held_yaw = None;
+    }
+
+    /// One filter step with `gyro` in rad/s, `acc` in g and `dt` in s, returns the new
+    /// estimate. A zero or non finite `acc` skips the accelerometer correction
+    pub fn update(&mut self, gyro: Vec3A, acc: Vec3A, dt: f32) -> Quat {
+        self.step(Some(gyro), acc, dt, 1., 1.)
+    }
+
+    /// [`update`](Self::update) with the readings of `sample`, weighed by their provenance
+    /// and handled by their plausibility, see the [module docs](self#provenance)
+    pub fn update_sample(&mut self, sample: &MpuSample, dt: f32) -> Quat {
+        let Some(weight) = self.plausibility.weight(sample.plausibility().class()) else {
+            return self.orientation;
+        };
+        let tags = sample.provenance();
+        let (acc_tag, gyro_tag) = (tags.get(FieldGroup::Acc), tags.get(FieldGroup::Gyro));
+        let gyro = (gyro_tag != Provenance::Invalid).then_some(sample.gyro());
+        let acc_weight = acc_tag.weight() * weight;
+        self.step(
+            gyro,
+            sample.acc(),
+            dt,
+            acc_weight,
+            acc_weight * gyro_tag.weight(),
+        )
+    }
+
+    /// [`update`](Self::update) of a sample taken at the driver's continuity `token`, a
+    /// re-entry if it changed since the last one, see the [module docs](self#re-entry)
+    pub fn update_with_token(&mut self, gyro: Vec3A, acc: Vec3A, dt: f32, token: u32) -> Quat {
+        self.observe_continuity(token);
+        self.update(gyro, acc, dt)
+    }
+
+    /// [`update_sample`](Self::update_sample) of a sample taken at the driver's continuity
+    /// `token`, a re-entry if it changed since the last one. A skipped sample observes the
+    /// token all the same, the re-entry starts with the next one used
+    pub fn update_sample_with_token(&mut self, sample: &MpuSample, dt: f32, token: u32) -> Quat {
+        self.observe_continuity(token);
+        self.update_sample(sample, dt)
+    }
+
+    /// takes `token`, starting a re-entry on a change
+    fn observe_continuity(&mut self, token: u32) {
+        let previous = self.continuity.replace(token);
+        self.continuity_break = previous.is_some_and(|previous| previous != token);
+        if !self.continuity_break {
+            return;
+        }
+        self.held_yaw = match self.reentry_yaw {
+            ReentryYaw::Hold => Some(euler::to_xyz_rpy(self.orientation).yaw),
+            ReentryYaw::Zero => Some(0.),
+        };
+        self.init = Some((Accumulator::default(), 0));
+        self.gated_s = 0.;
+        self.ramp_elapsed_s = None;
+        self.ramp_complete = false;
+        self.reentries = self.reentries.wrapping_add(1);
+    }
+
+    /// filter step, the correction gain scaled by `acc_weight` and the bias step by
+    /// `bias_weight`, no prediction without `gyro`
+    fn step(
+        &mut self,
+        gyro: Option<Vec3A>,
+        acc: Vec3A,
+        dt: f32,
+        acc_weight: f32,
+        bias_weight: f32,
+    ) -> Quat {
+        if self.init.is_some() {
+            return self.collect_init(acc, acc_weight);
+        }
+        let rate = gyro.map(|gyro| gyro - self.bias);
+        let alpha = match self.gain_schedule {
+            Some(schedule) => {
+                let mut gain = 1. - schedule.alpha(rate.map_or(0., Vec3A::length));
+                if schedule.accel_trust_weighting {
+                    let trust = tilt::classify(acc.length(), &TiltThresholds::default());
+                    gain *= FilterGainSchedule::trust_weight(trust);
+                }
+                let alpha = 1. - gain;
+                self.scheduled_alpha = Some(alpha);
+                alpha
+            }
+            None => self.alpha,
+        };
+        let predicted = match rate {
+            Some(rate) => {
+                (self.orientation * Quat::from_scaled_axis(Vec3::from(rate * dt))).normalize()
+            }
+            None => self.orientation,
+        };
+        self.orientation = predicted;
+
+        let measured = acc.normalize_or_zero();
+        let gated = match self.accel_gate {
+            Some(max) => (acc.length() - 1.).abs() > max,
+            None => false,
+        };
+        if measured == Vec3A::ZERO || acc_weight <= 0. || gated {
+            self.gate(dt);
+            return self.orientation;
+        }
+        let ramp = self.resume(dt);
+        let expected = predicted.inverse() * Vec3A::Z;
+        let correction = Quat::from_rotation_arc(Vec3::from(measured), Vec3::from(expected));
+        let gain = 1. - alpha;
+        self.accel_weight = acc_weight * ramp;
+        self.orientation =
+            (predicted * Quat::IDENTITY.slerp(correction, gain * self.accel_weight)).normalize();
+
+        if let Some(tau) = self.bias_time_constant_s {
+            let error = Vec3A::from(correction.to_scaled_axis());
+            self.bias -= error * (gain * bias_weight * ramp / tau);
+        }
+        self.orientation
+    }
+
+    /// an update of the auto initialization, readings without weight do not count
+    fn collect_init(&mut self, acc: Vec3A, acc_weight: f32) -> Quat {
+        let Some((burst, attempts)) = &mut self.init else {
+            return self.orientation;
+        };
+        if acc_weight <= 0. {
+            return self.orientation;
+        }
+        burst.push(acc);
+        if burst.count() < self.init_criteria.min_samples {
+            return self.orientation;
+        }
+        let burst = InitBurst::from_accumulator(burst);
+        *attempts += 1;
+        let attempts = *attempts;
+        if self.initialize_from_burst(&burst).is_initialized() {
+            if let Some(yaw) = self.held_yaw.take() {
+                let rpy = euler::to_xyz_rpy(self.orientation);
+                self.orientation = euler::from_xyz_rpy(rpy.roll, rpy.pitch, yaw);
+            }
+        } else {
+            self.init = (attempts < self.init_criteria.max_attempts)
+                .then(|| (Accumulator::default(), attempts));
+            if self.init.is_none() {
+                self.held_yaw = None;
+            }
+        }
+        self.orientation
+    }
+
+    /// an update without correction
+    fn gate(&mut self, dt: f32) {
+        self.gated_s += dt;
+        self.accel_weight = 0.;
+        if let Some(r) = self.reacquisition {
+            if self.gated_s > r.after_gated_s {
+                self.ramp_complete = false;
+            }
+        }
+    }
+
+    /// corrections resume, returns the ramp weight
+    fn resume(&mut self, dt: f32) -> f32 {
+        let gated_s = core::mem::replace(&mut self.gated_s, 0.);
+        let Some(r) = self.reacquisition else {
+            return 1.;
+        };
+        if gated_s > r.after_gated_s {
+            log_debug!(
+                FUSION,
+                "accel gated for {} s, reacquisition ramp over {} s",
+                gated_s,
+                r.ramp_s
+            );
+            self.ramp_elapsed_s = Some(0.);
+        }
+        let Some(elapsed) = self.ramp_elapsed_s else {
+            return 1.;
+        };
+        let elapsed = elapsed + dt;
+        if elapsed >= r.ramp_s {
+            self.ramp_elapsed_s = None;
+            self.ramp_complete = true;
+            log_debug!(FUSION, "reacquisition ramp complete");
+            return 1.;
+        }
+        self.ramp_elapsed_s = Some(elapsed);
+        elapsed / r.ramp_s
+    }
+}
+
+#[cfg(feature = "driver")]
+impl<I, D, E> Mpu6050<I, D>
+where
+    I: Write<Error = E> + WriteRead<Error = E>,
+{
+    /// Reads a sample in one burst with [`get_all`](Self::get_all) and steps `filter` with
+    /// it over `dt` s at the driver's continuity token, see
+    /// [`update_sample_with_token`](ComplementaryFilter::update_sample_with_token) and the
+    /// [module docs](self#driver). A failed read leaves the filter as it was
+    pub fn update_orientation(
+        &mut self,
+        filter: &mut ComplementaryFilter,
+        dt: f32,
+    ) -> Result<Quat, Mpu6050Error<E>> {
+        let sample = self.get_all()?;
+        Ok(filter.update_sample_with_token(&sample, dt, self.continuity_token()))
+    }
+
+    pub(crate) fn collect_init_burst_using(
+        &mut self,
+        delay: &mut impl DelayMs<u8>,
+        n: u16,
+        deadline: &mut dyn FnMut() -> bool,
+    ) -> Result<InitBurst, Mpu6050Error<E>> {
+        let mut watch = Watch::new(INIT_BURST_PHASE, deadline, self.io_stats.transactions);
+        let mut acc = Accumulator::default();
+        for idx in 0..n {
+            if idx > 0 {
+                watch.check(self.io_stats.transactions, Some(&acc))?;
+                watch.wait(delay, INIT_BURST_INTERVAL_MS);
+            }
+            watch.check(self.io_stats.transactions, Some(&acc))?;
+            acc.push(self.get_acc()?);
+        }
+        Ok(InitBurst::from_accumulator(&acc))
+    }
+}
@@ -0,0 +1,316 @@
+//! Oscillator frequency error, measured against the host clock.
+//!
+//! The sample rate derives from the chip's clock, the internal 8 MHz oscillator or a gyro
+//! PLL, and is off by up to a few percent. Everything derived from the nominal output data
+//! rate (ODR) inherits the error: integration steps, sample intervals, timestamps of FIFO
+//! frames. [`Mpu6050::estimate_clock_error`] counts samples over a host timed interval and
+//! compares them with the configured ODR:
+//! 1. the gyro X axis alone is written to the FIFO, 2 bytes per sample
+//! 2. per window: FIFO reset, host delay of the window length, FIFO count read. A window is
+//!    at most [`MAX_WINDOW_MS`] and at most [`WINDOW_FILL`] of the FIFO at the nominal ODR,
+//!    so a clock up to a third fast does not overflow it
+//! 3. windows repeat until `measurement_ms` is covered, the previous FIFO sources are
+//!    restored and the FIFO is reset and left disabled
+//!
+//! No sample is read and nothing is timestamped, only counted. A window costs
+//! [`TRANSACTIONS_PER_WINDOW`] transactions: the read-modify-write of USER_CTRL FIFO_RESET
+//! and the FIFO_COUNT read, 12 transactions per second up to an ODR of 1.5 kHz and at most
+//! 3 per window length, 60 per second at 8 kHz. Setup and restore add 8 transactions once.
+//! The host interval is the delay: the FIFO_COUNT read after the delay lengthens every
+//! window by one transaction, about 0.1 ms at 400 kHz or 0.04 % of a 250 ms window,
+//! measured as a slightly fast clock.
+//!
+//! #### Statistics
+//! [`ClockCounter`] holds the counts and is independent of the bus. A window of `T` at the
+//! true rate `f` counts `floor(f T)` or `ceil(f T)` samples depending on the sample phase at
+//! the reset, a quantization error of variance at most 1/4. The ratio true rate / nominal
+//! rate is the total count over the nominal count of the total time. Its 95 % confidence
+//! interval is ±1.96 standard errors, the larger of the quantization bound `√k / 2` over `k`
+//! windows and the spread of the per window ratios, which holds host delay jitter.
+//!
+//! #### Correction
+//! [`Mpu6050::apply_clock_correction`] stores a ratio, from then on
+//! [`effective_odr_hz`](Mpu6050::effective_odr_hz),
+//! [`nominal_sample_interval_us`](Mpu6050::nominal_sample_interval_us), the resampler of
+//! [`uniform_resampler`](Mpu6050::uniform_resampler) and
+//! [`fifo_frame_timestamps_us`](Mpu6050::fifo_frame_timestamps_us) use the corrected rate.
+//! The ratio belongs to the clock source it was measured with, measure again after
+//! changing it.
+//! ```
+//! use mpu6050::oscillator::ClockCounter;
+//!
+//! // 1 kHz nominal, a sensor 2 % fast counts about 204 samples per 200 ms window
+//! let mut counter = ClockCounter::new(1000.);
+//! for frames in [204, 205, 204, 203, 204] {
+//!     counter.add_window(frames, 200_000);
+//! }
+//! let estimate = counter.estimate().unwrap();
+//! assert!((estimate.ratio - 1.02).abs() < 1e-6);
+//! assert!(estimate.ratio_low < 1.02 && 1.02 < estimate.ratio_high);
+//! assert!((estimate.effective_odr_hz - 1020.).abs() < 1e-3);
+//! ```
+
+use core::fmt;
+
+#[cfg(feature = "driver")]
+use embedded_hal::blocking::{
+    delay::DelayMs,
+    i2c::{Write, WriteRead},
+};
+
+use crate::decimal::Decimal;
+use crate::device::Capability;
+use crate::fifo::{FifoSources, FIFO_CAPACITY};
+#[cfg(not(feature = "std"))]
+use crate::math::FloatExt;
+use crate::resample::UniformResampler;
+use crate::settings::SettingsError;
+use crate::time_math::before_us;
+use crate::{Mpu6050, Mpu6050Error};
+
+/// Longest measurement window in ms, the longest delay of a `DelayMs<u8>` call
+pub const MAX_WINDOW_MS: u32 = 250;
+/// Fraction of the FIFO a window fills at the nominal rate
+pub const WINDOW_FILL: f32 = 0.75;
+/// FIFO bytes per counted sample, gyro X alone
+pub const BYTES_PER_SAMPLE: u16 = 2;
+/// Bus transactions of a measurement window
+pub const TRANSACTIONS_PER_WINDOW: u32 = 3;
+/// Largest correction accepted by [`Mpu6050::apply_clock_correction`], in ppm
+pub const MAX_CORRECTION_PPM: u32 = 100_000;
+/// Standard errors of the 95 % confidence interval
+const Z_95: f64 = 1.96;
+
+/// Result of a clock error measurement, see the module docs
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct ClockErrorEstimate {
+    /// true rate / nominal rate, above 1 for a fast clock
+    pub ratio: f32,
+    /// lower end of the 95 % confidence interval of `ratio`
+    pub ratio_low: f32,
+    /// upper end of the 95 % confidence interval of `ratio`
+    pub ratio_high: f32,
+    /// configured ODR in Hz
+    pub nominal_odr_hz: f32,
+    /// measured ODR in Hz, `nominal_odr_hz * ratio`
+    pub effective_odr_hz: f32,
+    /// samples counted
+    pub samples: u64,
+    /// windows measured
+    pub windows: u32,
+    /// host time of all windows in µs
+    pub duration_us: u64,
+}
+
+impl ClockErrorEstimate {
+    /// frequency error in ppm, positive for a fast clock
+    pub fn error_ppm(&self) -> f32 {
+        (self.ratio - 1.) * 1e6
+    }
+
+    /// half width of the confidence interval in ppm
+    pub fn confidence_ppm(&self) -> f32 {
+        (self.ratio_high - self.ratio_low) / 2. * 1e6
+    }
+}
+
+impl fmt::Display for ClockErrorEstimate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "clock {:+.0} ppm ± {:.0} ppm (95 %), ODR {:.2} Hz nominal {:.2} Hz, {} samples in {} windows",
+            Decimal(self.error_ppm()),
+            Decimal(self.confidence_ppm()),
+            Decimal(self.effective_odr_hz),
+            Decimal(self.nominal_odr_hz),
+            self.samples,
+            self.windows
+        )
+    }
+}
+
+/// Sample counts over host timed windows, see the module docs
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct ClockCounter {
+    nominal_odr_hz: f32,
+    windows: u32,
+    samples: u64,
+    duration_us: u64,
+    /// sum and sum of squares of the per window ratios
+    ratio_sum: f64,
+    ratio_sq_sum: f64,
+}
+
+impl ClockCounter {
+    /// counter for a configured rate of `nominal_odr_hz`
+    pub fn new(nominal_odr_hz: f32) -> Self {
+        Self {
+            nominal_odr_hz,
+            windows: 0,
+            samples: 0,
+            duration_us: 0,
+            ratio_sum: 0.,
+            ratio_sq_sum: 0.,
+        }
+    }
+
+    /// adds a window of `window_us` host time in which `samples` were counted. Empty
+    /// windows are ignored
+    pub fn add_window(&mut self, samples: u32, window_us: u64) {
+        if window_us == 0 {
+            return;
+        }
+        let ratio = samples as f64 / (self.nominal_odr_hz as f64 * window_us as f64 * 1e-6);
+        self.windows += 1;
+        self.samples += samples as u64;
+        self.duration_us += window_us;
+        self.ratio_sum += ratio;
+        self.ratio_sq_sum += ratio * ratio;
+    }
+
+    /// windows added
+    pub fn windows(&self) -> u32 {
+        self.windows
+    }
+
+    /// estimate over all windows, None without a window
+    pub fn estimate(&self) -> Option<ClockErrorEstimate> {
+        if self.windows == 0 {
+            return None;
+        }
+        let nominal_samples = self.nominal_odr_hz as f64 * self.duration_us as f64 * 1e-6;
+        let ratio = self.samples as f64 / nominal_samples;
+        let k = self.windows as f64;
+        let quantization = k.sqrt() / 2. / nominal_samples;
+        let spread = if self.windows > 1 {
+            let mean = self.ratio_sum / k;
+            let variance = (self.ratio_sq_sum - k * mean * mean).max(0.) / (k - 1.);
+            (variance / k).sqrt()
+        } else {
+            0.
+        };
+        let half_width = Z_95 * quantization.max(spread);
+        Some(ClockErrorEstimate {
+            ratio: ratio as f32,
+            ratio_low: (ratio - half_width) as f32,
+            ratio_high: (ratio + half_width) as f32,
+            nominal_odr_hz: self.nominal_odr_hz,
+            effective_odr_hz: (self.nominal_odr_hz as f64 * ratio) as f32,
+            samples: self.samples,
+            windows: self.windows,
+            duration_us: self.duration_us,
+        })
+    }
+}
+
+/// Window length in ms at a nominal rate of `odr_hz`, see the module docs
+pub fn window_ms(odr_hz: f32) -> u32 {
+    let fill_ms = WINDOW_FILL * (FIFO_CAPACITY / BYTES_PER_SAMPLE) as f32 / odr_hz * 1000.;
+    (fill_ms as u32).clamp(1, MAX_WINDOW_MS)
+}
+
+impl<I, D> Mpu6050<I, D> {
+    /// Stores the ratio true rate / nominal rate of an [`estimate_clock_error`] measurement,
+    /// 1 removes the correction. Ratios off by more than [`MAX_CORRECTION_PPM`] are refused
+    ///
+    /// [`estimate_clock_error`]: Mpu6050::estimate_clock_error
+    pub fn apply_clock_correction(&mut self, ratio: f32) -> Result<(), SettingsError> {
+        let max = 1. + MAX_CORRECTION_PPM as f32 * 1e-6;
+        if !(ratio >= 1. / max && ratio <= max) {
+            return Err(SettingsError::ClockCorrection);
+        }
+        self.clock_ratio = ratio;
+        Ok(())
+    }
+
+    /// ratio stored by [`apply_clock_correction`](Self::apply_clock_correction), 1 without
+    pub fn clock_correction(&self) -> f32 {
+        self.clock_ratio
+    }
+
+    /// ODR of the configuration last written by the driver, corrected. No bus access
+    pub fn effective_odr_hz(&self) -> f32 {
+        self.check_aliasing().odr_hz * self.clock_ratio
+    }
+
+    /// interval between samples in µs at the [`effective_odr_hz`](Self::effective_odr_hz)
+    pub fn nominal_sample_interval_us(&self) -> f32 {
+        1e6 / self.effective_odr_hz()
+    }
+
+    /// [`UniformResampler`] to `rate_hz` for this driver's samples, its skew is measured
+    /// against the corrected rate
+    pub fn uniform_resampler(&self, rate_hz: u32, max_bridge_us: u64) -> UniformResampler {
+        UniformResampler::new(rate_hz, self.effective_odr_hz(), max_bridge_us)
+    }
+
+    /// Host timestamps of `frames` FIFO frames read at once, the newest sampled at
+    /// `newest_us`, oldest first, spaced by the corrected sample interval
+    pub fn fifo_frame_timestamps_us(
+        &self,
+        newest_us: u64,
+        frames: usize,
+    ) -> impl Iterator<Item = u64> {
+        let interval_us = self.nominal_sample_interval_us() as f64;
+        (0..frames).map(move |i| {
+            let back_us = ((frames - 1 - i) as f64 * interval_us).round() as u64;
+            before_us(newest_us, back_us)
+        })
+    }
+}
+
+#[cfg(feature = "driver")]
+impl<I, D, E> Mpu6050<I, D>
+where
+    I: Write<Error = E> + WriteRead<Error = E>,
+{
+    /// Measures the clock error over `measurement_ms` of `delay`, at least one window, see
+    /// the module docs. Needs the FIFO idle ([`Mpu6050Error::FifoActive`] otherwise) and
+    /// the gyros running, cycle mode is refused. The correction is not applied
+    pub fn estimate_clock_error<DL: DelayMs<u8>>(
+        &mut self,
+        delay: &mut DL,
+        measurement_ms: u32,
+    ) -> Result<ClockErrorEstimate, Mpu6050Error<E>> {
+        self.require(Capability::Fifo)?;
+        if self.fifo_streaming {
+            return Err(Mpu6050Error::FifoActive);
+        }
+        if self.cycle.is_some() {
+            return Err(Mpu6050Error::InvalidSettings(
+                SettingsError::ClockMeasurementInCycle,
+            ));
+        }
+        let mut counter = ClockCounter::new(self.check_aliasing().odr_hz);
+        let previous = self.fifo_sources;
+        self.set_fifo_sources(FifoSources::NONE.with_gyro_axes([true, false, false]))?;
+        let counted = self.count_clock_windows(delay, measurement_ms, &mut counter);
+        // restored even after a failed window
+        let restored = self
+            .set_fifo_enabled(false)
+            .and_then(|_| self.set_fifo_sources(previous))
+            .and_then(|_| self.reset_fifo());
+        counted.and(restored)?;
+        Ok(counter.estimate().expect("at least one window"))
+    }
+
+    fn count_clock_windows<DL: DelayMs<u8>>(
+        &mut self,
+        delay: &mut DL,
+        measurement_ms: u32,
+        counter: &mut ClockCounter,
+    ) -> Result<(), Mpu6050Error<E>> {
+        self.set_fifo_enabled(true)?;
+        let window = window_ms(self.check_aliasing().odr_hz);
+        let mut remaining = measurement_ms.max(1);
+        while remaining > 0 {
+            let ms = remaining.min(window);
+            self.reset_fifo()?;
+            delay.delay_ms(ms as u8);
+            let count = self.fifo_count()?;
+            counter.add_window((count / BYTES_PER_SAMPLE) as u32, ms as u64 * 1000);
+            remaining -= ms;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,354 @@
+//! Reduced precision binary export for bandwidth limited links.
+//!
+//! Each sample is stored as 7 values, accel x, y, z, gyro x, y, z and temperature, in raw
+//! counts of the recorded ranges reduced to 12 or 10 bits and bit-packed MSB first without
+//! padding between samples. A batch is
+//!
+//! | bytes | content |
+//! |:---|:---|
+//! | 1 | format version ([`PACKED_VERSION`]) |
+//! | 1 | bit width, 12 or 10 |
+//! | 1 | accel range (bits 3:2), gyro range (bits 1:0) |
+//! | 1 | flags, bit 0: at least one value saturated, bit 1: provenance block present |
+//! | 2 | sample count, little endian |
+//! | n | packed values, the last byte zero padded |
+//! | 3 N | provenance block, if flagged: the [`SampleProvenance`] bytes of every sample |
+//! | 2 | CRC-16/CCITT-FALSE over all previous bytes, little endian |
+//!
+//! The provenance block is written when a sample is not all measured, see
+//! [`provenance`](crate::provenance); a batch of measured samples is the same as in version 1,
+//! which still decodes, with only the version byte changed.
+//!
+//! #### Reduction
+//! Scaled values are rounded to counts of the recorded ranges and the counts narrowed to the
+//! bit width, both with the [`RoundingMode`], nearest even unless encoded with
+//! [`encode_binary_packed_with_rounding`], see [`conversion`](crate::conversion). Values above
+//! the largest reduced value saturate, as do scaled values outside the recorded range, both
+//! set the saturation flag. Decoding is exact, every reduced value maps to one count.
+//!
+//! #### Size
+//! | bit width | bits per sample | bytes for N samples |
+//! |:---|:---|:---|
+//! | 16 (unpacked) | 112 | 14 N |
+//! | 12 | 84 | 8 + ceil(10.5 N) |
+//! | 10 | 70 | 8 + ceil(8.75 N) |
+//!
+//! The provenance block adds 24 bits per sample, [`packed_len_with_provenance`].
+
+use core::fmt;
+
+use crate::conversion::{self, RoundingMode};
+use crate::device::{AccelRange, GyroRange};
+use crate::provenance::{SampleProvenance, PROVENANCE_LEN};
+use crate::MpuSample;
+
+/// Format version written to the header
+pub const PACKED_VERSION: u8 = 2;
+/// Header bytes before the packed values
+pub const PACKED_HEADER_LEN: usize = 6;
+/// CRC bytes after the packed values
+pub const PACKED_CRC_LEN: usize = 2;
+/// Values per sample
+pub const VALUES_PER_SAMPLE: usize = 7;
+
+const FLAG_SATURATED: u8 = 0x01;
+const FLAG_PROVENANCE: u8 = 0x02;
+/// last version without the provenance block
+const PACKED_VERSION_1: u8 = 1;
+
+/// Bit width of packed values
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum PackedBits {
+    /// 12 bits per value, 16x coarser than raw
+    B12 = 12,
+    /// 10 bits per value, 64x coarser than raw
+    B10 = 10,
+}
+
+impl PackedBits {
+    fn from_u8(bits: u8) -> Option<Self> {
+        match bits {
+            12 => Some(PackedBits::B12),
+            10 => Some(PackedBits::B10),
+            _ => None,
+        }
+    }
+}
+
+/// Bytes needed to encode `samples` measured samples at `bits`
+pub fn packed_len(samples: usize, bits: PackedBits) -> usize {
+    let payload_bits = samples * VALUES_PER_SAMPLE * bits as usize;
+    PACKED_HEADER_LEN + payload_bits.div_ceil(8) + PACKED_CRC_LEN
+}
+
+/// Bytes needed to encode `samples` samples at `bits` with the provenance block, enough for
+/// any samples
+pub fn packed_len_with_provenance(samples: usize, bits: PackedBits) -> usize {
+    packed_len(samples, bits) + samples * PROVENANCE_LEN
+}
+
+/// Encoding failure
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum EncodeError {
+    /// output buffer too small, required length
+    BufferTooSmall(usize),
+    /// more samples than the u16 sample count holds
+    TooManySamples(usize),
+}
+
+impl fmt::Display for EncodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EncodeError::BufferTooSmall(len) => {
+                write!(f, "buffer too small, {} bytes required", len)
+            }
+            EncodeError::TooManySamples(n) => write!(f, "{} samples exceed one batch", n),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for EncodeError {}
+
+/// Decoding failure
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum DecodeError {
+    /// input shorter than its header says, required length
+    Truncated(usize),
+    /// unknown version or bit width
+    BadHeader,
+    /// CRC of the batch does not match
+    CrcMismatch,
+    /// reserved provenance tag
+    BadProvenance,
+    /// output slice too small, samples in the batch
+    OutputTooSmall(usize),
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DecodeError::Truncated(len) => write!(f, "batch truncated, {} bytes expected", len),
+            DecodeError::BadHeader => f.write_str("unknown packed format"),
+            DecodeError::CrcMismatch => f.write_str("CRC mismatch"),
+            DecodeError::BadProvenance => f.write_str("reserved provenance tag"),
+            DecodeError::OutputTooSmall(n) => write!(f, "output too small for {} samples", n),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for DecodeError {}
+
+/// Header of a decoded batch
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct PackedBatch {
+    /// samples in the batch
+    pub count: usize,
+    /// bit width of the values
+    pub bits: PackedBits,
+    /// accelerometer range of the counts
+    pub accel_range: AccelRange,
+    /// gyro range of the counts
+    pub gyro_range: GyroRange,
+    /// at least one value saturated while encoding
+    pub saturated: bool,
+    /// the batch holds a provenance block, otherwise every sample decodes as measured
+    pub provenance: bool,
+}
+
+/// CRC-16/CCITT-FALSE: poly 0x1021, init 0xffff, no reflection, no final xor
+pub fn crc16(bytes: &[u8]) -> u16 {
+    bytes.iter().fold(0xffff, |mut crc: u16, byte| {
+        crc ^= (*byte as u16) << 8;
+        for _ in 0..8 {
+            crc = if crc & 0x8000 != 0 {
+                (crc << 1) ^ 0x1021
+            } else {
+                crc << 1
+            };
+        }
+        crc
+    })
+}
+
+/// Encodes `samples` recorded at the given ranges into `buf`, returns the bytes written. The
+/// provenance block makes it [`packed_len_with_provenance`] long unless every sample is
+/// measured
+pub fn encode_binary_packed(
+    samples: &[MpuSample],
+    bits: PackedBits,
+    accel_range: AccelRange,
+    gyro_range: GyroRange,
+    buf: &mut [u8],
+) -> Result<usize, EncodeError> {
+    let rounding = RoundingMode::default();
+    encode_binary_packed_with_rounding(samples, bits, accel_range, gyro_range, rounding, buf)
+}
+
+/// [`encode_binary_packed`] rounding with `rounding`, [`RoundingMode::Legacy`] for the bytes
+/// of earlier releases
+pub fn encode_binary_packed_with_rounding(
+    samples: &[MpuSample],
+    bits: PackedBits,
+    accel_range: AccelRange,
+    gyro_range: GyroRange,
+    rounding: RoundingMode,
+    buf: &mut [u8],
+) -> Result<usize, EncodeError> {
+    let count =
+        u16::try_from(samples.len()).map_err(|_| EncodeError::TooManySamples(samples.len()))?;
+    let tagged = samples.iter().any(|s| !s.provenance().is_measured());
+    let len = match tagged {
+        true => packed_len_with_provenance(samples.len(), bits),
+        false => packed_len(samples.len(), bits),
+    };
+    if buf.len() < len {
+        return Err(EncodeError::BufferTooSmall(len));
+    }
+    let buf = &mut buf[..len];
+    buf.fill(0);
+
+    let values_end = packed_len(samples.len(), bits) - PACKED_CRC_LEN;
+    if tagged {
+        let block = &mut buf[values_end..len - PACKED_CRC_LEN];
+        for (tags, sample) in block.chunks_exact_mut(PROVENANCE_LEN).zip(samples) {
+            tags.copy_from_slice(&sample.provenance().to_bytes());
+        }
+    }
+    let mut saturated = false;
+    let mut writer = BitWriter::new(&mut buf[PACKED_HEADER_LEN..values_end]);
+    let (acc_sens, gyro_sens) = (accel_range.sensitivity(), gyro_range.sensitivity());
+    for sample in samples {
+        let (acc, acc_clipped) = conversion::units_to_counts(sample.acc, acc_sens, rounding);
+        let (gyro, gyro_clipped) = conversion::rad_s_to_counts(sample.gyro, gyro_sens, rounding);
+        let (temp, temp_clipped) = conversion::celsius_to_counts(sample.temp, rounding);
+        saturated |= acc_clipped || gyro_clipped || temp_clipped;
+        for count in [acc[0], acc[1], acc[2], gyro[0], gyro[1], gyro[2], temp] {
+            let (reduced, clipped) = conversion::narrow(count, bits as u32, rounding);
+            saturated |= clipped;
+            writer.write(reduced as u32 & ((1 << bits as u32) - 1), bits as u32);
+        }
+    }
+
+    buf[0] = PACKED_VERSION;
+    buf[1] = bits as u8;
+    buf[2] = (accel_range as u8) << 2 | gyro_range as u8;
+    buf[3] = if saturated { FLAG_SATURATED } else { 0 } | if tagged { FLAG_PROVENANCE } else { 0 };
+    buf[4..6].copy_from_slice(&count.to_le_bytes());
+    let crc = crc16(&buf[..len - PACKED_CRC_LEN]);
+    buf[len - PACKED_CRC_LEN..].copy_from_slice(&crc.to_le_bytes());
+    Ok(len)
+}
+
+/// Decodes a batch of this or the previous version into `out`, values are scaled back with
+/// the recorded ranges
+pub fn decode_binary_packed(buf: &[u8], out: &mut [MpuSample]) -> Result<PackedBatch, DecodeError> {
+    if buf.len() < PACKED_HEADER_LEN {
+        return Err(DecodeError::Truncated(PACKED_HEADER_LEN));
+    }
+    let bits = match (buf[0], PackedBits::from_u8(buf[1])) {
+        (PACKED_VERSION | PACKED_VERSION_1, Some(bits)) => bits,
+        _ => return Err(DecodeError::BadHeader),
+    };
+    let tagged = buf[0] != PACKED_VERSION_1 && buf[3] & FLAG_PROVENANCE != 0;
+    let count = u16::from_le_bytes([buf[4], buf[5]]) as usize;
+    let len = match tagged {
+        true => packed_len_with_provenance(count, bits),
+        false => packed_len(count, bits),
+    };
+    if buf.len() < len {
+        return Err(DecodeError::Truncated(len));
+    }
+    let crc = u16::from_le_bytes([buf[len - 2], buf[len - 1]]);
+    if crc != crc16(&buf[..len - PACKED_CRC_LEN]) {
+        return Err(DecodeError::CrcMismatch);
+    }
+    if out.len() < count {
+        return Err(DecodeError::OutputTooSmall(count));
+    }
+
+    let batch = PackedBatch {
+        count,
+        bits,
+        accel_range: AccelRange::from(buf[2] >> 2 & 0x03),
+        gyro_range: GyroRange::from(buf[2] & 0x03),
+        saturated: buf[3] & FLAG_SATURATED != 0,
+        provenance: tagged,
+    };
+    let values_end = packed_len(count, bits) - PACKED_CRC_LEN;
+    let mut tags = buf[values_end..len - PACKED_CRC_LEN].chunks_exact(PROVENANCE_LEN);
+    let (acc_sens, gyro_sens) = (
+        batch.accel_range.sensitivity(),
+        batch.gyro_range.sensitivity(),
+    );
+    let mut reader = BitReader::new(&buf[PACKED_HEADER_LEN..values_end]);
+    for sample in out.iter_mut().take(count) {
+        let mut counts = [0; VALUES_PER_SAMPLE];
+        for count in counts.iter_mut() {
+            *count = expand(reader.read(bits as u32), bits);
+        }
+        let [ax, ay, az, gx, gy, gz, temp] = counts;
+        *sample = MpuSample::new(
+            conversion::counts_to_units([ax, ay, az], acc_sens),
+            conversion::counts_to_rad_s([gx, gy, gz], gyro_sens),
+            conversion::counts_to_celsius(temp),
+        );
+        if let Some(tags) = tags.next() {
+            let provenance = [tags[0], tags[1], tags[2]];
+            let provenance =
+                SampleProvenance::from_bytes(provenance).ok_or(DecodeError::BadProvenance)?;
+            *sample = sample.with_provenance(provenance);
+        }
+    }
+    Ok(batch)
+}
+
+/// reduced value back to counts
+fn expand(value: u32, bits: PackedBits) -> i16 {
+    let width = bits as u32;
+    // sign extend
+    let signed = ((value << (32 - width)) as i32) >> (32 - width);
+    conversion::widen(signed as i16, width)
+}
+
+struct BitWriter<'a> {
+    buf: &'a mut [u8],
+    bit: usize,
+}
+
+impl<'a> BitWriter<'a> {
+    fn new(buf: &'a mut [u8]) -> Self {
+        Self { buf, bit: 0 }
+    }
+
+    fn write(&mut self, value: u32, width: u32) {
+        for i in (0..width).rev() {
+            if value >> i & 1 != 0 {
+                self.buf[self.bit / 8] |= 0x80 >> (self.bit % 8);
+            }
+            self.bit += 1;
+        }
+    }
+}
+
+struct BitReader<'a> {
+    buf: &'a [u8],
+    bit: usize,
+}
+
+impl<'a> BitReader<'a> {
+    fn new(buf: &'a [u8]) -> Self {
+        Self { buf, bit: 0 }
+    }
+
+    fn read(&mut self, width: u32) -> u32 {
+        let mut value = 0;
+        for _ in 0..width {
+            let set = self.buf[self.bit / 8] & (0x80 >> (self.bit % 8)) != 0;
+            value = value << 1 | set as u32;
+            self.bit += 1;
+        }
+        value
+    }
+}
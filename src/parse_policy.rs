@@ -0,0 +1,278 @@
+//! Structural checks of burst frames at parse time, and what to do with a frame failing them.
+//!
+//! A glitch on the bus can corrupt the bytes of a burst without failing the transaction: a
+//! slave holding SDA low reads as zeros, a lost clock edge as one byte repeated. The
+//! frame parses, the values are nonsense. [`check_frame`] looks for the patterns no
+//! sensor output can produce, in this order:
+//!
+//! | [`FrameCheck`] | fires on |
+//! |:---|:---|
+//! | [`AllZero`](FrameCheck::AllZero) | all 14 bytes zero: 0 g on every axis in free fall at exactly 36.53 °C with no rotation |
+//! | [`IdenticalBytes`](FrameCheck::IdenticalBytes) | all 14 bytes the same non-zero value, e.g. 0xFF of a floating bus |
+//! | [`TemperatureOutOfRange`](FrameCheck::TemperatureOutOfRange) | the temperature outside [`SILICON_TEMP_LIMITS_C`], the storage range of the absolute maximum ratings |
+//!
+//! The temperature is the one of the driver's [`TempModel`](crate::scale::TempModel), a
+//! calibration included. The checks cost one pass over the 14 bytes, one temperature
+//! conversion and two comparisons: no table, no bus access, a few tens of cycles next to
+//! the 14 byte transfer.
+//!
+//! They are structural: whether the bytes can be a frame at all, from one frame alone.
+//! Whether the values make physical sense over time, a stuck axis, a jump no motion
+//! explains, is the [`plausibility`](crate::plausibility) scorer's part, which runs later
+//! on the scaled sample and never rejects one.
+//!
+//! #### Policies
+//! [`Mpu6050::set_parse_policy`] selects what the burst reads
+//! ([`read_batch`](Mpu6050::read_batch), [`read_into_views`](Mpu6050::read_into_views) and
+//! the other reads of the whole ACCEL_XOUT_H..GYRO_ZOUT_L window in one transaction) do:
+//!
+//! | [`ParsePolicy`] | checks | a failing frame |
+//! |:---|:---|:---|
+//! | [`Lenient`](ParsePolicy::Lenient), the default | none | parsed as any other, bit for bit the behaviour without this module |
+//! | [`Strict`](ParsePolicy::Strict) | all | [`Mpu6050Error::ImplausibleFrame`] with the check, the frame is dropped |
+//! | [`Diagnose`](ParsePolicy::Diagnose) | all | parsed as any other and recorded in the [`ParseDiagnostics`] |
+//!
+//! The error's [`recovery_hint`](Mpu6050Error::recovery_hint) is
+//! [`RetryImmediately`](crate::hint::RecoveryHint::RetryImmediately): the next burst is
+//! most likely fine. A stream failing over and over is a wiring problem.
+//!
+//! #### Diagnostics
+//! [`parse_diagnostics`](Mpu6050::parse_diagnostics) counts the frames checked and failed
+//! under both checking policies and keeps the last [`PARSE_DIAGNOSTICS_LEN`] failures of
+//! [`Diagnose`](ParsePolicy::Diagnose), the raw bytes, the check fired and the index of
+//! the frame among the checked ones, for offline analysis of how often and in which
+//! pattern corruption occurs. Changing the policy keeps them,
+//! [`clear_parse_diagnostics`](Mpu6050::clear_parse_diagnostics) starts over.
+//!
+//! #### Limits
+//! The separate reads of [`run_sampling_loop`](Mpu6050::run_sampling_loop), `get_acc` and
+//! friends are not one frame and are not checked, nor are FIFO frames, whose layout depends
+//! on the sources. With `minimal-pipeline` the checks are compiled out, see
+//! [`stages`](crate::stages).
+//!
+//! ```
+//! use mpu6050::frame::{encode_frame, RawFrame};
+//! use mpu6050::parse_policy::*;
+//! use mpu6050::scale::TempModel;
+//!
+//! let model = TempModel::NATIVE;
+//! assert_eq!(check_frame(&[0; 14], &model), Some(FrameCheck::AllZero));
+//! assert_eq!(
+//!     check_frame(&[0xff; 14], &model),
+//!     Some(FrameCheck::IdenticalBytes { byte: 0xff })
+//! );
+//!
+//! let at_rest = RawFrame { acc: [0, 0, 16384], temp: -2000, gyro: [3, -2, 1] };
+//! assert_eq!(check_frame(&encode_frame(&at_rest), &model), None);
+//! // -32768 counts: -59.8 °C
+//! let frozen = RawFrame { temp: i16::MIN, ..at_rest };
+//! assert!(matches!(
+//!     check_frame(&encode_frame(&frozen), &model),
+//!     Some(FrameCheck::TemperatureOutOfRange { raw: i16::MIN })
+//! ));
+//! ```
+
+use core::fmt;
+
+use crate::frame::{word, FRAME_LEN};
+use crate::scale::TempModel;
+use crate::Mpu6050;
+#[cfg(not(feature = "minimal-pipeline"))]
+use crate::Mpu6050Error;
+
+/// Temperatures in °C a frame may report, the storage range of the datasheet's absolute
+/// maximum ratings
+pub const SILICON_TEMP_LIMITS_C: (f32, f32) = (-40., 125.);
+
+/// Failures kept by [`ParseDiagnostics`]
+pub const PARSE_DIAGNOSTICS_LEN: usize = 8;
+
+/// What the burst reads do with a frame failing the checks, see the module docs
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub enum ParsePolicy {
+    /// no checks
+    #[default]
+    Lenient,
+    /// a failing frame is an error
+    Strict,
+    /// a failing frame is accepted and recorded
+    Diagnose,
+}
+
+/// Structural check a frame failed, see the module docs
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum FrameCheck {
+    /// all bytes zero
+    AllZero,
+    /// all bytes the same non-zero value
+    IdenticalBytes {
+        /// the repeated byte
+        byte: u8,
+    },
+    /// temperature outside [`SILICON_TEMP_LIMITS_C`]
+    TemperatureOutOfRange {
+        /// TEMP_OUT counts of the frame
+        raw: i16,
+    },
+}
+
+impl fmt::Display for FrameCheck {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FrameCheck::AllZero => f.write_str("all bytes zero"),
+            FrameCheck::IdenticalBytes { byte } => write!(f, "all bytes {:#04x}", byte),
+            FrameCheck::TemperatureOutOfRange { raw } => {
+                write!(f, "temperature out of range, {} counts", raw)
+            }
+        }
+    }
+}
+
+/// First check of the module docs `bytes` fails, with `model` the conversion of its
+/// temperature
+pub fn check_frame(bytes: &[u8; FRAME_LEN], model: &TempModel) -> Option<FrameCheck> {
+    let first = bytes[0];
+    if bytes.iter().all(|byte| *byte == first) {
+        return Some(match first {
+            0 => FrameCheck::AllZero,
+            byte => FrameCheck::IdenticalBytes { byte },
+        });
+    }
+    let raw = word(&bytes[6..8]);
+    let celsius = model.celsius(raw);
+    let (min, max) = SILICON_TEMP_LIMITS_C;
+    // written so that NaN fails
+    if !(celsius >= min && celsius <= max) {
+        return Some(FrameCheck::TemperatureOutOfRange { raw });
+    }
+    None
+}
+
+/// One frame recorded under [`ParsePolicy::Diagnose`]
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct ParseDiagnostic {
+    /// the frame as read, from ACCEL_XOUT_H
+    pub bytes: [u8; FRAME_LEN],
+    /// the check it failed
+    pub check: FrameCheck,
+    /// index of the frame among the checked ones, from 0
+    pub frame: u32,
+}
+
+/// Counters of the checked frames and the last failures, see the module docs
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub struct ParseDiagnostics {
+    entries: [Option<ParseDiagnostic>; PARSE_DIAGNOSTICS_LEN],
+    next: usize,
+    checked: u32,
+    failed: u32,
+    recorded: u32,
+}
+
+impl ParseDiagnostics {
+    /// frames checked, wrapping
+    pub fn checked(&self) -> u32 {
+        self.checked
+    }
+
+    /// frames failing a check, wrapping
+    pub fn failed(&self) -> u32 {
+        self.failed
+    }
+
+    /// failures recorded, including the ones overwritten since, wrapping
+    pub fn recorded(&self) -> u32 {
+        self.recorded
+    }
+
+    /// number of failures kept
+    pub fn len(&self) -> usize {
+        self.entries.iter().flatten().count()
+    }
+
+    /// no failure kept
+    pub fn is_empty(&self) -> bool {
+        self.entries[0].is_none()
+    }
+
+    /// the failures kept, oldest first
+    pub fn iter(&self) -> impl Iterator<Item = &ParseDiagnostic> {
+        let (newer, older) = self.entries.split_at(self.next);
+        older.iter().chain(newer).flatten()
+    }
+
+    /// the latest failure kept
+    pub fn latest(&self) -> Option<&ParseDiagnostic> {
+        let last = (self.next + PARSE_DIAGNOSTICS_LEN - 1) % PARSE_DIAGNOSTICS_LEN;
+        self.entries[last].as_ref()
+    }
+
+    /// counts a checked frame, its check if it failed one
+    #[cfg(not(feature = "minimal-pipeline"))]
+    fn count(&mut self, check: Option<FrameCheck>) -> u32 {
+        let frame = self.checked;
+        self.checked = self.checked.wrapping_add(1);
+        if check.is_some() {
+            self.failed = self.failed.wrapping_add(1);
+        }
+        frame
+    }
+
+    #[cfg(not(feature = "minimal-pipeline"))]
+    fn record(&mut self, diagnostic: ParseDiagnostic) {
+        self.entries[self.next] = Some(diagnostic);
+        self.next = (self.next + 1) % PARSE_DIAGNOSTICS_LEN;
+        self.recorded = self.recorded.wrapping_add(1);
+    }
+}
+
+impl<I, D> Mpu6050<I, D> {
+    /// Selects what the burst reads do with frames failing the structural checks, see the
+    /// module docs. Keeps the diagnostics
+    pub fn set_parse_policy(&mut self, policy: ParsePolicy) {
+        self.parse_policy = policy;
+    }
+
+    /// policy set with [`set_parse_policy`](Self::set_parse_policy),
+    /// [`Lenient`](ParsePolicy::Lenient) by default
+    pub fn parse_policy(&self) -> ParsePolicy {
+        self.parse_policy
+    }
+
+    /// counters and the last failures of the checking policies
+    pub fn parse_diagnostics(&self) -> &ParseDiagnostics {
+        &self.parse_diagnostics
+    }
+
+    /// forgets the counters and the failures kept
+    pub fn clear_parse_diagnostics(&mut self) {
+        self.parse_diagnostics = ParseDiagnostics::default();
+    }
+
+    /// `bytes` of a burst through the parse policy, an error for a failing frame while
+    /// strict
+    #[cfg(not(feature = "minimal-pipeline"))]
+    pub(crate) fn check_burst<E>(
+        &mut self,
+        bytes: &[u8; FRAME_LEN],
+        model: &TempModel,
+    ) -> Result<(), Mpu6050Error<E>> {
+        if self.parse_policy == ParsePolicy::Lenient {
+            return Ok(());
+        }
+        let check = check_frame(bytes, model);
+        let frame = self.parse_diagnostics.count(check);
+        match (check, self.parse_policy) {
+            (None, _) | (_, ParsePolicy::Lenient) => Ok(()),
+            (Some(reason), ParsePolicy::Strict) => Err(Mpu6050Error::ImplausibleFrame { reason }),
+            (Some(check), ParsePolicy::Diagnose) => {
+                self.parse_diagnostics.record(ParseDiagnostic {
+                    bytes: *bytes,
+                    check,
+                    frame,
+                });
+                Ok(())
+            }
+        }
+    }
+}
@@ -0,0 +1,181 @@
+//! Calibration against an external motion reference, for moving platforms.
+//!
+//! The calibrations of [`auto_setup`](crate::Mpu6050::auto_setup) assume the device is at
+//! rest: the gyro averages to its bias, the accelerometer to its offset plus 1g on Z. On a
+//! ship or a slowly moving vehicle neither holds, the averages contain the platform motion
+//! and the standard deviation gate rejects the run.
+//!
+//! [`Mpu6050::calibrate_gyro_with_reference`] and [`Mpu6050::calibrate_accel_with_reference`]
+//! take the motion from a reference instead: a closure called right after every sample
+//! returns what an ideal sensor would measure at that moment, in the sensor frame. The
+//! offset is then the mean difference between readings and reference, and the standard
+//! deviation gate applies to the differences. This is the same sampling and estimator as
+//! the standard path, which passes a constant reference: zero rate, 1g on Z.
+//!
+//! The result is only as good as the reference. Its errors, including a delay against the
+//! samples, end up in the offset.
+
+use glam::Vec3A;
+
+#[cfg(feature = "driver")]
+use crate::config_epoch::ConfigChange;
+use crate::setup::{Accumulator, AutoSetupFailure, AutoSetupOptions, AutoSetupPhase, Tracker};
+use crate::{Mpu6050, Mpu6050Error};
+#[cfg(feature = "driver")]
+use embedded_hal::{
+    blocking::delay::DelayMs,
+    blocking::i2c::{Write, WriteRead},
+};
+
+/// Options of a referenced calibration
+#[derive(Copy, Clone, Debug)]
+pub struct ReferencedCalibration {
+    /// number of samples averaged
+    pub samples: u16,
+    /// maximum per-axis standard deviation of the differences to the reference, in rad/s or g
+    pub max_std_dev: f32,
+    /// time between samples in ms
+    pub sample_interval_ms: u8,
+}
+
+impl ReferencedCalibration {
+    /// the gyro calibration settings of `options`
+    pub const fn gyro(options: &AutoSetupOptions) -> Self {
+        Self {
+            samples: options.gyro_samples,
+            max_std_dev: options.max_gyro_std_dev,
+            sample_interval_ms: options.sample_interval_ms,
+        }
+    }
+
+    /// the accel calibration settings of `options`
+    pub const fn accel(options: &AutoSetupOptions) -> Self {
+        Self {
+            samples: options.acc_samples,
+            max_std_dev: options.max_acc_std_dev,
+            sample_interval_ms: options.sample_interval_ms,
+        }
+    }
+}
+
+/// Outcome of a referenced calibration
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct CalibrationResult {
+    /// offset now in effect
+    pub offset: Vec3A,
+    /// largest per-axis standard deviation of the differences to the reference
+    pub std_dev: f32,
+    /// samples averaged
+    pub samples: u32,
+}
+
+#[cfg(feature = "driver")]
+impl<I, D, E> Mpu6050<I, D>
+where
+    I: Write<Error = E> + WriteRead<Error = E>,
+{
+    pub(crate) fn calibrate_gyro_with_reference_using(
+        &mut self,
+        delay: &mut impl DelayMs<u8>,
+        mut reference: impl FnMut() -> Vec3A,
+        options: ReferencedCalibration,
+        deadline: &mut dyn FnMut() -> bool,
+    ) -> Result<CalibrationResult, AutoSetupFailure<E>> {
+        let phase = AutoSetupPhase::GyroCalibration;
+        let mut t = Tracker::standalone(phase, deadline, self.io_stats.transactions);
+        self.calibrate_gyro_tracked(delay, &mut t, None, &mut reference, options)
+    }
+
+    pub(crate) fn calibrate_accel_with_reference_using(
+        &mut self,
+        delay: &mut impl DelayMs<u8>,
+        mut reference: impl FnMut() -> Vec3A,
+        options: ReferencedCalibration,
+        deadline: &mut dyn FnMut() -> bool,
+    ) -> Result<CalibrationResult, AutoSetupFailure<E>> {
+        let phase = AutoSetupPhase::AccelCalibration;
+        let mut t = Tracker::standalone(phase, deadline, self.io_stats.transactions);
+        self.calibrate_accel_tracked(delay, &mut t, None, &mut reference, options)
+    }
+
+    /// gyro calibration within the phase of `t`, continuing the samples of `resumed`
+    pub(crate) fn calibrate_gyro_tracked(
+        &mut self,
+        delay: &mut impl DelayMs<u8>,
+        t: &mut Tracker,
+        resumed: Option<Accumulator>,
+        reference: &mut dyn FnMut() -> Vec3A,
+        options: ReferencedCalibration,
+    ) -> Result<CalibrationResult, AutoSetupFailure<E>> {
+        // offsets apply before the per-axis factors
+        let per_axis = self.gyro_scale.per_axis;
+        let result = self.calibrate_with_reference(
+            delay,
+            t,
+            resumed,
+            Self::read_gyro_uncorrected,
+            &mut || reference() / per_axis,
+            options,
+        )?;
+        self.gyro_offset = result.offset;
+        self.bump_epoch(ConfigChange::Offsets);
+        Ok(result)
+    }
+
+    /// accel calibration within the phase of `t`, continuing the samples of `resumed`
+    pub(crate) fn calibrate_accel_tracked(
+        &mut self,
+        delay: &mut impl DelayMs<u8>,
+        t: &mut Tracker,
+        resumed: Option<Accumulator>,
+        reference: &mut dyn FnMut() -> Vec3A,
+        options: ReferencedCalibration,
+    ) -> Result<CalibrationResult, AutoSetupFailure<E>> {
+        let per_axis = self.acc_scale.per_axis;
+        let result = self.calibrate_with_reference(
+            delay,
+            t,
+            resumed,
+            Self::read_acc_uncorrected,
+            &mut || reference() / per_axis,
+            options,
+        )?;
+        self.acc_offset = result.offset;
+        self.bump_epoch(ConfigChange::Offsets);
+        Ok(result)
+    }
+
+    fn calibrate_with_reference(
+        &mut self,
+        delay: &mut impl DelayMs<u8>,
+        t: &mut Tracker,
+        resumed: Option<Accumulator>,
+        read: fn(&mut Self) -> Result<Vec3A, Mpu6050Error<E>>,
+        reference: &mut dyn FnMut() -> Vec3A,
+        options: ReferencedCalibration,
+    ) -> Result<CalibrationResult, AutoSetupFailure<E>> {
+        let acc = self
+            .sample_stats(
+                delay,
+                t,
+                resumed,
+                options.samples,
+                options.sample_interval_ms,
+                read,
+                reference,
+            )
+            .map_err(AutoSetupFailure::Device)?;
+        let std_dev = acc.max_std_dev();
+        if std_dev > options.max_std_dev {
+            return Err(AutoSetupFailure::CalibrationRejected {
+                std_dev,
+                limit: options.max_std_dev,
+            });
+        }
+        Ok(CalibrationResult {
+            offset: acc.mean(),
+            std_dev,
+            samples: acc.count(),
+        })
+    }
+}
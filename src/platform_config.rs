@@ -0,0 +1,681 @@
+//! Driver setup from a description of the board.
+//!
+//! A [`PlatformDescription`] is what a Linux deployment knows about its sensor, kept in a
+//! TOML or JSON file next to the application instead of in code: the i2c-dev bus, the address
+//! or that it is probed, the init profile, a preset of [`presets`], the mounting as an
+//! [`AxisMap`] and a calibration blob written at the factory or during commissioning.
+//!
+//! ```toml
+//! bus = "/dev/i2c-1"
+//! address = "probe"             # or 0x68, 0x69; 0x68 if left out
+//! init = "auto_setup"           # or "init", the default
+//! settings = "handheld_ui"      # a preset name, case insensitive; init's defaults if left out
+//! orientation = "-Y+X+Z"        # or a matrix [[0, -1, 0], [1, 0, 0], [0, 0, 1]]; identity if left out
+//! # calibration = "/var/lib/imu/offsets.bin"
+//! ```
+//! Unknown keys are ignored, a description can carry the fields of other tools or of a
+//! newer release. Bad values are refused with the line and column and what would have been
+//! accepted, see [`DescriptionError`].
+//!
+//! `Mpu6050::from_platform_description` (feature `linux`) runs the description over
+//! i2c-dev, [`Mpu6050::from_platform_description_with`] over any bus given by an opener.
+//! The stages, each failing with its own [`PlatformError`] variant and [`PlatformStage`]:
+//!
+//! 1. calibration: the blob is read and decoded first, a missing file fails before the bus
+//!    is touched
+//! 2. bus open: the opener is given the bus path
+//! 3. probe: WHO_AM_I is read at both [`DeviceAddr`]s, the configured address or the one
+//!    probed must answer with a known chip
+//! 4. build: the builder with the address and the calibration's offsets
+//! 5. init: [`init`](Mpu6050::init) or [`auto_setup`](Mpu6050::auto_setup) with its
+//!    default options
+//! 6. settings: the preset through [`apply_settings`](Mpu6050::apply_settings)
+//!
+//! The driver does not rotate its readings, the mounting is handed back in the
+//! [`PlatformReport`] with everything else that was applied.
+//!
+//! ```
+//! use mpu6050::axis_map::AxisMap;
+//! use mpu6050::platform_config::{AddressSpec, InitProfile, PlatformDescription};
+//!
+//! let desc = PlatformDescription::from_toml_str(
+//!     r#"
+//!     bus = "/dev/i2c-1"
+//!     address = "probe"
+//!     settings = "handheld_ui"
+//!     orientation = "-Y+X+Z"
+//!     "#,
+//! )
+//! .unwrap();
+//! assert_eq!(desc.address, AddressSpec::Probe);
+//! assert_eq!(desc.init, InitProfile::Init);
+//! assert_eq!(desc.settings.as_deref(), Some("HANDHELD_UI"));
+//! assert_eq!(desc.orientation, AxisMap::from_name("-Y+X+Z").unwrap());
+//!
+//! let err = PlatformDescription::from_toml_str("bus = \"/dev/i2c-1\"\norientation = \"+X+X+Z\"")
+//!     .unwrap_err();
+//! assert!(err.to_string().contains("chip axis X used twice"));
+//! ```
+//!
+//! #### Calibration blob
+//! [`OffsetCalibration::to_bytes`] and [`OffsetCalibration::from_bytes`], the offsets the
+//! builder takes:
+//!
+//! | bytes | content |
+//! |:---|:---|
+//! | 1 | format version ([`OFFSET_CALIBRATION_VERSION`]) |
+//! | 1 | reserved, 0 |
+//! | 3 × 4 | gyro offset in rad/s, X, Y, Z `f32`, little endian |
+//! | 3 × 4 | accelerometer offset in g, X, Y, Z `f32`, little endian |
+//! | 2 | CRC-16/CCITT-FALSE ([`crc16`]) over all previous bytes, little endian |
+//!
+//! An `auto_setup` profile calibrates itself, a description with both is refused.
+
+use core::fmt::{self, Debug, Display};
+use std::io;
+use std::path::{Path, PathBuf};
+
+use embedded_hal::blocking::delay::DelayMs;
+use embedded_hal::blocking::i2c::{Write, WriteRead};
+use serde::de::{self, Deserializer};
+use serde::Deserialize;
+
+use crate::axis_map::AxisMap;
+use crate::board::DeviceAddr;
+use crate::decimal::DecimalVec;
+use crate::device::ChipVariant;
+#[cfg(all(feature = "linux", target_os = "linux"))]
+use crate::linux::{I2cdev, Sleep};
+use crate::packed::{crc16, DecodeError};
+use crate::presets;
+use crate::setup::{AutoSetupError, AutoSetupOptions, AutoSetupReport};
+use crate::{Mpu6050, Mpu6050Builder, Mpu6050BuilderError, Mpu6050Error, Register, Vec3A};
+
+/// Format version written by [`OffsetCalibration::to_bytes`]
+pub const OFFSET_CALIBRATION_VERSION: u8 = 1;
+/// Length of a serialized calibration
+pub const OFFSET_CALIBRATION_LEN: usize = 2 + 6 * 4 + 2;
+
+/// The board as a description file gives it, see the [module docs](self)
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+pub struct PlatformDescription {
+    /// i2c-dev bus, `/dev/i2c-1`
+    pub bus: String,
+    /// sensor address or probing, 0x68 if not given
+    #[serde(default, deserialize_with = "address")]
+    pub address: AddressSpec,
+    /// how the chip is brought up
+    #[serde(default)]
+    pub init: InitProfile,
+    /// name of a preset in [`presets::ALL`], init's defaults if None
+    #[serde(default, deserialize_with = "preset")]
+    pub settings: Option<String>,
+    /// mounting of the chip, a rotation of [`AxisMap::ROTATIONS`]
+    #[serde(default, deserialize_with = "orientation")]
+    pub orientation: AxisMap,
+    /// file holding an [`OffsetCalibration`]
+    #[serde(default)]
+    pub calibration: Option<PathBuf>,
+}
+
+/// Address of the sensor
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum AddressSpec {
+    /// known strapping of AD0
+    Fixed(DeviceAddr),
+    /// the one address answering, `"probe"` in a description
+    Probe,
+}
+
+impl Default for AddressSpec {
+    fn default() -> Self {
+        AddressSpec::Fixed(DeviceAddr::Ad0Low)
+    }
+}
+
+/// Bring-up of the chip, `"init"` or `"auto_setup"` in a description
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum InitProfile {
+    /// [`Mpu6050::init`]
+    #[default]
+    Init,
+    /// [`Mpu6050::auto_setup`] with [`AutoSetupOptions::default`], calibrating the gyro
+    AutoSetup,
+}
+
+impl Display for InitProfile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            InitProfile::Init => "init",
+            InitProfile::AutoSetup => "auto_setup",
+        })
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(untagged, expecting = "an address, 0x68 or 0x69, or \"probe\"")]
+enum RawAddress {
+    Number(i64),
+    Text(String),
+}
+
+fn address<'de, D: Deserializer<'de>>(deserializer: D) -> Result<AddressSpec, D::Error> {
+    let found = match RawAddress::deserialize(deserializer)? {
+        RawAddress::Text(text) if text == "probe" => return Ok(AddressSpec::Probe),
+        RawAddress::Number(number) => DeviceAddr::ALL
+            .into_iter()
+            .find(|addr| i64::from(addr.addr()) == number)
+            .ok_or_else(|| format!("{:#x}", number)),
+        RawAddress::Text(text) => Err(format!("{:?}", text)),
+    };
+    found.map(AddressSpec::Fixed).map_err(|found| {
+        de::Error::custom(format!(
+            "address {} is not an MPU-6050 address, expected 0x68, 0x69 or \"probe\"",
+            found
+        ))
+    })
+}
+
+fn preset<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<String>, D::Error> {
+    let name = String::deserialize(deserializer)?;
+    match presets::ALL
+        .iter()
+        .find(|(known, _)| known.eq_ignore_ascii_case(&name))
+    {
+        Some((known, _)) => Ok(Some(known.to_string())),
+        None => {
+            let names: Vec<_> = presets::ALL.iter().map(|(known, _)| *known).collect();
+            Err(de::Error::custom(format!(
+                "unknown preset {:?}, expected one of {}",
+                name,
+                names.join(", ")
+            )))
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(
+    untagged,
+    expecting = "an orientation, a name like \"-Y+X+Z\" or a 3x3 matrix of 0, 1 and -1"
+)]
+enum RawOrientation {
+    Name(String),
+    Matrix([[i8; 3]; 3]),
+}
+
+fn orientation<'de, D: Deserializer<'de>>(deserializer: D) -> Result<AxisMap, D::Error> {
+    match RawOrientation::deserialize(deserializer)? {
+        RawOrientation::Name(name) => AxisMap::from_name(&name)
+            .map_err(|e| de::Error::custom(format!("orientation {:?}: {}", name, e))),
+        RawOrientation::Matrix(matrix) => AxisMap::from_matrix(matrix)
+            .map_err(|e| de::Error::custom(format!("orientation matrix: {}", e))),
+    }
+}
+
+impl PlatformDescription {
+    /// Reads a description from TOML
+    pub fn from_toml_str(text: &str) -> Result<Self, DescriptionError> {
+        let desc: Self = toml::from_str(text).map_err(|e| DescriptionError::Parse {
+            path: None,
+            message: e.to_string(),
+        })?;
+        desc.check()?;
+        Ok(desc)
+    }
+
+    /// Reads a description from JSON
+    pub fn from_json_str(text: &str) -> Result<Self, DescriptionError> {
+        let desc: Self = serde_json::from_str(text).map_err(|e| DescriptionError::Parse {
+            path: None,
+            message: e.to_string(),
+        })?;
+        desc.check()?;
+        Ok(desc)
+    }
+
+    /// Reads a description file, JSON if its extension is `json`, TOML otherwise
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, DescriptionError> {
+        let path = path.as_ref();
+        let text = std::fs::read_to_string(path).map_err(|source| DescriptionError::Read {
+            path: path.to_owned(),
+            source,
+        })?;
+        let parsed = if path.extension().is_some_and(|ext| ext == "json") {
+            Self::from_json_str(&text)
+        } else {
+            Self::from_toml_str(&text)
+        };
+        parsed.map_err(|e| match e {
+            DescriptionError::Parse { message, .. } => DescriptionError::Parse {
+                path: Some(path.to_owned()),
+                message,
+            },
+            other => other,
+        })
+    }
+
+    /// Refuses fields contradicting each other
+    pub fn check(&self) -> Result<(), DescriptionError> {
+        if self.init == InitProfile::AutoSetup && self.calibration.is_some() {
+            return Err(DescriptionError::Conflict(
+                "init = \"auto_setup\" calibrates itself, remove it or the calibration file",
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// A description that could not be read
+#[derive(Debug)]
+pub enum DescriptionError {
+    /// the file could not be read
+    Read {
+        /// description file
+        path: PathBuf,
+        /// what the read failed with
+        source: io::Error,
+    },
+    /// not a description: the parser's message with line, column and the expected values
+    Parse {
+        /// description file, None for a string
+        path: Option<PathBuf>,
+        /// what the parser found
+        message: String,
+    },
+    /// fields contradicting each other
+    Conflict(&'static str),
+}
+
+impl Display for DescriptionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DescriptionError::Read { path, source } => {
+                write!(f, "cannot read {}: {}", path.display(), source)
+            }
+            DescriptionError::Parse {
+                path: Some(path),
+                message,
+            } => write!(f, "{}: {}", path.display(), message),
+            DescriptionError::Parse {
+                path: None,
+                message,
+            } => f.write_str(message),
+            DescriptionError::Conflict(reason) => f.write_str(reason),
+        }
+    }
+}
+
+impl std::error::Error for DescriptionError {}
+
+/// Gyro and accelerometer offsets in a file, see the [module docs](self#calibration-blob)
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct OffsetCalibration {
+    /// gyro offset in rad/s, [`Mpu6050Builder::gyro_offset`]
+    pub gyro_offset: [f32; 3],
+    /// accelerometer offset in g, [`Mpu6050Builder::acc_offset`]
+    pub acc_offset: [f32; 3],
+}
+
+impl OffsetCalibration {
+    /// The offsets in effect on `mpu`, to store after a calibration
+    pub fn of<I, D, E>(mpu: &Mpu6050<I, D>) -> Self
+    where
+        I: Write<Error = E> + WriteRead<Error = E>,
+    {
+        Self {
+            gyro_offset: mpu.get_gyro_offset().to_array(),
+            acc_offset: mpu.get_acc_offset().to_array(),
+        }
+    }
+
+    /// Serialized form, see the [module docs](self#calibration-blob)
+    pub fn to_bytes(&self) -> [u8; OFFSET_CALIBRATION_LEN] {
+        let mut bytes = [0; OFFSET_CALIBRATION_LEN];
+        bytes[0] = OFFSET_CALIBRATION_VERSION;
+        let values = self.gyro_offset.iter().chain(&self.acc_offset);
+        for (chunk, value) in bytes[2..].chunks_exact_mut(4).zip(values) {
+            chunk.copy_from_slice(&value.to_le_bytes());
+        }
+        let crc = crc16(&bytes[..OFFSET_CALIBRATION_LEN - 2]);
+        bytes[OFFSET_CALIBRATION_LEN - 2..].copy_from_slice(&crc.to_le_bytes());
+        bytes
+    }
+
+    /// Reads a calibration written by [`to_bytes`](Self::to_bytes)
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, DecodeError> {
+        if bytes.len() < OFFSET_CALIBRATION_LEN {
+            return Err(DecodeError::Truncated(OFFSET_CALIBRATION_LEN));
+        }
+        let bytes = &bytes[..OFFSET_CALIBRATION_LEN];
+        if bytes[0] != OFFSET_CALIBRATION_VERSION {
+            return Err(DecodeError::BadHeader);
+        }
+        let crc = u16::from_le_bytes([
+            bytes[OFFSET_CALIBRATION_LEN - 2],
+            bytes[OFFSET_CALIBRATION_LEN - 1],
+        ]);
+        if crc != crc16(&bytes[..OFFSET_CALIBRATION_LEN - 2]) {
+            return Err(DecodeError::CrcMismatch);
+        }
+        let value = |idx: usize| {
+            let at = 2 + idx * 4;
+            f32::from_le_bytes([bytes[at], bytes[at + 1], bytes[at + 2], bytes[at + 3]])
+        };
+        Ok(Self {
+            gyro_offset: [value(0), value(1), value(2)],
+            acc_offset: [value(3), value(4), value(5)],
+        })
+    }
+}
+
+/// Stage of a platform setup, see the [module docs](self)
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum PlatformStage {
+    /// the description file: read, parsed or contradicting
+    File,
+    /// reading or decoding the calibration blob
+    Calibration,
+    /// opening the bus
+    BusOpen,
+    /// finding the chip at its address
+    Probe,
+    /// building the driver
+    Build,
+    /// init or auto setup
+    Init,
+    /// applying the preset
+    Settings,
+}
+
+/// A platform setup that failed, with the stage it failed in
+#[derive(Debug)]
+pub enum PlatformError<E> {
+    /// the description, see [`PlatformDescription::from_file`]
+    File(DescriptionError),
+    /// the calibration file could not be read
+    CalibrationRead {
+        /// calibration file
+        path: PathBuf,
+        /// what the read failed with
+        source: io::Error,
+    },
+    /// the calibration file is not an [`OffsetCalibration`]
+    CalibrationDecode {
+        /// calibration file
+        path: PathBuf,
+        /// why it does not decode
+        error: DecodeError,
+    },
+    /// the opener failed
+    BusOpen {
+        /// bus given to the opener
+        bus: String,
+        /// what the opener failed with
+        source: io::Error,
+    },
+    /// no known chip at the configured address, or not exactly one when probing
+    Probe {
+        /// address of the description
+        address: AddressSpec,
+        /// whether 0x68 and 0x69 answered with a known WHO_AM_I
+        answered: [bool; 2],
+    },
+    /// the builder refused the configuration
+    Build(Mpu6050BuilderError),
+    /// [`Mpu6050::init`] failed
+    Init(Mpu6050Error<E>),
+    /// [`Mpu6050::auto_setup`] failed
+    AutoSetup(AutoSetupError<E>),
+    /// [`Mpu6050::apply_settings`] failed
+    Settings(Mpu6050Error<E>),
+}
+
+impl<E> PlatformError<E> {
+    /// stage the setup failed in
+    pub fn stage(&self) -> PlatformStage {
+        match self {
+            PlatformError::File(_) => PlatformStage::File,
+            PlatformError::CalibrationRead { .. } | PlatformError::CalibrationDecode { .. } => {
+                PlatformStage::Calibration
+            }
+            PlatformError::BusOpen { .. } => PlatformStage::BusOpen,
+            PlatformError::Probe { .. } => PlatformStage::Probe,
+            PlatformError::Build(_) => PlatformStage::Build,
+            PlatformError::Init(_) | PlatformError::AutoSetup(_) => PlatformStage::Init,
+            PlatformError::Settings(_) => PlatformStage::Settings,
+        }
+    }
+}
+
+impl<E> From<DescriptionError> for PlatformError<E> {
+    fn from(error: DescriptionError) -> Self {
+        PlatformError::File(error)
+    }
+}
+
+impl<E: Display> Display for PlatformError<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PlatformError::File(error) => write!(f, "description: {}", error),
+            PlatformError::CalibrationRead { path, source } => {
+                write!(f, "calibration: cannot read {}: {}", path.display(), source)
+            }
+            PlatformError::CalibrationDecode { path, error } => {
+                write!(f, "calibration: {}: {}", path.display(), error)
+            }
+            PlatformError::BusOpen { bus, source } => {
+                write!(f, "bus open: cannot open {}: {}", bus, source)
+            }
+            PlatformError::Probe { address, answered } => {
+                f.write_str("probe: ")?;
+                match (address, answered) {
+                    (AddressSpec::Probe, [true, true]) => {
+                        f.write_str("chips answer at 0x68 and 0x69, give the address")
+                    }
+                    (AddressSpec::Probe, _) => f.write_str("no known chip at 0x68 or 0x69"),
+                    (AddressSpec::Fixed(addr), _) => {
+                        write!(f, "no known chip at {:#04x}", addr.addr())?;
+                        let others = DeviceAddr::ALL.iter().zip(answered);
+                        for (other, _) in others.filter(|(_, answered)| **answered) {
+                            write!(f, ", one answers at {:#04x}", other.addr())?;
+                        }
+                        Ok(())
+                    }
+                }
+            }
+            PlatformError::Build(error) => write!(f, "build: {}", error),
+            PlatformError::Init(error) => write!(f, "init: {}", error),
+            PlatformError::AutoSetup(error) => write!(f, "init: {}", error),
+            PlatformError::Settings(error) => write!(f, "settings: {}", error),
+        }
+    }
+}
+
+impl<E: Debug + Display> std::error::Error for PlatformError<E> {}
+
+/// What a platform setup applied
+#[derive(Clone, Debug)]
+pub struct PlatformReport {
+    /// bus given to the opener
+    pub bus: String,
+    /// address of the chip
+    pub address: u8,
+    /// the address was probed, not configured
+    pub probed: bool,
+    /// the chip's WHO_AM_I at the probe
+    pub chip: ChipVariant,
+    /// profile the chip was brought up with
+    pub init: InitProfile,
+    /// report of the auto setup, if the profile ran it
+    pub auto_setup: Option<AutoSetupReport>,
+    /// preset applied after the init, None for the defaults
+    pub settings: Option<String>,
+    /// mounting for the readings, not applied by the driver
+    pub orientation: AxisMap,
+    /// calibration file the offsets came from
+    pub calibration: Option<PathBuf>,
+    /// gyro offset in effect, rad/s
+    pub gyro_offset: Vec3A,
+    /// accelerometer offset in effect, g
+    pub acc_offset: Vec3A,
+}
+
+impl Display for PlatformReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let how = if self.probed { "probed" } else { "configured" };
+        writeln!(f, "bus: {}", self.bus)?;
+        writeln!(f, "address: {:#04x} ({})", self.address, how)?;
+        writeln!(f, "chip: {:?}", self.chip)?;
+        writeln!(f, "init: {}", self.init)?;
+        writeln!(
+            f,
+            "settings: {}",
+            self.settings.as_deref().unwrap_or("defaults")
+        )?;
+        writeln!(f, "orientation: {}", self.orientation)?;
+        match &self.calibration {
+            Some(path) => writeln!(f, "calibration: {}", path.display())?,
+            None => writeln!(f, "calibration: none")?,
+        }
+        writeln!(f, "gyro offset: {} rad/s", DecimalVec(self.gyro_offset))?;
+        write!(f, "acc offset: {} g", DecimalVec(self.acc_offset))
+    }
+}
+
+/// Whether a known chip answers WHO_AM_I at `addr`, a failed transfer is no answer
+fn answers<I: WriteRead>(i2c: &mut I, addr: DeviceAddr) -> Option<ChipVariant> {
+    let mut who_am_i = [0];
+    i2c.write_read(addr.addr(), &[Register::WHO_AM_I.addr()], &mut who_am_i)
+        .ok()?;
+    match ChipVariant::from_who_am_i(who_am_i[0]) {
+        ChipVariant::Unknown(_) => None,
+        variant => Some(variant),
+    }
+}
+
+impl<I, E> Mpu6050<I>
+where
+    I: Write<Error = E> + WriteRead<Error = E>,
+{
+    /// Sets the driver up as `desc` describes, over the bus `open` returns for its path, see
+    /// the [module docs](crate::platform_config) for the stages. The device is ready, with
+    /// what was applied in the report
+    #[allow(clippy::result_large_err)]
+    pub fn from_platform_description_with<D: DelayMs<u8>>(
+        desc: &PlatformDescription,
+        open: impl FnOnce(&str) -> io::Result<I>,
+        delay: &mut D,
+    ) -> Result<(Self, PlatformReport), PlatformError<E>> {
+        desc.check()?;
+        let calibration = match &desc.calibration {
+            Some(path) => {
+                let bytes =
+                    std::fs::read(path).map_err(|source| PlatformError::CalibrationRead {
+                        path: path.clone(),
+                        source,
+                    })?;
+                let calibration = OffsetCalibration::from_bytes(&bytes).map_err(|error| {
+                    PlatformError::CalibrationDecode {
+                        path: path.clone(),
+                        error,
+                    }
+                })?;
+                Some(calibration)
+            }
+            None => None,
+        };
+
+        let mut i2c = open(&desc.bus).map_err(|source| PlatformError::BusOpen {
+            bus: desc.bus.clone(),
+            source,
+        })?;
+
+        let found = DeviceAddr::ALL.map(|addr| answers(&mut i2c, addr));
+        let answered = found.map(|chip| chip.is_some());
+        let picked = match desc.address {
+            AddressSpec::Fixed(addr) => DeviceAddr::ALL.iter().position(|a| *a == addr),
+            AddressSpec::Probe => match answered {
+                [true, false] => Some(0),
+                [false, true] => Some(1),
+                _ => None,
+            },
+        };
+        let (addr, chip) = match picked.and_then(|idx| Some((DeviceAddr::ALL[idx], found[idx]?))) {
+            Some(picked) => picked,
+            None => {
+                return Err(PlatformError::Probe {
+                    address: desc.address,
+                    answered,
+                })
+            }
+        };
+
+        let mut builder = Mpu6050Builder::new().i2c(i2c).slave_addr(addr.addr());
+        if let Some(calibration) = calibration {
+            builder = builder
+                .gyro_offset(calibration.gyro_offset)
+                .acc_offset(calibration.acc_offset);
+        }
+        let mut mpu = builder.build().map_err(PlatformError::Build)?;
+
+        let auto_setup = match desc.init {
+            InitProfile::Init => {
+                mpu.init(delay).map_err(PlatformError::Init)?;
+                None
+            }
+            InitProfile::AutoSetup => Some(
+                mpu.auto_setup(delay, AutoSetupOptions::default(), None)
+                    .map_err(PlatformError::AutoSetup)?,
+            ),
+        };
+        if let Some(name) = &desc.settings {
+            let (_, settings) = presets::ALL
+                .iter()
+                .find(|(known, _)| *known == name)
+                .expect("preset names are checked when parsing");
+            mpu.apply_settings(settings)
+                .map_err(PlatformError::Settings)?;
+        }
+
+        let report = PlatformReport {
+            bus: desc.bus.clone(),
+            address: addr.addr(),
+            probed: desc.address == AddressSpec::Probe,
+            chip,
+            init: desc.init,
+            auto_setup,
+            settings: desc.settings.clone(),
+            orientation: desc.orientation,
+            calibration: desc.calibration.clone(),
+            gyro_offset: mpu.get_gyro_offset(),
+            acc_offset: mpu.get_acc_offset(),
+        };
+        Ok((mpu, report))
+    }
+}
+
+#[cfg(all(feature = "linux", target_os = "linux"))]
+impl Mpu6050<I2cdev> {
+    /// [`from_platform_description_with`](Self::from_platform_description_with) over
+    /// i2c-dev, sleeping for the delays
+    ///
+    /// ```no_run
+    /// use mpu6050::platform_config::{PlatformDescription, PlatformError};
+    /// use mpu6050::Mpu6050;
+    ///
+    /// # fn main() -> Result<(), PlatformError<std::io::Error>> {
+    /// let desc = PlatformDescription::from_file("/etc/imu.toml")?;
+    /// let (mut mpu, report) = Mpu6050::from_platform_description(&desc)?;
+    /// println!("{}", report);
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[allow(clippy::result_large_err)]
+    pub fn from_platform_description(
+        desc: &PlatformDescription,
+    ) -> Result<(Self, PlatformReport), PlatformError<io::Error>> {
+        Self::from_platform_description_with(desc, |bus| I2cdev::open(bus), &mut Sleep)
+    }
+}
@@ -0,0 +1,327 @@
+//! Cheap per-sample plausibility scoring.
+//!
+//! [`Mpu6050::set_plausibility_config`] opts every delivered sample into a handful of checks,
+//! run where samples leave the driver (see [`hook`](crate::hook)), before the hook. The
+//! result is the sample's [`plausibility`](crate::MpuSample::plausibility), a
+//! [`PlausibilityScore`]: the bitmask of the failed [`PlausibilityCheck`]s and a class.
+//!
+//! | check | fails when | bit |
+//! |:---|:---|:---|
+//! | [`AccMagnitude`](PlausibilityCheck::AccMagnitude) | accel magnitude outside `acc_magnitude_g` | `0x01` |
+//! | [`GyroRate`](PlausibilityCheck::GyroRate) | gyro rate magnitude above `max_gyro_rad_s` | `0x02` |
+//! | [`TempRange`](PlausibilityCheck::TempRange) | temperature outside `temp_c` | `0x04` |
+//! | [`TempRate`](PlausibilityCheck::TempRate) | temperature moved more than `max_temp_step_c` since the previous sample | `0x08` |
+//! | [`Stale`](PlausibilityCheck::Stale) | accel and gyro equal to those of the previous sample | `0x10` |
+//! | [`Clipped`](PlausibilityCheck::Clipped) | a raw accel or gyro axis at the end of its range | `0x20` |
+//!
+//! A sample failing nothing is [`Ok`](Plausibility::Ok), one failing a single check
+//! [`Suspect`](Plausibility::Suspect), one failing two or more, or any check in the
+//! `implausible` mask of the config, [`Implausible`](Plausibility::Implausible). Groups
+//! tagged [`Invalid`](crate::provenance::Provenance::Invalid), the unread parts of a single
+//! sensor read, are not checked. Clipping is seen on the direct reads only, FIFO frames are
+//! not checked for it.
+//!
+//! #### Overhead
+//! The bounds are squared once by [`PlausibilityScorer::new`], a sample costs at most
+//! 6 multiplications, 5 additions, 6 comparisons and 2 vector equalities, no division and no
+//! square root, and no bus access. Without a config the driver skips the scorer entirely,
+//! samples carry [`PlausibilityScore::OK`].
+//!
+//! A [`ComplementaryFilter`](crate::orientation::ComplementaryFilter) skips or de-weights
+//! scored samples with
+//! [`with_plausibility_policy`](crate::orientation::ComplementaryFilter::with_plausibility_policy).
+//!
+//! ```
+//! use mpu6050::plausibility::*;
+//! use mpu6050::{MpuSample, Vec3A};
+//!
+//! let mut scorer = PlausibilityScorer::new(PlausibilityConfig {
+//!     acc_magnitude_g: Some((0.8, 1.2)),
+//!     temp_c: Some((-40., 85.)),
+//!     stale: true,
+//!     ..PlausibilityConfig::NONE
+//! });
+//! let at_rest = MpuSample::new(Vec3A::Z, Vec3A::splat(0.01), 25.);
+//! assert_eq!(scorer.score(&at_rest), PlausibilityScore::OK);
+//!
+//! let dropped = at_rest.with_acc(Vec3A::ZERO).with_gyro(Vec3A::ZERO);
+//! let score = scorer.score(&dropped);
+//! assert_eq!(score.bits(), PlausibilityCheck::AccMagnitude.bit());
+//! assert_eq!(score.class(), Plausibility::Suspect);
+//!
+//! // the same values again: stale, implausible on its own
+//! let score = scorer.score(&dropped);
+//! assert!(score.failed(PlausibilityCheck::Stale));
+//! assert_eq!(score.class(), Plausibility::Implausible);
+//! ```
+
+use glam::Vec3A;
+
+use crate::provenance::{FieldGroup, Provenance};
+use crate::{Mpu6050, MpuSample};
+
+/// One check of the scorer, see the [module docs](self)
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum PlausibilityCheck {
+    /// accel magnitude outside the configured bounds
+    AccMagnitude,
+    /// gyro rate magnitude above the configured limit
+    GyroRate,
+    /// temperature outside the configured band
+    TempRange,
+    /// temperature step from the previous sample above the configured limit
+    TempRate,
+    /// accel and gyro equal to the previous sample
+    Stale,
+    /// raw accel or gyro axis at the end of its range
+    Clipped,
+}
+
+impl PlausibilityCheck {
+    /// all checks, in bit order
+    pub const ALL: [Self; 6] = [
+        Self::AccMagnitude,
+        Self::GyroRate,
+        Self::TempRange,
+        Self::TempRate,
+        Self::Stale,
+        Self::Clipped,
+    ];
+
+    /// bit of the check in [`PlausibilityScore::bits`]
+    pub const fn bit(self) -> u8 {
+        1 << self as u8
+    }
+}
+
+/// Checks making a sample [`Implausible`](Plausibility::Implausible) on their own unless
+/// configured otherwise: a stale sample repeats old data, a clipped one has an axis at the rail
+pub const DEFAULT_IMPLAUSIBLE: u8 =
+    PlausibilityCheck::Stale.bit() | PlausibilityCheck::Clipped.bit();
+
+/// Overall class of a sample
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, PartialOrd, Ord)]
+pub enum Plausibility {
+    /// no check failed, or none configured
+    #[default]
+    Ok,
+    /// a single check failed
+    Suspect,
+    /// several checks failed, or one of the implausible mask
+    Implausible,
+}
+
+/// Failed checks of a sample and their class
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub struct PlausibilityScore {
+    failed: u8,
+    class: Plausibility,
+}
+
+impl PlausibilityScore {
+    /// nothing failed
+    pub const OK: Self = Self {
+        failed: 0,
+        class: Plausibility::Ok,
+    };
+
+    /// score of the `failed` check bits, classified with the `implausible` mask
+    pub const fn new(failed: u8, implausible: u8) -> Self {
+        let class = if failed == 0 {
+            Plausibility::Ok
+        } else if failed & implausible != 0 || failed.count_ones() > 1 {
+            Plausibility::Implausible
+        } else {
+            Plausibility::Suspect
+        };
+        Self { failed, class }
+    }
+
+    /// bitmask of the failed checks, see [`PlausibilityCheck::bit`]
+    pub const fn bits(&self) -> u8 {
+        self.failed
+    }
+
+    /// overall class
+    pub const fn class(&self) -> Plausibility {
+        self.class
+    }
+
+    /// whether `check` failed
+    pub const fn failed(&self, check: PlausibilityCheck) -> bool {
+        self.failed & check.bit() != 0
+    }
+
+    /// failed checks, in bit order
+    pub fn failed_checks(&self) -> impl Iterator<Item = PlausibilityCheck> + '_ {
+        PlausibilityCheck::ALL
+            .into_iter()
+            .filter(|check| self.failed(*check))
+    }
+}
+
+/// Checks to run, each off when None or false
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct PlausibilityConfig {
+    /// smallest and largest plausible accel magnitude in g
+    pub acc_magnitude_g: Option<(f32, f32)>,
+    /// largest plausible gyro rate magnitude in rad/s
+    pub max_gyro_rad_s: Option<f32>,
+    /// coldest and hottest plausible temperature in degrees celsius
+    pub temp_c: Option<(f32, f32)>,
+    /// largest plausible temperature change between two samples in degrees celsius
+    pub max_temp_step_c: Option<f32>,
+    /// check for accel and gyro equal to the previous sample
+    pub stale: bool,
+    /// check for raw axes at the end of their range
+    pub clipped: bool,
+    /// checks making a sample implausible on their own, bits of [`PlausibilityCheck::bit`]
+    pub implausible: u8,
+}
+
+impl PlausibilityConfig {
+    /// no check, the [`DEFAULT_IMPLAUSIBLE`] mask
+    pub const NONE: Self = Self {
+        acc_magnitude_g: None,
+        max_gyro_rad_s: None,
+        temp_c: None,
+        max_temp_step_c: None,
+        stale: false,
+        clipped: false,
+        implausible: DEFAULT_IMPLAUSIBLE,
+    };
+}
+
+impl Default for PlausibilityConfig {
+    fn default() -> Self {
+        Self::NONE
+    }
+}
+
+/// Scores a stream of samples against a [`PlausibilityConfig`], no bus access
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct PlausibilityScorer {
+    config: PlausibilityConfig,
+    /// squared accel magnitude bounds
+    acc_squared: Option<(f32, f32)>,
+    /// squared gyro rate limit
+    gyro_squared: Option<f32>,
+    previous_acc: Option<Vec3A>,
+    previous_gyro: Option<Vec3A>,
+    previous_temp: Option<f32>,
+    clipped: bool,
+}
+
+impl PlausibilityScorer {
+    /// scorer without a previous sample
+    pub fn new(config: PlausibilityConfig) -> Self {
+        let square = |x: f32| x * x;
+        Self {
+            config,
+            acc_squared: config
+                .acc_magnitude_g
+                .map(|(min, max)| (square(min), square(max))),
+            gyro_squared: config.max_gyro_rad_s.map(square),
+            previous_acc: None,
+            previous_gyro: None,
+            previous_temp: None,
+            clipped: false,
+        }
+    }
+
+    /// configuration
+    pub fn config(&self) -> PlausibilityConfig {
+        self.config
+    }
+
+    /// forgets the previous sample and a pending clip
+    pub fn reset(&mut self) {
+        *self = Self::new(self.config);
+    }
+
+    /// the raw readings of the next sample scored had a clipped axis
+    pub fn note_clipped(&mut self) {
+        self.clipped = true;
+    }
+
+    /// score of `sample`, which becomes the previous sample of the next one
+    pub fn score(&mut self, sample: &MpuSample) -> PlausibilityScore {
+        let valid = |group| sample.provenance().get(group) != Provenance::Invalid;
+        let mut failed = 0;
+        let mut fail = |check: PlausibilityCheck, failing: bool| {
+            if failing {
+                failed |= check.bit();
+            }
+        };
+        let (acc, gyro, temp) = (
+            valid(FieldGroup::Acc).then_some(sample.acc),
+            valid(FieldGroup::Gyro).then_some(sample.gyro),
+            valid(FieldGroup::Temp).then_some(sample.temp),
+        );
+        if let (Some(acc), Some((min, max))) = (acc, self.acc_squared) {
+            let magnitude = acc.length_squared();
+            fail(
+                PlausibilityCheck::AccMagnitude,
+                !(min..=max).contains(&magnitude),
+            );
+        }
+        if let (Some(gyro), Some(max)) = (gyro, self.gyro_squared) {
+            fail(PlausibilityCheck::GyroRate, gyro.length_squared() > max);
+        }
+        if let (Some(temp), Some((min, max))) = (temp, self.config.temp_c) {
+            fail(PlausibilityCheck::TempRange, !(min..=max).contains(&temp));
+        }
+        if let (Some(temp), Some(previous), Some(max)) =
+            (temp, self.previous_temp, self.config.max_temp_step_c)
+        {
+            fail(PlausibilityCheck::TempRate, (temp - previous).abs() > max);
+        }
+        if self.config.stale {
+            let stale = matches!(
+                (acc, gyro, self.previous_acc, self.previous_gyro),
+                (Some(acc), Some(gyro), Some(previous_acc), Some(previous_gyro))
+                    if acc == previous_acc && gyro == previous_gyro
+            );
+            fail(PlausibilityCheck::Stale, stale);
+        }
+        fail(
+            PlausibilityCheck::Clipped,
+            self.config.clipped && self.clipped,
+        );
+
+        self.clipped = false;
+        self.previous_acc = acc.or(self.previous_acc);
+        self.previous_gyro = gyro.or(self.previous_gyro);
+        self.previous_temp = temp.or(self.previous_temp);
+        PlausibilityScore::new(failed, self.config.implausible)
+    }
+}
+
+impl<I, D> Mpu6050<I, D> {
+    /// Sets or removes the per-sample plausibility checks, see the
+    /// [module docs](crate::plausibility). Setting a config starts without a previous sample
+    pub fn set_plausibility_config(&mut self, config: Option<PlausibilityConfig>) {
+        self.plausibility = config.map(PlausibilityScorer::new);
+    }
+
+    /// config set with [`set_plausibility_config`](Self::set_plausibility_config)
+    pub fn plausibility_config(&self) -> Option<PlausibilityConfig> {
+        self.plausibility.as_ref().map(PlausibilityScorer::config)
+    }
+
+    /// scores `sample` if checks are configured
+    #[cfg(not(feature = "minimal-pipeline"))]
+    pub(crate) fn score_plausibility(&mut self, sample: MpuSample) -> MpuSample {
+        match &mut self.plausibility {
+            Some(scorer) => sample.with_plausibility(scorer.score(&sample)),
+            None => sample,
+        }
+    }
+
+    /// a raw read had a clipped axis, scored with the next sample delivered
+    pub(crate) fn note_clipped(&mut self) {
+        if let Some(scorer) = &mut self.plausibility {
+            scorer.note_clipped();
+        }
+    }
+}
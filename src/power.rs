@@ -0,0 +1,289 @@
+//! Average current draw estimated from the active configuration.
+//!
+//! [`Mpu6050::power_estimate`] models the chip as the driver configured it, itemized in µA:
+//!
+//! | mode | base | gyro | accel | temperature | I2C |
+//! |:---|:---|:---|:---|:---|:---|
+//! | [`Sleep`](PowerMode::Sleep) | `sleep_ua` | 0 | 0 | 0 | 0 |
+//! | [`Normal`](PowerMode::Normal) | `base_ua` | a third of `gyro_ua` per axis out of standby | `accel_ua` | `temp_ua` | traffic |
+//! | [`Cycle`](PowerMode::Cycle) | `sleep_ua` | 0 | the cycle current at the wake frequency less `sleep_ua` | 0 | traffic |
+//!
+//! The figures come from the [`CurrentTable`] of the chip's
+//! [capabilities](crate::device::ChipCapabilities::supply_current), the datasheet currents
+//! in [`device`](crate::device) until [`probe_capabilities`](Mpu6050::probe_capabilities)
+//! found another variant. The traffic term is the share of time the bus carries the
+//! driver's reads, `sample rate * bits per sample / bus frequency` of the [`BusTraffic`]
+//! (see [`bus`](crate::bus) for the bit counts), times `i2c_active_ua`. Cycle mode has the
+//! gyros and the temperature sensor in standby, as [`apply_settings`](Mpu6050::apply_settings)
+//! puts them.
+//!
+//! The model knows the sleep state, the temperature sensor and cycle mode as set through the
+//! driver: PWR_MGMT_2 standby bits written directly are not seen, [`PowerConfig::estimate`]
+//! models those. [`Mpu6050::power_change`] projects settings before applying them, and the
+//! transitions of an installed [governor](crate::governor) carry their [`PowerChange`].
+//!
+//! ```
+//! use mpu6050::device::{LP_WAKE_CTRL, MPU6050_CURRENT};
+//! use mpu6050::power::*;
+//! use mpu6050::settings::Mpu6050Settings;
+//!
+//! let traffic = BusTraffic::DEFAULT;
+//! let normal = PowerConfig::from_settings(&Mpu6050Settings::new(), traffic);
+//! let cycle = PowerConfig::from_settings(
+//!     &Mpu6050Settings::new().with_cycle(Some(LP_WAKE_CTRL::_5)),
+//!     traffic,
+//! );
+//! let change = PowerChange {
+//!     before: normal.estimate(&MPU6050_CURRENT),
+//!     after: cycle.estimate(&MPU6050_CURRENT),
+//! };
+//! assert_eq!(change.before.sensors_ua(), 3800.);
+//! assert!(change.after.total_ua < 21.);
+//! assert!(change.saving_ua() > 3700.);
+//! ```
+
+use crate::bus::ReadPlan;
+use crate::device::{CurrentTable, LP_WAKE_CTRL};
+use crate::settings::Mpu6050Settings;
+use crate::Mpu6050;
+
+/// Default bus frequency of [`BusTraffic`], fast mode
+pub const DEFAULT_BUS_HZ: u32 = 400_000;
+
+/// Operating mode of the chip
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum PowerMode {
+    /// SLEEP set, all sensors off
+    Sleep,
+    /// awake, sampling continuously
+    Normal,
+    /// accel-only cycle mode waking at the given frequency
+    Cycle(LP_WAKE_CTRL),
+}
+
+/// Bus use of the driver, the traffic term of the model
+#[derive(Copy, Clone, Debug)]
+pub struct BusTraffic {
+    /// bus clock in Hz
+    pub bus_hz: u32,
+    /// operations per sample
+    pub plan: ReadPlan<'static>,
+}
+
+impl BusTraffic {
+    /// one burst read per sample at [`DEFAULT_BUS_HZ`]
+    pub const DEFAULT: Self = Self {
+        bus_hz: DEFAULT_BUS_HZ,
+        plan: ReadPlan::BURST,
+    };
+
+    /// share of time the bus carries the reads at `sample_rate_hz`, at most 1
+    pub fn utilization(&self, sample_rate_hz: f32) -> f32 {
+        if self.bus_hz == 0 {
+            return 0.;
+        }
+        let bits_per_second = sample_rate_hz * self.plan.bits_per_sample() as f32;
+        (bits_per_second / self.bus_hz as f32).clamp(0., 1.)
+    }
+}
+
+impl Default for BusTraffic {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+/// Power relevant state of the driver, see the module docs
+#[derive(Copy, Clone, Debug)]
+pub struct PowerState {
+    /// SLEEP set, true from power-on and reset until the wake of init
+    pub asleep: bool,
+    /// temperature sensor enabled, TEMP_DIS clear
+    pub temp_enabled: bool,
+    /// bus use
+    pub traffic: BusTraffic,
+}
+
+impl Default for PowerState {
+    fn default() -> Self {
+        Self {
+            asleep: true,
+            temp_enabled: true,
+            traffic: BusTraffic::DEFAULT,
+        }
+    }
+}
+
+/// Inputs of the model
+#[derive(Copy, Clone, Debug)]
+pub struct PowerConfig {
+    /// operating mode
+    pub mode: PowerMode,
+    /// gyro X, Y, Z out of standby
+    pub gyro_axes: [bool; 3],
+    /// accelerometer out of standby
+    pub accel: bool,
+    /// temperature sensor enabled
+    pub temp: bool,
+    /// samples read per second
+    pub sample_rate_hz: f32,
+    /// bus use per sample
+    pub traffic: BusTraffic,
+}
+
+impl PowerConfig {
+    /// the chip awake with `settings` applied: every sensor on, or in cycle mode the gyros and
+    /// the temperature sensor in standby. One sample read per output sample
+    pub fn from_settings(settings: &Mpu6050Settings, traffic: BusTraffic) -> Self {
+        let cycling = settings.cycle.is_some();
+        Self {
+            mode: settings.cycle.map_or(PowerMode::Normal, PowerMode::Cycle),
+            gyro_axes: [!cycling; 3],
+            accel: true,
+            temp: !cycling,
+            sample_rate_hz: settings.sample_rate_hz(),
+            traffic,
+        }
+    }
+
+    /// estimate with the currents of `table`
+    pub fn estimate(&self, table: &CurrentTable) -> PowerEstimate {
+        let ua = |on: bool, current: u32| match on {
+            true => current as f32,
+            false => 0.,
+        };
+        let i2c_ua = self.traffic.utilization(self.sample_rate_hz) * table.i2c_active_ua as f32;
+        let (base_ua, gyro_ua, accel_ua, temp_ua, i2c_ua) = match self.mode {
+            PowerMode::Sleep => (table.sleep_ua as f32, [0.; 3], 0., 0., 0.),
+            PowerMode::Normal => (
+                table.base_ua as f32,
+                self.gyro_axes.map(|on| ua(on, table.gyro_ua) / 3.),
+                ua(self.accel, table.accel_ua),
+                ua(self.temp, table.temp_ua),
+                i2c_ua,
+            ),
+            PowerMode::Cycle(wake) => {
+                let accel = table.cycle(wake).saturating_sub(table.sleep_ua);
+                (
+                    table.sleep_ua as f32,
+                    [0.; 3],
+                    ua(self.accel, accel),
+                    0.,
+                    i2c_ua,
+                )
+            }
+        };
+        PowerEstimate {
+            mode: self.mode,
+            base_ua,
+            gyro_ua,
+            accel_ua,
+            temp_ua,
+            i2c_ua,
+            total_ua: base_ua + gyro_ua.iter().sum::<f32>() + accel_ua + temp_ua + i2c_ua,
+        }
+    }
+}
+
+/// Estimated average current, itemized, in µA
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct PowerEstimate {
+    /// mode estimated
+    pub mode: PowerMode,
+    /// shared by the sensors, the sleep current asleep and cycling
+    pub base_ua: f32,
+    /// gyro X, Y, Z
+    pub gyro_ua: [f32; 3],
+    /// accelerometer
+    pub accel_ua: f32,
+    /// temperature sensor
+    pub temp_ua: f32,
+    /// bus traffic
+    pub i2c_ua: f32,
+    /// sum of the contributions
+    pub total_ua: f32,
+}
+
+impl PowerEstimate {
+    /// total without the bus traffic, the chip's supply current
+    pub fn sensors_ua(&self) -> f32 {
+        self.total_ua - self.i2c_ua
+    }
+
+    /// hours a battery of `capacity_mah` powers the chip alone
+    pub fn battery_hours(&self, capacity_mah: f32) -> f32 {
+        capacity_mah * 1000. / self.total_ua
+    }
+}
+
+/// Estimates before and after a configuration change
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct PowerChange {
+    /// estimate of the configuration left
+    pub before: PowerEstimate,
+    /// estimate of the configuration entered
+    pub after: PowerEstimate,
+}
+
+impl PowerChange {
+    /// current saved in µA, negative for a change drawing more
+    pub fn saving_ua(&self) -> f32 {
+        self.before.total_ua - self.after.total_ua
+    }
+}
+
+impl<I, D> Mpu6050<I, D> {
+    /// Estimated average current of the chip as configured, see the
+    /// [module docs](crate::power)
+    pub fn power_estimate(&self) -> PowerEstimate {
+        self.power_config()
+            .estimate(&self.capabilities.supply_current)
+    }
+
+    /// Inputs of [`power_estimate`](Self::power_estimate)
+    pub fn power_config(&self) -> PowerConfig {
+        let state = self.power;
+        let cycling = self.cycle.is_some();
+        let mode = match (state.asleep, self.cycle) {
+            (true, _) => PowerMode::Sleep,
+            (false, Some(wake)) => PowerMode::Cycle(wake),
+            (false, None) => PowerMode::Normal,
+        };
+        PowerConfig {
+            mode,
+            gyro_axes: [!cycling; 3],
+            accel: true,
+            temp: state.temp_enabled,
+            sample_rate_hz: Mpu6050Settings::new()
+                .with_dlpf_cfg(self.dlpf_cfg)
+                .with_sample_rate_div(self.sample_rate_div)
+                .with_cycle(self.cycle)
+                .sample_rate_hz(),
+            traffic: state.traffic,
+        }
+    }
+
+    /// Estimates now and after applying `target`, awake
+    pub fn power_change(&self, target: &Mpu6050Settings) -> PowerChange {
+        let table = &self.capabilities.supply_current;
+        PowerChange {
+            before: self.power_estimate(),
+            after: PowerConfig::from_settings(target, self.power.traffic).estimate(table),
+        }
+    }
+
+    /// Bus use assumed by the traffic term, [`BusTraffic::DEFAULT`] unless set
+    pub fn bus_traffic(&self) -> BusTraffic {
+        self.power.traffic
+    }
+
+    /// Sets the bus use assumed by the traffic term
+    pub fn set_bus_traffic(&mut self, traffic: BusTraffic) {
+        self.power.traffic = traffic;
+    }
+
+    /// Power relevant state tracked by the driver
+    pub fn power_state(&self) -> PowerState {
+        self.power
+    }
+}
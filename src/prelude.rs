@@ -0,0 +1,148 @@
+//! The user-facing surface in one import.
+//!
+//! ```
+//! use mpu6050::prelude::*;
+//!
+//! let settings = Mpu6050Settings::new().with_accel_range(AccelRange::G8);
+//! assert_eq!(settings.accel_range, AccelRange::G8);
+//!
+//! let sample = MpuSample::new(Vec3A::Z, Vec3A::ZERO, 25.);
+//! let mut filter = ComplementaryFilter::new(0.98);
+//! let orientation: Quat = filter.update_sample(&sample, 0.01);
+//! assert!(orientation.is_normalized());
+//! ```
+//!
+//! Contents: the driver and its builder, the error types, ranges and configuration values,
+//! samples and settings, the fusion types and every type a public method of [`Mpu6050`] or
+//! [`Mpu6050Builder`] takes or returns. Values are plain `f32` and glam vectors in the units
+//! the methods name, so there are no unit types to import.
+//!
+//! Not included: the raw register addresses and bit definitions, import them from
+//! [`registers`](crate::registers) or [`device`](crate::device) where needed. [`BitBlock`] is
+//! the exception, the raw bit field calls take it.
+
+pub use glam::{Quat, Vec3A};
+
+pub use crate::{Mpu6050, Mpu6050Builder, Mpu6050BuilderError, Mpu6050Error};
+
+pub use crate::aliasing::AliasingAssessment;
+pub use crate::alignment::{
+    AlignmentConfig, AlignmentError, AlignmentEstimate, AlignmentProgress,
+    DynamicAlignmentEstimator, Maneuver,
+};
+#[cfg(feature = "async")]
+pub use crate::async_driver::Mpu6050Async;
+pub use crate::aux_i2c::{ExtDataSlot, SlaveConfig, SlaveSlot};
+#[cfg(feature = "std")]
+pub use crate::black_box::SharedBlackBox;
+pub use crate::black_box::{BlackBox, BlackBoxEvent, FailureRecord};
+pub use crate::board::{
+    AddrConstraint, BoardConstraints, ConstraintViolation, DeviceAddr, IntPinConfig,
+    IntPinConstraint,
+};
+pub use crate::bus::RateTooHigh;
+pub use crate::calibration::{BackgroundCalibrationStatus, CalibrationConfig};
+pub use crate::config::{DefaultsReport, Mpu6050Config};
+pub use crate::config_epoch::{ConfigChange, Tracked};
+pub use crate::config_events::{
+    ChangeSource, ConfigChangeEvent, ConfigField, ConfigObserver, ConfigObserverStatus, ConfigValue,
+};
+pub use crate::connection::{ConnectionMonitor, ConnectionState, ReconnectOutcome};
+pub use crate::continuity::ContinuityBreak;
+pub use crate::conversion::RoundingMode;
+pub use crate::cooperative::DrainBudget;
+pub use crate::deadline::AbortProgress;
+pub use crate::delay::{NoDelay, OwnedDelay};
+pub use crate::device::{
+    AccelRange, Axis, Capability, ChipCapabilities, ChipIdCheck, ChipVariant, CloneAssessment,
+    CloneEvidence, CloneSign, CycleAdjustability, DeniedRange, GyroRange, LowPowerField,
+    LowPowerWakeFrequency, MotionEngineStatus, StandbyFlags, ACCEL_HPF, CLKSEL, DLPF, EXT_SYNC,
+    LP_WAKE_CTRL,
+};
+pub use crate::error_budget::ErrorBudget;
+pub use crate::fifo::{FifoFrame, FifoSchema, FifoSources, MixedReadPolicy};
+pub use crate::fifo_recovery::{FifoClock, FifoGap, FifoRecoveryStatus, GapEstimate};
+pub use crate::governor::{GovernorTransition, PowerGovernor};
+pub use crate::hint::{HintContext, RecoveryHint};
+pub use crate::hook::{SampleHook, SampleObserver, SampleTap};
+pub use crate::impact::{
+    ImpactPhase, ImpactRangeConfig, ImpactRanger, ImpactStats, RangeTransition, RearmPolicy,
+};
+pub use crate::init_findings::{FindingAction, FindingKind, InitFinding, InitFindings};
+pub use crate::interleave::{InterleaveRates, InterleaveState, RangeInterleave};
+pub use crate::interpolation::{InterpolatingBuffer, TimestampError};
+pub use crate::interrupt::{
+    InterruptEdgeTracker, InterruptEvents, MotionDetectionConfig, MotionEvent,
+};
+pub use crate::lever_arm::{LeverArmCompensation, LeverArmCompensator};
+#[cfg(feature = "std")]
+pub use crate::log_header::LogHeader;
+pub use crate::logging::TraceLevel;
+#[cfg(feature = "std")]
+pub use crate::long_term::SharedLongTermStats;
+pub use crate::long_term::{ChannelSummary, LongTermStats, StatsSnapshot};
+pub use crate::low_power::LowPowerAdjust;
+#[cfg(target_has_atomic = "32")]
+pub use crate::mailbox::LatestSampleMailbox;
+pub use crate::metrics::{GaugeLimiter, MetricsSink};
+pub use crate::motion_verify::{MotionStimulus, MotionVerifyOutcome, WakeOnMotionPolicy};
+pub use crate::op_bounds::IoStats;
+pub use crate::orientation::{
+    ComplementaryFilter, FilterGainSchedule, InitBurst, InitCriteria, InitQuality,
+    OrientationEstimate, PlausibilityPolicy, Reacquisition, ReacquisitionState, ReentryYaw,
+    SampleHandling,
+};
+pub use crate::oscillator::ClockErrorEstimate;
+pub use crate::parse_policy::{FrameCheck, ParseDiagnostic, ParseDiagnostics, ParsePolicy};
+pub use crate::platform::{CalibrationResult, ReferencedCalibration};
+#[cfg(feature = "platform-config")]
+pub use crate::platform_config::{
+    OffsetCalibration, PlatformDescription, PlatformError, PlatformReport,
+};
+pub use crate::plausibility::{
+    Plausibility, PlausibilityCheck, PlausibilityConfig, PlausibilityScore,
+};
+pub use crate::power::{
+    BusTraffic, PowerChange, PowerConfig, PowerEstimate, PowerMode, PowerState,
+};
+pub use crate::provenance::{FieldGroup, Provenance, SampleProvenance};
+pub use crate::recovery::{
+    BusErrorKind, BusRecovery, BusRecoveryOutcome, BusRecoveryStats, LockupDetection, ResyncOutcome,
+};
+#[cfg(target_has_atomic = "8")]
+pub use crate::reentrancy::SharedMpu6050;
+pub use crate::reentrancy::{DriverInfo, SampleContext};
+pub use crate::register::{
+    AccessViolation, Field, FieldValue, Register, RegisterSpec, RegisterValue, TypedRegister,
+};
+pub use crate::registers::BitBlock;
+pub use crate::resample::UniformResampler;
+pub use crate::resolution::ResolutionInfo;
+pub use crate::sample::MpuSample;
+pub use crate::sampling::{SampleControl, SampleMeta, SamplingError};
+pub use crate::scale::{Pipeline, ScaleModel};
+pub use crate::scale_drift::{
+    ScaleDriftConfig, ScaleDriftError, ScaleDriftEstimate, ScaleDriftMonitor, ScaleDriftProgress,
+};
+pub use crate::script::{RegisterScript, ScriptError, ScriptReport};
+pub use crate::self_heating::{
+    BootTransientModel, SelfHeatingFitter, SelfHeatingPoint, SelfHeatingSummary, SettlingFit,
+};
+pub use crate::settings::{Mpu6050Settings, SettingsError};
+pub use crate::settling::{SettleCountdown, SettlingPolicy};
+pub use crate::setup::{
+    AutoSetupError, AutoSetupFailure, AutoSetupOptions, AutoSetupPhase, AutoSetupReport,
+};
+pub use crate::shutdown::{ShutdownCheck, ShutdownEvidence, ShutdownItem};
+pub use crate::skew::{SkewCorrection, SkewReference};
+pub use crate::smoothing::{FilterSpec, FilteredView, Smoothing};
+pub use crate::snapshot::DriverStateSnapshot;
+#[cfg(feature = "spectrum")]
+pub use crate::spectrum::SpectrumAnalyzer;
+pub use crate::split::{ConsumerSpec, OverflowPolicy, StreamSplitter};
+pub use crate::supervisor::{SupervisorConfig, SupervisorEvent};
+pub use crate::temp_calibration::{TempCalibration, TempCalibrationError};
+pub use crate::tilt::{TiltEstimate, TiltThresholds};
+#[cfg(feature = "driver")]
+pub use crate::transfer::TransferMode;
+pub use crate::validity::{CalibrationKind, CalibrationMeta, CalibrationPolicy, CalibrationStatus};
@@ -0,0 +1,72 @@
+//! Settings for common applications.
+//!
+//! Numbers in the docs follow [`Mpu6050Settings::resolution`] and
+//! [`Mpu6050Settings::sample_rate_hz`]; noise is RMS over the DLPF bandwidth, delay is the
+//! DLPF group delay from the register map. Apply with
+//! [`Mpu6050::apply_settings`](crate::Mpu6050::apply_settings).
+
+use crate::device::*;
+use crate::settings::Mpu6050Settings;
+
+/// Rate loop of a multirotor flight controller.
+///
+/// ±2000°/s and ±16g so flips and crashes do not clip. DLPF_CFG 1 keeps the delay at 1.9 ms
+/// for the gyro while removing the worst of the motor noise above 188 Hz, 1 kHz output rate.
+/// * resolution 0.061 °/s, 0.49 mg per LSB
+/// * noise 0.069 °/s, 5.4 mg RMS
+/// * delay 1.9 ms gyro, 2.0 ms accel
+pub const DRONE_RATE_CONTROL: Mpu6050Settings = Mpu6050Settings::new()
+    .with_accel_range(AccelRange::G16)
+    .with_gyro_range(GyroRange::D2000)
+    .with_dlpf_cfg(1)
+    .with_sample_rate_div(0);
+
+/// Handheld pointing, gesture or UI input.
+///
+/// Human motion stays well below ±250°/s and ±2g, the finest ranges maximise resolution.
+/// DLPF_CFG 5 removes hand tremor above 10 Hz, 100 Hz output rate (1 kHz / 10).
+/// * resolution 0.0076 °/s, 0.061 mg per LSB
+/// * noise 0.016 °/s, 1.3 mg RMS
+/// * delay 13.4 ms gyro, 13.8 ms accel
+pub const HANDHELD_UI: Mpu6050Settings = Mpu6050Settings::new()
+    .with_accel_range(AccelRange::G2)
+    .with_gyro_range(GyroRange::D250)
+    .with_dlpf_cfg(5)
+    .with_sample_rate_div(9);
+
+/// Vibration logging on machinery.
+///
+/// ±16g for shocks, the accelerometer's full 260 Hz bandwidth with the DLPF off. The accel
+/// output is 1 kHz regardless of SMPLRT_DIV, the divider of 7 brings the 8 kHz gyro rate down
+/// to match. At 1 kHz a host polling sample by sample drops data, drain the FIFO instead.
+/// * resolution 0.015 °/s, 0.49 mg per LSB
+/// * noise 0.080 °/s, 6.5 mg RMS
+/// * delay 0.98 ms gyro, none for accel
+pub const VIBRATION_LOGGING: Mpu6050Settings = Mpu6050Settings::new()
+    .with_accel_range(AccelRange::G16)
+    .with_gyro_range(GyroRange::D500)
+    .with_dlpf_cfg(0)
+    .with_sample_rate_div(7);
+
+/// Battery powered tilt or orientation sensing.
+///
+/// Accel-only cycle mode: the device sleeps and wakes at 5 Hz for a single accel sample,
+/// gyros and temperature sensor in standby, clocked from the internal oscillator since the
+/// gyro PLL stops. ±2g for the finest tilt resolution.
+/// * resolution 0.061 mg per LSB
+/// * noise 6.5 mg RMS, about 0.4° of tilt
+/// * 200 ms between samples
+pub const LOW_POWER_TILT: Mpu6050Settings = Mpu6050Settings::new()
+    .with_accel_range(AccelRange::G2)
+    .with_gyro_range(GyroRange::D250)
+    .with_dlpf_cfg(0)
+    .with_clock_source(CLKSEL::OSCILL)
+    .with_cycle(Some(LP_WAKE_CTRL::_5));
+
+/// All presets with their names
+pub const ALL: [(&str, Mpu6050Settings); 4] = [
+    ("DRONE_RATE_CONTROL", DRONE_RATE_CONTROL),
+    ("HANDHELD_UI", HANDHELD_UI),
+    ("VIBRATION_LOGGING", VIBRATION_LOGGING),
+    ("LOW_POWER_TILT", LOW_POWER_TILT),
+];
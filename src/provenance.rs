@@ -0,0 +1,338 @@
+//! Where the values of a sample come from.
+//!
+//! Every [`MpuSample`](crate::MpuSample) carries a [`SampleProvenance`]: one [`Provenance`]
+//! tag per field group ([`FieldGroup`]: accelerometer, gyro, temperature), telling whether
+//! the values were measured at the sample's instant or made up by a stage of the driver.
+//! Estimators assuming independent measurement noise need to know: an interpolated value is
+//! correlated with its neighbours, a held one repeats an older measurement.
+//!
+//! | tag | value |
+//! |:---|:---|
+//! | [`Measured`](Provenance::Measured) | read from the sensor for this instant |
+//! | [`Interpolated`](Provenance::Interpolated) | computed from measurements on both sides |
+//! | [`Held`](Provenance::Held) | an older measurement repeated, `age` samples old |
+//! | [`Substituted`](Provenance::Substituted) | a measurement replaced by another value |
+//! | [`Synthesized`](Provenance::Synthesized) | generated without a measurement around it |
+//! | [`Invalid`](Provenance::Invalid) | no value, NaN |
+//!
+//! #### Producing stages
+//! | stage | tags |
+//! |:---|:---|
+//! | [`run_sampling_loop`](crate::Mpu6050::run_sampling_loop), [`sample_into_interp_buffer`](crate::Mpu6050::sample_into_interp_buffer), [`DifferentialPair`](crate::differential::DifferentialPair) reads | all measured |
+//! | single sensor reads ([`get_acc`](crate::Mpu6050::get_acc), `get_gyro`, `get_temp`) seen by hook and tap | the groups not read invalid |
+//! | [`interpolate`](crate::interpolation::interpolate), [`UniformResampler`](crate::resample::UniformResampler) | the worst of both inputs and interpolated, inputs unchanged on an exact hit |
+//! | [`InterpolatingBuffer`](crate::interpolation::InterpolatingBuffer) extrapolation | the worst of the inputs and synthesized |
+//! | [`skew`](crate::skew) correction | the shifted group the worst of both samples and interpolated |
+//! | [`lever_arm`](crate::lever_arm) compensation | unchanged, the term in [`lever_arm_correction`](crate::MpuSample::lever_arm_correction) |
+//! | [`merge_interleaved`](crate::interleave::merge_interleaved) | accelerometer from the axis sources, the worst axis |
+//! | [`DifferentialPair`](crate::differential::DifferentialPair) axis mapping | unchanged |
+//! | [`hook`](crate::hook) | whatever the hook sets, a hook replacing values tags them |
+//!
+//! Combining tags keeps the worst, in the order of the table above: measured, interpolated,
+//! held (older is worse), substituted, synthesized, invalid.
+//!
+//! #### Encoding
+//! A tag is one byte, bits 2:0 the kind, bits 7:3 the age of a held value in samples,
+//! saturating at [`MAX_HELD_AGE`]:
+//!
+//! | bits 2:0 | kind |
+//! |:---|:---|
+//! | 0 | measured |
+//! | 1 | interpolated |
+//! | 2 | held |
+//! | 3 | substituted |
+//! | 4 | synthesized |
+//! | 5 | invalid |
+//!
+//! Codes 6 and 7 are reserved, as are the age bits of every kind but held, they decode to
+//! None. A [`SampleProvenance`] is [`PROVENANCE_LEN`] bytes, accelerometer, gyro,
+//! temperature; all measured is all zero. The [`packed`](crate::packed) format stores them
+//! per sample, the text form `M`, `I`, `H<age>`, `S`, `Y`, `X` fills the CSV columns
+//! [`PROVENANCE_CSV_COLUMNS`].
+//!
+//! #### Fusion
+//! [`Provenance::weight`] is the weight the fusion filters give a value, 1 for a
+//! measurement:
+//!
+//! | tag | weight |
+//! |:---|:---|
+//! | measured | 1 |
+//! | interpolated | 1/2 |
+//! | held, `age` | 1/2 / (1 + age) |
+//! | substituted | 1/4 |
+//! | synthesized, invalid | 0 |
+//!
+//! [`ComplementaryFilter::update_sample`](crate::orientation::ComplementaryFilter::update_sample)
+//! scales its accelerometer correction with the accelerometer weight and its bias update with
+//! both weights, see there.
+//! ```
+//! use mpu6050::provenance::{FieldGroup, Provenance, SampleProvenance};
+//!
+//! let tags = SampleProvenance::MEASURED
+//!     .with(FieldGroup::Acc, Provenance::Held { age: 2 })
+//!     .with(FieldGroup::Temp, Provenance::Invalid);
+//! assert_eq!(tags.to_string(), "H2,M,X");
+//! assert_eq!(tags.to_bytes(), [0x12, 0x00, 0x05]);
+//! assert_eq!(SampleProvenance::from_bytes(tags.to_bytes()), Some(tags));
+//! assert_eq!("H2,M,X".parse(), Ok(tags));
+//! ```
+
+use core::fmt;
+use core::str::FromStr;
+
+/// Oldest age of a held value the encoding holds, older ones saturate
+pub const MAX_HELD_AGE: u8 = 31;
+/// Bytes of an encoded [`SampleProvenance`]
+pub const PROVENANCE_LEN: usize = 3;
+/// CSV column names of the text form, in its order
+pub const PROVENANCE_CSV_COLUMNS: [&str; PROVENANCE_LEN] = ["acc_src", "gyro_src", "temp_src"];
+
+const KIND_MASK: u8 = 0x07;
+const AGE_SHIFT: u32 = 3;
+
+/// Origin of the values of one field group, see the module docs
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Hash)]
+pub enum Provenance {
+    /// read from the sensor for this instant
+    #[default]
+    Measured,
+    /// computed from measurements on both sides
+    Interpolated,
+    /// an older measurement repeated
+    Held {
+        /// samples since the measurement, at most [`MAX_HELD_AGE`]
+        age: u8,
+    },
+    /// a measurement replaced by another value
+    Substituted,
+    /// generated without a measurement around it
+    Synthesized,
+    /// no value, NaN
+    Invalid,
+}
+
+impl Provenance {
+    /// held value `age` samples old, saturating at [`MAX_HELD_AGE`]
+    pub fn held(age: u32) -> Self {
+        Provenance::Held {
+            age: age.min(MAX_HELD_AGE as u32) as u8,
+        }
+    }
+
+    /// encoded tag, see the module docs
+    pub fn to_bits(self) -> u8 {
+        match self {
+            Provenance::Measured => 0,
+            Provenance::Interpolated => 1,
+            Provenance::Held { age } => 2 | age.min(MAX_HELD_AGE) << AGE_SHIFT,
+            Provenance::Substituted => 3,
+            Provenance::Synthesized => 4,
+            Provenance::Invalid => 5,
+        }
+    }
+
+    /// decodes a tag, None for reserved codes and age bits outside a held tag
+    pub fn from_bits(bits: u8) -> Option<Self> {
+        let age = bits >> AGE_SHIFT;
+        let provenance = match bits & KIND_MASK {
+            2 => return Some(Provenance::Held { age }),
+            0 => Provenance::Measured,
+            1 => Provenance::Interpolated,
+            3 => Provenance::Substituted,
+            4 => Provenance::Synthesized,
+            5 => Provenance::Invalid,
+            _ => return None,
+        };
+        (age == 0).then_some(provenance)
+    }
+
+    /// read from the sensor for this instant
+    pub fn is_measured(self) -> bool {
+        self == Provenance::Measured
+    }
+
+    /// position in the order of the module docs, higher is worse
+    fn rank(self) -> u16 {
+        match self {
+            Provenance::Measured => 0,
+            Provenance::Interpolated => 1,
+            Provenance::Held { age } => 2 + age as u16,
+            Provenance::Substituted => 0x100,
+            Provenance::Synthesized => 0x101,
+            Provenance::Invalid => 0x102,
+        }
+    }
+
+    /// the worse of both, for values derived from both
+    pub fn worst(self, other: Self) -> Self {
+        if other.rank() > self.rank() {
+            other
+        } else {
+            self
+        }
+    }
+
+    /// weight in fusion filters, see the module docs
+    pub fn weight(self) -> f32 {
+        match self {
+            Provenance::Measured => 1.,
+            Provenance::Interpolated => 0.5,
+            Provenance::Held { age } => 0.5 / (1. + age as f32),
+            Provenance::Substituted => 0.25,
+            Provenance::Synthesized | Provenance::Invalid => 0.,
+        }
+    }
+}
+
+impl fmt::Display for Provenance {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Provenance::Measured => f.write_str("M"),
+            Provenance::Interpolated => f.write_str("I"),
+            Provenance::Held { age } => write!(f, "H{}", age),
+            Provenance::Substituted => f.write_str("S"),
+            Provenance::Synthesized => f.write_str("Y"),
+            Provenance::Invalid => f.write_str("X"),
+        }
+    }
+}
+
+/// Text form not recognized
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct ParseProvenanceError;
+
+impl fmt::Display for ParseProvenanceError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("unknown provenance tag")
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ParseProvenanceError {}
+
+impl FromStr for Provenance {
+    type Err = ParseProvenanceError;
+
+    fn from_str(s: &str) -> Result<Self, ParseProvenanceError> {
+        match s {
+            "M" => Ok(Provenance::Measured),
+            "I" => Ok(Provenance::Interpolated),
+            "S" => Ok(Provenance::Substituted),
+            "Y" => Ok(Provenance::Synthesized),
+            "X" => Ok(Provenance::Invalid),
+            _ => match s.strip_prefix('H').map(str::parse::<u8>) {
+                Some(Ok(age)) if age <= MAX_HELD_AGE => Ok(Provenance::Held { age }),
+                _ => Err(ParseProvenanceError),
+            },
+        }
+    }
+}
+
+/// Field groups of a sample tagged together
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub enum FieldGroup {
+    /// the three accelerometer axes
+    Acc,
+    /// the three gyro axes
+    Gyro,
+    /// the temperature
+    Temp,
+}
+
+impl FieldGroup {
+    /// all groups in encoding order
+    pub const ALL: [FieldGroup; PROVENANCE_LEN] =
+        [FieldGroup::Acc, FieldGroup::Gyro, FieldGroup::Temp];
+}
+
+/// Provenance of every field group of a sample, see the module docs
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Hash)]
+pub struct SampleProvenance {
+    groups: [Provenance; PROVENANCE_LEN],
+}
+
+impl SampleProvenance {
+    /// everything measured, the tags of a sample read from the sensor
+    pub const MEASURED: Self = Self::uniform(Provenance::Measured);
+
+    /// every group tagged `provenance`
+    pub const fn uniform(provenance: Provenance) -> Self {
+        Self {
+            groups: [provenance; PROVENANCE_LEN],
+        }
+    }
+
+    /// tag of `group`
+    pub fn get(&self, group: FieldGroup) -> Provenance {
+        self.groups[group as usize]
+    }
+
+    /// same tags with `group` tagged `provenance`
+    pub fn with(mut self, group: FieldGroup, provenance: Provenance) -> Self {
+        self.groups[group as usize] = provenance;
+        self
+    }
+
+    /// every group measured
+    pub fn is_measured(&self) -> bool {
+        self.groups.iter().all(|p| p.is_measured())
+    }
+
+    /// the worse tag of both for every group
+    pub fn worst(self, other: Self) -> Self {
+        let mut groups = self.groups;
+        for (tag, other) in groups.iter_mut().zip(other.groups) {
+            *tag = tag.worst(other);
+        }
+        Self { groups }
+    }
+
+    /// tags of values interpolated between samples tagged `self` and `other`
+    pub fn interpolated(self, other: Self) -> Self {
+        self.worst(other)
+            .worst(Self::uniform(Provenance::Interpolated))
+    }
+
+    /// tags of values extrapolated from samples tagged `self`
+    pub fn extrapolated(self) -> Self {
+        self.worst(Self::uniform(Provenance::Synthesized))
+    }
+
+    /// encoded, one byte per group in [`FieldGroup::ALL`] order
+    pub fn to_bytes(&self) -> [u8; PROVENANCE_LEN] {
+        self.groups.map(Provenance::to_bits)
+    }
+
+    /// decodes [`to_bytes`](Self::to_bytes), None if a tag is reserved
+    pub fn from_bytes(bytes: [u8; PROVENANCE_LEN]) -> Option<Self> {
+        let mut groups = [Provenance::Measured; PROVENANCE_LEN];
+        for (tag, byte) in groups.iter_mut().zip(bytes) {
+            *tag = Provenance::from_bits(byte)?;
+        }
+        Some(Self { groups })
+    }
+}
+
+/// the CSV form, one comma separated tag per column of [`PROVENANCE_CSV_COLUMNS`]
+impl fmt::Display for SampleProvenance {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let [acc, gyro, temp] = self.groups;
+        write!(f, "{},{},{}", acc, gyro, temp)
+    }
+}
+
+impl FromStr for SampleProvenance {
+    type Err = ParseProvenanceError;
+
+    fn from_str(s: &str) -> Result<Self, ParseProvenanceError> {
+        let mut groups = [Provenance::Measured; PROVENANCE_LEN];
+        let mut fields = s.split(',');
+        for tag in groups.iter_mut() {
+            *tag = fields.next().ok_or(ParseProvenanceError)?.trim().parse()?;
+        }
+        match fields.next() {
+            Some(_) => Err(ParseProvenanceError),
+            None => Ok(Self { groups }),
+        }
+    }
+}
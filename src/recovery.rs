@@ -0,0 +1,425 @@
+//! Recovery from an I2C bus lockup through a user-provided bus reset.
+//!
+//! A slave that loses clock sync in the middle of a byte can hold SDA low forever, or keep
+//! SCL stretched: every later transaction fails with a timeout or an arbitration loss until
+//! someone clocks the slave out of its state (nine SCL pulses and a stop, toggled by hand on
+//! the pins). The driver cannot do that itself, the pins belong to the application's GPIO
+//! layer, so [`Mpu6050::set_bus_recovery`] registers a [`BusRecovery`] with a callback the
+//! driver invokes when it sees the lockup signature. Without a registration nothing of this
+//! runs: one `Option` check per transaction.
+//!
+//! #### Detection
+//! embedded-hal 0.2 errors are opaque to the driver, so it asks the optional classifier of
+//! the registration what a failed transaction was ([`BusErrorKind`]), e.g. from the HAL's
+//! last error or by reading the SDA level. Without a classifier every failure counts as
+//! [`Bus`](BusErrorKind::Bus). A [`Nak`](BusErrorKind::Nak) never counts: nobody answered, an
+//! unplugged sensor is the [`connection`](crate::connection) monitor's business.
+//!
+//! [`LockupDetection`] is the signature: at least `failures` bus errors among the last
+//! `window` attempted transactions, the latest being one of them. The default, 8 of 8, is 8
+//! bus errors in a row. After a detection the history starts over, and `min_interval`
+//! transactions must pass before the callback is invoked again, detections in between
+//! count as rate limited ([`BusRecoveryStats::rate_limited`]). With auto disconnect on,
+//! keep the disconnect threshold above `failures`: short-circuited operations are not
+//! transactions.
+//!
+//! #### Resynchronization
+//! The operation that completed the signature still returns its error, the recovery runs
+//! before it returns. On [`Recovered`](BusRecoveryOutcome::Recovered) the driver checks
+//! the device in one go, while the connection monitor is
+//! [`Reinitializing`](crate::connection::ConnectionState::Reinitializing):
+//!
+//! 1. WHO_AM_I, against the chip seen at init
+//! 2. PWR_MGMT_1: its reset value 0x40 means the chip lost power, which the driver never
+//!    writes
+//! 3. SMPLRT_DIV through ACCEL_CONFIG in one burst, against the cached sample rate divider,
+//!    DLPF_CFG and full scale ranges. A field that differs is written again
+//!
+//! A different chip or a reset chip is [`NeedsReconnect`](ResyncOutcome::NeedsReconnect),
+//! left `Disconnected` for [`try_reconnect`](Mpu6050::try_reconnect), like a failed
+//! transaction of the check. Otherwise the driver resumes `Connected`. Every invocation
+//! emits [`BUS_RECOVERIES`](crate::metrics::BUS_RECOVERIES) with a
+//! [`BusRecovery`](crate::metrics::MetricEvent::BusRecovery) event.
+//!
+//! ```
+//! use mpu6050::recovery::*;
+//!
+//! // 3 bus errors among 5 transactions
+//! let mut detector = LockupDetector::new(LockupDetection::new(3, 5).with_min_interval(0));
+//! assert_eq!(detector.record(Some(BusErrorKind::Bus)), LockupVerdict::Clear);
+//! assert_eq!(detector.record(None), LockupVerdict::Clear);
+//! assert_eq!(detector.record(Some(BusErrorKind::Nak)), LockupVerdict::Clear);
+//! assert_eq!(detector.record(Some(BusErrorKind::Bus)), LockupVerdict::Clear);
+//! assert_eq!(detector.record(Some(BusErrorKind::Bus)), LockupVerdict::Lockup);
+//! ```
+
+#[cfg(feature = "driver")]
+use embedded_hal::blocking::i2c::{Write, WriteRead};
+
+use crate::Mpu6050;
+#[cfg(feature = "driver")]
+use crate::{
+    config_events::{ChangeSource, ConfigField, ConfigValue},
+    device::DEFAULT_SLAVE_ADDR,
+    metrics::{self, MetricEvent},
+    register::{Register, RegisterValue},
+    registers::{ACCEL_CONFIG, CONFIG, GYRO_CONFIG},
+    Mpu6050Error,
+};
+
+/// PWR_MGMT_1 after a power-on reset: asleep on the internal oscillator
+pub const PWR_MGMT_1_RESET: u8 = 0x40;
+
+/// Longest detection window in transactions
+pub const MAX_WINDOW: u8 = 32;
+
+/// What a failed transaction was, as told by the classifier
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum BusErrorKind {
+    /// the address was not acknowledged, never part of the signature
+    Nak,
+    /// timeout, arbitration loss or stuck line
+    Bus,
+}
+
+/// What the bus reset callback did
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum BusRecoveryOutcome {
+    /// the lines were stuck and are free now, the driver resynchronizes
+    Recovered,
+    /// the lines were free, nothing done
+    NotStuck,
+    /// the lines are still stuck
+    Failed,
+}
+
+/// Result of the resynchronization after a recovered bus
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum ResyncOutcome {
+    /// same chip, configuration intact or written again
+    Resumed {
+        /// fields written again
+        restored: u8,
+    },
+    /// a different chip answered or the chip was reset, see the module docs
+    NeedsReconnect,
+    /// a transaction of the check failed
+    BusFailed,
+}
+
+/// Lockup signature and rate limit, see the module docs
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct LockupDetection {
+    failures: u8,
+    window: u8,
+    min_interval: u32,
+}
+
+impl Default for LockupDetection {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+impl LockupDetection {
+    /// 8 bus errors in a row, at most one invocation per 1000 transactions
+    pub const DEFAULT: Self = Self {
+        failures: 8,
+        window: 8,
+        min_interval: 1000,
+    };
+
+    /// `failures` bus errors among the last `window` transactions. The window is clamped to
+    /// 1..=[`MAX_WINDOW`], the failures to 1..=window
+    pub fn new(failures: u8, window: u8) -> Self {
+        let window = window.clamp(1, MAX_WINDOW);
+        Self {
+            failures: failures.clamp(1, window),
+            window,
+            min_interval: Self::DEFAULT.min_interval,
+        }
+    }
+
+    /// transactions between two invocations of the callback at least
+    pub fn with_min_interval(mut self, transactions: u32) -> Self {
+        self.min_interval = transactions;
+        self
+    }
+
+    /// bus errors of the signature
+    pub fn failures(&self) -> u8 {
+        self.failures
+    }
+
+    /// transactions of the signature
+    pub fn window(&self) -> u8 {
+        self.window
+    }
+
+    /// transactions between two invocations at least
+    pub fn min_interval(&self) -> u32 {
+        self.min_interval
+    }
+}
+
+/// Verdict of [`LockupDetector::record`]
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum LockupVerdict {
+    /// no signature
+    Clear,
+    /// signature seen, invoke the callback
+    Lockup,
+    /// signature seen within `min_interval` of the last invocation
+    RateLimited,
+}
+
+/// Signature matching over the attempted transactions, no bus access
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct LockupDetector {
+    detection: LockupDetection,
+    /// one bit per transaction, newest lowest, set for a bus error
+    history: u32,
+    /// transactions since the last invocation, None before the first
+    since_invocation: Option<u32>,
+}
+
+impl LockupDetector {
+    /// detector with an empty history
+    pub const fn new(detection: LockupDetection) -> Self {
+        Self {
+            detection,
+            history: 0,
+            since_invocation: None,
+        }
+    }
+
+    /// signature in use
+    pub fn detection(&self) -> LockupDetection {
+        self.detection
+    }
+
+    /// Called after each attempted transaction, `failure` None for a success
+    pub fn record(&mut self, failure: Option<BusErrorKind>) -> LockupVerdict {
+        let bus_error = failure == Some(BusErrorKind::Bus);
+        let mask = u32::MAX >> (32 - self.detection.window as u32);
+        self.history = (self.history << 1 | bus_error as u32) & mask;
+        if let Some(since) = self.since_invocation.as_mut() {
+            *since = since.saturating_add(1);
+        }
+        if !bus_error || self.history.count_ones() < self.detection.failures as u32 {
+            return LockupVerdict::Clear;
+        }
+
+        self.history = 0;
+        match self.since_invocation {
+            Some(since) if since < self.detection.min_interval => LockupVerdict::RateLimited,
+            _ => {
+                self.since_invocation = Some(0);
+                LockupVerdict::Lockup
+            }
+        }
+    }
+}
+
+/// Bus reset registration, see the module docs
+#[derive(Copy, Clone, Debug)]
+pub struct BusRecovery {
+    callback: fn() -> BusRecoveryOutcome,
+    classify: Option<fn() -> BusErrorKind>,
+    detection: LockupDetection,
+}
+
+impl BusRecovery {
+    /// `callback` frees the bus, the default signature and no classifier
+    pub const fn new(callback: fn() -> BusRecoveryOutcome) -> Self {
+        Self {
+            callback,
+            classify: None,
+            detection: LockupDetection::DEFAULT,
+        }
+    }
+
+    /// `classify` tells the kind of the failed transaction just seen
+    pub const fn with_classifier(mut self, classify: fn() -> BusErrorKind) -> Self {
+        self.classify = Some(classify);
+        self
+    }
+
+    /// signature and rate limit
+    pub const fn with_detection(mut self, detection: LockupDetection) -> Self {
+        self.detection = detection;
+        self
+    }
+
+    /// signature and rate limit in use
+    pub fn detection(&self) -> LockupDetection {
+        self.detection
+    }
+}
+
+/// Counters since the registration
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub struct BusRecoveryStats {
+    /// callback invocations
+    pub invocations: u32,
+    /// signatures seen within the rate limit, no invocation
+    pub rate_limited: u32,
+    /// resynchronizations that resumed
+    pub resumed: u32,
+    /// configuration fields written again by them
+    pub restored: u32,
+    /// invocations left for `try_reconnect`: callback failed, chip changed or reset, check
+    /// failed
+    pub unrecovered: u32,
+}
+
+/// Registration with its detector and counters
+#[derive(Copy, Clone, Debug)]
+pub(crate) struct BusRecoveryState {
+    recovery: BusRecovery,
+    detector: LockupDetector,
+    stats: BusRecoveryStats,
+    /// kind of the latest transaction if it failed, for the [`hint`](crate::hint) context
+    last_failure: Option<BusErrorKind>,
+}
+
+impl BusRecoveryState {
+    pub(crate) fn stats(&self) -> BusRecoveryStats {
+        self.stats
+    }
+
+    pub(crate) fn last_failure(&self) -> Option<BusErrorKind> {
+        self.last_failure
+    }
+}
+
+impl<I, D> Mpu6050<I, D> {
+    /// Registers the bus reset, None turns the lockup detection off. Starts with an empty
+    /// history and zero counters
+    pub fn set_bus_recovery(&mut self, recovery: Option<BusRecovery>) {
+        self.bus_recovery = recovery.map(|recovery| BusRecoveryState {
+            recovery,
+            detector: LockupDetector::new(recovery.detection),
+            stats: BusRecoveryStats::default(),
+            last_failure: None,
+        });
+    }
+
+    /// registration set with [`set_bus_recovery`](Self::set_bus_recovery)
+    pub fn bus_recovery(&self) -> Option<BusRecovery> {
+        self.bus_recovery.as_ref().map(|state| state.recovery)
+    }
+
+    /// counters of the registration, None without
+    pub fn bus_recovery_stats(&self) -> Option<BusRecoveryStats> {
+        self.bus_recovery.as_ref().map(BusRecoveryState::stats)
+    }
+}
+
+#[cfg(feature = "driver")]
+impl<I, D, E> Mpu6050<I, D>
+where
+    I: Write<Error = E> + WriteRead<Error = E>,
+{
+    /// Feeds an attempted transaction to the detector, recovers on a lockup. Does nothing
+    /// during the recovery itself, the registration is taken out meanwhile
+    pub(crate) fn watch_for_lockup(&mut self, ok: bool) {
+        let Some(state) = self.bus_recovery.as_mut() else {
+            return;
+        };
+        let failure = match ok {
+            true => None,
+            false => Some(state.recovery.classify.map_or(BusErrorKind::Bus, |f| f())),
+        };
+        state.last_failure = failure;
+        match state.detector.record(failure) {
+            LockupVerdict::Clear => {}
+            LockupVerdict::RateLimited => {
+                state.stats.rate_limited += 1;
+                self.emit_counter(metrics::BUS_RECOVERIES_RATE_LIMITED, 1);
+            }
+            LockupVerdict::Lockup => self.recover_bus(),
+        }
+    }
+
+    fn recover_bus(&mut self) {
+        let Some(mut state) = self.bus_recovery.take() else {
+            return;
+        };
+        state.stats.invocations += 1;
+        let outcome = (state.recovery.callback)();
+        let resync = match outcome {
+            BusRecoveryOutcome::Recovered => Some(self.resync_after_recovery()),
+            BusRecoveryOutcome::NotStuck => None,
+            BusRecoveryOutcome::Failed => {
+                state.stats.unrecovered += 1;
+                None
+            }
+        };
+        match resync {
+            Some(ResyncOutcome::Resumed { restored }) => {
+                state.stats.resumed += 1;
+                state.stats.restored += restored as u32;
+            }
+            Some(_) => state.stats.unrecovered += 1,
+            None => {}
+        }
+        self.bus_recovery = Some(state);
+        self.emit_event(
+            metrics::BUS_RECOVERIES,
+            MetricEvent::BusRecovery { outcome, resync },
+        );
+    }
+
+    fn resync_after_recovery(&mut self) -> ResyncOutcome {
+        self.connection.begin_reconnect();
+        let outcome = self
+            .with_change_source(ChangeSource::Resync, Self::resync)
+            .unwrap_or(ResyncOutcome::BusFailed);
+        self.connection
+            .end_reconnect(matches!(outcome, ResyncOutcome::Resumed { .. }));
+        outcome
+    }
+
+    fn resync(&mut self) -> Result<ResyncOutcome, Mpu6050Error<E>> {
+        let found = self.read_register(Register::WHO_AM_I)?;
+        if found != self.chip_id.unwrap_or(DEFAULT_SLAVE_ADDR) {
+            return Ok(ResyncOutcome::NeedsReconnect);
+        }
+        if self.read_register(Register::PWR_MGMT_1)? == PWR_MGMT_1_RESET {
+            return Ok(ResyncOutcome::NeedsReconnect);
+        }
+
+        // SMPLRT_DIV, CONFIG, GYRO_CONFIG, ACCEL_CONFIG
+        let mut config = [0; 4];
+        self.read_registers(Register::SMPLRT_DIV, &mut config)?;
+        let mut restored = 0;
+        // the value found is the old value of the change events, see `config_events`
+        let mut found = |mpu: &mut Self, field, value| {
+            mpu.observe_device_value(field, ConfigValue::Code(value));
+            restored += 1;
+        };
+        if config[0] != self.sample_rate_div {
+            found(self, ConfigField::SampleRateDivider, config[0]);
+            self.write_sample_rate_div(self.sample_rate_div)?;
+        }
+        let dlpf_cfg = RegisterValue::<CONFIG>::from_byte(config[1]).field(CONFIG::DLPF_CFG_FIELD);
+        if dlpf_cfg != self.dlpf_cfg {
+            found(self, ConfigField::Dlpf, dlpf_cfg);
+            self.write_dlpf_cfg(self.dlpf_cfg)?;
+        }
+        let gyro_range =
+            RegisterValue::<GYRO_CONFIG>::from_byte(config[2]).field(GYRO_CONFIG::FS_SEL_FIELD);
+        if gyro_range != self.gyro_range as u8 {
+            found(self, ConfigField::GyroRange, gyro_range);
+            self.set_gyro_range(self.gyro_range)?;
+        }
+        let accel_range =
+            RegisterValue::<ACCEL_CONFIG>::from_byte(config[3]).field(ACCEL_CONFIG::FS_SEL_FIELD);
+        if accel_range != self.accel_range as u8 {
+            found(self, ConfigField::AccelRange, accel_range);
+            self.set_accel_range(self.accel_range)?;
+        }
+        Ok(ResyncOutcome::Resumed { restored })
+    }
+}
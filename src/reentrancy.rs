@@ -0,0 +1,252 @@
+//! Which user code runs inside a driver call, and what it may do there.
+//!
+//! Several extension points run user code while a driver method is on the stack. None of
+//! them may call back into the same driver: the method holds `&mut Mpu6050` and may be
+//! halfway through updating its caches. Most of them cannot by construction, the others
+//! meet a runtime flag that fails the call with [`Mpu6050Error::Reentrancy`] rather than let
+//! it touch the half-updated state.
+//!
+//! #### Callback points
+//! | callback | installed with | runs from | state mid-update when it runs | may call the driver |
+//! |:---|:---|:---|:---|:---|
+//! | [sample hook](crate::hook::SampleHook) | [`set_sample_hook`](Mpu6050::set_sample_hook) | every scaled read, [`deliver`](crate::hook) | supervisor, background calibration, governor, validity and plausibility updated for this sample, the sample not yet returned | no, `fn` pointer |
+//! | [sample tap](crate::hook::SampleTap) | [`set_sample_tap`](Mpu6050::set_sample_tap) | after the hook | as for the hook, the gauges not yet emitted | no, `fn` pointer |
+//! | [sample observer](crate::hook::SampleObserver) | [`set_sample_observer`](Mpu6050::set_sample_observer) | after the tap | as for the tap, the observer gets a [`SampleContext`] | no, `fn` pointer |
+//! | supervisor [`callback`](crate::supervisor::SupervisorConfig::callback) | [`set_supervisor`](Mpu6050::set_supervisor) | the read tripping the latch | the latch set, the other consumers and the hook not yet run | no, `fn` pointer |
+//! | [`MetricsSink`](crate::metrics::MetricsSink) | [`set_metrics_sink`](Mpu6050::set_metrics_sink) | bus errors, recoveries, FIFO overflows, per-sample gauges | the counters of the operation emitting, e.g. a FIFO drain before its frames are read | no, `&'static` shared sink |
+//! | `progress`, `deadline`, `yield_fn`, `prompt`, `reference`, `sink`, `on_frame`, `delay_between` | arguments of the operation | inside that operation | the operation's own state, e.g. the sums of a calibration or the frames of a drain not yet parsed | no, the driver is borrowed by the call |
+//! | [`DelayMs`](embedded_hal::blocking::delay::DelayMs) and the I2C implementation | builder, arguments | every wait and transaction | anything, a transaction is the innermost step | no, owned or borrowed by the call |
+//! | host callbacks of the [`ffi`](crate::ffi) layer | `mpu6050_create` | every transaction and wait | as for the I2C implementation | refused at runtime, see below |
+//!
+//! The [config observer](crate::config_events) is a queue drained by the caller, not a
+//! callback, and the derive closure of [`tracked`](Mpu6050::tracked) gets `&Self` with no
+//! way to mutate.
+//!
+//! #### Type-level restrictions
+//! The function pointer callbacks capture nothing and their arguments lead nowhere near the
+//! driver: the hook gets `&mut MpuSample`, the tap `&MpuSample`, the observer a
+//! [`SampleContext`], a copy of the driver's configuration next to the sample, the
+//! supervisor callback the event by value. The metrics sink is a `&'static dyn MetricsSink`,
+//! `Sync`, installed before the driver is borrowed by a call. Closures passed to an
+//! operation run while the operation borrows the driver mutably, the borrow checker refuses
+//! any that move or borrow it:
+//! ```compile_fail
+//! use embedded_hal::blocking::i2c::{Write, WriteRead};
+//! use mpu6050::{Mpu6050, MpuSample};
+//!
+//! fn reenter<I, E>(mpu: &mut Mpu6050<I>)
+//! where
+//!     I: Write<Error = E> + WriteRead<Error = E>,
+//! {
+//!     let mut out = [MpuSample::default(); 4];
+//!     mpu.read_batch(|| drop(mpu.get_acc()), &mut out).ok();
+//! }
+//! ```
+//! A capturing closure does not coerce to a callback pointer either:
+//! ```compile_fail
+//! use mpu6050::reentrancy::SampleContext;
+//! use mpu6050::Mpu6050;
+//!
+//! fn reenter<I>(mpu: &mut Mpu6050<I>) {
+//!     let driver = &*mpu;
+//!     mpu.set_sample_observer(Some(|_: &SampleContext| {
+//!         driver.config_epoch();
+//!     }));
+//! }
+//! ```
+//! and the context hands out copies and shared references only, there is no path from it to
+//! the driver:
+//! ```compile_fail
+//! use mpu6050::reentrancy::SampleContext;
+//! use mpu6050::MpuSample;
+//!
+//! fn tamper(context: &SampleContext) {
+//!     let sample: &mut MpuSample = context.sample();
+//! }
+//! ```
+//!
+//! #### Runtime flag
+//! The types stop safe code reaching the driver from a callback, except through a global.
+//! A driver in a `static` behind a lock deadlocks when a callback takes the lock its own
+//! call is holding, one behind a `RefCell` panics. [`SharedMpu6050`] is the static form
+//! that reports instead: [`with`](SharedMpu6050::with) sets a busy flag for the duration of
+//! its closure, and a nested `with`, from a hook, a sink or another context interrupting
+//! the call, fails at once with [`Mpu6050Error::Reentrancy`] without touching the driver.
+//! The flag is one atomic swap, targets without (Cortex-M0) have no `SharedMpu6050`. It is
+//! not a lock: nothing waits, a context finding the driver busy retries later or gives up.
+//!
+//! The [`ffi`](crate::ffi) layer has the same flag on every handle: a host callback calling
+//! back into the handle it was called from gets the [code](Mpu6050Error::code) of
+//! `Reentrancy` instead of undefined behaviour.
+//! ```
+//! use std::sync::OnceLock;
+//!
+//! use mpu6050::reentrancy::SharedMpu6050;
+//! use mpu6050::{Mpu6050Error, MpuSample};
+//! # use embedded_hal::blocking::i2c::{Write, WriteRead};
+//! # struct Bus;
+//! # impl Write for Bus {
+//! #     type Error = ();
+//! #     fn write(&mut self, _: u8, _: &[u8]) -> Result<(), ()> { Ok(()) }
+//! # }
+//! # impl WriteRead for Bus {
+//! #     type Error = ();
+//! #     fn write_read(&mut self, _: u8, _: &[u8], buf: &mut [u8]) -> Result<(), ()> {
+//! #         buf.fill(0);
+//! #         Ok(())
+//! #     }
+//! # }
+//!
+//! static MPU: OnceLock<SharedMpu6050<Bus>> = OnceLock::new();
+//!
+//! /// misbehaving: reads the driver from inside its own read
+//! fn tap(_: &MpuSample) {
+//!     let nested = MPU.get().unwrap().with(|mpu| mpu.get_temp());
+//!     assert!(matches!(nested, Err(Mpu6050Error::Reentrancy)));
+//! }
+//!
+//! let mut mpu = mpu6050::Mpu6050Builder::new().i2c(Bus).build().unwrap();
+//! mpu.set_sample_tap(Some(tap));
+//! let shared = MPU.get_or_init(|| SharedMpu6050::new(mpu));
+//! assert!(shared.with(|mpu| mpu.get_temp()).is_ok());
+//! ```
+
+#[cfg(target_has_atomic = "8")]
+use core::cell::UnsafeCell;
+#[cfg(target_has_atomic = "8")]
+use core::sync::atomic::{AtomicBool, Ordering};
+
+#[cfg(target_has_atomic = "8")]
+use crate::delay::NoDelay;
+use crate::device::{AccelRange, GyroRange};
+#[cfg(target_has_atomic = "8")]
+use crate::Mpu6050Error;
+use crate::{Mpu6050, MpuSample};
+
+/// Copy of the driver's configuration handed to callbacks instead of the driver
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct DriverInfo {
+    /// 7-bit address of the device
+    pub slave_addr: u8,
+    /// accelerometer range the sample was scaled with
+    pub accel_range: AccelRange,
+    /// gyro range the sample was scaled with
+    pub gyro_range: GyroRange,
+    /// [configuration epoch](crate::config_epoch) of the driver
+    pub config_epoch: u32,
+    /// [continuity token](crate::continuity) of the driver
+    pub continuity_token: u32,
+    /// DLPF_CFG as last written
+    pub dlpf_cfg: u8,
+    /// SMPLRT_DIV as last written
+    pub sample_rate_div: u8,
+    /// FIFO streaming is enabled, direct reads are refused
+    pub fifo_streaming: bool,
+}
+
+/// What a [`SampleObserver`](crate::hook::SampleObserver) sees: the sample and a copy of the
+/// driver's configuration, nothing leading back to the driver
+#[derive(Copy, Clone, Debug)]
+pub struct SampleContext<'a> {
+    sample: &'a MpuSample,
+    info: DriverInfo,
+}
+
+impl<'a> SampleContext<'a> {
+    #[cfg(not(feature = "minimal-pipeline"))]
+    pub(crate) fn new(sample: &'a MpuSample, info: DriverInfo) -> Self {
+        Self { sample, info }
+    }
+
+    /// the sample, after the hook
+    pub fn sample(&self) -> &'a MpuSample {
+        self.sample
+    }
+
+    /// configuration of the driver at the read
+    pub fn info(&self) -> DriverInfo {
+        self.info
+    }
+}
+
+impl<I, D> Mpu6050<I, D> {
+    /// copy of the configuration callbacks may see, see [`reentrancy`](crate::reentrancy)
+    pub fn driver_info(&self) -> DriverInfo {
+        DriverInfo {
+            slave_addr: self.slave_addr,
+            accel_range: self.accel_range,
+            gyro_range: self.gyro_range,
+            config_epoch: self.config_epoch,
+            continuity_token: self.continuity_token,
+            dlpf_cfg: self.dlpf_cfg,
+            sample_rate_div: self.sample_rate_div,
+            fifo_streaming: self.fifo_streaming,
+        }
+    }
+}
+
+/// A driver for a `static`, refusing nested access instead of deadlocking, see the
+/// [module docs](self#runtime-flag)
+#[cfg(target_has_atomic = "8")]
+pub struct SharedMpu6050<I, D = NoDelay> {
+    busy: AtomicBool,
+    mpu: UnsafeCell<Mpu6050<I, D>>,
+}
+
+// SAFETY: `with` hands out the driver to one caller at a time, the busy flag refuses every
+// other. The driver itself moves between the contexts calling `with`, hence `Send`
+#[cfg(target_has_atomic = "8")]
+unsafe impl<I: Send, D: Send> Sync for SharedMpu6050<I, D> {}
+
+/// clears the busy flag when `with` returns or unwinds
+#[cfg(target_has_atomic = "8")]
+struct Release<'a>(&'a AtomicBool);
+
+#[cfg(target_has_atomic = "8")]
+impl Drop for Release<'_> {
+    fn drop(&mut self) {
+        self.0.store(false, Ordering::Release);
+    }
+}
+
+#[cfg(target_has_atomic = "8")]
+impl<I, D> SharedMpu6050<I, D> {
+    /// shares `mpu`
+    pub fn new(mpu: Mpu6050<I, D>) -> Self {
+        Self {
+            busy: AtomicBool::new(false),
+            mpu: UnsafeCell::new(mpu),
+        }
+    }
+
+    /// `f` on the driver, [`Reentrancy`](Mpu6050Error::Reentrancy) without calling it if
+    /// another `with` is running, nested in its call stack or in a context it was
+    /// interrupted by
+    pub fn with<R, E>(
+        &self,
+        f: impl FnOnce(&mut Mpu6050<I, D>) -> Result<R, Mpu6050Error<E>>,
+    ) -> Result<R, Mpu6050Error<E>> {
+        if self.busy.swap(true, Ordering::Acquire) {
+            return Err(Mpu6050Error::Reentrancy);
+        }
+        let _release = Release(&self.busy);
+        // SAFETY: the swap above returned false, no other reference to the driver exists
+        // until `_release` clears the flag
+        f(unsafe { &mut *self.mpu.get() })
+    }
+
+    /// a `with` is running
+    pub fn is_busy(&self) -> bool {
+        self.busy.load(Ordering::Relaxed)
+    }
+
+    /// the driver, the exclusive borrow proves no `with` is running
+    pub fn get_mut(&mut self) -> &mut Mpu6050<I, D> {
+        self.mpu.get_mut()
+    }
+
+    /// the driver back
+    pub fn into_inner(self) -> Mpu6050<I, D> {
+        self.mpu.into_inner()
+    }
+}
@@ -0,0 +1,1156 @@
+//! Typed register access, the safe counterpart to the raw `u8` address calls.
+//!
+//! [`Register`] enumerates the register map, so a register and a value can no longer be
+//! swapped: `write_register(0x01, Register::PWR_MGMT_1)` does not compile. Every access is
+//! checked before the bus is touched and fails with [`Mpu6050Error::InvalidRegisterAccess`]:
+//! * writes to read-only registers ([`RegisterAccess::ReadOnly`])
+//! * single byte access to half of a [`RegisterPair`], use the `_word` calls for those
+//! * word access to anything but the high register of a pair
+//!
+//! | call | transactions |
+//! |:---|:---|
+//! | [`read_register`](Mpu6050::read_register), [`write_register`](Mpu6050::write_register) | 1 |
+//! | [`modify_register`](Mpu6050::modify_register), `write_register_bit`, `write_register_field` | 1 read, 1 write |
+//! | [`read_field`](Mpu6050::read_field), [`read_flag`](Mpu6050::read_flag), [`read_fields`](Mpu6050::read_fields), [`write_fields`](Mpu6050::write_fields) | 1 |
+//! | [`write_field`](Mpu6050::write_field), [`modify_fields`](Mpu6050::modify_fields) | 1 read, 1 write |
+//! | [`read_register_word`](Mpu6050::read_register_word), [`write_register_word`](Mpu6050::write_register_word) | 1, both bytes |
+//! | [`read_registers`](Mpu6050::read_registers) | 1 burst |
+//! | [`read_typed`](Mpu6050::read_typed), [`write_typed`](Mpu6050::write_typed) | 1 |
+//!
+//! The typed calls end in the same bus primitives as the raw ones: traces, operation
+//! counters and the connection monitor see both alike. Like the raw calls they bypass the
+//! settings cached by the driver, change ranges, DLPF and sample rate with their setters.
+//!
+//! The fields of a register are associated constants of its register struct in
+//! [`device`](crate::device), `ACCEL_CONFIG::ACCEL_HPF_FIELD` is a [`Field<ACCEL_CONFIG>`]
+//! and [`write_field`](Mpu6050::write_field) takes it to ACCEL_CONFIG and nowhere else. The
+//! value converts from `u8`, `bool` and the register's own enums, see [`FieldValue`]. The
+//! driver accesses fields this way only; `write_register_bit` and `write_register_field`
+//! remain as the escape hatch for bits without a typed field.
+//!
+//! [`TypedRegister`] is implemented by the register structs of [`device`](crate::device)
+//! whose whole content has a typed value:
+//! ```
+//! use mpu6050::device::{AccelRange, ACCEL_CONFIG, ACCEL_HPF, CLKSEL, PWR_MGMT_1};
+//! use mpu6050::register::{
+//!     AccelConfigValue, PowerManagement1, Register, RegisterSpec, TypedRegister,
+//! };
+//!
+//! let value = AccelConfigValue {
+//!     self_test: [false; 3],
+//!     range: AccelRange::G8,
+//!     hpf: ACCEL_HPF::_5,
+//! };
+//! assert_eq!(ACCEL_CONFIG::REGISTER, Register::ACCEL_CONFIG);
+//! assert_eq!(ACCEL_CONFIG::to_byte(value), 0b0001_0001);
+//! assert_eq!(ACCEL_CONFIG::from_byte(0b0001_0001), value);
+//!
+//! let awake = PWR_MGMT_1::from_byte(0x01);
+//! assert!(!awake.sleep);
+//! assert_eq!(awake.clksel, CLKSEL::GXAXIS);
+//! ```
+
+use core::fmt;
+use core::marker::PhantomData;
+
+#[cfg(feature = "driver")]
+use embedded_hal::blocking::i2c::{Write, WriteRead};
+
+#[cfg(feature = "driver")]
+use crate::bits;
+#[cfg(feature = "driver")]
+use crate::bus::DriverOp;
+use crate::device::*;
+#[cfg(feature = "driver")]
+use crate::{Mpu6050, Mpu6050Error};
+
+/// Whether a register can be written
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum RegisterAccess {
+    /// status and data registers
+    ReadOnly,
+    /// configuration registers
+    ReadWrite,
+}
+
+macro_rules! registers {
+    ($($name:ident = $addr:literal, $access:ident, $doc:literal;)*) => {
+        /// Registers of the register map (rev 4.2) and the accel offsets of the MPU6500
+        /// family, named as in the register map
+        #[allow(non_camel_case_types)]
+        #[derive(Copy, Clone, Debug, Eq, PartialEq, Hash, PartialOrd, Ord)]
+        #[repr(u8)]
+        pub enum Register {
+            $(
+                #[doc = $doc]
+                $name = $addr,
+            )*
+        }
+
+        impl Register {
+            /// all registers, sorted by address
+            pub const ALL: &'static [Register] = &[$(Register::$name,)*];
+
+            /// name as in the register map
+            pub const fn name(self) -> &'static str {
+                match self {
+                    $(Register::$name => stringify!($name),)*
+                }
+            }
+
+            /// read-only or writable
+            pub const fn access(self) -> RegisterAccess {
+                match self {
+                    $(Register::$name => RegisterAccess::$access,)*
+                }
+            }
+        }
+    };
+}
+
+registers! {
+    XA_OFFS_H = 0x06, ReadWrite, "accel X offset, high byte";
+    XA_OFFS_L_TC = 0x07, ReadWrite, "accel X offset, low byte";
+    YA_OFFS_H = 0x08, ReadWrite, "accel Y offset, high byte";
+    YA_OFFS_L_TC = 0x09, ReadWrite, "accel Y offset, low byte";
+    ZA_OFFS_H = 0x0a, ReadWrite, "accel Z offset, high byte";
+    ZA_OFFS_L_TC = 0x0b, ReadWrite, "accel Z offset, low byte";
+    SELF_TEST_X = 0x0d, ReadWrite, "self test X";
+    SELF_TEST_Y = 0x0e, ReadWrite, "self test Y";
+    SELF_TEST_Z = 0x0f, ReadWrite, "self test Z";
+    SELF_TEST_A = 0x10, ReadWrite, "self test accel";
+    XG_OFFS_USRH = 0x13, ReadWrite, "gyro X offset, high byte";
+    XG_OFFS_USRL = 0x14, ReadWrite, "gyro X offset, low byte";
+    YG_OFFS_USRH = 0x15, ReadWrite, "gyro Y offset, high byte";
+    YG_OFFS_USRL = 0x16, ReadWrite, "gyro Y offset, low byte";
+    ZG_OFFS_USRH = 0x17, ReadWrite, "gyro Z offset, high byte";
+    ZG_OFFS_USRL = 0x18, ReadWrite, "gyro Z offset, low byte";
+    SMPLRT_DIV = 0x19, ReadWrite, "sample rate divider";
+    CONFIG = 0x1a, ReadWrite, "FSYNC and DLPF configuration";
+    GYRO_CONFIG = 0x1b, ReadWrite, "gyro self test and range";
+    ACCEL_CONFIG = 0x1c, ReadWrite, "accel self test, range and high pass filter";
+    MOT_THR = 0x1f, ReadWrite, "motion detection threshold";
+    MOT_DUR = 0x20, ReadWrite, "motion detection duration";
+    FIFO_EN = 0x23, ReadWrite, "FIFO sources";
+    I2C_MST_CTRL = 0x24, ReadWrite, "I2C master control";
+    I2C_SLV0_ADDR = 0x25, ReadWrite, "aux slave 0 address";
+    I2C_SLV0_REG = 0x26, ReadWrite, "aux slave 0 register";
+    I2C_SLV0_CTRL = 0x27, ReadWrite, "aux slave 0 control";
+    I2C_SLV1_ADDR = 0x28, ReadWrite, "aux slave 1 address";
+    I2C_SLV1_REG = 0x29, ReadWrite, "aux slave 1 register";
+    I2C_SLV1_CTRL = 0x2a, ReadWrite, "aux slave 1 control";
+    I2C_SLV2_ADDR = 0x2b, ReadWrite, "aux slave 2 address";
+    I2C_SLV2_REG = 0x2c, ReadWrite, "aux slave 2 register";
+    I2C_SLV2_CTRL = 0x2d, ReadWrite, "aux slave 2 control";
+    I2C_SLV3_ADDR = 0x2e, ReadWrite, "aux slave 3 address";
+    I2C_SLV3_REG = 0x2f, ReadWrite, "aux slave 3 register";
+    I2C_SLV3_CTRL = 0x30, ReadWrite, "aux slave 3 control";
+    I2C_SLV4_ADDR = 0x31, ReadWrite, "aux slave 4 address";
+    I2C_SLV4_REG = 0x32, ReadWrite, "aux slave 4 register";
+    I2C_SLV4_DO = 0x33, ReadWrite, "aux slave 4 data out";
+    I2C_SLV4_CTRL = 0x34, ReadWrite, "aux slave 4 control";
+    I2C_SLV4_DI = 0x35, ReadOnly, "aux slave 4 data in";
+    I2C_MST_STATUS = 0x36, ReadOnly, "I2C master status";
+    INT_PIN_CFG = 0x37, ReadWrite, "interrupt pin configuration";
+    INT_ENABLE = 0x38, ReadWrite, "interrupt enable";
+    INT_STATUS = 0x3a, ReadOnly, "interrupt status, cleared on read";
+    ACCEL_XOUT_H = 0x3b, ReadOnly, "accel X, high byte";
+    ACCEL_XOUT_L = 0x3c, ReadOnly, "accel X, low byte";
+    ACCEL_YOUT_H = 0x3d, ReadOnly, "accel Y, high byte";
+    ACCEL_YOUT_L = 0x3e, ReadOnly, "accel Y, low byte";
+    ACCEL_ZOUT_H = 0x3f, ReadOnly, "accel Z, high byte";
+    ACCEL_ZOUT_L = 0x40, ReadOnly, "accel Z, low byte";
+    TEMP_OUT_H = 0x41, ReadOnly, "temperature, high byte";
+    TEMP_OUT_L = 0x42, ReadOnly, "temperature, low byte";
+    GYRO_XOUT_H = 0x43, ReadOnly, "gyro X, high byte";
+    GYRO_XOUT_L = 0x44, ReadOnly, "gyro X, low byte";
+    GYRO_YOUT_H = 0x45, ReadOnly, "gyro Y, high byte";
+    GYRO_YOUT_L = 0x46, ReadOnly, "gyro Y, low byte";
+    GYRO_ZOUT_H = 0x47, ReadOnly, "gyro Z, high byte";
+    GYRO_ZOUT_L = 0x48, ReadOnly, "gyro Z, low byte";
+    EXT_SENS_DATA_00 = 0x49, ReadOnly, "aux data 0";
+    EXT_SENS_DATA_01 = 0x4a, ReadOnly, "aux data 1";
+    EXT_SENS_DATA_02 = 0x4b, ReadOnly, "aux data 2";
+    EXT_SENS_DATA_03 = 0x4c, ReadOnly, "aux data 3";
+    EXT_SENS_DATA_04 = 0x4d, ReadOnly, "aux data 4";
+    EXT_SENS_DATA_05 = 0x4e, ReadOnly, "aux data 5";
+    EXT_SENS_DATA_06 = 0x4f, ReadOnly, "aux data 6";
+    EXT_SENS_DATA_07 = 0x50, ReadOnly, "aux data 7";
+    EXT_SENS_DATA_08 = 0x51, ReadOnly, "aux data 8";
+    EXT_SENS_DATA_09 = 0x52, ReadOnly, "aux data 9";
+    EXT_SENS_DATA_10 = 0x53, ReadOnly, "aux data 10";
+    EXT_SENS_DATA_11 = 0x54, ReadOnly, "aux data 11";
+    EXT_SENS_DATA_12 = 0x55, ReadOnly, "aux data 12";
+    EXT_SENS_DATA_13 = 0x56, ReadOnly, "aux data 13";
+    EXT_SENS_DATA_14 = 0x57, ReadOnly, "aux data 14";
+    EXT_SENS_DATA_15 = 0x58, ReadOnly, "aux data 15";
+    EXT_SENS_DATA_16 = 0x59, ReadOnly, "aux data 16";
+    EXT_SENS_DATA_17 = 0x5a, ReadOnly, "aux data 17";
+    EXT_SENS_DATA_18 = 0x5b, ReadOnly, "aux data 18";
+    EXT_SENS_DATA_19 = 0x5c, ReadOnly, "aux data 19";
+    EXT_SENS_DATA_20 = 0x5d, ReadOnly, "aux data 20";
+    EXT_SENS_DATA_21 = 0x5e, ReadOnly, "aux data 21";
+    EXT_SENS_DATA_22 = 0x5f, ReadOnly, "aux data 22";
+    EXT_SENS_DATA_23 = 0x60, ReadOnly, "aux data 23";
+    MOT_DETECT_STATUS = 0x61, ReadOnly, "motion detection status";
+    I2C_SLV0_DO = 0x63, ReadWrite, "aux slave 0 data out";
+    I2C_SLV1_DO = 0x64, ReadWrite, "aux slave 1 data out";
+    I2C_SLV2_DO = 0x65, ReadWrite, "aux slave 2 data out";
+    I2C_SLV3_DO = 0x66, ReadWrite, "aux slave 3 data out";
+    I2C_MST_DELAY_CTRL = 0x67, ReadWrite, "I2C master delay control";
+    SIGNAL_PATH_RESET = 0x68, ReadWrite, "signal path reset";
+    MOT_DETECT_CONTROL = 0x69, ReadWrite, "motion detection control";
+    USER_CTRL = 0x6a, ReadWrite, "user control";
+    PWR_MGMT_1 = 0x6b, ReadWrite, "power management 1";
+    PWR_MGMT_2 = 0x6c, ReadWrite, "power management 2";
+    FIFO_COUNTH = 0x72, ReadOnly, "FIFO count, high byte";
+    FIFO_COUNTL = 0x73, ReadOnly, "FIFO count, low byte";
+    FIFO_R_W = 0x74, ReadWrite, "FIFO data, the address does not increment";
+    WHO_AM_I = 0x75, ReadOnly, "chip id";
+    XA_OFFSET_H = 0x77, ReadWrite, "accel X offset, high byte, MPU6500 family";
+    XA_OFFSET_L = 0x78, ReadWrite, "accel X offset, low byte, MPU6500 family";
+    YA_OFFSET_H = 0x7a, ReadWrite, "accel Y offset, high byte, MPU6500 family";
+    YA_OFFSET_L = 0x7b, ReadWrite, "accel Y offset, low byte, MPU6500 family";
+    ZA_OFFSET_H = 0x7d, ReadWrite, "accel Z offset, high byte, MPU6500 family";
+    ZA_OFFSET_L = 0x7e, ReadWrite, "accel Z offset, low byte, MPU6500 family";
+}
+
+impl Register {
+    /// register address
+    pub const fn addr(self) -> u8 {
+        self as u8
+    }
+
+    /// register at `addr`, None for addresses outside the register map
+    pub fn from_addr(addr: u8) -> Option<Register> {
+        Self::ALL
+            .binary_search_by_key(&addr, |reg| reg.addr())
+            .ok()
+            .map(|index| Self::ALL[index])
+    }
+
+    /// Register of an address from the crate's own tables, see [`from_addr`](Self::from_addr)
+    pub(crate) fn known(addr: u8) -> Register {
+        Self::from_addr(addr).expect("address of the register map")
+    }
+
+    /// pair this register is half of
+    pub fn pair(self) -> Option<&'static RegisterPair> {
+        register_pair(self.addr())
+    }
+
+    /// EXT_SENS_DATA register `n`, None past the 24th
+    pub fn ext_sens_data(n: u8) -> Option<Register> {
+        (n < EXT_SENS_DATA_LEN).then(|| Self::known(EXT_SENS_DATA_00 + n))
+    }
+
+    /// ADDR, REG and CTRL of aux slave `n`, None past slave 3
+    pub fn i2c_slv(n: u8) -> Option<[Register; 3]> {
+        (n < 4).then(|| {
+            [
+                Self::known(I2C_SLV::addr_reg(n)),
+                Self::known(I2C_SLV::reg_reg(n)),
+                Self::known(I2C_SLV::ctrl_reg(n)),
+            ]
+        })
+    }
+
+    /// Checks a single byte access
+    pub fn check_byte(self, write: bool) -> Result<(), AccessViolation> {
+        if write && self.access() == RegisterAccess::ReadOnly {
+            return Err(AccessViolation::ReadOnly);
+        }
+        if self.pair().is_some() {
+            return Err(AccessViolation::SplitsPair);
+        }
+        Ok(())
+    }
+
+    /// Checks a word access, `self` must be the high register of a pair
+    pub fn check_word(self, write: bool) -> Result<(), AccessViolation> {
+        if write && self.access() == RegisterAccess::ReadOnly {
+            return Err(AccessViolation::ReadOnly);
+        }
+        match self.pair() {
+            Some(pair) if pair.high == self.addr() => Ok(()),
+            _ => Err(AccessViolation::NotAPair),
+        }
+    }
+
+    /// Checks a read of `len` bytes starting at `self`: neither end may split a pair.
+    /// [`FIFO_R_W`](Register::FIFO_R_W) does not increment and takes any length
+    pub fn check_burst(self, len: usize) -> Result<(), AccessViolation> {
+        if self == Register::FIFO_R_W || len == 0 {
+            return Ok(());
+        }
+        let last = self.addr().wrapping_add((len - 1) as u8);
+        let splits_start = self.pair().is_some_and(|pair| pair.high != self.addr());
+        let splits_end = register_pair(last).is_some_and(|pair| pair.high == last);
+        if splits_start || splits_end {
+            return Err(AccessViolation::SplitsPair);
+        }
+        Ok(())
+    }
+}
+
+impl From<Register> for u8 {
+    fn from(reg: Register) -> u8 {
+        reg.addr()
+    }
+}
+
+impl fmt::Display for Register {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} (0x{:02x})", self.name(), self.addr())
+    }
+}
+
+/// Why a typed access was refused, nothing was transferred
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum AccessViolation {
+    /// write to a read-only register
+    ReadOnly,
+    /// single byte access to half of a register pair
+    SplitsPair,
+    /// word access to a register that is not the high register of a pair
+    NotAPair,
+}
+
+impl fmt::Display for AccessViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            AccessViolation::ReadOnly => "register is read-only",
+            AccessViolation::SplitsPair => "splits a register pair, use the word access",
+            AccessViolation::NotAPair => "not the high register of a pair",
+        })
+    }
+}
+
+/// Register marker of [`device`](crate::device) with its place in the register map. The
+/// [`Field`]s of a marker only work on its register
+pub trait RegisterSpec {
+    /// the register
+    const REGISTER: Register;
+}
+
+/// Register whose whole content has a typed value, implemented by the register structs of
+/// [`device`](crate::device)
+pub trait TypedRegister: RegisterSpec {
+    /// typed content
+    type Value: Copy;
+    /// register byte of `value`
+    fn to_byte(value: Self::Value) -> u8;
+    /// typed content of a register byte
+    fn from_byte(byte: u8) -> Self::Value;
+}
+
+/// Aux slave register family, one register per slave 0 to 3
+pub trait SlaveRegisterSpec {
+    /// the register of slave `n`, None past slave 3
+    fn register(n: u8) -> Option<Register>;
+}
+
+macro_rules! register_specs {
+    ($($marker:ident => $reg:ident,)*) => {
+        $(
+            impl RegisterSpec for $marker {
+                const REGISTER: Register = Register::$reg;
+            }
+        )*
+    };
+}
+
+register_specs! {
+    CONFIG => CONFIG,
+    GYRO_CONFIG => GYRO_CONFIG,
+    ACCEL_CONFIG => ACCEL_CONFIG,
+    FIFO_EN_BITS => FIFO_EN,
+    I2C_MST_CTRL => I2C_MST_CTRL,
+    INT_PIN_CFG => INT_PIN_CFG,
+    INT_ENABLE => INT_ENABLE,
+    INT_STATUS => INT_STATUS,
+    MOT_DETECT_STATUS => MOT_DETECT_STATUS,
+    SIGNAL_PATH_RESET => SIGNAL_PATH_RESET,
+    MOT_DETECT_CONTROL => MOT_DETECT_CONTROL,
+    USER_CTRL => USER_CTRL,
+    PWR_MGMT_1 => PWR_MGMT_1,
+    PWR_MGMT_2 => PWR_MGMT_2,
+}
+
+/// I2C_SLVn_ADDR of aux slaves 0 to 3, owner of their fields
+#[allow(non_camel_case_types)]
+#[derive(Copy, Clone, Debug)]
+pub struct I2C_SLV_ADDR;
+
+/// I2C_SLVn_CTRL of aux slaves 0 to 3, owner of their fields
+#[allow(non_camel_case_types)]
+#[derive(Copy, Clone, Debug)]
+pub struct I2C_SLV_CTRL;
+
+impl SlaveRegisterSpec for I2C_SLV_ADDR {
+    fn register(n: u8) -> Option<Register> {
+        Register::i2c_slv(n).map(|[addr, _, _]| addr)
+    }
+}
+
+impl SlaveRegisterSpec for I2C_SLV_CTRL {
+    fn register(n: u8) -> Option<Register> {
+        Register::i2c_slv(n).map(|[_, _, ctrl]| ctrl)
+    }
+}
+
+/// Bit field of register `R`. The field constants are associated items of the register
+/// markers, so a field can only be used with its own register:
+/// ```
+/// use mpu6050::device::{AccelRange, ACCEL_CONFIG};
+/// use mpu6050::register::RegisterValue;
+///
+/// let value = RegisterValue::<ACCEL_CONFIG>::ZERO
+///     .with(ACCEL_CONFIG::FS_SEL_FIELD, AccelRange::G8)
+///     .with(ACCEL_CONFIG::XA_ST_FIELD, true);
+/// assert_eq!(value.byte(), 0b1001_0000);
+/// assert_eq!(value.field(ACCEL_CONFIG::FS_SEL_FIELD), AccelRange::G8 as u8);
+/// ```
+/// ```compile_fail
+/// use mpu6050::device::{ACCEL_CONFIG, GYRO_CONFIG};
+/// use mpu6050::register::RegisterValue;
+///
+/// RegisterValue::<GYRO_CONFIG>::ZERO.with(ACCEL_CONFIG::ACCEL_HPF_FIELD, 1);
+/// ```
+pub struct Field<R> {
+    block: BitBlock,
+    register: PhantomData<fn() -> R>,
+}
+
+impl<R> Field<R> {
+    const fn new(block: BitBlock) -> Self {
+        Self {
+            block,
+            register: PhantomData,
+        }
+    }
+
+    const fn flag(bit: u8) -> Self {
+        Self::new(BitBlock { bit, length: 1 })
+    }
+
+    /// highest bit and width
+    pub const fn bit_block(self) -> BitBlock {
+        self.block
+    }
+
+    /// the field's bits within the register byte
+    pub const fn mask(self) -> u8 {
+        let shift = self.block.bit + 1 - self.block.length;
+        (((1u16 << self.block.length) - 1) as u8) << shift
+    }
+
+    const fn shift(self) -> u8 {
+        self.block.bit + 1 - self.block.length
+    }
+}
+
+impl<R: RegisterSpec> Field<R> {
+    /// the register of the field
+    pub const fn register(self) -> Register {
+        R::REGISTER
+    }
+}
+
+impl<R> Clone for Field<R> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<R> Copy for Field<R> {}
+
+impl<R> PartialEq for Field<R> {
+    fn eq(&self, other: &Self) -> bool {
+        self.block == other.block
+    }
+}
+
+impl<R> Eq for Field<R> {}
+
+impl<R> fmt::Debug for Field<R> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Field")
+            .field("bit", &self.block.bit)
+            .field("length", &self.block.length)
+            .finish()
+    }
+}
+
+/// Value of a field of register `R`: any `u8` or `bool`, masked to the field width, and the
+/// typed values of `R`'s fields, a [`GyroRange`] only converts for GYRO_CONFIG
+pub struct FieldValue<R> {
+    value: u8,
+    register: PhantomData<fn() -> R>,
+}
+
+impl<R> FieldValue<R> {
+    const fn new(value: u8) -> Self {
+        Self {
+            value,
+            register: PhantomData,
+        }
+    }
+
+    /// the raw value
+    pub const fn get(self) -> u8 {
+        self.value
+    }
+}
+
+impl<R> From<u8> for FieldValue<R> {
+    fn from(value: u8) -> Self {
+        Self::new(value)
+    }
+}
+
+impl<R> From<bool> for FieldValue<R> {
+    fn from(value: bool) -> Self {
+        Self::new(value as u8)
+    }
+}
+
+macro_rules! field_values {
+    ($($value:ty => $marker:ident,)*) => {
+        $(
+            impl From<$value> for FieldValue<$marker> {
+                fn from(value: $value) -> Self {
+                    Self::new(value as u8)
+                }
+            }
+        )*
+    };
+}
+
+field_values! {
+    EXT_SYNC => CONFIG,
+    GyroRange => GYRO_CONFIG,
+    AccelRange => ACCEL_CONFIG,
+    ACCEL_HPF => ACCEL_CONFIG,
+    CLKSEL => PWR_MGMT_1,
+    LP_WAKE_CTRL => PWR_MGMT_2,
+}
+
+/// Content of register `R`, read and built field by field with the fields of `R`
+pub struct RegisterValue<R> {
+    byte: u8,
+    register: PhantomData<fn() -> R>,
+}
+
+impl<R> RegisterValue<R> {
+    /// all bits clear
+    pub const ZERO: Self = Self::from_byte(0);
+
+    /// content `byte`
+    pub const fn from_byte(byte: u8) -> Self {
+        Self {
+            byte,
+            register: PhantomData,
+        }
+    }
+
+    /// the register byte
+    pub const fn byte(self) -> u8 {
+        self.byte
+    }
+
+    /// value of `field`
+    pub const fn field(self, field: Field<R>) -> u8 {
+        (self.byte & field.mask()) >> field.shift()
+    }
+
+    /// true if any bit of `field` is set
+    pub const fn flag(self, field: Field<R>) -> bool {
+        self.byte & field.mask() != 0
+    }
+
+    /// this content with `field` set to `value`
+    pub fn with(self, field: Field<R>, value: impl Into<FieldValue<R>>) -> Self {
+        let placed = (value.into().get() << field.shift()) & field.mask();
+        Self::from_byte(self.byte & !field.mask() | placed)
+    }
+}
+
+impl<R: RegisterSpec> RegisterValue<R> {
+    /// power-on content, see [`reset_value`]
+    pub fn reset() -> Self {
+        Self::from_byte(reset_value(R::REGISTER.addr()).unwrap_or(0))
+    }
+}
+
+impl<R> Clone for RegisterValue<R> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<R> Copy for RegisterValue<R> {}
+
+impl<R> PartialEq for RegisterValue<R> {
+    fn eq(&self, other: &Self) -> bool {
+        self.byte == other.byte
+    }
+}
+
+impl<R> Eq for RegisterValue<R> {}
+
+impl<R> fmt::Debug for RegisterValue<R> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "RegisterValue(0x{:02x})", self.byte)
+    }
+}
+
+macro_rules! fields {
+    ($($marker:ident {
+        $($(#[doc = $doc:literal])* $name:ident = $kind:ident($($arg:expr),*);)*
+    })*) => {
+        $(
+            impl $marker {
+                $(
+                    $(#[doc = $doc])*
+                    pub const $name: Field<$marker> = Field::$kind($($arg),*);
+                )*
+            }
+        )*
+    };
+}
+
+fields! {
+    CONFIG {
+        /// [`EXT_SYNC_SET`](CONFIG::EXT_SYNC_SET)
+        EXT_SYNC_SET_FIELD = new(CONFIG::EXT_SYNC_SET);
+        /// [`DLPF_CFG`](CONFIG::DLPF_CFG)
+        DLPF_CFG_FIELD = new(CONFIG::DLPF_CFG);
+    }
+    GYRO_CONFIG {
+        /// [`XG_ST`](GYRO_CONFIG::XG_ST)
+        XG_ST_FIELD = flag(GYRO_CONFIG::XG_ST);
+        /// [`YG_ST`](GYRO_CONFIG::YG_ST)
+        YG_ST_FIELD = flag(GYRO_CONFIG::YG_ST);
+        /// [`ZG_ST`](GYRO_CONFIG::ZG_ST)
+        ZG_ST_FIELD = flag(GYRO_CONFIG::ZG_ST);
+        /// [`FS_SEL`](GYRO_CONFIG::FS_SEL)
+        FS_SEL_FIELD = new(GYRO_CONFIG::FS_SEL);
+    }
+    ACCEL_CONFIG {
+        /// [`XA_ST`](ACCEL_CONFIG::XA_ST)
+        XA_ST_FIELD = flag(ACCEL_CONFIG::XA_ST);
+        /// [`YA_ST`](ACCEL_CONFIG::YA_ST)
+        YA_ST_FIELD = flag(ACCEL_CONFIG::YA_ST);
+        /// [`ZA_ST`](ACCEL_CONFIG::ZA_ST)
+        ZA_ST_FIELD = flag(ACCEL_CONFIG::ZA_ST);
+        /// [`FS_SEL`](ACCEL_CONFIG::FS_SEL)
+        FS_SEL_FIELD = new(ACCEL_CONFIG::FS_SEL);
+        /// [`ACCEL_HPF`](ACCEL_CONFIG::ACCEL_HPF)
+        ACCEL_HPF_FIELD = new(ACCEL_CONFIG::ACCEL_HPF);
+    }
+    FIFO_EN_BITS {
+        /// [`TEMP`](FIFO_EN_BITS::TEMP)
+        TEMP_FIELD = flag(FIFO_EN_BITS::TEMP);
+        /// [`XG`](FIFO_EN_BITS::XG)
+        XG_FIELD = flag(FIFO_EN_BITS::XG);
+        /// [`YG`](FIFO_EN_BITS::YG)
+        YG_FIELD = flag(FIFO_EN_BITS::YG);
+        /// [`ZG`](FIFO_EN_BITS::ZG)
+        ZG_FIELD = flag(FIFO_EN_BITS::ZG);
+        /// [`ACCEL`](FIFO_EN_BITS::ACCEL)
+        ACCEL_FIELD = flag(FIFO_EN_BITS::ACCEL);
+        /// [`SLV2`](FIFO_EN_BITS::SLV2)
+        SLV2_FIELD = flag(FIFO_EN_BITS::SLV2);
+        /// [`SLV1`](FIFO_EN_BITS::SLV1)
+        SLV1_FIELD = flag(FIFO_EN_BITS::SLV1);
+        /// [`SLV0`](FIFO_EN_BITS::SLV0)
+        SLV0_FIELD = flag(FIFO_EN_BITS::SLV0);
+    }
+    I2C_MST_CTRL {
+        /// [`SLV_3_FIFO_EN`](I2C_MST_CTRL::SLV_3_FIFO_EN)
+        SLV_3_FIFO_EN_FIELD = flag(I2C_MST_CTRL::SLV_3_FIFO_EN);
+    }
+    I2C_SLV_ADDR {
+        /// [`RW`](I2C_SLV::RW)
+        RW_FIELD = flag(I2C_SLV::RW);
+        /// [`ADDR`](I2C_SLV::ADDR)
+        ADDR_FIELD = new(I2C_SLV::ADDR);
+    }
+    I2C_SLV_CTRL {
+        /// [`EN`](I2C_SLV::EN)
+        EN_FIELD = flag(I2C_SLV::EN);
+        /// [`BYTE_SW`](I2C_SLV::BYTE_SW)
+        BYTE_SW_FIELD = flag(I2C_SLV::BYTE_SW);
+        /// [`REG_DIS`](I2C_SLV::REG_DIS)
+        REG_DIS_FIELD = flag(I2C_SLV::REG_DIS);
+        /// [`GRP`](I2C_SLV::GRP)
+        GRP_FIELD = flag(I2C_SLV::GRP);
+        /// [`LEN`](I2C_SLV::LEN)
+        LEN_FIELD = new(I2C_SLV::LEN);
+    }
+    INT_PIN_CFG {
+        /// [`INT_LEVEL`](INT_PIN_CFG::INT_LEVEL)
+        INT_LEVEL_FIELD = flag(INT_PIN_CFG::INT_LEVEL);
+        /// [`INT_OPEN`](INT_PIN_CFG::INT_OPEN)
+        INT_OPEN_FIELD = flag(INT_PIN_CFG::INT_OPEN);
+        /// [`LATCH_INT_EN`](INT_PIN_CFG::LATCH_INT_EN)
+        LATCH_INT_EN_FIELD = flag(INT_PIN_CFG::LATCH_INT_EN);
+        /// [`INT_RD_CLEAR`](INT_PIN_CFG::INT_RD_CLEAR)
+        INT_RD_CLEAR_FIELD = flag(INT_PIN_CFG::INT_RD_CLEAR);
+        /// [`FSYNC_INT_LEVEL`](INT_PIN_CFG::FSYNC_INT_LEVEL)
+        FSYNC_INT_LEVEL_FIELD = flag(INT_PIN_CFG::FSYNC_INT_LEVEL);
+        /// [`FSYNC_INT_EN`](INT_PIN_CFG::FSYNC_INT_EN)
+        FSYNC_INT_EN_FIELD = flag(INT_PIN_CFG::FSYNC_INT_EN);
+        /// [`I2C_BYPASS_EN`](INT_PIN_CFG::I2C_BYPASS_EN)
+        I2C_BYPASS_EN_FIELD = flag(INT_PIN_CFG::I2C_BYPASS_EN);
+        /// [`CLKOUT_EN`](INT_PIN_CFG::CLKOUT_EN)
+        CLKOUT_EN_FIELD = flag(INT_PIN_CFG::CLKOUT_EN);
+    }
+    INT_ENABLE {
+        /// [`FF_EN`](INT_ENABLE::FF_EN)
+        FF_EN_FIELD = flag(INT_ENABLE::FF_EN);
+        /// [`MOT_EN`](INT_ENABLE::MOT_EN)
+        MOT_EN_FIELD = flag(INT_ENABLE::MOT_EN);
+        /// [`ZMOT_EN`](INT_ENABLE::ZMOT_EN)
+        ZMOT_EN_FIELD = flag(INT_ENABLE::ZMOT_EN);
+        /// [`FIFO_OFLOW_END`](INT_ENABLE::FIFO_OFLOW_END)
+        FIFO_OFLOW_EN_FIELD = flag(INT_ENABLE::FIFO_OFLOW_END);
+        /// [`I2C_MST_INT_EN`](INT_ENABLE::I2C_MST_INT_EN)
+        I2C_MST_INT_EN_FIELD = flag(INT_ENABLE::I2C_MST_INT_EN);
+        /// [`DATA_RDY_EN`](INT_ENABLE::DATA_RDY_EN)
+        DATA_RDY_EN_FIELD = flag(INT_ENABLE::DATA_RDY_EN);
+    }
+    INT_STATUS {
+        /// [`FF_INT`](INT_STATUS::FF_INT)
+        FF_INT_FIELD = flag(INT_STATUS::FF_INT);
+        /// [`MOT_INT`](INT_STATUS::MOT_INT)
+        MOT_INT_FIELD = flag(INT_STATUS::MOT_INT);
+        /// [`ZMOT_INT`](INT_STATUS::ZMOT_INT)
+        ZMOT_INT_FIELD = flag(INT_STATUS::ZMOT_INT);
+        /// [`FIFO_OFLOW_INT`](INT_STATUS::FIFO_OFLOW_INT)
+        FIFO_OFLOW_INT_FIELD = flag(INT_STATUS::FIFO_OFLOW_INT);
+        /// [`I2C_MSF_INT`](INT_STATUS::I2C_MSF_INT)
+        I2C_MST_INT_FIELD = flag(INT_STATUS::I2C_MSF_INT);
+        /// [`DATA_RDY_INT`](INT_STATUS::DATA_RDY_INT)
+        DATA_RDY_INT_FIELD = flag(INT_STATUS::DATA_RDY_INT);
+    }
+    SIGNAL_PATH_RESET {
+        /// [`GYRO_RESET`](SIGNAL_PATH_RESET::GYRO_RESET)
+        GYRO_RESET_FIELD = flag(SIGNAL_PATH_RESET::GYRO_RESET);
+        /// [`ACCEL_RESET`](SIGNAL_PATH_RESET::ACCEL_RESET)
+        ACCEL_RESET_FIELD = flag(SIGNAL_PATH_RESET::ACCEL_RESET);
+        /// [`TEMP_RESET`](SIGNAL_PATH_RESET::TEMP_RESET)
+        TEMP_RESET_FIELD = flag(SIGNAL_PATH_RESET::TEMP_RESET);
+    }
+    MOT_DETECT_CONTROL {
+        /// [`ACCEL_ON_DELAY`](MOT_DETECT_CONTROL::ACCEL_ON_DELAY)
+        ACCEL_ON_DELAY_FIELD = new(MOT_DETECT_CONTROL::ACCEL_ON_DELAY);
+        /// [`FF_COUNT`](MOT_DETECT_CONTROL::FF_COUNT)
+        FF_COUNT_FIELD = new(MOT_DETECT_CONTROL::FF_COUNT);
+        /// [`MOT_COUNT`](MOT_DETECT_CONTROL::MOT_COUNT)
+        MOT_COUNT_FIELD = new(MOT_DETECT_CONTROL::MOT_COUNT);
+    }
+    USER_CTRL {
+        /// [`FIFO_EN`](USER_CTRL::FIFO_EN)
+        FIFO_EN_FIELD = flag(USER_CTRL::FIFO_EN);
+        /// [`I2C_MST_EN`](USER_CTRL::I2C_MST_EN)
+        I2C_MST_EN_FIELD = flag(USER_CTRL::I2C_MST_EN);
+        /// [`I2C_IF_DIS`](USER_CTRL::I2C_IF_DIS)
+        I2C_IF_DIS_FIELD = flag(USER_CTRL::I2C_IF_DIS);
+        /// [`FIFO_RESET`](USER_CTRL::FIFO_RESET)
+        FIFO_RESET_FIELD = flag(USER_CTRL::FIFO_RESET);
+        /// [`I2C_MST_RESET`](USER_CTRL::I2C_MST_RESET)
+        I2C_MST_RESET_FIELD = flag(USER_CTRL::I2C_MST_RESET);
+        /// [`SIG_COND_RESET`](USER_CTRL::SIG_COND_RESET)
+        SIG_COND_RESET_FIELD = flag(USER_CTRL::SIG_COND_RESET);
+    }
+    PWR_MGMT_1 {
+        /// [`DEVICE_RESET`](PWR_MGMT_1::DEVICE_RESET)
+        DEVICE_RESET_FIELD = flag(PWR_MGMT_1::DEVICE_RESET);
+        /// [`SLEEP`](PWR_MGMT_1::SLEEP)
+        SLEEP_FIELD = flag(PWR_MGMT_1::SLEEP);
+        /// [`CYCLE`](PWR_MGMT_1::CYCLE)
+        CYCLE_FIELD = flag(PWR_MGMT_1::CYCLE);
+        /// [`TEMP_DIS`](PWR_MGMT_1::TEMP_DIS)
+        TEMP_DIS_FIELD = flag(PWR_MGMT_1::TEMP_DIS);
+        /// [`CLKSEL`](PWR_MGMT_1::CLKSEL)
+        CLKSEL_FIELD = new(PWR_MGMT_1::CLKSEL);
+    }
+    PWR_MGMT_2 {
+        /// [`LP_WAKE_CTRL`](PWR_MGMT_2::LP_WAKE_CTRL)
+        LP_WAKE_CTRL_FIELD = new(PWR_MGMT_2::LP_WAKE_CTRL);
+        /// [`STBY_XA`](PWR_MGMT_2::STBY_XA)
+        STBY_XA_FIELD = flag(PWR_MGMT_2::STBY_XA);
+        /// [`STBY_YA`](PWR_MGMT_2::STBY_YA)
+        STBY_YA_FIELD = flag(PWR_MGMT_2::STBY_YA);
+        /// [`STBY_ZA`](PWR_MGMT_2::STBY_ZA)
+        STBY_ZA_FIELD = flag(PWR_MGMT_2::STBY_ZA);
+        /// [`STBY_XG`](PWR_MGMT_2::STBY_XG)
+        STBY_XG_FIELD = flag(PWR_MGMT_2::STBY_XG);
+        /// [`STBY_YG`](PWR_MGMT_2::STBY_YG)
+        STBY_YG_FIELD = flag(PWR_MGMT_2::STBY_YG);
+        /// [`STBY_ZG`](PWR_MGMT_2::STBY_ZG)
+        STBY_ZG_FIELD = flag(PWR_MGMT_2::STBY_ZG);
+    }
+}
+
+/// CONFIG content
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct ConfigValue {
+    /// FSYNC latch location
+    pub ext_sync: EXT_SYNC,
+    /// DLPF_CFG, 0..=7
+    pub dlpf_cfg: u8,
+}
+
+impl TypedRegister for CONFIG {
+    type Value = ConfigValue;
+
+    fn to_byte(value: ConfigValue) -> u8 {
+        RegisterValue::ZERO
+            .with(CONFIG::EXT_SYNC_SET_FIELD, value.ext_sync)
+            .with(CONFIG::DLPF_CFG_FIELD, value.dlpf_cfg)
+            .byte()
+    }
+
+    fn from_byte(byte: u8) -> ConfigValue {
+        let value = RegisterValue::<CONFIG>::from_byte(byte);
+        ConfigValue {
+            ext_sync: EXT_SYNC::from(value.field(CONFIG::EXT_SYNC_SET_FIELD)),
+            dlpf_cfg: value.field(CONFIG::DLPF_CFG_FIELD),
+        }
+    }
+}
+
+/// GYRO_CONFIG content
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct GyroConfigValue {
+    /// self test X, Y, Z
+    pub self_test: [bool; 3],
+    /// full scale range
+    pub range: GyroRange,
+}
+
+const GYRO_SELF_TEST: [Field<GYRO_CONFIG>; 3] = [
+    GYRO_CONFIG::XG_ST_FIELD,
+    GYRO_CONFIG::YG_ST_FIELD,
+    GYRO_CONFIG::ZG_ST_FIELD,
+];
+
+impl TypedRegister for GYRO_CONFIG {
+    type Value = GyroConfigValue;
+
+    fn to_byte(value: GyroConfigValue) -> u8 {
+        GYRO_SELF_TEST
+            .iter()
+            .zip(value.self_test)
+            .fold(
+                RegisterValue::ZERO.with(GYRO_CONFIG::FS_SEL_FIELD, value.range),
+                |byte, (field, on)| byte.with(*field, on),
+            )
+            .byte()
+    }
+
+    fn from_byte(byte: u8) -> GyroConfigValue {
+        let value = RegisterValue::<GYRO_CONFIG>::from_byte(byte);
+        GyroConfigValue {
+            self_test: GYRO_SELF_TEST.map(|field| value.flag(field)),
+            range: GyroRange::from(value.field(GYRO_CONFIG::FS_SEL_FIELD)),
+        }
+    }
+}
+
+/// ACCEL_CONFIG content
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct AccelConfigValue {
+    /// self test X, Y, Z
+    pub self_test: [bool; 3],
+    /// full scale range
+    pub range: AccelRange,
+    /// high pass filter, the reserved values 5 and 6 read as `_RESET`
+    pub hpf: ACCEL_HPF,
+}
+
+const ACCEL_SELF_TEST: [Field<ACCEL_CONFIG>; 3] = [
+    ACCEL_CONFIG::XA_ST_FIELD,
+    ACCEL_CONFIG::YA_ST_FIELD,
+    ACCEL_CONFIG::ZA_ST_FIELD,
+];
+
+impl TypedRegister for ACCEL_CONFIG {
+    type Value = AccelConfigValue;
+
+    fn to_byte(value: AccelConfigValue) -> u8 {
+        let fields = RegisterValue::ZERO
+            .with(ACCEL_CONFIG::FS_SEL_FIELD, value.range)
+            .with(ACCEL_CONFIG::ACCEL_HPF_FIELD, value.hpf);
+        ACCEL_SELF_TEST
+            .iter()
+            .zip(value.self_test)
+            .fold(fields, |byte, (field, on)| byte.with(*field, on))
+            .byte()
+    }
+
+    fn from_byte(byte: u8) -> AccelConfigValue {
+        let value = RegisterValue::<ACCEL_CONFIG>::from_byte(byte);
+        AccelConfigValue {
+            self_test: ACCEL_SELF_TEST.map(|field| value.flag(field)),
+            range: AccelRange::from(value.field(ACCEL_CONFIG::FS_SEL_FIELD)),
+            hpf: ACCEL_HPF::from(value.field(ACCEL_CONFIG::ACCEL_HPF_FIELD)),
+        }
+    }
+}
+
+/// PWR_MGMT_1 content, bit 4 is reserved and written 0
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct PowerManagement1 {
+    /// reset all registers, reads back 0
+    pub device_reset: bool,
+    /// sleep mode
+    pub sleep: bool,
+    /// cycle between sleep and single samples
+    pub cycle: bool,
+    /// temperature sensor disabled
+    pub temp_dis: bool,
+    /// clock source
+    pub clksel: CLKSEL,
+}
+
+impl TypedRegister for PWR_MGMT_1 {
+    type Value = PowerManagement1;
+
+    fn to_byte(value: PowerManagement1) -> u8 {
+        RegisterValue::ZERO
+            .with(PWR_MGMT_1::DEVICE_RESET_FIELD, value.device_reset)
+            .with(PWR_MGMT_1::SLEEP_FIELD, value.sleep)
+            .with(PWR_MGMT_1::CYCLE_FIELD, value.cycle)
+            .with(PWR_MGMT_1::TEMP_DIS_FIELD, value.temp_dis)
+            .with(PWR_MGMT_1::CLKSEL_FIELD, value.clksel)
+            .byte()
+    }
+
+    fn from_byte(byte: u8) -> PowerManagement1 {
+        let value = RegisterValue::<PWR_MGMT_1>::from_byte(byte);
+        PowerManagement1 {
+            device_reset: value.flag(PWR_MGMT_1::DEVICE_RESET_FIELD),
+            sleep: value.flag(PWR_MGMT_1::SLEEP_FIELD),
+            cycle: value.flag(PWR_MGMT_1::CYCLE_FIELD),
+            temp_dis: value.flag(PWR_MGMT_1::TEMP_DIS_FIELD),
+            clksel: CLKSEL::from(value.field(PWR_MGMT_1::CLKSEL_FIELD)),
+        }
+    }
+}
+
+/// PWR_MGMT_2 content
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct PowerManagement2 {
+    /// wake frequency in cycle mode
+    pub wake: LP_WAKE_CTRL,
+    /// standby of accel X, Y, Z and gyro X, Y, Z
+    pub standby: [bool; 6],
+}
+
+/// standby fields of accel X, Y, Z and gyro X, Y, Z
+pub(crate) const STANDBY_FIELDS: [Field<PWR_MGMT_2>; 6] = [
+    PWR_MGMT_2::STBY_XA_FIELD,
+    PWR_MGMT_2::STBY_YA_FIELD,
+    PWR_MGMT_2::STBY_ZA_FIELD,
+    PWR_MGMT_2::STBY_XG_FIELD,
+    PWR_MGMT_2::STBY_YG_FIELD,
+    PWR_MGMT_2::STBY_ZG_FIELD,
+];
+
+impl TypedRegister for PWR_MGMT_2 {
+    type Value = PowerManagement2;
+
+    fn to_byte(value: PowerManagement2) -> u8 {
+        STANDBY_FIELDS
+            .iter()
+            .zip(value.standby)
+            .fold(
+                RegisterValue::ZERO.with(PWR_MGMT_2::LP_WAKE_CTRL_FIELD, value.wake),
+                |byte, (field, on)| byte.with(*field, on),
+            )
+            .byte()
+    }
+
+    fn from_byte(byte: u8) -> PowerManagement2 {
+        let value = RegisterValue::<PWR_MGMT_2>::from_byte(byte);
+        PowerManagement2 {
+            wake: LP_WAKE_CTRL::from(value.field(PWR_MGMT_2::LP_WAKE_CTRL_FIELD)),
+            standby: STANDBY_FIELDS.map(|field| value.flag(field)),
+        }
+    }
+}
+
+#[cfg(feature = "driver")]
+impl<I, D, E> Mpu6050<I, D>
+where
+    I: Write<Error = E> + WriteRead<Error = E>,
+{
+    fn check<T>(reg: Register, checked: Result<T, AccessViolation>) -> Result<T, Mpu6050Error<E>> {
+        checked.map_err(|violation| Mpu6050Error::InvalidRegisterAccess {
+            register: reg,
+            violation,
+        })
+    }
+
+    /// reads `reg`
+    pub fn read_register(&mut self, reg: Register) -> Result<u8, Mpu6050Error<E>> {
+        Self::check(reg, reg.check_byte(false))?;
+        self.read_byte(reg.addr())
+    }
+
+    /// writes `value` to `reg`
+    pub fn write_register(&mut self, reg: Register, value: u8) -> Result<(), Mpu6050Error<E>> {
+        Self::check(reg, reg.check_byte(true))?;
+        self.write_byte(reg.addr(), value)
+    }
+
+    /// Reads `reg`, passes the value to `f` and writes its result back: one read, one write,
+    /// also if the value is unchanged. Returns the value written. A failed read calls
+    /// neither `f` nor the write
+    pub fn modify_register(
+        &mut self,
+        reg: Register,
+        f: impl FnOnce(u8) -> u8,
+    ) -> Result<u8, Mpu6050Error<E>> {
+        Self::check(reg, reg.check_byte(true))?;
+        self.as_op(DriverOp::ConfigUpdate, |mpu| {
+            let value = f(mpu.read_byte(reg.addr())?);
+            mpu.write_byte(reg.addr(), value)?;
+            Ok(value)
+        })
+    }
+
+    /// Bit `bit` of `reg`, the raw escape hatch. The driver itself reads typed fields with
+    /// [`read_flag`](Self::read_flag)
+    pub fn read_register_bit(&mut self, reg: Register, bit: u8) -> Result<bool, Mpu6050Error<E>> {
+        Ok(bits::get_bit(self.read_register(reg)?, bit) != 0)
+    }
+
+    /// Sets or clears bit `bit` of `reg`, see [`modify_register`](Self::modify_register). The
+    /// raw escape hatch of [`write_field`](Self::write_field)
+    pub fn write_register_bit(
+        &mut self,
+        reg: Register,
+        bit: u8,
+        enable: bool,
+    ) -> Result<(), Mpu6050Error<E>> {
+        self.modify_register(reg, |mut byte| {
+            bits::set_bit(&mut byte, bit, enable);
+            byte
+        })?;
+        Ok(())
+    }
+
+    /// Field `block` of `reg`, the raw escape hatch of [`read_field`](Self::read_field)
+    pub fn read_register_field(
+        &mut self,
+        reg: Register,
+        block: BitBlock,
+    ) -> Result<u8, Mpu6050Error<E>> {
+        Ok(bits::get_bits(
+            self.read_register(reg)?,
+            block.bit,
+            block.length,
+        ))
+    }
+
+    /// Writes `value` to field `block` of `reg`, see [`modify_register`](Self::modify_register).
+    /// The raw escape hatch of [`write_field`](Self::write_field)
+    pub fn write_register_field(
+        &mut self,
+        reg: Register,
+        block: BitBlock,
+        value: u8,
+    ) -> Result<(), Mpu6050Error<E>> {
+        self.modify_register(reg, |mut byte| {
+            bits::set_bits(&mut byte, block.bit, block.length, value);
+            byte
+        })?;
+        Ok(())
+    }
+
+    /// content of `R`, field by field
+    pub fn read_fields<R: RegisterSpec>(&mut self) -> Result<RegisterValue<R>, Mpu6050Error<E>> {
+        Ok(RegisterValue::from_byte(self.read_register(R::REGISTER)?))
+    }
+
+    /// writes the content of `R`, all fields at once
+    pub fn write_fields<R: RegisterSpec>(
+        &mut self,
+        value: RegisterValue<R>,
+    ) -> Result<(), Mpu6050Error<E>> {
+        self.write_register(R::REGISTER, value.byte())
+    }
+
+    /// Reads `R`, passes the content to `f` and writes its result back, see
+    /// [`modify_register`](Self::modify_register). Returns the content written
+    pub fn modify_fields<R: RegisterSpec>(
+        &mut self,
+        f: impl FnOnce(RegisterValue<R>) -> RegisterValue<R>,
+    ) -> Result<RegisterValue<R>, Mpu6050Error<E>> {
+        let byte =
+            self.modify_register(R::REGISTER, |byte| f(RegisterValue::from_byte(byte)).byte())?;
+        Ok(RegisterValue::from_byte(byte))
+    }
+
+    /// value of `field`
+    pub fn read_field<R: RegisterSpec>(&mut self, field: Field<R>) -> Result<u8, Mpu6050Error<E>> {
+        Ok(self.read_fields::<R>()?.field(field))
+    }
+
+    /// true if `field` is set
+    pub fn read_flag<R: RegisterSpec>(&mut self, field: Field<R>) -> Result<bool, Mpu6050Error<E>> {
+        Ok(self.read_fields::<R>()?.flag(field))
+    }
+
+    /// writes `value` to `field`, the other fields of `R` are kept, see
+    /// [`modify_register`](Self::modify_register)
+    pub fn write_field<R: RegisterSpec>(
+        &mut self,
+        field: Field<R>,
+        value: impl Into<FieldValue<R>>,
+    ) -> Result<(), Mpu6050Error<E>> {
+        let value = value.into();
+        self.modify_fields::<R>(|fields| fields.with(field, value))?;
+        Ok(())
+    }
+
+    /// 16 bit value of the pair starting at `reg`, in one transaction
+    pub fn read_register_word(&mut self, reg: Register) -> Result<i16, Mpu6050Error<E>> {
+        Self::check(reg, reg.check_word(false))?;
+        self.read_word(reg.addr())
+    }
+
+    /// writes the 16 bit `value` to the pair starting at `reg`, in one transaction
+    pub fn write_register_word(
+        &mut self,
+        reg: Register,
+        value: i16,
+    ) -> Result<(), Mpu6050Error<E>> {
+        Self::check(reg, reg.check_word(true))?;
+        self.write_word(reg.addr(), value)
+    }
+
+    /// reads `buf.len()` registers starting at `reg` in one transaction
+    pub fn read_registers(&mut self, reg: Register, buf: &mut [u8]) -> Result<(), Mpu6050Error<E>> {
+        Self::check(reg, reg.check_burst(buf.len()))?;
+        self.read_bytes(reg.addr(), buf)
+    }
+
+    /// typed content of `R`
+    pub fn read_typed<R: TypedRegister>(&mut self) -> Result<R::Value, Mpu6050Error<E>> {
+        Ok(R::from_byte(self.read_register(R::REGISTER)?))
+    }
+
+    /// writes the typed content of `R`, all fields at once
+    pub fn write_typed<R: TypedRegister>(
+        &mut self,
+        value: R::Value,
+    ) -> Result<(), Mpu6050Error<E>> {
+        self.write_register(R::REGISTER, R::to_byte(value))
+    }
+}
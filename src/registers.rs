@@ -0,0 +1,450 @@
+//! Raw register addresses and bit definitions of the register map.
+//!
+//! For drivers of their own, register dumps and the raw `u8` calls of the driver. Everything
+//! here is also reachable under [`device`](crate::device) for existing paths, but neither is
+//! part of the [`prelude`](crate::prelude). The typed counterpart, checking access before the
+//! bus is touched, is [`register`](crate::register).
+//!
+//! ```
+//! use mpu6050::registers::{PWR_MGMT_1, WHOAMI};
+//!
+//! assert_eq!(WHOAMI, 0x75);
+//! assert_eq!(PWR_MGMT_1::ADDR, 0x6b);
+//! assert_eq!(PWR_MGMT_1::CLKSEL.length, 3);
+//! ```
+
+/// Motion Threshold Register
+pub const MOT_THR: u8 = 0x1F;
+/// Motion Duration Detection Register
+pub const MOT_DUR: u8 = 0x20;
+/// High Byte Register Gyro x orientation
+pub const GYRO_REGX_H: u8 = 0x43;
+/// High Byte Register Gyro y orientation
+pub const GYRO_REGY_H: u8 = 0x45;
+/// High Byte Register Gyro z orientation
+pub const GYRO_REGZ_H: u8 = 0x47;
+/// High Byte Register Calc roll
+pub const ACC_REGX_H: u8 = 0x3b;
+/// High Byte Register Calc pitch
+pub const ACC_REGY_H: u8 = 0x3d;
+/// High Byte Register Calc yaw
+pub const ACC_REGZ_H: u8 = 0x3f;
+/// High Byte Register Temperature
+pub const TEMP_OUT_H: u8 = 0x41;
+/// Internal register to check slave addr
+pub const WHOAMI: u8 = 0x75;
+/// Sample Rate Divider Register
+pub const SMPLRT_DIV: u8 = 0x19;
+/// FIFO Enable Register, which sensors are written to the FIFO
+pub const FIFO_EN: u8 = 0x23;
+/// Registers 114 and 115: FIFO count, high byte first
+pub const FIFO_COUNT_H: u8 = 0x72;
+/// Register 116: FIFO read and write
+pub const FIFO_R_W: u8 = 0x74;
+/// Registers 6 and 7: factory X accel offset, bit 0 of the low byte reserved. Not in the
+/// register map, see [`AccelOffsetScaling`](crate::device::AccelOffsetScaling)
+pub const XA_OFFS_H: u8 = 0x06;
+/// Registers 8 and 9: factory Y accel offset
+pub const YA_OFFS_H: u8 = 0x08;
+/// Registers 10 and 11: factory Z accel offset
+pub const ZA_OFFS_H: u8 = 0x0a;
+/// Registers 19 and 20: X gyro offset, added to the output in
+/// [`GYRO_OFFSET_DPS_PER_LSB`](crate::device::GYRO_OFFSET_DPS_PER_LSB)
+pub const XG_OFFS_USRH: u8 = 0x13;
+/// Registers 21 and 22: Y gyro offset
+pub const YG_OFFS_USRH: u8 = 0x15;
+/// Registers 23 and 24: Z gyro offset
+pub const ZG_OFFS_USRH: u8 = 0x17;
+/// MPU-6500 registers 119 and 120: X accel offset, bit 0 of the low byte reserved
+pub const XA_OFFSET_H: u8 = 0x77;
+/// MPU-6500 registers 122 and 123: Y accel offset
+pub const YA_OFFSET_H: u8 = 0x7a;
+/// MPU-6500 registers 125 and 126: Z accel offset
+pub const ZA_OFFSET_H: u8 = 0x7d;
+
+/// 16 bit value in a high and a low register, accessed in one two byte transaction with
+/// `read_register_word` and `write_register_word` so the device never sees half of an update
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct RegisterPair {
+    /// name of the high register as in the register map
+    pub name: &'static str,
+    /// high register, the low one follows
+    pub high: u8,
+    /// written by the driver, not only read
+    pub writable: bool,
+}
+
+impl RegisterPair {
+    /// low register
+    pub const fn low(&self) -> u8 {
+        self.high + 1
+    }
+}
+
+const fn pair(name: &'static str, high: u8, writable: bool) -> RegisterPair {
+    RegisterPair {
+        name,
+        high,
+        writable,
+    }
+}
+
+/// Every register pair the driver accesses
+pub const REGISTER_PAIRS: [RegisterPair; 17] = [
+    pair("XA_OFFS_H", XA_OFFS_H, true),
+    pair("YA_OFFS_H", YA_OFFS_H, true),
+    pair("ZA_OFFS_H", ZA_OFFS_H, true),
+    pair("XG_OFFS_USRH", XG_OFFS_USRH, true),
+    pair("YG_OFFS_USRH", YG_OFFS_USRH, true),
+    pair("ZG_OFFS_USRH", ZG_OFFS_USRH, true),
+    pair("ACCEL_XOUT_H", ACC_REGX_H, false),
+    pair("ACCEL_YOUT_H", ACC_REGY_H, false),
+    pair("ACCEL_ZOUT_H", ACC_REGZ_H, false),
+    pair("TEMP_OUT_H", TEMP_OUT_H, false),
+    pair("GYRO_XOUT_H", GYRO_REGX_H, false),
+    pair("GYRO_YOUT_H", GYRO_REGY_H, false),
+    pair("GYRO_ZOUT_H", GYRO_REGZ_H, false),
+    pair("FIFO_COUNTH", FIFO_COUNT_H, false),
+    pair("XA_OFFSET_H", XA_OFFSET_H, true),
+    pair("YA_OFFSET_H", YA_OFFSET_H, true),
+    pair("ZA_OFFSET_H", ZA_OFFSET_H, true),
+];
+
+/// Pair `reg` is the high or the low register of
+pub fn register_pair(reg: u8) -> Option<&'static RegisterPair> {
+    REGISTER_PAIRS
+        .iter()
+        .find(|pair| pair.high == reg || pair.low() == reg)
+}
+
+#[allow(non_camel_case_types)]
+#[derive(Copy, Clone, Debug)]
+/// Register 35: FIFO Enable bits, the register address is [`FIFO_EN`]
+pub struct FIFO_EN_BITS;
+
+impl FIFO_EN_BITS {
+    /// temperature
+    pub const TEMP: u8 = 7;
+    /// gyro X
+    pub const XG: u8 = 6;
+    /// gyro Y
+    pub const YG: u8 = 5;
+    /// gyro Z
+    pub const ZG: u8 = 4;
+    /// accel X, Y and Z
+    pub const ACCEL: u8 = 3;
+    /// EXT_SENS_DATA of aux slave 2
+    pub const SLV2: u8 = 2;
+    /// EXT_SENS_DATA of aux slave 1
+    pub const SLV1: u8 = 1;
+    /// EXT_SENS_DATA of aux slave 0
+    pub const SLV0: u8 = 0;
+}
+
+#[allow(non_camel_case_types)]
+#[derive(Copy, Clone, Debug)]
+/// Register 36: I2C Master Control
+pub struct I2C_MST_CTRL;
+
+impl I2C_MST_CTRL {
+    /// Base Address
+    pub const ADDR: u8 = 0x24;
+    /// EXT_SENS_DATA of aux slave 3 to FIFO
+    pub const SLV_3_FIFO_EN: u8 = 5;
+}
+
+/// Describes a bit block from bit number 'bit' to 'bit'+'length'
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct BitBlock {
+    pub bit: u8,
+    pub length: u8,
+}
+
+#[allow(non_camel_case_types)]
+#[derive(Copy, Clone, Debug)]
+/// Register 26: Configuration (DLPF, External signal)
+pub struct CONFIG;
+
+impl CONFIG {
+    /// Base Address
+    pub const ADDR: u8 = 0x1a;
+    /// external Frame Synchronisation (FSYNC)
+    pub const EXT_SYNC_SET: BitBlock = BitBlock { bit: 5, length: 3 };
+    /// Digital Low Pass Filter (DLPF) config
+    pub const DLPF_CFG: BitBlock = BitBlock { bit: 2, length: 3 };
+}
+
+#[allow(non_camel_case_types)]
+#[derive(Copy, Clone, Debug)]
+/// Register 27: Gyro Config
+pub struct GYRO_CONFIG;
+
+impl GYRO_CONFIG {
+    pub const ADDR: u8 = 0x1b;
+    /// Gyro x axis self test bit
+    pub const XG_ST: u8 = 7;
+    /// Gyro y axis self test bit
+    pub const YG_ST: u8 = 6;
+    /// Gyro z axis self test bit
+    pub const ZG_ST: u8 = 5;
+    /// Gyro Config FS_SEL
+    pub const FS_SEL: BitBlock = BitBlock { bit: 4, length: 2 };
+}
+
+#[allow(non_camel_case_types)]
+#[derive(Copy, Clone, Debug)]
+/// Register 28: Accel Config
+pub struct ACCEL_CONFIG;
+
+impl ACCEL_CONFIG {
+    /// Base Address
+    pub const ADDR: u8 = 0x1c;
+    /// Accel x axis self test bit
+    pub const XA_ST: u8 = 7;
+    /// Accel y axis self test bit
+    pub const YA_ST: u8 = 6;
+    /// Accel z axis self test bit
+    pub const ZA_ST: u8 = 5;
+    /// Accel Config FS_SEL
+    pub const FS_SEL: BitBlock = BitBlock { bit: 4, length: 2 };
+    /// Accel Config ACCEL_HPF
+    pub const ACCEL_HPF: BitBlock = BitBlock { bit: 2, length: 3 };
+}
+
+#[allow(non_camel_case_types)]
+#[derive(Copy, Clone, Debug)]
+/// Register 55: INT Pin / Bypass Enable Configuration
+pub struct INT_PIN_CFG;
+
+impl INT_PIN_CFG {
+    /// Base Address
+    pub const ADDR: u8 = 0x37;
+    /// INT pin logic level
+    pub const INT_LEVEL: u8 = 7;
+    /// INT pin config
+    pub const INT_OPEN: u8 = 6;
+    /// Pulse (length)
+    pub const LATCH_INT_EN: u8 = 5;
+    /// INT clear conditions
+    pub const INT_RD_CLEAR: u8 = 4;
+    /// FSYNC PIN logic level
+    pub const FSYNC_INT_LEVEL: u8 = 3;
+    /// FSYNC PIN config
+    pub const FSYNC_INT_EN: u8 = 2;
+    /// i2c access/bypass
+    pub const I2C_BYPASS_EN: u8 = 1;
+    /// enable/disable reference clock output
+    pub const CLKOUT_EN: u8 = 0;
+}
+
+#[allow(non_camel_case_types)]
+#[derive(Copy, Clone, Debug)]
+/// Register 56: Interrupt Status
+pub struct INT_ENABLE;
+
+impl INT_ENABLE {
+    /// Base Address
+    pub const ADDR: u8 = 0x38;
+    /// Generate interrupt Free Fall Detection
+    pub const FF_EN: u8 = 7;
+    /// Generate interrupt with Motion Detected
+    pub const MOT_EN: u8 = 6;
+    /// Generate iterrrupt when Zero Motion Detection
+    pub const ZMOT_EN: u8 = 5;
+    /// Generate iterrupt when FIFO buffer overflow
+    pub const FIFO_OFLOW_END: u8 = 4;
+    /// this  bit enables  any  of  the  I2C  Masterinterrupt  sources  to generate an interrupt
+    pub const I2C_MST_INT_EN: u8 = 3;
+    /// enables Data Ready interrupt, each time a write operation to all sensor registers completed
+    pub const DATA_RDY_EN: u8 = 0;
+}
+
+#[allow(non_camel_case_types)]
+#[derive(Copy, Clone, Debug)]
+/// Register 58: Interrupt Status
+pub struct INT_STATUS;
+
+impl INT_STATUS {
+    /// Base Address
+    pub const ADDR: u8 = 0x3a;
+    /// Free Fall Interrupt
+    pub const FF_INT: u8 = 7;
+    /// Motion Detection Interrupt
+    pub const MOT_INT: u8 = 6;
+    /// Zero Motion Detection Interrupt
+    pub const ZMOT_INT: u8 = 5;
+    /// FIFO buffer overflow
+    pub const FIFO_OFLOW_INT: u8 = 4;
+    /// i2c master interrupt has been generated
+    pub const I2C_MSF_INT: u8 = 3;
+    /// Data is ready
+    pub const DATA_RDY_INT: u8 = 0;
+}
+
+#[allow(non_camel_case_types)]
+#[derive(Copy, Clone, Debug)]
+/// Register 97: Motion Detection Status
+pub struct MOT_DETECT_STATUS;
+
+impl MOT_DETECT_STATUS {
+    /// Base Address
+    pub const ADDR: u8 = 0x61;
+    /// motion  in  the  negative  X  axis  has generated a Motion detection interrupt
+    pub const MOT_XNEG: u8 = 7;
+    /// motion  in  the  positive  X  axis  has generated a Motion detection interrupt
+    pub const MOT_XPOS: u8 = 6;
+    /// motion  in  the  negative  Y  axis  has generated a Motion detection interrupt
+    pub const MOT_YNEG: u8 = 5;
+    /// motion  in  the positive  Y  axis  has generated a Motion detection interrupt
+    pub const MOT_YPOS: u8 = 4;
+    /// motion  in  the  negative  Z  axis  has generated a Motion detection interrupt.
+    pub const MOT_ZNEG: u8 = 3;
+    /// motion  in  the  positive  Z  axis  has generated a Motion detection interrupt
+    pub const MOT_ZPOS: u8 = 2;
+    /// Zero  Motion detection  interrupt  is generated
+    pub const MOT_ZRMOT: u8 = 0;
+}
+
+#[allow(non_camel_case_types)]
+#[derive(Copy, Clone, Debug)]
+/// Register 104: Signal Path Reset, write only, the bits clear themselves
+pub struct SIGNAL_PATH_RESET;
+
+impl SIGNAL_PATH_RESET {
+    /// Base Address
+    pub const ADDR: u8 = 0x68;
+    /// reset the gyro analog and digital signal paths
+    pub const GYRO_RESET: u8 = 2;
+    /// reset the accelerometer analog and digital signal paths
+    pub const ACCEL_RESET: u8 = 1;
+    /// reset the temperature sensor analog and digital signal paths
+    pub const TEMP_RESET: u8 = 0;
+}
+
+#[allow(non_camel_case_types)]
+#[derive(Copy, Clone, Debug)]
+/// Register 105: Motion Detection Control
+pub struct MOT_DETECT_CONTROL;
+
+impl MOT_DETECT_CONTROL {
+    /// Base Address
+    pub const ADDR: u8 = 0x69;
+    /// Additional delay
+    pub const ACCEL_ON_DELAY: BitBlock = BitBlock { bit: 5, length: 2 };
+    ///  Free Fall count
+    pub const FF_COUNT: BitBlock = BitBlock { bit: 3, length: 2 };
+    /// Motion Detection cound
+    pub const MOT_COUNT: BitBlock = BitBlock { bit: 1, length: 2 };
+}
+
+#[allow(non_camel_case_types)]
+#[derive(Copy, Clone, Debug)]
+/// Registers 37 to 48: I2C Slave 0-3 Control, three registers (ADDR, REG, CTRL) per slave
+pub struct I2C_SLV;
+
+impl I2C_SLV {
+    /// Address of I2C_SLV0_ADDR, slave n starts at ADDR_BASE + n * STRIDE
+    pub const ADDR_BASE: u8 = 0x25;
+    /// Registers per slave
+    pub const STRIDE: u8 = 3;
+    /// I2C_SLVn_ADDR: transfer is a read
+    pub const RW: u8 = 7;
+    /// I2C_SLVn_ADDR: 7 bit slave address
+    pub const ADDR: BitBlock = BitBlock { bit: 6, length: 7 };
+    /// I2C_SLVn_CTRL: enable slave
+    pub const EN: u8 = 7;
+    /// I2C_SLVn_CTRL: swap bytes of words
+    pub const BYTE_SW: u8 = 6;
+    /// I2C_SLVn_CTRL: transfer data only, no register address
+    pub const REG_DIS: u8 = 5;
+    /// I2C_SLVn_CTRL: word grouping
+    pub const GRP: u8 = 4;
+    /// I2C_SLVn_CTRL: number of bytes transferred
+    pub const LEN: BitBlock = BitBlock { bit: 3, length: 4 };
+
+    /// I2C_SLVn_ADDR of slave n
+    pub const fn addr_reg(n: u8) -> u8 {
+        Self::ADDR_BASE + n * Self::STRIDE
+    }
+
+    /// I2C_SLVn_REG of slave n
+    pub const fn reg_reg(n: u8) -> u8 {
+        Self::ADDR_BASE + n * Self::STRIDE + 1
+    }
+
+    /// I2C_SLVn_CTRL of slave n
+    pub const fn ctrl_reg(n: u8) -> u8 {
+        Self::ADDR_BASE + n * Self::STRIDE + 2
+    }
+}
+
+/// Register 73: first External Sensor Data register
+pub const EXT_SENS_DATA_00: u8 = 0x49;
+/// Number of External Sensor Data registers (EXT_SENS_DATA_00 to 23)
+pub const EXT_SENS_DATA_LEN: u8 = 24;
+
+#[allow(non_camel_case_types)]
+#[derive(Copy, Clone, Debug)]
+/// Register 106: User Control
+pub struct USER_CTRL;
+
+impl USER_CTRL {
+    /// Base Address
+    pub const ADDR: u8 = 0x6a;
+    /// FIFO enable
+    pub const FIFO_EN: u8 = 6;
+    /// I2C master mode enable
+    pub const I2C_MST_EN: u8 = 5;
+    /// I2C interface disable (SPI only)
+    pub const I2C_IF_DIS: u8 = 4;
+    /// FIFO reset
+    pub const FIFO_RESET: u8 = 2;
+    /// I2C master reset
+    pub const I2C_MST_RESET: u8 = 1;
+    /// Reset all signal paths and sensor registers
+    pub const SIG_COND_RESET: u8 = 0;
+}
+
+#[allow(non_camel_case_types)]
+#[derive(Copy, Clone, Debug)]
+/// Register 107: Power Management 1
+pub struct PWR_MGMT_1;
+
+impl PWR_MGMT_1 {
+    /// Base Address
+    pub const ADDR: u8 = 0x6b;
+    /// Device Reset bit
+    pub const DEVICE_RESET: u8 = 7;
+    /// Sleep mode bit (Should be called "Low Power", doesn't actually sleep)
+    pub const SLEEP: u8 = 6;
+    /// Cycle bit for wake operations
+    pub const CYCLE: u8 = 5;
+    /// Temperature sensor enable/disable bit
+    pub const TEMP_DIS: u8 = 3;
+    /// Clock Control
+    pub const CLKSEL: BitBlock = BitBlock { bit: 2, length: 3 };
+}
+
+#[allow(non_camel_case_types)]
+#[derive(Copy, Clone, Debug)]
+/// Register 107: Power Management 2
+pub struct PWR_MGMT_2;
+
+impl PWR_MGMT_2 {
+    /// Base Address
+    pub const ADDR: u8 = 0x6c;
+    /// Wake up frequency
+    pub const LP_WAKE_CTRL: BitBlock = BitBlock { bit: 7, length: 2 };
+    /// disable accel axis x
+    pub const STBY_XA: u8 = 5;
+    /// disable accel axis y
+    pub const STBY_YA: u8 = 4;
+    /// disable accel axis z
+    pub const STBY_ZA: u8 = 3;
+    /// disable gyro  axis x
+    pub const STBY_XG: u8 = 2;
+    /// disable gyro  axis y
+    pub const STBY_YG: u8 = 1;
+    /// disable gyro  axis z
+    pub const STBY_ZG: u8 = 0;
+}
@@ -0,0 +1,1367 @@
+//! Command protocol for controlling the driver over a byte stream, a UART, USB CDC or a
+//! radio link, from a host tool.
+//!
+//! The MCU side hands incoming bytes to [`Mpu6050::serve`] through a [`RemoteLink`] over its
+//! [`Transport`], the host sends [`Command`]s with a [`RemoteClient`] over another link.
+//! Every command gets exactly one final [`Response`], long running commands stream part
+//! responses before it. The crate has no serde dependency, frames are a fixed binary layout
+//! checked with the [`crc16`](crate::packed::crc16) of the other formats.
+//!
+//! #### Framing
+//! | bytes | content |
+//! |:---|:---|
+//! | 1 | protocol version ([`REMOTE_VERSION`]) |
+//! | 1 | sequence number, chosen by the client and echoed by every response to the command |
+//! | 1 | opcode |
+//! | 0 to [`MAX_BODY`] | body, numbers little endian, `f32` as IEEE 754 bits |
+//! | 2 | CRC-16/CCITT-FALSE over all previous bytes, little endian |
+//!
+//! On the wire a frame is COBS encoded and terminated by a zero byte, so a receiver joining
+//! a stream mid-frame or dropping bytes resynchronises at the next zero, at most
+//! [`MAX_WIRE`] bytes. A frame failing to decode is answered with
+//! [`Rejected`](Response::Rejected) and sequence number 0, the sequence of a garbled frame
+//! is not trusted. Zero bytes between frames are skipped.
+//!
+//! #### Commands
+//! | opcode | command | body | final response |
+//! |:---|:---|:---|:---|
+//! | 0x01 | [`Hello`](Command::Hello) | | [`Hello`](Response::Hello) |
+//! | 0x02 | [`Init`](Command::Init) | profile: 0 init only, `n` init then [`presets::ALL`](crate::presets::ALL)`[n - 1]` | [`Ack`](Response::Ack) |
+//! | 0x03 | [`GetAll`](Command::GetAll) | | [`Sample`](Response::Sample) |
+//! | 0x04 | [`SetAccelRange`](Command::SetAccelRange) | AFS_SEL | [`Ack`](Response::Ack) |
+//! | 0x05 | [`SetGyroRange`](Command::SetGyroRange) | FS_SEL | [`Ack`](Response::Ack) |
+//! | 0x06 | [`SetDlpf`](Command::SetDlpf) | DLPF_CFG | [`Ack`](Response::Ack) |
+//! | 0x07 | [`SetSampleRateDivider`](Command::SetSampleRateDivider) | SMPLRT_DIV | [`Ack`](Response::Ack) |
+//! | 0x08 | [`Calibrate`](Command::Calibrate) | gyro samples u16, accel samples u16, 1 if level | [`Calibrated`](Response::Calibrated) |
+//! | 0x09 | [`SelfTest`](Command::SelfTest) | | [`SelfTest`](Response::SelfTest) |
+//! | 0x0a | [`ExportCalibration`](Command::ExportCalibration) | | [`BlobDone`](Response::BlobDone) |
+//! | 0x0b | [`HealthCheck`](Command::HealthCheck) | | [`Health`](Response::Health) |
+//!
+//! A calibration is [`auto_setup`](Mpu6050::auto_setup) at the current ranges without
+//! thermal wait and actuation check, the accel HPF back at reset. The self-test is its
+//! actuation check alone, the accel response to the self-test bits in g. The export is the
+//! [`LogHeader`](crate::log_header::LogHeader) of the current configuration, scaling and
+//! offsets, decoded on the host with [`LogHeader::decode`](crate::log_header::LogHeader::decode).
+//! The header allocates, a server built without the `std` feature rejects the export as an
+//! [unknown command](RejectReason::UnknownCommand).
+//!
+//! #### Responses
+//! | opcode | response | body |
+//! |:---|:---|:---|
+//! | 0x80 | [`Ack`](Response::Ack) | |
+//! | 0x81 | [`Hello`](Response::Hello) | version, [`MAX_BODY`], number of profiles |
+//! | 0x82 | [`Sample`](Response::Sample) | accel x, y, z in g, gyro x, y, z in rad/s, temperature, `f32`, flags: bit 0 settling, bit 1 out of band |
+//! | 0x83 | [`Calibrated`](Response::Calibrated) | gyro offset x, y, z, accel offset x, y, z, `f32` |
+//! | 0x84 | [`SelfTest`](Response::SelfTest) | response x, y, z, `f32` |
+//! | 0x85 | [`BlobDone`](Response::BlobDone) | length u16, CRC-16 of the blob u16 |
+//! | 0x86 | [`Health`](Response::Health) | WHO_AM_I, flags: bit 0 WHO_AM_I read, bit 1 connected, bit 2 asleep, bit 3 supervisor tripped, transactions u32, calibration (0 valid, 1 degraded, 2 expired), [reasons](crate::validity::InvalidationReasons::bits) |
+//! | 0xa0 | [`Progress`](Response::Progress) | [phase](crate::setup::AutoSetupPhase), part u16 |
+//! | 0xa1 | [`BlobPart`](Response::BlobPart) | offset u16, up to [`BLOB_CHUNK`] bytes |
+//! | 0xe0 | [`Failed`](Response::Failed) | 0 and the [error code](crate::black_box#error-codes), or 1, the phase and the error code, 0 if the samples were rejected |
+//! | 0xe1 | [`Rejected`](Response::Rejected) | [`RejectReason`], version of the server |
+//!
+//! Opcodes 0xa0 to 0xdf are part responses, more follow to the same command. All others
+//! are final.
+//!
+//! #### Streaming
+//! [`serve`](Mpu6050::serve) handles the frames already received, at most
+//! [`ServeBudget::commands`] of them, and returns when the transport has no more bytes: an
+//! executor calls it from its loop like any other task. A calibration is run cooperatively,
+//! see [`cooperative`](crate::cooperative): whenever [`ServeBudget::progress`] is spent,
+//! and at the start of every phase, a [`Progress`](Response::Progress) frame goes out
+//! instead of a yield. The export is sent as [`BlobPart`](Response::BlobPart)s of
+//! [`BLOB_CHUNK`] bytes. A transport error while streaming aborts the calibration through
+//! its [`deadline`](crate::deadline), no final response is sent and `serve` returns the
+//! error; the client times out or sees the error of its own side.
+//! ```
+//! use embedded_hal::blocking::delay::DelayMs;
+//! use embedded_hal::blocking::i2c::{Write, WriteRead};
+//! use mpu6050::remote::{RemoteLink, ServeBudget, Transport};
+//! use mpu6050::Mpu6050;
+//!
+//! // called every tick of the executor
+//! fn tick<I, E, T: Transport>(
+//!     mpu: &mut Mpu6050<I>,
+//!     delay: &mut impl DelayMs<u8>,
+//!     link: &mut RemoteLink<T>,
+//! ) -> Result<(), T::Error>
+//! where
+//!     I: Write<Error = E> + WriteRead<Error = E>,
+//! {
+//!     mpu.serve(delay, link, ServeBudget::default())?;
+//!     Ok(())
+//! }
+//! ```
+//!
+//! #### Versioning
+//! [`REMOTE_VERSION`] changes with any change to an existing command or response: a layout,
+//! a meaning, an opcode. Adding a command or response with a new opcode, or a new
+//! [`RejectReason`], keeps the version; an older server answers a command it does not know
+//! with [`UnknownCommand`](RejectReason::UnknownCommand). The three header bytes, the CRC,
+//! [`Hello`](Command::Hello) and [`Rejected`](Response::Rejected) keep their layout across
+//! versions, so a client always learns the version of the server: a frame of another
+//! version is answered with [`Version`](RejectReason::Version) and the server's version.
+//! Server and client of one crate release speak one version, there is no negotiation.
+
+use core::cell::RefCell;
+use core::fmt::{self, Debug, Display};
+
+use embedded_hal::blocking::{
+    delay::DelayMs,
+    i2c::{Write, WriteRead},
+};
+use glam::Vec3A;
+
+use crate::cooperative::DrainBudget;
+use crate::device::{AccelRange, GyroRange};
+use crate::packed::crc16;
+use crate::presets;
+use crate::register::Register;
+use crate::setup::{AutoSetupFailure, AutoSetupOptions, AutoSetupPhase};
+use crate::validity::{CalibrationStatus, InvalidationReasons};
+use crate::{Mpu6050, MpuSample};
+
+/// Version of the protocol, see [versioning](self#versioning)
+pub const REMOTE_VERSION: u8 = 1;
+
+/// Longest body of a frame
+pub const MAX_BODY: usize = 64;
+
+/// Bytes of a frame around its body: version, sequence number, opcode and CRC
+pub const FRAME_OVERHEAD: usize = 5;
+
+/// Longest frame before encoding
+pub const MAX_FRAME: usize = FRAME_OVERHEAD + MAX_BODY;
+
+/// Longest frame on the wire, COBS overhead and delimiter included
+pub const MAX_WIRE: usize = MAX_FRAME + MAX_FRAME / 254 + 2;
+
+/// Blob bytes per [`BlobPart`](Response::BlobPart)
+pub const BLOB_CHUNK: usize = 48;
+
+/// Byte stream between client and server
+pub trait Transport {
+    /// Error of the stream
+    type Error: Debug;
+
+    /// reads the bytes available into `buf` without waiting, returns how many, 0 if none
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error>;
+
+    /// writes all of `bytes`
+    fn write(&mut self, bytes: &[u8]) -> Result<(), Self::Error>;
+}
+
+/// Why a frame was refused
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum RejectReason {
+    /// invalid COBS encoding or shorter than the frame overhead
+    Malformed,
+    /// CRC does not match
+    CrcMismatch,
+    /// no delimiter within [`MAX_WIRE`] bytes
+    TooLong,
+    /// protocol version of another release
+    Version,
+    /// opcode not known to the server
+    UnknownCommand,
+    /// body of the wrong length, or a value out of range
+    BadArgument,
+}
+
+impl RejectReason {
+    /// stable code on the wire
+    pub const fn code(self) -> u8 {
+        match self {
+            RejectReason::Malformed => 1,
+            RejectReason::CrcMismatch => 2,
+            RejectReason::TooLong => 3,
+            RejectReason::Version => 4,
+            RejectReason::UnknownCommand => 5,
+            RejectReason::BadArgument => 6,
+        }
+    }
+
+    /// reason of a code, None for codes of a newer version
+    pub const fn from_code(code: u8) -> Option<Self> {
+        Some(match code {
+            1 => RejectReason::Malformed,
+            2 => RejectReason::CrcMismatch,
+            3 => RejectReason::TooLong,
+            4 => RejectReason::Version,
+            5 => RejectReason::UnknownCommand,
+            6 => RejectReason::BadArgument,
+            _ => return None,
+        })
+    }
+}
+
+impl Display for RejectReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            RejectReason::Malformed => "malformed frame",
+            RejectReason::CrcMismatch => "CRC mismatch",
+            RejectReason::TooLong => "frame too long",
+            RejectReason::Version => "protocol version mismatch",
+            RejectReason::UnknownCommand => "unknown command",
+            RejectReason::BadArgument => "bad argument",
+        })
+    }
+}
+
+/// Frame of the protocol, see [framing](self#framing)
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct Frame {
+    /// protocol version, [`REMOTE_VERSION`] for frames built here
+    pub version: u8,
+    /// sequence number
+    pub seq: u8,
+    /// opcode of the command or response
+    pub opcode: u8,
+    len: u8,
+    body: [u8; MAX_BODY],
+}
+
+impl Frame {
+    /// frame of this version, None if `body` is longer than [`MAX_BODY`]
+    pub fn new(seq: u8, opcode: u8, body: &[u8]) -> Option<Self> {
+        let mut frame = Self {
+            version: REMOTE_VERSION,
+            seq,
+            opcode,
+            len: body.len() as u8,
+            body: [0; MAX_BODY],
+        };
+        frame.body.get_mut(..body.len())?.copy_from_slice(body);
+        Some(frame)
+    }
+
+    /// body of the frame
+    pub fn body(&self) -> &[u8] {
+        &self.body[..self.len as usize]
+    }
+
+    /// writes the frame as it goes on the wire, delimiter included, returns the length
+    pub fn encode(&self, wire: &mut [u8; MAX_WIRE]) -> usize {
+        let mut raw = [0; MAX_FRAME];
+        let body = self.body();
+        raw[..3].copy_from_slice(&[self.version, self.seq, self.opcode]);
+        raw[3..3 + body.len()].copy_from_slice(body);
+        let len = 3 + body.len();
+        let crc = crc16(&raw[..len]);
+        raw[len..len + 2].copy_from_slice(&crc.to_le_bytes());
+        let wire_len = cobs_encode(&raw[..len + 2], wire);
+        wire[wire_len] = 0;
+        wire_len + 1
+    }
+
+    /// frame from the bytes received before a delimiter, the delimiter excluded. The
+    /// version is not checked, see [versioning](self#versioning)
+    pub fn decode(wire: &[u8]) -> Result<Self, RejectReason> {
+        let mut raw = [0; MAX_FRAME];
+        let len = cobs_decode(wire, &mut raw)?;
+        if len < FRAME_OVERHEAD {
+            return Err(RejectReason::Malformed);
+        }
+        let crc = u16::from_le_bytes([raw[len - 2], raw[len - 1]]);
+        if crc16(&raw[..len - 2]) != crc {
+            return Err(RejectReason::CrcMismatch);
+        }
+        let mut frame = Self::new(raw[1], raw[2], &raw[3..len - 2]).ok_or(RejectReason::TooLong)?;
+        frame.version = raw[0];
+        Ok(frame)
+    }
+}
+
+/// COBS encoding of `data` into `out` without delimiter, returns the length
+fn cobs_encode(data: &[u8], out: &mut [u8]) -> usize {
+    let mut code_at = 0;
+    let mut len = 1;
+    let mut code = 1;
+    for &byte in data {
+        if byte != 0 {
+            out[len] = byte;
+            len += 1;
+            code += 1;
+        }
+        if byte == 0 || code == 0xff {
+            out[code_at] = code;
+            code_at = len;
+            len += 1;
+            code = 1;
+        }
+    }
+    out[code_at] = code;
+    len
+}
+
+/// COBS decoding of `wire` into `out`, returns the length
+fn cobs_decode(wire: &[u8], out: &mut [u8]) -> Result<usize, RejectReason> {
+    let mut i = 0;
+    let mut len = 0;
+    while i < wire.len() {
+        let code = wire[i] as usize;
+        let block = wire
+            .get(i + 1..i + code)
+            .filter(|block| code > 0 && !block.contains(&0))
+            .ok_or(RejectReason::Malformed)?;
+        out.get_mut(len..len + block.len())
+            .ok_or(RejectReason::TooLong)?
+            .copy_from_slice(block);
+        len += block.len();
+        i += code;
+        if code < 0xff && i < wire.len() {
+            *out.get_mut(len).ok_or(RejectReason::TooLong)? = 0;
+            len += 1;
+        }
+    }
+    Ok(len)
+}
+
+/// Body being written
+struct BodyWriter {
+    buf: [u8; MAX_BODY],
+    len: usize,
+}
+
+impl BodyWriter {
+    fn new() -> Self {
+        Self {
+            buf: [0; MAX_BODY],
+            len: 0,
+        }
+    }
+
+    fn bytes(&mut self, bytes: &[u8]) -> &mut Self {
+        self.buf[self.len..self.len + bytes.len()].copy_from_slice(bytes);
+        self.len += bytes.len();
+        self
+    }
+
+    fn u8(&mut self, value: u8) -> &mut Self {
+        self.bytes(&[value])
+    }
+
+    fn u16(&mut self, value: u16) -> &mut Self {
+        self.bytes(&value.to_le_bytes())
+    }
+
+    fn u32(&mut self, value: u32) -> &mut Self {
+        self.bytes(&value.to_le_bytes())
+    }
+
+    fn f32(&mut self, value: f32) -> &mut Self {
+        self.bytes(&value.to_le_bytes())
+    }
+
+    fn vec(&mut self, value: Vec3A) -> &mut Self {
+        self.f32(value.x).f32(value.y).f32(value.z)
+    }
+
+    fn frame(&self, seq: u8, opcode: u8) -> Frame {
+        Frame::new(seq, opcode, &self.buf[..self.len]).expect("bodies fit MAX_BODY")
+    }
+}
+
+/// Body being read, every read failing with [`RejectReason::BadArgument`] past its end
+struct BodyReader<'a> {
+    body: &'a [u8],
+}
+
+impl<'a> BodyReader<'a> {
+    fn bytes<const N: usize>(&mut self) -> Result<[u8; N], RejectReason> {
+        let bytes = self.body.get(..N).ok_or(RejectReason::BadArgument)?;
+        self.body = &self.body[N..];
+        Ok(bytes.try_into().expect("N bytes"))
+    }
+
+    fn u8(&mut self) -> Result<u8, RejectReason> {
+        Ok(self.bytes::<1>()?[0])
+    }
+
+    fn u16(&mut self) -> Result<u16, RejectReason> {
+        Ok(u16::from_le_bytes(self.bytes()?))
+    }
+
+    fn u32(&mut self) -> Result<u32, RejectReason> {
+        Ok(u32::from_le_bytes(self.bytes()?))
+    }
+
+    fn f32(&mut self) -> Result<f32, RejectReason> {
+        Ok(f32::from_le_bytes(self.bytes()?))
+    }
+
+    fn vec(&mut self) -> Result<Vec3A, RejectReason> {
+        Ok(Vec3A::new(self.f32()?, self.f32()?, self.f32()?))
+    }
+
+    fn rest(&mut self) -> &'a [u8] {
+        core::mem::take(&mut self.body)
+    }
+
+    /// `value` if the whole body was read
+    fn end<T>(&self, value: T) -> Result<T, RejectReason> {
+        match self.body.is_empty() {
+            true => Ok(value),
+            false => Err(RejectReason::BadArgument),
+        }
+    }
+}
+
+/// Options of a [`Calibrate`](Command::Calibrate), the rest of the
+/// [`AutoSetupOptions`] at their defaults
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct RemoteCalibration {
+    /// samples averaged for the gyro offset
+    pub gyro_samples: u16,
+    /// samples averaged for the accel offset
+    pub acc_samples: u16,
+    /// the device lies level, Z up. The accel is calibrated only if set
+    pub device_is_level: bool,
+}
+
+impl Default for RemoteCalibration {
+    fn default() -> Self {
+        let options = AutoSetupOptions::default();
+        Self {
+            gyro_samples: options.gyro_samples,
+            acc_samples: options.acc_samples,
+            device_is_level: options.device_is_level,
+        }
+    }
+}
+
+/// Command from the client, see the [commands](self#commands)
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Command {
+    /// version and limits of the server
+    Hello,
+    /// [`init`](Mpu6050::init), then the settings of a profile
+    Init {
+        /// 0 init only, `n` [`presets::ALL`](crate::presets::ALL)`[n - 1]`
+        profile: u8,
+    },
+    /// one scaled sample
+    GetAll,
+    /// [`set_accel_range`](Mpu6050::set_accel_range)
+    SetAccelRange(AccelRange),
+    /// [`set_gyro_range`](Mpu6050::set_gyro_range)
+    SetGyroRange(GyroRange),
+    /// [`set_dlpf`](Mpu6050::set_dlpf)
+    SetDlpf(u8),
+    /// [`set_sample_rate_divider`](Mpu6050::set_sample_rate_divider)
+    SetSampleRateDivider(u8),
+    /// calibrates the offsets, streaming progress
+    Calibrate(RemoteCalibration),
+    /// accel self-test response
+    SelfTest,
+    /// the calibration blob, streamed in parts
+    ExportCalibration,
+    /// chip, connection, power and calibration state
+    HealthCheck,
+}
+
+impl Command {
+    /// opcode on the wire
+    pub const fn opcode(&self) -> u8 {
+        match self {
+            Command::Hello => 0x01,
+            Command::Init { .. } => 0x02,
+            Command::GetAll => 0x03,
+            Command::SetAccelRange(_) => 0x04,
+            Command::SetGyroRange(_) => 0x05,
+            Command::SetDlpf(_) => 0x06,
+            Command::SetSampleRateDivider(_) => 0x07,
+            Command::Calibrate(_) => 0x08,
+            Command::SelfTest => 0x09,
+            Command::ExportCalibration => 0x0a,
+            Command::HealthCheck => 0x0b,
+        }
+    }
+
+    /// frame of the command with sequence number `seq`
+    pub fn to_frame(&self, seq: u8) -> Frame {
+        let mut body = BodyWriter::new();
+        match *self {
+            Command::Init { profile } => body.u8(profile),
+            Command::SetAccelRange(range) => body.u8(range as u8),
+            Command::SetGyroRange(range) => body.u8(range as u8),
+            Command::SetDlpf(dlpf_cfg) => body.u8(dlpf_cfg),
+            Command::SetSampleRateDivider(div) => body.u8(div),
+            Command::Calibrate(options) => body
+                .u16(options.gyro_samples)
+                .u16(options.acc_samples)
+                .u8(options.device_is_level as u8),
+            _ => &mut body,
+        };
+        body.frame(seq, self.opcode())
+    }
+
+    /// command of a frame. A profile or range out of range is a
+    /// [`BadArgument`](RejectReason::BadArgument), the DLPF configuration is left to the
+    /// driver's validation
+    pub fn from_frame(frame: &Frame) -> Result<Self, RejectReason> {
+        let mut body = BodyReader { body: frame.body() };
+        let command = match frame.opcode {
+            0x01 => Command::Hello,
+            0x02 => match body.u8()? {
+                profile if usize::from(profile) <= presets::ALL.len() => Command::Init { profile },
+                _ => return Err(RejectReason::BadArgument),
+            },
+            0x03 => Command::GetAll,
+            0x04 => Command::SetAccelRange(range_arg(body.u8()?)?.into()),
+            0x05 => Command::SetGyroRange(range_arg(body.u8()?)?.into()),
+            0x06 => Command::SetDlpf(body.u8()?),
+            0x07 => Command::SetSampleRateDivider(body.u8()?),
+            0x08 => Command::Calibrate(RemoteCalibration {
+                gyro_samples: body.u16()?,
+                acc_samples: body.u16()?,
+                device_is_level: body.u8()? != 0,
+            }),
+            0x09 => Command::SelfTest,
+            0x0a => Command::ExportCalibration,
+            0x0b => Command::HealthCheck,
+            _ => return Err(RejectReason::UnknownCommand),
+        };
+        body.end(command)
+    }
+}
+
+/// AFS_SEL or FS_SEL, 0 to 3
+fn range_arg(sel: u8) -> Result<u8, RejectReason> {
+    match sel {
+        0..=3 => Ok(sel),
+        _ => Err(RejectReason::BadArgument),
+    }
+}
+
+/// Version and limits of a server
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct ServerInfo {
+    /// protocol version
+    pub version: u8,
+    /// longest body accepted
+    pub max_body: u8,
+    /// profiles of [`Init`](Command::Init) besides 0
+    pub profiles: u8,
+}
+
+/// Offsets determined by a [`Calibrate`](Command::Calibrate), applied on the server
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct CalibrationOffsets {
+    /// gyro offset in rad/s
+    pub gyro: Vec3A,
+    /// accel offset in g
+    pub acc: Vec3A,
+}
+
+/// State of the server's sensor
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct RemoteHealth {
+    /// WHO_AM_I, None if the read failed
+    pub who_am_i: Option<u8>,
+    /// [`connection_state`](Mpu6050::connection_state) is `Connected`
+    pub connected: bool,
+    /// [`io_stats`](Mpu6050::io_stats) transactions
+    pub transactions: u32,
+    /// [`power_state`](Mpu6050::power_state) asleep
+    pub asleep: bool,
+    /// the [`supervisor`](crate::supervisor) tripped
+    pub supervisor_tripped: bool,
+    /// [`calibration_status`](Mpu6050::calibration_status)
+    pub calibration: CalibrationStatus,
+}
+
+/// Part of an exported blob
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct BlobChunk {
+    /// position of the part in the blob
+    pub offset: u16,
+    len: u8,
+    data: [u8; BLOB_CHUNK],
+}
+
+impl BlobChunk {
+    /// part at `offset`, None if `data` is longer than [`BLOB_CHUNK`]
+    pub fn new(offset: u16, data: &[u8]) -> Option<Self> {
+        let mut chunk = Self {
+            offset,
+            len: data.len() as u8,
+            data: [0; BLOB_CHUNK],
+        };
+        chunk.data.get_mut(..data.len())?.copy_from_slice(data);
+        Some(chunk)
+    }
+
+    /// bytes of the part
+    pub fn data(&self) -> &[u8] {
+        &self.data[..self.len as usize]
+    }
+}
+
+/// Command that ran and failed
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum RemoteFailure {
+    /// driver error, by its [code](crate::black_box#error-codes)
+    Driver(u8),
+    /// calibration failed in `phase`
+    Calibration {
+        /// failed phase
+        phase: AutoSetupPhase,
+        /// driver error code, None if the samples were rejected as moving
+        driver: Option<u8>,
+    },
+}
+
+impl Display for RemoteFailure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = |code| crate::black_box::error_name(code).unwrap_or("unknown error");
+        match self {
+            RemoteFailure::Driver(code) => write!(f, "{} ({})", name(*code), code),
+            RemoteFailure::Calibration {
+                phase,
+                driver: Some(code),
+            } => write!(f, "{} failed: {} ({})", phase.name(), name(*code), code),
+            RemoteFailure::Calibration {
+                phase,
+                driver: None,
+            } => write!(f, "{} failed: samples rejected", phase.name()),
+        }
+    }
+}
+
+/// Response of the server, see the [responses](self#responses)
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Response {
+    /// command done
+    Ack,
+    /// answer to [`Hello`](Command::Hello)
+    Hello(ServerInfo),
+    /// answer to [`GetAll`](Command::GetAll), the settling and out of band flags kept
+    Sample(MpuSample),
+    /// calibration done and applied
+    Calibrated(CalibrationOffsets),
+    /// accel self-test response
+    SelfTest {
+        /// response per axis in g
+        response_g: Vec3A,
+    },
+    /// blob sent
+    BlobDone {
+        /// length of the blob
+        len: u16,
+        /// [`crc16`](crate::packed::crc16) of the blob
+        crc: u16,
+    },
+    /// answer to [`HealthCheck`](Command::HealthCheck)
+    Health(RemoteHealth),
+    /// calibration progress, part response
+    Progress {
+        /// phase running
+        phase: AutoSetupPhase,
+        /// number of the progress frame, from 0
+        part: u16,
+    },
+    /// blob part, part response
+    BlobPart(BlobChunk),
+    /// command failed
+    Failed(RemoteFailure),
+    /// frame refused
+    Rejected {
+        /// why
+        reason: RejectReason,
+        /// protocol version of the server
+        version: u8,
+    },
+}
+
+impl Response {
+    /// opcode on the wire
+    pub const fn opcode(&self) -> u8 {
+        match self {
+            Response::Ack => 0x80,
+            Response::Hello(_) => 0x81,
+            Response::Sample(_) => 0x82,
+            Response::Calibrated(_) => 0x83,
+            Response::SelfTest { .. } => 0x84,
+            Response::BlobDone { .. } => 0x85,
+            Response::Health(_) => 0x86,
+            Response::Progress { .. } => 0xa0,
+            Response::BlobPart(_) => 0xa1,
+            Response::Failed(_) => 0xe0,
+            Response::Rejected { .. } => 0xe1,
+        }
+    }
+
+    /// true for part responses, more follow to the same command
+    pub const fn is_part(&self) -> bool {
+        matches!(self.opcode(), 0xa0..=0xdf)
+    }
+
+    /// frame of the response with sequence number `seq`
+    pub fn to_frame(&self, seq: u8) -> Frame {
+        let mut body = BodyWriter::new();
+        match *self {
+            Response::Ack => &mut body,
+            Response::Hello(info) => body.u8(info.version).u8(info.max_body).u8(info.profiles),
+            Response::Sample(sample) => body
+                .vec(sample.acc())
+                .vec(sample.gyro())
+                .f32(sample.temp())
+                .u8(sample.settling() as u8 | (sample.out_of_band() as u8) << 1),
+            Response::Calibrated(offsets) => body.vec(offsets.gyro).vec(offsets.acc),
+            Response::SelfTest { response_g } => body.vec(response_g),
+            Response::BlobDone { len, crc } => body.u16(len).u16(crc),
+            Response::Health(health) => {
+                let flags = health.who_am_i.is_some() as u8
+                    | (health.connected as u8) << 1
+                    | (health.asleep as u8) << 2
+                    | (health.supervisor_tripped as u8) << 3;
+                let calibration = match health.calibration {
+                    CalibrationStatus::Valid => 0,
+                    CalibrationStatus::Degraded { .. } => 1,
+                    CalibrationStatus::Expired { .. } => 2,
+                };
+                body.u8(health.who_am_i.unwrap_or(0))
+                    .u8(flags)
+                    .u32(health.transactions)
+                    .u8(calibration)
+                    .u8(health.calibration.reasons().bits())
+            }
+            Response::Progress { phase, part } => body.u8(phase as u8).u16(part),
+            Response::BlobPart(chunk) => body.u16(chunk.offset).bytes(chunk.data()),
+            Response::Failed(RemoteFailure::Driver(code)) => body.u8(0).u8(code),
+            Response::Failed(RemoteFailure::Calibration { phase, driver }) => {
+                body.u8(1).u8(phase as u8).u8(driver.unwrap_or(0))
+            }
+            Response::Rejected { reason, version } => body.u8(reason.code()).u8(version),
+        };
+        body.frame(seq, self.opcode())
+    }
+
+    /// response of a frame
+    pub fn from_frame(frame: &Frame) -> Result<Self, RejectReason> {
+        let mut body = BodyReader { body: frame.body() };
+        let response = match frame.opcode {
+            0x80 => Response::Ack,
+            0x81 => Response::Hello(ServerInfo {
+                version: body.u8()?,
+                max_body: body.u8()?,
+                profiles: body.u8()?,
+            }),
+            0x82 => {
+                let (acc, gyro, temp) = (body.vec()?, body.vec()?, body.f32()?);
+                let flags = body.u8()?;
+                Response::Sample(
+                    MpuSample::new(acc, gyro, temp)
+                        .with_settling(flags & 1 != 0)
+                        .with_out_of_band(flags & 2 != 0),
+                )
+            }
+            0x83 => Response::Calibrated(CalibrationOffsets {
+                gyro: body.vec()?,
+                acc: body.vec()?,
+            }),
+            0x84 => Response::SelfTest {
+                response_g: body.vec()?,
+            },
+            0x85 => Response::BlobDone {
+                len: body.u16()?,
+                crc: body.u16()?,
+            },
+            0x86 => {
+                let (who_am_i, flags, transactions) = (body.u8()?, body.u8()?, body.u32()?);
+                let (calibration, reasons) = (body.u8()?, body.u8()?);
+                let reasons = InvalidationReasons::from_bits(reasons);
+                Response::Health(RemoteHealth {
+                    who_am_i: (flags & 1 != 0).then_some(who_am_i),
+                    connected: flags & 2 != 0,
+                    transactions,
+                    asleep: flags & 4 != 0,
+                    supervisor_tripped: flags & 8 != 0,
+                    calibration: match calibration {
+                        0 => CalibrationStatus::Valid,
+                        1 => CalibrationStatus::Degraded { reasons },
+                        2 => CalibrationStatus::Expired { reasons },
+                        _ => return Err(RejectReason::BadArgument),
+                    },
+                })
+            }
+            0xa0 => Response::Progress {
+                phase: phase_arg(body.u8()?)?,
+                part: body.u16()?,
+            },
+            0xa1 => {
+                let offset = body.u16()?;
+                let chunk = BlobChunk::new(offset, body.rest()).ok_or(RejectReason::TooLong)?;
+                Response::BlobPart(chunk)
+            }
+            0xe0 => Response::Failed(match body.u8()? {
+                0 => RemoteFailure::Driver(body.u8()?),
+                1 => RemoteFailure::Calibration {
+                    phase: phase_arg(body.u8()?)?,
+                    driver: Some(body.u8()?).filter(|code| *code != 0),
+                },
+                _ => return Err(RejectReason::BadArgument),
+            }),
+            0xe1 => Response::Rejected {
+                reason: RejectReason::from_code(body.u8()?).ok_or(RejectReason::BadArgument)?,
+                version: body.u8()?,
+            },
+            _ => return Err(RejectReason::UnknownCommand),
+        };
+        body.end(response)
+    }
+}
+
+fn phase_arg(phase: u8) -> Result<AutoSetupPhase, RejectReason> {
+    AutoSetupPhase::ALL
+        .get(usize::from(phase))
+        .copied()
+        .ok_or(RejectReason::BadArgument)
+}
+
+/// Framing over a [`Transport`], on either side. Keeps the bytes of a frame received in
+/// part until its delimiter arrives
+#[derive(Debug)]
+pub struct RemoteLink<T> {
+    transport: T,
+    rx: [u8; MAX_WIRE],
+    rx_len: usize,
+    discarding: bool,
+}
+
+impl<T> RemoteLink<T> {
+    /// link over `transport`, nothing received yet
+    pub fn new(transport: T) -> Self {
+        Self {
+            transport,
+            rx: [0; MAX_WIRE],
+            rx_len: 0,
+            discarding: false,
+        }
+    }
+
+    /// the transport
+    pub fn transport(&self) -> &T {
+        &self.transport
+    }
+
+    /// the transport, mutable
+    pub fn transport_mut(&mut self) -> &mut T {
+        &mut self.transport
+    }
+
+    /// the transport, bytes of a partly received frame dropped
+    pub fn into_inner(self) -> T {
+        self.transport
+    }
+}
+
+impl<T: Transport> RemoteLink<T> {
+    /// writes `frame`
+    pub fn send(&mut self, frame: &Frame) -> Result<(), T::Error> {
+        let mut wire = [0; MAX_WIRE];
+        let len = frame.encode(&mut wire);
+        self.transport.write(&wire[..len])
+    }
+
+    /// writes `command` with sequence number `seq`
+    pub fn send_command(&mut self, seq: u8, command: &Command) -> Result<(), T::Error> {
+        self.send(&command.to_frame(seq))
+    }
+
+    /// writes `response` with sequence number `seq`
+    pub fn send_response(&mut self, seq: u8, response: &Response) -> Result<(), T::Error> {
+        self.send(&response.to_frame(seq))
+    }
+
+    /// Reads until the next delimiter, None once the transport has no more bytes. Reads a
+    /// byte at a time, bytes after the delimiter stay in the transport. A frame overrunning
+    /// [`MAX_WIRE`] is dropped up to its delimiter, then reported as
+    /// [`TooLong`](RejectReason::TooLong)
+    #[allow(clippy::type_complexity)]
+    pub fn receive(&mut self) -> Result<Option<Result<Frame, RejectReason>>, T::Error> {
+        let mut byte = [0];
+        while self.transport.read(&mut byte)? > 0 {
+            match byte[0] {
+                0 => {
+                    let len = core::mem::take(&mut self.rx_len);
+                    if core::mem::take(&mut self.discarding) {
+                        return Ok(Some(Err(RejectReason::TooLong)));
+                    }
+                    if len > 0 {
+                        return Ok(Some(Frame::decode(&self.rx[..len])));
+                    }
+                }
+                _ if self.discarding => {}
+                _ if self.rx_len == MAX_WIRE => self.discarding = true,
+                byte => {
+                    self.rx[self.rx_len] = byte;
+                    self.rx_len += 1;
+                }
+            }
+        }
+        Ok(None)
+    }
+}
+
+/// Work of one [`serve`](Mpu6050::serve) call
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct ServeBudget {
+    /// frames handled before returning, 0 is taken as 1
+    pub commands: u16,
+    /// bus work of a calibration between two [`Progress`](Response::Progress) frames
+    pub progress: DrainBudget,
+}
+
+impl Default for ServeBudget {
+    fn default() -> Self {
+        Self {
+            commands: 4,
+            progress: DrainBudget::Transactions(100),
+        }
+    }
+}
+
+/// Frames handled by one [`serve`](Mpu6050::serve) call
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub struct ServeStats {
+    /// frames handled, rejected ones included
+    pub commands: u16,
+    /// frames answered with [`Rejected`](Response::Rejected)
+    pub rejected: u16,
+}
+
+/// Progress frames of a running calibration
+struct ProgressStream<'l, T: Transport> {
+    link: &'l mut RemoteLink<T>,
+    seq: u8,
+    phase: AutoSetupPhase,
+    part: u16,
+    error: Option<T::Error>,
+}
+
+impl<T: Transport> ProgressStream<'_, T> {
+    fn send(&mut self, phase: Option<AutoSetupPhase>) {
+        self.phase = phase.unwrap_or(self.phase);
+        if self.error.is_none() {
+            let progress = Response::Progress {
+                phase: self.phase,
+                part: self.part,
+            };
+            self.error = self.link.send_response(self.seq, &progress).err();
+            self.part = self.part.saturating_add(1);
+        }
+    }
+}
+
+impl<I, D, E> Mpu6050<I, D>
+where
+    I: Write<Error = E> + WriteRead<Error = E>,
+{
+    pub(crate) fn serve_using<T: Transport>(
+        &mut self,
+        delay: &mut impl DelayMs<u8>,
+        link: &mut RemoteLink<T>,
+        budget: ServeBudget,
+    ) -> Result<ServeStats, T::Error> {
+        let mut stats = ServeStats::default();
+        while stats.commands < budget.commands.max(1) {
+            let Some(frame) = link.receive()? else {
+                break;
+            };
+            stats.commands += 1;
+            let rejected = |reason| Response::Rejected {
+                reason,
+                version: REMOTE_VERSION,
+            };
+            let (seq, response) = match frame {
+                Err(reason) => (0, rejected(reason)),
+                Ok(frame) if frame.version != REMOTE_VERSION => {
+                    (frame.seq, rejected(RejectReason::Version))
+                }
+                Ok(frame) => match Command::from_frame(&frame) {
+                    Err(reason) => (frame.seq, rejected(reason)),
+                    Ok(command) => {
+                        let response =
+                            self.execute_command(delay, link, frame.seq, command, budget)?;
+                        (frame.seq, response)
+                    }
+                },
+            };
+            if let Response::Rejected { .. } = response {
+                stats.rejected += 1;
+            }
+            link.send_response(seq, &response)?;
+        }
+        Ok(stats)
+    }
+
+    /// runs `command`, the final response for the caller to send
+    fn execute_command<T: Transport>(
+        &mut self,
+        delay: &mut impl DelayMs<u8>,
+        link: &mut RemoteLink<T>,
+        seq: u8,
+        command: Command,
+        budget: ServeBudget,
+    ) -> Result<Response, T::Error> {
+        let response = match command {
+            Command::Hello => Ok(Response::Hello(ServerInfo {
+                version: REMOTE_VERSION,
+                max_body: MAX_BODY as u8,
+                profiles: presets::ALL.len() as u8,
+            })),
+            Command::Init { profile } => self.init_using(delay).and_then(|()| {
+                match usize::from(profile).checked_sub(1) {
+                    Some(n) => self.apply_settings(&presets::ALL[n].1),
+                    None => Ok(()),
+                }
+                .map(|()| Response::Ack)
+            }),
+            Command::GetAll => self.read_sample().map(Response::Sample),
+            Command::SetAccelRange(range) => self.set_accel_range(range).map(|()| Response::Ack),
+            Command::SetGyroRange(range) => self.set_gyro_range(range).map(|()| Response::Ack),
+            Command::SetDlpf(dlpf_cfg) => self.set_dlpf(dlpf_cfg).map(|()| Response::Ack),
+            Command::SetSampleRateDivider(div) => {
+                self.set_sample_rate_divider(div).map(|()| Response::Ack)
+            }
+            Command::Calibrate(options) => {
+                return self.calibrate_streamed(delay, link, seq, options, budget.progress)
+            }
+            Command::SelfTest => self
+                .actuation_response_using(delay)
+                .map(|response_g| Response::SelfTest { response_g }),
+            #[cfg(feature = "std")]
+            Command::ExportCalibration => return self.export_streamed(link, seq),
+            #[cfg(not(feature = "std"))]
+            Command::ExportCalibration => {
+                return Ok(Response::Rejected {
+                    reason: RejectReason::UnknownCommand,
+                    version: REMOTE_VERSION,
+                })
+            }
+            Command::HealthCheck => Ok(Response::Health(RemoteHealth {
+                who_am_i: self.read_register(Register::WHO_AM_I).ok(),
+                connected: self.connection_state() == crate::connection::ConnectionState::Connected,
+                transactions: self.io_stats.transactions,
+                asleep: self.power_state().asleep,
+                supervisor_tripped: self.supervisor_tripped().is_some(),
+                calibration: self.calibration_status(),
+            })),
+        };
+        Ok(response.unwrap_or_else(|e| Response::Failed(RemoteFailure::Driver(e.code()))))
+    }
+
+    fn calibrate_streamed<T: Transport>(
+        &mut self,
+        delay: &mut impl DelayMs<u8>,
+        link: &mut RemoteLink<T>,
+        seq: u8,
+        calibration: RemoteCalibration,
+        budget: DrainBudget,
+    ) -> Result<Response, T::Error> {
+        let options = AutoSetupOptions {
+            accel_range: self.accel_range,
+            gyro_range: self.gyro_range,
+            actuation_check: false,
+            gyro_samples: calibration.gyro_samples,
+            device_is_level: calibration.device_is_level,
+            acc_samples: calibration.acc_samples,
+            ..AutoSetupOptions::default()
+        };
+        let stream = RefCell::new(ProgressStream {
+            link,
+            seq,
+            phase: AutoSetupPhase::Init,
+            part: 0,
+            error: None,
+        });
+        let mut on_phase = |phase| stream.borrow_mut().send(Some(phase));
+        let result = self.auto_setup_streamed_using(
+            delay,
+            options,
+            Some(&mut on_phase),
+            || stream.borrow().error.is_some(),
+            budget,
+            || stream.borrow_mut().send(None),
+        );
+        if let Some(error) = stream.into_inner().error {
+            return Err(error);
+        }
+        Ok(match result {
+            Ok(report) => Response::Calibrated(CalibrationOffsets {
+                gyro: report.gyro_offset,
+                acc: report.acc_offset,
+            }),
+            Err(error) => Response::Failed(RemoteFailure::Calibration {
+                phase: error.phase,
+                driver: match error.failure {
+                    AutoSetupFailure::Device(e) => Some(e.code()),
+                    _ => None,
+                },
+            }),
+        })
+    }
+
+    #[cfg(feature = "std")]
+    fn export_streamed<T: Transport>(
+        &mut self,
+        link: &mut RemoteLink<T>,
+        seq: u8,
+    ) -> Result<Response, T::Error> {
+        let header = match self.build_log_header() {
+            Ok(header) => header,
+            Err(e) => return Ok(Response::Failed(RemoteFailure::Driver(e.code()))),
+        };
+        let mut blob = vec![0; header.encoded_len()];
+        header.encode(&mut blob).expect("sized by encoded_len");
+        for (i, data) in blob.chunks(BLOB_CHUNK).enumerate() {
+            let chunk = BlobChunk::new((i * BLOB_CHUNK) as u16, data).expect("BLOB_CHUNK bytes");
+            link.send_response(seq, &Response::BlobPart(chunk))?;
+        }
+        Ok(Response::BlobDone {
+            len: blob.len() as u16,
+            crc: crc16(&blob),
+        })
+    }
+}
+
+/// Error of a [`RemoteClient`] request
+#[derive(Debug, PartialEq)]
+pub enum ClientError<E> {
+    /// error of the client's transport
+    Transport(E),
+    /// no final response within the idle reads
+    Timeout,
+    /// a response frame that did not decode, or an export whose parts do not add up
+    Garbled(RejectReason),
+    /// the server speaks another protocol version
+    Version(u8),
+    /// the server refused the command
+    Rejected(RejectReason),
+    /// the command ran and failed
+    Failed(RemoteFailure),
+    /// a final response of another command, its opcode
+    Unexpected(u8),
+}
+
+impl<E: Debug> Display for ClientError<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ClientError::Transport(e) => write!(f, "transport error: {:?}", e),
+            ClientError::Timeout => f.write_str("no response"),
+            ClientError::Garbled(reason) => write!(f, "garbled response: {}", reason),
+            ClientError::Version(version) => {
+                write!(
+                    f,
+                    "server speaks version {}, not {}",
+                    version, REMOTE_VERSION
+                )
+            }
+            ClientError::Rejected(reason) => write!(f, "command rejected: {}", reason),
+            ClientError::Failed(failure) => write!(f, "command failed: {}", failure),
+            ClientError::Unexpected(opcode) => write!(f, "unexpected response {:#04x}", opcode),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<E: Debug> std::error::Error for ClientError<E> {}
+
+/// Host side of the protocol: sends a command, waits for its final response
+#[derive(Debug)]
+pub struct RemoteClient<T> {
+    link: RemoteLink<T>,
+    seq: u8,
+    max_idle_reads: u32,
+}
+
+impl<T> RemoteClient<T> {
+    /// client over `transport`, giving up after 10000 reads without a byte
+    pub fn new(transport: T) -> Self {
+        Self {
+            link: RemoteLink::new(transport),
+            seq: 0,
+            max_idle_reads: 10_000,
+        }
+    }
+
+    /// gives up after `reads` reads in a row returning no byte. A transport with a read
+    /// timeout of its own counts its timeouts
+    pub fn with_max_idle_reads(self, reads: u32) -> Self {
+        Self {
+            max_idle_reads: reads,
+            ..self
+        }
+    }
+
+    /// the link
+    pub fn link(&self) -> &RemoteLink<T> {
+        &self.link
+    }
+
+    /// the link, mutable
+    pub fn link_mut(&mut self) -> &mut RemoteLink<T> {
+        &mut self.link
+    }
+
+    /// the transport
+    pub fn into_inner(self) -> T {
+        self.link.into_inner()
+    }
+}
+
+impl<T: Transport> RemoteClient<T> {
+    /// Sends `command` with the next sequence number, 1 to 255, and returns its final
+    /// response, part responses passed to `on_part`. Responses of another sequence number
+    /// are left over from an earlier request and skipped, a rejection with number 0 is
+    /// taken as the server's answer to a garbled command
+    pub fn request(
+        &mut self,
+        command: &Command,
+        mut on_part: impl FnMut(&Response),
+    ) -> Result<Response, ClientError<T::Error>> {
+        self.seq = self.seq % 255 + 1;
+        self.link
+            .send_command(self.seq, command)
+            .map_err(ClientError::Transport)?;
+        let mut idle = 0;
+        loop {
+            let frame = match self.link.receive().map_err(ClientError::Transport)? {
+                Some(frame) => frame.map_err(ClientError::Garbled)?,
+                None if idle < self.max_idle_reads => {
+                    idle += 1;
+                    continue;
+                }
+                None => return Err(ClientError::Timeout),
+            };
+            idle = 0;
+            if frame.version != REMOTE_VERSION {
+                return Err(ClientError::Version(frame.version));
+            }
+            if frame.seq != self.seq && frame.seq != 0 {
+                continue;
+            }
+            match Response::from_frame(&frame).map_err(ClientError::Garbled)? {
+                part if part.is_part() => on_part(&part),
+                Response::Rejected {
+                    reason: RejectReason::Version,
+                    version,
+                } => return Err(ClientError::Version(version)),
+                Response::Rejected { reason, .. } => return Err(ClientError::Rejected(reason)),
+                Response::Failed(failure) => return Err(ClientError::Failed(failure)),
+                response => return Ok(response),
+            }
+        }
+    }
+
+    /// `command` expecting [`Ack`](Response::Ack)
+    fn acked(&mut self, command: Command) -> Result<(), ClientError<T::Error>> {
+        match self.request(&command, |_| {})? {
+            Response::Ack => Ok(()),
+            other => Err(ClientError::Unexpected(other.opcode())),
+        }
+    }
+
+    /// version and limits of the server
+    pub fn hello(&mut self) -> Result<ServerInfo, ClientError<T::Error>> {
+        match self.request(&Command::Hello, |_| {})? {
+            Response::Hello(info) => Ok(info),
+            other => Err(ClientError::Unexpected(other.opcode())),
+        }
+    }
+
+    /// initializes the sensor, then applies profile `profile`, 0 for none
+    pub fn init(&mut self, profile: u8) -> Result<(), ClientError<T::Error>> {
+        self.acked(Command::Init { profile })
+    }
+
+    /// one scaled sample
+    pub fn get_all(&mut self) -> Result<MpuSample, ClientError<T::Error>> {
+        match self.request(&Command::GetAll, |_| {})? {
+            Response::Sample(sample) => Ok(sample),
+            other => Err(ClientError::Unexpected(other.opcode())),
+        }
+    }
+
+    /// sets the accel range
+    pub fn set_accel_range(&mut self, range: AccelRange) -> Result<(), ClientError<T::Error>> {
+        self.acked(Command::SetAccelRange(range))
+    }
+
+    /// sets the gyro range
+    pub fn set_gyro_range(&mut self, range: GyroRange) -> Result<(), ClientError<T::Error>> {
+        self.acked(Command::SetGyroRange(range))
+    }
+
+    /// sets DLPF_CFG
+    pub fn set_dlpf(&mut self, dlpf_cfg: u8) -> Result<(), ClientError<T::Error>> {
+        self.acked(Command::SetDlpf(dlpf_cfg))
+    }
+
+    /// sets SMPLRT_DIV
+    pub fn set_sample_rate_divider(&mut self, div: u8) -> Result<(), ClientError<T::Error>> {
+        self.acked(Command::SetSampleRateDivider(div))
+    }
+
+    /// calibrates the offsets, `on_progress` called with the phase and number of every
+    /// progress frame
+    pub fn calibrate(
+        &mut self,
+        calibration: RemoteCalibration,
+        mut on_progress: impl FnMut(AutoSetupPhase, u16),
+    ) -> Result<CalibrationOffsets, ClientError<T::Error>> {
+        let on_part = |part: &Response| {
+            if let Response::Progress { phase, part } = *part {
+                on_progress(phase, part);
+            }
+        };
+        match self.request(&Command::Calibrate(calibration), on_part)? {
+            Response::Calibrated(offsets) => Ok(offsets),
+            other => Err(ClientError::Unexpected(other.opcode())),
+        }
+    }
+
+    /// accel self-test response per axis in g
+    pub fn self_test(&mut self) -> Result<Vec3A, ClientError<T::Error>> {
+        match self.request(&Command::SelfTest, |_| {})? {
+            Response::SelfTest { response_g } => Ok(response_g),
+            other => Err(ClientError::Unexpected(other.opcode())),
+        }
+    }
+
+    /// The calibration blob, an encoded [`LogHeader`](crate::log_header::LogHeader). Parts
+    /// out of order, missing or failing the final length and CRC check are
+    /// [`Garbled`](ClientError::Garbled)
+    #[cfg(feature = "std")]
+    pub fn export_calibration(&mut self) -> Result<Vec<u8>, ClientError<T::Error>> {
+        let mut blob = Vec::new();
+        let mut in_order = true;
+        let on_part = |part: &Response| {
+            if let Response::BlobPart(chunk) = part {
+                in_order &= usize::from(chunk.offset) == blob.len();
+                blob.extend_from_slice(chunk.data());
+            }
+        };
+        let (len, crc) = match self.request(&Command::ExportCalibration, on_part)? {
+            Response::BlobDone { len, crc } => (len, crc),
+            other => return Err(ClientError::Unexpected(other.opcode())),
+        };
+        if !in_order || usize::from(len) != blob.len() {
+            return Err(ClientError::Garbled(RejectReason::Malformed));
+        }
+        if crc16(&blob) != crc {
+            return Err(ClientError::Garbled(RejectReason::CrcMismatch));
+        }
+        Ok(blob)
+    }
+
+    /// chip, connection, power and calibration state
+    pub fn health(&mut self) -> Result<RemoteHealth, ClientError<T::Error>> {
+        match self.request(&Command::HealthCheck, |_| {})? {
+            Response::Health(health) => Ok(health),
+            other => Err(ClientError::Unexpected(other.opcode())),
+        }
+    }
+}
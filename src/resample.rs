@@ -0,0 +1,230 @@
+//! Resampling of timestamped samples onto an exact wall-clock grid.
+//!
+//! The sensor's oscillator is off by up to a few percent, a nominal 100 Hz stream arrives at
+//! 99.x or 100.x Hz of host time. [`UniformResampler`] takes the samples with their measured
+//! host timestamps and produces samples at exactly `origin + floor(k * 1e6 / rate)` µs, the
+//! origin being the first input timestamp. Values are interpolated linearly between the two
+//! inputs around each grid point with [`interpolate`], and tagged interpolated, grid points
+//! falling on an input are that input unchanged.
+//!
+//! An input interval longer than the bridge limit (FIFO overflow, disconnect) is not
+//! interpolated across: its grid points are replaced by one [`ResampledItem::Gap`].
+
+use crate::interpolation::{interpolate, InterpolatedSample, TimedSample, TimestampError};
+use crate::time_math::{elapsed_us, grid_time_us};
+
+/// Output of the resampler
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum ResampledItem {
+    /// sample on the grid
+    Sample {
+        /// grid timestamp in µs
+        t_us: u64,
+        /// interpolated values
+        sample: InterpolatedSample,
+    },
+    /// grid points left out because the inputs around them are too far apart
+    Gap {
+        /// timestamp of the input before the gap
+        from_us: u64,
+        /// timestamp of the input after the gap
+        to_us: u64,
+        /// grid points left out
+        skipped: u64,
+    },
+}
+
+/// Linear resampler onto a fixed rate grid
+#[derive(Copy, Clone, Debug)]
+pub struct UniformResampler {
+    rate_hz: u32,
+    nominal_input_hz: f32,
+    max_bridge_us: u64,
+    origin_us: Option<u64>,
+    next_k: u64,
+    prev: Option<TimedSample>,
+    bridged_intervals: u64,
+    bridged_us: u64,
+}
+
+impl UniformResampler {
+    /// Resampler to `rate_hz`, for inputs nominally at `nominal_input_hz` (used for the skew
+    /// estimate only). Input intervals above `max_bridge_us` are reported as gaps. A rate of
+    /// 0 is treated as 1 Hz
+    pub fn new(rate_hz: u32, nominal_input_hz: f32, max_bridge_us: u64) -> Self {
+        Self {
+            rate_hz: rate_hz.max(1),
+            nominal_input_hz,
+            max_bridge_us,
+            origin_us: None,
+            next_k: 0,
+            prev: None,
+            bridged_intervals: 0,
+            bridged_us: 0,
+        }
+    }
+
+    /// timestamp of grid point `k`, None before the first input
+    pub fn grid_time(&self, k: u64) -> Option<u64> {
+        Some(grid_time_us(self.origin_us?, k, self.rate_hz))
+    }
+
+    /// Input rate measured over all bridged intervals, None before the second input
+    pub fn input_rate_hz(&self) -> Option<f32> {
+        if self.bridged_us == 0 {
+            return None;
+        }
+        Some((self.bridged_intervals as f64 * 1e6 / self.bridged_us as f64) as f32)
+    }
+
+    /// Measured minus nominal duration of all bridged intervals in µs: positive if the
+    /// sensor runs slow relative to the host clock
+    pub fn clock_skew_us(&self) -> f32 {
+        let nominal_us = self.bridged_intervals as f64 * 1e6 / self.nominal_input_hz as f64;
+        (self.bridged_us as f64 - nominal_us) as f32
+    }
+
+    /// Feeds the next input. Returns the grid points up to and including `entry.t_us`.
+    /// Timestamps must be strictly increasing, a rejected input changes nothing
+    pub fn push(&mut self, entry: TimedSample) -> Result<GridPoints, TimestampError> {
+        let prev = match self.prev {
+            None => {
+                self.origin_us = Some(entry.t_us);
+                self.prev = Some(entry);
+                self.next_k = 1;
+                return Ok(GridPoints {
+                    prev: entry,
+                    cur: entry,
+                    origin_us: entry.t_us,
+                    rate_hz: self.rate_hz,
+                    k: 0,
+                    end_k: 1,
+                    gap: None,
+                });
+            }
+            Some(prev) if entry.t_us == prev.t_us => {
+                return Err(TimestampError::Duplicate { t_us: entry.t_us })
+            }
+            Some(prev) if entry.t_us < prev.t_us => {
+                return Err(TimestampError::OutOfOrder {
+                    newest_us: prev.t_us,
+                    t_us: entry.t_us,
+                })
+            }
+            Some(prev) => prev,
+        };
+
+        let mut k = self.next_k;
+        let mut end_k = k;
+        while self.grid_time(end_k).unwrap_or(u64::MAX) <= entry.t_us {
+            end_k += 1;
+        }
+
+        let interval = elapsed_us(prev.t_us, entry.t_us);
+        let mut gap = None;
+        if interval > self.max_bridge_us {
+            // grid points strictly inside the gap are left out, one exactly on the new
+            // input is still emitted
+            let mut inside_end = end_k;
+            if inside_end > k && self.grid_time(inside_end - 1) == Some(entry.t_us) {
+                inside_end -= 1;
+            }
+            gap = Some(ResampledItem::Gap {
+                from_us: prev.t_us,
+                to_us: entry.t_us,
+                skipped: inside_end - k,
+            });
+            k = inside_end;
+        } else {
+            self.bridged_intervals += 1;
+            self.bridged_us += interval;
+        }
+
+        self.next_k = end_k;
+        self.prev = Some(entry);
+        Ok(GridPoints {
+            prev,
+            cur: entry,
+            origin_us: self.origin_us.unwrap_or(entry.t_us),
+            rate_hz: self.rate_hz,
+            k,
+            end_k,
+            gap,
+        })
+    }
+}
+
+/// Grid points produced by one [`UniformResampler::push`]
+#[derive(Copy, Clone, Debug)]
+pub struct GridPoints {
+    prev: TimedSample,
+    cur: TimedSample,
+    origin_us: u64,
+    rate_hz: u32,
+    k: u64,
+    end_k: u64,
+    gap: Option<ResampledItem>,
+}
+
+impl Iterator for GridPoints {
+    type Item = ResampledItem;
+
+    fn next(&mut self) -> Option<ResampledItem> {
+        if let Some(gap) = self.gap.take() {
+            return Some(gap);
+        }
+        if self.k >= self.end_k {
+            return None;
+        }
+        let t_us = grid_time_us(self.origin_us, self.k, self.rate_hz);
+        self.k += 1;
+        Some(ResampledItem::Sample {
+            t_us,
+            sample: interpolate(&self.prev, &self.cur, t_us),
+        })
+    }
+}
+
+/// Iterator adapter, see [`resample`]
+pub struct Resample<I> {
+    inputs: I,
+    resampler: UniformResampler,
+    pending: Option<GridPoints>,
+}
+
+impl<I> Resample<I> {
+    /// the resampler, for its rate and skew estimates
+    pub fn resampler(&self) -> &UniformResampler {
+        &self.resampler
+    }
+}
+
+impl<I: Iterator<Item = TimedSample>> Iterator for Resample<I> {
+    type Item = Result<ResampledItem, TimestampError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(item) = self.pending.as_mut().and_then(Iterator::next) {
+                return Some(Ok(item));
+            }
+            let input = self.inputs.next()?;
+            match self.resampler.push(input) {
+                Ok(points) => self.pending = Some(points),
+                Err(error) => return Some(Err(error)),
+            }
+        }
+    }
+}
+
+/// Resamples a stream of timestamped samples, rejected timestamps are passed on as errors
+/// and do not stop the stream
+pub fn resample<I>(inputs: I, resampler: UniformResampler) -> Resample<I::IntoIter>
+where
+    I: IntoIterator<Item = TimedSample>,
+{
+    Resample {
+        inputs: inputs.into_iter(),
+        resampler,
+        pending: None,
+    }
+}
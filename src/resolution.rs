@@ -0,0 +1,70 @@
+//! Resolution and noise floor of a configuration, to choose ranges.
+//!
+//! RMS noise is estimated as noise density * √bandwidth, with the DLPF bandwidth standing in for
+//! the equivalent noise bandwidth. All numbers come from [`GYRO_NOISE_DENSITY_DPS`],
+//! [`ACCEL_NOISE_DENSITY_UG`], [`DLPF_BANDWIDTH_HZ`] and [`DLPF_DELAY_MS`].
+
+use crate::device::*;
+#[cfg(not(feature = "std"))]
+use crate::math::FloatExt;
+
+/// Resolution and expected noise of the active configuration
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct ResolutionInfo {
+    /// accel LSB in mg
+    pub accel_mg_per_lsb: f32,
+    /// gyro LSB in °/s
+    pub gyro_dps_per_lsb: f32,
+    /// accel DLPF bandwidth in Hz
+    pub accel_bandwidth_hz: f32,
+    /// gyro DLPF bandwidth in Hz
+    pub gyro_bandwidth_hz: f32,
+    /// accel DLPF delay in ms
+    pub accel_delay_ms: f32,
+    /// gyro DLPF delay in ms
+    pub gyro_delay_ms: f32,
+    /// expected accel RMS noise in mg
+    pub accel_noise_mg_rms: f32,
+    /// expected gyro RMS noise in °/s
+    pub gyro_noise_dps_rms: f32,
+}
+
+impl ResolutionInfo {
+    /// resolution and noise for the given ranges and DLPF_CFG value
+    pub fn new(accel: AccelRange, gyro: GyroRange, dlpf_cfg: u8) -> Self {
+        let cfg = (dlpf_cfg & 0x07) as usize;
+        let (accel_bandwidth_hz, gyro_bandwidth_hz) = DLPF_BANDWIDTH_HZ[cfg];
+        let (accel_delay_ms, gyro_delay_ms) = DLPF_DELAY_MS[cfg];
+        Self {
+            accel_mg_per_lsb: accel.resolution_mg_per_lsb(),
+            gyro_dps_per_lsb: gyro.resolution_dps_per_lsb(),
+            accel_bandwidth_hz,
+            gyro_bandwidth_hz,
+            accel_delay_ms,
+            gyro_delay_ms,
+            accel_noise_mg_rms: ACCEL_NOISE_DENSITY_UG / 1000. * accel_bandwidth_hz.sqrt(),
+            gyro_noise_dps_rms: GYRO_NOISE_DENSITY_DPS * gyro_bandwidth_hz.sqrt(),
+        }
+    }
+}
+
+/// Smallest ranges whose full scale covers the expected maximum times `headroom`
+/// (e.g. 1.5 for 50% margin). Falls back to the largest range if none does
+pub fn suggest_ranges(
+    max_expected_acc_g: f32,
+    max_expected_rate_dps: f32,
+    headroom: f32,
+) -> (AccelRange, GyroRange) {
+    let acc = max_expected_acc_g.abs() * headroom;
+    let rate = max_expected_rate_dps.abs() * headroom;
+    (
+        AccelRange::ALL
+            .into_iter()
+            .find(|r| r.full_scale_g() >= acc)
+            .unwrap_or(AccelRange::G16),
+        GyroRange::ALL
+            .into_iter()
+            .find(|r| r.full_scale_dps() >= rate)
+            .unwrap_or(GyroRange::D2000),
+    )
+}
@@ -0,0 +1,336 @@
+//! Combined accel, gyro and temperature sample.
+//!
+//! [`MpuSample`] is `#[non_exhaustive]`: new fields can be added without breaking users.
+//! Outside the crate it is constructed with [`MpuSample::new`] and the `with_*` methods only,
+//! fields are read through accessors. A struct literal does not compile:
+//! ```compile_fail
+//! use mpu6050::{MpuSample, Vec3A};
+//!
+//! let sample = MpuSample {
+//!     acc: Vec3A::Z,
+//!     gyro: Vec3A::ZERO,
+//!     temp: 25.,
+//!     ..MpuSample::default()
+//! };
+//! ```
+//!
+//! The type is `Copy`. It is a few Vec3A's wide and passed around per sample, so moves are as
+//! cheap as references; fields added later must keep it `Copy`.
+//!
+//! With the `minimal-pipeline` feature the sample is the three values alone: flags,
+//! provenance, time reference, score and lever-arm correction are not stored, their `with_*` methods return the
+//! sample unchanged and their accessors the defaults of [`MpuSample::new`], see
+//! [`stages`](crate::stages).
+
+use glam::Vec3A;
+
+use crate::device::AccelRange;
+use crate::impact::RangeTransition;
+use crate::plausibility::PlausibilityScore;
+use crate::provenance::SampleProvenance;
+use crate::skew::SkewReference;
+
+/// One sample of all sensors, in g, rad/s and degrees celsius
+///
+/// `PartialEq` compares the floats exactly (NaN != NaN, 0.0 == -0.0). It is meant for replay
+/// and encoder round trips, compare with a tolerance for anything computed
+#[non_exhaustive]
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct MpuSample {
+    pub(crate) acc: Vec3A,
+    pub(crate) gyro: Vec3A,
+    pub(crate) temp: f32,
+    #[cfg(not(feature = "minimal-pipeline"))]
+    pub(crate) settling: bool,
+    #[cfg(not(feature = "minimal-pipeline"))]
+    pub(crate) out_of_band: bool,
+    #[cfg(not(feature = "minimal-pipeline"))]
+    pub(crate) accel_range: Option<AccelRange>,
+    #[cfg(not(feature = "minimal-pipeline"))]
+    pub(crate) range_transition: Option<RangeTransition>,
+    #[cfg(not(feature = "minimal-pipeline"))]
+    pub(crate) provenance: SampleProvenance,
+    #[cfg(not(feature = "minimal-pipeline"))]
+    pub(crate) time_reference: Option<SkewReference>,
+    #[cfg(not(feature = "minimal-pipeline"))]
+    pub(crate) plausibility: PlausibilityScore,
+    #[cfg(not(feature = "minimal-pipeline"))]
+    pub(crate) lever_arm: Option<Vec3A>,
+}
+
+impl MpuSample {
+    /// sample from accel in g, gyro in rad/s and temperature in degrees celsius
+    pub const fn new(acc: Vec3A, gyro: Vec3A, temp: f32) -> Self {
+        Self {
+            acc,
+            gyro,
+            temp,
+            #[cfg(not(feature = "minimal-pipeline"))]
+            settling: false,
+            #[cfg(not(feature = "minimal-pipeline"))]
+            out_of_band: false,
+            #[cfg(not(feature = "minimal-pipeline"))]
+            accel_range: None,
+            #[cfg(not(feature = "minimal-pipeline"))]
+            range_transition: None,
+            #[cfg(not(feature = "minimal-pipeline"))]
+            provenance: SampleProvenance::MEASURED,
+            #[cfg(not(feature = "minimal-pipeline"))]
+            time_reference: None,
+            #[cfg(not(feature = "minimal-pipeline"))]
+            plausibility: PlausibilityScore::OK,
+            #[cfg(not(feature = "minimal-pipeline"))]
+            lever_arm: None,
+        }
+    }
+
+    /// sample from `[x, y, z]` arrays, see
+    /// [Math types and glam versions](crate#math-types-and-glam-versions)
+    pub fn from_arrays(acc: [f32; 3], gyro: [f32; 3], temp: f32) -> Self {
+        Self::new(Vec3A::from(acc), Vec3A::from(gyro), temp)
+    }
+
+    /// same sample with a different accel reading
+    pub const fn with_acc(self, acc: Vec3A) -> Self {
+        Self { acc, ..self }
+    }
+
+    /// same sample with a different gyro reading
+    pub const fn with_gyro(self, gyro: Vec3A) -> Self {
+        Self { gyro, ..self }
+    }
+
+    /// same sample with a different temperature
+    pub const fn with_temp(self, temp: f32) -> Self {
+        Self { temp, ..self }
+    }
+
+    /// accelerometer reading in g
+    pub fn acc(&self) -> Vec3A {
+        self.acc
+    }
+
+    /// gyro reading in rad/s
+    pub fn gyro(&self) -> Vec3A {
+        self.gyro
+    }
+
+    /// accelerometer reading in g, converted into any vector type constructible from
+    /// `[x, y, z]`
+    pub fn acc_as<V: From<[f32; 3]>>(&self) -> V {
+        V::from(self.acc.to_array())
+    }
+
+    /// gyro reading in rad/s, converted into any vector type constructible from `[x, y, z]`
+    pub fn gyro_as<V: From<[f32; 3]>>(&self) -> V {
+        V::from(self.gyro.to_array())
+    }
+
+    /// temperature in degrees celsius
+    pub fn temp(&self) -> f32 {
+        self.temp
+    }
+}
+
+#[cfg(not(feature = "minimal-pipeline"))]
+impl MpuSample {
+    /// same sample with a different settling flag
+    pub const fn with_settling(self, settling: bool) -> Self {
+        Self { settling, ..self }
+    }
+
+    /// same sample with a different out of band flag
+    pub const fn with_out_of_band(self, out_of_band: bool) -> Self {
+        Self {
+            out_of_band,
+            ..self
+        }
+    }
+
+    /// same sample tagged with the accel range it was captured at
+    pub const fn with_accel_range(self, accel_range: Option<AccelRange>) -> Self {
+        Self {
+            accel_range,
+            ..self
+        }
+    }
+
+    /// same sample with a different range transition
+    pub const fn with_range_transition(self, range_transition: Option<RangeTransition>) -> Self {
+        Self {
+            range_transition,
+            ..self
+        }
+    }
+
+    /// same sample with different provenance tags
+    pub const fn with_provenance(self, provenance: SampleProvenance) -> Self {
+        Self { provenance, ..self }
+    }
+
+    /// same sample referring to the instant of a different sensor
+    pub const fn with_time_reference(self, time_reference: Option<SkewReference>) -> Self {
+        Self {
+            time_reference,
+            ..self
+        }
+    }
+
+    /// same sample with a different plausibility score
+    pub const fn with_plausibility(self, plausibility: PlausibilityScore) -> Self {
+        Self {
+            plausibility,
+            ..self
+        }
+    }
+
+    /// taken while the signal path was settling, see [`settling`](crate::settling)
+    pub fn settling(&self) -> bool {
+        self.settling
+    }
+
+    /// direct read taken while FIFO streaming, not part of the FIFO timeline, see
+    /// [`fifo`](crate::fifo)
+    pub fn out_of_band(&self) -> bool {
+        self.out_of_band
+    }
+
+    /// accel range the reading was captured at, tagged while range interleaving or impact
+    /// ranging only, see [`interleave`](crate::interleave) and [`impact`](crate::impact)
+    pub fn accel_range(&self) -> Option<AccelRange> {
+        self.accel_range
+    }
+
+    /// range switch the sample is part of while impact ranging, see
+    /// [`impact`](crate::impact)
+    pub fn range_transition(&self) -> Option<RangeTransition> {
+        self.range_transition
+    }
+
+    /// whether each field group was measured or made up by the driver, see
+    /// [`provenance`](crate::provenance)
+    pub fn provenance(&self) -> SampleProvenance {
+        self.provenance
+    }
+
+    /// Sensor whose sampling instant all values refer to after the skew correction, None
+    /// for each sensor at its own instant, see [`skew`](crate::skew). A timestamp of the
+    /// sample is the time of its read either way
+    pub fn time_reference(&self) -> Option<SkewReference> {
+        self.time_reference
+    }
+
+    /// checks failed by the sample, [`PlausibilityScore::OK`] unless checks are configured,
+    /// see [`plausibility`](crate::plausibility)
+    pub fn plausibility(&self) -> PlausibilityScore {
+        self.plausibility
+    }
+
+    /// same sample with a different lever-arm correction, [`acc`](Self::acc) unchanged
+    pub const fn with_lever_arm_correction(self, lever_arm: Option<Vec3A>) -> Self {
+        Self { lever_arm, ..self }
+    }
+
+    /// acceleration in g taken off the accelerometer reading by the lever-arm compensation,
+    /// None if not compensated, see [`lever_arm`](crate::lever_arm)
+    pub fn lever_arm_correction(&self) -> Option<Vec3A> {
+        self.lever_arm
+    }
+
+    /// accelerometer reading in g before the lever-arm compensation, to the rounding of
+    /// adding the correction back, [`acc`](Self::acc) if not compensated
+    pub fn uncompensated_acc(&self) -> Vec3A {
+        match self.lever_arm {
+            Some(term) => self.acc + term,
+            None => self.acc,
+        }
+    }
+}
+
+#[cfg(feature = "minimal-pipeline")]
+impl MpuSample {
+    /// the same sample, settling flags are not stored with `minimal-pipeline`
+    pub const fn with_settling(self, _settling: bool) -> Self {
+        self
+    }
+
+    /// the same sample, out of band flags are not stored with `minimal-pipeline`
+    pub const fn with_out_of_band(self, _out_of_band: bool) -> Self {
+        self
+    }
+
+    /// the same sample, accel ranges are not stored with `minimal-pipeline`
+    pub const fn with_accel_range(self, _accel_range: Option<AccelRange>) -> Self {
+        self
+    }
+
+    /// the same sample, range transitions are not stored with `minimal-pipeline`
+    pub const fn with_range_transition(self, _range_transition: Option<RangeTransition>) -> Self {
+        self
+    }
+
+    /// the same sample, provenance tags are not stored with `minimal-pipeline`
+    pub const fn with_provenance(self, _provenance: SampleProvenance) -> Self {
+        self
+    }
+
+    /// the same sample, time references are not stored with `minimal-pipeline`
+    pub const fn with_time_reference(self, _time_reference: Option<SkewReference>) -> Self {
+        self
+    }
+
+    /// the same sample, plausibility scores are not stored with `minimal-pipeline`
+    pub const fn with_plausibility(self, _plausibility: PlausibilityScore) -> Self {
+        self
+    }
+
+    /// false, the settling stage is compiled out, see [`stages`](crate::stages)
+    pub fn settling(&self) -> bool {
+        false
+    }
+
+    /// false, the out of band check is compiled out, see [`stages`](crate::stages)
+    pub fn out_of_band(&self) -> bool {
+        false
+    }
+
+    /// None, range interleaving is compiled out, see [`stages`](crate::stages)
+    pub fn accel_range(&self) -> Option<AccelRange> {
+        None
+    }
+
+    /// None, impact ranging is compiled out, see [`stages`](crate::stages)
+    pub fn range_transition(&self) -> Option<RangeTransition> {
+        None
+    }
+
+    /// [`SampleProvenance::MEASURED`], tags are not stored, see [`stages`](crate::stages)
+    pub fn provenance(&self) -> SampleProvenance {
+        SampleProvenance::MEASURED
+    }
+
+    /// None, the skew correction is compiled out, see [`stages`](crate::stages)
+    pub fn time_reference(&self) -> Option<SkewReference> {
+        None
+    }
+
+    /// [`PlausibilityScore::OK`], plausibility is compiled out, see [`stages`](crate::stages)
+    pub fn plausibility(&self) -> PlausibilityScore {
+        PlausibilityScore::OK
+    }
+
+    /// the same sample, lever-arm corrections are not stored with `minimal-pipeline`
+    pub const fn with_lever_arm_correction(self, _lever_arm: Option<Vec3A>) -> Self {
+        self
+    }
+
+    /// None, the lever-arm compensation is compiled out, see [`stages`](crate::stages)
+    pub fn lever_arm_correction(&self) -> Option<Vec3A> {
+        None
+    }
+
+    /// [`acc`](Self::acc), the lever-arm compensation is compiled out
+    pub fn uncompensated_acc(&self) -> Vec3A {
+        self.acc
+    }
+}
@@ -0,0 +1,166 @@
+//! Interrupt driven sampling loop with overrun detection.
+//!
+//! The canonical pattern: data ready on the INT pin, wait for the pin, read the sample, hand
+//! it to the application, repeat. With a latched INT pin, a sample arriving while the
+//! application still processes the previous one leaves the pin asserted and DATA_RDY_INT set
+//! in INT_STATUS without a new edge. [`OverrunDetector`] checks exactly that: INT_STATUS read
+//! after the callback returned still has DATA_RDY set means at least one sample was missed.
+//!
+//! [`Mpu6050::run_sampling_loop`] is the blocking variant, waiting for the pin is left to a
+//! caller supplied closure (poll an input pin, wait for an EXTI flag, sleep until an interrupt).
+//! [`Mpu6050::samples`] is the pull variant without interrupt: an endless iterator of
+//! [`get_all`](Mpu6050::get_all) samples, paced by the caller.
+//!
+//! `Mpu6050Async::run_sampling_task` of the `async_driver` module, feature `async`, is the async variant awaiting the pin through `embedded-hal-async`'s
+//! `Wait`, on the same detector.
+
+#[cfg(feature = "driver")]
+use embedded_hal::blocking::i2c::{Write, WriteRead};
+
+use crate::device::INT_STATUS;
+use crate::frame::FRAME_LEN;
+use crate::metrics::{self, MetricEvent};
+use crate::register::{Register, RegisterValue};
+use crate::{Mpu6050, Mpu6050Error, MpuSample};
+
+/// Metadata passed with every sample
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub struct SampleMeta {
+    /// number of samples delivered before this one
+    pub sequence: u64,
+    /// a sample arrived while the previous callback was running and was lost
+    pub overrun: bool,
+    /// total overruns detected so far
+    pub overruns: u32,
+}
+
+/// Returned by the sample callback
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum SampleControl {
+    /// keep sampling
+    Continue,
+    /// leave the sampling loop
+    Stop,
+}
+
+/// Overrun detection state, no bus access
+#[derive(Copy, Clone, Debug, Default)]
+pub struct OverrunDetector {
+    sequence: u64,
+    overruns: u32,
+    pending_overrun: bool,
+}
+
+impl OverrunDetector {
+    /// new detector
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// INT_STATUS read after the callback returned
+    pub fn after_callback(&mut self, int_status: u8) {
+        if RegisterValue::<INT_STATUS>::from_byte(int_status).flag(INT_STATUS::DATA_RDY_INT_FIELD) {
+            self.pending_overrun = true;
+            self.overruns = self.overruns.saturating_add(1);
+        }
+    }
+
+    /// metadata for the next delivered sample
+    pub fn next_meta(&mut self) -> SampleMeta {
+        let meta = SampleMeta {
+            sequence: self.sequence,
+            overrun: self.pending_overrun,
+            overruns: self.overruns,
+        };
+        self.sequence = self.sequence.wrapping_add(1);
+        self.pending_overrun = false;
+        meta
+    }
+}
+
+/// Endless iterator of [`get_all`](Mpu6050::get_all) samples, see [`Mpu6050::samples`]
+#[cfg(feature = "driver")]
+pub struct Samples<'a, I, D> {
+    mpu: &'a mut Mpu6050<I, D>,
+}
+
+#[cfg(feature = "driver")]
+impl<I, D, E> Iterator for Samples<'_, I, D>
+where
+    I: Write<Error = E> + WriteRead<Error = E>,
+{
+    type Item = Result<MpuSample, Mpu6050Error<E>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        Some(self.mpu.get_all())
+    }
+}
+
+/// Error of the sampling loop
+#[derive(Debug)]
+pub enum SamplingError<E, P> {
+    /// driver error
+    Device(Mpu6050Error<E>),
+    /// waiting for the INT pin failed
+    Wait(P),
+}
+
+#[cfg(feature = "driver")]
+impl<I, D, E> Mpu6050<I, D>
+where
+    I: Write<Error = E> + WriteRead<Error = E>,
+{
+    /// Waits for the INT pin with `wait_int`, reads a sample and passes it to `on_sample`, until
+    /// the callback returns [`SampleControl::Stop`]. Expects data ready interrupts enabled on
+    /// an active high, latched INT pin cleared by reading INT_STATUS.
+    ///
+    /// Transactions per sample: INT_STATUS read (clears the pin), sample read, and after the
+    /// callback one more INT_STATUS read for overrun detection, which doubles as the clearing
+    /// read of the following sample when it found DATA_RDY set
+    pub fn run_sampling_loop<W, P, F>(
+        &mut self,
+        mut wait_int: W,
+        mut on_sample: F,
+    ) -> Result<(), SamplingError<E, P>>
+    where
+        W: FnMut() -> Result<(), P>,
+        F: FnMut(MpuSample, SampleMeta) -> SampleControl,
+    {
+        let mut detector = OverrunDetector::new();
+        let mut ready = false;
+        loop {
+            if !ready {
+                wait_int().map_err(SamplingError::Wait)?;
+                self.read_register(Register::INT_STATUS)
+                    .map_err(SamplingError::Device)?;
+            }
+            let sample = self.read_sample().map_err(SamplingError::Device)?;
+            if on_sample(sample, detector.next_meta()) == SampleControl::Stop {
+                return Ok(());
+            }
+            let status = self
+                .read_fields::<INT_STATUS>()
+                .map_err(SamplingError::Device)?;
+            detector.after_callback(status.byte());
+            ready = status.flag(INT_STATUS::DATA_RDY_INT_FIELD);
+            if ready {
+                self.forget_skew_history();
+                self.forget_lever_arm_history();
+                self.emit_event(metrics::SAMPLING_OVERRUNS, MetricEvent::SamplingOverrun);
+            }
+        }
+    }
+
+    /// Iterator reading one sample per item with [`get_all`](Self::get_all), never ending:
+    /// errors are items, the caller decides when to stop and waits between items for data
+    /// ready or a timer
+    pub fn samples(&mut self) -> Samples<'_, I, D> {
+        Samples { mpu: self }
+    }
+
+    /// [`read_sample`](Self::read_sample) in one burst of ACCEL_XOUT_H..GYRO_ZOUT_L. A
+    /// settling sample is flagged, not read again
+    pub(crate) fn read_sample_burst(&mut self) -> Result<MpuSample, Mpu6050Error<E>> {
+        self.read_sample_burst_into(&mut [0; FRAME_LEN])
+    }
+}
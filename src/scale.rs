@@ -0,0 +1,255 @@
+//! Scale model of the accelerometer and gyro.
+//!
+//! Scaled readings are computed in this order, for both sensors:
+//! 1. counts / `nominal` sensitivity of the active range (LSB per g, LSB per °/s)
+//! 2. for the gyro, conversion from °/s to rad/s
+//! 3. − offset (`acc_offset`, `gyro_offset`), the bias in g and rad/s
+//! 4. * `per_axis` scale factors
+//!
+//! With all factors at 1.0 the result is bit-identical to scaling with the nominal sensitivity
+//! alone. Range changes update `nominal` and keep `per_axis`, so per-axis factors determined
+//! by a calibration stay valid.
+//!
+//! #### Stages
+//! A [`Pipeline`] runs the same steps with [`PipelineStages`] switching their conventions:
+//! where a mounting [`AxisMap`] applies, the gyro unit, the temperature constants and a fixed
+//! point quantization of the outputs. [`PipelineStages::NATIVE`], the default and what the
+//! driver does, is the order above with no mounting map: bit-identical to the functions of
+//! this module. Other conventions, like those of InvenSense's eMPL, are combinations of the
+//! switches, see the `conformance` module.
+
+use glam::Vec3A;
+
+use crate::axis_map::AxisMap;
+use crate::conversion;
+use crate::device::{AccelRange, GyroRange, TEMP_OFFSET, TEMP_SENSITIVITY};
+use crate::frame::RawFrame;
+#[cfg(not(feature = "std"))]
+use crate::math::FloatExt;
+use crate::MpuSample;
+
+/// Nominal sensitivity of the active range and per-axis correction factors
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct ScaleModel {
+    /// sensitivity of the active range in LSB per unit
+    pub nominal: f32,
+    /// per-axis scale factors, multiplied onto the offset corrected reading
+    pub per_axis: Vec3A,
+}
+
+impl ScaleModel {
+    /// model with the given nominal sensitivity and unit per-axis factors
+    pub const fn new(nominal: f32) -> Self {
+        Self {
+            nominal,
+            per_axis: Vec3A::ONE,
+        }
+    }
+
+    /// same model with per-axis factors
+    pub const fn with_factors(self, per_axis: Vec3A) -> Self {
+        Self { per_axis, ..self }
+    }
+
+    /// steps 3 and 4: offset, then per-axis factors
+    pub fn correct(&self, uncorrected: Vec3A, offset: Vec3A) -> Vec3A {
+        (uncorrected - offset) * self.per_axis
+    }
+}
+
+/// step 1: accelerometer counts to g, see [`conversion`]
+pub fn acc_uncorrected(raw: [i16; 3], scale: &ScaleModel) -> Vec3A {
+    conversion::counts_to_units(raw, scale.nominal)
+}
+
+/// steps 1 and 2: gyro counts to rad/s, see [`conversion`]
+pub fn gyro_uncorrected(raw: [i16; 3], scale: &ScaleModel) -> Vec3A {
+    conversion::counts_to_rad_s(raw, scale.nominal)
+}
+
+/// temperature counts to °C, register map rev 4.2
+pub fn temp(raw: i16) -> f32 {
+    conversion::counts_to_celsius(raw)
+}
+
+/// Where a [`Pipeline`] maps the chip axes onto the mounting frame
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum OrientationStage {
+    /// on the corrected reading, offsets and per-axis factors are in chip axes
+    AfterCorrection,
+    /// on the scaled reading before the offsets, offsets and per-axis factors are in
+    /// mounting axes
+    BeforeOffsets,
+}
+
+/// Unit of the gyro readings of a [`Pipeline`], and of its gyro offset
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum GyroUnit {
+    /// rad/s, steps 1 and 2
+    RadPerSecond,
+    /// °/s, step 1 alone
+    DegreesPerSecond,
+}
+
+/// TEMP_OUT to °C as `(raw - raw_offset) / sensitivity + offset_c`
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct TempModel {
+    /// counts subtracted first
+    pub raw_offset: f32,
+    /// LSB per °C
+    pub sensitivity: f32,
+    /// °C added last
+    pub offset_c: f32,
+}
+
+impl TempModel {
+    /// register map rev 4.2, `raw / 340 + 36.53`
+    pub const NATIVE: TempModel = TempModel {
+        raw_offset: 0.,
+        sensitivity: TEMP_SENSITIVITY,
+        offset_c: TEMP_OFFSET,
+    };
+
+    /// temperature in °C
+    pub fn celsius(&self, raw: i16) -> f32 {
+        (raw as f32 - self.raw_offset) / self.sensitivity + self.offset_c
+    }
+}
+
+/// Conventions of the steps of a [`Pipeline`], see [Stages](self#stages)
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct PipelineStages {
+    /// mounting of the chip axes
+    pub orientation: AxisMap,
+    /// where `orientation` applies
+    pub orientation_stage: OrientationStage,
+    /// gyro output unit
+    pub gyro_unit: GyroUnit,
+    /// temperature constants
+    pub temp: TempModel,
+    /// outputs truncated toward zero to multiples of 2^-bits, None keeps the f32 results
+    pub fixed_point_bits: Option<u8>,
+}
+
+impl Default for PipelineStages {
+    fn default() -> Self {
+        Self::NATIVE
+    }
+}
+
+impl PipelineStages {
+    /// this crate's conventions, the order of the module docs
+    pub const NATIVE: PipelineStages = PipelineStages {
+        orientation: AxisMap::IDENTITY,
+        orientation_stage: OrientationStage::AfterCorrection,
+        gyro_unit: GyroUnit::RadPerSecond,
+        temp: TempModel::NATIVE,
+        fixed_point_bits: None,
+    };
+
+    /// same stages with the chip mounted as `orientation`
+    pub const fn with_orientation(self, orientation: AxisMap) -> Self {
+        Self {
+            orientation,
+            ..self
+        }
+    }
+
+    /// steps 3 and 4 with the mounting map at its stage, quantized
+    fn correct(&self, scale: &ScaleModel, uncorrected: Vec3A, offset: Vec3A) -> Vec3A {
+        let corrected = match self.orientation_stage {
+            OrientationStage::AfterCorrection => {
+                self.orientation.apply(scale.correct(uncorrected, offset))
+            }
+            OrientationStage::BeforeOffsets => {
+                scale.correct(self.orientation.apply(uncorrected), offset)
+            }
+        };
+        Vec3A::new(
+            self.quantize(corrected.x),
+            self.quantize(corrected.y),
+            self.quantize(corrected.z),
+        )
+    }
+
+    /// `value` truncated to the fixed point, if any
+    fn quantize(&self, value: f32) -> f32 {
+        match self.fixed_point_bits {
+            None => value,
+            Some(bits) => {
+                let one = (1u32 << bits) as f32;
+                (value * one).trunc() / one
+            }
+        }
+    }
+}
+
+/// The driver's scaling without the driver, for recorded frames
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Pipeline {
+    /// accelerometer scale model
+    pub acc_scale: ScaleModel,
+    /// gyro scale model
+    pub gyro_scale: ScaleModel,
+    /// accelerometer offset in g
+    pub acc_offset: Vec3A,
+    /// gyro offset in the [`GyroUnit`] of the stages, rad/s natively
+    pub gyro_offset: Vec3A,
+    /// conventions of the steps, [`PipelineStages::NATIVE`] in the driver
+    pub stages: PipelineStages,
+}
+
+impl Pipeline {
+    /// nominal scaling for the given ranges, no offsets, unit per-axis factors
+    pub fn new(accel_range: AccelRange, gyro_range: GyroRange) -> Self {
+        Self {
+            acc_scale: ScaleModel::new(accel_range.sensitivity()),
+            gyro_scale: ScaleModel::new(gyro_range.sensitivity()),
+            acc_offset: Vec3A::ZERO,
+            gyro_offset: Vec3A::ZERO,
+            stages: PipelineStages::NATIVE,
+        }
+    }
+
+    /// same pipeline with `stages`
+    pub fn with_stages(self, stages: PipelineStages) -> Self {
+        Self { stages, ..self }
+    }
+
+    /// same pipeline with the chip mounted as `orientation`
+    pub fn with_orientation(self, orientation: AxisMap) -> Self {
+        let stages = self.stages.with_orientation(orientation);
+        self.with_stages(stages)
+    }
+
+    /// accelerometer reading in g
+    pub fn acc(&self, raw: [i16; 3]) -> Vec3A {
+        let uncorrected = acc_uncorrected(raw, &self.acc_scale);
+        self.stages
+            .correct(&self.acc_scale, uncorrected, self.acc_offset)
+    }
+
+    /// gyro reading in the [`GyroUnit`] of the stages, rad/s natively
+    pub fn gyro(&self, raw: [i16; 3]) -> Vec3A {
+        let uncorrected = match self.stages.gyro_unit {
+            GyroUnit::RadPerSecond => gyro_uncorrected(raw, &self.gyro_scale),
+            GyroUnit::DegreesPerSecond => conversion::counts_to_units(raw, self.gyro_scale.nominal),
+        };
+        self.stages
+            .correct(&self.gyro_scale, uncorrected, self.gyro_offset)
+    }
+
+    /// temperature in °C
+    pub fn temp(&self, raw: i16) -> f32 {
+        self.stages.quantize(self.stages.temp.celsius(raw))
+    }
+
+    /// scaled sample of a frame
+    pub fn sample(&self, frame: &RawFrame) -> MpuSample {
+        MpuSample::new(
+            self.acc(frame.acc),
+            self.gyro(frame.gyro),
+            self.temp(frame.temp),
+        )
+    }
+}
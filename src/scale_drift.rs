@@ -0,0 +1,521 @@
+//! Accelerometer scale drift from quasi-static gravity at varied orientations.
+//!
+//! At rest the accelerometer reads gravity, 1g in every orientation. The
+//! [`gravity_trim`](crate::gravity_trim) trimmer turns a deviation of the magnitude into an
+//! offset, but a scale error deviates it too: along an axis, both read as a magnitude off
+//! by the same amount. Over orientations they separate, an offset changes sign with the
+//! axis, a scale error does not. [`ScaleDriftMonitor`] collects gravity at rest and estimates
+//! both, reporting the per-axis scale correction with its confidence. Nothing is applied
+//! until [`Mpu6050::apply_scale_correction`].
+//!
+//! #### Model
+//! A reading `a` with scale correction `s` and residual offset `b` per axis is gravity when
+//! `Σ s_i² (a_i - b_i)² = 1`, the axis aligned ellipsoid of the readings. With
+//! `p_i = (s_i² - 1) / 2` and the terms of second order in `b` dropped, the ellipsoid is
+//! linear in `p` and `b`:
+//!
+//! `p · a² - s² b · a = (1 - |a|²) / 2`
+//!
+//! and `s² b` is `b` to first order. Each reading gives a row `h = [a², -a]` with residual
+//! `r = (1 - |a|²) / 2` of a six parameter least squares problem, and the scale correction
+//! is `s = √(1 + 2p)`, exact in the scale. The offset is estimated alongside only to keep it
+//! out of the scale, [`offset_g`](ScaleDriftEstimate::offset_g) is for information.
+//!
+//! #### Orientations
+//! Accepted readings, at rest for [`min_still_s`](ScaleDriftConfig::min_still_s) with the
+//! gyro and the magnitude within their thresholds, go to the accumulator of the octant of
+//! their direction, the signs of the three axes. An accumulator holds the normal equations
+//! of its rows, residuals clipped to [`residual_clip_g`](ScaleDriftConfig::residual_clip_g),
+//! and forgets its oldest data once it holds
+//! [`octant_window_s`](ScaleDriftConfig::octant_window_s), so memory is bounded and a long
+//! rest in one place weighs no more than a short one elsewhere. An octant with
+//! [`min_octant_s`](ScaleDriftConfig::min_octant_s) is populated. The estimate weighs the
+//! populated octants alike, each averaged over its time.
+//!
+//! #### Confidence
+//! [`estimate`](ScaleDriftMonitor::estimate) refuses with
+//! [`LowConfidence`](ScaleDriftError::LowConfidence) below
+//! [`min_octants`](ScaleDriftConfig::min_octants) populated octants, or when the standard
+//! deviation of a scale correction exceeds [`max_std_dev`](ScaleDriftConfig::max_std_dev).
+//! The standard deviation counts each octant as one measurement with the RMS residual of
+//! the readings, at least [`noise_floor_g`](ScaleDriftConfig::noise_floor_g): conservative,
+//! errors shared by the readings of a rest do not average out. Populated octants are not
+//! enough by themselves: at the centers of the octants every axis sees a third of gravity
+//! squared and the axes do not separate. The spread of the orientations within and across
+//! the octants does it, the standard deviation says whether it sufficed. Readings off the
+//! ellipsoid, motion slipping through the stillness test, are refused as
+//! [`Inconsistent`](ScaleDriftError::Inconsistent).
+//!
+//! #### Applying
+//! [`Mpu6050::apply_scale_correction`] multiplies an accepted correction onto the per-axis
+//! accel [scale factors](crate::scale::ScaleModel::per_axis), bounded to
+//! [`max_step`](ScaleDriftConfig::max_step) per axis, and transforms the statistics of the
+//! monitor onto the new factors: it keeps accumulating without a reset. With a
+//! [`CalibrationPolicy::max_scale_drift`](crate::validity::CalibrationPolicy::max_scale_drift),
+//! [`Mpu6050::check_scale_drift`] and the correction left after applying degrade the
+//! [`ScaleFactors`](crate::validity::CalibrationKind::ScaleFactors) calibration beyond the
+//! limit, see [`validity`](crate::validity).
+//! ```
+//! use mpu6050::scale_drift::{ScaleDriftConfig, ScaleDriftError, ScaleDriftMonitor};
+//! use mpu6050::Vec3A;
+//!
+//! // the x axis reads 2% high
+//! let scale = Vec3A::new(1.02, 1., 1.);
+//! let mut monitor = ScaleDriftMonitor::new(ScaleDriftConfig::default());
+//! let mut rest = |monitor: &mut ScaleDriftMonitor, gravity: Vec3A| {
+//!     for _ in 0..1000 {
+//!         monitor.feed_readings(gravity.normalize() * scale, Vec3A::ZERO, 0.01);
+//!     }
+//!     // picked up and turned
+//!     monitor.feed_readings(Vec3A::Z, Vec3A::ONE, 0.01);
+//! };
+//! rest(&mut monitor, Vec3A::Z);
+//! assert!(matches!(monitor.estimate(), Err(ScaleDriftError::LowConfidence(_))));
+//!
+//! for x in [-1., 1.] {
+//!     for y in [-1., 1.] {
+//!         for z in [-1., 1.] {
+//!             rest(&mut monitor, Vec3A::new(0.3 * x, 0.5 * y, z));
+//!             rest(&mut monitor, Vec3A::new(x, 0.4 * y, 0.2 * z));
+//!             rest(&mut monitor, Vec3A::new(0.2 * x, y, 0.6 * z));
+//!         }
+//!     }
+//! }
+//! let estimate = monitor.estimate().unwrap();
+//! assert!((estimate.scale_correction.x - 1. / 1.02).abs() < 1e-3);
+//! ```
+
+use core::fmt;
+
+use glam::Vec3A;
+
+use crate::config_epoch::ConfigChange;
+#[cfg(not(feature = "std"))]
+use crate::math::FloatExt;
+use crate::{Mpu6050, MpuSample};
+
+/// Number of orientation octants
+pub const OCTANTS: usize = 8;
+
+/// Parameters of the fit: `p` then the offset
+const PARAMS: usize = 6;
+
+/// Relative weight of the regularization keeping the inverse of a singular fit finite
+const REGULARIZATION: f64 = 1e-9;
+
+/// Options of a [`ScaleDriftMonitor`]
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct ScaleDriftConfig {
+    /// gyro magnitudes above this (rad/s) are motion, default 0.03
+    pub still_gyro_rad_s: f32,
+    /// accel magnitudes further than this from 1g are motion, default 0.1, beyond the
+    /// scale errors to be seen
+    pub still_acc_g: f32,
+    /// continuous stillness before readings count, in s, default 1
+    pub min_still_s: f32,
+    /// residual clip in g, default 0.05
+    pub residual_clip_g: f32,
+    /// time an octant holds before forgetting its oldest data, in s, default 60
+    pub octant_window_s: f32,
+    /// time an octant needs to be populated, in s, default 5
+    pub min_octant_s: f32,
+    /// populated octants needed for an estimate, default 6
+    pub min_octants: usize,
+    /// largest standard deviation of an accepted scale correction, default 0.002
+    pub max_std_dev: f32,
+    /// smallest RMS residual assumed for the standard deviation, in g, default 0.001
+    pub noise_floor_g: f32,
+    /// largest RMS residual of an accepted estimate, in g, default 0.01
+    pub max_residual_g: f32,
+    /// largest change of a scale factor per [`Mpu6050::apply_scale_correction`], relative,
+    /// default 0.005
+    pub max_step: f32,
+}
+
+impl Default for ScaleDriftConfig {
+    fn default() -> Self {
+        Self {
+            still_gyro_rad_s: 0.03,
+            still_acc_g: 0.1,
+            min_still_s: 1.,
+            residual_clip_g: 0.05,
+            octant_window_s: 60.,
+            min_octant_s: 5.,
+            min_octants: 6,
+            max_std_dev: 0.002,
+            noise_floor_g: 0.001,
+            max_residual_g: 0.01,
+            max_step: 0.005,
+        }
+    }
+}
+
+/// What the monitor has seen so far
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct ScaleDriftProgress {
+    /// time held per octant in s, index bits 0 to 2 set for negative x, y and z
+    pub octant_s: [f32; OCTANTS],
+    /// octants with [`min_octant_s`](ScaleDriftConfig::min_octant_s)
+    pub populated: usize,
+    /// standard deviation of the scale correction per axis, infinite without enough octants
+    pub std_dev: Vec3A,
+}
+
+/// Scale drift estimated by [`ScaleDriftMonitor::estimate`]
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct ScaleDriftEstimate {
+    /// factors to multiply onto the per-axis accel scale factors
+    pub scale_correction: Vec3A,
+    /// standard deviation of the correction per axis
+    pub std_dev: Vec3A,
+    /// residual offset in g estimated alongside, not applied
+    pub offset_g: Vec3A,
+    /// RMS residual of the readings in g, the octants weighed alike
+    pub residual_rms_g: f32,
+    /// populated octants
+    pub populated: usize,
+}
+
+impl ScaleDriftEstimate {
+    /// largest relative deviation of a correction factor from 1
+    pub fn drift(&self) -> f32 {
+        (self.scale_correction - Vec3A::ONE).abs().max_element()
+    }
+}
+
+/// Why [`ScaleDriftMonitor::estimate`] returned no estimate
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum ScaleDriftError {
+    /// too few populated octants, or the orientations do not separate the axes
+    LowConfidence(ScaleDriftProgress),
+    /// the readings are off the ellipsoid beyond the residual bound, in g
+    Inconsistent {
+        /// RMS residual of the readings
+        residual_rms_g: f32,
+    },
+}
+
+impl fmt::Display for ScaleDriftError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ScaleDriftError::LowConfidence(progress) => write!(
+                f,
+                "low confidence, {} of {} octants populated, standard deviation {:.4}",
+                progress.populated,
+                OCTANTS,
+                progress.std_dev.max_element()
+            ),
+            ScaleDriftError::Inconsistent { residual_rms_g } => write!(
+                f,
+                "residual of {:.4} g RMS, the readings are not at rest",
+                residual_rms_g
+            ),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ScaleDriftError {}
+
+/// Normal equations of the rows of one octant
+#[derive(Copy, Clone, Debug, Default)]
+struct OctantAccumulator {
+    information: [[f32; PARAMS]; PARAMS],
+    rhs: [f32; PARAMS],
+    residual_sq: f32,
+    weight: f32,
+}
+
+impl OctantAccumulator {
+    fn add(&mut self, h: [f32; PARAMS], r: f32, weight: f32, window: f32) {
+        for (row, hi) in self.information.iter_mut().zip(h) {
+            for (value, hj) in row.iter_mut().zip(h) {
+                *value += hi * hj * weight;
+            }
+        }
+        for (value, hi) in self.rhs.iter_mut().zip(h) {
+            *value += hi * r * weight;
+        }
+        self.residual_sq += r * r * weight;
+        self.weight += weight;
+        if self.weight > window {
+            self.scale(window / self.weight);
+        }
+    }
+
+    fn scale(&mut self, factor: f32) {
+        self.information
+            .iter_mut()
+            .flatten()
+            .for_each(|v| *v *= factor);
+        self.rhs.iter_mut().for_each(|v| *v *= factor);
+        self.residual_sq *= factor;
+        self.weight *= factor;
+    }
+
+    /// the statistics of readings multiplied by `factors`: `h' = D h` with
+    /// `D = diag(f², f)` and `r' = r - δ · h` with `δ = [(f² - 1) / 2, 0]`
+    fn rescale(&mut self, factors: Vec3A) {
+        let f = factors.to_array();
+        let d = [f[0] * f[0], f[1] * f[1], f[2] * f[2], f[0], f[1], f[2]];
+        let delta = [
+            (d[0] - 1.) / 2.,
+            (d[1] - 1.) / 2.,
+            (d[2] - 1.) / 2.,
+            0.,
+            0.,
+            0.,
+        ];
+        let n_delta: [f32; PARAMS] =
+            core::array::from_fn(|i| (0..PARAMS).map(|j| self.information[i][j] * delta[j]).sum());
+        let delta_n_delta: f32 = (0..PARAMS).map(|i| delta[i] * n_delta[i]).sum();
+        let delta_rhs: f32 = (0..PARAMS).map(|i| delta[i] * self.rhs[i]).sum();
+        self.residual_sq += delta_n_delta - 2. * delta_rhs;
+        for i in 0..PARAMS {
+            self.rhs[i] = d[i] * (self.rhs[i] - n_delta[i]);
+            for j in 0..PARAMS {
+                self.information[i][j] *= d[i] * d[j];
+            }
+        }
+    }
+}
+
+/// Least squares of the populated octants
+struct Fit {
+    params: [f64; PARAMS],
+    covariance: [[f64; PARAMS]; PARAMS],
+    residual_rms_g: f32,
+    populated: usize,
+}
+
+/// Opportunistic accel scale drift estimator, see the [module docs](self)
+#[derive(Copy, Clone, Debug)]
+pub struct ScaleDriftMonitor {
+    config: ScaleDriftConfig,
+    octants: [OctantAccumulator; OCTANTS],
+    still_s: f32,
+}
+
+impl ScaleDriftMonitor {
+    /// new monitor without statistics
+    pub fn new(config: ScaleDriftConfig) -> Self {
+        Self {
+            config,
+            octants: [OctantAccumulator::default(); OCTANTS],
+            still_s: 0.,
+        }
+    }
+
+    /// options in use
+    pub fn config(&self) -> &ScaleDriftConfig {
+        &self.config
+    }
+
+    /// drops the statistics, keeping the options
+    pub fn reset(&mut self) {
+        *self = Self::new(self.config);
+    }
+
+    /// Feeds a sample taken `dt_s` after the previous one. True if it was accumulated
+    pub fn feed(&mut self, sample: &MpuSample, dt_s: f32) -> bool {
+        self.feed_readings(sample.acc(), sample.gyro(), dt_s)
+    }
+
+    /// [`feed`](Self::feed) with accel in g and gyro in rad/s
+    pub fn feed_readings(&mut self, acc: Vec3A, gyro: Vec3A, dt_s: f32) -> bool {
+        if dt_s.is_nan() || dt_s <= 0. {
+            return false;
+        }
+        let magnitude_sq = acc.length_squared();
+        let still = gyro.length() <= self.config.still_gyro_rad_s
+            && (magnitude_sq.sqrt() - 1.).abs() <= self.config.still_acc_g;
+        if !still {
+            self.still_s = 0.;
+            return false;
+        }
+        if self.still_s < self.config.min_still_s {
+            self.still_s += dt_s;
+            return false;
+        }
+        let clip = self.config.residual_clip_g;
+        let r = ((1. - magnitude_sq) / 2.).clamp(-clip, clip);
+        let h = [
+            acc.x * acc.x,
+            acc.y * acc.y,
+            acc.z * acc.z,
+            -acc.x,
+            -acc.y,
+            -acc.z,
+        ];
+        self.octants[octant(acc)].add(h, r, dt_s, self.config.octant_window_s);
+        true
+    }
+
+    /// time held per octant, populated octants and the standard deviations
+    pub fn progress(&self) -> ScaleDriftProgress {
+        let octant_s = self.octants.map(|octant| octant.weight);
+        let (populated, std_dev) = match self.fit() {
+            Some(fit) => (fit.populated, self.std_dev(&fit)),
+            None => (0, Vec3A::splat(f32::INFINITY)),
+        };
+        ScaleDriftProgress {
+            octant_s,
+            populated,
+            std_dev,
+        }
+    }
+
+    /// The scale correction of the readings so far, refused with too few or too similar
+    /// orientations or readings off the ellipsoid
+    pub fn estimate(&self) -> Result<ScaleDriftEstimate, ScaleDriftError> {
+        let progress = self.progress();
+        let fit = match self.fit() {
+            Some(fit) if fit.populated >= self.config.min_octants => fit,
+            _ => return Err(ScaleDriftError::LowConfidence(progress)),
+        };
+        if fit.residual_rms_g > self.config.max_residual_g {
+            return Err(ScaleDriftError::Inconsistent {
+                residual_rms_g: fit.residual_rms_g,
+            });
+        }
+        if progress.std_dev.max_element() > self.config.max_std_dev {
+            return Err(ScaleDriftError::LowConfidence(progress));
+        }
+        let p = fit.params;
+        let scale = |i: usize| (1. + 2. * p[i]).max(0.).sqrt() as f32;
+        Ok(ScaleDriftEstimate {
+            scale_correction: Vec3A::new(scale(0), scale(1), scale(2)),
+            std_dev: progress.std_dev,
+            offset_g: Vec3A::new(p[3] as f32, p[4] as f32, p[5] as f32),
+            residual_rms_g: fit.residual_rms_g,
+            populated: fit.populated,
+        })
+    }
+
+    /// Transforms the statistics onto readings multiplied by `factors`, called by
+    /// [`Mpu6050::apply_scale_correction`]. Call it when changing the scale factors by hand
+    pub fn rescale(&mut self, factors: Vec3A) {
+        self.octants
+            .iter_mut()
+            .for_each(|octant| octant.rescale(factors));
+    }
+
+    /// scale standard deviations, `∂s/∂p = 1/s` taken as 1
+    fn std_dev(&self, fit: &Fit) -> Vec3A {
+        let sigma = fit.residual_rms_g.max(self.config.noise_floor_g) as f64;
+        let std = |i: usize| (sigma * fit.covariance[i][i].max(0.).sqrt()) as f32;
+        Vec3A::new(std(0), std(1), std(2))
+    }
+
+    /// least squares over the populated octants, each of unit weight
+    fn fit(&self) -> Option<Fit> {
+        let mut information = [[0f64; PARAMS]; PARAMS];
+        let mut rhs = [0f64; PARAMS];
+        let mut residual_sq = 0.;
+        let mut populated = 0;
+        let populated_octants = self
+            .octants
+            .iter()
+            .filter(|octant| octant.weight >= self.config.min_octant_s);
+        for octant in populated_octants {
+            let weight = octant.weight as f64;
+            for (sum, value) in rhs.iter_mut().zip(octant.rhs) {
+                *sum += value as f64 / weight;
+            }
+            let rows = information.iter_mut().flatten();
+            for (sum, value) in rows.zip(octant.information.iter().flatten()) {
+                *sum += *value as f64 / weight;
+            }
+            residual_sq += octant.residual_sq as f64 / weight;
+            populated += 1;
+        }
+        if populated == 0 {
+            return None;
+        }
+        for (i, row) in information.iter_mut().enumerate() {
+            row[i] += REGULARIZATION * populated as f64;
+        }
+        let covariance = invert(information)?;
+        let params: [f64; PARAMS] =
+            core::array::from_fn(|i| (0..PARAMS).map(|j| covariance[i][j] * rhs[j]).sum());
+        let explained: f64 = (0..PARAMS).map(|i| params[i] * rhs[i]).sum();
+        let residual_rms_g = ((residual_sq - explained).max(0.) / populated as f64).sqrt();
+        Some(Fit {
+            params,
+            covariance,
+            residual_rms_g: residual_rms_g as f32,
+            populated,
+        })
+    }
+}
+
+/// octant of `v`, bits 0 to 2 set for negative x, y and z
+fn octant(v: Vec3A) -> usize {
+    (v.x < 0.) as usize | ((v.y < 0.) as usize) << 1 | ((v.z < 0.) as usize) << 2
+}
+
+/// inverse by Gauss-Jordan elimination with partial pivoting, None if singular
+fn invert(mut m: [[f64; PARAMS]; PARAMS]) -> Option<[[f64; PARAMS]; PARAMS]> {
+    let mut inverse = [[0f64; PARAMS]; PARAMS];
+    for (i, row) in inverse.iter_mut().enumerate() {
+        row[i] = 1.;
+    }
+    for col in 0..PARAMS {
+        let pivot = (col..PARAMS).max_by(|&a, &b| m[a][col].abs().total_cmp(&m[b][col].abs()))?;
+        if m[pivot][col] == 0. {
+            return None;
+        }
+        m.swap(col, pivot);
+        inverse.swap(col, pivot);
+        let scale = m[col][col];
+        for j in 0..PARAMS {
+            m[col][j] /= scale;
+            inverse[col][j] /= scale;
+        }
+        for row in 0..PARAMS {
+            if row == col {
+                continue;
+            }
+            let factor = m[row][col];
+            for j in 0..PARAMS {
+                m[row][j] -= factor * m[col][j];
+                inverse[row][j] -= factor * inverse[col][j];
+            }
+        }
+    }
+    Some(inverse)
+}
+
+impl<I, D> Mpu6050<I, D> {
+    /// Estimates the scale drift of `monitor` and passes an accepted estimate to the
+    /// calibration policy, see [`scale_drift`](crate::scale_drift)
+    pub fn check_scale_drift(
+        &mut self,
+        monitor: &ScaleDriftMonitor,
+    ) -> Result<ScaleDriftEstimate, ScaleDriftError> {
+        let estimate = monitor.estimate()?;
+        self.validity.set_scale_drift(estimate.drift());
+        Ok(estimate)
+    }
+
+    /// Multiplies the accepted scale correction of `monitor` onto the per-axis accel scale
+    /// factors, bounded to [`max_step`](ScaleDriftConfig::max_step) per axis, and rescales
+    /// the statistics of `monitor`. Returns the factors applied, the correction left is
+    /// passed to the calibration policy
+    pub fn apply_scale_correction(
+        &mut self,
+        monitor: &mut ScaleDriftMonitor,
+    ) -> Result<Vec3A, ScaleDriftError> {
+        let estimate = monitor.estimate()?;
+        let max_step = Vec3A::splat(monitor.config().max_step);
+        let step = estimate
+            .scale_correction
+            .clamp(Vec3A::ONE - max_step, Vec3A::ONE + max_step);
+        self.acc_scale.per_axis *= step;
+        monitor.rescale(step);
+        let left = (estimate.scale_correction / step - Vec3A::ONE).abs();
+        self.validity.set_scale_drift(left.max_element());
+        self.bump_epoch(ConfigChange::ScaleFactors);
+        Ok(step)
+    }
+}
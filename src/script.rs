@@ -0,0 +1,795 @@
+//! Register sequences as data, for bring-up quirks found after a release.
+//!
+//! A [`RegisterScript`] is a short list of [`ScriptStep`]s, e.g. stored in configuration
+//! flash and run with [`Mpu6050::run_script`] after `init`. The instruction set is
+//! restricted and every script is validated before its first bus access:
+//!
+//! * writes only reach the bits listed in [`SCRIPT_WRITABLE`]
+//! * at most [`MAX_SCRIPT_STEPS`] steps, delays of at most [`MAX_SCRIPT_DELAY_MS`] in total
+//! * a write to a field the driver caches (ranges, DLPF_CFG, SMPLRT_DIV) goes through the
+//!   driver's setter, caches and strict configuration behave as with a direct call. A write
+//!   covering both a cached field and other bits is refused
+//!
+//! The crate has no serde dependency, scripts have a fixed binary format:
+//!
+//! | bytes | content |
+//! |:---|:---|
+//! | 1 | format version ([`SCRIPT_VERSION`]) |
+//! | 1 | step count |
+//! | n | steps, an opcode followed by its operands |
+//! | 2 | CRC-16/CCITT-FALSE ([`crc16`](crate::packed::crc16)) over all previous bytes, little endian |
+//!
+//! | step | opcode | operands |
+//! |:---|:---|:---|
+//! | [`WriteReg`](ScriptStep::WriteReg) | 1 | reg, value |
+//! | [`WriteBits`](ScriptStep::WriteBits) | 2 | reg, start bit, length, value |
+//! | [`DelayMs`](ScriptStep::DelayMs) | 3 | ms |
+//! | [`ExpectBits`](ScriptStep::ExpectBits) | 4 | reg, start bit, length, value, 0 abort or 1 continue |
+//!
+//! Bytes after the CRC are ignored, e.g. erased flash after the script.
+//! ```
+//! use mpu6050::device::{PWR_MGMT_1, SIGNAL_PATH_RESET};
+//! use mpu6050::script::{RegisterScript, ScriptStep, MAX_SCRIPT_LEN};
+//!
+//! // batch with a slow wake: longer delay after clearing SLEEP, then reset the signal paths
+//! const QUIRK: RegisterScript = RegisterScript::new()
+//!     .with_step(ScriptStep::WriteBits { reg: PWR_MGMT_1::ADDR, start_bit: PWR_MGMT_1::SLEEP, length: 1, value: 0 })
+//!     .with_step(ScriptStep::DelayMs(50))
+//!     .with_step(ScriptStep::WriteReg { reg: SIGNAL_PATH_RESET::ADDR, value: 0x07 })
+//!     .with_step(ScriptStep::DelayMs(100));
+//!
+//! let mut flash = [0xff; MAX_SCRIPT_LEN];
+//! let len = QUIRK.encode(&mut flash).unwrap();
+//! assert_eq!(len, 2 + 5 + 2 + 3 + 2 + 2);
+//! assert_eq!(RegisterScript::decode(&flash), Ok(QUIRK));
+//! ```
+
+use core::fmt::{self, Debug, Display};
+
+#[cfg(feature = "driver")]
+use embedded_hal::blocking::{
+    delay::DelayMs,
+    i2c::{Write, WriteRead},
+};
+
+#[cfg(feature = "driver")]
+use crate::config_events::ChangeSource;
+use crate::device::*;
+use crate::packed::crc16;
+use crate::register::Register;
+#[cfg(feature = "driver")]
+use crate::register::RegisterValue;
+use crate::{Mpu6050, Mpu6050Error};
+
+/// Format version written to the header
+pub const SCRIPT_VERSION: u8 = 1;
+/// Steps in a script at most
+pub const MAX_SCRIPT_STEPS: usize = 32;
+/// Sum of the delays of a script at most, in ms
+pub const MAX_SCRIPT_DELAY_MS: u32 = 1000;
+/// Longest encoded script in bytes
+pub const MAX_SCRIPT_LEN: usize = 2 + MAX_SCRIPT_STEPS * 6 + 2;
+
+/// Field cached by the driver, written through its setter
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum CachedField {
+    /// SMPLRT_DIV, [`Mpu6050::set_sample_rate_divider`]
+    SampleRateDiv,
+    /// CONFIG DLPF_CFG, [`Mpu6050::set_dlpf`]
+    DlpfCfg,
+    /// GYRO_CONFIG FS_SEL, [`Mpu6050::set_gyro_range`]
+    GyroRange,
+    /// ACCEL_CONFIG AFS_SEL, [`Mpu6050::set_accel_range`]
+    AccelRange,
+}
+
+impl CachedField {
+    /// bits of the field in its register
+    pub const fn mask(self) -> u8 {
+        match self {
+            CachedField::SampleRateDiv => 0xff,
+            CachedField::DlpfCfg => CONFIG::DLPF_CFG_FIELD.mask(),
+            CachedField::GyroRange => GYRO_CONFIG::FS_SEL_FIELD.mask(),
+            CachedField::AccelRange => ACCEL_CONFIG::FS_SEL_FIELD.mask(),
+        }
+    }
+}
+
+/// Register bits a script may write
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct WritableRegister {
+    /// register address
+    pub reg: u8,
+    /// writable bits, `WriteReg` needs all eight
+    pub bits: u8,
+    /// field cached by the driver within `bits`
+    pub cached: Option<CachedField>,
+}
+
+const fn writable(reg: u8, bits: u8, cached: Option<CachedField>) -> WritableRegister {
+    WritableRegister { reg, bits, cached }
+}
+
+/// Write whitelist of scripts, sorted by address. Left out are the FIFO, interrupt, auxiliary
+/// I2C and power state bits the driver tracks, the offsets (16 bit pairs) and device reset
+pub const SCRIPT_WRITABLE: &[WritableRegister] = &[
+    writable(SMPLRT_DIV, 0xff, Some(CachedField::SampleRateDiv)),
+    writable(
+        CONFIG::ADDR,
+        CONFIG::DLPF_CFG_FIELD.mask(),
+        Some(CachedField::DlpfCfg),
+    ),
+    writable(GYRO_CONFIG::ADDR, 0xff, Some(CachedField::GyroRange)),
+    writable(ACCEL_CONFIG::ADDR, 0xff, Some(CachedField::AccelRange)),
+    writable(MOT_THR, 0xff, None),
+    writable(MOT_DUR, 0xff, None),
+    writable(SIGNAL_PATH_RESET::ADDR, 0xff, None),
+    writable(MOT_DETECT_CONTROL::ADDR, 0xff, None),
+    writable(
+        USER_CTRL::ADDR,
+        USER_CTRL::SIG_COND_RESET_FIELD.mask(),
+        None,
+    ),
+    writable(
+        PWR_MGMT_1::ADDR,
+        PWR_MGMT_1::SLEEP_FIELD.mask() | PWR_MGMT_1::CLKSEL_FIELD.mask(),
+        None,
+    ),
+];
+
+/// Whitelist entry of `reg`
+pub fn script_writable(reg: u8) -> Option<&'static WritableRegister> {
+    SCRIPT_WRITABLE.iter().find(|entry| entry.reg == reg)
+}
+
+/// Policy of a failed [`ExpectBits`](ScriptStep::ExpectBits)
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum OnFail {
+    /// stop the script with [`ScriptError::ExpectationFailed`]
+    Abort,
+    /// record the mismatch and run the next step
+    Continue,
+}
+
+/// Instruction of a [`RegisterScript`]. Bit fields are `length` bits from `start_bit` down,
+/// as in [`Mpu6050::write_register_field`]
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum ScriptStep {
+    /// write `value` to `reg`
+    WriteReg {
+        /// register
+        reg: u8,
+        /// value
+        value: u8,
+    },
+    /// read, modify and write a field of `reg`
+    WriteBits {
+        /// register
+        reg: u8,
+        /// most significant bit of the field
+        start_bit: u8,
+        /// field width, 1 to 8
+        length: u8,
+        /// field value
+        value: u8,
+    },
+    /// wait
+    DelayMs(u8),
+    /// read a field of `reg` and compare it to `value`
+    ExpectBits {
+        /// register
+        reg: u8,
+        /// most significant bit of the field
+        start_bit: u8,
+        /// field width, 1 to 8
+        length: u8,
+        /// expected field value
+        value: u8,
+        /// what a mismatch does
+        on_fail: OnFail,
+    },
+}
+
+const OP_WRITE_REG: u8 = 1;
+const OP_WRITE_BITS: u8 = 2;
+const OP_DELAY_MS: u8 = 3;
+const OP_EXPECT_BITS: u8 = 4;
+
+impl ScriptStep {
+    fn encoded_len(&self) -> usize {
+        match self {
+            ScriptStep::WriteReg { .. } => 3,
+            ScriptStep::WriteBits { .. } => 5,
+            ScriptStep::DelayMs(_) => 2,
+            ScriptStep::ExpectBits { .. } => 6,
+        }
+    }
+
+    fn encode(&self, buf: &mut [u8]) {
+        match *self {
+            ScriptStep::WriteReg { reg, value } => buf.copy_from_slice(&[OP_WRITE_REG, reg, value]),
+            ScriptStep::WriteBits {
+                reg,
+                start_bit,
+                length,
+                value,
+            } => buf.copy_from_slice(&[OP_WRITE_BITS, reg, start_bit, length, value]),
+            ScriptStep::DelayMs(ms) => buf.copy_from_slice(&[OP_DELAY_MS, ms]),
+            ScriptStep::ExpectBits {
+                reg,
+                start_bit,
+                length,
+                value,
+                on_fail,
+            } => {
+                let on_fail = match on_fail {
+                    OnFail::Abort => 0,
+                    OnFail::Continue => 1,
+                };
+                buf.copy_from_slice(&[OP_EXPECT_BITS, reg, start_bit, length, value, on_fail])
+            }
+        }
+    }
+
+    /// step at the start of `buf` and its length
+    fn decode(buf: &[u8], step: usize) -> Result<(Self, usize), DecodeError> {
+        let opcode = *buf.first().ok_or(DecodeError::Truncated)?;
+        let operands = |n: usize| buf.get(1..1 + n).ok_or(DecodeError::Truncated);
+        let decoded = match opcode {
+            OP_WRITE_REG => {
+                let o = operands(2)?;
+                ScriptStep::WriteReg {
+                    reg: o[0],
+                    value: o[1],
+                }
+            }
+            OP_WRITE_BITS => {
+                let o = operands(4)?;
+                ScriptStep::WriteBits {
+                    reg: o[0],
+                    start_bit: o[1],
+                    length: o[2],
+                    value: o[3],
+                }
+            }
+            OP_DELAY_MS => ScriptStep::DelayMs(operands(1)?[0]),
+            OP_EXPECT_BITS => {
+                let o = operands(5)?;
+                let on_fail = match o[4] {
+                    0 => OnFail::Abort,
+                    1 => OnFail::Continue,
+                    _ => return Err(DecodeError::UnknownOpcode { step, opcode }),
+                };
+                ScriptStep::ExpectBits {
+                    reg: o[0],
+                    start_bit: o[1],
+                    length: o[2],
+                    value: o[3],
+                    on_fail,
+                }
+            }
+            _ => return Err(DecodeError::UnknownOpcode { step, opcode }),
+        };
+        Ok((decoded, decoded.encoded_len()))
+    }
+}
+
+/// (mask, shift) of a field, None if it does not fit a register or `value` not the field
+fn field_mask(start_bit: u8, length: u8, value: u8) -> Option<(u8, u8)> {
+    if length == 0 || start_bit > 7 || length > start_bit + 1 {
+        return None;
+    }
+    let shift = start_bit + 1 - length;
+    let max = ((1u16 << length) - 1) as u8;
+    (value <= max).then_some((max << shift, shift))
+}
+
+/// Validated, bounded register sequence, see the [module docs](self)
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct RegisterScript {
+    steps: [ScriptStep; MAX_SCRIPT_STEPS],
+    len: usize,
+}
+
+impl Default for RegisterScript {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl RegisterScript {
+    /// script without steps
+    pub const fn new() -> Self {
+        Self {
+            steps: [ScriptStep::DelayMs(0); MAX_SCRIPT_STEPS],
+            len: 0,
+        }
+    }
+
+    /// with `step` appended, for const scripts. Panics beyond [`MAX_SCRIPT_STEPS`]
+    pub const fn with_step(mut self, step: ScriptStep) -> Self {
+        assert!(self.len < MAX_SCRIPT_STEPS, "script too long");
+        self.steps[self.len] = step;
+        self.len += 1;
+        self
+    }
+
+    /// script of `steps`, validated
+    pub fn from_steps(steps: &[ScriptStep]) -> Result<Self, ScriptViolation> {
+        let mut script = Self::new();
+        for step in steps {
+            script.push(*step)?;
+        }
+        script.validate()?;
+        Ok(script)
+    }
+
+    /// appends `step`, Err beyond [`MAX_SCRIPT_STEPS`]
+    pub fn push(&mut self, step: ScriptStep) -> Result<(), ScriptViolation> {
+        if self.len == MAX_SCRIPT_STEPS {
+            return Err(ScriptViolation::TooManySteps);
+        }
+        self.steps[self.len] = step;
+        self.len += 1;
+        Ok(())
+    }
+
+    /// steps in order
+    pub fn steps(&self) -> &[ScriptStep] {
+        &self.steps[..self.len]
+    }
+
+    /// number of steps
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// true without steps
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// sum of the delays in ms
+    pub fn total_delay_ms(&self) -> u32 {
+        self.steps()
+            .iter()
+            .map(|step| match step {
+                ScriptStep::DelayMs(ms) => u32::from(*ms),
+                _ => 0,
+            })
+            .sum()
+    }
+
+    /// Checks fields, the write whitelist [`SCRIPT_WRITABLE`] and the delay budget, without
+    /// bus access. [`Mpu6050::run_script`] runs nothing of a script failing this
+    pub fn validate(&self) -> Result<(), ScriptViolation> {
+        for (step, instruction) in self.steps().iter().enumerate() {
+            let (reg, mask) = match *instruction {
+                ScriptStep::WriteReg { reg, .. } => (reg, 0xff),
+                ScriptStep::WriteBits {
+                    reg,
+                    start_bit,
+                    length,
+                    value,
+                } => {
+                    let (mask, _) = field_mask(start_bit, length, value)
+                        .ok_or(ScriptViolation::InvalidField { step })?;
+                    (reg, mask)
+                }
+                ScriptStep::DelayMs(_) => continue,
+                ScriptStep::ExpectBits {
+                    reg,
+                    start_bit,
+                    length,
+                    value,
+                    ..
+                } => {
+                    field_mask(start_bit, length, value)
+                        .ok_or(ScriptViolation::InvalidField { step })?;
+                    Register::from_addr(reg)
+                        .filter(|register| register.check_byte(false).is_ok())
+                        .ok_or(ScriptViolation::NotReadable { step, reg })?;
+                    continue;
+                }
+            };
+            let entry = script_writable(reg)
+                .filter(|entry| mask & !entry.bits == 0)
+                .ok_or(ScriptViolation::NotWritable { step, reg })?;
+            if let Some(cached) = entry.cached {
+                if mask & cached.mask() != 0 && mask & !cached.mask() != 0 {
+                    return Err(ScriptViolation::SplitsCachedField { step, reg });
+                }
+            }
+        }
+        let total = self.total_delay_ms();
+        if total > MAX_SCRIPT_DELAY_MS {
+            return Err(ScriptViolation::DelayBudget(total));
+        }
+        Ok(())
+    }
+
+    /// encoded length in bytes
+    pub fn encoded_len(&self) -> usize {
+        let steps: usize = self.steps().iter().map(ScriptStep::encoded_len).sum();
+        2 + steps + 2
+    }
+
+    /// Encodes the script into `buf`, returns the bytes written
+    pub fn encode(&self, buf: &mut [u8]) -> Result<usize, EncodeError> {
+        let len = self.encoded_len();
+        if buf.len() < len {
+            return Err(EncodeError::BufferTooSmall(len));
+        }
+        buf[0] = SCRIPT_VERSION;
+        buf[1] = self.len as u8;
+        let mut at = 2;
+        for step in self.steps() {
+            let end = at + step.encoded_len();
+            step.encode(&mut buf[at..end]);
+            at = end;
+        }
+        let crc = crc16(&buf[..at]);
+        buf[at..len].copy_from_slice(&crc.to_le_bytes());
+        Ok(len)
+    }
+
+    /// Decodes and validates a script from the start of `buf`
+    pub fn decode(buf: &[u8]) -> Result<Self, DecodeError> {
+        let [version, count, ..] = *buf else {
+            return Err(DecodeError::Truncated);
+        };
+        if version != SCRIPT_VERSION {
+            return Err(DecodeError::BadHeader);
+        }
+        if usize::from(count) > MAX_SCRIPT_STEPS {
+            return Err(DecodeError::Invalid(ScriptViolation::TooManySteps));
+        }
+        let mut script = Self::new();
+        let mut at = 2;
+        for step in 0..usize::from(count) {
+            let (decoded, len) = ScriptStep::decode(&buf[at..], step)?;
+            script.steps[step] = decoded;
+            script.len += 1;
+            at += len;
+        }
+        let crc = buf.get(at..at + 2).ok_or(DecodeError::Truncated)?;
+        if crc16(&buf[..at]).to_le_bytes() != crc {
+            return Err(DecodeError::CrcMismatch);
+        }
+        script.validate().map_err(DecodeError::Invalid)?;
+        Ok(script)
+    }
+}
+
+/// Why a script is refused before running
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum ScriptViolation {
+    /// more than [`MAX_SCRIPT_STEPS`] steps
+    TooManySteps,
+    /// delays exceed [`MAX_SCRIPT_DELAY_MS`], their sum
+    DelayBudget(u32),
+    /// field outside a register or value wider than the field
+    InvalidField {
+        /// step index
+        step: usize,
+    },
+    /// write outside [`SCRIPT_WRITABLE`]
+    NotWritable {
+        /// step index
+        step: usize,
+        /// register
+        reg: u8,
+    },
+    /// write covering a cached field and other bits together
+    SplitsCachedField {
+        /// step index
+        step: usize,
+        /// register
+        reg: u8,
+    },
+    /// expectation on an address outside the register map or on half of a register pair
+    NotReadable {
+        /// step index
+        step: usize,
+        /// register
+        reg: u8,
+    },
+}
+
+impl Display for ScriptViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ScriptViolation::TooManySteps => {
+                write!(f, "more than {} steps", MAX_SCRIPT_STEPS)
+            }
+            ScriptViolation::DelayBudget(ms) => {
+                write!(f, "delays of {} ms exceed {} ms", ms, MAX_SCRIPT_DELAY_MS)
+            }
+            ScriptViolation::InvalidField { step } => {
+                write!(f, "step {}: invalid bit field", step)
+            }
+            ScriptViolation::NotWritable { step, reg } => {
+                write!(f, "step {}: register 0x{:02x} not writable", step, reg)
+            }
+            ScriptViolation::SplitsCachedField { step, reg } => write!(
+                f,
+                "step {}: write to register 0x{:02x} mixes a cached field with other bits",
+                step, reg
+            ),
+            ScriptViolation::NotReadable { step, reg } => {
+                write!(
+                    f,
+                    "step {}: register 0x{:02x} not readable alone",
+                    step, reg
+                )
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ScriptViolation {}
+
+/// Encoding failure
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum EncodeError {
+    /// output buffer too small, required length
+    BufferTooSmall(usize),
+}
+
+impl Display for EncodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EncodeError::BufferTooSmall(len) => {
+                write!(f, "buffer too small, {} bytes required", len)
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for EncodeError {}
+
+/// Decoding failure
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum DecodeError {
+    /// input ends within the script
+    Truncated,
+    /// unknown version
+    BadHeader,
+    /// unknown opcode or policy
+    UnknownOpcode {
+        /// step index
+        step: usize,
+        /// byte read
+        opcode: u8,
+    },
+    /// CRC of the script does not match
+    CrcMismatch,
+    /// decoded, but refused by [`RegisterScript::validate`]
+    Invalid(ScriptViolation),
+}
+
+impl Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DecodeError::Truncated => f.write_str("script truncated"),
+            DecodeError::BadHeader => f.write_str("unknown script format"),
+            DecodeError::UnknownOpcode { step, opcode } => {
+                write!(f, "step {}: unknown opcode 0x{:02x}", step, opcode)
+            }
+            DecodeError::CrcMismatch => f.write_str("CRC mismatch"),
+            DecodeError::Invalid(violation) => Display::fmt(violation, f),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for DecodeError {}
+
+/// Outcome of one step
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum StepOutcome {
+    /// not reached
+    NotRun,
+    /// register or cached field written
+    Written,
+    /// waited, ms
+    Delayed(u8),
+    /// field read as expected
+    Matched,
+    /// field read differing from the expectation, value read
+    Mismatch(u8),
+}
+
+/// Per-step outcomes of a run
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct ScriptReport {
+    outcomes: [StepOutcome; MAX_SCRIPT_STEPS],
+    len: usize,
+}
+
+impl ScriptReport {
+    fn new(len: usize) -> Self {
+        Self {
+            outcomes: [StepOutcome::NotRun; MAX_SCRIPT_STEPS],
+            len,
+        }
+    }
+
+    /// outcome of every step of the script, in order
+    pub fn outcomes(&self) -> &[StepOutcome] {
+        &self.outcomes[..self.len]
+    }
+
+    /// expectations that failed
+    pub fn mismatches(&self) -> usize {
+        self.outcomes()
+            .iter()
+            .filter(|outcome| matches!(outcome, StepOutcome::Mismatch(_)))
+            .count()
+    }
+
+    /// time waited in ms
+    pub fn delay_ms(&self) -> u32 {
+        self.outcomes()
+            .iter()
+            .map(|outcome| match outcome {
+                StepOutcome::Delayed(ms) => u32::from(*ms),
+                _ => 0,
+            })
+            .sum()
+    }
+}
+
+/// Failure of [`Mpu6050::run_script`]
+#[derive(Debug)]
+pub enum ScriptError<E> {
+    /// refused before any bus access
+    Rejected(ScriptViolation),
+    /// an [`OnFail::Abort`] expectation failed at `step`, later steps did not run
+    ExpectationFailed {
+        /// step index
+        step: usize,
+        /// outcomes up to the failed step
+        report: ScriptReport,
+    },
+    /// the driver failed at `step`, e.g. a bus error or strict configuration refusing a rate
+    Driver {
+        /// step index
+        step: usize,
+        /// driver error, the steps before succeeded
+        error: Mpu6050Error<E>,
+    },
+}
+
+impl<E: Display> Display for ScriptError<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ScriptError::Rejected(violation) => write!(f, "script rejected, {}", violation),
+            ScriptError::ExpectationFailed { step, report } => match report.outcomes()[*step] {
+                StepOutcome::Mismatch(actual) => {
+                    write!(f, "step {}: expectation failed, read {:#x}", step, actual)
+                }
+                _ => write!(f, "step {}: expectation failed", step),
+            },
+            ScriptError::Driver { step, error, .. } => write!(f, "step {}: {}", step, error),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<E: Debug + Display> std::error::Error for ScriptError<E> {}
+
+#[cfg(feature = "driver")]
+impl<I, D, E> Mpu6050<I, D>
+where
+    I: Write<Error = E> + WriteRead<Error = E>,
+{
+    pub(crate) fn run_script_using(
+        &mut self,
+        script: &RegisterScript,
+        delay: &mut impl DelayMs<u8>,
+    ) -> Result<ScriptReport, ScriptError<E>> {
+        script.validate().map_err(ScriptError::Rejected)?;
+        let mut report = ScriptReport::new(script.len());
+        for (step, instruction) in script.steps().iter().enumerate() {
+            let outcome = self
+                .with_change_source(ChangeSource::Script, |mpu| {
+                    mpu.run_script_step(*instruction, delay)
+                })
+                .map_err(|error| ScriptError::Driver { step, error })?;
+            report.outcomes[step] = outcome;
+            if let (
+                StepOutcome::Mismatch(_),
+                ScriptStep::ExpectBits {
+                    on_fail: OnFail::Abort,
+                    ..
+                },
+            ) = (outcome, instruction)
+            {
+                return Err(ScriptError::ExpectationFailed { step, report });
+            }
+        }
+        Ok(report)
+    }
+
+    fn run_script_step(
+        &mut self,
+        step: ScriptStep,
+        delay: &mut impl DelayMs<u8>,
+    ) -> Result<StepOutcome, Mpu6050Error<E>> {
+        match step {
+            ScriptStep::WriteReg { reg, value } => self.write_script_bits(reg, 0xff, value)?,
+            ScriptStep::WriteBits {
+                reg,
+                start_bit,
+                length,
+                value,
+            } => {
+                // validated
+                let (mask, shift) = field_mask(start_bit, length, value).unwrap_or((0, 0));
+                self.write_script_bits(reg, mask, value << shift)?
+            }
+            ScriptStep::DelayMs(ms) => {
+                delay.delay_ms(ms);
+                return Ok(StepOutcome::Delayed(ms));
+            }
+            ScriptStep::ExpectBits {
+                reg,
+                start_bit,
+                length,
+                value,
+                ..
+            } => {
+                let (mask, shift) = field_mask(start_bit, length, value).unwrap_or((0, 0));
+                let actual = (self.read_register(Register::known(reg))? & mask) >> shift;
+                return Ok(if actual == value {
+                    StepOutcome::Matched
+                } else {
+                    StepOutcome::Mismatch(actual)
+                });
+            }
+        }
+        Ok(StepOutcome::Written)
+    }
+
+    /// writes the `mask` bits of `bits` to a whitelisted `reg`, cached fields through their
+    /// setter
+    fn write_script_bits(&mut self, reg: u8, mask: u8, bits: u8) -> Result<(), Mpu6050Error<E>> {
+        let cached = script_writable(reg)
+            .and_then(|entry| entry.cached)
+            .filter(|cached| mask & cached.mask() != 0);
+        let Some(cached) = cached else {
+            let reg = Register::known(reg);
+            return match mask {
+                0xff => self.write_register(reg, bits),
+                _ => self
+                    .modify_register(reg, |byte| byte & !mask | bits)
+                    .map(|_| ()),
+            };
+        };
+        // the field as cached, with the written bits replaced
+        let current = match cached {
+            CachedField::SampleRateDiv => self.sample_rate_div,
+            CachedField::DlpfCfg => self.dlpf_cfg,
+            CachedField::GyroRange => RegisterValue::<GYRO_CONFIG>::ZERO
+                .with(GYRO_CONFIG::FS_SEL_FIELD, self.gyro_range)
+                .byte(),
+            CachedField::AccelRange => RegisterValue::<ACCEL_CONFIG>::ZERO
+                .with(ACCEL_CONFIG::FS_SEL_FIELD, self.accel_range)
+                .byte(),
+        };
+        let byte = current & !mask | bits;
+        match cached {
+            CachedField::SampleRateDiv => self.set_sample_rate_divider(byte),
+            CachedField::DlpfCfg => self.set_dlpf(byte & cached.mask()),
+            CachedField::GyroRange => self.set_gyro_range(GyroRange::from(
+                RegisterValue::<GYRO_CONFIG>::from_byte(byte).field(GYRO_CONFIG::FS_SEL_FIELD),
+            )),
+            CachedField::AccelRange => self.set_accel_range(AccelRange::from(
+                RegisterValue::<ACCEL_CONFIG>::from_byte(byte).field(ACCEL_CONFIG::FS_SEL_FIELD),
+            )),
+        }
+    }
+}
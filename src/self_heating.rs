@@ -0,0 +1,606 @@
+//! Gyro self-heating characterization and the boot-transient corrector.
+//!
+//! The die warms by a few °C over the first minutes after power-up, and the gyro bias follows
+//! it along a settling curve of its own per unit. [`Mpu6050::characterize_self_heating`]
+//! measures that curve from a cold boot, for a factory tool to store per unit:
+//!
+//! 1. every `interval_ms` a stillness window of [`WINDOW_SAMPLES`] bursts
+//!    [`WINDOW_SAMPLE_INTERVAL_MS`] apart is read, the mean gyro rate of the window, scaled
+//!    and without offsets, is the bias estimate of that instant
+//! 2. a window whose per-axis standard deviation exceeds [`MAX_GYRO_STD_DEV`] rad/s or
+//!    [`MAX_ACC_STD_DEV`] g is motion: the routine aborts like an expired
+//!    [`deadline`](crate::deadline), [`Mpu6050Error::Aborted`] in phase
+//!    [`MOTION_PHASE`], and nothing is fitted. The caller's own deadline aborts in
+//!    [`PHASE`]
+//! 3. each window is a [`SelfHeatingPoint`] streamed to the sink, and kept for the fit in a
+//!    [`SelfHeatingFitter`] of at most [`FIT_CAPACITY`] points, thinned out evenly when
+//!    full: memory stays bounded whatever the duration, the sink has the full record
+//! 4. the returned [`SelfHeatingSummary`] fits each gyro axis and the temperature to the
+//!    exponential settling model below
+//!
+//! #### The model
+//! `y(t) = asymptote + amplitude · exp(-t / time_constant)`, t in s since the routine started.
+//! For a fixed time constant the model is linear in asymptote and amplitude, solved by least
+//! squares in closed form. [`fit_exponential_settling`] searches the time constant on a
+//! logarithmic grid of [`TAU_GRID`] values from 1 % of the recorded span to 4 times the span,
+//! then refines between the neighbours of the best one by golden section on its logarithm.
+//! [`SettlingFit`] carries the RMS residual and R² of the fit; a time constant at either end
+//! of the search is [`at_bound`](SettlingFit::at_bound), the record did not resolve it: too
+//! short to see the settling, or settled within the first interval.
+//!
+//! #### Cold start
+//! The curve only shows from a cold die. Without a reference of the ambient temperature the
+//! routine judges it from the temperature fit: a die starting at least
+//! [`COLD_START_MIN_RISE_C`] below its fitted asymptote was near ambient, and
+//! [`cold_start`](SelfHeatingSummary::cold_start) is true. Otherwise the summary says so and a
+//! warning is logged under [`CALIBRATION`](crate::logging::CALIBRATION): the die had already
+//! warmed, and the amplitudes fitted are the tail of the curve only.
+//!
+//! #### Boot-transient correction
+//! [`SelfHeatingSummary::model`] turns the fit into a [`BootTransientModel`], stored with
+//! [`Mpu6050::set_boot_transient_correction`] right after power-up. The gyro offsets correct the
+//! asymptote, the steady bias, and the corrector removes the part of the curve above it,
+//! `amplitude · exp(-t / time_constant)`, from every direct gyro reading for the first
+//! [`active_for_s`](BootTransientModel::active_for_s), [`ACTIVE_TIME_CONSTANTS`] of the
+//! slowest axis by default. The driver has no clock: [`Mpu6050::advance_boot_transient`]
+//! moves the corrector's time on. FIFO frames and the [`Pipeline`](crate::scale::Pipeline) are
+//! not corrected, nor is anything in the `minimal-pipeline` build, see
+//! [`stages`](crate::stages).
+//! ```
+//! use mpu6050::self_heating::{fit_exponential_settling, BootTransientModel};
+//! use mpu6050::Vec3A;
+//!
+//! let t: Vec<f32> = (0..60).map(|i| i as f32 * 10.).collect();
+//! let y: Vec<f32> = t.iter().map(|t| 0.01 + 0.02 * (-t / 90.).exp()).collect();
+//! let fit = fit_exponential_settling(&t, &y).unwrap();
+//! assert!((fit.time_constant_s - 90.).abs() < 1.);
+//! assert!((fit.amplitude - 0.02).abs() < 1e-4);
+//!
+//! let model = BootTransientModel::new(Vec3A::splat(0.02), Vec3A::splat(90.));
+//! assert_eq!(model.active_for_s, 450.);
+//! assert!((model.correction_at(0.) + Vec3A::splat(0.02)).length() < 1e-6);
+//! assert_eq!(model.correction_at(451.), Vec3A::ZERO);
+//! ```
+
+#[cfg(feature = "driver")]
+use embedded_hal::blocking::{
+    delay::DelayMs,
+    i2c::{Write, WriteRead},
+};
+use glam::Vec3A;
+
+use crate::config_epoch::ConfigChange;
+#[cfg(feature = "driver")]
+use crate::deadline::AbortProgress;
+#[cfg(feature = "driver")]
+use crate::logging::log_warn;
+#[cfg(not(feature = "std"))]
+use crate::math::FloatExt;
+use crate::packed::{crc16, DecodeError};
+#[cfg(feature = "driver")]
+use crate::scale;
+#[cfg(feature = "driver")]
+use crate::setup::Accumulator;
+use crate::Mpu6050;
+#[cfg(feature = "driver")]
+use crate::Mpu6050Error;
+
+/// Bursts read per stillness window
+pub const WINDOW_SAMPLES: u16 = 20;
+/// Time between the bursts of a window, in ms
+pub const WINDOW_SAMPLE_INTERVAL_MS: u8 = 2;
+/// Largest per-axis gyro standard deviation of a still window, rad/s, the limit of the
+/// gyro calibration
+pub const MAX_GYRO_STD_DEV: f32 = 0.01;
+/// Largest per-axis accel standard deviation of a still window, g, the limit of the accel
+/// calibration
+pub const MAX_ACC_STD_DEV: f32 = 0.02;
+/// Smallest rise of the die temperature to its asymptote of a cold start, °C
+pub const COLD_START_MIN_RISE_C: f32 = 1.;
+/// Points kept for the fit
+pub const FIT_CAPACITY: usize = 64;
+/// Fewest points [`fit_exponential_settling`] fits
+pub const MIN_FIT_POINTS: usize = 5;
+/// Time constants tried by the grid search of [`fit_exponential_settling`]
+pub const TAU_GRID: usize = 48;
+/// Time constants of the slowest axis a [`BootTransientModel::new`] stays active for
+pub const ACTIVE_TIME_CONSTANTS: f32 = 5.;
+/// [`Mpu6050Error::Aborted`] phase of an expired deadline
+pub const PHASE: &str = "SelfHeating";
+/// [`Mpu6050Error::Aborted`] phase of a window that moved
+pub const MOTION_PHASE: &str = "SelfHeatingMotion";
+/// Format version written by [`BootTransientModel::to_bytes`]
+pub const BOOT_TRANSIENT_VERSION: u8 = 1;
+/// Length of the serialized model
+pub const BOOT_TRANSIENT_LEN: usize = 32;
+
+/// One stillness window of a characterization
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct SelfHeatingPoint {
+    /// time of the window's middle since the routine started, in s
+    pub elapsed_s: f32,
+    /// mean die temperature of the window in °C
+    pub temp_c: f32,
+    /// mean gyro rate of the window in rad/s, scaled without offsets
+    pub gyro_bias: Vec3A,
+    /// largest per-axis gyro standard deviation of the window in rad/s
+    pub gyro_std_dev: f32,
+}
+
+/// `asymptote + amplitude · exp(-t / time_constant)` fitted by [`fit_exponential_settling`]
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct SettlingFit {
+    /// value at t = 0 above the asymptote, in the units of the data
+    pub amplitude: f32,
+    /// time constant in s
+    pub time_constant_s: f32,
+    /// settled value, in the units of the data
+    pub asymptote: f32,
+    /// RMS of the residuals, in the units of the data
+    pub rms_residual: f32,
+    /// coefficient of determination, 1 for a perfect fit, 1 as well for constant data
+    pub r_squared: f32,
+    /// the time constant is at an end of the search range and not resolved by the data
+    pub at_bound: bool,
+    /// points fitted
+    pub points: u32,
+}
+
+impl SettlingFit {
+    /// the model at `t_s`
+    pub fn value_at(&self, t_s: f32) -> f32 {
+        self.asymptote + self.transient_at(t_s)
+    }
+
+    /// the model above its asymptote at `t_s`
+    pub fn transient_at(&self, t_s: f32) -> f32 {
+        self.amplitude * (-t_s / self.time_constant_s).exp()
+    }
+}
+
+/// Fits `y` at times `t_s` to the exponential settling model, see the
+/// [module docs](self#the-model). None with fewer than [`MIN_FIT_POINTS`] points, a
+/// non-finite value or no time span; extra values of the longer slice are ignored
+pub fn fit_exponential_settling(t_s: &[f32], y: &[f32]) -> Option<SettlingFit> {
+    let n = t_s.len().min(y.len());
+    let (t_s, y) = (&t_s[..n], &y[..n]);
+    if n < MIN_FIT_POINTS || t_s.iter().chain(y).any(|v| !v.is_finite()) {
+        return None;
+    }
+    let first = t_s.iter().copied().fold(f32::INFINITY, f32::min);
+    let last = t_s.iter().copied().fold(f32::NEG_INFINITY, f32::max);
+    let span = (last - first) as f64;
+    if span <= 0. {
+        return None;
+    }
+    let (low, high) = ((span / 100.).ln(), (span * 4.).ln());
+    let grid = |k: usize| low + (high - low) * k as f64 / (TAU_GRID - 1) as f64;
+    let sse = |ln_tau: f64| solve_linear(t_s, y, ln_tau.exp()).map_or(f64::INFINITY, |s| s.2);
+    let best = (0..TAU_GRID)
+        .min_by(|&a, &b| sse(grid(a)).total_cmp(&sse(grid(b))))
+        .unwrap_or(0);
+
+    // golden section between the neighbours of the best grid point
+    let (mut a, mut b) = (
+        grid(best.saturating_sub(1)),
+        grid((best + 1).min(TAU_GRID - 1)),
+    );
+    let ratio = (5f64.sqrt() - 1.) / 2.;
+    let (mut c, mut d) = (b - ratio * (b - a), a + ratio * (b - a));
+    let (mut sse_c, mut sse_d) = (sse(c), sse(d));
+    for _ in 0..40 {
+        if sse_c < sse_d {
+            b = d;
+            (d, sse_d) = (c, sse_c);
+            c = b - ratio * (b - a);
+            sse_c = sse(c);
+        } else {
+            a = c;
+            (c, sse_c) = (d, sse_d);
+            d = a + ratio * (b - a);
+            sse_d = sse(d);
+        }
+    }
+    let ln_tau = if sse(grid(best)) < sse_c.min(sse_d) {
+        grid(best)
+    } else {
+        (a + b) / 2.
+    };
+    let tau = ln_tau.exp();
+    let (asymptote, amplitude, sse) = solve_linear(t_s, y, tau)?;
+    let mean = y.iter().map(|&v| v as f64).sum::<f64>() / n as f64;
+    let sst: f64 = y.iter().map(|&v| (v as f64 - mean).powi(2)).sum();
+    let edge = (high - low) / (TAU_GRID - 1) as f64;
+    Some(SettlingFit {
+        amplitude: amplitude as f32,
+        time_constant_s: tau as f32,
+        asymptote: asymptote as f32,
+        rms_residual: (sse / n as f64).sqrt() as f32,
+        r_squared: if sst > 0. {
+            (1. - sse / sst) as f32
+        } else {
+            1.
+        },
+        at_bound: ln_tau < low + edge || ln_tau > high - edge,
+        points: n as u32,
+    })
+}
+
+/// asymptote, amplitude and squared error of the least squares fit at time constant `tau`
+fn solve_linear(t_s: &[f32], y: &[f32], tau: f64) -> Option<(f64, f64, f64)> {
+    let n = t_s.len() as f64;
+    let x = |t: f32| (-(t as f64) / tau).exp();
+    let mean_x = t_s.iter().map(|&t| x(t)).sum::<f64>() / n;
+    let mean_y = y.iter().map(|&v| v as f64).sum::<f64>() / n;
+    let (mut sxx, mut sxy) = (0., 0.);
+    for (&t, &v) in t_s.iter().zip(y) {
+        let dx = x(t) - mean_x;
+        sxx += dx * dx;
+        sxy += dx * (v as f64 - mean_y);
+    }
+    if sxx < 1e-12 {
+        return None;
+    }
+    let amplitude = sxy / sxx;
+    let asymptote = mean_y - amplitude * mean_x;
+    let sse = t_s
+        .iter()
+        .zip(y)
+        .map(|(&t, &v)| (v as f64 - asymptote - amplitude * x(t)).powi(2))
+        .sum();
+    Some((asymptote, amplitude, sse))
+}
+
+/// Points of a characterization kept for the fit, at most [`FIT_CAPACITY`]. Once full every
+/// other point is dropped and only every second point from then on is taken, so the kept
+/// points stay evenly spread over the record
+#[derive(Clone, Debug)]
+pub struct SelfHeatingFitter {
+    points: [SelfHeatingPoint; FIT_CAPACITY],
+    len: usize,
+    /// points pushed
+    seen: u32,
+    /// every `stride`th point pushed is kept
+    stride: u32,
+    start_temp_c: Option<f32>,
+}
+
+impl Default for SelfHeatingFitter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SelfHeatingFitter {
+    /// fitter without points
+    pub const fn new() -> Self {
+        Self {
+            points: [SelfHeatingPoint {
+                elapsed_s: 0.,
+                temp_c: 0.,
+                gyro_bias: Vec3A::ZERO,
+                gyro_std_dev: 0.,
+            }; FIT_CAPACITY],
+            len: 0,
+            seen: 0,
+            stride: 1,
+            start_temp_c: None,
+        }
+    }
+
+    /// adds the next point of the record, in time order
+    pub fn push(&mut self, point: SelfHeatingPoint) {
+        self.start_temp_c.get_or_insert(point.temp_c);
+        let index = self.seen;
+        self.seen += 1;
+        if !index.is_multiple_of(self.stride) {
+            return;
+        }
+        if self.len == FIT_CAPACITY {
+            for i in 0..FIT_CAPACITY / 2 {
+                self.points[i] = self.points[2 * i];
+            }
+            self.len = FIT_CAPACITY / 2;
+            self.stride *= 2;
+            if !index.is_multiple_of(self.stride) {
+                return;
+            }
+        }
+        self.points[self.len] = point;
+        self.len += 1;
+    }
+
+    /// the points kept
+    pub fn points(&self) -> &[SelfHeatingPoint] {
+        &self.points[..self.len]
+    }
+
+    /// fits of the points kept, see [`SelfHeatingSummary`]
+    pub fn summary(&self) -> SelfHeatingSummary {
+        let points = self.points();
+        let mut t = [0.; FIT_CAPACITY];
+        let mut y = [0.; FIT_CAPACITY];
+        for (t, point) in t.iter_mut().zip(points) {
+            *t = point.elapsed_s;
+        }
+        let t = &t[..points.len()];
+        let mut fit = |value: &dyn Fn(&SelfHeatingPoint) -> f32| {
+            for (y, point) in y.iter_mut().zip(points) {
+                *y = value(point);
+            }
+            fit_exponential_settling(t, &y[..points.len()])
+        };
+        let gyro = [
+            fit(&|p| p.gyro_bias.x),
+            fit(&|p| p.gyro_bias.y),
+            fit(&|p| p.gyro_bias.z),
+        ];
+        let temperature = fit(&|p| p.temp_c);
+        let start_temp_c = self.start_temp_c.unwrap_or(f32::NAN);
+        SelfHeatingSummary {
+            gyro,
+            temperature,
+            start_temp_c,
+            cold_start: temperature
+                .is_some_and(|fit| fit.asymptote - start_temp_c >= COLD_START_MIN_RISE_C),
+            points: self.seen,
+            duration_s: points.last().map_or(0., |point| point.elapsed_s),
+        }
+    }
+}
+
+/// Result of [`Mpu6050::characterize_self_heating`]
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct SelfHeatingSummary {
+    /// fits of the gyro bias in rad/s, x, y, z. None for too few points
+    pub gyro: [Option<SettlingFit>; 3],
+    /// fit of the die temperature in °C
+    pub temperature: Option<SettlingFit>,
+    /// die temperature of the first window in °C
+    pub start_temp_c: f32,
+    /// the die started near ambient, see the [module docs](self#cold-start)
+    pub cold_start: bool,
+    /// points recorded, all of them streamed to the sink
+    pub points: u32,
+    /// time of the last point in s
+    pub duration_s: f32,
+}
+
+impl SelfHeatingSummary {
+    /// the boot-transient corrector of the gyro fits, None unless all three axes fitted
+    pub fn model(&self) -> Option<BootTransientModel> {
+        let [Some(x), Some(y), Some(z)] = self.gyro else {
+            return None;
+        };
+        Some(BootTransientModel::new(
+            Vec3A::new(x.amplitude, y.amplitude, z.amplitude),
+            Vec3A::new(x.time_constant_s, y.time_constant_s, z.time_constant_s),
+        ))
+    }
+}
+
+/// Gyro bias above its asymptote after power-up, see the
+/// [module docs](self#boot-transient-correction)
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct BootTransientModel {
+    /// bias above the asymptote at power-up in rad/s, per axis
+    pub amplitude: Vec3A,
+    /// time constants in s, per axis
+    pub time_constant_s: Vec3A,
+    /// time after power-up the correction applies for, in s
+    pub active_for_s: f32,
+}
+
+impl BootTransientModel {
+    /// model active for [`ACTIVE_TIME_CONSTANTS`] of the slowest axis
+    pub fn new(amplitude: Vec3A, time_constant_s: Vec3A) -> Self {
+        Self {
+            amplitude,
+            time_constant_s,
+            active_for_s: ACTIVE_TIME_CONSTANTS * time_constant_s.max_element(),
+        }
+    }
+
+    /// true for finite amplitudes, positive finite time constants and a finite duration
+    pub fn is_valid(&self) -> bool {
+        self.amplitude.is_finite()
+            && self.time_constant_s.is_finite()
+            && self.time_constant_s.min_element() > 0.
+            && self.active_for_s.is_finite()
+            && self.active_for_s >= 0.
+    }
+
+    /// offset correcting a reading `t_s` after power-up, zero once inactive
+    pub fn correction_at(&self, t_s: f32) -> Vec3A {
+        if t_s > self.active_for_s {
+            return Vec3A::ZERO;
+        }
+        let decay = |tau: f32| (-t_s / tau).exp();
+        let tau = self.time_constant_s;
+        -self.amplitude * Vec3A::new(decay(tau.x), decay(tau.y), decay(tau.z))
+    }
+
+    /// Serialized form: format version ([`BOOT_TRANSIENT_VERSION`]), a reserved 0, the
+    /// amplitudes, time constants and active time as f32 little endian, CRC-16/CCITT-FALSE
+    /// over all previous bytes
+    pub fn to_bytes(&self) -> [u8; BOOT_TRANSIENT_LEN] {
+        let mut bytes = [0; BOOT_TRANSIENT_LEN];
+        bytes[0] = BOOT_TRANSIENT_VERSION;
+        let values = self
+            .amplitude
+            .to_array()
+            .into_iter()
+            .chain(self.time_constant_s.to_array())
+            .chain([self.active_for_s]);
+        for (chunk, value) in bytes[2..30].chunks_exact_mut(4).zip(values) {
+            chunk.copy_from_slice(&value.to_le_bytes());
+        }
+        let crc = crc16(&bytes[..BOOT_TRANSIENT_LEN - 2]);
+        bytes[BOOT_TRANSIENT_LEN - 2..].copy_from_slice(&crc.to_le_bytes());
+        bytes
+    }
+
+    /// Reads a model written by [`to_bytes`](Self::to_bytes), invalid parameters are a bad
+    /// header
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, DecodeError> {
+        if bytes.len() < BOOT_TRANSIENT_LEN {
+            return Err(DecodeError::Truncated(BOOT_TRANSIENT_LEN));
+        }
+        let bytes = &bytes[..BOOT_TRANSIENT_LEN];
+        if bytes[0] != BOOT_TRANSIENT_VERSION {
+            return Err(DecodeError::BadHeader);
+        }
+        let crc = u16::from_le_bytes([bytes[30], bytes[31]]);
+        if crc != crc16(&bytes[..BOOT_TRANSIENT_LEN - 2]) {
+            return Err(DecodeError::CrcMismatch);
+        }
+        let float = |i: usize| {
+            let at = 2 + 4 * i;
+            f32::from_le_bytes([bytes[at], bytes[at + 1], bytes[at + 2], bytes[at + 3]])
+        };
+        let model = Self {
+            amplitude: Vec3A::new(float(0), float(1), float(2)),
+            time_constant_s: Vec3A::new(float(3), float(4), float(5)),
+            active_for_s: float(6),
+        };
+        if !model.is_valid() {
+            return Err(DecodeError::BadHeader);
+        }
+        Ok(model)
+    }
+}
+
+/// A stored model and the time since power-up
+#[derive(Copy, Clone, Debug)]
+pub(crate) struct BootTransient {
+    pub(crate) model: BootTransientModel,
+    pub(crate) elapsed_s: f32,
+}
+
+impl<I, D> Mpu6050<I, D> {
+    /// Stores the boot-transient corrector, its time starting at 0: set it right after
+    /// power-up. None removes it. Advances the [`config_epoch`](crate::config_epoch) with
+    /// [`ConfigChange::Offsets`]. Returns false and keeps the corrector as it was for a model
+    /// failing [`BootTransientModel::is_valid`]
+    pub fn set_boot_transient_correction(&mut self, model: Option<BootTransientModel>) -> bool {
+        if model.is_some_and(|model| !model.is_valid()) {
+            return false;
+        }
+        self.boot_transient = model.map(|model| BootTransient {
+            model,
+            elapsed_s: 0.,
+        });
+        self.bump_epoch(ConfigChange::Offsets);
+        true
+    }
+
+    /// the stored corrector, None without one
+    pub fn boot_transient_correction(&self) -> Option<BootTransientModel> {
+        self.boot_transient.map(|transient| transient.model)
+    }
+
+    /// Moves the corrector's time since power-up on by `elapsed_ms`
+    pub fn advance_boot_transient(&mut self, elapsed_ms: u32) {
+        if let Some(transient) = &mut self.boot_transient {
+            transient.elapsed_s += elapsed_ms as f32 / 1000.;
+        }
+    }
+
+    /// the corrector's time since power-up in s, None without one
+    pub fn boot_transient_elapsed_s(&self) -> Option<f32> {
+        self.boot_transient.map(|transient| transient.elapsed_s)
+    }
+
+    /// offset the corrector adds to a gyro reading now, zero without one
+    #[cfg(not(feature = "minimal-pipeline"))]
+    pub(crate) fn boot_transient_offset(&self) -> Vec3A {
+        self.boot_transient.map_or(Vec3A::ZERO, |transient| {
+            transient.model.correction_at(transient.elapsed_s)
+        })
+    }
+}
+
+#[cfg(feature = "driver")]
+impl<I, D, E> Mpu6050<I, D>
+where
+    I: Write<Error = E> + WriteRead<Error = E>,
+{
+    /// the routine of [`characterize_self_heating`](Mpu6050::characterize_self_heating)
+    pub(crate) fn characterize_self_heating_using(
+        &mut self,
+        delay: &mut impl DelayMs<u8>,
+        duration_s: u16,
+        interval_ms: u16,
+        sink: &mut dyn FnMut(SelfHeatingPoint),
+        deadline: &mut dyn FnMut() -> bool,
+    ) -> Result<SelfHeatingSummary, Mpu6050Error<E>> {
+        let start_op = self.io_stats.transactions;
+        let window_ms = u32::from(WINDOW_SAMPLES) * u32::from(WINDOW_SAMPLE_INTERVAL_MS);
+        let interval_ms = u32::from(interval_ms).max(1);
+        let total_ms = u32::from(duration_s) * 1000;
+        let mut fitter = SelfHeatingFitter::new();
+        let mut elapsed_ms = 0u32;
+        let mut last_bias = None;
+        let mut next_ms = 0;
+        while next_ms <= total_ms {
+            let abort = |mpu: &Self, phase, elapsed_ms, last_bias| Mpu6050Error::Aborted {
+                phase,
+                progress: AbortProgress {
+                    elapsed_ms,
+                    transactions: mpu.io_stats.transactions.wrapping_sub(start_op),
+                    samples: fitter.seen,
+                    partial_mean: last_bias,
+                },
+            };
+            while elapsed_ms < next_ms {
+                if deadline() {
+                    return Err(abort(self, PHASE, elapsed_ms, last_bias));
+                }
+                let ms = (next_ms - elapsed_ms).min(u32::from(u8::MAX)) as u8;
+                delay.delay_ms(ms);
+                elapsed_ms += u32::from(ms);
+            }
+            let window_start_ms = elapsed_ms;
+            let (mut gyro, mut acc) = (Accumulator::default(), Accumulator::default());
+            let mut temp_sum = 0.;
+            for i in 0..WINDOW_SAMPLES {
+                if i > 0 {
+                    if deadline() {
+                        return Err(abort(self, PHASE, elapsed_ms, last_bias));
+                    }
+                    delay.delay_ms(WINDOW_SAMPLE_INTERVAL_MS);
+                    elapsed_ms += u32::from(WINDOW_SAMPLE_INTERVAL_MS);
+                }
+                if deadline() {
+                    return Err(abort(self, PHASE, elapsed_ms, last_bias));
+                }
+                let frame = self.get_all_raw()?;
+                gyro.push(scale::gyro_uncorrected(frame.gyro, &self.gyro_scale));
+                acc.push(scale::acc_uncorrected(frame.acc, &self.acc_scale));
+                temp_sum += self.temp_model().celsius(frame.temp);
+            }
+            if gyro.max_std_dev() > MAX_GYRO_STD_DEV || acc.max_std_dev() > MAX_ACC_STD_DEV {
+                return Err(abort(self, MOTION_PHASE, elapsed_ms, last_bias));
+            }
+            let point = SelfHeatingPoint {
+                elapsed_s: (window_start_ms + elapsed_ms) as f32 / 2000.,
+                temp_c: temp_sum / WINDOW_SAMPLES as f32,
+                gyro_bias: gyro.mean(),
+                gyro_std_dev: gyro.max_std_dev(),
+            };
+            last_bias = Some(point.gyro_bias);
+            fitter.push(point);
+            sink(point);
+            next_ms = window_start_ms + interval_ms.max(window_ms);
+        }
+        let summary = fitter.summary();
+        if !summary.cold_start {
+            log_warn!(
+                CALIBRATION,
+                "self heating characterized from a warm die, {} °C at the start",
+                summary.start_temp_c
+            );
+        }
+        Ok(summary)
+    }
+}
@@ -0,0 +1,532 @@
+//! Complete sensor configuration as one value.
+//!
+//! [`Mpu6050Settings`] is const-constructible, so configurations can be shipped as consts (see
+//! [`presets`](crate::presets)) and applied in one call with [`Mpu6050::apply_settings`].
+//! [`Mpu6050Settings::from_config`] goes the other way, from a register snapshot back to
+//! settings, e.g. to adopt a device configured by a bootloader, which
+//! [`Mpu6050::adopt_device_settings`] does for the driver's cached configuration.
+//!
+//! #### Configuration tables
+//! Per-board configuration can live in flash as rodata: settings, their
+//! [`validate`](Mpu6050Settings::validate), the [`AxisMap`](crate::axis_map::AxisMap)s of
+//! the 24 mountings and everything of [`Mpu6050Builder`](crate::Mpu6050Builder) but the
+//! bus and the delay evaluate in const context. The builder keeps offsets and scale factors
+//! as `[x, y, z]` until it builds, its `*_array` setters take them in consts.
+//! ```
+//! use mpu6050::device::{AccelRange, GyroRange};
+//! use mpu6050::settings::Mpu6050Settings;
+//! use mpu6050::Mpu6050Builder;
+//!
+//! struct Bus; // the board's I2C peripheral
+//!
+//! static BOARDS: [Mpu6050Settings; 2] = [
+//!     Mpu6050Settings::new().with_dlpf_cfg(3),
+//!     Mpu6050Settings::new().with_accel_range(AccelRange::G8).with_dlpf_cfg(1),
+//! ];
+//! // rejected at compile time if a table entry is invalid
+//! const _: () = assert!(BOARDS[1].validate().is_ok());
+//!
+//! const REV_B: Mpu6050Builder<Bus> = Mpu6050Builder::new()
+//!     .slave_addr(0x69)
+//!     .gyro_sensitivity(GyroRange::D500)
+//!     .gyro_offset_array([0.01, -0.002, 0.]);
+//! // at runtime: REV_B.i2c(bus).build()
+//! ```
+
+use core::fmt;
+
+#[cfg(feature = "driver")]
+use embedded_hal::blocking::i2c::{Write, WriteRead};
+
+use crate::aliasing;
+use crate::bus;
+use crate::config::Mpu6050Config;
+#[cfg(feature = "driver")]
+use crate::config_epoch::ConfigChange;
+#[cfg(feature = "driver")]
+use crate::config_events::ChangeSource;
+#[cfg(feature = "driver")]
+use crate::continuity::ContinuityBreak;
+use crate::device::*;
+#[cfg(not(feature = "std"))]
+use crate::math::FloatExt;
+use crate::register::{Register, RegisterValue};
+use crate::resolution::ResolutionInfo;
+#[cfg(feature = "driver")]
+use crate::snapshot::SyncPoints;
+use crate::{Mpu6050, Mpu6050Error};
+
+/// Gyro output rate in Hz with the DLPF disabled (DLPF_CFG 0 or 7)
+pub const GYRO_OUTPUT_RATE_DLPF_OFF_HZ: f32 = 8000.;
+/// Gyro output rate in Hz with the DLPF enabled
+pub const GYRO_OUTPUT_RATE_DLPF_ON_HZ: f32 = 1000.;
+
+/// Ranges, filters, rate and power mode of the sensor
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct Mpu6050Settings {
+    /// accelerometer full scale range
+    pub accel_range: AccelRange,
+    /// gyro full scale range
+    pub gyro_range: GyroRange,
+    /// DLPF_CFG value, 0..=6
+    pub dlpf_cfg: u8,
+    /// SMPLRT_DIV value, sample rate = gyro output rate / (1 + div)
+    pub sample_rate_div: u8,
+    /// accelerometer high pass filter
+    pub accel_hpf: ACCEL_HPF,
+    /// clock source
+    pub clock_source: CLKSEL,
+    /// accel-only cycle mode with the given wake frequency, gyros and temperature sensor are
+    /// put in standby. None for normal operation
+    pub cycle: Option<LP_WAKE_CTRL>,
+}
+
+impl Default for Mpu6050Settings {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Mpu6050Settings {
+    /// settings applied by [`Mpu6050::init`]: ±2g, ±250°/s, DLPF off, no divider, PLL with X gyro
+    pub const fn new() -> Self {
+        Self {
+            accel_range: AccelRange::G2,
+            gyro_range: GyroRange::D250,
+            dlpf_cfg: 0,
+            sample_rate_div: 0,
+            accel_hpf: ACCEL_HPF::_RESET,
+            clock_source: CLKSEL::GXAXIS,
+            cycle: None,
+        }
+    }
+
+    /// set accelerometer range
+    pub const fn with_accel_range(mut self, range: AccelRange) -> Self {
+        self.accel_range = range;
+        self
+    }
+
+    /// set gyro range
+    pub const fn with_gyro_range(mut self, range: GyroRange) -> Self {
+        self.gyro_range = range;
+        self
+    }
+
+    /// set DLPF_CFG
+    pub const fn with_dlpf_cfg(mut self, dlpf_cfg: u8) -> Self {
+        self.dlpf_cfg = dlpf_cfg;
+        self
+    }
+
+    /// set SMPLRT_DIV
+    pub const fn with_sample_rate_div(mut self, div: u8) -> Self {
+        self.sample_rate_div = div;
+        self
+    }
+
+    /// set accelerometer high pass filter
+    pub const fn with_accel_hpf(mut self, hpf: ACCEL_HPF) -> Self {
+        self.accel_hpf = hpf;
+        self
+    }
+
+    /// set clock source
+    pub const fn with_clock_source(mut self, source: CLKSEL) -> Self {
+        self.clock_source = source;
+        self
+    }
+
+    /// set cycle mode wake frequency, None for normal operation
+    pub const fn with_cycle(mut self, cycle: Option<LP_WAKE_CTRL>) -> Self {
+        self.cycle = cycle;
+        self
+    }
+
+    /// Checks for combinations the device does not support
+    pub const fn validate(&self) -> Result<(), SettingsError> {
+        if self.dlpf_cfg > 6 {
+            return Err(SettingsError::ReservedDlpfCfg(self.dlpf_cfg));
+        }
+        let gyro_clock = matches!(
+            self.clock_source,
+            CLKSEL::GXAXIS | CLKSEL::GYAXIS | CLKSEL::GZAXIS
+        );
+        if self.cycle.is_some() && gyro_clock {
+            return Err(SettingsError::CycleWithGyroClock);
+        }
+        if matches!(self.clock_source, CLKSEL::RESERV | CLKSEL::STOP) {
+            return Err(SettingsError::UnusableClock(self.clock_source));
+        }
+        Ok(())
+    }
+
+    /// Settings held in a register snapshot, validated. Bits [`apply_settings`](Mpu6050::apply_settings)
+    /// derives from the settings (sensor standby, TEMP_DIS, SLEEP) are not read back
+    pub fn from_config(config: &Mpu6050Config) -> Result<Self, SettingsError> {
+        let accel_config = config
+            .fields::<ACCEL_CONFIG>()
+            .unwrap_or(RegisterValue::ZERO);
+        let gyro_config = config
+            .fields::<GYRO_CONFIG>()
+            .unwrap_or(RegisterValue::ZERO);
+        let config_value = config.fields::<CONFIG>().unwrap_or(RegisterValue::ZERO);
+        let pwr_mgmt_1 = config.fields::<PWR_MGMT_1>().unwrap_or(RegisterValue::ZERO);
+        let pwr_mgmt_2 = config.fields::<PWR_MGMT_2>().unwrap_or(RegisterValue::ZERO);
+        let cycle = pwr_mgmt_1.flag(PWR_MGMT_1::CYCLE_FIELD);
+        let settings = Self {
+            accel_range: AccelRange::from(accel_config.field(ACCEL_CONFIG::FS_SEL_FIELD)),
+            gyro_range: GyroRange::from(gyro_config.field(GYRO_CONFIG::FS_SEL_FIELD)),
+            dlpf_cfg: config_value.field(CONFIG::DLPF_CFG_FIELD),
+            sample_rate_div: config.get(SMPLRT_DIV).unwrap_or(0),
+            accel_hpf: ACCEL_HPF::from(accel_config.field(ACCEL_CONFIG::ACCEL_HPF_FIELD)),
+            clock_source: CLKSEL::from(pwr_mgmt_1.field(PWR_MGMT_1::CLKSEL_FIELD)),
+            cycle: cycle
+                .then(|| LP_WAKE_CTRL::from(pwr_mgmt_2.field(PWR_MGMT_2::LP_WAKE_CTRL_FIELD))),
+        };
+        settings.validate()?;
+        Ok(settings)
+    }
+
+    /// Output data rate in Hz, the wake frequency in cycle mode
+    pub fn sample_rate_hz(&self) -> f32 {
+        if let Some(wake) = self.cycle {
+            return match wake {
+                LP_WAKE_CTRL::_1P25 => 1.25,
+                LP_WAKE_CTRL::_2P5 => 2.5,
+                LP_WAKE_CTRL::_5 => 5.,
+                LP_WAKE_CTRL::_10 => 10.,
+            };
+        }
+        let output_rate = match self.dlpf_cfg {
+            0 | 7 => GYRO_OUTPUT_RATE_DLPF_OFF_HZ,
+            _ => GYRO_OUTPUT_RATE_DLPF_ON_HZ,
+        };
+        output_rate / (1. + self.sample_rate_div as f32)
+    }
+
+    /// resolution, bandwidth, delay and noise of these settings
+    pub fn resolution(&self) -> ResolutionInfo {
+        ResolutionInfo::new(self.accel_range, self.gyro_range, self.dlpf_cfg)
+    }
+}
+
+/// Settings rejected by [`Mpu6050Settings::validate`]
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum SettingsError {
+    /// DLPF_CFG 7 is reserved, values above do not fit the field
+    ReservedDlpfCfg(u8),
+    /// cycle mode puts the gyros in standby, a gyro clock reference stops with them
+    CycleWithGyroClock,
+    /// reserved or stopped clock
+    UnusableClock(CLKSEL),
+    /// range interleaving needs a low range below the high range
+    InterleaveRanges(AccelRange, AccelRange),
+    /// range interleaving period without a usable sample per phase, shortest usable period
+    InterleavePeriod {
+        /// period requested
+        period: u8,
+        /// shortest period leaving a sample after the settle samples
+        min: u8,
+    },
+    /// cycle mode puts the gyros in standby, the clock error is counted in gyro samples
+    ClockMeasurementInCycle,
+    /// clock correction beyond [`MAX_CORRECTION_PPM`](crate::oscillator::MAX_CORRECTION_PPM)
+    ClockCorrection,
+    /// impact trigger not a positive finite magnitude
+    ImpactTrigger,
+    /// impact ranging needs a high range above the low range, low and high
+    ImpactRanges(AccelRange, AccelRange),
+    /// impact hold without a usable sample after the settle samples, shortest usable hold
+    ImpactHold {
+        /// hold requested
+        hold: u16,
+        /// shortest hold leaving a sample after the settle samples
+        min: u16,
+    },
+    /// range interleaving and impact ranging both switch FS_SEL, one at a time
+    RangeModesExclusive,
+    /// no SMPLRT_DIV gives this rate in Hz at the DLPF setting, see
+    /// [`set_sample_rate_hz`](Mpu6050::set_sample_rate_hz)
+    UnreachableSampleRate(u16),
+    /// [`adjust_low_power`](Mpu6050::adjust_low_power) outside cycle mode
+    NotCycling,
+    /// a setting [`adjust_low_power`](Mpu6050::adjust_low_power) cannot change while
+    /// cycling, see [`CYCLE_ADJUSTABILITY`]; leave cycle mode with
+    /// [`apply_settings_diff`](Mpu6050::apply_settings_diff) to change it
+    NotAdjustableInCycle(LowPowerField),
+}
+
+impl fmt::Display for SettingsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SettingsError::ReservedDlpfCfg(cfg) => write!(f, "reserved DLPF_CFG value {}", cfg),
+            SettingsError::CycleWithGyroClock => {
+                f.write_str("cycle mode requires a clock source other than a gyro")
+            }
+            SettingsError::UnusableClock(clk) => write!(f, "unusable clock source {:?}", clk),
+            SettingsError::InterleaveRanges(low, high) => {
+                write!(f, "interleaved range {:?} is not below {:?}", low, high)
+            }
+            SettingsError::InterleavePeriod { period, min } => write!(
+                f,
+                "interleave period {} leaves no settled sample, at least {}",
+                period, min
+            ),
+            SettingsError::ClockMeasurementInCycle => {
+                f.write_str("clock error estimation needs the gyros, not cycle mode")
+            }
+            SettingsError::ClockCorrection => write!(
+                f,
+                "clock correction beyond {} ppm",
+                crate::oscillator::MAX_CORRECTION_PPM
+            ),
+            SettingsError::ImpactTrigger => {
+                f.write_str("impact trigger is not a positive magnitude")
+            }
+            SettingsError::ImpactRanges(low, high) => {
+                write!(f, "impact range {:?} is not above {:?}", high, low)
+            }
+            SettingsError::ImpactHold { hold, min } => write!(
+                f,
+                "impact hold {} leaves no settled sample, at least {}",
+                hold, min
+            ),
+            SettingsError::RangeModesExclusive => {
+                f.write_str("range interleaving and impact ranging exclude each other")
+            }
+            SettingsError::UnreachableSampleRate(hz) => {
+                write!(f, "sample rate {} Hz unreachable at this DLPF setting", hz)
+            }
+            SettingsError::NotCycling => f.write_str("not in cycle mode"),
+            SettingsError::NotAdjustableInCycle(field) => write!(
+                f,
+                "{:?} cannot change in cycle mode, leave it to change",
+                field
+            ),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for SettingsError {}
+
+#[cfg(feature = "driver")]
+impl<I, D, E> Mpu6050<I, D>
+where
+    I: Write<Error = E> + WriteRead<Error = E>,
+{
+    /// Reads the configuration registers back into settings, see [`Mpu6050Settings::from_config`]
+    pub fn read_settings(&mut self) -> Result<Mpu6050Settings, Mpu6050Error<E>> {
+        let config = self.read_config()?;
+        Mpu6050Settings::from_config(&config).map_err(Mpu6050Error::InvalidSettings)
+    }
+
+    /// Reads the configuration registers back and takes the ranges, DLPF_CFG, SMPLRT_DIV
+    /// and cycle mode as the driver's configuration without writing anything, e.g. after a
+    /// bootloader configured the device. Advances the [`config_epoch`](crate::config_epoch)
+    /// like [`apply_settings`](Self::apply_settings), the changes are reported as
+    /// [`Adopt`](crate::config_events::ChangeSource::Adopt). Returns the settings read
+    pub fn adopt_device_settings(&mut self) -> Result<Mpu6050Settings, Mpu6050Error<E>> {
+        let settings = self.read_settings()?;
+        self.with_change_source(ChangeSource::Adopt, |mpu| {
+            let op = Some(mpu.io_stats.transactions);
+            mpu.accel_range = settings.accel_range;
+            mpu.acc_scale.nominal = settings.accel_range.sensitivity();
+            mpu.bump_epoch(ConfigChange::AccelRange);
+            mpu.gyro_range = settings.gyro_range;
+            mpu.gyro_scale.nominal = settings.gyro_range.sensitivity();
+            mpu.bump_epoch(ConfigChange::GyroRange);
+            mpu.dlpf_cfg = settings.dlpf_cfg;
+            mpu.bump_epoch(ConfigChange::Dlpf);
+            mpu.sample_rate_div = settings.sample_rate_div;
+            mpu.bump_epoch(ConfigChange::SampleRateDivider);
+            mpu.cycle = settings.cycle;
+            mpu.synced = SyncPoints {
+                accel_range: op,
+                gyro_range: op,
+                dlpf_cfg: op,
+                sample_rate_div: op,
+            };
+        });
+        Ok(settings)
+    }
+
+    /// Validates and writes `settings`. The sensor is woken up; in cycle mode the gyros and the
+    /// temperature sensor are put in standby, otherwise all sensors are enabled. In strict
+    /// configuration settings likely to alias are refused, see [`aliasing`]
+    pub fn apply_settings(&mut self, settings: &Mpu6050Settings) -> Result<(), Mpu6050Error<E>> {
+        settings.validate().map_err(Mpu6050Error::InvalidSettings)?;
+        self.refuse_aliasing(settings.check_aliasing())?;
+
+        self.set_clock_source(settings.clock_source)?;
+        self.set_accel_range(settings.accel_range)?;
+        self.set_gyro_range(settings.gyro_range)?;
+        self.set_accel_hpf(settings.accel_hpf)?;
+        self.write_dlpf_cfg(settings.dlpf_cfg)?;
+        self.write_sample_rate_div(settings.sample_rate_div)?;
+
+        let was_cycling = self.read_flag(PWR_MGMT_1::CYCLE_FIELD)?;
+        self.write_power_mode(settings.cycle, was_cycling)?;
+        self.set_sleep_enabled(false)
+    }
+
+    /// Writes only what differs between `current`, the settings in effect, and `target`, in
+    /// the order of [`apply_settings`](Self::apply_settings). Same validation, the sensor
+    /// is expected awake. Switching between operating points this way leaves unchanged
+    /// registers and their settling alone, see [`governor`](crate::governor)
+    pub fn apply_settings_diff(
+        &mut self,
+        current: &Mpu6050Settings,
+        target: &Mpu6050Settings,
+    ) -> Result<(), Mpu6050Error<E>> {
+        target.validate().map_err(Mpu6050Error::InvalidSettings)?;
+        self.refuse_aliasing(target.check_aliasing())?;
+
+        if current.clock_source != target.clock_source {
+            self.set_clock_source(target.clock_source)?;
+        }
+        if current.accel_range != target.accel_range {
+            self.set_accel_range(target.accel_range)?;
+        }
+        if current.gyro_range != target.gyro_range {
+            self.set_gyro_range(target.gyro_range)?;
+        }
+        if current.accel_hpf != target.accel_hpf {
+            self.set_accel_hpf(target.accel_hpf)?;
+        }
+        if current.dlpf_cfg != target.dlpf_cfg {
+            self.write_dlpf_cfg(target.dlpf_cfg)?;
+        }
+        if current.sample_rate_div != target.sample_rate_div {
+            self.write_sample_rate_div(target.sample_rate_div)?;
+        }
+        if current.cycle != target.cycle {
+            self.write_power_mode(target.cycle, current.cycle.is_some())?;
+        }
+        Ok(())
+    }
+
+    /// PWR_MGMT_2 standby and wake frequency, TEMP_DIS and CYCLE for `cycle`
+    pub(crate) fn write_power_mode(
+        &mut self,
+        cycle: Option<LP_WAKE_CTRL>,
+        was_cycling: bool,
+    ) -> Result<(), Mpu6050Error<E>> {
+        self.cycle = cycle;
+        let mut pwr_mgmt_2 = RegisterValue::<PWR_MGMT_2>::ZERO;
+        if let Some(wake) = cycle {
+            pwr_mgmt_2 = pwr_mgmt_2.with(PWR_MGMT_2::LP_WAKE_CTRL_FIELD, wake);
+            for field in [
+                PWR_MGMT_2::STBY_XG_FIELD,
+                PWR_MGMT_2::STBY_YG_FIELD,
+                PWR_MGMT_2::STBY_ZG_FIELD,
+            ] {
+                pwr_mgmt_2 = pwr_mgmt_2.with(field, true);
+            }
+        }
+        let cycling = cycle.is_some();
+        self.write_fields(pwr_mgmt_2)?;
+        self.write_field(PWR_MGMT_1::TEMP_DIS_FIELD, cycling)?;
+        self.power.temp_enabled = !cycling;
+        self.write_field(PWR_MGMT_1::CYCLE_FIELD, cycling)?;
+        self.note_cycle_transition(was_cycling, cycling);
+        Ok(())
+    }
+
+    /// settling and continuity of a CYCLE change from `was_cycling`
+    pub(crate) fn note_cycle_transition(&mut self, was_cycling: bool, cycling: bool) {
+        if was_cycling && !cycling {
+            self.settle.trigger(SettleTrigger::CycleExit);
+        }
+        if was_cycling != cycling {
+            self.break_continuity(match cycling {
+                true => ContinuityBreak::CycleEntered,
+                false => ContinuityBreak::CycleLeft,
+            });
+        }
+    }
+
+    /// set DLPF_CFG, 0..=6, keeping the sample rate divider. In strict configuration a
+    /// combination likely to alias is refused, see [`aliasing`]. Change both with
+    /// [`apply_settings`](Self::apply_settings) to avoid passing through such a combination
+    pub fn set_dlpf(&mut self, dlpf_cfg: u8) -> Result<(), Mpu6050Error<E>> {
+        if dlpf_cfg > 6 {
+            return Err(Mpu6050Error::InvalidSettings(
+                SettingsError::ReservedDlpfCfg(dlpf_cfg),
+            ));
+        }
+        self.refuse_aliasing(aliasing::assess(
+            dlpf_cfg,
+            self.sample_rate_div,
+            self.cycle.is_some(),
+        ))?;
+        self.write_dlpf_cfg(dlpf_cfg)
+    }
+
+    /// set SMPLRT_DIV, keeping the DLPF. In strict configuration a combination likely to
+    /// alias is refused, see [`aliasing`]. With [`Mpu6050Builder::bus_hz`](crate::Mpu6050Builder::bus_hz) a rate the bus
+    /// cannot sustain is refused as well, see [`bus`](crate::bus)
+    pub fn set_sample_rate_divider(&mut self, div: u8) -> Result<(), Mpu6050Error<E>> {
+        self.refuse_aliasing(aliasing::assess(self.dlpf_cfg, div, self.cycle.is_some()))?;
+        if let Some(bus_hz) = self.bus_hz {
+            bus::check_rate(bus_hz, self.dlpf_cfg, div).map_err(Mpu6050Error::BusRateTooHigh)?;
+        }
+        self.write_sample_rate_div(div)
+    }
+
+    /// get DLPF_CFG as a bandwidth
+    pub fn get_dlpf(&mut self) -> Result<DLPF, Mpu6050Error<E>> {
+        let dlpf_cfg: u8 = self.read_field(CONFIG::DLPF_CFG_FIELD)?;
+
+        Ok(DLPF::from(dlpf_cfg))
+    }
+
+    /// set the DLPF bandwidth, see [`set_dlpf`](Self::set_dlpf)
+    pub fn set_dlpf_bandwidth(&mut self, bandwidth: DLPF) -> Result<(), Mpu6050Error<E>> {
+        self.set_dlpf(bandwidth as u8)
+    }
+
+    /// get SMPLRT_DIV, the output data rate being the gyro output rate / (1 + divider)
+    pub fn get_sample_rate_divider(&mut self) -> Result<u8, Mpu6050Error<E>> {
+        self.read_register(Register::SMPLRT_DIV)
+    }
+
+    /// Sets the divider closest to `hz` from the gyro output rate of the DLPF setting,
+    /// [`GYRO_OUTPUT_RATE_DLPF_OFF_HZ`] or [`GYRO_OUTPUT_RATE_DLPF_ON_HZ`], and returns the
+    /// rate achieved. Rates no divider reaches, 0 and above the output rate or below 1/256 of
+    /// it, are [`SettingsError::UnreachableSampleRate`]; so set the DLPF first. Same strict
+    /// configuration and bus checks as [`set_sample_rate_divider`](Self::set_sample_rate_divider).
+    /// In cycle mode the rate returned takes effect when cycle mode is left
+    pub fn set_sample_rate_hz(&mut self, hz: u16) -> Result<f32, Mpu6050Error<E>> {
+        let output_rate = match self.dlpf_cfg {
+            0 | 7 => GYRO_OUTPUT_RATE_DLPF_OFF_HZ,
+            _ => GYRO_OUTPUT_RATE_DLPF_ON_HZ,
+        };
+        let div = (output_rate / f32::from(hz)).round() - 1.;
+        if hz == 0 || f32::from(hz) > output_rate || div > 255. {
+            return Err(Mpu6050Error::InvalidSettings(
+                SettingsError::UnreachableSampleRate(hz),
+            ));
+        }
+        self.set_sample_rate_divider(div as u8)?;
+        Ok(output_rate / (1. + div))
+    }
+
+    pub(crate) fn write_dlpf_cfg(&mut self, dlpf_cfg: u8) -> Result<(), Mpu6050Error<E>> {
+        self.write_field(CONFIG::DLPF_CFG_FIELD, dlpf_cfg)?;
+        self.dlpf_cfg = dlpf_cfg;
+        self.bump_epoch(ConfigChange::Dlpf);
+        self.synced.dlpf_cfg = Some(self.io_stats.transactions);
+        self.settle.trigger(SettleTrigger::Dlpf);
+        Ok(())
+    }
+
+    pub(crate) fn write_sample_rate_div(&mut self, div: u8) -> Result<(), Mpu6050Error<E>> {
+        self.write_register(Register::SMPLRT_DIV, div)?;
+        self.sample_rate_div = div;
+        self.bump_epoch(ConfigChange::SampleRateDivider);
+        self.synced.sample_rate_div = Some(self.io_stats.transactions);
+        Ok(())
+    }
+}
@@ -0,0 +1,75 @@
+//! Discarding or flagging of samples taken while the signal path settles.
+//!
+//! Operations disturbing the signal path (see [`SettleTrigger`]) arm a per-sensor countdown of
+//! [`SETTLE_SAMPLES`](crate::device::SETTLE_SAMPLES). Every accel or gyro read of the driver
+//! counts one sample down; what happens to those samples is the [`SettlingPolicy`]. The
+//! countdown counts reads, not sensor samples: reading faster than the sample rate returns
+//! the same sample again, so pair `Discard` with reads paced at the sample rate.
+//!
+//! Steady state reads cost nothing extra: with both countdowns at 0 no additional
+//! transaction is made under any policy.
+
+use crate::device::SettleTrigger;
+
+/// [`Mpu6050Error::Aborted`](crate::Mpu6050Error::Aborted) phase of an expired deadline in
+/// [`wait_settled_with_deadline`](crate::Mpu6050::wait_settled_with_deadline)
+pub const PHASE: &str = "Settling";
+
+/// What scaled reads do with samples taken while settling
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum SettlingPolicy {
+    /// no special handling, countdowns still run
+    Ignore,
+    /// read again, up to `max_retries` additional reads per call; the blocking default
+    Discard {
+        /// additional reads per call
+        max_retries: u8,
+    },
+    /// return the sample, flagged `settling` where the result carries flags ([`MpuSample`](crate::MpuSample))
+    Flag,
+}
+
+impl Default for SettlingPolicy {
+    fn default() -> Self {
+        SettlingPolicy::Discard { max_retries: 3 }
+    }
+}
+
+/// Remaining settling samples per sensor, no bus access
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub struct SettleCountdown {
+    /// accel samples until valid
+    pub acc: u8,
+    /// gyro samples until valid
+    pub gyro: u8,
+}
+
+impl SettleCountdown {
+    /// arm the countdowns for `trigger`, never shortening a running countdown
+    pub fn trigger(&mut self, trigger: SettleTrigger) {
+        let (acc, gyro) = trigger.samples();
+        self.acc = self.acc.max(acc);
+        self.gyro = self.gyro.max(gyro);
+    }
+
+    /// count an accel sample, true if it was taken while settling
+    pub fn consume_acc(&mut self) -> bool {
+        consume(&mut self.acc)
+    }
+
+    /// count a gyro sample, true if it was taken while settling
+    pub fn consume_gyro(&mut self) -> bool {
+        consume(&mut self.gyro)
+    }
+
+    /// true if either sensor is settling
+    pub fn is_settling(&self) -> bool {
+        self.acc > 0 || self.gyro > 0
+    }
+}
+
+fn consume(remaining: &mut u8) -> bool {
+    let settling = *remaining > 0;
+    *remaining = remaining.saturating_sub(1);
+    settling
+}
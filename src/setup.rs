@@ -0,0 +1,769 @@
+//! Guided startup: init, thermal settling, actuation check, calibration and final configuration
+//! in one call.
+//!
+//! [`Mpu6050::auto_setup`] runs the phases in the order of [`AutoSetupPhase`]. Every phase that
+//! ran is recorded in the [`AutoSetupReport`], so on failure the caller knows exactly how far
+//! the device got. The state left behind on failure is:
+//! * failure in `Init`: device state unknown, driver offsets untouched
+//! * failure in any later phase: device awake, initialized, ranges from the options applied,
+//!   offsets of completed calibration phases applied, accel self-test bits cleared if the bus allows
+//!
+//! [`Mpu6050::auto_setup_with_deadline`] gives up at a caller supplied deadline, see
+//! [`deadline`](crate::deadline), and [`Mpu6050::auto_setup_resume`] continues from the
+//! report of the error.
+
+use core::fmt::{self, Debug, Display};
+
+#[cfg(feature = "driver")]
+use crate::cooperative::DrainBudget;
+use crate::cooperative::Pacer;
+use crate::deadline::{self, AbortProgress};
+use crate::decimal::{Decimal, DecimalVec};
+use crate::device::*;
+use crate::log_header::CalibrationMethod;
+#[cfg(not(feature = "std"))]
+use crate::math::FloatExt;
+use crate::op_bounds::IoStats;
+#[cfg(feature = "driver")]
+use crate::platform::ReferencedCalibration;
+use crate::validity::{CalibrationKind, CalibrationMeta};
+use crate::{Mpu6050, Mpu6050Error};
+#[cfg(feature = "driver")]
+use embedded_hal::{
+    blocking::delay::DelayMs,
+    blocking::i2c::{Write, WriteRead},
+};
+use glam::Vec3A;
+
+/// Phases of [`Mpu6050::auto_setup`], in execution order
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum AutoSetupPhase {
+    /// Wake and verify the device, apply the requested ranges
+    Init = 0,
+    /// Wait until the die temperature stops changing
+    ThermalWait,
+    /// Toggle the accel self-test bits and check the sensor responds
+    ActuationCheck,
+    /// Average gyro readings to determine the zero rate offset
+    GyroCalibration,
+    /// Average accel readings against 1g on Z to determine the accel offset
+    AccelCalibration,
+    /// Apply the final configuration (ranges, HPF)
+    ApplyConfiguration,
+}
+
+impl AutoSetupPhase {
+    /// Number of phases
+    pub const COUNT: usize = 6;
+
+    /// All phases in execution order
+    pub const ALL: [AutoSetupPhase; Self::COUNT] = [
+        AutoSetupPhase::Init,
+        AutoSetupPhase::ThermalWait,
+        AutoSetupPhase::ActuationCheck,
+        AutoSetupPhase::GyroCalibration,
+        AutoSetupPhase::AccelCalibration,
+        AutoSetupPhase::ApplyConfiguration,
+    ];
+
+    /// name of the phase, as reported in [`Mpu6050Error::Aborted`]
+    pub const fn name(self) -> &'static str {
+        match self {
+            AutoSetupPhase::Init => "Init",
+            AutoSetupPhase::ThermalWait => "ThermalWait",
+            AutoSetupPhase::ActuationCheck => "ActuationCheck",
+            AutoSetupPhase::GyroCalibration => "GyroCalibration",
+            AutoSetupPhase::AccelCalibration => "AccelCalibration",
+            AutoSetupPhase::ApplyConfiguration => "ApplyConfiguration",
+        }
+    }
+}
+
+/// Thermal settling options: wait until consecutive temperature readings agree
+#[derive(Copy, Clone, Debug)]
+pub struct ThermalWait {
+    /// Maximum time to wait in ms. Running out of budget is reported, not treated as an error
+    pub max_ms: u32,
+    /// Time between temperature readings in ms
+    pub interval_ms: u8,
+    /// Maximum change between two readings in degrees celsius still considered stable
+    pub tolerance_c: f32,
+    /// Number of consecutive stable readings required
+    pub stable_readings: u8,
+}
+
+impl Default for ThermalWait {
+    fn default() -> Self {
+        Self {
+            max_ms: 30_000,
+            interval_ms: 250,
+            tolerance_c: 0.05,
+            stable_readings: 8,
+        }
+    }
+}
+
+/// Options for [`Mpu6050::auto_setup`]
+#[derive(Copy, Clone, Debug)]
+pub struct AutoSetupOptions {
+    /// Accelerometer range applied during init and in the final configuration
+    pub accel_range: AccelRange,
+    /// Gyro range applied during init and in the final configuration
+    pub gyro_range: GyroRange,
+    /// Accel HPF applied in the final configuration
+    pub accel_hpf: ACCEL_HPF,
+    /// Wait for thermal stability before calibrating, skipped if None
+    pub thermal_wait: Option<ThermalWait>,
+    /// Run the quick accel self-test actuation check
+    pub actuation_check: bool,
+    /// Minimum self-test response per axis in g for the actuation check to pass
+    pub min_actuation_g: f32,
+    /// Number of samples averaged for the gyro calibration
+    pub gyro_samples: u16,
+    /// Maximum standard deviation of the gyro samples in rad/s, larger means the device moved
+    pub max_gyro_std_dev: f32,
+    /// Caller asserts the device is lying level (Z up). Accel calibration only runs if set
+    pub device_is_level: bool,
+    /// Number of samples averaged for the accel calibration
+    pub acc_samples: u16,
+    /// Maximum standard deviation of the accel samples in g
+    pub max_acc_std_dev: f32,
+    /// Time between calibration samples in ms
+    pub sample_interval_ms: u8,
+}
+
+impl Default for AutoSetupOptions {
+    fn default() -> Self {
+        Self {
+            accel_range: AccelRange::G2,
+            gyro_range: GyroRange::D250,
+            accel_hpf: ACCEL_HPF::_RESET,
+            thermal_wait: None,
+            actuation_check: true,
+            min_actuation_g: 0.1,
+            gyro_samples: 500,
+            max_gyro_std_dev: 0.01,
+            device_is_level: false,
+            acc_samples: 500,
+            max_acc_std_dev: 0.02,
+            sample_interval_ms: 2,
+        }
+    }
+}
+
+/// Outcome of a single phase
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum PhaseStatus {
+    /// Phase has not run (yet)
+    NotRun,
+    /// Phase was disabled by the options
+    Skipped,
+    /// Phase completed
+    Completed,
+    /// Phase ran out of its time budget, setup continued (thermal wait only)
+    TimedOut,
+    /// Phase failed, see [`AutoSetupError`]
+    Failed,
+    /// The caller's deadline expired during the phase, see [`Mpu6050::auto_setup_resume`]
+    Aborted,
+}
+
+/// Status and duration of a single phase
+#[derive(Copy, Clone, Debug)]
+pub struct PhaseOutcome {
+    /// What happened
+    pub status: PhaseStatus,
+    /// Time spent waiting in this phase in ms (delays only, bus time is not measured)
+    pub duration_ms: u32,
+}
+
+/// Result of [`Mpu6050::auto_setup`]
+#[derive(Copy, Clone, Debug)]
+pub struct AutoSetupReport {
+    /// Outcome per phase, indexed by `AutoSetupPhase as usize`
+    pub phases: [PhaseOutcome; AutoSetupPhase::COUNT],
+    /// Gyro offset in effect after setup
+    pub gyro_offset: Vec3A,
+    /// Accel offset in effect after setup
+    pub acc_offset: Vec3A,
+    /// Standard deviation of the gyro calibration samples, if it ran
+    pub gyro_std_dev: Option<f32>,
+    /// Standard deviation of the accel calibration samples, if it ran
+    pub acc_std_dev: Option<f32>,
+    /// Temperature at the end of the thermal wait, if it ran
+    pub temperature: Option<f32>,
+    /// samples of an aborted calibration phase, picked up by a resume
+    partial: Option<Accumulator>,
+}
+
+impl AutoSetupReport {
+    fn new(gyro_offset: Vec3A, acc_offset: Vec3A) -> Self {
+        Self {
+            phases: [PhaseOutcome {
+                status: PhaseStatus::NotRun,
+                duration_ms: 0,
+            }; AutoSetupPhase::COUNT],
+            gyro_offset,
+            acc_offset,
+            gyro_std_dev: None,
+            acc_std_dev: None,
+            temperature: None,
+            partial: None,
+        }
+    }
+
+    /// Outcome of the given phase
+    pub fn phase(&self, phase: AutoSetupPhase) -> PhaseOutcome {
+        self.phases[phase as usize]
+    }
+
+    /// Total time spent waiting in ms
+    pub fn total_duration_ms(&self) -> u32 {
+        self.phases.iter().map(|p| p.duration_ms).sum()
+    }
+
+    fn set(&mut self, phase: AutoSetupPhase, status: PhaseStatus, duration_ms: u32) {
+        self.phases[phase as usize] = PhaseOutcome {
+            status,
+            duration_ms,
+        };
+    }
+}
+
+/// Reason a phase of [`Mpu6050::auto_setup`] failed
+#[derive(Debug)]
+pub enum AutoSetupFailure<E> {
+    /// Bus or chip id error
+    Device(Mpu6050Error<E>),
+    /// Self-test response below `min_actuation_g` on at least one axis
+    ActuationCheck {
+        /// measured self-test response in g
+        response: Vec3A,
+    },
+    /// Samples varied too much, device was probably moving
+    CalibrationRejected {
+        /// largest per-axis standard deviation measured
+        std_dev: f32,
+        /// allowed maximum
+        limit: f32,
+    },
+}
+
+/// Error of [`Mpu6050::auto_setup`], with the phase it happened in and what completed before
+#[derive(Debug)]
+pub struct AutoSetupError<E> {
+    /// Phase that failed
+    pub phase: AutoSetupPhase,
+    /// Outcome of all phases up to and including the failed one
+    pub report: AutoSetupReport,
+    /// What went wrong
+    pub failure: AutoSetupFailure<E>,
+}
+
+impl<E: Display> Display for AutoSetupError<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "auto setup failed in phase {:?}: ", self.phase)?;
+        match &self.failure {
+            AutoSetupFailure::Device(error) => write!(f, "{}", error),
+            AutoSetupFailure::ActuationCheck { response } => {
+                write!(
+                    f,
+                    "self-test response too small: {} g",
+                    DecimalVec(*response)
+                )
+            }
+            AutoSetupFailure::CalibrationRejected { std_dev, limit } => write!(
+                f,
+                "calibration rejected, std dev {} exceeds {}",
+                Decimal(*std_dev),
+                Decimal(*limit)
+            ),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<E: Debug + Display> std::error::Error for AutoSetupError<E> {}
+
+/// Running mean and variance per axis (Welford)
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub(crate) struct Accumulator {
+    count: u32,
+    mean: Vec3A,
+    m2: Vec3A,
+}
+
+impl Accumulator {
+    pub(crate) fn push(&mut self, value: Vec3A) {
+        self.count += 1;
+        let delta = value - self.mean;
+        self.mean += delta / self.count as f32;
+        self.m2 += delta * (value - self.mean);
+    }
+
+    pub(crate) fn count(&self) -> u32 {
+        self.count
+    }
+
+    pub(crate) fn mean(&self) -> Vec3A {
+        self.mean
+    }
+
+    /// Largest per-axis standard deviation
+    pub(crate) fn max_std_dev(&self) -> f32 {
+        if self.count < 2 {
+            return 0.0;
+        }
+        (self.m2 / (self.count - 1) as f32).max_element().sqrt()
+    }
+}
+
+/// Keeps track of the phase being run, the time spent in it and the caller's deadline
+pub(crate) struct Tracker<'a, 'p, 'd> {
+    report: AutoSetupReport,
+    phase: AutoSetupPhase,
+    elapsed_ms: u32,
+    progress: Option<&'a mut (dyn FnMut(AutoSetupPhase) + 'p)>,
+    deadline: &'d mut dyn FnMut() -> bool,
+    /// operation counter at the start of the setup
+    start_op: u32,
+    /// delay time of the whole setup
+    total_ms: u32,
+    /// yields of a cooperative setup
+    pacer: Option<Pacer<'d>>,
+}
+
+impl<'a, 'p, 'd> Tracker<'a, 'p, 'd> {
+    /// tracker for a single calibration run outside of auto setup
+    pub(crate) fn standalone(
+        phase: AutoSetupPhase,
+        deadline: &'d mut dyn FnMut() -> bool,
+        start_op: u32,
+    ) -> Self {
+        Self {
+            report: AutoSetupReport::new(Vec3A::ZERO, Vec3A::ZERO),
+            phase,
+            elapsed_ms: 0,
+            progress: None,
+            deadline,
+            start_op,
+            total_ms: 0,
+            pacer: None,
+        }
+    }
+
+    fn begin(&mut self, phase: AutoSetupPhase) {
+        self.phase = phase;
+        self.elapsed_ms = 0;
+        if let Some(progress) = self.progress.as_mut() {
+            progress(phase);
+        }
+    }
+
+    /// true if `phase` completed or was skipped in the report resumed from
+    fn done(&self, phase: AutoSetupPhase) -> bool {
+        matches!(
+            self.report.phase(phase).status,
+            PhaseStatus::Completed | PhaseStatus::Skipped | PhaseStatus::TimedOut
+        )
+    }
+
+    /// true if `phase` was aborted in the report resumed from
+    fn aborted(&self, phase: AutoSetupPhase) -> bool {
+        self.report.phase(phase).status == PhaseStatus::Aborted
+    }
+
+    /// Aborted error if the deadline expired, `partial` are the samples of the phase so far
+    fn check<E>(&mut self, op: u32, partial: Option<&Accumulator>) -> Result<(), Mpu6050Error<E>> {
+        if !(self.deadline)() {
+            return Ok(());
+        }
+        self.report.partial = partial.copied().filter(|acc| acc.count() > 0);
+        Err(Mpu6050Error::Aborted {
+            phase: self.phase.name(),
+            progress: AbortProgress {
+                elapsed_ms: self.total_ms,
+                transactions: op.wrapping_sub(self.start_op),
+                samples: partial.map_or(0, Accumulator::count),
+                partial_mean: self.report.partial.map(|acc| acc.mean()),
+            },
+        })
+    }
+
+    /// yield point of a cooperative setup, after the delay following a reading
+    fn pace(&mut self, now: IoStats) {
+        if let Some(pacer) = self.pacer.as_mut() {
+            pacer.pace(now);
+        }
+    }
+
+    #[cfg(feature = "driver")]
+    fn wait<D: DelayMs<u8>>(&mut self, delay: &mut D, ms: u8) {
+        delay.delay_ms(ms);
+        self.elapsed_ms += ms as u32;
+        self.total_ms += ms as u32;
+    }
+
+    fn finish(&mut self, status: PhaseStatus) {
+        self.report.set(self.phase, status, self.elapsed_ms);
+    }
+
+    fn fail<E>(&mut self, failure: AutoSetupFailure<E>) -> AutoSetupError<E> {
+        let status = match failure {
+            AutoSetupFailure::Device(Mpu6050Error::Aborted { .. }) => PhaseStatus::Aborted,
+            _ => PhaseStatus::Failed,
+        };
+        self.finish(status);
+        AutoSetupError {
+            phase: self.phase,
+            report: self.report,
+            failure,
+        }
+    }
+}
+
+#[cfg(feature = "driver")]
+impl<I, D, E> Mpu6050<I, D>
+where
+    I: Write<Error = E> + WriteRead<Error = E>,
+{
+    // the error carries the full report on purpose, setup runs once at boot
+    #[allow(clippy::result_large_err)]
+    pub(crate) fn auto_setup_using(
+        &mut self,
+        delay: &mut impl DelayMs<u8>,
+        options: AutoSetupOptions,
+        progress: Option<&mut dyn FnMut(AutoSetupPhase)>,
+    ) -> Result<AutoSetupReport, AutoSetupError<E>> {
+        self.auto_setup_with_deadline_using(delay, options, progress, deadline::never)
+    }
+
+    #[allow(clippy::result_large_err)]
+    pub(crate) fn auto_setup_with_deadline_using(
+        &mut self,
+        delay: &mut impl DelayMs<u8>,
+        options: AutoSetupOptions,
+        progress: Option<&mut dyn FnMut(AutoSetupPhase)>,
+        mut deadline: impl FnMut() -> bool,
+    ) -> Result<AutoSetupReport, AutoSetupError<E>> {
+        let report = AutoSetupReport::new(self.gyro_offset, self.acc_offset);
+        self.run_auto_setup(delay, options, progress, &mut deadline, None, report)
+    }
+
+    #[allow(clippy::result_large_err)]
+    pub(crate) fn auto_setup_cooperative_using(
+        &mut self,
+        delay: &mut impl DelayMs<u8>,
+        options: AutoSetupOptions,
+        progress: Option<&mut dyn FnMut(AutoSetupPhase)>,
+        budget: DrainBudget,
+        mut yield_fn: impl FnMut(),
+    ) -> Result<AutoSetupReport, AutoSetupError<E>> {
+        let report = AutoSetupReport::new(self.gyro_offset, self.acc_offset);
+        let mut never = deadline::never;
+        let pacer = Pacer::new(budget, self.io_stats, &mut yield_fn);
+        self.run_auto_setup(delay, options, progress, &mut never, Some(pacer), report)
+    }
+
+    /// [`auto_setup_cooperative`](Self::auto_setup_cooperative) giving up at `deadline`, for
+    /// the streamed calibration of the [`remote`](crate::remote) server
+    #[cfg(feature = "remote")]
+    #[allow(clippy::result_large_err)]
+    pub(crate) fn auto_setup_streamed_using(
+        &mut self,
+        delay: &mut impl DelayMs<u8>,
+        options: AutoSetupOptions,
+        progress: Option<&mut dyn FnMut(AutoSetupPhase)>,
+        mut deadline: impl FnMut() -> bool,
+        budget: DrainBudget,
+        mut yield_fn: impl FnMut(),
+    ) -> Result<AutoSetupReport, AutoSetupError<E>> {
+        let report = AutoSetupReport::new(self.gyro_offset, self.acc_offset);
+        let pacer = Pacer::new(budget, self.io_stats, &mut yield_fn);
+        self.run_auto_setup(delay, options, progress, &mut deadline, Some(pacer), report)
+    }
+
+    /// self-test response of the actuation check alone, the self-test bits cleared again if
+    /// the bus allows
+    #[cfg(feature = "remote")]
+    pub(crate) fn actuation_response_using(
+        &mut self,
+        delay: &mut impl DelayMs<u8>,
+    ) -> Result<Vec3A, Mpu6050Error<E>> {
+        let mut never = deadline::never;
+        let start = self.io_stats.transactions;
+        let mut t = Tracker::standalone(AutoSetupPhase::ActuationCheck, &mut never, start);
+        let response = self.accel_actuation_response(delay, &mut t);
+        if response.is_err() {
+            let _ = self.set_accel_self_test_all(false);
+        }
+        response
+    }
+
+    #[allow(clippy::result_large_err)]
+    pub(crate) fn auto_setup_resume_using(
+        &mut self,
+        delay: &mut impl DelayMs<u8>,
+        options: AutoSetupOptions,
+        previous: &AutoSetupReport,
+        progress: Option<&mut dyn FnMut(AutoSetupPhase)>,
+        mut deadline: impl FnMut() -> bool,
+    ) -> Result<AutoSetupReport, AutoSetupError<E>> {
+        self.run_auto_setup(delay, options, progress, &mut deadline, None, *previous)
+    }
+
+    #[allow(clippy::result_large_err)]
+    fn run_auto_setup<'d>(
+        &mut self,
+        delay: &mut impl DelayMs<u8>,
+        options: AutoSetupOptions,
+        progress: Option<&mut dyn FnMut(AutoSetupPhase)>,
+        deadline: &'d mut dyn FnMut() -> bool,
+        pacer: Option<Pacer<'d>>,
+        report: AutoSetupReport,
+    ) -> Result<AutoSetupReport, AutoSetupError<E>> {
+        let mut t = Tracker {
+            report,
+            phase: AutoSetupPhase::Init,
+            elapsed_ms: 0,
+            progress,
+            deadline,
+            start_op: self.io_stats.transactions,
+            total_ms: 0,
+            pacer,
+        };
+        let partial = t.report.partial.take();
+
+        // init
+        if !t.done(AutoSetupPhase::Init) {
+            t.begin(AutoSetupPhase::Init);
+            let res = t.check(self.io_stats.transactions, None).and_then(|_| {
+                self.init_using(delay)?;
+                self.set_accel_range(options.accel_range)?;
+                self.set_gyro_range(options.gyro_range)
+            });
+            if let Err(error) = res {
+                return Err(t.fail(AutoSetupFailure::Device(error)));
+            }
+            // wake delay of init
+            t.elapsed_ms += 100;
+            t.total_ms += 100;
+            t.finish(PhaseStatus::Completed);
+        }
+
+        // thermal wait
+        if !t.done(AutoSetupPhase::ThermalWait) {
+            t.begin(AutoSetupPhase::ThermalWait);
+            match options.thermal_wait {
+                None => t.finish(PhaseStatus::Skipped),
+                Some(wait) => match self.wait_thermal_stable(delay, &mut t, wait) {
+                    Ok(status) => t.finish(status),
+                    Err(error) => return Err(t.fail(AutoSetupFailure::Device(error))),
+                },
+            }
+        }
+
+        // actuation check
+        if !t.done(AutoSetupPhase::ActuationCheck) {
+            t.begin(AutoSetupPhase::ActuationCheck);
+            if options.actuation_check {
+                match self.accel_actuation_response(delay, &mut t) {
+                    Ok(response) if response.min_element() >= options.min_actuation_g => {
+                        t.finish(PhaseStatus::Completed)
+                    }
+                    Ok(response) => {
+                        return Err(t.fail(AutoSetupFailure::ActuationCheck { response }))
+                    }
+                    Err(error) => {
+                        // best effort, leave the self-test bits cleared
+                        let _ = self.set_accel_self_test_all(false);
+                        return Err(t.fail(AutoSetupFailure::Device(error)));
+                    }
+                }
+            } else {
+                t.finish(PhaseStatus::Skipped);
+            }
+        }
+
+        // gyro calibration
+        if !t.done(AutoSetupPhase::GyroCalibration) {
+            t.begin(AutoSetupPhase::GyroCalibration);
+            let resumed = partial.filter(|_| t.aborted(AutoSetupPhase::GyroCalibration));
+            let calibration = ReferencedCalibration::gyro(&options);
+            match self.calibrate_gyro_tracked(
+                delay,
+                &mut t,
+                resumed,
+                &mut || Vec3A::ZERO,
+                calibration,
+            ) {
+                Ok(result) => {
+                    t.report.gyro_std_dev = Some(result.std_dev);
+                    t.report.gyro_offset = result.offset;
+                    t.finish(PhaseStatus::Completed);
+                }
+                Err(failure) => {
+                    if let AutoSetupFailure::CalibrationRejected { std_dev, .. } = failure {
+                        t.report.gyro_std_dev = Some(std_dev);
+                    }
+                    return Err(t.fail(failure));
+                }
+            }
+        }
+
+        // accel calibration
+        if !t.done(AutoSetupPhase::AccelCalibration) {
+            t.begin(AutoSetupPhase::AccelCalibration);
+            if options.device_is_level {
+                let resumed = partial.filter(|_| t.aborted(AutoSetupPhase::AccelCalibration));
+                let calibration = ReferencedCalibration::accel(&options);
+                match self.calibrate_accel_tracked(
+                    delay,
+                    &mut t,
+                    resumed,
+                    &mut || Vec3A::Z,
+                    calibration,
+                ) {
+                    Ok(result) => {
+                        t.report.acc_std_dev = Some(result.std_dev);
+                        t.report.acc_offset = result.offset;
+                        t.finish(PhaseStatus::Completed);
+                    }
+                    Err(failure) => {
+                        if let AutoSetupFailure::CalibrationRejected { std_dev, .. } = failure {
+                            t.report.acc_std_dev = Some(std_dev);
+                        }
+                        return Err(t.fail(failure));
+                    }
+                }
+            } else {
+                t.finish(PhaseStatus::Skipped);
+            }
+        }
+
+        // final configuration
+        t.begin(AutoSetupPhase::ApplyConfiguration);
+        let res = t.check(self.io_stats.transactions, None).and_then(|_| {
+            self.set_accel_range(options.accel_range)?;
+            self.set_gyro_range(options.gyro_range)?;
+            self.set_accel_hpf(options.accel_hpf)
+        });
+        if let Err(error) = res {
+            return Err(t.fail(AutoSetupFailure::Device(error)));
+        }
+        t.finish(PhaseStatus::Completed);
+
+        if t.report.gyro_std_dev.is_some() || t.report.acc_std_dev.is_some() {
+            let meta = CalibrationMeta {
+                temperature_c: t.report.temperature,
+                gyro_std_dev: t.report.gyro_std_dev,
+                acc_std_dev: t.report.acc_std_dev,
+                ..CalibrationMeta::new(CalibrationMethod::Static)
+            };
+            self.set_calibration_meta(CalibrationKind::SoftwareOffsets, Some(meta));
+        }
+        Ok(t.report)
+    }
+
+    /// Polls the temperature until `stable_readings` consecutive readings are within tolerance
+    fn wait_thermal_stable(
+        &mut self,
+        delay: &mut impl DelayMs<u8>,
+        t: &mut Tracker,
+        wait: ThermalWait,
+    ) -> Result<PhaseStatus, Mpu6050Error<E>> {
+        t.check(self.io_stats.transactions, None)?;
+        let mut last = self.read_temp()?;
+        let mut stable = 0;
+        t.report.temperature = Some(last);
+        while t.elapsed_ms < wait.max_ms {
+            t.check(self.io_stats.transactions, None)?;
+            t.wait(delay, wait.interval_ms);
+            t.pace(self.io_stats);
+            t.check(self.io_stats.transactions, None)?;
+            let temp = self.read_temp()?;
+            t.report.temperature = Some(temp);
+            if (temp - last).abs() <= wait.tolerance_c {
+                stable += 1;
+                if stable >= wait.stable_readings {
+                    return Ok(PhaseStatus::Completed);
+                }
+            } else {
+                stable = 0;
+            }
+            last = temp;
+        }
+        Ok(PhaseStatus::TimedOut)
+    }
+
+    /// Difference between accel readings with and without self-test enabled, in g
+    fn accel_actuation_response(
+        &mut self,
+        delay: &mut impl DelayMs<u8>,
+        t: &mut Tracker,
+    ) -> Result<Vec3A, Mpu6050Error<E>> {
+        t.check(self.io_stats.transactions, None)?;
+        let before = self.read_acc_uncorrected()?;
+        self.set_accel_self_test_checked(t, true)?;
+        t.check(self.io_stats.transactions, None)?;
+        t.wait(delay, 20);
+        t.check(self.io_stats.transactions, None)?;
+        let during = self.read_acc_uncorrected()?;
+        self.set_accel_self_test_checked(t, false)?;
+        t.check(self.io_stats.transactions, None)?;
+        t.wait(delay, 20);
+        Ok((during - before).abs())
+    }
+
+    fn set_accel_self_test_all(&mut self, enable: bool) -> Result<(), Mpu6050Error<E>> {
+        self.set_accel_x_self_test(enable)?;
+        self.set_accel_y_self_test(enable)?;
+        self.set_accel_z_self_test(enable)
+    }
+
+    /// [`set_accel_self_test_all`](Self::set_accel_self_test_all) checking the deadline
+    /// before every axis
+    fn set_accel_self_test_checked(
+        &mut self,
+        t: &mut Tracker,
+        enable: bool,
+    ) -> Result<(), Mpu6050Error<E>> {
+        for field in [
+            ACCEL_CONFIG::XA_ST_FIELD,
+            ACCEL_CONFIG::YA_ST_FIELD,
+            ACCEL_CONFIG::ZA_ST_FIELD,
+        ] {
+            t.check(self.io_stats.transactions, None)?;
+            self.write_field(field, enable)?;
+        }
+        self.settle.trigger(SettleTrigger::SelfTest);
+        Ok(())
+    }
+
+    /// Collects the differences between `read` and `reference`, called right after every
+    /// reading, until the accumulator, starting from `resumed`, holds `samples`. The negated
+    /// mean is the offset: every calibration goes through here, the standard ones with a
+    /// constant reference
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) fn sample_stats(
+        &mut self,
+        delay: &mut impl DelayMs<u8>,
+        t: &mut Tracker,
+        resumed: Option<Accumulator>,
+        samples: u16,
+        interval_ms: u8,
+        read: fn(&mut Self) -> Result<Vec3A, Mpu6050Error<E>>,
+        reference: &mut dyn FnMut() -> Vec3A,
+    ) -> Result<Accumulator, Mpu6050Error<E>> {
+        let mut acc = resumed.unwrap_or_default();
+        while acc.count() < samples as u32 {
+            t.check(self.io_stats.transactions, Some(&acc))?;
+            let reading = read(self)?;
+            acc.push(reading - reference());
+            t.check(self.io_stats.transactions, Some(&acc))?;
+            t.wait(delay, interval_ms);
+            t.pace(self.io_stats);
+        }
+        Ok(acc)
+    }
+}
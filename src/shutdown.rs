@@ -0,0 +1,404 @@
+//! Coordinated shutdown into the lowest power state, certified by reading every touched
+//! register back.
+//!
+//! [`Mpu6050::shutdown`] runs the steps of [`ShutdownItem`] in order, each a write to one
+//! register, and then reads each one back:
+//!
+//! | [`ShutdownItem`] | write | verified by |
+//! |:---|:---|:---|
+//! | [`InterruptsDisabled`](ShutdownItem::InterruptsDisabled) | INT_ENABLE 0x00 | INT_ENABLE 0x00 |
+//! | [`FifoSourcesCleared`](ShutdownItem::FifoSourcesCleared) | FIFO_EN 0x00 | FIFO_EN 0x00 |
+//! | [`FifoAndMasterDisabled`](ShutdownItem::FifoAndMasterDisabled) | USER_CTRL FIFO_EN and I2C_MST_EN cleared, then [`SHUTDOWN_SETTLE_MS`] | both bits clear |
+//! | [`FifoEmpty`](ShutdownItem::FifoEmpty) | USER_CTRL FIFO_RESET set | FIFO_COUNT 0 |
+//! | [`BypassDisabled`](ShutdownItem::BypassDisabled) | INT_PIN_CFG I2C_BYPASS_EN cleared | the bit clear |
+//! | [`AxesInStandby`](ShutdownItem::AxesInStandby) | PWR_MGMT_2 all six STBY bits set | all six set |
+//! | [`Asleep`](ShutdownItem::Asleep) | PWR_MGMT_1 SLEEP set, CYCLE cleared | SLEEP set, CYCLE clear |
+//!
+//! The bit changes are read-modify-writes, the other bits of the registers are kept. The wait
+//! gives the aux master the time to finish a transaction in flight before the FIFO is reset,
+//! a 24 byte read at 100 kHz takes about 2.5 ms. SLV_3_FIFO_EN is left as it is, with the
+//! master off it writes nothing.
+//!
+//! The read-backs are reads on the bus after the last write, not the driver's idea of the
+//! registers: a write the chip dropped, a register stuck or a bus glitch shows up as a
+//! [`ShutdownCheck`] that did not pass. [`ShutdownEvidence::verified`] is the overall verdict,
+//! [`failures`](ShutdownEvidence::failures) the checks that failed, and its `Display` is a
+//! report of one line per check for a log.
+//!
+//! #### Errors
+//! [`shutdown`](Mpu6050::shutdown) stops at the first bus error and returns it. A completed
+//! sequence returns the evidence, verified or not. For a shutdown that must get as far as it
+//! can, on the way out of a brownout handler say,
+//! [`shutdown_best_effort`](Mpu6050::shutdown_best_effort) never fails: a step whose write
+//! failed is recorded as not [`written`](ShutdownCheck::written) and the next step runs, a
+//! failed read-back as not [`observed`](ShutdownCheck::observed). Both take the delay like
+//! [`init`](Mpu6050::init), or none with an owned one, see [`delay`](crate::delay).
+//!
+//! [`verify_shutdown_state`](Mpu6050::verify_shutdown_state) runs the read-backs alone, to
+//! check later that nothing woke the chip; its checks have no write.
+//!
+//! #### Driver state
+//! The driver follows the writes that went through: the FIFO sources are cleared with a new
+//! [configuration epoch](crate::config_epoch), the FIFO is not streaming, the chip is asleep
+//! and not cycling. [`init`](Mpu6050::init) wakes it again; the axes stay in standby until
+//! PWR_MGMT_2 is written, by [`apply_settings`](Mpu6050::apply_settings) for example.
+//! ```
+//! use embedded_hal::blocking::delay::DelayMs;
+//! use embedded_hal::blocking::i2c::{Write, WriteRead};
+//! use mpu6050::Mpu6050;
+//!
+//! // power down before cutting VDD, the report goes to the log
+//! fn power_down<I, E>(mpu: &mut Mpu6050<I>, delay: &mut impl DelayMs<u8>) -> bool
+//! where
+//!     I: Write<Error = E> + WriteRead<Error = E>,
+//! {
+//!     let evidence = mpu.shutdown_best_effort(delay);
+//!     if !evidence.verified() {
+//!         eprintln!("{}", evidence);
+//!     }
+//!     evidence.verified()
+//! }
+//! ```
+
+use core::fmt;
+
+#[cfg(feature = "driver")]
+use embedded_hal::blocking::{
+    delay::DelayMs,
+    i2c::{Write, WriteRead},
+};
+
+#[cfg(feature = "driver")]
+use crate::config_epoch::ConfigChange;
+#[cfg(feature = "driver")]
+use crate::continuity::ContinuityBreak;
+#[cfg(feature = "driver")]
+use crate::device::{INT_PIN_CFG, PWR_MGMT_1, PWR_MGMT_2, USER_CTRL};
+#[cfg(feature = "driver")]
+use crate::fifo::FifoSources;
+#[cfg(feature = "driver")]
+use crate::logging::{log_debug, log_warn};
+use crate::register::Register;
+#[cfg(feature = "driver")]
+use crate::register::STANDBY_FIELDS;
+#[cfg(feature = "driver")]
+use crate::{Mpu6050, Mpu6050Error};
+
+/// Wait in ms after disabling the FIFO and the aux master, before the FIFO reset
+pub const SHUTDOWN_SETTLE_MS: u8 = 5;
+
+/// Steps of the shutdown sequence
+pub const SHUTDOWN_STEPS: usize = 7;
+
+/// PWR_MGMT_2 STBY_XA to STBY_ZG
+const ALL_AXES_STANDBY: u8 = 0x3f;
+
+/// Step of the shutdown sequence and what its read-back has to show, see the module docs
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum ShutdownItem {
+    /// no interrupt source enabled
+    InterruptsDisabled,
+    /// nothing written to the FIFO
+    FifoSourcesCleared,
+    /// FIFO and aux I2C master disabled
+    FifoAndMasterDisabled,
+    /// FIFO reset, no byte in it
+    FifoEmpty,
+    /// aux bus not bridged to the host bus
+    BypassDisabled,
+    /// every accel and gyro axis in standby
+    AxesInStandby,
+    /// sleeping, not cycling
+    Asleep,
+}
+
+impl ShutdownItem {
+    /// the steps in the order of the sequence
+    pub const ALL: [ShutdownItem; SHUTDOWN_STEPS] = [
+        ShutdownItem::InterruptsDisabled,
+        ShutdownItem::FifoSourcesCleared,
+        ShutdownItem::FifoAndMasterDisabled,
+        ShutdownItem::FifoEmpty,
+        ShutdownItem::BypassDisabled,
+        ShutdownItem::AxesInStandby,
+        ShutdownItem::Asleep,
+    ];
+
+    /// register read back, the first of the pair for FIFO_COUNT
+    pub const fn register(self) -> Register {
+        match self {
+            ShutdownItem::InterruptsDisabled => Register::INT_ENABLE,
+            ShutdownItem::FifoSourcesCleared => Register::FIFO_EN,
+            ShutdownItem::FifoAndMasterDisabled => Register::USER_CTRL,
+            ShutdownItem::FifoEmpty => Register::FIFO_COUNTH,
+            ShutdownItem::BypassDisabled => Register::INT_PIN_CFG,
+            ShutdownItem::AxesInStandby => Register::PWR_MGMT_2,
+            ShutdownItem::Asleep => Register::PWR_MGMT_1,
+        }
+    }
+
+    /// bits of the read-back that are checked
+    pub const fn mask(self) -> u16 {
+        match self {
+            ShutdownItem::InterruptsDisabled | ShutdownItem::FifoSourcesCleared => 0xff,
+            ShutdownItem::FifoAndMasterDisabled => 0x60,
+            ShutdownItem::FifoEmpty => 0xffff,
+            ShutdownItem::BypassDisabled => 0x02,
+            ShutdownItem::AxesInStandby => ALL_AXES_STANDBY as u16,
+            ShutdownItem::Asleep => 0x60,
+        }
+    }
+
+    /// value of the checked bits after the shutdown
+    pub const fn expected(self) -> u16 {
+        match self {
+            ShutdownItem::AxesInStandby => ALL_AXES_STANDBY as u16,
+            ShutdownItem::Asleep => 0x40,
+            _ => 0,
+        }
+    }
+}
+
+impl fmt::Display for ShutdownItem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            ShutdownItem::InterruptsDisabled => "interrupts disabled",
+            ShutdownItem::FifoSourcesCleared => "FIFO sources cleared",
+            ShutdownItem::FifoAndMasterDisabled => "FIFO and aux master disabled",
+            ShutdownItem::FifoEmpty => "FIFO empty",
+            ShutdownItem::BypassDisabled => "bypass disabled",
+            ShutdownItem::AxesInStandby => "axes in standby",
+            ShutdownItem::Asleep => "asleep",
+        })
+    }
+}
+
+/// Write and read-back of one step
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct ShutdownCheck {
+    /// the step
+    pub item: ShutdownItem,
+    /// register read back
+    pub register: Register,
+    /// bits checked
+    pub mask: u16,
+    /// value of the checked bits expected
+    pub expected: u16,
+    /// value read back, all bits, None if the read failed
+    pub observed: Option<u16>,
+    /// whether the write went through, None for a read-back without a write
+    pub written: Option<bool>,
+}
+
+impl ShutdownCheck {
+    /// check of `item` before its write and read-back
+    const fn pending(item: ShutdownItem) -> Self {
+        Self {
+            item,
+            register: item.register(),
+            mask: item.mask(),
+            expected: item.expected(),
+            observed: None,
+            written: None,
+        }
+    }
+
+    /// the write, if any, went through and the read-back shows the expected bits
+    pub fn passed(&self) -> bool {
+        self.written != Some(false)
+            && self
+                .observed
+                .is_some_and(|value| value & self.mask == self.expected)
+    }
+}
+
+impl fmt::Display for ShutdownCheck {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // two hex digits a byte, four for the FIFO count
+        let width = if self.mask > 0xff { 6 } else { 4 };
+        write!(
+            f,
+            "{}, {}: expected {:#0w$x} of {:#0w$x}, ",
+            self.item,
+            self.register,
+            self.expected,
+            self.mask,
+            w = width
+        )?;
+        match self.observed {
+            Some(value) => write!(f, "observed {:#0w$x}", value, w = width)?,
+            None => f.write_str("not read")?,
+        }
+        if self.written == Some(false) {
+            f.write_str(", write failed")?;
+        }
+        f.write_str(match self.passed() {
+            true => ", ok",
+            false => ", FAILED",
+        })
+    }
+}
+
+/// Read-backs of a shutdown, one check per [`ShutdownItem`] in sequence order
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct ShutdownEvidence {
+    checks: [ShutdownCheck; SHUTDOWN_STEPS],
+}
+
+impl ShutdownEvidence {
+    /// the checks in sequence order
+    pub fn checks(&self) -> &[ShutdownCheck; SHUTDOWN_STEPS] {
+        &self.checks
+    }
+
+    /// the check of `item`
+    pub fn check(&self, item: ShutdownItem) -> &ShutdownCheck {
+        &self.checks[item as usize]
+    }
+
+    /// every check passed
+    pub fn verified(&self) -> bool {
+        self.checks.iter().all(ShutdownCheck::passed)
+    }
+
+    /// the checks that did not pass, in sequence order
+    pub fn failures(&self) -> impl Iterator<Item = &ShutdownCheck> {
+        self.checks.iter().filter(|check| !check.passed())
+    }
+}
+
+impl fmt::Display for ShutdownEvidence {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.failures().count() {
+            0 => f.write_str("shutdown verified")?,
+            n => write!(
+                f,
+                "shutdown not verified, {} of {} failed",
+                n, SHUTDOWN_STEPS
+            )?,
+        }
+        for check in &self.checks {
+            write!(f, "\n  {}", check)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "driver")]
+impl<I, D, E> Mpu6050<I, D>
+where
+    I: Write<Error = E> + WriteRead<Error = E>,
+{
+    /// Reads back the registers of the shutdown sequence without writing any, see the
+    /// module docs. Fails at the first bus error
+    pub fn verify_shutdown_state(&mut self) -> Result<ShutdownEvidence, Mpu6050Error<E>> {
+        let mut checks = ShutdownItem::ALL.map(ShutdownCheck::pending);
+        self.read_back(&mut checks, true)
+            .map_or(Ok(ShutdownEvidence { checks }), Err)
+    }
+
+    /// the sequence and its read-backs, stopping at the first bus error if `stop`, which is
+    /// returned with the evidence gathered until then
+    pub(crate) fn shutdown_using(
+        &mut self,
+        delay: &mut impl DelayMs<u8>,
+        stop: bool,
+    ) -> (ShutdownEvidence, Option<Mpu6050Error<E>>) {
+        let mut checks = ShutdownItem::ALL.map(ShutdownCheck::pending);
+        let mut error = None;
+        for check in &mut checks {
+            let res = self.shutdown_step(check.item, delay);
+            check.written = Some(res.is_ok());
+            if let (Err(e), true) = (res, stop) {
+                error = Some(e);
+                break;
+            }
+        }
+        if error.is_none() {
+            error = self.read_back(&mut checks, stop);
+        }
+        let evidence = ShutdownEvidence { checks };
+        // a bus error stopping the sequence is the caller's
+        match (&error, evidence.verified()) {
+            (Some(_), _) => {}
+            (None, true) => log_debug!(POWER, "{}", evidence),
+            (None, false) => log_warn!(POWER, "{}", evidence),
+        }
+        (evidence, error)
+    }
+
+    /// reads every check back, stopping at the first bus error if `stop`
+    fn read_back(
+        &mut self,
+        checks: &mut [ShutdownCheck; SHUTDOWN_STEPS],
+        stop: bool,
+    ) -> Option<Mpu6050Error<E>> {
+        for check in checks {
+            let res = match check.item {
+                ShutdownItem::FifoEmpty => self.fifo_count(),
+                item => self.read_register(item.register()).map(u16::from),
+            };
+            match res {
+                Ok(value) => check.observed = Some(value),
+                Err(e) if stop => return Some(e),
+                Err(_) => {}
+            }
+        }
+        None
+    }
+
+    fn shutdown_step(
+        &mut self,
+        item: ShutdownItem,
+        delay: &mut impl DelayMs<u8>,
+    ) -> Result<(), Mpu6050Error<E>> {
+        match item {
+            ShutdownItem::InterruptsDisabled => self.write_register(Register::INT_ENABLE, 0),
+            ShutdownItem::FifoSourcesCleared => {
+                self.bump_epoch(ConfigChange::FifoSources);
+                self.write_register(Register::FIFO_EN, 0)?;
+                let slave_3 = self.fifo_sources.slaves[3];
+                self.fifo_sources = FifoSources {
+                    slaves: [false, false, false, slave_3],
+                    ..FifoSources::NONE
+                };
+                Ok(())
+            }
+            ShutdownItem::FifoAndMasterDisabled => {
+                self.modify_fields::<USER_CTRL>(|fields| {
+                    fields
+                        .with(USER_CTRL::FIFO_EN_FIELD, false)
+                        .with(USER_CTRL::I2C_MST_EN_FIELD, false)
+                })?;
+                self.fifo_streaming = false;
+                delay.delay_ms(SHUTDOWN_SETTLE_MS);
+                Ok(())
+            }
+            ShutdownItem::FifoEmpty => self.reset_fifo(),
+            ShutdownItem::BypassDisabled => {
+                self.write_field(INT_PIN_CFG::I2C_BYPASS_EN_FIELD, false)
+            }
+            ShutdownItem::AxesInStandby => {
+                self.modify_fields::<PWR_MGMT_2>(|fields| {
+                    STANDBY_FIELDS
+                        .iter()
+                        .fold(fields, |fields, field| fields.with(*field, true))
+                })?;
+                Ok(())
+            }
+            ShutdownItem::Asleep => {
+                self.modify_fields::<PWR_MGMT_1>(|fields| {
+                    fields
+                        .with(PWR_MGMT_1::CYCLE_FIELD, false)
+                        .with(PWR_MGMT_1::SLEEP_FIELD, true)
+                })?;
+                if !self.power.asleep {
+                    self.break_continuity(ContinuityBreak::Sleep);
+                }
+                self.power.asleep = true;
+                self.cycle = None;
+                Ok(())
+            }
+        }
+    }
+}
@@ -0,0 +1,255 @@
+//! Intra-sample skew between the accelerometer and the gyro, and its compensation.
+//!
+//! The accelerometer and gyro values of one sample were not sampled at the same instant:
+//! their paths have different DLPF delays, and with the DLPF off the accelerometer output
+//! updates at 1 kHz while the gyro runs at 8 kHz. The skew is how much older the gyro
+//! reading is than the accelerometer reading, negative when the gyro is the newer one.
+//! [`default_skew_us`] derives it from [`DLPF_DELAY_MS`], register map rev 4.2 section 4.3:
+//!
+//! | DLPF_CFG | accel delay | gyro delay | skew |
+//! |:---|:---|:---|:---|
+//! | 0, 7 | 0 | 0.98 ms | +980 µs, +480 µs above 1 kHz ODR |
+//! | 1 | 2.0 ms | 1.9 ms | −100 µs |
+//! | 2 | 3.0 ms | 2.8 ms | −200 µs |
+//! | 3 | 4.9 ms | 4.8 ms | −100 µs |
+//! | 4 | 8.5 ms | 8.3 ms | −200 µs |
+//! | 5 | 13.8 ms | 13.4 ms | −400 µs |
+//! | 6 | 19.0 ms | 18.6 ms | −400 µs |
+//!
+//! Above 1 kHz ODR with the DLPF off, a read finds the accelerometer output on average half
+//! its 1 ms update period old, 500 µs less skew. A characterization of the actual part goes
+//! in [`SkewCorrection::with_skew_us`].
+//!
+//! #### Correction
+//! [`Mpu6050::set_skew_correction`] opts the combined sample path
+//! ([`run_sampling_loop`](Mpu6050::run_sampling_loop),
+//! [`sample_into_interp_buffer`](Mpu6050::sample_into_interp_buffer)) into shifting one
+//! sensor onto the other's instant, by linear extrapolation from the previous sample
+//! ([`extrapolate`]):
+//!
+//! | [`SkewReference`] | shifted | by |
+//! |:---|:---|:---|
+//! | [`Accel`](SkewReference::Accel) | gyro | +skew |
+//! | [`Gyro`](SkewReference::Gyro) | accelerometer | −skew |
+//!
+//! The corrected sample reports its [`time_reference`](crate::MpuSample::time_reference):
+//! all of its values are those of the reference sensor's sampling instant, which is its
+//! DLPF delay before the read. Uncorrected samples report None, each sensor at its own
+//! instant. The shifted group is tagged
+//! [`Interpolated`](crate::provenance::Provenance::Interpolated) or worse, see
+//! [`provenance`](crate::provenance).
+//!
+//! The previous sample is assumed one [`nominal_sample_interval_us`] old. The first sample
+//! and the one after an overrun of the sampling loop go out uncorrected.
+//!
+//! #### Limits
+//! A linear extrapolation is only sensible while the signal is smooth over one sample
+//! interval. The correction is active at an ODR of at least [`MIN_OVERSAMPLING`] times the
+//! gyro DLPF bandwidth ([`correction_sensible`]) and never in cycle mode, otherwise samples
+//! go out uncorrected. The shift is clamped to one sample interval whatever the skew.
+//!
+//! ```
+//! use mpu6050::skew::*;
+//! use mpu6050::{MpuSample, Vec3A};
+//!
+//! assert_eq!(default_skew_us(5, 100.), -400.);
+//!
+//! // a ramp extrapolates exactly: 0.5 rad/s per ms, shifted 0.25 ms
+//! let shifted = extrapolate(Vec3A::ZERO, Vec3A::splat(0.5), 1000., 250.);
+//! assert_eq!(shifted, Vec3A::splat(0.625));
+//!
+//! let mut corrector = SkewCorrector::new(SkewCorrection::new(SkewReference::Accel));
+//! let at_rest = MpuSample::new(Vec3A::Z, Vec3A::ZERO, 25.);
+//! let first = corrector.correct(at_rest, 250., 1000.);
+//! assert_eq!(first.time_reference(), None);
+//! let second = corrector.correct(at_rest.with_gyro(Vec3A::splat(0.5)), 250., 1000.);
+//! assert_eq!(second.time_reference(), Some(SkewReference::Accel));
+//! assert_eq!(second.gyro(), Vec3A::splat(0.625));
+//! ```
+//!
+//! [`nominal_sample_interval_us`]: Mpu6050::nominal_sample_interval_us
+
+use glam::Vec3A;
+
+use crate::device::{DLPF_BANDWIDTH_HZ, DLPF_DELAY_MS};
+#[cfg(not(feature = "std"))]
+use crate::math::FloatExt;
+use crate::provenance::{FieldGroup, Provenance};
+use crate::{Mpu6050, MpuSample};
+
+/// Smallest ODR for the correction, in multiples of the gyro DLPF bandwidth
+pub const MIN_OVERSAMPLING: f32 = 4.;
+
+/// Sensor whose sampling instant a corrected sample refers to
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum SkewReference {
+    /// gyro shifted onto the accelerometer instant
+    Accel,
+    /// accelerometer shifted onto the gyro instant
+    Gyro,
+}
+
+/// Skew correction configuration
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct SkewCorrection {
+    /// sensor the sample refers to after correction
+    pub reference: SkewReference,
+    /// characterized skew in µs, None for [`default_skew_us`]
+    pub skew_us: Option<f32>,
+}
+
+impl SkewCorrection {
+    /// correction onto `reference` with the default skew
+    pub const fn new(reference: SkewReference) -> Self {
+        Self {
+            reference,
+            skew_us: None,
+        }
+    }
+
+    /// characterized skew of the part in µs, non-finite values are ignored
+    pub fn with_skew_us(mut self, skew_us: f32) -> Self {
+        if skew_us.is_finite() {
+            self.skew_us = Some(skew_us);
+        }
+        self
+    }
+}
+
+/// Skew in µs, positive for a gyro reading older than the accelerometer reading, see the
+/// module docs
+pub fn default_skew_us(dlpf_cfg: u8, odr_hz: f32) -> f32 {
+    let (acc_ms, gyro_ms) = DLPF_DELAY_MS[(dlpf_cfg & 7) as usize];
+    let skew_us = (gyro_ms - acc_ms) * 1000.;
+    // rounded to the µs, the table is given to 0.1 ms
+    let skew_us = skew_us.round();
+    if matches!(dlpf_cfg & 7, 0 | 7) && odr_hz > 1000. {
+        skew_us - 500.
+    } else {
+        skew_us
+    }
+}
+
+/// An ODR of `odr_hz` is high enough for a linear extrapolation at `dlpf_cfg`, see the
+/// module docs
+pub fn correction_sensible(dlpf_cfg: u8, odr_hz: f32) -> bool {
+    let (_, gyro_bandwidth_hz) = DLPF_BANDWIDTH_HZ[(dlpf_cfg & 7) as usize];
+    odr_hz >= MIN_OVERSAMPLING * gyro_bandwidth_hz
+}
+
+/// `current` moved `shift_us` forward in time along the line through `previous`, one
+/// `interval_us` earlier. The shift is clamped to ±`interval_us`
+pub fn extrapolate(previous: Vec3A, current: Vec3A, interval_us: f32, shift_us: f32) -> Vec3A {
+    let shift_us = shift_us.clamp(-interval_us, interval_us);
+    current + (current - previous) * (shift_us / interval_us)
+}
+
+/// Skew correction over a stream of samples one interval apart, no bus access
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct SkewCorrector {
+    correction: SkewCorrection,
+    previous: Option<MpuSample>,
+}
+
+impl SkewCorrector {
+    /// corrector without a previous sample
+    pub const fn new(correction: SkewCorrection) -> Self {
+        Self {
+            correction,
+            previous: None,
+        }
+    }
+
+    /// configuration
+    pub fn correction(&self) -> SkewCorrection {
+        self.correction
+    }
+
+    /// forgets the previous sample, the next one goes out uncorrected
+    pub fn reset(&mut self) {
+        self.previous = None;
+    }
+
+    /// `sample` shifted onto the reference instant, `skew_us` the skew and `interval_us` the
+    /// time since the previous sample. Uncorrected without a previous sample
+    pub fn correct(&mut self, sample: MpuSample, skew_us: f32, interval_us: f32) -> MpuSample {
+        let Some(previous) = self.previous.replace(sample) else {
+            return sample;
+        };
+        let (group, shift_us) = match self.correction.reference {
+            SkewReference::Accel => (FieldGroup::Gyro, skew_us),
+            SkewReference::Gyro => (FieldGroup::Acc, -skew_us),
+        };
+        let tag = sample
+            .provenance()
+            .get(group)
+            .worst(previous.provenance().get(group))
+            .worst(Provenance::Interpolated);
+        let corrected = match group {
+            FieldGroup::Gyro => sample.with_gyro(extrapolate(
+                previous.gyro,
+                sample.gyro,
+                interval_us,
+                shift_us,
+            )),
+            _ => sample.with_acc(extrapolate(previous.acc, sample.acc, interval_us, shift_us)),
+        };
+        corrected
+            .with_provenance(sample.provenance().with(group, tag))
+            .with_time_reference(Some(self.correction.reference))
+    }
+}
+
+impl<I, D> Mpu6050<I, D> {
+    /// Opts the combined sample path into the skew correction, None turns it off. Forgets
+    /// the previous sample
+    pub fn set_skew_correction(&mut self, correction: Option<SkewCorrection>) {
+        self.skew = correction.map(SkewCorrector::new);
+    }
+
+    /// correction set with [`set_skew_correction`](Self::set_skew_correction)
+    pub fn skew_correction(&self) -> Option<SkewCorrection> {
+        self.skew.as_ref().map(SkewCorrector::correction)
+    }
+
+    /// Skew of the configuration last written by the driver in µs: the characterized skew
+    /// of the correction, [`default_skew_us`] without
+    pub fn intra_sample_skew_us(&self) -> f32 {
+        let odr_hz = self.effective_odr_hz();
+        self.skew
+            .as_ref()
+            .and_then(|skew| skew.correction().skew_us)
+            .unwrap_or_else(|| default_skew_us(self.dlpf_cfg, odr_hz))
+    }
+
+    /// a correction is set and the configuration last written allows it, see the module docs
+    pub fn skew_correction_active(&self) -> bool {
+        self.skew.is_some()
+            && self.cycle.is_none()
+            && correction_sensible(self.dlpf_cfg, self.effective_odr_hz())
+    }
+
+    /// `sample` through the skew correction, unchanged while inactive
+    #[cfg(not(feature = "minimal-pipeline"))]
+    pub(crate) fn correct_skew(&mut self, sample: MpuSample) -> MpuSample {
+        if !self.skew_correction_active() {
+            if let Some(skew) = self.skew.as_mut() {
+                skew.reset();
+            }
+            return sample;
+        }
+        let skew_us = self.intra_sample_skew_us();
+        let interval_us = self.nominal_sample_interval_us();
+        match self.skew.as_mut() {
+            Some(skew) => skew.correct(sample, skew_us, interval_us),
+            None => sample,
+        }
+    }
+
+    /// samples were lost, the next one has no previous sample to extrapolate from
+    pub(crate) fn forget_skew_history(&mut self) {
+        if let Some(skew) = self.skew.as_mut() {
+            skew.reset();
+        }
+    }
+}
@@ -0,0 +1,324 @@
+//! Output smoothing per consumer.
+//!
+//! The driver's own output stays raw. Consumers wanting smoothed values each hold a
+//! [`FilteredView`] with their own [`FilterSpec`] and filter state: a display can run a heavy
+//! low-pass while the control loop reads raw samples from the same stream. A view does not
+//! borrow the driver, any number of them exist side by side and are fed explicitly:
+//! [`FilteredView::ingest`] from whatever read path the application uses, or
+//! [`Mpu6050::read_into_views`], one burst read of accel, temperature and gyro fed to every
+//! view given.
+//!
+//! #### Filters
+//! Each axis and the temperature run a [`ChannelFilter`] of the [`Smoothing`] of their
+//! group:
+//!
+//! | smoothing | response |
+//! |:---|:---|
+//! | [`Raw`](Smoothing::Raw) | the input |
+//! | [`LowPass`](Smoothing::LowPass) | `y += alpha (x - y)`, started at the first input: a step of height h is at `h (1 - (1 - alpha)^k)` k samples after it |
+//! | [`Median`](Smoothing::Median) | median of the last `window` inputs, the mean of the middle two for an even count: a spike shorter than half the window never shows |
+//!
+//! [`LowPassFilter`] and [`MedianFilter`] are the state types, usable on their own. The
+//! median window is bounded by the capacity `N` of the view, [`DEFAULT_MEDIAN_CAPACITY`]
+//! unless chosen, nothing is allocated. A NaN input, a part of a partial sample not read,
+//! passes through without touching the state. Samples keep their flags and provenance.
+//!
+//! ```
+//! use mpu6050::smoothing::*;
+//! use mpu6050::{MpuSample, Vec3A};
+//!
+//! let spec = FilterSpec {
+//!     acc: Smoothing::LowPass { alpha: 0.5 },
+//!     ..FilterSpec::RAW
+//! };
+//! let mut display = FilteredView::<5>::new(spec);
+//! let mut control = FilteredView::<5>::new(FilterSpec::RAW);
+//! for acc in [0., 1., 1.] {
+//!     let sample = MpuSample::new(Vec3A::splat(acc), Vec3A::ZERO, 25.);
+//!     display.ingest(&sample);
+//!     control.ingest(&sample);
+//! }
+//! assert_eq!(display.latest().unwrap().acc(), Vec3A::splat(0.75));
+//! assert_eq!(control.latest().unwrap().acc(), Vec3A::ONE);
+//! ```
+
+#[cfg(feature = "driver")]
+use embedded_hal::blocking::i2c::{Write, WriteRead};
+use glam::Vec3A;
+
+#[cfg(feature = "driver")]
+use crate::Mpu6050Error;
+use crate::{Mpu6050, MpuSample};
+
+/// Median window capacity of a [`FilteredView`] unless chosen
+pub const DEFAULT_MEDIAN_CAPACITY: usize = 9;
+
+/// Smoothing of one field group, see the module docs
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub enum Smoothing {
+    /// unfiltered
+    #[default]
+    Raw,
+    /// first order IIR low-pass, `alpha` clamped to (0, 1], 1 is unfiltered
+    LowPass {
+        /// weight of a new input
+        alpha: f32,
+    },
+    /// running median, `window` clamped to 1..=N of the view
+    Median {
+        /// inputs the median is taken over
+        window: u8,
+    },
+}
+
+/// Smoothing per field group of a [`FilteredView`]
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct FilterSpec {
+    /// accelerometer, per axis
+    pub acc: Smoothing,
+    /// gyro, per axis
+    pub gyro: Smoothing,
+    /// temperature
+    pub temp: Smoothing,
+}
+
+impl FilterSpec {
+    /// nothing filtered
+    pub const RAW: Self = Self {
+        acc: Smoothing::Raw,
+        gyro: Smoothing::Raw,
+        temp: Smoothing::Raw,
+    };
+}
+
+/// State of a first order IIR low-pass
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct LowPassFilter {
+    alpha: f32,
+    value: Option<f32>,
+}
+
+impl LowPassFilter {
+    /// low-pass weighting a new input with `alpha`, clamped to (0, 1], NaN as 1
+    pub fn new(alpha: f32) -> Self {
+        let alpha = match alpha.is_nan() {
+            true => 1.,
+            false => alpha.clamp(f32::EPSILON, 1.),
+        };
+        Self { alpha, value: None }
+    }
+
+    /// weight of a new input
+    pub fn alpha(&self) -> f32 {
+        self.alpha
+    }
+
+    /// filters `x`, the first input is taken as is
+    pub fn update(&mut self, x: f32) -> f32 {
+        if x.is_nan() {
+            return x;
+        }
+        let y = match self.value {
+            Some(y) => y + self.alpha * (x - y),
+            None => x,
+        };
+        self.value = Some(y);
+        y
+    }
+
+    /// last output, None before the first input
+    pub fn value(&self) -> Option<f32> {
+        self.value
+    }
+
+    /// forgets the inputs
+    pub fn reset(&mut self) {
+        self.value = None;
+    }
+}
+
+/// State of a running median over up to `N` inputs
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct MedianFilter<const N: usize> {
+    window: usize,
+    inputs: [f32; N],
+    len: usize,
+    next: usize,
+}
+
+impl<const N: usize> MedianFilter<N> {
+    /// median over the last `window` inputs, clamped to 1..=N
+    pub fn new(window: usize) -> Self {
+        Self {
+            window: window.clamp(1, N.max(1)),
+            inputs: [0.; N],
+            len: 0,
+            next: 0,
+        }
+    }
+
+    /// inputs the median is taken over once enough arrived
+    pub fn window(&self) -> usize {
+        self.window
+    }
+
+    /// filters `x`, the median of the inputs so far until the window is full. Without
+    /// capacity the input passes through
+    pub fn update(&mut self, x: f32) -> f32 {
+        if x.is_nan() || N == 0 {
+            return x;
+        }
+        self.inputs[self.next] = x;
+        self.next = (self.next + 1) % self.window;
+        self.len = (self.len + 1).min(self.window);
+
+        let mut sorted = [0.; N];
+        let sorted = &mut sorted[..self.len];
+        sorted.copy_from_slice(&self.inputs[..self.len]);
+        sorted.sort_unstable_by(f32::total_cmp);
+        let middle = self.len / 2;
+        match self.len % 2 {
+            1 => sorted[middle],
+            _ => (sorted[middle - 1] + sorted[middle]) / 2.,
+        }
+    }
+
+    /// forgets the inputs
+    pub fn reset(&mut self) {
+        self.len = 0;
+        self.next = 0;
+    }
+}
+
+/// Filter state of one channel, an axis or the temperature
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum ChannelFilter<const N: usize> {
+    /// passes the input through
+    Raw,
+    /// IIR low-pass
+    LowPass(LowPassFilter),
+    /// running median
+    Median(MedianFilter<N>),
+}
+
+impl<const N: usize> ChannelFilter<N> {
+    /// fresh state of `smoothing`
+    pub fn new(smoothing: Smoothing) -> Self {
+        match smoothing {
+            Smoothing::Raw => Self::Raw,
+            Smoothing::LowPass { alpha } => Self::LowPass(LowPassFilter::new(alpha)),
+            Smoothing::Median { window } => Self::Median(MedianFilter::new(window as usize)),
+        }
+    }
+
+    /// filters `x`
+    pub fn update(&mut self, x: f32) -> f32 {
+        match self {
+            Self::Raw => x,
+            Self::LowPass(filter) => filter.update(x),
+            Self::Median(filter) => filter.update(x),
+        }
+    }
+
+    /// forgets the inputs
+    pub fn reset(&mut self) {
+        match self {
+            Self::Raw => {}
+            Self::LowPass(filter) => filter.reset(),
+            Self::Median(filter) => filter.reset(),
+        }
+    }
+}
+
+/// One consumer's smoothed view of the sample stream, see the module docs
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct FilteredView<const N: usize = DEFAULT_MEDIAN_CAPACITY> {
+    spec: FilterSpec,
+    acc: [ChannelFilter<N>; 3],
+    gyro: [ChannelFilter<N>; 3],
+    temp: ChannelFilter<N>,
+    latest: Option<MpuSample>,
+    ingested: u64,
+}
+
+impl<const N: usize> FilteredView<N> {
+    /// view filtering with `spec`, median windows bounded by `N`
+    pub fn new(spec: FilterSpec) -> Self {
+        Self {
+            spec,
+            acc: [ChannelFilter::new(spec.acc); 3],
+            gyro: [ChannelFilter::new(spec.gyro); 3],
+            temp: ChannelFilter::new(spec.temp),
+            latest: None,
+            ingested: 0,
+        }
+    }
+
+    /// smoothing per field group
+    pub fn spec(&self) -> FilterSpec {
+        self.spec
+    }
+
+    /// filters `sample`, returns and keeps the smoothed sample
+    pub fn ingest(&mut self, sample: &MpuSample) -> MpuSample {
+        let filter = |filters: &mut [ChannelFilter<N>; 3], v: Vec3A| {
+            let [x, y, z] = v.to_array();
+            Vec3A::new(
+                filters[0].update(x),
+                filters[1].update(y),
+                filters[2].update(z),
+            )
+        };
+        let smoothed = sample
+            .with_acc(filter(&mut self.acc, sample.acc()))
+            .with_gyro(filter(&mut self.gyro, sample.gyro()))
+            .with_temp(self.temp.update(sample.temp()));
+        self.latest = Some(smoothed);
+        self.ingested += 1;
+        smoothed
+    }
+
+    /// last smoothed sample, None before the first
+    pub fn latest(&self) -> Option<MpuSample> {
+        self.latest
+    }
+
+    /// samples ingested since creation or the last reset
+    pub fn ingested(&self) -> u64 {
+        self.ingested
+    }
+
+    /// forgets the samples, the spec stays
+    pub fn reset(&mut self) {
+        *self = Self::new(self.spec);
+    }
+}
+
+impl<I, D> Mpu6050<I, D> {
+    /// A [`FilteredView`] of [`DEFAULT_MEDIAN_CAPACITY`] filtering with `spec`, fed by
+    /// [`read_into_views`](Self::read_into_views) or [`FilteredView::ingest`]. It does not
+    /// borrow the driver; [`FilteredView::new`] builds views of other capacities
+    pub fn filtered_view(&self, spec: FilterSpec) -> FilteredView {
+        FilteredView::new(spec)
+    }
+}
+
+#[cfg(feature = "driver")]
+impl<I, D, E> Mpu6050<I, D>
+where
+    I: Write<Error = E> + WriteRead<Error = E>,
+{
+    /// Reads accel, temperature and gyro in one burst and feeds every view, returns the raw
+    /// sample. One transaction, plus the range switch while interleaving; a settling sample
+    /// is flagged, not read again
+    pub fn read_into_views<const N: usize>(
+        &mut self,
+        views: &mut [&mut FilteredView<N>],
+    ) -> Result<MpuSample, Mpu6050Error<E>> {
+        let sample = self.read_sample_burst()?;
+        for view in views.iter_mut() {
+            view.ingest(&sample);
+        }
+        Ok(sample)
+    }
+}
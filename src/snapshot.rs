@@ -0,0 +1,490 @@
+//! Read-only view of everything the driver caches, for bug reports.
+//!
+//! [`Mpu6050::debug_state`] copies every cached field into a [`DriverStateSnapshot`] without
+//! touching the bus. Its `Display` output is meant to be pasted into an issue as is.
+//!
+//! Cached hardware settings carry the value of the operation counter
+//! ([`IoStats::transactions`]) at their last write, None if the driver never wrote them and
+//! assumes the builder's value.
+
+use core::fmt;
+
+use glam::Vec3A;
+
+use crate::aux_i2c::SlaveConfig;
+use crate::board::BoardConstraints;
+use crate::calibration::{BackgroundCalibration, BackgroundCalibrationStatus};
+use crate::config_epoch::ConfigChange;
+use crate::config_events::{ConfigObserver, ConfigObserverStatus};
+use crate::connection::{ConnectionMonitor, RESTORED_REGISTERS};
+use crate::continuity::ContinuityBreak;
+use crate::conversion::RoundingMode;
+use crate::device::{AccelRange, ChipCapabilities, ChipIdCheck, GyroRange, LP_WAKE_CTRL};
+use crate::fifo::{FifoSources, MixedReadPolicy};
+use crate::fifo_recovery::FifoRecoveryStatus;
+use crate::governor::{GovernorStatus, PowerGovernor};
+use crate::impact::ImpactRanger;
+use crate::init_findings::InitFindings;
+use crate::interleave::InterleaveState;
+use crate::interrupt::InterruptEdgeTracker;
+use crate::lever_arm::LeverArmCompensator;
+use crate::logging::TraceLevel;
+use crate::metrics::GaugeLimiter;
+use crate::op_bounds::IoStats;
+use crate::parse_policy::{ParseDiagnostics, ParsePolicy};
+use crate::plausibility::{PlausibilityConfig, PlausibilityScorer};
+use crate::power::PowerState;
+use crate::recovery::{BusRecoveryState, BusRecoveryStats};
+use crate::scale::ScaleModel;
+use crate::self_heating::BootTransientModel;
+use crate::settling::{SettleCountdown, SettlingPolicy};
+use crate::skew::{SkewCorrection, SkewCorrector};
+use crate::supervisor::Supervisor;
+use crate::temp_calibration::TempCalibration;
+use crate::tilt::TiltThresholds;
+use crate::validity::CalibrationMonitor;
+use crate::Mpu6050;
+
+/// Operation counter values at the last write of cached hardware settings
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub struct SyncPoints {
+    /// ACCEL_CONFIG FS_SEL
+    pub accel_range: Option<u32>,
+    /// GYRO_CONFIG FS_SEL
+    pub gyro_range: Option<u32>,
+    /// CONFIG DLPF_CFG
+    pub dlpf_cfg: Option<u32>,
+    /// SMPLRT_DIV
+    pub sample_rate_div: Option<u32>,
+}
+
+/// Copy of the driver's cached state
+#[derive(Copy, Clone, Debug)]
+pub struct DriverStateSnapshot {
+    /// i2c address
+    pub slave_addr: u8,
+    /// WHO_AM_I value seen by the last verify or reconnect
+    pub chip_id: Option<u8>,
+    /// register of the latest failed transaction
+    pub failed_register: Option<u8>,
+    /// cached accelerometer range
+    pub accel_range: AccelRange,
+    /// cached gyro range
+    pub gyro_range: GyroRange,
+    /// cached DLPF_CFG
+    pub dlpf_cfg: u8,
+    /// cached SMPLRT_DIV
+    pub sample_rate_div: u8,
+    /// cached cycle mode wake frequency, None in normal operation
+    pub cycle: Option<LP_WAKE_CTRL>,
+    /// configurations likely to alias are refused, see [`aliasing`](crate::aliasing)
+    pub strict_configuration: bool,
+    /// bus sample rate changes are checked against, see [`bus`](crate::bus)
+    pub bus_hz: Option<u32>,
+    /// last writes of the cached hardware settings
+    pub synced: SyncPoints,
+    /// accelerometer scale model
+    pub acc_scale: ScaleModel,
+    /// gyro scale model
+    pub gyro_scale: ScaleModel,
+    /// accelerometer offset
+    pub acc_offset: Vec3A,
+    /// gyro offset
+    pub gyro_offset: Vec3A,
+    /// connection state machine
+    pub connection: ConnectionMonitor,
+    /// last values written to [`RESTORED_REGISTERS`], written back by a reconnect
+    pub restored_registers: [Option<u8>; RESTORED_REGISTERS.len()],
+    /// tilt trust thresholds
+    pub tilt_thresholds: TiltThresholds,
+    /// limit supervisor, None if not installed
+    pub supervisor: Option<Supervisor>,
+    /// power governor state, None if not installed
+    pub governor: Option<GovernorStatus>,
+    /// background calibration progress, None if not running
+    pub background_calibration: Option<BackgroundCalibrationStatus>,
+    /// boot-transient corrector, see [`self_heating`](crate::self_heating)
+    pub boot_transient: Option<BootTransientModel>,
+    /// the corrector's time since power-up in s, None without one
+    pub boot_transient_elapsed_s: Option<f32>,
+    /// configured aux slaves
+    pub aux_slaves: [Option<SlaveConfig>; 4],
+    /// aux layout generation
+    pub aux_generation: u32,
+    /// FIFO sources
+    pub fifo_sources: FifoSources,
+    /// configuration epoch, see [`config_epoch`](crate::config_epoch)
+    pub config_epoch: u32,
+    /// change advancing the epoch last
+    pub last_config_change: Option<ConfigChange>,
+    /// change events waiting and dropped, None without an observer, see
+    /// [`config_events`](crate::config_events)
+    pub config_observer: Option<ConfigObserverStatus>,
+    /// continuity token, see [`continuity`](crate::continuity)
+    pub continuity_token: u32,
+    /// break advancing the token last
+    pub last_continuity_break: Option<ContinuityBreak>,
+    /// the FIFO is enabled
+    pub fifo_streaming: bool,
+    /// direct reads while streaming
+    pub mixed_read_policy: MixedReadPolicy,
+    /// overflows recovered from, see [`fifo_recovery`](crate::fifo_recovery)
+    pub fifo_recovery: FifoRecoveryStatus,
+    /// a FIFO clock is installed
+    pub fifo_clock: bool,
+    /// range interleaving progress, None if not interleaving
+    pub range_interleave: Option<InterleaveState>,
+    /// impact ranging state, None if not ranging
+    pub impact_ranger: Option<ImpactRanger>,
+    /// clock correction ratio, 1 without
+    pub clock_ratio: f32,
+    /// intra-sample skew correction, None if off
+    pub skew_correction: Option<SkewCorrection>,
+    /// lever arm in meters, zero by default
+    pub lever_arm: Vec3A,
+    /// lever-arm compensation state, None if off
+    pub lever_arm_compensation: Option<LeverArmCompensator>,
+    /// bus lockup recovery counters, None if not registered
+    pub bus_recovery: Option<BusRecoveryStats>,
+    /// what the last init found, see [`init_findings`](crate::init_findings)
+    pub init_findings: InitFindings,
+    /// power relevant state, see [`power`](crate::power)
+    pub power: PowerState,
+    /// board constraints, None if unconstrained
+    pub board: Option<BoardConstraints>,
+    /// per-sample plausibility checks, None if off
+    pub plausibility: Option<PlausibilityConfig>,
+    /// structural checks of burst frames, see [`parse_policy`](crate::parse_policy)
+    pub parse_policy: ParsePolicy,
+    /// counters and failures of the checks
+    pub parse_diagnostics: ParseDiagnostics,
+    /// transactions logged, see [`logging`](crate::logging)
+    pub trace_level: TraceLevel,
+    /// built with deferred bus contact and not activated yet
+    pub dormant: bool,
+    /// rounding of conversions to counts
+    pub rounding: RoundingMode,
+    /// calibration policy, metadata and status, see [`validity`](crate::validity)
+    pub calibration_validity: CalibrationMonitor,
+    /// violations of guarded sections, see [`traffic_guard`](crate::traffic_guard)
+    pub traffic_violations: u32,
+    /// temperature calibration, None for the chip's datasheet formula, see
+    /// [`temp_calibration`](crate::temp_calibration)
+    pub temp_calibration: Option<TempCalibration>,
+    /// raw reads of the deny-listed undocumented ranges allowed, see
+    /// [`clone_detect`](crate::clone_detect)
+    pub allow_undocumented: bool,
+    /// WHO_AM_I values accepted by verify and reconnect
+    pub chip_id_check: ChipIdCheck,
+    /// interrupt edge tracker
+    pub interrupt_tracker: InterruptEdgeTracker,
+    /// settling countdowns
+    pub settle: SettleCountdown,
+    /// settling policy
+    pub settling_policy: SettlingPolicy,
+    /// bus usage counters, `transactions` is the operation counter
+    pub io_stats: IoStats,
+    /// capabilities of the connected chip
+    pub capabilities: ChipCapabilities,
+    /// a sample hook is installed, see [`hook`](crate::hook)
+    pub sample_hook: bool,
+    /// a sample tap is installed
+    pub sample_tap: bool,
+    /// a sample observer is installed
+    pub sample_observer: bool,
+    /// the driver owns a delay, see [`delay`](crate::delay)
+    pub owns_delay: bool,
+    /// a metrics sink is installed, see [`metrics`](crate::metrics)
+    pub metrics_sink: bool,
+    /// per-sample gauge limiter, None if gauges are off
+    pub gauge_limiter: Option<GaugeLimiter>,
+}
+
+impl fmt::Display for DriverStateSnapshot {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "mpu6050 driver state")?;
+        writeln!(f, "slave_addr: 0x{:02x}", self.slave_addr)?;
+        match self.chip_id {
+            Some(id) => writeln!(f, "chip_id: 0x{:02x}", id)?,
+            None => writeln!(f, "chip_id: not verified")?,
+        }
+        if let Some(reg) = self.failed_register {
+            writeln!(f, "failed_register: 0x{:02x}", reg)?;
+        }
+        writeln!(
+            f,
+            "accel_range: {:?} (synced at op {:?})",
+            self.accel_range, self.synced.accel_range
+        )?;
+        writeln!(
+            f,
+            "gyro_range: {:?} (synced at op {:?})",
+            self.gyro_range, self.synced.gyro_range
+        )?;
+        writeln!(
+            f,
+            "dlpf_cfg: {} (synced at op {:?})",
+            self.dlpf_cfg, self.synced.dlpf_cfg
+        )?;
+        writeln!(
+            f,
+            "sample_rate_div: {} (synced at op {:?})",
+            self.sample_rate_div, self.synced.sample_rate_div
+        )?;
+        writeln!(f, "cycle: {:?}", self.cycle)?;
+        writeln!(f, "strict_configuration: {}", self.strict_configuration)?;
+        writeln!(f, "bus_hz: {:?}", self.bus_hz)?;
+        writeln!(f, "acc_scale: {:?}", self.acc_scale)?;
+        writeln!(f, "gyro_scale: {:?}", self.gyro_scale)?;
+        writeln!(f, "acc_offset: {:?}", self.acc_offset)?;
+        writeln!(f, "gyro_offset: {:?}", self.gyro_offset)?;
+        writeln!(
+            f,
+            "connection: {:?}, {} consecutive errors, threshold {}, auto {}",
+            self.connection.state(),
+            self.connection.consecutive_errors(),
+            self.connection.threshold(),
+            self.connection.auto()
+        )?;
+        writeln!(f, "restored_registers: {:?}", self.restored_registers)?;
+        writeln!(f, "tilt_thresholds: {:?}", self.tilt_thresholds)?;
+        writeln!(f, "supervisor: {:?}", self.supervisor)?;
+        writeln!(f, "governor: {:?}", self.governor)?;
+        writeln!(
+            f,
+            "background_calibration: {:?}",
+            self.background_calibration
+        )?;
+        writeln!(
+            f,
+            "boot_transient: {:?}, elapsed {:?} s",
+            self.boot_transient, self.boot_transient_elapsed_s
+        )?;
+        writeln!(
+            f,
+            "aux: generation {}, slaves {:?}",
+            self.aux_generation, self.aux_slaves
+        )?;
+        writeln!(
+            f,
+            "config_epoch: {}, last change {:?}",
+            self.config_epoch, self.last_config_change
+        )?;
+        writeln!(f, "config_observer: {:?}", self.config_observer)?;
+        writeln!(
+            f,
+            "continuity_token: {}, last break {:?}",
+            self.continuity_token, self.last_continuity_break
+        )?;
+        writeln!(
+            f,
+            "fifo: sources {:?}, streaming {}, mixed reads {:?}",
+            self.fifo_sources, self.fifo_streaming, self.mixed_read_policy
+        )?;
+        writeln!(
+            f,
+            "fifo_recovery: {:?}, clock {}",
+            self.fifo_recovery, self.fifo_clock
+        )?;
+        writeln!(f, "range_interleave: {:?}", self.range_interleave)?;
+        writeln!(f, "impact_ranger: {:?}", self.impact_ranger)?;
+        writeln!(f, "clock_ratio: {}", self.clock_ratio)?;
+        writeln!(f, "skew_correction: {:?}", self.skew_correction)?;
+        writeln!(f, "lever_arm: {:?}", self.lever_arm)?;
+        writeln!(
+            f,
+            "lever_arm_compensation: {:?}",
+            self.lever_arm_compensation
+        )?;
+        writeln!(f, "bus_recovery: {:?}", self.bus_recovery)?;
+        writeln!(f, "init_findings: {:?}", self.init_findings)?;
+        writeln!(f, "power: {:?}", self.power)?;
+        writeln!(f, "board: {:?}", self.board)?;
+        writeln!(f, "plausibility: {:?}", self.plausibility)?;
+        writeln!(f, "parse_policy: {:?}", self.parse_policy)?;
+        writeln!(
+            f,
+            "parse_diagnostics: {} checked, {} failed",
+            self.parse_diagnostics.checked(),
+            self.parse_diagnostics.failed()
+        )?;
+        writeln!(f, "trace_level: {:?}", self.trace_level)?;
+        writeln!(f, "dormant: {}", self.dormant)?;
+        writeln!(f, "rounding: {:?}", self.rounding)?;
+        writeln!(f, "calibration_validity: {:?}", self.calibration_validity)?;
+        writeln!(f, "traffic_violations: {}", self.traffic_violations)?;
+        writeln!(f, "temp_calibration: {:?}", self.temp_calibration)?;
+        writeln!(f, "allow_undocumented: {}", self.allow_undocumented)?;
+        writeln!(f, "chip_id_check: {:?}", self.chip_id_check)?;
+        writeln!(f, "capabilities: {:?}", self.capabilities)?;
+        writeln!(
+            f,
+            "sample_hook: {}, sample_tap: {}, sample_observer: {}",
+            self.sample_hook, self.sample_tap, self.sample_observer
+        )?;
+        writeln!(f, "owns_delay: {}", self.owns_delay)?;
+        writeln!(
+            f,
+            "metrics_sink: {}, gauge_limiter: {:?}",
+            self.metrics_sink, self.gauge_limiter
+        )?;
+        writeln!(f, "interrupt_tracker: {:?}", self.interrupt_tracker)?;
+        writeln!(
+            f,
+            "settling: {:?}, countdown acc {} gyro {}",
+            self.settling_policy, self.settle.acc, self.settle.gyro
+        )?;
+        write!(
+            f,
+            "io: {} transactions, {} bytes",
+            self.io_stats.transactions, self.io_stats.bytes
+        )
+    }
+}
+
+impl<I, D> Mpu6050<I, D> {
+    /// Copy of all cached driver state, no bus access
+    pub fn debug_state(&self) -> DriverStateSnapshot {
+        // exhaustive on purpose: a field added to Mpu6050 fails to compile here until it is
+        // added to the snapshot (or explicitly ignored, like the bus handle)
+        let Mpu6050 {
+            i2c: _,
+            delay,
+            slave_addr,
+            accel_range,
+            gyro_range,
+            acc_scale,
+            gyro_scale,
+            gyro_offset,
+            acc_offset,
+            connection,
+            shadow,
+            chip_id,
+            failed_register,
+            tilt_thresholds,
+            supervisor,
+            governor,
+            background_calibration,
+            boot_transient,
+            aux,
+            fifo_sources,
+            config_epoch,
+            last_config_change,
+            config_observer,
+            change_source: _,
+            continuity_token,
+            last_continuity_break,
+            dlpf_cfg,
+            sample_rate_div,
+            cycle,
+            strict_configuration,
+            bus_hz,
+            interrupt_tracker,
+            settle,
+            settling_policy,
+            io_stats,
+            synced,
+            capabilities,
+            sample_hook,
+            sample_tap,
+            sample_observer,
+            metrics,
+            gauge_limiter,
+            fifo_streaming,
+            mixed_read_policy,
+            fifo_timeline: _,
+            interleave,
+            impact,
+            clock_ratio,
+            skew,
+            lever_arm,
+            lever_arm_compensation,
+            bus_recovery,
+            init_findings,
+            power,
+            board,
+            plausibility,
+            parse_policy,
+            parse_diagnostics,
+            trace_level,
+            dormant,
+            rounding,
+            validity,
+            traffic,
+            temp_calibration,
+            allow_undocumented,
+            chip_id_check,
+        } = self;
+        DriverStateSnapshot {
+            slave_addr: *slave_addr,
+            chip_id: *chip_id,
+            failed_register: *failed_register,
+            accel_range: *accel_range,
+            gyro_range: *gyro_range,
+            dlpf_cfg: *dlpf_cfg,
+            sample_rate_div: *sample_rate_div,
+            cycle: *cycle,
+            strict_configuration: *strict_configuration,
+            bus_hz: *bus_hz,
+            synced: *synced,
+            acc_scale: *acc_scale,
+            gyro_scale: *gyro_scale,
+            acc_offset: *acc_offset,
+            gyro_offset: *gyro_offset,
+            connection: *connection,
+            restored_registers: shadow.values(),
+            tilt_thresholds: *tilt_thresholds,
+            supervisor: *supervisor,
+            governor: governor.as_ref().map(PowerGovernor::status),
+            background_calibration: background_calibration
+                .as_ref()
+                .map(BackgroundCalibration::status),
+            boot_transient: boot_transient.map(|transient| transient.model),
+            boot_transient_elapsed_s: boot_transient.map(|transient| transient.elapsed_s),
+            aux_slaves: aux.slaves(),
+            aux_generation: aux.generation(),
+            fifo_sources: *fifo_sources,
+            config_epoch: *config_epoch,
+            last_config_change: *last_config_change,
+            config_observer: config_observer.as_ref().map(ConfigObserver::status),
+            continuity_token: *continuity_token,
+            last_continuity_break: *last_continuity_break,
+            fifo_streaming: *fifo_streaming,
+            mixed_read_policy: *mixed_read_policy,
+            fifo_recovery: self.fifo_recovery_status(),
+            fifo_clock: self.get_fifo_clock().is_some(),
+            range_interleave: *interleave,
+            impact_ranger: *impact,
+            clock_ratio: *clock_ratio,
+            skew_correction: skew.as_ref().map(SkewCorrector::correction),
+            lever_arm: *lever_arm,
+            lever_arm_compensation: *lever_arm_compensation,
+            bus_recovery: bus_recovery.as_ref().map(BusRecoveryState::stats),
+            init_findings: *init_findings,
+            power: *power,
+            board: *board,
+            plausibility: plausibility.as_ref().map(PlausibilityScorer::config),
+            parse_policy: *parse_policy,
+            parse_diagnostics: *parse_diagnostics,
+            trace_level: *trace_level,
+            dormant: *dormant,
+            rounding: *rounding,
+            calibration_validity: *validity,
+            traffic_violations: traffic.violations(),
+            temp_calibration: *temp_calibration,
+            allow_undocumented: *allow_undocumented,
+            chip_id_check: *chip_id_check,
+            interrupt_tracker: *interrupt_tracker,
+            settle: *settle,
+            settling_policy: *settling_policy,
+            io_stats: *io_stats,
+            capabilities: *capabilities,
+            sample_hook: sample_hook.is_some(),
+            sample_tap: sample_tap.is_some(),
+            sample_observer: sample_observer.is_some(),
+            owns_delay: delay.is_some(),
+            metrics_sink: metrics.is_some(),
+            gauge_limiter: *gauge_limiter,
+        }
+    }
+}
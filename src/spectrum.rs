@@ -0,0 +1,356 @@
+//! Vibration spectrum of one accelerometer value, feature `spectrum`.
+//!
+//! [`SpectrumAnalyzer`] keeps the last `N` samples of an axis or of the magnitude and
+//! computes their spectrum with a radix-2 FFT: mean removed, Hann window, `N / 2 + 1`
+//! magnitude bins from DC to half the sample rate, `sample_rate_hz / N` apart. Magnitudes are
+//! amplitudes in g, corrected for the coherent gain of the window: a tone of amplitude `A`
+//! exactly on a bin reads `A` there and `A / 2` in both neighbours, a tone halfway between
+//! two bins reads about `0.85 A` in both (scalloping loss of the Hann window). A tone at
+//! exactly half the sample rate has a single neighbour, reading `A` like the Nyquist bin.
+//!
+//! [`dominant_frequency`](SpectrumAnalyzer::dominant_frequency) answers "what frequency
+//! is this vibration", [`peaks`](SpectrumAnalyzer::peaks) lists the strongest local maxima.
+//! The resolution is the bin spacing; a tone between bins is reported at one of its two
+//! neighbours.
+//!
+//! ```
+//! use mpu6050::spectrum::{SpectrumAnalyzer, SpectrumSource};
+//!
+//! // 125 Hz vibration on Z sampled at 1 kHz, 0.2g on top of gravity
+//! let mut analyzer = SpectrumAnalyzer::<256>::new(SpectrumSource::AccelZ);
+//! for n in 0..256 {
+//!     let t = n as f32 / 1000.;
+//!     analyzer.push(1. + 0.2 * (2. * core::f32::consts::PI * 125. * t).sin());
+//! }
+//! analyzer.compute().unwrap();
+//! let (hz, magnitude) = analyzer.dominant_frequency(1000.);
+//! assert_eq!(hz, 125.);
+//! assert!((magnitude - 0.2).abs() < 1e-4);
+//! ```
+//!
+//! #### Memory
+//! No allocation and no stack buffers, the transform runs in place: an analyzer is two
+//! `[f32; N]` (samples and work area, which holds the magnitudes afterwards) plus four words,
+//! 2 KiB at `N = 256` and 4 KiB at `N = 512`. A [`compute`](SpectrumAnalyzer::compute) costs
+//! `N / 4 * log2(N / 2)` complex butterflies and `N / 2` square roots. Only `core` is used,
+//! square roots and twiddle factors included, so the feature builds `no_std` alongside
+//! `minimal`.
+
+#[cfg(feature = "driver")]
+use embedded_hal::blocking::i2c::{Write, WriteRead};
+
+#[cfg(feature = "driver")]
+use crate::{Mpu6050, Mpu6050Error};
+
+/// Accelerometer value fed to a [`SpectrumAnalyzer`]
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum SpectrumSource {
+    /// X axis
+    AccelX,
+    /// Y axis
+    AccelY,
+    /// Z axis
+    AccelZ,
+    /// length of the acceleration vector, independent of the orientation
+    AccelMagnitude,
+}
+
+impl SpectrumSource {
+    /// the value of `acc`, in g
+    pub fn select(self, acc: [f32; 3]) -> f32 {
+        match self {
+            SpectrumSource::AccelX => acc[0],
+            SpectrumSource::AccelY => acc[1],
+            SpectrumSource::AccelZ => acc[2],
+            SpectrumSource::AccelMagnitude => {
+                let [x, y, z] = acc.map(f64::from);
+                sqrt(x * x + y * y + z * z) as f32
+            }
+        }
+    }
+}
+
+/// Local maximum of the spectrum
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Peak {
+    /// bin index, 0 is DC
+    pub bin: usize,
+    /// bin center frequency
+    pub frequency_hz: f32,
+    /// amplitude in g
+    pub magnitude: f32,
+}
+
+/// Spectrum of the last `N` samples, `N` a power of two of at least 4
+#[derive(Clone, Debug)]
+pub struct SpectrumAnalyzer<const N: usize> {
+    source: SpectrumSource,
+    samples: [f32; N],
+    // next slot of `samples`, the oldest sample once full
+    head: usize,
+    len: usize,
+    // FFT work area, the magnitudes in the first N / 2 + 1 entries after `compute`
+    bins: [f32; N],
+    computed: bool,
+}
+
+impl<const N: usize> SpectrumAnalyzer<N> {
+    /// Empty analyzer of `source`. Panics unless `N` is a power of two of at least 4
+    pub const fn new(source: SpectrumSource) -> Self {
+        assert!(
+            N >= 4 && N.is_power_of_two(),
+            "N must be a power of two >= 4"
+        );
+        Self {
+            source,
+            samples: [0.; N],
+            head: 0,
+            len: 0,
+            bins: [0.; N],
+            computed: false,
+        }
+    }
+
+    /// value taken from the accelerometer by [`Mpu6050::feed_spectrum`]
+    pub fn source(&self) -> SpectrumSource {
+        self.source
+    }
+
+    /// adds a sample in g, replacing the oldest once `N` are held
+    pub fn push(&mut self, sample: f32) {
+        self.samples[self.head] = sample;
+        self.head = (self.head + 1) % N;
+        self.len = (self.len + 1).min(N);
+    }
+
+    /// number of samples held
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// no samples held
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// `N` samples held, [`compute`](Self::compute) succeeds
+    pub fn is_full(&self) -> bool {
+        self.len == N
+    }
+
+    /// forgets samples and spectrum
+    pub fn clear(&mut self) {
+        self.head = 0;
+        self.len = 0;
+        self.computed = false;
+    }
+
+    /// Computes the spectrum of the last `N` samples, None while fewer are held. The
+    /// magnitudes stay available through [`magnitudes`](Self::magnitudes) until the next
+    /// compute
+    pub fn compute(&mut self) -> Option<&[f32]> {
+        if !self.is_full() {
+            return None;
+        }
+        let mean = self.samples.iter().map(|s| f64::from(*s)).sum::<f64>() / N as f64;
+        // periodic Hann window, cos(2πn / N) by rotation
+        let (uc, us) = unit(N);
+        let (mut c, mut s) = (1., 0.);
+        for n in 0..N {
+            let sample = f64::from(self.samples[(self.head + n) % N]);
+            self.bins[n] = ((sample - mean) * (0.5 - 0.5 * c)) as f32;
+            (c, s) = (c * uc - s * us, c * us + s * uc);
+        }
+        real_fft(&mut self.bins);
+        // amplitude: twice the bin over the window sum N / 2, DC and Nyquist once
+        let m = N / 2;
+        let edge = 2. / N as f64;
+        let dc = (f64::from(self.bins[0]) * edge).abs();
+        let nyquist = (f64::from(self.bins[1]) * edge).abs();
+        for k in 1..m {
+            // k < 2k: entry k belongs to a bin already done
+            let (re, im) = (f64::from(self.bins[2 * k]), f64::from(self.bins[2 * k + 1]));
+            self.bins[k] = (sqrt(re * re + im * im) * 2. * edge) as f32;
+        }
+        self.bins[0] = dc as f32;
+        self.bins[m] = nyquist as f32;
+        self.computed = true;
+        Some(self.magnitudes())
+    }
+
+    /// magnitudes of the last [`compute`](Self::compute), `N / 2 + 1` bins from DC to
+    /// Nyquist, empty before the first
+    pub fn magnitudes(&self) -> &[f32] {
+        if self.computed {
+            &self.bins[..=N / 2]
+        } else {
+            &[]
+        }
+    }
+
+    /// center frequency of `bin`
+    pub fn bin_frequency(&self, bin: usize, sample_rate_hz: f32) -> f32 {
+        (bin as f64 * f64::from(sample_rate_hz) / N as f64) as f32
+    }
+
+    /// Frequency and magnitude of the strongest bin above DC, the lower one of equal bins.
+    /// `(0., 0.)` before the first [`compute`](Self::compute)
+    pub fn dominant_frequency(&self, sample_rate_hz: f32) -> (f32, f32) {
+        let mut best = (0, 0.);
+        for (bin, magnitude) in self.magnitudes().iter().enumerate().skip(1) {
+            if *magnitude > best.1 {
+                best = (bin, *magnitude);
+            }
+        }
+        match best {
+            (0, _) => (0., 0.),
+            (bin, magnitude) => (self.bin_frequency(bin, sample_rate_hz), magnitude),
+        }
+    }
+
+    /// The `K` strongest local maxima above DC, strongest first. A maximum spanning two
+    /// equal bins counts once, at the lower one
+    pub fn peaks<const K: usize>(&self, sample_rate_hz: f32) -> [Option<Peak>; K] {
+        let mut peaks = [None; K];
+        let bins = self.magnitudes();
+        for bin in 1..bins.len() {
+            let magnitude = bins[bin];
+            let rising = magnitude > bins[bin - 1];
+            let falling = bins.get(bin + 1).is_none_or(|next| magnitude >= *next);
+            if !(rising && falling) {
+                continue;
+            }
+            let peak = Peak {
+                bin,
+                frequency_hz: self.bin_frequency(bin, sample_rate_hz),
+                magnitude,
+            };
+            // insertion into the sorted list, equal magnitudes keep the lower bin first
+            let Some(at) = peaks
+                .iter()
+                .position(|p: &Option<Peak>| p.is_none_or(|p| p.magnitude < magnitude))
+            else {
+                continue;
+            };
+            peaks[at..].rotate_right(1);
+            peaks[at] = Some(peak);
+        }
+        peaks
+    }
+}
+
+/// In place FFT of the real `buf`, read as `N / 2` complex values `buf[2n] + i buf[2n + 1]`.
+/// Afterwards `buf[2k], buf[2k + 1]` is bin `k` for `0 < k < N / 2`, `buf[0]` is DC and
+/// `buf[1]` Nyquist, both real
+fn real_fft(buf: &mut [f32]) {
+    let m = buf.len() / 2;
+    complex_fft(buf);
+    // split the transform of the packed even and odd samples:
+    // X[k] = E[k] + W^k O[k], X[m - k] = conj(E[k] - W^k O[k]), W = e^(-2πi / N)
+    let (uc, us) = unit(buf.len());
+    let (mut wr, mut wi) = (1., 0.);
+    for k in 1..=m / 2 {
+        (wr, wi) = (wr * uc + wi * us, wi * uc - wr * us);
+        let (a, b) = (2 * k, 2 * (m - k));
+        let (zr, zi) = (f64::from(buf[a]), f64::from(buf[a + 1]));
+        let (yr, yi) = (f64::from(buf[b]), f64::from(buf[b + 1]));
+        let (er, ei) = ((zr + yr) / 2., (zi - yi) / 2.);
+        let (or, oi) = ((zi + yi) / 2., (yr - zr) / 2.);
+        let (tr, ti) = (wr * or - wi * oi, wr * oi + wi * or);
+        buf[b] = (er - tr) as f32;
+        buf[b + 1] = (ti - ei) as f32;
+        buf[a] = (er + tr) as f32;
+        buf[a + 1] = (ei + ti) as f32;
+    }
+    let (zr, zi) = (buf[0], buf[1]);
+    buf[0] = zr + zi;
+    buf[1] = zr - zi;
+}
+
+/// In place radix-2 decimation in time FFT of `buf.len() / 2` interleaved complex values
+fn complex_fft(buf: &mut [f32]) {
+    let m = buf.len() / 2;
+    let mut j = 0;
+    for i in 1..m {
+        let mut bit = m >> 1;
+        while j & bit != 0 {
+            j ^= bit;
+            bit >>= 1;
+        }
+        j |= bit;
+        if i < j {
+            buf.swap(2 * i, 2 * j);
+            buf.swap(2 * i + 1, 2 * j + 1);
+        }
+    }
+    let mut len = 2;
+    while len <= m {
+        let (uc, us) = unit(len);
+        for start in (0..m).step_by(len) {
+            let (mut wr, mut wi) = (1f64, 0f64);
+            for j in start..start + len / 2 {
+                let (a, b) = (2 * j, 2 * (j + len / 2));
+                let (w_re, w_im) = (wr as f32, wi as f32);
+                let tr = buf[b] * w_re - buf[b + 1] * w_im;
+                let ti = buf[b] * w_im + buf[b + 1] * w_re;
+                buf[b] = buf[a] - tr;
+                buf[b + 1] = buf[a + 1] - ti;
+                buf[a] += tr;
+                buf[a + 1] += ti;
+                // times e^(-2πi / len)
+                (wr, wi) = (wr * uc + wi * us, wi * uc - wr * us);
+            }
+        }
+        len *= 2;
+    }
+}
+
+/// `(cos, sin)` of `2π / n` for a power of two `n`, by halving the angle from a full turn:
+/// cos θ/2 = √((1 + cos θ) / 2), sin θ/2 = sin θ / (2 cos θ/2)
+fn unit(n: usize) -> (f64, f64) {
+    let (mut c, mut s) = (1., 0.);
+    let mut turn = 1;
+    while turn < n {
+        turn *= 2;
+        (c, s) = match turn {
+            2 => (-1., 0.),
+            4 => (0., 1.),
+            _ => {
+                let half = sqrt((1. + c) / 2.);
+                (half, s / (2. * half))
+            }
+        };
+    }
+    (c, s)
+}
+
+/// square root by Newton's method, `core` has none
+fn sqrt(x: f64) -> f64 {
+    if x <= 0. || !x.is_finite() {
+        return x.max(0.);
+    }
+    // halved exponent, within a few percent
+    let mut y = f64::from_bits((x.to_bits() >> 1) + 0x1ff8_0000_0000_0000);
+    for _ in 0..5 {
+        y = 0.5 * (y + x / y);
+    }
+    y
+}
+
+#[cfg(feature = "driver")]
+impl<I, D, E> Mpu6050<I, D>
+where
+    I: Write<Error = E> + WriteRead<Error = E>,
+{
+    /// Reads the accelerometer like [`get_acc`](Self::get_acc), one burst of the three axes,
+    /// and pushes the value selected by the analyzer's [`SpectrumSource`]. Returns whether
+    /// the analyzer holds a full window
+    pub fn feed_spectrum<const N: usize>(
+        &mut self,
+        analyzer: &mut SpectrumAnalyzer<N>,
+    ) -> Result<bool, Mpu6050Error<E>> {
+        let acc = self.get_acc()?;
+        analyzer.push(analyzer.source().select(acc.to_array()));
+        Ok(analyzer.is_full())
+    }
+}
@@ -0,0 +1,293 @@
+//! Fan-out of one sample stream to consumers at their own rates.
+//!
+//! Firmware with a 1 kHz attitude loop, a 100 Hz logger and 10 Hz telemetry drains the FIFO
+//! once. A [`StreamSplitter`] takes each sample and hands it to every consumer whose
+//! [`Decimator`] takes it, into the consumer's own bounded queue; each consumer drains its
+//! queue with [`consumer`](StreamSplitter::consumer) when it runs. A sample is copied into
+//! the queues taking it only, the 10 Hz queue sees one sample in a hundred. Everything lives
+//! in the splitter, `CONSUMERS` queues of `DEPTH` samples each, nothing is allocated.
+//! [`Mpu6050::drain_fifo_into`] drains the FIFO into a splitter in one call.
+//!
+//! #### Decimation
+//! A decimator of factor n takes the first sample and every nth after it, unchanged: a
+//! consumer of factor 1 sees the input bit for bit. A factor of 0 is treated as 1.
+//!
+//! #### Overflow
+//! A consumer not draining in time fills its queue. What happens to a sample for a full queue
+//! is explicit per consumer, and counted in [`ConsumerStats::dropped`]:
+//!
+//! | policy | full queue |
+//! |:---|:---|
+//! | [`DropOldest`](OverflowPolicy::DropOldest) | the oldest queued sample is discarded, the new one queued: the queue holds the latest |
+//! | [`DropNewest`](OverflowPolicy::DropNewest) | the new sample is discarded: the queue holds the samples from before it filled |
+//!
+//! The producer never waits on a consumer, blocking is not an option.
+//! ```
+//! use mpu6050::split::*;
+//! use mpu6050::{MpuSample, Vec3A};
+//!
+//! let mut splitter = StreamSplitter::<3, 16>::new([
+//!     ConsumerSpec::new(1, OverflowPolicy::DropOldest),   // attitude, 1 kHz
+//!     ConsumerSpec::new(10, OverflowPolicy::DropOldest),  // logging, 100 Hz
+//!     ConsumerSpec::new(100, OverflowPolicy::DropNewest), // telemetry, 10 Hz
+//! ]);
+//! for _ in 0..10 {
+//!     splitter.ingest(&MpuSample::new(Vec3A::Z, Vec3A::ZERO, 25.));
+//! }
+//! assert_eq!(splitter.consumer(0).count(), 10);
+//! assert_eq!(splitter.consumer(1).count(), 1);
+//! assert_eq!(splitter.consumer(2).count(), 1);
+//! assert_eq!(splitter.stats(0).dropped, 0);
+//! ```
+
+#[cfg(feature = "driver")]
+use embedded_hal::blocking::i2c::{Write, WriteRead};
+use glam::Vec3A;
+
+#[cfg(feature = "driver")]
+use crate::fifo::{FifoFrame, FifoSchema};
+#[cfg(feature = "driver")]
+use crate::provenance::{FieldGroup, Provenance, SampleProvenance};
+use crate::MpuSample;
+#[cfg(feature = "driver")]
+use crate::{Mpu6050, Mpu6050Error};
+
+/// Keeps the first input and every `factor`th after it
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct Decimator {
+    factor: u32,
+    phase: u32,
+}
+
+impl Decimator {
+    /// decimator of `factor`, 0 is treated as 1
+    pub const fn new(factor: u32) -> Self {
+        Self {
+            factor: if factor == 0 { 1 } else { factor },
+            phase: 0,
+        }
+    }
+
+    /// inputs per kept one
+    pub const fn factor(&self) -> u32 {
+        self.factor
+    }
+
+    /// advances by one input, true if it is kept
+    pub fn accept(&mut self) -> bool {
+        let keep = self.phase == 0;
+        self.phase += 1;
+        if self.phase == self.factor {
+            self.phase = 0;
+        }
+        keep
+    }
+
+    /// the next input is kept
+    pub fn reset(&mut self) {
+        self.phase = 0;
+    }
+}
+
+/// What a full queue does with a new sample, see the module docs
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum OverflowPolicy {
+    /// discard the oldest queued sample for the new one
+    DropOldest,
+    /// discard the new sample
+    DropNewest,
+}
+
+/// Rate and overflow policy of one consumer
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct ConsumerSpec {
+    /// inputs per sample taken, see [`Decimator`]
+    pub decimation: u32,
+    /// what a full queue does
+    pub overflow: OverflowPolicy,
+}
+
+impl ConsumerSpec {
+    /// consumer taking every `decimation`th sample
+    pub const fn new(decimation: u32, overflow: OverflowPolicy) -> Self {
+        Self {
+            decimation,
+            overflow,
+        }
+    }
+}
+
+/// Counters of one consumer since the splitter was created or reset
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub struct ConsumerStats {
+    /// samples its decimator took, dropped ones included
+    pub accepted: u64,
+    /// samples discarded by the overflow policy
+    pub dropped: u64,
+    /// samples waiting in the queue
+    pub queued: usize,
+}
+
+/// Ring buffer of one consumer
+#[derive(Copy, Clone, Debug)]
+struct Queue<const DEPTH: usize> {
+    samples: [MpuSample; DEPTH],
+    head: usize,
+    len: usize,
+}
+
+impl<const DEPTH: usize> Queue<DEPTH> {
+    const EMPTY: Self = Self {
+        samples: [MpuSample::new(Vec3A::ZERO, Vec3A::ZERO, 0.); DEPTH],
+        head: 0,
+        len: 0,
+    };
+
+    /// queues `sample`, returns false if it was dropped or displaced one
+    fn push(&mut self, sample: &MpuSample, overflow: OverflowPolicy) -> bool {
+        if DEPTH == 0 {
+            return false;
+        }
+        let full = self.len == DEPTH;
+        if full {
+            if overflow == OverflowPolicy::DropNewest {
+                return false;
+            }
+            self.pop();
+        }
+        self.samples[(self.head + self.len) % DEPTH] = *sample;
+        self.len += 1;
+        !full
+    }
+
+    fn pop(&mut self) -> Option<MpuSample> {
+        if self.len == 0 {
+            return None;
+        }
+        let sample = self.samples[self.head];
+        self.head = (self.head + 1) % DEPTH;
+        self.len -= 1;
+        Some(sample)
+    }
+}
+
+/// One consumer's decimator, queue and counters
+#[derive(Copy, Clone, Debug)]
+struct Consumer<const DEPTH: usize> {
+    spec: ConsumerSpec,
+    decimator: Decimator,
+    queue: Queue<DEPTH>,
+    accepted: u64,
+    dropped: u64,
+}
+
+/// Fan-out of a sample stream to `CONSUMERS` queues of `DEPTH` samples, see the module docs
+#[derive(Clone, Debug)]
+pub struct StreamSplitter<const CONSUMERS: usize, const DEPTH: usize> {
+    consumers: [Consumer<DEPTH>; CONSUMERS],
+    ingested: u64,
+}
+
+impl<const CONSUMERS: usize, const DEPTH: usize> StreamSplitter<CONSUMERS, DEPTH> {
+    /// splitter with one consumer per spec, in order
+    pub fn new(specs: [ConsumerSpec; CONSUMERS]) -> Self {
+        Self {
+            consumers: specs.map(|spec| Consumer {
+                spec,
+                decimator: Decimator::new(spec.decimation),
+                queue: Queue::EMPTY,
+                accepted: 0,
+                dropped: 0,
+            }),
+            ingested: 0,
+        }
+    }
+
+    /// spec of consumer `idx`. Panics if `idx >= CONSUMERS`
+    pub fn spec(&self, idx: usize) -> ConsumerSpec {
+        self.consumers[idx].spec
+    }
+
+    /// hands `sample` to every consumer whose decimator takes it
+    pub fn ingest(&mut self, sample: &MpuSample) {
+        self.ingested += 1;
+        for consumer in &mut self.consumers {
+            if !consumer.decimator.accept() {
+                continue;
+            }
+            consumer.accepted += 1;
+            if !consumer.queue.push(sample, consumer.spec.overflow) {
+                consumer.dropped += 1;
+            }
+        }
+    }
+
+    /// Drains the queue of consumer `idx`, oldest first. Samples not taken from the iterator
+    /// stay queued. Panics if `idx >= CONSUMERS`
+    pub fn consumer(&mut self, idx: usize) -> impl Iterator<Item = MpuSample> + '_ {
+        let queue = &mut self.consumers[idx].queue;
+        core::iter::from_fn(move || queue.pop())
+    }
+
+    /// counters of consumer `idx`. Panics if `idx >= CONSUMERS`
+    pub fn stats(&self, idx: usize) -> ConsumerStats {
+        let consumer = &self.consumers[idx];
+        ConsumerStats {
+            accepted: consumer.accepted,
+            dropped: consumer.dropped,
+            queued: consumer.queue.len,
+        }
+    }
+
+    /// samples ingested since creation or the last reset
+    pub fn ingested(&self) -> u64 {
+        self.ingested
+    }
+
+    /// empties the queues, zeroes the counters and restarts the decimators, the specs stay
+    pub fn reset(&mut self) {
+        *self = Self::new(self.consumers.map(|consumer| consumer.spec));
+    }
+}
+
+/// `frame` as a sample in g, rad/s and °C with the sensitivities of its schema, offsets not
+/// applied. Parts not in the frame are NaN and tagged [`Provenance::Invalid`]
+#[cfg(feature = "driver")]
+fn frame_sample(frame: &FifoFrame) -> MpuSample {
+    let tag = |read: bool| match read {
+        true => Provenance::Measured,
+        false => Provenance::Invalid,
+    };
+    let (acc, gyro, temp) = (frame.acc_g(), frame.gyro_rad_s(), frame.temp_c());
+    let provenance = SampleProvenance::MEASURED
+        .with(FieldGroup::Acc, tag(acc.is_some()))
+        .with(FieldGroup::Gyro, tag(gyro.is_some()))
+        .with(FieldGroup::Temp, tag(temp.is_some()));
+    MpuSample::new(
+        acc.unwrap_or(Vec3A::NAN),
+        gyro.unwrap_or(Vec3A::NAN),
+        temp.unwrap_or(f32::NAN),
+    )
+    .with_provenance(provenance)
+}
+
+#[cfg(feature = "driver")]
+impl<I, D, E> Mpu6050<I, D>
+where
+    I: Write<Error = E> + WriteRead<Error = E>,
+{
+    /// [`drain_fifo`](Self::drain_fifo) ingesting every frame into `splitter`, returns the
+    /// number of frames. The frames are scaled with the schema's sensitivities, offsets not
+    /// applied, like [`FifoFrame`]; parts not in the frame are NaN and tagged invalid. Every
+    /// sample passes the [`hook`](crate::hook) before it is ingested
+    pub fn drain_fifo_into<const CONSUMERS: usize, const DEPTH: usize>(
+        &mut self,
+        schema: &FifoSchema,
+        buf: &mut [u8],
+        splitter: &mut StreamSplitter<CONSUMERS, DEPTH>,
+    ) -> Result<usize, Mpu6050Error<E>> {
+        self.drain_fifo_with(schema, buf, |mpu, frame| {
+            splitter.ingest(&mpu.deliver(frame_sample(&frame)))
+        })
+    }
+}
@@ -0,0 +1,281 @@
+//! Stages of the scaled reads, and the `minimal-pipeline` build without the optional ones.
+//!
+//! A scaled read, [`get_acc`](Mpu6050::get_acc), [`get_gyro`](Mpu6050::get_gyro),
+//! [`get_temp`](Mpu6050::get_temp), [`get_all`](Mpu6050::get_all), a sample of the
+//! [`sampling`](crate::sampling) loop, of [`read_into_views`](Mpu6050::read_into_views) or of
+//! [`read_batch`](Mpu6050::read_batch), parses the counts, scales them and applies the
+//! offsets. Around that the full build runs the optional stages, each a branch on its
+//! runtime configuration:
+//!
+//! | order | stage | module | `minimal-pipeline` |
+//! |:---|:---|:---|:---|
+//! | 1 | out of band check while FIFO streaming | [`fifo`](crate::fifo) | not checked, never flagged |
+//! | 2 | range interleaving | [`interleave`](crate::interleave) | no switches, samples untagged |
+//! | 3 | impact ranging | [`impact`](crate::impact) | no switches, samples untagged |
+//! | 4 | frame checks of bursts | [`parse_policy`](crate::parse_policy) | not checked, every frame parsed |
+//! | 5 | clipping metrics | [`metrics`](crate::metrics) | not emitted |
+//! | 6 | settling flags and retries | [`settling`](crate::settling) | no flags, no retries, the countdown stands still |
+//! | 7 | boot-transient correction | [`self_heating`](crate::self_heating) | gyro as read |
+//! | 8 | supervisor | [`supervisor`](crate::supervisor) | not checked |
+//! | 9 | background gyro calibration | [`calibration`](crate::calibration) | not fed |
+//! | 10 | skew correction | [`skew`](crate::skew) | values at their own instants |
+//! | 11 | lever-arm compensation | [`lever_arm`](crate::lever_arm) | accelerometer as read |
+//! | 12 | power governor | [`governor`](crate::governor) | not fed |
+//! | 13 | calibration validity | [`validity`](crate::validity) | not observed |
+//! | 14 | plausibility | [`plausibility`](crate::plausibility) | not scored |
+//! | 15 | hook and tap | [`hook`](crate::hook) | not called |
+//! | 16 | sample gauges | [`metrics`](crate::metrics) | not emitted |
+//!
+//! The `minimal-pipeline` feature is for builds audited for hard realtime: the stages are
+//! compiled out, not skipped, and a scaled read is the transaction and parse, offset, scale,
+//! return, with no branch on a runtime option. Their configuration calls stay and are
+//! accepted, so code written against the full build compiles, but nothing in a scaled read
+//! reads what they set. [`MpuSample`] carries the values only: the `with_*` calls of flags,
+//! provenance, time references, scores and lever-arm corrections return the sample
+//! unchanged and the accessors return the defaults of [`MpuSample::new`], for driver reads
+//! and FIFO frames alike. Settling policies add no reads, so the bounds in
+//! [`op_bounds`](crate::op_bounds) are those of
+//! [`SettlingPolicy::Ignore`](crate::settling::SettlingPolicy::Ignore) for every policy. The
+//! feature implies `no-guards`, see [`traffic_guard`](crate::traffic_guard). The bus
+//! bookkeeping of every transaction, [`io_stats`](Mpu6050::io_stats), the connection monitor
+//! and the dormant checks, is not a stage and stays.
+//!
+//! Both compositions are below, one per build, in the order of the table. With every option
+//! off the two return the same bits, `tests/minimal_pipeline.rs` checks both builds against
+//! the same digest:
+//! ```text
+//! cargo test --features minimal-pipeline --test minimal_pipeline
+//! ```
+
+#[cfg(feature = "driver")]
+use embedded_hal::blocking::i2c::{Write, WriteRead};
+
+use glam::Vec3A;
+
+use crate::device::ACC_REGX_H;
+use crate::frame::{parse_frame, FRAME_LEN};
+#[cfg(not(feature = "minimal-pipeline"))]
+use crate::metrics::{self, MetricEvent, Sensor};
+#[cfg(feature = "minimal-pipeline")]
+use crate::register::Register;
+#[cfg(not(feature = "minimal-pipeline"))]
+use crate::settling::SettlingPolicy;
+#[cfg(not(feature = "minimal-pipeline"))]
+use crate::supervisor::SupervisorEvent;
+use crate::{scale, Mpu6050, Mpu6050Error, MpuSample};
+
+#[cfg(all(feature = "driver", not(feature = "minimal-pipeline")))]
+impl<I, D, E> Mpu6050<I, D>
+where
+    I: Write<Error = E> + WriteRead<Error = E>,
+{
+    /// scaled accel reading of [`get_acc`](Self::get_acc)
+    pub(crate) fn read_acc_scaled(&mut self) -> Result<Vec3A, Mpu6050Error<E>> {
+        let out_of_band = self.check_direct_read()?;
+        let (acc, settling) = self.get_acc_flagged()?;
+        Ok(self
+            .deliver_partial(Some(acc), None, None, settling, out_of_band)
+            .acc)
+    }
+
+    /// scaled gyro reading of [`get_gyro`](Self::get_gyro)
+    pub(crate) fn read_gyro_scaled(&mut self) -> Result<Vec3A, Mpu6050Error<E>> {
+        let out_of_band = self.check_direct_read()?;
+        let (gyro, settling) = self.get_gyro_flagged()?;
+        Ok(self
+            .deliver_partial(None, Some(gyro), None, settling, out_of_band)
+            .gyro)
+    }
+
+    /// temperature of [`get_temp`](Self::get_temp)
+    pub(crate) fn read_temp_scaled(&mut self) -> Result<f32, Mpu6050Error<E>> {
+        let out_of_band = self.check_direct_read()?;
+        let temp = self.read_temp()?;
+        Ok(self
+            .deliver_partial(None, None, Some(temp), false, out_of_band)
+            .temp)
+    }
+
+    /// reads accel, gyro and temperature into a sample, passed through the [`hook`](crate::hook)
+    pub(crate) fn read_sample(&mut self) -> Result<MpuSample, Mpu6050Error<E>> {
+        let out_of_band = self.check_direct_read()?;
+        let accel_range = self.step_interleave()?.or(self.step_impact_ranging()?);
+        let (acc, acc_settling) = self.get_acc_flagged()?;
+        let (gyro, gyro_settling) = self.get_gyro_flagged()?;
+        let sample = MpuSample::new(acc, gyro, self.read_temp()?)
+            .with_settling(acc_settling || gyro_settling)
+            .with_out_of_band(out_of_band)
+            .with_accel_range(accel_range);
+        let sample = self.detect_impact(sample)?;
+        let sample = self.correct_skew(sample);
+        let sample = self.compensate_lever_arm(sample);
+        self.feed_governor(&sample)?;
+        Ok(self.deliver(sample))
+    }
+
+    /// [`read_sample_burst`](Self::read_sample_burst) reading the frame into `bytes`. The
+    /// burst is a valid window by construction, the register map check is left out
+    #[inline(always)]
+    pub(crate) fn read_sample_burst_into(
+        &mut self,
+        bytes: &mut [u8; FRAME_LEN],
+    ) -> Result<MpuSample, Mpu6050Error<E>> {
+        let out_of_band = self.check_direct_read()?;
+        let accel_range = self.step_interleave()?.or(self.step_impact_ranging()?);
+        self.read_burst(ACC_REGX_H, bytes)?;
+        let temp_model = self.temp_model();
+        self.check_burst(bytes, &temp_model)?;
+        let frame = parse_frame(bytes);
+        self.emit_clipped(Sensor::Accel, frame.acc);
+        self.emit_clipped(Sensor::Gyro, frame.gyro);
+        let acc_settling = self.settle.consume_acc();
+        let gyro_settling = self.settle.consume_gyro();
+        let acc = scale::acc_uncorrected(frame.acc, &self.acc_scale);
+        let acc = self.finish_acc(acc, acc_settling);
+        let gyro = scale::gyro_uncorrected(frame.gyro, &self.gyro_scale);
+        let gyro = self.finish_gyro(gyro, gyro_settling);
+        let temp = temp_model.celsius(frame.temp);
+        let sample = MpuSample::new(acc, gyro, temp)
+            .with_settling(acc_settling || gyro_settling)
+            .with_out_of_band(out_of_band)
+            .with_accel_range(accel_range);
+        let sample = self.detect_impact(sample)?;
+        let sample = self.correct_skew(sample);
+        let sample = self.compensate_lever_arm(sample);
+        self.feed_governor(&sample)?;
+        Ok(self.deliver(sample))
+    }
+
+    /// Accelerometer reading in g and whether it was taken while settling, before the
+    /// [`hook`](crate::hook)
+    fn get_acc_flagged(&mut self) -> Result<(Vec3A, bool), Mpu6050Error<E>> {
+        let mut uncorrected = self.read_acc_uncorrected()?;
+        let mut settling = self.settle.consume_acc();
+        let mut retries = 0;
+        while settling && self.retry_settling(Sensor::Accel, &mut retries) {
+            uncorrected = self.read_acc_uncorrected()?;
+            settling = self.settle.consume_acc();
+        }
+        Ok((self.finish_acc(uncorrected, settling), settling))
+    }
+
+    /// offsets applied to a scaled accel reading, checked by the supervisor unless settling
+    fn finish_acc(&mut self, uncorrected: Vec3A, settling: bool) -> Vec3A {
+        let acc = self.acc_scale.correct(uncorrected, self.acc_offset);
+        if let (Some(supervisor), false) = (&mut self.supervisor, settling) {
+            let tripped = supervisor.check_acc(acc);
+            self.emit_trip(tripped);
+        }
+        acc
+    }
+
+    /// Gyro reading in rad/s and whether it was taken while settling, before the
+    /// [`hook`](crate::hook)
+    fn get_gyro_flagged(&mut self) -> Result<(Vec3A, bool), Mpu6050Error<E>> {
+        let mut uncorrected = self.read_gyro_uncorrected()?;
+        let mut settling = self.settle.consume_gyro();
+        let mut retries = 0;
+        while settling && self.retry_settling(Sensor::Gyro, &mut retries) {
+            uncorrected = self.read_gyro_uncorrected()?;
+            settling = self.settle.consume_gyro();
+        }
+        Ok((self.finish_gyro(uncorrected, settling), settling))
+    }
+
+    /// boot transient and offsets applied to a scaled gyro reading, fed to the background
+    /// calibration and checked by the supervisor unless settling
+    fn finish_gyro(&mut self, uncorrected: Vec3A, settling: bool) -> Vec3A {
+        let uncorrected = uncorrected + self.boot_transient_offset();
+        let gyro = self.gyro_scale.correct(uncorrected, self.gyro_offset);
+        if !settling {
+            self.feed_background_calibration(uncorrected, gyro);
+            if let Some(supervisor) = &mut self.supervisor {
+                let tripped = supervisor.check_gyro(gyro);
+                self.emit_trip(tripped);
+            }
+        }
+        gyro
+    }
+
+    /// true if the policy allows another read to replace a settling sample
+    fn retry_settling(&self, sensor: Sensor, retries: &mut u8) -> bool {
+        match self.settling_policy {
+            SettlingPolicy::Discard { max_retries } if *retries < max_retries => {
+                *retries += 1;
+                self.emit_event(
+                    metrics::SETTLING_RETRIES,
+                    MetricEvent::SettlingRetry(sensor),
+                );
+                true
+            }
+            _ => false,
+        }
+    }
+
+    fn emit_trip(&self, tripped: Option<SupervisorEvent>) {
+        if let Some(event) = tripped {
+            self.emit_event(
+                metrics::SUPERVISOR_TRIPS,
+                MetricEvent::SupervisorTripped(event),
+            );
+        }
+    }
+}
+
+#[cfg(all(feature = "driver", feature = "minimal-pipeline"))]
+impl<I, D, E> Mpu6050<I, D>
+where
+    I: Write<Error = E> + WriteRead<Error = E>,
+{
+    /// scaled accel reading of [`get_acc`](Self::get_acc)
+    pub(crate) fn read_acc_scaled(&mut self) -> Result<Vec3A, Mpu6050Error<E>> {
+        let raw = self.read_rot_raw(Register::ACCEL_XOUT_H)?;
+        Ok(self.scale_acc(raw))
+    }
+
+    /// scaled gyro reading of [`get_gyro`](Self::get_gyro)
+    pub(crate) fn read_gyro_scaled(&mut self) -> Result<Vec3A, Mpu6050Error<E>> {
+        let raw = self.read_rot_raw(Register::GYRO_XOUT_H)?;
+        Ok(self.scale_gyro(raw))
+    }
+
+    /// temperature of [`get_temp`](Self::get_temp)
+    pub(crate) fn read_temp_scaled(&mut self) -> Result<f32, Mpu6050Error<E>> {
+        self.read_temp()
+    }
+
+    /// reads accel, gyro and temperature into a sample
+    pub(crate) fn read_sample(&mut self) -> Result<MpuSample, Mpu6050Error<E>> {
+        let acc = self.read_acc_scaled()?;
+        let gyro = self.read_gyro_scaled()?;
+        Ok(MpuSample::new(acc, gyro, self.read_temp()?))
+    }
+
+    /// [`read_sample_burst`](Self::read_sample_burst) reading the frame into `bytes`. The
+    /// burst is a valid window by construction, the register map check is left out
+    #[inline(always)]
+    pub(crate) fn read_sample_burst_into(
+        &mut self,
+        bytes: &mut [u8; FRAME_LEN],
+    ) -> Result<MpuSample, Mpu6050Error<E>> {
+        self.read_burst(ACC_REGX_H, bytes)?;
+        let frame = parse_frame(bytes);
+        Ok(MpuSample::new(
+            self.scale_acc(frame.acc),
+            self.scale_gyro(frame.gyro),
+            self.temp_model().celsius(frame.temp),
+        ))
+    }
+
+    #[inline(always)]
+    fn scale_acc(&self, raw: [i16; 3]) -> Vec3A {
+        let uncorrected = scale::acc_uncorrected(raw, &self.acc_scale);
+        self.acc_scale.correct(uncorrected, self.acc_offset)
+    }
+
+    #[inline(always)]
+    fn scale_gyro(&self, raw: [i16; 3]) -> Vec3A {
+        let uncorrected = scale::gyro_uncorrected(raw, &self.gyro_scale);
+        self.gyro_scale.correct(uncorrected, self.gyro_offset)
+    }
+}
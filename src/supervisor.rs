@@ -0,0 +1,125 @@
+//! Soft limits on the sampling path.
+//!
+//! When configured, every scaled gyro and accel read is checked against the limits. A limit
+//! exceeded for `consecutive` reads in a row latches a [`SupervisorEvent`], which stays until
+//! [`Mpu6050::clear_supervisor`](crate::Mpu6050::clear_supervisor) is called. The optional
+//! callback runs once, when the latch is set, from within the read that tripped it.
+//! Without a supervisor configured the cost per read is a single `Option` check.
+
+use glam::Vec3A;
+
+use crate::device::Axis;
+
+/// Which limit was exceeded
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum SupervisorLimit {
+    /// gyro rate on an axis, limit from `max_gyro_rad_s`
+    GyroRate(Axis),
+    /// accel magnitude, limit from `max_acc_g`
+    AccelMagnitude,
+}
+
+/// Latched limit violation
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct SupervisorEvent {
+    /// limit exceeded
+    pub limit: SupervisorLimit,
+    /// offending value of the read that tripped the supervisor, rad/s or g
+    pub value: f32,
+}
+
+/// Supervisor limits
+#[derive(Copy, Clone, Debug)]
+pub struct SupervisorConfig {
+    /// per-axis limit on the absolute gyro rate in rad/s
+    pub max_gyro_rad_s: Option<Vec3A>,
+    /// limit on the accel magnitude in g
+    pub max_acc_g: Option<f32>,
+    /// reads in a row a limit must be exceeded before tripping, 0 behaves like 1
+    pub consecutive: u8,
+    /// invoked once when the supervisor trips
+    pub callback: Option<fn(SupervisorEvent)>,
+}
+
+/// Debounce and latch state, no bus access
+#[derive(Copy, Clone, Debug)]
+pub struct Supervisor {
+    config: SupervisorConfig,
+    gyro_count: u8,
+    acc_count: u8,
+    tripped: Option<SupervisorEvent>,
+}
+
+impl Supervisor {
+    /// new supervisor, not tripped
+    pub fn new(config: SupervisorConfig) -> Self {
+        Self {
+            config,
+            gyro_count: 0,
+            acc_count: 0,
+            tripped: None,
+        }
+    }
+
+    /// limits in use
+    pub fn config(&self) -> &SupervisorConfig {
+        &self.config
+    }
+
+    /// latched event, if any
+    pub fn tripped(&self) -> Option<SupervisorEvent> {
+        self.tripped
+    }
+
+    /// clears the latch and the debounce counters
+    pub fn clear(&mut self) {
+        self.tripped = None;
+        self.gyro_count = 0;
+        self.acc_count = 0;
+    }
+
+    /// check a scaled gyro reading in rad/s, returns the event if this reading tripped the
+    /// supervisor
+    pub fn check_gyro(&mut self, gyro: Vec3A) -> Option<SupervisorEvent> {
+        let max = self.config.max_gyro_rad_s?;
+        let abs = gyro.abs();
+        let violation = Axis::ALL
+            .into_iter()
+            .find(|axis| abs[*axis as usize] > max[*axis as usize]);
+        let event = violation.map(|axis| SupervisorEvent {
+            limit: SupervisorLimit::GyroRate(axis),
+            value: gyro[axis as usize],
+        });
+        let count = debounce(&mut self.gyro_count, event.is_some());
+        self.update(event, count)
+    }
+
+    /// check a scaled accel reading in g, returns the event if this reading tripped the
+    /// supervisor
+    pub fn check_acc(&mut self, acc: Vec3A) -> Option<SupervisorEvent> {
+        let max = self.config.max_acc_g?;
+        let magnitude = acc.length();
+        let event = (magnitude > max).then_some(SupervisorEvent {
+            limit: SupervisorLimit::AccelMagnitude,
+            value: magnitude,
+        });
+        let count = debounce(&mut self.acc_count, event.is_some());
+        self.update(event, count)
+    }
+
+    fn update(&mut self, event: Option<SupervisorEvent>, count: u8) -> Option<SupervisorEvent> {
+        if self.tripped.is_some() || count < self.config.consecutive.max(1) {
+            return None;
+        }
+        self.tripped = event;
+        if let (Some(event), Some(callback)) = (event, self.config.callback) {
+            callback(event);
+        }
+        event
+    }
+}
+
+fn debounce(count: &mut u8, exceeded: bool) -> u8 {
+    *count = if exceeded { count.saturating_add(1) } else { 0 };
+    *count
+}
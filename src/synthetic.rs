@@ -0,0 +1,42 @@
+//! Synthetic sensor data for examples and doctests.
+//!
+//! Axes are the chip axes printed on most breakout boards. The docs of this crate use X
+//! forward, Y left, Z up when talking about a vehicle or a table: a right-handed frame, so
+//! positive rotations follow the right-hand rule about each axis.
+
+use glam::Vec3A;
+
+use crate::conversion::{self, RoundingMode};
+use crate::device::{AccelRange, GyroRange};
+use crate::frame::{encode_frame, RawFrame, FRAME_LEN};
+#[cfg(not(feature = "std"))]
+use crate::math::FloatExt;
+
+/// Raw frame bytes as read from ACCEL_XOUT_H for readings in g and °/s at the given ranges.
+/// Counts are rounded to nearest even and saturate at the range limits, temperature is 0 counts
+/// (36.53 °C)
+pub fn frame_bytes(
+    acc_g: Vec3A,
+    gyro_dps: Vec3A,
+    accel: AccelRange,
+    gyro: GyroRange,
+) -> [u8; FRAME_LEN] {
+    encode_frame(&RawFrame {
+        acc: counts(acc_g, accel.sensitivity()),
+        temp: 0,
+        gyro: counts(gyro_dps, gyro.sensitivity()),
+    })
+}
+
+fn counts(value: Vec3A, lsb_per_unit: f32) -> [i16; 3] {
+    conversion::units_to_counts(value, lsb_per_unit, RoundingMode::NearestEven).0
+}
+
+/// Accelerometer reading in g at rest, rolled by `roll_deg` about +X (right side down is
+/// positive), then pitched by `pitch_deg` about +Y (nose down is positive). The accelerometer
+/// measures the reaction to gravity, +1g along Z when flat
+pub fn at_rest(roll_deg: f32, pitch_deg: f32) -> Vec3A {
+    let (sr, cr) = roll_deg.to_radians().sin_cos();
+    let (sp, cp) = pitch_deg.to_radians().sin_cos();
+    Vec3A::new(-sp * cr, sr, cp * cr)
+}
@@ -0,0 +1,355 @@
+//! Temperature calibration: one conversion model for every temperature the driver reports.
+//!
+//! The datasheet formulas are typical values, parts spread over several degrees around them.
+//! [`TempCalibration`] is the conversion of TEMP_OUT counts to °C of a driver, one of:
+//!
+//! | model | conversion | made with |
+//! |:---|:---|:---|
+//! | [`DatasheetDefault`](TempCalibration::DatasheetDefault) | the formula of the chip, `raw / 340 + 36.53` for the MPU-6050 | nothing, the default |
+//! | [`SinglePoint`](TempCalibration::SinglePoint) | the datasheet sensitivity, the offset through one reference point | [`Mpu6050::calibrate_temp_single_point`] |
+//! | [`TwoPoint`](TempCalibration::TwoPoint) | gain and offset through two reference points | [`Mpu6050::calibrate_temp_two_point`] |
+//!
+//! Every model is a [`TempModel`], the temperature constants of a
+//! [`Pipeline`](crate::scale::Pipeline): [`get_temp`](Mpu6050::get_temp), the sample reads,
+//! the FIFO schemas, the [`pipeline`](Mpu6050::pipeline) and the log header all convert with
+//! the driver's [`temp_calibration`](Mpu6050::temp_calibration), and so does everything
+//! evaluating their temperatures, like the [`validity`](crate::validity) rules and the
+//! [`plausibility`](crate::plausibility) checks. Without a calibration the chip's formula
+//! follows the detected variant.
+//!
+//! A two-point calibration needs references at least [`MIN_TWO_POINT_SPAN_C`] apart and a
+//! resulting sensitivity within [`SENSITIVITY_RANGE_LSB_PER_C`], half to twice the
+//! datasheet's, which rejects swapped or repeated points.
+//!
+//! #### Persistence
+//! [`TempCalibration::to_bytes`] writes the model for storage, [`TempCalibration::from_bytes`]
+//! reads it back; the log header records it too:
+//!
+//! | bytes | content |
+//! |:---|:---|
+//! | 1 | format version ([`TEMP_CALIBRATION_VERSION`]) |
+//! | 1 | model: 0 datasheet default, 1 single point, 2 two point |
+//! | 1 | datasheet formula: 0 MPU-6050, 1 MPU-6500, 0 for two point |
+//! | 1 | reserved, 0 |
+//! | 4 | sensitivity in LSB per °C, f32 little endian, 0 unless two point |
+//! | 4 | offset in °C, f32 little endian, 0 for the datasheet default |
+//! | 2 | CRC-16/CCITT-FALSE ([`crc16`]) over all previous bytes, little endian |
+//! ```
+//! use mpu6050::temp_calibration::TempCalibration;
+//!
+//! // 25 °C read as 40 °C, 60 °C as 78 °C by the datasheet formula
+//! let raw = |c: f32| ((c - 36.53) * 340.) as i16;
+//! let calibration = TempCalibration::two_point(raw(40.), 25., raw(78.), 60.).unwrap();
+//! assert!((calibration.celsius(raw(59.)) - 42.5).abs() < 0.01);
+//!
+//! let bytes = calibration.to_bytes();
+//! assert_eq!(TempCalibration::from_bytes(&bytes), Ok(calibration));
+//! ```
+
+use core::fmt;
+
+use crate::config_epoch::ConfigChange;
+use crate::decimal::Decimal;
+use crate::device::TempFormula;
+use crate::packed::{crc16, DecodeError};
+use crate::scale::TempModel;
+use crate::Mpu6050;
+
+/// Format version written by [`TempCalibration::to_bytes`]
+pub const TEMP_CALIBRATION_VERSION: u8 = 1;
+/// Length of the serialized calibration
+pub const TEMP_CALIBRATION_LEN: usize = 14;
+/// Smallest distance of the two reference temperatures in °C
+pub const MIN_TWO_POINT_SPAN_C: f32 = 10.;
+/// Accepted sensitivities of a two-point calibration in LSB per °C
+pub const SENSITIVITY_RANGE_LSB_PER_C: (f32, f32) = (170., 680.);
+
+/// Conversion of TEMP_OUT counts to °C, see the module docs
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum TempCalibration {
+    /// the datasheet formula
+    DatasheetDefault {
+        /// formula of the chip
+        variant: TempFormula,
+    },
+    /// the datasheet sensitivity of `variant`, the offset of a reference point
+    SinglePoint {
+        /// formula whose sensitivity is kept
+        variant: TempFormula,
+        /// °C at 0 counts
+        offset_c: f32,
+    },
+    /// gain and offset of two reference points
+    TwoPoint {
+        /// LSB per °C
+        sensitivity: f32,
+        /// °C at 0 counts
+        offset_c: f32,
+    },
+}
+
+impl TempCalibration {
+    /// Model through the two reference points, `raw_low` counts read at `actual_low_c` and
+    /// `raw_high` at `actual_high_c`
+    pub fn two_point(
+        raw_low: i16,
+        actual_low_c: f32,
+        raw_high: i16,
+        actual_high_c: f32,
+    ) -> Result<Self, TempCalibrationError> {
+        if !actual_low_c.is_finite() || !actual_high_c.is_finite() {
+            return Err(TempCalibrationError::NotFinite);
+        }
+        let span_c = actual_high_c - actual_low_c;
+        if span_c.abs() < MIN_TWO_POINT_SPAN_C {
+            return Err(TempCalibrationError::PointsTooClose { span_c });
+        }
+        let sensitivity = (raw_high as f32 - raw_low as f32) / span_c;
+        let calibration = TempCalibration::TwoPoint {
+            sensitivity,
+            offset_c: actual_low_c - raw_low as f32 / sensitivity,
+        };
+        calibration.validate()?;
+        Ok(calibration)
+    }
+
+    /// Model of `variant`'s sensitivity through the reference point, `raw` counts read at
+    /// `actual_c`
+    pub fn single_point(
+        variant: TempFormula,
+        raw: i16,
+        actual_c: f32,
+    ) -> Result<Self, TempCalibrationError> {
+        let sensitivity = variant.model().sensitivity;
+        let calibration = TempCalibration::SinglePoint {
+            variant,
+            offset_c: actual_c - raw as f32 / sensitivity,
+        };
+        calibration.validate()?;
+        Ok(calibration)
+    }
+
+    /// Checks the parameters: finite, and a two-point sensitivity within
+    /// [`SENSITIVITY_RANGE_LSB_PER_C`]
+    pub fn validate(&self) -> Result<(), TempCalibrationError> {
+        let (sensitivity, offset_c) = match *self {
+            TempCalibration::DatasheetDefault { .. } => return Ok(()),
+            TempCalibration::SinglePoint { offset_c, .. } => (None, offset_c),
+            TempCalibration::TwoPoint {
+                sensitivity,
+                offset_c,
+            } => (Some(sensitivity), offset_c),
+        };
+        let (min, max) = SENSITIVITY_RANGE_LSB_PER_C;
+        match sensitivity {
+            Some(lsb_per_c) if !lsb_per_c.is_finite() => Err(TempCalibrationError::NotFinite),
+            Some(lsb_per_c) if !(min..=max).contains(&lsb_per_c) => {
+                Err(TempCalibrationError::SensitivityOutOfRange { lsb_per_c })
+            }
+            _ if !offset_c.is_finite() => Err(TempCalibrationError::NotFinite),
+            _ => Ok(()),
+        }
+    }
+
+    /// the conversion as pipeline temperature constants
+    pub fn model(&self) -> TempModel {
+        match *self {
+            TempCalibration::DatasheetDefault { variant } => variant.model(),
+            TempCalibration::SinglePoint { variant, offset_c } => TempModel {
+                offset_c,
+                ..variant.model()
+            },
+            TempCalibration::TwoPoint {
+                sensitivity,
+                offset_c,
+            } => TempModel {
+                raw_offset: 0.,
+                sensitivity,
+                offset_c,
+            },
+        }
+    }
+
+    /// temperature in °C
+    pub fn celsius(&self, raw: i16) -> f32 {
+        self.model().celsius(raw)
+    }
+
+    /// Serialized form, see the [module docs](self)
+    pub fn to_bytes(&self) -> [u8; TEMP_CALIBRATION_LEN] {
+        let (kind, variant, sensitivity, offset_c) = match *self {
+            TempCalibration::DatasheetDefault { variant } => (0, variant, 0., 0.),
+            TempCalibration::SinglePoint { variant, offset_c } => (1, variant, 0., offset_c),
+            TempCalibration::TwoPoint {
+                sensitivity,
+                offset_c,
+            } => (2, TempFormula::Mpu6050, sensitivity, offset_c),
+        };
+        let mut bytes = [0; TEMP_CALIBRATION_LEN];
+        bytes[0] = TEMP_CALIBRATION_VERSION;
+        bytes[1] = kind;
+        bytes[2] = variant as u8;
+        bytes[4..8].copy_from_slice(&sensitivity.to_le_bytes());
+        bytes[8..12].copy_from_slice(&offset_c.to_le_bytes());
+        let crc = crc16(&bytes[..TEMP_CALIBRATION_LEN - 2]);
+        bytes[TEMP_CALIBRATION_LEN - 2..].copy_from_slice(&crc.to_le_bytes());
+        bytes
+    }
+
+    /// Reads a calibration written by [`to_bytes`](Self::to_bytes), parameters failing
+    /// [`validate`](Self::validate) are a bad header
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, DecodeError> {
+        if bytes.len() < TEMP_CALIBRATION_LEN {
+            return Err(DecodeError::Truncated(TEMP_CALIBRATION_LEN));
+        }
+        let bytes = &bytes[..TEMP_CALIBRATION_LEN];
+        if bytes[0] != TEMP_CALIBRATION_VERSION {
+            return Err(DecodeError::BadHeader);
+        }
+        let crc = u16::from_le_bytes([bytes[12], bytes[13]]);
+        if crc != crc16(&bytes[..TEMP_CALIBRATION_LEN - 2]) {
+            return Err(DecodeError::CrcMismatch);
+        }
+        let variant = match bytes[2] {
+            0 => TempFormula::Mpu6050,
+            1 => TempFormula::Mpu6500,
+            _ => return Err(DecodeError::BadHeader),
+        };
+        let float = |at: usize| {
+            f32::from_le_bytes([bytes[at], bytes[at + 1], bytes[at + 2], bytes[at + 3]])
+        };
+        let calibration = match bytes[1] {
+            0 => TempCalibration::DatasheetDefault { variant },
+            1 => TempCalibration::SinglePoint {
+                variant,
+                offset_c: float(8),
+            },
+            2 => TempCalibration::TwoPoint {
+                sensitivity: float(4),
+                offset_c: float(8),
+            },
+            _ => return Err(DecodeError::BadHeader),
+        };
+        calibration.validate().map_err(|_| DecodeError::BadHeader)?;
+        Ok(calibration)
+    }
+}
+
+impl fmt::Display for TempCalibration {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let model = self.model();
+        let kind = match self {
+            TempCalibration::DatasheetDefault { .. } => "datasheet",
+            TempCalibration::SinglePoint { .. } => "single point",
+            TempCalibration::TwoPoint { .. } => "two point",
+        };
+        write!(
+            f,
+            "{}: raw / {} + {} °C",
+            kind,
+            Decimal(model.sensitivity),
+            Decimal(model.offset_c)
+        )
+    }
+}
+
+/// Calibration rejected by [`TempCalibration::validate`] and the constructors
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum TempCalibrationError {
+    /// a reference temperature or a parameter is NaN or infinite
+    NotFinite,
+    /// reference temperatures closer than [`MIN_TWO_POINT_SPAN_C`], their distance
+    PointsTooClose {
+        /// high minus low reference in °C
+        span_c: f32,
+    },
+    /// sensitivity outside [`SENSITIVITY_RANGE_LSB_PER_C`], e.g. of swapped or equal counts
+    SensitivityOutOfRange {
+        /// LSB per °C of the points
+        lsb_per_c: f32,
+    },
+}
+
+impl fmt::Display for TempCalibrationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TempCalibrationError::NotFinite => f.write_str("temperature calibration not finite"),
+            TempCalibrationError::PointsTooClose { span_c } => write!(
+                f,
+                "reference points {} °C apart, at least {} °C required",
+                Decimal(*span_c),
+                Decimal(MIN_TWO_POINT_SPAN_C)
+            ),
+            TempCalibrationError::SensitivityOutOfRange { lsb_per_c } => write!(
+                f,
+                "sensitivity {} LSB/°C outside {} to {}",
+                Decimal(*lsb_per_c),
+                Decimal(SENSITIVITY_RANGE_LSB_PER_C.0),
+                Decimal(SENSITIVITY_RANGE_LSB_PER_C.1)
+            ),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for TempCalibrationError {}
+
+impl<I, D> Mpu6050<I, D> {
+    /// model every temperature is converted with, see the
+    /// [`temp_calibration`](crate::temp_calibration) module
+    pub fn temp_calibration(&self) -> TempCalibration {
+        self.temp_calibration
+            .unwrap_or(TempCalibration::DatasheetDefault {
+                variant: self.capabilities.temp_formula,
+            })
+    }
+
+    /// Replaces the model, None goes back to the datasheet formula of the detected chip.
+    /// Rejected calibrations leave the model as it was. Advances the
+    /// [`config_epoch`](crate::config_epoch), the FIFO schema carries the model
+    pub fn set_temp_calibration(
+        &mut self,
+        calibration: Option<TempCalibration>,
+    ) -> Result<(), TempCalibrationError> {
+        if let Some(calibration) = calibration {
+            calibration.validate()?;
+        }
+        self.temp_calibration = calibration;
+        self.bump_epoch(ConfigChange::TempCalibration);
+        Ok(())
+    }
+
+    /// Calibrates gain and offset from two reference points, `raw_low` counts of
+    /// [`get_temp_raw`](Mpu6050::get_temp_raw) read at `actual_low_c` and `raw_high` at
+    /// `actual_high_c`. Returns the new model
+    pub fn calibrate_temp_two_point(
+        &mut self,
+        raw_low: i16,
+        actual_low_c: f32,
+        raw_high: i16,
+        actual_high_c: f32,
+    ) -> Result<TempCalibration, TempCalibrationError> {
+        let calibration =
+            TempCalibration::two_point(raw_low, actual_low_c, raw_high, actual_high_c)?;
+        self.set_temp_calibration(Some(calibration))?;
+        Ok(calibration)
+    }
+
+    /// Calibrates the offset from one reference point, `raw` counts read at `actual_c`, the
+    /// datasheet sensitivity of the detected chip stays. Returns the new model
+    pub fn calibrate_temp_single_point(
+        &mut self,
+        raw: i16,
+        actual_c: f32,
+    ) -> Result<TempCalibration, TempCalibrationError> {
+        let calibration =
+            TempCalibration::single_point(self.capabilities.temp_formula, raw, actual_c)?;
+        self.set_temp_calibration(Some(calibration))?;
+        Ok(calibration)
+    }
+
+    /// constants of [`temp_calibration`](Self::temp_calibration)
+    pub(crate) fn temp_model(&self) -> TempModel {
+        self.temp_calibration().model()
+    }
+}
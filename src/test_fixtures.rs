@@ -0,0 +1,284 @@
+//! Golden sample corpus: named frame sequences with their physical ground truth.
+//!
+//! Tests need realistic register frames, and a test inventing its own bytes checks nothing
+//! beyond itself. The fixtures here are the shared corpus: each [`Fixture`] is a sequence of
+//! [`Truth`]s, the physical state of the sensor per sample in the chip axes, with the ranges
+//! it is documented at. The frames are generated from the truth by [`encode`], the inverse of
+//! the driver's scaling: the [`conversion`](crate::conversion)s of the
+//! [`Pipeline`] run backwards, rounding to nearest even and saturating at the range limits
+//! like the chip. Every fixture generates at every range combination, noise free.
+//!
+//! | fixture | samples | interval | ranges | content |
+//! |:---|:---|:---|:---|:---|
+//! | [`STATIONARY`] | 64 | 10 ms | ±2 g, ±250 °/s | flat, Z up, no rotation, 25 °C |
+//! | [`TILTED_30`] | 64 | 10 ms | ±2 g, ±250 °/s | at rest, rolled 30° about +X, right side down |
+//! | [`ROTATING_Z`] | 100 | 10 ms | ±2 g, ±500 °/s | flat, turning counterclockwise seen from above at 90 °/s for 1 s |
+//! | [`IMPACT`] | 16 | 1 ms | ±16 g, ±2000 °/s | flat, a 24 g half sine shock on +X over samples 6 to 9, clipping in 7 and 8 |
+//! | [`TEMP_RAMP`] | 41 | 1 s | ±2 g, ±250 °/s | flat, the die warming from 20 °C to 40 °C in 0.5 °C steps |
+//!
+//! #### Ground truth
+//! [`Fixture::expected`] is the exact scaled output of a frame: what the getters of a driver
+//! without offsets and [`Pipeline::sample`] return for it, bit for bit. It is within half an
+//! LSB of the truth on every axis, except where the truth is beyond the range: the count
+//! saturates there and the output is the range limit, see [`Fixture::saturates`]. Scaled back
+//! with [`encode`] the expected output gives the frame again, exactly.
+//!
+//! The golden file `tests/golden/fixtures.txt` holds the bytes and the expected outputs of
+//! every fixture at its documented ranges. A new fixture is a [`Fixture`] with a truth
+//! function added to [`ALL`], the golden is regenerated with `UPDATE_GOLDENS=1`. A changed
+//! line of an existing fixture is a change of the conversions.
+//!
+//! #### FIFO sequences
+//! [`Fixture::fifo_bytes`] lays the frames out as the FIFO holds them for a set of sources,
+//! in the layout of [`FifoSchema`].
+//!
+//! The parsing, FIFO, packed encoding, fusion, metrics and batch tests of the crate replay
+//! the fixtures through a register mock, byte for byte as the chip would hold them.
+//!
+//! Enable with the `test-fixtures` feature, or `test-util` which includes it.
+//!
+//! #### Axis conventions
+//! ```
+//! use mpu6050::test_fixtures::{ROTATING_Z, STATIONARY, TILTED_30};
+//! use mpu6050::Vec3A;
+//!
+//! // flat, Z up: the accelerometer measures the reaction to gravity, +1 g on Z
+//! assert_eq!(STATIONARY.expected(0).acc(), Vec3A::Z);
+//! // rolled right side down: positive roll about +X, the reaction moves to +Y
+//! assert!((TILTED_30.expected(0).acc().y - 0.5).abs() < 1e-4);
+//! // counterclockwise seen from above: a positive Z rate, right-handed
+//! let gyro = ROTATING_Z.expected(0).gyro();
+//! assert!((gyro.z - 90f32.to_radians()).abs() < 1e-3);
+//! ```
+
+use std::vec::Vec;
+
+use glam::Vec3A;
+
+use crate::conversion::{self, RoundingMode};
+use crate::device::{AccelRange, GyroRange};
+use crate::fifo::{FifoFieldKind, FifoSchema, FifoSources};
+use crate::frame::{encode_frame, RawFrame, FRAME_LEN};
+use crate::scale::Pipeline;
+use crate::synthetic;
+use crate::MpuSample;
+
+/// Physical state of the sensor at one sample, in the chip axes
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Truth {
+    /// specific force in g, the reaction to gravity included
+    pub acc_g: Vec3A,
+    /// rotation rate in °/s
+    pub gyro_dps: Vec3A,
+    /// die temperature in °C
+    pub temp_c: f32,
+}
+
+impl Truth {
+    /// at rest flat, Z up, at 25 °C
+    pub const FLAT: Truth = Truth {
+        acc_g: Vec3A::Z,
+        gyro_dps: Vec3A::ZERO,
+        temp_c: 25.,
+    };
+}
+
+/// Counts of `truth` at the ranges, and whether any of them saturated. Nearest even rounding,
+/// the inverse of the [`Pipeline`]
+pub fn encode(truth: &Truth, accel_range: AccelRange, gyro_range: GyroRange) -> (RawFrame, bool) {
+    let mode = RoundingMode::NearestEven;
+    let (acc, acc_saturated) =
+        conversion::units_to_counts(truth.acc_g, accel_range.sensitivity(), mode);
+    let (gyro, gyro_saturated) =
+        conversion::units_to_counts(truth.gyro_dps, gyro_range.sensitivity(), mode);
+    let (temp, temp_saturated) = conversion::celsius_to_counts(truth.temp_c, mode);
+    (
+        RawFrame { acc, temp, gyro },
+        acc_saturated || gyro_saturated || temp_saturated,
+    )
+}
+
+/// A named sequence of samples, see the [module docs](self)
+#[derive(Copy, Clone, Debug)]
+pub struct Fixture {
+    /// name, as in the golden file
+    pub name: &'static str,
+    /// what the sequence shows
+    pub description: &'static str,
+    /// accel range the fixture is documented at
+    pub accel_range: AccelRange,
+    /// gyro range the fixture is documented at
+    pub gyro_range: GyroRange,
+    /// time between two samples in s
+    pub interval_s: f32,
+    /// samples
+    pub len: usize,
+    truth: fn(usize) -> Truth,
+}
+
+impl Fixture {
+    /// truth of sample `idx`, panics past the end
+    pub fn truth(&self, idx: usize) -> Truth {
+        assert!(idx < self.len, "{} has {} samples", self.name, self.len);
+        (self.truth)(idx)
+    }
+
+    /// truths of all samples
+    pub fn truths(&self) -> impl Iterator<Item = Truth> + '_ {
+        (0..self.len).map(|idx| self.truth(idx))
+    }
+
+    /// time of sample `idx` in s from the first
+    pub fn time_s(&self, idx: usize) -> f32 {
+        idx as f32 * self.interval_s
+    }
+
+    /// frame of sample `idx` at the documented ranges
+    pub fn frame(&self, idx: usize) -> RawFrame {
+        self.frame_at(idx, self.accel_range, self.gyro_range)
+    }
+
+    /// frame of sample `idx` at any ranges
+    pub fn frame_at(&self, idx: usize, accel_range: AccelRange, gyro_range: GyroRange) -> RawFrame {
+        encode(&self.truth(idx), accel_range, gyro_range).0
+    }
+
+    /// bytes of sample `idx` as read from ACCEL_XOUT_H, at the documented ranges
+    pub fn bytes(&self, idx: usize) -> [u8; FRAME_LEN] {
+        encode_frame(&self.frame(idx))
+    }
+
+    /// bytes of sample `idx` at any ranges
+    pub fn bytes_at(
+        &self,
+        idx: usize,
+        accel_range: AccelRange,
+        gyro_range: GyroRange,
+    ) -> [u8; FRAME_LEN] {
+        encode_frame(&self.frame_at(idx, accel_range, gyro_range))
+    }
+
+    /// a count of sample `idx` saturates at the ranges
+    pub fn saturates(&self, idx: usize, accel_range: AccelRange, gyro_range: GyroRange) -> bool {
+        encode(&self.truth(idx), accel_range, gyro_range).1
+    }
+
+    /// exact scaled output of sample `idx` at the documented ranges, no offsets
+    pub fn expected(&self, idx: usize) -> MpuSample {
+        self.expected_at(idx, self.accel_range, self.gyro_range)
+    }
+
+    /// exact scaled output of sample `idx` at any ranges, no offsets
+    pub fn expected_at(
+        &self,
+        idx: usize,
+        accel_range: AccelRange,
+        gyro_range: GyroRange,
+    ) -> MpuSample {
+        Pipeline::new(accel_range, gyro_range).sample(&self.frame_at(idx, accel_range, gyro_range))
+    }
+
+    /// all samples as the FIFO holds them with `sources`, at the documented ranges. Aux
+    /// slaves have no data here, their fields are zero
+    pub fn fifo_bytes(&self, sources: FifoSources) -> Vec<u8> {
+        let schema = FifoSchema::new(sources, [0; 4], self.accel_range, self.gyro_range);
+        let mut bytes = Vec::with_capacity(schema.frame_len() * self.len);
+        for idx in 0..self.len {
+            let frame = self.frame(idx);
+            for field in schema.fields() {
+                let word = match field.kind {
+                    FifoFieldKind::AccelX => frame.acc[0],
+                    FifoFieldKind::AccelY => frame.acc[1],
+                    FifoFieldKind::AccelZ => frame.acc[2],
+                    FifoFieldKind::Temp => frame.temp,
+                    FifoFieldKind::GyroX => frame.gyro[0],
+                    FifoFieldKind::GyroY => frame.gyro[1],
+                    FifoFieldKind::GyroZ => frame.gyro[2],
+                    FifoFieldKind::ExtSens(_) => {
+                        bytes.resize(bytes.len() + field.width as usize, 0);
+                        continue;
+                    }
+                };
+                bytes.extend_from_slice(&word.to_be_bytes());
+            }
+        }
+        bytes
+    }
+}
+
+/// Flat and still, Z up, 25 °C
+pub const STATIONARY: Fixture = Fixture {
+    name: "STATIONARY",
+    description: "flat, Z up, no rotation, 25 °C",
+    accel_range: AccelRange::G2,
+    gyro_range: GyroRange::D250,
+    interval_s: 0.01,
+    len: 64,
+    truth: |_| Truth::FLAT,
+};
+
+/// At rest, rolled 30° about +X, right side down
+pub const TILTED_30: Fixture = Fixture {
+    name: "TILTED_30",
+    description: "at rest, rolled 30° about +X, right side down",
+    accel_range: AccelRange::G2,
+    gyro_range: GyroRange::D250,
+    interval_s: 0.01,
+    len: 64,
+    truth: |_| Truth {
+        acc_g: synthetic::at_rest(30., 0.),
+        ..Truth::FLAT
+    },
+};
+
+/// Flat, turning counterclockwise seen from above at 90 °/s, the yaw after sample `i` is
+/// `i` times 0.9°
+pub const ROTATING_Z: Fixture = Fixture {
+    name: "ROTATING_Z",
+    description: "flat, turning counterclockwise seen from above at 90 °/s",
+    accel_range: AccelRange::G2,
+    gyro_range: GyroRange::D500,
+    interval_s: 0.01,
+    len: 100,
+    truth: |_| Truth {
+        gyro_dps: Vec3A::new(0., 0., 90.),
+        ..Truth::FLAT
+    },
+};
+
+/// Flat on a table struck from -X: a 24 g half sine on +X over samples 6 to 9 at 1 kHz
+pub const IMPACT: Fixture = Fixture {
+    name: "IMPACT",
+    description: "flat, a 24 g half sine shock on +X over samples 6 to 9",
+    accel_range: AccelRange::G16,
+    gyro_range: GyroRange::D2000,
+    interval_s: 0.001,
+    len: 16,
+    truth: |idx| {
+        let shock = match idx {
+            6..=9 => 24. * (core::f32::consts::PI * (idx - 5) as f32 / 5.).sin(),
+            _ => 0.,
+        };
+        Truth {
+            acc_g: Vec3A::new(shock, 0., 1.),
+            ..Truth::FLAT
+        }
+    },
+};
+
+/// Flat and still, the die warming from 20 °C to 40 °C in 0.5 °C steps
+pub const TEMP_RAMP: Fixture = Fixture {
+    name: "TEMP_RAMP",
+    description: "flat, the die warming from 20 °C to 40 °C in 0.5 °C steps",
+    accel_range: AccelRange::G2,
+    gyro_range: GyroRange::D250,
+    interval_s: 1.,
+    len: 41,
+    truth: |idx| Truth {
+        temp_c: 20. + 0.5 * idx as f32,
+        ..Truth::FLAT
+    },
+};
+
+/// Every fixture, in the order of the golden file
+pub const ALL: [Fixture; 5] = [STATIONARY, TILTED_30, ROTATING_Z, IMPACT, TEMP_RAMP];
@@ -0,0 +1,137 @@
+//! Accel-only tilt with a trust score.
+//!
+//! Roll and pitch from the accelerometer are only meaningful when it measures gravity and
+//! nothing else. The deviation of the measured magnitude from 1g is a cheap indicator of
+//! additional linear acceleration, [`classify`] turns it into a [`TiltTrust`].
+//!
+//! A leveling routine that refuses to act on untrustworthy readings:
+//! ```
+//! use embedded_hal::blocking::i2c::{Write, WriteRead};
+//! use mpu6050::{tilt::TiltTrust, Mpu6050, Mpu6050Error};
+//!
+//! /// Returns the (roll, pitch) correction to apply, None if the device is being accelerated
+//! fn level_step<I, E>(mpu: &mut Mpu6050<I>) -> Result<Option<(f32, f32)>, Mpu6050Error<E>>
+//! where
+//!     I: Write<Error = E> + WriteRead<Error = E>,
+//! {
+//!     let tilt = mpu.get_acc_angles_checked()?;
+//!     match tilt.trust {
+//!         TiltTrust::High | TiltTrust::Medium => Ok(Some((-tilt.roll, -tilt.pitch))),
+//!         TiltTrust::Low | TiltTrust::Invalid => Ok(None),
+//!     }
+//! }
+//! ```
+
+use glam::{Quat, Vec3A};
+
+use crate::determinism::atan2;
+use crate::euler::{self, AerospaceYpr};
+#[cfg(not(feature = "std"))]
+use crate::math::FloatExt;
+
+/// How much an accel-only tilt estimate can be trusted
+#[derive(Copy, Clone, Debug, Eq, PartialEq, PartialOrd, Ord)]
+pub enum TiltTrust {
+    /// Magnitude close to zero, free fall or broken sensor: no usable direction
+    Invalid,
+    /// Magnitude deviates more than `medium` from 1g
+    Low,
+    /// Magnitude within `medium` of 1g
+    Medium,
+    /// Magnitude within `high` of 1g
+    High,
+}
+
+/// Thresholds for [`classify`], relative deviations from 1g
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct TiltThresholds {
+    /// max relative deviation for High, default 0.02 (±2%)
+    pub high: f32,
+    /// max relative deviation for Medium, default 0.10 (±10%)
+    pub medium: f32,
+    /// magnitudes below this (in g) are Invalid, default 0.1
+    pub invalid_below: f32,
+}
+
+impl Default for TiltThresholds {
+    fn default() -> Self {
+        Self {
+            high: 0.02,
+            medium: 0.10,
+            invalid_below: 0.1,
+        }
+    }
+}
+
+/// Roll/pitch from the accelerometer with its trust score
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct TiltEstimate {
+    /// roll in rad
+    pub roll: f32,
+    /// pitch in rad
+    pub pitch: f32,
+    /// trust derived from the measured magnitude
+    pub trust: TiltTrust,
+}
+
+impl TiltEstimate {
+    /// orientation with zero yaw, `roll` and `pitch` in the XYZ convention of [`euler`]
+    pub fn orientation(&self) -> Quat {
+        euler::from_xyz_rpy(self.roll, self.pitch, 0.0)
+    }
+
+    /// the same orientation in aerospace yaw-pitch-roll
+    pub fn aerospace_ypr(&self) -> AerospaceYpr {
+        euler::to_aerospace_ypr(self.orientation())
+    }
+}
+
+/// Classifies an accelerometer magnitude in g. Boundaries are inclusive towards the better class
+pub fn classify(magnitude_g: f32, thresholds: &TiltThresholds) -> TiltTrust {
+    if magnitude_g.is_nan() || magnitude_g < thresholds.invalid_below {
+        return TiltTrust::Invalid;
+    }
+    let deviation = (magnitude_g - 1.0).abs();
+    if deviation <= thresholds.high {
+        TiltTrust::High
+    } else if deviation <= thresholds.medium {
+        TiltTrust::Medium
+    } else {
+        TiltTrust::Low
+    }
+}
+
+/// Roll and pitch in rad from accelerometer readings
+/// https://www.nxp.com/docs/en/application-note/AN3461.pdf equation 28, 29
+///
+/// Right-handed about the chip axes (X forward, Y left, Z up): roll is positive with the
+/// right side down, pitch is positive nose down.
+/// ```
+/// use mpu6050::{synthetic, tilt::roll_pitch};
+///
+/// // tilted 30° right
+/// let (roll, pitch) = roll_pitch(synthetic::at_rest(30., 0.));
+/// assert!((roll.to_degrees() - 30.).abs() < 1e-3);
+/// assert!(pitch.abs() < 1e-3);
+///
+/// // nose 20° down
+/// let (roll, pitch) = roll_pitch(synthetic::at_rest(0., 20.));
+/// assert!(roll.abs() < 1e-3);
+/// assert!((pitch.to_degrees() - 20.).abs() < 1e-3);
+/// ```
+pub fn roll_pitch(acc: Vec3A) -> (f32, f32) {
+    (
+        atan2(acc.y, (acc.x * acc.x + acc.z * acc.z).sqrt()),
+        atan2(-acc.x, (acc.y * acc.y + acc.z * acc.z).sqrt()),
+    )
+}
+
+/// Tilt estimate with trust for accelerometer readings in g
+pub fn estimate(acc: Vec3A, thresholds: &TiltThresholds) -> TiltEstimate {
+    let (roll, pitch) = roll_pitch(acc);
+    TiltEstimate {
+        roll,
+        pitch,
+        trust: classify(acc.length(), thresholds),
+    }
+}
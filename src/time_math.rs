@@ -0,0 +1,277 @@
+//! Wrap-aware time arithmetic, one place for every time difference of the crate.
+//!
+//! All timestamps the crate takes or returns are u64 µs and all sample indices u64: neither
+//! wraps within the life of a deployment. Hardware timers are narrower, a free-running
+//! 32 bit µs counter wraps every 71.6 minutes. A [`Clock`] declares its width in
+//! [`WIDTH_BITS`](Clock::WIDTH_BITS), and [`ExtendedClock`] (or a bare [`ClockExtender`] for
+//! readings taken elsewhere) turns its readings into u64 timestamps by counting the wraps.
+//!
+//! #### Extension
+//! A reading below the previous one means the counter wrapped once in between. Two or more
+//! wraps between readings look exactly like one, the time lost is invisible in the raw value
+//! alone. [`ClockExtender::extend_expecting`] takes the elapsed time expected from an
+//! independent source, usually the sample indices times the sample interval, and counts the
+//! full periods the readings cannot show from it. Such an [`Extension`] is flagged: its
+//! timestamp relies on the expectation, not on the counter. [`ClockExtender::extend`] has no
+//! expectation and assumes at most one wrap.
+//!
+//! #### Differences
+//! Between extended timestamps, [`elapsed_us`] saturates at 0 for a later timestamp that is
+//! in fact older and [`offset_us`] is signed; [`before_us`] steps back from a timestamp,
+//! saturating at 0. The consumers all go through them:
+//!
+//! | consumer | difference |
+//! |:---|:---|
+//! | [`ComplementaryFilter`](crate::orientation::ComplementaryFilter), callers | `dt` from [`dt_s`] |
+//! | [`interpolate`](crate::interpolation::interpolate) | position between two samples |
+//! | [`InterpolatingBuffer::sample_at`](crate::interpolation::InterpolatingBuffer::sample_at) | age beyond the buffered span |
+//! | [`UniformResampler`](crate::resample::UniformResampler) | input interval, [`grid_time_us`] |
+//! | [`FsyncTracker`](crate::fsync::FsyncTracker) | tag and sample intervals |
+//! | [`fifo_frame_timestamps_us`](crate::Mpu6050::fifo_frame_timestamps_us) | FIFO frame times |
+//! | [`MotionCompensation::apply`](crate::interrupt::MotionCompensation::apply) | motion event time |
+//! | [`DifferentialPair::read_pair_timed`](crate::differential::DifferentialPair::read_pair_timed) | read skew |
+//!
+//! ```
+//! use mpu6050::time_math::*;
+//!
+//! /// 32 bit µs timer
+//! struct Timer(u32);
+//!
+//! impl Clock for Timer {
+//!     const WIDTH_BITS: u32 = 32;
+//!
+//!     fn now_us(&mut self) -> u64 {
+//!         // 10 ms per reading
+//!         self.0 = self.0.wrapping_add(10_000);
+//!         self.0 as u64
+//!     }
+//! }
+//!
+//! let mut clock = ExtendedClock::new(Timer(u32::MAX - 15_000));
+//! let before = clock.now_us();
+//! let after = clock.now_us();
+//! assert_eq!(elapsed_us(before, after), 10_000);
+//! assert!(after > u32::MAX as u64);
+//!
+//! // three wraps between two readings, seen as one
+//! let mut extender = ClockExtender::new(32);
+//! extender.extend(100);
+//! let period = 1 << 32;
+//! let extension = extender.extend_expecting(50, 3 * period);
+//! assert_eq!(extension.skipped_wraps, 2);
+//! assert!(extension.is_flagged());
+//! assert_eq!(extension.t_us, 3 * period + 50);
+//! ```
+
+/// Time source of µs readings wrapping at 2^[`WIDTH_BITS`](Self::WIDTH_BITS)
+pub trait Clock {
+    /// significant bits of a reading, 1..=64
+    const WIDTH_BITS: u32 = 64;
+
+    /// current reading in µs, bits above `WIDTH_BITS` are ignored
+    fn now_us(&mut self) -> u64;
+}
+
+/// a closure is a 64 bit clock
+impl<F: FnMut() -> u64> Clock for F {
+    fn now_us(&mut self) -> u64 {
+        self()
+    }
+}
+
+/// The bits of a reading of a `width_bits` wide counter, `width_bits` clamped to 1..=64
+pub const fn width_mask(width_bits: u32) -> u64 {
+    let width_bits = clamp_width(width_bits);
+    u64::MAX >> (64 - width_bits)
+}
+
+/// period of a `width_bits` wide counter, 2^width_bits
+const fn period(width_bits: u32) -> u128 {
+    1u128 << clamp_width(width_bits)
+}
+
+const fn clamp_width(width_bits: u32) -> u32 {
+    match width_bits {
+        0 => 1,
+        1..=64 => width_bits,
+        _ => 64,
+    }
+}
+
+/// µs from raw reading `earlier` to raw reading `later` of a `width_bits` wide counter,
+/// assuming at most one wrap in between
+pub const fn wrapping_elapsed(earlier: u64, later: u64, width_bits: u32) -> u64 {
+    later.wrapping_sub(earlier) & width_mask(width_bits)
+}
+
+/// µs from extended timestamp `earlier` to `later`, 0 if `later` is older
+pub const fn elapsed_us(earlier: u64, later: u64) -> u64 {
+    later.saturating_sub(earlier)
+}
+
+/// signed µs from extended timestamp `from` to `to`, saturating at the i64 range
+pub const fn offset_us(from: u64, to: u64) -> i64 {
+    let offset = to as i128 - from as i128;
+    if offset > i64::MAX as i128 {
+        i64::MAX
+    } else if offset < i64::MIN as i128 {
+        i64::MIN
+    } else {
+        offset as i64
+    }
+}
+
+/// extended timestamp `back_us` before `t_us`, saturating at 0
+pub const fn before_us(t_us: u64, back_us: u64) -> u64 {
+    t_us.saturating_sub(back_us)
+}
+
+/// seconds from extended timestamp `earlier` to `later` for the filters' `dt`, 0 if `later`
+/// is older
+pub fn dt_s(earlier: u64, later: u64) -> f32 {
+    (elapsed_us(earlier, later) as f64 * 1e-6) as f32
+}
+
+/// extended timestamp of point `k` of a `rate_hz` grid starting at `origin_us`:
+/// `origin + floor(k * 1e6 / rate)`, saturating at u64::MAX. A rate of 0 is treated as 1 Hz
+pub const fn grid_time_us(origin_us: u64, k: u64, rate_hz: u32) -> u64 {
+    let rate_hz = if rate_hz == 0 { 1 } else { rate_hz };
+    let t_us = origin_us as u128 + k as u128 * 1_000_000 / rate_hz as u128;
+    if t_us > u64::MAX as u128 {
+        u64::MAX
+    } else {
+        t_us as u64
+    }
+}
+
+/// A raw reading extended to 64 bits, see the module docs
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct Extension {
+    /// extended timestamp in µs
+    pub t_us: u64,
+    /// full counter periods between the previous reading and this one, counted from the
+    /// expectation because the readings cannot show them
+    pub skipped_wraps: u64,
+    /// the expectation was closer to halfway between two wrap counts than a quarter period,
+    /// `skipped_wraps` is a guess
+    pub ambiguous: bool,
+}
+
+impl Extension {
+    /// the timestamp does not follow from the readings alone
+    pub fn is_flagged(&self) -> bool {
+        self.skipped_wraps > 0 || self.ambiguous
+    }
+}
+
+/// Extends the readings of a narrow counter to 64 bits by counting wraps, no clock access
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct ClockExtender {
+    width_bits: u32,
+    previous: Option<(u64, u64)>,
+}
+
+impl ClockExtender {
+    /// extender of a `width_bits` wide counter, clamped to 1..=64. The first reading extends
+    /// to itself
+    pub const fn new(width_bits: u32) -> Self {
+        Self {
+            width_bits: clamp_width(width_bits),
+            previous: None,
+        }
+    }
+
+    /// extender of the counter of `C`
+    pub const fn for_clock<C: Clock>() -> Self {
+        Self::new(C::WIDTH_BITS)
+    }
+
+    /// width of the counter
+    pub fn width_bits(&self) -> u32 {
+        self.width_bits
+    }
+
+    /// last extended timestamp, None before the first reading
+    pub fn last_us(&self) -> Option<u64> {
+        self.previous.map(|(_, t_us)| t_us)
+    }
+
+    /// `raw` extended, assuming at most one wrap since the previous reading
+    pub fn extend(&mut self, raw: u64) -> u64 {
+        self.extend_with(raw, None).t_us
+    }
+
+    /// `raw` extended, `expected_us` the time expected since the previous reading. Counts
+    /// the wraps the readings cannot show, see the module docs
+    pub fn extend_expecting(&mut self, raw: u64, expected_us: u64) -> Extension {
+        self.extend_with(raw, Some(expected_us))
+    }
+
+    fn extend_with(&mut self, raw: u64, expected_us: Option<u64>) -> Extension {
+        let raw = raw & width_mask(self.width_bits);
+        let Some((previous_raw, previous_us)) = self.previous else {
+            self.previous = Some((raw, raw));
+            return Extension {
+                t_us: raw,
+                skipped_wraps: 0,
+                ambiguous: false,
+            };
+        };
+        let seen = wrapping_elapsed(previous_raw, raw, self.width_bits) as u128;
+        let period = period(self.width_bits);
+        let (skipped_wraps, ambiguous) = match expected_us {
+            Some(expected) if expected as u128 > seen => {
+                // periods between the seen and the expected elapsed time, rounded
+                let missing = expected as u128 - seen;
+                let (wraps, rest) = (missing / period, missing % period);
+                let wraps = wraps + (rest >= period / 2) as u128;
+                let distance = rest.min(period - rest);
+                (wraps, distance > period / 4)
+            }
+            _ => (0, false),
+        };
+        let elapsed = seen + skipped_wraps * period;
+        let t_us = (previous_us as u128 + elapsed).min(u64::MAX as u128) as u64;
+        self.previous = Some((raw, t_us));
+        Extension {
+            t_us,
+            skipped_wraps: skipped_wraps.min(u64::MAX as u128) as u64,
+            ambiguous,
+        }
+    }
+}
+
+/// A [`Clock`] of any width read as a 64 bit clock, see the module docs
+#[derive(Clone, Debug)]
+pub struct ExtendedClock<C> {
+    clock: C,
+    extender: ClockExtender,
+}
+
+impl<C: Clock> ExtendedClock<C> {
+    /// wraps `clock`
+    pub fn new(clock: C) -> Self {
+        Self {
+            clock,
+            extender: ClockExtender::for_clock::<C>(),
+        }
+    }
+
+    /// reads the clock, `expected_us` the time expected since the previous reading
+    pub fn now_expecting(&mut self, expected_us: u64) -> Extension {
+        let raw = self.clock.now_us();
+        self.extender.extend_expecting(raw, expected_us)
+    }
+
+    /// the wrapped clock
+    pub fn inner(&mut self) -> &mut C {
+        &mut self.clock
+    }
+}
+
+impl<C: Clock> Clock for ExtendedClock<C> {
+    fn now_us(&mut self) -> u64 {
+        let raw = self.clock.now_us();
+        self.extender.extend(raw)
+    }
+}
@@ -0,0 +1,83 @@
+//! Bootloader sized subset of the driver, feature `minimal`.
+//!
+//! [`TinyMpu`] wakes the sensor, reads raw accelerometer counts and puts it back to sleep.
+//! Nothing else: no floats, no glam, no builder, no error wrapping, no state beyond the bus
+//! and address. The registers come from [`device`](crate::device), the same constants the
+//! full driver uses.
+//!
+//! Raw counts are at the power-on range of ±2g, 16384 counts per g, so decisions are plain
+//! integer comparisons. Face down is z below -0.8g:
+//! ```
+//! use embedded_hal::blocking::i2c::{Write, WriteRead};
+//! use mpu6050::tiny::TinyMpu;
+//!
+//! fn face_down<I, E>(mpu: &mut TinyMpu<I>) -> Result<bool, E>
+//! where
+//!     I: Write<Error = E> + WriteRead<Error = E>,
+//! {
+//!     let [_, _, z] = mpu.read_accel_raw()?;
+//!     Ok(z < -13107)
+//! }
+//! ```
+//!
+//! #### Flash size target
+//! `wake`, `read_accel_raw` and `sleep` together stay below 512 bytes of code on
+//! `thumbv6m-none-eabi` at `opt-level = "z"`, without the bus implementation and core
+//! intrinsics. `scripts/size-check.sh` measures it with the `tiny_size` example and fails
+//! above the target. `tests/minimal.rs` checks the build itself: `no_std` and no glam in the
+//! dependency graph.
+
+use embedded_hal::blocking::i2c::{Write, WriteRead};
+
+use crate::device::{ACC_REGX_H, CLKSEL, PWR_MGMT_1};
+
+/// Minimal MPU6050 access: wake, raw accelerometer, sleep
+pub struct TinyMpu<I> {
+    i2c: I,
+    address: u8,
+}
+
+impl<I, E> TinyMpu<I>
+where
+    I: Write<Error = E> + WriteRead<Error = E>,
+{
+    /// new sensor at `address`, usually [`DEFAULT_SLAVE_ADDR`](crate::device::DEFAULT_SLAVE_ADDR).
+    /// No bus access
+    pub fn new(i2c: I, address: u8) -> Self {
+        Self { i2c, address }
+    }
+
+    /// clears sleep and selects the X gyro PLL, like the full driver's init. The accelerometer
+    /// needs about 100 ms after this before its data is valid, the delay is up to the caller
+    pub fn wake(&mut self) -> Result<(), E> {
+        self.i2c
+            .write(self.address, &[PWR_MGMT_1::ADDR, CLKSEL::GXAXIS as u8])
+    }
+
+    /// raw accelerometer counts x, y, z
+    pub fn read_accel_raw(&mut self) -> Result<[i16; 3], E> {
+        let mut buf = [0u8; 6];
+        self.i2c.write_read(self.address, &[ACC_REGX_H], &mut buf)?;
+        Ok([
+            i16::from_be_bytes([buf[0], buf[1]]),
+            i16::from_be_bytes([buf[2], buf[3]]),
+            i16::from_be_bytes([buf[4], buf[5]]),
+        ])
+    }
+
+    /// sets sleep, keeping the clock source selected by [`wake`](Self::wake)
+    pub fn sleep(&mut self) -> Result<(), E> {
+        self.i2c.write(
+            self.address,
+            &[
+                PWR_MGMT_1::ADDR,
+                1 << PWR_MGMT_1::SLEEP | CLKSEL::GXAXIS as u8,
+            ],
+        )
+    }
+
+    /// returns the bus
+    pub fn release(self) -> I {
+        self.i2c
+    }
+}
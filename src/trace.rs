@@ -0,0 +1,426 @@
+//! Transaction traces and golden files for register access ordering.
+//!
+//! Some sequences only work in one order: the clock source is selected before sleep is
+//! cleared, bypass is enabled only after the I2C master is disabled. [`TracingI2c`] wraps
+//! any I2C implementation and records every transaction, [`TraceHandle::render`] turns the
+//! record into one canonical line per transaction:
+//!
+//! ```text
+//! # init
+//! W 0x68 PWR_MGMT_1 [01]
+//! R 0x68 WHO_AM_I [68]
+//! R 0x68 FIFO_EN [00]
+//! W 0x68 FIFO_EN [78]
+//! ```
+//!
+//! direction (`W`rite, `R`ead), bus address, register by name (hex for registers unknown to
+//! the driver, `-` for reads without register) and the payload. Lines starting with `#` are
+//! labels inserted with [`TraceHandle::mark`].
+//!
+//! [`check_golden`] compares a rendered trace against a committed golden file and describes
+//! a mismatch as a line diff. With the environment variable `UPDATE_GOLDENS` set to anything
+//! but `0` the golden file is (re)written instead, to be reviewed and committed along with an
+//! intentional change of the sequence.
+//!
+//! Enable with the `test-util` feature.
+
+use core::cell::RefCell;
+use core::fmt::{self, Display};
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::string::String;
+use std::vec::Vec;
+
+use embedded_hal::blocking::i2c::{Read, Write, WriteRead};
+
+use crate::device::{
+    register_info, register_pair, ACC_REGX_H, ACC_REGY_H, ACC_REGZ_H, EXT_SENS_DATA_00,
+    EXT_SENS_DATA_LEN, FIFO_COUNT_H, FIFO_EN, FIFO_R_W, GYRO_REGX_H, GYRO_REGY_H, GYRO_REGZ_H,
+    I2C_MST_CTRL, I2C_SLV, INT_STATUS, MOT_DETECT_STATUS, SIGNAL_PATH_RESET, TEMP_OUT_H, WHOAMI,
+};
+
+/// Environment variable switching [`check_golden`] from comparing to writing
+pub const UPDATE_GOLDENS_VAR: &str = "UPDATE_GOLDENS";
+
+/// Direction of a recorded transaction
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Direction {
+    /// master to device, the first byte is the register
+    Write,
+    /// device to master, after writing the register with a repeated start if `reg` is set
+    Read,
+}
+
+/// Recorded transaction or label
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum TraceEntry {
+    /// bus transaction, recorded whether it succeeded or not
+    Transaction {
+        /// direction
+        direction: Direction,
+        /// 7 bit bus address
+        address: u8,
+        /// register written before the payload, None for plain reads
+        reg: Option<u8>,
+        /// bytes written after the register, or bytes read
+        data: Vec<u8>,
+        /// false if the inner bus returned an error
+        ok: bool,
+    },
+    /// label from [`TraceHandle::mark`]
+    Mark(String),
+}
+
+impl Display for TraceEntry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (direction, address, reg, data, ok) = match self {
+            TraceEntry::Mark(label) => return write!(f, "# {}", label),
+            TraceEntry::Transaction {
+                direction,
+                address,
+                reg,
+                data,
+                ok,
+            } => (direction, address, reg, data, ok),
+        };
+        let direction = match direction {
+            Direction::Write => 'W',
+            Direction::Read => 'R',
+        };
+        write!(f, "{} 0x{:02x} ", direction, address)?;
+        match reg {
+            Some(reg) => write_register_name(f, *reg)?,
+            None => f.write_str("-")?,
+        }
+        f.write_str(" [")?;
+        for (i, byte) in data.iter().enumerate() {
+            let sep = if i == 0 { "" } else { " " };
+            write!(f, "{}{:02x}", sep, byte)?;
+        }
+        f.write_str("]")?;
+        if !ok {
+            f.write_str(" ERR")?;
+        }
+        Ok(())
+    }
+}
+
+/// Writes the register name, decoded registers first, then the fixed function registers
+fn write_register_name(f: &mut fmt::Formatter<'_>, reg: u8) -> fmt::Result {
+    if let Some(info) = register_info(reg) {
+        return f.write_str(info.name);
+    }
+    let name = match reg {
+        WHOAMI => "WHO_AM_I",
+        FIFO_EN => "FIFO_EN",
+        I2C_MST_CTRL::ADDR => "I2C_MST_CTRL",
+        FIFO_COUNT_H => "FIFO_COUNTH",
+        FIFO_R_W => "FIFO_R_W",
+        INT_STATUS::ADDR => "INT_STATUS",
+        MOT_DETECT_STATUS::ADDR => "MOT_DETECT_STATUS",
+        SIGNAL_PATH_RESET::ADDR => "SIGNAL_PATH_RESET",
+        ACC_REGX_H => "ACCEL_XOUT_H",
+        ACC_REGY_H => "ACCEL_YOUT_H",
+        ACC_REGZ_H => "ACCEL_ZOUT_H",
+        TEMP_OUT_H => "TEMP_OUT_H",
+        GYRO_REGX_H => "GYRO_XOUT_H",
+        GYRO_REGY_H => "GYRO_YOUT_H",
+        GYRO_REGZ_H => "GYRO_ZOUT_H",
+        _ => "",
+    };
+    if !name.is_empty() {
+        return f.write_str(name);
+    }
+    if let Some(pair) = register_pair(reg).filter(|pair| pair.high == reg) {
+        return f.write_str(pair.name);
+    }
+    let slaves = I2C_SLV::ADDR_BASE..I2C_SLV::ctrl_reg(3) + 1;
+    if slaves.contains(&reg) {
+        let offset = reg - I2C_SLV::ADDR_BASE;
+        let part = ["ADDR", "REG", "CTRL"][(offset % I2C_SLV::STRIDE) as usize];
+        return write!(f, "I2C_SLV{}_{}", offset / I2C_SLV::STRIDE, part);
+    }
+    if (EXT_SENS_DATA_00..EXT_SENS_DATA_00 + EXT_SENS_DATA_LEN).contains(&reg) {
+        return write!(f, "EXT_SENS_DATA_{:02}", reg - EXT_SENS_DATA_00);
+    }
+    write!(f, "0x{:02x}", reg)
+}
+
+/// Access to the record of a [`TracingI2c`] owned by someone else, usually the driver
+#[derive(Clone, Debug, Default)]
+pub struct TraceHandle {
+    entries: Rc<RefCell<Vec<TraceEntry>>>,
+}
+
+impl TraceHandle {
+    /// inserts a label, rendered as `# label`
+    pub fn mark(&self, label: &str) {
+        self.entries
+            .borrow_mut()
+            .push(TraceEntry::Mark(label.into()));
+    }
+
+    /// entries recorded so far
+    pub fn entries(&self) -> Vec<TraceEntry> {
+        self.entries.borrow().clone()
+    }
+
+    /// number of recorded transactions, labels not included
+    pub fn transactions(&self) -> usize {
+        self.entries
+            .borrow()
+            .iter()
+            .filter(|entry| matches!(entry, TraceEntry::Transaction { .. }))
+            .count()
+    }
+
+    /// forgets everything recorded so far, e.g. the setup of a test
+    pub fn clear(&self) {
+        self.entries.borrow_mut().clear();
+    }
+
+    /// canonical text form, one line per entry, every line terminated by a newline
+    pub fn render(&self) -> String {
+        render(&self.entries.borrow())
+    }
+}
+
+/// canonical text form of `entries`, see the [module docs](self)
+pub fn render(entries: &[TraceEntry]) -> String {
+    let mut out = String::new();
+    for entry in entries {
+        out.push_str(&entry.to_string());
+        out.push('\n');
+    }
+    out
+}
+
+/// I2C wrapper recording every transaction
+#[derive(Debug)]
+pub struct TracingI2c<I> {
+    inner: I,
+    handle: TraceHandle,
+}
+
+impl<I> TracingI2c<I> {
+    /// Wraps `inner`, the handle reads the record while the wrapper is owned by the driver
+    pub fn new(inner: I) -> (Self, TraceHandle) {
+        let handle = TraceHandle::default();
+        let wrapper = Self {
+            inner,
+            handle: handle.clone(),
+        };
+        (wrapper, handle)
+    }
+
+    /// another handle to the record
+    pub fn handle(&self) -> TraceHandle {
+        self.handle.clone()
+    }
+
+    /// wrapped bus
+    pub fn inner(&mut self) -> &mut I {
+        &mut self.inner
+    }
+
+    /// unwraps the bus
+    pub fn into_inner(self) -> I {
+        self.inner
+    }
+
+    fn record(&self, direction: Direction, address: u8, reg: Option<u8>, data: &[u8], ok: bool) {
+        self.handle
+            .entries
+            .borrow_mut()
+            .push(TraceEntry::Transaction {
+                direction,
+                address,
+                reg,
+                data: data.into(),
+                ok,
+            });
+    }
+}
+
+impl<I: Write> Write for TracingI2c<I> {
+    type Error = I::Error;
+
+    fn write(&mut self, address: u8, bytes: &[u8]) -> Result<(), Self::Error> {
+        let res = self.inner.write(address, bytes);
+        let (reg, data) = match bytes.split_first() {
+            Some((reg, data)) => (Some(*reg), data),
+            None => (None, bytes),
+        };
+        self.record(Direction::Write, address, reg, data, res.is_ok());
+        res
+    }
+}
+
+impl<I: Read> Read for TracingI2c<I> {
+    type Error = I::Error;
+
+    fn read(&mut self, address: u8, buffer: &mut [u8]) -> Result<(), Self::Error> {
+        let res = self.inner.read(address, buffer);
+        self.record(Direction::Read, address, None, buffer, res.is_ok());
+        res
+    }
+}
+
+impl<I: WriteRead> WriteRead for TracingI2c<I> {
+    type Error = I::Error;
+
+    fn write_read(
+        &mut self,
+        address: u8,
+        bytes: &[u8],
+        buffer: &mut [u8],
+    ) -> Result<(), Self::Error> {
+        let res = self.inner.write_read(address, bytes, buffer);
+        // only the register pointer is written before a read
+        self.record(
+            Direction::Read,
+            address,
+            bytes.first().copied(),
+            buffer,
+            res.is_ok(),
+        );
+        res
+    }
+}
+
+/// Trace differing from its golden file
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct GoldenMismatch {
+    /// golden file
+    pub path: PathBuf,
+    /// golden content, None if the file does not exist or is unreadable
+    pub expected: Option<String>,
+    /// rendered trace
+    pub actual: String,
+}
+
+impl Display for GoldenMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.expected {
+            None => writeln!(f, "golden file {} missing", self.path.display())?,
+            Some(expected) => {
+                writeln!(f, "trace differs from {}", self.path.display())?;
+                f.write_str(&line_diff(expected, &self.actual))?;
+            }
+        }
+        write!(
+            f,
+            "rerun with {}=1 to accept the new trace, then review the golden diff",
+            UPDATE_GOLDENS_VAR
+        )
+    }
+}
+
+impl std::error::Error for GoldenMismatch {}
+
+/// true if [`UPDATE_GOLDENS_VAR`] asks for goldens to be rewritten
+pub fn update_requested() -> bool {
+    std::env::var_os(UPDATE_GOLDENS_VAR).is_some_and(|value| value != "0" && !value.is_empty())
+}
+
+/// Compares `actual` with the golden file at `path`, or writes it if [`update_requested`].
+/// Line endings are normalized, so goldens checked out with CRLF still match
+pub fn check_golden(path: impl AsRef<Path>, actual: &str) -> Result<(), GoldenMismatch> {
+    let path = path.as_ref();
+    if update_requested() {
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir).ok();
+        }
+        let written = std::fs::write(path, actual);
+        return written.map_err(|_| GoldenMismatch {
+            path: path.into(),
+            expected: None,
+            actual: actual.into(),
+        });
+    }
+    let expected = std::fs::read_to_string(path)
+        .ok()
+        .map(|golden| golden.replace("\r\n", "\n"));
+    if expected.as_deref() == Some(actual) {
+        return Ok(());
+    }
+    Err(GoldenMismatch {
+        path: path.into(),
+        expected,
+        actual: actual.into(),
+    })
+}
+
+/// Lines of context kept around changes in [`line_diff`]
+const DIFF_CONTEXT: usize = 2;
+
+/// Line diff from `expected` to `actual`: `-` lines only in expected, `+` lines only in
+/// actual, each with its line number on that side, unchanged lines with their number in
+/// actual. Unchanged runs are cut down to
+/// [`DIFF_CONTEXT`] lines around changes
+/// ```
+/// use mpu6050::trace::line_diff;
+///
+/// let expected = "W 0x68 PWR_MGMT_1 [01]\nR 0x68 WHO_AM_I [68]\n";
+/// let actual = "R 0x68 WHO_AM_I [68]\nW 0x68 PWR_MGMT_1 [01]\n";
+/// assert_eq!(
+///     line_diff(expected, actual),
+///     "-    1 W 0x68 PWR_MGMT_1 [01]\n     1 R 0x68 WHO_AM_I [68]\n+    2 W 0x68 PWR_MGMT_1 [01]\n"
+/// );
+/// ```
+pub fn line_diff(expected: &str, actual: &str) -> String {
+    let a: Vec<&str> = expected.lines().collect();
+    let b: Vec<&str> = actual.lines().collect();
+    // longest common subsequence table, lcs[i][j] for the suffixes a[i..] and b[j..]
+    let mut lcs = vec![vec![0usize; b.len() + 1]; a.len() + 1];
+    for i in (0..a.len()).rev() {
+        for j in (0..b.len()).rev() {
+            lcs[i][j] = if a[i] == b[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    // (marker, line number, text)
+    let mut ops = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < a.len() || j < b.len() {
+        if i < a.len() && j < b.len() && a[i] == b[j] {
+            ops.push((' ', j + 1, a[i]));
+            i += 1;
+            j += 1;
+        } else if i < a.len() && (j == b.len() || lcs[i + 1][j] >= lcs[i][j + 1]) {
+            ops.push(('-', i + 1, a[i]));
+            i += 1;
+        } else {
+            ops.push(('+', j + 1, b[j]));
+            j += 1;
+        }
+    }
+
+    let changed: Vec<usize> = (0..ops.len()).filter(|k| ops[*k].0 != ' ').collect();
+    let near_change = |k: usize| {
+        changed
+            .iter()
+            .any(|c| k + DIFF_CONTEXT >= *c && k <= c + DIFF_CONTEXT)
+    };
+    let mut out = String::new();
+    let mut skipped = false;
+    for (k, (marker, line, text)) in ops.iter().enumerate() {
+        if !near_change(k) {
+            skipped = true;
+            continue;
+        }
+        if skipped {
+            out.push_str("  ...\n");
+            skipped = false;
+        }
+        out.push_str(&format!("{}{:>5} {}\n", marker, line, text));
+    }
+    if skipped && !out.is_empty() {
+        out.push_str("  ...\n");
+    }
+    out
+}
@@ -0,0 +1,315 @@
+//! Guarded sections: bus traffic a control window does not expect is caught where it
+//! happens.
+//!
+//! A feature turned on elsewhere can add a transaction to a timing critical path, e.g. a
+//! temperature read inside a 500 µs control window, and only show as a missed deadline.
+//! [`Mpu6050::traffic_guard`] opens a section in which every transaction needs a permission
+//! granted with [`TrafficGuard::allow`]; the guard derefs to the driver, the section is its
+//! lifetime. A transaction beyond its permissions is a violation, checked before it reaches
+//! the bus:
+//!
+//! | build | [`ViolationAction`] by default | violation |
+//! |:---|:---|:---|
+//! | debug assertions on | [`Panic`](ViolationAction::Panic) | counted, then a panic naming the op, the transaction is not attempted |
+//! | debug assertions off | [`Count`](ViolationAction::Count) | counted, the transaction runs |
+//! | `no-guards` feature | | nothing: the accounting is empty and compiles away |
+//!
+//! Violations add up in [`Mpu6050::traffic_violations`] over the driver's life and emit
+//! [`TRAFFIC_VIOLATIONS`](crate::metrics::TRAFFIC_VIOLATIONS), the guard's own
+//! [`violations`](TrafficGuard::violations) count those of its section. Transactions the
+//! disconnect or dormant checks refuse are no traffic and need no permission.
+//!
+//! #### Classification
+//! Every transaction is one [`DriverOp`]. The read-modify-write of
+//! [`modify_register`](Mpu6050::modify_register), and the bit and field writes built on it,
+//! declares itself as one [`ConfigUpdate`](DriverOp::ConfigUpdate) for both of its
+//! transactions; every other transaction is classified from its register and length:
+//!
+//! | transaction | op |
+//! |:---|:---|
+//! | write | [`ConfigWrite`](DriverOp::ConfigWrite), register pairs included |
+//! | 14 bytes from ACCEL_XOUT_H | [`SampleBurst`](DriverOp::SampleBurst) |
+//! | 6 bytes from ACCEL_XOUT_H | [`AccelRead`](DriverOp::AccelRead) |
+//! | 6 bytes from GYRO_XOUT_H | [`GyroRead`](DriverOp::GyroRead) |
+//! | 2 bytes from TEMP_OUT_H | [`TempRead`](DriverOp::TempRead) |
+//! | 1 byte from INT_STATUS | [`InterruptStatusRead`](DriverOp::InterruptStatusRead) |
+//! | n bytes from FIFO_R_W | [`FifoDrain(n)`](DriverOp::FifoDrain) |
+//! | any other read of n bytes | [`RegisterRead(n)`](DriverOp::RegisterRead) |
+//!
+//! A permission of a sized op covers transactions of at most its size, the largest granted
+//! for the op applies. Nested guards keep their own permissions, the outer section's are
+//! back when the inner guard drops.
+//! ```
+//! # use mpu6050::prelude::*;
+//! # use mpu6050::bus::DriverOp;
+//! # use embedded_hal::blocking::i2c::{Write, WriteRead};
+//! fn control_step<I, E>(mpu: &mut Mpu6050<I>) -> Result<(Vec3A, Vec3A), Mpu6050Error<E>>
+//! where
+//!     I: Write<Error = E> + WriteRead<Error = E>,
+//! {
+//!     let mut guard = mpu.traffic_guard();
+//!     guard
+//!         .allow(DriverOp::AccelRead, 1)
+//!         .allow(DriverOp::GyroRead, 1);
+//!     // anything else in here panics in a debug build
+//!     Ok((guard.get_acc()?, guard.get_gyro()?))
+//! }
+//! ```
+
+use core::ops::{Deref, DerefMut};
+
+use crate::bus::DriverOp;
+#[cfg(feature = "driver")]
+use crate::registers::{ACC_REGX_H, FIFO_R_W, GYRO_REGX_H, INT_STATUS, TEMP_OUT_H};
+use crate::Mpu6050;
+
+/// What a violation does, see the module docs
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum ViolationAction {
+    /// count it and panic before the transaction
+    Panic,
+    /// count it, the transaction runs
+    Count,
+}
+
+impl Default for ViolationAction {
+    /// [`Panic`](Self::Panic) with debug assertions, [`Count`](Self::Count) without
+    fn default() -> Self {
+        match cfg!(debug_assertions) {
+            true => ViolationAction::Panic,
+            false => ViolationAction::Count,
+        }
+    }
+}
+
+/// ops with their own permissions, payload sizes aside
+#[cfg(not(feature = "no-guards"))]
+const KINDS: usize = 9;
+
+/// permission slot of `op`, and its size for the sized ops
+#[cfg(not(feature = "no-guards"))]
+fn slot(op: DriverOp) -> (usize, usize) {
+    match op {
+        DriverOp::SampleBurst => (0, 0),
+        DriverOp::AccelRead => (1, 0),
+        DriverOp::GyroRead => (2, 0),
+        DriverOp::TempRead => (3, 0),
+        DriverOp::FifoDrain(n) => (4, n),
+        DriverOp::ConfigWrite => (5, 0),
+        DriverOp::ConfigUpdate => (6, 0),
+        DriverOp::InterruptStatusRead => (7, 0),
+        DriverOp::RegisterRead(n) => (8, n),
+    }
+}
+
+/// Permissions of an open section
+#[cfg(not(feature = "no-guards"))]
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+struct Section {
+    remaining: [u32; KINDS],
+    max_len: [usize; KINDS],
+    action: ViolationAction,
+    violations: u32,
+}
+
+/// Guard state of the driver, empty with `no-guards`
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub(crate) struct TrafficAccounting {
+    #[cfg(not(feature = "no-guards"))]
+    section: Option<Section>,
+    /// op declared by the running access of several transactions, and whether it was charged
+    #[cfg(not(feature = "no-guards"))]
+    declared: Option<(DriverOp, bool)>,
+    #[cfg(not(feature = "no-guards"))]
+    violations: u32,
+    #[cfg(not(feature = "no-guards"))]
+    last_violation: Option<DriverOp>,
+}
+
+impl TrafficAccounting {
+    pub(crate) fn violations(&self) -> u32 {
+        #[cfg(not(feature = "no-guards"))]
+        return self.violations;
+        #[cfg(feature = "no-guards")]
+        0
+    }
+
+    pub(crate) fn last_violation(&self) -> Option<DriverOp> {
+        #[cfg(not(feature = "no-guards"))]
+        return self.last_violation;
+        #[cfg(feature = "no-guards")]
+        None
+    }
+
+    /// Charges a transaction of `op` to the open section, true if it was a violation.
+    /// Panics on a violation with [`ViolationAction::Panic`]
+    #[cfg_attr(feature = "no-guards", allow(unused_variables))]
+    #[inline(always)]
+    pub(crate) fn admit(&mut self, op: DriverOp) -> bool {
+        #[cfg(not(feature = "no-guards"))]
+        {
+            let Some(section) = self.section.as_mut() else {
+                return false;
+            };
+            let op = match self.declared.as_mut() {
+                Some((_, true)) => return false,
+                Some((declared, charged)) => {
+                    *charged = true;
+                    *declared
+                }
+                None => op,
+            };
+            let (slot, len) = slot(op);
+            if section.remaining[slot] > 0 && len <= section.max_len[slot] {
+                section.remaining[slot] -= 1;
+                return false;
+            }
+            section.violations = section.violations.saturating_add(1);
+            self.violations = self.violations.saturating_add(1);
+            self.last_violation = Some(op);
+            if section.action == ViolationAction::Panic {
+                panic!("bus traffic not allowed in the guarded section: {:?}", op);
+            }
+            true
+        }
+        #[cfg(feature = "no-guards")]
+        false
+    }
+}
+
+impl<I, D> Mpu6050<I, D> {
+    /// Opens a guarded section without permissions, see the
+    /// [`traffic_guard`](crate::traffic_guard) module
+    pub fn traffic_guard(&mut self) -> TrafficGuard<'_, I, D> {
+        #[cfg(not(feature = "no-guards"))]
+        let outer = self.traffic.section.replace(Section {
+            remaining: [0; KINDS],
+            max_len: [0; KINDS],
+            action: ViolationAction::default(),
+            violations: 0,
+        });
+        TrafficGuard {
+            mpu: self,
+            #[cfg(not(feature = "no-guards"))]
+            outer,
+        }
+    }
+
+    /// violations of guarded sections since the driver was built, saturating. Always 0 with
+    /// `no-guards`
+    pub fn traffic_violations(&self) -> u32 {
+        self.traffic.violations()
+    }
+
+    /// op of the latest violation, None before the first
+    pub fn last_traffic_violation(&self) -> Option<DriverOp> {
+        self.traffic.last_violation()
+    }
+
+    /// runs `f` as one `op`, its transactions charged once
+    #[cfg_attr(feature = "no-guards", allow(unused_variables))]
+    #[inline(always)]
+    pub(crate) fn as_op<T>(&mut self, op: DriverOp, f: impl FnOnce(&mut Self) -> T) -> T {
+        #[cfg(not(feature = "no-guards"))]
+        let outer = self.traffic.declared.replace((op, false));
+        let res = f(self);
+        #[cfg(not(feature = "no-guards"))]
+        {
+            self.traffic.declared = outer;
+        }
+        res
+    }
+}
+
+/// op of a read of `len` bytes from `reg`, see the module docs
+#[cfg(feature = "driver")]
+pub(crate) fn classify_read(reg: u8, len: usize) -> DriverOp {
+    match (reg, len) {
+        (ACC_REGX_H, 14) => DriverOp::SampleBurst,
+        (ACC_REGX_H, 6) => DriverOp::AccelRead,
+        (GYRO_REGX_H, 6) => DriverOp::GyroRead,
+        (TEMP_OUT_H, 2) => DriverOp::TempRead,
+        (INT_STATUS::ADDR, 1) => DriverOp::InterruptStatusRead,
+        (FIFO_R_W, n) => DriverOp::FifoDrain(n),
+        (_, n) => DriverOp::RegisterRead(n),
+    }
+}
+
+/// Open guarded section, derefs to the driver. Dropping it closes the section
+pub struct TrafficGuard<'a, I, D = crate::NoDelay> {
+    mpu: &'a mut Mpu6050<I, D>,
+    #[cfg(not(feature = "no-guards"))]
+    outer: Option<Section>,
+}
+
+impl<I, D> TrafficGuard<'_, I, D> {
+    /// permits `count` more transactions of `op`, of at most its size for the sized ops
+    #[cfg_attr(feature = "no-guards", allow(unused_variables))]
+    pub fn allow(&mut self, op: DriverOp, count: u32) -> &mut Self {
+        #[cfg(not(feature = "no-guards"))]
+        if let Some(section) = self.mpu.traffic.section.as_mut() {
+            let (slot, len) = slot(op);
+            section.remaining[slot] = section.remaining[slot].saturating_add(count);
+            section.max_len[slot] = section.max_len[slot].max(len);
+        }
+        self
+    }
+
+    /// sets what a violation does, the build's default otherwise
+    #[cfg_attr(feature = "no-guards", allow(unused_variables))]
+    pub fn on_violation(&mut self, action: ViolationAction) -> &mut Self {
+        #[cfg(not(feature = "no-guards"))]
+        if let Some(section) = self.mpu.traffic.section.as_mut() {
+            section.action = action;
+        }
+        self
+    }
+
+    /// permissions of `op` left
+    #[cfg_attr(feature = "no-guards", allow(unused_variables))]
+    pub fn remaining(&self, op: DriverOp) -> u32 {
+        #[cfg(not(feature = "no-guards"))]
+        return self
+            .mpu
+            .traffic
+            .section
+            .map_or(0, |section| section.remaining[slot(op).0]);
+        #[cfg(feature = "no-guards")]
+        0
+    }
+
+    /// violations in this section
+    pub fn violations(&self) -> u32 {
+        #[cfg(not(feature = "no-guards"))]
+        return self
+            .mpu
+            .traffic
+            .section
+            .map_or(0, |section| section.violations);
+        #[cfg(feature = "no-guards")]
+        0
+    }
+}
+
+impl<I, D> Deref for TrafficGuard<'_, I, D> {
+    type Target = Mpu6050<I, D>;
+
+    fn deref(&self) -> &Self::Target {
+        self.mpu
+    }
+}
+
+impl<I, D> DerefMut for TrafficGuard<'_, I, D> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.mpu
+    }
+}
+
+impl<I, D> Drop for TrafficGuard<'_, I, D> {
+    fn drop(&mut self) {
+        #[cfg(not(feature = "no-guards"))]
+        {
+            self.mpu.traffic.section = self.outer.take();
+        }
+    }
+}
@@ -0,0 +1,164 @@
+//! Register reads with or without a repeated start.
+//!
+//! A register read is a write of the register pointer followed by the read. Every read of
+//! the driver is a single `write_read`, which an I2C implementation is expected to issue with
+//! a repeated start in between. Some controllers (certain ESP-IDF configurations, bit-banged
+//! buses) put a stop there or fail the combined transfer now and then. The MPU6050 tolerates
+//! the stop: the pointer written in one transaction is where a later bare read starts.
+//!
+//! [`TransferI2c`] wraps an I2C implementation and issues reads in the [`TransferMode`] it
+//! is set to, [`TransferMode::StopStart`] being a `write` of the pointer followed by a bare
+//! `read`. The bare read needs the `Read` trait, so only buses wrapped in [`TransferI2c`]
+//! have to provide it, everything else keeps the `Write + WriteRead` bounds. The mode of a
+//! driver owning the wrapper is changed with
+//! [`Mpu6050::set_transfer_mode`](crate::Mpu6050::set_transfer_mode), at any time between
+//! operations: every read carries its own pointer in either mode, nothing depends on the
+//! mode of the previous one.
+//!
+//! # Auto increment
+//!
+//! The register pointer advances by one after every byte read or written, which is what makes
+//! burst reads work. The exception is FIFO_R_W: the pointer stays, consecutive bytes read
+//! are consecutive FIFO bytes. In stop-start mode this matters in two ways:
+//!
+//! - the pointer is written before every read, never left to where the previous transaction
+//!   put it. After a burst of ACCEL_XOUT_H..GYRO_ZOUT_L it rests at EXT_SENS_DATA_00, after
+//!   reading FIFO_COUNTH and FIFO_COUNTL it happens to rest at FIFO_R_W, and a bare read
+//!   relying on either would silently read (and, from the FIFO, consume) other data
+//! - a FIFO drain writes the FIFO_R_W pointer once and reads all its frames in one bare read,
+//!   the pointer not advancing past FIFO_R_W. The same holds for the repeated start
+//!
+//! ```
+//! use embedded_hal::blocking::i2c::{Read, Write, WriteRead};
+//! use mpu6050::transfer::{TransferI2c, TransferMode};
+//!
+//! /// answers every read with the register pointer
+//! #[derive(Default)]
+//! struct Pointer(u8);
+//!
+//! impl Write for Pointer {
+//!     type Error = ();
+//!     fn write(&mut self, _: u8, bytes: &[u8]) -> Result<(), ()> {
+//!         self.0 = bytes[0];
+//!         Ok(())
+//!     }
+//! }
+//!
+//! impl Read for Pointer {
+//!     type Error = ();
+//!     fn read(&mut self, _: u8, buffer: &mut [u8]) -> Result<(), ()> {
+//!         buffer.fill(self.0);
+//!         Ok(())
+//!     }
+//! }
+//!
+//! impl WriteRead for Pointer {
+//!     type Error = ();
+//!     fn write_read(&mut self, _: u8, _: &[u8], _: &mut [u8]) -> Result<(), ()> {
+//!         panic!("no repeated start expected");
+//!     }
+//! }
+//!
+//! let mut i2c = TransferI2c::new(Pointer::default(), TransferMode::StopStart);
+//! let mut buf = [0; 2];
+//! i2c.write_read(0x68, &[0x3b], &mut buf).unwrap();
+//! assert_eq!(buf, [0x3b; 2]);
+//! ```
+
+use embedded_hal::blocking::i2c::{Read, Write, WriteRead};
+
+#[cfg(feature = "driver")]
+use crate::Mpu6050;
+
+/// How a register read is put on the bus
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub enum TransferMode {
+    /// pointer write and read in one `write_read`, with a repeated start in between
+    #[default]
+    RepeatedStart,
+    /// pointer `write`, stop, then a bare `read`
+    StopStart,
+}
+
+/// I2C wrapper issuing `write_read` in its [`TransferMode`]
+///
+/// Writes and bare reads pass through unchanged.
+#[derive(Debug)]
+pub struct TransferI2c<I> {
+    inner: I,
+    mode: TransferMode,
+}
+
+impl<I> TransferI2c<I> {
+    /// wraps `inner`, reading in `mode`
+    pub fn new(inner: I, mode: TransferMode) -> Self {
+        Self { inner, mode }
+    }
+
+    /// current mode
+    pub fn mode(&self) -> TransferMode {
+        self.mode
+    }
+
+    /// mode of the following reads
+    pub fn set_mode(&mut self, mode: TransferMode) {
+        self.mode = mode;
+    }
+
+    /// wrapped bus
+    pub fn inner(&mut self) -> &mut I {
+        &mut self.inner
+    }
+
+    /// unwraps the bus
+    pub fn into_inner(self) -> I {
+        self.inner
+    }
+}
+
+impl<I: Write> Write for TransferI2c<I> {
+    type Error = I::Error;
+
+    fn write(&mut self, address: u8, bytes: &[u8]) -> Result<(), Self::Error> {
+        self.inner.write(address, bytes)
+    }
+}
+
+impl<I: Read> Read for TransferI2c<I> {
+    type Error = I::Error;
+
+    fn read(&mut self, address: u8, buffer: &mut [u8]) -> Result<(), Self::Error> {
+        self.inner.read(address, buffer)
+    }
+}
+
+impl<I, E> WriteRead for TransferI2c<I>
+where
+    I: Write<Error = E> + Read<Error = E> + WriteRead<Error = E>,
+{
+    type Error = E;
+
+    fn write_read(&mut self, address: u8, bytes: &[u8], buffer: &mut [u8]) -> Result<(), E> {
+        match self.mode {
+            TransferMode::RepeatedStart => self.inner.write_read(address, bytes, buffer),
+            TransferMode::StopStart => {
+                self.inner.write(address, bytes)?;
+                self.inner.read(address, buffer)
+            }
+        }
+    }
+}
+
+#[cfg(feature = "driver")]
+impl<I, D> Mpu6050<TransferI2c<I>, D> {
+    /// Mode of the following register reads, burst and FIFO reads included. Takes effect
+    /// with the next read, see [`transfer`](crate::transfer)
+    pub fn set_transfer_mode(&mut self, mode: TransferMode) {
+        self.i2c.set_mode(mode);
+    }
+
+    /// mode of the register reads
+    pub fn get_transfer_mode(&self) -> TransferMode {
+        self.i2c.mode()
+    }
+}
@@ -0,0 +1,557 @@
+//! Calibration metadata and an expiry and invalidation policy.
+//!
+//! Each [`CalibrationKind`] the driver applies can carry a [`CalibrationMeta`]: how and when
+//! it was made, the temperature it was made at and its quality. A [`CalibrationPolicy`],
+//! set with [`Mpu6050::set_calibration_policy`], evaluates the metadata against the sample
+//! stream where samples leave the driver (see [`hook`](crate::hook)) and against the
+//! application time passed to [`Mpu6050::set_calibration_time`]:
+//!
+//! | rule | reason | status | clears |
+//! |:---|:---|:---|:---|
+//! | `max_age` | [`Age`](InvalidationReason::Age): older than the limit | [`Expired`](CalibrationStatus::Expired) | new metadata |
+//! | `max_temp_delta_c` | [`Temperature`](InvalidationReason::Temperature): further from the calibration temperature | [`Degraded`](CalibrationStatus::Degraded) | back within the limit |
+//! | `invalidate_on_shock_g` | [`Shock`](InvalidationReason::Shock): accel magnitude above the limit | [`Expired`](CalibrationStatus::Expired) | new metadata |
+//! | `max_scale_drift` | [`ScaleDrift`](InvalidationReason::ScaleDrift): scale factors off by more, [`ScaleFactors`](CalibrationKind::ScaleFactors) only | [`Degraded`](CalibrationStatus::Degraded) | a drift within the limit |
+//!
+//! Age and shock latch into the metadata's `latched` reasons, which persist with it; the
+//! temperature is evaluated again on every sample, the scale drift on every estimate passed
+//! to [`CalibrationMonitor::set_scale_drift`], by the driver from
+//! [`check_scale_drift`](Mpu6050::check_scale_drift) and
+//! [`apply_scale_correction`](Mpu6050::apply_scale_correction), see
+//! [`scale_drift`](crate::scale_drift). [`Mpu6050::calibration_status`] is the
+//! worst status of the kinds with metadata and the reasons of all of them, kinds without
+//! metadata are not tracked. The policy's `on_change` hook runs on every transition of it,
+//! e.g. to schedule a recalibration.
+//!
+//! Timestamps are in a unit of the application's choice, the same as `max_age`. Metadata
+//! without a timestamp is stamped by the next [`set_calibration_time`](Mpu6050::set_calibration_time),
+//! metadata without a temperature takes that of the next sample with a valid temperature.
+//! Groups tagged [`Invalid`](crate::provenance::Provenance::Invalid), the unread parts of a
+//! single sensor read, are not evaluated.
+//!
+//! The driver records metadata itself: a successful
+//! [`auto_setup`](Mpu6050::auto_setup) for the software offsets
+//! ([`Static`](CalibrationMethod::Static), with the temperature and standard deviations of
+//! its report), a committed background calibration for the software offsets
+//! ([`Background`](CalibrationMethod::Background)), and
+//! [`store_gyro_offset_in_hardware`](Mpu6050::store_gyro_offset_in_hardware) copies that of
+//! the software offsets to the hardware offsets. Offsets and scale factors set by the
+//! application get theirs from [`Mpu6050::set_calibration_meta`].
+//!
+//! #### Overhead
+//! Off by default: without a policy the driver skips the evaluation entirely. With one, a
+//! sample costs at most one squared magnitude and a comparison for the shock limit and a
+//! subtraction and a comparison per kind with metadata for the temperature, no square root
+//! and no bus access.
+//!
+//! #### Persistence
+//! [`CalibrationMeta::to_bytes`] writes the metadata for storage next to the calibration,
+//! [`CalibrationMeta::from_bytes`] reads it back after a reboot, latched reasons included:
+//!
+//! | bytes | content |
+//! |:---|:---|
+//! | 1 | format version ([`CALIBRATION_META_VERSION`]) |
+//! | 1 | [`CalibrationMethod`] |
+//! | 1 | flags, bits 0 to 3: timestamp, temperature, gyro and accel standard deviation present |
+//! | 1 | latched reasons, bits of [`InvalidationReason::bit`] |
+//! | 8 | timestamp, `u64` little endian |
+//! | 4 | temperature in °C, f32 little endian |
+//! | 4 | gyro standard deviation in rad/s, f32 little endian |
+//! | 4 | accel standard deviation in g, f32 little endian |
+//! | 2 | CRC-16/CCITT-FALSE ([`crc16`]) over all previous bytes, little endian |
+//!
+//! Absent values are written as zeros.
+//! ```
+//! use mpu6050::log_header::CalibrationMethod;
+//! use mpu6050::validity::*;
+//! use mpu6050::{MpuSample, Vec3A};
+//!
+//! let mut monitor = CalibrationMonitor::new();
+//! monitor.set_policy(Some(CalibrationPolicy {
+//!     max_temp_delta_c: Some(10.),
+//!     invalidate_on_shock_g: Some(4.),
+//!     ..CalibrationPolicy::NONE
+//! }));
+//! let mut meta = CalibrationMeta::new(CalibrationMethod::Static);
+//! meta.temperature_c = Some(25.);
+//! monitor.set_meta(CalibrationKind::SoftwareOffsets, Some(meta));
+//!
+//! monitor.observe(&MpuSample::new(Vec3A::Z, Vec3A::ZERO, 40.));
+//! let reasons = InvalidationReasons::EMPTY.with(InvalidationReason::Temperature);
+//! assert_eq!(monitor.status(), CalibrationStatus::Degraded { reasons });
+//!
+//! // back within 10 °C, then dropped
+//! monitor.observe(&MpuSample::new(Vec3A::Z, Vec3A::ZERO, 30.));
+//! assert_eq!(monitor.status(), CalibrationStatus::Valid);
+//! monitor.observe(&MpuSample::new(Vec3A::splat(5.), Vec3A::ZERO, 30.));
+//! assert!(monitor.status().is_expired());
+//!
+//! // the latched shock survives a reboot
+//! let stored = monitor.meta(CalibrationKind::SoftwareOffsets).unwrap().to_bytes();
+//! let restored = CalibrationMeta::from_bytes(&stored).unwrap();
+//! assert!(restored.latched.contains(InvalidationReason::Shock));
+//! ```
+
+use crate::log_header::CalibrationMethod;
+use crate::packed::{crc16, DecodeError};
+use crate::provenance::{FieldGroup, Provenance};
+use crate::{Mpu6050, MpuSample};
+
+/// Format version written by [`CalibrationMeta::to_bytes`]
+pub const CALIBRATION_META_VERSION: u8 = 1;
+/// Length of the serialized metadata
+pub const CALIBRATION_META_LEN: usize = 26;
+
+/// Calibration the driver applies
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum CalibrationKind {
+    /// `gyro_offset` and `acc_offset`
+    SoftwareOffsets,
+    /// the offset registers of the chip
+    HardwareOffsets,
+    /// the per-axis scale factors
+    ScaleFactors,
+}
+
+impl CalibrationKind {
+    /// all kinds, index matches [`CalibrationKind::index`]
+    pub const ALL: [CalibrationKind; 3] = [
+        CalibrationKind::SoftwareOffsets,
+        CalibrationKind::HardwareOffsets,
+        CalibrationKind::ScaleFactors,
+    ];
+
+    /// position in [`CalibrationKind::ALL`]
+    pub const fn index(self) -> usize {
+        self as usize
+    }
+}
+
+/// Why a calibration is no longer valid, see the [module docs](self)
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum InvalidationReason {
+    /// older than `max_age`
+    Age,
+    /// temperature further than `max_temp_delta_c` from the calibration temperature
+    Temperature,
+    /// accel magnitude above `invalidate_on_shock_g`
+    Shock,
+    /// scale drift above `max_scale_drift`
+    ScaleDrift,
+}
+
+impl InvalidationReason {
+    /// all reasons, in bit order
+    pub const ALL: [InvalidationReason; 4] = [
+        InvalidationReason::Age,
+        InvalidationReason::Temperature,
+        InvalidationReason::Shock,
+        InvalidationReason::ScaleDrift,
+    ];
+
+    /// bit in [`InvalidationReasons::bits`]
+    pub const fn bit(self) -> u8 {
+        1 << self as u8
+    }
+}
+
+/// Set of invalidation reasons
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub struct InvalidationReasons(u8);
+
+impl InvalidationReasons {
+    /// no reason
+    pub const EMPTY: InvalidationReasons = InvalidationReasons(0);
+
+    /// from bits of [`InvalidationReason::bit`], unknown bits are dropped
+    pub fn from_bits(bits: u8) -> Self {
+        let known = InvalidationReason::ALL
+            .iter()
+            .fold(0, |mask, reason| mask | reason.bit());
+        Self(bits & known)
+    }
+
+    /// bits of [`InvalidationReason::bit`]
+    pub const fn bits(self) -> u8 {
+        self.0
+    }
+
+    /// the set with `reason` added
+    pub const fn with(self, reason: InvalidationReason) -> Self {
+        Self(self.0 | reason.bit())
+    }
+
+    /// reasons in either set
+    pub const fn union(self, other: Self) -> Self {
+        Self(self.0 | other.0)
+    }
+
+    /// true if `reason` is part of the set
+    pub const fn contains(self, reason: InvalidationReason) -> bool {
+        self.0 & reason.bit() != 0
+    }
+
+    /// true if no reason is part of the set
+    pub const fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+
+    /// reasons in the set, in bit order
+    pub fn iter(self) -> impl Iterator<Item = InvalidationReason> {
+        InvalidationReason::ALL
+            .into_iter()
+            .filter(move |reason| self.contains(*reason))
+    }
+}
+
+/// How, when and how well a calibration was made
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct CalibrationMeta {
+    /// how the calibration was done
+    pub method: CalibrationMethod,
+    /// when, in the unit of [`CalibrationPolicy::max_age`]
+    pub timestamp: Option<u64>,
+    /// temperature at calibration in degrees celsius
+    pub temperature_c: Option<f32>,
+    /// largest per-axis gyro standard deviation while calibrating, in rad/s
+    pub gyro_std_dev: Option<f32>,
+    /// largest per-axis accel standard deviation while calibrating, in g
+    pub acc_std_dev: Option<f32>,
+    /// reasons that expired the calibration, kept until it is replaced
+    pub latched: InvalidationReasons,
+}
+
+impl CalibrationMeta {
+    /// metadata of a calibration done with `method`, nothing else known
+    pub const fn new(method: CalibrationMethod) -> Self {
+        Self {
+            method,
+            timestamp: None,
+            temperature_c: None,
+            gyro_std_dev: None,
+            acc_std_dev: None,
+            latched: InvalidationReasons::EMPTY,
+        }
+    }
+
+    /// Serializes the metadata, see the [module docs](self)
+    pub fn to_bytes(&self) -> [u8; CALIBRATION_META_LEN] {
+        let mut bytes = [0; CALIBRATION_META_LEN];
+        bytes[0] = CALIBRATION_META_VERSION;
+        bytes[1] = self.method as u8;
+        let optional = [self.temperature_c, self.gyro_std_dev, self.acc_std_dev];
+        bytes[2] = self.timestamp.is_some() as u8;
+        for (i, value) in optional.iter().enumerate() {
+            bytes[2] |= (value.is_some() as u8) << (i + 1);
+            let at = 12 + 4 * i;
+            bytes[at..at + 4].copy_from_slice(&value.unwrap_or(0.).to_le_bytes());
+        }
+        bytes[3] = self.latched.bits();
+        bytes[4..12].copy_from_slice(&self.timestamp.unwrap_or(0).to_le_bytes());
+        let crc = crc16(&bytes[..CALIBRATION_META_LEN - 2]);
+        bytes[CALIBRATION_META_LEN - 2..].copy_from_slice(&crc.to_le_bytes());
+        bytes
+    }
+
+    /// Reads metadata written by [`to_bytes`](Self::to_bytes)
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, DecodeError> {
+        if bytes.len() < CALIBRATION_META_LEN {
+            return Err(DecodeError::Truncated(CALIBRATION_META_LEN));
+        }
+        let bytes = &bytes[..CALIBRATION_META_LEN];
+        if bytes[0] != CALIBRATION_META_VERSION {
+            return Err(DecodeError::BadHeader);
+        }
+        let crc = u16::from_le_bytes([bytes[24], bytes[25]]);
+        if crc != crc16(&bytes[..CALIBRATION_META_LEN - 2]) {
+            return Err(DecodeError::CrcMismatch);
+        }
+        let method = CalibrationMethod::from_u8(bytes[1]).ok_or(DecodeError::BadHeader)?;
+        let flags = bytes[2];
+        let float = |i: usize| {
+            let at = 12 + 4 * i;
+            let value =
+                f32::from_le_bytes([bytes[at], bytes[at + 1], bytes[at + 2], bytes[at + 3]]);
+            (flags & 1 << (i + 1) != 0).then_some(value)
+        };
+        let mut timestamp = [0; 8];
+        timestamp.copy_from_slice(&bytes[4..12]);
+        Ok(Self {
+            method,
+            timestamp: (flags & 1 != 0).then_some(u64::from_le_bytes(timestamp)),
+            temperature_c: float(0),
+            gyro_std_dev: float(1),
+            acc_std_dev: float(2),
+            latched: InvalidationReasons::from_bits(bytes[3]),
+        })
+    }
+}
+
+/// Validity of calibrations, see the [module docs](self)
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub enum CalibrationStatus {
+    /// no rule of the policy violated, or nothing tracked
+    #[default]
+    Valid,
+    /// usable with reduced accuracy, recovers when the reasons go away
+    Degraded {
+        /// violated rules
+        reasons: InvalidationReasons,
+    },
+    /// stale until replaced
+    Expired {
+        /// violated rules, latched and current
+        reasons: InvalidationReasons,
+    },
+}
+
+impl CalibrationStatus {
+    /// violated rules, empty if valid
+    pub const fn reasons(self) -> InvalidationReasons {
+        match self {
+            CalibrationStatus::Valid => InvalidationReasons::EMPTY,
+            CalibrationStatus::Degraded { reasons } | CalibrationStatus::Expired { reasons } => {
+                reasons
+            }
+        }
+    }
+
+    /// true if expired
+    pub const fn is_expired(self) -> bool {
+        matches!(self, CalibrationStatus::Expired { .. })
+    }
+
+    fn from_reasons(latched: InvalidationReasons, current: InvalidationReasons) -> Self {
+        let reasons = latched.union(current);
+        if !latched.is_empty() {
+            CalibrationStatus::Expired { reasons }
+        } else if !reasons.is_empty() {
+            CalibrationStatus::Degraded { reasons }
+        } else {
+            CalibrationStatus::Valid
+        }
+    }
+}
+
+/// Rules invalidating calibrations, each off when None
+#[derive(Copy, Clone, Debug)]
+pub struct CalibrationPolicy {
+    /// oldest valid calibration, in the unit of the timestamps
+    pub max_age: Option<u64>,
+    /// largest temperature difference to the calibration in degrees celsius
+    pub max_temp_delta_c: Option<f32>,
+    /// accel magnitude in g beyond which a shock expires the calibrations
+    pub invalidate_on_shock_g: Option<f32>,
+    /// largest relative deviation of the scale correction of an estimate from 1
+    pub max_scale_drift: Option<f32>,
+    /// called with the new [`Mpu6050::calibration_status`] on every transition
+    pub on_change: Option<fn(CalibrationStatus)>,
+}
+
+impl CalibrationPolicy {
+    /// no rule, no hook
+    pub const NONE: Self = Self {
+        max_age: None,
+        max_temp_delta_c: None,
+        invalidate_on_shock_g: None,
+        max_scale_drift: None,
+        on_change: None,
+    };
+}
+
+impl Default for CalibrationPolicy {
+    fn default() -> Self {
+        Self::NONE
+    }
+}
+
+/// Evaluates calibration metadata against a [`CalibrationPolicy`], no bus access
+#[derive(Copy, Clone, Debug, Default)]
+pub struct CalibrationMonitor {
+    policy: Option<CalibrationPolicy>,
+    /// squared shock limit
+    shock_squared: Option<f32>,
+    metas: [Option<CalibrationMeta>; 3],
+    /// reasons of the current sample, not latched
+    current: [InvalidationReasons; 3],
+    /// drift of the last scale drift estimate
+    scale_drift: f32,
+    status: CalibrationStatus,
+}
+
+impl CalibrationMonitor {
+    /// monitor without policy and metadata
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// sets or removes the policy, keeping the metadata and its latched reasons
+    pub fn set_policy(&mut self, policy: Option<CalibrationPolicy>) {
+        self.policy = policy;
+        self.shock_squared = policy
+            .and_then(|policy| policy.invalidate_on_shock_g)
+            .map(|g| g * g);
+        self.current = [InvalidationReasons::EMPTY; 3];
+        self.update();
+    }
+
+    /// policy, None if off
+    pub fn policy(&self) -> Option<CalibrationPolicy> {
+        self.policy
+    }
+
+    /// sets or removes the metadata of `kind`, replacing its reasons with those latched in
+    /// `meta`
+    pub fn set_meta(&mut self, kind: CalibrationKind, meta: Option<CalibrationMeta>) {
+        self.metas[kind.index()] = meta;
+        self.current[kind.index()] = InvalidationReasons::EMPTY;
+        self.update();
+    }
+
+    /// metadata of `kind`
+    pub fn meta(&self, kind: CalibrationKind) -> Option<CalibrationMeta> {
+        self.metas[kind.index()]
+    }
+
+    /// Evaluates the age at application time `now`, stamping metadata without a timestamp
+    pub fn set_time(&mut self, now: u64) {
+        let max_age = self.policy.and_then(|policy| policy.max_age);
+        for meta in self.metas.iter_mut().flatten() {
+            let timestamp = *meta.timestamp.get_or_insert(now);
+            if max_age.is_some_and(|max| now.saturating_sub(timestamp) > max) {
+                meta.latched = meta.latched.with(InvalidationReason::Age);
+            }
+        }
+        self.update();
+    }
+
+    /// Evaluates the temperature and shock rules on `sample`
+    pub fn observe(&mut self, sample: &MpuSample) {
+        let Some(policy) = self.policy else {
+            return;
+        };
+        let valid = |group| sample.provenance().get(group) != Provenance::Invalid;
+        let shock = match self.shock_squared {
+            Some(limit) if valid(FieldGroup::Acc) => sample.acc.length_squared() > limit,
+            _ => false,
+        };
+        let temp = sample.temp;
+        let temp_valid = valid(FieldGroup::Temp) && !temp.is_nan();
+        for (meta, current) in self.metas.iter_mut().zip(&mut self.current) {
+            let Some(meta) = meta else {
+                continue;
+            };
+            if shock {
+                meta.latched = meta.latched.with(InvalidationReason::Shock);
+            }
+            if let (Some(max), true) = (policy.max_temp_delta_c, temp_valid) {
+                let reference = *meta.temperature_c.get_or_insert(temp);
+                *current = match (temp - reference).abs() > max {
+                    true => InvalidationReasons::EMPTY.with(InvalidationReason::Temperature),
+                    false => InvalidationReasons::EMPTY,
+                };
+            }
+        }
+        self.update();
+    }
+
+    /// Evaluates the scale drift rule on `drift`, the largest relative deviation of an
+    /// accepted scale correction from 1, kept until the next one
+    pub fn set_scale_drift(&mut self, drift: f32) {
+        self.scale_drift = drift;
+        self.update();
+    }
+
+    /// drift passed to [`set_scale_drift`](Self::set_scale_drift), 0 before
+    pub fn scale_drift(&self) -> f32 {
+        self.scale_drift
+    }
+
+    /// status of all kinds with metadata
+    pub fn status(&self) -> CalibrationStatus {
+        self.status
+    }
+
+    /// status of `kind`, Valid without metadata
+    pub fn status_of(&self, kind: CalibrationKind) -> CalibrationStatus {
+        match self.metas[kind.index()] {
+            Some(meta) => CalibrationStatus::from_reasons(meta.latched, self.current(kind)),
+            None => CalibrationStatus::Valid,
+        }
+    }
+
+    /// reasons of the current sample and the last scale drift
+    fn current(&self, kind: CalibrationKind) -> InvalidationReasons {
+        let current = self.current[kind.index()];
+        let max_drift = self.policy.and_then(|policy| policy.max_scale_drift);
+        match max_drift {
+            Some(max) if kind == CalibrationKind::ScaleFactors && self.scale_drift > max => {
+                current.with(InvalidationReason::ScaleDrift)
+            }
+            _ => current,
+        }
+    }
+
+    /// recomputes the status, calls the hook on a transition
+    fn update(&mut self) {
+        let mut latched = InvalidationReasons::EMPTY;
+        let mut current = InvalidationReasons::EMPTY;
+        for (meta, kind) in self.metas.iter().zip(CalibrationKind::ALL) {
+            if let Some(meta) = meta {
+                latched = latched.union(meta.latched);
+                current = current.union(self.current(kind));
+            }
+        }
+        let status = CalibrationStatus::from_reasons(latched, current);
+        if status != self.status {
+            self.status = status;
+            if let Some(on_change) = self.policy.and_then(|policy| policy.on_change) {
+                on_change(status);
+            }
+        }
+    }
+}
+
+impl<I, D> Mpu6050<I, D> {
+    /// sets or removes the calibration policy, off by default, see [`validity`](crate::validity)
+    pub fn set_calibration_policy(&mut self, policy: Option<CalibrationPolicy>) {
+        self.validity.set_policy(policy);
+    }
+
+    /// calibration policy, None if off
+    pub fn calibration_policy(&self) -> Option<CalibrationPolicy> {
+        self.validity.policy()
+    }
+
+    /// sets or removes the metadata of the calibration `kind`, e.g. restored with
+    /// [`CalibrationMeta::from_bytes`] or for offsets the application set
+    pub fn set_calibration_meta(&mut self, kind: CalibrationKind, meta: Option<CalibrationMeta>) {
+        self.validity.set_meta(kind, meta);
+    }
+
+    /// metadata of the calibration `kind`, None if not tracked
+    pub fn calibration_meta(&self, kind: CalibrationKind) -> Option<CalibrationMeta> {
+        self.validity.meta(kind)
+    }
+
+    /// passes the application time, in the unit of the timestamps, for the age rule
+    pub fn set_calibration_time(&mut self, now: u64) {
+        self.validity.set_time(now);
+    }
+
+    /// validity of the tracked calibrations, see [`validity`](crate::validity)
+    pub fn calibration_status(&self) -> CalibrationStatus {
+        self.validity.status()
+    }
+
+    /// validity of the calibration `kind`, Valid if not tracked
+    pub fn calibration_status_of(&self, kind: CalibrationKind) -> CalibrationStatus {
+        self.validity.status_of(kind)
+    }
+
+    /// Called from [`deliver`](Self::deliver)
+    #[cfg(not(feature = "minimal-pipeline"))]
+    pub(crate) fn observe_calibration_validity(&mut self, sample: &MpuSample) {
+        self.validity.observe(sample);
+    }
+}
@@ -0,0 +1,187 @@
+//! The `accelerometer` crate's traits over the mock bus: scaled and raw readings, the sample
+//! rate of the cached configuration, the error kinds, and a driver over a bus error without
+//! `Debug`, see the `accel_traits` module.
+
+mod common;
+
+use embedded_hal::blocking::i2c::{Write, WriteRead};
+use mpu6050::accel_traits::error_kind;
+use mpu6050::accelerometer::vector::{F32x3, I16x3};
+use mpu6050::accelerometer::{Accelerometer, ErrorKind, RawAccelerometer};
+use mpu6050::device::*;
+use mpu6050::settings::SettingsError;
+use mpu6050::synthetic::frame_bytes;
+use mpu6050::*;
+
+use common::{NoDelay, SharedBus};
+
+const ADDR: u8 = DEFAULT_SLAVE_ADDR;
+
+fn driver() -> (Mpu6050<SharedBus>, SharedBus) {
+    let bus = SharedBus::new(&[ADDR]);
+    let mut mpu = Mpu6050Builder::new()
+        .i2c(bus.clone())
+        .acc_offset([0.01, -0.02, 0.])
+        .build()
+        .unwrap();
+    mpu.init(&mut NoDelay).unwrap();
+    mpu.set_accel_range(AccelRange::G4).unwrap();
+    mpu.set_settling_policy(settling::SettlingPolicy::Ignore);
+    (mpu, bus)
+}
+
+/// mean of `n` readings from any accelerometer
+fn mean(sensor: &mut impl Accelerometer, n: u8) -> F32x3 {
+    let mut sum = F32x3::new(0., 0., 0.);
+    for _ in 0..n {
+        let acc = sensor.accel_norm().unwrap();
+        sum = F32x3::new(sum.x + acc.x, sum.y + acc.y, sum.z + acc.z);
+    }
+    let n = f32::from(n);
+    F32x3::new(sum.x / n, sum.y / n, sum.z / n)
+}
+
+#[test]
+fn normalized_readings_are_get_acc() {
+    let (mut mpu, bus) = driver();
+    let frame = frame_bytes(
+        Vec3A::new(0.5, -0.25, 0.75),
+        Vec3A::ZERO,
+        AccelRange::G4,
+        GyroRange::D250,
+    );
+    bus.device(ADDR, |mock| mock.set_frame(&frame));
+    let acc = mpu.get_acc().unwrap();
+    let norm = mpu.accel_norm().unwrap();
+    assert_eq!((norm.x, norm.y, norm.z), (acc.x, acc.y, acc.z));
+    // offsets subtracted like in the driver's own reading
+    assert!((norm.x - 0.49).abs() < 1e-3 && (norm.y + 0.23).abs() < 1e-3);
+
+    let mean = mean(&mut mpu, 4);
+    assert_eq!((mean.x, mean.y, mean.z), (acc.x, acc.y, acc.z));
+}
+
+#[test]
+fn raw_readings_are_the_counts() {
+    let (mut mpu, bus) = driver();
+    bus.device(ADDR, |mock| {
+        mock.set_frame(&[0x12, 0x34, 0xff, 0xfe, 0x80, 0x00, 0, 0, 0, 0, 0, 0, 0, 0])
+    });
+    let raw: I16x3 = mpu.accel_raw().unwrap();
+    assert_eq!((raw.x, raw.y, raw.z), (0x1234, -2, i16::MIN));
+    assert_eq!(mpu.get_acc_raw().unwrap(), [0x1234, -2, i16::MIN]);
+}
+
+#[test]
+fn sample_rate_is_the_accelerometer_output_rate() {
+    let (mut mpu, bus) = driver();
+    bus.take_log();
+    // DLPF off: 8 kHz output data rate, 1 kHz accelerometer
+    assert_eq!(mpu.sample_rate().unwrap(), 1000.);
+    mpu.set_dlpf(3).unwrap();
+    mpu.set_sample_rate_divider(4).unwrap();
+    assert_eq!(mpu.sample_rate().unwrap(), 200.);
+    mpu.set_dlpf(0).unwrap();
+    mpu.set_sample_rate_divider(31).unwrap();
+    assert_eq!(mpu.sample_rate().unwrap(), 250.);
+
+    // cycling at LP_WAKE_CTRL 2, 20 Hz
+    mpu.set_clock_source(CLKSEL::OSCILL).unwrap();
+    bus.device(ADDR, |mock| mock.regs[PWR_MGMT_2::ADDR as usize] = 2 << 6);
+    mpu.set_cycle_enabled(true).unwrap();
+    bus.take_log();
+    assert_eq!(mpu.sample_rate().unwrap(), 20.);
+    assert!(bus.take_log().is_empty());
+    mpu.set_cycle_enabled(false).unwrap();
+    assert_eq!(mpu.sample_rate().unwrap(), 250.);
+}
+
+/// bus failing every transaction with an error that has no `Debug`
+struct DeadBus;
+
+struct Opaque;
+
+impl Write for DeadBus {
+    type Error = Opaque;
+    fn write(&mut self, _: u8, _: &[u8]) -> Result<(), Opaque> {
+        Err(Opaque)
+    }
+}
+
+impl WriteRead for DeadBus {
+    type Error = Opaque;
+    fn write_read(&mut self, _: u8, _: &[u8], _: &mut [u8]) -> Result<(), Opaque> {
+        Err(Opaque)
+    }
+}
+
+/// bus failing every transaction with a `Debug` error
+#[derive(Debug)]
+struct Nack;
+
+struct NackBus;
+
+impl Write for NackBus {
+    type Error = Nack;
+    fn write(&mut self, _: u8, _: &[u8]) -> Result<(), Nack> {
+        Err(Nack)
+    }
+}
+
+impl WriteRead for NackBus {
+    type Error = Nack;
+    fn write_read(&mut self, _: u8, _: &[u8], _: &mut [u8]) -> Result<(), Nack> {
+        Err(Nack)
+    }
+}
+
+#[test]
+fn bus_errors_are_bus_kind_with_the_driver_error() {
+    let mut mpu = Mpu6050Builder::new().i2c(NackBus).build().unwrap();
+    let error = mpu.accel_norm().unwrap_err();
+    assert_eq!(error.kind(), ErrorKind::Bus);
+    assert!(matches!(error.cause(), Some(Mpu6050Error::I2c(Nack))));
+    let error = mpu.accel_raw().unwrap_err();
+    assert_eq!(error.kind(), ErrorKind::Bus);
+    assert!(matches!(error.into_cause(), Mpu6050Error::I2c(Nack)));
+    // from the cache, the bus is not asked
+    assert_eq!(mpu.sample_rate().unwrap(), 1000.);
+}
+
+#[test]
+fn driver_over_an_error_without_debug() {
+    // the driver keeps its bounds, only the trait impls need Debug
+    let mut mpu = Mpu6050Builder::new().i2c(DeadBus).build().unwrap();
+    assert!(matches!(mpu.get_acc(), Err(Mpu6050Error::I2c(Opaque))));
+    assert_eq!(
+        error_kind(&Mpu6050Error::<Opaque>::I2c(Opaque)),
+        ErrorKind::Bus
+    );
+}
+
+#[test]
+fn error_kinds_classify_the_driver_errors() {
+    type Error = Mpu6050Error<()>;
+    let kinds = [
+        (Error::Disconnected, ErrorKind::Bus),
+        (Error::InvalidChipId(0x70), ErrorKind::Device),
+        (Error::Unsupported(Capability::Fifo), ErrorKind::Device),
+        (Error::NotActivated, ErrorKind::Mode),
+        (Error::FifoActive, ErrorKind::Mode),
+        (Error::DelayRequired, ErrorKind::Mode),
+        (Error::Reentrancy, ErrorKind::Mode),
+        (
+            Error::InvalidSettings(SettingsError::ReservedDlpfCfg(7)),
+            ErrorKind::Param,
+        ),
+        (Error::BufferTooSmall(14), ErrorKind::Param),
+    ];
+    for (error, kind) in kinds {
+        assert_eq!(error_kind(&error), kind, "{:?}", error);
+    }
+
+    // a refused configuration through the trait's error type
+    let (mut mpu, _) = driver();
+    let refused = mpu.set_dlpf(7).unwrap_err();
+    assert_eq!(error_kind(&refused), ErrorKind::Param);
+}
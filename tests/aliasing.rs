@@ -0,0 +1,218 @@
+//! Aliasing assessment of rate and filter configurations, see the `aliasing` module, and
+//! setting rate and filter by bandwidth and by rate.
+
+mod common;
+
+use mpu6050::aliasing::{assess, AliasingRisk};
+use mpu6050::device::{CONFIG, DEFAULT_SLAVE_ADDR, DLPF, LP_WAKE_CTRL, SMPLRT_DIV};
+use mpu6050::settings::{Mpu6050Settings, SettingsError};
+use mpu6050::*;
+
+use common::{NoDelay, SharedBus};
+
+use AliasingRisk::{AliasingLikely as A, Marginal as M, Ok as O};
+
+const DIVIDERS: [u8; 9] = [0, 1, 3, 7, 9, 19, 49, 99, 255];
+
+/// expected risk per DLPF_CFG (rows) and divider in `DIVIDERS` (columns)
+const TABLE: [[AliasingRisk; 9]; 7] = [
+    // 260 / 256 Hz, 8 kHz: 8000, 4000, 2000, 1000, 800, 400, 160, 80, 31 Hz
+    [O, O, O, O, O, A, A, A, A],
+    // 184 / 188 Hz, 1 kHz from here: 1000, 500, 250, 125, 100, 50, 20, 10, 3.9 Hz
+    [O, O, A, A, A, A, A, A, A],
+    // 94 / 98 Hz
+    [O, O, O, A, A, A, A, A, A],
+    // 44 / 42 Hz
+    [O, O, O, O, M, A, A, A, A],
+    // 21 / 20 Hz
+    [O, O, O, O, O, M, A, A, A],
+    // 10 / 10 Hz
+    [O, O, O, O, O, O, M, A, A],
+    // 5 / 5 Hz
+    [O, O, O, O, O, O, O, M, A],
+];
+
+#[test]
+fn every_dlpf_setting_at_several_dividers() {
+    for (cfg, row) in TABLE.iter().enumerate() {
+        for (div, expected) in DIVIDERS.iter().zip(row) {
+            let assessment = assess(cfg as u8, *div, false);
+            assert_eq!(
+                assessment.risk, *expected,
+                "DLPF_CFG {} SMPLRT_DIV {}: {}",
+                cfg, div, assessment
+            );
+            // settings assess the same
+            let settings = Mpu6050Settings::new()
+                .with_dlpf_cfg(cfg as u8)
+                .with_sample_rate_div(*div);
+            assert_eq!(settings.check_aliasing(), assessment);
+        }
+    }
+}
+
+#[test]
+fn thresholds_are_inclusive() {
+    // 10 Hz bandwidth at 25 Hz and 20 Hz
+    let at_ok = assess(5, 39, false);
+    assert_eq!((at_ok.ratio, at_ok.risk), (2.5, AliasingRisk::Ok));
+    let at_marginal = assess(5, 49, false);
+    assert_eq!(
+        (at_marginal.ratio, at_marginal.risk),
+        (2.0, AliasingRisk::Marginal)
+    );
+}
+
+#[test]
+fn accel_rate_is_capped_at_its_output_rate() {
+    // DLPF off at 8 kHz: the gyro is sampled 31 times its bandwidth, the accel at 1 kHz
+    let assessment = assess(0, 0, false);
+    assert_eq!(assessment.odr_hz, 8000.);
+    assert_eq!(assessment.accel_rate_hz, 1000.);
+    assert_eq!(assessment.ratio, 1000. / 260.);
+}
+
+#[test]
+fn cycle_mode_is_not_assessed() {
+    let settings = Mpu6050Settings::new().with_cycle(Some(LP_WAKE_CTRL::_1P25));
+    let assessment = settings.check_aliasing();
+    assert!(assessment.cycle);
+    assert_eq!(assessment.risk, AliasingRisk::Ok);
+}
+
+#[test]
+fn presets_assess_ok() {
+    for (name, settings) in presets::ALL {
+        let assessment = settings.check_aliasing();
+        assert_eq!(
+            assessment.risk,
+            AliasingRisk::Ok,
+            "{}: {}",
+            name,
+            assessment
+        );
+    }
+}
+
+fn driver(strict: bool) -> (SharedBus, Mpu6050<SharedBus>) {
+    let bus = SharedBus::new(&[DEFAULT_SLAVE_ADDR]);
+    let mut mpu = Mpu6050Builder::new()
+        .i2c(bus.clone())
+        .strict_configuration(strict)
+        .build()
+        .unwrap();
+    mpu.init(&mut NoDelay).unwrap();
+    (bus, mpu)
+}
+
+#[test]
+fn advisory_by_default() {
+    let (_, mut mpu) = driver(false);
+    assert_eq!(mpu.check_aliasing().risk, AliasingRisk::Ok);
+
+    mpu.set_sample_rate_divider(19).unwrap();
+    let assessment = mpu.check_aliasing();
+    assert_eq!(assessment.risk, AliasingRisk::AliasingLikely);
+    assert_eq!(assessment.odr_hz, 400.);
+
+    mpu.set_dlpf(4).unwrap();
+    assert_eq!(mpu.check_aliasing().risk, AliasingRisk::Marginal);
+
+    mpu.apply_settings(&presets::HANDHELD_UI).unwrap();
+    assert_eq!(mpu.check_aliasing(), presets::HANDHELD_UI.check_aliasing());
+}
+
+#[test]
+fn strict_configuration_refuses_aliasing_before_writing() {
+    let (bus, mut mpu) = driver(true);
+    assert!(mpu.get_strict_configuration());
+    bus.take_log();
+
+    match mpu.set_sample_rate_divider(19) {
+        Err(Mpu6050Error::AliasingLikely(assessment)) => assert_eq!(assessment.odr_hz, 400.),
+        other => panic!("{:?}", other),
+    }
+    let wide_open_slow = Mpu6050Settings::new().with_sample_rate_div(99);
+    assert!(matches!(
+        mpu.apply_settings(&wide_open_slow),
+        Err(Mpu6050Error::AliasingLikely(_))
+    ));
+    assert!(bus.take_log().is_empty());
+    assert_eq!(mpu.check_aliasing().risk, AliasingRisk::Ok);
+
+    // narrowing the filter first makes the same divider acceptable
+    mpu.set_dlpf(6).unwrap();
+    mpu.set_sample_rate_divider(19).unwrap();
+    // and widening it again is refused
+    assert!(matches!(
+        mpu.set_dlpf(0),
+        Err(Mpu6050Error::AliasingLikely(_))
+    ));
+    let state = mpu.debug_state();
+    assert_eq!((state.dlpf_cfg, state.sample_rate_div), (6, 19));
+    let regs = bus.device(DEFAULT_SLAVE_ADDR, |mock| mock.regs);
+    assert_eq!(regs[SMPLRT_DIV as usize], 19);
+    assert_eq!(regs[CONFIG::ADDR as usize] & 0x07, 6);
+
+    // Marginal passes
+    let (_, mut mpu) = driver(true);
+    mpu.set_dlpf(3).unwrap();
+    mpu.set_sample_rate_divider(9).unwrap();
+    assert_eq!(mpu.check_aliasing().risk, AliasingRisk::Marginal);
+}
+
+#[test]
+fn dlpf_bandwidth_round_trips() {
+    let (bus, mut mpu) = driver(false);
+    assert_eq!(mpu.get_dlpf().unwrap(), DLPF::_260);
+    for bandwidth in [
+        DLPF::_184,
+        DLPF::_94,
+        DLPF::_44,
+        DLPF::_21,
+        DLPF::_10,
+        DLPF::_5,
+        DLPF::_260,
+    ] {
+        mpu.set_dlpf_bandwidth(bandwidth).unwrap();
+        assert_eq!(mpu.get_dlpf().unwrap(), bandwidth);
+        assert_eq!(mpu.debug_state().dlpf_cfg, bandwidth as u8);
+    }
+    // the reserved DLPF_CFG 7 filters like 0
+    bus.device(DEFAULT_SLAVE_ADDR, |mock| {
+        mock.regs[CONFIG::ADDR as usize] = 7
+    });
+    assert_eq!(mpu.get_dlpf().unwrap(), DLPF::_260);
+
+    mpu.set_sample_rate_divider(42).unwrap();
+    assert_eq!(mpu.get_sample_rate_divider().unwrap(), 42);
+}
+
+#[test]
+fn sample_rate_picks_the_nearest_divider() {
+    let (bus, mut mpu) = driver(false);
+    // DLPF off, from 8 kHz
+    assert_eq!(mpu.set_sample_rate_hz(8000).unwrap(), 8000.);
+    assert_eq!(mpu.set_sample_rate_hz(1000).unwrap(), 1000.);
+    assert_eq!(mpu.get_sample_rate_divider().unwrap(), 7);
+    assert_eq!(mpu.set_sample_rate_hz(32).unwrap(), 8000. / 250.);
+
+    // DLPF on, from 1 kHz
+    mpu.set_dlpf_bandwidth(DLPF::_44).unwrap();
+    assert_eq!(mpu.set_sample_rate_hz(100).unwrap(), 100.);
+    assert_eq!(mpu.set_sample_rate_hz(300).unwrap(), 1000. / 3.);
+    assert_eq!(mpu.get_sample_rate_divider().unwrap(), 2);
+    assert_eq!(mpu.set_sample_rate_hz(4).unwrap(), 4.);
+
+    bus.take_log();
+    for hz in [0, 3, 2000] {
+        assert!(matches!(
+            mpu.set_sample_rate_hz(hz),
+            Err(Mpu6050Error::InvalidSettings(
+                SettingsError::UnreachableSampleRate(r)
+            )) if r == hz
+        ));
+    }
+    assert!(bus.take_log().is_empty());
+    assert_eq!(mpu.debug_state().sample_rate_div, 249);
+}
@@ -0,0 +1,267 @@
+//! [`DynamicAlignmentEstimator`] on synthetic drives with injected misalignments.
+
+use mpu6050::alignment::*;
+use mpu6050::axis_map::AxisMap;
+use mpu6050::device::Axis;
+use mpu6050::{Quat, Vec3A, PI_180};
+
+const DT_S: f32 = 0.01;
+
+/// Deterministic noise in [-amplitude, amplitude]
+struct Noise(u64);
+
+impl Noise {
+    fn next(&mut self, amplitude: f32) -> f32 {
+        self.0 = self
+            .0
+            .wrapping_mul(6_364_136_223_846_793_005)
+            .wrapping_add(1_442_695_040_888_963_407);
+        ((self.0 >> 40) as f32 / (1u64 << 24) as f32 * 2. - 1.) * amplitude
+    }
+
+    fn vec(&mut self, amplitude: f32) -> Vec3A {
+        Vec3A::new(
+            self.next(amplitude),
+            self.next(amplitude),
+            self.next(amplitude),
+        )
+    }
+}
+
+/// One stretch of a drive: what the caller labels it, the specific force and rate in the
+/// body frame, for `seconds`
+struct Segment {
+    maneuver: Maneuver,
+    acc: Vec3A,
+    gyro: Vec3A,
+    seconds: f32,
+}
+
+fn segment(maneuver: Maneuver, acc: Vec3A, gyro: Vec3A, seconds: f32) -> Segment {
+    Segment {
+        maneuver,
+        acc,
+        gyro,
+        seconds,
+    }
+}
+
+/// gravity on a road of `grade` and `camber` in degrees
+fn gravity(grade: f32, camber: f32) -> Vec3A {
+    Quat::from_rotation_y(grade * PI_180) * Quat::from_rotation_x(camber * PI_180) * Vec3A::Z
+}
+
+/// City driving: stops on roads of varied grade and camber, launches, turns and braking,
+/// the body pitching on its suspension
+fn city_drive(laps: usize) -> Vec<Segment> {
+    const STOPS: [(f32, f32); 4] = [(1.5, -1.), (-1.5, 1.), (0.5, 0.8), (-0.5, -0.8)];
+    let mut drive = Vec::new();
+    for lap in 0..laps {
+        let (grade, camber) = STOPS[lap % STOPS.len()];
+        let ground = gravity(grade, camber);
+        drive.push(segment(Maneuver::QuasiStatic, ground, Vec3A::ZERO, 5.));
+        // the nose lifts by 1° launching at 0.25 g
+        let launch = Quat::from_rotation_y(-PI_180) * (ground + Vec3A::new(0.25, 0., 0.));
+        drive.push(segment(Maneuver::Longitudinal, launch, Vec3A::ZERO, 6.));
+        drive.push(segment(
+            Maneuver::Other,
+            ground + Vec3A::new(0., 0.3, 0.),
+            Vec3A::new(0., 0., 0.3),
+            4.,
+        ));
+        drive.push(segment(Maneuver::QuasiStatic, ground, Vec3A::ZERO, 8.));
+        // and dips by 1.5° braking at 0.35 g
+        let braking = Quat::from_rotation_y(1.5 * PI_180) * (ground + Vec3A::new(-0.35, 0., 0.));
+        drive.push(segment(Maneuver::Longitudinal, braking, Vec3A::ZERO, 3.));
+    }
+    drive
+}
+
+/// Feeds `drive` through a sensor rotated by `misalignment` from the body, with noise
+fn run(estimator: &mut DynamicAlignmentEstimator, drive: &[Segment], misalignment: Vec3A) {
+    let to_sensor = Quat::from_scaled_axis(misalignment.into()).inverse();
+    let mut noise = Noise(7);
+    for segment in drive {
+        for _ in 0..(segment.seconds / DT_S) as usize {
+            let acc = to_sensor * segment.acc + noise.vec(0.004);
+            let gyro = to_sensor * segment.gyro + noise.vec(0.004);
+            estimator.feed_readings(acc, gyro, DT_S, segment.maneuver);
+        }
+    }
+}
+
+fn estimator() -> DynamicAlignmentEstimator {
+    DynamicAlignmentEstimator::new(AlignmentConfig::default())
+}
+
+#[test]
+fn injected_misalignments_are_recovered() {
+    let cases = [
+        Vec3A::ZERO,
+        Vec3A::new(0.02, -0.03, 0.05),
+        Vec3A::new(-0.05, 0.04, -0.08),
+        Vec3A::new(0.1, 0.05, 0.02),
+    ];
+    for misalignment in cases {
+        let mut estimator = estimator();
+        run(&mut estimator, &city_drive(8), misalignment);
+        let estimate = estimator.solve().unwrap();
+        let error = estimate.rotation_vector - misalignment;
+        // within 0.2° per axis
+        assert!(
+            error.abs().max_element() < 0.2 * PI_180,
+            "{:?}: {:?}",
+            misalignment,
+            error
+        );
+        assert!(estimate.residual_rms_rad < 0.03);
+        let progress = estimator.progress();
+        assert_eq!(progress.constrained, [true; 3]);
+        assert!(progress.quasi_static_s > 80. && progress.longitudinal_s > 60.);
+
+        // the estimate takes the readings back onto the body axes
+        let to_sensor = Quat::from_scaled_axis(misalignment.into()).inverse();
+        let forward = estimate.apply(to_sensor * Vec3A::X);
+        assert!((forward - Vec3A::X).length() < 0.2 * PI_180);
+    }
+}
+
+#[test]
+fn stops_alone_leave_yaw_unobservable() {
+    let drive: Vec<_> = city_drive(4)
+        .into_iter()
+        .filter(|s| s.maneuver == Maneuver::QuasiStatic)
+        .collect();
+    let mut estimator = estimator();
+    run(&mut estimator, &drive, Vec3A::new(0.02, -0.03, 0.05));
+    match estimator.solve() {
+        Err(AlignmentError::InsufficientExcitation(progress)) => {
+            assert_eq!(progress.constrained, [true, true, false]);
+            assert!(progress.excitation.z < 0.1);
+            assert_eq!(progress.longitudinal_s, 0.);
+        }
+        other => panic!("{:?}", other),
+    }
+}
+
+#[test]
+fn acceleration_without_a_gravity_reference_is_rejected() {
+    let drive: Vec<_> = city_drive(4)
+        .into_iter()
+        .filter(|s| s.maneuver == Maneuver::Longitudinal)
+        .collect();
+    let mut estimator = estimator();
+    run(&mut estimator, &drive, Vec3A::new(0.02, -0.03, 0.05));
+    let progress = estimator.progress();
+    assert_eq!((progress.quasi_static_s, progress.longitudinal_s), (0., 0.));
+    assert_eq!(progress.constrained, [false; 3]);
+    assert_eq!(progress.rejected, 3600);
+    assert!(matches!(
+        estimator.solve(),
+        Err(AlignmentError::InsufficientExcitation(_))
+    ));
+}
+
+#[test]
+fn turns_and_claims_the_sensors_contradict_constrain_nothing() {
+    let ground = Vec3A::Z;
+    let drive = [
+        segment(Maneuver::QuasiStatic, ground, Vec3A::ZERO, 3.),
+        segment(Maneuver::Other, Vec3A::new(0.2, 0.3, 1.), Vec3A::Z, 10.),
+        // claimed straight while turning, claimed still while launching
+        segment(
+            Maneuver::Longitudinal,
+            Vec3A::new(0.3, 0., 1.),
+            Vec3A::Z * 0.2,
+            5.,
+        ),
+        segment(
+            Maneuver::QuasiStatic,
+            Vec3A::new(0.3, 0., 1.),
+            Vec3A::ZERO,
+            5.,
+        ),
+        // a pothole is not longitudinal acceleration
+        segment(
+            Maneuver::Longitudinal,
+            Vec3A::new(0., 0., 1.5),
+            Vec3A::ZERO,
+            1.,
+        ),
+    ];
+    let mut estimator = estimator();
+    run(&mut estimator, &drive, Vec3A::ZERO);
+    let progress = estimator.progress();
+    assert_eq!(progress.quasi_static_s.round(), 2.);
+    assert_eq!(progress.longitudinal_s, 0.);
+    assert_eq!(progress.rejected, 1100);
+}
+
+#[test]
+fn large_misalignments_are_refused() {
+    // just under 10° from Z at rest, the yaw takes the solution beyond the bound
+    let misalignment = Vec3A::new(0.12, 0.12, 0.12);
+    let mut estimator = estimator();
+    run(&mut estimator, &city_drive(8), misalignment);
+    match estimator.solve() {
+        Err(AlignmentError::NotSmall { angle_rad }) => {
+            assert!((angle_rad - misalignment.length()).abs() < 0.01)
+        }
+        other => panic!("{:?}", other),
+    }
+}
+
+#[test]
+fn inconsistent_stops_are_refused() {
+    // stops on 6° slopes both ways disagree by far more than the residual bound
+    let mut drive = city_drive(8);
+    for (k, segment) in drive.iter_mut().enumerate() {
+        if segment.maneuver == Maneuver::QuasiStatic {
+            let grade = if k % 2 == 0 { 6. } else { -6. };
+            segment.acc = gravity(grade, 0.);
+        }
+    }
+    let mut estimator = estimator();
+    run(&mut estimator, &drive, Vec3A::ZERO);
+    match estimator.solve() {
+        Err(AlignmentError::Inconsistent { residual_rms_rad }) => {
+            assert!(residual_rms_rad > 0.05)
+        }
+        other => panic!("{:?}", other),
+    }
+}
+
+#[test]
+fn mounting_matrix_composes_the_axis_map() {
+    let map = AxisMap::new([Axis::Y, Axis::X, Axis::Z], [true, false, false]);
+    let mut estimator = estimator();
+    let misalignment = Vec3A::new(0.02, -0.03, 0.05);
+    run(&mut estimator, &city_drive(4), misalignment);
+    let estimate = estimator.solve().unwrap();
+    let chip = Vec3A::new(0.3, -0.2, 0.9);
+    let composed = estimate.mounting_matrix(map) * chip;
+    assert!((composed - estimate.apply(map.apply(chip))).length() < 1e-6);
+    assert!((estimate.matrix() * Vec3A::X - estimate.apply(Vec3A::X)).length() < 1e-6);
+}
+
+#[test]
+fn reset_drops_the_statistics() {
+    let mut estimator = estimator();
+    run(&mut estimator, &city_drive(2), Vec3A::ZERO);
+    estimator.reset();
+    assert_eq!(estimator.gravity_reference(), None);
+    let progress = estimator.progress();
+    assert_eq!((progress.quasi_static_s, progress.rejected), (0., 0));
+}
+
+#[test]
+fn errors_name_what_is_missing() {
+    let mut estimator = estimator();
+    run(
+        &mut estimator,
+        &[segment(Maneuver::QuasiStatic, Vec3A::Z, Vec3A::ZERO, 3.)],
+        Vec3A::ZERO,
+    );
+    let error = estimator.solve().unwrap_err();
+    assert_eq!(error.to_string(), "not enough excitation of yaw");
+}
@@ -0,0 +1,1331 @@
+//! Compile-time lock on the signatures of the blessed surface: every public method of
+//! [`Mpu6050`] and [`Mpu6050Builder`] coerced to a function pointer of concrete types, every
+//! variant of [`Mpu6050Error`] matched, every public field of the prelude types read at its
+//! type. A changed signature fails to compile here; `tests/public_api.rs` catches the rest.
+//!
+//! Generic methods are pinned with a turbofish, methods taking `impl Trait` through a
+//! closure calling them. Deprecated items are not part of the surface.
+
+mod common;
+
+use std::convert::Infallible;
+
+use mpu6050::aliasing::AliasingRisk;
+use mpu6050::axis_map::AxisMap;
+use mpu6050::board::IntPinRequest;
+use mpu6050::bus::{DriverOp, ReadPlan};
+use mpu6050::cooperative::{DrainProgress, FifoDrainError};
+use mpu6050::device::{AccelOffsetScaling, CurrentTable, TempFormula, GYRO_CONFIG};
+use mpu6050::frame::RawFrame;
+use mpu6050::governor::{ActivityMetrics, GovernorStatus, TransitionReason};
+use mpu6050::interrupt::{
+    InterruptSet, InterruptSource, MotionCompensation, MotionDetectionConfig,
+};
+use mpu6050::log_header::{CalibrationMethod, CalibrationNote};
+#[cfg(feature = "platform-config")]
+use mpu6050::platform_config::OffsetCalibration;
+use mpu6050::power::PowerConfig;
+use mpu6050::prelude::*;
+use mpu6050::register::GyroConfigValue;
+#[cfg(feature = "remote")]
+use mpu6050::remote::{RemoteLink, ServeBudget, ServeStats, Transport};
+use mpu6050::scale::{PipelineStages, TempModel};
+use mpu6050::setup::{PhaseOutcome, ThermalWait};
+use mpu6050::snapshot::SyncPoints;
+#[cfg(feature = "spectrum")]
+use mpu6050::spectrum::SpectrumAnalyzer;
+use mpu6050::supervisor::{Supervisor, SupervisorLimit};
+use mpu6050::tilt::TiltTrust;
+use mpu6050::traffic_guard::TrafficGuard;
+use mpu6050::transfer::TransferI2c;
+use mpu6050::validity::{CalibrationMonitor, InvalidationReasons};
+
+use common::{NoDelay as Delay, RegisterMock};
+
+type Builder = Mpu6050Builder<RegisterMock>;
+type Mpu = Mpu6050<RegisterMock>;
+/// a driver owning a delay
+type Timed = Mpu6050<RegisterMock, Delay>;
+/// a driver choosing its read transfers
+type Transfer = Mpu6050<TransferI2c<RegisterMock>>;
+type Error = Mpu6050Error<Infallible>;
+type SelfHeating = Result<SelfHeatingSummary, Error>;
+type Progress<'a> = Option<&'a mut dyn FnMut(AutoSetupPhase)>;
+type Setup = Result<AutoSetupReport, AutoSetupError<Infallible>>;
+type Calibration = Result<CalibrationResult, AutoSetupFailure<Infallible>>;
+#[cfg(feature = "platform-config")]
+type PlatformSetup = Result<(Mpu, PlatformReport), PlatformError<Infallible>>;
+#[cfg(feature = "platform-config")]
+type BusOpener = fn(&str) -> std::io::Result<RegisterMock>;
+
+/// a byte stream for the remote server
+#[cfg(feature = "remote")]
+struct Wire;
+
+#[cfg(feature = "remote")]
+impl Transport for Wire {
+    type Error = Infallible;
+
+    fn read(&mut self, _buf: &mut [u8]) -> Result<usize, Infallible> {
+        Ok(0)
+    }
+
+    fn write(&mut self, _bytes: &[u8]) -> Result<(), Infallible> {
+        Ok(())
+    }
+}
+
+#[test]
+// the setup errors carry their report, as the methods do
+#[allow(clippy::result_large_err)]
+fn method_signatures() {
+    // lib
+    let _: fn() -> Builder = Builder::new;
+    let _: fn(Builder, RegisterMock) -> Builder = Builder::i2c;
+    let _: fn(Builder, Delay) -> Mpu6050Builder<RegisterMock, Delay> = Builder::delay::<Delay>;
+    let _: fn(Builder, u8) -> Builder = Builder::slave_addr;
+    let _: fn(Builder, AccelRange) -> Builder = Builder::acc_sensitivity;
+    let _: fn(Builder, GyroRange) -> Builder = Builder::gyro_sensitivity;
+    let _ = |b: Builder, offset: [f32; 3]| -> Builder { b.gyro_offset(offset) };
+    let _: fn(Builder, [f32; 3]) -> Builder = Builder::gyro_offset_array;
+    let _ = |b: Builder, offset: Vec3A| -> Builder { b.acc_offset(offset) };
+    let _: fn(Builder, [f32; 3]) -> Builder = Builder::acc_offset_array;
+    let _: fn(Builder, Vec3A) -> Builder = Builder::acc_scale_factors;
+    let _: fn(Builder, [f32; 3]) -> Builder = Builder::acc_scale_factors_array;
+    let _: fn(Builder, Vec3A) -> Builder = Builder::gyro_scale_factors;
+    let _: fn(Builder, [f32; 3]) -> Builder = Builder::gyro_scale_factors_array;
+    let _: fn(Builder, u32) -> Builder = Builder::disconnect_threshold;
+    let _: fn(Builder, bool) -> Builder = Builder::auto_disconnect;
+    let _: fn(Builder, bool) -> Builder = Builder::strict_configuration;
+    let _: fn(Builder, BoardConstraints) -> Builder = Builder::board_constraints;
+    let _: fn(Builder) -> Builder = Builder::defer_bus_contact;
+    let _: fn(Builder, RoundingMode) -> Builder = Builder::rounding;
+    let _: fn(Builder, TempCalibration) -> Builder = Builder::temp_calibration;
+    let _: fn(Builder) -> Builder = Builder::unsafe_allow_undocumented;
+    let _: fn(Builder, &'static [u8]) -> Builder = Builder::expect_chip_ids;
+    let _: fn(Builder) -> Builder = Builder::skip_chip_id_check;
+    let _: fn(Builder) -> Result<Mpu, Mpu6050BuilderError> = Builder::build;
+    let _: fn(&mut Mpu, CLKSEL) -> Result<(), Error> = Mpu::set_clock_source;
+    let _: fn(&mut Mpu) -> Result<CLKSEL, Error> = Mpu::get_clock_source;
+    let _: fn(&Mpu) -> ConnectionState = Mpu::connection_state;
+    let _: fn(&mut Mpu) -> &mut ConnectionMonitor = Mpu::connection_monitor;
+    let _: fn(&mut Mpu, MotionDetectionConfig) -> Result<(), Error> = Mpu::setup_motion_detection;
+    let _: fn(&mut Mpu) -> Result<bool, Error> = Mpu::get_motion_detected;
+    let _: fn(&mut Mpu, ACCEL_HPF) -> Result<(), Error> = Mpu::set_accel_hpf;
+    let _: fn(&mut Mpu) -> Result<ACCEL_HPF, Error> = Mpu::get_accel_hpf;
+    let _: fn(&mut Mpu, GyroRange) -> Result<(), Error> = Mpu::set_gyro_range;
+    let _: fn(&mut Mpu) -> Result<GyroRange, Error> = Mpu::get_gyro_range;
+    let _: fn(&mut Mpu, AccelRange) -> Result<(), Error> = Mpu::set_accel_range;
+    let _: fn(&mut Mpu) -> Result<AccelRange, Error> = Mpu::get_accel_range;
+    let _: fn(&mut Mpu, bool) -> Result<(), Error> = Mpu::set_sleep_enabled;
+    let _: fn(&mut Mpu) -> Result<bool, Error> = Mpu::get_sleep_enabled;
+    let _: fn(&mut Mpu, bool) -> Result<(), Error> = Mpu::set_temp_enabled;
+    let _: fn(&mut Mpu) -> Result<bool, Error> = Mpu::get_temp_enabled;
+    let _: fn(&mut Mpu, bool) -> Result<(), Error> = Mpu::set_accel_x_self_test;
+    let _: fn(&mut Mpu) -> Result<bool, Error> = Mpu::get_accel_x_self_test;
+    let _: fn(&mut Mpu, bool) -> Result<(), Error> = Mpu::set_accel_y_self_test;
+    let _: fn(&mut Mpu) -> Result<bool, Error> = Mpu::get_accel_y_self_test;
+    let _: fn(&mut Mpu, bool) -> Result<(), Error> = Mpu::set_accel_z_self_test;
+    let _: fn(&mut Mpu) -> Result<bool, Error> = Mpu::get_accel_z_self_test;
+    let _: fn(&mut Mpu) -> Result<Quat, Error> = Mpu::get_acc_angles;
+    let _: fn(&mut Mpu) -> Result<TiltEstimate, Error> = Mpu::get_acc_angles_checked;
+    let _: fn(&mut Mpu, TiltThresholds) = Mpu::set_tilt_thresholds;
+    let _: fn(&Mpu) -> TiltThresholds = Mpu::get_tilt_thresholds;
+    let _: fn(&mut Mpu) -> Result<[i16; 3], Error> = Mpu::get_acc_raw;
+    let _: fn(&mut Mpu) -> Result<[i16; 3], Error> = Mpu::get_gyro_raw;
+    let _: fn(&mut Mpu) -> Result<i16, Error> = Mpu::get_temp_raw;
+    let _: fn(&mut Mpu) -> Result<RawFrame, Error> = Mpu::get_all_raw;
+    let _: fn(&Mpu, u32) -> Result<(), RateTooHigh> = Mpu::validate_rate_against_bus;
+    let _: fn(&mut Mpu) -> Result<Vec3A, Error> = Mpu::get_acc;
+    let _: fn(&mut Mpu) -> Result<Vec3A, Error> = Mpu::get_gyro;
+    let _: fn(&mut Mpu, SettlingPolicy) = Mpu::set_settling_policy;
+    let _: fn(&Mpu) -> SettlingPolicy = Mpu::get_settling_policy;
+    let _: fn(&Mpu) -> SettleCountdown = Mpu::settle_countdown;
+    let _: fn(&Mpu) -> IoStats = Mpu::io_stats;
+    let _: fn(&mut Mpu) = Mpu::reset_io_stats;
+    let _: fn(&Mpu) -> Pipeline = Mpu::pipeline;
+    let _: fn(&Mpu) -> ResolutionInfo = Mpu::current_resolution;
+    let _: fn(&mut Mpu, Vec3A) = Mpu::set_accel_scale_factors;
+    let _: fn(&mut Mpu, Vec3A) = Mpu::set_gyro_scale_factors;
+    let _: fn(&Mpu) -> ScaleModel = Mpu::get_accel_scale;
+    let _: fn(&Mpu) -> ScaleModel = Mpu::get_gyro_scale;
+    let _: fn(&Mpu) -> Vec3A = Mpu::get_acc_offset;
+    let _: fn(&Mpu) -> Vec3A = Mpu::get_gyro_offset;
+    let _ = |m: &mut Mpu, offset: Vec3A| m.set_acc_offset(offset);
+    let _ = |m: &mut Mpu, offset: [f32; 3]| m.set_gyro_offset(offset);
+    let _: fn(&mut Mpu, SupervisorConfig) = Mpu::set_supervisor;
+    let _: fn(&mut Mpu) = Mpu::remove_supervisor;
+    let _: fn(&Mpu) -> Option<SupervisorEvent> = Mpu::supervisor_tripped;
+    let _: fn(&mut Mpu) = Mpu::clear_supervisor;
+    let _: fn(&mut Mpu) -> Result<[f32; 3], Error> = Mpu::get_acc_as::<[f32; 3]>;
+    let _: fn(&mut Mpu) -> Result<[f32; 3], Error> = Mpu::get_gyro_as::<[f32; 3]>;
+    let _: fn(&mut Mpu) -> Result<[f32; 4], Error> = Mpu::get_acc_angles_as::<[f32; 4]>;
+    let _: fn(&mut Mpu) -> Result<f32, Error> = Mpu::get_temp;
+    let _: fn(&mut Mpu) -> Result<MpuSample, Error> = Mpu::get_all;
+    let _: fn(&mut Mpu, u8, u8) -> Result<(), Error> = Mpu::write_byte;
+    let _: fn(&mut Mpu, u8, i16) -> Result<(), Error> = Mpu::write_word;
+    let _: fn(&mut Mpu, u8) -> Result<u8, Error> = Mpu::read_byte;
+    let _: fn(&mut Mpu, u8) -> Result<i16, Error> = Mpu::read_word;
+    let _: fn(&mut Mpu, u8, &mut [u8]) -> Result<(), Error> = Mpu::read_bytes;
+    // aliasing
+    let _: fn(&Mpu) -> AliasingAssessment = Mpu::check_aliasing;
+    let _: fn(&mut Mpu, bool) = Mpu::set_strict_configuration;
+    let _: fn(&Mpu) -> bool = Mpu::get_strict_configuration;
+    // batch
+    let _ = |mpu: &mut Mpu, out: &mut [MpuSample; 4]| -> Result<(), Error> {
+        mpu.read_batch(|| {}, out)
+    };
+    // board
+    let _: fn(&Mpu) -> Option<BoardConstraints> = Mpu::board_constraints;
+    let _: fn(&mut Mpu, IntPinConfig) -> Result<(), Error> = Mpu::configure_int_pin;
+    let _: fn(&mut Mpu) -> Result<(), Error> = Mpu::enable_data_ready_strobe;
+    let _: fn(&mut Mpu) -> Result<u8, Error> = Mpu::probe_address;
+    // calibration
+    let _: fn(&mut Mpu, CalibrationConfig) = Mpu::start_background_calibration;
+    let _: fn(&Mpu) -> Option<BackgroundCalibrationStatus> = Mpu::background_calibration_status;
+    let _: fn(&mut Mpu) -> Option<Vec3A> = Mpu::commit_background_calibration;
+    let _: fn(&mut Mpu) = Mpu::cancel_background_calibration;
+    // capability
+    let _: fn(&Mpu) -> ChipCapabilities = Mpu::capabilities;
+    let _: fn(&mut Mpu) -> Result<ChipCapabilities, Error> = Mpu::probe_capabilities;
+    // clone_detect
+    let _: fn(&Mpu) -> bool = Mpu::undocumented_allowed;
+    // config
+    let _: fn(&mut Mpu) -> Result<Mpu6050Config, Error> = Mpu::read_config;
+    let _: fn(&mut Mpu) -> Result<DefaultsReport, Error> = Mpu::assert_device_at_defaults;
+    // config_epoch
+    let _: fn(&Mpu) -> u32 = Mpu::config_epoch;
+    let _: fn(&Mpu) -> Option<ConfigChange> = Mpu::last_config_change;
+    let _: fn(&Mpu) -> Tracked<Pipeline> = |mpu| mpu.tracked(Mpu::pipeline);
+    let _: fn(&Tracked<Pipeline>, &Mpu) -> bool = Tracked::is_current;
+    let _: for<'a> fn(&'a Tracked<Pipeline>, &Mpu) -> Result<&'a Pipeline, Error> =
+        Tracked::get::<RegisterMock, NoDelay, Infallible>;
+    let _: fn(&FifoSchema, &Mpu) -> bool = FifoSchema::is_current;
+    // config_events
+    let _: fn(&mut Mpu, Option<ConfigObserver>) -> Option<ConfigObserver> =
+        Mpu::set_config_observer;
+    let _: fn(&mut Mpu) -> Option<&mut ConfigObserver> = Mpu::config_observer;
+    let _: fn(&mut ConfigObserver) -> Option<ConfigChangeEvent> = ConfigObserver::pop;
+    let _: fn(&ConfigObserver) -> ConfigObserverStatus = ConfigObserver::status;
+    let _: fn(ConfigField) -> ConfigChange = ConfigField::change;
+    // continuity
+    let _: fn(&Mpu) -> u32 = Mpu::continuity_token;
+    let _: fn(&Mpu) -> Option<ContinuityBreak> = Mpu::last_continuity_break;
+    // conversion
+    let _: fn(&Mpu) -> RoundingMode = Mpu::rounding;
+    let _: fn(&mut Mpu, RoundingMode) = Mpu::set_rounding;
+    // cooperative
+    let _ = |mpu: &mut Mpu,
+             schema: &FifoSchema,
+             out: &mut [u8],
+             budget: DrainBudget|
+     -> Result<DrainProgress, FifoDrainError<Infallible>> {
+        mpu.drain_fifo_cooperative(schema, out, budget, || {})
+    };
+    // delay
+    let _: fn(&Mpu) -> bool = Mpu::has_delay;
+    let _: fn(&mut Mpu) -> Option<&mut NoDelay> = Mpu::delay_mut;
+    let _: fn(&mut Mpu) -> Result<u32, Error> = Mpu::wait_settled;
+    let _: fn(&mut Mpu, &mut Delay) -> Result<ReconnectOutcome, Error> =
+        Mpu::try_reconnect::<Delay>;
+    let _: fn(&mut Mpu, &mut Delay, AutoSetupOptions, Progress) -> Setup = Mpu::auto_setup::<Delay>;
+    let _ = |mpu: &mut Mpu, options: AutoSetupOptions, progress: Progress| -> Setup {
+        mpu.auto_setup_with_deadline(&mut Delay, options, progress, || false)
+    };
+    let _ = |mpu: &mut Mpu,
+             options: AutoSetupOptions,
+             previous: &AutoSetupReport,
+             progress: Progress|
+     -> Setup {
+        mpu.auto_setup_resume(&mut Delay, options, previous, progress, || false)
+    };
+    let _ = |mpu: &mut Mpu, options: AutoSetupOptions, progress: Progress| -> Setup {
+        mpu.auto_setup_cooperative(&mut Delay, options, progress, DrainBudget::Bytes(64), || {})
+    };
+    let _ = |mpu: &mut Mpu, options: ReferencedCalibration| -> Calibration {
+        mpu.calibrate_gyro_with_reference(&mut Delay, || Vec3A::ZERO, options)
+    };
+    let _ = |mpu: &mut Mpu, options: ReferencedCalibration| -> Calibration {
+        mpu.calibrate_accel_with_reference(&mut Delay, || Vec3A::Z, options)
+    };
+    let _: fn(&mut Mpu, &mut Delay, u16) -> Calibration = Mpu::calibrate_gyro::<Delay>;
+    let _: fn(&mut Mpu, &mut Delay, u16) -> Calibration = Mpu::calibrate_accel::<Delay>;
+    let _: fn(
+        &mut Mpu,
+        &RegisterScript,
+        &mut Delay,
+    ) -> Result<ScriptReport, ScriptError<Infallible>> = Mpu::run_script::<Delay>;
+    let _: fn(&mut Mpu, &mut Delay, u16) -> Result<InitBurst, Error> =
+        Mpu::collect_init_burst::<Delay>;
+    let _: fn(&mut Mpu, &mut Delay) -> CloneAssessment = Mpu::detect_clone_heuristics::<Delay>;
+    let _: fn(&mut Mpu, &mut Delay) -> Result<ShutdownEvidence, Error> = Mpu::shutdown::<Delay>;
+    let _: fn(&mut Mpu, &mut Delay) -> ShutdownEvidence = Mpu::shutdown_best_effort::<Delay>;
+    let _: fn(&mut Mpu, &mut Delay) -> Result<(), Error> = Mpu::exit_low_power_mode::<Delay>;
+    let _ = |mpu: &mut Mpu, sink: &mut dyn FnMut(SelfHeatingPoint)| -> SelfHeating {
+        mpu.characterize_self_heating(&mut Delay, 600, 5000, &mut |point| sink(point))
+    };
+    let _ = |mpu: &mut Mpu| -> SelfHeating {
+        mpu.characterize_self_heating_with_deadline(&mut Delay, 600, 5000, &mut |_| {}, || false)
+    };
+    let _ =
+        |mpu: &mut Mpu, prompt: Option<&mut dyn FnMut()>| -> Result<MotionVerifyOutcome, Error> {
+            mpu.verify_motion_detection(&mut Delay, prompt)
+        };
+    let _: fn(&mut Timed) -> Result<(), Error> = Timed::init;
+    let _: fn(&mut Timed) -> Result<ReconnectOutcome, Error> = Timed::try_reconnect;
+    let _: fn(&mut Timed) -> Result<(), Error> = Timed::reset_device;
+    let _: fn(&mut Timed, AutoSetupOptions, Progress) -> Setup = Timed::auto_setup;
+    let _ = |mpu: &mut Timed, options: AutoSetupOptions, progress: Progress| -> Setup {
+        mpu.auto_setup_with_deadline(options, progress, || false)
+    };
+    let _ = |mpu: &mut Timed,
+             options: AutoSetupOptions,
+             previous: &AutoSetupReport,
+             progress: Progress|
+     -> Setup { mpu.auto_setup_resume(options, previous, progress, || false) };
+    let _ = |mpu: &mut Timed, options: AutoSetupOptions, progress: Progress| -> Setup {
+        mpu.auto_setup_cooperative(options, progress, DrainBudget::Transactions(8), || {})
+    };
+    let _ = |mpu: &mut Timed, options: ReferencedCalibration| -> Calibration {
+        mpu.calibrate_gyro_with_reference(|| Vec3A::ZERO, options)
+    };
+    let _ = |mpu: &mut Timed, options: ReferencedCalibration| -> Calibration {
+        mpu.calibrate_accel_with_reference(|| Vec3A::Z, options)
+    };
+    let _: fn(&mut Timed, u16) -> Calibration = Timed::calibrate_gyro;
+    let _: fn(&mut Timed, u16) -> Calibration = Timed::calibrate_accel;
+    let _: fn(&mut Timed, &RegisterScript) -> Result<ScriptReport, ScriptError<Infallible>> =
+        Timed::run_script;
+    let _: fn(&mut Timed, u16) -> Result<InitBurst, Error> = Timed::collect_init_burst;
+    let _: fn(&mut Timed) -> CloneAssessment = Timed::detect_clone_heuristics;
+    let _: fn(&mut Timed) -> Result<ShutdownEvidence, Error> = Timed::shutdown;
+    let _: fn(&mut Timed) -> ShutdownEvidence = Timed::shutdown_best_effort;
+    let _: fn(&mut Timed) -> Result<(), Error> = Timed::exit_low_power_mode;
+    let _ =
+        |mpu: &mut Timed| -> SelfHeating { mpu.characterize_self_heating(60, 1000, &mut |_| {}) };
+    let _ = |mpu: &mut Timed| -> SelfHeating {
+        mpu.characterize_self_heating_with_deadline(60, 1000, &mut |_| {}, || false)
+    };
+    let _ = |mpu: &mut Timed,
+             prompt: Option<&mut dyn FnMut()>|
+     -> Result<MotionVerifyOutcome, Error> { mpu.verify_motion_detection(prompt) };
+    // dormant
+    let _: fn(&Mpu) -> bool = Mpu::is_dormant;
+    let _: fn(&mut Mpu, &mut Delay) -> Result<(), Error> = Mpu::activate::<Delay>;
+    let _: fn(&mut Timed) -> Result<(), Error> = Timed::activate;
+    // error_budget
+    let _: fn(&Mpu) -> ErrorBudget = Mpu::error_budget;
+    // fifo
+    let _: fn(&Mpu) -> FifoSchema = Mpu::fifo_schema;
+    let _: fn(&Mpu, &FifoSchema, &[u8]) -> Result<FifoFrame, Error> =
+        Mpu::parse_fifo_frame::<Infallible>;
+    let _: fn(&Mpu) -> bool = Mpu::fifo_streaming;
+    let _: fn(&mut Mpu, MixedReadPolicy) = Mpu::set_mixed_read_policy;
+    let _: fn(&Mpu) -> MixedReadPolicy = Mpu::get_mixed_read_policy;
+    let _: fn(&mut Mpu, FifoSources) -> Result<FifoSchema, Error> = Mpu::set_fifo_sources;
+    let _: fn(&Mpu) -> FifoSources = Mpu::get_fifo_sources;
+    let _: fn(&mut Mpu, bool) -> Result<(), Error> = Mpu::set_fifo_enabled;
+    let _: fn(&mut Mpu) -> Result<(), Error> = Mpu::reset_fifo;
+    let _: fn(&mut Mpu) -> Result<u16, Error> = Mpu::fifo_count;
+    let _ = |mpu: &mut Mpu, schema: &FifoSchema, buf: &mut [u8]| -> Result<usize, Error> {
+        mpu.drain_fifo(schema, buf, |_: FifoFrame| {})
+    };
+    let _: fn(&mut Mpu, Option<FifoClock>) = Mpu::set_fifo_clock;
+    let _: fn(&Mpu) -> Option<FifoClock> = Mpu::get_fifo_clock;
+    let _: fn(&Mpu) -> FifoRecoveryStatus = Mpu::fifo_recovery_status;
+    let _: fn(u16, usize) -> FifoGap = FifoGap::from_content;
+    let _: fn(u16, usize, u32, u64, f32) -> FifoGap = FifoGap::from_elapsed;
+    // fsync
+    let _: fn(&mut Mpu, EXT_SYNC) -> Result<(), Error> = Mpu::set_fsync_source;
+    let _: fn(&mut Mpu) -> Result<EXT_SYNC, Error> = Mpu::get_fsync_source;
+    // governor
+    let _: fn(&Mpu) -> Option<&PowerGovernor> = Mpu::power_governor;
+    let _: fn(&mut Mpu) -> Option<PowerGovernor> = Mpu::remove_power_governor;
+    let _: fn(&mut Mpu, PowerGovernor) -> Result<(), Error> = Mpu::set_power_governor;
+    let _: fn(&mut Mpu, u32) -> Result<Option<GovernorTransition>, Error> = Mpu::governor_tick;
+    let _: fn(&mut Mpu) -> Result<Option<GovernorTransition>, Error> = Mpu::governor_motion_wake;
+    // gravity_trim
+    let _: fn(&mut Mpu, Vec3A) = Mpu::apply_acc_trim;
+    // hint
+    let _: fn(&Mpu) -> HintContext = Mpu::hint_context;
+    // hook
+    let _: fn(&mut Mpu, Option<SampleHook>) = Mpu::set_sample_hook;
+    let _: fn(&Mpu) -> Option<SampleHook> = Mpu::get_sample_hook;
+    let _: fn(&mut Mpu, Option<SampleTap>) = Mpu::set_sample_tap;
+    let _: fn(&Mpu) -> Option<SampleTap> = Mpu::get_sample_tap;
+    let _: fn(&mut Mpu, Option<SampleObserver>) = Mpu::set_sample_observer;
+    let _: fn(&Mpu) -> Option<SampleObserver> = Mpu::get_sample_observer;
+    // hw_offsets
+    let _: fn(&mut Mpu) -> Result<[i16; 3], Error> = Mpu::get_gyro_hw_offsets;
+    let _: fn(&mut Mpu, [i16; 3]) -> Result<(), Error> = Mpu::set_gyro_hw_offsets;
+    let _: fn(&mut Mpu) -> Result<[i16; 3], Error> = Mpu::get_accel_hw_offsets;
+    let _: fn(&mut Mpu, [i16; 3]) -> Result<(), Error> = Mpu::set_accel_hw_offsets;
+    let _: fn(&mut Mpu) -> Result<[i16; 3], Error> = Mpu::store_gyro_offset_in_hardware;
+    // impact
+    let _: fn(&mut Mpu, ImpactRangeConfig) -> Result<(), Error> = Mpu::configure_impact_ranging;
+    let _: fn(&mut Mpu) -> Result<(), Error> = Mpu::disable_impact_ranging;
+    let _: fn(&Mpu) -> Option<ImpactRanger> = Mpu::impact_ranger;
+    let _: fn(&mut Mpu) -> bool = Mpu::rearm_impact_ranging;
+    // init_findings
+    let _: fn(&Mpu) -> &InitFindings = Mpu::init_findings;
+    // interleave
+    let _: fn(&mut Mpu, RangeInterleave) -> Result<(), Error> = Mpu::enable_range_interleaving;
+    let _: fn(&mut Mpu, AccelRange) -> Result<(), Error> = Mpu::disable_range_interleaving;
+    let _: fn(&Mpu) -> Option<InterleaveState> = Mpu::range_interleave;
+    let _: fn(&Mpu) -> Option<InterleaveRates> = Mpu::range_interleave_rates;
+    // interpolation
+    let _: fn(
+        &mut Mpu,
+        &mut InterpolatingBuffer<4>,
+        u64,
+        Option<Quat>,
+    ) -> Result<MpuSample, Error> = Mpu::sample_into_interp_buffer::<4>;
+    // interrupt
+    let _: fn(&mut Mpu) -> Result<InterruptEvents, Error> = Mpu::poll_interrupt_events;
+    let _: fn(&mut Mpu) -> Result<InterruptSet, Error> = Mpu::get_int_status;
+    let _: fn(&mut Mpu, InterruptSource, bool) -> Result<(), Error> = Mpu::set_int_enabled;
+    let _: fn(&mut Mpu) -> Result<InterruptSet, Error> = Mpu::get_int_enabled;
+    let _: fn(&mut Mpu, u64) -> Result<Option<MotionEvent>, Error> = Mpu::take_motion_event;
+    let _: fn(&Mpu) -> &InterruptEdgeTracker = Mpu::interrupt_tracker;
+    let _: fn(&mut Mpu) = Mpu::reset_interrupt_tracker;
+    // lever_arm
+    let _: fn(&mut Mpu, Vec3A) = Mpu::set_lever_arm;
+    let _: fn(&Mpu) -> Vec3A = Mpu::lever_arm;
+    let _: fn(&mut Mpu, Option<LeverArmCompensation>) = Mpu::set_lever_arm_compensation;
+    let _: fn(&Mpu) -> Option<LeverArmCompensation> = Mpu::lever_arm_compensation;
+    let _: fn(&Mpu) -> bool = Mpu::lever_arm_compensation_active;
+    // log_header
+    let _: fn(&mut Mpu) -> Result<LogHeader, Error> = Mpu::build_log_header;
+    // logging
+    let _: fn(&mut Mpu, TraceLevel) = Mpu::set_trace_level;
+    let _: fn(&Mpu) -> TraceLevel = Mpu::trace_level;
+    // low_power
+    let _: fn(&mut Mpu, LowPowerAdjust) -> Result<(), Error> = Mpu::adjust_low_power;
+    let _: fn(&mut Mpu, LowPowerWakeFrequency) -> Result<(), Error> =
+        Mpu::enter_low_power_accel_mode;
+    let _: fn(&mut Mpu, bool) -> Result<(), Error> = Mpu::set_cycle_enabled;
+    let _: fn(&mut Mpu, StandbyFlags) -> Result<(), Error> = Mpu::set_standby_mask;
+    let _: fn(&mut Mpu) -> Result<StandbyFlags, Error> = Mpu::get_standby_mask;
+    let _: fn(LowPowerWakeFrequency) -> LP_WAKE_CTRL = LowPowerWakeFrequency::lp_wake_ctrl;
+    let _: fn(LowPowerWakeFrequency) -> u32 = LowPowerWakeFrequency::centi_hz;
+    let _: fn(StandbyFlags, Axis, bool) -> StandbyFlags = StandbyFlags::with_accel;
+    let _: fn(StandbyFlags, Axis) -> bool = StandbyFlags::gyro;
+    let _: fn(u8) -> StandbyFlags = StandbyFlags::from_bits;
+    // alignment
+    let _: fn(AlignmentConfig) -> DynamicAlignmentEstimator = DynamicAlignmentEstimator::new;
+    let _: fn(&mut DynamicAlignmentEstimator, &MpuSample, f32, Maneuver) -> bool =
+        DynamicAlignmentEstimator::feed;
+    let _: fn(&mut DynamicAlignmentEstimator, Vec3A, Vec3A, f32, Maneuver) -> bool =
+        DynamicAlignmentEstimator::feed_readings;
+    let _: fn(&DynamicAlignmentEstimator) -> AlignmentProgress =
+        DynamicAlignmentEstimator::progress;
+    let _: fn(&DynamicAlignmentEstimator) -> Result<AlignmentEstimate, AlignmentError> =
+        DynamicAlignmentEstimator::solve;
+    let _: fn(&DynamicAlignmentEstimator) -> Option<Vec3A> =
+        DynamicAlignmentEstimator::gravity_reference;
+    let _: fn(&mut DynamicAlignmentEstimator) = DynamicAlignmentEstimator::reset;
+    let _: fn(&AlignmentEstimate) -> Quat = AlignmentEstimate::quat;
+    let _: fn(&AlignmentEstimate, AxisMap) -> mpu6050::glam::Mat3A =
+        AlignmentEstimate::mounting_matrix;
+    // scale_drift
+    let _: fn(&mut Mpu, &ScaleDriftMonitor) -> Result<ScaleDriftEstimate, ScaleDriftError> =
+        Mpu::check_scale_drift;
+    let _: fn(&mut Mpu, &mut ScaleDriftMonitor) -> Result<Vec3A, ScaleDriftError> =
+        Mpu::apply_scale_correction;
+    let _: fn(ScaleDriftConfig) -> ScaleDriftMonitor = ScaleDriftMonitor::new;
+    let _: fn(&mut ScaleDriftMonitor, &MpuSample, f32) -> bool = ScaleDriftMonitor::feed;
+    let _: fn(&mut ScaleDriftMonitor, Vec3A, Vec3A, f32) -> bool = ScaleDriftMonitor::feed_readings;
+    let _: fn(&ScaleDriftMonitor) -> ScaleDriftProgress = ScaleDriftMonitor::progress;
+    let _: fn(&ScaleDriftMonitor) -> Result<ScaleDriftEstimate, ScaleDriftError> =
+        ScaleDriftMonitor::estimate;
+    let _: fn(&mut ScaleDriftMonitor, Vec3A) = ScaleDriftMonitor::rescale;
+    let _: fn(&mut ScaleDriftMonitor) = ScaleDriftMonitor::reset;
+    let _: fn(&ScaleDriftEstimate) -> f32 = ScaleDriftEstimate::drift;
+    let _: fn(&mut CalibrationMonitor, f32) = CalibrationMonitor::set_scale_drift;
+    let _: fn(&CalibrationMonitor) -> f32 = CalibrationMonitor::scale_drift;
+    // self_heating
+    let _: fn(&mut Mpu, Option<BootTransientModel>) -> bool = Mpu::set_boot_transient_correction;
+    let _: fn(&Mpu) -> Option<BootTransientModel> = Mpu::boot_transient_correction;
+    let _: fn(&mut Mpu, u32) = Mpu::advance_boot_transient;
+    let _: fn(&Mpu) -> Option<f32> = Mpu::boot_transient_elapsed_s;
+    let _: fn(Vec3A, Vec3A) -> BootTransientModel = BootTransientModel::new;
+    let _: fn(&BootTransientModel, f32) -> Vec3A = BootTransientModel::correction_at;
+    let _: fn(&SelfHeatingSummary) -> Option<BootTransientModel> = SelfHeatingSummary::model;
+    let _: fn(&mut SelfHeatingFitter, SelfHeatingPoint) = SelfHeatingFitter::push;
+    let _: fn(&SelfHeatingFitter) -> SelfHeatingSummary = SelfHeatingFitter::summary;
+    let _: fn(&SettlingFit, f32) -> f32 = SettlingFit::value_at;
+    // metrics
+    let _: fn(&mut Mpu, Option<&'static dyn MetricsSink>) = Mpu::set_metrics_sink;
+    let _: fn(&Mpu) -> Option<&'static dyn MetricsSink> = Mpu::get_metrics_sink;
+    let _: fn(&mut Mpu, Option<GaugeLimiter>) = Mpu::set_gauge_limiter;
+    let _: fn(&Mpu) -> Option<GaugeLimiter> = Mpu::get_gauge_limiter;
+    // motion_verify
+    let _: fn(&mut Mpu, WakeOnMotionPolicy, MotionDetectionConfig) -> Result<(), Error> =
+        Mpu::arm_wake_on_motion;
+    // orientation
+    let _: fn(&mut Mpu, &mut ComplementaryFilter, f32) -> Result<Quat, Error> =
+        Mpu::update_orientation;
+    // oscillator
+    let _: fn(&mut Mpu, f32) -> Result<(), SettingsError> = Mpu::apply_clock_correction;
+    let _: fn(&Mpu) -> f32 = Mpu::clock_correction;
+    let _: fn(&Mpu) -> f32 = Mpu::effective_odr_hz;
+    let _: fn(&Mpu) -> f32 = Mpu::nominal_sample_interval_us;
+    let _: fn(&Mpu, u32, u64) -> UniformResampler = Mpu::uniform_resampler;
+    let _ = |mpu: &Mpu, newest_us: u64, frames: usize| -> Vec<u64> {
+        mpu.fifo_frame_timestamps_us(newest_us, frames).collect()
+    };
+    let _: fn(&mut Mpu, &mut Delay, u32) -> Result<ClockErrorEstimate, Error> =
+        Mpu::estimate_clock_error::<Delay>;
+    // parse_policy
+    let _: fn(&mut Mpu, ParsePolicy) = Mpu::set_parse_policy;
+    let _: fn(&Mpu) -> ParsePolicy = Mpu::parse_policy;
+    let _: fn(&Mpu) -> &ParseDiagnostics = Mpu::parse_diagnostics;
+    let _: fn(&mut Mpu) = Mpu::clear_parse_diagnostics;
+    // plausibility
+    let _: fn(&mut Mpu, Option<PlausibilityConfig>) = Mpu::set_plausibility_config;
+    let _: fn(&Mpu) -> Option<PlausibilityConfig> = Mpu::plausibility_config;
+    // power
+    let _: fn(&Mpu) -> PowerEstimate = Mpu::power_estimate;
+    let _: fn(&Mpu) -> PowerConfig = Mpu::power_config;
+    let _: fn(&Mpu, &Mpu6050Settings) -> PowerChange = Mpu::power_change;
+    let _: fn(&Mpu) -> BusTraffic = Mpu::bus_traffic;
+    let _: fn(&mut Mpu, BusTraffic) = Mpu::set_bus_traffic;
+    let _: fn(&Mpu) -> PowerState = Mpu::power_state;
+    // recovery
+    let _: fn(&mut Mpu, Option<BusRecovery>) = Mpu::set_bus_recovery;
+    let _: fn(&Mpu) -> Option<BusRecovery> = Mpu::bus_recovery;
+    let _: fn(&Mpu) -> Option<BusRecoveryStats> = Mpu::bus_recovery_stats;
+    // reentrancy
+    let _: fn(&Mpu) -> DriverInfo = Mpu::driver_info;
+    fn context<'a>(context: &SampleContext<'a>) -> (&'a MpuSample, DriverInfo) {
+        (context.sample(), context.info())
+    }
+    let _ = context;
+    let _: fn(Mpu) -> SharedMpu6050<RegisterMock> = SharedMpu6050::new;
+    let _ = |shared: &SharedMpu6050<RegisterMock>| -> Result<f32, Error> {
+        shared.with(|mpu: &mut Mpu| mpu.get_temp())
+    };
+    let _: fn(&SharedMpu6050<RegisterMock>) -> bool = SharedMpu6050::is_busy;
+    let _: fn(&mut SharedMpu6050<RegisterMock>) -> &mut Mpu = SharedMpu6050::get_mut;
+    let _: fn(SharedMpu6050<RegisterMock>) -> Mpu = SharedMpu6050::into_inner;
+    // register
+    let _: fn(&mut Mpu, Register) -> Result<u8, Error> = Mpu::read_register;
+    let _: fn(&mut Mpu, Register, u8) -> Result<(), Error> = Mpu::write_register;
+    let _ = |mpu: &mut Mpu, reg: Register| -> Result<u8, Error> {
+        mpu.modify_register(reg, |value: u8| value)
+    };
+    let _: fn(&mut Mpu, Register, u8) -> Result<bool, Error> = Mpu::read_register_bit;
+    let _: fn(&mut Mpu, Register, u8, bool) -> Result<(), Error> = Mpu::write_register_bit;
+    let _: fn(&mut Mpu, Register, BitBlock) -> Result<u8, Error> = Mpu::read_register_field;
+    let _: fn(&mut Mpu, Register, BitBlock, u8) -> Result<(), Error> = Mpu::write_register_field;
+    let _: fn(&mut Mpu, Register) -> Result<i16, Error> = Mpu::read_register_word;
+    let _: fn(&mut Mpu, Register, i16) -> Result<(), Error> = Mpu::write_register_word;
+    let _: fn(&mut Mpu, Register, &mut [u8]) -> Result<(), Error> = Mpu::read_registers;
+    let _: fn(&mut Mpu) -> Result<GyroConfigValue, Error> = Mpu::read_typed::<GYRO_CONFIG>;
+    let _: fn(&mut Mpu, GyroConfigValue) -> Result<(), Error> = Mpu::write_typed::<GYRO_CONFIG>;
+    let _: fn(&mut Mpu) -> Result<RegisterValue<GYRO_CONFIG>, Error> = Mpu::read_fields;
+    let _: fn(&mut Mpu, RegisterValue<GYRO_CONFIG>) -> Result<(), Error> = Mpu::write_fields;
+    let _ = |mpu: &mut Mpu| -> Result<RegisterValue<GYRO_CONFIG>, Error> {
+        mpu.modify_fields(|value| value.with(GYRO_CONFIG::FS_SEL_FIELD, GyroRange::D500))
+    };
+    let _: fn(&mut Mpu, Field<GYRO_CONFIG>) -> Result<u8, Error> = Mpu::read_field;
+    let _: fn(&mut Mpu, Field<GYRO_CONFIG>) -> Result<bool, Error> = Mpu::read_flag;
+    let _: fn(&mut Mpu, Field<GYRO_CONFIG>, GyroRange) -> Result<(), Error> = Mpu::write_field;
+    // platform_config
+    #[cfg(feature = "platform-config")]
+    let _: fn(&PlatformDescription, BusOpener, &mut Delay) -> PlatformSetup =
+        Mpu::from_platform_description_with::<Delay>;
+    #[cfg(feature = "platform-config")]
+    let _: fn(&Mpu) -> OffsetCalibration = OffsetCalibration::of;
+    // remote
+    #[cfg(feature = "remote")]
+    let _: fn(
+        &mut Mpu,
+        &mut Delay,
+        &mut RemoteLink<Wire>,
+        ServeBudget,
+    ) -> Result<ServeStats, Infallible> = Mpu::serve::<Delay, Wire>;
+    #[cfg(feature = "remote")]
+    let _: fn(
+        &mut Timed,
+        &mut RemoteLink<Wire>,
+        ServeBudget,
+    ) -> Result<ServeStats, Infallible> = Timed::serve::<Wire>;
+    // sampling
+    let _ = |mpu: &mut Mpu| -> Result<(), SamplingError<Infallible, ()>> {
+        mpu.run_sampling_loop(
+            || Ok::<(), ()>(()),
+            |_: MpuSample, _: SampleMeta| SampleControl::Stop,
+        )
+    };
+    // settings
+    let _: fn(&mut Mpu) -> Result<Mpu6050Settings, Error> = Mpu::read_settings;
+    let _: fn(&mut Mpu) -> Result<Mpu6050Settings, Error> = Mpu::adopt_device_settings;
+    let _: fn(&mut Mpu, &Mpu6050Settings) -> Result<(), Error> = Mpu::apply_settings;
+    let _: fn(&mut Mpu, &Mpu6050Settings, &Mpu6050Settings) -> Result<(), Error> =
+        Mpu::apply_settings_diff;
+    let _: fn(&mut Mpu, u8) -> Result<(), Error> = Mpu::set_dlpf;
+    let _: fn(&mut Mpu, u8) -> Result<(), Error> = Mpu::set_sample_rate_divider;
+    let _: fn(&mut Mpu) -> Result<DLPF, Error> = Mpu::get_dlpf;
+    let _: fn(&mut Mpu, DLPF) -> Result<(), Error> = Mpu::set_dlpf_bandwidth;
+    let _: fn(&mut Mpu) -> Result<u8, Error> = Mpu::get_sample_rate_divider;
+    let _: fn(&mut Mpu, u16) -> Result<f32, Error> = Mpu::set_sample_rate_hz;
+    // shutdown
+    let _: fn(&mut Mpu) -> Result<ShutdownEvidence, Error> = Mpu::verify_shutdown_state;
+    // skew
+    let _: fn(&mut Mpu, Option<SkewCorrection>) = Mpu::set_skew_correction;
+    let _: fn(&Mpu) -> Option<SkewCorrection> = Mpu::skew_correction;
+    let _: fn(&Mpu) -> f32 = Mpu::intra_sample_skew_us;
+    let _: fn(&Mpu) -> bool = Mpu::skew_correction_active;
+    // smoothing
+    let _: fn(&Mpu, FilterSpec) -> FilteredView = Mpu::filtered_view;
+    let _: fn(&mut Mpu, &mut [&mut FilteredView<3>]) -> Result<MpuSample, Error> =
+        Mpu::read_into_views::<3>;
+    // snapshot
+    let _: fn(&Mpu) -> DriverStateSnapshot = Mpu::debug_state;
+    // spectrum
+    #[cfg(feature = "spectrum")]
+    let _: fn(&mut Mpu, &mut SpectrumAnalyzer<64>) -> Result<bool, Error> =
+        Mpu::feed_spectrum::<64>;
+    // split
+    let _ = |mpu: &mut Mpu,
+             schema: &FifoSchema,
+             buf: &mut [u8],
+             splitter: &mut StreamSplitter<3, 8>|
+     -> Result<usize, Error> { mpu.drain_fifo_into(schema, buf, splitter) };
+    // temp_calibration
+    let _: fn(&Mpu) -> TempCalibration = Mpu::temp_calibration;
+    let _: fn(&mut Mpu, Option<TempCalibration>) -> Result<(), TempCalibrationError> =
+        Mpu::set_temp_calibration;
+    let _: fn(&mut Mpu, i16, f32, i16, f32) -> Result<TempCalibration, TempCalibrationError> =
+        Mpu::calibrate_temp_two_point;
+    let _: fn(&mut Mpu, i16, f32) -> Result<TempCalibration, TempCalibrationError> =
+        Mpu::calibrate_temp_single_point;
+    // traffic_guard
+    let _: fn(&mut Mpu) -> TrafficGuard<'_, RegisterMock> = Mpu::traffic_guard;
+    let _: fn(&Mpu) -> u32 = Mpu::traffic_violations;
+    let _: fn(&Mpu) -> Option<DriverOp> = Mpu::last_traffic_violation;
+    // transfer
+    let _: fn(&mut Transfer, TransferMode) = Transfer::set_transfer_mode;
+    let _: fn(&Transfer) -> TransferMode = Transfer::get_transfer_mode;
+    // validity
+    let _: fn(&mut Mpu, Option<CalibrationPolicy>) = Mpu::set_calibration_policy;
+    let _: fn(&Mpu) -> Option<CalibrationPolicy> = Mpu::calibration_policy;
+    let _: fn(&mut Mpu, CalibrationKind, Option<CalibrationMeta>) = Mpu::set_calibration_meta;
+    let _: fn(&Mpu, CalibrationKind) -> Option<CalibrationMeta> = Mpu::calibration_meta;
+    let _: fn(&mut Mpu, u64) = Mpu::set_calibration_time;
+    let _: fn(&Mpu) -> CalibrationStatus = Mpu::calibration_status;
+    let _: fn(&Mpu, CalibrationKind) -> CalibrationStatus = Mpu::calibration_status_of;
+}
+
+#[test]
+fn error_variants() {
+    let describe = |error: &Error| match error {
+        Mpu6050Error::I2c(error) => match *error {},
+        Mpu6050Error::InvalidChipId(id) => format!("{}", id),
+        Mpu6050Error::Disconnected => "disconnected".into(),
+        Mpu6050Error::ExtDataOverflow(needed) => format!("{}", needed),
+        Mpu6050Error::StaleExtDataSlot => "stale slot".into(),
+        #[allow(deprecated)]
+        Mpu6050Error::StaleFifoSchema => "stale schema".into(),
+        Mpu6050Error::StaleConfiguration { captured, current } => {
+            let (_, _): (&u32, &u32) = (captured, current);
+            "stale configuration".into()
+        }
+        Mpu6050Error::BufferTooSmall(needed) => format!("{}", needed),
+        Mpu6050Error::InvalidSettings(error) => format!("{:?}", error as &SettingsError),
+        Mpu6050Error::AliasingLikely(assessment) => {
+            format!("{:?}", assessment as &AliasingAssessment)
+        }
+        Mpu6050Error::InvalidTimestamp(error) => format!("{:?}", error as &TimestampError),
+        Mpu6050Error::Unsupported(capability) => format!("{:?}", capability as &Capability),
+        Mpu6050Error::Aborted { phase, progress } => {
+            let (_, _): (&&'static str, &AbortProgress) = (phase, progress);
+            "aborted".into()
+        }
+        Mpu6050Error::DelayRequired => "delay required".into(),
+        Mpu6050Error::FifoActive => "fifo active".into(),
+        Mpu6050Error::InvalidRegisterAccess {
+            register,
+            violation,
+        } => {
+            let (_, _): (&Register, &AccessViolation) = (register, violation);
+            "invalid access".into()
+        }
+        Mpu6050Error::BoardConstraint(violation) => {
+            let _: &ConstraintViolation = violation;
+            "board constraint".into()
+        }
+        Mpu6050Error::NotActivated => "not activated".into(),
+        Mpu6050Error::DeniedRegisterRange(range) => {
+            let _: &DeniedRange = range;
+            "denied register range".into()
+        }
+        Mpu6050Error::ImplausibleFrame { reason } => {
+            let _: &FrameCheck = reason;
+            "implausible frame".into()
+        }
+        Mpu6050Error::WakeOnMotionRefused(engine) => {
+            let _: &MotionEngineStatus = engine;
+            "wake on motion refused".into()
+        }
+        Mpu6050Error::Reentrancy => "reentrancy".into(),
+        Mpu6050Error::BusRateTooHigh(error) => {
+            let _: &RateTooHigh = error;
+            "bus rate too high".into()
+        }
+    };
+    assert_eq!(describe(&Mpu6050Error::InvalidChipId(0x70)), "112");
+    let _: fn(&Error) -> RecoveryHint = Error::recovery_hint;
+    let _: fn(&Error, &HintContext) -> RecoveryHint = Error::recovery_hint_with;
+    let _: fn(&Error) -> u8 = Error::code;
+    assert_eq!(describe(&Mpu6050Error::DelayRequired), "delay required");
+}
+
+#[test]
+fn prelude_fields() {
+    let _ = |x: &AliasingAssessment| {
+        let _: &AliasingRisk = &x.risk;
+        let _: &f32 = &x.odr_hz;
+        let _: &f32 = &x.accel_rate_hz;
+        let _: &f32 = &x.accel_bandwidth_hz;
+        let _: &f32 = &x.gyro_bandwidth_hz;
+        let _: &f32 = &x.ratio;
+        let _: &bool = &x.cycle;
+    };
+    let _ = |x: &BoardConstraints| {
+        let _: &IntPinConstraint = &x.int_pin;
+        let _: &AddrConstraint = &x.addr;
+    };
+    let _ = |x: &IntPinConfig| {
+        let _: &bool = &x.active_low;
+        let _: &bool = &x.open_drain;
+        let _: &bool = &x.latched;
+        let _: &bool = &x.clear_on_any_read;
+    };
+    let _ = |x: &ConstraintViolation| match x {
+        ConstraintViolation::IntPin {
+            constraint,
+            request,
+        } => {
+            let (_, _): (&IntPinConstraint, &IntPinRequest) = (constraint, request);
+        }
+        ConstraintViolation::IntPinNotApplied {
+            constraint,
+            int_pin_cfg,
+        } => {
+            let (_, _): (&IntPinConstraint, &u8) = (constraint, int_pin_cfg);
+        }
+        ConstraintViolation::AddrConflict {
+            constraint,
+            slave_addr,
+        } => {
+            let (_, _): (&AddrConstraint, &u8) = (constraint, slave_addr);
+        }
+        ConstraintViolation::ProbeFailed { answered } => {
+            let _: &[bool; 2] = answered;
+        }
+    };
+    let _ = |x: &RateTooHigh| {
+        let _: &f32 = &x.required_hz;
+        let _: &f32 = &x.available_hz;
+    };
+    let _ = |x: &CalibrationConfig| {
+        let _: &u32 = &x.samples;
+        let _: &f32 = &x.max_std_dev;
+        let _: &f32 = &x.stillness_threshold;
+        let _: &bool = &x.manual_commit;
+    };
+    let _ = |x: &BackgroundCalibrationStatus| {
+        let _: &u32 = &x.accumulated;
+        let _: &u32 = &x.remaining;
+        let _: &f32 = &x.std_dev;
+        let _: &u32 = &x.rejected;
+        let _: &Option<Vec3A> = &x.staged;
+    };
+    let _ = |x: &AbortProgress| {
+        let _: &u32 = &x.elapsed_ms;
+        let _: &u32 = &x.transactions;
+        let _: &u32 = &x.samples;
+        let _: &Option<Vec3A> = &x.partial_mean;
+    };
+    let _ = |x: &ChipCapabilities| {
+        let _: &ChipVariant = &x.variant;
+        let _: &bool = &x.fifo_available;
+        let _: &bool = &x.dmp_loadable;
+        let _: &TempFormula = &x.temp_formula;
+        let _: &AccelOffsetScaling = &x.accel_offset_scaling;
+        let _: &bool = &x.aux_i2c_master;
+        let _: &u32 = &x.max_gyro_odr;
+        let _: &CurrentTable = &x.supply_current;
+        let _: &Option<CloneAssessment> = &x.clone_assessment;
+        let _: &MotionEngineStatus = &x.motion_engine;
+    };
+    let _ = |x: &ErrorBudget| {
+        let _: &f32 = &x.noise_density_dps;
+        let _: &f32 = &x.bias_instability_dps;
+        let _: &f32 = &x.lsb_dps;
+        let _: &f32 = &x.update_rate_hz;
+        let _: &f32 = &x.angular_accel_dps2;
+    };
+    let _ = |x: &FifoSources| {
+        let _: &bool = &x.accel;
+        let _: &bool = &x.temp;
+        let _: &[bool; 3] = &x.gyro;
+        let _: &[bool; 4] = &x.slaves;
+    };
+    let _ = |x: &FifoSchema| {
+        let _: &AccelRange = &x.accel_range;
+        let _: &GyroRange = &x.gyro_range;
+        let _: &f32 = &x.acc_sensitivity;
+        let _: &f32 = &x.gyro_sensitivity;
+        let _: &u32 = &x.generation;
+        let _: &TempModel = &x.temp;
+    };
+    let _ = |x: &FifoFrame| {
+        let _: &Option<[i16; 3]> = &x.acc;
+        let _: &Option<i16> = &x.temp;
+        let _: &[Option<i16>; 3] = &x.gyro;
+        let _: &u32 = &x.generation;
+        let _: &u64 = &x.sequence;
+        let _: &Option<FifoGap> = &x.gap;
+    };
+    let _ = |x: &FifoGap| {
+        let _: &u16 = &x.pre_reset_count;
+        let _: &u32 = &x.lost_frames;
+        let _: &GapEstimate = &x.estimate;
+        let _: &u16 = &x.overflows;
+    };
+    let _ = |x: &FifoRecoveryStatus| {
+        let _: &u32 = &x.overflows;
+        let _: &u64 = &x.lost_frames;
+        let _: &Option<FifoGap> = &x.pending_gap;
+        let _: &u64 = &x.next_sequence;
+    };
+    let _ = |x: GapEstimate| match x {
+        GapEstimate::Elapsed | GapEstimate::FifoContent => (),
+    };
+    let _ = |x: &AlignmentProgress| {
+        let _: &f32 = &x.quasi_static_s;
+        let _: &f32 = &x.longitudinal_s;
+        let _: &u32 = &x.rejected;
+        let _: &Vec3A = &x.excitation;
+        let _: &[bool; 3] = &x.constrained;
+    };
+    let _ = |x: &AlignmentEstimate| {
+        let _: &Vec3A = &x.rotation_vector;
+        let _: &f32 = &x.residual_rms_rad;
+        let _: &Vec3A = &x.excitation;
+    };
+    let _ = |x: Maneuver| match x {
+        Maneuver::QuasiStatic | Maneuver::Longitudinal | Maneuver::Other => (),
+    };
+    let _ = |x: &ScaleDriftProgress| {
+        let _: &[f32; 8] = &x.octant_s;
+        let _: &usize = &x.populated;
+        let _: &Vec3A = &x.std_dev;
+    };
+    let _ = |x: &ScaleDriftEstimate| {
+        let _: &Vec3A = &x.scale_correction;
+        let _: &Vec3A = &x.std_dev;
+        let _: &Vec3A = &x.offset_g;
+        let _: &f32 = &x.residual_rms_g;
+        let _: &usize = &x.populated;
+    };
+    let _ = |x: ScaleDriftError| match x {
+        ScaleDriftError::LowConfidence(progress) => progress.populated as f32,
+        ScaleDriftError::Inconsistent { residual_rms_g } => residual_rms_g,
+    };
+    let _ = |x: &GovernorTransition| {
+        let _: &usize = &x.from;
+        let _: &usize = &x.to;
+        let _: &&'static str = &x.from_name;
+        let _: &&'static str = &x.to_name;
+        let _: &TransitionReason = &x.reason;
+        let _: &ActivityMetrics = &x.metrics;
+        let _: &f32 = &x.dwell_ms;
+        let _: &f32 = &x.sample_rate_hz;
+        let _: &Option<PowerChange> = &x.power;
+    };
+    let _ = |x: &ImpactRangeConfig| {
+        let _: &f32 = &x.trigger_g;
+        let _: &u16 = &x.hold_samples;
+        let _: &AccelRange = &x.high_range;
+        let _: &RearmPolicy = &x.rearm_policy;
+    };
+    let _ = |x: &ImpactStats| {
+        let _: &u32 = &x.impacts;
+        let _: &u32 = &x.extensions;
+        let _: &f32 = &x.peak_g;
+        let _: &u32 = &x.clipped_anyway;
+    };
+    let _ = |x: &ImpactPhase| match x {
+        ImpactPhase::Armed | ImpactPhase::Disarmed => {}
+        ImpactPhase::High { remaining } => {
+            let _: &u16 = remaining;
+        }
+        ImpactPhase::Quiet { remaining } => {
+            let _: &u8 = remaining;
+        }
+    };
+    let _ = |x: &InitFinding| {
+        let _: &FindingKind = &x.kind;
+        let _: &FindingAction = &x.action;
+    };
+    let _ = |x: &RangeInterleave| {
+        let _: &AccelRange = &x.low;
+        let _: &AccelRange = &x.high;
+        let _: &u8 = &x.period;
+    };
+    let _ = |x: &InterleaveRates| {
+        let _: &f32 = &x.per_range_hz;
+        let _: &f32 = &x.combined_hz;
+        let _: &f32 = &x.switches_hz;
+        let _: &f32 = &x.switch_transactions_hz;
+    };
+    let _ = |x: &InterleaveState| {
+        let _: &RangeInterleave = &x.config;
+        let _: &bool = &x.at_high;
+        let _: &u8 = &x.in_phase;
+        let _: &SettlingPolicy = &x.restore_policy;
+    };
+    let _ = |x: &InterruptEvents| {
+        let _: &InterruptSet = &x.fired;
+        let _: &InterruptSet = &x.asserted;
+    };
+    let _ = |x: &MotionEvent| {
+        let _: &u64 = &x.status_read_us;
+        let _: &MotionCompensation = &x.compensation;
+        let _: &u64 = &x.estimated_us;
+    };
+    let _ = |x: &LogHeader| {
+        let _: &String = &x.driver_version;
+        let _: &u8 = &x.who_am_i;
+        let _: &AccelRange = &x.accel_range;
+        let _: &GyroRange = &x.gyro_range;
+        let _: &u8 = &x.dlpf_cfg;
+        let _: &u8 = &x.sample_rate_div;
+        let _: &Option<LP_WAKE_CTRL> = &x.cycle;
+        let _: &Pipeline = &x.pipeline;
+        let _: &Vec<(u8, u8)> = &x.registers;
+        let _: &AxisMap = &x.axis_map;
+        let _: &Option<CalibrationNote> = &x.calibration;
+        let _: &Option<TempCalibration> = &x.temp_calibration;
+    };
+    let _ = |x: &IoStats| {
+        let _: &u32 = &x.transactions;
+        let _: &u32 = &x.bytes;
+    };
+    let _ = |x: &FilterGainSchedule| {
+        let _: &f32 = &x.alpha_at_rest;
+        let _: &f32 = &x.alpha_dynamic;
+        let _: &f32 = &x.gyro_rate_knee_rad_s;
+        let _: &bool = &x.accel_trust_weighting;
+    };
+    let _ = |x: &Reacquisition| {
+        let _: &f32 = &x.after_gated_s;
+        let _: &f32 = &x.ramp_s;
+    };
+    let _ = |x: &OrientationEstimate| {
+        let _: &Quat = &x.orientation;
+        let _: &Vec3A = &x.bias;
+        let _: &bool = &x.accel_gated;
+        let _: &f32 = &x.gated_s;
+        let _: &f32 = &x.accel_weight;
+        let _: &f32 = &x.alpha;
+        let _: &ReacquisitionState = &x.reacquisition;
+        let _: &bool = &x.initializing;
+        let _: &bool = &x.continuity_break;
+        let _: &u32 = &x.reentries;
+    };
+    let _ = |x: &InitCriteria| {
+        let _: &u32 = &x.min_samples;
+        let _: &f32 = &x.max_std_dev_g;
+        let _: &f32 = &x.max_magnitude_deviation;
+        let _: &u8 = &x.max_attempts;
+    };
+    let _ = |x: &InitBurst| {
+        let _: &Vec3A = &x.mean;
+        let _: &f32 = &x.std_dev_g;
+        let _: &u32 = &x.samples;
+    };
+    let _ = |x: &InitQuality| match x {
+        InitQuality::Initialized { std_dev_g } | InitQuality::Moving { std_dev_g } => {
+            let _: &f32 = std_dev_g;
+        }
+        InitQuality::NotGravity { magnitude_g } => {
+            let _: &f32 = magnitude_g;
+        }
+        InitQuality::TooFewSamples { samples } => {
+            let _: &u32 = samples;
+        }
+    };
+    let _ = |x: &PlausibilityPolicy| {
+        let _: &SampleHandling = &x.suspect;
+        let _: &SampleHandling = &x.implausible;
+    };
+    let _ = |x: &ClockErrorEstimate| {
+        let _: &f32 = &x.ratio;
+        let _: &f32 = &x.ratio_low;
+        let _: &f32 = &x.ratio_high;
+        let _: &f32 = &x.nominal_odr_hz;
+        let _: &f32 = &x.effective_odr_hz;
+        let _: &u64 = &x.samples;
+        let _: &u32 = &x.windows;
+        let _: &u64 = &x.duration_us;
+    };
+    let _ = |x: &ReferencedCalibration| {
+        let _: &u16 = &x.samples;
+        let _: &f32 = &x.max_std_dev;
+        let _: &u8 = &x.sample_interval_ms;
+    };
+    let _ = |x: &CalibrationResult| {
+        let _: &Vec3A = &x.offset;
+        let _: &f32 = &x.std_dev;
+        let _: &u32 = &x.samples;
+    };
+    let _ = |x: &PlausibilityConfig| {
+        let _: &Option<(f32, f32)> = &x.acc_magnitude_g;
+        let _: &Option<f32> = &x.max_gyro_rad_s;
+        let _: &Option<(f32, f32)> = &x.temp_c;
+        let _: &Option<f32> = &x.max_temp_step_c;
+        let _: &bool = &x.stale;
+        let _: &bool = &x.clipped;
+        let _: &u8 = &x.implausible;
+    };
+    let _ = |x: &BusTraffic| {
+        let _: &u32 = &x.bus_hz;
+        let _: &ReadPlan<'static> = &x.plan;
+    };
+    let _ = |x: &PowerChange| {
+        let _: &PowerEstimate = &x.before;
+        let _: &PowerEstimate = &x.after;
+    };
+    let _ = |x: &PowerConfig| {
+        let _: &PowerMode = &x.mode;
+        let _: &[bool; 3] = &x.gyro_axes;
+        let _: &bool = &x.accel;
+        let _: &bool = &x.temp;
+        let _: &f32 = &x.sample_rate_hz;
+        let _: &BusTraffic = &x.traffic;
+    };
+    let _ = |x: &PowerEstimate| {
+        let _: &PowerMode = &x.mode;
+        let _: &f32 = &x.base_ua;
+        let _: &[f32; 3] = &x.gyro_ua;
+        let _: &f32 = &x.accel_ua;
+        let _: &f32 = &x.temp_ua;
+        let _: &f32 = &x.i2c_ua;
+        let _: &f32 = &x.total_ua;
+    };
+    let _ = |x: &PowerState| {
+        let _: &bool = &x.asleep;
+        let _: &bool = &x.temp_enabled;
+        let _: &BusTraffic = &x.traffic;
+    };
+    let _ = |x: &BusRecoveryStats| {
+        let _: &u32 = &x.invocations;
+        let _: &u32 = &x.rate_limited;
+        let _: &u32 = &x.resumed;
+        let _: &u32 = &x.restored;
+        let _: &u32 = &x.unrecovered;
+    };
+    let _ = |x: &BitBlock| {
+        let _: &u8 = &x.bit;
+        let _: &u8 = &x.length;
+    };
+    let _ = |x: &ResolutionInfo| {
+        let _: &f32 = &x.accel_mg_per_lsb;
+        let _: &f32 = &x.gyro_dps_per_lsb;
+        let _: &f32 = &x.accel_bandwidth_hz;
+        let _: &f32 = &x.gyro_bandwidth_hz;
+        let _: &f32 = &x.accel_delay_ms;
+        let _: &f32 = &x.gyro_delay_ms;
+        let _: &f32 = &x.accel_noise_mg_rms;
+        let _: &f32 = &x.gyro_noise_dps_rms;
+    };
+    let _ = |x: &SampleMeta| {
+        let _: &u64 = &x.sequence;
+        let _: &bool = &x.overrun;
+        let _: &u32 = &x.overruns;
+    };
+    let _ = |x: &ScaleModel| {
+        let _: &f32 = &x.nominal;
+        let _: &Vec3A = &x.per_axis;
+    };
+    let _ = |x: &Pipeline| {
+        let _: &ScaleModel = &x.acc_scale;
+        let _: &ScaleModel = &x.gyro_scale;
+        let _: &Vec3A = &x.acc_offset;
+        let _: &Vec3A = &x.gyro_offset;
+        let _: &PipelineStages = &x.stages;
+    };
+    let _ = |x: &Mpu6050Settings| {
+        let _: &AccelRange = &x.accel_range;
+        let _: &GyroRange = &x.gyro_range;
+        let _: &u8 = &x.dlpf_cfg;
+        let _: &u8 = &x.sample_rate_div;
+        let _: &ACCEL_HPF = &x.accel_hpf;
+        let _: &CLKSEL = &x.clock_source;
+        let _: &Option<LP_WAKE_CTRL> = &x.cycle;
+    };
+    let _ = |x: &SettleCountdown| {
+        let _: &u8 = &x.acc;
+        let _: &u8 = &x.gyro;
+    };
+    let _ = |x: &AutoSetupOptions| {
+        let _: &AccelRange = &x.accel_range;
+        let _: &GyroRange = &x.gyro_range;
+        let _: &ACCEL_HPF = &x.accel_hpf;
+        let _: &Option<ThermalWait> = &x.thermal_wait;
+        let _: &bool = &x.actuation_check;
+        let _: &f32 = &x.min_actuation_g;
+        let _: &u16 = &x.gyro_samples;
+        let _: &f32 = &x.max_gyro_std_dev;
+        let _: &bool = &x.device_is_level;
+        let _: &u16 = &x.acc_samples;
+        let _: &f32 = &x.max_acc_std_dev;
+        let _: &u8 = &x.sample_interval_ms;
+    };
+    let _ = |x: &AutoSetupReport| {
+        let _: &[PhaseOutcome; AutoSetupPhase::COUNT] = &x.phases;
+        let _: &Vec3A = &x.gyro_offset;
+        let _: &Vec3A = &x.acc_offset;
+        let _: &Option<f32> = &x.gyro_std_dev;
+        let _: &Option<f32> = &x.acc_std_dev;
+        let _: &Option<f32> = &x.temperature;
+    };
+    let _ = |x: &AutoSetupError<Infallible>| {
+        let _: &AutoSetupPhase = &x.phase;
+        let _: &AutoSetupReport = &x.report;
+        let _: &AutoSetupFailure<Infallible> = &x.failure;
+    };
+    let _ = |x: &SkewCorrection| {
+        let _: &SkewReference = &x.reference;
+        let _: &Option<f32> = &x.skew_us;
+    };
+    let _ = |x: &ParseDiagnostic| {
+        let _: &[u8; 14] = &x.bytes;
+        let _: &FrameCheck = &x.check;
+        let _: &u32 = &x.frame;
+    };
+    let _ = |x: &LeverArmCompensation| {
+        let _: &f32 = &x.alpha_cutoff_hz;
+        let _: &f32 = &x.max_alpha;
+    };
+    let _ = |x: &FilterSpec| {
+        let _: &Smoothing = &x.acc;
+        let _: &Smoothing = &x.gyro;
+        let _: &Smoothing = &x.temp;
+    };
+    let _ = |x: &ConsumerSpec| {
+        let _: &u32 = &x.decimation;
+        let _: &OverflowPolicy = &x.overflow;
+    };
+    let _ = |x: &TempCalibration| match x {
+        TempCalibration::DatasheetDefault { variant } => {
+            let _: &TempFormula = variant;
+        }
+        TempCalibration::SinglePoint { variant, offset_c } => {
+            let (_, _): (&TempFormula, &f32) = (variant, offset_c);
+        }
+        TempCalibration::TwoPoint {
+            sensitivity,
+            offset_c,
+        } => {
+            let (_, _): (&f32, &f32) = (sensitivity, offset_c);
+        }
+    };
+    let _ = |x: &TempCalibrationError| match x {
+        TempCalibrationError::NotFinite => {}
+        TempCalibrationError::PointsTooClose { span_c } => {
+            let _: &f32 = span_c;
+        }
+        TempCalibrationError::SensitivityOutOfRange { lsb_per_c } => {
+            let _: &f32 = lsb_per_c;
+        }
+    };
+    let _ = |x: &ChannelSummary| {
+        let _: &u64 = &x.count;
+        let _: &f32 = &x.min;
+        let _: &f32 = &x.max;
+        let _: &f32 = &x.mean;
+        let _: &f32 = &x.p50;
+        let _: &f32 = &x.p95;
+        let _: &f32 = &x.p99;
+        let _: &f32 = &x.resolution;
+    };
+    let _ = |x: &StatsSnapshot| {
+        let _: &ChannelSummary = &x.acc_magnitude_g;
+        let _: &[ChannelSummary; 3] = &x.gyro_rad_s;
+        let _: &ChannelSummary = &x.temp_c;
+    };
+    let _ = |x: &DeniedRange| {
+        let _: &u8 = &x.first;
+        let _: &u8 = &x.last;
+        let _: &&'static str = &x.hazard;
+    };
+    let _ = |x: &CycleAdjustability| {
+        let _: &LowPowerField = &x.field;
+        let _: &u8 = &x.register;
+        let _: &bool = &x.adjustable;
+        let _: &&'static str = &x.reason;
+    };
+    let _ = |x: &LowPowerAdjust| {
+        let _: &Option<LP_WAKE_CTRL> = &x.wake_freq;
+        let _: &Option<u8> = &x.motion_threshold;
+        let _: &Option<u8> = &x.motion_duration;
+        let _: &Option<AccelRange> = &x.accel_range;
+        let _: &Option<ACCEL_HPF> = &x.accel_hpf;
+    };
+    let _ = |x: &CloneAssessment| match x {
+        CloneAssessment::LikelyGenuine | CloneAssessment::Inconclusive => {}
+        CloneAssessment::LikelyClone { evidence } => {
+            let _: &CloneEvidence = evidence;
+        }
+    };
+    let _ = |x: &CloneSign| match x {
+        CloneSign::ForeignWhoAmI
+        | CloneSign::WhoAmIFollowsAd0
+        | CloneSign::BlankSelfTestTrim
+        | CloneSign::BlankAccelTrim
+        | CloneSign::SelfTestOutOfRange => {}
+    };
+    let _ = |x: &ConfigChange| match x {
+        ConfigChange::AccelRange
+        | ConfigChange::GyroRange
+        | ConfigChange::Dlpf
+        | ConfigChange::SampleRateDivider
+        | ConfigChange::FifoSources
+        | ConfigChange::AuxSlaves
+        | ConfigChange::Offsets
+        | ConfigChange::ScaleFactors
+        | ConfigChange::TempCalibration
+        | ConfigChange::Reset => {}
+    };
+    let _ = |x: &DriverInfo| {
+        let _: &u8 = &x.slave_addr;
+        let _: &AccelRange = &x.accel_range;
+        let _: &GyroRange = &x.gyro_range;
+        let _: &u32 = &x.config_epoch;
+        let _: &u32 = &x.continuity_token;
+        let _: &u8 = &x.dlpf_cfg;
+        let _: &u8 = &x.sample_rate_div;
+        let _: &bool = &x.fifo_streaming;
+    };
+    let _ = |x: &DriverStateSnapshot| {
+        let _: &u8 = &x.slave_addr;
+        let _: &Option<u8> = &x.chip_id;
+        let _: &Option<u8> = &x.failed_register;
+        let _: &AccelRange = &x.accel_range;
+        let _: &GyroRange = &x.gyro_range;
+        let _: &u8 = &x.dlpf_cfg;
+        let _: &u8 = &x.sample_rate_div;
+        let _: &Option<LP_WAKE_CTRL> = &x.cycle;
+        let _: &bool = &x.strict_configuration;
+        let _: &SyncPoints = &x.synced;
+        let _: &ScaleModel = &x.acc_scale;
+        let _: &ScaleModel = &x.gyro_scale;
+        let _: &Vec3A = &x.acc_offset;
+        let _: &Vec3A = &x.gyro_offset;
+        let _: &ConnectionMonitor = &x.connection;
+        let _: &TiltThresholds = &x.tilt_thresholds;
+        let _: &Option<Supervisor> = &x.supervisor;
+        let _: &Option<GovernorStatus> = &x.governor;
+        let _: &Option<BackgroundCalibrationStatus> = &x.background_calibration;
+        let _: &[Option<SlaveConfig>; 4] = &x.aux_slaves;
+        let _: &u32 = &x.aux_generation;
+        let _: &FifoSources = &x.fifo_sources;
+        let _: &u32 = &x.config_epoch;
+        let _: &Option<ConfigChange> = &x.last_config_change;
+        let _: &Option<ConfigObserverStatus> = &x.config_observer;
+        let _: &bool = &x.fifo_streaming;
+        let _: &MixedReadPolicy = &x.mixed_read_policy;
+        let _: &FifoRecoveryStatus = &x.fifo_recovery;
+        let _: &bool = &x.fifo_clock;
+        let _: &Option<InterleaveState> = &x.range_interleave;
+        let _: &Option<ImpactRanger> = &x.impact_ranger;
+        let _: &f32 = &x.clock_ratio;
+        let _: &Option<SkewCorrection> = &x.skew_correction;
+        let _: &Vec3A = &x.lever_arm;
+        let _: &Option<LeverArmCompensator> = &x.lever_arm_compensation;
+        let _: &Option<BusRecoveryStats> = &x.bus_recovery;
+        let _: &InitFindings = &x.init_findings;
+        let _: &PowerState = &x.power;
+        let _: &Option<BoardConstraints> = &x.board;
+        let _: &Option<PlausibilityConfig> = &x.plausibility;
+        let _: &ParsePolicy = &x.parse_policy;
+        let _: &ParseDiagnostics = &x.parse_diagnostics;
+        let _: &TraceLevel = &x.trace_level;
+        let _: &u32 = &x.continuity_token;
+        let _: &Option<ContinuityBreak> = &x.last_continuity_break;
+        let _: &bool = &x.dormant;
+        let _: &RoundingMode = &x.rounding;
+        let _: &CalibrationMonitor = &x.calibration_validity;
+        let _: &u32 = &x.traffic_violations;
+        let _: &Option<TempCalibration> = &x.temp_calibration;
+        let _: &bool = &x.allow_undocumented;
+        let _: &ChipIdCheck = &x.chip_id_check;
+        let _: &InterruptEdgeTracker = &x.interrupt_tracker;
+        let _: &SettleCountdown = &x.settle;
+        let _: &SettlingPolicy = &x.settling_policy;
+        let _: &IoStats = &x.io_stats;
+        let _: &ChipCapabilities = &x.capabilities;
+        let _: &bool = &x.sample_hook;
+        let _: &bool = &x.sample_tap;
+        let _: &bool = &x.sample_observer;
+        let _: &bool = &x.owns_delay;
+        let _: &bool = &x.metrics_sink;
+        let _: &Option<GaugeLimiter> = &x.gauge_limiter;
+    };
+    let _ = |x: &SupervisorEvent| {
+        let _: &SupervisorLimit = &x.limit;
+        let _: &f32 = &x.value;
+    };
+    let _ = |x: &SupervisorConfig| {
+        let _: &Option<Vec3A> = &x.max_gyro_rad_s;
+        let _: &Option<f32> = &x.max_acc_g;
+        let _: &u8 = &x.consecutive;
+        let _: &Option<fn(SupervisorEvent)> = &x.callback;
+    };
+    let _ = |x: &TiltThresholds| {
+        let _: &f32 = &x.high;
+        let _: &f32 = &x.medium;
+        let _: &f32 = &x.invalid_below;
+    };
+    let _ = |x: &TiltEstimate| {
+        let _: &f32 = &x.roll;
+        let _: &f32 = &x.pitch;
+        let _: &TiltTrust = &x.trust;
+    };
+    let _ = |x: &CalibrationMeta| {
+        let _: &CalibrationMethod = &x.method;
+        let _: &Option<u64> = &x.timestamp;
+        let _: &Option<f32> = &x.temperature_c;
+        let _: &Option<f32> = &x.gyro_std_dev;
+        let _: &Option<f32> = &x.acc_std_dev;
+        let _: &InvalidationReasons = &x.latched;
+    };
+    let _ = |x: &CalibrationPolicy| {
+        let _: &Option<u64> = &x.max_age;
+        let _: &Option<f32> = &x.max_temp_delta_c;
+        let _: &Option<f32> = &x.invalidate_on_shock_g;
+        let _: &Option<f32> = &x.max_scale_drift;
+        let _: &Option<fn(CalibrationStatus)> = &x.on_change;
+    };
+    let _ = |x: &HintContext| {
+        let _: &Option<BusErrorKind> = &x.bus_error;
+        let _: &u32 = &x.successes;
+        let _: &u32 = &x.consecutive_errors;
+        let _: &u32 = &x.disconnect_threshold;
+        let _: &u32 = &x.unrecovered_lockups;
+    };
+    let _ = |x: &ShutdownCheck| {
+        let _: &ShutdownItem = &x.item;
+        let _: &Register = &x.register;
+        let _: &u16 = &x.mask;
+        let _: &u16 = &x.expected;
+        let _: &Option<u16> = &x.observed;
+        let _: &Option<bool> = &x.written;
+    };
+}
@@ -0,0 +1,326 @@
+//! Async driver against embedded-hal-mock's async I2C: the README loop as an async task, the
+//! readings of the blocking driver for the same registers, errors, and the interrupt driven
+//! sampling task over a mock INT pin, see the `async_driver` module.
+
+mod common;
+
+use std::future::Future;
+use std::pin::pin;
+use std::task::{Context, Poll, Waker};
+
+use embedded_hal_async::i2c::ErrorKind;
+use embedded_hal_mock::eh1::delay::{CheckedDelay, NoopDelay, Transaction as DelayTransaction};
+use embedded_hal_mock::eh1::digital::{
+    Mock as PinMock, State as PinState, Transaction as PinTransaction,
+};
+use embedded_hal_mock::eh1::i2c::{Mock, Transaction};
+use mpu6050::async_driver::Mpu6050Async;
+use mpu6050::device::*;
+use mpu6050::sampling::{SampleControl, SampleMeta, SamplingError};
+use mpu6050::settling::SettlingPolicy;
+use mpu6050::*;
+
+use common::{NoDelay, SharedBus};
+
+const ADDR: u8 = DEFAULT_SLAVE_ADDR;
+
+/// polls `future` to completion, the mocks never return pending
+fn block_on<F: Future>(future: F) -> F::Output {
+    let mut future = pin!(future);
+    let mut cx = Context::from_waker(Waker::noop());
+    loop {
+        if let Poll::Ready(output) = future.as_mut().poll(&mut cx) {
+            return output;
+        }
+    }
+}
+
+/// read, modify, write of one register, `before` read and `after` written
+fn update(reg: u8, before: u8, after: u8) -> [Transaction; 2] {
+    [
+        Transaction::write_read(ADDR, vec![reg], vec![before]),
+        Transaction::write(ADDR, vec![reg, after]),
+    ]
+}
+
+fn init_transactions() -> Vec<Transaction> {
+    let mut transactions = vec![
+        Transaction::write_read(ADDR, vec![WHOAMI], vec![0x68]),
+        Transaction::write(ADDR, vec![PWR_MGMT_1::ADDR, 0x01]),
+    ];
+    transactions.extend(update(ACCEL_CONFIG::ADDR, 0, 0));
+    transactions.extend(update(GYRO_CONFIG::ADDR, 0, 0));
+    transactions.extend(update(ACCEL_CONFIG::ADDR, 0, 0));
+    transactions
+}
+
+/// 14 bytes from ACCEL_XOUT_H, at rest, 90 °/s about Z, ±2g and ±250 °/s
+fn frame() -> [u8; 14] {
+    let rate = Vec3A::new(0., 0., 90.);
+    synthetic::frame_bytes(Vec3A::Z, rate, AccelRange::G2, GyroRange::D250)
+}
+
+fn read(reg: u8, bytes: &[u8]) -> Transaction {
+    Transaction::write_read(ADDR, vec![reg], bytes.to_vec())
+}
+
+/// the loop of the README's basic usage, `n` times round
+async fn readme_loop(
+    mpu: &mut Mpu6050Async<Mock>,
+    delay: &mut CheckedDelay,
+    n: usize,
+) -> Result<Vec<(Quat, f32, Vec3A, Vec3A)>, Mpu6050Error<ErrorKind>> {
+    mpu.init(delay).await?;
+    let mut readings = Vec::new();
+    for _ in 0..n {
+        // get roll and pitch estimate
+        let angles = mpu.get_acc_angles().await?;
+        // get temp
+        let temp = mpu.get_temp().await?;
+        // get gyro data, scaled with sensitivity
+        let gyro = mpu.get_gyro().await?;
+        // get accelerometer data, scaled with sensitivity
+        let acc = mpu.get_acc().await?;
+        readings.push((angles, temp, gyro, acc));
+    }
+    Ok(readings)
+}
+
+#[test]
+fn the_readme_loop_runs_as_an_async_task() {
+    let bytes = frame();
+    let mut transactions = init_transactions();
+    for _ in 0..2 {
+        transactions.extend([
+            read(ACC_REGX_H, &bytes[0..6]),
+            read(TEMP_OUT_H, &bytes[6..8]),
+            read(GYRO_REGX_H, &bytes[8..14]),
+            read(ACC_REGX_H, &bytes[0..6]),
+        ]);
+    }
+    let mut i2c = Mock::new(&transactions);
+    let mut delay = CheckedDelay::new(&[DelayTransaction::async_delay_ms(100)]);
+
+    let mut mpu = Mpu6050Async::new(i2c.clone());
+    let readings = block_on(readme_loop(&mut mpu, &mut delay, 2)).unwrap();
+    i2c.done();
+    delay.done();
+
+    assert_eq!(readings.len(), 2);
+    let (angles, _, gyro, acc) = readings[0];
+    assert!(angles.angle_between(Quat::IDENTITY) < 1e-3);
+    assert!((gyro.z - core::f32::consts::FRAC_PI_2).abs() < 1e-3);
+    assert!((acc - Vec3A::Z).length() < 1e-3);
+}
+
+#[test]
+fn readings_match_the_blocking_driver() {
+    let bytes = synthetic::frame_bytes(
+        Vec3A::new(0.3, -0.5, 0.8),
+        Vec3A::new(10., -200., 35.),
+        AccelRange::G8,
+        GyroRange::D500,
+    );
+    let bus = SharedBus::new(&[ADDR]);
+    bus.device(ADDR, |m| m.set_frame(&bytes));
+    let mut blocking = Mpu6050Builder::new().i2c(bus.clone()).build().unwrap();
+    blocking.init(&mut NoDelay).unwrap();
+    blocking.set_settling_policy(SettlingPolicy::Ignore);
+    blocking.set_accel_range(AccelRange::G8).unwrap();
+    blocking.set_gyro_range(GyroRange::D500).unwrap();
+
+    let mut transactions = init_transactions();
+    transactions.extend(update(ACCEL_CONFIG::ADDR, 0, 2 << 3));
+    transactions.extend(update(GYRO_CONFIG::ADDR, 0, 1 << 3));
+    transactions.extend([
+        read(ACC_REGX_H, &bytes[0..6]),
+        read(GYRO_REGX_H, &bytes[8..14]),
+        read(TEMP_OUT_H, &bytes[6..8]),
+        read(ACC_REGX_H, &bytes[0..6]),
+    ]);
+    let mut i2c = Mock::new(&transactions);
+    let mut mpu = Mpu6050Async::new(i2c.clone());
+    block_on(async {
+        mpu.init(&mut NoopDelay::new()).await.unwrap();
+        mpu.set_accel_range(AccelRange::G8).await.unwrap();
+        mpu.set_gyro_range(GyroRange::D500).await.unwrap();
+        assert_eq!(mpu.get_acc().await.unwrap(), blocking.get_acc().unwrap());
+        assert_eq!(mpu.get_gyro().await.unwrap(), blocking.get_gyro().unwrap());
+        assert_eq!(mpu.get_temp().await.unwrap(), blocking.get_temp().unwrap());
+        assert_eq!(
+            mpu.get_acc_angles().await.unwrap(),
+            blocking.get_acc_angles().unwrap()
+        );
+    });
+    i2c.done();
+}
+
+#[test]
+fn register_helpers_and_errors() {
+    let mut transactions = vec![Transaction::write_read(ADDR, vec![WHOAMI], vec![0x70])];
+    transactions.extend(update(PWR_MGMT_1::ADDR, 0x01, 0x41));
+    transactions.extend([
+        Transaction::write_read(ADDR, vec![PWR_MGMT_1::ADDR], vec![0x41]),
+        Transaction::write_read(ADDR, vec![GYRO_CONFIG::ADDR], vec![0b0001_1000]),
+        Transaction::write(ADDR, vec![SMPLRT_DIV, 9]).with_error(ErrorKind::Bus),
+    ]);
+    let mut i2c = Mock::new(&transactions);
+    let mut mpu = Mpu6050Async::new(i2c.clone());
+    block_on(async {
+        assert_eq!(mpu.read_byte(WHOAMI).await.unwrap(), 0x70);
+        mpu.set_sleep_enabled(true).await.unwrap();
+        assert!(mpu.get_sleep_enabled().await.unwrap());
+        assert_eq!(mpu.get_gyro_range().await.unwrap(), GyroRange::D2000);
+        assert!(matches!(
+            mpu.write_byte(SMPLRT_DIV, 9).await,
+            Err(Mpu6050Error::I2c(ErrorKind::Bus))
+        ));
+        // refused before the bus
+        assert!(matches!(
+            mpu.read_bytes(0x6d, &mut [0; 4]).await,
+            Err(Mpu6050Error::DeniedRegisterRange(_))
+        ));
+    });
+    i2c.done();
+
+    // a foreign chip fails init before the wake
+    let mut i2c = Mock::new(&[Transaction::write_read(ADDR, vec![WHOAMI], vec![0x70])]);
+    let mut mpu = Mpu6050Async::new(i2c.clone());
+    let res = block_on(mpu.init(&mut NoopDelay::new()));
+    assert!(matches!(res, Err(Mpu6050Error::InvalidChipId(0x70))));
+    i2c.done();
+    // unless accepted
+    let mut transactions = init_transactions();
+    transactions[0] = Transaction::write_read(ADDR, vec![WHOAMI], vec![0x70]);
+    let mut i2c = Mock::new(&transactions);
+    let mut mpu = Mpu6050Async::new(i2c.clone()).with_chip_id_check(ChipIdCheck::OneOf(&[0x70]));
+    block_on(mpu.init(&mut NoopDelay::new())).unwrap();
+    i2c.done();
+}
+
+/// the INT pin configured for the sampling task, I2C_BYPASS_EN kept, then DATA_RDY_EN
+fn sampling_setup() -> Vec<Transaction> {
+    let mut transactions = update(INT_PIN_CFG::ADDR, 0xd2, 0x22).to_vec();
+    transactions.extend(update(INT_ENABLE::ADDR, 0x00, 0x01));
+    transactions
+}
+
+/// INT_STATUS with DATA_RDY and the frame in one burst
+fn sample_burst(bytes: &[u8; 14]) -> Transaction {
+    read(INT_STATUS::ADDR, &[&[0x01][..], bytes].concat())
+}
+
+fn pin_high() -> PinTransaction {
+    PinTransaction::wait_for_state(PinState::High)
+}
+
+#[test]
+fn the_sampling_task_awaits_the_pin_for_every_sample() {
+    let bytes = frame();
+    let mut transactions = sampling_setup();
+    let mut pin_transactions = Vec::new();
+    for i in 0..3 {
+        pin_transactions.push(pin_high());
+        transactions.push(sample_burst(&bytes));
+        if i < 2 {
+            transactions.push(read(INT_STATUS::ADDR, &[0x00]));
+        }
+    }
+    let mut i2c = Mock::new(&transactions);
+    let mut pin = PinMock::new(&pin_transactions);
+
+    let mut mpu = Mpu6050Async::new(i2c.clone());
+    let mut delivered = Vec::new();
+    block_on(mpu.run_sampling_task(&mut pin, |sample, meta| {
+        delivered.push((sample, meta));
+        match meta.sequence {
+            2 => SampleControl::Stop,
+            _ => SampleControl::Continue,
+        }
+    }))
+    .unwrap();
+    i2c.done();
+    pin.done();
+
+    assert_eq!(delivered.len(), 3);
+    for (i, (sample, meta)) in delivered.iter().enumerate() {
+        let expected = SampleMeta {
+            sequence: i as u64,
+            overrun: false,
+            overruns: 0,
+        };
+        assert_eq!(*meta, expected);
+        assert!((sample.acc() - Vec3A::Z).length() < 1e-3);
+        assert!((sample.gyro().z - core::f32::consts::FRAC_PI_2).abs() < 1e-3);
+    }
+}
+
+#[test]
+fn a_slow_callback_is_an_overrun_and_skips_the_wait() {
+    let bytes = frame();
+    let mut transactions = sampling_setup();
+    transactions.extend([
+        sample_burst(&bytes),
+        // DATA_RDY still set after the callback: a sample was missed
+        read(INT_STATUS::ADDR, &[0x01]),
+        sample_burst(&bytes),
+        read(INT_STATUS::ADDR, &[0x00]),
+        sample_burst(&bytes),
+    ]);
+    let mut i2c = Mock::new(&transactions);
+    // the second sample is read without awaiting the pin
+    let mut pin = PinMock::new(&[pin_high(), pin_high()]);
+
+    let mut mpu = Mpu6050Async::new(i2c.clone());
+    let mut metas = Vec::new();
+    block_on(mpu.run_sampling_task(&mut pin, |_, meta| {
+        metas.push(meta);
+        match metas.len() {
+            3 => SampleControl::Stop,
+            _ => SampleControl::Continue,
+        }
+    }))
+    .unwrap();
+    i2c.done();
+    pin.done();
+
+    let overruns: Vec<_> = metas.iter().map(|m| (m.overrun, m.overruns)).collect();
+    assert_eq!(overruns, [(false, 0), (true, 1), (false, 1)]);
+}
+
+#[test]
+fn stop_returns_without_touching_the_bus_again() {
+    let bytes = frame();
+    let mut transactions = sampling_setup();
+    transactions.push(sample_burst(&bytes));
+    let mut i2c = Mock::new(&transactions);
+    let mut pin = PinMock::new(&[pin_high()]);
+
+    let mut mpu = Mpu6050Async::new(i2c.clone());
+    let mut calls = 0;
+    block_on(mpu.run_sampling_task(&mut pin, |_, _| {
+        calls += 1;
+        SampleControl::Stop
+    }))
+    .unwrap();
+    // no INT_STATUS read after the callback, no further wait
+    i2c.done();
+    pin.done();
+    assert_eq!(calls, 1);
+
+    // a bus error ends the task before the first wait
+    let transactions = [
+        Transaction::write_read(ADDR, vec![INT_PIN_CFG::ADDR], vec![0])
+            .with_error(ErrorKind::Other),
+    ];
+    let mut i2c = Mock::new(&transactions);
+    let mut pin = PinMock::new(&[]);
+    let mut mpu = Mpu6050Async::new(i2c.clone());
+    let res = block_on(mpu.run_sampling_task(&mut pin, |_, _| SampleControl::Continue));
+    assert!(matches!(
+        res,
+        Err(SamplingError::Device(Mpu6050Error::I2c(ErrorKind::Other)))
+    ));
+    i2c.done();
+    pin.done();
+}
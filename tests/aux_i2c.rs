@@ -0,0 +1,206 @@
+//! EXT_SENS_DATA bookkeeping of the aux I2C slaves: the packing rule against the register
+//! map's example, and two slaves on a mock register file reading their own bytes, see the
+//! `aux_i2c` module.
+
+mod common;
+
+use mpu6050::aux_i2c::{ext_data_layout, SlaveConfig, SlaveSlot};
+use mpu6050::device::{EXT_SENS_DATA_00, I2C_SLV};
+use mpu6050::{Mpu6050, Mpu6050Builder, Mpu6050Error};
+
+use common::SharedBus;
+
+const ADDR: u8 = 0x68;
+
+fn read(len: u8) -> Option<SlaveConfig> {
+    Some(SlaveConfig {
+        address: 0x0c,
+        register: 0x03,
+        len,
+        read: true,
+        byte_swap: false,
+    })
+}
+
+/// 6 magnetometer bytes
+const MAG: SlaveConfig = SlaveConfig {
+    address: 0x0c,
+    register: 0x03,
+    len: 6,
+    read: true,
+    byte_swap: false,
+};
+
+/// 2 barometer bytes
+const BARO: SlaveConfig = SlaveConfig {
+    address: 0x77,
+    register: 0xf6,
+    len: 2,
+    read: true,
+    byte_swap: true,
+};
+
+#[test]
+fn layout_follows_the_register_map_example() {
+    // register map rev 4.2 section 4.19: SLV0 with 4 bytes and SLV1 with 2 bytes use
+    // EXT_SENS_DATA_00 to 03 and 04 to 05, an added SLV2 with 4 bytes 06 to 09
+    assert_eq!(
+        ext_data_layout(&[read(4), read(2), None, None]),
+        Ok([Some((0, 4)), Some((4, 2)), None, None])
+    );
+    assert_eq!(
+        ext_data_layout(&[read(4), read(2), read(4), None]),
+        Ok([Some((0, 4)), Some((4, 2)), Some((6, 4)), None])
+    );
+    // SLV1 disabled again: SLV2 moves down to 04 to 07
+    assert_eq!(
+        ext_data_layout(&[read(4), None, read(4), None]),
+        Ok([Some((0, 4)), None, Some((4, 4)), None])
+    );
+}
+
+#[test]
+fn write_slaves_and_empty_reads_take_no_space() {
+    let write = Some(SlaveConfig { read: false, ..MAG });
+    assert_eq!(
+        ext_data_layout(&[write, read(0), read(3), read(1)]),
+        Ok([None, None, Some((0, 3)), Some((3, 1))])
+    );
+}
+
+#[test]
+fn layout_is_limited_to_24_bytes() {
+    assert_eq!(
+        ext_data_layout(&[read(15), read(9), None, None]),
+        Ok([Some((0, 15)), Some((15, 9)), None, None])
+    );
+    assert_eq!(
+        ext_data_layout(&[read(15), read(9), None, read(1)]),
+        Err(25)
+    );
+}
+
+fn setup() -> (SharedBus, Mpu6050<SharedBus>) {
+    let bus = SharedBus::new(&[ADDR]);
+    bus.device(ADDR, |mock| {
+        for i in 0..24 {
+            mock.regs[(EXT_SENS_DATA_00 + i) as usize] = 0xa0 + i;
+        }
+    });
+    let mpu = Mpu6050Builder::new().i2c(bus.clone()).build().unwrap();
+    (bus, mpu)
+}
+
+#[test]
+fn configured_slaves_are_written_and_tracked() {
+    let (bus, mut mpu) = setup();
+    let mag = mpu
+        .configure_i2c_slave(SlaveSlot::Slv0, MAG)
+        .unwrap()
+        .unwrap();
+    let baro = mpu
+        .configure_i2c_slave(SlaveSlot::Slv1, BARO)
+        .unwrap()
+        .unwrap();
+
+    bus.device(ADDR, |mock| {
+        let slv = |n: u8| {
+            [
+                mock.regs[I2C_SLV::addr_reg(n) as usize],
+                mock.regs[I2C_SLV::reg_reg(n) as usize],
+                mock.regs[I2C_SLV::ctrl_reg(n) as usize],
+            ]
+        };
+        // read bit and address, register, enable, byte swap and length
+        assert_eq!(slv(0), [0x8c, 0x03, 0x86]);
+        assert_eq!(slv(1), [0xf7, 0xf6, 0xc2]);
+    });
+
+    assert_eq!((mag.slave, mag.offset, mag.len), (SlaveSlot::Slv0, 0, 6));
+    // the first handle is stale after the second configuration, the layout is current
+    let layout: Vec<_> = mpu.ext_data_layout().collect();
+    assert_eq!(layout.len(), 2);
+    assert_eq!(
+        (layout[1].slave, layout[1].offset, layout[1].len),
+        (SlaveSlot::Slv1, 6, 2)
+    );
+    assert_eq!(layout[1], baro);
+    assert!(matches!(
+        mpu.read_ext_slot(&mag, &mut [0; 6]),
+        Err(Mpu6050Error::StaleExtDataSlot)
+    ));
+}
+
+#[test]
+fn interleaved_data_is_attributed_to_each_slave() {
+    let (_, mut mpu) = setup();
+    mpu.configure_i2c_slave(SlaveSlot::Slv0, MAG).unwrap();
+    mpu.configure_i2c_slave(SlaveSlot::Slv1, BARO).unwrap();
+    let layout: Vec<_> = mpu.ext_data_layout().collect();
+
+    let mut mag = [0; 8];
+    mpu.read_ext_slot(&layout[0], &mut mag).unwrap();
+    assert_eq!(mag, [0xa0, 0xa1, 0xa2, 0xa3, 0xa4, 0xa5, 0, 0]);
+    let mut baro = [0; 2];
+    mpu.read_ext_slot(&layout[1], &mut baro).unwrap();
+    assert_eq!(baro, [0xa6, 0xa7]);
+
+    assert!(matches!(
+        mpu.read_ext_slot(&layout[0], &mut [0; 5]),
+        Err(Mpu6050Error::BufferTooSmall(6))
+    ));
+}
+
+#[test]
+fn reconfiguring_moves_downstream_slaves() {
+    let (bus, mut mpu) = setup();
+    mpu.configure_i2c_slave(SlaveSlot::Slv0, MAG).unwrap();
+    let baro = mpu
+        .configure_i2c_slave(SlaveSlot::Slv2, BARO)
+        .unwrap()
+        .unwrap();
+    assert_eq!(baro.offset, 6);
+
+    // fewer magnetometer bytes
+    mpu.configure_i2c_slave(SlaveSlot::Slv0, SlaveConfig { len: 4, ..MAG })
+        .unwrap();
+    assert!(matches!(
+        mpu.read_ext_slot(&baro, &mut [0; 2]),
+        Err(Mpu6050Error::StaleExtDataSlot)
+    ));
+    let baro = mpu.ext_data_layout().nth(1).unwrap();
+    assert_eq!((baro.slave, baro.offset), (SlaveSlot::Slv2, 4));
+    let mut data = [0; 2];
+    mpu.read_ext_slot(&baro, &mut data).unwrap();
+    assert_eq!(data, [0xa4, 0xa5]);
+
+    // magnetometer off
+    mpu.disable_i2c_slave(SlaveSlot::Slv0).unwrap();
+    assert_eq!(
+        bus.device(ADDR, |mock| mock.regs[I2C_SLV::ctrl_reg(0) as usize]) & 0x80,
+        0
+    );
+    let layout: Vec<_> = mpu.ext_data_layout().collect();
+    assert_eq!(layout.len(), 1);
+    assert_eq!((layout[0].slave, layout[0].offset), (SlaveSlot::Slv2, 0));
+}
+
+#[test]
+fn overflowing_configuration_is_refused_without_writes() {
+    let (bus, mut mpu) = setup();
+    mpu.configure_i2c_slave(SlaveSlot::Slv0, SlaveConfig { len: 15, ..MAG })
+        .unwrap();
+    mpu.configure_i2c_slave(SlaveSlot::Slv1, SlaveConfig { len: 8, ..MAG })
+        .unwrap();
+    let before: Vec<_> = mpu.ext_data_layout().collect();
+    bus.take_log();
+
+    assert!(matches!(
+        mpu.configure_i2c_slave(SlaveSlot::Slv3, BARO),
+        Err(Mpu6050Error::ExtDataOverflow(25))
+    ));
+    assert!(bus.take_log().is_empty());
+    // handles stay valid
+    assert_eq!(mpu.ext_data_layout().collect::<Vec<_>>(), before);
+    assert!(mpu.read_ext_slot(&before[1], &mut [0; 8]).is_ok());
+}
@@ -0,0 +1,166 @@
+//! Background gyro calibration fed from normal reads of a mixed still and moving stream:
+//! progress, pausing on motion, the quality gate, the swap and the manual commit, see the
+//! `calibration` module. The driver reads a mock register file.
+
+mod common;
+
+use mpu6050::calibration::CalibrationConfig;
+use mpu6050::device::{AccelRange, GyroRange, DEFAULT_SLAVE_ADDR};
+use mpu6050::{synthetic, Mpu6050, Mpu6050Builder, Vec3A};
+
+use common::SharedBus;
+
+/// constant bias of the simulated gyro in rad/s
+const BIAS: Vec3A = Vec3A::new(0.02, -0.015, 0.01);
+
+const CONFIG: CalibrationConfig = CalibrationConfig {
+    samples: 20,
+    max_std_dev: 0.01,
+    stillness_threshold: 0.1,
+    manual_commit: false,
+};
+
+struct Stream {
+    bus: SharedBus,
+    mpu: Mpu6050<SharedBus>,
+}
+
+impl Stream {
+    fn new() -> Self {
+        let bus = SharedBus::new(&[DEFAULT_SLAVE_ADDR]);
+        let mpu = Mpu6050Builder::new().i2c(bus.clone()).build().unwrap();
+        let mut stream = Self { bus, mpu };
+        stream.show(BIAS);
+        // settling samples are not fed
+        while stream.mpu.settle_countdown().is_settling() {
+            stream.mpu.get_gyro().unwrap();
+        }
+        stream
+    }
+
+    /// gyro reading in rad/s
+    fn show(&mut self, gyro: Vec3A) {
+        let frame = synthetic::frame_bytes(
+            Vec3A::Z,
+            gyro * (180. / core::f32::consts::PI),
+            AccelRange::G2,
+            GyroRange::D250,
+        );
+        self.bus
+            .device(DEFAULT_SLAVE_ADDR, |mock| mock.set_frame(&frame));
+    }
+
+    /// `count` gyro reads of the still device
+    fn still(&mut self, count: u32) {
+        self.show(BIAS);
+        for _ in 0..count {
+            self.mpu.get_gyro().unwrap();
+        }
+    }
+
+    /// `count` gyro reads while turning
+    fn moving(&mut self, count: u32) {
+        self.show(BIAS + Vec3A::new(0., 0., 1.));
+        for _ in 0..count {
+            self.mpu.get_gyro().unwrap();
+        }
+    }
+}
+
+fn assert_close(actual: Vec3A, expected: Vec3A) {
+    // one LSB at ±250°/s is 1.3e-4 rad/s
+    assert!(
+        (actual - expected).abs().max_element() < 2e-4,
+        "{actual:?} != {expected:?}"
+    );
+}
+
+#[test]
+fn progress_counts_still_samples_only() {
+    let mut s = Stream::new();
+    s.mpu.start_background_calibration(CONFIG);
+    let status = s.mpu.background_calibration_status().unwrap();
+    assert_eq!((status.accumulated, status.remaining), (0, 20));
+
+    s.still(8);
+    // motion pauses without resetting
+    s.moving(5);
+    let status = s.mpu.background_calibration_status().unwrap();
+    assert_eq!((status.accumulated, status.remaining), (8, 12));
+    assert!(status.std_dev < 1e-6, "{}", status.std_dev);
+    assert_eq!(status.staged, None);
+    assert_eq!(s.mpu.get_gyro_offset(), Vec3A::ZERO);
+
+    s.still(11);
+    assert_eq!(s.mpu.background_calibration_status().unwrap().remaining, 1);
+}
+
+#[test]
+fn offset_is_swapped_in_when_complete() {
+    let mut s = Stream::new();
+    s.mpu.start_background_calibration(CONFIG);
+    s.still(10);
+    s.moving(3);
+    s.still(10);
+
+    assert_eq!(s.mpu.background_calibration_status(), None);
+    assert_close(s.mpu.get_gyro_offset(), BIAS);
+    assert_close(s.mpu.get_gyro().unwrap(), Vec3A::ZERO);
+}
+
+#[test]
+fn noisy_attempts_are_rejected_and_restarted() {
+    let mut s = Stream::new();
+    s.mpu.start_background_calibration(CONFIG);
+    // ±0.03 rad/s around the bias: still, but a std dev of 0.03
+    for i in 0..20 {
+        let sign = if i % 2 == 0 { 1. } else { -1. };
+        s.show(BIAS + Vec3A::splat(0.03 * sign));
+        s.mpu.get_gyro().unwrap();
+    }
+    let status = s.mpu.background_calibration_status().unwrap();
+    assert_eq!(status.rejected, 1);
+    assert_eq!(status.accumulated, 0);
+    assert_eq!(s.mpu.get_gyro_offset(), Vec3A::ZERO);
+
+    s.still(20);
+    assert_eq!(s.mpu.background_calibration_status(), None);
+    assert_close(s.mpu.get_gyro_offset(), BIAS);
+}
+
+#[test]
+fn manual_commit_stages_the_offset() {
+    let mut s = Stream::new();
+    s.mpu.start_background_calibration(CalibrationConfig {
+        manual_commit: true,
+        ..CONFIG
+    });
+    assert_eq!(s.mpu.commit_background_calibration(), None);
+    s.still(25);
+
+    let staged = s
+        .mpu
+        .background_calibration_status()
+        .unwrap()
+        .staged
+        .unwrap();
+    assert_close(staged, BIAS);
+    // nothing applied yet
+    assert_eq!(s.mpu.get_gyro_offset(), Vec3A::ZERO);
+
+    assert_eq!(s.mpu.commit_background_calibration(), Some(staged));
+    assert_eq!(s.mpu.get_gyro_offset(), staged);
+    assert_eq!(s.mpu.background_calibration_status(), None);
+}
+
+#[test]
+fn reads_make_no_extra_transactions() {
+    let mut s = Stream::new();
+    s.bus.take_log();
+    s.still(5);
+    let plain = s.bus.take_log();
+
+    s.mpu.start_background_calibration(CONFIG);
+    s.still(5);
+    assert_eq!(s.bus.take_log(), plain);
+}
@@ -0,0 +1,201 @@
+//! Polled batches: a batch against single burst reads over the same scripted frames, bit for
+//! bit, and its bus usage, see the `batch` module. `get_all` against the single getters and
+//! the golden fixtures.
+
+mod common;
+
+use std::cell::Cell;
+
+use mpu6050::device::*;
+use mpu6050::frame::parse_frame;
+use mpu6050::op_bounds;
+use mpu6050::plausibility::PlausibilityConfig;
+use mpu6050::settling::SettlingPolicy;
+use mpu6050::synthetic::frame_bytes;
+use mpu6050::*;
+
+use common::{NoDelay, SharedBus};
+
+const ADDR: u8 = DEFAULT_SLAVE_ADDR;
+const K: usize = 8;
+
+/// frames at ±2 g and ±250 °/s, the fourth repeating the third
+fn script() -> [[u8; 14]; K] {
+    let mut frames = [[0; 14]; K];
+    for (i, frame) in frames.iter_mut().enumerate() {
+        let k = if i == 3 { 2. } else { i as f32 };
+        *frame = frame_bytes(
+            Vec3A::new(0.01 * k, -0.02, 1. - 0.001 * k),
+            Vec3A::new(k, -0.5 * k, 0.25),
+            AccelRange::G2,
+            GyroRange::D250,
+        );
+        frame[6..8].copy_from_slice(&(100 * i as i16 - 400).to_be_bytes());
+    }
+    frames
+}
+
+fn thermal_hook(sample: &mut MpuSample) {
+    *sample = sample.with_gyro(sample.gyro() * (1. + 0.001 * sample.temp()));
+}
+
+/// a driver right after init, settling countdowns running, with a hook and stale checks
+fn driver() -> (Mpu6050<SharedBus>, SharedBus) {
+    let bus = SharedBus::new(&[ADDR]);
+    let mut mpu = Mpu6050Builder::new()
+        .i2c(bus.clone())
+        .gyro_offset([0.01, 0., -0.02])
+        .build()
+        .unwrap();
+    mpu.init(&mut NoDelay).unwrap();
+    mpu.set_sample_hook(Some(thermal_hook));
+    mpu.set_plausibility_config(Some(PlausibilityConfig {
+        stale: true,
+        ..PlausibilityConfig::NONE
+    }));
+    mpu.reset_io_stats();
+    (mpu, bus)
+}
+
+#[test]
+fn batch_is_single_reads_bit_for_bit() {
+    let frames = script();
+
+    let (mut mpu, bus) = driver();
+    let singles: Vec<MpuSample> = frames
+        .iter()
+        .map(|frame| {
+            bus.device(ADDR, |mock| mock.set_frame(frame));
+            mpu.read_into_views::<1>(&mut []).unwrap()
+        })
+        .collect();
+    let single_io = mpu.io_stats();
+
+    let (mut mpu, bus) = driver();
+    bus.device(ADDR, |mock| mock.set_frame(&frames[0]));
+    let next = Cell::new(1);
+    let mut batch = [MpuSample::default(); K];
+    mpu.read_batch(
+        || {
+            bus.device(ADDR, |mock| mock.set_frame(&frames[next.get()]));
+            next.set(next.get() + 1);
+        },
+        &mut batch,
+    )
+    .unwrap();
+    // paced between reads only
+    assert_eq!(next.get(), K);
+
+    assert!(singles[0].settling());
+    assert!(singles
+        .iter()
+        .any(|s| s.plausibility() != singles[0].plausibility()));
+    let bits = |v: Vec3A| v.to_array().map(f32::to_bits);
+    for (i, (b, s)) in batch.iter().zip(&singles).enumerate() {
+        assert_eq!(bits(b.acc()), bits(s.acc()), "sample {}", i);
+        assert_eq!(bits(b.gyro()), bits(s.gyro()), "sample {}", i);
+        assert_eq!(b.temp().to_bits(), s.temp().to_bits(), "sample {}", i);
+        assert_eq!(b, s, "sample {}", i);
+        assert_eq!(
+            (
+                b.settling(),
+                b.out_of_band(),
+                b.provenance(),
+                b.plausibility()
+            ),
+            (
+                s.settling(),
+                s.out_of_band(),
+                s.provenance(),
+                s.plausibility()
+            )
+        );
+    }
+
+    // the same traffic as the single reads
+    assert_eq!(mpu.io_stats(), single_io);
+    assert!(mpu.io_stats().within(op_bounds::read_batch(K as u32)));
+    assert_eq!(mpu.io_stats().transactions, K as u32);
+    assert_eq!(mpu.io_stats().bytes, 17 * K as u32);
+}
+
+#[test]
+fn failed_batch_leaves_the_rest_untouched() {
+    let bus = SharedBus::new(&[ADDR]);
+    let mut mpu = Mpu6050Builder::new()
+        .i2c(bus)
+        .defer_bus_contact()
+        .build()
+        .unwrap();
+    let mut out = [MpuSample::default(); 2];
+    let mut paced = 0;
+    assert!(mpu.read_batch(|| paced += 1, &mut out).is_err());
+    assert_eq!(paced, 0);
+    assert_eq!(out, [MpuSample::default(); 2]);
+    assert_eq!(mpu.io_stats().transactions, 0);
+}
+
+#[test]
+fn get_all_is_the_single_getters_in_one_burst() {
+    let frames = script();
+    let bus = SharedBus::new(&[ADDR]);
+    let mut mpu = Mpu6050Builder::new()
+        .i2c(bus.clone())
+        .gyro_offset([0.01, 0., -0.02])
+        .build()
+        .unwrap();
+    mpu.init(&mut NoDelay).unwrap();
+    mpu.set_settling_policy(SettlingPolicy::Ignore);
+    let bits = |v: Vec3A| v.to_array().map(f32::to_bits);
+    for frame_bytes in &frames {
+        bus.device(ADDR, |mock| mock.set_frame(frame_bytes));
+        let (acc, gyro, temp) = (
+            mpu.get_acc().unwrap(),
+            mpu.get_gyro().unwrap(),
+            mpu.get_temp().unwrap(),
+        );
+        let raw = (
+            mpu.get_acc_raw().unwrap(),
+            mpu.get_temp_raw().unwrap(),
+            mpu.get_gyro_raw().unwrap(),
+        );
+
+        mpu.reset_io_stats();
+        let sample = mpu.get_all().unwrap();
+        assert_eq!(bits(sample.acc()), bits(acc));
+        assert_eq!(bits(sample.gyro()), bits(gyro));
+        assert_eq!(sample.temp().to_bits(), temp.to_bits());
+        assert_eq!(mpu.io_stats().transactions, 1);
+        assert!(mpu.io_stats().within(op_bounds::READ_SAMPLE_BURST));
+
+        let frame = mpu.get_all_raw().unwrap();
+        assert_eq!((frame.acc, frame.temp, frame.gyro), raw);
+        assert_eq!(frame, parse_frame(frame_bytes));
+        assert_eq!(mpu.io_stats().transactions, 2);
+    }
+}
+
+#[cfg(feature = "test-fixtures")]
+#[test]
+fn get_all_reads_the_fixture_ground_truth() {
+    use mpu6050::test_fixtures::{ROTATING_Z, TEMP_RAMP};
+
+    let bus = SharedBus::new(&[ADDR]);
+    let mut mpu = Mpu6050Builder::new().i2c(bus.clone()).build().unwrap();
+    mpu.init(&mut NoDelay).unwrap();
+    mpu.set_settling_policy(SettlingPolicy::Ignore);
+    for fixture in [ROTATING_Z, TEMP_RAMP] {
+        mpu.set_accel_range(fixture.accel_range).unwrap();
+        mpu.set_gyro_range(fixture.gyro_range).unwrap();
+        for idx in 0..fixture.len {
+            bus.device(ADDR, |mock| mock.set_frame(&fixture.bytes(idx)));
+            // the scaled values, the sample marked settling after the range change
+            let (sample, expected) = (mpu.get_all().unwrap(), fixture.expected(idx));
+            assert_eq!(
+                (sample.acc(), sample.gyro(), sample.temp()),
+                (expected.acc(), expected.gyro(), expected.temp())
+            );
+            assert_eq!(mpu.get_all_raw().unwrap(), fixture.frame(idx));
+        }
+    }
+}
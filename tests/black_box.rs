@@ -0,0 +1,469 @@
+//! Black box: the entry encoding byte for byte, the error codes, export and import round
+//! trips with truncated buffers and blobs, and the driver feeding a static box, see the
+//! `black_box` module.
+
+mod common;
+
+use mpu6050::aliasing::{AliasingAssessment, AliasingRisk};
+use mpu6050::black_box::*;
+use mpu6050::board::{AddrConstraint, ConstraintViolation, DeviceAddr};
+use mpu6050::bus::RateTooHigh;
+use mpu6050::chaos::{ChaosConfig, FlakyI2c};
+use mpu6050::connection::ReconnectOutcome;
+use mpu6050::deadline::AbortProgress;
+use mpu6050::device::*;
+use mpu6050::hint::RecoveryHint;
+use mpu6050::init_findings::{FindingAction, FindingKind, InitFinding};
+use mpu6050::interpolation::TimestampError;
+use mpu6050::metrics::{MemorySink, MetricEvent, MetricsSink, INITS, OPERATION_FAILURES};
+use mpu6050::packed::DecodeError;
+use mpu6050::parse_policy::FrameCheck;
+use mpu6050::recovery::{BusRecoveryOutcome, ResyncOutcome};
+use mpu6050::register::{AccessViolation, Register};
+use mpu6050::settings::SettingsError;
+use mpu6050::supervisor::{SupervisorEvent, SupervisorLimit};
+use mpu6050::*;
+
+use common::{NoDelay, RegisterMock};
+
+/// every kind with its bytes, the documented encoding
+fn encoded() -> Vec<(BlackBoxEvent, [u8; ENTRY_LEN])> {
+    vec![
+        (
+            BlackBoxEvent::Initialized { chip_id: 0x68 },
+            [0x01, 0x68, 0, 0, 0, 0, 0, 0],
+        ),
+        (
+            BlackBoxEvent::OperationFailed(FailureRecord {
+                operation: FailedOperation::Init,
+                error: 1,
+                detail: 0,
+                register: Some(0x6b),
+                hint: RecoveryHint::RetryAfterDelay { ms: 1000 },
+            }),
+            [0x02, 1, 1, 0, 0x6b, 1, 0xe8, 0x03],
+        ),
+        (
+            BlackBoxEvent::OperationFailed(FailureRecord {
+                operation: FailedOperation::Reconnect,
+                error: 2,
+                detail: 0x72,
+                register: None,
+                hint: RecoveryHint::FixConfiguration,
+            }),
+            [0x02, 2, 2, 0x72, 0xff, 6, 0, 0],
+        ),
+        (
+            BlackBoxEvent::InitFinding(InitFinding {
+                kind: FindingKind::SelfTestActive {
+                    gyro: [true, false, false],
+                    accel: [false, false, true],
+                },
+                action: FindingAction::Cleared,
+            }),
+            [0x03, 0, 0, 0b10_0001, 0, 0, 0, 0],
+        ),
+        (
+            BlackBoxEvent::InitFinding(InitFinding {
+                kind: FindingKind::PowerState { pwr_mgmt_1: 0x01 },
+                action: FindingAction::Overwritten,
+            }),
+            [0x03, 1, 1, 0x01, 0, 0, 0, 0],
+        ),
+        (
+            BlackBoxEvent::InitFinding(InitFinding {
+                kind: FindingKind::FifoOverflowLatched { int_status: 0x10 },
+                action: FindingAction::Kept,
+            }),
+            [0x03, 2, 2, 0x10, 0, 0, 0, 0],
+        ),
+        (
+            BlackBoxEvent::InitFinding(InitFinding {
+                kind: FindingKind::NonDefaultRegister {
+                    register: Register::SMPLRT_DIV,
+                    value: 9,
+                },
+                action: FindingAction::Overwritten,
+            }),
+            [0x03, 3, 1, 9, 0x19, 0, 0, 0],
+        ),
+        (BlackBoxEvent::Disconnected, [0x04, 0, 0, 0, 0, 0, 0, 0]),
+        (
+            BlackBoxEvent::Reconnected(ReconnectOutcome::SameChip { chip_id: 0x68 }),
+            [0x05, 0, 0x68, 0x68, 0, 0, 0, 0],
+        ),
+        (
+            BlackBoxEvent::Reconnected(ReconnectOutcome::DifferentChip {
+                previous: 0x68,
+                found: 0x70,
+            }),
+            [0x05, 1, 0x68, 0x70, 0, 0, 0, 0],
+        ),
+        (
+            BlackBoxEvent::SupervisorTripped(SupervisorEvent {
+                limit: SupervisorLimit::GyroRate(Axis::Y),
+                value: 1.,
+            }),
+            [0x06, 1, 0, 0, 0x80, 0x3f, 0, 0],
+        ),
+        (
+            BlackBoxEvent::SupervisorTripped(SupervisorEvent {
+                limit: SupervisorLimit::AccelMagnitude,
+                value: -2.,
+            }),
+            [0x06, 3, 0, 0, 0, 0xc0, 0, 0],
+        ),
+        (
+            BlackBoxEvent::BusRecovery {
+                outcome: BusRecoveryOutcome::Recovered,
+                resync: Some(ResyncOutcome::Resumed { restored: 4 }),
+            },
+            [0x07, 0, 1, 4, 0, 0, 0, 0],
+        ),
+        (
+            BlackBoxEvent::BusRecovery {
+                outcome: BusRecoveryOutcome::Failed,
+                resync: None,
+            },
+            [0x07, 2, 0, 0, 0, 0, 0, 0],
+        ),
+        (
+            BlackBoxEvent::FifoOverflow { bytes: 1024 },
+            [0x08, 0x00, 0x04, 0, 0, 0, 0, 0],
+        ),
+    ]
+}
+
+#[test]
+fn entry_encoding_is_stable() {
+    for (event, bytes) in encoded() {
+        assert_eq!(event.encode(), bytes, "{:?}", event);
+        assert_eq!(BlackBoxEvent::decode(&bytes), event);
+    }
+
+    // delays beyond the u16 saturate
+    let record = FailureRecord {
+        operation: FailedOperation::Init,
+        error: 1,
+        detail: 0,
+        register: None,
+        hint: RecoveryHint::RetryAfterDelay { ms: 100_000 },
+    };
+    let bytes = BlackBoxEvent::OperationFailed(record).encode();
+    assert_eq!(bytes[6..], [0xff, 0xff]);
+
+    // unknown kinds and codes keep their bytes
+    for bytes in [
+        [0x00; ENTRY_LEN],
+        [0x09, 1, 2, 3, 4, 5, 6, 7],
+        [0x02, 3, 1, 0, 0xff, 0, 0, 0],
+        [0x02, 1, 1, 0, 0xff, 7, 0, 0],
+        [0x03, 3, 1, 0, 0x80, 0, 0, 0],
+        [0x06, 4, 0, 0, 0, 0, 0, 0],
+        [0x07, 0, 4, 0, 0, 0, 0, 0],
+    ] {
+        let event = BlackBoxEvent::decode(&bytes);
+        assert_eq!(event, BlackBoxEvent::Unknown(bytes));
+        assert_eq!(event.encode(), bytes);
+    }
+}
+
+#[test]
+fn data_rate_events_are_left_out() {
+    let mut black_box = BlackBox::<4>::new();
+    assert!(!black_box.record(&MetricEvent::SamplingOverrun));
+    assert!(!black_box.record(&MetricEvent::SettlingRetry(metrics::Sensor::Gyro)));
+    assert!(black_box.record(&MetricEvent::Disconnected));
+    assert_eq!((black_box.len(), black_box.recorded()), (1, 1));
+}
+
+fn every_variant() -> Vec<Mpu6050Error<()>> {
+    #[allow(deprecated)]
+    let stale_schema = Mpu6050Error::StaleFifoSchema;
+    vec![
+        Mpu6050Error::I2c(()),
+        Mpu6050Error::InvalidChipId(0x72),
+        Mpu6050Error::Disconnected,
+        Mpu6050Error::ExtDataOverflow(30),
+        Mpu6050Error::StaleExtDataSlot,
+        stale_schema,
+        Mpu6050Error::StaleConfiguration {
+            captured: 3,
+            current: 5,
+        },
+        Mpu6050Error::BufferTooSmall(300),
+        Mpu6050Error::InvalidSettings(SettingsError::ReservedDlpfCfg(7)),
+        Mpu6050Error::AliasingLikely(AliasingAssessment {
+            risk: AliasingRisk::AliasingLikely,
+            odr_hz: 100.,
+            accel_rate_hz: 1000.,
+            accel_bandwidth_hz: 260.,
+            gyro_bandwidth_hz: 256.,
+            ratio: 2.6,
+            cycle: false,
+        }),
+        Mpu6050Error::InvalidTimestamp(TimestampError::Duplicate { t_us: 5 }),
+        Mpu6050Error::Unsupported(Capability::Fifo),
+        Mpu6050Error::Aborted {
+            phase: "gyro calibration",
+            progress: AbortProgress::default(),
+        },
+        Mpu6050Error::DelayRequired,
+        Mpu6050Error::FifoActive,
+        Mpu6050Error::InvalidRegisterAccess {
+            register: Register::WHO_AM_I,
+            violation: AccessViolation::ReadOnly,
+        },
+        Mpu6050Error::BoardConstraint(ConstraintViolation::AddrConflict {
+            constraint: AddrConstraint::Fixed(DeviceAddr::Ad0Low),
+            slave_addr: 0x69,
+        }),
+        Mpu6050Error::NotActivated,
+        Mpu6050Error::DeniedRegisterRange(DENIED_RANGES[0]),
+        Mpu6050Error::ImplausibleFrame {
+            reason: FrameCheck::AllZero,
+        },
+        Mpu6050Error::WakeOnMotionRefused(MotionEngineStatus::Broken),
+        Mpu6050Error::Reentrancy,
+        Mpu6050Error::BusRateTooHigh(RateTooHigh {
+            required_hz: 1000.,
+            available_hz: 370.,
+        }),
+    ]
+}
+
+#[test]
+fn error_codes_are_stable() {
+    let errors = every_variant();
+    assert_eq!(errors.len(), ERROR_NAMES.len());
+    for (i, error) in errors.iter().enumerate() {
+        assert_eq!(error.code() as usize, i + 1, "{:?}", error);
+    }
+    assert_eq!(error_name(1), Some("i2c"));
+    assert_eq!(error_name(2), Some("invalid_chip_id"));
+    assert_eq!(error_name(19), Some("denied_register_range"));
+    assert_eq!(error_name(20), Some("implausible_frame"));
+    assert_eq!(error_name(21), Some("wake_on_motion_refused"));
+    assert_eq!(error_name(22), Some("reentrancy"));
+    assert_eq!(error_name(23), Some("bus_rate_too_high"));
+    assert_eq!((error_name(0), error_name(24)), (None, None));
+}
+
+fn filled<const N: usize>(events: usize) -> BlackBox<N> {
+    let mut black_box = BlackBox::new();
+    let encoded = encoded();
+    for i in 0..events {
+        black_box.push(&encoded[i % encoded.len()].0);
+    }
+    black_box
+}
+
+#[test]
+fn export_round_trips() {
+    // wrapped around twice
+    let black_box = filled::<8>(20);
+    assert_eq!((black_box.len(), black_box.recorded()), (8, 20));
+    let events: Vec<_> = black_box.events().collect();
+    let expected: Vec<_> = (12..20).map(|i| encoded()[i % 15].0).collect();
+    assert_eq!(events, expected);
+
+    let mut blob = [0; export_len(8)];
+    assert_eq!(black_box.export(&mut blob), blob.len());
+    assert_eq!(blob[..8], [BLACK_BOX_VERSION, 8, 8, 0, 20, 0, 0, 0]);
+    assert_eq!(blob[8..16], expected[0].encode());
+    let export = decode(&blob).unwrap();
+    assert_eq!((export.recorded, export.dropped()), (20, 12));
+    assert_eq!(export.events, expected);
+    assert_eq!(BlackBox::<8>::import(&blob).unwrap(), black_box);
+
+    // larger boxes keep everything, smaller the newest
+    let larger = BlackBox::<32>::import(&blob).unwrap();
+    assert_eq!(larger.events().collect::<Vec<_>>(), expected);
+    assert_eq!(larger.recorded(), 20);
+    let smaller = BlackBox::<3>::import(&blob).unwrap();
+    assert_eq!(smaller.events().collect::<Vec<_>>(), expected[5..]);
+    assert_eq!(smaller.recorded(), 20);
+
+    // an empty box and a box without entries
+    let mut empty = [0; export_len(0)];
+    assert_eq!(BlackBox::<8>::new().export(&mut empty), 10);
+    assert!(decode(&empty).unwrap().events.is_empty());
+    let mut zero = BlackBox::<0>::new();
+    zero.push(&BlackBoxEvent::Disconnected);
+    assert_eq!(zero.export(&mut blob), 10);
+    assert_eq!(decode(&blob).unwrap().dropped(), 1);
+
+    // clearing forgets the count too
+    let mut cleared = black_box;
+    cleared.clear();
+    assert_eq!(cleared, BlackBox::new());
+}
+
+#[test]
+fn truncated_buffers_keep_the_newest() {
+    let black_box = filled::<8>(20);
+    let expected: Vec<_> = black_box.events().collect();
+    for len in 0..export_len(8) + 4 {
+        let mut out = vec![0xaa; len];
+        let written = black_box.export(&mut out);
+        if len < export_len(0) {
+            assert_eq!(written, 0);
+            assert!(out.iter().all(|b| *b == 0xaa));
+            continue;
+        }
+        let count = ((len - export_len(0)) / ENTRY_LEN).min(8);
+        assert_eq!(written, export_len(count), "{}", len);
+        assert!(out[written..].iter().all(|b| *b == 0xaa));
+        let export = decode(&out).unwrap();
+        assert_eq!(export.events, expected[8 - count..]);
+        assert_eq!(export.recorded, 20);
+    }
+}
+
+#[test]
+fn truncated_and_corrupt_blobs_are_refused() {
+    let black_box = filled::<8>(5);
+    let mut blob = [0xff; 128];
+    let len = black_box.export(&mut blob);
+    assert_eq!(len, export_len(5));
+
+    // bytes after the CRC are ignored, e.g. the rest of a flash page
+    assert_eq!(decode(&blob).unwrap(), decode(&blob[..len]).unwrap());
+    for cut in 0..len {
+        let expected = if cut < EXPORT_HEADER_LEN {
+            export_len(0)
+        } else {
+            len
+        };
+        assert_eq!(
+            decode(&blob[..cut]),
+            Err(DecodeError::Truncated(expected)),
+            "{}",
+            cut
+        );
+        assert_eq!(
+            BlackBox::<8>::import(&blob[..cut]),
+            Err(DecodeError::Truncated(expected))
+        );
+    }
+    for i in 2..len {
+        let mut corrupt = blob;
+        corrupt[i] ^= 0x10;
+        let result = decode(&corrupt);
+        // a larger count reads past the end or the CRC
+        assert!(
+            matches!(
+                result,
+                Err(DecodeError::CrcMismatch | DecodeError::Truncated(_))
+            ),
+            "{}: {:?}",
+            i,
+            result
+        );
+    }
+    // erased flash and a future format
+    assert_eq!(decode(&[0xff; 64]), Err(DecodeError::BadHeader));
+    let mut future = blob;
+    future[0] = BLACK_BOX_VERSION + 1;
+    assert_eq!(decode(&future), Err(DecodeError::BadHeader));
+    let mut wide = blob;
+    wide[1] = 12;
+    assert_eq!(decode(&wide), Err(DecodeError::BadHeader));
+}
+
+static INIT_BOX: SharedBlackBox<8> = SharedBlackBox::new();
+
+#[test]
+fn failed_init_is_recorded() {
+    let mut mock = RegisterMock::new();
+    mock.regs[WHOAMI as usize] = 0x72;
+    let mut mpu = Mpu6050Builder::new().i2c(mock).build().unwrap();
+    mpu.set_metrics_sink(Some(&INIT_BOX));
+    let error = mpu.init(&mut NoDelay).unwrap_err();
+    let record = FailureRecord {
+        operation: FailedOperation::Init,
+        error: 2,
+        detail: 0x72,
+        register: Some(WHOAMI),
+        hint: error.recovery_hint(),
+    };
+    let events: Vec<_> = INIT_BOX.black_box().events().collect();
+    assert_eq!(events, [BlackBoxEvent::OperationFailed(record)]);
+    assert_eq!(
+        record.to_string(),
+        "init failed: invalid_chip_id 0x72 at register 0x75, fix the configuration"
+    );
+
+    // the record outlives the driver
+    drop(mpu);
+    let mut mock = RegisterMock::new();
+    mock.regs[WHOAMI as usize] = 0x68;
+    let mut mpu = Mpu6050Builder::new().i2c(mock).build().unwrap();
+    mpu.set_metrics_sink(Some(&INIT_BOX));
+    mpu.init(&mut NoDelay).unwrap();
+    let mut eeprom = [0xff; 256];
+    let len = INIT_BOX.export(&mut eeprom);
+    assert!(INIT_BOX.black_box().is_empty());
+    let export = decode(&eeprom[..len]).unwrap();
+    assert_eq!(export.events[0], BlackBoxEvent::OperationFailed(record));
+    assert_eq!(
+        export.events.last(),
+        Some(&BlackBoxEvent::Initialized { chip_id: 0x68 })
+    );
+    let text = export.to_string();
+    assert!(text.starts_with("2 events recorded, 0 dropped\n0: init failed"));
+    assert!(text.ends_with("1: initialized, chip 0x68"));
+}
+
+#[test]
+fn bus_failures_name_the_register() {
+    let (i2c, chaos) = FlakyI2c::new(RegisterMock::new(), ChaosConfig::NONE);
+    let black_box = Box::leak(Box::new(SharedBlackBox::<8>::new()));
+    let mut mpu = Mpu6050Builder::new().i2c(i2c).build().unwrap();
+    mpu.set_metrics_sink(Some(black_box));
+    mpu.init(&mut NoDelay).unwrap();
+    black_box.clear();
+
+    chaos.fail_next(3);
+    for _ in 0..3 {
+        assert!(mpu.get_temp().is_err());
+    }
+    chaos.fail_next(1);
+    let error = mpu.try_reconnect(&mut NoDelay).unwrap_err();
+    let hint = error.recovery_hint_with(&mpu.hint_context());
+    mpu.try_reconnect(&mut NoDelay).unwrap();
+    let failed_register = mpu.debug_state().failed_register;
+    assert_eq!(failed_register, Some(WHOAMI));
+    assert_eq!(
+        black_box.black_box().events().collect::<Vec<_>>(),
+        [
+            BlackBoxEvent::Disconnected,
+            BlackBoxEvent::OperationFailed(FailureRecord {
+                operation: FailedOperation::Reconnect,
+                error: 1,
+                detail: 0,
+                register: failed_register,
+                hint,
+            }),
+            BlackBoxEvent::Reconnected(ReconnectOutcome::SameChip { chip_id: 0x68 }),
+        ]
+    );
+}
+
+#[test]
+fn forwarding_keeps_the_other_sink() {
+    let sink: &'static MemorySink = Box::leak(Box::new(MemorySink::new()));
+    let black_box = Box::leak(Box::new(SharedBlackBox::<8>::forwarding(sink)));
+    let mut mpu = Mpu6050Builder::new()
+        .i2c(RegisterMock::new())
+        .build()
+        .unwrap();
+    mpu.set_metrics_sink(Some(black_box));
+    mpu.init(&mut NoDelay).unwrap();
+    black_box.gauge(metrics::GAUGE_TEMP_C, 25.);
+    assert_eq!(sink.counter_value(INITS), 1);
+    assert_eq!(sink.counter_value(OPERATION_FAILURES), 0);
+    assert_eq!(sink.gauge_value(metrics::GAUGE_TEMP_C), Some(25.));
+    assert_eq!(sink.events(), [MetricEvent::Initialized { chip_id: 0x68 }]);
+    assert_eq!(black_box.black_box().len(), 1);
+}
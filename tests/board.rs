@@ -0,0 +1,376 @@
+//! Board constraints on mock buses: every INT pin request under each pin constraint, the
+//! probed and fixed address paths, and the unconstrained driver unchanged, see the `board`
+//! module.
+
+mod common;
+
+use embedded_hal::blocking::i2c::{Write, WriteRead};
+use mpu6050::board::*;
+use mpu6050::device::*;
+use mpu6050::interrupt::{InterruptSource, MotionDetectionConfig};
+use mpu6050::register::Register;
+use mpu6050::*;
+
+use common::{Access, NoDelay, SharedBus};
+
+const INT_PIN_CFG_ADDR: u8 = INT_PIN_CFG::ADDR;
+
+/// [`SharedBus`] with only some of its devices answering, the others NACK
+struct ProbeBus {
+    bus: SharedBus,
+    present: Vec<u8>,
+}
+
+#[derive(Debug)]
+struct Nack;
+
+impl ProbeBus {
+    fn check(&self, address: u8) -> Result<(), Nack> {
+        match self.present.contains(&address) {
+            true => Ok(()),
+            false => Err(Nack),
+        }
+    }
+}
+
+impl Write for ProbeBus {
+    type Error = Nack;
+
+    fn write(&mut self, address: u8, bytes: &[u8]) -> Result<(), Nack> {
+        self.check(address)?;
+        self.bus.write(address, bytes).map_err(|_| Nack)
+    }
+}
+
+impl WriteRead for ProbeBus {
+    type Error = Nack;
+
+    fn write_read(&mut self, address: u8, bytes: &[u8], buffer: &mut [u8]) -> Result<(), Nack> {
+        self.check(address)?;
+        self.bus
+            .write_read(address, bytes, buffer)
+            .map_err(|_| Nack)
+    }
+}
+
+fn constrained(int_pin: IntPinConstraint) -> (Mpu6050<SharedBus>, SharedBus) {
+    let bus = SharedBus::new(&[DEFAULT_SLAVE_ADDR]);
+    let mut mpu = Mpu6050Builder::new()
+        .i2c(bus.clone())
+        .board_constraints(BoardConstraints {
+            int_pin,
+            addr: AddrConstraint::Fixed(DeviceAddr::Ad0Low),
+        })
+        .build()
+        .unwrap();
+    mpu.init(&mut NoDelay).unwrap();
+    bus.take_log();
+    (mpu, bus)
+}
+
+fn reg(bus: &SharedBus, addr: u8) -> u8 {
+    bus.device(DEFAULT_SLAVE_ADDR, |m| m.regs[addr as usize])
+}
+
+fn wrote(log: &[Access], addr: u8) -> bool {
+    log.iter().any(|access| !access.read && access.reg == addr)
+}
+
+fn violation<E>(result: Result<(), Mpu6050Error<E>>) -> ConstraintViolation {
+    match result {
+        Err(Mpu6050Error::BoardConstraint(violation)) => violation,
+        _ => panic!("not a constraint violation"),
+    }
+}
+
+#[test]
+fn unconstrained_is_the_driver_without_constraints() {
+    let run = |board: Option<BoardConstraints>| {
+        let bus = SharedBus::new(&[DEFAULT_SLAVE_ADDR]);
+        let builder = Mpu6050Builder::new().i2c(bus.clone());
+        let builder = match board {
+            Some(board) => builder.board_constraints(board),
+            None => builder,
+        };
+        let mut mpu = builder.build().unwrap();
+        mpu.init(&mut NoDelay).unwrap();
+        mpu.setup_motion_detection(MotionDetectionConfig::DEFAULT)
+            .unwrap();
+        mpu.configure_int_pin(IntPinConfig::DATA_READY_STROBE)
+            .unwrap();
+        mpu.enable_data_ready_strobe().unwrap();
+        mpu.reset_device(&mut NoDelay).unwrap();
+        let regs = bus.device(DEFAULT_SLAVE_ADDR, |m| m.regs);
+        (bus.take_log(), regs)
+    };
+    let (log, regs) = run(None);
+    let unconstrained = BoardConstraints {
+        int_pin: IntPinConstraint::Unconstrained,
+        addr: AddrConstraint::Fixed(DeviceAddr::Ad0Low),
+    };
+    assert_eq!(run(Some(unconstrained)), (log.clone(), regs));
+    assert!(log
+        .iter()
+        .all(|access| access.address == DEFAULT_SLAVE_ADDR));
+
+    // motion detection as before: active-high push-pull latched
+    let bus = SharedBus::new(&[DEFAULT_SLAVE_ADDR]);
+    let mut mpu = Mpu6050Builder::new().i2c(bus.clone()).build().unwrap();
+    mpu.init(&mut NoDelay).unwrap();
+    assert_eq!(mpu.board_constraints(), None);
+    mpu.setup_motion_detection(MotionDetectionConfig::DEFAULT)
+        .unwrap();
+    assert_eq!(reg(&bus, INT_PIN_CFG_ADDR), 1 << INT_PIN_CFG::LATCH_INT_EN);
+}
+
+#[test]
+fn motion_trigger_pin_and_sources_configured_separately() {
+    let bus = SharedBus::new(&[DEFAULT_SLAVE_ADDR]);
+    let mut mpu = Mpu6050Builder::new().i2c(bus.clone()).build().unwrap();
+    mpu.init(&mut NoDelay).unwrap();
+    mpu.setup_motion_detection(MotionDetectionConfig {
+        threshold: 20,
+        duration: 5,
+    })
+    .unwrap();
+    assert_eq!(reg(&bus, Register::MOT_THR.addr()), 20);
+    assert_eq!(reg(&bus, Register::MOT_DUR.addr()), 5);
+
+    // another pin after the setup
+    mpu.configure_int_pin(IntPinConfig {
+        active_low: true,
+        open_drain: true,
+        latched: true,
+        clear_on_any_read: true,
+    })
+    .unwrap();
+    assert_eq!(reg(&bus, INT_PIN_CFG_ADDR), 0xf0);
+
+    // one source at a time, the others untouched
+    mpu.set_int_enabled(InterruptSource::DataReady, true)
+        .unwrap();
+    mpu.set_int_enabled(InterruptSource::Motion, false).unwrap();
+    mpu.set_int_enabled(InterruptSource::FifoOverflow, true)
+        .unwrap();
+    assert_eq!(reg(&bus, Register::INT_ENABLE.addr()), 0x11);
+    let enabled = mpu.get_int_enabled().unwrap();
+    assert_eq!(
+        enabled.iter().collect::<Vec<_>>(),
+        [InterruptSource::FifoOverflow, InterruptSource::DataReady]
+    );
+
+    // every source from one read
+    bus.device(DEFAULT_SLAVE_ADDR, |m| {
+        m.regs[Register::INT_STATUS.addr() as usize] = 0x71
+    });
+    bus.take_log();
+    let status = mpu.get_int_status().unwrap();
+    assert_eq!(bus.take_log().len(), 1);
+    assert_eq!(status.bits(), 0x71);
+    for source in InterruptSource::ALL {
+        assert_eq!(status.contains(source), 0x71 & (1 << source.bit()) != 0);
+    }
+    assert!(mpu.get_motion_detected().unwrap());
+}
+
+#[test]
+fn a_wired_or_line_gets_open_drain_active_low() {
+    let open_drain_active_low = (1 << INT_PIN_CFG::INT_LEVEL) | (1 << INT_PIN_CFG::INT_OPEN);
+    let (mut mpu, bus) = constrained(IntPinConstraint::RequireOpenDrainActiveLow);
+    // init put the pin on the line's terms before waking
+    assert_eq!(reg(&bus, INT_PIN_CFG_ADDR), open_drain_active_low);
+
+    // adapted
+    mpu.setup_motion_detection(MotionDetectionConfig::DEFAULT)
+        .unwrap();
+    assert_eq!(
+        reg(&bus, INT_PIN_CFG_ADDR),
+        open_drain_active_low | (1 << INT_PIN_CFG::LATCH_INT_EN)
+    );
+    assert_ne!(reg(&bus, INT_ENABLE::ADDR) & (1 << INT_ENABLE::MOT_EN), 0);
+    mpu.enable_data_ready_strobe().unwrap();
+    assert_eq!(reg(&bus, INT_PIN_CFG_ADDR), open_drain_active_low);
+    assert_ne!(
+        reg(&bus, INT_ENABLE::ADDR) & (1 << INT_ENABLE::DATA_RDY_EN),
+        0
+    );
+
+    // rejected before touching the bus
+    bus.take_log();
+    let push_pull = IntPinConfig::MOTION;
+    assert_eq!(
+        violation(mpu.configure_int_pin(push_pull)),
+        ConstraintViolation::IntPin {
+            constraint: IntPinConstraint::RequireOpenDrainActiveLow,
+            request: IntPinRequest::Configure(push_pull),
+        }
+    );
+    assert_eq!(bus.take_log(), []);
+    let open_drain_only = IntPinConfig {
+        open_drain: true,
+        ..IntPinConfig::MOTION
+    };
+    assert!(mpu.configure_int_pin(open_drain_only).is_err());
+    // accepted when compliant
+    mpu.configure_int_pin(push_pull.open_drain_active_low())
+        .unwrap();
+    assert_eq!(
+        reg(&bus, INT_PIN_CFG_ADDR),
+        open_drain_active_low | (1 << INT_PIN_CFG::LATCH_INT_EN)
+    );
+
+    // a reset puts the pin back on the line's terms
+    mpu.reset_device(&mut NoDelay).unwrap();
+    assert_eq!(
+        reg(&bus, INT_PIN_CFG_ADDR) & open_drain_active_low,
+        open_drain_active_low
+    );
+}
+
+#[test]
+fn an_int_pin_write_that_does_not_take_fails_init() {
+    let bus = SharedBus::new(&[DEFAULT_SLAVE_ADDR]);
+    bus.device(DEFAULT_SLAVE_ADDR, |m| m.read_only.push(INT_PIN_CFG_ADDR));
+    let mut mpu = Mpu6050Builder::new()
+        .i2c(bus.clone())
+        .board_constraints(BoardConstraints {
+            int_pin: IntPinConstraint::RequireOpenDrainActiveLow,
+            addr: AddrConstraint::Fixed(DeviceAddr::Ad0Low),
+        })
+        .build()
+        .unwrap();
+    let error = mpu.init(&mut NoDelay).unwrap_err();
+    assert!(matches!(
+        error,
+        Mpu6050Error::BoardConstraint(ConstraintViolation::IntPinNotApplied {
+            constraint: IntPinConstraint::RequireOpenDrainActiveLow,
+            int_pin_cfg: 0,
+        })
+    ));
+    assert_eq!(
+        format!("{}", error),
+        "board constraint: INT pin RequireOpenDrainActiveLow not applied, INT_PIN_CFG reads 0x00"
+    );
+    // still asleep, init stopped before the wake
+    assert_eq!(reg(&bus, PWR_MGMT_1::ADDR), 0x40);
+}
+
+#[test]
+fn an_unconnected_pin_is_left_alone() {
+    let (mut mpu, bus) = constrained(IntPinConstraint::NotConnected);
+
+    // adapted: motion is polled in INT_STATUS
+    mpu.setup_motion_detection(MotionDetectionConfig::DEFAULT)
+        .unwrap();
+    assert!(!wrote(&bus.take_log(), INT_PIN_CFG_ADDR));
+    assert_eq!(reg(&bus, INT_PIN_CFG_ADDR), 0);
+    assert_ne!(reg(&bus, INT_ENABLE::ADDR) & (1 << INT_ENABLE::MOT_EN), 0);
+
+    // rejected before touching the bus
+    assert_eq!(
+        violation(mpu.enable_data_ready_strobe()),
+        ConstraintViolation::IntPin {
+            constraint: IntPinConstraint::NotConnected,
+            request: IntPinRequest::DataReadyStrobe,
+        }
+    );
+    let config = IntPinConfig::DATA_READY_STROBE.open_drain_active_low();
+    assert_eq!(
+        violation(mpu.configure_int_pin(config)),
+        ConstraintViolation::IntPin {
+            constraint: IntPinConstraint::NotConnected,
+            request: IntPinRequest::Configure(config),
+        }
+    );
+    assert_eq!(bus.take_log(), []);
+    assert_eq!(
+        reg(&bus, INT_ENABLE::ADDR) & (1 << INT_ENABLE::DATA_RDY_EN),
+        0
+    );
+}
+
+#[test]
+fn probing_finds_the_strapped_address() {
+    let probe = |present: &[u8]| {
+        let bus = SharedBus::new(&[0x68, 0x69]);
+        let mut mpu = Mpu6050Builder::new()
+            .i2c(ProbeBus {
+                bus: bus.clone(),
+                present: present.to_vec(),
+            })
+            .board_constraints(BoardConstraints {
+                int_pin: IntPinConstraint::Unconstrained,
+                addr: AddrConstraint::Probe,
+            })
+            .build()
+            .unwrap();
+        let result = mpu.init(&mut NoDelay);
+        let errors = mpu.connection_monitor().consecutive_errors();
+        (result.map(|_| mpu.debug_state().slave_addr), bus, errors)
+    };
+
+    let (found, bus, errors) = probe(&[0x69]);
+    assert_eq!(found.unwrap(), 0x69);
+    // the NACK at 0x68 does not count toward a disconnect
+    assert_eq!(errors, 0);
+    // the chip at 0x69 was initialized, awake on the gyro clock
+    assert_eq!(
+        bus.device(0x69, |m| m.regs[PWR_MGMT_1::ADDR as usize]),
+        0x01
+    );
+    let (found, _, _) = probe(&[0x68]);
+    assert_eq!(found.unwrap(), 0x68);
+
+    for (present, answered) in [(&[][..], [false, false]), (&[0x68, 0x69][..], [true, true])] {
+        let (found, _, _) = probe(present);
+        assert!(matches!(
+            found,
+            Err(Mpu6050Error::BoardConstraint(ConstraintViolation::ProbeFailed { answered: a }))
+                if a == answered
+        ));
+    }
+
+    // another device answering at 0x68 is not the chip
+    let bus = SharedBus::new(&[0x68, 0x69]);
+    bus.device(0x68, |m| m.regs[WHOAMI as usize] = 0x00);
+    let mut mpu = Mpu6050Builder::new().i2c(bus.clone()).build().unwrap();
+    assert_eq!(mpu.probe_address().unwrap(), 0x69);
+}
+
+#[test]
+fn a_fixed_address_skips_probing() {
+    let bus = SharedBus::new(&[0x68, 0x69]);
+    let fixed = BoardConstraints {
+        int_pin: IntPinConstraint::Unconstrained,
+        addr: AddrConstraint::Fixed(DeviceAddr::Ad0High),
+    };
+    let mut mpu = Mpu6050Builder::new()
+        .i2c(bus.clone())
+        .board_constraints(fixed)
+        .build()
+        .unwrap();
+    mpu.init(&mut NoDelay).unwrap();
+    assert!(bus.take_log().iter().all(|access| access.address == 0x69));
+    assert_eq!(mpu.board_constraints(), Some(fixed));
+    assert_eq!(mpu.debug_state().board, Some(fixed));
+
+    // the same address given twice is fine, another one is a conflict
+    let build = |slave_addr| {
+        Mpu6050Builder::new()
+            .i2c(bus.clone())
+            .slave_addr(slave_addr)
+            .board_constraints(fixed)
+            .build()
+    };
+    assert!(build(0x69).is_ok());
+    match build(0x68) {
+        Err(Mpu6050BuilderError::BoardConstraint(violation)) => assert_eq!(
+            violation,
+            ConstraintViolation::AddrConflict {
+                constraint: AddrConstraint::Fixed(DeviceAddr::Ad0High),
+                slave_addr: 0x68,
+            }
+        ),
+        _ => panic!("conflict not refused"),
+    }
+}
@@ -0,0 +1,175 @@
+//! Bus budget against hand-computed wire costs at 100 and 400 kHz, and the sample rate check of
+//! the driver, see the `bus` module.
+
+mod common;
+
+use mpu6050::bus::{
+    transaction_cost_bits, transaction_cost_bytes, BusBudget, DriverOp, ReadPlan,
+    DEFAULT_BUS_OVERHEAD,
+};
+use mpu6050::device::DEFAULT_SLAVE_ADDR;
+use mpu6050::{Mpu6050Builder, Mpu6050Error};
+
+use common::SharedBus;
+
+fn close(actual: f32, expected: f32) -> bool {
+    (actual - expected).abs() < 1e-2
+}
+
+#[test]
+fn bytes_per_operation() {
+    // writes: address, register, payload; reads: address, register, address, payload
+    assert_eq!(transaction_cost_bytes(DriverOp::SampleBurst), 17);
+    assert_eq!(transaction_cost_bytes(DriverOp::AccelRead), 9);
+    assert_eq!(transaction_cost_bytes(DriverOp::GyroRead), 9);
+    assert_eq!(transaction_cost_bytes(DriverOp::TempRead), 5);
+    assert_eq!(transaction_cost_bytes(DriverOp::FifoDrain(0)), 3);
+    assert_eq!(transaction_cost_bytes(DriverOp::FifoDrain(1024)), 1027);
+    assert_eq!(transaction_cost_bytes(DriverOp::ConfigWrite), 3);
+    // read of the register, then the write
+    assert_eq!(transaction_cost_bytes(DriverOp::ConfigUpdate), 4 + 3);
+    assert_eq!(transaction_cost_bytes(DriverOp::InterruptStatusRead), 4);
+    assert_eq!(transaction_cost_bytes(DriverOp::RegisterRead(3)), 6);
+}
+
+#[test]
+fn bits_per_operation() {
+    // write: START + 9 * (2 + n) + STOP; read: START + 18 + repeated START + 9 * (1 + n) + STOP
+    assert_eq!(transaction_cost_bits(DriverOp::ConfigWrite), 1 + 27 + 1);
+    assert_eq!(
+        transaction_cost_bits(DriverOp::InterruptStatusRead),
+        1 + 18 + 1 + 18 + 1
+    );
+    assert_eq!(transaction_cost_bits(DriverOp::ConfigUpdate), 39 + 29);
+    assert_eq!(
+        transaction_cost_bits(DriverOp::SampleBurst),
+        1 + 18 + 1 + 135 + 1
+    );
+    assert_eq!(transaction_cost_bits(DriverOp::AccelRead), 84);
+    assert_eq!(transaction_cost_bits(DriverOp::TempRead), 48);
+    assert_eq!(
+        transaction_cost_bits(DriverOp::FifoDrain(1024)),
+        30 + 9 * 1024
+    );
+
+    assert_eq!(ReadPlan::SEPARATE.bits_per_sample(), 84 + 84 + 48);
+    assert_eq!(ReadPlan::BURST.bits_per_sample(), 156);
+    assert_eq!(ReadPlan::BURST_WITH_STATUS.bits_per_sample(), 39 + 156);
+}
+
+#[test]
+fn max_sample_rate_at_100_khz() {
+    let raw = BusBudget::new(100_000, 0.);
+    assert_eq!(raw.available_bits_per_second(), 100_000.);
+    assert!(close(raw.max_sample_rate(&ReadPlan::BURST), 641.03));
+    assert!(close(raw.max_sample_rate(&ReadPlan::SEPARATE), 462.96));
+
+    let budget = BusBudget::new(100_000, DEFAULT_BUS_OVERHEAD);
+    assert_eq!(budget.available_bits_per_second(), 80_000.);
+    // 80_000 / 156, 80_000 / 216 and 80_000 / 195
+    assert!(close(budget.max_sample_rate(&ReadPlan::BURST), 512.82));
+    assert!(close(budget.max_sample_rate(&ReadPlan::SEPARATE), 370.37));
+    assert!(close(
+        budget.max_sample_rate(&ReadPlan::BURST_WITH_STATUS),
+        410.26
+    ));
+    // FIFO of 1 kHz samples drained 100 times a second: 10 frames of 12 bytes per drain
+    let drain = ReadPlan {
+        per_sample: &[DriverOp::InterruptStatusRead, DriverOp::FifoDrain(120)],
+    };
+    assert!(close(
+        budget.max_sample_rate(&drain),
+        80_000. / (39. + 1110.)
+    ));
+}
+
+#[test]
+fn max_sample_rate_at_400_khz() {
+    let budget = BusBudget::new(400_000, DEFAULT_BUS_OVERHEAD);
+    assert_eq!(budget.available_bits_per_second(), 320_000.);
+    assert!(close(budget.max_sample_rate(&ReadPlan::BURST), 2051.28));
+    assert!(close(budget.max_sample_rate(&ReadPlan::SEPARATE), 1481.48));
+    assert!(close(
+        budget.max_sample_rate(&ReadPlan::BURST_WITH_STATUS),
+        1641.03
+    ));
+    // eight devices sharing the bus
+    let shared = BusBudget::new(400_000, 1. - 0.8 / 8.);
+    assert!(close(shared.max_sample_rate(&ReadPlan::BURST), 256.41));
+}
+
+#[test]
+fn overhead_is_clamped_and_empty_plans_are_free() {
+    assert_eq!(
+        BusBudget::new(100_000, -1.).available_bits_per_second(),
+        100_000.
+    );
+    assert_eq!(BusBudget::new(100_000, 2.).available_bits_per_second(), 0.);
+    assert_eq!(
+        BusBudget::new(100_000, 2.).max_sample_rate(&ReadPlan::BURST),
+        0.
+    );
+    let empty = ReadPlan { per_sample: &[] };
+    assert_eq!(
+        BusBudget::new(100_000, 0.).max_sample_rate(&empty),
+        f32::INFINITY
+    );
+}
+
+#[test]
+fn validation_checks_the_configured_rate() {
+    let bus = SharedBus::new(&[DEFAULT_SLAVE_ADDR]);
+    let mut mpu = Mpu6050Builder::new().i2c(bus).build().unwrap();
+    // reset state: DLPF off, 8 kHz
+    let error = mpu.validate_rate_against_bus(400_000).unwrap_err();
+    assert_eq!(error.required_hz, 8000.);
+    assert!(close(error.available_hz, 1481.48));
+
+    mpu.set_dlpf(3).unwrap();
+    assert!(mpu.validate_rate_against_bus(400_000).is_ok());
+    let error = mpu.validate_rate_against_bus(100_000).unwrap_err();
+    assert_eq!(error.required_hz, 1000.);
+    assert!(error
+        .to_string()
+        .starts_with("sample rate 1000 Hz exceeds bus capacity of 370."));
+    mpu.set_sample_rate_divider(2).unwrap();
+    assert!(mpu.validate_rate_against_bus(100_000).is_ok());
+}
+
+#[test]
+fn divider_changes_are_checked_when_opted_in() {
+    let bus = SharedBus::new(&[DEFAULT_SLAVE_ADDR]);
+    let mut mpu = Mpu6050Builder::new()
+        .i2c(bus.clone())
+        .bus_hz(100_000)
+        .build()
+        .unwrap();
+    mpu.set_dlpf(3).unwrap();
+    bus.take_log();
+
+    // 500 Hz against 370 Hz available, refused before the write
+    match mpu.set_sample_rate_divider(1) {
+        Err(Mpu6050Error::BusRateTooHigh(error)) => {
+            assert_eq!(error.required_hz, 500.);
+            assert!(close(error.available_hz, 370.37));
+        }
+        other => panic!("{:?}", other),
+    }
+    assert!(matches!(
+        mpu.set_sample_rate_hz(500),
+        Err(Mpu6050Error::BusRateTooHigh(_))
+    ));
+    assert!(bus.take_log().is_empty());
+    assert_eq!(mpu.debug_state().sample_rate_div, 0);
+    assert_eq!(mpu.debug_state().bus_hz, Some(100_000));
+
+    mpu.set_sample_rate_divider(2).unwrap();
+    assert_eq!(mpu.set_sample_rate_hz(250).unwrap(), 250.);
+    assert_eq!(mpu.debug_state().sample_rate_div, 3);
+
+    // without the opt-in any divider is written
+    let bus = SharedBus::new(&[DEFAULT_SLAVE_ADDR]);
+    let mut mpu = Mpu6050Builder::new().i2c(bus).build().unwrap();
+    mpu.set_sample_rate_divider(0).unwrap();
+    assert!(mpu.validate_rate_against_bus(100_000).is_err());
+}
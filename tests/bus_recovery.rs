@@ -0,0 +1,279 @@
+//! Bus lockup detection and recovery: the signature scripted through `FlakyI2c`, the callback
+//! invocations and rate limit, and the resynchronization against a golden trace, see the
+//! `recovery` module.
+
+mod common;
+
+use std::cell::{Cell, RefCell};
+
+use mpu6050::chaos::{ChaosConfig, ChaosHandle, FaultKind, FlakyI2c};
+use mpu6050::connection::ConnectionState;
+use mpu6050::device::*;
+use mpu6050::metrics::{MemorySink, MetricEvent, BUS_RECOVERIES, BUS_RECOVERIES_RATE_LIMITED};
+use mpu6050::recovery::*;
+use mpu6050::settling::SettlingPolicy;
+use mpu6050::trace::{check_golden, TraceHandle, TracingI2c};
+use mpu6050::*;
+
+use common::{NoDelay, SharedBus};
+
+const ADDR: u8 = DEFAULT_SLAVE_ADDR;
+
+thread_local! {
+    static CHAOS: RefCell<Option<ChaosHandle>> = const { RefCell::new(None) };
+    static CALLS: Cell<u32> = const { Cell::new(0) };
+    static OUTCOME: Cell<BusRecoveryOutcome> = const { Cell::new(BusRecoveryOutcome::Recovered) };
+}
+
+/// the fault `FlakyI2c` injected into the failed transaction
+fn classify() -> BusErrorKind {
+    let fault = CHAOS.with(|chaos| chaos.borrow().as_ref().and_then(ChaosHandle::last_fault));
+    match fault {
+        Some(FaultKind::Nak) => BusErrorKind::Nak,
+        _ => BusErrorKind::Bus,
+    }
+}
+
+fn bus_reset() -> BusRecoveryOutcome {
+    CALLS.with(|calls| calls.set(calls.get() + 1));
+    OUTCOME.with(Cell::get)
+}
+
+fn calls() -> u32 {
+    CALLS.with(Cell::get)
+}
+
+type Mpu = Mpu6050<TracingI2c<FlakyI2c<SharedBus>>>;
+
+struct Rig {
+    mpu: Mpu,
+    bus: SharedBus,
+    chaos: ChaosHandle,
+    trace: TraceHandle,
+    sink: &'static MemorySink,
+}
+
+/// initialized driver with bus errors of `fault` kind on demand, the recovery registered with
+/// `detection`, the trace cleared. Settling samples are not read again, so every read is one
+/// transaction
+fn rig(fault: FaultKind, detection: LockupDetection) -> Rig {
+    let bus = SharedBus::new(&[ADDR]);
+    let (flaky, chaos) = FlakyI2c::new(bus.clone(), ChaosConfig::NONE.with_fault(fault));
+    let (i2c, trace) = TracingI2c::new(flaky);
+    let mut mpu = Mpu6050Builder::new().i2c(i2c).build().unwrap();
+    mpu.init(&mut NoDelay).unwrap();
+    mpu.set_settling_policy(SettlingPolicy::Ignore);
+    CHAOS.with(|handle| *handle.borrow_mut() = Some(chaos.clone()));
+    mpu.set_bus_recovery(Some(
+        BusRecovery::new(bus_reset)
+            .with_classifier(classify)
+            .with_detection(detection),
+    ));
+    let sink: &'static MemorySink = Box::leak(Box::new(MemorySink::new()));
+    mpu.set_metrics_sink(Some(sink));
+    trace.clear();
+    Rig {
+        mpu,
+        bus,
+        chaos,
+        trace,
+        sink,
+    }
+}
+
+impl Rig {
+    /// the recovery events emitted
+    fn recoveries(&self) -> Vec<MetricEvent> {
+        let events = self.sink.events().into_iter();
+        events
+            .filter(|event| matches!(event, MetricEvent::BusRecovery { .. }))
+            .collect()
+    }
+
+    /// `n` accelerometer reads, one transaction each, the next `failing` of them fail
+    fn reads(&mut self, n: u32, failing: u32) {
+        self.chaos.fail_next(failing);
+        for k in 0..n {
+            assert_eq!(self.mpu.get_acc().is_err(), k < failing, "read {}", k);
+        }
+    }
+}
+
+#[test]
+fn detector_matches_the_signature_within_the_window() {
+    // 3 bus errors among the last 4 transactions
+    let mut detector = LockupDetector::new(LockupDetection::new(3, 4).with_min_interval(0));
+    let bus = Some(BusErrorKind::Bus);
+    let nak = Some(BusErrorKind::Nak);
+    let verdicts: Vec<_> = [bus, None, bus, None, bus, nak, bus, bus]
+        .into_iter()
+        .map(|failure| detector.record(failure))
+        .collect();
+    let lockup_at: Vec<_> = (0..verdicts.len())
+        .filter(|k| verdicts[*k] == LockupVerdict::Lockup)
+        .collect();
+    assert_eq!(lockup_at, [7]);
+    // the history starts over, a success within the window does not break the signature
+    assert_eq!(detector.record(bus), LockupVerdict::Clear);
+    assert_eq!(detector.record(None), LockupVerdict::Clear);
+    assert_eq!(detector.record(bus), LockupVerdict::Clear);
+    assert_eq!(detector.record(bus), LockupVerdict::Lockup);
+
+    // clamped and conservative
+    assert_eq!(LockupDetection::new(0, 0), LockupDetection::new(1, 1));
+    assert_eq!(LockupDetection::new(40, 50).window(), MAX_WINDOW);
+    assert_eq!(LockupDetection::new(40, 50).failures(), MAX_WINDOW);
+    assert_eq!(LockupDetection::default().failures(), 8);
+    assert_eq!(LockupDetection::default().window(), 8);
+    assert_eq!(LockupDetection::default().min_interval(), 1000);
+}
+
+#[test]
+fn callback_fires_exactly_at_the_threshold_and_naks_never_count() {
+    let mut rig = rig_with_fresh_calls(FaultKind::BusError, LockupDetection::new(4, 4));
+    rig.reads(4, 3);
+    assert_eq!(calls(), 0);
+    rig.reads(4, 4);
+    assert_eq!(calls(), 1);
+    assert_eq!(rig.sink.counter_value(BUS_RECOVERIES), 1);
+    let stats = rig.mpu.bus_recovery_stats().unwrap();
+    assert_eq!(stats.invocations, 1);
+    assert_eq!(stats.resumed, 1);
+    assert_eq!(rig.mpu.connection_state(), ConnectionState::Connected);
+    assert_eq!(rig.mpu.debug_state().bus_recovery, Some(stats));
+
+    // a NAKing sensor looks unplugged, not locked up
+    let mut rig = rig_with_fresh_calls(FaultKind::Nak, LockupDetection::new(4, 4));
+    rig.reads(20, 16);
+    assert_eq!(calls(), 0);
+    assert_eq!(
+        rig.mpu.bus_recovery_stats(),
+        Some(BusRecoveryStats::default())
+    );
+
+    // nothing runs without a registration
+    rig.mpu.set_bus_recovery(None);
+    rig.chaos
+        .set_config(ChaosConfig::NONE.with_fault(FaultKind::BusError));
+    rig.reads(20, 16);
+    assert_eq!(calls(), 0);
+    assert_eq!(rig.mpu.bus_recovery_stats(), None);
+}
+
+fn rig_with_fresh_calls(fault: FaultKind, detection: LockupDetection) -> Rig {
+    CALLS.with(|calls| calls.set(0));
+    OUTCOME.with(|outcome| outcome.set(BusRecoveryOutcome::Recovered));
+    rig(fault, detection)
+}
+
+#[test]
+fn invocations_are_rate_limited() {
+    let mut rig = rig_with_fresh_calls(
+        FaultKind::BusError,
+        LockupDetection::new(2, 2).with_min_interval(20),
+    );
+    rig.reads(2, 2);
+    assert_eq!(calls(), 1);
+    // 2 + 2 transactions since the invocation
+    rig.reads(2, 2);
+    assert_eq!(calls(), 1);
+    let stats = rig.mpu.bus_recovery_stats().unwrap();
+    assert_eq!(stats.rate_limited, 1);
+    assert_eq!(rig.sink.counter_value(BUS_RECOVERIES_RATE_LIMITED), 1);
+
+    // the resync transactions are not part of the count
+    rig.reads(14, 0);
+    rig.reads(2, 2);
+    assert_eq!(calls(), 1);
+    rig.reads(2, 2);
+    assert_eq!(calls(), 2);
+    assert_eq!(rig.mpu.bus_recovery_stats().unwrap().rate_limited, 2);
+}
+
+#[test]
+fn unrecovered_bus_is_left_to_reconnect() {
+    let mut rig = rig_with_fresh_calls(
+        FaultKind::BusError,
+        LockupDetection::new(3, 3).with_min_interval(0),
+    );
+    OUTCOME.with(|outcome| outcome.set(BusRecoveryOutcome::Failed));
+    rig.reads(3, 3);
+    assert_eq!(calls(), 1);
+    // no resynchronization, the driver counts the failures as before
+    assert!(rig.trace.render().lines().all(|line| line.ends_with("ERR")));
+    assert_eq!(
+        rig.recoveries(),
+        [MetricEvent::BusRecovery {
+            outcome: BusRecoveryOutcome::Failed,
+            resync: None,
+        }]
+    );
+
+    // recovered, but the chip came back from a power loss
+    OUTCOME.with(|outcome| outcome.set(BusRecoveryOutcome::Recovered));
+    rig.bus.device(ADDR, |m| {
+        m.regs[PWR_MGMT_1::ADDR as usize] = PWR_MGMT_1_RESET
+    });
+    rig.reads(3, 3);
+    assert_eq!(
+        rig.recoveries()[1],
+        MetricEvent::BusRecovery {
+            outcome: BusRecoveryOutcome::Recovered,
+            resync: Some(ResyncOutcome::NeedsReconnect),
+        }
+    );
+    assert!(matches!(
+        rig.mpu.connection_state(),
+        ConnectionState::Disconnected { .. }
+    ));
+    assert_eq!(rig.mpu.bus_recovery_stats().unwrap().unrecovered, 2);
+    rig.mpu.try_reconnect(&mut NoDelay).unwrap();
+    assert_eq!(rig.mpu.connection_state(), ConnectionState::Connected);
+}
+
+#[test]
+fn resynchronization_restores_lost_configuration() {
+    let mut rig = rig_with_fresh_calls(
+        FaultKind::BusError,
+        LockupDetection::new(4, 4).with_min_interval(0),
+    );
+    rig.mpu.set_accel_range(AccelRange::G8).unwrap();
+    rig.mpu.set_dlpf(3).unwrap();
+    rig.mpu.set_sample_rate_divider(4).unwrap();
+    // the glitch that locked the bus also cleared the accelerometer range and the divider
+    rig.bus.device(ADDR, |m| {
+        m.regs[ACCEL_CONFIG::ADDR as usize] = 0;
+        m.regs[SMPLRT_DIV as usize] = 0;
+    });
+    rig.trace.clear();
+
+    rig.trace.mark("lockup");
+    rig.reads(4, 4);
+    rig.trace.mark("resumed");
+    rig.mpu.get_acc().unwrap();
+
+    let path = format!(
+        "{}/tests/golden/bus_recovery.trace",
+        env!("CARGO_MANIFEST_DIR")
+    );
+    if let Err(mismatch) = check_golden(path, &rig.trace.render()) {
+        panic!("{}", mismatch);
+    }
+    assert_eq!(
+        rig.recoveries(),
+        [MetricEvent::BusRecovery {
+            outcome: BusRecoveryOutcome::Recovered,
+            resync: Some(ResyncOutcome::Resumed { restored: 2 }),
+        }]
+    );
+    let stats = rig.mpu.bus_recovery_stats().unwrap();
+    assert_eq!((stats.resumed, stats.restored), (1, 2));
+    rig.bus.device(ADDR, |m| {
+        assert_eq!(
+            m.regs[ACCEL_CONFIG::ADDR as usize],
+            (AccelRange::G8 as u8) << 3
+        );
+        assert_eq!(m.regs[SMPLRT_DIV as usize], 4);
+    });
+    assert_eq!(rig.mpu.debug_state().accel_range, AccelRange::G8);
+}
@@ -0,0 +1,140 @@
+//! Calibration at rest: the offsets of a still device at every accel range, the gravity on
+//! Z, the rejection of a moving device and the offsets fed back through the builder, see
+//! the `calibration` module.
+
+mod common;
+
+use embedded_hal::blocking::delay::DelayMs;
+use mpu6050::device::{AccelRange, GyroRange, ACCEL_SENS, DEFAULT_SLAVE_ADDR};
+use mpu6050::log_header::CalibrationMethod;
+use mpu6050::settling::SettlingPolicy;
+use mpu6050::setup::AutoSetupFailure;
+use mpu6050::validity::CalibrationKind;
+use mpu6050::*;
+
+use common::{NoDelay, SharedBus};
+
+const GYRO_BIAS: Vec3A = Vec3A::new(0.02, -0.01, 0.015);
+const ACC_BIAS: Vec3A = Vec3A::new(0.03, -0.02, 0.05);
+
+fn show(bus: &SharedBus, acc: Vec3A, gyro: Vec3A, accel_range: AccelRange) {
+    let frame = synthetic::frame_bytes(
+        acc + ACC_BIAS,
+        (gyro + GYRO_BIAS) / PI_180,
+        accel_range,
+        GyroRange::D250,
+    );
+    bus.device(DEFAULT_SLAVE_ADDR, |mock| mock.set_frame(&frame));
+}
+
+fn driver(accel_range: AccelRange) -> (SharedBus, Mpu6050<SharedBus>) {
+    let bus = SharedBus::new(&[DEFAULT_SLAVE_ADDR]);
+    let mut mpu = Mpu6050Builder::new().i2c(bus.clone()).build().unwrap();
+    mpu.init(&mut NoDelay).unwrap();
+    mpu.set_settling_policy(SettlingPolicy::Ignore);
+    mpu.set_accel_range(accel_range).unwrap();
+    show(&bus, Vec3A::Z, Vec3A::ZERO, accel_range);
+    (bus, mpu)
+}
+
+/// a hand shaking the device, the next frame shown while waiting for it
+struct Shaking {
+    bus: SharedBus,
+    sample: u32,
+}
+
+impl DelayMs<u8> for Shaking {
+    fn delay_ms(&mut self, _ms: u8) {
+        self.sample += 1;
+        let swing = 0.2 * (self.sample as f32 * 0.7).sin();
+        let acc = Vec3A::new(swing, 0., 1.);
+        let gyro = Vec3A::new(0., 0., 10. * swing);
+        show(&self.bus, acc, gyro, AccelRange::G2);
+    }
+}
+
+fn assert_close(actual: Vec3A, expected: Vec3A, tolerance: f32) {
+    assert!(
+        (actual - expected).abs().max_element() < tolerance,
+        "{actual:?} != {expected:?}"
+    );
+}
+
+#[test]
+fn still_device_gives_the_biases_at_every_range() {
+    for accel_range in AccelRange::ALL {
+        let (_, mut mpu) = driver(accel_range);
+        let gyro = mpu.calibrate_gyro(&mut NoDelay, 100).unwrap();
+        let acc = mpu.calibrate_accel(&mut NoDelay, 100).unwrap();
+        let (a, b, c, d) = ACCEL_SENS;
+        let lsb = 1. / [a, b, c, d][accel_range as usize];
+        assert_close(acc.offset, ACC_BIAS, lsb);
+        assert_close(gyro.offset, GYRO_BIAS, 1e-3);
+        assert_eq!((gyro.samples, acc.samples), (100, 100));
+        assert_eq!(acc.std_dev, 0.);
+
+        // applied, readable back, and the readings now the truth
+        assert_eq!(mpu.get_gyro_offset(), gyro.offset);
+        assert_eq!(mpu.get_acc_offset(), acc.offset);
+        assert_close(mpu.get_acc().unwrap(), Vec3A::Z, lsb);
+        assert_close(mpu.get_gyro().unwrap(), Vec3A::ZERO, 1e-3);
+        let meta = mpu
+            .calibration_meta(CalibrationKind::SoftwareOffsets)
+            .unwrap();
+        assert_eq!(meta.method, CalibrationMethod::Static);
+        assert_eq!(meta.acc_std_dev, Some(0.));
+    }
+}
+
+#[test]
+fn moving_device_is_rejected_and_keeps_the_offsets() {
+    let bus = SharedBus::new(&[DEFAULT_SLAVE_ADDR]);
+    let mut mpu = Mpu6050Builder::new()
+        .i2c(bus.clone())
+        .gyro_offset([0.1, 0.2, 0.3])
+        .acc_offset([0.01, 0.02, 0.03])
+        .build()
+        .unwrap();
+    mpu.init(&mut NoDelay).unwrap();
+    let mut hand = Shaking { bus, sample: 0 };
+
+    let Err(AutoSetupFailure::CalibrationRejected { std_dev, limit }) =
+        mpu.calibrate_gyro(&mut hand, 100)
+    else {
+        panic!("moving gyro calibration accepted");
+    };
+    assert!(std_dev > limit && limit == 0.01, "{} {}", std_dev, limit);
+    let Err(AutoSetupFailure::CalibrationRejected { std_dev, limit }) =
+        mpu.calibrate_accel(&mut hand, 100)
+    else {
+        panic!("moving accel calibration accepted");
+    };
+    assert!(std_dev > limit && limit == 0.02, "{} {}", std_dev, limit);
+    assert_eq!(mpu.get_gyro_offset(), Vec3A::new(0.1, 0.2, 0.3));
+    assert_eq!(mpu.get_acc_offset(), Vec3A::new(0.01, 0.02, 0.03));
+    assert_eq!(mpu.calibration_meta(CalibrationKind::SoftwareOffsets), None);
+}
+
+#[test]
+fn stored_offsets_apply_on_the_next_boot() {
+    let (bus, mut mpu) = driver(AccelRange::G2);
+    let gyro = mpu.calibrate_gyro(&mut NoDelay, 50).unwrap().offset;
+    let acc = mpu.calibrate_accel(&mut NoDelay, 50).unwrap().offset;
+    let stored = (
+        mpu.get_gyro_offset().to_array(),
+        mpu.get_acc_offset().to_array(),
+    );
+
+    let mut rebooted = Mpu6050Builder::new()
+        .i2c(bus)
+        .gyro_offset(stored.0)
+        .acc_offset(stored.1)
+        .build()
+        .unwrap();
+    assert_eq!(
+        (rebooted.get_gyro_offset(), rebooted.get_acc_offset()),
+        (gyro, acc)
+    );
+    assert_eq!(rebooted.get_acc().unwrap(), mpu.get_acc().unwrap());
+    assert_eq!(rebooted.get_gyro().unwrap(), mpu.get_gyro().unwrap());
+}
@@ -0,0 +1,188 @@
+//! Capability probing on mock chips, see the `capability` module.
+
+mod common;
+
+use mpu6050::aux_i2c::{SlaveConfig, SlaveSlot};
+use mpu6050::device::*;
+use mpu6050::*;
+
+use common::{NoDelay, SharedBus};
+
+const ADDR: u8 = 0x68;
+
+fn driver(bus: &SharedBus) -> Mpu6050<SharedBus> {
+    Mpu6050Builder::new().i2c(bus.clone()).build().unwrap()
+}
+
+fn probe(who_am_i: u8) -> ChipCapabilities {
+    let bus = SharedBus::new(&[ADDR]);
+    bus.device(ADDR, |device| device.regs[WHOAMI as usize] = who_am_i);
+    driver(&bus).probe_capabilities().unwrap()
+}
+
+#[test]
+fn variant_matrix() {
+    let caps = probe(0x68);
+    assert_eq!(caps, capabilities(ChipVariant::Mpu6050));
+    assert!(caps.fifo_available && caps.dmp_loadable && caps.aux_i2c_master);
+    assert_eq!(caps.temp_formula, TempFormula::Mpu6050);
+    assert_eq!(caps.accel_offset_scaling, AccelOffsetScaling::Mpu6050);
+    assert_eq!(caps.max_gyro_odr, 8000);
+
+    for who_am_i in [0x70, 0x71, 0x73] {
+        let caps = probe(who_am_i);
+        assert!(caps.fifo_available && caps.dmp_loadable && caps.aux_i2c_master);
+        assert_eq!(caps.temp_formula, TempFormula::Mpu6500);
+        assert_eq!(caps.accel_offset_scaling, AccelOffsetScaling::Mpu6500);
+        assert_eq!(caps.max_gyro_odr, 32000);
+    }
+    assert_eq!(probe(0x70).variant, ChipVariant::Mpu6500);
+    assert_eq!(probe(0x73).variant, ChipVariant::Mpu9250);
+
+    let caps = probe(0x98);
+    assert_eq!(caps.variant, ChipVariant::Unknown(0x98));
+    assert!(!caps.fifo_available && !caps.dmp_loadable && !caps.aux_i2c_master);
+    assert_eq!(caps.accel_offset_scaling, AccelOffsetScaling::Unknown);
+}
+
+#[test]
+fn chip_id_checked_on_the_device_id() {
+    let init = |builder: Mpu6050Builder<SharedBus>, addr: u8, who_am_i: u8| {
+        let bus = SharedBus::new(&[addr]);
+        bus.device(addr, |device| device.regs[WHOAMI as usize] = who_am_i);
+        let mut mpu = builder.i2c(bus).slave_addr(addr).build().unwrap();
+        mpu.init(&mut NoDelay).map(|_| mpu.debug_state().chip_id)
+    };
+
+    // AD0 high: the device ID is the same, a clone following AD0 only sets the reserved bit 0
+    for (addr, who_am_i) in [(0x68, 0x68), (0x69, 0x68), (0x69, 0x69)] {
+        assert_eq!(
+            init(Mpu6050Builder::new(), addr, who_am_i).unwrap(),
+            Some(who_am_i)
+        );
+    }
+    // the raw byte reported
+    for who_am_i in [0x70, 0x00, 0x6a] {
+        let error = init(Mpu6050Builder::new(), 0x69, who_am_i).unwrap_err();
+        assert!(matches!(error, Mpu6050Error::InvalidChipId(id) if id == who_am_i));
+    }
+    assert_eq!(
+        init(Mpu6050Builder::new(), 0x68, 0x70)
+            .unwrap_err()
+            .to_string(),
+        "invalid chip id: 112"
+    );
+
+    // the later parts by list, on all 8 bits
+    let later = || Mpu6050Builder::new().expect_chip_ids(&[0x68, 0x70, 0x71]);
+    for who_am_i in [0x68, 0x70, 0x71] {
+        assert_eq!(init(later(), 0x68, who_am_i).unwrap(), Some(who_am_i));
+    }
+    for who_am_i in [0x69, 0x72] {
+        assert!(matches!(
+            init(later(), 0x68, who_am_i),
+            Err(Mpu6050Error::InvalidChipId(id)) if id == who_am_i
+        ));
+    }
+
+    // or not at all
+    let unchecked = || Mpu6050Builder::new().skip_chip_id_check();
+    assert_eq!(init(unchecked(), 0x68, 0x00).unwrap(), Some(0x00));
+    assert_eq!(
+        ChipIdCheck::default(),
+        ChipIdCheck::Mpu6050,
+        "verified by default"
+    );
+}
+
+#[test]
+fn a_refused_chip_id_writes_nothing() {
+    let checks = [
+        (Mpu6050Builder::new(), 0x70),
+        (Mpu6050Builder::new().expect_chip_ids(&[0x68, 0x70]), 0x71),
+    ];
+    for (builder, who_am_i) in checks {
+        let bus = SharedBus::new(&[ADDR]);
+        bus.device(ADDR, |device| device.regs[WHOAMI as usize] = who_am_i);
+        let before = bus.device(ADDR, |device| device.regs);
+        let mut mpu = builder.i2c(bus.clone()).build().unwrap();
+        assert!(matches!(
+            mpu.init(&mut NoDelay),
+            Err(Mpu6050Error::InvalidChipId(id)) if id == who_am_i
+        ));
+        let log = bus.take_log();
+        assert!(log.iter().any(|a| a.reg == WHOAMI));
+        assert!(log.iter().all(|a| a.read), "{:?}", log);
+        assert_eq!(bus.device(ADDR, |device| device.regs), before);
+    }
+}
+
+#[test]
+fn probe_restores_registers() {
+    for previous in [0x00, 0x78, 0x80] {
+        let bus = SharedBus::new(&[ADDR]);
+        bus.device(ADDR, |device| device.regs[FIFO_EN as usize] = previous);
+        let before = bus.device(ADDR, |device| device.regs);
+
+        assert!(driver(&bus).probe_capabilities().unwrap().fifo_available);
+        assert_eq!(bus.device(ADDR, |device| device.regs), before);
+        let writes = bus.take_log().into_iter().filter(|a| !a.read);
+        assert!(writes.map(|a| a.reg).all(|reg| reg == FIFO_EN));
+    }
+}
+
+#[test]
+fn broken_fifo_is_detected() {
+    let bus = SharedBus::new(&[ADDR]);
+    bus.device(ADDR, |device| device.read_only.push(FIFO_EN));
+    let mut mpu = driver(&bus);
+    mpu.init(&mut NoDelay).unwrap();
+
+    let caps = mpu.capabilities();
+    assert!(!caps.fifo_available);
+    assert!(caps.aux_i2c_master);
+}
+
+#[test]
+fn unsupported_features_are_refused() {
+    let bus = SharedBus::new(&[ADDR]);
+    bus.device(ADDR, |device| device.regs[WHOAMI as usize] = 0x98);
+    let mut mpu = driver(&bus);
+    // before probing, an MPU-6050 is assumed
+    assert!(mpu.capabilities().aux_i2c_master);
+
+    mpu.probe_capabilities().unwrap();
+    bus.take_log();
+    assert!(matches!(
+        mpu.set_i2c_master_enabled(true),
+        Err(Mpu6050Error::Unsupported(Capability::AuxI2cMaster))
+    ));
+    let slave = SlaveConfig {
+        address: 0x0c,
+        register: 0x03,
+        len: 6,
+        read: true,
+        byte_swap: false,
+    };
+    assert!(matches!(
+        mpu.configure_i2c_slave(SlaveSlot::Slv0, slave),
+        Err(Mpu6050Error::Unsupported(Capability::AuxI2cMaster))
+    ));
+    assert!(bus.take_log().is_empty());
+    // disabling is always allowed
+    assert!(mpu.set_i2c_master_enabled(false).is_ok());
+}
+
+#[test]
+fn temperature_uses_the_variant_formula() {
+    let bus = SharedBus::new(&[ADDR]);
+    bus.device(ADDR, |device| {
+        device.regs[WHOAMI as usize] = 0x70;
+        device.regs[TEMP_OUT_H as usize..TEMP_OUT_H as usize + 2]
+            .copy_from_slice(&0i16.to_be_bytes());
+    });
+    let mut mpu = driver(&bus);
+    assert!((mpu.get_temp().unwrap() - 36.53).abs() < 1e-3);
+    mpu.probe_capabilities().unwrap();
+    assert!((mpu.get_temp().unwrap() - 21.0).abs() < 1e-3);
+}
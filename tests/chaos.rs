@@ -0,0 +1,334 @@
+//! Driver behavior under injected bus faults: reconnect, settling discards, application
+//! retries and spike rejection of corrupted reads, see the `chaos` module.
+
+mod common;
+
+use std::convert::Infallible;
+
+use embedded_hal::blocking::i2c::{Write, WriteRead};
+use mpu6050::chaos::{BitFlip, ChaosConfig, ChaosHandle, FaultKind, FlakyError, FlakyI2c};
+use mpu6050::connection::{ConnectionState, ReconnectOutcome};
+use mpu6050::device::{AccelRange, DEFAULT_SLAVE_ADDR, WHOAMI};
+use mpu6050::interrupt::{InterruptSource, MotionDetectionConfig};
+use mpu6050::plausibility::{Plausibility, PlausibilityCheck, PlausibilityConfig};
+use mpu6050::register::Register;
+use mpu6050::settling::SettlingPolicy;
+use mpu6050::smoothing::{FilterSpec, Smoothing};
+use mpu6050::supervisor::{SupervisorConfig, SupervisorLimit};
+use mpu6050::*;
+
+use common::{NoDelay, RegisterMock, SharedBus};
+
+fn injected<T: core::fmt::Debug>(
+    res: Result<T, Mpu6050Error<FlakyError<Infallible>>>,
+) -> FaultKind {
+    match res {
+        Err(Mpu6050Error::I2c(FlakyError::Injected(kind))) => kind,
+        other => panic!("expected an injected fault, got {:?}", other),
+    }
+}
+
+#[test]
+fn every_nth_fault_is_reported_with_its_kind_and_never_reaches_the_bus() {
+    let config = ChaosConfig::NONE
+        .with_fail_every(2)
+        .with_fault(FaultKind::BusError);
+    let (mut i2c, chaos) = FlakyI2c::new(RegisterMock::new(), config);
+
+    assert!(i2c.write(0x68, &[0x10, 0xaa]).is_ok());
+    assert_eq!(
+        i2c.write(0x68, &[0x11, 0xbb]),
+        Err(FlakyError::Injected(FaultKind::BusError))
+    );
+    assert_eq!(i2c.inner().regs[0x10], 0xaa);
+    assert_eq!(i2c.inner().regs[0x11], 0);
+    assert_eq!(chaos.stats().transactions, 2);
+    assert_eq!(chaos.stats().faults, 1);
+}
+
+#[test]
+fn seeded_faults_are_reproducible() {
+    let pattern = |seed| {
+        let config = ChaosConfig::NONE.with_fail_probability(0.3, seed);
+        let (mut i2c, _) = FlakyI2c::new(RegisterMock::new(), config);
+        let mut buf = [0; 1];
+        (0..64)
+            .map(|_| i2c.write_read(0x68, &[WHOAMI], &mut buf).is_err())
+            .collect::<Vec<_>>()
+    };
+
+    let a = pattern(42);
+    assert_eq!(a, pattern(42));
+    assert_ne!(a, pattern(43));
+    let faults = a.iter().filter(|failed| **failed).count();
+    assert!((8..=32).contains(&faults), "{} faults", faults);
+}
+
+#[test]
+fn disconnect_short_circuits_until_reconnect() {
+    let (i2c, chaos) = FlakyI2c::new(RegisterMock::new(), ChaosConfig::NONE);
+    let mut mpu = Mpu6050Builder::new()
+        .i2c(i2c)
+        .disconnect_threshold(3)
+        .auto_disconnect(true)
+        .build()
+        .unwrap();
+    mpu.init(&mut NoDelay).unwrap();
+
+    chaos.fail_next(3);
+    for _ in 0..3 {
+        injected(mpu.get_temp());
+    }
+    assert!(matches!(
+        mpu.connection_state(),
+        ConnectionState::Disconnected { .. }
+    ));
+
+    // the bus is left alone while disconnected
+    let before = chaos.stats().transactions;
+    assert!(matches!(mpu.get_temp(), Err(Mpu6050Error::Disconnected)));
+    assert_eq!(chaos.stats().transactions, before);
+
+    assert_eq!(
+        mpu.try_reconnect(&mut NoDelay).unwrap(),
+        ReconnectOutcome::SameChip { chip_id: 0x68 }
+    );
+    assert_eq!(mpu.connection_state(), ConnectionState::Connected);
+    assert!(mpu.get_temp().is_ok());
+}
+
+#[test]
+fn a_replugged_chip_gets_its_configuration_back() {
+    let bus = SharedBus::new(&[DEFAULT_SLAVE_ADDR]);
+    let (i2c, chaos) = FlakyI2c::new(bus.clone(), ChaosConfig::NONE);
+    let mut mpu = Mpu6050Builder::new()
+        .i2c(i2c)
+        .disconnect_threshold(1)
+        .build()
+        .unwrap();
+    mpu.init(&mut NoDelay).unwrap();
+    mpu.set_accel_range(AccelRange::G8).unwrap();
+    mpu.set_dlpf(3).unwrap();
+    mpu.set_sample_rate_divider(9).unwrap();
+    let motion = MotionDetectionConfig {
+        threshold: 20,
+        duration: 5,
+    };
+    mpu.setup_motion_detection(motion).unwrap();
+    mpu.set_int_enabled(InterruptSource::DataReady, true)
+        .unwrap();
+    let configured = bus.device(DEFAULT_SLAVE_ADDR, |m| m.regs);
+
+    // unplugged, and back after a power loss: every register at its reset value
+    chaos.fail_next(1);
+    injected(mpu.get_temp());
+    bus.device(DEFAULT_SLAVE_ADDR, |m| m.regs = RegisterMock::new().regs);
+    assert_eq!(
+        mpu.try_reconnect(&mut NoDelay).unwrap(),
+        ReconnectOutcome::SameChip { chip_id: 0x68 }
+    );
+    let restored = [
+        Register::PWR_MGMT_1,
+        Register::ACCEL_CONFIG,
+        Register::GYRO_CONFIG,
+        Register::CONFIG,
+        Register::SMPLRT_DIV,
+        Register::INT_PIN_CFG,
+        Register::INT_ENABLE,
+        Register::MOT_THR,
+        Register::MOT_DUR,
+        Register::MOT_DETECT_CONTROL,
+    ];
+    bus.device(DEFAULT_SLAVE_ADDR, |m| {
+        for reg in restored {
+            let addr = reg.addr() as usize;
+            assert_eq!(m.regs[addr], configured[addr], "{:?}", reg);
+        }
+    });
+}
+
+#[test]
+fn failed_reconnect_stays_disconnected() {
+    let (i2c, chaos) = FlakyI2c::new(RegisterMock::new(), ChaosConfig::NONE);
+    let mut mpu = Mpu6050Builder::new()
+        .i2c(i2c)
+        .disconnect_threshold(1)
+        .auto_disconnect(true)
+        .build()
+        .unwrap();
+    mpu.init(&mut NoDelay).unwrap();
+
+    chaos.fail_next(2);
+    injected(mpu.get_temp());
+    injected(mpu.try_reconnect(&mut NoDelay));
+    assert!(matches!(
+        mpu.connection_state(),
+        ConnectionState::Disconnected { .. }
+    ));
+    assert!(mpu.try_reconnect(&mut NoDelay).is_ok());
+}
+
+#[test]
+fn settling_discard_survives_a_failed_read() {
+    let (i2c, chaos) = FlakyI2c::new(RegisterMock::new(), ChaosConfig::NONE);
+    let mut mpu = Mpu6050Builder::new().i2c(i2c).build().unwrap();
+    mpu.set_settling_policy(SettlingPolicy::Discard { max_retries: 3 });
+    mpu.init(&mut NoDelay).unwrap();
+    let pending = mpu.settle_countdown().acc;
+    assert!(pending > 0);
+
+    // a failed read consumes nothing from the countdown
+    chaos.fail_next(1);
+    injected(mpu.get_acc());
+    assert_eq!(mpu.settle_countdown().acc, pending);
+
+    chaos.reset_stats();
+    let acc = mpu.get_acc().unwrap();
+    assert!((acc - Vec3A::Z).length() < 1e-3);
+    assert_eq!(chaos.stats().transactions, 1 + pending as u32);
+    assert_eq!(mpu.settle_countdown().acc, 0);
+}
+
+#[test]
+fn supervisor_debounce_rejects_single_spikes() {
+    // sign bit of ACCEL_XOUT_H: X reads -2g, magnitude ~2.24g
+    let spike = |every| BitFlip {
+        byte: 0,
+        bit: 7,
+        every,
+    };
+    let supervisor = |consecutive| SupervisorConfig {
+        max_gyro_rad_s: None,
+        max_acc_g: Some(2.0),
+        consecutive,
+        callback: None,
+    };
+
+    let (i2c, chaos) = FlakyI2c::new(RegisterMock::new(), ChaosConfig::NONE);
+    let mut mpu = Mpu6050Builder::new().i2c(i2c).build().unwrap();
+    mpu.set_settling_policy(SettlingPolicy::Ignore);
+    mpu.init(&mut NoDelay).unwrap();
+    while mpu.settle_countdown().is_settling() {
+        mpu.get_acc().unwrap();
+        mpu.get_gyro().unwrap();
+    }
+
+    // every other read corrupted: never two in a row
+    mpu.set_supervisor(supervisor(2));
+    chaos.set_config(ChaosConfig::NONE.with_corruption(spike(2)));
+    for _ in 0..21 {
+        mpu.get_acc().unwrap();
+    }
+    assert_eq!(chaos.stats().corruptions, 10);
+    assert_eq!(mpu.supervisor_tripped(), None);
+
+    // every read corrupted: trips on the second
+    chaos.set_config(ChaosConfig::NONE.with_corruption(spike(1)));
+    mpu.get_acc().unwrap();
+    assert_eq!(mpu.supervisor_tripped(), None);
+    assert!(mpu.get_acc().unwrap().x < -1.9);
+    let event = mpu.supervisor_tripped().unwrap();
+    assert_eq!(event.limit, SupervisorLimit::AccelMagnitude);
+}
+
+/// initialized driver without faults, settling samples not read again
+fn settled() -> (Mpu6050<FlakyI2c<RegisterMock>>, ChaosHandle) {
+    let (i2c, chaos) = FlakyI2c::new(RegisterMock::new(), ChaosConfig::NONE);
+    let mut mpu = Mpu6050Builder::new().i2c(i2c).build().unwrap();
+    mpu.set_settling_policy(SettlingPolicy::Ignore);
+    mpu.init(&mut NoDelay).unwrap();
+    (mpu, chaos)
+}
+
+/// sign bit of ACCEL_XOUT_H flipped every `every` reads: X reads -2g
+fn acc_x_spike(every: u32) -> BitFlip {
+    BitFlip {
+        byte: 0,
+        bit: 7,
+        every,
+    }
+}
+
+#[test]
+fn retrying_in_the_application_recovers_every_injected_fault() {
+    // no retry policy in the driver: a failed read is returned at once, without side effects,
+    // so retrying it is up to the application
+    fn retry<T, E>(attempts: u32, mut op: impl FnMut() -> Result<T, E>) -> Result<T, E> {
+        let mut res = op();
+        for _ in 1..attempts {
+            if res.is_ok() {
+                break;
+            }
+            res = op();
+        }
+        res
+    }
+
+    let (mut mpu, chaos) = settled();
+    chaos.set_config(
+        ChaosConfig::NONE
+            .with_fail_every(3)
+            .with_fault(FaultKind::BusError),
+    );
+    chaos.reset_stats();
+    for _ in 0..30 {
+        let acc = retry(2, || mpu.get_acc()).unwrap();
+        assert!((acc - Vec3A::Z).length() < 1e-3);
+    }
+    let stats = chaos.stats();
+    assert_eq!(stats.faults, stats.transactions / 3);
+    assert_eq!(stats.transactions - stats.faults, 30);
+    // single failures never add up to a disconnect
+    assert_eq!(mpu.connection_state(), ConnectionState::Connected);
+}
+
+#[test]
+fn a_median_view_rejects_corrupted_reads() {
+    let spec = FilterSpec {
+        acc: Smoothing::Median { window: 3 },
+        ..FilterSpec::RAW
+    };
+    let (mut mpu, chaos) = settled();
+    let mut raw = mpu.filtered_view(FilterSpec::RAW);
+    let mut median = mpu.filtered_view(spec);
+    chaos.set_config(ChaosConfig::NONE.with_corruption(acc_x_spike(4)));
+
+    let mut spikes = 0;
+    for _ in 0..20 {
+        let sample = mpu.get_all().unwrap();
+        if raw.ingest(&sample).acc().x < -1.9 {
+            spikes += 1;
+        }
+        // one corrupted read in any 3: never the median
+        assert!(median.ingest(&sample).acc().x.abs() < 1e-3);
+    }
+    assert_eq!(spikes, 5);
+    assert_eq!(chaos.stats().corruptions, 5);
+}
+
+#[test]
+fn plausibility_scores_flag_corrupted_reads() {
+    let (mut mpu, chaos) = settled();
+    mpu.set_plausibility_config(Some(PlausibilityConfig {
+        acc_magnitude_g: Some((0.8, 1.2)),
+        ..PlausibilityConfig::NONE
+    }));
+    chaos.set_config(ChaosConfig::NONE.with_corruption(acc_x_spike(2)));
+
+    let classes: Vec<_> = (0..6)
+        .map(|_| mpu.get_all().unwrap().plausibility().class())
+        .collect();
+    use Plausibility::*;
+    assert_eq!(classes, [Ok, Suspect, Ok, Suspect, Ok, Suspect]);
+
+    // the magnitude check alone rejects the sample once in the implausible mask
+    mpu.set_plausibility_config(Some(PlausibilityConfig {
+        acc_magnitude_g: Some((0.8, 1.2)),
+        implausible: PlausibilityCheck::AccMagnitude.bit(),
+        ..PlausibilityConfig::NONE
+    }));
+    let score = [mpu.get_all().unwrap(), mpu.get_all().unwrap()].map(|s| s.plausibility());
+    assert_eq!(score[0].class(), Ok);
+    assert_eq!(score[1].class(), Implausible);
+    assert!(score[1].failed(PlausibilityCheck::AccMagnitude));
+}
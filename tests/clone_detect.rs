@@ -0,0 +1,340 @@
+//! Undocumented register space on mock chips: the deny-list refusing raw reads before the bus
+//! and the opt-in, each clone heuristic, and the assessment in the capabilities, see the
+//! `clone_detect` module.
+
+mod common;
+
+use std::convert::Infallible;
+
+use embedded_hal::blocking::i2c::{Write, WriteRead};
+use mpu6050::register::Register;
+use mpu6050::{device::*, *};
+
+use common::{NoDelay, SharedBus};
+
+const ADDR: u8 = DEFAULT_SLAVE_ADDR;
+
+/// self-test response of a genuine part at ±8 g, about 0.5 g per axis
+const GENUINE_RESPONSE: [i16; 3] = [2050, 2130, 1980];
+
+/// a [`SharedBus`] whose accel bursts move by `response` while ACCEL_CONFIG has the self-test
+/// bits set
+#[derive(Clone)]
+struct SelfTestBus {
+    bus: SharedBus,
+    address: u8,
+    response: [i16; 3],
+}
+
+impl SelfTestBus {
+    /// factory trims written, answering at `address`
+    fn genuine(address: u8) -> Self {
+        let bus = SharedBus::new(&[address]);
+        bus.device(address, |mock| {
+            mock.regs[0x0d..0x11].copy_from_slice(&[0x73, 0x8c, 0x52, 0x39]);
+            mock.regs[0x06..0x0c].copy_from_slice(&[0xf5, 0x2a, 0x05, 0xd8, 0x09, 0x6e]);
+        });
+        Self {
+            bus,
+            address,
+            response: GENUINE_RESPONSE,
+        }
+    }
+
+    fn regs(&self, f: impl FnOnce(&mut [u8; 256])) {
+        self.bus.device(self.address, |mock| f(&mut mock.regs));
+    }
+
+    fn reg(&self, reg: u8) -> u8 {
+        self.bus
+            .device(self.address, |mock| mock.regs[reg as usize])
+    }
+
+    /// registers of every transaction since the last call
+    fn touched(&self) -> Vec<u8> {
+        self.bus
+            .take_log()
+            .iter()
+            .map(|access| access.reg)
+            .collect()
+    }
+}
+
+impl Write for SelfTestBus {
+    type Error = Infallible;
+
+    fn write(&mut self, address: u8, bytes: &[u8]) -> Result<(), Infallible> {
+        self.bus.write(address, bytes)
+    }
+}
+
+impl WriteRead for SelfTestBus {
+    type Error = Infallible;
+
+    fn write_read(
+        &mut self,
+        address: u8,
+        bytes: &[u8],
+        buffer: &mut [u8],
+    ) -> Result<(), Infallible> {
+        self.bus.write_read(address, bytes, buffer)?;
+        let self_test = self.reg(ACCEL_CONFIG::ADDR) & 0xe0 == 0xe0;
+        if bytes == [ACC_REGX_H] && self_test {
+            for (pair, response) in buffer.chunks_exact_mut(2).zip(self.response) {
+                let value = i16::from_be_bytes([pair[0], pair[1]]).wrapping_add(response);
+                pair.copy_from_slice(&value.to_be_bytes());
+            }
+        }
+        Ok(())
+    }
+}
+
+fn driver(bus: &SelfTestBus) -> Mpu6050<SelfTestBus> {
+    Mpu6050Builder::new()
+        .i2c(bus.clone())
+        .slave_addr(bus.address)
+        .build()
+        .unwrap()
+}
+
+/// an initialized driver on `bus`, the log cleared
+fn awake(bus: &SelfTestBus) -> Mpu6050<SelfTestBus> {
+    let mut mpu = driver(bus);
+    mpu.init(&mut NoDelay).unwrap();
+    bus.touched();
+    mpu
+}
+
+fn clone_with(signs: &[CloneSign]) -> CloneAssessment {
+    let evidence = signs
+        .iter()
+        .fold(CloneEvidence::EMPTY, |evidence, sign| evidence.with(*sign));
+    CloneAssessment::LikelyClone { evidence }
+}
+
+#[test]
+fn deny_list_refuses_raw_reads_before_the_bus() {
+    let bus = SelfTestBus::genuine(ADDR);
+    let mut mpu = driver(&bus);
+    assert!(!mpu.undocumented_allowed());
+    let denied = |mpu: &mut Mpu6050<SelfTestBus>, reg: u8, len: usize| {
+        let mut buf = vec![0; len];
+        match mpu.read_bytes(reg, &mut buf) {
+            Err(Mpu6050Error::DeniedRegisterRange(range)) => Some(range),
+            Ok(()) => None,
+            Err(other) => panic!("{:?}", other),
+        }
+    };
+    assert_eq!(denied(&mut mpu, 0x62, 1), Some(DENIED_RANGES[0]));
+    // bursts into and across a range
+    assert_eq!(denied(&mut mpu, 0x49, 26), Some(DENIED_RANGES[0]));
+    assert_eq!(denied(&mut mpu, 0x00, 0x80), Some(DENIED_RANGES[0]));
+    assert_eq!(denied(&mut mpu, 0x6c, 2), Some(DENIED_RANGES[1]));
+    assert_eq!(denied(&mut mpu, 0x70, 1), Some(DENIED_RANGES[1]));
+    assert!(matches!(
+        mpu.read_byte(0x6f),
+        Err(Mpu6050Error::DeniedRegisterRange(_))
+    ));
+    assert!(matches!(
+        mpu.read_word(0x61),
+        Err(Mpu6050Error::DeniedRegisterRange(_))
+    ));
+    let mut buf = [0; 2];
+    assert!(matches!(
+        mpu.read_registers(Register::MOT_DETECT_STATUS, &mut buf),
+        Err(Mpu6050Error::DeniedRegisterRange(_))
+    ));
+    assert_eq!(bus.touched(), [0u8; 0]);
+
+    // the neighbours, FIFO_R_W not incrementing, and every register of the map
+    assert_eq!(denied(&mut mpu, 0x49, 25), None);
+    assert_eq!(denied(&mut mpu, 0x63, 10), None);
+    assert_eq!(denied(&mut mpu, 0x72, 2), None);
+    assert_eq!(denied(&mut mpu, FIFO_R_W, 200), None);
+    assert_eq!(bus.touched().len(), 4);
+    for reg in Register::ALL {
+        assert_eq!(denied_range(reg.addr(), 1), None, "{}", reg);
+    }
+
+    let error: Mpu6050Error<Infallible> = Mpu6050Error::DeniedRegisterRange(DENIED_RANGES[0]);
+    assert_eq!(
+        error.to_string(),
+        "read of denied register range 0x62 (aux I2C master lockup on clones)"
+    );
+    assert!(DENIED_RANGES[1].to_string().starts_with("0x6d..=0x71 ("));
+}
+
+#[test]
+fn opt_in_reads_denied_space() {
+    let bus = SelfTestBus::genuine(ADDR);
+    bus.regs(|regs| regs[0x62] = 0x5a);
+    let mut mpu = Mpu6050Builder::new()
+        .i2c(bus.clone())
+        .unsafe_allow_undocumented()
+        .build()
+        .unwrap();
+    assert!(mpu.undocumented_allowed());
+    assert!(mpu.debug_state().allow_undocumented);
+    assert_eq!(mpu.read_byte(0x62).unwrap(), 0x5a);
+    let mut buf = [0; 5];
+    mpu.read_bytes(0x6d, &mut buf).unwrap();
+    assert_eq!(bus.touched(), [0x62, 0x6d]);
+}
+
+#[test]
+fn genuine_part_assesses_genuine() {
+    let bus = SelfTestBus::genuine(ADDR);
+    let mut mpu = awake(&bus);
+    mpu.set_accel_range(AccelRange::G4).unwrap();
+    let accel_config = bus.reg(ACCEL_CONFIG::ADDR);
+    assert_eq!(mpu.capabilities().clone_assessment, None);
+
+    let assessment = mpu.detect_clone_heuristics(&mut NoDelay);
+    assert_eq!(assessment, CloneAssessment::LikelyGenuine);
+    assert_eq!(mpu.capabilities().clone_assessment, Some(assessment));
+    assert!(mpu.capabilities().dmp_loadable);
+    // ACCEL_CONFIG restored, the driver state untouched
+    assert_eq!(bus.reg(ACCEL_CONFIG::ADDR), accel_config);
+    assert_eq!(mpu.get_accel_range().unwrap(), AccelRange::G4);
+
+    // documented probes only
+    let touched = bus.touched();
+    assert!(touched.contains(&WHOAMI) && touched.contains(&Register::SELF_TEST_X.addr()));
+    assert!(touched.iter().all(|reg| denied_range(*reg, 1).is_none()));
+    assert!(touched
+        .iter()
+        .all(|reg| Register::from_addr(*reg).is_some()));
+
+    // AD0 high: a genuine part still answers 0x68
+    let bus = SelfTestBus::genuine(ADDR + 1);
+    let mut mpu = awake(&bus);
+    assert_eq!(
+        mpu.detect_clone_heuristics(&mut NoDelay),
+        CloneAssessment::LikelyGenuine
+    );
+}
+
+#[test]
+fn who_am_i_heuristic() {
+    // WHO_AM_I reflecting AD0, 0x69 being no InvenSense id either
+    let bus = SelfTestBus::genuine(ADDR + 1);
+    bus.regs(|regs| regs[WHOAMI as usize] = ADDR + 1);
+    let mut mpu = driver(&bus);
+    assert_eq!(
+        mpu.detect_clone_heuristics(&mut NoDelay),
+        clone_with(&[CloneSign::ForeignWhoAmI, CloneSign::WhoAmIFollowsAd0])
+    );
+
+    // a made up id at 0x68
+    let bus = SelfTestBus::genuine(ADDR);
+    let mut mpu = awake(&bus);
+    bus.regs(|regs| regs[WHOAMI as usize] = 0x98);
+    assert_eq!(
+        mpu.detect_clone_heuristics(&mut NoDelay),
+        clone_with(&[CloneSign::ForeignWhoAmI])
+    );
+
+    // an MPU-6500 skips the MPU-6050 probes
+    let bus = SelfTestBus::genuine(ADDR);
+    let mut mpu = awake(&bus);
+    bus.regs(|regs| regs[WHOAMI as usize] = 0x70);
+    assert_eq!(
+        mpu.detect_clone_heuristics(&mut NoDelay),
+        CloneAssessment::Inconclusive
+    );
+    assert_eq!(bus.touched(), [WHOAMI]);
+}
+
+#[test]
+fn factory_trim_heuristic() {
+    // a single blank trim is a weak sign
+    let bus = SelfTestBus::genuine(ADDR);
+    let mut mpu = awake(&bus);
+    bus.regs(|regs| regs[0x06..0x0c].fill(0));
+    assert_eq!(
+        mpu.detect_clone_heuristics(&mut NoDelay),
+        CloneAssessment::Inconclusive
+    );
+
+    bus.regs(|regs| regs[0x06..0x11].fill(0xff));
+    assert_eq!(
+        mpu.detect_clone_heuristics(&mut NoDelay),
+        clone_with(&[CloneSign::BlankSelfTestTrim, CloneSign::BlankAccelTrim])
+    );
+}
+
+#[test]
+fn self_test_heuristic() {
+    for response in [[0; 3], [2050, 2130, 0], [2050, -6000, 1980]] {
+        let mut bus = SelfTestBus::genuine(ADDR);
+        bus.response = response;
+        let mut mpu = awake(&bus);
+        let accel_config = bus.reg(ACCEL_CONFIG::ADDR);
+        assert_eq!(
+            mpu.detect_clone_heuristics(&mut NoDelay),
+            clone_with(&[CloneSign::SelfTestOutOfRange]),
+            "{:?}",
+            response
+        );
+        assert_eq!(bus.reg(ACCEL_CONFIG::ADDR), accel_config);
+    }
+
+    // a response pointing down counts by its size
+    let mut bus = SelfTestBus::genuine(ADDR);
+    bus.response = GENUINE_RESPONSE.map(|lsb| -lsb);
+    let mut mpu = awake(&bus);
+    assert_eq!(
+        mpu.detect_clone_heuristics(&mut NoDelay),
+        CloneAssessment::LikelyGenuine
+    );
+
+    // asleep, the self-test is skipped
+    let bus = SelfTestBus::genuine(ADDR);
+    let mut mpu = driver(&bus);
+    assert_eq!(
+        mpu.detect_clone_heuristics(&mut NoDelay),
+        CloneAssessment::Inconclusive
+    );
+    assert!(!bus.touched().contains(&ACC_REGX_H));
+}
+
+#[test]
+fn failed_probes_are_inconclusive() {
+    let bus = SelfTestBus::genuine(ADDR);
+    let mut mpu = Mpu6050Builder::new()
+        .i2c(bus.clone())
+        .defer_bus_contact()
+        .build()
+        .unwrap();
+    assert_eq!(
+        mpu.detect_clone_heuristics(&mut NoDelay),
+        CloneAssessment::Inconclusive
+    );
+    assert_eq!(bus.touched(), [0u8; 0]);
+    assert_eq!(
+        mpu.capabilities().clone_assessment,
+        Some(CloneAssessment::Inconclusive)
+    );
+}
+
+#[test]
+fn capabilities_keep_the_assessment() {
+    let mut bus = SelfTestBus::genuine(ADDR);
+    bus.response = [0; 3];
+    let mut mpu = awake(&bus);
+    let assessment = mpu.detect_clone_heuristics(&mut NoDelay);
+    assert!(assessment.is_likely_clone());
+    // a likely clone is not assumed to load DMP firmware
+    let caps = mpu.capabilities();
+    assert_eq!(caps.clone_assessment, Some(assessment));
+    assert!(!caps.dmp_loadable && caps.fifo_available);
+    assert_eq!(
+        caps,
+        capabilities(ChipVariant::Mpu6050).with_clone_assessment(assessment)
+    );
+
+    // kept by a probe of the same variant, dropped for another chip
+    assert_eq!(mpu.probe_capabilities().unwrap(), caps);
+    bus.regs(|regs| regs[WHOAMI as usize] = 0x70);
+    assert_eq!(mpu.probe_capabilities().unwrap().clone_assessment, None);
+}
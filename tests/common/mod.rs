@@ -0,0 +1,344 @@
+//! Mock buses shared by the integration tests.
+
+// every test binary uses a different subset
+#![allow(dead_code)]
+
+use std::cell::{Cell, RefCell};
+use std::collections::VecDeque;
+use std::convert::Infallible;
+use std::rc::Rc;
+
+use embedded_hal::blocking::delay::DelayMs;
+use embedded_hal::blocking::i2c::{Read, Write, WriteRead};
+use mpu6050::device::{ACC_REGX_H, FIFO_COUNT_H, FIFO_R_W, PWR_MGMT_1, USER_CTRL, WHOAMI};
+use mpu6050::fifo::FIFO_CAPACITY;
+
+/// Register file answering like an MPU6050 at rest, 1g on Z at ±2g
+pub struct RegisterMock {
+    pub regs: [u8; 256],
+    /// registers ignoring writes
+    pub read_only: Vec<u8>,
+    /// FIFO content, read through FIFO_R_W and counted in FIFO_COUNTH/L
+    pub fifo: VecDeque<u8>,
+    /// register pointer, where a bare read starts
+    pub pointer: u8,
+}
+
+impl RegisterMock {
+    pub fn new() -> Self {
+        let mut regs = [0; 256];
+        regs[WHOAMI as usize] = 0x68;
+        regs[PWR_MGMT_1::ADDR as usize] = 0x40;
+        let z = ACC_REGX_H as usize + 4;
+        regs[z..z + 2].copy_from_slice(&16384i16.to_be_bytes());
+        Self {
+            regs,
+            read_only: Vec::new(),
+            fifo: VecDeque::new(),
+            pointer: 0,
+        }
+    }
+
+    /// sets the 14 bytes of ACCEL_XOUT_H..GYRO_ZOUT_L
+    pub fn set_frame(&mut self, frame: &[u8; 14]) {
+        let start = ACC_REGX_H as usize;
+        self.regs[start..start + 14].copy_from_slice(frame);
+    }
+}
+
+impl Write for RegisterMock {
+    type Error = Infallible;
+
+    fn write(&mut self, _address: u8, bytes: &[u8]) -> Result<(), Infallible> {
+        if let Some((reg, data)) = bytes.split_first() {
+            for (addr, byte) in (*reg..).zip(data) {
+                if !self.read_only.contains(&addr) {
+                    self.regs[addr as usize] = *byte;
+                }
+            }
+            self.pointer = reg.wrapping_add(data.len() as u8);
+        }
+        Ok(())
+    }
+}
+
+/// Reads from the pointer, which auto increments except at FIFO_R_W
+impl Read for RegisterMock {
+    type Error = Infallible;
+
+    fn read(&mut self, _address: u8, buffer: &mut [u8]) -> Result<(), Infallible> {
+        let count = (self.fifo.len() as u16).to_be_bytes();
+        for byte in buffer {
+            *byte = match self.pointer {
+                FIFO_R_W => self.fifo.pop_front().unwrap_or(0),
+                FIFO_COUNT_H => count[0],
+                reg if reg == FIFO_COUNT_H + 1 => count[1],
+                reg => self.regs[reg as usize],
+            };
+            if self.pointer != FIFO_R_W {
+                self.pointer = self.pointer.wrapping_add(1);
+            }
+        }
+        Ok(())
+    }
+}
+
+impl WriteRead for RegisterMock {
+    type Error = Infallible;
+
+    fn write_read(
+        &mut self,
+        address: u8,
+        bytes: &[u8],
+        buffer: &mut [u8],
+    ) -> Result<(), Infallible> {
+        self.write(address, bytes)?;
+        self.read(address, buffer)
+    }
+}
+
+/// Transaction seen on a [`SharedBus`]
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct Access {
+    pub address: u8,
+    pub reg: u8,
+    pub read: bool,
+}
+
+struct Bus {
+    devices: Vec<(u8, RegisterMock)>,
+    log: Vec<Access>,
+}
+
+/// One bus with a [`RegisterMock`] per address, every clone is a handle to the same bus
+#[derive(Clone)]
+pub struct SharedBus {
+    bus: Rc<RefCell<Bus>>,
+}
+
+impl SharedBus {
+    pub fn new(addresses: &[u8]) -> Self {
+        let devices = addresses
+            .iter()
+            .map(|address| (*address, RegisterMock::new()))
+            .collect();
+        Self {
+            bus: Rc::new(RefCell::new(Bus {
+                devices,
+                log: Vec::new(),
+            })),
+        }
+    }
+
+    /// access to the device at `address`, bypassing the log
+    pub fn device<R>(&self, address: u8, f: impl FnOnce(&mut RegisterMock) -> R) -> R {
+        let mut bus = self.bus.borrow_mut();
+        let (_, device) = bus
+            .devices
+            .iter_mut()
+            .find(|(a, _)| *a == address)
+            .expect("no device at address");
+        f(device)
+    }
+
+    /// transactions since the last call
+    pub fn take_log(&self) -> Vec<Access> {
+        std::mem::take(&mut self.bus.borrow_mut().log)
+    }
+
+    fn access<R>(&self, access: Access, f: impl FnOnce(&mut RegisterMock) -> R) -> R {
+        self.bus.borrow_mut().log.push(access);
+        self.device(access.address, f)
+    }
+}
+
+impl Write for SharedBus {
+    type Error = Infallible;
+
+    fn write(&mut self, address: u8, bytes: &[u8]) -> Result<(), Infallible> {
+        let access = Access {
+            address,
+            reg: bytes[0],
+            read: false,
+        };
+        self.access(access, |device| device.write(address, bytes))
+    }
+}
+
+/// logged with the register at the device's pointer
+impl Read for SharedBus {
+    type Error = Infallible;
+
+    fn read(&mut self, address: u8, buffer: &mut [u8]) -> Result<(), Infallible> {
+        let access = Access {
+            address,
+            reg: self.device(address, |device| device.pointer),
+            read: true,
+        };
+        self.access(access, |device| device.read(address, buffer))
+    }
+}
+
+impl WriteRead for SharedBus {
+    type Error = Infallible;
+
+    fn write_read(
+        &mut self,
+        address: u8,
+        bytes: &[u8],
+        buffer: &mut [u8],
+    ) -> Result<(), Infallible> {
+        let access = Access {
+            address,
+            reg: bytes[0],
+            read: true,
+        };
+        self.access(access, |device| device.write_read(address, bytes, buffer))
+    }
+}
+
+thread_local! {
+    static CHIP_TIME_US: Cell<u64> = const { Cell::new(0) };
+}
+
+/// time of the [`FifoChip`]s of this thread, a [`FifoClock`](mpu6050::fifo_recovery::FifoClock)
+pub fn chip_time_us() -> u64 {
+    CHIP_TIME_US.with(Cell::get)
+}
+
+struct Chip {
+    mock: RegisterMock,
+    frame_len: usize,
+    period_us: u64,
+    /// frames written so far, the number of the next
+    written: u64,
+    /// frames to write before the transaction that many transactions from now
+    scheduled: Vec<(usize, u64)>,
+    /// USER_CTRL writes fail
+    fail_user_ctrl: bool,
+}
+
+/// A [`RegisterMock`] whose FIFO behaves like the chip's: frames of `frame_len` bytes
+/// written at `period_us`, frame n carrying n in its first word, the oldest bytes
+/// overwritten once [`FIFO_CAPACITY`] bytes are queued, emptied by USER_CTRL FIFO_RESET.
+/// Every clone is a handle to the same chip, writing frames advances [`chip_time_us`]
+#[derive(Clone)]
+pub struct FifoChip {
+    chip: Rc<RefCell<Chip>>,
+}
+
+impl FifoChip {
+    pub fn new(frame_len: usize, period_us: u64) -> Self {
+        CHIP_TIME_US.with(|time| time.set(0));
+        Self {
+            chip: Rc::new(RefCell::new(Chip {
+                mock: RegisterMock::new(),
+                frame_len,
+                period_us,
+                written: 0,
+                scheduled: Vec::new(),
+                fail_user_ctrl: false,
+            })),
+        }
+    }
+
+    /// the chip samples `frames` more frames
+    pub fn write_frames(&self, frames: u64) {
+        let mut chip = self.chip.borrow_mut();
+        for _ in 0..frames {
+            let mut frame = vec![0; chip.frame_len];
+            frame[..2].copy_from_slice(&(chip.written as i16).to_be_bytes());
+            chip.mock.fifo.extend(frame);
+            let excess = chip.mock.fifo.len().saturating_sub(FIFO_CAPACITY as usize);
+            chip.mock.fifo.drain(..excess);
+            chip.written += 1;
+            CHIP_TIME_US.with(|time| time.set(time.get() + chip.period_us));
+        }
+    }
+
+    /// writes `frames` right before the transaction `transactions` from now, 0 the next
+    pub fn write_frames_before(&self, transactions: usize, frames: u64) {
+        self.chip
+            .borrow_mut()
+            .scheduled
+            .push((transactions, frames));
+    }
+
+    /// frames written so far
+    pub fn written(&self) -> u64 {
+        self.chip.borrow().written
+    }
+
+    /// bytes queued in the FIFO
+    pub fn fifo_len(&self) -> usize {
+        self.chip.borrow().mock.fifo.len()
+    }
+
+    /// USER_CTRL writes fail while set
+    pub fn fail_user_ctrl(&self, fail: bool) {
+        self.chip.borrow_mut().fail_user_ctrl = fail;
+    }
+
+    /// runs what is due before this transaction
+    fn transaction(&self) {
+        let mut due = 0;
+        self.chip
+            .borrow_mut()
+            .scheduled
+            .retain_mut(|(transactions, frames)| {
+                if *transactions == 0 {
+                    due += *frames;
+                    return false;
+                }
+                *transactions -= 1;
+                true
+            });
+        self.write_frames(due);
+    }
+}
+
+impl Write for FifoChip {
+    type Error = ();
+
+    fn write(&mut self, address: u8, bytes: &[u8]) -> Result<(), ()> {
+        self.transaction();
+        let mut chip = self.chip.borrow_mut();
+        if bytes.first() == Some(&USER_CTRL::ADDR) {
+            if chip.fail_user_ctrl {
+                return Err(());
+            }
+            if bytes
+                .get(1)
+                .is_some_and(|v| v & (1 << USER_CTRL::FIFO_RESET) != 0)
+            {
+                chip.mock.fifo.clear();
+                chip.mock
+                    .write(
+                        address,
+                        &[bytes[0], bytes[1] & !(1 << USER_CTRL::FIFO_RESET)],
+                    )
+                    .ok();
+                return Ok(());
+            }
+        }
+        chip.mock.write(address, bytes).ok();
+        Ok(())
+    }
+}
+
+impl WriteRead for FifoChip {
+    type Error = ();
+
+    fn write_read(&mut self, address: u8, bytes: &[u8], buffer: &mut [u8]) -> Result<(), ()> {
+        self.transaction();
+        let mut chip = self.chip.borrow_mut();
+        chip.mock.write_read(address, bytes, buffer).ok();
+        Ok(())
+    }
+}
+
+pub struct NoDelay;
+
+impl DelayMs<u8> for NoDelay {
+    fn delay_ms(&mut self, _ms: u8) {}
+}
@@ -0,0 +1,249 @@
+//! Upstream API shim: readings against upstream's formulas and the native driver for a table
+//! of register frames, error mapping, see the `compat` module.
+
+mod common;
+
+use core::f32::consts::PI;
+
+use mpu6050::compat;
+use mpu6050::device::*;
+use mpu6050::fifo::FifoSources;
+use mpu6050::*;
+
+use common::{NoDelay, RegisterMock};
+
+/// (accel range, gyro range, acc counts, temp counts, gyro counts)
+type Case = (AccelRange, GyroRange, [i16; 3], i16, [i16; 3]);
+
+const CASES: [Case; 8] = [
+    (AccelRange::G2, GyroRange::D250, [0, 0, 16384], 0, [0, 0, 0]),
+    (
+        AccelRange::G2,
+        GyroRange::D250,
+        [8192, -8192, 11585],
+        -521,
+        [131, -262, 13100],
+    ),
+    (
+        AccelRange::G4,
+        GyroRange::D500,
+        [-4096, 7094, 0],
+        1000,
+        [-32768, 32767, 1],
+    ),
+    (
+        AccelRange::G8,
+        GyroRange::D1000,
+        [4096, 0, 0],
+        -12420,
+        [328, 0, -328],
+    ),
+    (
+        AccelRange::G16,
+        GyroRange::D2000,
+        [i16::MAX, i16::MIN, -1],
+        i16::MAX,
+        [164, 1640, -16400],
+    ),
+    (
+        AccelRange::G16,
+        GyroRange::D250,
+        [-2048, -2048, -2048],
+        i16::MIN,
+        [-1, -1, -1],
+    ),
+    (
+        AccelRange::G4,
+        GyroRange::D2000,
+        [0, 8192, 0],
+        340,
+        [7, 11, 13],
+    ),
+    (
+        AccelRange::G2,
+        GyroRange::D1000,
+        [-16384, 0, -100],
+        -12,
+        [-3280, 3280, 0],
+    ),
+];
+
+fn frame(case: &Case) -> [u8; 14] {
+    let (_, _, acc, temp, gyro) = case;
+    let mut frame = [0; 14];
+    for (i, value) in acc.iter().chain([temp]).chain(gyro).enumerate() {
+        frame[2 * i..2 * i + 2].copy_from_slice(&value.to_be_bytes());
+    }
+    frame
+}
+
+fn acc_sens(range: AccelRange) -> f32 {
+    match range {
+        AccelRange::G2 => ACCEL_SENS.0,
+        AccelRange::G4 => ACCEL_SENS.1,
+        AccelRange::G8 => ACCEL_SENS.2,
+        AccelRange::G16 => ACCEL_SENS.3,
+    }
+}
+
+fn gyro_sens(range: GyroRange) -> f32 {
+    match range {
+        GyroRange::D250 => GYRO_SENS.0,
+        GyroRange::D500 => GYRO_SENS.1,
+        GyroRange::D1000 => GYRO_SENS.2,
+        GyroRange::D2000 => GYRO_SENS.3,
+    }
+}
+
+/// upstream's conversions, transcribed: (acc, gyro, temp, [roll, pitch])
+fn upstream(case: &Case) -> ([f32; 3], [f32; 3], f32, [f32; 2]) {
+    let (arange, grange, acc, temp, gyro) = *case;
+    let acc = acc.map(|v| v as f32 / acc_sens(arange));
+    let gyro = gyro.map(|v| v as f32 * (PI / 180.0) / gyro_sens(grange));
+    let temp = temp as f32 / 340. + 36.53;
+    let angles = [
+        acc[1].atan2((acc[0].powi(2) + acc[2].powi(2)).sqrt()),
+        (-acc[0]).atan2((acc[1].powi(2) + acc[2].powi(2)).sqrt()),
+    ];
+    (acc, gyro, temp, angles)
+}
+
+fn close(a: &[f32], b: &[f32]) -> bool {
+    a.iter()
+        .zip(b)
+        .all(|(a, b)| (a - b).abs() <= 1e-6 * b.abs().max(1.))
+}
+
+fn compat_driver(case: &Case) -> compat::Mpu6050<RegisterMock> {
+    let mut mpu = compat::Mpu6050::new(RegisterMock::new());
+    mpu.init(&mut NoDelay).unwrap();
+    mpu.set_accel_range(case.0).unwrap();
+    mpu.set_gyro_range(case.1).unwrap();
+    mpu.inner_mut().reset_io_stats();
+    mpu
+}
+
+#[test]
+fn readings_match_upstream_formulas() {
+    for (i, case) in CASES.iter().enumerate() {
+        let mut mpu = compat_driver(case);
+        let (acc, gyro, temp, angles) = upstream(case);
+        let frame = frame(case);
+        // the mock is inside the driver, write the frame through the raw accessors
+        for (offset, byte) in frame.iter().enumerate() {
+            mpu.write_byte(ACC_REGX_H + offset as u8, *byte).unwrap();
+        }
+        mpu.inner_mut().reset_io_stats();
+
+        assert!(
+            close(&mpu.get_acc().unwrap().to_array(), &acc),
+            "case {}",
+            i
+        );
+        assert!(
+            close(&mpu.get_gyro().unwrap().to_array(), &gyro),
+            "case {}",
+            i
+        );
+        assert!(close(&[mpu.get_temp().unwrap()], &[temp]), "case {}", i);
+        assert!(
+            close(&mpu.get_acc_angles().unwrap().to_array(), &angles),
+            "case {}",
+            i
+        );
+        // one transaction per reading, as upstream
+        assert_eq!(mpu.inner().io_stats().transactions, 4, "case {}", i);
+    }
+}
+
+#[test]
+fn readings_match_native_driver() {
+    for (i, case) in CASES.iter().enumerate() {
+        let mut mock = RegisterMock::new();
+        mock.set_frame(&frame(case));
+        let mut native = Mpu6050Builder::new().i2c(mock).build().unwrap();
+        native.init(&mut NoDelay).unwrap();
+        native.set_accel_range(case.0).unwrap();
+        native.set_gyro_range(case.1).unwrap();
+        let mut mpu = compat::Mpu6050::from(native);
+
+        let acc = mpu.inner_mut().get_acc().unwrap();
+        let gyro = mpu.inner_mut().get_gyro().unwrap();
+        let temp = mpu.inner_mut().get_temp().unwrap();
+        let (roll, pitch) = tilt::roll_pitch(acc);
+        assert_eq!(mpu.get_acc().unwrap(), acc, "case {}", i);
+        assert_eq!(mpu.get_gyro().unwrap(), gyro, "case {}", i);
+        assert_eq!(mpu.get_temp().unwrap(), temp, "case {}", i);
+        assert_eq!(mpu.get_acc_angles().unwrap().to_array(), [roll, pitch]);
+        assert_eq!(mpu.get_acc_as::<[f32; 3]>().unwrap(), acc.to_array());
+        assert_eq!(mpu.get_gyro_as::<[f32; 3]>().unwrap(), gyro.to_array());
+        assert_eq!(mpu.get_acc_angles_as::<[f32; 2]>().unwrap(), [roll, pitch]);
+    }
+}
+
+#[test]
+fn constructors() {
+    let mpu = compat::Mpu6050::new_with_addr_and_sens(
+        RegisterMock::new(),
+        0x69,
+        AccelRange::G8,
+        GyroRange::D1000,
+    );
+    let state = mpu.inner().debug_state();
+    assert_eq!(state.slave_addr, 0x69);
+    assert_eq!(state.accel_range, AccelRange::G8);
+    assert_eq!(state.gyro_range, GyroRange::D1000);
+
+    // ranges read back as configured, init restores ±2 g and ±250 °/s as upstream does
+    let mut mpu =
+        compat::Mpu6050::new_with_sens(RegisterMock::new(), AccelRange::G4, GyroRange::D500);
+    mpu.init(&mut NoDelay).unwrap();
+    assert_eq!(mpu.get_accel_range().unwrap(), AccelRange::G2);
+    assert_eq!(mpu.get_gyro_range().unwrap(), GyroRange::D250);
+    assert!((mpu.get_acc().unwrap().z - 1.).abs() < 1e-6);
+
+    let mpu = compat::Mpu6050::new_with_addr(RegisterMock::new(), 0x69);
+    assert_eq!(mpu.into_inner().debug_state().slave_addr, 0x69);
+}
+
+#[test]
+fn raw_access() {
+    let mut mpu = compat::Mpu6050::new(RegisterMock::new());
+    mpu.write_bits(SMPLRT_DIV, 5, 3, 0b101).unwrap();
+    assert_eq!(mpu.read_byte(SMPLRT_DIV).unwrap(), 0b0010_1000);
+    assert_eq!(mpu.read_bits(SMPLRT_DIV, 5, 3).unwrap(), 0b101);
+    assert_eq!(mpu.read_bit(SMPLRT_DIV, 5).unwrap(), 1);
+    assert_eq!(mpu.read_bit(SMPLRT_DIV, 4).unwrap(), 0);
+    mpu.write_bit(SMPLRT_DIV, 0, true).unwrap();
+    let mut buf = [0; 2];
+    mpu.read_bytes(SMPLRT_DIV, &mut buf).unwrap();
+    assert_eq!(buf[0], 0b0010_1001);
+}
+
+#[test]
+fn errors_map_to_upstream_variants() {
+    let mut mock = RegisterMock::new();
+    mock.regs[WHOAMI as usize] = 0x72;
+    let mut mpu = compat::Mpu6050::new(mock);
+    assert!(matches!(
+        mpu.init(&mut NoDelay),
+        Err(compat::Mpu6050Error::InvalidChipId(0x72))
+    ));
+
+    assert!(matches!(
+        compat::Mpu6050Error::from(Mpu6050Error::I2c(5)),
+        compat::Mpu6050Error::I2c(5)
+    ));
+
+    // native only errors need native configuration
+    let mut mpu = compat::Mpu6050::new(RegisterMock::new());
+    let native = mpu.inner_mut();
+    native
+        .set_fifo_sources(FifoSources::NONE.with_accel(true))
+        .unwrap();
+    native.set_fifo_enabled(true).unwrap();
+    assert!(matches!(
+        mpu.get_acc(),
+        Err(compat::Mpu6050Error::Native(Mpu6050Error::FifoActive))
+    ));
+}
@@ -0,0 +1,128 @@
+//! Register diffs between configuration snapshots and raw images: several fields changed in
+//! one register, registers unknown to the driver, the empty diff and the ordering, see the
+//! `config` module.
+
+mod common;
+
+use mpu6050::config::{diff_register_images, FieldChange, Mpu6050Config, RegisterDiff};
+use mpu6050::device::{ACCEL_CONFIG, DEFAULT_SLAVE_ADDR, PWR_MGMT_1, RESET_VALUES, USER_CTRL};
+use mpu6050::Mpu6050Builder;
+
+use common::{NoDelay, SharedBus};
+
+fn change(name: &'static str, old: u8, new: u8) -> FieldChange {
+    FieldChange { name, old, new }
+}
+
+#[test]
+fn several_fields_of_one_register() {
+    let working = Mpu6050Config::from_image(&[(PWR_MGMT_1::ADDR, 0x40)]);
+    // accel at ±16 g with the 0.63 Hz high pass, awake on the X gyro PLL; unsorted image
+    let broken = Mpu6050Config::from_image(&[(PWR_MGMT_1::ADDR, 0x01), (ACCEL_CONFIG::ADDR, 0x1c)]);
+    let diff = working.diff(&broken);
+    let registers: Vec<_> = diff.iter().collect();
+    assert_eq!(registers.len(), 2);
+    assert_eq!(
+        *registers[0],
+        RegisterDiff {
+            addr: ACCEL_CONFIG::ADDR,
+            old: Some(0x00),
+            new: Some(0x1c),
+        }
+    );
+    assert_eq!(
+        registers[0].fields().collect::<Vec<_>>(),
+        [change("AFS_SEL", 0, 3), change("ACCEL_HPF", 0, 4)]
+    );
+    assert_eq!(
+        registers[1].fields().collect::<Vec<_>>(),
+        [change("SLEEP", 1, 0), change("CLKSEL", 0, 1)]
+    );
+    assert_eq!(
+        diff.to_string(),
+        "ACCEL_CONFIG (0x1c): AFS_SEL 0->3, ACCEL_HPF 0->4\n\
+         PWR_MGMT_1 (0x6b): SLEEP 1->0, CLKSEL 0->1"
+    );
+    // the other direction swaps the values only
+    assert_eq!(
+        broken.diff(&working).to_string(),
+        "ACCEL_CONFIG (0x1c): AFS_SEL 3->0, ACCEL_HPF 4->0\n\
+         PWR_MGMT_1 (0x6b): SLEEP 0->1, CLKSEL 1->0"
+    );
+}
+
+#[test]
+fn identical_snapshots_diff_empty() {
+    let config = Mpu6050Config::from_image(RESET_VALUES);
+    let diff = config.diff(&config);
+    assert!(diff.is_empty());
+    assert_eq!(diff.iter().count(), 0);
+    assert_eq!(diff.to_string(), "no differences");
+    // registers outside the snapshot do not take part
+    let with_who_am_i = Mpu6050Config::from_image(&[(PWR_MGMT_1::ADDR, 0x40), (0x75, 0x70)]);
+    assert!(config.diff(&with_who_am_i).is_empty());
+    assert_eq!(diff_register_images(RESET_VALUES, RESET_VALUES).count(), 0);
+}
+
+#[test]
+fn unknown_registers_fall_back_to_bytes() {
+    // XG_OFFS_USRH and WHO_AM_I have no field description
+    let a = [(0x75, 0x68), (0x13, 0x00), (PWR_MGMT_1::ADDR, 0x40)];
+    let b = [(0x13, 0xff), (PWR_MGMT_1::ADDR, 0x00), (0x75, 0x70)];
+    let diffs: Vec<_> = diff_register_images(&a, &b).collect();
+    assert_eq!(
+        diffs.iter().map(|d| d.addr).collect::<Vec<_>>(),
+        [0x13, PWR_MGMT_1::ADDR, 0x75]
+    );
+    assert!(diffs[0].info().is_none());
+    assert_eq!(diffs[0].fields().count(), 0);
+    let lines: Vec<_> = diffs.iter().map(ToString::to_string).collect();
+    assert_eq!(
+        lines,
+        [
+            "0x13: 0x00->0xff",
+            "PWR_MGMT_1 (0x6b): SLEEP 1->0",
+            "0x75: 0x68->0x70"
+        ]
+    );
+}
+
+#[test]
+fn registers_missing_on_one_side() {
+    let a = [(USER_CTRL::ADDR, 0x40), (0x13, 0x01)];
+    let b = [(0x13, 0x01), (0x3a, 0x01)];
+    let lines: Vec<_> = diff_register_images(&a, &b)
+        .map(|d| {
+            // no side to compare fields against
+            assert_eq!(d.fields().count(), 0);
+            d.to_string()
+        })
+        .collect();
+    assert_eq!(lines, ["0x3a: --->0x01", "USER_CTRL (0x6a): 0x40->--"]);
+
+    // the first of duplicate entries counts
+    let duplicated = [(0x13, 0x01), (0x13, 0x02)];
+    assert_eq!(diff_register_images(&a, &duplicated).count(), 1);
+}
+
+#[test]
+fn device_snapshot_against_the_reset_image() {
+    let bus = SharedBus::new(&[DEFAULT_SLAVE_ADDR]);
+    let mut mpu = Mpu6050Builder::new().i2c(bus.clone()).build().unwrap();
+    assert!(mpu
+        .read_config()
+        .unwrap()
+        .diff(&Mpu6050Config::from_image(RESET_VALUES))
+        .is_empty());
+
+    mpu.init(&mut NoDelay).unwrap();
+    let current = mpu.read_config().unwrap();
+    assert_eq!(current.get(PWR_MGMT_1::ADDR), Some(0x01));
+    assert_eq!(current.get(0x75), None);
+    assert_eq!(
+        Mpu6050Config::from_image(RESET_VALUES)
+            .diff(&current)
+            .to_string(),
+        "PWR_MGMT_1 (0x6b): SLEEP 1->0, CLKSEL 0->1"
+    );
+}
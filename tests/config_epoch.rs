@@ -0,0 +1,374 @@
+//! Configuration epoch: every configuration operation advances it through the one choke
+//! point, the others keep it, and tracked values revalidate or are refused, see the
+//! `config_epoch` module. The settings side is covered by `settings_fuzz`.
+
+mod common;
+
+use mpu6050::aux_i2c::{SlaveConfig, SlaveSlot};
+use mpu6050::calibration::CalibrationConfig;
+use mpu6050::config_epoch::{ConfigChange, Tracked};
+use mpu6050::conversion::RoundingMode;
+use mpu6050::device::*;
+use mpu6050::fifo::FifoSources;
+use mpu6050::interleave::RangeInterleave;
+use mpu6050::platform::ReferencedCalibration;
+use mpu6050::resolution::ResolutionInfo;
+use mpu6050::scale::Pipeline;
+use mpu6050::scale_drift::{ScaleDriftConfig, ScaleDriftMonitor};
+use mpu6050::settings::Mpu6050Settings;
+use mpu6050::settling::SettlingPolicy;
+use mpu6050::setup::AutoSetupOptions;
+use mpu6050::*;
+
+use common::{NoDelay, SharedBus};
+
+type Mpu = Mpu6050<SharedBus>;
+type Op = fn(&mut Mpu);
+
+const MAGNETOMETER: SlaveConfig = SlaveConfig {
+    address: 0x0c,
+    register: 0x03,
+    len: 6,
+    read: true,
+    byte_swap: false,
+};
+
+fn driver() -> Mpu {
+    let bus = SharedBus::new(&[DEFAULT_SLAVE_ADDR]);
+    let mut mpu = Mpu6050Builder::new().i2c(bus).build().unwrap();
+    mpu.init(&mut NoDelay).unwrap();
+    mpu.set_settling_policy(SettlingPolicy::Flag);
+    mpu
+}
+
+fn setup_options() -> AutoSetupOptions {
+    AutoSetupOptions {
+        // the mock does not respond to self-test
+        min_actuation_g: 0.0,
+        gyro_samples: 10,
+        device_is_level: true,
+        acc_samples: 10,
+        ..AutoSetupOptions::default()
+    }
+}
+
+fn background(manual_commit: bool) -> CalibrationConfig {
+    CalibrationConfig {
+        samples: 4,
+        manual_commit,
+        ..CalibrationConfig::default()
+    }
+}
+
+/// every public operation of the epoch table, with the change it records last
+fn configuration_ops() -> Vec<(&'static str, ConfigChange, Op)> {
+    vec![
+        ("set_accel_range", ConfigChange::AccelRange, |mpu| {
+            mpu.set_accel_range(AccelRange::G8).unwrap()
+        }),
+        ("set_gyro_range", ConfigChange::GyroRange, |mpu| {
+            mpu.set_gyro_range(GyroRange::D500).unwrap()
+        }),
+        ("set_dlpf", ConfigChange::Dlpf, |mpu| {
+            mpu.set_dlpf(3).unwrap()
+        }),
+        (
+            "set_sample_rate_divider",
+            ConfigChange::SampleRateDivider,
+            |mpu| mpu.set_sample_rate_divider(9).unwrap(),
+        ),
+        // the divider is written last
+        ("apply_settings", ConfigChange::SampleRateDivider, |mpu| {
+            mpu.apply_settings(&Mpu6050Settings::new().with_dlpf_cfg(2))
+                .unwrap()
+        }),
+        ("apply_settings_diff", ConfigChange::Dlpf, |mpu| {
+            let current = Mpu6050Settings::new();
+            mpu.apply_settings_diff(&current, &current.with_dlpf_cfg(2))
+                .unwrap()
+        }),
+        (
+            "enable_range_interleaving",
+            ConfigChange::AccelRange,
+            |mpu| {
+                mpu.enable_range_interleaving(RangeInterleave {
+                    low: AccelRange::G2,
+                    high: AccelRange::G16,
+                    period: 3,
+                })
+                .unwrap()
+            },
+        ),
+        ("set_fifo_sources", ConfigChange::FifoSources, |mpu| {
+            mpu.set_fifo_sources(FifoSources::NONE.with_accel(true))
+                .map(drop)
+                .unwrap()
+        }),
+        ("configure_i2c_slave", ConfigChange::AuxSlaves, |mpu| {
+            mpu.configure_i2c_slave(SlaveSlot::Slv0, MAGNETOMETER)
+                .map(drop)
+                .unwrap()
+        }),
+        ("disable_i2c_slave", ConfigChange::AuxSlaves, |mpu| {
+            mpu.disable_i2c_slave(SlaveSlot::Slv0).unwrap()
+        }),
+        ("apply_acc_trim", ConfigChange::Offsets, |mpu| {
+            mpu.apply_acc_trim(Vec3A::new(0., 0., 0.01))
+        }),
+        ("set_gyro_hw_offsets", ConfigChange::Offsets, |mpu| {
+            mpu.set_gyro_hw_offsets([10, -10, 0]).unwrap()
+        }),
+        ("set_accel_hw_offsets", ConfigChange::Offsets, |mpu| {
+            mpu.set_accel_hw_offsets([10, -10, 0]).unwrap()
+        }),
+        (
+            "store_gyro_offset_in_hardware",
+            ConfigChange::Offsets,
+            |mpu| mpu.store_gyro_offset_in_hardware().map(drop).unwrap(),
+        ),
+        (
+            "calibrate_gyro_with_reference",
+            ConfigChange::Offsets,
+            |mpu| {
+                let options = ReferencedCalibration::gyro(&setup_options());
+                mpu.calibrate_gyro_with_reference(&mut NoDelay, || Vec3A::ZERO, options)
+                    .map(drop)
+                    .unwrap()
+            },
+        ),
+        (
+            "calibrate_accel_with_reference",
+            ConfigChange::Offsets,
+            |mpu| {
+                let options = ReferencedCalibration::accel(&setup_options());
+                mpu.calibrate_accel_with_reference(&mut NoDelay, || Vec3A::Z, options)
+                    .map(drop)
+                    .unwrap()
+            },
+        ),
+        // calibrates, then applies the ranges
+        ("auto_setup", ConfigChange::GyroRange, |mpu| {
+            mpu.auto_setup(&mut NoDelay, setup_options(), None)
+                .map(drop)
+                .unwrap()
+        }),
+        ("background calibration", ConfigChange::Offsets, |mpu| {
+            mpu.start_background_calibration(background(false));
+            while mpu.background_calibration_status().is_some() {
+                mpu.get_gyro().unwrap();
+            }
+        }),
+        (
+            "commit_background_calibration",
+            ConfigChange::Offsets,
+            |mpu| {
+                mpu.start_background_calibration(background(true));
+                while mpu.commit_background_calibration().is_none() {
+                    mpu.get_gyro().unwrap();
+                }
+            },
+        ),
+        (
+            "set_accel_scale_factors",
+            ConfigChange::ScaleFactors,
+            |mpu| mpu.set_accel_scale_factors(Vec3A::new(1.01, 1., 0.99)),
+        ),
+        (
+            "set_gyro_scale_factors",
+            ConfigChange::ScaleFactors,
+            |mpu| mpu.set_gyro_scale_factors(Vec3A::ONE),
+        ),
+        (
+            "apply_scale_correction",
+            ConfigChange::ScaleFactors,
+            |mpu| {
+                let mut monitor = ScaleDriftMonitor::new(ScaleDriftConfig::default());
+                for octant in 0..8 {
+                    let sign = |bit: i32| if octant >> bit & 1 == 0 { 1. } else { -1. };
+                    for (x, y, z) in [(0.3, 0.5, 1.), (1., 0.4, 0.2), (0.2, 1., 0.6)] {
+                        let gravity = Vec3A::new(x * sign(0), y * sign(1), z * sign(2));
+                        for _ in 0..800 {
+                            monitor.feed_readings(gravity.normalize() * 1.01, Vec3A::ZERO, 0.01);
+                        }
+                        monitor.feed_readings(Vec3A::Z, Vec3A::ONE, 0.01);
+                    }
+                }
+                mpu.apply_scale_correction(&mut monitor).map(drop).unwrap()
+            },
+        ),
+        (
+            "set_temp_calibration",
+            ConfigChange::TempCalibration,
+            |mpu| mpu.set_temp_calibration(None).unwrap(),
+        ),
+        (
+            "calibrate_temp_two_point",
+            ConfigChange::TempCalibration,
+            |mpu| {
+                mpu.calibrate_temp_two_point(-3400, 26.5, 0, 36.53)
+                    .map(drop)
+                    .unwrap()
+            },
+        ),
+        (
+            "calibrate_temp_single_point",
+            ConfigChange::TempCalibration,
+            |mpu| mpu.calibrate_temp_single_point(0, 37.).map(drop).unwrap(),
+        ),
+        ("reset_device", ConfigChange::Reset, |mpu| {
+            mpu.reset_device(&mut NoDelay).unwrap()
+        }),
+    ]
+}
+
+#[test]
+fn every_configuration_operation_advances_the_epoch() {
+    for (name, change, op) in configuration_ops() {
+        let mut mpu = driver();
+        let before = mpu.config_epoch();
+        let schema = mpu.fifo_schema();
+        let resolution = mpu.tracked(Mpu::current_resolution);
+        op(&mut mpu);
+        assert_ne!(mpu.config_epoch(), before, "{}", name);
+        assert_eq!(mpu.last_config_change(), Some(change), "{}", name);
+        assert!(!schema.is_current(&mpu), "{}", name);
+        assert!(!resolution.is_current(&mpu), "{}", name);
+    }
+    // the table names every change
+    for change in ConfigChange::ALL {
+        assert!(
+            configuration_ops().iter().any(|(_, c, _)| *c == change),
+            "{}",
+            change
+        );
+    }
+}
+
+#[test]
+fn other_operations_keep_the_epoch() {
+    let ops: [(&str, Op); 11] = [
+        ("set_clock_source", |mpu| {
+            mpu.set_clock_source(CLKSEL::GZAXIS).unwrap()
+        }),
+        ("set_sleep_enabled", |mpu| {
+            mpu.set_sleep_enabled(false).unwrap()
+        }),
+        ("set_temp_enabled", |mpu| {
+            mpu.set_temp_enabled(true).unwrap()
+        }),
+        ("set_accel_hpf", |mpu| {
+            mpu.set_accel_hpf(ACCEL_HPF::_5).unwrap()
+        }),
+        ("set_rounding", |mpu| {
+            mpu.set_rounding(RoundingMode::Truncate)
+        }),
+        ("set_settling_policy", |mpu| {
+            mpu.set_settling_policy(SettlingPolicy::Discard { max_retries: 2 })
+        }),
+        ("apply_clock_correction", |mpu| {
+            mpu.apply_clock_correction(1.0001).unwrap()
+        }),
+        ("set_fifo_enabled", |mpu| {
+            mpu.set_fifo_enabled(false).unwrap()
+        }),
+        ("reset_fifo", |mpu| mpu.reset_fifo().unwrap()),
+        ("set_i2c_master_enabled", |mpu| {
+            mpu.set_i2c_master_enabled(true).unwrap()
+        }),
+        ("reads", |mpu| {
+            mpu.get_acc().unwrap();
+            mpu.get_gyro().unwrap();
+            mpu.get_temp().unwrap();
+        }),
+    ];
+    for (name, op) in ops {
+        let mut mpu = driver();
+        let before = (mpu.config_epoch(), mpu.last_config_change());
+        op(&mut mpu);
+        assert_eq!(
+            (mpu.config_epoch(), mpu.last_config_change()),
+            before,
+            "{}",
+            name
+        );
+    }
+}
+
+#[test]
+fn tracked_values_revalidate_or_are_refused() {
+    let mut mpu = driver();
+    let pipeline: Tracked<Pipeline> = mpu.tracked(Mpu::pipeline);
+    let resolution: Tracked<ResolutionInfo> = mpu.tracked(Mpu::current_resolution);
+    assert_eq!(pipeline.epoch(), mpu.config_epoch());
+    assert!(pipeline.is_current(&mpu));
+    assert_eq!(
+        *resolution.get::<_, _, ()>(&mpu).unwrap(),
+        mpu.current_resolution()
+    );
+
+    // same values, an unchanged configuration keeps them
+    mpu.get_acc().unwrap();
+    assert!(pipeline.is_current(&mpu) && resolution.is_current(&mpu));
+
+    mpu.set_accel_range(AccelRange::G4).unwrap();
+    let captured = pipeline.epoch();
+    let current = mpu.config_epoch();
+    for stale in [
+        pipeline.get::<_, _, ()>(&mpu).map(drop),
+        resolution.get::<_, _, ()>(&mpu).map(drop),
+    ] {
+        assert!(matches!(
+            stale,
+            Err(Mpu6050Error::StaleConfiguration { captured: c, current: n })
+                if (c, n) == (captured, current)
+        ));
+    }
+    let error: Mpu6050Error<std::convert::Infallible> =
+        Mpu6050Error::StaleConfiguration { captured, current };
+    assert_eq!(
+        error.to_string(),
+        format!(
+            "stale configuration, derived at epoch {}, driver at {}",
+            captured, current
+        )
+    );
+    // unchecked, the value of its epoch
+    assert_eq!(pipeline.into_inner().acc_scale.nominal, 16384.);
+
+    let pipeline = mpu.tracked(Mpu::pipeline);
+    assert_eq!(
+        pipeline.get::<_, _, ()>(&mpu).unwrap().acc_scale.nominal,
+        8192.
+    );
+    assert_eq!(mpu.debug_state().config_epoch, current);
+    assert_eq!(
+        mpu.debug_state().last_config_change,
+        Some(ConfigChange::AccelRange)
+    );
+}
+
+#[test]
+fn stale_fifo_schema_is_refused_before_the_bus() {
+    let bus = SharedBus::new(&[DEFAULT_SLAVE_ADDR]);
+    let mut mpu = Mpu6050Builder::new().i2c(bus.clone()).build().unwrap();
+    mpu.init(&mut NoDelay).unwrap();
+    let schema = mpu
+        .set_fifo_sources(FifoSources::NONE.with_accel(true))
+        .unwrap();
+    assert!(schema.is_current(&mpu));
+
+    // same layout and scaling, still a configuration change
+    mpu.set_dlpf(1).unwrap();
+    bus.take_log();
+    assert!(matches!(
+        mpu.drain_fifo(&schema, &mut [0; 12], |_| ()),
+        Err(Mpu6050Error::StaleConfiguration { captured, .. }) if captured == schema.generation
+    ));
+    assert!(bus.take_log().is_empty());
+    let current = mpu.fifo_schema();
+    assert_eq!(
+        (current.sources(), current.frame_len()),
+        (schema.sources(), schema.frame_len())
+    );
+    assert_eq!(mpu.drain_fifo(&current, &mut [0; 12], |_| ()).unwrap(), 0);
+}
@@ -0,0 +1,338 @@
+//! Configuration change events: values and attribution per source through scripted
+//! scenarios, the queue bound and the driver without an observer, see the `config_events`
+//! module.
+
+mod common;
+
+use mpu6050::chaos::{ChaosConfig, ChaosHandle, FaultKind, FlakyI2c};
+use mpu6050::config_events::*;
+use mpu6050::device::*;
+use mpu6050::governor::*;
+use mpu6050::recovery::*;
+use mpu6050::script::{RegisterScript, ScriptStep};
+use mpu6050::settings::Mpu6050Settings;
+use mpu6050::settling::SettlingPolicy;
+use mpu6050::*;
+
+use common::{NoDelay, SharedBus};
+
+const ADDR: u8 = DEFAULT_SLAVE_ADDR;
+
+fn initialized(bus: &SharedBus) -> Mpu6050<SharedBus> {
+    let mut mpu = Mpu6050Builder::new().i2c(bus.clone()).build().unwrap();
+    mpu.init(&mut NoDelay).unwrap();
+    mpu.set_settling_policy(SettlingPolicy::Ignore);
+    mpu
+}
+
+fn observed(bus: &SharedBus) -> Mpu6050<SharedBus> {
+    let mut mpu = initialized(bus);
+    assert!(mpu
+        .set_config_observer(Some(ConfigObserver::new()))
+        .is_none());
+    mpu
+}
+
+fn drain<I, D>(mpu: &mut Mpu6050<I, D>) -> Vec<ConfigChangeEvent> {
+    mpu.config_observer().unwrap().drain().collect()
+}
+
+/// field, old and new of `events`
+fn changes(events: &[ConfigChangeEvent]) -> Vec<(ConfigField, ConfigValue, ConfigValue)> {
+    events.iter().map(|e| (e.field, e.old, e.new)).collect()
+}
+
+fn sources(events: &[ConfigChangeEvent]) -> Vec<ChangeSource> {
+    events.iter().map(|e| e.source).collect()
+}
+
+use ConfigValue::Code;
+
+#[test]
+fn calls_of_the_application_report_each_changed_field() {
+    let bus = SharedBus::new(&[ADDR]);
+    let mut mpu = observed(&bus);
+
+    mpu.set_accel_range(AccelRange::G8).unwrap();
+    mpu.set_dlpf(3).unwrap();
+    // written again without a change, the epoch advances, nothing is reported
+    mpu.set_accel_range(AccelRange::G8).unwrap();
+    let epoch = mpu.config_epoch();
+    mpu.set_gyro_scale_factors([1., 1.01, 1.].into());
+
+    let events = drain(&mut mpu);
+    assert_eq!(
+        changes(&events),
+        [
+            (ConfigField::AccelRange, Code(0), Code(2)),
+            (ConfigField::Dlpf, Code(0), Code(3)),
+            (
+                ConfigField::GyroScaleFactors,
+                ConfigValue::Vector([1.; 3]),
+                ConfigValue::Vector([1., 1.01, 1.]),
+            ),
+        ]
+    );
+    assert!(sources(&events).iter().all(|s| *s == ChangeSource::UserApi));
+    assert_eq!(events[2].epoch, epoch + 1);
+    assert_eq!(
+        events[2].field.change(),
+        config_epoch::ConfigChange::ScaleFactors
+    );
+
+    // a reset reports what it restored, in field order
+    mpu.set_sample_rate_divider(4).unwrap();
+    mpu.reset_device(&mut NoDelay).unwrap();
+    let events = drain(&mut mpu);
+    assert_eq!(
+        changes(&events),
+        [
+            (ConfigField::SampleRateDivider, Code(0), Code(4)),
+            (ConfigField::AccelRange, Code(2), Code(0)),
+            (ConfigField::Dlpf, Code(3), Code(0)),
+            (ConfigField::SampleRateDivider, Code(4), Code(0)),
+        ]
+    );
+    assert!(mpu.config_observer().unwrap().is_empty());
+}
+
+#[test]
+fn governor_transitions_are_attributed_to_the_governor() {
+    let bus = SharedBus::new(&[ADDR]);
+    let mut mpu = observed(&bus);
+    let high = Mpu6050Settings::new()
+        .with_accel_range(AccelRange::G8)
+        .with_gyro_range(GyroRange::D1000)
+        .with_dlpf_cfg(3)
+        .with_sample_rate_div(3);
+    let activity = |level: f32| ActivityLevel {
+        gyro_rms: level,
+        acc_variance: level / 10.,
+    };
+    let config = GovernorConfig::two(
+        OperatingPoint::new("idle", presets::HANDHELD_UI, 0),
+        OperatingPoint::new("active", high, 0),
+        GovernorRule {
+            up: activity(1.),
+            down: activity(0.5),
+        },
+    );
+    // installing applies the first point, the application's call
+    mpu.set_power_governor(PowerGovernor::new(config, 0).unwrap())
+        .unwrap();
+    let installed = drain(&mut mpu);
+    assert_eq!(
+        changes(&installed),
+        [
+            (ConfigField::Dlpf, Code(0), Code(5)),
+            (ConfigField::SampleRateDivider, Code(0), Code(9)),
+        ]
+    );
+    assert!(sources(&installed)
+        .iter()
+        .all(|s| *s == ChangeSource::UserApi));
+
+    assert!(mpu.governor_motion_wake().unwrap().is_some());
+    let events = drain(&mut mpu);
+    assert_eq!(
+        changes(&events),
+        [
+            (ConfigField::AccelRange, Code(0), Code(2)),
+            (ConfigField::GyroRange, Code(0), Code(2)),
+            (ConfigField::Dlpf, Code(5), Code(3)),
+            (ConfigField::SampleRateDivider, Code(9), Code(3)),
+        ]
+    );
+    assert!(sources(&events)
+        .iter()
+        .all(|s| *s == ChangeSource::Governor));
+
+    // the source ends with the transition
+    mpu.set_gyro_range(GyroRange::D250).unwrap();
+    assert_eq!(sources(&drain(&mut mpu)), [ChangeSource::UserApi]);
+}
+
+#[test]
+fn script_writes_to_cached_fields_are_attributed_to_the_script() {
+    let bus = SharedBus::new(&[ADDR]);
+    let mut mpu = observed(&bus);
+    let script = RegisterScript::new()
+        .with_step(ScriptStep::WriteReg {
+            reg: SMPLRT_DIV,
+            value: 7,
+        })
+        .with_step(ScriptStep::WriteBits {
+            reg: ACCEL_CONFIG::ADDR,
+            start_bit: ACCEL_CONFIG::FS_SEL.bit,
+            length: ACCEL_CONFIG::FS_SEL.length,
+            value: 3,
+        });
+    mpu.run_script(&script, &mut NoDelay).unwrap();
+
+    let events = drain(&mut mpu);
+    assert_eq!(
+        changes(&events),
+        [
+            (ConfigField::SampleRateDivider, Code(0), Code(7)),
+            (ConfigField::AccelRange, Code(0), Code(3)),
+        ]
+    );
+    assert!(sources(&events).iter().all(|s| *s == ChangeSource::Script));
+}
+
+#[test]
+fn adopting_takes_the_device_configuration_without_writing() {
+    let bus = SharedBus::new(&[ADDR]);
+    let mut mpu = observed(&bus);
+    // a bootloader configured the device
+    bus.device(ADDR, |m| {
+        m.regs[SMPLRT_DIV as usize] = 19;
+        m.regs[CONFIG::ADDR as usize] = 2;
+        m.regs[GYRO_CONFIG::ADDR as usize] = 0b01 << 3;
+    });
+    bus.take_log();
+
+    let settings = mpu.adopt_device_settings().unwrap();
+    assert_eq!(settings.sample_rate_div, 19);
+    assert!(bus.take_log().iter().all(|access| access.read));
+    assert_eq!(mpu.get_gyro_range().unwrap(), GyroRange::D500);
+    assert_eq!(mpu.debug_state().gyro_range, GyroRange::D500);
+
+    let events = drain(&mut mpu);
+    assert_eq!(
+        changes(&events),
+        [
+            (ConfigField::GyroRange, Code(0), Code(1)),
+            (ConfigField::Dlpf, Code(0), Code(2)),
+            (ConfigField::SampleRateDivider, Code(0), Code(19)),
+        ]
+    );
+    assert!(sources(&events).iter().all(|s| *s == ChangeSource::Adopt));
+}
+
+fn classify() -> BusErrorKind {
+    BusErrorKind::Bus
+}
+
+fn bus_reset() -> BusRecoveryOutcome {
+    BusRecoveryOutcome::Recovered
+}
+
+#[test]
+fn resync_reports_the_restored_fields_with_the_values_found() {
+    let bus = SharedBus::new(&[ADDR]);
+    let (flaky, chaos): (_, ChaosHandle) = FlakyI2c::new(
+        bus.clone(),
+        ChaosConfig::NONE.with_fault(FaultKind::BusError),
+    );
+    let mut mpu = Mpu6050Builder::new().i2c(flaky).build().unwrap();
+    mpu.init(&mut NoDelay).unwrap();
+    mpu.set_settling_policy(SettlingPolicy::Ignore);
+    mpu.set_bus_recovery(Some(
+        BusRecovery::new(bus_reset)
+            .with_classifier(classify)
+            .with_detection(LockupDetection::new(4, 4).with_min_interval(0)),
+    ));
+    mpu.set_accel_range(AccelRange::G8).unwrap();
+    mpu.set_sample_rate_divider(4).unwrap();
+    mpu.set_config_observer(Some(ConfigObserver::new()));
+    // the glitch locking the bus also changed the accelerometer range and the divider
+    bus.device(ADDR, |m| {
+        m.regs[ACCEL_CONFIG::ADDR as usize] = 0b01 << 3;
+        m.regs[SMPLRT_DIV as usize] = 0;
+    });
+
+    chaos.fail_next(4);
+    for _ in 0..4 {
+        assert!(mpu.get_acc().is_err());
+    }
+    mpu.get_acc().unwrap();
+
+    let events = drain(&mut mpu);
+    assert_eq!(
+        changes(&events),
+        [
+            (ConfigField::SampleRateDivider, Code(0), Code(4)),
+            (ConfigField::AccelRange, Code(1), Code(2)),
+        ]
+    );
+    assert!(sources(&events).iter().all(|s| *s == ChangeSource::Resync));
+}
+
+#[test]
+fn a_full_queue_keeps_the_newest_and_counts_the_overwritten() {
+    let bus = SharedBus::new(&[ADDR]);
+    let mut mpu = observed(&bus);
+    let extra = 3;
+    for div in 1..=(ConfigObserver::CAPACITY + extra) as u8 {
+        mpu.set_sample_rate_divider(div).unwrap();
+    }
+
+    let status = mpu.debug_state().config_observer.unwrap();
+    assert_eq!(status.pending, ConfigObserver::CAPACITY);
+    assert_eq!(status.dropped, extra as u32);
+    let observer = mpu.config_observer().unwrap();
+    let first = observer.pop().unwrap();
+    assert_eq!(
+        (first.old, first.new),
+        (Code(extra as u8), Code(extra as u8 + 1))
+    );
+    assert_eq!(observer.drain().count(), ConfigObserver::CAPACITY - 1);
+    // draining leaves the count
+    assert_eq!(observer.dropped(), extra as u32);
+
+    // unregistering hands back what was left
+    mpu.set_sample_rate_divider(0).unwrap();
+    let observer = mpu.set_config_observer(None).unwrap();
+    assert_eq!(observer.len(), 1);
+    assert!(mpu.config_observer().is_none());
+}
+
+#[test]
+fn without_an_observer_nothing_is_recorded_and_the_traffic_is_the_same() {
+    let run = |mpu: &mut Mpu6050<SharedBus>| {
+        mpu.set_accel_range(AccelRange::G4).unwrap();
+        mpu.set_dlpf(2).unwrap();
+        mpu.apply_acc_trim([0., 0., 0.01].into());
+        mpu.adopt_device_settings().unwrap();
+    };
+    let plain_bus = SharedBus::new(&[ADDR]);
+    let mut plain = initialized(&plain_bus);
+    plain_bus.take_log();
+    run(&mut plain);
+    assert!(plain.debug_state().config_observer.is_none());
+
+    let observed_bus = SharedBus::new(&[ADDR]);
+    let mut mpu = observed(&observed_bus);
+    observed_bus.take_log();
+    run(&mut mpu);
+    assert_eq!(plain_bus.take_log(), observed_bus.take_log());
+    assert_eq!(plain.config_epoch(), mpu.config_epoch());
+
+    // an observer registered later starts from the configuration then
+    plain.set_config_observer(Some(ConfigObserver::new()));
+    assert!(plain.config_observer().unwrap().is_empty());
+    plain.set_accel_range(AccelRange::G16).unwrap();
+    assert_eq!(
+        changes(&drain(&mut plain)),
+        [(ConfigField::AccelRange, Code(1), Code(3))]
+    );
+}
+
+#[cfg(all(feature = "serde", feature = "platform-config"))]
+#[test]
+fn events_round_trip_through_serde() {
+    let event = ConfigChangeEvent {
+        field: ConfigField::AccelOffset,
+        old: ConfigValue::Vector([0.; 3]),
+        new: ConfigValue::Vector([0., 0., 0.01]),
+        source: ChangeSource::Governor,
+        epoch: 7,
+    };
+    let json = serde_json::to_string(&event).unwrap();
+    assert_eq!(
+        serde_json::from_str::<ConfigChangeEvent>(&json).unwrap(),
+        event
+    );
+    assert!(core::mem::size_of::<ConfigChangeEvent>() <= 40);
+}
@@ -0,0 +1,194 @@
+//! The eMPL profile against the reference vectors of `tests/golden/empl_vectors.txt`, within
+//! one LSB, and the documented divergences of the native pipeline, see the `conformance`
+//! module.
+
+use mpu6050::axis_map::AxisMap;
+use mpu6050::conformance::ConformanceProfile;
+use mpu6050::device::{AccelRange, Axis, GyroRange};
+use mpu6050::scale::{self, Pipeline, PipelineStages};
+use mpu6050::Vec3A;
+
+const VECTORS: &str = include_str!("golden/empl_vectors.txt");
+
+const Q16: f32 = 65536.;
+const RAD_PER_DEG: f32 = core::f32::consts::PI / 180.;
+
+struct Vector {
+    line: usize,
+    accel: AccelRange,
+    gyro_range: GyroRange,
+    mounting: AxisMap,
+    acc_bias: Vec3A,
+    gyro_bias: Vec3A,
+    acc: [i16; 3],
+    gyro: [i16; 3],
+    temp: i16,
+    acc_q16: [i32; 3],
+    gyro_q16: [i32; 3],
+    temp_q16: i32,
+}
+
+impl Vector {
+    fn empl(&self) -> Pipeline {
+        let mut pipeline = Pipeline::new(self.accel, self.gyro_range)
+            .with_profile(ConformanceProfile::Empl)
+            .with_orientation(self.mounting);
+        // the vectors add their bias, offsets are subtracted
+        pipeline.acc_offset = -self.acc_bias;
+        pipeline.gyro_offset = -self.gyro_bias;
+        pipeline
+    }
+}
+
+fn mounting(text: &str) -> AxisMap {
+    let mut axes = [Axis::X; 3];
+    let mut negate = [false; 3];
+    for (i, pair) in text.as_bytes().chunks(2).enumerate() {
+        negate[i] = pair[0] == b'-';
+        axes[i] = Axis::ALL[(pair[1] - b'X') as usize];
+    }
+    AxisMap::new(axes, negate)
+}
+
+fn vectors() -> Vec<Vector> {
+    VECTORS
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.starts_with('#'))
+        .map(|(i, line)| {
+            let cols: Vec<Vec<&str>> = line
+                .split(" | ")
+                .map(|col| col.split(' ').collect())
+                .collect();
+            let vec3 = |col: usize| {
+                let v: Vec<f32> = cols[col].iter().map(|x| x.parse().unwrap()).collect();
+                Vec3A::new(v[0], v[1], v[2])
+            };
+            let int3 = |col: usize| -> [i32; 3] {
+                let v: Vec<i32> = cols[col].iter().map(|x| x.parse().unwrap()).collect();
+                [v[0], v[1], v[2]]
+            };
+            let counts = |col: usize| int3(col).map(|x| x as i16);
+            let accel = match cols[0][0] {
+                "2" => AccelRange::G2,
+                "4" => AccelRange::G4,
+                "8" => AccelRange::G8,
+                _ => AccelRange::G16,
+            };
+            let gyro_range = match cols[0][1] {
+                "250" => GyroRange::D250,
+                "500" => GyroRange::D500,
+                "1000" => GyroRange::D1000,
+                _ => GyroRange::D2000,
+            };
+            Vector {
+                line: i + 1,
+                accel,
+                gyro_range,
+                mounting: mounting(cols[0][2]),
+                acc_bias: vec3(1),
+                gyro_bias: vec3(2),
+                acc: counts(3),
+                gyro: counts(4),
+                temp: cols[5][0].parse().unwrap(),
+                acc_q16: int3(6),
+                gyro_q16: int3(7),
+                temp_q16: cols[8][0].parse().unwrap(),
+            }
+        })
+        .collect()
+}
+
+/// `actual` within `lsb` of the q16 `expected`, and on the q16 grid
+fn assert_close(actual: Vec3A, expected: [i32; 3], lsb: f32, what: &str, line: usize) {
+    for axis in 0..3 {
+        let expected = expected[axis] as f32 / Q16;
+        assert!(
+            (actual[axis] - expected).abs() <= lsb,
+            "line {} {} axis {}: {} vs {}",
+            line,
+            what,
+            axis,
+            actual[axis],
+            expected
+        );
+        assert_eq!((actual[axis] * Q16).fract(), 0., "line {} {}", line, what);
+    }
+}
+
+#[test]
+fn empl_profile_matches_the_reference_vectors() {
+    let vectors = vectors();
+    assert_eq!(vectors.len(), 32);
+    for v in &vectors {
+        let empl = v.empl();
+        let acc_lsb = 1. / empl.acc_scale.nominal;
+        let gyro_lsb = 1. / empl.gyro_scale.nominal;
+        assert_close(empl.acc(v.acc), v.acc_q16, acc_lsb, "acc", v.line);
+        assert_close(empl.gyro(v.gyro), v.gyro_q16, gyro_lsb, "gyro", v.line);
+        let expected = v.temp_q16 as f32 / Q16;
+        assert!(
+            (empl.temp(v.temp) - expected).abs() <= 1. / 340.,
+            "line {} temp",
+            v.line
+        );
+    }
+}
+
+#[test]
+fn native_temperature_is_lower_on_every_vector() {
+    for v in vectors() {
+        let native = Pipeline::new(v.accel, v.gyro_range);
+        let divergence = v.temp_q16 as f32 / Q16 - native.temp(v.temp);
+        assert!(
+            (0.0015..0.0035).contains(&divergence),
+            "line {}: {}",
+            v.line,
+            divergence
+        );
+    }
+}
+
+#[test]
+fn empl_offsets_are_in_mounting_axes_and_degrees() {
+    for v in vectors() {
+        let empl = v.empl();
+        // the same correction natively: chip axes and rad/s, no quantization
+        let mut native = Pipeline::new(v.accel, v.gyro_range).with_orientation(v.mounting);
+        let inverse = |bias: Vec3A| {
+            let (axes, negate) = v.mounting.axes();
+            let mut chip = Vec3A::ZERO;
+            for i in 0..3 {
+                chip[axes[i] as usize] = if negate[i] { -bias[i] } else { bias[i] };
+            }
+            chip
+        };
+        native.acc_offset = -inverse(v.acc_bias);
+        native.gyro_offset = -inverse(v.gyro_bias) * RAD_PER_DEG;
+        let acc_lsb = 1. / empl.acc_scale.nominal;
+        let gyro_lsb = 1. / empl.gyro_scale.nominal;
+        let acc = native.acc(v.acc) - empl.acc(v.acc);
+        let gyro = native.gyro(v.gyro) / RAD_PER_DEG - empl.gyro(v.gyro);
+        assert!(acc.abs().max_element() <= acc_lsb, "line {}", v.line);
+        assert!(gyro.abs().max_element() <= gyro_lsb, "line {}", v.line);
+    }
+}
+
+#[test]
+fn native_stages_are_the_plain_functions() {
+    assert_eq!(
+        ConformanceProfile::default().stages(),
+        PipelineStages::NATIVE
+    );
+    for v in vectors() {
+        let plain = Pipeline::new(v.accel, v.gyro_range);
+        let native = v.empl().with_profile(ConformanceProfile::Native);
+        assert_eq!(native.stages.orientation, v.mounting);
+        let native = native.with_orientation(AxisMap::IDENTITY);
+        let acc = scale::acc_uncorrected(v.acc, &plain.acc_scale);
+        let gyro = scale::gyro_uncorrected(v.gyro, &plain.gyro_scale);
+        assert_eq!(native.acc(v.acc), acc + v.acc_bias);
+        assert_eq!(native.gyro(v.gyro), gyro + v.gyro_bias);
+        assert_eq!(native.temp(v.temp), scale::temp(v.temp));
+    }
+}
@@ -0,0 +1,126 @@
+//! Configuration tables in const context: a static settings table applied at runtime, a
+//! builder template, the 24 mountings, and const items forcing their evaluation at compile
+//! time, see the `settings` module.
+
+mod common;
+
+use mpu6050::axis_map::AxisMap;
+use mpu6050::device::*;
+use mpu6050::presets;
+use mpu6050::settings::Mpu6050Settings;
+use mpu6050::*;
+
+use common::{NoDelay, SharedBus};
+
+/// one entry per board revision, in rodata
+static BOARD_CONFIGS: [Mpu6050Settings; 3] = [
+    presets::HANDHELD_UI,
+    Mpu6050Settings::new()
+        .with_accel_range(AccelRange::G8)
+        .with_gyro_range(GyroRange::D1000)
+        .with_dlpf_cfg(2)
+        .with_sample_rate_div(4),
+    presets::LOW_POWER_TILT.with_cycle(Some(LP_WAKE_CTRL::_1P25)),
+];
+
+/// mounted upside down, chip X and Y swapped
+const MOUNTING: AxisMap = AxisMap::new([Axis::Y, Axis::X, Axis::Z], [false, false, true]);
+
+/// every part of the builder but the bus
+const TEMPLATE: Mpu6050Builder<SharedBus> = Mpu6050Builder::new()
+    .slave_addr(0x69)
+    .acc_sensitivity(AccelRange::G4)
+    .gyro_sensitivity(GyroRange::D500)
+    .gyro_offset_array([0.01, -0.02, 0.03])
+    .acc_offset_array([0.001, 0., -0.002])
+    .acc_scale_factors_array([1.01, 0.99, 1.])
+    .gyro_scale_factors_array([1., 1.02, 0.98])
+    .disconnect_threshold(5)
+    .auto_disconnect(true)
+    .strict_configuration(false)
+    .rounding(conversion::RoundingMode::Truncate);
+
+// evaluated by the compiler, a non-const call or a failed check does not build
+const _: () = {
+    let mut i = 0;
+    while i < BOARD_CONFIGS.len() {
+        assert!(BOARD_CONFIGS[i].validate().is_ok());
+        i += 1;
+    }
+    assert!(Mpu6050Settings::new().with_dlpf_cfg(7).validate().is_err());
+    assert!(MOUNTING.is_rotation());
+    assert!(AxisMap::ROTATIONS[23].is_rotation());
+    let (axes, negate) = AxisMap::ROTATIONS[0].axes();
+    assert!(axes[2] as u8 == Axis::Z as u8 && !negate[2]);
+};
+
+fn driver(builder: Mpu6050Builder<SharedBus>, addr: u8) -> (Mpu6050<SharedBus>, SharedBus) {
+    let bus = SharedBus::new(&[addr]);
+    let mut mpu = builder.i2c(bus.clone()).build().unwrap();
+    mpu.init(&mut NoDelay).unwrap();
+    (mpu, bus)
+}
+
+#[test]
+fn static_table_entries_apply_at_runtime() {
+    for settings in &BOARD_CONFIGS {
+        let (mut mpu, _bus) = driver(Mpu6050Builder::new(), DEFAULT_SLAVE_ADDR);
+        mpu.apply_settings(settings).unwrap();
+        assert_eq!(mpu.read_settings().unwrap(), *settings);
+    }
+}
+
+#[test]
+fn const_template_builds_the_runtime_driver() {
+    let runtime = Mpu6050Builder::new()
+        .slave_addr(0x69)
+        .acc_sensitivity(AccelRange::G4)
+        .gyro_sensitivity(GyroRange::D500)
+        .gyro_offset([0.01, -0.02, 0.03])
+        .acc_offset(Vec3A::new(0.001, 0., -0.002))
+        .acc_scale_factors(Vec3A::new(1.01, 0.99, 1.))
+        .gyro_scale_factors(Vec3A::new(1., 1.02, 0.98))
+        .disconnect_threshold(5)
+        .auto_disconnect(true)
+        .rounding(conversion::RoundingMode::Truncate);
+    let (from_const, _) = driver(TEMPLATE, 0x69);
+    let (from_runtime, _) = driver(runtime, 0x69);
+    let (a, b) = (from_const.debug_state(), from_runtime.debug_state());
+    assert_eq!(a.slave_addr, 0x69);
+    assert_eq!(a.gyro_offset, Vec3A::new(0.01, -0.02, 0.03));
+    assert_eq!(a.acc_scale.per_axis, Vec3A::new(1.01, 0.99, 1.));
+    assert_eq!(format!("{:?}", a), format!("{:?}", b));
+}
+
+#[test]
+fn rotations_are_the_24_mountings() {
+    let rotations = AxisMap::ROTATIONS;
+    assert_eq!(rotations[0], AxisMap::IDENTITY);
+    assert!(rotations.contains(&MOUNTING));
+    for (i, map) in rotations.iter().enumerate() {
+        assert!(map.is_rotation(), "{:?}", map);
+        assert!(!rotations[..i].contains(map), "{:?} twice", map);
+        // a rotation keeps the handedness: X × Y = Z
+        let x = map.apply(Vec3A::X);
+        let y = map.apply(Vec3A::Y);
+        assert_eq!(x.cross(y), map.apply(Vec3A::Z), "{:?}", map);
+    }
+    // every map that is a rotation is listed
+    let all = Axis::ALL;
+    let mut count = 0;
+    for a in all {
+        for b in all {
+            for c in all {
+                for signs in 0..8 {
+                    let negate = [signs & 1 != 0, signs & 2 != 0, signs & 4 != 0];
+                    let map = AxisMap::new([a, b, c], negate);
+                    if map.is_rotation() {
+                        assert!(rotations.contains(&map), "{:?}", map);
+                        count += 1;
+                    }
+                }
+            }
+        }
+    }
+    assert_eq!(count, 24);
+}
@@ -0,0 +1,301 @@
+//! Continuity token: every audited power transition advances it with its cause, the other
+//! operations keep it, and the complementary filter re-enters across scripted sleep and
+//! wake cycles without an orientation jump, see the `continuity` module and the re-entry
+//! section of `orientation`.
+
+mod common;
+
+use mpu6050::continuity::ContinuityBreak;
+use mpu6050::device::*;
+use mpu6050::governor::*;
+use mpu6050::orientation::{ComplementaryFilter, InitCriteria, ReentryYaw};
+use mpu6050::presets;
+use mpu6050::register::Register;
+use mpu6050::settings::Mpu6050Settings;
+use mpu6050::*;
+
+use common::{NoDelay, SharedBus};
+
+type Mpu = Mpu6050<SharedBus>;
+type Op = fn(&mut Mpu);
+
+const DT: f32 = 0.01;
+/// gyro bias left in the readings, 0.3 °/s
+const BIAS: Vec3A = Vec3A::new(0.005, -0.003, 0.002);
+/// minutes asleep
+const SLEEP_S: f32 = 600.;
+
+fn driver() -> Mpu {
+    let bus = SharedBus::new(&[DEFAULT_SLAVE_ADDR]);
+    let mut mpu = Mpu6050Builder::new().i2c(bus).build().unwrap();
+    mpu.init(&mut NoDelay).unwrap();
+    mpu
+}
+
+fn cycling() -> Mpu6050Settings {
+    presets::LOW_POWER_TILT
+}
+
+fn governor() -> PowerGovernor {
+    let rule = GovernorRule {
+        up: ActivityLevel {
+            gyro_rms: 0.3,
+            acc_variance: 0.01,
+        },
+        down: ActivityLevel {
+            gyro_rms: 0.1,
+            acc_variance: 0.002,
+        },
+    };
+    let config = GovernorConfig::two(
+        OperatingPoint::new("rest", presets::LOW_POWER_TILT, 0),
+        OperatingPoint::new("active", presets::HANDHELD_UI, 0),
+        rule,
+    );
+    PowerGovernor::new(config, 0).unwrap()
+}
+
+/// the audited list of the `continuity` module, each operation from an awake driver
+fn transitions() -> [(&'static str, ContinuityBreak, Op); 10] {
+    [
+        ("set_sleep_enabled(true)", ContinuityBreak::Sleep, |mpu| {
+            mpu.set_sleep_enabled(true).unwrap()
+        }),
+        ("shutdown", ContinuityBreak::Sleep, |mpu| {
+            mpu.shutdown(&mut NoDelay).map(drop).unwrap()
+        }),
+        ("set_sleep_enabled(false)", ContinuityBreak::Wake, |mpu| {
+            mpu.set_sleep_enabled(true).unwrap();
+            mpu.set_sleep_enabled(false).unwrap()
+        }),
+        ("init", ContinuityBreak::Wake, |mpu| {
+            mpu.init(&mut NoDelay).unwrap()
+        }),
+        ("apply_settings", ContinuityBreak::CycleEntered, |mpu| {
+            mpu.apply_settings(&cycling()).unwrap()
+        }),
+        ("apply_settings_diff", ContinuityBreak::CycleLeft, |mpu| {
+            mpu.apply_settings(&cycling()).unwrap();
+            mpu.apply_settings_diff(&cycling(), &Mpu6050Settings::new())
+                .unwrap()
+        }),
+        ("set_power_governor", ContinuityBreak::CycleEntered, |mpu| {
+            mpu.set_power_governor(governor()).unwrap()
+        }),
+        ("governor_motion_wake", ContinuityBreak::CycleLeft, |mpu| {
+            mpu.set_power_governor(governor()).unwrap();
+            mpu.governor_motion_wake().unwrap().unwrap();
+        }),
+        ("reset_device", ContinuityBreak::Reset, |mpu| {
+            mpu.reset_device(&mut NoDelay).unwrap()
+        }),
+        ("try_reconnect", ContinuityBreak::Reconnect, |mpu| {
+            mpu.try_reconnect(&mut NoDelay).map(drop).unwrap()
+        }),
+    ]
+}
+
+#[test]
+fn every_transition_advances_the_token() {
+    let fresh = Mpu6050Builder::new()
+        .i2c(SharedBus::new(&[DEFAULT_SLAVE_ADDR]))
+        .build()
+        .unwrap();
+    assert_eq!(
+        (fresh.continuity_token(), fresh.last_continuity_break()),
+        (0, None)
+    );
+
+    for (name, cause, op) in transitions() {
+        let mut mpu = driver();
+        let before = mpu.continuity_token();
+        op(&mut mpu);
+        assert_ne!(mpu.continuity_token(), before, "{}", name);
+        assert_eq!(mpu.last_continuity_break(), Some(cause), "{}", name);
+        assert_eq!(
+            mpu.debug_state().continuity_token,
+            mpu.continuity_token(),
+            "{}",
+            name
+        );
+    }
+    // the table names every break
+    for cause in ContinuityBreak::ALL {
+        assert!(
+            transitions().iter().any(|(_, c, _)| *c == cause),
+            "{}",
+            cause
+        );
+    }
+}
+
+#[test]
+fn other_operations_keep_the_token() {
+    let ops: [(&str, Op); 8] = [
+        ("set_sleep_enabled(false) awake", |mpu| {
+            mpu.set_sleep_enabled(false).unwrap()
+        }),
+        ("set_sleep_enabled(true) asleep", |mpu| {
+            mpu.set_sleep_enabled(true).unwrap()
+        }),
+        ("set_accel_range", |mpu| {
+            mpu.set_accel_range(AccelRange::G8).unwrap()
+        }),
+        ("set_gyro_range", |mpu| {
+            mpu.set_gyro_range(GyroRange::D1000).unwrap()
+        }),
+        ("apply_settings without cycle", |mpu| {
+            mpu.apply_settings(&presets::HANDHELD_UI).unwrap()
+        }),
+        ("apply_settings cycling again", |mpu| {
+            mpu.apply_settings(&cycling().with_dlpf_cfg(3)).unwrap()
+        }),
+        ("write_register", |mpu| {
+            mpu.write_register(Register::PWR_MGMT_1, 0x40).unwrap()
+        }),
+        ("reads", |mpu| {
+            mpu.get_acc().unwrap();
+            mpu.get_gyro().unwrap();
+            mpu.get_temp().unwrap();
+        }),
+    ];
+    for (name, op) in ops {
+        let mut mpu = driver();
+        // the second sleep and the second cycling settings find the state already there
+        match name {
+            "set_sleep_enabled(true) asleep" => mpu.set_sleep_enabled(true).unwrap(),
+            "apply_settings cycling again" => mpu.apply_settings(&cycling()).unwrap(),
+            _ => {}
+        }
+        let (token, cause) = (mpu.continuity_token(), mpu.last_continuity_break());
+        op(&mut mpu);
+        assert_eq!(mpu.continuity_token(), token, "{}", name);
+        assert_eq!(mpu.last_continuity_break(), cause, "{}", name);
+    }
+}
+
+/// a filter settled for 10 s at `roll_deg` and `yaw_deg` with the bias left in the gyro
+fn settled(roll_deg: f32, yaw_deg: f32, token: u32) -> ComplementaryFilter {
+    let mut filter = ComplementaryFilter::new(0.98);
+    let q = euler::from_xyz_rpy(roll_deg.to_radians(), 0., yaw_deg.to_radians());
+    filter.reset_to(q);
+    for _ in 0..1000 {
+        filter.update_with_token(BIAS, synthetic::at_rest(roll_deg, 0.), DT, token);
+    }
+    filter
+}
+
+/// the largest step of the orientation over `updates` of (gyro, acc, dt, token)
+fn largest_step(
+    filter: &mut ComplementaryFilter,
+    updates: impl IntoIterator<Item = (Vec3A, Vec3A, f32, u32)>,
+) -> f32 {
+    let mut previous = filter.estimate().orientation;
+    let mut largest = 0f32;
+    for (gyro, acc, dt, token) in updates {
+        let q = filter.update_with_token(gyro, acc, dt, token);
+        largest = largest.max(q.angle_between(previous));
+        previous = q;
+    }
+    largest
+}
+
+#[test]
+fn sleep_and_wake_reenter_without_a_jump() {
+    let mut mpu = driver();
+    let asleep_at = mpu.continuity_token();
+    let mut filter = settled(10., 0., asleep_at);
+    let mut naive = filter;
+    assert_eq!(filter.continuity_token(), Some(asleep_at));
+    assert!(!filter.estimate().continuity_break);
+
+    mpu.set_sleep_enabled(true).unwrap();
+    mpu.set_sleep_enabled(false).unwrap();
+    let woken = mpu.continuity_token();
+    assert_ne!(woken, asleep_at);
+
+    // the first sample after the wake, dt since the last one before the sleep
+    let acc = synthetic::at_rest(10., 0.);
+    let before = naive.estimate().orientation;
+    naive.update(BIAS, acc, SLEEP_S);
+    assert!(naive.estimate().orientation.angle_between(before) > 1.);
+
+    let wake = (BIAS, acc, SLEEP_S, woken);
+    let burst = (1..InitCriteria::DEFAULT.min_samples).map(|_| (BIAS, acc, DT, woken));
+    let after = (0..500).map(|_| (BIAS, acc, DT, woken));
+    let step = largest_step(&mut filter, Some(wake).into_iter().chain(burst));
+    assert!(step < 0.5f32.to_radians(), "{}", step.to_degrees());
+    let estimate = filter.estimate();
+    assert!(!estimate.initializing && !estimate.continuity_break);
+    assert_eq!(estimate.reentries, 1);
+    let step = largest_step(&mut filter, after);
+    assert!(step < 0.5f32.to_radians(), "{}", step.to_degrees());
+    let roll = euler::to_xyz_rpy(filter.estimate().orientation).roll;
+    assert!((roll.to_degrees() - 10.).abs() < 0.5);
+
+    // a second cycle counts again, the same token does not
+    mpu.set_sleep_enabled(true).unwrap();
+    mpu.set_sleep_enabled(false).unwrap();
+    filter.update_with_token(BIAS, acc, SLEEP_S, mpu.continuity_token());
+    let estimate = filter.estimate();
+    assert!(estimate.continuity_break && estimate.initializing);
+    assert_eq!(estimate.reentries, 2);
+    filter.update_with_token(BIAS, acc, DT, mpu.continuity_token());
+    assert!(!filter.estimate().continuity_break);
+}
+
+#[test]
+fn reentry_holds_or_zeroes_the_yaw() {
+    for policy in [ReentryYaw::Hold, ReentryYaw::Zero] {
+        let mut filter = settled(0., 45., 1).with_reentry_yaw(policy);
+        assert_eq!(filter.reentry_yaw(), policy);
+        // the bias drifted the yaw of the settled filter by a degree
+        let held = euler::to_xyz_rpy(filter.estimate().orientation).yaw;
+        assert!((held.to_degrees() - 45.).abs() < 2.);
+        let yaw = match policy {
+            ReentryYaw::Hold => held,
+            ReentryYaw::Zero => 0.,
+        };
+        // moved to 20° of pitch while asleep
+        let acc = synthetic::at_rest(0., 20.);
+        filter.update_with_token(BIAS, acc, SLEEP_S, 2);
+        for _ in 1..InitCriteria::DEFAULT.min_samples {
+            assert!(filter.estimate().initializing);
+            filter.update_with_token(BIAS, acc, DT, 2);
+        }
+        let rpy = euler::to_xyz_rpy(filter.estimate().orientation);
+        assert!(!filter.estimate().initializing);
+        assert!((rpy.pitch.to_degrees() - 20.).abs() < 0.01, "{:?}", policy);
+        assert!((rpy.yaw - yaw).abs() < 1e-4, "{:?}", policy);
+    }
+}
+
+#[test]
+fn rejected_bursts_run_on_from_the_held_orientation() {
+    let mut filter = settled(10., 30., 1);
+    let held = filter.estimate().orientation;
+    // shaken on every wake reading: no burst passes
+    let attempts = InitCriteria::DEFAULT.max_attempts as u32;
+    for i in 0..InitCriteria::DEFAULT.min_samples * attempts {
+        let shake = if i % 2 == 0 { 0.3 } else { -0.3 };
+        filter.update_with_token(BIAS, synthetic::at_rest(10., 0.) + shake, DT, 2);
+        assert_eq!(filter.estimate().orientation, held);
+    }
+    assert!(!filter.estimate().initializing);
+    let rpy = euler::to_xyz_rpy(filter.estimate().orientation);
+    assert!((rpy.yaw.to_degrees() - 30.).abs() < 0.5);
+}
+
+#[test]
+fn samples_observe_the_token() {
+    let mut filter = ComplementaryFilter::new(0.98);
+    assert_eq!(filter.continuity_token(), None);
+    let sample = MpuSample::new(synthetic::at_rest(5., 0.), BIAS, 25.);
+    filter.update_sample_with_token(&sample, DT, 3);
+    assert_eq!(filter.continuity_token(), Some(3));
+    assert!(!filter.estimate().continuity_break);
+    filter.update_sample_with_token(&sample, SLEEP_S, 4);
+    let estimate = filter.estimate();
+    assert!(estimate.continuity_break && estimate.initializing);
+    assert_eq!(filter.continuity_token(), Some(4));
+}
@@ -0,0 +1,340 @@
+//! Conversions and their rounding: exhaustive round trips over the i16 range, the bias of
+//! each mode, and the legacy mode against the conversions it replaced, see the `conversion`
+//! module.
+
+mod common;
+
+use mpu6050::conversion::*;
+use mpu6050::device::{AccelRange, GyroRange, ACCEL_SENS, GYRO_SENS};
+use mpu6050::packed::*;
+use mpu6050::*;
+
+use common::RegisterMock;
+
+const MODES: [RoundingMode; 3] = [
+    RoundingMode::NearestEven,
+    RoundingMode::Truncate,
+    RoundingMode::Legacy,
+];
+
+fn all_counts() -> impl Iterator<Item = i16> {
+    i16::MIN..=i16::MAX
+}
+
+/// `back` is `count` or the count next to it toward zero
+fn truncated(count: i16, back: i16) -> bool {
+    back == count || back == count - count.signum()
+}
+
+#[test]
+fn counts_round_trip_through_scaled_values() {
+    for mode in MODES {
+        let check = |count: i16, back: i16, what: &str| match mode {
+            RoundingMode::Truncate => {
+                assert!(truncated(count, back), "{} {}: {}", what, count, back)
+            }
+            _ => assert_eq!(back, count, "{} {:?}", what, mode),
+        };
+        let (a, b, c, d) = ACCEL_SENS;
+        for lsb in [a, b, c, d] {
+            for count in all_counts() {
+                let g = counts_to_units([count; 3], lsb);
+                let (back, saturated) = units_to_counts(g, lsb, mode);
+                check(count, back[0], "accel");
+                assert!(!saturated);
+            }
+        }
+        let (a, b, c, d) = GYRO_SENS;
+        for lsb in [a, b, c, d] {
+            for count in all_counts() {
+                let rad_s = counts_to_rad_s([count; 3], lsb);
+                let (back, saturated) = rad_s_to_counts(rad_s, lsb, mode);
+                check(count, back[1], "gyro");
+                assert!(!saturated);
+            }
+        }
+        for count in all_counts() {
+            let (back, saturated) = celsius_to_counts(counts_to_celsius(count), mode);
+            check(count, back, "temp");
+            assert!(!saturated);
+        }
+    }
+}
+
+#[test]
+fn scaled_values_round_within_the_bound_of_the_mode() {
+    let lsb = ACCEL_SENS.0;
+    for k in -40_000..=40_000 {
+        let value = k as f32 / 16.;
+        for mode in MODES {
+            let ([count, _, _], _) = units_to_counts(Vec3A::new(value / lsb, 0., 0.), lsb, mode);
+            let error = f32::from(count) - value;
+            match mode {
+                RoundingMode::Truncate => assert!(error.abs() < 1. && error * value <= 0.),
+                _ => assert!(error.abs() <= 0.5, "{:?} {}: {}", mode, value, count),
+            }
+        }
+        // ties to even
+        if k % 16 == 8 {
+            let (count, _) = RoundingMode::NearestEven.to_count(value);
+            assert_eq!(count % 2, 0, "{}", value);
+        }
+    }
+    // saturation
+    for mode in MODES {
+        assert_eq!(mode.to_count(40_000.), (i16::MAX, true));
+        assert_eq!(mode.to_count(-40_000.), (i16::MIN, true));
+        assert_eq!(mode.to_count(f32::NAN), (0, false));
+    }
+}
+
+#[test]
+fn narrowing_is_exact_back_and_bounded_forth() {
+    for bits in [12, 10] {
+        let step = 1i32 << (16 - bits);
+        let (min, max) = (-(1i16 << (bits - 1)), (1i16 << (bits - 1)) - 1);
+        for mode in MODES {
+            for value in min..=max {
+                assert_eq!(narrow(widen(value, bits), bits, mode), (value, false));
+            }
+            for count in all_counts() {
+                let (value, saturated) = narrow(count, bits, mode);
+                assert!((min..=max).contains(&value));
+                let error = i32::from(widen(value, bits)) - i32::from(count);
+                if saturated {
+                    assert_eq!(value, max, "{:?} {}", mode, count);
+                    continue;
+                }
+                match mode {
+                    RoundingMode::Truncate => {
+                        assert!(error.abs() < step && error * i32::from(count) <= 0)
+                    }
+                    _ => assert!(error.abs() <= step / 2, "{:?} {}", mode, count),
+                }
+            }
+        }
+    }
+}
+
+/// mean error of `convert` in LSB over `inputs`
+fn mean_error(inputs: impl Iterator<Item = f64>, convert: impl Fn(f64) -> f64) -> f64 {
+    let (sum, n) = inputs.fold((0., 0.), |(sum, n), x| (sum + convert(x) - x, n + 1.));
+    sum / n
+}
+
+#[test]
+fn nearest_even_is_unbiased() {
+    // eighths of an LSB, a tie every eighth value
+    let symmetric = || (-80_000..=80_000).map(|k| k as f64 / 8.);
+    let positive = || (0..=160_000).map(|k| k as f64 / 8.);
+    let rounded = |mode: RoundingMode| move |x: f64| f64::from(mode.to_count(x as f32).0);
+    assert!(mean_error(symmetric(), rounded(RoundingMode::NearestEven)).abs() < 1e-9);
+    assert!(mean_error(positive(), rounded(RoundingMode::NearestEven)).abs() < 1e-4);
+    // ties away from zero: up for positive values, by half an LSB every eighth value
+    let legacy = mean_error(positive(), rounded(RoundingMode::Legacy));
+    assert!((legacy - 1. / 16.).abs() < 1e-4, "{}", legacy);
+    let truncate = mean_error(positive(), rounded(RoundingMode::Truncate));
+    assert!((truncate + 7. / 16.).abs() < 1e-4, "{}", truncate);
+
+    // narrowing to 12 bits, error in counts
+    let narrowed =
+        |mode: RoundingMode| move |x: f64| f64::from(widen(narrow(x as i16, 12, mode).0, 12));
+    let symmetric = || (-30_000..=30_000).map(f64::from);
+    let positive = || (0..=30_000).map(f64::from);
+    assert!(mean_error(symmetric(), narrowed(RoundingMode::NearestEven)).abs() < 1e-9);
+    assert!(mean_error(positive(), narrowed(RoundingMode::NearestEven)).abs() < 0.01);
+    // ties up, half a step of 16 counts every 16 counts
+    let legacy = mean_error(symmetric(), narrowed(RoundingMode::Legacy));
+    assert!((legacy - 0.5).abs() < 0.01, "{}", legacy);
+    assert!(mean_error(symmetric(), narrowed(RoundingMode::Truncate)).abs() < 1e-9);
+    let truncate = mean_error(positive(), narrowed(RoundingMode::Truncate));
+    assert!((truncate + 7.5).abs() < 0.01, "{}", truncate);
+}
+
+/// the packed encoder's reduction before the conversion module
+fn reduce_before(count: f32, bits: u32) -> (u32, bool) {
+    let shift = 16 - bits;
+    let max = (1i32 << (bits - 1)) - 1;
+    let min = -(1i32 << (bits - 1));
+    let rounded = count.round();
+    let mut clipped = rounded > i16::MAX as f32 || rounded < i16::MIN as f32;
+    let count = rounded as i16 as i32;
+    let mut reduced = (count + (1 << (shift - 1))) >> shift;
+    if reduced > max {
+        reduced = max;
+        clipped = true;
+    } else if reduced < min {
+        reduced = min;
+        clipped = true;
+    }
+    (reduced as u32 & ((1 << bits) - 1), clipped)
+}
+
+/// the hardware offset conversion before the conversion module
+fn gyro_offset_counts_before(current: [i16; 3], offset: Vec3A) -> ([i16; 3], Vec3A) {
+    use mpu6050::device::GYRO_OFFSET_DPS_PER_LSB;
+    let counts = (offset * (180. / core::f32::consts::PI) / GYRO_OFFSET_DPS_PER_LSB).round();
+    let mut offsets = current;
+    let mut residue = offset;
+    for axis in 0..3 {
+        let target = (f32::from(current[axis]) + counts[axis]).clamp(-32768., 32767.);
+        offsets[axis] = target as i16;
+        let added = target - f32::from(current[axis]);
+        residue[axis] -= added * GYRO_OFFSET_DPS_PER_LSB * (core::f32::consts::PI / 180.);
+    }
+    (offsets, residue)
+}
+
+#[test]
+fn legacy_reproduces_the_previous_conversions() {
+    let legacy = RoundingMode::Legacy;
+    let reduce = |value: f32, bits: u32| {
+        let (count, clipped) = legacy.to_count(value);
+        let (reduced, saturated) = narrow(count, bits, legacy);
+        (
+            reduced as u16 as u32 & ((1 << bits) - 1),
+            clipped || saturated,
+        )
+    };
+    for bits in [12, 10] {
+        // quarter counts for the ties, beyond the i16 range for the saturation
+        for k in -140_000..=140_000 {
+            let value = k as f32 / 4.;
+            assert_eq!(reduce(value, bits), reduce_before(value, bits), "{}", value);
+        }
+    }
+    for k in -2000..=2000 {
+        let offset = Vec3A::new(k as f32, -k as f32 * 0.37, k as f32 * 13.1) * 1e-4;
+        for current in [[0; 3], [100, -100, 7], [i16::MAX, i16::MIN, 0]] {
+            let new = gyro_offset_counts(current, offset, legacy);
+            assert_eq!(
+                new,
+                gyro_offset_counts_before(current, offset),
+                "{}",
+                offset
+            );
+        }
+    }
+
+    // the packed bytes follow, nearest even differing at ties only
+    let samples: Vec<_> = (-200..200)
+        .map(|k| {
+            let acc = Vec3A::new(k as f32, -k as f32 * 3.5, 0.5) / 16384. * 8.;
+            let gyro = Vec3A::new(k as f32 * 0.125, 0., -k as f32) / 131. * PI_180;
+            MpuSample::new(acc, gyro, 36.53 + k as f32 / 680.)
+        })
+        .collect();
+    let len = packed_len(samples.len(), PackedBits::B12);
+    let encode = |mode| {
+        let mut buf = vec![0; len];
+        encode_binary_packed_with_rounding(
+            &samples,
+            PackedBits::B12,
+            AccelRange::G2,
+            GyroRange::D250,
+            mode,
+            &mut buf,
+        )
+        .unwrap();
+        buf
+    };
+    let mut expected = vec![0; len];
+    packed_before(&samples, &mut expected);
+    assert_eq!(encode(legacy), expected);
+    assert_ne!(encode(RoundingMode::NearestEven), expected);
+}
+
+/// a 12 bit packed batch of measured samples at ±2 g and ±250 °/s as encoded before the
+/// conversion module
+fn packed_before(samples: &[MpuSample], buf: &mut [u8]) {
+    let (mut bits, mut saturated) = (Vec::new(), false);
+    for sample in samples {
+        let acc = sample.acc() * 16384.;
+        let gyro = sample.gyro() / PI_180 * 131.;
+        let temp = (sample.temp() - 36.53) * 340.;
+        for value in [acc.x, acc.y, acc.z, gyro.x, gyro.y, gyro.z, temp] {
+            let (reduced, clipped) = reduce_before(value, 12);
+            saturated |= clipped;
+            bits.extend((0..12).rev().map(|i| reduced >> i & 1 != 0));
+        }
+    }
+    buf.fill(0);
+    buf[0] = PACKED_VERSION;
+    buf[1] = 12;
+    buf[2] = (AccelRange::G2 as u8) << 2 | GyroRange::D250 as u8;
+    buf[3] = saturated as u8;
+    buf[4..6].copy_from_slice(&(samples.len() as u16).to_le_bytes());
+    for (i, bit) in bits.into_iter().enumerate() {
+        if bit {
+            buf[PACKED_HEADER_LEN + i / 8] |= 0x80 >> (i % 8);
+        }
+    }
+    let crc_at = buf.len() - PACKED_CRC_LEN;
+    let crc = crc16(&buf[..crc_at]);
+    buf[crc_at..].copy_from_slice(&crc.to_le_bytes());
+}
+
+#[test]
+fn hardware_offsets_round_with_the_driver_mode() {
+    let mut mpu = Mpu6050Builder::new()
+        .i2c(RegisterMock::new())
+        .rounding(RoundingMode::Truncate)
+        .build()
+        .unwrap();
+    assert_eq!(mpu.rounding(), RoundingMode::Truncate);
+    assert_eq!(mpu.debug_state().rounding, RoundingMode::Truncate);
+    // a bias of 18.8 and -37.6 counts, subtracted as -18.8 and 37.6
+    mpu.gyro_offset = Vec3A::new(-0.01, 0.02, 0.);
+    assert_eq!(mpu.store_gyro_offset_in_hardware().unwrap(), [18, -37, 0]);
+
+    mpu.set_rounding(RoundingMode::default());
+    mpu.set_gyro_hw_offsets([0; 3]).unwrap();
+    mpu.gyro_offset = Vec3A::new(-0.01, 0.02, 0.);
+    assert_eq!(mpu.store_gyro_offset_in_hardware().unwrap(), [19, -38, 0]);
+}
+
+#[cfg(feature = "test-fixtures")]
+#[test]
+fn fixtures_pack_within_the_packed_step() {
+    use mpu6050::test_fixtures::ALL;
+
+    for fixture in ALL {
+        let (a, g) = (fixture.accel_range, fixture.gyro_range);
+        let acc_lsb = [ACCEL_SENS.0, ACCEL_SENS.1, ACCEL_SENS.2, ACCEL_SENS.3][a as usize];
+        let gyro_lsb = [GYRO_SENS.0, GYRO_SENS.1, GYRO_SENS.2, GYRO_SENS.3][g as usize];
+        let samples: Vec<MpuSample> = (0..fixture.len).map(|idx| fixture.expected(idx)).collect();
+        for bits in [PackedBits::B12, PackedBits::B10] {
+            let what = format!("{} {:?}", fixture.name, bits);
+            let pack = |samples: &[MpuSample]| {
+                let mut buf = vec![0; packed_len(samples.len(), bits)];
+                encode_binary_packed(samples, bits, a, g, &mut buf).unwrap();
+                buf
+            };
+            let buf = pack(&samples);
+            let mut decoded = vec![MpuSample::new(Vec3A::ZERO, Vec3A::ZERO, 0.); fixture.len];
+            let batch = decode_binary_packed(&buf, &mut decoded).unwrap();
+            assert_eq!(
+                (batch.count, batch.accel_range, batch.gyro_range),
+                (fixture.len, a, g)
+            );
+
+            // half a packed step, a whole one where the top of the range saturated
+            let step = (1 << (16 - bits as u32)) as f32;
+            let bound = if batch.saturated { step } else { step / 2. };
+            for (idx, (back, sample)) in decoded.iter().zip(&samples).enumerate() {
+                let acc = (back.acc() - sample.acc()).abs().max_element() * acc_lsb;
+                let gyro = (back.gyro() - sample.gyro()).abs().max_element() / PI_180 * gyro_lsb;
+                let temp = (back.temp() - sample.temp()).abs() * 340.;
+                for error in [acc, gyro, temp] {
+                    assert!(error <= bound * (1. + 1e-4), "{} {}: {}", what, idx, error);
+                }
+            }
+            // the decoded values are on the packed grid, packing them again keeps them
+            let mut again = decoded.clone();
+            decode_binary_packed(&pack(&decoded), &mut again).unwrap();
+            assert_eq!(again, decoded, "{}", what);
+            let clipped = (0..fixture.len).any(|idx| fixture.saturates(idx, a, g));
+            assert_eq!(batch.saturated, clipped, "{}", what);
+        }
+    }
+}
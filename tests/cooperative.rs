@@ -0,0 +1,374 @@
+//! Cooperative drains and calibrations: budgeted drains reassemble the frames of a single
+//! drain, an error mid-drain resumes aligned, see the `cooperative` module.
+
+mod common;
+
+use std::cell::Cell;
+use std::fmt::{self, Debug, Display};
+use std::rc::Rc;
+
+use embedded_hal::blocking::i2c::{Write, WriteRead};
+use mpu6050::cooperative::*;
+use mpu6050::device::{DEFAULT_SLAVE_ADDR, FIFO_R_W};
+use mpu6050::fifo::*;
+use mpu6050::setup::AutoSetupOptions;
+use mpu6050::*;
+
+use common::{NoDelay, SharedBus};
+
+/// accel, temperature and gyro, 14 bytes
+fn sources() -> FifoSources {
+    FifoSources::NONE
+        .with_accel(true)
+        .with_temp(true)
+        .with_gyro(true)
+}
+
+/// frame `k` of the script, accel X carries `k`
+fn frame(k: i16) -> Vec<u8> {
+    [k, -k, 2 * k, 100, k, 0, -3 * k]
+        .iter()
+        .flat_map(|word| word.to_be_bytes())
+        .collect()
+}
+
+fn push_frames(bus: &SharedBus, frames: std::ops::Range<i16>) {
+    bus.device(DEFAULT_SLAVE_ADDR, |mock| {
+        mock.fifo.extend(frames.flat_map(frame))
+    });
+}
+
+fn driver<I, E>(i2c: I) -> (Mpu6050<I>, FifoSchema)
+where
+    I: Write<Error = E> + WriteRead<Error = E>,
+    E: Debug,
+{
+    let mut mpu = Mpu6050Builder::new().i2c(i2c).build().unwrap();
+    mpu.init(&mut NoDelay).unwrap();
+    let schema = mpu.set_fifo_sources(sources()).unwrap();
+    (mpu, schema)
+}
+
+/// accel X of every parsed frame
+fn sequence(frames: &[FifoFrame]) -> Vec<i16> {
+    frames.iter().map(|f| f.acc.unwrap()[0]).collect()
+}
+
+/// cooperative drains of `out_len` bytes until the count is drained, `before` running
+/// ahead of each, the parsed frames and the progress of every drain
+fn drain_all<I, E>(
+    mpu: &mut Mpu6050<I>,
+    schema: &FifoSchema,
+    out_len: usize,
+    budget: DrainBudget,
+    yield_fn: &mut dyn FnMut(),
+    before: &dyn Fn(),
+) -> (Vec<FifoFrame>, Vec<DrainProgress>)
+where
+    I: Write<Error = E> + WriteRead<Error = E>,
+    E: Debug,
+{
+    let mut out = vec![0; out_len];
+    let (mut frames, mut drains) = (Vec::new(), Vec::new());
+    loop {
+        before();
+        let progress = mpu
+            .drain_fifo_cooperative(schema, &mut out, budget, &mut *yield_fn)
+            .unwrap();
+        let chunks = out[..progress.bytes].chunks_exact(schema.frame_len());
+        for (k, bytes) in chunks.enumerate() {
+            let mut frame = parse_fifo_frame(schema, bytes).unwrap();
+            frame.sequence = progress.first_sequence + k as u64;
+            frame.gap = if k == 0 { progress.gap } else { None };
+            frames.push(frame);
+        }
+        drains.push(progress);
+        if progress.remaining < schema.frame_len() {
+            return (frames, drains);
+        }
+    }
+}
+
+const BUDGETS: [DrainBudget; 9] = [
+    DrainBudget::Bytes(0),
+    DrainBudget::Bytes(1),
+    DrainBudget::Bytes(14),
+    DrainBudget::Bytes(15),
+    DrainBudget::Bytes(64),
+    DrainBudget::Bytes(1024),
+    DrainBudget::Transactions(1),
+    DrainBudget::Transactions(2),
+    DrainBudget::Transactions(7),
+];
+
+#[test]
+fn budgeted_drains_reassemble_a_single_drain() {
+    let bus = SharedBus::new(&[DEFAULT_SLAVE_ADDR]);
+    let (mut mpu, schema) = driver(bus.clone());
+    push_frames(&bus, 0..60);
+    let mut single = Vec::new();
+    assert_eq!(
+        mpu.drain_fifo(&schema, &mut [0; 1024], |f| single.push(f))
+            .unwrap(),
+        60
+    );
+    assert_eq!(sequence(&single), (0..60).collect::<Vec<_>>());
+
+    for budget in BUDGETS {
+        for out_len in [14, 20, 100, 1024] {
+            let bus = SharedBus::new(&[DEFAULT_SLAVE_ADDR]);
+            let (mut mpu, schema) = driver(bus.clone());
+            push_frames(&bus, 0..60);
+            bus.take_log();
+            let yields = Cell::new(0);
+            let most = Cell::new(0);
+            let log = bus.clone();
+            let mut on_yield = || {
+                yields.set(yields.get() + 1);
+                most.set(most.get().max(log.take_log().len()));
+            };
+            let (frames, drains) =
+                drain_all(&mut mpu, &schema, out_len, budget, &mut on_yield, &|| {
+                    most.set(most.get().max(log.take_log().len()))
+                });
+            let what = format!("{:?} into {} bytes", budget, out_len);
+            assert_eq!(frames, single, "{}", what);
+            assert!(drains.iter().all(|d| d.discarded == 0));
+            assert_eq!(drains.len(), 60usize.div_ceil(out_len / 14));
+
+            // pieces of the budget, yields between them only
+            let per_drain = (out_len / 14).min(60);
+            let pieces_per_drain = match budget {
+                DrainBudget::Bytes(n) => {
+                    let frames_per_read = (n as usize / 14).max(1);
+                    // the count read goes with the first
+                    assert!(most.get() <= 2, "{}", what);
+                    per_drain.div_ceil(frames_per_read)
+                }
+                DrainBudget::Transactions(n) => {
+                    assert!(most.get() <= n as usize, "{}", what);
+                    // the count read and one read per frame
+                    (per_drain + 1).div_ceil(n as usize)
+                }
+            };
+            assert!(yields.get() >= pieces_per_drain - 1, "{}", what);
+            assert!(yields.get() <= drains.len() * pieces_per_drain, "{}", what);
+        }
+    }
+}
+
+#[test]
+fn frames_written_while_yielding_stay_for_the_next_drain() {
+    let bus = SharedBus::new(&[DEFAULT_SLAVE_ADDR]);
+    let (mut mpu, schema) = driver(bus.clone());
+    push_frames(&bus, 0..10);
+    // the chip writes a frame during every yield
+    let next = Cell::new(10);
+    let writer = bus.clone();
+    let mut on_yield = || {
+        push_frames(&writer, next.get()..next.get() + 1);
+        next.set(next.get() + 1);
+    };
+    let mut out = [0; 1024];
+    let first = mpu
+        .drain_fifo_cooperative(&schema, &mut out, DrainBudget::Bytes(28), &mut on_yield)
+        .unwrap();
+    assert_eq!(first.frames, 10);
+    assert_eq!(first.remaining, 0);
+    assert_eq!(next.get(), 14);
+    let parse = |bytes: &[u8]| {
+        bytes
+            .chunks_exact(14)
+            .map(|b| parse_fifo_frame(&schema, b).unwrap())
+            .collect::<Vec<_>>()
+    };
+    let mut frames = parse(&out[..first.bytes]);
+    let second = mpu
+        .drain_fifo_cooperative(&schema, &mut out, DrainBudget::Bytes(1024), &mut on_yield)
+        .unwrap();
+    assert_eq!(second.frames, 4);
+    frames.extend(parse(&out[..second.bytes]));
+    assert_eq!(sequence(&frames), (0..14).collect::<Vec<_>>());
+}
+
+/// fault of [`CutBus`]
+#[derive(Debug)]
+struct Cut;
+
+impl Display for Cut {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "cut")
+    }
+}
+
+/// [`SharedBus`] failing the nth FIFO read after taking `consumed` bytes off the FIFO, once
+#[derive(Clone)]
+struct CutBus {
+    bus: SharedBus,
+    /// nth FIFO read to fail and the bytes it takes
+    cut: Rc<Cell<Option<(usize, usize)>>>,
+    fifo_reads: Rc<Cell<usize>>,
+}
+
+impl Write for CutBus {
+    type Error = Cut;
+
+    fn write(&mut self, address: u8, bytes: &[u8]) -> Result<(), Cut> {
+        self.bus.write(address, bytes).map_err(|_| Cut)
+    }
+}
+
+impl WriteRead for CutBus {
+    type Error = Cut;
+
+    fn write_read(&mut self, address: u8, bytes: &[u8], buffer: &mut [u8]) -> Result<(), Cut> {
+        if bytes == [FIFO_R_W] {
+            let n = self.fifo_reads.get();
+            self.fifo_reads.set(n + 1);
+            if let Some((_, consumed)) = self.cut.get().filter(|(nth, _)| *nth == n) {
+                self.cut.set(None);
+                self.bus
+                    .write_read(address, bytes, &mut buffer[..consumed])
+                    .ok();
+                return Err(Cut);
+            }
+        }
+        self.bus.write_read(address, bytes, buffer).map_err(|_| Cut)
+    }
+}
+
+#[test]
+fn an_error_mid_drain_resumes_aligned() {
+    for consumed in [0, 5, 13, 14, 20, 28] {
+        let bus = CutBus {
+            bus: SharedBus::new(&[DEFAULT_SLAVE_ADDR]),
+            cut: Rc::new(Cell::new(None)),
+            fifo_reads: Rc::new(Cell::new(0)),
+        };
+        let (mut mpu, schema) = driver(bus.clone());
+        push_frames(&bus.bus, 0..10);
+        // the second read of two frames fails
+        bus.cut.set(Some((1, consumed)));
+        let mut out = [0; 256];
+        let error = mpu
+            .drain_fifo_cooperative(&schema, &mut out, DrainBudget::Bytes(28), || ())
+            .unwrap_err();
+        assert!(matches!(error.error, Mpu6050Error::I2c(Cut)));
+        assert_eq!(
+            error.to_string(),
+            "FIFO drain failed after 2 frames: i2c error: cut"
+        );
+        let before = error.progress;
+        assert_eq!((before.frames, before.bytes, before.discarded), (2, 28, 0));
+        // the frames of the failed read were not drained
+        assert_eq!(before.remaining, 8 * 14);
+        let mut frames: Vec<_> = out[..28]
+            .chunks_exact(14)
+            .map(|b| parse_fifo_frame(&schema, b).unwrap())
+            .collect();
+
+        // the next drain discards the rest of the cut frame
+        let (after, drains) = drain_all(
+            &mut mpu,
+            &schema,
+            256,
+            DrainBudget::Bytes(28),
+            &mut || (),
+            &|| (),
+        );
+        assert_eq!(
+            drains[0].discarded,
+            (14 - consumed % 14) % 14,
+            "{}",
+            consumed
+        );
+        frames.extend(after);
+        let resumed = 2 + consumed.div_ceil(14) as i16;
+        let expected: Vec<_> = (0..2).chain(resumed..10).collect();
+        assert_eq!(sequence(&frames), expected, "{}", consumed);
+    }
+}
+
+#[test]
+fn a_failed_count_read_drains_nothing() {
+    let bus = CutBus {
+        bus: SharedBus::new(&[DEFAULT_SLAVE_ADDR]),
+        cut: Rc::new(Cell::new(None)),
+        fifo_reads: Rc::new(Cell::new(0)),
+    };
+    let (mut mpu, schema) = driver(bus.clone());
+    let error = mpu
+        .drain_fifo_cooperative(&schema, &mut [0; 10], DrainBudget::Bytes(28), || ())
+        .unwrap_err();
+    assert!(matches!(error.error, Mpu6050Error::BufferTooSmall(14)));
+    assert_eq!(error.progress, DrainProgress::default());
+    let stale = schema;
+    mpu.set_fifo_sources(FifoSources::NONE.with_accel(true))
+        .unwrap();
+    let error = mpu
+        .drain_fifo_cooperative(&stale, &mut [0; 64], DrainBudget::Bytes(28), || ())
+        .unwrap_err();
+    assert!(matches!(
+        error.error,
+        Mpu6050Error::StaleConfiguration { captured, current } if captured == stale.generation
+            && current == mpu.config_epoch()
+    ));
+}
+
+fn options() -> AutoSetupOptions {
+    AutoSetupOptions {
+        // the mock does not respond to self-test
+        min_actuation_g: 0.0,
+        gyro_samples: 60,
+        device_is_level: true,
+        acc_samples: 60,
+        ..AutoSetupOptions::default()
+    }
+}
+
+#[test]
+fn cooperative_setup_yields_between_its_readings() {
+    let bus = SharedBus::new(&[DEFAULT_SLAVE_ADDR]);
+    let mut mpu = Mpu6050Builder::new().i2c(bus.clone()).build().unwrap();
+    let plain = mpu.auto_setup(&mut NoDelay, options(), None).unwrap();
+    let transactions = bus.take_log().len();
+
+    for budget in [10, 25] {
+        let bus = SharedBus::new(&[DEFAULT_SLAVE_ADDR]);
+        let mut mpu = Mpu6050Builder::new().i2c(bus.clone()).build().unwrap();
+        let log = bus.clone();
+        let mut between = Vec::new();
+        let report = mpu
+            .auto_setup_cooperative(
+                &mut NoDelay,
+                options(),
+                None,
+                DrainBudget::Transactions(budget),
+                || between.push(log.take_log().len()),
+            )
+            .unwrap();
+        assert_eq!(report.gyro_offset, plain.gyro_offset);
+        assert_eq!(report.acc_offset, plain.acc_offset);
+        let rest = bus.take_log().len();
+        assert_eq!(between.iter().sum::<usize>() + rest, transactions);
+        // yields once the budget is spent, right after the reading spending it; init and
+        // the actuation check have no reading in between, the final configuration follows
+        let budget = budget as usize;
+        assert!(between[0] >= budget, "{:?}", between);
+        assert!(between[1..].iter().all(|n| *n == budget), "{:?}", between);
+        assert!(rest <= budget + 6, "{}", rest);
+    }
+
+    // the owned delay flavour
+    let bus = SharedBus::new(&[DEFAULT_SLAVE_ADDR]);
+    let mut mpu = Mpu6050Builder::new()
+        .i2c(bus)
+        .delay(NoDelay)
+        .build()
+        .unwrap();
+    let mut yields = 0;
+    mpu.auto_setup_cooperative(options(), None, DrainBudget::Bytes(90), || yields += 1)
+        .unwrap();
+    // 120 sample reads of 9 bytes on the wire
+    assert!(yields >= 12, "{}", yields);
+}
@@ -0,0 +1,458 @@
+//! Deadline aborts of `auto_setup` and the standalone long running operations against a mock
+//! with a fake clock, see the `deadline` module.
+
+mod common;
+
+use std::cell::Cell;
+use std::convert::Infallible;
+use std::rc::Rc;
+
+use embedded_hal::blocking::delay::DelayMs;
+use embedded_hal::blocking::i2c::{Write, WriteRead};
+use mpu6050::deadline::AbortProgress;
+use mpu6050::device::{MotionEngineStatus, GYRO_REGX_H};
+use mpu6050::motion_verify::MOTION_SETTLE_MS;
+use mpu6050::platform::ReferencedCalibration;
+use mpu6050::register::Register;
+use mpu6050::settling::SettlingPolicy;
+use mpu6050::setup::{
+    AutoSetupFailure, AutoSetupOptions, AutoSetupPhase, PhaseStatus, ThermalWait,
+};
+use mpu6050::*;
+
+use common::RegisterMock;
+
+/// bus time of a single transaction in µs
+const TRANSACTION_US: u64 = 200;
+/// longest delay between two deadline checks with the options below, the self-test settling
+const DELAY_QUANTUM_US: u64 = 20_000;
+/// a read-modify-write is the longest bus operation between two checks
+const BUS_OPERATION_US: u64 = 2 * TRANSACTION_US;
+/// an aborted actuation check clears the three self-test bits
+const SELF_TEST_CLEANUP_US: u64 = 3 * BUS_OPERATION_US;
+
+#[derive(Clone, Default)]
+struct Clock {
+    now_us: Rc<Cell<u64>>,
+    gyro_reads: Rc<Cell<u32>>,
+}
+
+impl Clock {
+    fn now(&self) -> u64 {
+        self.now_us.get()
+    }
+
+    fn advance(&self, us: u64) {
+        self.now_us.set(self.now_us.get() + us);
+    }
+
+    fn expired_at(&self, deadline_us: u64) -> impl FnMut() -> bool {
+        let clock = self.clone();
+        move || clock.now() >= deadline_us
+    }
+}
+
+/// [`RegisterMock`] where every transaction takes [`TRANSACTION_US`]
+struct ClockedBus {
+    mock: RegisterMock,
+    clock: Clock,
+}
+
+impl Write for ClockedBus {
+    type Error = Infallible;
+
+    fn write(&mut self, address: u8, bytes: &[u8]) -> Result<(), Infallible> {
+        self.clock.advance(TRANSACTION_US);
+        self.mock.write(address, bytes)
+    }
+}
+
+impl WriteRead for ClockedBus {
+    type Error = Infallible;
+
+    fn write_read(&mut self, address: u8, bytes: &[u8], buf: &mut [u8]) -> Result<(), Infallible> {
+        self.clock.advance(TRANSACTION_US);
+        if bytes[0] == GYRO_REGX_H {
+            self.clock.gyro_reads.set(self.clock.gyro_reads.get() + 1);
+        }
+        self.mock.write_read(address, bytes, buf)
+    }
+}
+
+struct FakeDelay(Clock);
+
+impl DelayMs<u8> for FakeDelay {
+    fn delay_ms(&mut self, ms: u8) {
+        self.0.advance(ms as u64 * 1000);
+    }
+}
+
+fn options() -> AutoSetupOptions {
+    AutoSetupOptions {
+        thermal_wait: Some(ThermalWait {
+            max_ms: 200,
+            interval_ms: 10,
+            tolerance_c: 0.05,
+            stable_readings: 4,
+        }),
+        // the mock does not respond to self-test
+        min_actuation_g: 0.0,
+        gyro_samples: 50,
+        device_is_level: true,
+        acc_samples: 50,
+        ..AutoSetupOptions::default()
+    }
+}
+
+fn driver(clock: &Clock) -> Mpu6050<ClockedBus> {
+    let mut mock = RegisterMock::new();
+    // constant gyro zero rate offset of 100 counts on X
+    let x = GYRO_REGX_H as usize;
+    mock.regs[x..x + 2].copy_from_slice(&100i16.to_be_bytes());
+    let bus = ClockedBus {
+        mock,
+        clock: clock.clone(),
+    };
+    Mpu6050Builder::new().i2c(bus).build().unwrap()
+}
+
+fn aborted(
+    error: &setup::AutoSetupError<Infallible>,
+) -> Option<(&'static str, deadline::AbortProgress)> {
+    match error.failure {
+        setup::AutoSetupFailure::Device(Mpu6050Error::Aborted { phase, progress }) => {
+            Some((phase, progress))
+        }
+        _ => None,
+    }
+}
+
+#[test]
+fn overrun_is_bounded_by_one_bus_operation_and_one_delay() {
+    let reference = Clock::default();
+    driver(&reference)
+        .auto_setup(&mut FakeDelay(reference.clone()), options(), None)
+        .unwrap();
+    let duration_us = reference.now();
+
+    // Init runs uninterrupted once started, deadlines from its end on are checked tightly
+    let init = Clock::default();
+    driver(&init).init(&mut FakeDelay(init.clone())).unwrap();
+    let init_end_us = init.now() + 4 * TRANSACTION_US;
+
+    let mut phases = Vec::new();
+    for deadline_us in (init_end_us..duration_us).step_by(1_700) {
+        let clock = Clock::default();
+        let res = driver(&clock).auto_setup_with_deadline(
+            &mut FakeDelay(clock.clone()),
+            options(),
+            None,
+            clock.expired_at(deadline_us),
+        );
+        let err = match res {
+            Err(err) => err,
+            // expired within the final six transactions, which run once started
+            Ok(_) => {
+                assert!(clock.now() - deadline_us <= 6 * TRANSACTION_US);
+                continue;
+            }
+        };
+        let (phase, progress) = aborted(&err).expect("aborted");
+        let overrun = clock.now() - deadline_us;
+        let cleanup = match phase {
+            "ActuationCheck" => SELF_TEST_CLEANUP_US,
+            _ => 0,
+        };
+        assert!(
+            overrun <= BUS_OPERATION_US + DELAY_QUANTUM_US + cleanup,
+            "overrun of {} µs in {} at deadline {} µs",
+            overrun,
+            phase,
+            deadline_us
+        );
+        assert_eq!(err.report.phase(err.phase).status, PhaseStatus::Aborted);
+        assert_eq!(phase, err.phase.name());
+        assert!(progress.transactions > 0);
+        if !phases.contains(&phase) {
+            phases.push(phase);
+        }
+    }
+    assert_eq!(
+        phases,
+        [
+            "ThermalWait",
+            "ActuationCheck",
+            "GyroCalibration",
+            "AccelCalibration",
+            "ApplyConfiguration"
+        ]
+    );
+}
+
+#[test]
+fn expired_deadline_aborts_before_the_first_transaction() {
+    let clock = Clock::default();
+    let err = driver(&clock)
+        .auto_setup_with_deadline(&mut FakeDelay(clock.clone()), options(), None, || true)
+        .unwrap_err();
+    assert_eq!(err.phase, AutoSetupPhase::Init);
+    assert_eq!(clock.now(), 0);
+    assert!(err.to_string().contains("aborted at deadline in Init"));
+}
+
+#[test]
+fn resumed_calibration_keeps_the_samples_collected() {
+    let reference = Clock::default();
+    let expected = driver(&reference)
+        .auto_setup(&mut FakeDelay(reference.clone()), options(), None)
+        .unwrap();
+
+    let clock = Clock::default();
+    let mut mpu = driver(&clock);
+    let mut delay = FakeDelay(clock.clone());
+    let mut gyro_samples = 0;
+    let err = mpu
+        .auto_setup_with_deadline(&mut delay, options(), None, || {
+            // abort after 20 gyro calibration samples
+            gyro_samples = clock.gyro_reads.get();
+            gyro_samples >= 20
+        })
+        .unwrap_err();
+    let (phase, progress) = aborted(&err).unwrap();
+    assert_eq!(phase, "GyroCalibration");
+    assert_eq!(progress.samples, 20);
+    assert!(progress.partial_mean.unwrap().x > 0.);
+
+    let mut resumed_phases = Vec::new();
+    let report = mpu
+        .auto_setup_resume(
+            &mut delay,
+            options(),
+            &err.report,
+            Some(&mut |phase| resumed_phases.push(phase)),
+            deadline::never,
+        )
+        .unwrap();
+    // samples are neither dropped nor taken twice
+    assert_eq!(clock.gyro_reads.get(), options().gyro_samples as u32);
+    assert_eq!(report.gyro_offset, expected.gyro_offset);
+    assert_eq!(report.acc_offset, expected.acc_offset);
+    assert_eq!(
+        resumed_phases,
+        [
+            AutoSetupPhase::GyroCalibration,
+            AutoSetupPhase::AccelCalibration,
+            AutoSetupPhase::ApplyConfiguration
+        ]
+    );
+    for phase in AutoSetupPhase::ALL {
+        assert_eq!(report.phase(phase).status, expected.phase(phase).status);
+    }
+}
+
+/// phase and progress of an aborted standalone operation
+type Abort = (&'static str, AbortProgress);
+
+fn abort_of(error: Mpu6050Error<Infallible>) -> Abort {
+    match error {
+        Mpu6050Error::Aborted { phase, progress } => (phase, progress),
+        error => panic!("not aborted: {:?}", error),
+    }
+}
+
+fn calibration_abort(failure: AutoSetupFailure<Infallible>) -> Abort {
+    match failure {
+        AutoSetupFailure::Device(error) => abort_of(error),
+        failure => panic!("not aborted: {:?}", failure),
+    }
+}
+
+/// initialised driver reading every sample as it comes, one transaction per reading
+fn initialised(clock: &Clock) -> Mpu6050<ClockedBus> {
+    let mut mpu = driver(clock);
+    mpu.init(&mut FakeDelay(clock.clone())).unwrap();
+    mpu.set_settling_policy(SettlingPolicy::Ignore);
+    mpu
+}
+
+/// Runs `op` on a fresh driver from `make` with deadlines from the start of `op` to its end,
+/// every `step_us`. Every deadline is overrun by at most one bus operation, `quantum_us` and
+/// `cleanup_us`, whether `op` aborts or completes. Returns the aborts and the driver of each
+fn sweep<M>(
+    make: impl Fn(&Clock) -> M,
+    mut op: impl FnMut(&mut M, &Clock, &mut dyn FnMut() -> bool) -> Option<Abort>,
+    step_us: usize,
+    quantum_us: u64,
+    cleanup_us: u64,
+) -> Vec<(Abort, M)> {
+    let reference = Clock::default();
+    let mut mpu = make(&reference);
+    let start_us = reference.now();
+    assert!(op(&mut mpu, &reference, &mut || false).is_none());
+    let end_us = reference.now();
+
+    let mut aborts = Vec::new();
+    for deadline_us in (start_us..end_us).step_by(step_us) {
+        let clock = Clock::default();
+        let mut mpu = make(&clock);
+        assert_eq!(clock.now(), start_us);
+        let abort = op(&mut mpu, &clock, &mut clock.expired_at(deadline_us));
+        let overrun = clock.now() - deadline_us;
+        assert!(
+            overrun <= BUS_OPERATION_US + quantum_us + cleanup_us,
+            "overrun of {} µs at deadline {} µs, {:?}",
+            overrun,
+            deadline_us,
+            abort
+        );
+        if let Some(abort) = abort {
+            aborts.push((abort, mpu));
+        }
+    }
+    // the deadline at the start aborts before the first transaction
+    assert_eq!(aborts[0].0 .1.transactions, 0);
+    aborts
+}
+
+#[test]
+fn standalone_calibrations_abort_within_one_sample_and_keep_the_offsets() {
+    let aborts = sweep(
+        initialised,
+        |mpu, clock, deadline| {
+            let mut delay = FakeDelay(clock.clone());
+            let res = mpu.calibrate_gyro_with_deadline(&mut delay, 50, deadline);
+            res.err().map(calibration_abort)
+        },
+        300,
+        2_000,
+        0,
+    );
+    for ((phase, progress), mpu) in &aborts {
+        assert_eq!(*phase, "GyroCalibration");
+        assert_eq!(mpu.get_gyro_offset(), Vec3A::ZERO);
+        assert!(progress.samples <= 50);
+        assert_eq!(progress.transactions, progress.samples);
+        assert_eq!(progress.partial_mean.is_some(), progress.samples > 0);
+        assert!(progress.partial_mean.is_none_or(|mean| mean.x > 0.));
+    }
+    assert!(aborts
+        .iter()
+        .any(|((_, progress), _)| progress.samples > 25));
+
+    let calibration = ReferencedCalibration {
+        samples: 20,
+        max_std_dev: 0.1,
+        sample_interval_ms: 5,
+    };
+    let aborts = sweep(
+        initialised,
+        |mpu, clock, deadline| {
+            let mut delay = FakeDelay(clock.clone());
+            let res = mpu.calibrate_accel_with_reference_with_deadline(
+                &mut delay,
+                || Vec3A::Z,
+                calibration,
+                deadline,
+            );
+            res.err().map(calibration_abort)
+        },
+        400,
+        5_000,
+        0,
+    );
+    for ((phase, _), mpu) in &aborts {
+        assert_eq!(*phase, "AccelCalibration");
+        assert_eq!(mpu.get_acc_offset(), Vec3A::ZERO);
+    }
+}
+
+#[test]
+fn init_burst_aborts_within_one_reading() {
+    let aborts = sweep(
+        initialised,
+        |mpu, clock, deadline| {
+            let mut delay = FakeDelay(clock.clone());
+            let res = mpu.collect_init_burst_with_deadline(&mut delay, 20, deadline);
+            res.err().map(abort_of)
+        },
+        200,
+        u64::from(orientation::INIT_BURST_INTERVAL_MS) * 1000,
+        0,
+    );
+    for ((phase, progress), _) in &aborts {
+        assert_eq!(*phase, orientation::INIT_BURST_PHASE);
+        assert_eq!(progress.transactions, progress.samples);
+        assert!(progress.samples < 20);
+    }
+}
+
+#[test]
+fn wait_settled_aborts_on_a_sample_period_and_resumes() {
+    // 100 Hz output data rate, a 10 ms sample period
+    let owning = |clock: &Clock| {
+        let mut mpu = Mpu6050Builder::new()
+            .i2c(ClockedBus {
+                mock: RegisterMock::new(),
+                clock: clock.clone(),
+            })
+            .delay(FakeDelay(clock.clone()))
+            .build()
+            .unwrap();
+        mpu.init().unwrap();
+        mpu.set_dlpf(3).unwrap();
+        mpu.set_sample_rate_divider(9).unwrap();
+        mpu
+    };
+    let total_ms = owning(&Clock::default()).wait_settled().unwrap();
+    assert!(total_ms >= 30);
+
+    let aborts = sweep(
+        owning,
+        |mpu, _, deadline| mpu.wait_settled_with_deadline(deadline).err().map(abort_of),
+        1_000,
+        10_000,
+        0,
+    );
+    for ((phase, progress), mut mpu) in aborts {
+        assert_eq!(phase, settling::PHASE);
+        assert_eq!(progress.transactions, 0);
+        assert_eq!(progress.elapsed_ms, progress.samples * 10);
+        // the second call waits out what the first left
+        assert_eq!(progress.elapsed_ms + mpu.wait_settled().unwrap(), total_ms);
+        assert!(!mpu.debug_state().settle.is_settling());
+    }
+}
+
+#[test]
+fn motion_verification_aborts_and_restores_the_registers() {
+    let saved = [
+        Register::ACCEL_CONFIG,
+        Register::MOT_THR,
+        Register::MOT_DUR,
+        Register::MOT_DETECT_CONTROL,
+        Register::INT_ENABLE,
+    ];
+    let mut before = initialised(&Clock::default());
+    let before = saved.map(|reg| before.read_register(reg).unwrap());
+    // the saved registers are written back and INT_STATUS read
+    let restore_us = (saved.len() as u64 + 1) * TRANSACTION_US;
+    let aborts = sweep(
+        initialised,
+        |mpu, clock, deadline| {
+            let mut delay = FakeDelay(clock.clone());
+            let res = mpu.verify_motion_detection_with_deadline(&mut delay, None, deadline);
+            res.err().map(abort_of)
+        },
+        700,
+        u64::from(MOTION_SETTLE_MS) * 1000,
+        restore_us,
+    );
+    for ((phase, _), mut mpu) in aborts {
+        assert_eq!(phase, motion_verify::PHASE);
+        assert_eq!(
+            mpu.capabilities().motion_engine,
+            MotionEngineStatus::Unverified
+        );
+        assert_eq!(saved.map(|reg| mpu.read_register(reg).unwrap()), before);
+    }
+}
@@ -0,0 +1,133 @@
+//! `debug_state` on a mock register file: no bus traffic, cached values and their sync points
+//! following the driver, and the bug report rendering, see the `snapshot` module.
+
+mod common;
+
+use mpu6050::device::{AccelRange, GyroRange, DEFAULT_SLAVE_ADDR};
+use mpu6050::settling::SettlingPolicy;
+use mpu6050::snapshot::SyncPoints;
+use mpu6050::{Mpu6050Builder, Vec3A};
+
+use common::{NoDelay, SharedBus};
+
+#[test]
+fn snapshot_costs_no_transaction() {
+    let bus = SharedBus::new(&[DEFAULT_SLAVE_ADDR]);
+    let mut mpu = Mpu6050Builder::new().i2c(bus.clone()).build().unwrap();
+    mpu.init(&mut NoDelay).unwrap();
+    bus.take_log();
+    let before = mpu.io_stats();
+    let state = mpu.debug_state();
+    let _ = state.to_string();
+    assert!(bus.take_log().is_empty());
+    assert_eq!(mpu.io_stats(), before);
+    assert_eq!(state.io_stats, before);
+}
+
+#[test]
+fn builder_values_are_not_synced() {
+    let bus = SharedBus::new(&[0x69]);
+    let mpu = Mpu6050Builder::new()
+        .i2c(bus)
+        .slave_addr(0x69)
+        .build()
+        .unwrap();
+    let state = mpu.debug_state();
+    assert_eq!(state.slave_addr, 0x69);
+    assert_eq!(state.chip_id, None);
+    assert_eq!(state.synced, SyncPoints::default());
+    assert_eq!(state.io_stats.transactions, 0);
+    assert!(!state.settle.is_settling());
+    assert!(!state.owns_delay && !state.sample_hook && !state.metrics_sink);
+}
+
+#[test]
+fn cached_values_follow_the_driver() {
+    let bus = SharedBus::new(&[DEFAULT_SLAVE_ADDR]);
+    let mut mpu = Mpu6050Builder::new().i2c(bus.clone()).build().unwrap();
+    mpu.init(&mut NoDelay).unwrap();
+    let state = mpu.debug_state();
+    assert_eq!(state.chip_id, Some(0x68));
+    // written by init, accel range first, at the counter value after each write
+    let synced = state.synced.accel_range.unwrap();
+    assert!(synced > 0 && synced <= state.io_stats.transactions);
+    assert!(state.synced.gyro_range.unwrap() > synced);
+    assert!(state.settle.is_settling());
+
+    mpu.set_accel_range(AccelRange::G8).unwrap();
+    let after_range = mpu.io_stats().transactions;
+    mpu.set_gyro_range(GyroRange::D1000).unwrap();
+    mpu.set_dlpf(3).unwrap();
+    mpu.set_acc_offset(Vec3A::new(0.01, 0., -0.02));
+    mpu.set_settling_policy(SettlingPolicy::Flag);
+
+    let state = mpu.debug_state();
+    assert_eq!(
+        (state.accel_range, state.gyro_range, state.dlpf_cfg),
+        (AccelRange::G8, GyroRange::D1000, 3)
+    );
+    assert_eq!(state.synced.accel_range, Some(after_range));
+    assert!(state.synced.gyro_range.unwrap() > after_range);
+    assert!(state.synced.dlpf_cfg.unwrap() > state.synced.gyro_range.unwrap());
+    assert_eq!(state.synced.sample_rate_div, None);
+    assert_eq!(state.acc_offset, Vec3A::new(0.01, 0., -0.02));
+    assert_eq!(state.settling_policy, SettlingPolicy::Flag);
+    assert_eq!(state.settle, mpu.settle_countdown());
+    assert_eq!(state.io_stats, mpu.io_stats());
+}
+
+#[test]
+fn display_renders_one_line_per_field_group() {
+    let bus = SharedBus::new(&[DEFAULT_SLAVE_ADDR]);
+    let mut mpu = Mpu6050Builder::new().i2c(bus).build().unwrap();
+    let fresh = mpu.debug_state().to_string();
+    assert!(fresh.starts_with("mpu6050 driver state\nslave_addr: 0x68\n"));
+    assert!(fresh.contains("chip_id: not verified\n"));
+    assert!(fresh.contains("accel_range: G2 (synced at op None)\n"));
+    assert!(!fresh.contains("failed_register"));
+    // the io line closes the report, no trailing newline
+    assert!(fresh.ends_with("io: 0 transactions, 0 bytes"));
+
+    mpu.init(&mut NoDelay).unwrap();
+    mpu.set_gyro_range(GyroRange::D500).unwrap();
+    let state = mpu.debug_state();
+    let report = state.to_string();
+    assert!(report.contains("chip_id: 0x68\n"));
+    assert!(report.contains(&format!(
+        "gyro_range: D500 (synced at op {:?})\n",
+        state.synced.gyro_range
+    )));
+    assert!(report.contains(&format!(
+        "settling: Discard {{ max_retries: 3 }}, countdown acc {} gyro {}\n",
+        state.settle.acc, state.settle.gyro
+    )));
+
+    let keys: Vec<_> = report
+        .lines()
+        .skip(1)
+        .map(|line| line.split(':').next().unwrap())
+        .collect();
+    for key in [
+        "slave_addr",
+        "chip_id",
+        "accel_range",
+        "gyro_range",
+        "dlpf_cfg",
+        "sample_rate_div",
+        "connection",
+        "supervisor",
+        "fifo",
+        "power",
+        "capabilities",
+        "interrupt_tracker",
+        "settling",
+        "io",
+    ] {
+        assert!(keys.contains(&key), "{key} missing");
+    }
+    // keys are unique
+    let mut sorted = keys.clone();
+    sorted.sort_unstable();
+    sorted.dedup();
+    assert_eq!(sorted.len(), keys.len());
+}
@@ -0,0 +1,192 @@
+//! Fixed point formatting against core's: the same text with a precision, the same content
+//! without one, and the `Display` impls rendering what they did with core's formatting, see
+//! the `decimal` module. `scripts/float-fmt-check.sh` checks the linked symbols.
+
+use mpu6050::aliasing::{AliasingAssessment, AliasingRisk};
+use mpu6050::bus::RateTooHigh;
+use mpu6050::decimal::{Decimal, DecimalVec, DEFAULT_PLACES, MAX_PLACES};
+use mpu6050::device::AccelRange;
+use mpu6050::error_budget::ErrorEstimate;
+use mpu6050::interleave::{AxisSource, MergedSample};
+use mpu6050::oscillator::ClockErrorEstimate;
+use mpu6050::{MpuSample, Vec3A};
+
+/// finite f32 from all exponents, xorshift bits
+fn values() -> impl Iterator<Item = f32> {
+    let mut state = 0x6050_u64;
+    let random = core::iter::from_fn(move || {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        Some(f32::from_bits(state as u32))
+    });
+    let ties = (-2000..2000).map(|i| i as f32 / 8.);
+    let edges = [
+        0.,
+        -0.,
+        0.5,
+        1.5,
+        2.5,
+        f32::MAX,
+        f32::MIN,
+        f32::MIN_POSITIVE,
+        f32::EPSILON,
+        16_777_215.5,
+        16_777_216.,
+        1e-45,
+    ];
+    random
+        .take(200_000)
+        .filter(|v| v.is_finite())
+        .chain(ties)
+        .chain(edges)
+}
+
+#[test]
+fn fixed_places_are_cores_text() {
+    for value in values() {
+        for places in 0..=MAX_PLACES {
+            assert_eq!(
+                format!("{:.*}", places, Decimal(value)),
+                format!("{:.*}", places, value),
+                "{:e} at {} places",
+                value,
+                places
+            );
+        }
+    }
+    assert_eq!(format!("{:.12}", Decimal(0.5)), format!("{:.9}", 0.5));
+}
+
+#[test]
+fn default_format_keeps_the_content() {
+    for value in values().filter(|v| v.abs() < 16_777_216.) {
+        let text = format!("{}", Decimal(value));
+        let exact = f64::from(value);
+        let parsed: f64 = text.parse().unwrap();
+        assert!(
+            (parsed - exact).abs() <= 0.5e-6 + exact.abs() * f64::EPSILON,
+            "{} as {}",
+            value,
+            text
+        );
+        // core's shortest form where it has few decimals and is as close
+        let core = format!("{}", value);
+        let decimals = core.split('.').nth(1).map_or(0, str::len);
+        let shortest: f64 = core.parse().unwrap();
+        if decimals <= DEFAULT_PLACES && (shortest - exact).abs() < 0.5e-6 {
+            assert_eq!(text, core, "{}", value);
+        }
+    }
+}
+
+#[test]
+fn flags_and_special_values() {
+    assert_eq!(format!("{:+.1}", Decimal(2.)), "+2.0");
+    assert_eq!(format!("{:+.0}", Decimal(-0.4)), "-0");
+    assert_eq!(format!("{:>8.2}|", Decimal(-1.5)), "   -1.50|");
+    assert_eq!(format!("{:<6}|", Decimal(0.25)), "0.25  |");
+    assert_eq!(format!("{:07.2}", Decimal(-3.)), "-003.00");
+    assert_eq!(format!("{}", Decimal(f32::NAN)), "NaN");
+    assert_eq!(format!("{:.3}", Decimal(f32::INFINITY)), "inf");
+    assert_eq!(format!("{}", Decimal(f32::NEG_INFINITY)), "-inf");
+    assert_eq!(format!("{}", Decimal(1e20)), "100000002004087734272");
+    assert_eq!(
+        format!("{:.1}", DecimalVec(Vec3A::new(0.25, -2., 1e-3))),
+        "[0.2, -2.0, 0.0]"
+    );
+}
+
+#[test]
+fn display_impls_render_as_before() {
+    // the format strings of the impls before the decimal module, formatted by core
+    let assessment = AliasingAssessment {
+        risk: AliasingRisk::Marginal,
+        odr_hz: 1000.,
+        accel_rate_hz: 1000.,
+        accel_bandwidth_hz: 260.,
+        gyro_bandwidth_hz: 256.,
+        ratio: 0.26,
+        cycle: false,
+    };
+    assert_eq!(
+        assessment.to_string(),
+        format!(
+            "{:?}: ODR {} Hz, accel {} Hz over {} Hz bandwidth, gyro {} Hz bandwidth, ratio {:.2}",
+            assessment.risk,
+            assessment.odr_hz,
+            assessment.accel_rate_hz,
+            assessment.accel_bandwidth_hz,
+            assessment.gyro_bandwidth_hz,
+            assessment.ratio
+        )
+    );
+
+    let estimate = ErrorEstimate {
+        horizon_s: 12.5,
+        angle_random_walk_deg: 0.012345,
+        bias_drift_deg: 0.4,
+        quantization_deg: 0.00001,
+        integration_deg: 1.23456,
+        total_deg: 1.3,
+    };
+    let mut before = format!(
+        "orientation error {} s after correction, 1 sigma per axis:\n",
+        estimate.horizon_s
+    );
+    for (name, value) in [
+        ("angle random walk", estimate.angle_random_walk_deg),
+        ("bias drift", estimate.bias_drift_deg),
+        ("quantization", estimate.quantization_deg),
+        ("integration", estimate.integration_deg),
+    ] {
+        before += &format!("  {:<18} {:.4}°\n", name, value);
+    }
+    before += &format!("  {:<18} {:.4}°", "total (rss)", estimate.total_deg);
+    assert_eq!(estimate.to_string(), before);
+
+    let clock = ClockErrorEstimate {
+        ratio: 1.000_125,
+        ratio_low: 1.000_1,
+        ratio_high: 1.000_15,
+        nominal_odr_hz: 1000.,
+        effective_odr_hz: 1000.125,
+        samples: 60_000,
+        windows: 6,
+        duration_us: 60_000_000,
+    };
+    assert_eq!(
+        clock.to_string(),
+        format!(
+            "clock {:+.0} ppm ± {:.0} ppm (95 %), ODR {:.2} Hz nominal {:.2} Hz, {} samples in {} windows",
+            clock.error_ppm(),
+            clock.confidence_ppm(),
+            clock.effective_odr_hz,
+            clock.nominal_odr_hz,
+            clock.samples,
+            clock.windows
+        )
+    );
+
+    let rate = RateTooHigh {
+        required_hz: 1150.5,
+        available_hz: 812.25,
+    };
+    assert_eq!(
+        rate.to_string(),
+        format!(
+            "sample rate {} Hz exceeds bus capacity of {} Hz",
+            rate.required_hz, rate.available_hz
+        )
+    );
+
+    let merged = MergedSample {
+        sample: MpuSample::new(Vec3A::new(0.5, -0.015625, 1.25), Vec3A::ZERO, 25.),
+        sources: [AxisSource::Measured(AccelRange::G2); 3],
+        clipped: false,
+    };
+    assert_eq!(
+        merged.to_string(),
+        format!("{} g from {:?}", merged.sample.acc(), merged.sources)
+    );
+}
@@ -0,0 +1,215 @@
+//! Owned delay: both call styles compile in generic code, the owned flavour waits what the
+//! explicit one does, and waiting without a delay is refused, see the `delay` module.
+
+mod common;
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use embedded_hal::blocking::delay::DelayMs;
+use embedded_hal::blocking::i2c::{Write, WriteRead};
+use mpu6050::device::SettleTrigger;
+use mpu6050::script::*;
+use mpu6050::*;
+
+use common::RegisterMock;
+
+/// delay recording the requested waits
+#[derive(Clone, Default)]
+struct RecordingDelay(Rc<RefCell<Vec<u8>>>);
+
+impl RecordingDelay {
+    fn take(&self) -> Vec<u8> {
+        core::mem::take(&mut self.0.borrow_mut())
+    }
+}
+
+impl DelayMs<u8> for RecordingDelay {
+    fn delay_ms(&mut self, ms: u8) {
+        self.0.borrow_mut().push(ms);
+    }
+}
+
+fn explicit_style<I, E>(
+    mpu: &mut Mpu6050<I>,
+    delay: &mut impl DelayMs<u8>,
+) -> Result<(), Mpu6050Error<E>>
+where
+    I: Write<Error = E> + WriteRead<Error = E>,
+{
+    mpu.init(delay)?;
+    mpu.reset_device(delay)
+}
+
+fn owned_style<I, D, E>(mpu: &mut Mpu6050<I, D>) -> Result<(), Mpu6050Error<E>>
+where
+    I: Write<Error = E> + WriteRead<Error = E>,
+    D: DelayMs<u8>,
+{
+    mpu.init()?;
+    mpu.reset_device()
+}
+
+fn owned() -> (Mpu6050<RegisterMock, RecordingDelay>, RecordingDelay) {
+    let delay = RecordingDelay::default();
+    let mpu = Mpu6050Builder::new()
+        .i2c(RegisterMock::new())
+        .delay(delay.clone())
+        .build()
+        .unwrap();
+    (mpu, delay)
+}
+
+#[test]
+fn both_styles_wait_the_same() {
+    let (mut mpu, delay) = owned();
+    owned_style(&mut mpu).unwrap();
+    let owned_waits = delay.take();
+
+    let mut explicit = Mpu6050Builder::new()
+        .i2c(RegisterMock::new())
+        .build()
+        .unwrap();
+    let mut explicit_delay = RecordingDelay::default();
+    explicit_style(&mut explicit, &mut explicit_delay).unwrap();
+
+    assert_eq!(owned_waits, vec![100, 100]);
+    assert_eq!(owned_waits, explicit_delay.take());
+    assert!(mpu.has_delay());
+    assert!(mpu.debug_state().owns_delay);
+    assert!(!explicit.has_delay());
+    assert!(!explicit.debug_state().owns_delay);
+}
+
+#[test]
+fn builder_delay_keeps_settings() {
+    let mpu = Mpu6050Builder::new()
+        .i2c(RegisterMock::new())
+        .slave_addr(0x69)
+        .strict_configuration(true)
+        .delay(RecordingDelay::default())
+        .build()
+        .unwrap();
+    let state = mpu.debug_state();
+    assert_eq!(state.slave_addr, 0x69);
+    assert!(state.strict_configuration);
+    assert!(state.owns_delay);
+}
+
+#[test]
+fn script_uses_owned_delay() {
+    let (mut mpu, delay) = owned();
+    mpu.init().unwrap();
+    delay.take();
+    let script = RegisterScript::new()
+        .with_step(ScriptStep::DelayMs(20))
+        .with_step(ScriptStep::DelayMs(30));
+    let report = mpu.run_script(&script).unwrap();
+    assert_eq!(report.delay_ms(), 50);
+    assert_eq!(delay.take(), vec![20, 30]);
+}
+
+#[test]
+fn wait_settled_waits_out_the_countdown() {
+    let (mut mpu, delay) = owned();
+    mpu.init().unwrap();
+    delay.take();
+
+    let (acc, gyro) = SettleTrigger::Wake.samples();
+    let period_ms = (1000. / mpu.check_aliasing().odr_hz).ceil() as u32;
+    let expected = u32::from(acc.max(gyro)) * period_ms;
+    assert_eq!(mpu.wait_settled().unwrap(), expected);
+    assert_eq!(
+        delay.take().iter().map(|&ms| u32::from(ms)).sum::<u32>(),
+        expected
+    );
+    assert!(!mpu.debug_state().settle.is_settling());
+
+    // nothing pending, nothing waited
+    assert_eq!(mpu.wait_settled().unwrap(), 0);
+    assert!(delay.take().is_empty());
+}
+
+#[test]
+fn wait_settled_requires_a_delay() {
+    let mut mpu = Mpu6050Builder::new()
+        .i2c(RegisterMock::new())
+        .build()
+        .unwrap();
+    assert_eq!(mpu.wait_settled().unwrap(), 0);
+
+    mpu.init(&mut common::NoDelay).unwrap();
+    assert!(matches!(
+        mpu.wait_settled(),
+        Err(Mpu6050Error::DelayRequired)
+    ));
+    // the countdown is left for the reads to consume
+    assert!(mpu.debug_state().settle.is_settling());
+}
+
+#[test]
+fn init_burst_waits_between_readings() {
+    let frame = synthetic::frame_bytes(
+        synthetic::at_rest(0., 30.),
+        Vec3A::ZERO,
+        device::AccelRange::G2,
+        device::GyroRange::D250,
+    );
+    let mock = || {
+        let mut mock = RegisterMock::new();
+        mock.set_frame(&frame);
+        mock
+    };
+    let delay = RecordingDelay::default();
+    let mut mpu = Mpu6050Builder::new()
+        .i2c(mock())
+        .delay(delay.clone())
+        .build()
+        .unwrap();
+    mpu.init().unwrap();
+    mpu.set_settling_policy(settling::SettlingPolicy::Flag);
+    delay.take();
+    mpu.reset_io_stats();
+    let burst = mpu.collect_init_burst(4).unwrap();
+    assert_eq!(delay.take(), vec![orientation::INIT_BURST_INTERVAL_MS; 3]);
+    assert_eq!(burst.samples, 4);
+    assert_eq!(burst.std_dev_g, 0.);
+    assert!(burst.mean.distance(synthetic::at_rest(0., 30.)) < 1e-3);
+    assert_eq!(mpu.io_stats().transactions, 4);
+
+    let mut explicit = Mpu6050Builder::new().i2c(mock()).build().unwrap();
+    explicit.init(&mut common::NoDelay).unwrap();
+    let mut explicit_delay = RecordingDelay::default();
+    assert_eq!(
+        explicit.collect_init_burst(&mut explicit_delay, 4).unwrap(),
+        burst
+    );
+    assert_eq!(explicit_delay.take().len(), 3);
+}
+
+#[test]
+fn clone_heuristics_wait_for_the_self_test() {
+    use mpu6050::clone_detect::{SELF_TEST_INTERVAL_MS, SELF_TEST_SAMPLES, SELF_TEST_SETTLE_MS};
+
+    let (mut mpu, delay) = owned();
+    mpu.init().unwrap();
+    delay.take();
+    // the mock has no factory trim and no self-test response
+    let assessment = mpu.detect_clone_heuristics();
+    assert!(assessment.is_likely_clone());
+    let mut half = vec![SELF_TEST_SETTLE_MS];
+    half.extend(vec![SELF_TEST_INTERVAL_MS; SELF_TEST_SAMPLES as usize - 1]);
+    assert_eq!(delay.take(), [half.clone(), half].concat());
+
+    let mut explicit = Mpu6050Builder::new()
+        .i2c(RegisterMock::new())
+        .build()
+        .unwrap();
+    explicit.init(&mut common::NoDelay).unwrap();
+    let mut explicit_delay = RecordingDelay::default();
+    assert_eq!(
+        explicit.detect_clone_heuristics(&mut explicit_delay),
+        assessment
+    );
+    assert_eq!(explicit_delay.take().len(), 2 * SELF_TEST_SAMPLES as usize);
+}
@@ -0,0 +1,142 @@
+//! Golden checksums of the pipeline outputs for a fixed vector of raw frames, bit-exact on every
+//! target with the `deterministic` feature, see the `determinism` module.
+//!
+//! The vector is generated with integer arithmetic only, so it is the same everywhere. A
+//! checksum changing means the outputs changed in some bit: on one target only, the feature
+//! lost its guarantee; everywhere, the operation order of the math changed. Check with fused
+//! multiply-add available as well:
+//! `RUSTFLAGS="-C target-feature=+fma" cargo test --features deterministic --test determinism`
+
+use mpu6050::determinism::determinism_checksum;
+use mpu6050::device::{AccelRange, GyroRange};
+use mpu6050::euler::to_xyz_rpy;
+use mpu6050::frame::RawFrame;
+use mpu6050::scale::Pipeline;
+use mpu6050::tilt::roll_pitch;
+use mpu6050::{MpuSample, Quat, Vec3A};
+
+/// scaled acc, gyro and temp of [`frames`]
+const SCALING_CHECKSUM: u64 = 0xb917_aaae_07e6_55a1;
+/// roll and pitch from the scaled acc of [`frames`]
+const TILT_CHECKSUM: u64 = 0x8fe5_670b_5f29_e2ce;
+/// roll, pitch and yaw of quaternions from the raw words of [`frames`]
+const EULER_CHECKSUM: u64 = 0xf173_3102_1dd7_6d95;
+
+/// xorshift64*, as in settings_fuzz
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 >> 12;
+        self.0 ^= self.0 << 25;
+        self.0 ^= self.0 >> 27;
+        self.0.wrapping_mul(0x2545_f491_4f6c_dd1d)
+    }
+
+    fn word(&mut self) -> i16 {
+        (self.next() >> 48) as u16 as i16
+    }
+
+    fn triplet(&mut self) -> [i16; 3] {
+        [self.word(), self.word(), self.word()]
+    }
+}
+
+/// the corners of the value range, then 512 random frames
+fn frames() -> Vec<RawFrame> {
+    let corners = [i16::MIN, -1, 0, 1, i16::MAX];
+    let mut frames: Vec<_> = corners
+        .iter()
+        .map(|&v| RawFrame {
+            acc: [v, -v.saturating_add(1), v / 2],
+            temp: v,
+            gyro: [v / 3, v, -v.saturating_add(1)],
+        })
+        .collect();
+    let mut rng = Rng(0xde7e_4a11);
+    frames.extend((0..512).map(|_| RawFrame {
+        acc: rng.triplet(),
+        temp: rng.word(),
+        gyro: rng.triplet(),
+    }));
+    frames
+}
+
+fn pipeline() -> Pipeline {
+    let mut pipeline = Pipeline::new(AccelRange::G4, GyroRange::D500);
+    pipeline.acc_offset = Vec3A::new(0.012_5, -0.031, 0.07);
+    pipeline.gyro_offset = Vec3A::new(-0.002, 0.000_7, 0.011);
+    pipeline.acc_scale.per_axis = Vec3A::new(1.003, 0.998, 1.012);
+    pipeline.gyro_scale.per_axis = Vec3A::new(0.995, 1.001, 1.004);
+    pipeline
+}
+
+fn scaled() -> Vec<MpuSample> {
+    let pipeline = pipeline();
+    frames()
+        .iter()
+        .map(|frame| pipeline.sample(frame))
+        .collect()
+}
+
+#[test]
+fn scaling_matches_the_golden_checksum() {
+    let samples = scaled();
+    assert_eq!(samples.len(), 517);
+    assert_eq!(determinism_checksum(&samples), SCALING_CHECKSUM);
+}
+
+#[test]
+fn tilt_matches_the_golden_checksum() {
+    let angles: Vec<_> = scaled()
+        .iter()
+        .map(|sample| {
+            let (roll, pitch) = roll_pitch(sample.acc());
+            MpuSample::new(Vec3A::new(roll, pitch, 0.), Vec3A::ZERO, 0.)
+        })
+        .collect();
+    assert_eq!(determinism_checksum(&angles), TILT_CHECKSUM);
+}
+
+#[test]
+fn euler_angles_match_the_golden_checksum() {
+    let angles: Vec<_> = frames()
+        .iter()
+        .map(|frame| {
+            let [x, y, z] = frame.acc.map(f32::from);
+            let q = Quat::from_xyzw(x, y, z, f32::from(frame.temp)).normalize();
+            let rpy = to_xyz_rpy(q);
+            MpuSample::new(Vec3A::new(rpy.roll, rpy.pitch, rpy.yaw), Vec3A::ZERO, 0.)
+        })
+        .collect();
+    assert_eq!(determinism_checksum(&angles), EULER_CHECKSUM);
+}
+
+#[test]
+fn checksum_covers_every_bit_and_canonicalizes_nan() {
+    let sample = MpuSample::new(Vec3A::new(1., 0., 0.), Vec3A::ZERO, 25.);
+    let base = determinism_checksum(&[sample]);
+    let flipped = MpuSample::new(
+        Vec3A::new(f32::from_bits(1f32.to_bits() ^ 1), 0., 0.),
+        Vec3A::ZERO,
+        25.,
+    );
+    assert_ne!(determinism_checksum(&[flipped]), base);
+    // -0 and 0 differ in their bits
+    let negative_zero = MpuSample::new(Vec3A::new(1., -0., 0.), Vec3A::ZERO, 25.);
+    assert_ne!(determinism_checksum(&[negative_zero]), base);
+    // the order of the samples counts
+    let other = MpuSample::new(Vec3A::ZERO, Vec3A::ONE, 20.);
+    assert_ne!(
+        determinism_checksum(&[sample, other]),
+        determinism_checksum(&[other, sample])
+    );
+
+    let nan = |bits| MpuSample::new(Vec3A::new(f32::from_bits(bits), 0., 0.), Vec3A::ZERO, 0.);
+    assert_eq!(
+        determinism_checksum(&[nan(0x7fc0_0001)]),
+        determinism_checksum(&[nan(0xffc0_0000)])
+    );
+    // FNV-1a offset basis for no input
+    assert_eq!(determinism_checksum(&[]), 0xcbf2_9ce4_8422_2325);
+}
@@ -0,0 +1,131 @@
+//! [`DifferentialPair`] on two mock sensors sharing one bus.
+
+mod common;
+
+use std::cell::Cell;
+
+use mpu6050::axis_map::AxisMap;
+use mpu6050::device::{AccelRange, Axis, GyroRange, ACCEL_CONFIG};
+use mpu6050::differential::{DifferentialPair, PairError};
+use mpu6050::presets;
+use mpu6050::*;
+
+use common::{NoDelay, SharedBus};
+
+const SOURCE: u8 = 0x68;
+const PAYLOAD: u8 = 0x69;
+
+fn pair(bus: &SharedBus) -> DifferentialPair<SharedBus, SharedBus> {
+    let build = |address| {
+        Mpu6050Builder::new()
+            .i2c(bus.clone())
+            .slave_addr(address)
+            .build()
+            .unwrap()
+    };
+    let mut pair = DifferentialPair::new(build(SOURCE), build(PAYLOAD));
+    pair.init(&mut NoDelay).unwrap();
+    pair
+}
+
+#[test]
+fn settings_are_applied_to_both() {
+    let bus = SharedBus::new(&[SOURCE, PAYLOAD]);
+    let mut pair = pair(&bus);
+    pair.apply_settings(&presets::VIBRATION_LOGGING).unwrap();
+
+    let config = |address| bus.device(address, |device| device.regs[ACCEL_CONFIG::ADDR as usize]);
+    assert_eq!(config(SOURCE), config(PAYLOAD));
+    assert_eq!(
+        pair.source().current_resolution(),
+        pair.payload().current_resolution()
+    );
+}
+
+#[test]
+fn diverged_configuration_is_reported() {
+    let bus = SharedBus::new(&[SOURCE, PAYLOAD]);
+    let mut pair = pair(&bus);
+    // someone else reconfigured the payload behind the pair's back
+    bus.device(PAYLOAD, |device| {
+        device.regs[ACCEL_CONFIG::ADDR as usize] = 0x18;
+    });
+
+    match pair.check_lockstep() {
+        Err(PairError::Diverged(diff)) => {
+            let regs: Vec<u8> = diff.iter().map(|d| d.addr).collect();
+            assert_eq!(regs, [ACCEL_CONFIG::ADDR]);
+        }
+        other => panic!("expected divergence, got {:?}", other.err()),
+    }
+}
+
+#[test]
+fn source_is_read_completely_before_payload() {
+    let bus = SharedBus::new(&[SOURCE, PAYLOAD]);
+    let mut pair = pair(&bus);
+    bus.take_log();
+
+    pair.read_pair().unwrap();
+    let addresses: Vec<u8> = bus.take_log().iter().map(|a| a.address).collect();
+    let first_payload = addresses.iter().position(|a| *a == PAYLOAD).unwrap();
+    assert!(first_payload > 0);
+    assert!(addresses[..first_payload].iter().all(|a| *a == SOURCE));
+    assert!(addresses[first_payload..].iter().all(|a| *a == PAYLOAD));
+}
+
+#[test]
+fn skew_is_measured_between_read_starts() {
+    let bus = SharedBus::new(&[SOURCE, PAYLOAD]);
+    let mut pair = pair(&bus);
+    // 1 kHz, 100 µs skew allowed with the default fraction
+    pair.set_sample_interval_us(Some(1000.0));
+
+    let script = [10_000u64, 10_080, 20_000, 20_250];
+    let next = Cell::new(0);
+    let clock = || {
+        let t = script[next.get()];
+        next.set(next.get() + 1);
+        t
+    };
+
+    let sample = pair.read_pair_timed(clock).unwrap();
+    assert_eq!(sample.t_us, Some(10_000));
+    assert_eq!(sample.skew_us, Some(80));
+    assert!(!sample.coherence_degraded);
+
+    let sample = pair.read_pair_timed(clock).unwrap();
+    assert_eq!(sample.skew_us, Some(250));
+    assert!(sample.coherence_degraded);
+
+    assert_eq!(pair.read_pair().unwrap().skew_us, None);
+}
+
+#[test]
+fn axis_maps_align_the_frames() {
+    let bus = SharedBus::new(&[SOURCE, PAYLOAD]);
+    let (acc_range, gyro_range) = (AccelRange::G2, GyroRange::D250);
+    // the same motion, 1g along common X and 10 °/s about common Z, plus 5 °/s of payload
+    // roll. The payload is mounted turned 90° about Z: its -Y points along common X
+    bus.device(SOURCE, |device| {
+        let frame =
+            synthetic::frame_bytes(Vec3A::X, Vec3A::new(0., 0., 10.), acc_range, gyro_range);
+        device.set_frame(&frame);
+    });
+    bus.device(PAYLOAD, |device| {
+        let acc = Vec3A::new(0., -1., 0.);
+        let gyro = Vec3A::new(0., -5., 10.);
+        device.set_frame(&synthetic::frame_bytes(acc, gyro, acc_range, gyro_range));
+    });
+
+    let payload_map = AxisMap::new([Axis::Y, Axis::X, Axis::Z], [true, false, false]);
+    assert!(payload_map.is_rotation());
+    let mut pair = pair(&bus).with_axis_maps(AxisMap::IDENTITY, payload_map);
+
+    let sample = pair.read_pair().unwrap();
+    assert!((sample.payload.acc() - Vec3A::X).length() < 1e-3);
+    let differential = sample.differential();
+    assert!(differential.acc.length() < 1e-3);
+    let roll = Vec3A::new(5f32.to_radians(), 0., 0.);
+    assert!((differential.gyro - roll).length() < 1e-3);
+}
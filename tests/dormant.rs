@@ -0,0 +1,660 @@
+//! Dormant drivers: no transaction before `activate` from any public method touching the bus,
+//! the methods enumerated from the API snapshot so a new one cannot slip past, then the init
+//! on activation, see the `dormant` module.
+
+mod common;
+
+use std::collections::BTreeSet;
+use std::fmt::Debug;
+
+use mpu6050::aux_i2c::{SlaveConfig, SlaveSlot};
+use mpu6050::board::IntPinConfig;
+use mpu6050::cooperative::DrainBudget;
+use mpu6050::device::*;
+use mpu6050::fifo::FifoSources;
+use mpu6050::fsync::{FsyncConfig, FsyncTracker};
+use mpu6050::governor::*;
+use mpu6050::impact::{ImpactRangeConfig, RearmPolicy};
+use mpu6050::interleave::RangeInterleave;
+use mpu6050::interpolation::InterpolatingBuffer;
+use mpu6050::interrupt::{InterruptSource, MotionDetectionConfig};
+use mpu6050::low_power::LowPowerAdjust;
+use mpu6050::motion_verify::WakeOnMotionPolicy;
+use mpu6050::op_bounds::IoStats;
+use mpu6050::platform::ReferencedCalibration;
+use mpu6050::register::{ConfigValue, Register, RegisterValue};
+use mpu6050::sampling::SampleControl;
+use mpu6050::script::{RegisterScript, ScriptStep};
+use mpu6050::settings::Mpu6050Settings;
+use mpu6050::setup::AutoSetupOptions;
+use mpu6050::smoothing::FilteredView;
+use mpu6050::split::{ConsumerSpec, OverflowPolicy::DropOldest, StreamSplitter};
+use mpu6050::*;
+
+use common::{NoDelay, SharedBus};
+
+type Mpu = Mpu6050<SharedBus>;
+type Owning = Mpu6050<SharedBus, NoDelay>;
+
+const SNAPSHOT: &str = include_str!("golden/public_api.txt");
+
+fn dormant() -> (Mpu, SharedBus) {
+    let bus = SharedBus::new(&[DEFAULT_SLAVE_ADDR]);
+    let mpu = Mpu6050Builder::new()
+        .i2c(bus.clone())
+        .defer_bus_contact()
+        .build()
+        .unwrap();
+    (mpu, bus)
+}
+
+fn dormant_owning() -> (Owning, SharedBus) {
+    let bus = SharedBus::new(&[DEFAULT_SLAVE_ADDR]);
+    let mpu = Mpu6050Builder::new()
+        .i2c(bus.clone())
+        .delay(NoDelay)
+        .defer_bus_contact()
+        .build()
+        .unwrap();
+    (mpu, bus)
+}
+
+/// public driver methods that can reach the bus: `&mut self` methods of `Mpu6050` returning an
+/// error generic over the bus error, from the snapshot, the upstream compatible wrapper aside
+fn bus_methods() -> BTreeSet<&'static str> {
+    let mut names: BTreeSet<&str> = SNAPSHOT
+        .lines()
+        .filter(|line| !line.starts_with("crate::compat:") && line.contains("> Mpu6050<I"))
+        .filter_map(|line| {
+            let (_, item) = line.split_once(" pub fn ")?;
+            let (name, rest) = item.split_once(['(', '<'])?;
+            let ret = rest.split_once("&mut self")?.1.split_once("->")?.1;
+            (ret.contains("Error<E") || ret.contains("Failure<E")).then_some(name)
+        })
+        .collect();
+    names.remove("activate");
+    if !cfg!(feature = "spectrum") {
+        names.remove("feed_spectrum");
+    }
+    names
+}
+
+/// calls methods on a dormant driver, recording their names
+struct Refusals {
+    bus: SharedBus,
+    called: BTreeSet<&'static str>,
+}
+
+impl Refusals {
+    /// `call` fails with `NotActivated`, possibly wrapped, without a transaction
+    fn check<M, T: Debug, X: Debug>(
+        &mut self,
+        mpu: &mut M,
+        name: &'static str,
+        call: impl FnOnce(&mut M) -> Result<T, X>,
+    ) {
+        let res = format!("{:?}", call(mpu));
+        assert!(
+            res.starts_with("Err(") && res.contains("NotActivated"),
+            "{}: {}",
+            name,
+            res
+        );
+        assert_eq!(self.bus.take_log(), vec![], "{} reached the bus", name);
+        self.called.insert(name);
+    }
+
+    /// `call`, which has no register access, runs as on an active driver
+    fn check_no_bus<M, T>(
+        &mut self,
+        mpu: &mut M,
+        name: &'static str,
+        call: impl FnOnce(&mut M) -> T,
+    ) {
+        call(mpu);
+        assert_eq!(self.bus.take_log(), vec![], "{} reached the bus", name);
+        self.called.insert(name);
+    }
+}
+
+fn governor() -> PowerGovernor {
+    let config = GovernorConfig::two(
+        OperatingPoint::new("rest", presets::LOW_POWER_TILT, 100),
+        OperatingPoint::new("move", presets::HANDHELD_UI, 100),
+        GovernorRule {
+            up: ActivityLevel {
+                gyro_rms: 0.3,
+                acc_variance: 0.01,
+            },
+            down: ActivityLevel {
+                gyro_rms: 0.1,
+                acc_variance: 0.002,
+            },
+        },
+    );
+    PowerGovernor::new(config, 0).unwrap()
+}
+
+#[allow(deprecated, clippy::result_large_err)]
+#[test]
+fn dormant_driver_refuses_every_bus_method_without_traffic() {
+    let (mut mpu, bus) = dormant();
+    let mut r = Refusals {
+        bus,
+        called: BTreeSet::new(),
+    };
+    assert!(mpu.is_dormant());
+    assert!(mpu.debug_state().dormant);
+    let options = AutoSetupOptions::default();
+    let settings = Mpu6050Settings::new();
+    let schema = mpu.fifo_schema();
+    let mut buf = [0; 14];
+
+    // core and raw access
+    r.check(&mut mpu, "init", |m| m.init(&mut NoDelay));
+    r.check(&mut mpu, "reset_device", |m| m.reset_device(&mut NoDelay));
+    r.check(&mut mpu, "try_reconnect", |m| m.try_reconnect(&mut NoDelay));
+    r.check(&mut mpu, "get_acc", Mpu::get_acc);
+    r.check(&mut mpu, "get_acc_as", |m| m.get_acc_as::<[f32; 3]>());
+    r.check(&mut mpu, "get_acc_raw", Mpu::get_acc_raw);
+    r.check(&mut mpu, "get_acc_angles", Mpu::get_acc_angles);
+    r.check(&mut mpu, "get_acc_angles_as", |m| {
+        m.get_acc_angles_as::<[f32; 4]>()
+    });
+    r.check(
+        &mut mpu,
+        "get_acc_angles_checked",
+        Mpu::get_acc_angles_checked,
+    );
+    r.check(&mut mpu, "get_gyro", Mpu::get_gyro);
+    r.check(&mut mpu, "get_gyro_as", |m| m.get_gyro_as::<[f32; 3]>());
+    r.check(&mut mpu, "get_gyro_raw", Mpu::get_gyro_raw);
+    r.check(&mut mpu, "get_temp", Mpu::get_temp);
+    r.check(&mut mpu, "get_temp_raw", Mpu::get_temp_raw);
+    r.check(&mut mpu, "get_all", Mpu::get_all);
+    r.check(&mut mpu, "get_all_raw", Mpu::get_all_raw);
+    r.check(&mut mpu, "update_orientation", |m| {
+        m.update_orientation(&mut orientation::ComplementaryFilter::default(), 0.01)
+    });
+    r.check(&mut mpu, "get_accel_hpf", Mpu::get_accel_hpf);
+    r.check(&mut mpu, "set_accel_hpf", |m| {
+        m.set_accel_hpf(ACCEL_HPF::_RESET)
+    });
+    r.check(&mut mpu, "get_accel_range", Mpu::get_accel_range);
+    r.check(&mut mpu, "set_accel_range", |m| {
+        m.set_accel_range(AccelRange::G4)
+    });
+    r.check(&mut mpu, "get_gyro_range", Mpu::get_gyro_range);
+    r.check(&mut mpu, "set_gyro_range", |m| {
+        m.set_gyro_range(GyroRange::D500)
+    });
+    r.check(&mut mpu, "get_clock_source", Mpu::get_clock_source);
+    r.check(&mut mpu, "set_clock_source", |m| {
+        m.set_clock_source(CLKSEL::GXAXIS)
+    });
+    r.check(
+        &mut mpu,
+        "get_accel_x_self_test",
+        Mpu::get_accel_x_self_test,
+    );
+    r.check(
+        &mut mpu,
+        "get_accel_y_self_test",
+        Mpu::get_accel_y_self_test,
+    );
+    r.check(
+        &mut mpu,
+        "get_accel_z_self_test",
+        Mpu::get_accel_z_self_test,
+    );
+    r.check(&mut mpu, "set_accel_x_self_test", |m| {
+        m.set_accel_x_self_test(true)
+    });
+    r.check(&mut mpu, "set_accel_y_self_test", |m| {
+        m.set_accel_y_self_test(true)
+    });
+    r.check(&mut mpu, "set_accel_z_self_test", |m| {
+        m.set_accel_z_self_test(true)
+    });
+    r.check(&mut mpu, "get_motion_detected", Mpu::get_motion_detected);
+    r.check(&mut mpu, "setup_motion_detection", |m| {
+        m.setup_motion_detection(MotionDetectionConfig::DEFAULT)
+    });
+    r.check(&mut mpu, "get_sleep_enabled", Mpu::get_sleep_enabled);
+    r.check(&mut mpu, "set_sleep_enabled", |m| {
+        m.set_sleep_enabled(false)
+    });
+    r.check(&mut mpu, "get_temp_enabled", Mpu::get_temp_enabled);
+    r.check(&mut mpu, "set_temp_enabled", |m| m.set_temp_enabled(false));
+    r.check(&mut mpu, "read_byte", |m| m.read_byte(0x75));
+    r.check(&mut mpu, "read_bytes", |m| m.read_bytes(0x3b, &mut buf));
+    r.check(&mut mpu, "read_word", |m| m.read_word(0x3b));
+    r.check(&mut mpu, "read_bits", |m| m.read_bits(0x6b, 2, 3));
+    r.check(&mut mpu, "write_byte", |m| m.write_byte(0x6b, 0));
+    r.check(&mut mpu, "write_word", |m| m.write_word(0x13, 0));
+    r.check(&mut mpu, "write_bit", |m| m.write_bit(0x6b, 6, false));
+    r.check(&mut mpu, "write_bits", |m| m.write_bits(0x6b, 2, 3, 1));
+    // register layer
+    let reg = Register::PWR_MGMT_1;
+    let field = BitBlock { bit: 2, length: 3 };
+    r.check(&mut mpu, "read_register", |m| m.read_register(reg));
+    r.check(&mut mpu, "read_register_bit", |m| {
+        m.read_register_bit(reg, 6)
+    });
+    r.check(&mut mpu, "read_register_field", |m| {
+        m.read_register_field(reg, field)
+    });
+    r.check(&mut mpu, "read_register_word", |m| {
+        m.read_register_word(Register::ACCEL_XOUT_H)
+    });
+    r.check(&mut mpu, "read_registers", |m| {
+        m.read_registers(Register::ACCEL_XOUT_H, &mut buf)
+    });
+    r.check(&mut mpu, "read_typed", Mpu::read_typed::<GYRO_CONFIG>);
+    r.check(&mut mpu, "write_register", |m| m.write_register(reg, 0));
+    r.check(&mut mpu, "write_register_bit", |m| {
+        m.write_register_bit(reg, 6, false)
+    });
+    r.check(&mut mpu, "write_register_field", |m| {
+        m.write_register_field(reg, field, 1)
+    });
+    r.check(&mut mpu, "write_register_word", |m| {
+        m.write_register_word(Register::XG_OFFS_USRH, 0)
+    });
+    r.check(&mut mpu, "write_typed", |m| {
+        m.write_typed::<CONFIG>(ConfigValue {
+            ext_sync: EXT_SYNC::DISABLED,
+            dlpf_cfg: 3,
+        })
+    });
+    r.check(&mut mpu, "modify_register", |m| {
+        m.modify_register(reg, |v| v)
+    });
+    r.check(&mut mpu, "read_fields", Mpu::read_fields::<PWR_MGMT_1>);
+    r.check(&mut mpu, "read_field", |m| {
+        m.read_field(PWR_MGMT_1::CLKSEL_FIELD)
+    });
+    r.check(&mut mpu, "read_flag", |m| {
+        m.read_flag(PWR_MGMT_1::SLEEP_FIELD)
+    });
+    r.check(&mut mpu, "write_fields", |m| {
+        m.write_fields(RegisterValue::<PWR_MGMT_1>::reset())
+    });
+    r.check(&mut mpu, "write_field", |m| {
+        m.write_field(PWR_MGMT_1::SLEEP_FIELD, false)
+    });
+    r.check(&mut mpu, "modify_fields", |m| {
+        m.modify_fields::<PWR_MGMT_1>(|v| v)
+    });
+    let script = RegisterScript::new().with_step(ScriptStep::WriteReg {
+        reg: SIGNAL_PATH_RESET::ADDR,
+        value: 0x07,
+    });
+    r.check(&mut mpu, "run_script", |m| {
+        m.run_script(&script, &mut NoDelay)
+    });
+    // settings and configuration
+    r.check(&mut mpu, "read_settings", Mpu::read_settings);
+    r.check(
+        &mut mpu,
+        "adopt_device_settings",
+        Mpu::adopt_device_settings,
+    );
+    r.check(&mut mpu, "apply_settings", |m| m.apply_settings(&settings));
+    r.check(&mut mpu, "apply_settings_diff", |m| {
+        m.apply_settings_diff(&settings, &presets::HANDHELD_UI)
+    });
+    r.check(&mut mpu, "set_dlpf", |m| m.set_dlpf(3));
+    r.check(&mut mpu, "set_sample_rate_divider", |m| {
+        m.set_sample_rate_divider(9)
+    });
+    r.check(&mut mpu, "get_dlpf", Mpu::get_dlpf);
+    r.check(&mut mpu, "set_dlpf_bandwidth", |m| {
+        m.set_dlpf_bandwidth(DLPF::_44)
+    });
+    r.check(
+        &mut mpu,
+        "get_sample_rate_divider",
+        Mpu::get_sample_rate_divider,
+    );
+    r.check(&mut mpu, "set_sample_rate_hz", |m| {
+        m.set_sample_rate_hz(100)
+    });
+    r.check(&mut mpu, "adjust_low_power", |m| {
+        m.adjust_low_power(LowPowerAdjust::default())
+    });
+    r.check(&mut mpu, "enter_low_power_accel_mode", |m| {
+        m.enter_low_power_accel_mode(LowPowerWakeFrequency::Hz5)
+    });
+    r.check(&mut mpu, "exit_low_power_mode", |m| {
+        m.exit_low_power_mode(&mut NoDelay)
+    });
+    r.check(&mut mpu, "characterize_self_heating", |m| {
+        m.characterize_self_heating(&mut NoDelay, 60, 1000, &mut |_| {})
+    });
+    r.check(&mut mpu, "characterize_self_heating_with_deadline", |m| {
+        m.characterize_self_heating_with_deadline(&mut NoDelay, 60, 1000, &mut |_| {}, || false)
+    });
+    r.check(&mut mpu, "set_cycle_enabled", |m| {
+        m.set_cycle_enabled(false)
+    });
+    r.check(&mut mpu, "set_standby_mask", |m| {
+        m.set_standby_mask(StandbyFlags::NONE)
+    });
+    r.check(&mut mpu, "get_standby_mask", Mpu::get_standby_mask);
+    r.check(&mut mpu, "read_config", Mpu::read_config);
+    r.check(
+        &mut mpu,
+        "assert_device_at_defaults",
+        Mpu::assert_device_at_defaults,
+    );
+    r.check(&mut mpu, "build_log_header", Mpu::build_log_header);
+    r.check(&mut mpu, "probe_capabilities", Mpu::probe_capabilities);
+    r.check(&mut mpu, "probe_address", Mpu::probe_address);
+    r.check(&mut mpu, "configure_int_pin", |m| {
+        m.configure_int_pin(IntPinConfig::MOTION)
+    });
+    r.check(
+        &mut mpu,
+        "enable_data_ready_strobe",
+        Mpu::enable_data_ready_strobe,
+    );
+    r.check(&mut mpu, "get_fsync_source", Mpu::get_fsync_source);
+    r.check(&mut mpu, "set_fsync_source", |m| {
+        m.set_fsync_source(EXT_SYNC::TEMP_OUT_L)
+    });
+    let mut tracker = FsyncTracker::new(FsyncConfig::default());
+    r.check(&mut mpu, "get_all_fsync", |m| {
+        m.get_all_fsync(EXT_SYNC::TEMP_OUT_L, &mut tracker, 1000)
+    });
+    assert_eq!(tracker.stats().samples, 0);
+    r.check(&mut mpu, "get_accel_hw_offsets", Mpu::get_accel_hw_offsets);
+    r.check(&mut mpu, "get_gyro_hw_offsets", Mpu::get_gyro_hw_offsets);
+    r.check(&mut mpu, "set_accel_hw_offsets", |m| {
+        m.set_accel_hw_offsets([0; 3])
+    });
+    r.check(&mut mpu, "set_gyro_hw_offsets", |m| {
+        m.set_gyro_hw_offsets([0; 3])
+    });
+    r.check(
+        &mut mpu,
+        "store_gyro_offset_in_hardware",
+        Mpu::store_gyro_offset_in_hardware,
+    );
+    r.check(&mut mpu, "enable_range_interleaving", |m| {
+        m.enable_range_interleaving(RangeInterleave {
+            low: AccelRange::G2,
+            high: AccelRange::G16,
+            period: 4,
+        })
+    });
+    r.check(&mut mpu, "disable_range_interleaving", |m| {
+        m.disable_range_interleaving(AccelRange::G2)
+    });
+    // arming and disarming impact ranging write nothing outside a hold
+    r.check_no_bus(&mut mpu, "configure_impact_ranging", |m| {
+        m.configure_impact_ranging(ImpactRangeConfig {
+            trigger_g: 1.8,
+            hold_samples: 8,
+            high_range: AccelRange::G16,
+            rearm_policy: RearmPolicy::Immediate,
+        })
+        .unwrap()
+    });
+    r.check_no_bus(&mut mpu, "disable_impact_ranging", |m| {
+        m.disable_impact_ranging().unwrap()
+    });
+    // auxiliary bus
+    let slave = SlaveConfig {
+        address: 0x77,
+        register: 0xf7,
+        len: 3,
+        read: true,
+        byte_swap: false,
+    };
+    r.check(&mut mpu, "set_i2c_master_enabled", |m| {
+        m.set_i2c_master_enabled(true)
+    });
+    r.check(&mut mpu, "configure_i2c_slave", |m| {
+        m.configure_i2c_slave(SlaveSlot::Slv0, slave)
+    });
+    // a slot handed out by an active driver
+    let mut active = Mpu6050Builder::new()
+        .i2c(SharedBus::new(&[DEFAULT_SLAVE_ADDR]))
+        .build()
+        .unwrap();
+    let ext = active
+        .configure_i2c_slave(SlaveSlot::Slv0, slave)
+        .unwrap()
+        .unwrap();
+    r.check(&mut mpu, "read_ext_slot", |m| {
+        m.read_ext_slot(&ext, &mut buf)
+    });
+    r.check(&mut mpu, "disable_i2c_slave", |m| {
+        m.disable_i2c_slave(SlaveSlot::Slv0)
+    });
+    // FIFO
+    r.check(&mut mpu, "set_fifo_sources", |m| {
+        m.set_fifo_sources(FifoSources::NONE.with_accel(true))
+    });
+    r.check(&mut mpu, "set_fifo_enabled", |m| m.set_fifo_enabled(true));
+    r.check(&mut mpu, "reset_fifo", Mpu::reset_fifo);
+    r.check(&mut mpu, "fifo_count", Mpu::fifo_count);
+    r.check(&mut mpu, "drain_fifo", |m| {
+        m.drain_fifo(&schema, &mut [0; 64], |_| ())
+    });
+    r.check(&mut mpu, "drain_fifo_cooperative", |m| {
+        m.drain_fifo_cooperative(&schema, &mut [0; 64], DrainBudget::Bytes(16), || ())
+    });
+    r.check(&mut mpu, "drain_fifo_into", |m| {
+        let mut splitter = StreamSplitter::<1, 4>::new([ConsumerSpec::new(1, DropOldest)]);
+        m.drain_fifo_into(&schema, &mut [0; 64], &mut splitter)
+    });
+    // interrupts and power
+    r.check(
+        &mut mpu,
+        "poll_interrupt_events",
+        Mpu::poll_interrupt_events,
+    );
+    r.check(&mut mpu, "take_motion_event", |m| m.take_motion_event(0));
+    r.check(&mut mpu, "get_int_status", Mpu::get_int_status);
+    r.check(&mut mpu, "set_int_enabled", |m| {
+        m.set_int_enabled(InterruptSource::Motion, true)
+    });
+    r.check(&mut mpu, "get_int_enabled", Mpu::get_int_enabled);
+    r.check(&mut mpu, "set_power_governor", |m| {
+        m.set_power_governor(governor())
+    });
+    r.check(&mut mpu, "governor_tick", |m| m.governor_tick(10));
+    r.check(&mut mpu, "governor_motion_wake", Mpu::governor_motion_wake);
+    r.check(&mut mpu, "shutdown", |m| m.shutdown(&mut NoDelay));
+    r.check(
+        &mut mpu,
+        "verify_shutdown_state",
+        Mpu::verify_shutdown_state,
+    );
+    // the best effort refuses every step alike, quietly
+    assert_eq!(mpu.shutdown_best_effort(&mut NoDelay).failures().count(), 7);
+    assert_eq!(r.bus.take_log(), vec![]);
+    // sampling and setup
+    r.check(&mut mpu, "read_into_views", |m| {
+        m.read_into_views::<3>(&mut [&mut FilteredView::new(Default::default())])
+    });
+    r.check(&mut mpu, "read_batch", |m| {
+        m.read_batch(|| {}, &mut [MpuSample::default(); 2])
+    });
+    r.check(&mut mpu, "sample_into_interp_buffer", |m| {
+        m.sample_into_interp_buffer(&mut InterpolatingBuffer::<4>::new(), 0, None)
+    });
+    r.check(&mut mpu, "run_sampling_loop", |m| {
+        m.run_sampling_loop(|| Ok::<(), ()>(()), |_, _| SampleControl::Stop)
+    });
+    r.check(&mut mpu, "estimate_clock_error", |m| {
+        m.estimate_clock_error(&mut NoDelay, 100)
+    });
+    r.check(&mut mpu, "auto_setup", |m| {
+        m.auto_setup(&mut NoDelay, options, None)
+    });
+    r.check(&mut mpu, "auto_setup_with_deadline", |m| {
+        m.auto_setup_with_deadline(&mut NoDelay, options, None, || false)
+    });
+    r.check(&mut mpu, "auto_setup_cooperative", |m| {
+        m.auto_setup_cooperative(
+            &mut NoDelay,
+            options,
+            None,
+            DrainBudget::Transactions(8),
+            || (),
+        )
+    });
+    let previous = mpu
+        .auto_setup(&mut NoDelay, options, None)
+        .unwrap_err()
+        .report;
+    r.check(&mut mpu, "auto_setup_resume", |m| {
+        m.auto_setup_resume(&mut NoDelay, options, &previous, None, || false)
+    });
+    r.check(&mut mpu, "calibrate_gyro_with_reference", |m| {
+        let calibration = ReferencedCalibration::gyro(&options);
+        m.calibrate_gyro_with_reference(&mut NoDelay, || Vec3A::ZERO, calibration)
+    });
+    r.check(&mut mpu, "calibrate_accel_with_reference", |m| {
+        let calibration = ReferencedCalibration::accel(&options);
+        m.calibrate_accel_with_reference(&mut NoDelay, || Vec3A::Z, calibration)
+    });
+    r.check(&mut mpu, "calibrate_gyro", |m| {
+        m.calibrate_gyro(&mut NoDelay, 4)
+    });
+    r.check(&mut mpu, "calibrate_accel", |m| {
+        m.calibrate_accel(&mut NoDelay, 4)
+    });
+    r.check(&mut mpu, "collect_init_burst", |m| {
+        m.collect_init_burst(&mut NoDelay, 4)
+    });
+    r.check(
+        &mut mpu,
+        "calibrate_gyro_with_reference_with_deadline",
+        |m| {
+            let calibration = ReferencedCalibration::gyro(&options);
+            m.calibrate_gyro_with_reference_with_deadline(
+                &mut NoDelay,
+                || Vec3A::ZERO,
+                calibration,
+                || false,
+            )
+        },
+    );
+    r.check(
+        &mut mpu,
+        "calibrate_accel_with_reference_with_deadline",
+        |m| {
+            let calibration = ReferencedCalibration::accel(&options);
+            m.calibrate_accel_with_reference_with_deadline(
+                &mut NoDelay,
+                || Vec3A::Z,
+                calibration,
+                || false,
+            )
+        },
+    );
+    r.check(&mut mpu, "calibrate_gyro_with_deadline", |m| {
+        m.calibrate_gyro_with_deadline(&mut NoDelay, 4, || false)
+    });
+    r.check(&mut mpu, "calibrate_accel_with_deadline", |m| {
+        m.calibrate_accel_with_deadline(&mut NoDelay, 4, || false)
+    });
+    r.check(&mut mpu, "collect_init_burst_with_deadline", |m| {
+        m.collect_init_burst_with_deadline(&mut NoDelay, 4, || false)
+    });
+    r.check(&mut mpu, "verify_motion_detection", |m| {
+        m.verify_motion_detection(&mut NoDelay, None)
+    });
+    r.check(&mut mpu, "verify_motion_detection_with_deadline", |m| {
+        m.verify_motion_detection_with_deadline(&mut NoDelay, None, || false)
+    });
+    r.check(&mut mpu, "arm_wake_on_motion", |m| {
+        m.arm_wake_on_motion(
+            WakeOnMotionPolicy::RequireVerified,
+            MotionDetectionConfig::DEFAULT,
+        )
+    });
+    #[cfg(feature = "spectrum")]
+    r.check(&mut mpu, "feed_spectrum", |m| {
+        use mpu6050::spectrum::{SpectrumAnalyzer, SpectrumSource};
+        m.feed_spectrum(&mut SpectrumAnalyzer::<64>::new(SpectrumSource::AccelX))
+    });
+
+    // the owned delay flavours
+    let (mut owning, bus) = dormant_owning();
+    r.bus = bus;
+    r.check(&mut owning, "init", Owning::init);
+    r.check(&mut owning, "reset_device", Owning::reset_device);
+    r.check(&mut owning, "try_reconnect", Owning::try_reconnect);
+    r.check_no_bus(&mut owning, "wait_settled", |m| {
+        assert_eq!(m.wait_settled().unwrap(), 0);
+    });
+    r.check_no_bus(&mut owning, "wait_settled_with_deadline", |m| {
+        assert_eq!(m.wait_settled_with_deadline(|| false).unwrap(), 0);
+    });
+    r.check(&mut owning, "run_script", |m| m.run_script(&script));
+    r.check(&mut owning, "collect_init_burst", |m| {
+        m.collect_init_burst(4)
+    });
+    r.check(&mut owning, "calibrate_gyro_with_deadline", |m| {
+        m.calibrate_gyro_with_deadline(4, || false)
+    });
+    r.check(&mut owning, "auto_setup", |m| m.auto_setup(options, None));
+    r.check(&mut owning, "shutdown", Owning::shutdown);
+    r.check(
+        &mut owning,
+        "exit_low_power_mode",
+        Owning::exit_low_power_mode,
+    );
+    r.check(&mut owning, "verify_motion_detection", |m| {
+        m.verify_motion_detection(None)
+    });
+    r.check(&mut owning, "characterize_self_heating", |m| {
+        m.characterize_self_heating(60, 1000, &mut |_| {})
+    });
+    r.check(&mut owning, "auto_setup_cooperative", |m| {
+        m.auto_setup_cooperative(options, None, DrainBudget::Bytes(64), || ())
+    });
+
+    let missing: Vec<_> = bus_methods().difference(&r.called).copied().collect();
+    assert_eq!(
+        missing,
+        Vec::<&str>::new(),
+        "bus methods not checked dormant"
+    );
+    // nothing counted, nothing seen by the connection monitor
+    assert_eq!(mpu.io_stats(), IoStats::default());
+    assert!(mpu.is_dormant());
+}
+
+#[test]
+fn activate_runs_the_init_profile() {
+    let (mut mpu, bus) = dormant();
+    mpu.activate(&mut NoDelay).unwrap();
+    assert!(!mpu.is_dormant());
+    assert!(!mpu.debug_state().dormant);
+
+    // the same traffic as the init of a driver built active
+    let reference = SharedBus::new(&[DEFAULT_SLAVE_ADDR]);
+    let mut active = Mpu6050Builder::new()
+        .i2c(reference.clone())
+        .build()
+        .unwrap();
+    assert!(!active.is_dormant());
+    active.init(&mut NoDelay).unwrap();
+    let log = bus.take_log();
+    assert!(!log.is_empty());
+    assert_eq!(log, reference.take_log());
+    mpu.get_acc().unwrap();
+
+    let (mut owning, bus) = dormant_owning();
+    owning.activate().unwrap();
+    assert_eq!(bus.take_log(), log);
+    // activating an active driver is its init
+    active.activate(&mut NoDelay).unwrap();
+    assert_eq!(reference.take_log(), log);
+}
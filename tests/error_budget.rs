@@ -0,0 +1,109 @@
+//! Error budget against hand computed cases, see the `error_budget` module.
+
+mod common;
+
+use mpu6050::device::{GyroRange, DEFAULT_SLAVE_ADDR};
+use mpu6050::error_budget::{ErrorBudget, ErrorEstimate, ErrorTracker};
+use mpu6050::*;
+
+use common::{NoDelay, SharedBus};
+
+/// (horizon, angle random walk, bias, quantization, integration, total)
+type Case = (f32, f32, f32, f32, f32, f32);
+
+fn assert_case(budget: &ErrorBudget, case: Case) {
+    let (horizon, arw, bias, quantization, integration, total) = case;
+    let estimate = budget.estimate(horizon);
+    let pairs = [
+        ("angle random walk", estimate.angle_random_walk_deg, arw),
+        ("bias", estimate.bias_drift_deg, bias),
+        ("quantization", estimate.quantization_deg, quantization),
+        ("integration", estimate.integration_deg, integration),
+        ("total", estimate.total_deg, total),
+    ];
+    for (name, actual, expected) in pairs {
+        assert!(
+            (actual - expected).abs() <= expected * 1e-4,
+            "{} after {} s: {} vs {}",
+            name,
+            horizon,
+            actual,
+            expected
+        );
+    }
+}
+
+#[test]
+fn fine_range_at_1khz() {
+    // ±250 °/s, q = 1/131 °/s, 1 kHz, datasheet noise 0.005 °/s/√Hz, default bias
+    // instability 0.01 °/s and angular acceleration 100 °/s²:
+    //   ARW 0.005 √t, bias 0.01 t, quantization q/√12 √(t/1000), integration 100 t / 2000
+    let budget = ErrorBudget::new(GyroRange::D250, 1000.);
+    assert_case(&budget, (1., 0.005, 0.01, 6.96848e-5, 0.05, 0.0512348));
+    assert_case(&budget, (10., 0.0158114, 0.1, 2.20363e-4, 0.5, 0.510147));
+    assert_case(&budget, (60., 0.0387298, 0.6, 5.39776e-4, 3., 3.05966));
+}
+
+#[test]
+fn coarse_range_at_100hz_with_measured_figures() {
+    // ±2000 °/s, q = 1/16.4 °/s, 100 Hz, bias instability 0.005 °/s, 2 °/s²:
+    //   ARW 0.005 √t, bias 0.005 t, quantization q/√12 √(t/100), integration 2 t / 200
+    let budget = ErrorBudget::new(GyroRange::D2000, 100.)
+        .with_bias_instability(0.005)
+        .with_angular_acceleration(2.);
+    assert_case(&budget, (1., 0.005, 0.005, 1.76021e-3, 0.01, 0.0123733));
+    assert_case(&budget, (10., 0.0158114, 0.05, 5.56629e-3, 0.1, 0.113053));
+    assert_case(&budget, (60., 0.0387298, 0.3, 0.0136346, 0.6, 0.672076));
+}
+
+#[test]
+fn noise_density_override_and_zero_horizon() {
+    let budget = ErrorBudget::new(GyroRange::D500, 200.).with_noise_density(0.01);
+    assert_eq!(budget.estimate(9.).angle_random_walk_deg, 0.03);
+    let zero = budget.estimate(0.);
+    assert_eq!(zero.total_deg, 0.);
+    assert_eq!(budget.estimate(-1.), zero);
+}
+
+#[test]
+fn report_rendering() {
+    let estimate = ErrorBudget::new(GyroRange::D250, 1000.).estimate(10.);
+    assert_eq!(
+        estimate.to_string(),
+        "orientation error 10 s after correction, 1 sigma per axis:\n\
+         \x20 angle random walk  0.0158°\n\
+         \x20 bias drift         0.1000°\n\
+         \x20 quantization       0.0002°\n\
+         \x20 integration        0.5000°\n\
+         \x20 total (rss)        0.5101°"
+    );
+}
+
+#[test]
+fn runtime_estimate_shares_the_model() {
+    let budget = ErrorBudget::new(GyroRange::D250, 1000.);
+    let mut tracker = ErrorTracker::new(budget);
+    assert_eq!(tracker.current_error_estimate(), budget.estimate(0.));
+    // 2.5 s of 4 ms updates
+    for _ in 0..625 {
+        tracker.advance(0.004);
+    }
+    let estimate: ErrorEstimate = tracker.current_error_estimate();
+    assert!((tracker.elapsed_s() - 2.5).abs() < 1e-4);
+    assert_eq!(estimate, budget.estimate(tracker.elapsed_s()));
+    tracker.correct();
+    assert_eq!(tracker.elapsed_s(), 0.);
+    assert_eq!(tracker.current_error_estimate().total_deg, 0.);
+}
+
+#[test]
+fn driver_configuration() {
+    let bus = SharedBus::new(&[DEFAULT_SLAVE_ADDR]);
+    let mut mpu = Mpu6050Builder::new().i2c(bus).build().unwrap();
+    mpu.init(&mut NoDelay).unwrap();
+    mpu.set_gyro_range(GyroRange::D1000).unwrap();
+    mpu.set_dlpf(3).unwrap();
+    mpu.set_sample_rate_divider(9).unwrap();
+    assert_eq!(mpu.error_budget(), ErrorBudget::new(GyroRange::D1000, 100.));
+    assert_eq!(mpu.error_budget().lsb_dps, 1. / 32.8);
+}
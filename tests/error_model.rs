@@ -0,0 +1,255 @@
+//! Sensor error models: the truth through an empty model, the deviation of the noise and the
+//! walk from their densities, the deterministic accelerometer and temperature terms, dropouts,
+//! the integrated orientation and the determinism by seed, see the `error_model` module.
+
+use mpu6050::device::{AccelRange, GyroRange};
+use mpu6050::error_model::*;
+use mpu6050::frame::encode_frame;
+use mpu6050::test_fixtures::{encode, Truth, ROTATING_Z, STATIONARY, TEMP_RAMP, TILTED_30};
+use mpu6050::*;
+
+const DT: f32 = 0.01;
+
+fn simulator(model: ErrorModel, seed: u64) -> Simulator {
+    Simulator::new(model, AccelRange::G2, GyroRange::D250, DT, seed)
+}
+
+fn std_dev(values: impl Iterator<Item = f32>) -> f32 {
+    let values: Vec<f32> = values.collect();
+    let mean = values.iter().sum::<f32>() / values.len() as f32;
+    let var = values.iter().map(|v| (v - mean).powi(2)).sum::<f32>() / values.len() as f32;
+    var.sqrt()
+}
+
+#[test]
+fn without_errors_the_samples_are_the_truth() {
+    let mut sim = Simulator::for_fixture(&TILTED_30, ErrorModel::NONE, 1);
+    for (idx, emitted) in sim.run(&TILTED_30).iter().enumerate() {
+        let truth = TILTED_30.truth(idx);
+        assert_eq!(emitted.sample.acc(), truth.acc_g);
+        assert_eq!(emitted.sample.gyro(), truth.gyro_dps * PI_180);
+        assert_eq!(emitted.frame, TILTED_30.frame(idx));
+        assert_eq!(emitted.truth.truth, truth);
+        assert_eq!(emitted.truth.accel_bias_g, Vec3A::ZERO);
+        assert_eq!(emitted.truth.gyro_bias_dps, Vec3A::ZERO);
+    }
+
+    // quantized, the driver's output of the fixture bit for bit
+    let model = ErrorModel::NONE.with_quantization(true);
+    let mut sim = Simulator::for_fixture(&ROTATING_Z, model, 1);
+    for (idx, emitted) in sim.run(&ROTATING_Z).iter().enumerate() {
+        assert_eq!(emitted.sample, ROTATING_Z.expected(idx));
+        assert_eq!(emitted.bytes(), ROTATING_Z.bytes(idx));
+    }
+}
+
+#[test]
+fn white_noise_has_the_deviation_of_its_density() {
+    let mut sim = simulator(ErrorModel::NONE.with_white_noise(WhiteNoise::MPU6050), 5);
+    let samples: Vec<SimSample> = (0..20_000).map(|_| sim.step(&Truth::FLAT)).collect();
+    // 50 Hz of noise bandwidth at 100 Hz
+    let acc = 400e-6 * 50f32.sqrt();
+    let gyro = 0.005 * 50f32.sqrt() * PI_180;
+    for axis in 0..3 {
+        let a = std_dev(samples.iter().map(|s| s.sample.acc()[axis]));
+        let g = std_dev(samples.iter().map(|s| s.sample.gyro()[axis]));
+        assert!((a / acc - 1.).abs() < 0.03, "accel {} {}", axis, a);
+        assert!((g / gyro - 1.).abs() < 0.03, "gyro {} {}", axis, g);
+    }
+
+    // the DLPF bandwidth narrows it
+    let narrow = WhiteNoise {
+        bandwidth_hz: Some(5.),
+        ..WhiteNoise::MPU6050
+    };
+    let mut sim = simulator(ErrorModel::NONE.with_white_noise(narrow), 5);
+    let z = std_dev((0..20_000).map(|_| sim.step(&Truth::FLAT).sample.acc().z));
+    assert!((z / (400e-6 * 5f32.sqrt()) - 1.).abs() < 0.03, "{}", z);
+}
+
+#[test]
+fn bias_walk_steps_with_its_coefficient() {
+    let walk = GyroBiasWalk {
+        initial_dps: Vec3A::new(0.5, -0.3, 0.2),
+        random_walk_dps_per_rt_s: 0.02,
+    };
+    let mut sim = simulator(ErrorModel::NONE.with_gyro_bias(walk), 9);
+    let biases: Vec<Vec3A> = (0..20_000)
+        .map(|_| {
+            let emitted = sim.step(&Truth::FLAT);
+            // no noise: the reading is the true bias
+            assert_eq!(emitted.sample.gyro(), emitted.truth.gyro_bias_dps * PI_180);
+            emitted.truth.gyro_bias_dps
+        })
+        .collect();
+    assert_eq!(biases[0], walk.initial_dps);
+    let expected = 0.02 * DT.sqrt();
+    for axis in 0..3 {
+        let step = std_dev(biases.windows(2).map(|w| w[1][axis] - w[0][axis]));
+        assert!((step / expected - 1.).abs() < 0.03, "{} {}", axis, step);
+    }
+
+    // a zero coefficient holds the bias
+    let held = GyroBiasWalk {
+        random_walk_dps_per_rt_s: 0.,
+        ..walk
+    };
+    let mut sim = simulator(ErrorModel::NONE.with_gyro_bias(held), 9);
+    assert!((0..100).all(|_| sim.step(&Truth::FLAT).truth.gyro_bias_dps == walk.initial_dps));
+}
+
+#[test]
+fn accel_errors_and_the_temperature_profile_apply_exactly() {
+    let errors = AccelErrors {
+        bias_g: Vec3A::new(0.02, -0.01, 0.03),
+        scale: Vec3A::new(0.02, -0.01, 0.03),
+        cross_axis: [0.01, -0.02, 0.005, 0.01, -0.015, 0.02],
+    };
+    let mut sim = Simulator::for_fixture(&TILTED_30, ErrorModel::NONE.with_accel(errors), 1);
+    let emitted = sim.step(&TILTED_30.truth(0));
+    let a = TILTED_30.truth(0).acc_g;
+    let x = 1.02 * a.x + 0.01 * a.y - 0.02 * a.z + 0.02;
+    let y = 0.005 * a.x + 0.99 * a.y + 0.01 * a.z - 0.01;
+    let z = -0.015 * a.x + 0.02 * a.y + 1.03 * a.z + 0.03;
+    assert!(
+        (emitted.sample.acc() - Vec3A::new(x, y, z))
+            .abs()
+            .max_element()
+            < 1e-6
+    );
+    assert_eq!(emitted.truth.accel_bias_g, errors.bias_g);
+
+    // scripted warm up from 20 °C to 40 °C over 10 s, then held
+    static PROFILE: [(f32, f32); 2] = [(0., 20.), (10., 40.)];
+    let temp_bias = TempBias {
+        reference_c: 25.,
+        accel_g_per_c: Vec3A::new(0.5e-3, -0.5e-3, 1e-3),
+        gyro_dps_per_c: Vec3A::new(0.05, 0.02, -0.03),
+    };
+    let model = ErrorModel::NONE
+        .with_temp_bias(temp_bias)
+        .with_temp_profile(&PROFILE);
+    let mut sim = Simulator::new(model, AccelRange::G2, GyroRange::D250, 1., 1);
+    for second in 0..15 {
+        let emitted = sim.step(&Truth::FLAT);
+        let temp = 20. + 2. * second.min(10) as f32;
+        assert!((emitted.truth.truth.temp_c - temp).abs() < 1e-4);
+        assert_eq!(emitted.sample.temp(), emitted.truth.truth.temp_c);
+        let delta = emitted.truth.truth.temp_c - 25.;
+        assert_eq!(
+            emitted.truth.gyro_bias_dps,
+            temp_bias.gyro_dps_per_c * delta
+        );
+        assert_eq!(emitted.truth.accel_bias_g, temp_bias.accel_g_per_c * delta);
+        assert_eq!(
+            emitted.sample.acc(),
+            Vec3A::Z + temp_bias.accel_g_per_c * delta
+        );
+    }
+
+    // without a profile the bias follows the temperature of the truth
+    let mut sim = Simulator::for_fixture(&TEMP_RAMP, ErrorModel::NONE.with_temp_bias(temp_bias), 1);
+    let last = sim.run(&TEMP_RAMP).pop().unwrap();
+    assert_eq!(last.truth.truth.temp_c, 40.);
+    assert_eq!(last.truth.gyro_bias_dps, temp_bias.gyro_dps_per_c * 15.);
+}
+
+#[test]
+fn dropouts_replace_the_frame_and_are_reported() {
+    for kind in [DropoutKind::Hold, DropoutKind::Zero, DropoutKind::Corrupt] {
+        let model = ErrorModel::MPU6050.with_dropouts(Dropouts {
+            probability: 0.1,
+            kind,
+        });
+        let mut sim = Simulator::for_fixture(&STATIONARY, model, 3);
+        let mut clean = Simulator::for_fixture(&STATIONARY, ErrorModel::MPU6050, 3);
+        let mut previous: Option<SimSample> = None;
+        let mut events = 0;
+        for _ in 0..5000 {
+            let emitted = sim.step(&Truth::FLAT);
+            // the other components draw as without dropouts
+            let undisturbed = clean.step(&Truth::FLAT);
+            match emitted.truth.event {
+                None => assert_eq!(emitted.sample, undisturbed.sample),
+                Some(event) => {
+                    events += 1;
+                    assert_eq!(event, kind);
+                    match kind {
+                        DropoutKind::Hold => {
+                            let previous = previous.unwrap();
+                            assert_eq!(emitted.frame, previous.frame);
+                            assert_eq!(emitted.sample, previous.sample);
+                        }
+                        DropoutKind::Zero => assert_eq!(emitted.bytes(), [0; 14]),
+                        DropoutKind::Corrupt => {
+                            let bytes = encode_frame(&undisturbed.frame);
+                            let changed = (0..14).filter(|&i| emitted.bytes()[i] != bytes[i]);
+                            assert_eq!(changed.count(), 1);
+                        }
+                    }
+                }
+            }
+            previous = Some(emitted);
+        }
+        assert!((400..600).contains(&events), "{:?} {}", kind, events);
+    }
+}
+
+#[test]
+fn orientation_integrates_the_true_rates() {
+    let mut sim = Simulator::for_fixture(&ROTATING_Z, ErrorModel::MPU6050, 1);
+    for (idx, emitted) in sim.run(&ROTATING_Z).iter().enumerate() {
+        let yaw = (0.9 * idx as f32).to_radians();
+        let expected = Quat::from_rotation_z(yaw);
+        assert!(
+            emitted.truth.orientation.angle_between(expected) < 1e-3,
+            "{}",
+            idx
+        );
+    }
+
+    // a given attitude restarts the integration
+    let mut sim = simulator(ErrorModel::NONE, 1).with_orientation(Quat::from_rotation_x(0.5));
+    let spin = Truth {
+        gyro_dps: Vec3A::new(0., 0., 100.),
+        ..Truth::FLAT
+    };
+    assert_eq!(
+        sim.step(&spin).truth.orientation,
+        Quat::from_rotation_x(0.5)
+    );
+    sim.step_at(&spin, Quat::IDENTITY);
+    let next = sim.step(&Truth::FLAT).truth.orientation;
+    assert!(next.angle_between(Quat::from_rotation_z(1f32.to_radians())) < 1e-3);
+    assert_eq!(sim.samples(), 3);
+}
+
+#[test]
+fn the_seed_determines_every_sample() {
+    let model = ErrorModel::MPU6050
+        .with_gyro_bias(GyroBiasWalk {
+            initial_dps: Vec3A::new(1., 0., -1.),
+            random_walk_dps_per_rt_s: 0.05,
+        })
+        .with_dropouts(Dropouts {
+            probability: 0.05,
+            kind: DropoutKind::Corrupt,
+        });
+    let run = |seed| Simulator::for_fixture(&ROTATING_Z, model, seed).run(&ROTATING_Z);
+    assert_eq!(run(11), run(11));
+    assert_ne!(run(11), run(12));
+
+    // the frame is the quantized reading
+    for emitted in run(11).iter().filter(|s| s.truth.event.is_none()) {
+        let sample = emitted.sample;
+        let reading = Truth {
+            acc_g: sample.acc(),
+            gyro_dps: sample.gyro() / PI_180,
+            temp_c: sample.temp(),
+        };
+        assert_eq!(
+            encode(&reading, AccelRange::G2, GyroRange::D500).0,
+            emitted.frame
+        );
+    }
+}
@@ -0,0 +1,432 @@
+//! C ABI: the committed header against the generated one, the functions over callbacks into
+//! a register mock, the error codes and the caught panics, and `ffi/example.c` built and
+//! run against the static library, see the `ffi` module. After changing the ABI, rerun with
+//! `UPDATE_GOLDENS=1` and review the header diff.
+
+mod common;
+
+use std::cell::Cell;
+use std::ffi::c_void;
+use std::path::Path;
+use std::process::Command;
+use std::ptr;
+
+use embedded_hal::blocking::i2c::{Write, WriteRead};
+use mpu6050::device::{AccelRange, DEFAULT_SLAVE_ADDR, GYRO_SENS};
+use mpu6050::ffi::*;
+use mpu6050::frame::encode_frame;
+use mpu6050::orientation::ComplementaryFilter;
+use mpu6050::test_fixtures::{ROTATING_Z, STATIONARY};
+use mpu6050::trace::check_golden;
+use mpu6050::*;
+
+use common::SharedBus;
+
+const ADDR: u8 = DEFAULT_SLAVE_ADDR;
+
+/// the host side: the mock behind the callbacks
+struct Host {
+    bus: SharedBus,
+    /// status every transaction returns, 0 for a working bus
+    fail: i32,
+    delays: u32,
+    /// panic in the next delay, as a Rust callback may
+    panic_in_delay: bool,
+    /// handle the delay calls back into, and the codes it got
+    reenter: *mut Mpu6050Handle,
+    reentered: Vec<i32>,
+}
+
+impl Host {
+    fn new() -> Box<Host> {
+        Box::new(Host {
+            bus: SharedBus::new(&[ADDR]),
+            fail: 0,
+            delays: 0,
+            panic_in_delay: false,
+            reenter: ptr::null_mut(),
+            reentered: Vec::new(),
+        })
+    }
+
+    fn callbacks(&mut self) -> Mpu6050Bus {
+        Mpu6050Bus {
+            context: self as *mut Host as *mut c_void,
+            write: Some(write),
+            write_read: Some(write_read),
+            delay_ms: Some(delay_ms),
+        }
+    }
+}
+
+unsafe extern "C-unwind" fn write(
+    context: *mut c_void,
+    address: u8,
+    bytes: *const u8,
+    len: usize,
+) -> i32 {
+    let host = unsafe { &mut *(context as *mut Host) };
+    if host.fail != 0 {
+        return host.fail;
+    }
+    let bytes = unsafe { std::slice::from_raw_parts(bytes, len) };
+    match host.bus.write(address, bytes) {
+        Ok(()) => 0,
+        Err(never) => match never {},
+    }
+}
+
+unsafe extern "C-unwind" fn write_read(
+    context: *mut c_void,
+    address: u8,
+    bytes: *const u8,
+    len: usize,
+    buffer: *mut u8,
+    buffer_len: usize,
+) -> i32 {
+    let host = unsafe { &mut *(context as *mut Host) };
+    if host.fail != 0 {
+        return host.fail;
+    }
+    let bytes = unsafe { std::slice::from_raw_parts(bytes, len) };
+    let buffer = unsafe { std::slice::from_raw_parts_mut(buffer, buffer_len) };
+    match host.bus.write_read(address, bytes, buffer) {
+        Ok(()) => 0,
+        Err(never) => match never {},
+    }
+}
+
+unsafe extern "C-unwind" fn delay_ms(context: *mut c_void, _ms: u32) {
+    let host = unsafe { &mut *(context as *mut Host) };
+    host.delays += 1;
+    if host.panic_in_delay {
+        panic!("delay callback");
+    }
+    if !host.reenter.is_null() {
+        let mut out = Mpu6050Reading::default();
+        let code = unsafe { mpu6050_get_all(host.reenter, &mut out) };
+        host.reentered.push(code);
+    }
+}
+
+/// a created and initialized handle over `host`
+fn handle(host: &mut Host) -> *mut Mpu6050Handle {
+    let mut handle = ptr::null_mut();
+    unsafe {
+        assert_eq!(
+            mpu6050_create(&host.callbacks(), ADDR, &mut handle),
+            MPU6050_OK
+        );
+        assert_eq!(mpu6050_init(handle), MPU6050_OK);
+    }
+    handle
+}
+
+fn reading(handle: *mut Mpu6050Handle) -> Mpu6050Reading {
+    let mut out = Mpu6050Reading::default();
+    assert_eq!(unsafe { mpu6050_get_all(handle, &mut out) }, MPU6050_OK);
+    out
+}
+
+#[test]
+fn header_matches_the_committed_one() {
+    let generated = include_str!(concat!(env!("OUT_DIR"), "/mpu6050.h"));
+    let path = format!("{}/ffi/mpu6050.h", env!("CARGO_MANIFEST_DIR"));
+    if let Err(mismatch) = check_golden(path, generated) {
+        panic!("{}", mismatch);
+    }
+}
+
+#[test]
+fn readings_and_ranges_through_the_abi() {
+    let mut host = Host::new();
+    let handle = handle(&mut host);
+    for (fixture, idx) in [(STATIONARY, 0), (ROTATING_Z, 10)] {
+        host.bus.device(ADDR, |mock| {
+            mock.set_frame(&encode_frame(&fixture.frame(idx)))
+        });
+        let gyro_range = fixture.gyro_range as u8;
+        assert_eq!(
+            unsafe { mpu6050_set_gyro_range(handle, gyro_range) },
+            MPU6050_OK
+        );
+        let expected = fixture.expected(idx);
+        assert_eq!(
+            reading(handle),
+            Mpu6050Reading {
+                acc_g: expected.acc().to_array(),
+                gyro_rad_s: expected.gyro().to_array(),
+                temp_c: expected.temp(),
+            },
+            "{}",
+            fixture.name
+        );
+    }
+
+    // by register value, the same counts at ±8 g
+    host.bus
+        .device(ADDR, |mock| mock.set_frame(&STATIONARY.bytes(0)));
+    let afs_sel = AccelRange::G8 as u8;
+    assert_eq!(
+        unsafe { mpu6050_set_accel_range(handle, afs_sel) },
+        MPU6050_OK
+    );
+    assert_eq!(reading(handle).acc_g, [0., 0., 4.]);
+    assert_eq!(
+        host.bus.device(ADDR, |mock| mock.regs[0x1c] >> 3 & 3),
+        afs_sel
+    );
+    unsafe {
+        assert_eq!(mpu6050_set_accel_range(handle, 4), MPU6050_INVALID_ARGUMENT);
+        assert_eq!(
+            mpu6050_set_gyro_range(handle, 0xff),
+            MPU6050_INVALID_ARGUMENT
+        );
+        mpu6050_destroy(handle);
+    }
+}
+
+#[test]
+fn gyro_calibration_reports_progress() {
+    let mut host = Host::new();
+    let handle = handle(&mut host);
+    // 1 °/s on X at ±250 °/s
+    let (lsb_per_dps, ..) = GYRO_SENS;
+    let mut frame = STATIONARY.frame(0);
+    frame.gyro[0] = lsb_per_dps as i16;
+    host.bus
+        .device(ADDR, |mock| mock.set_frame(&encode_frame(&frame)));
+
+    unsafe extern "C-unwind" fn progress(context: *mut c_void, taken: u16, total: u16) {
+        let seen = unsafe { &*(context as *const Cell<Vec<(u16, u16)>>) };
+        let mut calls = seen.take();
+        calls.push((taken, total));
+        seen.set(calls);
+    }
+    let seen: Cell<Vec<(u16, u16)>> = Cell::new(Vec::new());
+    let mut offset = [0f32; 3];
+    let code = unsafe {
+        mpu6050_calibrate_gyro(
+            handle,
+            20,
+            Some(progress),
+            &seen as *const _ as *mut c_void,
+            offset.as_mut_ptr(),
+        )
+    };
+    assert_eq!(code, MPU6050_OK);
+    let calls = seen.take();
+    assert_eq!(calls, (1..=20).map(|taken| (taken, 20)).collect::<Vec<_>>());
+    assert!((offset[0] - PI_180).abs() < 1e-6, "{:?}", offset);
+    assert_eq!(offset[1..], [0., 0.]);
+    assert!(reading(handle).gyro_rad_s[0].abs() < 1e-6);
+    assert!(host.delays >= 20);
+
+    // without progress or output
+    let code = unsafe { mpu6050_calibrate_gyro(handle, 4, None, ptr::null_mut(), ptr::null_mut()) };
+    assert_eq!(code, MPU6050_OK);
+    unsafe { mpu6050_destroy(handle) };
+}
+
+#[test]
+fn errors_map_to_the_code_table() {
+    let mut host = Host::new();
+    let handle = handle(&mut host);
+    let mut out = Mpu6050Reading::default();
+
+    host.fail = 7;
+    let code = unsafe { mpu6050_get_all(handle, &mut out) };
+    assert_eq!(code, i32::from(Mpu6050Error::I2c(7).code()));
+    assert_eq!(unsafe { mpu6050_last_bus_status(handle) }, 7);
+    assert_eq!(out, Mpu6050Reading::default());
+    host.fail = 0;
+    host.bus.device(ADDR, |mock| mock.regs[0x75] = 0x00);
+    let code = unsafe { mpu6050_init(handle) };
+    assert_eq!(code, i32::from(Mpu6050Error::<()>::InvalidChipId(0).code()));
+
+    // a moving device: the delay shows another rate every sample
+    unsafe extern "C-unwind" fn shaking(context: *mut c_void, _ms: u32) {
+        let host = unsafe { &mut *(context as *mut Host) };
+        host.delays += 1;
+        let mut frame = STATIONARY.frame(0);
+        frame.gyro[2] = if host.delays % 2 == 0 { 500 } else { -500 };
+        host.bus
+            .device(ADDR, |mock| mock.set_frame(&encode_frame(&frame)));
+    }
+    let mut moving = Host::new();
+    let mut callbacks = moving.callbacks();
+    callbacks.delay_ms = Some(shaking);
+    let mut shaken = ptr::null_mut();
+    unsafe {
+        assert_eq!(mpu6050_create(&callbacks, ADDR, &mut shaken), MPU6050_OK);
+        assert_eq!(mpu6050_init(shaken), MPU6050_OK);
+        let mut offset = [9f32; 3];
+        let code = mpu6050_calibrate_gyro(shaken, 20, None, ptr::null_mut(), offset.as_mut_ptr());
+        assert_eq!(code, MPU6050_CALIBRATION_REJECTED);
+        assert_eq!(offset, [9.; 3]);
+        mpu6050_destroy(shaken);
+    }
+
+    // NULL and incomplete arguments
+    unsafe {
+        assert_eq!(
+            mpu6050_get_all(ptr::null_mut(), &mut out),
+            MPU6050_NULL_POINTER
+        );
+        assert_eq!(
+            mpu6050_get_all(handle, ptr::null_mut()),
+            MPU6050_NULL_POINTER
+        );
+        assert_eq!(mpu6050_init(ptr::null_mut()), MPU6050_NULL_POINTER);
+        assert_eq!(mpu6050_last_bus_status(ptr::null()), 0);
+        let mut other = ptr::null_mut();
+        assert_eq!(
+            mpu6050_create(ptr::null(), ADDR, &mut other),
+            MPU6050_NULL_POINTER
+        );
+        assert_eq!(
+            mpu6050_create(&host.callbacks(), ADDR, ptr::null_mut()),
+            MPU6050_NULL_POINTER
+        );
+        let incomplete = Mpu6050Bus {
+            write_read: None,
+            ..host.callbacks()
+        };
+        assert_eq!(
+            mpu6050_create(&incomplete, ADDR, &mut other),
+            MPU6050_NULL_POINTER
+        );
+        assert!(other.is_null());
+        mpu6050_destroy(ptr::null_mut());
+        mpu6050_destroy(handle);
+    }
+}
+
+#[test]
+fn a_panic_is_caught_and_poisons_the_handle() {
+    let mut host = Host::new();
+    let handle = handle(&mut host);
+    let mut out = Mpu6050Reading::default();
+    host.panic_in_delay = true;
+    unsafe {
+        let code = mpu6050_calibrate_gyro(handle, 4, None, ptr::null_mut(), ptr::null_mut());
+        assert_eq!(code, MPU6050_PANICKED);
+        host.panic_in_delay = false;
+        // poisoned: nothing runs, nothing reaches the bus
+        host.bus.take_log();
+        assert_eq!(mpu6050_get_all(handle, &mut out), MPU6050_PANICKED);
+        assert_eq!(mpu6050_init(handle), MPU6050_PANICKED);
+        assert_eq!(host.bus.take_log(), vec![]);
+        mpu6050_destroy(handle);
+    }
+
+    // another handle is unaffected
+    let other = self::handle(&mut host);
+    assert_eq!(reading(other).acc_g, [0., 0., 1.]);
+    unsafe { mpu6050_destroy(other) };
+}
+
+#[test]
+fn a_callback_calling_into_its_handle_is_refused() {
+    assert_eq!(
+        MPU6050_REENTERED,
+        i32::from(Mpu6050Error::<()>::Reentrancy.code())
+    );
+    let mut host = Host::new();
+    let handle = handle(&mut host);
+    let other = self::handle(&mut host);
+    host.reenter = handle;
+    unsafe {
+        let code = mpu6050_calibrate_gyro(handle, 4, None, ptr::null_mut(), ptr::null_mut());
+        // the running call is unaffected, every nested one refused
+        assert_eq!(code, MPU6050_OK);
+        assert_eq!(host.reentered, vec![MPU6050_REENTERED; 4]);
+        assert_eq!(mpu6050_last_bus_status(handle), 0);
+
+        // after the call the handle is free again, another handle is never busy
+        host.reentered.clear();
+        host.reenter = other;
+        let code = mpu6050_calibrate_gyro(handle, 2, None, ptr::null_mut(), ptr::null_mut());
+        assert_eq!(code, MPU6050_OK);
+        assert_eq!(host.reentered, vec![MPU6050_OK; 2]);
+        host.reenter = ptr::null_mut();
+        assert_eq!(reading(handle).acc_g, [0., 0., 1.]);
+        mpu6050_destroy(other);
+        mpu6050_destroy(handle);
+    }
+}
+
+#[test]
+fn filter_matches_the_rust_filter() {
+    let filter = mpu6050_filter_create(0.98);
+    assert!(!filter.is_null());
+    let mut rust = ComplementaryFilter::new(0.98);
+    let mut q = Mpu6050Quat::default();
+    for idx in 0..ROTATING_Z.len {
+        let expected = ROTATING_Z.expected(idx);
+        let reading = Mpu6050Reading {
+            acc_g: expected.acc().to_array(),
+            gyro_rad_s: expected.gyro().to_array(),
+            temp_c: expected.temp(),
+        };
+        let code =
+            unsafe { mpu6050_filter_update(filter, &reading, ROTATING_Z.interval_s, &mut q) };
+        assert_eq!(code, MPU6050_OK);
+        let r = rust.update(expected.gyro(), expected.acc(), ROTATING_Z.interval_s);
+        assert_eq!([q.x, q.y, q.z, q.w], r.to_array());
+    }
+    unsafe {
+        let reading = Mpu6050Reading::default();
+        assert_eq!(
+            mpu6050_filter_update(ptr::null_mut(), &reading, 0.01, &mut q),
+            MPU6050_NULL_POINTER
+        );
+        assert_eq!(
+            mpu6050_filter_update(filter, ptr::null(), 0.01, &mut q),
+            MPU6050_NULL_POINTER
+        );
+        mpu6050_filter_destroy(filter);
+        mpu6050_filter_destroy(ptr::null_mut());
+    }
+    for alpha in [-0.1, 1.1, f32::NAN] {
+        assert!(mpu6050_filter_create(alpha).is_null(), "{}", alpha);
+    }
+}
+
+/// `command`, its output on failure
+fn run(command: &mut Command) -> String {
+    let output = command.output().expect("spawning");
+    let text = format!(
+        "{}{}",
+        String::from_utf8_lossy(&output.stdout),
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert!(output.status.success(), "{:?}: {}", command, text);
+    text
+}
+
+#[test]
+#[cfg(unix)]
+fn c_example_runs_against_mock_callbacks() {
+    let root = Path::new(env!("CARGO_MANIFEST_DIR"));
+    // a target directory of its own, the one of the test run is locked
+    let target = root.join("target").join("ffi-example");
+    run(Command::new(env!("CARGO"))
+        .current_dir(root)
+        .args(["rustc", "-q", "--lib", "--crate-type", "staticlib"])
+        .args(["--features", "ffi", "--target-dir"])
+        .arg(&target));
+    let example = target.join("example");
+    run(
+        Command::new(std::env::var("CC").unwrap_or_else(|_| "cc".into()))
+            .arg(root.join("ffi/example.c"))
+            .arg("-I")
+            .arg(root.join("ffi"))
+            .arg(target.join("debug/libmpu6050.a"))
+            .args(["-lpthread", "-ldl", "-lm", "-o"])
+            .arg(&example),
+    );
+    let output = run(&mut Command::new(&example));
+    assert!(output.contains("calibrated from 50 samples"), "{}", output);
+    assert!(output.ends_with("ok\n"), "{}", output);
+}
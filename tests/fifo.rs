@@ -0,0 +1,478 @@
+//! FIFO frame layouts, parsing and schema invalidation, see the `fifo` module.
+
+mod common;
+
+use mpu6050::aux_i2c::{SlaveConfig, SlaveSlot};
+use mpu6050::device::{
+    AccelRange, GyroRange, ACC_REGX_H, DEFAULT_SLAVE_ADDR, EXT_SENS_DATA_00, FIFO_EN, GYRO_REGX_H,
+    GYRO_REGY_H, GYRO_REGZ_H, I2C_MST_CTRL, INT_STATUS, TEMP_OUT_H,
+};
+use mpu6050::fifo::*;
+use mpu6050::frame::parse_frame;
+use mpu6050::sampling::{SampleControl, SamplingError};
+use mpu6050::*;
+
+use common::{NoDelay, SharedBus};
+
+use FifoFieldKind::*;
+
+/// EXT_SENS_DATA lengths of slaves 0 to 3, slave 1 stores nothing
+const EXT_LEN: [u8; 4] = [3, 0, 6, 2];
+
+/// sources of bit n of `mask`: accel, temp, gyro X, Y, Z, slaves 0 to 3
+fn sources(mask: u16) -> FifoSources {
+    let bit = |n: u16| mask & (1 << n) != 0;
+    FifoSources {
+        accel: bit(0),
+        temp: bit(1),
+        gyro: [bit(2), bit(3), bit(4)],
+        slaves: [bit(5), bit(6), bit(7), bit(8)],
+    }
+}
+
+/// the layout written out from the register map: enabled sources sorted by the address of
+/// their data registers, packed without gaps
+fn documented_layout(sources: &FifoSources) -> Vec<FifoField> {
+    let mut ext_address = EXT_SENS_DATA_00;
+    let mut ext = Vec::new();
+    for (slave, len) in SlaveSlot::ALL.into_iter().zip(EXT_LEN) {
+        ext.push((
+            ext_address,
+            ExtSens(slave),
+            len,
+            sources.slaves[slave as usize],
+        ));
+        ext_address += len;
+    }
+    let mut registers = vec![
+        (ACC_REGX_H, AccelX, 2, sources.accel),
+        (ACC_REGX_H + 2, AccelY, 2, sources.accel),
+        (ACC_REGX_H + 4, AccelZ, 2, sources.accel),
+        (TEMP_OUT_H, Temp, 2, sources.temp),
+        (GYRO_REGX_H, GyroX, 2, sources.gyro[0]),
+        (GYRO_REGY_H, GyroY, 2, sources.gyro[1]),
+        (GYRO_REGZ_H, GyroZ, 2, sources.gyro[2]),
+    ];
+    registers.extend(ext);
+    registers.sort_by_key(|(address, ..)| *address);
+
+    let mut offset = 0;
+    registers
+        .into_iter()
+        .filter(|(_, _, width, enabled)| *enabled && *width > 0)
+        .map(|(_, kind, width, _)| {
+            let field = FifoField {
+                kind,
+                offset,
+                width,
+            };
+            offset += width;
+            field
+        })
+        .collect()
+}
+
+#[test]
+fn every_source_combination_follows_the_register_order() {
+    for mask in 0..1 << 9 {
+        let sources = sources(mask);
+        let schema = FifoSchema::new(sources, EXT_LEN, AccelRange::G2, GyroRange::D250);
+        let expected = documented_layout(&sources);
+        assert_eq!(schema.fields(), expected.as_slice(), "{sources:?}");
+        let len: u8 = expected.iter().map(|f| f.width).sum();
+        assert_eq!(schema.frame_len(), len as usize);
+        assert!(schema.frame_len() <= MAX_FIFO_FRAME_LEN);
+
+        // register values round trip
+        let mut mst_ctrl = 0;
+        if sources.slaves[3] {
+            mst_ctrl |= 1 << I2C_MST_CTRL::SLV_3_FIFO_EN;
+        }
+        assert_eq!(
+            FifoSources::from_registers(sources.fifo_en(), mst_ctrl),
+            sources
+        );
+        // and so does the serialized schema
+        assert_eq!(FifoSchema::from_bytes(&schema.to_bytes()), Ok(schema));
+    }
+    assert_eq!(sources(0x1f).fifo_en(), 0xf8);
+}
+
+#[test]
+fn recorded_full_sensor_frames_match_the_data_registers() {
+    let schema = FifoSchema::new(
+        FifoSources::NONE
+            .with_accel(true)
+            .with_temp(true)
+            .with_gyro(true),
+        [0; 4],
+        AccelRange::G4,
+        GyroRange::D500,
+    );
+    // two frames from a device lying flat at ±4g and ±500°/s, turning slowly about Z
+    let recorded: [u8; 28] = [
+        0x00, 0x12, 0xff, 0xe8, 0x20, 0x05, 0xf2, 0x30, 0x00, 0x03, 0xff, 0xfe, 0x01, 0x06, 0x00,
+        0x10, 0xff, 0xea, 0x1f, 0xfd, 0xf2, 0x34, 0x00, 0x02, 0xff, 0xfd, 0x01, 0x07,
+    ];
+    let frames: Vec<FifoFrame> = recorded
+        .chunks_exact(schema.frame_len())
+        .map(|bytes| parse_fifo_frame(&schema, bytes).unwrap())
+        .collect();
+    assert_eq!(frames[0].acc, Some([18, -24, 8197]));
+    assert_eq!(frames[1].gyro, [Some(2), Some(-3), Some(263)]);
+    // the frame is the data register burst
+    for (frame, bytes) in frames.iter().zip(recorded.chunks_exact(14)) {
+        let registers = parse_frame(bytes.try_into().unwrap());
+        assert_eq!(frame.acc, Some(registers.acc));
+        assert_eq!(frame.temp, Some(registers.temp));
+        assert_eq!(frame.gyro, registers.gyro.map(Some));
+    }
+    let acc = frames[0].acc_g().unwrap();
+    assert!((acc.z - 1.0006).abs() < 1e-4, "{acc}");
+    let gyro = frames[1].gyro_rad_s().unwrap();
+    assert!((gyro.z - 263. / 65.5 * PI_180).abs() < 1e-6, "{gyro}");
+    assert!((frames[0].temp_c().unwrap() - (-3536. / 340. + 36.53)).abs() < 1e-4);
+}
+
+#[test]
+fn recorded_partial_gyro_frames() {
+    // gyro X and Z only, e.g. a two axis rate logger
+    let schema = FifoSchema::new(
+        FifoSources::NONE.with_gyro_axes([true, false, true]),
+        [0; 4],
+        AccelRange::G2,
+        GyroRange::D2000,
+    );
+    assert_eq!(schema.frame_len(), 4);
+    let recorded = [0x01, 0x00, 0xfe, 0xcc, 0x00, 0x80, 0xff, 0x00];
+    let frames: Vec<FifoFrame> = recorded
+        .chunks_exact(4)
+        .map(|bytes| parse_fifo_frame(&schema, bytes).unwrap())
+        .collect();
+    assert_eq!(frames[0].gyro, [Some(256), None, Some(-308)]);
+    assert_eq!(frames[1].gyro, [Some(128), None, Some(-256)]);
+    assert_eq!((frames[0].acc, frames[0].temp), (None, None));
+    // not all axes, no vector
+    assert_eq!(frames[0].gyro_rad_s(), None);
+}
+
+#[test]
+fn recorded_accel_and_aux_frames() {
+    // accel with a 3 byte barometer on slave 0 and a 6 byte magnetometer on slave 2
+    let schema = FifoSchema::new(
+        FifoSources::NONE
+            .with_accel(true)
+            .with_slave(SlaveSlot::Slv0, true)
+            .with_slave(SlaveSlot::Slv2, true),
+        EXT_LEN,
+        AccelRange::G8,
+        GyroRange::D250,
+    );
+    assert_eq!(schema.frame_len(), 15);
+    assert_eq!(
+        schema.field(ExtSens(SlaveSlot::Slv2)),
+        Some(FifoField {
+            kind: ExtSens(SlaveSlot::Slv2),
+            offset: 9,
+            width: 6
+        })
+    );
+    assert_eq!(schema.field(ExtSens(SlaveSlot::Slv3)), None);
+    let recorded = [
+        0x00, 0x00, 0x00, 0x00, 0x10, 0x00, 0x6a, 0x3c, 0x81, 0x01, 0x2c, 0xff, 0x9c, 0x02, 0x58,
+    ];
+    let frame = parse_fifo_frame(&schema, &recorded).unwrap();
+    assert_eq!(frame.acc, Some([0, 0, 4096]));
+    assert_eq!(frame.acc_g(), Some(Vec3A::Z));
+    assert_eq!(frame.ext(SlaveSlot::Slv0), Some(&[0x6a, 0x3c, 0x81][..]));
+    assert_eq!(
+        frame.ext(SlaveSlot::Slv2),
+        Some(&[0x01, 0x2c, 0xff, 0x9c, 0x02, 0x58][..])
+    );
+    assert_eq!(frame.ext(SlaveSlot::Slv1), None);
+
+    assert_eq!(
+        parse_fifo_frame(&schema, &recorded[..14]),
+        Err(FifoError::ShortInput {
+            expected: 15,
+            actual: 14
+        })
+    );
+}
+
+#[test]
+fn serialized_schema_is_checked() {
+    let schema = FifoSchema::new(
+        FifoSources::NONE.with_accel(true),
+        EXT_LEN,
+        AccelRange::G16,
+        GyroRange::D1000,
+    )
+    .with_sensitivities(2050., 32.9);
+    let bytes = schema.to_bytes();
+    let read = FifoSchema::from_bytes(&bytes).unwrap();
+    assert_eq!(
+        (read.accel_range, read.gyro_range),
+        (AccelRange::G16, GyroRange::D1000)
+    );
+    assert_eq!((read.acc_sensitivity, read.gyro_sensitivity), (2050., 32.9));
+
+    let mut future = bytes;
+    future[0] = FIFO_SCHEMA_VERSION + 1;
+    assert_eq!(
+        FifoSchema::from_bytes(&future),
+        Err(FifoError::UnknownVersion(FIFO_SCHEMA_VERSION + 1))
+    );
+    let mut overflow = bytes;
+    overflow[4..8].copy_from_slice(&[15, 10, 0, 0]);
+    assert_eq!(
+        FifoSchema::from_bytes(&overflow),
+        Err(FifoError::ExtDataOverflow(25))
+    );
+    assert!(matches!(
+        FifoSchema::from_bytes(&bytes[..19]),
+        Err(FifoError::ShortInput { .. })
+    ));
+}
+
+fn driver() -> (SharedBus, Mpu6050<SharedBus>) {
+    let bus = SharedBus::new(&[DEFAULT_SLAVE_ADDR]);
+    let mut mpu = Mpu6050Builder::new().i2c(bus.clone()).build().unwrap();
+    mpu.init(&mut NoDelay).unwrap();
+    (bus, mpu)
+}
+
+fn push_fifo(bus: &SharedBus, bytes: &[u8]) {
+    bus.device(DEFAULT_SLAVE_ADDR, |mock| mock.fifo.extend(bytes));
+}
+
+#[test]
+fn driver_schema_follows_its_configuration() {
+    let (bus, mut mpu) = driver();
+    assert_eq!(mpu.fifo_schema().frame_len(), 0);
+
+    let barometer = SlaveConfig {
+        address: 0x77,
+        register: 0xf7,
+        len: 3,
+        read: true,
+        byte_swap: false,
+    };
+    mpu.configure_i2c_slave(SlaveSlot::Slv3, barometer).unwrap();
+    let sources = FifoSources::NONE
+        .with_gyro(true)
+        .with_slave(SlaveSlot::Slv3, true);
+    let schema = mpu.set_fifo_sources(sources).unwrap();
+    assert_eq!(schema, mpu.fifo_schema());
+    assert_eq!(schema.frame_len(), 9);
+    let regs = bus.device(DEFAULT_SLAVE_ADDR, |mock| mock.regs);
+    assert_eq!(regs[FIFO_EN as usize], 0x70);
+    assert_ne!(
+        regs[I2C_MST_CTRL::ADDR as usize] & 1 << I2C_MST_CTRL::SLV_3_FIFO_EN,
+        0
+    );
+    assert_eq!(mpu.debug_state().fifo_sources, sources);
+
+    // three frames and a partial one
+    let frame = [0, 1, 0, 2, 0, 3, 0xaa, 0xbb, 0xcc];
+    for _ in 0..3 {
+        push_fifo(&bus, &frame);
+    }
+    push_fifo(&bus, &frame[..4]);
+    let mut buf = [0; 64];
+    let mut frames = Vec::new();
+    assert_eq!(
+        mpu.drain_fifo(&schema, &mut buf, |f| frames.push(f))
+            .unwrap(),
+        3
+    );
+    assert!(frames.iter().all(|f| f.gyro == [Some(1), Some(2), Some(3)]));
+    assert!(frames
+        .iter()
+        .all(|f| f.ext(SlaveSlot::Slv3) == Some(&frame[6..])));
+    assert_eq!(mpu.fifo_count().unwrap(), 4);
+
+    // a buffer for one frame drains one
+    push_fifo(&bus, &frame[4..]);
+    assert_eq!(mpu.drain_fifo(&schema, &mut buf[..10], |_| ()).unwrap(), 1);
+}
+
+#[test]
+fn schema_invalidated_mid_stream() {
+    let (bus, mut mpu) = driver();
+    let schema = mpu
+        .set_fifo_sources(FifoSources::NONE.with_accel(true))
+        .unwrap();
+    push_fifo(&bus, &[0x40, 0, 0, 0, 0, 0]);
+    let mut buf = [0; 32];
+
+    // range change: same layout, different scaling
+    mpu.set_accel_range(AccelRange::G16).unwrap();
+    bus.take_log();
+    assert!(!schema.is_current(&mpu));
+    assert!(matches!(
+        mpu.drain_fifo(&schema, &mut buf, |_| ()),
+        Err(Mpu6050Error::StaleConfiguration { .. })
+    ));
+    assert!(bus.take_log().is_empty());
+    let current = mpu.fifo_schema();
+    assert!(matches!(
+        mpu.parse_fifo_frame::<()>(&schema, &buf),
+        Err(Mpu6050Error::StaleConfiguration { captured, current: now })
+            if captured == schema.generation && now == current.generation
+    ));
+    assert!(current.is_current(&mpu));
+    assert!(current.generation > schema.generation);
+    assert_eq!(current.acc_sensitivity, 2048.);
+    let mut acc = None;
+    mpu.drain_fifo(&current, &mut buf, |f| acc = f.acc_g())
+        .unwrap();
+    assert_eq!(acc, Some(Vec3A::X * 8.));
+
+    // FIFO_EN change: new layout
+    let accel_only = mpu.fifo_schema();
+    let both = mpu
+        .set_fifo_sources(FifoSources::NONE.with_accel(true).with_temp(true))
+        .unwrap();
+    assert_eq!(both.frame_len(), 8);
+    assert!(matches!(
+        mpu.drain_fifo(&accel_only, &mut buf, |_| ()),
+        Err(Mpu6050Error::StaleConfiguration { .. })
+    ));
+    assert!(mpu.parse_fifo_frame::<()>(&both, &buf).is_ok());
+
+    // aux reconfiguration and reset invalidate as well
+    let schema = mpu.fifo_schema();
+    mpu.disable_i2c_slave(SlaveSlot::Slv0).unwrap();
+    assert_ne!(mpu.fifo_schema().generation, schema.generation);
+    let schema = mpu.fifo_schema();
+    mpu.reset_device(&mut NoDelay).unwrap();
+    let reset = mpu.fifo_schema();
+    assert_ne!(reset.generation, schema.generation);
+    assert_eq!((reset.sources(), reset.frame_len()), (FifoSources::NONE, 0));
+}
+
+#[test]
+fn direct_reads_are_refused_while_streaming() {
+    let (bus, mut mpu) = driver();
+    mpu.set_fifo_sources(FifoSources::NONE.with_accel(true))
+        .unwrap();
+    mpu.set_fifo_enabled(true).unwrap();
+    assert!(mpu.fifo_streaming());
+    assert_eq!(mpu.get_mixed_read_policy(), MixedReadPolicy::Reject);
+    bus.take_log();
+
+    assert!(matches!(mpu.get_acc(), Err(Mpu6050Error::FifoActive)));
+    assert!(matches!(mpu.get_gyro(), Err(Mpu6050Error::FifoActive)));
+    assert!(matches!(mpu.get_temp(), Err(Mpu6050Error::FifoActive)));
+    assert!(matches!(
+        mpu.run_sampling_loop(|| Ok::<(), ()>(()), |_, _| SampleControl::Stop),
+        Err(SamplingError::Device(Mpu6050Error::FifoActive))
+    ));
+    // refused before the data registers, raw reads stay available
+    assert!(bus
+        .take_log()
+        .iter()
+        .all(|access| access.reg == INT_STATUS::ADDR));
+    assert!(mpu.get_acc_raw().is_ok());
+
+    mpu.set_fifo_enabled(false).unwrap();
+    assert!(mpu.get_acc().is_ok());
+    mpu.set_fifo_enabled(true).unwrap();
+    mpu.reset_device(&mut NoDelay).unwrap();
+    assert!(!mpu.debug_state().fifo_streaming);
+    assert!(mpu.get_acc().is_ok());
+}
+
+/// out of band flag of one sample from the sampling loop
+fn sampled_out_of_band(mpu: &mut Mpu6050<SharedBus>) -> bool {
+    let mut flag = None;
+    mpu.run_sampling_loop(
+        || Ok::<(), ()>(()),
+        |sample, _| {
+            flag = Some(sample.out_of_band());
+            SampleControl::Stop
+        },
+    )
+    .unwrap();
+    flag.unwrap()
+}
+
+#[test]
+fn allowed_direct_reads_are_marked() {
+    let (_bus, mut mpu) = driver();
+    mpu.set_mixed_read_policy(MixedReadPolicy::Allow { mark: true });
+    assert!(!sampled_out_of_band(&mut mpu));
+
+    mpu.set_fifo_enabled(true).unwrap();
+    assert!(sampled_out_of_band(&mut mpu));
+    assert!((mpu.get_acc().unwrap().z - 1.).abs() < 1e-3);
+
+    mpu.set_mixed_read_policy(MixedReadPolicy::Allow { mark: false });
+    assert!(!sampled_out_of_band(&mut mpu));
+    assert_eq!(
+        mpu.debug_state().mixed_read_policy,
+        MixedReadPolicy::Allow { mark: false }
+    );
+}
+
+#[test]
+fn interleaved_direct_reads_leave_the_fifo_alone() {
+    let (bus, mut mpu) = driver();
+    let schema = mpu
+        .set_fifo_sources(FifoSources::NONE.with_accel(true))
+        .unwrap();
+    mpu.set_fifo_enabled(true).unwrap();
+    mpu.set_mixed_read_policy(MixedReadPolicy::Allow { mark: true });
+    let frames: Vec<[i16; 3]> = (0..10).map(|i| [i, -i, 100 + i]).collect();
+    for frame in &frames {
+        let bytes: Vec<u8> = frame.iter().flat_map(|v| v.to_be_bytes()).collect();
+        push_fifo(&bus, &bytes);
+    }
+
+    // 3 frames per drain, a direct read of every sensor in between
+    let mut buf = [0; 18];
+    let mut drained = Vec::new();
+    while drained.len() < frames.len() {
+        mpu.drain_fifo(&schema, &mut buf, |f| drained.push(f.acc.unwrap()))
+            .unwrap();
+        let sample = mpu.get_acc().unwrap();
+        assert!((sample.z - 1.).abs() < 1e-3);
+        mpu.get_gyro().unwrap();
+        mpu.get_temp().unwrap();
+    }
+    assert_eq!(drained, frames);
+    assert_eq!(mpu.fifo_count().unwrap(), 0);
+}
+
+#[cfg(feature = "test-fixtures")]
+#[test]
+fn fixture_sequence_drains_to_its_ground_truth() {
+    use mpu6050::test_fixtures::IMPACT;
+
+    let (bus, mut mpu) = driver();
+    mpu.set_accel_range(IMPACT.accel_range).unwrap();
+    mpu.set_gyro_range(IMPACT.gyro_range).unwrap();
+    let sources = FifoSources::NONE
+        .with_accel(true)
+        .with_temp(true)
+        .with_gyro(true);
+    let schema = mpu.set_fifo_sources(sources).unwrap();
+    mpu.set_fifo_enabled(true).unwrap();
+    push_fifo(&bus, &IMPACT.fifo_bytes(sources));
+
+    let mut buf = [0; 64];
+    let mut drained = Vec::new();
+    while mpu
+        .drain_fifo(&schema, &mut buf, |f| drained.push(f))
+        .unwrap()
+        > 0
+    {}
+    assert_eq!(drained.len(), IMPACT.len);
+    for (idx, frame) in drained.iter().enumerate() {
+        let expected = IMPACT.expected(idx);
+        assert_eq!(frame.acc_g(), Some(expected.acc()), "{}", idx);
+        assert_eq!(frame.gyro_rad_s(), Some(expected.gyro()), "{}", idx);
+        assert_eq!(frame.temp_c(), Some(expected.temp()), "{}", idx);
+    }
+}
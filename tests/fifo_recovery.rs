@@ -0,0 +1,245 @@
+//! FIFO overflow recovery against a chip whose FIFO overwrites its oldest bytes once full:
+//! the exact frames and gaps delivered around overflows mid-frame, during a drain and right
+//! after a reset, see the `fifo_recovery` module.
+
+mod common;
+
+use mpu6050::cooperative::DrainBudget;
+use mpu6050::fifo::*;
+use mpu6050::fifo_recovery::*;
+use mpu6050::*;
+
+use common::{chip_time_us, FifoChip};
+
+type Mpu = Mpu6050<FifoChip>;
+
+/// accel only, 6 bytes: the 1024 byte FIFO does not end on a frame boundary
+const LEN: usize = 6;
+/// whole frames of a full FIFO
+const FULL: u32 = 1024 / LEN as u32;
+
+/// a streaming driver at 1 kHz, the chip writing a frame every ms
+fn streaming(clock: bool) -> (Mpu, FifoChip, FifoSchema) {
+    let chip = FifoChip::new(LEN, 1000);
+    let mut mpu = Mpu6050Builder::new().i2c(chip.clone()).build().unwrap();
+    mpu.set_dlpf(1).unwrap();
+    assert_eq!(mpu.check_aliasing().odr_hz, 1000.);
+    if clock {
+        mpu.set_fifo_clock(Some(chip_time_us));
+    }
+    let schema = mpu
+        .set_fifo_sources(FifoSources::NONE.with_accel(true))
+        .unwrap();
+    mpu.set_fifo_enabled(true).unwrap();
+    (mpu, chip, schema)
+}
+
+/// frame number, sequence and gap of every frame a drain of up to `frames` delivered
+fn drain(mpu: &mut Mpu, schema: &FifoSchema, frames: usize) -> Vec<(i16, u64, Option<FifoGap>)> {
+    let mut buf = vec![0; frames * LEN];
+    let mut out = Vec::new();
+    let n = mpu
+        .drain_fifo(schema, &mut buf, |frame| {
+            out.push((frame.acc.unwrap()[0], frame.sequence, frame.gap))
+        })
+        .unwrap();
+    assert_eq!(n, out.len());
+    out
+}
+
+fn gap(lost_frames: u32, estimate: GapEstimate, overflows: u16) -> FifoGap {
+    FifoGap {
+        pre_reset_count: 1024,
+        lost_frames,
+        estimate,
+        overflows,
+    }
+}
+
+#[test]
+fn frames_are_numbered_in_fifo_order() {
+    let (mut mpu, chip, schema) = streaming(false);
+    chip.write_frames(5);
+    let frames = drain(&mut mpu, &schema, 16);
+    assert_eq!(
+        frames,
+        (0..5).map(|k| (k, k as u64, None)).collect::<Vec<_>>()
+    );
+    chip.write_frames(3);
+    let frames = drain(&mut mpu, &schema, 16);
+    assert_eq!(
+        frames,
+        (5..8).map(|k| (k, k as u64, None)).collect::<Vec<_>>()
+    );
+    assert_eq!(
+        mpu.fifo_recovery_status(),
+        FifoRecoveryStatus {
+            next_sequence: 8,
+            ..FifoRecoveryStatus::default()
+        }
+    );
+}
+
+#[test]
+fn overflow_mid_frame_discards_the_fifo_and_tags_the_next_frame() {
+    let (mut mpu, chip, schema) = streaming(false);
+    // 1200 bytes written, the FIFO holds the last 1024 starting 4 bytes into frame 29
+    chip.write_frames(200);
+    assert_eq!(chip.fifo_len(), 1024);
+    assert!(drain(&mut mpu, &schema, 256).is_empty());
+    assert_eq!(chip.fifo_len(), 0);
+    let content = gap(FULL, GapEstimate::FifoContent, 1);
+    assert_eq!(
+        mpu.fifo_recovery_status(),
+        FifoRecoveryStatus {
+            overflows: 1,
+            lost_frames: FULL as u64,
+            pending_gap: Some(content),
+            next_sequence: FULL as u64,
+        }
+    );
+
+    // without a clock the frames written over are not counted, the sequence falls behind
+    chip.write_frames(2);
+    let frames = drain(&mut mpu, &schema, 16);
+    assert_eq!(frames, [(200, 170, Some(content)), (201, 171, None)]);
+    assert_eq!(mpu.fifo_recovery_status().pending_gap, None);
+    assert_eq!(mpu.fifo_recovery_status().overflows, 1);
+}
+
+#[test]
+fn a_clock_counts_the_frames_written_over() {
+    let (mut mpu, chip, schema) = streaming(true);
+    chip.write_frames(200);
+    assert!(drain(&mut mpu, &schema, 256).is_empty());
+    chip.write_frames(2);
+    let frames = drain(&mut mpu, &schema, 16);
+    let elapsed = gap(200, GapEstimate::Elapsed, 1);
+    assert_eq!(frames, [(200, 200, Some(elapsed)), (201, 201, None)]);
+    assert_eq!(mpu.fifo_recovery_status().lost_frames, 200);
+}
+
+#[test]
+fn overflow_during_a_drain_counts_the_frames_left_and_written() {
+    let (mut mpu, chip, schema) = streaming(true);
+    chip.write_frames(10);
+    // 4 of the 10 frames fit the buffer, the chip writes 200 more while they are handled
+    let mut buf = [0; 4 * LEN];
+    let mut first = Vec::new();
+    let writer = chip.clone();
+    mpu.drain_fifo(&schema, &mut buf, |frame| {
+        first.push((frame.acc.unwrap()[0], frame.sequence, frame.gap));
+        if first.len() == 2 {
+            writer.write_frames(200);
+        }
+    })
+    .unwrap();
+    assert_eq!(
+        first,
+        (0..4).map(|k| (k, k as u64, None)).collect::<Vec<_>>()
+    );
+
+    // frames 4 to 209 lost: the 6 left by the drain and the 200 written since
+    assert!(drain(&mut mpu, &schema, 256).is_empty());
+    chip.write_frames(1);
+    let frames = drain(&mut mpu, &schema, 16);
+    assert_eq!(
+        frames,
+        [(210, 210, Some(gap(206, GapEstimate::Elapsed, 1)))]
+    );
+}
+
+#[test]
+fn overflow_right_after_the_reset_merges_the_gaps() {
+    for clock in [false, true] {
+        let (mut mpu, chip, schema) = streaming(clock);
+        chip.write_frames(200);
+        // count read, USER_CTRL read, FIFO_RESET write, then the FIFO fills again
+        chip.write_frames_before(3, 180);
+        assert!(drain(&mut mpu, &schema, 256).is_empty());
+        assert!(drain(&mut mpu, &schema, 256).is_empty());
+        chip.write_frames(1);
+        let frames = drain(&mut mpu, &schema, 16);
+        let merged = if clock {
+            gap(380, GapEstimate::Elapsed, 2)
+        } else {
+            gap(2 * FULL, GapEstimate::FifoContent, 2)
+        };
+        let sequence = merged.lost_frames as u64;
+        assert_eq!(frames, [(380, sequence, Some(merged))]);
+        let status = mpu.fifo_recovery_status();
+        assert_eq!((status.overflows, status.lost_frames), (2, sequence));
+    }
+}
+
+#[test]
+fn cooperative_drains_take_the_same_path() {
+    let (mut mpu, chip, schema) = streaming(true);
+    chip.write_frames(3);
+    let mut out = [0; 32 * LEN];
+    let budget = DrainBudget::Transactions(1);
+    let progress = mpu
+        .drain_fifo_cooperative(&schema, &mut out, budget, || {})
+        .unwrap();
+    assert_eq!(
+        (progress.frames, progress.first_sequence, progress.gap),
+        (3, 0, None)
+    );
+
+    chip.write_frames(200);
+    let progress = mpu
+        .drain_fifo_cooperative(&schema, &mut out, budget, || {})
+        .unwrap();
+    assert_eq!(
+        (progress.frames, progress.first_sequence, progress.gap),
+        (0, 203, None)
+    );
+    assert_eq!(chip.fifo_len(), 0);
+
+    chip.write_frames(5);
+    let progress = mpu
+        .drain_fifo_cooperative(&schema, &mut out, budget, || {})
+        .unwrap();
+    let elapsed = gap(200, GapEstimate::Elapsed, 1);
+    assert_eq!(
+        (progress.frames, progress.first_sequence, progress.gap),
+        (5, 203, Some(elapsed))
+    );
+    let first = mpu.parse_fifo_frame::<()>(&schema, &out[..LEN]).unwrap();
+    assert_eq!(first.acc.unwrap()[0], 203);
+    assert_eq!(mpu.fifo_recovery_status().next_sequence, 208);
+}
+
+#[test]
+fn a_failed_reset_records_no_gap() {
+    let (mut mpu, chip, schema) = streaming(true);
+    chip.write_frames(200);
+    chip.fail_user_ctrl(true);
+    let mut buf = [0; 16 * LEN];
+    assert!(mpu.drain_fifo(&schema, &mut buf, |_| {}).is_err());
+    assert_eq!(mpu.fifo_recovery_status(), FifoRecoveryStatus::default());
+
+    chip.fail_user_ctrl(false);
+    chip.write_frames(10);
+    assert!(drain(&mut mpu, &schema, 16).is_empty());
+    chip.write_frames(1);
+    let frames = drain(&mut mpu, &schema, 16);
+    assert_eq!(
+        frames,
+        [(210, 210, Some(gap(210, GapEstimate::Elapsed, 1)))]
+    );
+}
+
+#[test]
+fn enabling_the_fifo_starts_a_new_timeline() {
+    let (mut mpu, chip, schema) = streaming(true);
+    chip.write_frames(200);
+    drain(&mut mpu, &schema, 16);
+    mpu.set_fifo_enabled(false).unwrap();
+    mpu.set_fifo_enabled(true).unwrap();
+    let status = mpu.fifo_recovery_status();
+    assert_eq!(status.overflows, 1);
+    assert_eq!((status.next_sequence, status.pending_gap), (0, None));
+    chip.write_frames(1);
+    assert_eq!(drain(&mut mpu, &schema, 16), [(200, 0, None)]);
+}
@@ -0,0 +1,249 @@
+//! [`FsyncTracker`] on synthetic trigger patterns, and fed from the burst reads and FIFO
+//! drains of the driver.
+
+mod common;
+
+use mpu6050::device::{AccelRange, GyroRange, DEFAULT_SLAVE_ADDR, EXT_SYNC};
+use mpu6050::fifo::{parse_fifo_frame, FifoSchema, FifoSources};
+use mpu6050::frame::RawFrame;
+use mpu6050::fsync::{fsync_tag, fsync_tag_fifo, FsyncConfig, FsyncStats, FsyncTag, FsyncTracker};
+use mpu6050::settling::SettlingPolicy;
+use mpu6050::{Mpu6050, Mpu6050Builder};
+
+use common::{NoDelay, SharedBus};
+
+/// 1 kHz sampling
+const SAMPLE_US: u64 = 1000;
+
+/// Feeds `samples` samples, a sample is tagged if an edge arrived since the previous one
+fn run(config: FsyncConfig, samples: u64, edges_us: &[u64]) -> FsyncStats {
+    let mut tracker = FsyncTracker::new(config);
+    let mut edges = edges_us.iter().peekable();
+    for index in 1..=samples {
+        let t_us = index * SAMPLE_US;
+        let mut tagged = false;
+        while edges.next_if(|edge| **edge <= t_us).is_some() {
+            tagged = true;
+        }
+        tracker.feed(index, t_us, tagged);
+    }
+    tracker.stats()
+}
+
+/// Deterministic jitter in [-amplitude, amplitude] µs
+fn jitter(i: u64, amplitude: u64) -> i64 {
+    let x = i.wrapping_mul(0x9e37_79b9_7f4a_7c15) >> 40;
+    (x % (2 * amplitude + 1)) as i64 - amplitude as i64
+}
+
+/// 30 Hz camera trigger, first edge at 10.5 ms
+fn camera(n: u64, skip: impl Fn(u64) -> bool) -> Vec<u64> {
+    (0..n)
+        .filter(|i| !skip(*i))
+        .map(|i| 10_500 + i * 33_333)
+        .collect()
+}
+
+#[test]
+fn regular_trigger_is_counted_exactly() {
+    let stats = run(FsyncConfig::default(), 10_000, &camera(300, |_| false));
+
+    assert_eq!(stats.tags, 300);
+    assert_eq!(stats.missed_edges, 0);
+    assert_eq!(stats.edges, 300);
+    assert!((stats.trigger_rate_hz.unwrap() - 30.0).abs() < 0.1);
+    assert!(!stats.aliasing);
+    assert_eq!(stats.last_tag.unwrap().index, 9978);
+}
+
+#[test]
+fn jitter_is_not_missed_edges() {
+    let edges: Vec<u64> = camera(300, |_| false)
+        .iter()
+        .enumerate()
+        .map(|(i, t)| (*t as i64 + jitter(i as u64, 2000)) as u64)
+        .collect();
+    let stats = run(FsyncConfig::default(), 10_000, &edges);
+
+    assert_eq!(stats.tags, 300);
+    assert_eq!(stats.missed_edges, 0);
+    assert!((stats.trigger_rate_hz.unwrap() - 30.0).abs() < 1.0);
+}
+
+#[test]
+fn dropped_edges_are_detected() {
+    // every 10th edge is lost, twice two in a row
+    let skip = |i: u64| i % 10 == 5 || i == 101 || i == 201;
+    let stats = run(FsyncConfig::default(), 10_000, &camera(300, skip));
+
+    assert_eq!(stats.tags, 268);
+    assert_eq!(stats.missed_edges, 32);
+    assert_eq!(stats.edges, 300);
+}
+
+#[test]
+fn aliased_edges_are_counted_with_known_rate() {
+    // 1.25 kHz trigger on 1 kHz sampling: every 4th sample latches two edges
+    let edges: Vec<u64> = (0..1250).map(|i| 300 + i * 800).collect();
+    let config = FsyncConfig {
+        expected_trigger_hz: Some(1250.),
+        ..FsyncConfig::default()
+    };
+    let stats = run(config, 1000, &edges);
+
+    assert_eq!(stats.tags, 1000);
+    assert!(stats.aliasing);
+    assert!(stats.edges.abs_diff(1250) <= 1, "{:?}", stats);
+}
+
+#[test]
+fn aliasing_is_reported_without_known_rate() {
+    let edges: Vec<u64> = (0..1250).map(|i| 300 + i * 800).collect();
+    let stats = run(FsyncConfig::default(), 1000, &edges);
+
+    // the merged edges cannot be counted, but the stream is flagged
+    assert_eq!(stats.tags, 1000);
+    assert!(stats.aliasing);
+    assert_eq!(stats.consecutive_tags, 999);
+}
+
+#[test]
+fn bursts_faster_than_sampling_are_flagged() {
+    // bursts of 3 edges 1.2 ms apart every 50 ms
+    let edges: Vec<u64> = (0..100)
+        .flat_map(|burst| (0..3).map(move |i| 50_100 + burst * 50_000 + i * 1200))
+        .collect();
+    let stats = run(FsyncConfig::default(), 5_100, &edges);
+
+    assert!(stats.aliasing);
+    assert!(stats.consecutive_tags > 0);
+    // the pauses between bursts are not taken for missed edges
+    assert_eq!(stats.missed_edges, 0);
+}
+
+#[test]
+fn tag_extraction_follows_ext_sync() {
+    let frame = RawFrame {
+        acc: [0, 0, 1],
+        temp: 2,
+        gyro: [3, 0, 0],
+    };
+    assert!(!fsync_tag(&frame, EXT_SYNC::DISABLED));
+    assert!(!fsync_tag(&frame, EXT_SYNC::TEMP_OUT_L));
+    assert!(fsync_tag(&frame, EXT_SYNC::GYRO_XOUT_L));
+    assert!(!fsync_tag(&frame, EXT_SYNC::ACCEL_XOUT_L));
+    assert!(fsync_tag(&frame, EXT_SYNC::ACCEL_ZOUT_L));
+}
+
+fn driver() -> (SharedBus, Mpu6050<SharedBus>) {
+    let bus = SharedBus::new(&[DEFAULT_SLAVE_ADDR]);
+    let mut mpu = Mpu6050Builder::new().i2c(bus.clone()).build().unwrap();
+    mpu.init(&mut NoDelay).unwrap();
+    mpu.set_settling_policy(SettlingPolicy::Ignore);
+    mpu.set_fsync_source(EXT_SYNC::GYRO_XOUT_L).unwrap();
+    (bus, mpu)
+}
+
+#[test]
+fn burst_reads_feed_the_tracker() {
+    let (bus, mut mpu) = driver();
+    assert_eq!(mpu.get_fsync_source().unwrap(), EXT_SYNC::GYRO_XOUT_L);
+    let skip = |i: u64| i % 10 == 5;
+    let edges = camera(60, skip);
+    let mut tracker = FsyncTracker::new(FsyncConfig::default());
+    let mut pending = edges.iter().peekable();
+    let mut tagged_indices = Vec::new();
+    for index in 0..2000u64 {
+        let t_us = (index + 1) * SAMPLE_US;
+        let mut tagged = false;
+        while pending.next_if(|edge| **edge <= t_us).is_some() {
+            tagged = true;
+        }
+        let mut frame = [0; 14];
+        // LSB of GYRO_XOUT_L
+        frame[9] = u8::from(tagged);
+        bus.device(DEFAULT_SLAVE_ADDR, |mock| mock.set_frame(&frame));
+        let (_, tag) = mpu
+            .get_all_fsync(EXT_SYNC::GYRO_XOUT_L, &mut tracker, t_us)
+            .unwrap();
+        assert_eq!(tag.is_some(), tagged);
+        if let Some(tag) = tag {
+            assert_eq!(tag.index, index);
+            tagged_indices.push(index);
+        }
+    }
+
+    // the same counts as the tag stream fed directly, whose indices start at 1
+    let direct = run(FsyncConfig::default(), 2000, &edges);
+    let stats = tracker.stats();
+    let last_tag = direct.last_tag.map(|tag| FsyncTag {
+        index: tag.index - 1,
+        ..tag
+    });
+    assert_eq!(stats, FsyncStats { last_tag, ..direct });
+    assert_eq!(stats.tags, tagged_indices.len() as u64);
+    assert_eq!(stats.missed_edges, 6);
+    assert_eq!(stats.edges, 60);
+}
+
+#[test]
+fn fifo_drains_feed_the_tracker() {
+    let (bus, mut mpu) = driver();
+    let schema = mpu
+        .set_fifo_sources(FifoSources::NONE.with_gyro(true))
+        .unwrap();
+    // 100 Hz trigger on 1 kHz frames: every 10th frame tagged in GYRO_XOUT_L
+    for i in 0..100u8 {
+        let tagged = i % 10 == 3;
+        bus.device(DEFAULT_SLAVE_ADDR, |mock| {
+            mock.fifo.extend([0, u8::from(tagged), 0, 0, 0, 0])
+        });
+    }
+
+    let mut tracker = FsyncTracker::new(FsyncConfig::default());
+    let mut untracked = FsyncTracker::new(FsyncConfig::default());
+    let mut tags = Vec::new();
+    let mut buf = [0; 600];
+    let drained = mpu
+        .drain_fifo(&schema, &mut buf, |frame| {
+            if let Some(tag) = tracker.feed_fifo(&frame, EXT_SYNC::GYRO_XOUT_L, SAMPLE_US) {
+                assert_eq!(tag.index, frame.sequence);
+                tags.push(tag);
+            }
+            // ACCEL_XOUT_L is not in the FIFO: nothing fed
+            assert_eq!(
+                untracked.feed_fifo(&frame, EXT_SYNC::ACCEL_XOUT_L, SAMPLE_US),
+                None
+            );
+        })
+        .unwrap();
+
+    assert_eq!(drained, 100);
+    let stats = tracker.stats();
+    assert_eq!(stats.samples, 100);
+    assert_eq!(stats.tags, 10);
+    assert_eq!(stats.missed_edges, 0);
+    assert!(
+        (stats.trigger_rate_hz.unwrap() - 100.).abs() < 0.1,
+        "{:?}",
+        stats
+    );
+    assert!(tags.windows(2).all(|t| t[1].index - t[0].index == 10));
+    assert_eq!(untracked.stats().samples, 0);
+}
+
+#[test]
+fn fifo_tag_extraction_follows_ext_sync() {
+    let schema = FifoSchema::new(
+        FifoSources::NONE.with_accel(true).with_temp(true),
+        [0; 4],
+        AccelRange::G2,
+        GyroRange::D250,
+    );
+    let frame = parse_fifo_frame(&schema, &[0, 0, 0, 0, 0, 1, 0, 3]).unwrap();
+    assert_eq!(fsync_tag_fifo(&frame, EXT_SYNC::DISABLED), Some(false));
+    assert_eq!(fsync_tag_fifo(&frame, EXT_SYNC::ACCEL_XOUT_L), Some(false));
+    assert_eq!(fsync_tag_fifo(&frame, EXT_SYNC::ACCEL_ZOUT_L), Some(true));
+    assert_eq!(fsync_tag_fifo(&frame, EXT_SYNC::TEMP_OUT_L), Some(true));
+    assert_eq!(fsync_tag_fifo(&frame, EXT_SYNC::GYRO_YOUT_L), None);
+}
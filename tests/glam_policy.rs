@@ -0,0 +1,113 @@
+//! The math type policy seen from a downstream crate with a math library of its own, a
+//! stand-in for a different glam version: its types never meet this crate's, values cross
+//! as arrays, and this crate's types are named through its re-exports.
+
+mod common;
+
+use mpu6050::orientation::ComplementaryFilter;
+use mpu6050::{Mpu6050Builder, MpuSample};
+
+use common::{NoDelay, RegisterMock};
+
+/// what a downstream glam of another major version looks like from here: distinct types
+/// with the same array conversions
+mod downstream_glam {
+    #[derive(Copy, Clone, Debug, PartialEq)]
+    pub struct Vec3A {
+        pub x: f32,
+        pub y: f32,
+        pub z: f32,
+    }
+
+    impl Vec3A {
+        pub fn to_array(self) -> [f32; 3] {
+            [self.x, self.y, self.z]
+        }
+    }
+
+    impl From<[f32; 3]> for Vec3A {
+        fn from([x, y, z]: [f32; 3]) -> Self {
+            Self { x, y, z }
+        }
+    }
+
+    #[derive(Copy, Clone, Debug, PartialEq)]
+    pub struct Quat([f32; 4]);
+
+    impl From<[f32; 4]> for Quat {
+        fn from(xyzw: [f32; 4]) -> Self {
+            Self(xyzw)
+        }
+    }
+}
+
+use downstream_glam as theirs;
+
+#[test]
+fn values_cross_the_boundary_as_arrays() {
+    let offset = theirs::Vec3A::from([0.01, -0.02, 0.03]);
+    let mut mpu = Mpu6050Builder::new()
+        .i2c(RegisterMock::new())
+        .gyro_offset(offset.to_array())
+        .acc_offset(mpu6050::Vec3A::new(0., 0., 0.05))
+        .build()
+        .unwrap();
+    assert_eq!(mpu.gyro_offset.to_array(), offset.to_array());
+    mpu.init(&mut NoDelay).unwrap();
+    let gyro: theirs::Vec3A = mpu.get_gyro_as().unwrap();
+    assert_eq!(gyro.to_array(), mpu.get_gyro().unwrap().to_array());
+
+    let sample = MpuSample::from_arrays([0., 0., 1.], offset.to_array(), 25.);
+    assert_eq!(
+        sample.acc_as::<theirs::Vec3A>(),
+        theirs::Vec3A::from([0., 0., 1.])
+    );
+    assert_eq!(sample.gyro_as::<theirs::Vec3A>(), offset);
+    assert_eq!(sample.gyro_as::<[f32; 3]>(), offset.to_array());
+
+    let mut filter = ComplementaryFilter::new(0.98);
+    filter.update_sample(&sample, 0.01);
+    let q: theirs::Quat = filter.orientation_as();
+    assert_eq!(q, theirs::Quat::from(filter.orientation().to_array()));
+}
+
+#[test]
+fn the_crates_own_types_are_named_through_its_reexports() {
+    // the root re-exports are the types of `mpu6050::glam`
+    fn root(v: mpu6050::Vec3A, q: mpu6050::Quat) -> (mpu6050::glam::Vec3A, mpu6050::glam::Quat) {
+        (v, q)
+    }
+    let sample = MpuSample::new(mpu6050::glam::Vec3A::Z, mpu6050::glam::Vec3A::ZERO, 25.);
+    let (acc, _) = root(sample.acc(), mpu6050::glam::Quat::IDENTITY);
+    assert_eq!(acc, mpu6050::Vec3A::Z);
+}
+
+#[test]
+fn acc_angles_cross_as_xyzw() {
+    use mpu6050::device::{AccelRange, GyroRange};
+    use mpu6050::glam::EulerRot;
+    use mpu6050::synthetic;
+
+    // roll about +X, pitch about +Y, in degrees
+    for (roll, pitch) in [(0., 0.), (30., 0.), (-45., 0.), (0., 20.), (0., -60.)] {
+        let acc = synthetic::at_rest(roll, pitch);
+        let frame =
+            synthetic::frame_bytes(acc, mpu6050::Vec3A::ZERO, AccelRange::G2, GyroRange::D250);
+        let mut mock = RegisterMock::new();
+        mock.set_frame(&frame);
+        let mut mpu = Mpu6050Builder::new().i2c(mock).build().unwrap();
+        mpu.init(&mut NoDelay).unwrap();
+
+        let expected =
+            mpu6050::Quat::from_euler(EulerRot::XYZ, roll.to_radians(), pitch.to_radians(), 0.)
+                .to_array();
+        let xyzw: [f32; 4] = mpu.get_acc_angles_as().unwrap();
+        let error = xyzw.iter().zip(expected).map(|(a, b)| (a - b).abs());
+        assert!(error.fold(0., f32::max) < 1e-4, "{:?} {:?}", xyzw, expected);
+        // the vector part first, the scalar last
+        assert!(xyzw[3] >= xyzw[0].abs().max(xyzw[1].abs()));
+
+        let q: theirs::Quat = mpu.get_acc_angles_as().unwrap();
+        assert_eq!(q, theirs::Quat::from(xyzw));
+    }
+}
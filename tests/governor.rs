@@ -0,0 +1,333 @@
+//! Power governor on synthetic activity traces, see the `governor` module.
+
+mod common;
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use mpu6050::device::{
+    AccelRange, GyroRange, ACCEL_CONFIG, DEFAULT_SLAVE_ADDR, GYRO_CONFIG, PWR_MGMT_1,
+};
+use mpu6050::governor::*;
+use mpu6050::sampling::SampleControl;
+use mpu6050::settings::Mpu6050Settings;
+use mpu6050::*;
+
+use common::{NoDelay, SharedBus};
+
+const REST_DWELL_MS: u32 = 0;
+const WALK_DWELL_MS: u32 = 3000;
+const RUN_DWELL_MS: u32 = 1000;
+
+fn run_settings() -> Mpu6050Settings {
+    Mpu6050Settings::new()
+        .with_accel_range(AccelRange::G8)
+        .with_gyro_range(GyroRange::D1000)
+        .with_dlpf_cfg(3)
+        .with_sample_rate_div(3)
+}
+
+fn config() -> GovernorConfig {
+    GovernorConfig::three(
+        OperatingPoint::new("rest", presets::LOW_POWER_TILT, REST_DWELL_MS),
+        OperatingPoint::new("walk", presets::HANDHELD_UI, WALK_DWELL_MS),
+        OperatingPoint::new("run", run_settings(), RUN_DWELL_MS),
+        GovernorRule {
+            up: ActivityLevel {
+                gyro_rms: 0.3,
+                acc_variance: 0.01,
+            },
+            down: ActivityLevel {
+                gyro_rms: 0.1,
+                acc_variance: 0.002,
+            },
+        },
+        GovernorRule {
+            up: ActivityLevel {
+                gyro_rms: 2.,
+                acc_variance: 0.5,
+            },
+            down: ActivityLevel {
+                gyro_rms: 1.2,
+                acc_variance: 0.2,
+            },
+        },
+    )
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+enum Activity {
+    Rest,
+    Walk,
+    Run,
+}
+
+/// accel in g and gyro in rad/s at `t_s`: 2 Hz steps with growing amplitude
+fn motion(activity: Activity, t_s: f32) -> (Vec3A, Vec3A) {
+    let (acc_amplitude, gyro_amplitude) = match activity {
+        Activity::Rest => (0., 0.),
+        Activity::Walk => (0.3, 1.),
+        Activity::Run => (1.2, 4.),
+    };
+    // off the 2 Hz phase grid of the slower rates, so no sample lands on a zero crossing
+    let wave = (t_s * core::f32::consts::TAU * 2. + 0.3).sin();
+    (
+        synthetic::at_rest(0., 0.) + Vec3A::Z * (acc_amplitude * wave),
+        Vec3A::X * (gyro_amplitude * wave * core::f32::consts::SQRT_2),
+    )
+}
+
+type Trace = [(f32, Activity)];
+
+/// activity at `t_s` of a trace of (start in s, activity)
+fn activity_at(trace: &Trace, t_s: f32) -> Activity {
+    trace
+        .iter()
+        .rev()
+        .find(|(start, _)| t_s >= *start)
+        .map(|(_, activity)| *activity)
+        .unwrap()
+}
+
+/// transitions of a pure simulation of `trace` until `end_s`, with the time they happened
+fn simulate(trace: &Trace, end_s: f32) -> Vec<(f32, GovernorTransition)> {
+    let mut governor = PowerGovernor::new(config(), 0).unwrap();
+    let mut transitions = Vec::new();
+    let mut t_s = 0.;
+    while t_s < end_s {
+        let (acc, gyro) = motion(activity_at(trace, t_s), t_s);
+        if let Some(transition) = governor.feed(&MpuSample::new(acc, gyro, 25.)) {
+            governor.commit(&transition);
+            transitions.push((t_s, transition));
+        }
+        t_s += 1. / governor.current().settings.sample_rate_hz();
+    }
+    transitions
+}
+
+fn names(transitions: &[(f32, GovernorTransition)]) -> Vec<(&str, &str)> {
+    transitions
+        .iter()
+        .map(|(_, tr)| (tr.from_name, tr.to_name))
+        .collect()
+}
+
+const REST_WALK_RUN_REST: [(f32, Activity); 4] = [
+    (0., Activity::Rest),
+    (10., Activity::Walk),
+    (20., Activity::Run),
+    (30., Activity::Rest),
+];
+
+#[test]
+fn rest_walk_run_rest() {
+    let transitions = simulate(&REST_WALK_RUN_REST, 50.);
+    assert_eq!(
+        names(&transitions),
+        [
+            ("rest", "walk"),
+            ("walk", "run"),
+            ("run", "walk"),
+            ("walk", "rest")
+        ]
+    );
+    let times: Vec<f32> = transitions.iter().map(|(t, _)| *t).collect();
+    // each within a window of the change
+    assert!((10. ..11.).contains(&times[0]), "{times:?}");
+    assert!((20. ..21.).contains(&times[1]), "{times:?}");
+    assert!((30. ..31.).contains(&times[2]), "{times:?}");
+    // back at walk on rest data: held for the walk dwell
+    assert!(
+        times[3] >= times[2] + 2.99 && times[3] < times[2] + 3.1,
+        "{times:?}"
+    );
+
+    let config = config();
+    for (_, transition) in &transitions {
+        let point = &config.points()[transition.from];
+        assert!(
+            transition.dwell_ms >= point.min_dwell_ms as f32,
+            "{transition}"
+        );
+        assert_eq!(
+            transition.sample_interval_s(),
+            1. / config.points()[transition.to].settings.sample_rate_hz()
+        );
+    }
+    let [walk, run, slow, rest] = [0, 1, 2, 3].map(|i| transitions[i].1);
+    assert_eq!(walk.reason, TransitionReason::Activity);
+    assert!(walk.metrics.acc_variance > 0.01);
+    assert_eq!(run.reason, TransitionReason::Activity);
+    assert!(run.metrics.gyro_rms > 2.);
+    assert_eq!(
+        (slow.reason, rest.reason),
+        (TransitionReason::Rest, TransitionReason::Rest)
+    );
+    assert!(rest.metrics.gyro_rms < 0.1 && rest.metrics.acc_variance < 0.002);
+    assert_eq!(run.sample_rate_hz, 250.);
+}
+
+#[test]
+fn dwell_holds_walk_before_running() {
+    let trace = [(0., Activity::Rest), (10., Activity::Run)];
+    let transitions = simulate(&trace, 20.);
+    assert_eq!(names(&transitions), [("rest", "walk"), ("walk", "run")]);
+    let (entered_walk, _) = transitions[0];
+    let (entered_run, run) = transitions[1];
+    // running is obvious within a window, the dwell decides
+    assert!(entered_run >= entered_walk + 3., "{transitions:?}");
+    assert!(entered_run < entered_walk + 3.05, "{transitions:?}");
+    assert!(run.dwell_ms >= WALK_DWELL_MS as f32);
+}
+
+#[test]
+fn activity_between_the_levels_holds_the_point() {
+    // accel variance 0.0025 g², gyro RMS 0.2 rad/s: above walk's down level, below rest's up level
+    let mut governor = PowerGovernor::new(config(), 1).unwrap();
+    for i in 0..2000 {
+        let bump = if i % 2 == 0 { 0.05 } else { -0.05 };
+        let sample = MpuSample::new(Vec3A::new(0., 0., 1. + bump), Vec3A::ZERO, 25.)
+            .with_gyro(Vec3A::X * 0.2);
+        assert_eq!(governor.feed(&sample), None, "{:?}", governor.metrics());
+    }
+    let mut governor = PowerGovernor::new(config(), 0).unwrap();
+    for _ in 0..200 {
+        let sample = MpuSample::new(Vec3A::Z, Vec3A::X * 0.2, 25.);
+        assert_eq!(governor.feed(&sample), None);
+    }
+}
+
+#[test]
+fn motion_wake_skips_dwell_and_window() {
+    let mut governor = PowerGovernor::new(config(), 0).unwrap();
+    let wake = governor.motion_wake().unwrap();
+    assert_eq!((wake.from_name, wake.to_name), ("rest", "run"));
+    assert_eq!(wake.reason, TransitionReason::MotionWake);
+    governor.commit(&wake);
+    assert_eq!(governor.motion_wake(), None);
+
+    // still at run with rest data: the run dwell, only then one step down
+    let mut t_ms = 0.;
+    loop {
+        if let Some(transition) = governor.feed(&MpuSample::new(Vec3A::Z, Vec3A::ZERO, 25.)) {
+            assert_eq!(transition.to_name, "walk");
+            assert!(t_ms >= RUN_DWELL_MS as f32 - 4.);
+            break;
+        }
+        t_ms += 4.;
+    }
+}
+
+#[test]
+fn tick_advances_dwell_without_samples() {
+    // a window of still samples at walk, 100 Hz
+    let mut governor = PowerGovernor::new(config(), 1).unwrap();
+    for _ in 0..100 {
+        assert_eq!(
+            governor.feed(&MpuSample::new(Vec3A::Z, Vec3A::ZERO, 25.)),
+            None
+        );
+    }
+    assert_eq!(governor.tick(WALK_DWELL_MS - 1100), None);
+    let rest = governor.tick(200).unwrap();
+    assert_eq!(
+        (rest.to_name, rest.reason),
+        ("rest", TransitionReason::Rest)
+    );
+
+    // settling samples advance time but do not count as activity
+    let mut governor = PowerGovernor::new(config(), 0).unwrap();
+    let shaken = MpuSample::new(Vec3A::Z * 3., Vec3A::X * 5., 25.).with_settling(true);
+    for _ in 0..100 {
+        assert_eq!(governor.feed(&shaken), None);
+    }
+    assert_eq!(governor.metrics().samples, 0);
+    assert_eq!(governor.status().dwell_ms, 100. * 200.);
+}
+
+#[test]
+fn invalid_configurations() {
+    let three = config();
+    let no_hysteresis = GovernorConfig::two(
+        three.points()[0],
+        three.points()[1],
+        GovernorRule {
+            up: three.rules()[0].up,
+            down: three.rules()[0].up,
+        },
+    );
+    assert_eq!(
+        PowerGovernor::new(no_hysteresis, 0).unwrap_err(),
+        GovernorError::NoHysteresis { rule: 0 }
+    );
+    assert_eq!(
+        PowerGovernor::new(config().with_window_ms(0), 0).unwrap_err(),
+        GovernorError::EmptyWindow
+    );
+    assert_eq!(
+        PowerGovernor::new(config(), 3).unwrap_err(),
+        GovernorError::NoSuchPoint(3)
+    );
+}
+
+/// point entered last, set by the transition callback
+static CURRENT: AtomicUsize = AtomicUsize::new(0);
+
+fn record(transition: GovernorTransition) {
+    CURRENT.store(transition.to, Ordering::SeqCst);
+}
+
+/// the sensor: encodes `motion` with the ranges in its registers
+fn show(bus: &SharedBus, acc: Vec3A, gyro: Vec3A) {
+    bus.device(DEFAULT_SLAVE_ADDR, |mock| {
+        let accel_range = AccelRange::from((mock.regs[ACCEL_CONFIG::ADDR as usize] >> 3) & 0x03);
+        let gyro_range = GyroRange::from((mock.regs[GYRO_CONFIG::ADDR as usize] >> 3) & 0x03);
+        let rate = gyro * (180. / core::f32::consts::PI);
+        mock.set_frame(&synthetic::frame_bytes(acc, rate, accel_range, gyro_range));
+    });
+}
+
+#[test]
+fn driver_writes_registers_only_at_transitions() {
+    let bus = SharedBus::new(&[DEFAULT_SLAVE_ADDR]);
+    let mut mpu = Mpu6050Builder::new().i2c(bus.clone()).build().unwrap();
+    mpu.init(&mut NoDelay).unwrap();
+    let governor = PowerGovernor::new(config().with_callback(Some(record)), 0).unwrap();
+    mpu.set_power_governor(governor).unwrap();
+    let regs = bus.device(DEFAULT_SLAVE_ADDR, |mock| mock.regs);
+    assert_ne!(
+        regs[PWR_MGMT_1::ADDR as usize] & (1 << PWR_MGMT_1::CYCLE),
+        0
+    );
+    bus.take_log();
+
+    let points = config();
+    let points = points.points();
+    let mut t_s = 0.;
+    let mut writes_per_sample = Vec::new();
+    show(&bus, Vec3A::Z, Vec3A::ZERO);
+    mpu.run_sampling_loop(
+        || Ok::<(), ()>(()),
+        |_, _| {
+            writes_per_sample.push((t_s, bus.take_log().iter().filter(|a| !a.read).count()));
+            t_s += 1.
+                / points[CURRENT.load(Ordering::SeqCst)]
+                    .settings
+                    .sample_rate_hz();
+            let (acc, gyro) = motion(activity_at(&REST_WALK_RUN_REST, t_s), t_s);
+            show(&bus, acc, gyro);
+            if t_s < 50. {
+                SampleControl::Continue
+            } else {
+                SampleControl::Stop
+            }
+        },
+    )
+    .unwrap();
+
+    let writes: Vec<_> = writes_per_sample.iter().filter(|(_, n)| *n > 0).collect();
+    assert_eq!(writes.len(), 4, "{writes:?}");
+    let status = mpu.power_governor().unwrap().status();
+    assert_eq!((status.name, status.transitions), ("rest", 4));
+    assert_eq!(mpu.debug_state().governor, Some(status));
+    assert_eq!(mpu.read_settings().unwrap(), presets::LOW_POWER_TILT);
+}
@@ -0,0 +1,178 @@
+//! [`GravityMagnitudeTrimmer`] on simulated long deployments.
+
+use mpu6050::gravity_trim::{GravityMagnitudeTrimmer, TrimmerConfig};
+use mpu6050::{synthetic, Vec3A};
+
+const DT_S: f32 = 0.1;
+const HOUR: usize = 36_000;
+
+/// Deterministic noise in [-amplitude, amplitude]
+struct Noise(u64);
+
+impl Noise {
+    fn next(&mut self, amplitude: f32) -> f32 {
+        self.0 = self
+            .0
+            .wrapping_mul(6_364_136_223_846_793_005)
+            .wrapping_add(1_442_695_040_888_963_407);
+        ((self.0 >> 40) as f32 / (1u64 << 24) as f32 * 2. - 1.) * amplitude
+    }
+
+    fn vec(&mut self, amplitude: f32) -> Vec3A {
+        Vec3A::new(
+            self.next(amplitude),
+            self.next(amplitude),
+            self.next(amplitude),
+        )
+    }
+}
+
+/// Feeds `hours` of samples, gravity from `orientation(sample index)`, accel offset
+/// error `drift`. Returns the trimmer and the trims applied
+fn simulate(
+    config: TrimmerConfig,
+    hours: usize,
+    drift: Vec3A,
+    mut reading: impl FnMut(usize, &mut Noise) -> (Vec3A, Vec3A),
+) -> (GravityMagnitudeTrimmer, Vec3A) {
+    let mut trimmer = GravityMagnitudeTrimmer::new(config);
+    let mut noise = Noise(1);
+    let mut trim = Vec3A::ZERO;
+    for i in 0..hours * HOUR {
+        let (acc, gyro) = reading(i, &mut noise);
+        if let Some(correction) = trimmer.feed_readings(acc + drift + trim, gyro, DT_S) {
+            trim += correction.step;
+        }
+    }
+    (trimmer, trim)
+}
+
+/// Roll and pitch of a still device turned to a new orientation every 2 hours
+fn orientation(i: usize) -> (f32, f32) {
+    const ORIENTATIONS: [(f32, f32); 6] = [
+        (0., 0.),
+        (90., 0.),
+        (0., 90.),
+        (45., 30.),
+        (-60., -20.),
+        (180., 0.),
+    ];
+    ORIENTATIONS[i / (2 * HOUR) % ORIENTATIONS.len()]
+}
+
+/// Still device in the six orientations
+fn resting(i: usize, noise: &mut Noise) -> (Vec3A, Vec3A) {
+    let (roll, pitch) = orientation(i);
+    let acc = synthetic::at_rest(roll, pitch) + noise.vec(0.004);
+    (acc, noise.vec(0.005))
+}
+
+#[cfg(feature = "test-fixtures")]
+#[test]
+fn injected_drift_is_recovered() {
+    use mpu6050::device::{AccelRange, GyroRange};
+    use mpu6050::error_model::{AccelErrors, ErrorModel, Simulator, WhiteNoise};
+    use mpu6050::test_fixtures::Truth;
+
+    // the drift is an accelerometer bias of the simulated chip, read through datasheet noise
+    // at a 44 Hz DLPF and quantized at ±2 g
+    let model = ErrorModel::MPU6050
+        .with_white_noise(WhiteNoise {
+            bandwidth_hz: Some(44.),
+            ..WhiteNoise::MPU6050
+        })
+        .with_accel(AccelErrors {
+            bias_g: Vec3A::new(0.012, -0.008, 0.015),
+            scale: Vec3A::ZERO,
+            cross_axis: [0.; 6],
+        });
+    let mut sim = Simulator::new(model, AccelRange::G2, GyroRange::D250, DT_S, 1);
+    let mut true_bias = Vec3A::ZERO;
+    let reading = |i, _: &mut Noise| {
+        let (roll, pitch) = orientation(i);
+        let truth = Truth {
+            acc_g: synthetic::at_rest(roll, pitch),
+            ..Truth::FLAT
+        };
+        let emitted = sim.step(&truth);
+        true_bias = emitted.truth.accel_bias_g;
+        (emitted.sample.acc(), emitted.sample.gyro())
+    };
+    let (trimmer, trim) = simulate(TrimmerConfig::default(), 48, Vec3A::ZERO, reading);
+
+    // the trims cancel the true bias
+    assert!(
+        (trim + true_bias).abs().max_element() < 0.002,
+        "trim {:?}",
+        trim
+    );
+    assert!(!trimmer.saturated());
+    assert_eq!(trimmer.total_correction(), trim);
+    let log_total = trimmer.corrections_log().last().unwrap().total;
+    assert_eq!(log_total, trim);
+    assert!(trimmer.corrections_count() > 0);
+}
+
+#[test]
+fn flat_device_only_trims_z() {
+    let drift = Vec3A::new(0.01, 0.01, -0.01);
+    let flat = |_, noise: &mut Noise| (Vec3A::Z + noise.vec(0.004), noise.vec(0.005));
+    let (_, trim) = simulate(TrimmerConfig::default(), 24, drift, flat);
+
+    assert!((trim.z + drift.z).abs() < 0.002, "trim {:?}", trim);
+    assert!(
+        trim.x.abs() < 0.001 && trim.y.abs() < 0.001,
+        "trim {:?}",
+        trim
+    );
+}
+
+#[test]
+fn moving_device_is_never_trimmed() {
+    // vehicle: turning and accelerating all the time, magnitude 1.1 .. 1.3 g
+    let moving = |i: usize, noise: &mut Noise| {
+        let t = i as f32 * DT_S;
+        let acc = Vec3A::new(0.5 + 0.1 * t.sin(), 0.3 * (0.7 * t).cos(), 1.0) + noise.vec(0.01);
+        let gyro = Vec3A::new(0.0, 0.0, 0.2 + 0.1 * t.sin()) + noise.vec(0.01);
+        (acc, gyro)
+    };
+    let drift = Vec3A::new(0.01, 0.0, 0.02);
+    let (trimmer, trim) = simulate(TrimmerConfig::default(), 24, drift, moving);
+
+    assert_eq!(trim, Vec3A::ZERO);
+    assert_eq!(trimmer.corrections_count(), 0);
+    assert_eq!(trimmer.estimate(), Vec3A::ZERO);
+}
+
+#[test]
+fn short_still_pauses_are_ignored() {
+    // still for 1 s out of every 10, below min_still_s
+    let stop_and_go = |i: usize, noise: &mut Noise| {
+        if i % 100 < 10 {
+            (Vec3A::Z + noise.vec(0.004), noise.vec(0.005))
+        } else {
+            (Vec3A::new(0.3, 0.0, 1.2), Vec3A::new(0.0, 0.0, 0.5))
+        }
+    };
+    let (trimmer, _) = simulate(TrimmerConfig::default(), 12, Vec3A::Z * 0.02, stop_and_go);
+    assert_eq!(trimmer.corrections_count(), 0);
+}
+
+#[test]
+fn correction_saturates_at_budget() {
+    let drift = Vec3A::new(0.0, 0.0, 0.025);
+    let config = TrimmerConfig {
+        budget_g: 0.01,
+        ..TrimmerConfig::default()
+    };
+    let (trimmer, trim) = simulate(config, 24, drift, resting);
+
+    assert!(trimmer.saturated());
+    assert!((trim.z + 0.01).abs() < 1e-6, "trim {:?}", trim);
+    assert!(trim.abs().max_element() <= 0.01 + 1e-6);
+    assert!(trimmer.corrections_log().any(|c| c.saturated));
+    // every logged step is bounded
+    assert!(trimmer
+        .corrections_log()
+        .all(|c| c.step.abs().max_element() <= config.max_step_g + 1e-7));
+}
@@ -0,0 +1,284 @@
+//! Hardware-in-the-loop battery against the register mock: checks fail and skip without
+//! aborting the run, report JSON, the `decode` command of the binary, see the `hil` module.
+
+mod common;
+
+use embedded_hal::blocking::i2c::{Write, WriteRead};
+use mpu6050::device::*;
+use mpu6050::hil::*;
+use mpu6050::hint::{RecoveryHint, BUS_RETRY_DELAY_MS};
+use mpu6050::*;
+
+use common::{NoDelay, RegisterMock};
+
+/// quick bounds, the mock answers at once
+fn options() -> HilOptions {
+    HilOptions {
+        noise_samples: 20,
+        calibration_samples: 20,
+        fifo_duration_ms: 100,
+        motion_timeout_ms: 100,
+        ..HilOptions::default()
+    }
+}
+
+/// 1 g on Z, no rotation: accel and gyro words of one FIFO frame
+fn fifo_frame() -> [u8; 12] {
+    let mut frame = [0; 12];
+    frame[4..6].copy_from_slice(&16384i16.to_be_bytes());
+    frame
+}
+
+/// operator tapping the sensor right away
+#[derive(Default)]
+struct Bench {
+    prompts: Vec<String>,
+    started: Vec<CheckId>,
+}
+
+impl Operator for Bench {
+    fn interactive(&self) -> bool {
+        true
+    }
+
+    fn prompt(&mut self, message: &str) {
+        self.prompts.push(message.into());
+    }
+
+    fn starting(&mut self, check: CheckId) {
+        self.started.push(check);
+    }
+}
+
+/// nothing answers at the address
+struct Absent;
+
+impl Write for Absent {
+    type Error = ();
+
+    fn write(&mut self, _address: u8, _bytes: &[u8]) -> Result<(), ()> {
+        Err(())
+    }
+}
+
+impl WriteRead for Absent {
+    type Error = ();
+
+    fn write_read(&mut self, _address: u8, _bytes: &[u8], _buffer: &mut [u8]) -> Result<(), ()> {
+        Err(())
+    }
+}
+
+fn status(report: &HilReport, id: CheckId) -> CheckStatus {
+    report.check(id).unwrap().status
+}
+
+#[test]
+fn failing_checks_do_not_abort_the_run() {
+    let mut mock = RegisterMock::new();
+    mock.fifo.extend((0..10).flat_map(|_| fifo_frame()));
+    let mut mpu = Mpu6050Builder::new().i2c(mock).build().unwrap();
+    let report = run_hil(&mut mpu, &mut NoDelay, &mut Unattended, &options(), "mock");
+
+    let ids: Vec<_> = report.checks.iter().map(|c| c.id).collect();
+    assert_eq!(ids, CheckId::ALL);
+    assert_eq!(
+        report.chip,
+        Some(ChipInfo {
+            who_am_i: 0x68,
+            variant: ChipVariant::Mpu6050
+        })
+    );
+    for id in [
+        CheckId::Probe,
+        CheckId::InitProfiles,
+        CheckId::RangeSweep,
+        CheckId::DlpfSweep,
+        CheckId::GyroCalibration,
+        CheckId::FifoStreaming,
+    ] {
+        assert_eq!(status(&report, id), CheckStatus::Pass, "{:?}", id);
+    }
+    // the mock's output never changes and ignores the self-test bits
+    let noise = report.check(CheckId::Noise).unwrap();
+    assert_eq!(noise.status, CheckStatus::Fail);
+    assert_eq!(
+        noise.note.as_deref(),
+        Some("acc output constant; gyro output constant")
+    );
+    // out of bounds measurements, no error to hint from
+    assert_eq!(report.recovery_hint(), None);
+    assert_eq!(status(&report, CheckId::SelfTest), CheckStatus::Fail);
+    let motion = report.check(CheckId::MotionInterrupt).unwrap();
+    assert_eq!(motion.status, CheckStatus::Skip);
+    assert!(motion.note.as_deref().unwrap().contains("operator"));
+
+    let fifo = report.check(CheckId::FifoStreaming).unwrap();
+    assert!(fifo.measurements.contains(&("frames".into(), 10.)));
+    assert!(!report.passed());
+    assert_eq!(
+        (
+            report.count(CheckStatus::Pass),
+            report.count(CheckStatus::Fail),
+            report.count(CheckStatus::Skip)
+        ),
+        (6, 2, 1)
+    );
+    // left at init and the default settings
+    assert_eq!(
+        mpu.read_settings().unwrap(),
+        settings::Mpu6050Settings::new()
+    );
+}
+
+#[test]
+fn missing_fifo_data_fails_continuity() {
+    let mut mpu = Mpu6050Builder::new()
+        .i2c(RegisterMock::new())
+        .build()
+        .unwrap();
+    let options = HilOptions {
+        checks: vec![CheckId::FifoStreaming],
+        ..options()
+    };
+    let report = run_hil(&mut mpu, &mut NoDelay, &mut Unattended, &options, "mock");
+    assert_eq!(report.checks.len(), 2);
+    let fifo = report.check(CheckId::FifoStreaming).unwrap();
+    assert_eq!(fifo.status, CheckStatus::Fail);
+    assert_eq!(fifo.note.as_deref(), Some("0 frames, expected 10"));
+    assert!(!mpu.fifo_streaming());
+}
+
+#[test]
+fn no_answer_skips_everything_after_the_probe() {
+    let mut mpu = Mpu6050Builder::new().i2c(Absent).build().unwrap();
+    let mut bench = Bench::default();
+    let report = run_hil(&mut mpu, &mut NoDelay, &mut bench, &options(), "absent");
+    assert_eq!(report.chip, None);
+    assert_eq!(status(&report, CheckId::Probe), CheckStatus::Fail);
+    // unclassified, one failure below the disconnect threshold
+    let retry = RecoveryHint::RetryAfterDelay {
+        ms: BUS_RETRY_DELAY_MS,
+    };
+    assert_eq!(report.check(CheckId::Probe).unwrap().hint, Some(retry));
+    assert_eq!(report.recovery_hint(), Some(retry));
+    assert!(report.checks[1..]
+        .iter()
+        .all(|c| c.status == CheckStatus::Skip
+            && c.note.as_deref() == Some("no answer to the probe")));
+    assert_eq!(bench.started, [CheckId::Probe]);
+    assert!(report.to_json().contains("\"chip\":null"));
+}
+
+#[test]
+fn motion_check_prompts_the_operator() {
+    let mut mock = RegisterMock::new();
+    mock.regs[INT_STATUS::ADDR as usize] = 1 << INT_STATUS::MOT_INT;
+    let mut mpu = Mpu6050Builder::new().i2c(mock).build().unwrap();
+    let mut bench = Bench::default();
+    let options = HilOptions {
+        checks: vec![CheckId::MotionInterrupt, CheckId::RangeSweep],
+        ..options()
+    };
+    let report = run_hil(&mut mpu, &mut NoDelay, &mut bench, &options, "mock");
+    // battery order, not option order
+    assert_eq!(
+        bench.started,
+        [
+            CheckId::Probe,
+            CheckId::RangeSweep,
+            CheckId::MotionInterrupt
+        ]
+    );
+    assert_eq!(bench.prompts, ["tap the sensor within 0 s"]);
+    let motion = report.check(CheckId::MotionInterrupt).unwrap();
+    assert_eq!(motion.status, CheckStatus::Pass);
+    assert_eq!(motion.measurements, [("latency_ms".into(), 0.)]);
+}
+
+#[test]
+fn report_json_and_summary() {
+    let mut report = HilReport::new("/dev/i2c-\"1\"", 0x69);
+    report.chip = Some(ChipInfo {
+        who_am_i: 0x72,
+        variant: ChipVariant::Unknown(0x72),
+    });
+    report.checks.push(CheckResult {
+        id: CheckId::Noise,
+        status: CheckStatus::Fail,
+        note: Some("acc x noise\nhigh".into()),
+        hint: Some(RecoveryHint::RetryAfterDelay { ms: 10 }),
+        measurements: vec![("acc_x_mg_rms".into(), 2.5), ("limit".into(), f32::NAN)],
+    });
+    report.checks.push(CheckResult {
+        id: CheckId::SelfTest,
+        status: CheckStatus::Skip,
+        note: None,
+        hint: None,
+        measurements: Vec::new(),
+    });
+    assert_eq!(
+        report.to_json(),
+        format!(
+            concat!(
+                "{{\"schema_version\":{},\"driver_version\":\"{}\",",
+                "\"bus\":\"/dev/i2c-\\\"1\\\"\",\"address\":105,",
+                "\"chip\":{{\"who_am_i\":114,\"variant\":\"Unknown(114)\"}},",
+                "\"summary\":{{\"pass\":0,\"fail\":1,\"skip\":1}},\"checks\":[",
+                "{{\"id\":\"noise\",\"api\":\"Mpu6050::get_acc, Mpu6050::get_gyro\",",
+                "\"status\":\"fail\",\"note\":\"acc x noise\\nhigh\",",
+                "\"hint\":{{\"action\":\"retry_after_delay\",\"delay_ms\":10}},",
+                "\"measurements\":{{\"acc_x_mg_rms\":2.5,\"limit\":null}}}},",
+                "{{\"id\":\"self_test\",\"api\":\"Mpu6050::set_accel_x_self_test\",",
+                "\"status\":\"skip\",\"note\":null,\"hint\":null,\"measurements\":{{}}}}]}}"
+            ),
+            HIL_SCHEMA_VERSION,
+            env!("CARGO_PKG_VERSION")
+        )
+    );
+
+    let summary = report.to_string();
+    assert!(summary.starts_with("hil report v1, driver"));
+    assert!(summary.contains("Unknown(114) (WHO_AM_I 0x72)"));
+    assert!(summary.ends_with("0 passed, 1 failed, 1 skipped"));
+
+    for id in CheckId::ALL {
+        assert_eq!(CheckId::from_name(id.name()), Some(id));
+    }
+    assert_eq!(CheckId::from_name("nope"), None);
+}
+
+#[test]
+fn decode_command_prints_a_black_box_export() {
+    use mpu6050::black_box::BlackBox;
+    use mpu6050::metrics::MetricEvent;
+    use std::process::Command;
+
+    let mut black_box = BlackBox::<4>::new();
+    black_box.record(&MetricEvent::Initialized { chip_id: 0x68 });
+    black_box.record(&MetricEvent::Disconnected);
+    let mut page = [0xff; 64];
+    black_box.export(&mut page);
+    let dir = std::env::temp_dir();
+    let path = dir.join(format!("mpu6050-black-box-{}.bin", std::process::id()));
+    std::fs::write(&path, page).unwrap();
+
+    let hil = env!("CARGO_BIN_EXE_hil");
+    let output = Command::new(hil).arg("decode").arg(&path).output().unwrap();
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "2 events recorded, 0 dropped\n0: initialized, chip 0x68\n1: disconnected\n"
+    );
+
+    // erased flash does not decode
+    std::fs::write(&path, [0xff; 64]).unwrap();
+    let output = Command::new(hil).arg("decode").arg(&path).output().unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(output.status.code(), Some(2));
+    assert!(String::from_utf8(output.stderr)
+        .unwrap()
+        .ends_with(": unknown packed format\n"));
+    let output = Command::new(hil).arg("decode").output().unwrap();
+    assert_eq!(output.status.code(), Some(2));
+}
@@ -0,0 +1,413 @@
+//! Recovery hints: a row for every error variant, the I2C rows from the context and the
+//! driver's counters, the composite errors and the reconnect outcomes, see the `hint`
+//! module.
+
+mod common;
+
+use std::cell::Cell;
+
+use mpu6050::aliasing::{AliasingAssessment, AliasingRisk};
+use mpu6050::board::{AddrConstraint, ConstraintViolation, DeviceAddr};
+use mpu6050::bus::RateTooHigh;
+use mpu6050::chaos::{ChaosConfig, FaultKind, FlakyI2c};
+use mpu6050::connection::ReconnectOutcome;
+use mpu6050::cooperative::{DrainProgress, FifoDrainError};
+use mpu6050::deadline::AbortProgress;
+use mpu6050::device::*;
+use mpu6050::hint::*;
+use mpu6050::interpolation::TimestampError;
+use mpu6050::parse_policy::FrameCheck;
+use mpu6050::recovery::*;
+use mpu6050::register::{AccessViolation, Register};
+use mpu6050::sampling::SamplingError;
+use mpu6050::script::*;
+use mpu6050::settings::SettingsError;
+use mpu6050::setup::AutoSetupFailure;
+use mpu6050::*;
+
+use common::{NoDelay, RegisterMock};
+
+type Error = Mpu6050Error<()>;
+
+const RETRY: RecoveryHint = RecoveryHint::RetryAfterDelay {
+    ms: BUS_RETRY_DELAY_MS,
+};
+
+/// the module's table, without a catch-all: a new variant does not build without its row
+fn row(error: &Error) -> RecoveryHint {
+    match error {
+        Mpu6050Error::I2c(()) => RETRY,
+        Mpu6050Error::InvalidChipId(0x00 | 0xff) => RecoveryHint::CheckWiring,
+        Mpu6050Error::InvalidChipId(0x70 | 0x71 | 0x73) => RecoveryHint::ReplaceHardware,
+        Mpu6050Error::InvalidChipId(_) => RecoveryHint::FixConfiguration,
+        Mpu6050Error::Disconnected => RecoveryHint::CheckWiring,
+        Mpu6050Error::Unsupported(_)
+        | Mpu6050Error::WakeOnMotionRefused(MotionEngineStatus::Broken) => {
+            RecoveryHint::ReplaceHardware
+        }
+        Mpu6050Error::Aborted { .. } | Mpu6050Error::ImplausibleFrame { .. } => {
+            RecoveryHint::RetryImmediately
+        }
+        #[allow(deprecated)]
+        Mpu6050Error::ExtDataOverflow(_)
+        | Mpu6050Error::StaleExtDataSlot
+        | Mpu6050Error::StaleFifoSchema
+        | Mpu6050Error::StaleConfiguration { .. }
+        | Mpu6050Error::BufferTooSmall(_)
+        | Mpu6050Error::InvalidSettings(_)
+        | Mpu6050Error::AliasingLikely(_)
+        | Mpu6050Error::InvalidTimestamp(_)
+        | Mpu6050Error::DelayRequired
+        | Mpu6050Error::FifoActive
+        | Mpu6050Error::InvalidRegisterAccess { .. }
+        | Mpu6050Error::BoardConstraint(_)
+        | Mpu6050Error::NotActivated
+        | Mpu6050Error::DeniedRegisterRange(_)
+        | Mpu6050Error::WakeOnMotionRefused(_)
+        | Mpu6050Error::Reentrancy
+        | Mpu6050Error::BusRateTooHigh(_) => RecoveryHint::FixConfiguration,
+    }
+}
+
+/// one of each variant, the chip ids of every row
+#[allow(deprecated)]
+fn every_variant() -> Vec<Error> {
+    vec![
+        Mpu6050Error::I2c(()),
+        Mpu6050Error::InvalidChipId(0x00),
+        Mpu6050Error::InvalidChipId(0xff),
+        Mpu6050Error::InvalidChipId(0x70),
+        Mpu6050Error::InvalidChipId(0x73),
+        Mpu6050Error::InvalidChipId(0x72),
+        Mpu6050Error::Disconnected,
+        Mpu6050Error::ExtDataOverflow(30),
+        Mpu6050Error::StaleExtDataSlot,
+        Mpu6050Error::StaleFifoSchema,
+        Mpu6050Error::StaleConfiguration {
+            captured: 3,
+            current: 5,
+        },
+        Mpu6050Error::BufferTooSmall(12),
+        Mpu6050Error::InvalidSettings(SettingsError::ReservedDlpfCfg(7)),
+        Mpu6050Error::AliasingLikely(AliasingAssessment {
+            risk: AliasingRisk::AliasingLikely,
+            odr_hz: 100.,
+            accel_rate_hz: 1000.,
+            accel_bandwidth_hz: 260.,
+            gyro_bandwidth_hz: 256.,
+            ratio: 2.6,
+            cycle: false,
+        }),
+        Mpu6050Error::InvalidTimestamp(TimestampError::Duplicate { t_us: 5 }),
+        Mpu6050Error::Unsupported(Capability::Fifo),
+        Mpu6050Error::Aborted {
+            phase: "gyro calibration",
+            progress: AbortProgress::default(),
+        },
+        Mpu6050Error::DelayRequired,
+        Mpu6050Error::FifoActive,
+        Mpu6050Error::InvalidRegisterAccess {
+            register: Register::WHO_AM_I,
+            violation: AccessViolation::ReadOnly,
+        },
+        Mpu6050Error::BoardConstraint(ConstraintViolation::AddrConflict {
+            constraint: AddrConstraint::Fixed(DeviceAddr::Ad0Low),
+            slave_addr: 0x69,
+        }),
+        Mpu6050Error::NotActivated,
+        Mpu6050Error::DeniedRegisterRange(DENIED_RANGES[0]),
+        Mpu6050Error::ImplausibleFrame {
+            reason: FrameCheck::AllZero,
+        },
+        Mpu6050Error::WakeOnMotionRefused(MotionEngineStatus::Broken),
+        Mpu6050Error::WakeOnMotionRefused(MotionEngineStatus::Unverified),
+        Mpu6050Error::Reentrancy,
+        Mpu6050Error::BusRateTooHigh(RateTooHigh {
+            required_hz: 8000.,
+            available_hz: 1481.,
+        }),
+    ]
+}
+
+#[test]
+fn every_error_variant_has_its_row() {
+    for error in every_variant() {
+        assert_eq!(error.recovery_hint(), row(&error), "{:?}", error);
+        // the context only moves the I2C rows
+        let context = HintContext {
+            bus_error: Some(BusErrorKind::Nak),
+            successes: 10,
+            ..HintContext::NONE
+        };
+        if !matches!(error, Mpu6050Error::I2c(_)) {
+            assert_eq!(error.recovery_hint_with(&context), row(&error));
+        }
+    }
+}
+
+#[test]
+fn bus_errors_follow_the_context() {
+    let error = Error::I2c(());
+    let hint = |context: HintContext| error.recovery_hint_with(&context);
+    let nak = Some(BusErrorKind::Nak);
+    let bus = Some(BusErrorKind::Bus);
+    assert_eq!(hint(HintContext::default()), RETRY);
+    assert_eq!(
+        hint(HintContext {
+            bus_error: nak,
+            ..HintContext::NONE
+        }),
+        RecoveryHint::FixConfiguration
+    );
+    assert_eq!(
+        hint(HintContext {
+            bus_error: nak,
+            successes: 5000,
+            ..HintContext::NONE
+        }),
+        RecoveryHint::CheckWiring
+    );
+    let stuck = HintContext {
+        bus_error: bus,
+        successes: 5000,
+        consecutive_errors: 3,
+        ..HintContext::NONE
+    };
+    assert_eq!(hint(stuck), RecoveryHint::ResetDevice);
+    assert_eq!(
+        hint(HintContext {
+            successes: 0,
+            ..stuck
+        }),
+        RecoveryHint::CheckWiring
+    );
+    assert_eq!(
+        hint(HintContext {
+            disconnect_threshold: 4,
+            ..stuck
+        }),
+        RETRY
+    );
+    // a failed lockup recovery beats the counts
+    assert_eq!(
+        hint(HintContext {
+            unrecovered_lockups: 1,
+            consecutive_errors: 0,
+            ..stuck
+        }),
+        RecoveryHint::PowerCycle
+    );
+}
+
+thread_local! {
+    static LAST_FAULT: Cell<Option<FaultKind>> = const { Cell::new(None) };
+}
+
+fn classify() -> BusErrorKind {
+    match LAST_FAULT.with(Cell::get) {
+        Some(FaultKind::Nak) => BusErrorKind::Nak,
+        _ => BusErrorKind::Bus,
+    }
+}
+
+fn bus_reset() -> BusRecoveryOutcome {
+    BusRecoveryOutcome::Failed
+}
+
+#[test]
+fn driver_context_from_its_counters() {
+    // nothing answers, unclassified
+    let (absent, _) = FlakyI2c::new(RegisterMock::new(), ChaosConfig::NONE.with_fail_every(1));
+    let mut mpu = Mpu6050Builder::new().i2c(absent).build().unwrap();
+    let error = mpu.init(&mut NoDelay).unwrap_err();
+    let context = mpu.hint_context();
+    assert_eq!(context.successes, 0);
+    assert_eq!(context.bus_error, None);
+    assert_eq!(error.recovery_hint_with(&context), RETRY);
+    for _ in 0..2 {
+        let _ = mpu.get_acc();
+    }
+    let error = mpu.get_acc().unwrap_err();
+    assert_eq!(
+        error.recovery_hint_with(&mpu.hint_context()),
+        RecoveryHint::CheckWiring
+    );
+
+    // a NAK after the sensor answered, classified by the recovery registration
+    let (flaky, chaos) = FlakyI2c::new(
+        RegisterMock::new(),
+        ChaosConfig::NONE.with_fault(FaultKind::Nak),
+    );
+    let mut mpu = Mpu6050Builder::new().i2c(flaky).build().unwrap();
+    mpu.init(&mut NoDelay).unwrap();
+    mpu.set_bus_recovery(Some(
+        BusRecovery::new(bus_reset)
+            .with_classifier(classify)
+            .with_detection(LockupDetection::new(2, 2).with_min_interval(0)),
+    ));
+    LAST_FAULT.with(|fault| fault.set(Some(FaultKind::Nak)));
+    chaos.fail_next(1);
+    let error = mpu.get_acc().unwrap_err();
+    let context = mpu.hint_context();
+    assert!(context.successes > 0);
+    assert_eq!(context.bus_error, Some(BusErrorKind::Nak));
+    assert_eq!(
+        error.recovery_hint_with(&context),
+        RecoveryHint::CheckWiring
+    );
+
+    // bus errors up to a failed recovery
+    LAST_FAULT.with(|fault| fault.set(Some(FaultKind::BusError)));
+    chaos.fail_next(2);
+    let _ = mpu.get_acc();
+    let error = mpu.get_acc().unwrap_err();
+    assert_eq!(mpu.bus_recovery_stats().unwrap().unrecovered, 1);
+    assert_eq!(
+        error.recovery_hint_with(&mpu.hint_context()),
+        RecoveryHint::PowerCycle
+    );
+}
+
+#[test]
+fn composite_errors_pass_the_driver_error_on() {
+    let wrong_address = HintContext {
+        bus_error: Some(BusErrorKind::Nak),
+        ..HintContext::NONE
+    };
+    let script: ScriptError<()> = ScriptError::Driver {
+        step: 2,
+        error: Mpu6050Error::I2c(()),
+    };
+    assert_eq!(script.recovery_hint(), RETRY);
+    assert_eq!(
+        script.recovery_hint_with(&wrong_address),
+        RecoveryHint::FixConfiguration
+    );
+
+    // rejected before the bus, an expectation failing on the chip
+    let mut mpu = Mpu6050Builder::new()
+        .i2c(RegisterMock::new())
+        .build()
+        .unwrap();
+    mpu.init(&mut NoDelay).unwrap();
+    let rejected = RegisterScript::new().with_step(ScriptStep::WriteReg {
+        reg: WHOAMI,
+        value: 0,
+    });
+    let error = mpu.run_script(&rejected, &mut NoDelay).unwrap_err();
+    assert!(matches!(error, ScriptError::Rejected(_)));
+    assert_eq!(error.recovery_hint(), RecoveryHint::FixConfiguration);
+    let expecting = RegisterScript::new().with_step(ScriptStep::ExpectBits {
+        reg: WHOAMI,
+        start_bit: 7,
+        length: 8,
+        value: 0x70,
+        on_fail: OnFail::Abort,
+    });
+    let error = mpu.run_script(&expecting, &mut NoDelay).unwrap_err();
+    assert!(matches!(error, ScriptError::ExpectationFailed { .. }));
+    assert_eq!(error.recovery_hint(), RecoveryHint::ResetDevice);
+
+    let setup: AutoSetupFailure<()> = AutoSetupFailure::Device(Mpu6050Error::Disconnected);
+    assert_eq!(setup.recovery_hint(), RecoveryHint::CheckWiring);
+    let setup: AutoSetupFailure<()> = AutoSetupFailure::ActuationCheck {
+        response: Vec3A::ZERO,
+    };
+    assert_eq!(setup.recovery_hint(), RecoveryHint::ReplaceHardware);
+    let setup: AutoSetupFailure<()> = AutoSetupFailure::CalibrationRejected {
+        std_dev: 0.2,
+        limit: 0.05,
+    };
+    assert_eq!(
+        setup.recovery_hint(),
+        RecoveryHint::RetryAfterDelay {
+            ms: MOTION_RETRY_DELAY_MS
+        }
+    );
+
+    let drain: FifoDrainError<()> = FifoDrainError {
+        progress: DrainProgress::default(),
+        error: Mpu6050Error::StaleConfiguration {
+            captured: 0,
+            current: 1,
+        },
+    };
+    assert_eq!(drain.recovery_hint(), RecoveryHint::FixConfiguration);
+    let sampling: SamplingError<(), ()> = SamplingError::Device(Mpu6050Error::I2c(()));
+    assert_eq!(sampling.recovery_hint(), RETRY);
+    let sampling: SamplingError<(), ()> = SamplingError::Wait(());
+    assert_eq!(sampling.recovery_hint(), RecoveryHint::CheckWiring);
+}
+
+#[test]
+fn reconnect_outcomes_hint_from_the_same_rows() {
+    assert_eq!(
+        ReconnectOutcome::SameChip { chip_id: 0x68 }.recovery_hint(),
+        None
+    );
+    for found in [0x00, 0x70, 0x72] {
+        let outcome = ReconnectOutcome::DifferentChip {
+            previous: 0x68,
+            found,
+        };
+        assert_eq!(
+            outcome.recovery_hint(),
+            Some(Error::InvalidChipId(found).recovery_hint())
+        );
+    }
+    // another MPU-6050 wants init
+    let swapped = ReconnectOutcome::DifferentChip {
+        previous: 0x70,
+        found: 0x68,
+    };
+    assert_eq!(swapped.recovery_hint(), Some(RecoveryHint::ResetDevice));
+
+    assert_eq!(ResyncOutcome::Resumed { restored: 2 }.recovery_hint(), None);
+    assert_eq!(
+        ResyncOutcome::NeedsReconnect.recovery_hint(),
+        Some(RecoveryHint::ResetDevice)
+    );
+    let failed = HintContext {
+        bus_error: Some(BusErrorKind::Bus),
+        unrecovered_lockups: 1,
+        ..HintContext::NONE
+    };
+    assert_eq!(
+        ResyncOutcome::BusFailed.recovery_hint(),
+        Some(Error::I2c(()).recovery_hint_with(&failed))
+    );
+}
+
+#[test]
+fn hints_order_by_what_to_act_on_first() {
+    let mut hints = [
+        RecoveryHint::FixConfiguration,
+        RecoveryHint::RetryAfterDelay { ms: 1000 },
+        RecoveryHint::CheckWiring,
+        RecoveryHint::RetryImmediately,
+        RecoveryHint::PowerCycle,
+        RETRY,
+        RecoveryHint::ReplaceHardware,
+        RecoveryHint::ResetDevice,
+    ];
+    hints.sort();
+    let names: Vec<_> = hints.iter().map(RecoveryHint::name).collect();
+    assert_eq!(
+        names,
+        [
+            "retry_immediately",
+            "retry_after_delay",
+            "retry_after_delay",
+            "reset_device",
+            "power_cycle",
+            "check_wiring",
+            "replace_hardware",
+            "fix_configuration"
+        ]
+    );
+    assert_eq!(hints[1], RETRY);
+    assert!(hints[..4].iter().all(RecoveryHint::is_automatic));
+    assert!(!hints[4..].iter().any(RecoveryHint::is_automatic));
+    assert_eq!(RETRY.to_string(), "retry after 10 ms");
+    assert_eq!(RecoveryHint::CheckWiring.to_string(), "check wiring");
+}
@@ -0,0 +1,228 @@
+//! Every scaled output path passes the sample hook and tap, the raw reads do not, see the
+//! `hook` module.
+
+mod common;
+
+use std::cell::Cell;
+
+use mpu6050::device::DEFAULT_SLAVE_ADDR;
+use mpu6050::differential::DifferentialPair;
+use mpu6050::fifo::FifoSources;
+use mpu6050::interpolation::InterpolatingBuffer;
+use mpu6050::sampling::SampleControl;
+use mpu6050::smoothing::{FilterSpec, FilteredView};
+use mpu6050::split::{ConsumerSpec, OverflowPolicy, StreamSplitter};
+use mpu6050::*;
+
+use common::{NoDelay, RegisterMock, SharedBus};
+
+thread_local! {
+    static HOOKED: Cell<u32> = const { Cell::new(0) };
+    static TAPPED: Cell<u32> = const { Cell::new(0) };
+    static TAPPED_ACC_X: Cell<f32> = const { Cell::new(0.) };
+}
+
+/// adds 0.5g on X and 1°C, NaN parts of partial samples stay NaN
+fn hook(sample: &mut MpuSample) {
+    HOOKED.with(|n| n.set(n.get() + 1));
+    *sample = sample
+        .with_acc(sample.acc() + Vec3A::new(0.5, 0., 0.))
+        .with_temp(sample.temp() + 1.);
+}
+
+fn tap(sample: &MpuSample) {
+    TAPPED.with(|n| n.set(n.get() + 1));
+    TAPPED_ACC_X.with(|x| x.set(sample.acc().x));
+}
+
+fn counts() -> (u32, u32) {
+    (HOOKED.with(Cell::get), TAPPED.with(Cell::get))
+}
+
+fn hooked(mut mpu: Mpu6050<RegisterMock>) -> Mpu6050<RegisterMock> {
+    mpu.init(&mut NoDelay).unwrap();
+    mpu.set_sample_hook(Some(hook));
+    mpu.set_sample_tap(Some(tap));
+    mpu
+}
+
+fn driver() -> Mpu6050<RegisterMock> {
+    hooked(
+        Mpu6050Builder::new()
+            .i2c(RegisterMock::new())
+            .build()
+            .unwrap(),
+    )
+}
+
+/// runs `read` and asserts hook and tap ran exactly `n` times
+fn assert_passes<T>(n: u32, read: impl FnOnce() -> T) -> T {
+    let before = counts();
+    let out = read();
+    let after = counts();
+    assert_eq!(after.0 - before.0, n, "hook calls");
+    assert_eq!(after.1 - before.1, n, "tap calls");
+    out
+}
+
+#[test]
+fn single_sensor_reads_pass_the_hook() {
+    let mut mpu = driver();
+
+    let acc = assert_passes(1, || mpu.get_acc().unwrap());
+    assert!((acc.x - 0.5).abs() < 1e-3);
+    assert!((TAPPED_ACC_X.with(Cell::get) - acc.x).abs() < 1e-6);
+    assert_passes(1, || mpu.get_gyro().unwrap());
+    let temp = assert_passes(1, || mpu.get_temp().unwrap());
+    assert!((temp - 37.53).abs() < 1e-2, "{}", temp);
+
+    let acc: [f32; 3] = assert_passes(1, || mpu.get_acc_as().unwrap());
+    assert!((acc[0] - 0.5).abs() < 1e-3);
+    assert_passes(1, || mpu.get_gyro_as::<[f32; 3]>().unwrap());
+    // angles are computed from the corrected accel
+    let tilt = assert_passes(1, || mpu.get_acc_angles_checked().unwrap());
+    assert!(tilt.pitch < -0.1);
+    assert_passes(1, || mpu.get_acc_angles().unwrap());
+    assert_passes(1, || mpu.get_acc_angles_as::<[f32; 4]>().unwrap());
+}
+
+#[test]
+fn full_sample_reads_pass_the_hook_once() {
+    let mut mpu = driver();
+
+    let mut seen = Vec::new();
+    assert_passes(3, || {
+        mpu.run_sampling_loop(
+            || Ok::<(), ()>(()),
+            |sample, meta| {
+                seen.push(sample);
+                match meta.sequence {
+                    2 => SampleControl::Stop,
+                    _ => SampleControl::Continue,
+                }
+            },
+        )
+        .unwrap()
+    });
+    assert!(seen.iter().all(|s| (s.acc().x - 0.5).abs() < 1e-3));
+
+    let mut buffer = InterpolatingBuffer::<4>::new();
+    let sample = assert_passes(1, || {
+        mpu.sample_into_interp_buffer(&mut buffer, 1000, None)
+            .unwrap()
+    });
+    assert!((sample.acc().x - 0.5).abs() < 1e-3);
+    assert!((sample.temp() - 37.53).abs() < 1e-2);
+}
+
+#[test]
+fn burst_reads_pass_the_hook_once() {
+    let mut mpu = driver();
+
+    let sample = assert_passes(1, || mpu.get_all().unwrap());
+    assert!((sample.acc().x - 0.5).abs() < 1e-3);
+    assert!((sample.temp() - 37.53).abs() < 1e-2);
+
+    let samples = assert_passes(4, || {
+        mpu.samples()
+            .take(4)
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap()
+    });
+    assert!(samples.iter().all(|s| (s.acc().x - 0.5).abs() < 1e-3));
+
+    // the views are fed the hooked sample
+    let mut view: FilteredView = FilteredView::new(FilterSpec::RAW);
+    assert_passes(1, || mpu.read_into_views(&mut [&mut view]).unwrap());
+    assert!((view.latest().unwrap().acc().x - 0.5).abs() < 1e-3);
+
+    let mut batch = [MpuSample::default(); 3];
+    assert_passes(3, || mpu.read_batch(|| {}, &mut batch).unwrap());
+    assert!(batch.iter().all(|s| (s.acc().x - 0.5).abs() < 1e-3));
+}
+
+#[test]
+fn scaled_fifo_drains_pass_the_hook() {
+    let bus = SharedBus::new(&[DEFAULT_SLAVE_ADDR]);
+    let mut mpu = Mpu6050Builder::new().i2c(bus.clone()).build().unwrap();
+    mpu.init(&mut NoDelay).unwrap();
+    mpu.set_sample_hook(Some(hook));
+    mpu.set_sample_tap(Some(tap));
+    let schema = mpu
+        .set_fifo_sources(FifoSources::NONE.with_accel(true))
+        .unwrap();
+    // 1g on Z at ±2g
+    let frame = [0, 0, 0, 0, 0x40, 0];
+    bus.device(DEFAULT_SLAVE_ADDR, |mock| {
+        for _ in 0..5 {
+            mock.fifo.extend(frame);
+        }
+    });
+
+    let mut splitter =
+        StreamSplitter::<1, 8>::new([ConsumerSpec::new(1, OverflowPolicy::DropOldest)]);
+    let drained = assert_passes(5, || {
+        mpu.drain_fifo_into(&schema, &mut [0; 64], &mut splitter)
+            .unwrap()
+    });
+    assert_eq!(drained, 5);
+    let samples: Vec<_> = splitter.consumer(0).collect();
+    assert_eq!(samples.len(), 5);
+    for sample in samples {
+        assert!((sample.acc() - Vec3A::new(0.5, 0., 1.)).length() < 1e-3);
+        // not in the frame: NaN before and after the hook
+        assert!(sample.temp().is_nan() && sample.gyro().is_nan());
+    }
+
+    // the raw frames of drain_fifo are not scaled output
+    bus.device(DEFAULT_SLAVE_ADDR, |mock| mock.fifo.extend(frame));
+    assert_passes(0, || mpu.drain_fifo(&schema, &mut [0; 64], |_| ()).unwrap());
+}
+
+#[test]
+fn differential_pair_passes_both_hooks() {
+    let bus = SharedBus::new(&[0x68, 0x69]);
+    let build = |address| {
+        let mut mpu = Mpu6050Builder::new()
+            .i2c(bus.clone())
+            .slave_addr(address)
+            .build()
+            .unwrap();
+        mpu.init(&mut NoDelay).unwrap();
+        mpu.set_sample_hook(Some(hook));
+        mpu.set_sample_tap(Some(tap));
+        mpu
+    };
+    let mut pair = DifferentialPair::new(build(0x68), build(0x69));
+
+    let sample = assert_passes(2, || pair.read_pair().unwrap());
+    assert!((sample.source.acc().x - 0.5).abs() < 1e-3);
+    assert!((sample.payload.acc().x - 0.5).abs() < 1e-3);
+}
+
+#[test]
+fn partial_samples_carry_nan_for_parts_not_read() {
+    fn check_partial(sample: &MpuSample) {
+        assert!(sample.gyro().is_nan());
+        assert!(sample.temp().is_nan());
+        assert!(!sample.acc().is_nan());
+    }
+    let mut mpu = driver();
+    mpu.set_sample_hook(None);
+    mpu.set_sample_tap(Some(check_partial));
+    mpu.get_acc().unwrap();
+}
+
+#[test]
+fn raw_reads_and_removed_hooks_bypass() {
+    let mut mpu = driver();
+    assert_passes(0, || mpu.get_acc_raw().unwrap());
+    assert_passes(0, || mpu.get_gyro_raw().unwrap());
+    assert_passes(0, || mpu.get_all_raw().unwrap());
+
+    mpu.set_sample_hook(None);
+    mpu.set_sample_tap(None);
+    assert!(mpu.get_sample_hook().is_none());
+    let acc = assert_passes(0, || mpu.get_acc().unwrap());
+    assert!(acc.x.abs() < 1e-3);
+}
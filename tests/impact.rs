@@ -0,0 +1,368 @@
+//! Impact ranging: an impact profile scripted through the fake device, switch timing in the
+//! transaction trace, restoration, flags, extension of the hold and the rearm policies, see
+//! the `impact` module.
+
+mod common;
+
+use mpu6050::device::*;
+use mpu6050::impact::*;
+use mpu6050::interleave::RangeInterleave;
+use mpu6050::sampling::SampleControl;
+use mpu6050::settings::SettingsError;
+use mpu6050::settling::SettlingPolicy;
+use mpu6050::synthetic;
+use mpu6050::*;
+
+use common::{Access, NoDelay, SharedBus};
+
+const ADDR: u8 = 0x68;
+
+const CONFIG: ImpactRangeConfig = ImpactRangeConfig {
+    trigger_g: 1.8,
+    hold_samples: 3,
+    high_range: AccelRange::G16,
+    rearm_policy: RearmPolicy::Immediate,
+};
+
+fn read(reg: u8) -> Access {
+    Access {
+        address: ADDR,
+        reg,
+        read: true,
+    }
+}
+
+fn write(reg: u8) -> Access {
+    Access {
+        address: ADDR,
+        reg,
+        read: false,
+    }
+}
+
+/// the three reads of a sample
+fn sample_reads() -> Vec<Access> {
+    vec![read(ACC_REGX_H), read(GYRO_REGX_H), read(TEMP_OUT_H)]
+}
+
+fn switch() -> Vec<Access> {
+    vec![read(ACCEL_CONFIG::ADDR), write(ACCEL_CONFIG::ADDR)]
+}
+
+/// range the fake device is at
+fn device_range(bus: &SharedBus) -> AccelRange {
+    AccelRange::from(bus.device(ADDR, |m| m.regs[ACCEL_CONFIG::ADDR as usize] >> 3 & 0b11))
+}
+
+fn driver() -> (Mpu6050<SharedBus>, SharedBus) {
+    let bus = SharedBus::new(&[ADDR]);
+    let mut mpu = Mpu6050Builder::new().i2c(bus.clone()).build().unwrap();
+    mpu.init(&mut NoDelay).unwrap();
+    // past the settling of the wake
+    sample_profile(&mut mpu, &bus, &[1.; 3]);
+    (mpu, bus)
+}
+
+/// one sample per entry of `profile`, the z acceleration in g, through the sampling loop. The
+/// fake device converts it at the range it is at, saturating. Returns the samples and the
+/// transactions of each, without the INT_STATUS reads of the loop
+fn sample_profile(
+    mpu: &mut Mpu6050<SharedBus>,
+    bus: &SharedBus,
+    profile: &[f32],
+) -> (Vec<MpuSample>, Vec<Vec<Access>>) {
+    let mut samples = Vec::new();
+    let mut logs = Vec::new();
+    bus.take_log();
+    for z in profile {
+        let range = device_range(bus);
+        let frame =
+            synthetic::frame_bytes(Vec3A::new(0., 0., *z), Vec3A::ZERO, range, GyroRange::D250);
+        bus.device(ADDR, |m| m.set_frame(&frame));
+        mpu.run_sampling_loop(
+            || Ok::<(), ()>(()),
+            |sample, _| {
+                samples.push(sample);
+                SampleControl::Stop
+            },
+        )
+        .unwrap();
+        let log = bus.take_log();
+        logs.push(
+            log.into_iter()
+                .filter(|a| a.reg != INT_STATUS::ADDR)
+                .collect(),
+        );
+    }
+    (samples, logs)
+}
+
+/// range, transition and settling flag of each sample
+fn flags(samples: &[MpuSample]) -> Vec<(AccelRange, Option<RangeTransition>, bool)> {
+    samples
+        .iter()
+        .map(|s| (s.accel_range().unwrap(), s.range_transition(), s.settling()))
+        .collect()
+}
+
+#[test]
+fn the_switch_follows_the_triggering_read() {
+    let (mut mpu, bus) = driver();
+    mpu.configure_impact_ranging(CONFIG).unwrap();
+    // configuring touches no register
+    assert!(bus.take_log().is_empty());
+
+    let (samples, logs) = sample_profile(&mut mpu, &bus, &[1., 6., 6., 1.]);
+    assert_eq!(logs[0], sample_reads());
+    // the switch right after the reads of the triggering sample, in the same call
+    assert_eq!(logs[1], [sample_reads(), switch()].concat());
+    // the next sample, one period later, is the first at the high range
+    assert_eq!(logs[2], sample_reads());
+    assert_eq!(logs[3], sample_reads());
+    assert_eq!(device_range(&bus), AccelRange::G16);
+
+    // clipped at ±2 g. The fake device switches at once, the sample is flagged anyway
+    assert!(samples[1].acc().z > 1.99);
+    assert_eq!(samples[2].acc().z, 6.);
+    assert_eq!(
+        flags(&samples),
+        [
+            (AccelRange::G2, None, false),
+            (AccelRange::G2, Some(RangeTransition::Onset), false),
+            (AccelRange::G16, Some(RangeTransition::Switched), true),
+            (AccelRange::G16, None, false),
+        ]
+    );
+    let ranger = mpu.impact_ranger().unwrap();
+    assert_eq!(ranger.phase(), ImpactPhase::High { remaining: 1 });
+    assert!(ranger.is_high());
+    assert_eq!(ranger.stats().impacts, 1);
+}
+
+#[test]
+fn restoration_flags_and_statistics() {
+    let (mut mpu, bus) = driver();
+    mpu.configure_impact_ranging(CONFIG).unwrap();
+
+    let (samples, logs) = sample_profile(&mut mpu, &bus, &[5., 1., 1., 1., 1., 1.]);
+    assert_eq!(logs[4], [switch(), sample_reads()].concat());
+    assert_eq!(
+        flags(&samples),
+        [
+            (AccelRange::G2, Some(RangeTransition::Onset), false),
+            (AccelRange::G16, Some(RangeTransition::Switched), true),
+            (AccelRange::G16, None, false),
+            (AccelRange::G16, None, false),
+            (AccelRange::G2, Some(RangeTransition::Restored), true),
+            (AccelRange::G2, None, false),
+        ]
+    );
+    let ranger = mpu.impact_ranger().unwrap();
+    assert_eq!(ranger.phase(), ImpactPhase::Armed);
+    // the settling sample is not a captured value
+    assert_eq!(
+        ranger.stats(),
+        ImpactStats {
+            impacts: 1,
+            extensions: 0,
+            peak_g: 1.,
+            clipped_anyway: 0,
+        }
+    );
+
+    // clipped at ±16 g as well
+    let (samples, _) = sample_profile(&mut mpu, &bus, &[3., 20., 12., 1., 1.]);
+    assert!(samples[1].acc().z > 15.99);
+    let stats = mpu.impact_ranger().unwrap().stats();
+    assert_eq!((stats.impacts, stats.extensions), (2, 1));
+    assert_eq!((stats.peak_g, stats.clipped_anyway), (12., 0));
+    let (samples, _) = sample_profile(&mut mpu, &bus, &[1., 1., 5., 20., 20.]);
+    assert_eq!(
+        samples[1].range_transition(),
+        Some(RangeTransition::Restored)
+    );
+    assert_eq!(
+        samples[4].range_transition(),
+        Some(RangeTransition::Extended)
+    );
+    let stats = mpu.impact_ranger().unwrap().stats();
+    assert!(stats.peak_g >= 0.99 * 16.);
+    assert_eq!(stats.clipped_anyway, 1);
+    assert_eq!(
+        stats.to_string(),
+        format!(
+            "3 impacts, 2 extensions, peak {:.2} g, 1 clipped anyway",
+            stats.peak_g
+        )
+    );
+}
+
+#[test]
+fn a_second_impact_during_the_hold_extends_it() {
+    let (mut mpu, bus) = driver();
+    mpu.configure_impact_ranging(CONFIG).unwrap();
+
+    // onset at 0, second impact at 2: the hold ends 3 samples after it
+    let profile = [5., 1., 7., 1., 1., 1., 1., 1.];
+    let (samples, logs) = sample_profile(&mut mpu, &bus, &profile);
+    let at_high: Vec<_> = samples
+        .iter()
+        .map(|s| s.accel_range() == Some(AccelRange::G16))
+        .collect();
+    assert_eq!(at_high, [false, true, true, true, true, true, false, false]);
+    assert_eq!(
+        samples[2].range_transition(),
+        Some(RangeTransition::Extended)
+    );
+    assert_eq!(
+        samples[6].range_transition(),
+        Some(RangeTransition::Restored)
+    );
+    let switches: Vec<_> = logs.iter().map(|log| log.len() > 3).collect();
+    assert_eq!(
+        switches,
+        [true, false, false, false, false, false, true, false]
+    );
+    let stats = mpu.impact_ranger().unwrap().stats();
+    assert_eq!((stats.impacts, stats.extensions, stats.peak_g), (1, 1, 7.));
+
+    // the settling sample does not extend
+    let (samples, _) = sample_profile(&mut mpu, &bus, &[5., 9., 1., 1., 1.]);
+    assert_eq!(
+        samples[1].range_transition(),
+        Some(RangeTransition::Switched)
+    );
+    assert_eq!(
+        samples[4].range_transition(),
+        Some(RangeTransition::Restored)
+    );
+}
+
+#[test]
+fn rearm_policies() {
+    let (mut mpu, bus) = driver();
+    assert!(!mpu.rearm_impact_ranging());
+    mpu.configure_impact_ranging(ImpactRangeConfig {
+        rearm_policy: RearmPolicy::Manual,
+        ..CONFIG
+    })
+    .unwrap();
+    let (samples, _) = sample_profile(&mut mpu, &bus, &[5., 1., 1., 1., 5., 5., 5.]);
+    assert_eq!(
+        samples[4].range_transition(),
+        Some(RangeTransition::Restored)
+    );
+    assert!(samples[5..]
+        .iter()
+        .all(|s| s.accel_range() == Some(AccelRange::G2) && s.range_transition().is_none()));
+    assert_eq!(mpu.impact_ranger().unwrap().phase(), ImpactPhase::Disarmed);
+    assert!(mpu.rearm_impact_ranging());
+    assert!(!mpu.rearm_impact_ranging());
+    let (samples, _) = sample_profile(&mut mpu, &bus, &[5.]);
+    assert_eq!(samples[0].range_transition(), Some(RangeTransition::Onset));
+
+    // a reconfiguration during the hold switches back first
+    bus.take_log();
+    mpu.configure_impact_ranging(ImpactRangeConfig {
+        rearm_policy: RearmPolicy::AfterQuiet(2),
+        ..CONFIG
+    })
+    .unwrap();
+    assert_eq!(bus.take_log(), switch());
+    assert_eq!(mpu.impact_ranger().unwrap().stats(), ImpactStats::default());
+    // the sample after the switch back settles, the ringing after the impact resets the
+    // quiet count
+    let profile = [1., 5., 1., 1., 1., 1., 5., 1., 1., 5.];
+    let (samples, _) = sample_profile(&mut mpu, &bus, &profile);
+    let onsets: Vec<_> = samples
+        .iter()
+        .map(|s| s.range_transition() == Some(RangeTransition::Onset))
+        .collect();
+    assert_eq!(
+        onsets,
+        [false, true, false, false, false, false, false, false, false, true]
+    );
+}
+
+#[test]
+fn invalid_and_conflicting_configurations_are_refused_before_the_bus() {
+    let (mut mpu, bus) = driver();
+    bus.take_log();
+    let refused = |mpu: &mut Mpu6050<SharedBus>, config| match mpu.configure_impact_ranging(config)
+    {
+        Err(Mpu6050Error::InvalidSettings(e)) => e,
+        other => panic!("accepted: {:?}", other),
+    };
+    let nan = ImpactRangeConfig {
+        trigger_g: f32::NAN,
+        ..CONFIG
+    };
+    assert_eq!(refused(&mut mpu, nan), SettingsError::ImpactTrigger);
+    let short = ImpactRangeConfig {
+        hold_samples: 1,
+        ..CONFIG
+    };
+    assert_eq!(
+        refused(&mut mpu, short),
+        SettingsError::ImpactHold { hold: 1, min: 2 }
+    );
+    let low = ImpactRangeConfig {
+        high_range: AccelRange::G2,
+        ..CONFIG
+    };
+    assert_eq!(
+        refused(&mut mpu, low),
+        SettingsError::ImpactRanges(AccelRange::G2, AccelRange::G2)
+    );
+    assert!(bus.take_log().is_empty());
+    assert_eq!(mpu.impact_ranger(), None);
+
+    let interleave = RangeInterleave {
+        low: AccelRange::G2,
+        high: AccelRange::G16,
+        period: 3,
+    };
+    mpu.enable_range_interleaving(interleave).unwrap();
+    bus.take_log();
+    assert_eq!(
+        refused(&mut mpu, CONFIG),
+        SettingsError::RangeModesExclusive
+    );
+    mpu.disable_range_interleaving(AccelRange::G2).unwrap();
+    mpu.configure_impact_ranging(CONFIG).unwrap();
+    assert!(matches!(
+        mpu.enable_range_interleaving(interleave),
+        Err(Mpu6050Error::InvalidSettings(
+            SettingsError::RangeModesExclusive
+        ))
+    ));
+}
+
+#[test]
+fn disable_restores_the_range_and_the_policy() {
+    let (mut mpu, bus) = driver();
+    let policy = SettlingPolicy::Discard { max_retries: 2 };
+    mpu.set_settling_policy(policy);
+    mpu.configure_impact_ranging(CONFIG).unwrap();
+    assert_eq!(mpu.get_settling_policy(), SettlingPolicy::Flag);
+    sample_profile(&mut mpu, &bus, &[5., 5.]);
+
+    mpu.disable_impact_ranging().unwrap();
+    assert_eq!(bus.take_log(), switch());
+    assert_eq!(device_range(&bus), AccelRange::G2);
+    assert_eq!(mpu.get_settling_policy(), policy);
+    assert_eq!(mpu.impact_ranger(), None);
+    let (samples, _) = sample_profile(&mut mpu, &bus, &[5.]);
+    assert_eq!(
+        (samples[0].accel_range(), samples[0].range_transition()),
+        (None, None)
+    );
+    // nothing to disable
+    mpu.disable_impact_ranging().unwrap();
+    assert!(bus.take_log().is_empty());
+
+    mpu.configure_impact_ranging(CONFIG).unwrap();
+    mpu.reset_device(&mut NoDelay).unwrap();
+    assert_eq!(mpu.get_settling_policy(), policy);
+    assert_eq!(mpu.impact_ranger(), None);
+}
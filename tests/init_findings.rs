@@ -0,0 +1,253 @@
+//! Leftovers of a crashed previous run found by `init`: each finding scripted as a register
+//! image, with the corrective write in the trace and the reported finding, see the
+//! `init_findings` module.
+
+mod common;
+
+use mpu6050::device::*;
+use mpu6050::init_findings::*;
+use mpu6050::metrics::{MemorySink, MetricEvent, INIT_FINDINGS};
+use mpu6050::register::Register;
+use mpu6050::trace::{TraceHandle, TracingI2c};
+use mpu6050::*;
+
+use common::{NoDelay, SharedBus};
+
+const ADDR: u8 = DEFAULT_SLAVE_ADDR;
+
+/// driver on a chip with the register image of `script`, initialized, and its trace
+fn init_with(
+    script: impl FnOnce(&mut [u8; 256]),
+) -> (Mpu6050<TracingI2c<SharedBus>>, SharedBus, TraceHandle) {
+    let bus = SharedBus::new(&[ADDR]);
+    bus.device(ADDR, |m| script(&mut m.regs));
+    let (i2c, trace) = TracingI2c::new(bus.clone());
+    let mut mpu = Mpu6050Builder::new().i2c(i2c).build().unwrap();
+    mpu.init(&mut NoDelay).unwrap();
+    (mpu, bus, trace)
+}
+
+/// the writes of the trace up to the wake, the corrections
+fn corrections(trace: &TraceHandle) -> Vec<String> {
+    trace
+        .render()
+        .lines()
+        .take_while(|line| *line != "W 0x68 PWR_MGMT_1 [01]")
+        .filter(|line| line.starts_with('W'))
+        .map(String::from)
+        .collect()
+}
+
+fn findings(mpu: &Mpu6050<TracingI2c<SharedBus>>) -> Vec<InitFinding> {
+    mpu.init_findings().iter().copied().collect()
+}
+
+fn finding(kind: FindingKind, action: FindingAction) -> InitFinding {
+    InitFinding { kind, action }
+}
+
+#[test]
+fn a_chip_fresh_from_power_on_has_no_findings() {
+    let (mpu, _, trace) = init_with(|_| {});
+    assert!(mpu.init_findings().is_empty());
+    assert_eq!(mpu.debug_state().init_findings, InitFindings::default());
+    assert_eq!(corrections(&trace), Vec::<String>::new());
+}
+
+#[test]
+fn self_test_bits_are_cleared_before_the_wake() {
+    // mid self-test: gyro all axes at ±2000 °/s, accel X and Z at ±4g
+    let (mpu, bus, trace) = init_with(|regs| {
+        regs[GYRO_CONFIG::ADDR as usize] = 0b1111_1000;
+        regs[ACCEL_CONFIG::ADDR as usize] = 0b1010_1000;
+    });
+    assert_eq!(
+        corrections(&trace),
+        ["W 0x68 GYRO_CONFIG [18]", "W 0x68 ACCEL_CONFIG [08]"]
+    );
+    assert_eq!(
+        findings(&mpu),
+        [
+            finding(
+                FindingKind::SelfTestActive {
+                    gyro: [true, true, true],
+                    accel: [true, false, true],
+                },
+                FindingAction::Cleared
+            ),
+            finding(
+                FindingKind::NonDefaultRegister {
+                    register: Register::GYRO_CONFIG,
+                    value: 0b1111_1000,
+                },
+                FindingAction::Overwritten
+            ),
+            finding(
+                FindingKind::NonDefaultRegister {
+                    register: Register::ACCEL_CONFIG,
+                    value: 0b1010_1000,
+                },
+                FindingAction::Overwritten
+            ),
+        ]
+    );
+    assert!(mpu.init_findings().self_test().is_some());
+    // the init sequence then left both at their reset values
+    bus.device(ADDR, |m| {
+        assert_eq!(m.regs[GYRO_CONFIG::ADDR as usize], 0);
+        assert_eq!(m.regs[ACCEL_CONFIG::ADDR as usize], 0);
+    });
+
+    // self-test bits alone are not a non-default configuration
+    let (mpu, _, trace) = init_with(|regs| regs[GYRO_CONFIG::ADDR as usize] = 0b0010_0000);
+    assert_eq!(corrections(&trace), ["W 0x68 GYRO_CONFIG [00]"]);
+    assert_eq!(
+        findings(&mpu),
+        [finding(
+            FindingKind::SelfTestActive {
+                gyro: [false, false, true],
+                accel: [false; 3],
+            },
+            FindingAction::Cleared
+        )]
+    );
+}
+
+#[test]
+fn a_refused_chip_is_left_untouched() {
+    let bus = SharedBus::new(&[ADDR]);
+    bus.device(ADDR, |m| {
+        m.regs[Register::WHO_AM_I.addr() as usize] = 0x19;
+        m.regs[GYRO_CONFIG::ADDR as usize] = 0b1111_1000;
+        m.regs[INT_STATUS::ADDR as usize] = 1 << INT_STATUS::FIFO_OFLOW_INT;
+    });
+    let (i2c, trace) = TracingI2c::new(bus.clone());
+    let mut mpu = Mpu6050Builder::new().i2c(i2c).build().unwrap();
+    assert!(matches!(
+        mpu.init(&mut NoDelay),
+        Err(Mpu6050Error::InvalidChipId(0x19))
+    ));
+    // WHO_AM_I only, the self-test bits and the overflow are left to the application
+    assert_eq!(trace.render(), "R 0x68 WHO_AM_I [19]\n");
+    assert!(mpu.init_findings().is_empty());
+    bus.device(ADDR, |m| {
+        assert_eq!(m.regs[GYRO_CONFIG::ADDR as usize], 0b1111_1000);
+    });
+}
+
+#[test]
+fn a_chip_found_awake_is_reported_and_woken_as_usual() {
+    for pwr_mgmt_1 in [0x01, 0x28] {
+        let (mpu, bus, trace) = init_with(|regs| regs[PWR_MGMT_1::ADDR as usize] = pwr_mgmt_1);
+        assert_eq!(corrections(&trace), Vec::<String>::new());
+        assert_eq!(
+            findings(&mpu),
+            [finding(
+                FindingKind::PowerState { pwr_mgmt_1 },
+                FindingAction::Overwritten
+            )]
+        );
+        bus.device(ADDR, |m| {
+            assert_eq!(m.regs[PWR_MGMT_1::ADDR as usize], 0x01)
+        });
+    }
+
+    // a second init finds the chip the first one woke
+    let (mut mpu, _, _) = init_with(|_| {});
+    assert!(mpu.init_findings().is_empty());
+    mpu.init(&mut NoDelay).unwrap();
+    assert_eq!(
+        findings(&mpu),
+        [finding(
+            FindingKind::PowerState { pwr_mgmt_1: 0x01 },
+            FindingAction::Overwritten
+        )]
+    );
+}
+
+#[test]
+fn a_latched_fifo_overflow_resets_the_fifo() {
+    // streaming accel and gyro into the FIFO when the previous run died
+    let (mpu, _, trace) = init_with(|regs| {
+        regs[INT_STATUS::ADDR as usize] = 1 << INT_STATUS::FIFO_OFLOW_INT;
+        regs[USER_CTRL::ADDR as usize] = 1 << USER_CTRL::FIFO_EN;
+        regs[FIFO_EN as usize] = 0x78;
+    });
+    assert_eq!(corrections(&trace), ["W 0x68 USER_CTRL [44]"]);
+    assert_eq!(
+        findings(&mpu),
+        [
+            finding(
+                FindingKind::FifoOverflowLatched { int_status: 0x10 },
+                FindingAction::Cleared
+            ),
+            finding(
+                FindingKind::NonDefaultRegister {
+                    register: Register::FIFO_EN,
+                    value: 0x78,
+                },
+                FindingAction::Kept
+            ),
+            finding(
+                FindingKind::NonDefaultRegister {
+                    register: Register::USER_CTRL,
+                    value: 0x40,
+                },
+                FindingAction::Kept
+            ),
+        ]
+    );
+}
+
+#[test]
+fn non_default_registers_are_fingerprinted_and_kept() {
+    let sink: &'static MemorySink = Box::leak(Box::new(MemorySink::new()));
+    let bus = SharedBus::new(&[ADDR]);
+    bus.device(ADDR, |m| {
+        m.regs[SMPLRT_DIV as usize] = 4;
+        m.regs[CONFIG::ADDR as usize] = 3;
+        m.regs[INT_PIN_CFG::ADDR as usize] = 0x20;
+        m.regs[INT_ENABLE::ADDR as usize] = 0x01;
+        m.regs[PWR_MGMT_2::ADDR as usize] = 0x07;
+    });
+    let (i2c, trace) = TracingI2c::new(bus.clone());
+    let mut mpu = Mpu6050Builder::new().i2c(i2c).build().unwrap();
+    mpu.set_metrics_sink(Some(sink));
+    mpu.init(&mut NoDelay).unwrap();
+
+    assert_eq!(corrections(&trace), Vec::<String>::new());
+    let kept = |register, value| {
+        finding(
+            FindingKind::NonDefaultRegister { register, value },
+            FindingAction::Kept,
+        )
+    };
+    let expected = [
+        kept(Register::SMPLRT_DIV, 4),
+        kept(Register::CONFIG, 3),
+        kept(Register::INT_PIN_CFG, 0x20),
+        kept(Register::INT_ENABLE, 0x01),
+        kept(Register::PWR_MGMT_2, 0x07),
+    ];
+    assert_eq!(findings(&mpu), expected);
+    bus.device(ADDR, |m| {
+        assert_eq!(m.regs[SMPLRT_DIV as usize], 4);
+        assert_eq!(m.regs[PWR_MGMT_2::ADDR as usize], 0x07);
+    });
+
+    // one event per finding, before the init event
+    assert_eq!(sink.counter_value(INIT_FINDINGS), 5);
+    let events = sink.events();
+    let reported: Vec<_> = events
+        .iter()
+        .filter_map(|event| match event {
+            MetricEvent::InitFinding(finding) => Some(*finding),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(reported, expected);
+    assert!(matches!(
+        events.last(),
+        Some(MetricEvent::Initialized { .. })
+    ));
+}
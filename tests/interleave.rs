@@ -0,0 +1,282 @@
+//! Range interleaving: transaction sequence, tags and settling flags over full cycles, merge
+//! of synthetic clipped and unclipped patterns, see the `interleave` module.
+
+mod common;
+
+use mpu6050::device::*;
+use mpu6050::interleave::*;
+use mpu6050::sampling::SampleControl;
+use mpu6050::settings::SettingsError;
+use mpu6050::settling::SettlingPolicy;
+use mpu6050::*;
+
+use common::{Access, NoDelay, RegisterMock, SharedBus};
+
+const ADDR: u8 = 0x68;
+
+const CONFIG: RangeInterleave = RangeInterleave {
+    low: AccelRange::G2,
+    high: AccelRange::G16,
+    period: 3,
+};
+
+fn read(reg: u8) -> Access {
+    Access {
+        address: ADDR,
+        reg,
+        read: true,
+    }
+}
+
+fn write(reg: u8) -> Access {
+    Access {
+        address: ADDR,
+        reg,
+        read: false,
+    }
+}
+
+/// the three reads of a sample
+fn sample_reads() -> [Access; 3] {
+    [read(ACC_REGX_H), read(GYRO_REGX_H), read(TEMP_OUT_H)]
+}
+
+fn switch() -> [Access; 2] {
+    [read(ACCEL_CONFIG::ADDR), write(ACCEL_CONFIG::ADDR)]
+}
+
+/// `n` samples through the sampling loop
+fn sample<I>(mpu: &mut Mpu6050<I>, n: usize) -> Vec<MpuSample>
+where
+    I: embedded_hal::blocking::i2c::Write<Error = core::convert::Infallible>
+        + embedded_hal::blocking::i2c::WriteRead<Error = core::convert::Infallible>,
+{
+    let mut samples = Vec::new();
+    mpu.run_sampling_loop(
+        || Ok::<(), ()>(()),
+        |sample, _| {
+            samples.push(sample);
+            if samples.len() == n {
+                SampleControl::Stop
+            } else {
+                SampleControl::Continue
+            }
+        },
+    )
+    .unwrap();
+    samples
+}
+
+/// the log without the INT_STATUS reads of the sampling loop
+fn without_status(log: Vec<Access>) -> Vec<Access> {
+    log.into_iter()
+        .filter(|a| a.reg != INT_STATUS::ADDR)
+        .collect()
+}
+
+fn tagged(x: f32, range: AccelRange) -> MpuSample {
+    MpuSample::new(Vec3A::new(x, 0., 1.), Vec3A::ZERO, 25.).with_accel_range(Some(range))
+}
+
+#[test]
+fn switches_are_read_modify_writes_of_accel_config() {
+    let bus = SharedBus::new(&[ADDR]);
+    let mut mpu = Mpu6050Builder::new().i2c(bus.clone()).build().unwrap();
+    mpu.init(&mut NoDelay).unwrap();
+    bus.take_log();
+
+    mpu.enable_range_interleaving(CONFIG).unwrap();
+    assert_eq!(bus.take_log(), switch());
+    for phase in 0..4 {
+        sample(&mut mpu, 1);
+        let log = without_status(bus.take_log());
+        if phase > 0 {
+            assert_eq!(log, [switch().as_slice(), &sample_reads()].concat());
+        } else {
+            assert_eq!(log, sample_reads());
+        }
+        for _ in 1..CONFIG.period {
+            sample(&mut mpu, 1);
+            assert_eq!(without_status(bus.take_log()), sample_reads());
+        }
+    }
+    let fs_sel = bus.device(ADDR, |m| m.regs[ACCEL_CONFIG::ADDR as usize] >> 3 & 0b11);
+    // the 4th phase is a high one
+    assert_eq!(fs_sel, AccelRange::G16 as u8);
+}
+
+#[test]
+fn samples_are_tagged_over_full_cycles() {
+    let mut mpu = Mpu6050Builder::new()
+        .i2c(RegisterMock::new())
+        .build()
+        .unwrap();
+    mpu.init(&mut NoDelay).unwrap();
+    // past the settling of the wake
+    assert!(sample(&mut mpu, 3)
+        .iter()
+        .all(|s| s.accel_range().is_none()));
+    mpu.enable_range_interleaving(CONFIG).unwrap();
+
+    let seen: Vec<_> = sample(&mut mpu, 4 * CONFIG.period as usize + 1)
+        .iter()
+        .map(|s| (s.accel_range().unwrap(), s.settling()))
+        .collect();
+
+    let (low, high) = (CONFIG.low, CONFIG.high);
+    let cycle = [
+        (low, true),
+        (low, false),
+        (low, false),
+        (high, true),
+        (high, false),
+        (high, false),
+    ];
+    let expected: Vec<_> = cycle.iter().cycle().take(seen.len()).copied().collect();
+    assert_eq!(seen, expected);
+    let state = mpu.range_interleave().unwrap();
+    assert_eq!((state.at_high, state.in_phase), (false, 1));
+}
+
+#[test]
+fn merge_prefers_the_low_range_until_it_clips() {
+    let (low, high) = (AccelRange::G2, AccelRange::G16);
+    // quiet signal: every axis from the low range
+    let window = [
+        tagged(0.5, low),
+        tagged(0.75, high),
+        tagged(1., low),
+        tagged(0.25, high),
+        tagged(0.5, low),
+    ];
+    let merged = merge_interleaved(&window).unwrap();
+    assert_eq!(merged.sample.acc(), Vec3A::new(1., 0., 1.));
+    assert_eq!(merged.sources, [AxisSource::Measured(low); 3]);
+    assert!(!merged.clipped);
+    assert_eq!(merged.sample.accel_range(), None);
+
+    // the centre sample clipped, the high range samples around it interpolated
+    let window = [
+        tagged(1.5, low),
+        tagged(4., high),
+        tagged(2., low),
+        tagged(12., high),
+        tagged(1., low),
+    ];
+    let merged = merge_interleaved(&window).unwrap();
+    assert_eq!(merged.sample.acc().x, 8.);
+    assert_eq!(merged.sources[0], AxisSource::Interpolated(high));
+    assert_eq!(merged.sources[1], AxisSource::Measured(low));
+    assert!(!merged.clipped);
+
+    // a low range neighbour clipped, the high range centre wins
+    let window = [tagged(-2., low), tagged(-3., high), tagged(0.5, low)];
+    let merged = merge_interleaved(&window).unwrap();
+    assert_eq!(merged.sample.acc().x, -3.);
+    assert_eq!(merged.sources[0], AxisSource::Measured(high));
+
+    // clipped in both ranges
+    let window = [tagged(2., low), tagged(16., high), tagged(2., low)];
+    let merged = merge_interleaved(&window).unwrap();
+    assert!(merged.clipped);
+    assert!(merged.to_string().ends_with(", clipped"));
+}
+
+#[test]
+fn merge_holds_at_the_window_edges_and_skips_settling() {
+    let (low, high) = (AccelRange::G4, AccelRange::G8);
+    let window = [
+        tagged(7., high),
+        tagged(0.2, low).with_settling(true),
+        tagged(0.3, high),
+        tagged(0.4, low),
+        tagged(0.6, low),
+    ];
+    let merged = merge_interleaved(&window).unwrap();
+    assert_eq!(merged.sample.acc().x, 0.4);
+    assert_eq!(merged.sources[0], AxisSource::Held(low));
+
+    // untagged and settling samples are not usable
+    let untagged = MpuSample::new(Vec3A::ONE, Vec3A::ZERO, 25.);
+    assert_eq!(merge_interleaved(&[untagged, untagged]), None);
+    assert_eq!(merge_interleaved(&[]), None);
+    let merged = merge_interleaved(&[untagged, tagged(0.1, high)]).unwrap();
+    assert_eq!(merged.sources, [AxisSource::Measured(high); 3]);
+}
+
+#[test]
+fn invalid_configurations_are_refused_before_the_bus() {
+    let bus = SharedBus::new(&[ADDR]);
+    let mut mpu = Mpu6050Builder::new().i2c(bus.clone()).build().unwrap();
+    bus.take_log();
+    let short = RangeInterleave {
+        period: 1,
+        ..CONFIG
+    };
+    assert!(matches!(
+        mpu.enable_range_interleaving(short),
+        Err(Mpu6050Error::InvalidSettings(
+            SettingsError::InterleavePeriod { period: 1, min: 2 }
+        ))
+    ));
+    let reversed = RangeInterleave {
+        low: AccelRange::G8,
+        high: AccelRange::G4,
+        ..CONFIG
+    };
+    assert!(matches!(
+        mpu.enable_range_interleaving(reversed),
+        Err(Mpu6050Error::InvalidSettings(
+            SettingsError::InterleaveRanges(AccelRange::G8, AccelRange::G4)
+        ))
+    ));
+    assert_eq!(
+        RangeInterleave {
+            high: AccelRange::G2,
+            ..CONFIG
+        }
+        .validate(),
+        Err(SettingsError::InterleaveRanges(
+            AccelRange::G2,
+            AccelRange::G2
+        ))
+    );
+    assert!(bus.take_log().is_empty());
+    assert_eq!(mpu.range_interleave(), None);
+}
+
+#[test]
+fn rates_and_policy_restore() {
+    let rates = CONFIG.rates(1000.);
+    assert_eq!(rates.switches_hz, 1000. / 3.);
+    assert_eq!(rates.combined_hz, 2000. / 3.);
+    assert_eq!(rates.per_range_hz, 1000. / 3.);
+    assert_eq!(rates.switch_transactions_hz, 2000. / 3.);
+
+    let mut mpu = Mpu6050Builder::new()
+        .i2c(RegisterMock::new())
+        .build()
+        .unwrap();
+    mpu.init(&mut NoDelay).unwrap();
+    mpu.set_dlpf(1).unwrap();
+    mpu.set_sample_rate_divider(9).unwrap();
+    let policy = SettlingPolicy::Discard { max_retries: 2 };
+    mpu.set_settling_policy(policy);
+    assert_eq!(mpu.range_interleave_rates(), None);
+
+    mpu.enable_range_interleaving(CONFIG).unwrap();
+    assert_eq!(mpu.get_settling_policy(), SettlingPolicy::Flag);
+    assert_eq!(mpu.range_interleave_rates(), Some(CONFIG.rates(100.)));
+    // enabling again keeps the policy to restore
+    mpu.enable_range_interleaving(CONFIG).unwrap();
+    mpu.disable_range_interleaving(AccelRange::G4).unwrap();
+    assert_eq!(mpu.get_settling_policy(), policy);
+    assert_eq!(mpu.get_accel_range().unwrap(), AccelRange::G4);
+    assert_eq!(mpu.range_interleave(), None);
+    assert_eq!(sample(&mut mpu, 1)[0].accel_range(), None);
+
+    mpu.enable_range_interleaving(CONFIG).unwrap();
+    mpu.reset_device(&mut NoDelay).unwrap();
+    assert_eq!(mpu.get_settling_policy(), policy);
+    assert_eq!(mpu.range_interleave(), None);
+}
@@ -0,0 +1,218 @@
+//! `InterpolatingBuffer` against analytic inputs: linear ramps, queries on entries, the span
+//! and extrapolation window, and the timestamp ordering rules, see the `interpolation` module.
+
+mod common;
+
+use core::f32::consts::FRAC_PI_2;
+
+use glam::Quat;
+use mpu6050::device::DEFAULT_SLAVE_ADDR;
+use mpu6050::interpolation::{interpolate, InterpolatingBuffer, TimedSample, TimestampError};
+use mpu6050::provenance::{Provenance, SampleProvenance};
+use mpu6050::{Mpu6050Builder, Mpu6050Error, MpuSample, Vec3A};
+
+use common::SharedBus;
+
+/// accel x, gyro z and temperature rising linearly with time
+fn ramp(t_us: u64) -> TimedSample {
+    let t = t_us as f32 / 1000.;
+    TimedSample {
+        t_us,
+        sample: MpuSample::new(
+            Vec3A::new(t, -2. * t, 1.),
+            Vec3A::new(0., 0., 0.5 * t),
+            20. + t,
+        ),
+        orientation: None,
+    }
+}
+
+fn ramp_buffer<const N: usize>(stamps: &[u64]) -> InterpolatingBuffer<N> {
+    let mut buffer = InterpolatingBuffer::new();
+    for &t_us in stamps {
+        buffer.push(ramp(t_us)).unwrap();
+    }
+    buffer
+}
+
+#[test]
+fn linear_ramp_is_recovered_exactly() {
+    let buffer = ramp_buffer::<4>(&[0, 1000, 2000, 4000]);
+    // dyadic fractions of the spans, exact in f32
+    for t_us in [250, 500, 1500, 1750, 2500, 3000, 3500] {
+        let expected = ramp(t_us).sample;
+        let got = buffer.sample_at(t_us).unwrap();
+        assert_eq!(got.acc, expected.acc(), "{t_us}");
+        assert_eq!(got.gyro, expected.gyro(), "{t_us}");
+        assert_eq!(got.temp, expected.temp(), "{t_us}");
+        assert!(!got.extrapolated);
+        assert_eq!(
+            got.provenance,
+            SampleProvenance::uniform(Provenance::Interpolated)
+        );
+    }
+    // any other point on the line to rounding
+    let got = buffer.sample_at(1333).unwrap();
+    assert!((got.acc.x - 1.333).abs() < 1e-6);
+    assert!((got.temp - 21.333).abs() < 1e-5);
+}
+
+#[test]
+fn query_on_an_entry_returns_it_bit_for_bit() {
+    let odd = TimedSample {
+        t_us: 1000,
+        sample: MpuSample::new(Vec3A::new(0.1, 0.2, 0.3), Vec3A::new(-1e-7, 3.3, 7.1), 36.6)
+            .with_provenance(SampleProvenance::uniform(Provenance::held(2))),
+        orientation: Some(Quat::from_rotation_x(0.3)),
+    };
+    let mut buffer = InterpolatingBuffer::<4>::new();
+    buffer.push(ramp(0)).unwrap();
+    buffer.push(odd).unwrap();
+    buffer.push(ramp(3000)).unwrap();
+
+    let got = buffer.sample_at(1000).unwrap();
+    assert_eq!(
+        got.acc.to_array().map(f32::to_bits),
+        [0.1, 0.2, 0.3f32].map(f32::to_bits)
+    );
+    assert_eq!(got.gyro, odd.sample.gyro());
+    assert_eq!(got.temp.to_bits(), 36.6f32.to_bits());
+    assert_eq!(got.orientation, odd.orientation);
+    // the entry's own tags, nothing interpolated
+    assert_eq!(got.provenance, odd.sample.provenance());
+    assert!(!got.extrapolated);
+
+    // the span ends are entries too
+    assert_eq!(buffer.sample_at(0).unwrap().acc, ramp(0).sample.acc());
+    assert_eq!(buffer.sample_at(3000).unwrap().acc, ramp(3000).sample.acc());
+    assert_eq!(interpolate(&odd, &ramp(3000), 1000).acc, odd.sample.acc());
+}
+
+#[test]
+fn outside_the_span_is_none_without_a_window() {
+    let buffer = ramp_buffer::<4>(&[1000, 2000]);
+    assert_eq!(buffer.span(), Some((1000, 2000)));
+    assert_eq!(buffer.sample_at(999), None);
+    assert_eq!(buffer.sample_at(2001), None);
+    assert_eq!(InterpolatingBuffer::<4>::new().sample_at(0), None);
+}
+
+#[test]
+fn extrapolation_window_is_flagged_and_synthesized() {
+    let mut buffer = ramp_buffer::<4>(&[1000, 2000, 3000]);
+    buffer.set_extrapolation_window(500);
+
+    // continues the newest and the oldest pair's line
+    let after = buffer.sample_at(3500).unwrap();
+    assert!(after.extrapolated);
+    assert_eq!(after.acc, ramp(3500).sample.acc());
+    assert_eq!(
+        after.provenance,
+        SampleProvenance::uniform(Provenance::Synthesized)
+    );
+    let before = buffer.sample_at(500).unwrap();
+    assert!(before.extrapolated);
+    assert_eq!(before.acc, ramp(500).sample.acc());
+
+    assert_eq!(buffer.sample_at(3501), None);
+    assert_eq!(buffer.sample_at(499), None);
+    // inside the span nothing is flagged
+    assert!(!buffer.sample_at(2500).unwrap().extrapolated);
+}
+
+#[test]
+fn single_entry_extrapolates_as_a_constant() {
+    let mut buffer = ramp_buffer::<4>(&[1000]);
+    buffer.set_extrapolation_window(100);
+    let got = buffer.sample_at(1100).unwrap();
+    assert!(got.extrapolated);
+    assert_eq!(got.acc, ramp(1000).sample.acc());
+    assert!(!buffer.sample_at(1000).unwrap().extrapolated);
+}
+
+#[test]
+fn stalled_and_backwards_timestamps_are_rejected() {
+    let mut buffer = ramp_buffer::<4>(&[1000, 2000]);
+    assert_eq!(
+        buffer.push(ramp(2000)),
+        Err(TimestampError::Duplicate { t_us: 2000 })
+    );
+    assert_eq!(
+        buffer.push(ramp(1500)),
+        Err(TimestampError::OutOfOrder {
+            newest_us: 2000,
+            t_us: 1500
+        })
+    );
+    // unchanged
+    assert_eq!(buffer.len(), 2);
+    assert_eq!(buffer.span(), Some((1000, 2000)));
+    assert_eq!(buffer.sample_at(1500).unwrap().acc, ramp(1500).sample.acc());
+
+    assert!(buffer.push(ramp(2001)).is_ok());
+    buffer.clear();
+    // an empty buffer accepts any timestamp
+    assert!(buffer.push(ramp(0)).is_ok());
+}
+
+#[test]
+fn full_buffer_drops_the_oldest() {
+    let buffer = ramp_buffer::<3>(&[0, 1000, 2000, 3000, 4000]);
+    assert_eq!(buffer.len(), 3);
+    assert_eq!(buffer.span(), Some((2000, 4000)));
+    assert_eq!(buffer.sample_at(1500), None);
+    assert_eq!(buffer.sample_at(2500).unwrap().acc, ramp(2500).sample.acc());
+
+    let empty = ramp_buffer::<0>(&[0, 1000]);
+    assert!(empty.is_empty());
+    assert_eq!(empty.sample_at(0), None);
+}
+
+#[test]
+fn orientations_are_slerped_when_both_neighbours_have_one() {
+    let with = |t_us, q| TimedSample {
+        orientation: q,
+        ..ramp(t_us)
+    };
+    let mut buffer = InterpolatingBuffer::<4>::new();
+    buffer.push(with(0, Some(Quat::IDENTITY))).unwrap();
+    buffer
+        .push(with(1000, Some(Quat::from_rotation_z(FRAC_PI_2))))
+        .unwrap();
+    buffer.push(with(2000, None)).unwrap();
+
+    let half = buffer.sample_at(500).unwrap().orientation.unwrap();
+    assert!(half.angle_between(Quat::from_rotation_z(FRAC_PI_2 / 2.)) < 1e-5);
+    let quarter = buffer.sample_at(250).unwrap().orientation.unwrap();
+    assert!(quarter.angle_between(Quat::from_rotation_z(FRAC_PI_2 / 4.)) < 1e-5);
+    // one neighbour without
+    assert_eq!(buffer.sample_at(1500).unwrap().orientation, None);
+}
+
+#[test]
+fn driver_checks_the_timestamp_before_reading() {
+    let bus = SharedBus::new(&[DEFAULT_SLAVE_ADDR]);
+    let mut mpu = Mpu6050Builder::new().i2c(bus.clone()).build().unwrap();
+    let mut buffer = InterpolatingBuffer::<8>::new();
+
+    let sample = mpu
+        .sample_into_interp_buffer(&mut buffer, 1000, None)
+        .unwrap();
+    assert_eq!(buffer.sample_at(1000).unwrap().acc, sample.acc());
+    bus.take_log();
+
+    assert!(matches!(
+        mpu.sample_into_interp_buffer(&mut buffer, 1000, None),
+        Err(Mpu6050Error::InvalidTimestamp(TimestampError::Duplicate {
+            t_us: 1000
+        }))
+    ));
+    assert!(matches!(
+        mpu.sample_into_interp_buffer(&mut buffer, 10, None),
+        Err(Mpu6050Error::InvalidTimestamp(
+            TimestampError::OutOfOrder { .. }
+        ))
+    ));
+    assert!(bus.take_log().is_empty());
+    assert_eq!(buffer.len(), 1);
+}
@@ -0,0 +1,137 @@
+//! Rising-edge detection on INT_STATUS: the tracker through the tricky interleavings, and
+//! `poll_interrupt_events` on a mock register file, see the `interrupt` module.
+
+mod common;
+
+use mpu6050::device::{DEFAULT_SLAVE_ADDR, INT_STATUS};
+use mpu6050::interrupt::{InterruptEdgeTracker, InterruptSet, InterruptSource};
+use mpu6050::Mpu6050Builder;
+
+use common::{Access, SharedBus};
+
+const MOTION: u8 = 1 << INT_STATUS::MOT_INT;
+const DATA_READY: u8 = 1 << INT_STATUS::DATA_RDY_INT;
+const FREE_FALL: u8 = 1 << INT_STATUS::FF_INT;
+
+fn sources(set: InterruptSet) -> Vec<InterruptSource> {
+    set.iter().collect()
+}
+
+#[test]
+fn continuous_assertion_fires_once() {
+    let mut tracker = InterruptEdgeTracker::new();
+    let first = tracker.update(MOTION);
+    assert_eq!(sources(first.fired), [InterruptSource::Motion]);
+    for _ in 0..3 {
+        let events = tracker.update(MOTION);
+        assert!(events.fired.is_empty());
+        assert_eq!(sources(events.asserted), [InterruptSource::Motion]);
+    }
+    assert_eq!(tracker.count(InterruptSource::Motion), 1);
+}
+
+#[test]
+fn fire_and_clear_between_polls_counts() {
+    // latched, gone before the next poll, latched again: two events
+    let mut tracker = InterruptEdgeTracker::new();
+    assert!(tracker
+        .update(MOTION)
+        .fired
+        .contains(InterruptSource::Motion));
+    let cleared = tracker.update(0);
+    assert!(cleared.fired.is_empty() && cleared.asserted.is_empty());
+    assert!(tracker
+        .update(MOTION)
+        .fired
+        .contains(InterruptSource::Motion));
+    assert_eq!(tracker.count(InterruptSource::Motion), 2);
+}
+
+#[test]
+fn multiple_sources_in_one_read() {
+    let mut tracker = InterruptEdgeTracker::new();
+    tracker.update(DATA_READY);
+    // data ready continues, motion and free fall are new
+    let events = tracker.update(DATA_READY | MOTION | FREE_FALL);
+    assert_eq!(
+        sources(events.fired),
+        [InterruptSource::FreeFall, InterruptSource::Motion]
+    );
+    assert_eq!(
+        sources(events.asserted),
+        [
+            InterruptSource::FreeFall,
+            InterruptSource::Motion,
+            InterruptSource::DataReady
+        ]
+    );
+    // motion goes, free fall stays, data ready drops out for one poll and comes back
+    let events = tracker.update(FREE_FALL);
+    assert!(events.fired.is_empty());
+    let events = tracker.update(FREE_FALL | DATA_READY);
+    assert_eq!(sources(events.fired), [InterruptSource::DataReady]);
+
+    assert_eq!(tracker.count(InterruptSource::DataReady), 2);
+    assert_eq!(tracker.count(InterruptSource::Motion), 1);
+    assert_eq!(tracker.count(InterruptSource::FreeFall), 1);
+    assert_eq!(tracker.count(InterruptSource::ZeroMotion), 0);
+}
+
+#[test]
+fn unknown_bits_are_dropped() {
+    let mut tracker = InterruptEdgeTracker::new();
+    // bits 1 and 2 are reserved
+    let events = tracker.update(0b0000_0110);
+    assert!(events.fired.is_empty() && events.asserted.is_empty());
+}
+
+#[test]
+fn reset_forgets_state_and_counts() {
+    let mut tracker = InterruptEdgeTracker::new();
+    tracker.update(MOTION);
+    tracker.reset();
+    assert_eq!(tracker.count(InterruptSource::Motion), 0);
+    // still asserted, but new to the reset tracker
+    assert!(tracker
+        .update(MOTION)
+        .fired
+        .contains(InterruptSource::Motion));
+}
+
+#[test]
+fn driver_reads_int_status_once_per_poll() {
+    let bus = SharedBus::new(&[DEFAULT_SLAVE_ADDR]);
+    let mut mpu = Mpu6050Builder::new().i2c(bus.clone()).build().unwrap();
+    let status = |value| {
+        bus.device(DEFAULT_SLAVE_ADDR, |mock| {
+            mock.regs[INT_STATUS::ADDR as usize] = value
+        })
+    };
+
+    status(MOTION);
+    bus.take_log();
+    let events = mpu.poll_interrupt_events().unwrap();
+    assert_eq!(
+        bus.take_log(),
+        [Access {
+            address: DEFAULT_SLAVE_ADDR,
+            reg: INT_STATUS::ADDR,
+            read: true,
+        }]
+    );
+    assert_eq!(sources(events.fired), [InterruptSource::Motion]);
+
+    assert!(mpu.poll_interrupt_events().unwrap().fired.is_empty());
+    status(0);
+    mpu.poll_interrupt_events().unwrap();
+    status(MOTION | DATA_READY);
+    let events = mpu.poll_interrupt_events().unwrap();
+    assert_eq!(
+        sources(events.fired),
+        [InterruptSource::Motion, InterruptSource::DataReady]
+    );
+    assert_eq!(mpu.interrupt_tracker().count(InterruptSource::Motion), 2);
+
+    mpu.reset_interrupt_tracker();
+    assert_eq!(mpu.interrupt_tracker().count(InterruptSource::Motion), 0);
+}
@@ -0,0 +1,246 @@
+//! Lever-arm compensation: the rigid body terms against steady circular motion and a constant
+//! angular acceleration, the compensator's α filter, and the driver's sample path down to
+//! the fusion filter, see the `lever_arm` module.
+
+mod common;
+
+use mpu6050::device::*;
+use mpu6050::lever_arm::*;
+use mpu6050::orientation::ComplementaryFilter;
+use mpu6050::sampling::SampleControl;
+use mpu6050::synthetic::frame_bytes;
+use mpu6050::*;
+
+use common::{NoDelay, SharedBus};
+
+const ADDR: u8 = DEFAULT_SLAVE_ADDR;
+
+#[test]
+fn steady_circular_motion_leaves_no_lateral_residual() {
+    // the reference point on the turn axis, level, the sensor `radius` out along each axis
+    for (omega, radius) in [(1., 0.05), (PI, 0.5), (10., 0.02), (-4., 0.3)] {
+        let gyro = Vec3A::new(0., 0., omega);
+        for r in [
+            Vec3A::new(radius, 0., 0.),
+            Vec3A::new(0., -radius, 0.),
+            Vec3A::new(radius, radius, 0.2),
+        ] {
+            // centripetal, toward the axis, omega² times the distance from it
+            let off_axis = Vec3A::new(r.x, r.y, 0.);
+            let centripetal = -off_axis * omega * omega / STANDARD_GRAVITY;
+            let term = rigid_body_accel_g(r, gyro, Vec3A::ZERO);
+            assert!((term - centripetal).length() < 1e-6, "{} {}", omega, r);
+
+            let measured = Vec3A::Z + centripetal;
+            let residual = compensate(measured, r, gyro, Vec3A::ZERO) - Vec3A::Z;
+            assert!(residual.length() < 1e-6, "{} {}: {}", omega, r, residual);
+        }
+    }
+
+    // the tangential term of an angular acceleration about z at r along x points along y
+    let r = Vec3A::new(0.2, 0., 0.);
+    let term = rigid_body_accel_g(r, Vec3A::ZERO, Vec3A::new(0., 0., 49.0332));
+    assert!((term - Vec3A::new(0., 1., 0.)).length() < 1e-5);
+}
+
+#[test]
+fn zero_lever_arm_is_bit_identical() {
+    let acc = Vec3A::new(0.1234567, -0.7654321, 1.0000001);
+    for gyro in [Vec3A::ZERO, Vec3A::splat(3.3), Vec3A::new(1e9, -1e9, 7.)] {
+        let out = compensate(acc, Vec3A::ZERO, gyro, Vec3A::splat(1e5));
+        assert_eq!(
+            out.to_array().map(f32::to_bits),
+            acc.to_array().map(f32::to_bits)
+        );
+    }
+
+    let mut compensator = LeverArmCompensator::new(LeverArmCompensation::default());
+    for k in 0..10 {
+        let sample = MpuSample::new(acc, Vec3A::splat(k as f32), 25.);
+        let out = compensator.compensate(sample, Vec3A::ZERO, 1e-3);
+        assert_eq!(out, sample);
+        assert_eq!(out.lever_arm_correction(), None);
+        assert_eq!(out.uncompensated_acc(), acc);
+    }
+
+    // the driver with the compensation on and no lever arm returns the same samples
+    let (mut plain, plain_bus) = driver();
+    let (mut compensated, compensated_bus) = driver();
+    compensated.set_lever_arm_compensation(Some(LeverArmCompensation::default()));
+    assert!(!compensated.lever_arm_compensation_active());
+    let ramp = |k: u32| Vec3A::new(10. * k as f32, -3. * k as f32, 45.);
+    assert_eq!(
+        sample_frames(&mut plain, &plain_bus, 8, ramp),
+        sample_frames(&mut compensated, &compensated_bus, 8, ramp)
+    );
+}
+
+#[test]
+fn compensator_filters_the_angular_acceleration() {
+    let dt = 1e-3;
+    let alpha = 20.;
+    let config = LeverArmCompensation::default();
+    let r = Vec3A::new(0.1, 0., 0.);
+    let mut compensator = LeverArmCompensator::new(config);
+
+    // a spin-up at a constant α about z: the raw difference is exact, the filter converges
+    for k in 0..400 {
+        let omega = alpha * k as f32 * dt;
+        let gyro = Vec3A::new(0., 0., omega);
+        // centripetal along -x, tangential along +y
+        let measured =
+            Vec3A::new(-omega * omega * r.x, alpha * r.x, STANDARD_GRAVITY) / STANDARD_GRAVITY;
+        let out = compensator.compensate(MpuSample::new(measured, gyro, 25.), r, dt);
+        if k == 0 {
+            assert_eq!(compensator.alpha(), Vec3A::ZERO);
+        }
+        if k >= 300 {
+            assert!((compensator.alpha().z - alpha).abs() < 1e-3, "{}", k);
+            assert!(
+                (out.acc() - Vec3A::Z).length() < 1e-5,
+                "{}: {}",
+                k,
+                out.acc()
+            );
+        }
+        assert!((out.uncompensated_acc() - measured).length() < 1e-6);
+        let term = out.lever_arm_correction().unwrap();
+        assert!((out.acc() + term - measured).length() < 1e-6);
+    }
+
+    // one sample of the first order filter
+    let gain = 1. - (-2. * PI * DEFAULT_ALPHA_CUTOFF_HZ * dt).exp();
+    compensator.reset();
+    compensator.compensate(MpuSample::new(Vec3A::Z, Vec3A::ZERO, 25.), r, dt);
+    compensator.compensate(
+        MpuSample::new(Vec3A::Z, Vec3A::new(0., 0., 0.01), 25.),
+        r,
+        dt,
+    );
+    assert!((compensator.alpha().z - 10. * gain).abs() < 1e-4);
+
+    // a gyro glitch is clamped
+    compensator.compensate(
+        MpuSample::new(Vec3A::Z, Vec3A::new(0., 0., 1e4), 25.),
+        r,
+        dt,
+    );
+    assert_eq!(compensator.alpha().z, DEFAULT_MAX_ALPHA);
+
+    // a cutoff of zero drops the α term
+    let centripetal_only = config.with_alpha_cutoff_hz(0.);
+    let mut compensator = LeverArmCompensator::new(centripetal_only);
+    for k in 0..5 {
+        let gyro = Vec3A::new(0., 0., k as f32);
+        compensator.compensate(MpuSample::new(Vec3A::Z, gyro, 25.), r, dt);
+        assert_eq!(compensator.alpha(), Vec3A::ZERO);
+    }
+
+    // the builders ignore nonsense
+    let kept = config.with_alpha_cutoff_hz(f32::NAN).with_max_alpha(-1.);
+    assert_eq!(kept, config);
+}
+
+#[test]
+fn sample_path_compensates_and_fusion_reads_the_corrected_accel() {
+    let (mut mpu, bus) = driver();
+    // half a meter out along x, spinning at 180 °/s about z: about 0.5 g toward the axis
+    let r = Vec3A::new(0.5, 0., 0.);
+    let omega = PI;
+    let centripetal = -omega * omega * r.x / STANDARD_GRAVITY;
+    mpu.set_lever_arm(r);
+    mpu.set_lever_arm(Vec3A::splat(f32::NAN));
+    assert_eq!(mpu.lever_arm(), r);
+    assert!(!mpu.lever_arm_compensation_active());
+    mpu.set_lever_arm_compensation(Some(LeverArmCompensation::default()));
+    assert!(mpu.lever_arm_compensation_active());
+    assert_eq!(
+        mpu.lever_arm_compensation(),
+        Some(LeverArmCompensation::default())
+    );
+    assert_eq!(mpu.debug_state().lever_arm, r);
+
+    let acc = Vec3A::new(centripetal, 0., 1.);
+    let samples = sample_frames_with(&mut mpu, &bus, 20, |_| {
+        frame_bytes(
+            acc,
+            Vec3A::new(0., 0., 180.),
+            AccelRange::G2,
+            GyroRange::D250,
+        )
+    });
+    let gated = ComplementaryFilter::new(0.98).with_accel_gate(Some(0.1));
+    let (mut compensated, mut raw) = (gated, gated);
+    for sample in &samples {
+        assert!(
+            (sample.acc() - Vec3A::Z).length() < 2e-4,
+            "{}",
+            sample.acc()
+        );
+        assert!((sample.uncompensated_acc() - acc).length() < 2e-4);
+        assert!(sample.lever_arm_correction().is_some());
+        assert!(sample.provenance().is_measured());
+
+        compensated.update_sample(sample, 0.01);
+        assert!(!compensated.estimate().accel_gated);
+        raw.update_sample(&sample.with_acc(sample.uncompensated_acc()), 0.01);
+        assert!(raw.estimate().accel_gated);
+    }
+
+    // off: the reading as is
+    mpu.set_lever_arm_compensation(None);
+    let samples = sample_frames_with(&mut mpu, &bus, 2, |_| {
+        frame_bytes(
+            acc,
+            Vec3A::new(0., 0., 180.),
+            AccelRange::G2,
+            GyroRange::D250,
+        )
+    });
+    assert!((samples[1].acc() - acc).length() < 2e-4);
+    assert_eq!(samples[1].lever_arm_correction(), None);
+}
+
+fn driver() -> (Mpu6050<SharedBus>, SharedBus) {
+    let bus = SharedBus::new(&[ADDR]);
+    let mut mpu = Mpu6050Builder::new().i2c(bus.clone()).build().unwrap();
+    mpu.init(&mut NoDelay).unwrap();
+    (mpu, bus)
+}
+
+/// `samples` samples of the sampling loop at rest, the gyro in °/s given per sample
+fn sample_frames(
+    mpu: &mut Mpu6050<SharedBus>,
+    bus: &SharedBus,
+    samples: u32,
+    gyro_dps: impl Fn(u32) -> Vec3A,
+) -> Vec<MpuSample> {
+    sample_frames_with(mpu, bus, samples, |k| {
+        frame_bytes(Vec3A::Z, gyro_dps(k), AccelRange::G2, GyroRange::D250)
+    })
+}
+
+/// `samples` samples of the sampling loop, the frame given per sample
+fn sample_frames_with(
+    mpu: &mut Mpu6050<SharedBus>,
+    bus: &SharedBus,
+    samples: u32,
+    frame: impl Fn(u32) -> [u8; 14],
+) -> Vec<MpuSample> {
+    bus.device(ADDR, |m| m.set_frame(&frame(0)));
+    let mut out = Vec::new();
+    mpu.run_sampling_loop(
+        || Ok::<(), ()>(()),
+        |sample, _| {
+            out.push(sample);
+            let k = out.len() as u32;
+            bus.device(ADDR, |m| m.set_frame(&frame(k)));
+            match k < samples {
+                true => SampleControl::Continue,
+                false => SampleControl::Stop,
+            }
+        },
+    )
+    .unwrap();
+    out
+}
@@ -0,0 +1,191 @@
+//! Log header: round trips through the binary form, decoding without a driver, unknown
+//! fields, see the `log_header` module.
+
+mod common;
+
+use mpu6050::axis_map::AxisMap;
+use mpu6050::device::*;
+use mpu6050::frame::parse_frame;
+use mpu6050::log_header::*;
+use mpu6050::packed::{crc16, DecodeError, EncodeError};
+use mpu6050::resolution::ResolutionInfo;
+use mpu6050::*;
+
+use common::{NoDelay, RegisterMock};
+
+const FRAME: [u8; 14] = [
+    0x10, 0x00, 0xf0, 0x00, 0x20, 0x00, 0x01, 0x00, 0x00, 0x83, 0xff, 0x7d, 0x12, 0x34,
+];
+
+fn configured() -> Mpu6050<RegisterMock> {
+    let mut mock = RegisterMock::new();
+    mock.set_frame(&FRAME);
+    let mut mpu = Mpu6050Builder::new()
+        .i2c(mock)
+        .acc_offset(Vec3A::new(0.01, -0.02, 0.03))
+        .gyro_offset(Vec3A::new(-0.004, 0.005, 0.))
+        .acc_scale_factors(Vec3A::new(1.01, 0.99, 1.))
+        .build()
+        .unwrap();
+    mpu.init(&mut NoDelay).unwrap();
+    mpu.set_accel_range(AccelRange::G8).unwrap();
+    mpu.set_gyro_range(GyroRange::D1000).unwrap();
+    mpu.set_dlpf(3).unwrap();
+    mpu.set_sample_rate_divider(9).unwrap();
+    mpu
+}
+
+/// the header with an extra field of `tag` spliced in before the CRC
+fn with_field(encoded: &[u8], tag: u8, payload: &[u8]) -> Vec<u8> {
+    let mut out = encoded[..encoded.len() - 2].to_vec();
+    out.extend([tag, payload.len() as u8]);
+    out.extend(payload);
+    let len = (out.len() + 2) as u16;
+    out[1..3].copy_from_slice(&len.to_le_bytes());
+    let crc = crc16(&out);
+    out.extend(crc.to_le_bytes());
+    out
+}
+
+#[test]
+fn driver_header_round_trips() {
+    let mut mpu = configured();
+    let header = mpu
+        .build_log_header()
+        .unwrap()
+        .with_axis_map(AxisMap::new(
+            [Axis::Y, Axis::X, Axis::Z],
+            [false, true, false],
+        ))
+        .with_calibration(CalibrationNote {
+            method: CalibrationMethod::Referenced,
+            timestamp: Some(1_760_000_000),
+        });
+    assert_eq!(header.driver_version, env!("CARGO_PKG_VERSION"));
+    assert_eq!(header.variant(), ChipVariant::Mpu6050);
+    assert_eq!(header.config(), mpu.read_config().unwrap());
+    assert_eq!(header.pipeline, mpu.pipeline());
+    assert_eq!((header.dlpf_cfg, header.sample_rate_div), (3, 9));
+    assert_eq!(header.odr_hz(), 100.);
+    assert_eq!(
+        header.resolution(),
+        ResolutionInfo::new(AccelRange::G8, GyroRange::D1000, 3)
+    );
+
+    let mut buf = [0; 512];
+    let len = header.encode(&mut buf).unwrap();
+    assert_eq!(len, header.encoded_len());
+    // trailing log data is not part of the header
+    buf[len..len + 4].copy_from_slice(&[0xde, 0xad, 0xbe, 0xef]);
+    let (decoded, decoded_len) = LogHeader::decode(&buf[..len + 4]).unwrap();
+    assert_eq!(decoded_len, len);
+    assert_eq!(decoded, header);
+}
+
+#[test]
+fn scaling_is_reconstructed_from_the_header_alone() {
+    let mut mpu = configured();
+    let mut buf = [0; 512];
+    let len = mpu.build_log_header().unwrap().encode(&mut buf).unwrap();
+    let acc = mpu.get_acc().unwrap();
+    let gyro = mpu.get_gyro().unwrap();
+    let temp = mpu.get_temp().unwrap();
+
+    let (header, _) = LogHeader::decode(&buf[..len]).unwrap();
+    let sample = header.pipeline.sample(&parse_frame(&FRAME));
+    assert_eq!(sample.acc(), acc);
+    assert_eq!(sample.gyro(), gyro);
+    assert_eq!(sample.temp(), temp);
+}
+
+#[test]
+fn unknown_fields_are_skipped() {
+    let header =
+        LogHeader::new(0x70, AccelRange::G2, GyroRange::D250).with_calibration(CalibrationNote {
+            method: CalibrationMethod::Static,
+            timestamp: None,
+        });
+    let mut buf = [0; 256];
+    let len = header.encode(&mut buf).unwrap();
+    let extended = with_field(&buf[..len], 0xc0, &[1, 2, 3, 4, 5]);
+    let extended = with_field(&extended, 0x41, &[]);
+    assert_eq!(LogHeader::decode(&extended), Ok((header, extended.len())));
+}
+
+#[test]
+fn optional_fields_default() {
+    let header = LogHeader::new(0x68, AccelRange::G16, GyroRange::D2000);
+    let mut buf = [0; 256];
+    let len = header.encode(&mut buf).unwrap();
+    let (decoded, _) = LogHeader::decode(&buf[..len]).unwrap();
+    assert!(decoded.registers.is_empty());
+    assert_eq!(decoded.axis_map, AxisMap::IDENTITY);
+    assert_eq!(decoded.calibration, None);
+    assert_eq!(decoded.cycle, None);
+}
+
+#[test]
+fn corrupt_headers_are_rejected() {
+    let header = LogHeader::new(0x68, AccelRange::G2, GyroRange::D250);
+    let mut buf = [0; 256];
+    let len = header.encode(&mut buf).unwrap();
+    assert_eq!(
+        header.encode(&mut [0; 16]),
+        Err(EncodeError::BufferTooSmall(len))
+    );
+    assert_eq!(
+        LogHeader::decode(&buf[..len - 1]),
+        Err(DecodeError::Truncated(len))
+    );
+
+    let mut flipped = buf;
+    flipped[10] ^= 0x01;
+    assert_eq!(
+        LogHeader::decode(&flipped[..len]),
+        Err(DecodeError::CrcMismatch)
+    );
+
+    let mut version = buf;
+    version[0] = LOG_HEADER_VERSION + 1;
+    assert_eq!(
+        LogHeader::decode(&version[..len]),
+        Err(DecodeError::BadHeader)
+    );
+
+    // a known field too short for its payload
+    assert_eq!(
+        LogHeader::decode(&with_field(&buf[..len], 3, &[0, 0])),
+        Err(DecodeError::BadHeader)
+    );
+
+    // a required field missing: the chip field is the 2nd, after the version string
+    let version_len = buf[4] as usize;
+    let chip = 3 + 2 + version_len;
+    let mut without_chip = buf[..chip].to_vec();
+    without_chip.extend(&buf[chip + 3..len - 2]);
+    let stripped_len = (without_chip.len() + 2) as u16;
+    without_chip[1..3].copy_from_slice(&stripped_len.to_le_bytes());
+    let crc = crc16(&without_chip);
+    without_chip.extend(crc.to_le_bytes());
+    assert_eq!(
+        LogHeader::decode(&without_chip),
+        Err(DecodeError::BadHeader)
+    );
+}
+
+#[test]
+fn comment_lines() {
+    let mut mpu = configured();
+    let header = mpu.build_log_header().unwrap();
+    let mut csv = String::new();
+    header.write_comments(&mut csv).unwrap();
+    csv.push_str("t,ax,ay,az\n");
+
+    let comments: Vec<&str> = csv.lines().take_while(|l| l.starts_with('#')).collect();
+    assert_eq!(comments.len(), header.to_string().lines().count());
+    assert!(comments.iter().all(|line| line.starts_with("# ")));
+    assert!(comments.contains(&format!("# driver: {}", DRIVER_VERSION).as_str()));
+    assert!(comments.contains(&"# ranges: G8, D1000"));
+    assert!(comments.contains(&"# rate: DLPF_CFG 3, SMPLRT_DIV 9, ODR 100 Hz"));
+    assert_eq!(csv.lines().nth(comments.len()), Some("t,ax,ay,az"));
+}
@@ -0,0 +1,200 @@
+//! Logging through the `log` backend: the documented records of scripted scenarios, a
+//! reconnect, mode transitions and a settling discard, and the transactions traced at each
+//! trace level, see the `logging` module. That no backend compiles the logging to nothing is
+//! checked by the build without one.
+
+mod common;
+
+use std::cell::RefCell;
+use std::sync::Once;
+
+use log::{Level, LevelFilter, Log, Metadata, Record};
+
+use mpu6050::chaos::{ChaosConfig, FlakyI2c};
+use mpu6050::device::AccelRange;
+use mpu6050::logging::*;
+use mpu6050::settling::SettlingPolicy;
+use mpu6050::*;
+
+use common::{NoDelay, RegisterMock};
+
+thread_local! {
+    static RECORDS: RefCell<Vec<(Level, String, String)>> = const { RefCell::new(Vec::new()) };
+}
+
+/// records of the logging thread, each test runs on its own
+struct Capture;
+
+impl Log for Capture {
+    fn enabled(&self, _: &Metadata) -> bool {
+        true
+    }
+
+    fn log(&self, record: &Record) {
+        let entry = (
+            record.level(),
+            record.target().to_string(),
+            record.args().to_string(),
+        );
+        RECORDS.with(|records| records.borrow_mut().push(entry));
+    }
+
+    fn flush(&self) {}
+}
+
+fn capture() {
+    static INSTALL: Once = Once::new();
+    INSTALL.call_once(|| {
+        log::set_logger(&Capture).unwrap();
+        log::set_max_level(LevelFilter::Trace);
+    });
+    RECORDS.with(|records| records.borrow_mut().clear());
+}
+
+/// the records since the last call
+fn take() -> Vec<(Level, String, String)> {
+    RECORDS.with(|records| records.borrow_mut().drain(..).collect())
+}
+
+fn driver() -> Mpu6050<RegisterMock> {
+    let mut mpu = Mpu6050Builder::new()
+        .i2c(RegisterMock::new())
+        .build()
+        .unwrap();
+    mpu.init(&mut NoDelay).unwrap();
+    mpu
+}
+
+fn at(records: &[(Level, String, String)], level: Level, target: &str) -> Vec<String> {
+    records
+        .iter()
+        .filter(|(l, t, _)| *l == level && t == target)
+        .map(|(_, _, message)| message.clone())
+        .collect()
+}
+
+#[test]
+fn reconnect_logs_the_loss_the_failure_and_the_recovery() {
+    capture();
+    let (i2c, chaos) = FlakyI2c::new(RegisterMock::new(), ChaosConfig::NONE);
+    let mut mpu = Mpu6050Builder::new().i2c(i2c).build().unwrap();
+    mpu.init(&mut NoDelay).unwrap();
+    let records = take();
+    assert_eq!(
+        at(&records, Level::Debug, INIT),
+        ["initialized, WHO_AM_I 0x68"]
+    );
+
+    chaos.fail_next(3);
+    for _ in 0..3 {
+        assert!(mpu.get_temp().is_err());
+    }
+    // the errors returned by the reads are the caller's, the loss of the chip is logged
+    assert_eq!(
+        take(),
+        [(Level::Error, CONNECTION.into(), "connection lost".into())]
+    );
+
+    // no metrics sink: the failure record is built for the log all the same
+    chaos.fail_next(1);
+    assert!(mpu.try_reconnect(&mut NoDelay).is_err());
+    let records = take();
+    let failures = at(&records, Level::Error, CONNECTION);
+    assert_eq!(failures.len(), 1);
+    assert!(
+        failures[0].starts_with("reconnect failed: i2c"),
+        "{}",
+        failures[0]
+    );
+
+    mpu.try_reconnect(&mut NoDelay).unwrap();
+    let records = take();
+    assert_eq!(
+        at(&records, Level::Warn, CONNECTION),
+        ["reconnected: SameChip { chip_id: 104 }"]
+    );
+    assert!(at(&records, Level::Error, CONNECTION).is_empty());
+}
+
+#[test]
+fn mode_transitions_are_logged_at_debug() {
+    capture();
+    let mut mpu = driver();
+    take();
+
+    mpu.set_sleep_enabled(true).unwrap();
+    mpu.set_sleep_enabled(false).unwrap();
+    mpu.set_accel_range(AccelRange::G8).unwrap();
+    let records = take();
+    assert_eq!(at(&records, Level::Debug, POWER), ["asleep", "awake"]);
+    let epoch = mpu.config_epoch();
+    assert_eq!(
+        at(&records, Level::Debug, CONFIG),
+        [format!(
+            "configuration changed: {}, epoch {}",
+            mpu.last_config_change().unwrap(),
+            epoch
+        )]
+    );
+    // nothing else: no warnings, no transactions while the trace level is off
+    assert_eq!(records.len(), 3);
+}
+
+#[test]
+fn settling_discard_is_logged_per_retry() {
+    capture();
+    let mut mpu = driver();
+    mpu.set_settling_policy(SettlingPolicy::Discard { max_retries: 2 });
+    take();
+
+    mpu.get_acc().unwrap();
+    let records = take();
+    assert_eq!(
+        at(&records, Level::Debug, SAMPLING),
+        ["Accel settling sample discarded, read again"; 2]
+    );
+    assert_eq!(records.len(), 2);
+}
+
+#[test]
+fn transactions_follow_the_trace_level() {
+    capture();
+    let (i2c, chaos) = FlakyI2c::new(RegisterMock::new(), ChaosConfig::NONE);
+    let mut mpu = Mpu6050Builder::new().i2c(i2c).build().unwrap();
+    mpu.init(&mut NoDelay).unwrap();
+    assert_eq!(mpu.trace_level(), TraceLevel::Off);
+    take();
+
+    mpu.get_temp().unwrap();
+    assert!(take().is_empty());
+
+    mpu.set_trace_level(TraceLevel::Transactions);
+    assert_eq!(mpu.debug_state().trace_level, TraceLevel::Transactions);
+    mpu.get_temp().unwrap();
+    mpu.set_sleep_enabled(true).unwrap();
+    let records = take();
+    // the read of TEMP_OUT, the read and the write of PWR_MGMT_1, addressing included
+    assert_eq!(
+        at(&records, Level::Trace, BUS),
+        [
+            "TEMP_OUT_H (0x41): 5 bytes on the bus, ok",
+            "PWR_MGMT_1 (0x6b): 4 bytes on the bus, ok",
+            "PWR_MGMT_1 (0x6b): 3 bytes on the bus, ok"
+        ]
+    );
+    assert_eq!(at(&records, Level::Debug, POWER), ["asleep"]);
+
+    // failures only
+    mpu.set_trace_level(TraceLevel::Failures);
+    mpu.get_temp().unwrap();
+    chaos.fail_next(1);
+    assert!(mpu.get_temp().is_err());
+    assert_eq!(
+        take(),
+        [(
+            Level::Trace,
+            BUS.into(),
+            "TEMP_OUT_H (0x41): 5 bytes on the bus, failed".into()
+        )]
+    );
+}
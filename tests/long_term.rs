@@ -0,0 +1,245 @@
+//! Long-term statistics: percentile estimates against exact order statistics on synthetic
+//! distributions within the documented bound, the channels, the fixed memory, persistence,
+//! and passive collection from the sample tap, see the `long_term` module.
+
+mod common;
+
+use mpu6050::device::*;
+use mpu6050::long_term::*;
+use mpu6050::packed::DecodeError;
+use mpu6050::settling::SettlingPolicy;
+use mpu6050::synthetic::frame_bytes;
+use mpu6050::*;
+
+use common::{NoDelay, SharedBus};
+
+const ADDR: u8 = DEFAULT_SLAVE_ADDR;
+const N: usize = 100_000;
+
+/// xorshift64*, the generator of the `chaos` module
+struct Noise(u64);
+
+impl Noise {
+    fn new(seed: u64) -> Self {
+        Self(seed ^ 0x9e37_79b9_7f4a_7c15)
+    }
+
+    /// uniform in 0..1
+    fn uniform(&mut self) -> f32 {
+        self.0 ^= self.0 >> 12;
+        self.0 ^= self.0 << 25;
+        self.0 ^= self.0 >> 27;
+        let x = self.0.wrapping_mul(0x2545_f491_4f6c_dd1d);
+        (x >> 40) as f32 / (1u64 << 24) as f32
+    }
+
+    /// standard normal, Box-Muller
+    fn normal(&mut self) -> f32 {
+        let u = 1. - self.uniform();
+        let v = self.uniform();
+        (-2. * u.ln()).sqrt() * (2. * core::f32::consts::PI * v).cos()
+    }
+}
+
+/// nearest-rank percentile of sorted values
+fn exact(sorted: &[f32], p: f32) -> f32 {
+    let rank = ((p as f64 * sorted.len() as f64).ceil() as usize).clamp(1, sorted.len());
+    sorted[rank - 1]
+}
+
+/// ingests `values` into one channel and checks every estimate against the exact value
+fn check_distribution(name: &str, min_exponent: i16, mut values: Vec<f32>) -> ChannelSummary {
+    let mut channel = ChannelStats::<DEFAULT_BINS>::new(min_exponent);
+    for v in &values {
+        channel.ingest(*v);
+    }
+    values.sort_by(f32::total_cmp);
+    let summary = channel.summary();
+    let (min, max) = (values[0], values[N - 1]);
+    assert_eq!(
+        (summary.count, summary.min, summary.max),
+        (N as u64, min, max)
+    );
+    let mean = values.iter().map(|&v| v as f64).sum::<f64>() / N as f64;
+    assert!(
+        (summary.mean as f64 - mean).abs() < 1e-5 * mean.abs().max(1.),
+        "{}",
+        name
+    );
+
+    // the documented bound
+    let finest = 2f32.powi(min_exponent as i32);
+    let bound = finest.max(2. * (max - min) / (DEFAULT_BINS - 1) as f32);
+    assert!(summary.resolution <= bound, "{}: {:?}", name, summary);
+    for (p, estimate) in [(0.5, summary.p50), (0.95, summary.p95), (0.99, summary.p99)] {
+        let error = (estimate - exact(&values, p)).abs();
+        assert!(error < summary.resolution, "{} p{}: {}", name, p, error);
+    }
+    for p in [0., 0.01, 0.25, 0.75, 0.999, 1.] {
+        let error = (channel.percentile(p) - exact(&values, p)).abs();
+        assert!(error < summary.resolution, "{} p{}: {}", name, p, error);
+    }
+    summary
+}
+
+#[test]
+fn uniform_percentiles_within_bound() {
+    let mut noise = Noise::new(1);
+    let values = (0..N).map(|_| 20. + 10. * noise.uniform()).collect();
+    let summary = check_distribution("uniform", TEMP_MIN_EXPONENT, values);
+    // 10 °C over 64 bins: 0.25 °C bins
+    assert_eq!(summary.resolution, 0.25);
+}
+
+#[test]
+fn bimodal_percentiles_within_bound() {
+    // a gyro axis turning back and forth at ±0.5 rad/s
+    let mut noise = Noise::new(2);
+    let values = (0..N)
+        .map(|i| {
+            let mode = if i % 3 == 0 { -0.5 } else { 0.5 };
+            mode + 0.02 * noise.normal()
+        })
+        .collect();
+    check_distribution("bimodal", GYRO_MIN_EXPONENT, values);
+}
+
+#[test]
+fn heavy_tailed_percentiles_within_bound() {
+    // vibration magnitude: 1 g plus Pareto distributed shocks, alpha 2.5
+    let mut noise = Noise::new(3);
+    let values = (0..N)
+        .map(|_| 1. + 0.01 * (1. - noise.uniform()).powf(-1. / 2.5))
+        .collect();
+    let summary = check_distribution("pareto", ACC_MIN_EXPONENT, values);
+    assert!(summary.p99 > summary.p95 && summary.p95 > summary.p50);
+
+    // lognormal temperature excursions
+    let values = (0..N).map(|_| (0.8 * noise.normal()).exp()).collect();
+    check_distribution("lognormal", TEMP_MIN_EXPONENT, values);
+}
+
+#[test]
+fn channels_follow_the_sample() {
+    let mut stats = LongTermStats::<DEFAULT_BINS>::new();
+    for i in 0..100 {
+        let k = i as f32;
+        stats.ingest(&MpuSample::new(
+            Vec3A::new(0.6, 0., 0.8) * (1. + 0.001 * k),
+            Vec3A::new(k, -k, 0.5),
+            30.,
+        ));
+    }
+    // a single sensor read: gyro and temperature NaN, skipped
+    stats.ingest(&MpuSample::new(Vec3A::Z, Vec3A::NAN, f32::NAN));
+    let snapshot = stats.snapshot();
+    assert_eq!(snapshot.acc_magnitude_g.count, 101);
+    assert_eq!(snapshot.acc_magnitude_g.max, 1.099);
+    assert_eq!(
+        snapshot.gyro_rad_s.map(|c| (c.count, c.min, c.max)),
+        [(100, 0., 99.), (100, -99., 0.), (100, 0.5, 0.5)]
+    );
+    assert_eq!((snapshot.temp_c.count, snapshot.temp_c.p99), (100, 30.));
+    assert_eq!(stats.gyro(2).summary(), snapshot.gyro_rad_s[2]);
+
+    stats.reset();
+    assert_eq!(stats, LongTermStats::new());
+    assert_eq!(stats.temp().summary().count, 0);
+    assert!(stats.acc_magnitude().percentile(0.5).is_nan());
+}
+
+#[test]
+fn memory_is_fixed() {
+    assert_eq!(core::mem::size_of::<ChannelStats<DEFAULT_BINS>>(), 296);
+    assert_eq!(core::mem::size_of::<LongTermStats>(), 5 * 296);
+    assert_eq!(
+        core::mem::size_of::<ChannelStats<16>>(),
+        296 - 4 * (DEFAULT_BINS - 16)
+    );
+
+    // widening over any distance keeps the counts
+    let mut channel = ChannelStats::<16>::new(ACC_MIN_EXPONENT);
+    for v in [1e-3, -1e-3, 1e30, -3e38, 0.5, f32::INFINITY, f32::NAN] {
+        channel.ingest(v);
+    }
+    let summary = channel.summary();
+    assert_eq!((summary.count, summary.min, summary.max), (5, -3e38, 1e30));
+    assert!(summary.p50.abs() < summary.resolution);
+}
+
+#[test]
+fn snapshot_round_trips() {
+    let mut stats = LongTermStats::<DEFAULT_BINS>::new();
+    let mut noise = Noise::new(4);
+    for _ in 0..500 {
+        let acc = Vec3A::new(noise.normal(), noise.normal(), noise.normal()) * 0.1 + Vec3A::Z;
+        stats.ingest(&MpuSample::new(acc, Vec3A::splat(noise.normal()), f32::NAN));
+    }
+    // the temperature channel is empty
+    let snapshot = stats.snapshot();
+    assert_eq!(snapshot.temp_c.count, 0);
+    assert!(snapshot.temp_c.p50.is_nan());
+    let bytes = snapshot.to_bytes();
+    assert_eq!(bytes.len(), STATS_SNAPSHOT_LEN);
+    let decoded = StatsSnapshot::from_bytes(&bytes).unwrap();
+    assert_eq!(decoded.to_bytes(), bytes);
+    assert_eq!(decoded.acc_magnitude_g, snapshot.acc_magnitude_g);
+    assert_eq!(decoded.gyro_rad_s, snapshot.gyro_rad_s);
+
+    let mut corrupt = bytes;
+    corrupt[10] ^= 1;
+    assert_eq!(
+        StatsSnapshot::from_bytes(&corrupt),
+        Err(DecodeError::CrcMismatch)
+    );
+    assert_eq!(
+        StatsSnapshot::from_bytes(&bytes[..100]),
+        Err(DecodeError::Truncated(STATS_SNAPSHOT_LEN))
+    );
+    let text = snapshot.to_string();
+    assert_eq!(text.lines().count(), 5);
+    assert!(text.ends_with("temp °C: no values"));
+}
+
+static STATS: SharedLongTermStats = SharedLongTermStats::new();
+
+#[test]
+fn tap_collects_without_traffic() {
+    let bus = SharedBus::new(&[ADDR]);
+    let mut mpu = Mpu6050Builder::new().i2c(bus.clone()).build().unwrap();
+    mpu.init(&mut NoDelay).unwrap();
+    mpu.set_settling_policy(SettlingPolicy::Flag);
+    let frame = |k: f32| {
+        frame_bytes(
+            Vec3A::new(0., 0., 1. + 0.01 * k),
+            Vec3A::new(k, 0., 0.),
+            AccelRange::G2,
+            GyroRange::D250,
+        )
+    };
+    let read = |mpu: &mut Mpu6050<SharedBus>| {
+        for k in 0..20 {
+            bus.device(ADDR, |mock| mock.set_frame(&frame(k as f32)));
+            mpu.read_into_views::<1>(&mut []).unwrap();
+            mpu.get_acc().unwrap();
+        }
+    };
+
+    mpu.reset_io_stats();
+    read(&mut mpu);
+    let untapped = mpu.io_stats();
+
+    mpu.set_sample_tap(Some(|sample| STATS.ingest(sample)));
+    mpu.reset_io_stats();
+    read(&mut mpu);
+    assert_eq!(mpu.io_stats(), untapped);
+
+    let snapshot = STATS.take_snapshot();
+    // every read counts the accelerometer, the bursts the gyro and temperature too
+    assert_eq!(snapshot.acc_magnitude_g.count, 40);
+    assert_eq!(snapshot.gyro_rad_s[0].count, 20);
+    assert_eq!(snapshot.temp_c.count, 20);
+    assert!((snapshot.acc_magnitude_g.max - 1.19).abs() < 1e-3);
+    assert_eq!(STATS.snapshot().acc_magnitude_g.count, 0);
+    assert_eq!(STATS.stats(), LongTermStats::new());
+}
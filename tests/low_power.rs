@@ -0,0 +1,433 @@
+//! Cycle mode on a mock latching MOT_INT: entering and leaving the low power accelerometer
+//! mode, composed with motion detection, the standby bits and CYCLE alone, then in place
+//! reconfiguration, each adjustable setting, the refused ones, a motion event across an
+//! adjustment and the driver state afterwards, see the `low_power` module.
+
+mod common;
+
+use std::cell::Cell;
+use std::convert::Infallible;
+use std::rc::Rc;
+
+use embedded_hal::blocking::i2c::{Write, WriteRead};
+use mpu6050::device::*;
+use mpu6050::interrupt::MotionDetectionConfig;
+use mpu6050::low_power::LowPowerAdjust;
+use mpu6050::power::PowerMode;
+use mpu6050::register::Register;
+use mpu6050::settings::SettingsError;
+use mpu6050::*;
+
+use common::{Access, NoDelay, SharedBus};
+
+const ADDR: u8 = DEFAULT_SLAVE_ADDR;
+
+/// a [`SharedBus`] latching MOT_INT on motion above MOT_THR while cycling, cleared by
+/// reading INT_STATUS
+#[derive(Clone)]
+struct MotionBus {
+    bus: SharedBus,
+    latched: Rc<Cell<bool>>,
+}
+
+impl MotionBus {
+    fn new() -> Self {
+        Self {
+            bus: SharedBus::new(&[ADDR]),
+            latched: Rc::default(),
+        }
+    }
+
+    fn reg(&self, reg: Register) -> u8 {
+        self.bus.device(ADDR, |mock| mock.regs[reg.addr() as usize])
+    }
+
+    /// motion of `lsb` threshold units at the next wake
+    fn shake(&self, lsb: u8) {
+        let cycling = self.reg(Register::PWR_MGMT_1) & 0x20 != 0;
+        if cycling && lsb > self.reg(Register::MOT_THR) {
+            self.latched.set(true);
+        }
+    }
+
+    /// (register, read) of the transactions since the last call
+    fn take_log(&self) -> Vec<(u8, bool)> {
+        self.bus
+            .take_log()
+            .iter()
+            .map(|Access { reg, read, .. }| (*reg, *read))
+            .collect()
+    }
+}
+
+impl Write for MotionBus {
+    type Error = Infallible;
+
+    fn write(&mut self, address: u8, bytes: &[u8]) -> Result<(), Infallible> {
+        self.bus.write(address, bytes)
+    }
+}
+
+impl WriteRead for MotionBus {
+    type Error = Infallible;
+
+    fn write_read(
+        &mut self,
+        address: u8,
+        bytes: &[u8],
+        buffer: &mut [u8],
+    ) -> Result<(), Infallible> {
+        self.bus.write_read(address, bytes, buffer)?;
+        if bytes == [Register::INT_STATUS.addr()] && self.latched.take() {
+            buffer[0] |= 0x40;
+        }
+        Ok(())
+    }
+}
+
+/// a driver cycling at 5 Hz with a motion trigger, the log cleared
+fn cycling(bus: &MotionBus) -> Mpu6050<MotionBus> {
+    let mut mpu = Mpu6050Builder::new().i2c(bus.clone()).build().unwrap();
+    mpu.init(&mut NoDelay).unwrap();
+    mpu.setup_motion_detection(MotionDetectionConfig::DEFAULT)
+        .unwrap();
+    mpu.apply_settings(&presets::LOW_POWER_TILT).unwrap();
+    bus.take_log();
+    mpu
+}
+
+const PWR_MGMT_2_REG: u8 = PWR_MGMT_2::ADDR;
+
+fn awake(bus: &MotionBus) -> Mpu6050<MotionBus> {
+    let mut mpu = Mpu6050Builder::new().i2c(bus.clone()).build().unwrap();
+    mpu.init(&mut NoDelay).unwrap();
+    mpu
+}
+
+#[test]
+fn entering_follows_the_register_map_procedure() {
+    for freq in LowPowerWakeFrequency::ALL {
+        let bus = MotionBus::new();
+        let mut mpu = awake(&bus);
+        mpu.enter_low_power_accel_mode(freq).unwrap();
+        // wake frequency code and the gyros in standby
+        assert_eq!(bus.reg(Register::PWR_MGMT_2), (freq as u8) << 6 | 0x07);
+        // CYCLE and TEMP_DIS, SLEEP clear, internal oscillator
+        assert_eq!(bus.reg(Register::PWR_MGMT_1), 0x28);
+        let wake = freq.lp_wake_ctrl();
+        assert_eq!(LowPowerWakeFrequency::from(wake), freq);
+        assert_eq!(mpu.debug_state().cycle, Some(wake));
+        assert_eq!(mpu.power_config().mode, PowerMode::Cycle(wake));
+    }
+    assert_eq!(LowPowerWakeFrequency::Hz5.centi_hz(), 500);
+    assert_eq!(
+        LowPowerWakeFrequency::Hz5.lp_wake_ctrl(),
+        LP_WAKE_CTRL::_2P5
+    );
+
+    // asleep before, awake after
+    let bus = MotionBus::new();
+    let mut mpu = awake(&bus);
+    mpu.set_sleep_enabled(true).unwrap();
+    mpu.enter_low_power_accel_mode(LowPowerWakeFrequency::Hz40)
+        .unwrap();
+    assert_eq!(bus.reg(Register::PWR_MGMT_1) & 0x40, 0);
+}
+
+#[test]
+fn motion_detection_composes_in_either_order() {
+    for detection_first in [true, false] {
+        let bus = MotionBus::new();
+        let mut mpu = awake(&bus);
+        let detection = |mpu: &mut Mpu6050<MotionBus>| {
+            mpu.setup_motion_detection(MotionDetectionConfig::DEFAULT)
+                .unwrap()
+        };
+        if detection_first {
+            detection(&mut mpu);
+        }
+        mpu.enter_low_power_accel_mode(LowPowerWakeFrequency::Hz5)
+            .unwrap();
+        if !detection_first {
+            detection(&mut mpu);
+        }
+        assert_eq!(bus.reg(Register::PWR_MGMT_1), 0x28);
+        assert_eq!(bus.reg(Register::PWR_MGMT_2), 0x47);
+        assert_ne!(bus.reg(Register::INT_ENABLE) & 0x40, 0);
+        assert_eq!(bus.reg(Register::ACCEL_CONFIG) & 0x07, ACCEL_HPF::_5 as u8);
+
+        bus.shake(5);
+        assert!(mpu.take_motion_event(1_000_000).unwrap().is_none());
+        bus.shake(20);
+        assert!(
+            mpu.take_motion_event(2_000_000).unwrap().is_some(),
+            "{}",
+            detection_first
+        );
+    }
+}
+
+#[test]
+fn exit_restores_normal_mode() {
+    let bus = MotionBus::new();
+    let mut mpu = awake(&bus);
+    mpu.setup_motion_detection(MotionDetectionConfig::DEFAULT)
+        .unwrap();
+    mpu.enter_low_power_accel_mode(LowPowerWakeFrequency::Hz5)
+        .unwrap();
+    mpu.exit_low_power_mode(&mut NoDelay).unwrap();
+    assert_eq!(bus.reg(Register::PWR_MGMT_2), 0);
+    // PLL with the X gyro, CYCLE, TEMP_DIS and SLEEP clear
+    assert_eq!(bus.reg(Register::PWR_MGMT_1), 0x01);
+    assert_eq!(mpu.debug_state().cycle, None);
+    assert_eq!(mpu.power_config().mode, PowerMode::Normal);
+    // motion detection kept, nothing latched outside cycle mode
+    assert_ne!(bus.reg(Register::INT_ENABLE) & 0x40, 0);
+    bus.shake(20);
+    assert!(mpu.take_motion_event(1_000_000).unwrap().is_none());
+
+    // and with the owned delay
+    let bus = MotionBus::new();
+    let mut mpu = Mpu6050Builder::new()
+        .i2c(bus.clone())
+        .delay(NoDelay)
+        .build()
+        .unwrap();
+    mpu.init().unwrap();
+    mpu.enter_low_power_accel_mode(LowPowerWakeFrequency::Hz1P25)
+        .unwrap();
+    mpu.exit_low_power_mode().unwrap();
+    assert_eq!(bus.reg(Register::PWR_MGMT_1), 0x01);
+    assert_eq!(bus.reg(Register::PWR_MGMT_2), 0);
+}
+
+#[test]
+fn standby_bits_and_cycle_alone() {
+    assert_eq!(StandbyFlags::NONE.with_accel(Axis::X, true).bits(), 0x20);
+    assert_eq!(StandbyFlags::NONE.with_gyro(Axis::Z, true).bits(), 0x01);
+    assert_eq!(StandbyFlags::ALL.with_accel(Axis::Y, false).bits(), 0x2f);
+    assert_eq!(StandbyFlags::from_bits(0x47), StandbyFlags::GYRO);
+    let flags = StandbyFlags::ACCEL.with_gyro(Axis::Y, true);
+    assert!(flags.accel(Axis::Z) && flags.gyro(Axis::Y) && !flags.gyro(Axis::X));
+
+    let bus = MotionBus::new();
+    let mut mpu = awake(&bus);
+    mpu.write_register(Register::PWR_MGMT_2, 0x80).unwrap();
+    mpu.set_standby_mask(flags).unwrap();
+    // LP_WAKE_CTRL kept
+    assert_eq!(bus.reg(Register::PWR_MGMT_2), 0x80 | 0x38 | 0x02);
+    assert_eq!(mpu.get_standby_mask().unwrap(), flags);
+    mpu.set_standby_mask(StandbyFlags::NONE).unwrap();
+    assert_eq!(bus.reg(Register::PWR_MGMT_2), 0x80);
+    bus.take_log();
+
+    // a gyro clock stops with the gyros: refused before CYCLE is written
+    assert!(matches!(
+        mpu.set_cycle_enabled(true),
+        Err(Mpu6050Error::InvalidSettings(
+            SettingsError::CycleWithGyroClock
+        ))
+    ));
+    assert_eq!(bus.take_log(), vec![(PWR_MGMT_1::ADDR, true)]);
+
+    mpu.set_clock_source(CLKSEL::OSCILL).unwrap();
+    mpu.set_cycle_enabled(true).unwrap();
+    assert_eq!(bus.reg(Register::PWR_MGMT_1) & 0x20, 0x20);
+    // TEMP_DIS untouched
+    assert_eq!(bus.reg(Register::PWR_MGMT_1) & 0x08, 0);
+    assert_eq!(mpu.debug_state().cycle, Some(LP_WAKE_CTRL::_5));
+    mpu.set_cycle_enabled(false).unwrap();
+    assert_eq!(bus.reg(Register::PWR_MGMT_1) & 0x20, 0);
+    assert_eq!(mpu.debug_state().cycle, None);
+}
+
+#[test]
+fn each_adjustable_setting_is_written_in_place() {
+    let bus = MotionBus::new();
+    let mut mpu = cycling(&bus);
+    let pwr_mgmt_1 = bus.reg(Register::PWR_MGMT_1);
+    // gyros in standby, 5 Hz
+    assert_eq!(bus.reg(Register::PWR_MGMT_2), 0x87);
+
+    mpu.adjust_low_power(LowPowerAdjust {
+        wake_freq: Some(LP_WAKE_CTRL::_1P25),
+        ..LowPowerAdjust::default()
+    })
+    .unwrap();
+    assert_eq!(bus.reg(Register::PWR_MGMT_2), 0x07);
+    assert_eq!(
+        bus.take_log(),
+        vec![(PWR_MGMT_2_REG, true), (PWR_MGMT_2_REG, false)]
+    );
+
+    mpu.adjust_low_power(LowPowerAdjust {
+        motion_threshold: Some(30),
+        ..LowPowerAdjust::default()
+    })
+    .unwrap();
+    assert_eq!(bus.reg(Register::MOT_THR), 30);
+    assert_eq!(bus.take_log(), vec![(MOT_THR, false)]);
+
+    mpu.adjust_low_power(LowPowerAdjust {
+        motion_duration: Some(5),
+        ..LowPowerAdjust::default()
+    })
+    .unwrap();
+    assert_eq!(bus.reg(Register::MOT_DUR), 5);
+    assert_eq!(bus.take_log(), vec![(MOT_DUR, false)]);
+
+    // all three: the motion trigger before the wake frequency
+    mpu.adjust_low_power(LowPowerAdjust {
+        wake_freq: Some(LP_WAKE_CTRL::_10),
+        motion_threshold: Some(12),
+        motion_duration: Some(2),
+        ..LowPowerAdjust::default()
+    })
+    .unwrap();
+    assert_eq!(
+        bus.take_log(),
+        vec![
+            (MOT_THR, false),
+            (MOT_DUR, false),
+            (PWR_MGMT_2_REG, true),
+            (PWR_MGMT_2_REG, false)
+        ]
+    );
+    assert_eq!(bus.reg(Register::PWR_MGMT_2), 0xc7);
+    // CYCLE, TEMP_DIS and the clock untouched
+    assert_eq!(bus.reg(Register::PWR_MGMT_1), pwr_mgmt_1);
+
+    // nothing requested, nothing written
+    mpu.adjust_low_power(LowPowerAdjust::default()).unwrap();
+    assert_eq!(bus.take_log(), vec![]);
+}
+
+#[test]
+fn settings_needing_the_full_path_are_refused() {
+    let bus = MotionBus::new();
+    let mut mpu = cycling(&bus);
+    for (changes, field) in [
+        (
+            LowPowerAdjust {
+                accel_range: Some(AccelRange::G8),
+                ..LowPowerAdjust::default()
+            },
+            LowPowerField::AccelRange,
+        ),
+        (
+            LowPowerAdjust {
+                accel_hpf: Some(ACCEL_HPF::_0P63),
+                ..LowPowerAdjust::default()
+            },
+            LowPowerField::AccelHpf,
+        ),
+        // with an adjustable one in the same request: nothing written
+        (
+            LowPowerAdjust {
+                motion_threshold: Some(3),
+                accel_hpf: Some(ACCEL_HPF::_5),
+                ..LowPowerAdjust::default()
+            },
+            LowPowerField::AccelHpf,
+        ),
+    ] {
+        assert!(!cycle_adjustable(field));
+        match mpu.adjust_low_power(changes) {
+            Err(Mpu6050Error::InvalidSettings(SettingsError::NotAdjustableInCycle(f))) => {
+                assert_eq!(f, field)
+            }
+            other => panic!("{:?}", other),
+        }
+        assert_eq!(bus.take_log(), vec![]);
+    }
+    assert_ne!(bus.reg(Register::MOT_THR), 3);
+
+    // the table lists every field once, by its register
+    for field in [
+        LowPowerField::WakeFrequency,
+        LowPowerField::MotionThreshold,
+        LowPowerField::MotionDuration,
+        LowPowerField::AccelRange,
+        LowPowerField::AccelHpf,
+    ] {
+        let entries: Vec<_> = CYCLE_ADJUSTABILITY
+            .iter()
+            .filter(|entry| entry.field == field)
+            .collect();
+        assert_eq!(entries.len(), 1, "{:?}", field);
+        assert!(entries[0].reason.contains("register map"));
+    }
+
+    // outside cycle mode
+    mpu.apply_settings(&presets::HANDHELD_UI).unwrap();
+    bus.take_log();
+    assert!(matches!(
+        mpu.adjust_low_power(LowPowerAdjust {
+            motion_threshold: Some(3),
+            ..LowPowerAdjust::default()
+        }),
+        Err(Mpu6050Error::InvalidSettings(SettingsError::NotCycling))
+    ));
+    assert_eq!(bus.take_log(), vec![]);
+}
+
+#[test]
+fn motion_latched_before_an_adjustment_is_kept() {
+    let bus = MotionBus::new();
+    let mut mpu = cycling(&bus);
+    assert_eq!(bus.reg(Register::MOT_THR), 10);
+    bus.shake(20);
+
+    mpu.adjust_low_power(LowPowerAdjust {
+        wake_freq: Some(LP_WAKE_CTRL::_1P25),
+        motion_threshold: Some(40),
+        motion_duration: Some(1),
+        ..LowPowerAdjust::default()
+    })
+    .unwrap();
+    assert!(bus
+        .take_log()
+        .iter()
+        .all(|(reg, _)| *reg != Register::INT_STATUS.addr()));
+    assert!(mpu.take_motion_event(1_000_000).unwrap().is_some());
+    assert!(mpu.take_motion_event(2_000_000).unwrap().is_none());
+
+    // and the next wake sees the new threshold
+    bus.shake(20);
+    assert!(mpu.take_motion_event(3_000_000).unwrap().is_none());
+    bus.shake(50);
+    let event = mpu.take_motion_event(4_000_000).unwrap().unwrap();
+    assert_eq!(event.compensation.duration_us, 1000);
+}
+
+#[test]
+fn driver_state_follows_the_adjustment() {
+    let bus = MotionBus::new();
+    let mut mpu = cycling(&bus);
+    let before = mpu.power_estimate();
+    mpu.adjust_low_power(LowPowerAdjust {
+        wake_freq: Some(LP_WAKE_CTRL::_1P25),
+        ..LowPowerAdjust::default()
+    })
+    .unwrap();
+
+    assert_eq!(mpu.debug_state().cycle, Some(LP_WAKE_CTRL::_1P25));
+    assert_eq!(
+        mpu.power_config().mode,
+        PowerMode::Cycle(LP_WAKE_CTRL::_1P25)
+    );
+    assert!(mpu.power_estimate().total_ua < before.total_ua);
+    let in_effect = mpu.read_settings().unwrap();
+    assert_eq!(
+        in_effect,
+        presets::LOW_POWER_TILT.with_cycle(Some(LP_WAKE_CTRL::_1P25))
+    );
+
+    // leaving cycle mode from the settings in effect restores normal mode
+    mpu.apply_settings_diff(&in_effect, &presets::HANDHELD_UI)
+        .unwrap();
+    assert_eq!(mpu.debug_state().cycle, None);
+    assert_eq!(bus.reg(Register::PWR_MGMT_1) & 0x28, 0);
+    assert_eq!(bus.reg(Register::PWR_MGMT_2), 0);
+    assert_eq!(mpu.read_settings().unwrap(), presets::HANDHELD_UI);
+}
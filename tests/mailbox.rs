@@ -0,0 +1,89 @@
+//! Latest sample mailbox: the take and missed count semantics, and a threaded stress test
+//! checking for torn samples and exact missed counting, see the `mailbox` module.
+
+use mpu6050::mailbox::LatestSampleMailbox;
+use mpu6050::{MpuSample, Vec3A};
+
+/// sample `k`, its temperature the checksum of the other fields
+fn numbered(k: u32) -> MpuSample {
+    let k = k as f32;
+    let acc = Vec3A::new(k, k + 1., k + 2.);
+    let gyro = Vec3A::new(-k, 2. * k, k + 0.5);
+    MpuSample::new(acc, gyro, acc.x + acc.y + acc.z + gyro.x + gyro.y + gyro.z)
+}
+
+/// number of an untorn sample
+fn number(sample: &MpuSample) -> u32 {
+    let k = sample.acc().x as u32;
+    assert_eq!(*sample, numbered(k), "torn sample");
+    k
+}
+
+#[test]
+fn take_returns_the_latest_and_counts_overwrites() {
+    let mailbox = LatestSampleMailbox::new();
+    assert_eq!(mailbox.take_latest(), None);
+
+    assert!(mailbox.publish(numbered(1)));
+    assert_eq!(mailbox.take_latest(), Some((numbered(1), 0)));
+    assert_eq!(mailbox.take_latest(), None);
+
+    for k in 2..=4 {
+        mailbox.publish(numbered(k));
+    }
+    assert_eq!(mailbox.take_latest(), Some((numbered(4), 2)));
+
+    // publishes after a take start counting from zero, whatever slot they land in
+    for round in 0..10 {
+        mailbox.publish(numbered(10 + round));
+        mailbox.publish(numbered(20 + round));
+        assert_eq!(mailbox.take_latest(), Some((numbered(20 + round), 1)));
+    }
+
+    // the count saturates
+    for k in 0..70_000 {
+        mailbox.publish(numbered(k));
+    }
+    assert_eq!(mailbox.take_latest(), Some((numbered(69_999), u16::MAX)));
+    assert_eq!(
+        format!("{:?}", mailbox),
+        "LatestSampleMailbox { fresh: false, missed: 0 }"
+    );
+}
+
+#[test]
+fn concurrent_publish_and_take_never_tear() {
+    const PUBLISHES: u32 = 200_000;
+    let mailbox = LatestSampleMailbox::default();
+    let (taken, missed, saturated) = std::thread::scope(|scope| {
+        scope.spawn(|| {
+            for k in 1..=PUBLISHES {
+                assert!(mailbox.publish(numbered(k)));
+            }
+        });
+        let consumer = scope.spawn(|| {
+            let (mut taken, mut missed, mut last, mut saturated) = (0u32, 0u32, 0, false);
+            while last < PUBLISHES {
+                let Some((sample, overwritten)) = mailbox.take_latest() else {
+                    std::hint::spin_loop();
+                    continue;
+                };
+                let k = number(&sample);
+                assert!(k > last, "{} after {}", k, last);
+                // every publish between two takes was overwritten, the count saturates if
+                // the consumer was descheduled for long
+                let between = k - last - 1;
+                assert_eq!(between.min(u16::MAX as u32), overwritten as u32);
+                saturated |= between > u16::MAX as u32;
+                taken += 1;
+                missed += overwritten as u32;
+                last = k;
+            }
+            (taken, missed, saturated)
+        });
+        consumer.join().unwrap()
+    });
+    if !saturated {
+        assert_eq!(taken + missed, PUBLISHES);
+    }
+}
@@ -0,0 +1,340 @@
+//! Metrics sink: the exact counters and events of scripted scenarios, gauge rate limiting,
+//! see the `metrics` module.
+
+mod common;
+
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicU32, Ordering};
+
+use mpu6050::black_box::{FailedOperation, FailureRecord};
+use mpu6050::chaos::{ChaosConfig, FlakyI2c};
+use mpu6050::connection::ReconnectOutcome;
+use mpu6050::device::*;
+use mpu6050::fifo::{FifoSources, FIFO_CAPACITY};
+use mpu6050::interrupt::InterruptSource;
+use mpu6050::metrics::*;
+use mpu6050::settling::SettlingPolicy;
+use mpu6050::supervisor::{SupervisorConfig, SupervisorEvent, SupervisorLimit};
+use mpu6050::test_fixtures::IMPACT;
+use mpu6050::*;
+
+use common::{NoDelay, RegisterMock, SharedBus};
+
+fn sink() -> &'static MemorySink {
+    Box::leak(Box::new(MemorySink::new()))
+}
+
+fn driver(mock: RegisterMock) -> (Mpu6050<RegisterMock>, &'static MemorySink) {
+    let mut mpu = Mpu6050Builder::new().i2c(mock).build().unwrap();
+    let sink = sink();
+    mpu.set_metrics_sink(Some(sink));
+    (mpu, sink)
+}
+
+/// counters emitted, bus counters left out, sorted by name
+fn non_bus_counters(sink: &MemorySink) -> BTreeMap<&'static str, u64> {
+    sink.counters()
+        .into_iter()
+        .filter(|(name, _)| ![BUS_TRANSACTIONS, BUS_BYTES, BUS_ERRORS].contains(name))
+        .collect()
+}
+
+#[test]
+fn init() {
+    let (mut mpu, sink) = driver(RegisterMock::new());
+    mpu.init(&mut NoDelay).unwrap();
+
+    assert_eq!(sink.events(), [MetricEvent::Initialized { chip_id: 0x68 }]);
+    assert_eq!(non_bus_counters(sink), BTreeMap::from([(INITS, 1)]));
+    // the bus counters follow the operation counters exactly
+    let io = mpu.io_stats();
+    assert_eq!(sink.counter_value(BUS_TRANSACTIONS), io.transactions as u64);
+    assert_eq!(sink.counter_value(BUS_BYTES), io.bytes as u64);
+    assert_eq!(sink.counter_value(BUS_ERRORS), 0);
+    assert!(mpu.debug_state().metrics_sink);
+}
+
+#[test]
+fn settling_retry() {
+    let (mut mpu, sink) = driver(RegisterMock::new());
+    mpu.init(&mut NoDelay).unwrap();
+    mpu.set_settling_policy(SettlingPolicy::Discard { max_retries: 2 });
+    sink.clear();
+
+    mpu.get_acc().unwrap();
+    assert_eq!(
+        sink.events(),
+        [
+            MetricEvent::SettlingRetry(Sensor::Accel),
+            MetricEvent::SettlingRetry(Sensor::Accel)
+        ]
+    );
+    assert_eq!(
+        non_bus_counters(sink),
+        BTreeMap::from([(SETTLING_RETRIES, 2)])
+    );
+    assert_eq!(sink.counter_value(BUS_TRANSACTIONS), 3);
+}
+
+#[test]
+fn reconnect_retry() {
+    let (i2c, chaos) = FlakyI2c::new(RegisterMock::new(), ChaosConfig::NONE);
+    let mut mpu = Mpu6050Builder::new().i2c(i2c).build().unwrap();
+    let sink = sink();
+    mpu.set_metrics_sink(Some(sink));
+    mpu.init(&mut NoDelay).unwrap();
+    sink.clear();
+
+    chaos.fail_next(3);
+    for _ in 0..3 {
+        assert!(mpu.get_temp().is_err());
+    }
+    assert_eq!(sink.events(), [MetricEvent::Disconnected]);
+    assert_eq!(sink.counter_value(BUS_ERRORS), 3);
+    assert_eq!(non_bus_counters(sink), BTreeMap::from([(DISCONNECTS, 1)]));
+
+    // attempts while reinitializing do not count as a second disconnect
+    sink.clear();
+    chaos.fail_next(1);
+    assert!(mpu.try_reconnect(&mut NoDelay).is_err());
+    assert_eq!(
+        mpu.try_reconnect(&mut NoDelay).unwrap(),
+        ReconnectOutcome::SameChip { chip_id: 0x68 }
+    );
+    let events = sink.events();
+    assert!(matches!(
+        events[0],
+        MetricEvent::OperationFailed(FailureRecord {
+            operation: FailedOperation::Reconnect,
+            error: 1,
+            ..
+        })
+    ));
+    assert_eq!(
+        events[1..],
+        [MetricEvent::Reconnected(ReconnectOutcome::SameChip {
+            chip_id: 0x68
+        })]
+    );
+    assert_eq!(sink.counter_value(BUS_ERRORS), 1);
+    assert_eq!(
+        non_bus_counters(sink),
+        BTreeMap::from([(OPERATION_FAILURES, 1), (RECONNECTS, 1)])
+    );
+}
+
+#[test]
+fn clipped_sample() {
+    let mut mock = RegisterMock::new();
+    let mut frame = [0; 14];
+    frame[2..4].copy_from_slice(&i16::MIN.to_be_bytes());
+    frame[4..6].copy_from_slice(&i16::MAX.to_be_bytes());
+    frame[12..14].copy_from_slice(&i16::MAX.to_be_bytes());
+    mock.set_frame(&frame);
+    let (mut mpu, sink) = driver(mock);
+
+    mpu.get_acc().unwrap();
+    mpu.get_gyro().unwrap();
+    assert_eq!(
+        sink.events(),
+        [
+            MetricEvent::SampleClipped {
+                sensor: Sensor::Accel,
+                axis: Axis::Y
+            },
+            MetricEvent::SampleClipped {
+                sensor: Sensor::Gyro,
+                axis: Axis::Z
+            }
+        ]
+    );
+    assert_eq!(
+        non_bus_counters(sink),
+        BTreeMap::from([(ACC_CLIPPED, 1), (GYRO_CLIPPED, 1)])
+    );
+
+    // one short of the range is not clipped
+    sink.clear();
+    let mut mock = RegisterMock::new();
+    mock.set_frame(&[0x7f, 0xfe, 0x80, 0x01, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0]);
+    let (mut mpu, sink) = driver(mock);
+    mpu.get_acc().unwrap();
+    assert!(sink.events().is_empty());
+}
+
+#[test]
+fn impact_fixture_clips_where_documented() {
+    let bus = SharedBus::new(&[DEFAULT_SLAVE_ADDR]);
+    let mut mpu = Mpu6050Builder::new().i2c(bus.clone()).build().unwrap();
+    let sink = sink();
+    mpu.set_metrics_sink(Some(sink));
+    mpu.set_settling_policy(SettlingPolicy::Ignore);
+    mpu.set_accel_range(IMPACT.accel_range).unwrap();
+    mpu.set_gyro_range(IMPACT.gyro_range).unwrap();
+    let mut clipped = Vec::new();
+    for idx in 0..IMPACT.len {
+        bus.device(DEFAULT_SLAVE_ADDR, |m| m.set_frame(&IMPACT.bytes(idx)));
+        sink.clear();
+        assert_eq!(mpu.get_acc().unwrap(), IMPACT.expected(idx).acc());
+        if !sink.events().is_empty() {
+            assert_eq!(
+                sink.events(),
+                [MetricEvent::SampleClipped {
+                    sensor: Sensor::Accel,
+                    axis: Axis::X
+                }]
+            );
+            clipped.push(idx);
+        }
+    }
+    assert_eq!(clipped, [7, 8]);
+}
+
+#[test]
+fn fifo_overflow() {
+    let bus = SharedBus::new(&[DEFAULT_SLAVE_ADDR]);
+    let mut mpu = Mpu6050Builder::new().i2c(bus.clone()).build().unwrap();
+    let sink = sink();
+    mpu.set_metrics_sink(Some(sink));
+    let schema = mpu
+        .set_fifo_sources(FifoSources::NONE.with_accel(true))
+        .unwrap();
+    bus.device(DEFAULT_SLAVE_ADDR, |mock| {
+        mock.fifo.extend([0; FIFO_CAPACITY as usize]);
+        mock.regs[INT_STATUS::ADDR as usize] = 1 << INT_STATUS::FIFO_OFLOW_INT;
+    });
+    sink.clear();
+
+    let events = mpu.poll_interrupt_events().unwrap();
+    assert!(events.fired.contains(InterruptSource::FifoOverflow));
+    let mut buf = [0; 60];
+    // reset instead of read
+    assert_eq!(mpu.drain_fifo(&schema, &mut buf, |_| {}).unwrap(), 0);
+    assert_eq!(
+        sink.events(),
+        [
+            MetricEvent::Interrupt(InterruptSource::FifoOverflow),
+            MetricEvent::FifoOverflow {
+                bytes: FIFO_CAPACITY
+            }
+        ]
+    );
+    assert_eq!(
+        non_bus_counters(sink),
+        BTreeMap::from([("mpu6050.interrupt.fifo_overflow", 1), (FIFO_OVERFLOWS, 1)])
+    );
+
+    // below capacity, and the interrupt still asserted, nothing new
+    bus.device(DEFAULT_SLAVE_ADDR, |mock| {
+        assert_ne!(mock.regs[USER_CTRL::ADDR as usize] & 1 << USER_CTRL::FIFO_RESET, 0);
+        mock.fifo.truncate(60);
+    });
+    sink.clear();
+    mpu.poll_interrupt_events().unwrap();
+    mpu.drain_fifo(&schema, &mut buf, |_| {}).unwrap();
+    assert!(sink.events().is_empty());
+}
+
+#[test]
+fn supervisor_trip_is_emitted_once() {
+    let (mut mpu, sink) = driver(RegisterMock::new());
+    mpu.set_supervisor(SupervisorConfig {
+        max_gyro_rad_s: None,
+        max_acc_g: Some(0.5),
+        consecutive: 2,
+        callback: None,
+    });
+    for _ in 0..4 {
+        mpu.get_acc().unwrap();
+    }
+    assert_eq!(
+        sink.events(),
+        [MetricEvent::SupervisorTripped(SupervisorEvent {
+            limit: SupervisorLimit::AccelMagnitude,
+            value: 1.
+        })]
+    );
+    assert_eq!(
+        non_bus_counters(sink),
+        BTreeMap::from([(SUPERVISOR_TRIPS, 1)])
+    );
+}
+
+#[test]
+fn names_are_stable() {
+    // dashboards are built against these, changing one is a breaking change
+    assert_eq!(BUS_TRANSACTIONS, "mpu6050.bus.transactions");
+    assert_eq!(BUS_BYTES, "mpu6050.bus.bytes");
+    assert_eq!(BUS_ERRORS, "mpu6050.bus.errors");
+    assert_eq!(DISCONNECTS, "mpu6050.connection.disconnects");
+    assert_eq!(RECONNECTS, "mpu6050.connection.reconnects");
+    assert_eq!(INITS, "mpu6050.init");
+    assert_eq!(SETTLING_RETRIES, "mpu6050.settling.retries");
+    assert_eq!(ACC_CLIPPED, "mpu6050.acc.clipped");
+    assert_eq!(GYRO_CLIPPED, "mpu6050.gyro.clipped");
+    assert_eq!(SUPERVISOR_TRIPS, "mpu6050.supervisor.trips");
+    assert_eq!(SAMPLING_OVERRUNS, "mpu6050.sampling.overruns");
+    assert_eq!(FIFO_OVERFLOWS, "mpu6050.fifo.overflows");
+    assert_eq!(GAUGE_ACC_G, "mpu6050.sample.acc_g");
+    assert_eq!(GAUGE_GYRO_RAD_S, "mpu6050.sample.gyro_rad_s");
+    assert_eq!(GAUGE_TEMP_C, "mpu6050.sample.temp_c");
+    for (source, name) in InterruptSource::ALL.into_iter().zip([
+        "mpu6050.interrupt.free_fall",
+        "mpu6050.interrupt.motion",
+        "mpu6050.interrupt.zero_motion",
+        "mpu6050.interrupt.fifo_overflow",
+        "mpu6050.interrupt.i2c_master",
+        "mpu6050.interrupt.data_ready",
+    ]) {
+        assert_eq!(interrupt_counter(source), name);
+    }
+}
+
+/// counts gauge calls, ignores the rest
+#[derive(Default)]
+struct GaugeCount(AtomicU32);
+
+impl MetricsSink for GaugeCount {
+    fn gauge(&self, _name: &'static str, _value: f32) {
+        self.0.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+#[test]
+fn gauges_are_opt_in_and_rate_limited() {
+    let (mut mpu, sink) = driver(RegisterMock::new());
+    for _ in 0..5 {
+        mpu.get_acc().unwrap();
+    }
+    assert_eq!(sink.gauge_value(GAUGE_ACC_G), None);
+
+    mpu.set_gauge_limiter(Some(GaugeLimiter::new(3)));
+    mpu.get_acc().unwrap();
+    assert!((sink.gauge_value(GAUGE_ACC_G).unwrap() - 1.).abs() < 1e-3);
+    // single sensor reads leave the other parts out
+    assert_eq!(sink.gauge_value(GAUGE_GYRO_RAD_S), None);
+    assert_eq!(sink.gauge_value(GAUGE_TEMP_C), None);
+
+    let count: &'static GaugeCount = Box::leak(Box::default());
+    mpu.set_metrics_sink(Some(count));
+    mpu.set_gauge_limiter(Some(GaugeLimiter::new(3)));
+    for _ in 0..7 {
+        mpu.get_acc().unwrap();
+    }
+    // the 1st, 4th and 7th
+    assert_eq!(count.0.load(Ordering::Relaxed), 3);
+    assert_eq!(mpu.debug_state().gauge_limiter.map(|l| l.every()), Some(3));
+}
+
+#[test]
+fn gauge_limiter() {
+    let mut limiter = GaugeLimiter::new(4);
+    let passed: Vec<bool> = (0..9).map(|_| limiter.tick()).collect();
+    assert_eq!(
+        passed,
+        [true, false, false, false, true, false, false, false, true]
+    );
+    let mut every = GaugeLimiter::new(0);
+    assert_eq!(every.every(), 1);
+    assert!((0..5).all(|_| every.tick()));
+}
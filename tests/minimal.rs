@@ -0,0 +1,76 @@
+//! The `minimal` feature builds on its own: no_std, no glam, and not together with the full
+//! driver. Runs cargo on this package in a separate target directory.
+
+use std::process::{Command, Output};
+
+fn cargo(args: &[&str], rustflags: &str) -> Output {
+    let cargo = std::env::var("CARGO").unwrap_or_else(|_| "cargo".into());
+    let manifest = concat!(env!("CARGO_MANIFEST_DIR"), "/Cargo.toml");
+    Command::new(cargo)
+        .args(args)
+        .args(["--manifest-path", manifest])
+        .env(
+            "CARGO_TARGET_DIR",
+            concat!(env!("CARGO_MANIFEST_DIR"), "/target/minimal-check"),
+        )
+        .env("RUSTFLAGS", rustflags)
+        .output()
+        .expect("cargo runs")
+}
+
+fn stderr(output: &Output) -> String {
+    String::from_utf8_lossy(&output.stderr).into_owned()
+}
+
+#[test]
+fn minimal_dependency_graph_has_no_glam() {
+    let output = cargo(
+        &[
+            "tree",
+            "--no-default-features",
+            "--features",
+            "minimal",
+            "--edges",
+            "normal",
+            "--prefix",
+            "none",
+        ],
+        "",
+    );
+    assert!(output.status.success(), "{}", stderr(&output));
+    let tree = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        tree.lines().any(|l| l.starts_with("embedded-hal ")),
+        "{tree}"
+    );
+    assert!(!tree.lines().any(|l| l.starts_with("glam ")), "{tree}");
+}
+
+// the example is a no_std static library with its own panic handler, linking std would
+// define the handler twice
+#[test]
+fn minimal_builds_without_std() {
+    let output = cargo(
+        &[
+            "build",
+            "--example",
+            "tiny_size",
+            "--no-default-features",
+            "--features",
+            "minimal",
+        ],
+        "-C panic=abort",
+    );
+    assert!(output.status.success(), "{}", stderr(&output));
+}
+
+#[test]
+fn minimal_with_the_full_driver_is_a_compile_error() {
+    let output = cargo(&["check", "--lib", "--features", "minimal"], "");
+    assert!(!output.status.success());
+    assert!(
+        stderr(&output).contains("feature `minimal` replaces the full driver"),
+        "{}",
+        stderr(&output)
+    );
+}
@@ -0,0 +1,169 @@
+//! The `minimal-pipeline` build: scaled reads bit for bit the reference pipeline and the same
+//! digest in both builds, the stripped sample and bounds, and the stripped build run from the
+//! full one, see the `stages` module.
+
+mod common;
+
+use mpu6050::device::*;
+use mpu6050::frame::parse_frame;
+use mpu6050::op_bounds::{self, GET_RAW};
+use mpu6050::settling::SettlingPolicy;
+use mpu6050::synthetic::frame_bytes;
+use mpu6050::*;
+
+use common::{NoDelay, SharedBus};
+
+const ADDR: u8 = DEFAULT_SLAVE_ADDR;
+
+/// FNV-1a over the bits of every value of [`scaled_reads`], the same in both builds
+const DIGEST: u64 = 0x8ad3_901c_920f_b27d;
+
+/// frames at ±4 g and ±500 °/s with distinct values and temperatures
+fn script() -> Vec<[u8; 14]> {
+    (0..16)
+        .map(|i| {
+            let k = i as f32;
+            let mut frame = frame_bytes(
+                Vec3A::new(0.03 * k - 0.2, 1.5 - 0.1 * k, -0.7 + 0.011 * k),
+                Vec3A::new(17. * k - 100., -3.3 * k, 250. - 29. * k),
+                AccelRange::G4,
+                GyroRange::D500,
+            );
+            frame[6..8].copy_from_slice(&(377 * i as i16 - 3000).to_be_bytes());
+            frame
+        })
+        .collect()
+}
+
+/// a driver right after init, offsets and factors set, every optional stage off
+fn driver() -> (Mpu6050<SharedBus>, SharedBus) {
+    let bus = SharedBus::new(&[ADDR]);
+    let mut mpu = Mpu6050Builder::new()
+        .i2c(bus.clone())
+        .acc_sensitivity(AccelRange::G4)
+        .gyro_sensitivity(GyroRange::D500)
+        .acc_offset([-0.02, 0.01, -0.005])
+        .gyro_offset([-0.01, 0., 0.02])
+        .acc_scale_factors_array([1.001, 0.998, 1.])
+        .build()
+        .unwrap();
+    mpu.init(&mut NoDelay).unwrap();
+    mpu.set_settling_policy(SettlingPolicy::Flag);
+    (mpu, bus)
+}
+
+fn digest(digest: &mut u64, values: &[f32]) {
+    for bits in values.iter().map(|v| v.to_bits()) {
+        for byte in bits.to_le_bytes() {
+            *digest = (*digest ^ byte as u64).wrapping_mul(0x100_0000_01b3);
+        }
+    }
+}
+
+/// every value of every scaled read of the script, checked against the pipeline
+fn scaled_reads() -> u64 {
+    let (mut mpu, bus) = driver();
+    let pipeline = mpu.pipeline();
+    let bits = |v: Vec3A| v.to_array().map(f32::to_bits);
+    let mut hash = 0xcbf2_9ce4_8422_2325;
+    for frame in script() {
+        bus.device(ADDR, |mock| mock.set_frame(&frame));
+        let raw = parse_frame(&frame);
+        let expected = pipeline.sample(&raw);
+
+        let acc = mpu.get_acc().unwrap();
+        let gyro = mpu.get_gyro().unwrap();
+        let temp = mpu.get_temp().unwrap();
+        assert_eq!(bits(acc), bits(expected.acc()));
+        assert_eq!(bits(gyro), bits(expected.gyro()));
+        assert_eq!(temp.to_bits(), expected.temp().to_bits());
+
+        let burst = mpu.read_into_views::<1>(&mut []).unwrap();
+        let mut batch = [MpuSample::default(); 2];
+        mpu.read_batch(|| {}, &mut batch).unwrap();
+        for sample in [burst, batch[0], batch[1]] {
+            assert_eq!(bits(sample.acc()), bits(expected.acc()));
+            assert_eq!(bits(sample.gyro()), bits(expected.gyro()));
+            assert_eq!(sample.temp().to_bits(), expected.temp().to_bits());
+        }
+        digest(&mut hash, &acc.to_array());
+        digest(&mut hash, &gyro.to_array());
+        digest(&mut hash, &[temp]);
+        for sample in [burst, batch[0], batch[1]] {
+            digest(&mut hash, &sample.acc().to_array());
+            digest(&mut hash, &sample.gyro().to_array());
+            digest(&mut hash, &[sample.temp()]);
+        }
+    }
+    hash
+}
+
+#[test]
+fn scaled_reads_are_the_reference_pipeline_in_both_builds() {
+    assert_eq!(scaled_reads(), DIGEST);
+}
+
+#[test]
+fn stripped_stages_leave_nothing_behind() {
+    let (mut mpu, bus) = driver();
+    bus.device(ADDR, |mock| mock.set_frame(&script()[3]));
+    mpu.set_sample_hook(Some(|sample| *sample = sample.with_acc(Vec3A::ZERO)));
+    mpu.set_settling_policy(SettlingPolicy::Discard { max_retries: 3 });
+    mpu.reset_io_stats();
+    let settling = mpu.settle_countdown();
+    let sample = mpu.read_into_views::<1>(&mut []).unwrap();
+    let acc = mpu.get_acc().unwrap();
+    let policy = SettlingPolicy::Discard { max_retries: 3 };
+    if cfg!(feature = "minimal-pipeline") {
+        // the values alone, no flags or tags stored
+        assert!(!format!("{:?}", sample).contains("settling"));
+        assert_eq!(
+            sample,
+            MpuSample::new(sample.acc(), sample.gyro(), sample.temp())
+        );
+        assert!(!sample.settling());
+        assert_eq!(sample.with_settling(true), sample);
+        // no hook, no retries, the countdown untouched
+        assert_ne!(acc, Vec3A::ZERO);
+        assert_eq!(mpu.settle_countdown(), settling);
+        assert_eq!(mpu.io_stats().transactions, 2);
+        assert_eq!(op_bounds::get_acc(policy), GET_RAW);
+        assert_eq!(op_bounds::read_sample(policy).transactions, 3);
+    } else {
+        assert!(format!("{:?}", sample).contains("settling: true"));
+        assert!(sample.settling());
+        assert_eq!(sample.acc(), Vec3A::ZERO);
+        assert_eq!(acc, Vec3A::ZERO);
+        assert_ne!(mpu.settle_countdown(), settling);
+        assert_eq!(op_bounds::get_acc(policy), GET_RAW.times(4));
+        assert_eq!(op_bounds::read_sample(policy).transactions, 9);
+    }
+}
+
+// runs this file in the stripped build, in a separate target directory
+#[cfg(not(feature = "minimal-pipeline"))]
+#[test]
+fn stripped_build_passes_these_tests() {
+    let cargo = std::env::var("CARGO").unwrap_or_else(|_| "cargo".into());
+    let output = std::process::Command::new(cargo)
+        .args([
+            "test",
+            "--features",
+            "minimal-pipeline",
+            "--test",
+            "minimal_pipeline",
+            "--manifest-path",
+            concat!(env!("CARGO_MANIFEST_DIR"), "/Cargo.toml"),
+        ])
+        .env(
+            "CARGO_TARGET_DIR",
+            concat!(env!("CARGO_MANIFEST_DIR"), "/target/minimal-pipeline-check"),
+        )
+        .output()
+        .expect("cargo runs");
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+}
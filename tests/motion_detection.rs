@@ -0,0 +1,111 @@
+//! Arming motion detection must only touch the fields it needs: the accel range and its
+//! scaling, self-test bits, the INT pin's other bits, other interrupt enables and the clock
+//! source survive. The driver reads a mock register file.
+
+mod common;
+
+use mpu6050::device::{
+    AccelRange, GyroRange, ACCEL_CONFIG, DEFAULT_SLAVE_ADDR, INT_ENABLE, INT_PIN_CFG,
+    MOT_DETECT_CONTROL, PWR_MGMT_1,
+};
+use mpu6050::interrupt::MotionDetectionConfig;
+use mpu6050::{synthetic, Mpu6050, Mpu6050Builder, Vec3A};
+
+use common::SharedBus;
+
+fn setup() -> (SharedBus, Mpu6050<SharedBus>) {
+    let bus = SharedBus::new(&[DEFAULT_SLAVE_ADDR]);
+    let mpu = Mpu6050Builder::new().i2c(bus.clone()).build().unwrap();
+    (bus, mpu)
+}
+
+fn reg(bus: &SharedBus, addr: u8) -> u8 {
+    bus.device(DEFAULT_SLAVE_ADDR, |mock| mock.regs[addr as usize])
+}
+
+#[test]
+fn accel_range_and_scaling_survive_arming() {
+    let (bus, mut mpu) = setup();
+    mpu.set_accel_range(AccelRange::G8).unwrap();
+    assert_eq!(reg(&bus, ACCEL_CONFIG::ADDR), 0x10);
+
+    mpu.setup_motion_detection(MotionDetectionConfig::DEFAULT)
+        .unwrap();
+
+    // AFS_SEL 2 kept, ACCEL_HPF 5 Hz added
+    assert_eq!(reg(&bus, ACCEL_CONFIG::ADDR), 0x11);
+    assert_eq!(mpu.get_accel_range().unwrap(), AccelRange::G8);
+
+    // 3g is 12288 LSB at ±8g, readings at a wrongly assumed ±2g would be 4× too large
+    let acc = Vec3A::new(0., 0., 3.);
+    let frame = synthetic::frame_bytes(acc, Vec3A::ZERO, AccelRange::G8, GyroRange::D250);
+    bus.device(DEFAULT_SLAVE_ADDR, |mock| mock.set_frame(&frame));
+    let read = mpu.get_acc().unwrap();
+    assert!((read - acc).abs().max_element() < 1e-3, "{read:?}");
+}
+
+#[test]
+fn self_test_bits_survive_arming() {
+    let (bus, mut mpu) = setup();
+    mpu.set_accel_range(AccelRange::G4).unwrap();
+    mpu.set_accel_x_self_test(true).unwrap();
+    mpu.set_accel_z_self_test(true).unwrap();
+
+    mpu.setup_motion_detection(MotionDetectionConfig::DEFAULT)
+        .unwrap();
+
+    // XA_ST and ZA_ST, AFS_SEL 1, ACCEL_HPF 5 Hz
+    assert_eq!(reg(&bus, ACCEL_CONFIG::ADDR), 0xa9);
+}
+
+#[test]
+fn int_pin_and_enables_keep_unrelated_bits() {
+    let (bus, mut mpu) = setup();
+    bus.device(DEFAULT_SLAVE_ADDR, |mock| {
+        // bypass and FSYNC interrupt set by the application
+        mock.regs[INT_PIN_CFG::ADDR as usize] =
+            1 << INT_PIN_CFG::I2C_BYPASS_EN | 1 << INT_PIN_CFG::FSYNC_INT_EN;
+        mock.regs[INT_ENABLE::ADDR as usize] = 1 << INT_ENABLE::DATA_RDY_EN;
+        // asleep on the X gyro PLL
+        mock.regs[PWR_MGMT_1::ADDR as usize] = 0x41;
+    });
+
+    mpu.setup_motion_detection(MotionDetectionConfig {
+        threshold: 20,
+        duration: 1,
+    })
+    .unwrap();
+
+    // latched added, active high and push-pull as requested
+    assert_eq!(
+        reg(&bus, INT_PIN_CFG::ADDR),
+        1 << INT_PIN_CFG::LATCH_INT_EN
+            | 1 << INT_PIN_CFG::I2C_BYPASS_EN
+            | 1 << INT_PIN_CFG::FSYNC_INT_EN
+    );
+    assert_eq!(
+        reg(&bus, INT_ENABLE::ADDR),
+        1 << INT_ENABLE::MOT_EN | 1 << INT_ENABLE::DATA_RDY_EN
+    );
+    // awake, clock source kept
+    assert_eq!(reg(&bus, PWR_MGMT_1::ADDR), 0x01);
+    // ACCEL_ON_DELAY, FF_COUNT and MOT_COUNT 1
+    assert_eq!(reg(&bus, MOT_DETECT_CONTROL::ADDR), 0x15);
+}
+
+#[test]
+fn int_pin_polarity_set_before_is_overridden_only_in_its_fields() {
+    let (bus, mut mpu) = setup();
+    bus.device(DEFAULT_SLAVE_ADDR, |mock| {
+        // active low, open drain, clear on any read, CLKOUT
+        mock.regs[INT_PIN_CFG::ADDR as usize] = 0xd1;
+    });
+
+    mpu.setup_motion_detection(MotionDetectionConfig::DEFAULT)
+        .unwrap();
+
+    assert_eq!(
+        reg(&bus, INT_PIN_CFG::ADDR),
+        1 << INT_PIN_CFG::LATCH_INT_EN | 1 << INT_PIN_CFG::CLKOUT_EN
+    );
+}
@@ -0,0 +1,103 @@
+//! Motion event timestamps: the compensation terms for synthetic MOT_DUR and DLPF settings,
+//! the saturating subtraction and `take_motion_event` on a mock register file, see the
+//! `interrupt` module.
+
+mod common;
+
+use mpu6050::device::{DEFAULT_SLAVE_ADDR, INT_STATUS};
+use mpu6050::interrupt::{MotionCompensation, MotionDetectionConfig, MotionEvent};
+use mpu6050::Mpu6050Builder;
+
+use common::SharedBus;
+
+const MOTION: u8 = 1 << INT_STATUS::MOT_INT;
+
+#[test]
+fn terms_follow_mot_dur_and_the_dlpf_table() {
+    // MOT_DUR at 1 ms per LSB, the accel column of the delay table, 0.5 ms quantization
+    for (mot_dur, dlpf_cfg, filter_delay_us) in [
+        (0, 0, 0),
+        (1, 1, 2000),
+        (40, 2, 3000),
+        (40, 3, 4900),
+        (10, 4, 8500),
+        (10, 5, 13_800),
+        (255, 6, 19_000),
+        (5, 7, 0),
+    ] {
+        let compensation = MotionCompensation::new(mot_dur, dlpf_cfg);
+        assert_eq!(
+            compensation,
+            MotionCompensation {
+                duration_us: mot_dur as u32 * 1000,
+                filter_delay_us,
+                half_sample_us: 500,
+            },
+            "MOT_DUR {mot_dur} DLPF_CFG {dlpf_cfg}"
+        );
+        assert_eq!(
+            compensation.total_us(),
+            mot_dur as u32 * 1000 + filter_delay_us + 500
+        );
+    }
+    // bits above DLPF_CFG are the FSYNC field
+    assert_eq!(
+        MotionCompensation::new(40, 0b0011_1011),
+        MotionCompensation::new(40, 3)
+    );
+}
+
+#[test]
+fn apply_subtracts_the_total_and_saturates() {
+    // 40 ms, 4.9 ms, 0.5 ms
+    let compensation = MotionCompensation::new(40, 3);
+    assert_eq!(compensation.total_us(), 45_400);
+    assert_eq!(compensation.apply(1_000_000), 954_600);
+    assert_eq!(compensation.apply(45_400), 0);
+    assert_eq!(compensation.apply(1000), 0);
+
+    let event = MotionEvent::new(1_000_000, compensation);
+    assert_eq!(event.status_read_us, 1_000_000);
+    assert_eq!(event.compensation, compensation);
+    assert_eq!(event.estimated_us, 954_600);
+}
+
+#[test]
+fn driver_reports_fired_motion_only() {
+    let bus = SharedBus::new(&[DEFAULT_SLAVE_ADDR]);
+    let mut mpu = Mpu6050Builder::new().i2c(bus.clone()).build().unwrap();
+    mpu.setup_motion_detection(MotionDetectionConfig {
+        threshold: 10,
+        duration: 20,
+    })
+    .unwrap();
+    mpu.set_dlpf(4).unwrap();
+    let status = |value| {
+        bus.device(DEFAULT_SLAVE_ADDR, |mock| {
+            mock.regs[INT_STATUS::ADDR as usize] = value
+        })
+    };
+
+    assert_eq!(mpu.take_motion_event(5_000_000).unwrap(), None);
+    status(MOTION);
+    bus.take_log();
+    let event = mpu.take_motion_event(5_000_000).unwrap().unwrap();
+    // INT_STATUS, then MOT_DUR read back
+    assert_eq!(bus.take_log().len(), 2);
+    assert_eq!(event.compensation, MotionCompensation::new(20, 4));
+    assert_eq!(event.estimated_us, 5_000_000 - 20_000 - 8500 - 500);
+
+    // still asserted: no new event, and no MOT_DUR read
+    assert_eq!(mpu.take_motion_event(5_010_000).unwrap(), None);
+    assert_eq!(bus.take_log().len(), 1);
+    status(0);
+    assert_eq!(mpu.take_motion_event(5_020_000).unwrap(), None);
+    status(MOTION);
+    assert_eq!(
+        mpu.take_motion_event(5_030_000)
+            .unwrap()
+            .unwrap()
+            .status_read_us,
+        5_030_000
+    );
+}
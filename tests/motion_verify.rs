@@ -0,0 +1,337 @@
+//! Motion engine verification on a mock motion engine: a latch on the self-test and on an
+//! operator, broken engines and dead self-tests, the registers restored, and the arming
+//! policies, see the `motion_verify` module.
+
+mod common;
+
+use std::cell::Cell;
+use std::convert::Infallible;
+use std::rc::Rc;
+
+use embedded_hal::blocking::i2c::{Write, WriteRead};
+use mpu6050::hint::RecoveryHint;
+use mpu6050::interrupt::MotionDetectionConfig;
+use mpu6050::motion_verify::*;
+use mpu6050::register::Register;
+use mpu6050::{device::*, *};
+
+use common::{NoDelay, SharedBus};
+
+const ADDR: u8 = DEFAULT_SLAVE_ADDR;
+
+/// self-test response of a genuine part at ±8 g, about 0.5 g per axis
+const GENUINE_RESPONSE: [i16; 3] = [2050, 2130, 1980];
+
+/// state of the mock motion engine, shared with the test
+#[derive(Default)]
+struct Engine {
+    /// MOT_INT ever latches
+    works: bool,
+    /// accel output shift while the self-test bits are set
+    response: [i16; 3],
+    /// motion seen, reported on the next INT_STATUS read
+    pending: Cell<bool>,
+    /// INT_STATUS reads until the operator's motion arrives
+    motion_in: Cell<Option<u32>>,
+}
+
+/// a [`SharedBus`] emulating MOT_INT: a latch on motion or on the self-test switch while
+/// MOT_EN is set, MOT_THR nonzero and the engine works, cleared by reading INT_STATUS
+#[derive(Clone)]
+struct MotionBus {
+    bus: SharedBus,
+    engine: Rc<Engine>,
+}
+
+impl MotionBus {
+    fn new(works: bool, response: [i16; 3]) -> Self {
+        Self {
+            bus: SharedBus::new(&[ADDR]),
+            engine: Rc::new(Engine {
+                works,
+                response,
+                ..Engine::default()
+            }),
+        }
+    }
+
+    fn reg(&self, reg: Register) -> u8 {
+        self.bus.device(ADDR, |mock| mock.regs[reg.addr() as usize])
+    }
+
+    /// every register but INT_STATUS, cleared on read
+    fn snapshot(&self) -> Vec<u8> {
+        let mut regs = self.bus.device(ADDR, |mock| mock.regs.to_vec());
+        regs[Register::INT_STATUS.addr() as usize] = 0;
+        regs
+    }
+
+    /// the operator moves the device `reads` INT_STATUS reads from now
+    fn move_after(&self, reads: u32) {
+        self.engine.motion_in.set(Some(reads));
+    }
+
+    fn armed(&self) -> bool {
+        self.reg(Register::INT_ENABLE) & 0x40 != 0 && self.reg(Register::MOT_THR) != 0
+    }
+}
+
+impl Write for MotionBus {
+    type Error = Infallible;
+
+    fn write(&mut self, address: u8, bytes: &[u8]) -> Result<(), Infallible> {
+        let was_self_test = self.reg(Register::ACCEL_CONFIG) & 0xe0 == 0xe0;
+        self.bus.write(address, bytes)?;
+        let self_test = self.reg(Register::ACCEL_CONFIG) & 0xe0 == 0xe0;
+        if self_test && !was_self_test && self.engine.response != [0; 3] {
+            self.engine.pending.set(true);
+        }
+        Ok(())
+    }
+}
+
+impl WriteRead for MotionBus {
+    type Error = Infallible;
+
+    fn write_read(
+        &mut self,
+        address: u8,
+        bytes: &[u8],
+        buffer: &mut [u8],
+    ) -> Result<(), Infallible> {
+        self.bus.write_read(address, bytes, buffer)?;
+        if bytes == [Register::INT_STATUS.addr()] {
+            let engine = &self.engine;
+            match engine.motion_in.get() {
+                Some(0) => {
+                    engine.pending.set(true);
+                    engine.motion_in.set(None);
+                }
+                Some(reads) => engine.motion_in.set(Some(reads - 1)),
+                None => {}
+            }
+            if engine.works && self.armed() && engine.pending.take() {
+                buffer[0] |= 0x40;
+            }
+        }
+        let self_test = self.reg(Register::ACCEL_CONFIG) & 0xe0 == 0xe0;
+        if bytes == [ACC_REGX_H] && self_test {
+            for (pair, response) in buffer.chunks_exact_mut(2).zip(self.engine.response) {
+                let value = i16::from_be_bytes([pair[0], pair[1]]).wrapping_add(response);
+                pair.copy_from_slice(&value.to_be_bytes());
+            }
+        }
+        Ok(())
+    }
+}
+
+/// an initialized driver on `bus` with an application configuration, the log cleared
+fn awake(bus: &MotionBus) -> Mpu6050<MotionBus> {
+    let mut mpu = Mpu6050Builder::new().i2c(bus.clone()).build().unwrap();
+    mpu.init(&mut NoDelay).unwrap();
+    mpu.set_accel_range(AccelRange::G4).unwrap();
+    mpu.write_register(Register::MOT_THR, 20).unwrap();
+    mpu.write_register(Register::MOT_DUR, 40).unwrap();
+    mpu.write_register(Register::MOT_DETECT_CONTROL, 0x15)
+        .unwrap();
+    mpu.write_register(Register::INT_ENABLE, 0x01).unwrap();
+    bus.bus.take_log();
+    mpu
+}
+
+#[test]
+fn self_test_latch_verifies_and_restores_the_registers() {
+    let bus = MotionBus::new(true, GENUINE_RESPONSE);
+    let mut mpu = awake(&bus);
+    let before = bus.snapshot();
+    assert_eq!(
+        mpu.capabilities().motion_engine,
+        MotionEngineStatus::Unverified
+    );
+
+    let outcome = mpu.verify_motion_detection(&mut NoDelay, None).unwrap();
+    assert_eq!(
+        outcome,
+        MotionVerifyOutcome::Latched {
+            stimulus: MotionStimulus::SelfTest,
+            after_ms: 0
+        }
+    );
+    assert_eq!(outcome.engine_status(), Some(MotionEngineStatus::Verified));
+    assert_eq!(
+        mpu.capabilities().motion_engine,
+        MotionEngineStatus::Verified
+    );
+    let written: Vec<u8> = bus
+        .bus
+        .take_log()
+        .iter()
+        .filter(|access| !access.read)
+        .map(|access| access.reg)
+        .collect();
+    assert!(written.contains(&Register::MOT_THR.addr()));
+    assert_eq!(bus.snapshot(), before);
+
+    // kept across a probe, and arming allowed by every policy
+    assert_eq!(
+        mpu.probe_capabilities().unwrap().motion_engine,
+        MotionEngineStatus::Verified
+    );
+    mpu.arm_wake_on_motion(
+        WakeOnMotionPolicy::RequireVerified,
+        MotionDetectionConfig::DEFAULT,
+    )
+    .unwrap();
+    assert!(bus.armed());
+}
+
+#[test]
+fn broken_engine_is_recorded_and_refused() {
+    let bus = MotionBus::new(false, GENUINE_RESPONSE);
+    let mut mpu = awake(&bus);
+    let before = bus.snapshot();
+
+    let outcome = mpu.verify_motion_detection(&mut NoDelay, None).unwrap();
+    assert_eq!(
+        outcome,
+        MotionVerifyOutcome::NotLatched {
+            stimulus: MotionStimulus::SelfTest
+        }
+    );
+    assert_eq!(mpu.capabilities().motion_engine, MotionEngineStatus::Broken);
+    assert_eq!(bus.snapshot(), before);
+    bus.bus.take_log();
+
+    for policy in [
+        WakeOnMotionPolicy::RefuseBroken,
+        WakeOnMotionPolicy::RequireVerified,
+    ] {
+        let error = mpu
+            .arm_wake_on_motion(policy, MotionDetectionConfig::DEFAULT)
+            .unwrap_err();
+        assert!(matches!(
+            error,
+            Mpu6050Error::WakeOnMotionRefused(MotionEngineStatus::Broken)
+        ));
+        assert_eq!(error.recovery_hint(), RecoveryHint::ReplaceHardware);
+        assert_eq!(error.code(), 21);
+    }
+    assert_eq!(
+        bus.bus.take_log(),
+        vec![],
+        "a refused arming reached the bus"
+    );
+    mpu.arm_wake_on_motion(WakeOnMotionPolicy::Override, MotionDetectionConfig::DEFAULT)
+        .unwrap();
+    assert_eq!(bus.reg(Register::MOT_THR), 10);
+}
+
+#[test]
+fn dead_self_test_leaves_the_engine_unverified() {
+    let bus = MotionBus::new(true, [0; 3]);
+    let mut mpu = awake(&bus);
+    let before = bus.snapshot();
+
+    let outcome = mpu.verify_motion_detection(&mut NoDelay, None).unwrap();
+    assert_eq!(
+        outcome,
+        MotionVerifyOutcome::NoStimulus {
+            response_g: Vec3A::ZERO
+        }
+    );
+    assert_eq!(outcome.engine_status(), None);
+    assert_eq!(
+        mpu.capabilities().motion_engine,
+        MotionEngineStatus::Unverified
+    );
+    assert_eq!(bus.snapshot(), before);
+
+    let error = mpu
+        .arm_wake_on_motion(
+            WakeOnMotionPolicy::RequireVerified,
+            MotionDetectionConfig::DEFAULT,
+        )
+        .unwrap_err();
+    assert!(matches!(
+        error,
+        Mpu6050Error::WakeOnMotionRefused(MotionEngineStatus::Unverified)
+    ));
+    assert_eq!(error.recovery_hint(), RecoveryHint::FixConfiguration);
+    mpu.arm_wake_on_motion(
+        WakeOnMotionPolicy::RefuseBroken,
+        MotionDetectionConfig::DEFAULT,
+    )
+    .unwrap();
+    assert!(bus.armed());
+}
+
+#[test]
+fn operator_motion_latches_after_the_prompt() {
+    // the self-test is dead, the operator proves the engine
+    let bus = MotionBus::new(true, [0; 3]);
+    let mut mpu = awake(&bus);
+    let before = bus.snapshot();
+    let mut prompts = 0;
+    let mut prompt = || {
+        prompts += 1;
+        bus.move_after(3);
+    };
+
+    let outcome = mpu
+        .verify_motion_detection(&mut NoDelay, Some(&mut prompt))
+        .unwrap();
+    assert_eq!(prompts, 1);
+    assert_eq!(
+        outcome,
+        MotionVerifyOutcome::Latched {
+            stimulus: MotionStimulus::Operator,
+            after_ms: 3 * u32::from(MOTION_POLL_INTERVAL_MS)
+        }
+    );
+    assert_eq!(
+        mpu.capabilities().motion_engine,
+        MotionEngineStatus::Verified
+    );
+    assert_eq!(bus.snapshot(), before);
+
+    // a broken engine waits out the timeout
+    let bus = MotionBus::new(false, GENUINE_RESPONSE);
+    let mut mpu = awake(&bus);
+    let mut prompt = || bus.move_after(0);
+    let outcome = mpu
+        .verify_motion_detection(&mut NoDelay, Some(&mut prompt))
+        .unwrap();
+    assert_eq!(
+        outcome,
+        MotionVerifyOutcome::NotLatched {
+            stimulus: MotionStimulus::Operator
+        }
+    );
+    let polls = bus
+        .bus
+        .take_log()
+        .iter()
+        .filter(|access| access.reg == Register::INT_STATUS.addr())
+        .count() as u32;
+    // the settling read, one per interval and the first, the clearing read
+    assert_eq!(
+        polls,
+        PROMPT_TIMEOUT_MS / u32::from(MOTION_POLL_INTERVAL_MS) + 3
+    );
+}
+
+#[test]
+fn sleeping_chip_is_not_tested() {
+    let bus = MotionBus::new(true, GENUINE_RESPONSE);
+    let mut mpu = awake(&bus);
+    mpu.set_sleep_enabled(true).unwrap();
+    bus.bus.take_log();
+
+    let outcome = mpu.verify_motion_detection(&mut NoDelay, None).unwrap();
+    assert_eq!(outcome, MotionVerifyOutcome::Asleep);
+    assert_eq!(bus.bus.take_log(), vec![]);
+    assert_eq!(
+        mpu.capabilities().motion_engine,
+        MotionEngineStatus::Unverified
+    );
+}
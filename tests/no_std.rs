@@ -0,0 +1,71 @@
+//! Without the `std` feature the crate is no_std: the driver builds with its float math from
+//! `libm`, and glam is built without std. Runs cargo on this package in a separate target
+//! directory.
+
+use std::process::{Command, Output};
+
+fn cargo(args: &[&str]) -> Output {
+    let cargo = std::env::var("CARGO").unwrap_or_else(|_| "cargo".into());
+    let manifest = concat!(env!("CARGO_MANIFEST_DIR"), "/Cargo.toml");
+    Command::new(cargo)
+        .args(args)
+        .args(["--manifest-path", manifest])
+        .env(
+            "CARGO_TARGET_DIR",
+            concat!(env!("CARGO_MANIFEST_DIR"), "/target/no-std-check"),
+        )
+        .output()
+        .expect("cargo runs")
+}
+
+fn stderr(output: &Output) -> String {
+    String::from_utf8_lossy(&output.stderr).into_owned()
+}
+
+const DRIVER_NO_STD: [&str; 4] = [
+    "--lib",
+    "--no-default-features",
+    "--features",
+    "driver,libm",
+];
+
+#[test]
+fn glam_is_built_without_std() {
+    let output = cargo(&[
+        "tree",
+        "--no-default-features",
+        "--features",
+        "driver,libm",
+        "--edges",
+        "normal",
+        "--prefix",
+        "none",
+        "--format",
+        "{p} [{f}]",
+    ]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    let tree = String::from_utf8_lossy(&output.stdout);
+    let glam = tree
+        .lines()
+        .find(|l| l.starts_with("glam "))
+        .expect("glam in the tree");
+    assert!(glam.ends_with("[libm,num-traits]"), "{tree}");
+}
+
+#[test]
+fn driver_builds_without_std() {
+    let output = cargo(&[&["check"], &DRIVER_NO_STD[..]].concat());
+    assert!(output.status.success(), "{}", stderr(&output));
+}
+
+// the check that counts, a target without std, skipped where its core is not installed
+#[test]
+fn driver_builds_for_cortex_m() {
+    let target = ["--target", "thumbv7em-none-eabihf"];
+    let output = cargo(&[&["check"], &DRIVER_NO_STD[..], &target[..]].concat());
+    if stderr(&output).contains("target may not be installed") {
+        eprintln!("thumbv7em-none-eabihf not installed, skipped");
+        return;
+    }
+    assert!(output.status.success(), "{}", stderr(&output));
+}
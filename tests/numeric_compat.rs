@@ -0,0 +1,153 @@
+//! Numeric compatibility: the outputs of this build against the committed baseline
+//! `tests/golden/numeric_baseline.txt`, and the tolerance policy, comparison and file format
+//! of the harness, see the `numeric_compat` module. A drift fails here until the baseline is
+//! regenerated with `cargo run --example regenerate_baselines --features test-fixtures` and
+//! the diff reviewed.
+
+use mpu6050::numeric_compat::*;
+
+/// a key of `baseline` for `quantity`, sample 10 of the rotating fixture
+fn key(baseline: &Baseline, quantity: Quantity) -> Key {
+    baseline
+        .lines
+        .keys()
+        .find(|(case, sample, q)| case == "ROTATING_Z/G2/D500" && *sample == 10 && *q == quantity)
+        .cloned()
+        .unwrap()
+}
+
+#[test]
+fn outputs_match_the_baseline() {
+    let baseline = load().unwrap();
+    let report = compare(&baseline, &current());
+    assert!(report.is_clean(), "{}", report);
+    let samples: usize = CASES.iter().map(|case| case.fixture.len).sum();
+    assert_eq!(report.compared, samples * Quantity::ALL.len());
+}
+
+#[test]
+fn tolerance_per_quantity() {
+    let baseline = current();
+
+    // one ULP off in a scaled value is a drift
+    let mut drifted = baseline.clone();
+    let acc = key(&baseline, Quantity::Acc);
+    let value = &mut drifted.lines.get_mut(&acc).unwrap()[2];
+    *value = f32::from_bits(value.to_bits() + 1);
+    let report = compare(&baseline, &drifted);
+    assert_eq!(report.drifts.len(), 1);
+    assert_eq!(report.count(Quantity::Acc), 1);
+    let drift = &report.drifts[0];
+    assert_eq!(
+        (drift.case.as_str(), drift.sample),
+        ("ROTATING_Z/G2/D500", 10)
+    );
+    assert!(matches!(
+        drift.kind,
+        DriftKind::Value { component: 2, expected, actual } if expected == 1. && actual > 1.
+    ));
+    let message = report.to_string();
+    assert!(message.contains("ROTATING_Z/G2/D500 sample 10 acc[2]: baseline 1.0, now 1.0000001"));
+    assert!(message.contains("bit-exact"));
+    assert!(message.contains("regenerate_baselines"));
+
+    // so is a sign of zero
+    let mut drifted = baseline.clone();
+    let gyro = key(&baseline, Quantity::Gyro);
+    drifted.lines.get_mut(&gyro).unwrap()[0] = -0.;
+    assert_eq!(compare(&baseline, &drifted).count(Quantity::Gyro), 1);
+
+    // the fusion quantities within their epsilons
+    for (quantity, within, beyond) in [
+        (Quantity::Tilt, 5e-7, 2e-6),
+        (Quantity::Orientation, 5e-6, 2e-5),
+    ] {
+        let line = key(&baseline, quantity);
+        let mut drifted = baseline.clone();
+        drifted.lines.get_mut(&line).unwrap()[1] += within;
+        assert!(compare(&baseline, &drifted).is_clean(), "{}", quantity);
+        drifted.lines.get_mut(&line).unwrap()[1] += beyond;
+        let report = compare(&baseline, &drifted);
+        assert_eq!(report.count(quantity), 1);
+        assert!(report
+            .to_string()
+            .contains(&format!("{}: 1 ({})", quantity, quantity.tolerance())));
+    }
+}
+
+#[test]
+fn lines_missing_or_unexpected() {
+    let baseline = current();
+    let mut changed = baseline.clone();
+    let removed = key(&baseline, Quantity::Temp);
+    changed.lines.remove(&removed);
+    changed
+        .lines
+        .insert(("NEW/G2/D250".into(), 0, Quantity::Temp), vec![25.]);
+
+    let report = compare(&baseline, &changed);
+    let kinds: Vec<_> = report
+        .drifts
+        .iter()
+        .map(|drift| (drift.case.as_str(), drift.kind))
+        .collect();
+    assert_eq!(
+        kinds,
+        [
+            ("NEW/G2/D250", DriftKind::Unexpected),
+            ("ROTATING_Z/G2/D500", DriftKind::Missing)
+        ]
+    );
+    let message = report.to_string();
+    assert!(message.contains("2 of"));
+    assert!(message.contains("NEW/G2/D250 sample 0 temp: produced, not in the baseline"));
+}
+
+#[test]
+fn file_format_round_trips() {
+    let baseline = current();
+    assert_eq!(baseline.version, env!("CARGO_PKG_VERSION"));
+    let text = baseline.render();
+    assert!(text.starts_with(&format!("# baseline {}\n", baseline.version)));
+    assert_eq!(Baseline::parse(&text).unwrap(), baseline);
+    // checked out with CRLF
+    assert_eq!(
+        Baseline::parse(&text.replace('\n', "\r\n")).unwrap(),
+        baseline
+    );
+
+    for (text, line, reason) in [
+        (
+            "STATIONARY/G2/D250 0 temp 25.0\n",
+            0,
+            "no `# baseline <version>` line",
+        ),
+        (
+            "# baseline 0.2.0\nSTATIONARY/G2/D250 0 speed 1.0\n",
+            2,
+            "unknown quantity",
+        ),
+        (
+            "# baseline 0.2.0\nSTATIONARY/G2/D250 0 acc 1.0\n",
+            2,
+            "wrong number of components",
+        ),
+        (
+            "# baseline 0.2.0\nSTATIONARY/G2/D250 0 temp x\n",
+            2,
+            "not a number",
+        ),
+        (
+            "# baseline 0.2.0\nSTATIONARY/G2/D250 0 temp 1.0\nSTATIONARY/G2/D250 0 temp 1.0\n",
+            3,
+            "duplicate line",
+        ),
+    ] {
+        assert_eq!(
+            Baseline::parse(text),
+            Err(BaselineParseError { line, reason }),
+            "{}",
+            text
+        );
+    }
+}
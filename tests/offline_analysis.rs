@@ -0,0 +1,111 @@
+//! The offline analysis of the `log_analysis` example against the driver for the same
+//! recorded frames: parsed and scaled samples and the fused orientation are identical bit for
+//! bit, the hardware independent layer being the driver's implementation.
+
+mod common;
+#[allow(dead_code)]
+#[path = "../examples/log_analysis.rs"]
+mod log_analysis;
+
+use mpu6050::device::*;
+use mpu6050::frame::parse_frames;
+use mpu6050::orientation::ComplementaryFilter;
+use mpu6050::settling::SettlingPolicy;
+use mpu6050::synthetic::frame_bytes;
+use mpu6050::*;
+
+use common::{NoDelay, SharedBus};
+
+const ADDR: u8 = DEFAULT_SLAVE_ADDR;
+const DT: f32 = 0.01;
+
+/// a device rolling back and forth at ±8 g and ±1000 °/s, with a temperature drift
+fn recording() -> Vec<[u8; 14]> {
+    (0..300)
+        .map(|i| {
+            let t = i as f32 * DT;
+            let roll = 0.6 * (1.3 * t).sin();
+            let rate_dps = (0.6 * 1.3 * (1.3 * t).cos()).to_degrees();
+            let mut frame = frame_bytes(
+                Vec3A::new(0.02, roll.sin(), roll.cos()),
+                Vec3A::new(rate_dps, 0.4, -1.1),
+                AccelRange::G8,
+                GyroRange::D1000,
+            );
+            frame[6..8].copy_from_slice(&(i as i16 * 7 - 1200).to_be_bytes());
+            frame
+        })
+        .collect()
+}
+
+fn driver() -> (Mpu6050<SharedBus>, SharedBus) {
+    let bus = SharedBus::new(&[ADDR]);
+    let mut mpu = Mpu6050Builder::new()
+        .i2c(bus.clone())
+        .acc_sensitivity(AccelRange::G8)
+        .gyro_sensitivity(GyroRange::D1000)
+        .acc_offset([0.011, -0.004, 0.02])
+        .gyro_offset([0.003, -0.001, 0.002])
+        .acc_scale_factors_array([0.997, 1.002, 1.001])
+        .build()
+        .unwrap();
+    mpu.init(&mut NoDelay).unwrap();
+    mpu.set_settling_policy(SettlingPolicy::Ignore);
+    (mpu, bus)
+}
+
+fn bits(q: Quat) -> [u32; 4] {
+    q.to_array().map(f32::to_bits)
+}
+
+#[test]
+fn parsed_frames_scale_like_the_driver() {
+    let (mut mpu, bus) = driver();
+    let recording = recording();
+    let log = recording.concat();
+    let pipeline = mpu.pipeline();
+    let offline: Vec<_> = parse_frames(&log).map(|f| pipeline.sample(&f)).collect();
+    assert_eq!(offline.len(), recording.len());
+    for (frame, offline) in recording.iter().zip(offline) {
+        bus.device(ADDR, |mock| mock.set_frame(frame));
+        let sample = mpu.get_all().unwrap();
+        assert_eq!(
+            sample.acc().to_array().map(f32::to_bits),
+            offline.acc().to_array().map(f32::to_bits)
+        );
+        assert_eq!(
+            sample.gyro().to_array().map(f32::to_bits),
+            offline.gyro().to_array().map(f32::to_bits)
+        );
+        assert_eq!(sample.temp().to_bits(), offline.temp().to_bits());
+        assert_eq!(sample.provenance(), offline.provenance());
+    }
+}
+
+#[test]
+fn offline_orientation_is_the_driver_orientation() {
+    let (mut mpu, bus) = driver();
+    let recording = recording();
+    let offline = log_analysis::analyze(&recording.concat(), &mpu.pipeline(), DT);
+    assert_eq!(offline.len(), recording.len());
+
+    let mut filter = ComplementaryFilter::default();
+    for (i, (frame, offline)) in recording.iter().zip(&offline).enumerate() {
+        bus.device(ADDR, |mock| mock.set_frame(frame));
+        let online = mpu.update_orientation(&mut filter, DT).unwrap();
+        assert_eq!(bits(online), bits(*offline), "frame {i}");
+    }
+    // the filter tracked the roll, the comparison is not of identities
+    let roll = euler::to_xyz_rpy(offline[150]).roll;
+    assert!(roll.abs() > 0.1, "{roll}");
+}
+
+#[test]
+fn a_truncated_log_drops_the_partial_frame() {
+    let (mpu, _) = driver();
+    let log = recording().concat();
+    let whole = log_analysis::analyze(&log, &mpu.pipeline(), DT);
+    let cut = log_analysis::analyze(&log[..log.len() - 5], &mpu.pipeline(), DT);
+    assert_eq!(cut.len(), whole.len() - 1);
+    assert_eq!(cut[..], whole[..whole.len() - 1]);
+}
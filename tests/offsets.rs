@@ -0,0 +1,107 @@
+//! Offsets against embedded-hal-mock's blocking I2C: known raw words scaled at two ranges per
+//! sensor, the offset subtracted in physical units and kept across the range change, the
+//! runtime setters, see the `calibration` module.
+
+use embedded_hal_mock::eh0::i2c::{Mock, Transaction};
+use mpu6050::config_epoch::ConfigChange;
+use mpu6050::device::*;
+use mpu6050::settling::SettlingPolicy;
+use mpu6050::*;
+
+const ADDR: u8 = DEFAULT_SLAVE_ADDR;
+const PI_180: f32 = core::f32::consts::PI / 180.;
+
+/// big endian words as the data registers hold them
+fn words(values: [i16; 3]) -> Vec<u8> {
+    values.iter().flat_map(|v| v.to_be_bytes()).collect()
+}
+
+/// read, modify, write of one register, `before` read and `after` written
+fn update(reg: u8, before: u8, after: u8) -> [Transaction; 2] {
+    [
+        Transaction::write_read(ADDR, vec![reg], vec![before]),
+        Transaction::write(ADDR, vec![reg, after]),
+    ]
+}
+
+fn driver(transactions: &[Transaction]) -> (Mpu6050<Mock>, Mock) {
+    let mock = Mock::new(transactions);
+    let mut mpu = Mpu6050Builder::new()
+        .i2c(mock.clone())
+        .acc_offset([0.05, -0.02, 0.1])
+        .gyro_offset([0.01, 0., -0.02])
+        .build()
+        .unwrap();
+    mpu.set_settling_policy(SettlingPolicy::Ignore);
+    (mpu, mock)
+}
+
+fn assert_close(actual: Vec3A, expected: Vec3A) {
+    assert!(
+        (actual - expected).abs().max_element() < 1e-6,
+        "{actual:?} != {expected:?}"
+    );
+}
+
+#[test]
+fn acc_offsets_are_subtracted_in_g_at_both_ranges() {
+    // 0.5, -0.25 and 1 g at ±2 g, 2, -1 and 4 g at ±8 g
+    let raw = words([0x2000, -0x1000, 0x4000]);
+    let mut transactions = vec![Transaction::write_read(ADDR, vec![ACC_REGX_H], raw.clone())];
+    transactions.extend(update(ACCEL_CONFIG::ADDR, 0, 2 << 3));
+    transactions.push(Transaction::write_read(ADDR, vec![ACC_REGX_H], raw));
+    let (mut mpu, mut mock) = driver(&transactions);
+
+    let offset = Vec3A::new(0.05, -0.02, 0.1);
+    assert_close(mpu.get_acc().unwrap(), Vec3A::new(0.5, -0.25, 1.) - offset);
+    mpu.set_accel_range(AccelRange::G8).unwrap();
+    assert_eq!(mpu.get_acc_offset(), offset);
+    assert_close(mpu.get_acc().unwrap(), Vec3A::new(2., -1., 4.) - offset);
+    mock.done();
+}
+
+#[test]
+fn gyro_offsets_are_subtracted_in_rad_per_s_at_both_ranges() {
+    // 1, -2 and 0 °/s at ±250 °/s, 4, -8 and 0 °/s at ±1000 °/s
+    let raw = words([131, -262, 0]);
+    let mut transactions = vec![Transaction::write_read(
+        ADDR,
+        vec![GYRO_REGX_H],
+        raw.clone(),
+    )];
+    transactions.extend(update(GYRO_CONFIG::ADDR, 0, 2 << 3));
+    transactions.push(Transaction::write_read(ADDR, vec![GYRO_REGX_H], raw));
+    let (mut mpu, mut mock) = driver(&transactions);
+
+    let offset = Vec3A::new(0.01, 0., -0.02);
+    let d250 = Vec3A::new(1., -2., 0.) * PI_180;
+    assert_close(mpu.get_gyro().unwrap(), d250 - offset);
+    mpu.set_gyro_range(GyroRange::D1000).unwrap();
+    assert_eq!(mpu.get_gyro_offset(), offset);
+    let d1000 = Vec3A::new(131., -262., 0.) / 32.8 * PI_180;
+    assert_close(mpu.get_gyro().unwrap(), d1000 - offset);
+    mock.done();
+}
+
+#[test]
+fn setters_replace_the_offsets_and_move_the_epoch() {
+    let acc = words([0, 0, 0x4000]);
+    let gyro = words([131, 0, 0]);
+    let (mut mpu, mut mock) = driver(&[
+        Transaction::write_read(ADDR, vec![ACC_REGX_H], acc),
+        Transaction::write_read(ADDR, vec![GYRO_REGX_H], gyro),
+    ]);
+
+    let epoch = mpu.config_epoch();
+    mpu.set_acc_offset([0., 0., 0.02]);
+    assert!(mpu.config_epoch() != epoch);
+    let epoch = mpu.config_epoch();
+    mpu.set_gyro_offset(Vec3A::new(PI_180, 0., 0.));
+    assert!(mpu.config_epoch() != epoch);
+    assert_eq!(mpu.last_config_change(), Some(ConfigChange::Offsets));
+
+    assert_close(mpu.get_acc().unwrap(), Vec3A::new(0., 0., 0.98));
+    // a bias of 1 °/s, corrected away
+    assert_close(mpu.get_gyro().unwrap(), Vec3A::ZERO);
+    mock.done();
+}
@@ -0,0 +1,819 @@
+//! Complementary filter against synthetic trajectories: external corrections, bias handoff,
+//! the bias estimate on a drifting gyro, accelerometer reacquisition after sustained
+//! dynamics, the first fix from an accelerometer burst and gain scheduling, see the
+//! `orientation` module. The driver's updates read a mock register file.
+
+mod common;
+
+use mpu6050::device::{AccelRange, GyroRange, DEFAULT_SLAVE_ADDR};
+use mpu6050::orientation::{
+    ComplementaryFilter, FilterGainSchedule, InitCriteria, InitQuality, OrientationEstimate,
+    Reacquisition, ReacquisitionState, DEFAULT_ACCEL_GATE,
+};
+use mpu6050::{euler, synthetic, Mpu6050, Mpu6050Builder, Quat, Vec3A};
+
+use common::{NoDelay, SharedBus};
+
+const DT: f32 = 0.01;
+
+/// xorshift64*, the generator of the `chaos` module
+struct Noise(u64);
+
+impl Noise {
+    fn new(seed: u64) -> Self {
+        Self(seed ^ 0x9e37_79b9_7f4a_7c15)
+    }
+
+    /// uniform in -1..1
+    fn next(&mut self) -> f32 {
+        self.0 ^= self.0 >> 12;
+        self.0 ^= self.0 << 25;
+        self.0 ^= self.0 >> 27;
+        let x = self.0.wrapping_mul(0x2545_f491_4f6c_dd1d);
+        (x >> 40) as f32 / (1u64 << 23) as f32 - 1.
+    }
+
+    fn vec(&mut self, amplitude: f32) -> Vec3A {
+        Vec3A::new(self.next(), self.next(), self.next()) * amplitude
+    }
+}
+
+/// angle in rad between the gravity directions of two orientations, yaw does not count
+fn tilt_error(estimate: Quat, truth: Quat) -> f32 {
+    let a = estimate.inverse() * Vec3A::Z;
+    let b = truth.inverse() * Vec3A::Z;
+    a.dot(b).clamp(-1., 1.).acos()
+}
+
+/// One sample of a slowly rocking platform: (true orientation, gyro, accel)
+fn rocking(t: f32) -> (Quat, Vec3A, Vec3A) {
+    let roll = 0.3 * (0.5 * t).sin();
+    let pitch = 0.2 * (0.3 * t).sin();
+    let truth = Quat::from_rotation_x(roll) * Quat::from_rotation_y(pitch);
+    // body rates of roll about X then pitch about the rolled Y
+    let roll_rate = 0.15 * (0.5 * t).cos();
+    let pitch_rate = 0.06 * (0.3 * t).cos();
+    let gyro = Quat::from_rotation_y(-pitch) * Vec3A::new(roll_rate, 0., 0.)
+        + Vec3A::new(0., pitch_rate, 0.);
+    (truth, gyro, truth.inverse() * Vec3A::Z)
+}
+
+/// RMS tilt error against the true attitude over the second half of a 120 s run with a
+/// drifting gyro bias, and the RMS error of the X and Y bias estimate against the true bias
+#[cfg(feature = "test-fixtures")]
+fn drifting_gyro_rms(mut filter: ComplementaryFilter, seed: u64) -> (f32, f32) {
+    use mpu6050::error_model::{ErrorModel, GyroBiasWalk, Simulator};
+    use mpu6050::test_fixtures::Truth;
+    use mpu6050::PI_180;
+
+    // datasheet noise and quantization, the bias walking from about 1 °/s
+    let model = ErrorModel::MPU6050.with_gyro_bias(GyroBiasWalk {
+        initial_dps: Vec3A::new(0.02, -0.015, 0.01) / PI_180,
+        random_walk_dps_per_rt_s: 0.007,
+    });
+    let mut sim = Simulator::new(model, AccelRange::G2, GyroRange::D250, DT, seed);
+    let steps = 12_000;
+    let (mut tilt, mut bias) = (0., 0.);
+    let mut counted = 0;
+    for i in 0..steps {
+        let (attitude, rate, acc) = rocking(i as f32 * DT);
+        let truth = Truth {
+            acc_g: acc,
+            gyro_dps: rate / PI_180,
+            temp_c: 25.,
+        };
+        let emitted = sim.step_at(&truth, attitude);
+        let estimate = filter.update_sample(&emitted.sample, DT);
+        if i >= steps / 2 {
+            tilt += tilt_error(estimate, emitted.truth.orientation).powi(2);
+            let error = filter.bias_estimate() - emitted.truth.gyro_bias_dps * PI_180;
+            bias += error.truncate().length_squared();
+            counted += 1;
+        }
+    }
+    (
+        (tilt / counted as f32).sqrt(),
+        (bias / counted as f32).sqrt(),
+    )
+}
+
+#[test]
+fn external_correction_converges_at_documented_rate() {
+    let mut filter = ComplementaryFilter::new(0.98);
+    let external = Quat::from_rotation_x(0.4) * Quat::from_rotation_z(-0.7);
+    let initial = filter.orientation().angle_between(external);
+    for (weight, n) in [(0.25f32, 8), (0.1, 5)] {
+        filter.reset_to(Quat::IDENTITY);
+        for i in 1..=n {
+            filter.apply_external_correction(external, weight);
+            let expected = initial * (1. - weight).powi(i);
+            let remaining = filter.orientation().angle_between(external);
+            assert!(
+                (remaining - expected).abs() < 1e-3,
+                "w {} after {}: {} vs {}",
+                weight,
+                i,
+                remaining,
+                expected
+            );
+        }
+    }
+
+    // out of range confidences clamp
+    filter.reset_to(Quat::IDENTITY);
+    filter.apply_external_correction(external, 3.);
+    assert!(filter.orientation().angle_between(external) < 1e-3);
+    filter.apply_external_correction(Quat::IDENTITY, -1.);
+    filter.apply_external_correction(Quat::IDENTITY, f32::NAN);
+    assert!(filter.orientation().angle_between(external) < 1e-3);
+}
+
+#[test]
+fn external_correction_keeps_bias() {
+    let mut filter = ComplementaryFilter::new(0.98);
+    let bias = Vec3A::new(0.011, -0.007, 0.003);
+    filter.set_bias_estimate(bias);
+    filter.apply_external_correction(Quat::from_rotation_y(0.3), 0.5);
+    filter.reset_to(Quat::from_rotation_x(0.1));
+    assert_eq!(filter.bias_estimate(), bias);
+    assert_eq!(filter.orientation(), Quat::from_rotation_x(0.1));
+}
+
+#[test]
+fn bias_handoff_round_trips_exactly() {
+    let mut noise = Noise::new(3);
+    let mut source = ComplementaryFilter::new(0.98);
+    for i in 0..2000 {
+        let (_, rate, acc) = rocking(i as f32 * DT);
+        source.update(
+            rate + Vec3A::new(0.03, 0.01, 0.) + noise.vec(0.005),
+            acc,
+            DT,
+        );
+    }
+    let bias = source.bias_estimate();
+    assert_ne!(bias, Vec3A::ZERO);
+
+    // an external estimator reads the state and writes it back unchanged
+    let mut target = ComplementaryFilter::new(0.98);
+    target.set_bias_estimate(bias);
+    target.reset_to(source.orientation());
+    assert_eq!(target.bias_estimate().to_array(), bias.to_array());
+    assert_eq!(target, source);
+
+    // and both continue identically
+    for i in 2000..2100 {
+        let (_, rate, acc) = rocking(i as f32 * DT);
+        let gyro = rate + noise.vec(0.005);
+        assert_eq!(source.update(gyro, acc, DT), target.update(gyro, acc, DT));
+    }
+}
+
+#[cfg(feature = "test-fixtures")]
+#[test]
+fn bias_state_outperforms_fixed_bias_on_drifting_gyro() {
+    for seed in [1, 2, 3] {
+        let (held, _) = drifting_gyro_rms(
+            ComplementaryFilter::new(0.98).with_bias_time_constant(None),
+            seed,
+        );
+        let (estimated, bias) = drifting_gyro_rms(ComplementaryFilter::new(0.98), seed);
+        // the held filter carries alpha b dt / (1 - alpha) ≈ 0.012 rad of standing tilt error,
+        // the estimated bias leaves about 0.001 rad of noise
+        assert!(held > 0.01, "seed {}: held {}", seed, held);
+        assert!(
+            estimated < held * 0.15,
+            "seed {}: estimated {} vs held {}",
+            seed,
+            estimated,
+            held
+        );
+        // and the estimate follows the true walk on the observable axes, within a tenth of
+        // an about 0.025 rad/s bias
+        assert!(bias < 0.002, "seed {}: bias error {}", seed, bias);
+    }
+}
+
+#[test]
+fn bias_converges_with_time_constant() {
+    let bias = Vec3A::new(0.02, -0.01, 0.);
+    let mut filter = ComplementaryFilter::new(0.98).with_bias_time_constant(Some(2.));
+    // at rest and flat: x and y are observable
+    let steps = (3. * 2. / DT) as usize;
+    for _ in 0..steps {
+        filter.update(bias, Vec3A::Z, DT);
+    }
+    // three time constants leave about 5%, the attitude loop lags a little behind
+    let remaining = (filter.bias_estimate() - bias).length() / bias.length();
+    assert!(remaining < 0.1, "{}", remaining);
+    assert!(tilt_error(filter.orientation(), Quat::IDENTITY) < 1e-3);
+}
+
+/// 1.5 g: gated by a 10 % gate
+const SHAKEN: Vec3A = Vec3A::new(0., 0., 1.5);
+
+const REACQUISITION: Reacquisition = Reacquisition {
+    after_gated_s: 0.5,
+    ramp_s: 1.,
+};
+
+fn gated_filter(reacquisition: Option<Reacquisition>) -> ComplementaryFilter {
+    ComplementaryFilter::new(0.98)
+        .with_bias_time_constant(None)
+        .with_accel_gate(Some(0.1))
+        .with_reacquisition(reacquisition)
+}
+
+/// rotation angle between two orientations, precise for small angles
+fn step_angle(a: Quat, b: Quat) -> f32 {
+    2. * (a.inverse() * b).xyz().length().min(1.).asin()
+}
+
+/// 3 s of gyro-only dynamics with a rectified vibration error on the gyro, then calm:
+/// (tilt error when calm, per update steps and estimates of the calm updates)
+fn dynamics_then_calm(
+    mut filter: ComplementaryFilter,
+) -> (f32, Vec<f32>, Vec<OrientationEstimate>) {
+    for _ in 0..300 {
+        filter.update(Vec3A::new(0.1, 0., 0.), SHAKEN, DT);
+        assert!(filter.estimate().accel_gated);
+    }
+    let theta = tilt_error(filter.orientation(), Quat::IDENTITY);
+    let (mut steps, mut estimates) = (Vec::new(), Vec::new());
+    for _ in 0..400 {
+        let before = filter.orientation();
+        let after = filter.update(Vec3A::ZERO, Vec3A::Z, DT);
+        steps.push(step_angle(before, after));
+        estimates.push(filter.estimate());
+    }
+    (theta, steps, estimates)
+}
+
+#[test]
+fn reacquisition_bounds_the_step_after_sustained_dynamics() {
+    let g = 0.02f32;
+    let n = REACQUISITION.ramp_s / DT;
+
+    let (theta, snap, snap_estimates) = dynamics_then_calm(gated_filter(None));
+    assert!((theta - 0.3).abs() < 1e-3, "{}", theta);
+    let snap_max = snap.iter().cloned().fold(0., f32::max);
+    // the full correction right away
+    assert!((snap[0] - g * theta).abs() < 1e-4, "{}", snap[0]);
+    assert!(snap_estimates
+        .iter()
+        .all(|e| e.accel_weight == 1. && e.reacquisition == ReacquisitionState::Inactive));
+
+    let (theta, ramp, estimates) = dynamics_then_calm(gated_filter(Some(REACQUISITION)));
+    let ramp_max = ramp.iter().cloned().fold(0., f32::max);
+    let bound = theta * g.min((g / (core::f32::consts::E * n)).sqrt() * (g / 2.).exp());
+    assert!(
+        ramp_max <= bound * 1.01,
+        "largest step {} over the bound {}",
+        ramp_max,
+        bound
+    );
+    assert!(
+        ramp_max < snap_max * 0.45,
+        "ramp {} vs snap {}",
+        ramp_max,
+        snap_max
+    );
+
+    // monotone weight schedule, never above the normal weight
+    let weights: Vec<f32> = estimates.iter().map(|e| e.accel_weight).collect();
+    assert!((weights[0] - DT / REACQUISITION.ramp_s).abs() < 1e-6);
+    assert!(weights.windows(2).all(|w| w[0] <= w[1]));
+    assert!(weights.iter().all(|w| *w <= 1.));
+    let complete = estimates
+        .iter()
+        .position(|e| e.reacquisition == ReacquisitionState::Complete)
+        .unwrap();
+    assert!((99..=101).contains(&complete), "{}", complete);
+    let mut remaining = f32::INFINITY;
+    for e in &estimates[..complete] {
+        let ReacquisitionState::Ramping { remaining_s } = e.reacquisition else {
+            panic!("{:?}", e.reacquisition)
+        };
+        assert!(remaining_s < remaining);
+        remaining = remaining_s;
+    }
+    assert!(estimates[complete..]
+        .iter()
+        .all(|e| e.reacquisition == ReacquisitionState::Complete && e.accel_weight == 1.));
+    // reacquired in the end
+    assert!(tilt_error(estimates[399].orientation, Quat::IDENTITY) < 1e-3);
+}
+
+/// `n` updates, returns the estimate after the last
+fn run(filter: &mut ComplementaryFilter, acc: Vec3A, n: usize) -> OrientationEstimate {
+    for _ in 0..n {
+        filter.update(Vec3A::ZERO, acc, DT);
+    }
+    filter.estimate()
+}
+
+#[test]
+fn renewed_dynamics_interrupt_the_ramp() {
+    let mut filter = gated_filter(Some(REACQUISITION));
+    run(&mut filter, SHAKEN, 100);
+    let ramping = run(&mut filter, Vec3A::Z, 30);
+    assert!((ramping.accel_weight - 0.3).abs() < 1e-4);
+    let ReacquisitionState::Ramping { remaining_s } = ramping.reacquisition else {
+        panic!("{:?}", ramping.reacquisition)
+    };
+    assert!((remaining_s - 0.7).abs() < 1e-4);
+
+    // a short burst pauses the ramp, which continues where it stopped
+    let burst = run(&mut filter, SHAKEN, 20);
+    assert!(burst.accel_gated && burst.accel_weight == 0.);
+    assert!((burst.gated_s - 0.2).abs() < 1e-4);
+    assert_eq!(burst.reacquisition, ramping.reacquisition);
+    let resumed = run(&mut filter, Vec3A::Z, 1);
+    assert!(!resumed.accel_gated && resumed.gated_s == 0.);
+    assert!((resumed.accel_weight - 0.31).abs() < 1e-4);
+
+    // a long one restarts the gate and the ramp from 0
+    run(&mut filter, SHAKEN, 60);
+    let restarted = run(&mut filter, Vec3A::Z, 1);
+    assert!((restarted.accel_weight - 0.01).abs() < 1e-6);
+    let done = run(&mut filter, Vec3A::Z, 101);
+    assert_eq!(done.reacquisition, ReacquisitionState::Complete);
+
+    // complete until the next long gating, short ones snap
+    run(&mut filter, SHAKEN, 30);
+    assert_eq!(
+        run(&mut filter, Vec3A::Z, 1).reacquisition,
+        ReacquisitionState::Complete
+    );
+    let long = run(&mut filter, SHAKEN, 60);
+    assert_eq!(long.reacquisition, ReacquisitionState::Inactive);
+}
+
+#[test]
+fn gate_defaults_keep_the_filter_unchanged() {
+    let mut gated = ComplementaryFilter::new(0.98).with_reacquisition(Some(REACQUISITION));
+    let mut plain = ComplementaryFilter::new(0.98);
+    for i in 0..500 {
+        let (_, rate, acc) = rocking(i as f32 * DT);
+        // no gate configured: 1.5 g still corrects
+        let acc = if i % 7 == 0 { acc * 1.5 } else { acc };
+        assert_eq!(gated.update(rate, acc, DT), plain.update(rate, acc, DT));
+    }
+    let estimate = gated.estimate();
+    assert_eq!(estimate.reacquisition, ReacquisitionState::Inactive);
+    assert_eq!(estimate.accel_weight, 1.);
+    assert!(!estimate.accel_gated);
+
+    // an invalid gate or ramp is ignored
+    let filter = ComplementaryFilter::new(0.98)
+        .with_accel_gate(Some(f32::NAN))
+        .with_reacquisition(Some(Reacquisition {
+            after_gated_s: 1.,
+            ramp_s: 0.,
+        }));
+    assert_eq!(filter.accel_gate(), None);
+    assert_eq!(filter.reacquisition(), None);
+}
+
+/// `n` readings at rest at `roll` and `pitch` in degrees, with `noise` g of uniform noise
+fn resting_burst(roll: f32, pitch: f32, noise: f32, n: usize, seed: u64) -> Vec<Vec3A> {
+    let mut rng = Noise::new(seed);
+    let gravity = synthetic::at_rest(roll, pitch);
+    (0..n).map(|_| gravity + rng.vec(noise)).collect()
+}
+
+#[test]
+fn stationary_burst_gives_the_tilt_at_once() {
+    for (i, (roll, pitch)) in [(0., 0.), (25., -10.), (-40., 30.), (170., 5.), (10., 80.)]
+        .into_iter()
+        .enumerate()
+    {
+        let burst = resting_burst(roll, pitch, 0.01, 64, i as u64);
+        let mut filter = ComplementaryFilter::new(0.98);
+        let quality = filter.initialize_from_accel(&burst);
+        assert!(
+            matches!(quality, InitQuality::Initialized { std_dev_g } if std_dev_g < 0.01),
+            "{:?}",
+            quality
+        );
+        assert_eq!(filter.init_quality(), Some(quality));
+        let truth = euler::from_xyz_rpy(roll.to_radians(), pitch.to_radians(), 0.);
+        let error = tilt_error(filter.orientation(), truth);
+        assert!(error < 0.5f32.to_radians(), "{} {}: {}", roll, pitch, error);
+        // no yaw, and the angles back away from the pitch singularity
+        let rpy = euler::to_xyz_rpy(filter.orientation());
+        if pitch.abs() < 60. {
+            assert!(rpy.yaw.abs() < 1e-3, "{} {}: {:?}", roll, pitch, rpy);
+            assert!((rpy.roll.to_degrees() - roll).abs() < 0.5);
+            assert!((rpy.pitch.to_degrees() - pitch).abs() < 0.5);
+        }
+        assert!(!filter.estimate().initializing);
+    }
+
+    // noise-free, the tilt is exact
+    let mut filter = ComplementaryFilter::new(0.98);
+    filter.initialize_from_accel(&[synthetic::at_rest(-30., 15.); 32]);
+    let a = filter.orientation().inverse() * Vec3A::Z;
+    assert!(a.distance(synthetic::at_rest(-30., 15.)) < 1e-5);
+}
+
+#[test]
+fn unfit_bursts_are_rejected_with_their_verdict() {
+    let start = Quat::from_rotation_z(0.3);
+    let fresh = || {
+        let mut filter = ComplementaryFilter::new(0.98);
+        filter.reset_to(start);
+        filter
+    };
+
+    // a hand carrying the device: 0.3 g swings on top of gravity
+    let mut moving = resting_burst(10., 0., 0.005, 64, 7);
+    for (i, acc) in moving.iter_mut().enumerate() {
+        *acc += Vec3A::new(0.3 * (i as f32 * 0.4).sin(), 0., 0.);
+    }
+    let mut filter = fresh();
+    let quality = filter.initialize_from_accel(&moving);
+    assert!(
+        matches!(quality, InitQuality::Moving { std_dev_g } if std_dev_g > 0.1),
+        "{:?}",
+        quality
+    );
+    assert_eq!(filter.orientation(), start);
+    assert_eq!(filter.init_quality(), Some(quality));
+
+    // steady, but 1.3 g
+    let accelerating: Vec<Vec3A> = resting_burst(0., 0., 0.005, 64, 8)
+        .iter()
+        .map(|a| *a * 1.3)
+        .collect();
+    let mut filter = fresh();
+    assert!(matches!(
+        filter.initialize_from_accel(&accelerating),
+        InitQuality::NotGravity { magnitude_g } if (magnitude_g - 1.3).abs() < 0.01
+    ));
+    assert_eq!(filter.orientation(), start);
+
+    let mut filter = fresh();
+    assert_eq!(
+        filter.initialize_from_accel(&moving[..10]),
+        InitQuality::TooFewSamples { samples: 10 }
+    );
+    let mut nan = resting_burst(0., 0., 0.005, 64, 9);
+    nan[5] = Vec3A::NAN;
+    assert!(matches!(
+        filter.initialize_from_accel(&nan),
+        InitQuality::Moving { .. }
+    ));
+    assert_eq!(filter.orientation(), start);
+}
+
+#[test]
+fn auto_initialize_reports_until_the_burst_is_complete() {
+    let criteria = InitCriteria {
+        min_samples: 20,
+        ..InitCriteria::DEFAULT
+    };
+    let burst = resting_burst(-20., 35., 0.01, 20, 11);
+    let truth = euler::from_xyz_rpy((-20f32).to_radians(), 35f32.to_radians(), 0.);
+    let mut filter = ComplementaryFilter::new(0.98)
+        .with_init_criteria(criteria)
+        .with_auto_initialize(true);
+    assert!(filter.auto_initialize());
+    assert!(filter.estimate().initializing);
+
+    // held while collecting, the gyro ignored
+    for acc in &burst[..19] {
+        assert_eq!(
+            filter.update(Vec3A::new(0.5, 0., 0.), *acc, DT),
+            Quat::IDENTITY
+        );
+        assert!(filter.estimate().initializing);
+        assert_eq!(filter.init_quality(), None);
+    }
+    // the 20th update completes the burst and returns the tilt
+    let q = filter.update(Vec3A::ZERO, burst[19], DT);
+    assert!(tilt_error(q, truth) < 0.5f32.to_radians());
+    assert!(!filter.estimate().initializing);
+    assert!(filter.init_quality().unwrap().is_initialized());
+
+    // from there on a plain filter
+    let mut plain = ComplementaryFilter::new(0.98);
+    plain.reset_to(q);
+    for i in 0..50 {
+        let gyro = Vec3A::new(0.01 * i as f32, 0., 0.);
+        assert_eq!(
+            filter.update(gyro, burst[0], DT),
+            plain.update(gyro, burst[0], DT)
+        );
+    }
+}
+
+#[test]
+fn auto_initialize_retries_then_gives_up() {
+    let criteria = InitCriteria {
+        min_samples: 8,
+        max_attempts: 2,
+        ..InitCriteria::DEFAULT
+    };
+    let mut filter = ComplementaryFilter::new(0.98)
+        .with_init_criteria(criteria)
+        .with_auto_initialize(true);
+    let shaking = |i: usize| Vec3A::new(0.5 * (i as f32).sin(), 0., 1.);
+    for i in 0..8 {
+        filter.update(Vec3A::ZERO, shaking(i), DT);
+    }
+    // the first burst rejected, a second one started
+    assert!(matches!(
+        filter.init_quality(),
+        Some(InitQuality::Moving { .. })
+    ));
+    assert!(filter.estimate().initializing);
+    for i in 8..16 {
+        filter.update(Vec3A::ZERO, shaking(i), DT);
+    }
+    assert!(!filter.estimate().initializing);
+    assert_eq!(filter.orientation(), Quat::IDENTITY);
+    // runs on like a filter without initialization
+    filter.update(Vec3A::new(0.2, 0., 0.), Vec3A::Z, DT);
+    assert_ne!(filter.orientation(), Quat::IDENTITY);
+
+    // reset_to ends a pending initialization
+    let mut filter = ComplementaryFilter::new(0.98).with_auto_initialize(true);
+    filter.reset_to(Quat::from_rotation_x(0.1));
+    assert!(!filter.estimate().initializing);
+}
+
+#[cfg(feature = "test-fixtures")]
+#[test]
+fn fixtures_give_their_documented_attitude() {
+    use mpu6050::test_fixtures::{ROTATING_Z, TILTED_30};
+
+    // the first fix of a resting burst is the roll of the fixture
+    let burst: Vec<Vec3A> = (0..TILTED_30.len)
+        .map(|idx| TILTED_30.expected(idx).acc())
+        .collect();
+    let mut filter = ComplementaryFilter::new(0.98);
+    assert!(matches!(
+        filter.initialize_from_accel(&burst),
+        InitQuality::Initialized { .. }
+    ));
+    let rpy = euler::to_xyz_rpy(filter.orientation());
+    assert!((rpy.roll.to_degrees() - 30.).abs() < 0.05, "{:?}", rpy);
+    assert!(rpy.pitch.abs() < 1e-3 && rpy.yaw.abs() < 1e-3, "{:?}", rpy);
+
+    // 1 s at 90 °/s about Z, the yaw follows and the tilt stays level
+    let mut filter = ComplementaryFilter::new(0.98);
+    for idx in 0..ROTATING_Z.len {
+        filter.update_sample(&ROTATING_Z.expected(idx), ROTATING_Z.interval_s);
+    }
+    let rpy = euler::to_xyz_rpy(filter.orientation());
+    assert!((rpy.yaw.to_degrees() - 90.).abs() < 0.1, "{:?}", rpy);
+    assert!(tilt_error(filter.orientation(), Quat::IDENTITY) < 1e-3);
+}
+
+/// One sample of alternating segments, 3 s at rest and 2 s of fast rotation about X at
+/// 3 rad/s, alternating in direction, with a linear acceleration of up to 0.3 g while moving:
+/// (true rate, disturbance in g in the sensor frame)
+fn rest_and_rotation(t: f32) -> (Vec3A, Vec3A) {
+    let cycle = (t / 5.) as u32;
+    let phase = t - cycle as f32 * 5.;
+    if phase < 3. {
+        return (Vec3A::ZERO, Vec3A::ZERO);
+    }
+    let sign = if cycle.is_multiple_of(2) { 1. } else { -1. };
+    let disturbance = Vec3A::new((7. * t).sin(), (5. * t).cos(), 0.) * 0.3;
+    (Vec3A::new(3. * sign, 0., 0.), disturbance)
+}
+
+/// RMS tilt error of a 50 s run of `rest_and_rotation` with a 2 % gyro scale error, a gyro
+/// bias and noise
+fn rest_and_rotation_rms(mut filter: ComplementaryFilter, seed: u64) -> f32 {
+    let mut noise = Noise::new(seed);
+    let bias = Vec3A::new(0.01, -0.008, 0.005);
+    let mut truth = Quat::IDENTITY;
+    let steps = 5_000;
+    let mut sum = 0.;
+    for i in 0..steps {
+        let (rate, disturbance) = rest_and_rotation(i as f32 * DT);
+        truth = (truth * Quat::from_scaled_axis((rate * DT).into())).normalize();
+        let gyro = rate * 1.02 + bias + noise.vec(0.005);
+        let acc = truth.inverse() * Vec3A::Z + disturbance + noise.vec(0.01);
+        let estimate = filter.update(gyro, acc, DT);
+        sum += tilt_error(estimate, truth).powi(2);
+    }
+    (sum / steps as f32).sqrt()
+}
+
+const SCHEDULE: FilterGainSchedule = FilterGainSchedule {
+    alpha_at_rest: 0.9,
+    alpha_dynamic: 0.998,
+    gyro_rate_knee_rad_s: 0.5,
+    accel_trust_weighting: true,
+};
+
+#[test]
+fn gain_schedule_beats_either_fixed_alpha() {
+    for seed in [1, 2, 3] {
+        let rest = rest_and_rotation_rms(ComplementaryFilter::new(0.9), seed);
+        let dynamic = rest_and_rotation_rms(ComplementaryFilter::new(0.998), seed);
+        let scheduled = rest_and_rotation_rms(
+            ComplementaryFilter::new(0.98).with_gain_schedule(Some(SCHEDULE)),
+            seed,
+        );
+        let rate_only = rest_and_rotation_rms(
+            ComplementaryFilter::new(0.98).with_gain_schedule(Some(FilterGainSchedule {
+                accel_trust_weighting: false,
+                ..SCHEDULE
+            })),
+            seed,
+        );
+        // fixed 0.9 follows the disturbance, about 0.135 rad; fixed 0.998 takes seconds at
+        // rest to remove the scale error of a rotation, about 0.061 rad; scheduled about
+        // 0.047 rad, 0.044 rad on the rate alone. Asserted with a margin of 15 % on the
+        // better fixed alpha
+        let best_fixed = rest.min(dynamic);
+        for (name, rms) in [("scheduled", scheduled), ("rate only", rate_only)] {
+            assert!(
+                rms < best_fixed * 0.85,
+                "seed {}: {} {} vs fixed {} and {}",
+                seed,
+                name,
+                rms,
+                rest,
+                dynamic
+            );
+        }
+    }
+}
+
+#[test]
+fn without_a_schedule_the_fixed_alpha_steps_bit_for_bit() {
+    let constant = FilterGainSchedule {
+        alpha_at_rest: 0.98,
+        alpha_dynamic: 0.98,
+        gyro_rate_knee_rad_s: 1.,
+        accel_trust_weighting: false,
+    };
+    let mut plain = ComplementaryFilter::new(0.98);
+    let mut unset = ComplementaryFilter::new(0.98).with_gain_schedule(None);
+    let mut flat = ComplementaryFilter::new(0.98).with_gain_schedule(Some(constant));
+    let mut noise = Noise::new(4);
+    for i in 0..2000 {
+        let (rate, disturbance) = rest_and_rotation(i as f32 * DT);
+        let gyro = rate + noise.vec(0.005);
+        let acc = Vec3A::Z + disturbance + noise.vec(0.01);
+        let expected = plain.update(gyro, acc, DT);
+        for filter in [&mut unset, &mut flat] {
+            assert_eq!(filter.update(gyro, acc, DT).to_array(), expected.to_array());
+            assert_eq!(filter.bias_estimate(), plain.bias_estimate());
+        }
+    }
+    assert_eq!(plain.estimate().alpha, 0.98);
+    assert_eq!(unset.estimate(), plain.estimate());
+    assert_eq!(flat.estimate(), plain.estimate());
+}
+
+#[test]
+fn schedule_reports_the_effective_alpha() {
+    let mut filter = ComplementaryFilter::new(0.98).with_gain_schedule(Some(SCHEDULE));
+    assert_eq!(filter.estimate().alpha, 0.98);
+    // at rest and 1 g: at rest alpha, full trust
+    filter.update(Vec3A::ZERO, Vec3A::Z, DT);
+    assert_eq!(filter.estimate().alpha, 0.9);
+    // at the knee halfway
+    let knee = SCHEDULE.gyro_rate_knee_rad_s;
+    assert!((SCHEDULE.alpha(knee) - 0.949).abs() < 1e-6);
+    assert_eq!(SCHEDULE.alpha(2. * knee), 0.998);
+    assert_eq!(SCHEDULE.alpha(10.), 0.998);
+    // 1.05 g is Medium trust: half the at rest gain
+    filter.update(Vec3A::ZERO, Vec3A::Z * 1.05, DT);
+    assert!((filter.estimate().alpha - 0.95).abs() < 1e-6);
+    // 1.5 g is Low
+    filter.update(Vec3A::ZERO, Vec3A::Z * 1.5, DT);
+    assert!((filter.estimate().alpha - 0.99).abs() < 1e-6);
+
+    // a knee that is not positive disables the schedule, weights are clamped
+    let off = ComplementaryFilter::new(0.98).with_gain_schedule(Some(FilterGainSchedule {
+        gyro_rate_knee_rad_s: 0.,
+        ..SCHEDULE
+    }));
+    assert_eq!(off.gain_schedule(), None);
+    let clamped = ComplementaryFilter::new(0.98).with_gain_schedule(Some(FilterGainSchedule {
+        alpha_dynamic: 1.5,
+        ..SCHEDULE
+    }));
+    assert_eq!(clamped.gain_schedule().unwrap().alpha_dynamic, 1.);
+}
+
+fn driver_on(bus: &SharedBus) -> Mpu6050<SharedBus> {
+    let mut mpu = Mpu6050Builder::new().i2c(bus.clone()).build().unwrap();
+    mpu.init(&mut NoDelay).unwrap();
+    mpu
+}
+
+/// registers of a reading in g and °/s at the reset ranges
+fn set_reading(bus: &SharedBus, acc_g: Vec3A, gyro_dps: Vec3A) {
+    let bytes = synthetic::frame_bytes(acc_g, gyro_dps, AccelRange::G2, GyroRange::D250);
+    bus.device(DEFAULT_SLAVE_ADDR, |m| m.set_frame(&bytes));
+}
+
+#[test]
+fn driver_updates_follow_a_constant_rotation() {
+    let bus = SharedBus::new(&[DEFAULT_SLAVE_ADDR]);
+    let mut mpu = driver_on(&bus);
+    let mut filter = ComplementaryFilter::new(0.98);
+    // 10 °/s about the vertical, level: pure yaw, gravity agrees with the prediction
+    set_reading(&bus, Vec3A::Z, Vec3A::new(0., 0., 10.));
+    bus.take_log();
+    for _ in 0..300 {
+        mpu.update_orientation(&mut filter, DT).unwrap();
+    }
+    // one burst per update
+    assert_eq!(bus.take_log().len(), 300);
+    let rpy = euler::to_xyz_rpy(filter.orientation());
+    assert!((rpy.yaw.to_degrees() - 30.).abs() < 0.05, "{:?}", rpy);
+    assert!(rpy.roll.abs() < 1e-4 && rpy.pitch.abs() < 1e-4, "{:?}", rpy);
+    // reading the estimate again needs no transaction
+    assert_eq!(filter.estimate().orientation, filter.orientation());
+    assert!(bus.take_log().is_empty());
+}
+
+#[cfg(feature = "test-fixtures")]
+#[test]
+fn driver_updates_at_rest_with_noise_stay_bounded() {
+    use mpu6050::error_model::{ErrorModel, GyroBiasWalk, Simulator, WhiteNoise};
+    use mpu6050::test_fixtures::Truth;
+
+    let bus = SharedBus::new(&[DEFAULT_SLAVE_ADDR]);
+    let mut mpu = driver_on(&bus);
+    let mut filter = ComplementaryFilter::new(0.98);
+    // a noisy board, four and eight times the datasheet densities: 0.3 °/s of noise around
+    // a bias of 0.2 °/s
+    let model = ErrorModel::MPU6050
+        .with_white_noise(WhiteNoise {
+            accel_g_per_rt_hz: 1.6e-3,
+            gyro_dps_per_rt_hz: 0.04,
+            bandwidth_hz: None,
+        })
+        .with_gyro_bias(GyroBiasWalk {
+            initial_dps: Vec3A::splat(0.2),
+            random_walk_dps_per_rt_s: 0.,
+        });
+    let mut sim = Simulator::new(model, AccelRange::G2, GyroRange::D250, DT, 11)
+        .with_orientation(Quat::from_rotation_x(20f32.to_radians()));
+    let truth = Truth {
+        acc_g: synthetic::at_rest(20., 0.),
+        ..Truth::FLAT
+    };
+    let mut worst = 0f32;
+    for i in 0..3000 {
+        let emitted = sim.step(&truth);
+        bus.device(DEFAULT_SLAVE_ADDR, |m| m.set_frame(&emitted.bytes()));
+        mpu.update_orientation(&mut filter, DT).unwrap();
+        if i >= 500 {
+            worst = worst.max(tilt_error(filter.orientation(), emitted.truth.orientation));
+        }
+    }
+    assert!(worst.to_degrees() < 1., "{}", worst.to_degrees());
+}
+
+#[test]
+fn free_fall_skips_the_correction() {
+    let bus = SharedBus::new(&[DEFAULT_SLAVE_ADDR]);
+    let mut mpu = driver_on(&bus);
+    // gated by default
+    let mut filter = ComplementaryFilter::new(0.9);
+    assert_eq!(filter.accel_gate(), Some(DEFAULT_ACCEL_GATE));
+    set_reading(&bus, Vec3A::Z, Vec3A::ZERO);
+    mpu.update_orientation(&mut filter, DT).unwrap();
+    let level = filter.orientation();
+
+    // falling: a few hundredths of a g, mostly sideways, would pull the tilt over
+    set_reading(&bus, Vec3A::new(0.04, 0.03, 0.01), Vec3A::ZERO);
+    for _ in 0..50 {
+        mpu.update_orientation(&mut filter, DT).unwrap();
+    }
+    assert!(filter.estimate().accel_gated);
+    assert_eq!(filter.orientation(), level);
+
+    // only with the gate disabled do the same readings tilt it
+    let mut ungated = ComplementaryFilter::new(0.9).with_accel_gate(None);
+    for _ in 0..50 {
+        mpu.update_orientation(&mut ungated, DT).unwrap();
+    }
+    assert!(tilt_error(ungated.orientation(), level).to_degrees() > 30.);
+}
+
+#[test]
+fn driver_updates_re_enter_after_a_sleep() {
+    let bus = SharedBus::new(&[DEFAULT_SLAVE_ADDR]);
+    let mut mpu = driver_on(&bus);
+    let mut filter = ComplementaryFilter::new(0.98);
+    set_reading(&bus, Vec3A::Z, Vec3A::ZERO);
+    mpu.update_orientation(&mut filter, DT).unwrap();
+    mpu.set_sleep_enabled(true).unwrap();
+    mpu.set_sleep_enabled(false).unwrap();
+    mpu.update_orientation(&mut filter, 600.).unwrap();
+    let estimate = filter.estimate();
+    assert!(estimate.continuity_break && estimate.initializing);
+}
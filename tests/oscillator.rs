@@ -0,0 +1,321 @@
+//! Clock error estimation: confidence intervals over scripted sample streams, recovery of a
+//! skewed rate from a fake clocked sensor, users of the correction, see the `oscillator`
+//! module.
+
+mod common;
+
+use std::cell::Cell;
+use std::convert::Infallible;
+use std::rc::Rc;
+
+use embedded_hal::blocking::delay::DelayMs;
+use embedded_hal::blocking::i2c::{Write, WriteRead};
+use mpu6050::device::*;
+use mpu6050::fifo::FifoSources;
+use mpu6050::interpolation::TimedSample;
+use mpu6050::oscillator::*;
+use mpu6050::settings::{Mpu6050Settings, SettingsError};
+use mpu6050::*;
+
+use common::{NoDelay, RegisterMock};
+
+/// minimal LCG, uniform in [0, 1)
+struct Lcg(u64);
+
+impl Lcg {
+    fn next(&mut self) -> f64 {
+        self.0 = self
+            .0
+            .wrapping_mul(6364136223846793005)
+            .wrapping_add(1442695040888963407);
+        (self.0 >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
+
+/// samples at `phase_us + k / rate` in (from_us, to_us]
+fn samples_between(rate_hz: f64, phase_us: f64, from_us: f64, to_us: f64) -> u32 {
+    let index = |t: f64| ((t - phase_us) * rate_hz * 1e-6).floor();
+    (index(to_us) - index(from_us)) as u32
+}
+
+#[test]
+fn confidence_covers_scripted_streams() {
+    let mut rng = Lcg(7);
+    let mut covered = 0;
+    let runs = 200;
+    for run in 0..runs {
+        // -3 % to +3 %, 100 Hz to 1 kHz
+        let ratio = 0.97 + 0.06 * rng.next();
+        let nominal = [100., 250., 1000.][run % 3];
+        let rate = nominal * ratio;
+        let mut counter = ClockCounter::new(nominal as f32);
+        let mut t = 1e6 * rng.next();
+        for _ in 0..8 {
+            // host delay jitter of up to 0.5 ms, the counter sees the nominal window
+            let actual = 200_000. + 500. * rng.next();
+            counter.add_window(samples_between(rate, 0., t, t + actual), 200_000);
+            t += actual + 1000. * rng.next();
+        }
+        let estimate = counter.estimate().unwrap();
+        // the jitter is a bias of up to 0.25 %, a real measurement has to keep it small
+        let biased = ratio * (1. + 250. / 200_000.);
+        if (estimate.ratio_low as f64) <= biased && biased <= estimate.ratio_high as f64 + 2e-3 {
+            covered += 1;
+        }
+        assert_eq!(estimate.windows, 8);
+        assert_eq!(estimate.duration_us, 1_600_000);
+    }
+    assert!(covered >= runs * 95 / 100, "{} of {}", covered, runs);
+
+    // exact windows: the quantization bound alone covers every phase
+    for phase in 0..100 {
+        let rate = 1000. * 1.0123;
+        let mut counter = ClockCounter::new(1000.);
+        let mut t = phase as f64 * 9.7;
+        for _ in 0..4 {
+            counter.add_window(samples_between(rate, 0., t, t + 250_000.), 250_000);
+            t += 250_000.;
+        }
+        let estimate = counter.estimate().unwrap();
+        assert!(
+            (estimate.ratio_low as f64) <= 1.0123 && 1.0123 <= estimate.ratio_high as f64,
+            "phase {}: {}",
+            phase,
+            estimate
+        );
+        assert!(estimate.confidence_ppm() < 2000.);
+    }
+}
+
+#[test]
+fn counter_edge_cases() {
+    let mut counter = ClockCounter::new(1000.);
+    assert_eq!(counter.estimate(), None);
+    counter.add_window(5, 0);
+    assert_eq!(counter.windows(), 0);
+
+    counter.add_window(100, 100_000);
+    let estimate = counter.estimate().unwrap();
+    assert_eq!(estimate.ratio, 1.);
+    assert_eq!(estimate.error_ppm(), 0.);
+    // one window: quantization only, 1.96 * 0.5 / 100
+    assert!((estimate.confidence_ppm() - 9800.).abs() < 1.);
+    assert!(estimate.to_string().starts_with("clock +0 ppm ± 9800 ppm"));
+
+    assert_eq!(window_ms(1000.), 250);
+    assert_eq!(window_ms(8000.), 48);
+    assert_eq!(window_ms(4.), MAX_WINDOW_MS);
+}
+
+/// shared host time in µs
+type Clock = Rc<Cell<f64>>;
+
+/// delay advancing the fake host clock
+struct FakeDelay(Clock);
+
+impl DelayMs<u8> for FakeDelay {
+    fn delay_ms(&mut self, ms: u8) {
+        self.0.set(self.0.get() + ms as f64 * 1000.);
+    }
+}
+
+/// register mock whose FIFO fills at `rate_hz` of the fake clock, every transaction takes
+/// `bus_us`
+struct ClockedSensor {
+    regs: RegisterMock,
+    clock: Clock,
+    rate_hz: f64,
+    phase_us: f64,
+    bus_us: f64,
+    reset_us: f64,
+}
+
+impl ClockedSensor {
+    fn tick(&self) {
+        self.clock.set(self.clock.get() + self.bus_us);
+    }
+}
+
+impl Write for ClockedSensor {
+    type Error = Infallible;
+
+    fn write(&mut self, address: u8, bytes: &[u8]) -> Result<(), Infallible> {
+        self.regs.write(address, bytes)?;
+        let user_ctrl = &mut self.regs.regs[USER_CTRL::ADDR as usize];
+        if bytes[0] == USER_CTRL::ADDR && *user_ctrl & (1 << USER_CTRL::FIFO_RESET) != 0 {
+            // self clearing
+            *user_ctrl &= !(1 << USER_CTRL::FIFO_RESET);
+            self.reset_us = self.clock.get();
+        }
+        self.tick();
+        Ok(())
+    }
+}
+
+impl WriteRead for ClockedSensor {
+    type Error = Infallible;
+
+    fn write_read(
+        &mut self,
+        address: u8,
+        bytes: &[u8],
+        buffer: &mut [u8],
+    ) -> Result<(), Infallible> {
+        if bytes[0] == FIFO_COUNT_H {
+            let samples =
+                samples_between(self.rate_hz, self.phase_us, self.reset_us, self.clock.get());
+            let count = (samples * 2).min(1024) as u16;
+            buffer.copy_from_slice(&count.to_be_bytes()[..buffer.len()]);
+        } else {
+            self.regs.write_read(address, bytes, buffer)?;
+        }
+        self.tick();
+        Ok(())
+    }
+}
+
+#[test]
+fn skewed_rate_is_recovered() {
+    let clock = Clock::default();
+    // fewer samples at low rates, wider intervals
+    for (ratio, dlpf, div, max_ppm) in [
+        (1.0237, 1, 0, 2000.),
+        (0.9811, 1, 9, 16_000.),
+        (1.004, 0, 0, 500.),
+    ] {
+        let nominal = Mpu6050Settings::new()
+            .with_dlpf_cfg(dlpf)
+            .with_sample_rate_div(div)
+            .sample_rate_hz() as f64;
+        let sensor = ClockedSensor {
+            regs: RegisterMock::new(),
+            clock: clock.clone(),
+            rate_hz: nominal * ratio,
+            phase_us: 37.,
+            bus_us: 50.,
+            reset_us: 0.,
+        };
+        let mut mpu = Mpu6050Builder::new().i2c(sensor).build().unwrap();
+        mpu.init(&mut NoDelay).unwrap();
+        mpu.set_dlpf(dlpf).unwrap();
+        mpu.set_sample_rate_divider(div).unwrap();
+        let sources = FifoSources::NONE.with_accel(true);
+        mpu.set_fifo_sources(sources).unwrap();
+        mpu.reset_io_stats();
+
+        let measurement_ms = 2000;
+        let estimate = mpu
+            .estimate_clock_error(&mut FakeDelay(clock.clone()), measurement_ms)
+            .unwrap();
+        // the FIFO_COUNT read lengthens every window by one transaction
+        let window_us = window_ms(nominal as f32) as f64 * 1000.;
+        let measured = ratio * (1. + 50. / window_us);
+        assert!(
+            estimate.ratio_low as f64 <= measured && measured <= estimate.ratio_high as f64,
+            "{}: {}",
+            ratio,
+            estimate
+        );
+        assert!(estimate.confidence_ppm() < max_ppm, "{}", estimate);
+        assert_eq!(estimate.nominal_odr_hz as f64, nominal);
+        assert_eq!(estimate.duration_us, measurement_ms as u64 * 1000);
+
+        // bounded bus overhead: 3 per window, setup and restore once
+        let windows = estimate.windows;
+        assert_eq!(windows, measurement_ms.div_ceil(window_ms(nominal as f32)));
+        let transactions = mpu.io_stats().transactions;
+        assert!(
+            transactions <= windows * TRANSACTIONS_PER_WINDOW + 8,
+            "{} transactions for {} windows",
+            transactions,
+            windows
+        );
+        assert_eq!(mpu.get_fifo_sources(), sources);
+        assert!(!mpu.fifo_streaming());
+
+        mpu.apply_clock_correction(estimate.ratio).unwrap();
+        assert!((mpu.effective_odr_hz() as f64 - nominal * ratio).abs() < nominal * 2e-3);
+    }
+}
+
+#[test]
+fn measurement_is_refused_while_the_fifo_or_cycle_mode_runs() {
+    let mut mpu = Mpu6050Builder::new()
+        .i2c(RegisterMock::new())
+        .build()
+        .unwrap();
+    mpu.init(&mut NoDelay).unwrap();
+    mpu.set_fifo_enabled(true).unwrap();
+    assert!(matches!(
+        mpu.estimate_clock_error(&mut NoDelay, 100),
+        Err(Mpu6050Error::FifoActive)
+    ));
+    mpu.set_fifo_enabled(false).unwrap();
+    mpu.apply_settings(
+        &Mpu6050Settings::new()
+            .with_cycle(Some(LP_WAKE_CTRL::_10))
+            .with_clock_source(CLKSEL::OSCILL),
+    )
+    .unwrap();
+    mpu.reset_io_stats();
+    assert!(matches!(
+        mpu.estimate_clock_error(&mut NoDelay, 100),
+        Err(Mpu6050Error::InvalidSettings(
+            SettingsError::ClockMeasurementInCycle
+        ))
+    ));
+    assert_eq!(mpu.io_stats().transactions, 0);
+}
+
+#[test]
+fn correction_reaches_intervals_resampler_and_fifo_timestamps() {
+    let mut mpu = Mpu6050Builder::new()
+        .i2c(RegisterMock::new())
+        .build()
+        .unwrap();
+    mpu.init(&mut NoDelay).unwrap();
+    mpu.set_dlpf(1).unwrap();
+    mpu.set_sample_rate_divider(9).unwrap();
+    assert_eq!(mpu.clock_correction(), 1.);
+    assert_eq!(mpu.effective_odr_hz(), 100.);
+    assert_eq!(mpu.nominal_sample_interval_us(), 10_000.);
+    assert!(mpu
+        .fifo_frame_timestamps_us(1_000_000, 3)
+        .eq([980_000, 990_000, 1_000_000]));
+
+    // 2 % slow
+    mpu.apply_clock_correction(0.98).unwrap();
+    assert_eq!(mpu.effective_odr_hz(), 98.);
+    assert!((mpu.nominal_sample_interval_us() - 10_204.08).abs() < 0.01);
+    assert!(mpu
+        .fifo_frame_timestamps_us(1_000_000, 3)
+        .eq([979_592, 989_796, 1_000_000]));
+    assert_eq!(mpu.debug_state().clock_ratio, 0.98);
+
+    // samples arriving at the corrected interval show no skew
+    let mut resampler = mpu.uniform_resampler(50, 100_000);
+    for k in 0..=98u64 {
+        let sample = MpuSample::new(Vec3A::Z, Vec3A::ZERO, 25.);
+        let t_us = (k as f64 * 1e6 / 98.).round() as u64;
+        resampler
+            .push(TimedSample {
+                t_us,
+                sample,
+                orientation: None,
+            })
+            .unwrap();
+    }
+    assert!(resampler.clock_skew_us().abs() < 50.);
+
+    assert_eq!(
+        mpu.apply_clock_correction(1.2),
+        Err(SettingsError::ClockCorrection)
+    );
+    assert_eq!(
+        mpu.apply_clock_correction(f32::NAN),
+        Err(SettingsError::ClockCorrection)
+    );
+    assert_eq!(mpu.clock_correction(), 0.98);
+    mpu.apply_clock_correction(1.).unwrap();
+    assert_eq!(mpu.effective_odr_hz(), 100.);
+}
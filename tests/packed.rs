@@ -0,0 +1,302 @@
+//! Round trips of the reduced precision export: seeded random batches at both bit widths and
+//! every range, saturation, partial final bytes and the CRC, see the `packed` module.
+
+use mpu6050::conversion::{self, RoundingMode};
+use mpu6050::device::{AccelRange, GyroRange, ACCEL_SENS, GYRO_SENS};
+use mpu6050::packed::*;
+use mpu6050::{MpuSample, Vec3A};
+
+const ACCEL_RANGES: [AccelRange; 4] = [
+    AccelRange::G2,
+    AccelRange::G4,
+    AccelRange::G8,
+    AccelRange::G16,
+];
+const GYRO_RANGES: [GyroRange; 4] = [
+    GyroRange::D250,
+    GyroRange::D500,
+    GyroRange::D1000,
+    GyroRange::D2000,
+];
+
+/// LSB/g of a range
+fn acc_sens(range: AccelRange) -> f32 {
+    let (g2, g4, g8, g16) = ACCEL_SENS;
+    [g2, g4, g8, g16][range as usize]
+}
+
+/// LSB/(°/s) of a range
+fn gyro_sens(range: GyroRange) -> f32 {
+    let (d250, d500, d1000, d2000) = GYRO_SENS;
+    [d250, d500, d1000, d2000][range as usize]
+}
+
+/// xorshift64*
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self) -> u32 {
+        self.0 ^= self.0 >> 12;
+        self.0 ^= self.0 << 25;
+        self.0 ^= self.0 >> 27;
+        (self.0.wrapping_mul(0x2545_f491_4f6c_dd1d) >> 32) as u32
+    }
+
+    /// uniform in `lo..=hi`
+    fn range(&mut self, lo: i32, hi: i32) -> i32 {
+        lo + (self.next() % (hi - lo + 1) as u32) as i32
+    }
+}
+
+fn sample_from_counts(
+    counts: [i16; 7],
+    accel_range: AccelRange,
+    gyro_range: GyroRange,
+) -> MpuSample {
+    let [ax, ay, az, gx, gy, gz, temp] = counts;
+    MpuSample::new(
+        conversion::counts_to_units([ax, ay, az], acc_sens(accel_range)),
+        conversion::counts_to_rad_s([gx, gy, gz], gyro_sens(gyro_range)),
+        conversion::counts_to_celsius(temp),
+    )
+}
+
+fn round_trip(
+    samples: &[MpuSample],
+    bits: PackedBits,
+    accel_range: AccelRange,
+    gyro_range: GyroRange,
+) -> (PackedBatch, Vec<MpuSample>) {
+    let mut buf = vec![0; packed_len(samples.len(), bits)];
+    let len = encode_binary_packed(samples, bits, accel_range, gyro_range, &mut buf).unwrap();
+    assert_eq!(len, buf.len());
+    let mut out = vec![MpuSample::default(); samples.len()];
+    let batch = decode_binary_packed(&buf, &mut out).unwrap();
+    assert_eq!(batch.count, samples.len());
+    (batch, out)
+}
+
+#[test]
+fn representable_values_round_trip_exactly() {
+    let mut rng = Rng(0x9ac_4ed);
+    for bits in [PackedBits::B12, PackedBits::B10] {
+        let width = bits as u32;
+        let (lo, hi) = (-(1 << (width - 1)), (1 << (width - 1)) - 1);
+        for accel_range in ACCEL_RANGES {
+            for gyro_range in GYRO_RANGES {
+                let n = rng.range(1, 40) as usize;
+                let samples: Vec<_> = (0..n)
+                    .map(|_| {
+                        let counts =
+                            [0; 7].map(|_: i16| conversion::widen(rng.range(lo, hi) as i16, width));
+                        sample_from_counts(counts, accel_range, gyro_range)
+                    })
+                    .collect();
+                let (batch, out) = round_trip(&samples, bits, accel_range, gyro_range);
+                assert_eq!(
+                    (batch.bits, batch.accel_range, batch.gyro_range),
+                    (bits, accel_range, gyro_range)
+                );
+                assert!(!batch.saturated && !batch.provenance);
+                assert_eq!(out, samples, "{bits:?} {accel_range:?} {gyro_range:?}");
+            }
+        }
+    }
+}
+
+#[test]
+fn arbitrary_values_are_within_half_a_step() {
+    let mut rng = Rng(0x5eed_1e55);
+    for bits in [PackedBits::B12, PackedBits::B10] {
+        // half a reduced step in counts, plus the float rounding of the scaled values
+        let half_step = (1u32 << (15 - bits as u32)) as f32 + 0.01;
+        for (accel_range, gyro_range) in ACCEL_RANGES.into_iter().zip(GYRO_RANGES) {
+            let samples: Vec<_> = (0..33)
+                .map(|_| {
+                    let counts = [0; 7].map(|_: i16| rng.range(-30_000, 30_000) as i16);
+                    sample_from_counts(counts, accel_range, gyro_range)
+                })
+                .collect();
+            let (batch, out) = round_trip(&samples, bits, accel_range, gyro_range);
+            assert!(!batch.saturated);
+            let acc_tol = half_step / acc_sens(accel_range);
+            let gyro_tol = (half_step / gyro_sens(gyro_range)).to_radians();
+            let temp_tol = half_step / 340.;
+            for (decoded, original) in out.iter().zip(&samples) {
+                assert!((decoded.acc() - original.acc()).abs().max_element() <= acc_tol);
+                assert!((decoded.gyro() - original.gyro()).abs().max_element() <= gyro_tol);
+                assert!((decoded.temp() - original.temp()).abs() <= temp_tol);
+            }
+        }
+    }
+}
+
+#[test]
+fn narrowing_rounds_to_nearest_even() {
+    // 12 bits: steps of 16 counts, ties 8 and 24 go to the even reduced value
+    let counts =
+        |acc_x| sample_from_counts([acc_x, 0, 0, 0, 0, 0, 0], AccelRange::G2, GyroRange::D250);
+    let samples = [counts(8), counts(24), counts(9), counts(-8), counts(-25)];
+    let (_, out) = round_trip(&samples, PackedBits::B12, AccelRange::G2, GyroRange::D250);
+    let decoded: Vec<_> = out
+        .iter()
+        .map(|s| (s.acc().x * acc_sens(AccelRange::G2)) as i32)
+        .collect();
+    assert_eq!(decoded, [0, 32, 16, 0, -32]);
+    assert_eq!(
+        conversion::narrow(24, 12, RoundingMode::default()),
+        (2, false)
+    );
+}
+
+#[test]
+fn values_beyond_the_reduced_range_saturate_and_flag() {
+    let at_rest = MpuSample::new(Vec3A::Z, Vec3A::ZERO, 25.);
+    for bits in [PackedBits::B12, PackedBits::B10] {
+        let width = bits as u32;
+        let max = conversion::widen((1 << (width - 1)) - 1, width);
+        let min = conversion::widen(-(1 << (width - 1)), width);
+
+        // full scale counts: 32767 rounds up past the largest reduced value, -32768 fits
+        let edge = sample_from_counts(
+            [i16::MAX, i16::MIN, 0, i16::MAX, 0, 0, 0],
+            AccelRange::G4,
+            GyroRange::D500,
+        );
+        let (batch, out) = round_trip(&[at_rest, edge], bits, AccelRange::G4, GyroRange::D500);
+        assert!(batch.saturated, "{bits:?}");
+        let acc = out[1].acc() * acc_sens(AccelRange::G4);
+        assert_eq!([acc.x as i16, acc.y as i16], [max, min]);
+        // the other sample is unaffected
+        assert_eq!(out[0].acc(), Vec3A::Z);
+
+        // outside the recorded range, the scaled values are clipped
+        let over = MpuSample::new(Vec3A::new(3., -5., 0.), Vec3A::new(0., 100., 0.), 25.);
+        let (batch, out) = round_trip(&[over], bits, AccelRange::G2, GyroRange::D250);
+        assert!(batch.saturated);
+        let acc = out[0].acc() * acc_sens(AccelRange::G2);
+        assert_eq!([acc.x as i16, acc.y as i16], [max, min]);
+        assert!(out[0].gyro().y > 4.3);
+
+        let (batch, _) = round_trip(&[at_rest; 3], bits, AccelRange::G2, GyroRange::D250);
+        assert!(!batch.saturated);
+    }
+}
+
+#[test]
+fn partial_final_bytes_are_zero_padded() {
+    let sample = sample_from_counts([-64; 7], AccelRange::G2, GyroRange::D250);
+    for bits in [PackedBits::B12, PackedBits::B10] {
+        let width = bits as usize;
+        for n in 1..=8 {
+            let samples = vec![sample; n];
+            let mut buf = vec![0; packed_len(n, bits)];
+            encode_binary_packed(&samples, bits, AccelRange::G2, GyroRange::D250, &mut buf)
+                .unwrap();
+            let used_bits = n * VALUES_PER_SAMPLE * width;
+            let last = PACKED_HEADER_LEN + used_bits.div_ceil(8) - 1;
+            assert_eq!(last + 1 + PACKED_CRC_LEN, buf.len());
+            let padding = (8 - used_bits % 8) % 8;
+            assert_eq!(buf[last] & ((1u16 << padding) - 1) as u8, 0, "{bits:?} {n}");
+
+            let mut out = vec![MpuSample::default(); n];
+            decode_binary_packed(&buf, &mut out).unwrap();
+            assert_eq!(out, samples);
+        }
+    }
+}
+
+#[test]
+fn sizes_follow_the_documented_table() {
+    // 8 header and CRC bytes, then ceil(10.5 N) or ceil(8.75 N)
+    for (n, b12, b10) in [
+        (1, 19, 17),
+        (2, 29, 26),
+        (3, 40, 35),
+        (4, 50, 43),
+        (100, 1058, 883),
+    ] {
+        assert_eq!(packed_len(n, PackedBits::B12), b12);
+        assert_eq!(packed_len(n, PackedBits::B10), b10);
+    }
+    // the header is paid once per batch
+    let per_sample = |n| packed_len(n, PackedBits::B12) as f32 / n as f32;
+    assert!(per_sample(1) > per_sample(10) && per_sample(10) > per_sample(100));
+    assert!(per_sample(100) < 14. * 0.76);
+    assert_eq!(packed_len_with_provenance(4, PackedBits::B10), 43 + 4 * 3);
+}
+
+#[test]
+fn corrupted_batches_are_refused() {
+    let samples = [MpuSample::new(Vec3A::new(0.1, -0.5, 1.), Vec3A::ZERO, 30.); 3];
+    let mut buf = vec![0; packed_len(3, PackedBits::B12)];
+    encode_binary_packed(
+        &samples,
+        PackedBits::B12,
+        AccelRange::G2,
+        GyroRange::D250,
+        &mut buf,
+    )
+    .unwrap();
+    let mut out = [MpuSample::default(); 3];
+
+    // any flipped bit past the version and width, ranges, flags, count, values and CRC alike
+    for byte in 2..buf.len() {
+        for bit in 0..8 {
+            let mut corrupt = buf.clone();
+            corrupt[byte] ^= 1 << bit;
+            let result = decode_binary_packed(&corrupt, &mut out);
+            assert!(
+                matches!(
+                    result,
+                    Err(DecodeError::CrcMismatch | DecodeError::Truncated(_))
+                ),
+                "byte {byte} bit {bit}: {result:?}"
+            );
+        }
+    }
+    buf[1] = 16;
+    assert_eq!(
+        decode_binary_packed(&buf, &mut out),
+        Err(DecodeError::BadHeader)
+    );
+    buf[1] = 12;
+
+    let len = buf.len();
+    assert_eq!(
+        decode_binary_packed(&buf[..len - 1], &mut out),
+        Err(DecodeError::Truncated(len))
+    );
+    assert_eq!(
+        decode_binary_packed(&buf, &mut out[..2]),
+        Err(DecodeError::OutputTooSmall(3))
+    );
+    assert!(decode_binary_packed(&buf, &mut out).is_ok());
+}
+
+#[test]
+fn encoder_checks_the_buffer_and_the_sample_count() {
+    let samples = [MpuSample::default(); 5];
+    let need = packed_len(5, PackedBits::B10);
+    assert_eq!(
+        encode_binary_packed(
+            &samples,
+            PackedBits::B10,
+            AccelRange::G2,
+            GyroRange::D250,
+            &mut vec![0; need - 1],
+        ),
+        Err(EncodeError::BufferTooSmall(need))
+    );
+    let many = vec![MpuSample::default(); u16::MAX as usize + 1];
+    assert_eq!(
+        encode_binary_packed(
+            &many,
+            PackedBits::B10,
+            AccelRange::G2,
+            GyroRange::D250,
+            &mut [],
+        ),
+        Err(EncodeError::TooManySamples(many.len()))
+    );
+}
@@ -0,0 +1,227 @@
+//! Parse policies: each structural check against crafted corrupt frames, strict rejection,
+//! the diagnostics ring, and lenient parsing bit for bit as without the checks, see the
+//! `parse_policy` module.
+
+mod common;
+
+use std::convert::Infallible;
+
+use mpu6050::device::*;
+use mpu6050::frame::{encode_frame, RawFrame};
+use mpu6050::hint::RecoveryHint;
+use mpu6050::parse_policy::*;
+use mpu6050::scale::TempModel;
+use mpu6050::synthetic::frame_bytes;
+use mpu6050::*;
+
+use common::{NoDelay, SharedBus};
+
+const ADDR: u8 = DEFAULT_SLAVE_ADDR;
+
+fn at_rest() -> [u8; 14] {
+    frame_bytes(
+        Vec3A::Z,
+        Vec3A::new(1., -2., 0.5),
+        AccelRange::G2,
+        GyroRange::D250,
+    )
+}
+
+fn with_temp(raw: i16) -> [u8; 14] {
+    let bytes = at_rest();
+    let mut frame = mpu6050::frame::parse_frame(&bytes);
+    frame.temp = raw;
+    encode_frame(&frame)
+}
+
+/// one crafted frame per check
+fn corrupt_frames() -> [([u8; 14], FrameCheck); 5] {
+    [
+        ([0; 14], FrameCheck::AllZero),
+        ([0xff; 14], FrameCheck::IdenticalBytes { byte: 0xff }),
+        ([0x55; 14], FrameCheck::IdenticalBytes { byte: 0x55 }),
+        (
+            with_temp(30100),
+            FrameCheck::TemperatureOutOfRange { raw: 30100 },
+        ),
+        (
+            with_temp(-26021),
+            FrameCheck::TemperatureOutOfRange { raw: -26021 },
+        ),
+    ]
+}
+
+#[test]
+fn checks_fire_on_crafted_frames() {
+    let model = TempModel::NATIVE;
+    for (bytes, check) in corrupt_frames() {
+        assert_eq!(check_frame(&bytes, &model), Some(check), "{:02x?}", bytes);
+    }
+    assert_eq!(check_frame(&at_rest(), &model), None);
+
+    // the limits themselves pass: -26020 counts is -39.9994 °C, 30079 is 124.998 °C
+    assert_eq!(check_frame(&with_temp(-26020), &model), None);
+    assert_eq!(check_frame(&with_temp(30079), &model), None);
+    assert_eq!(
+        check_frame(&with_temp(30080), &model),
+        Some(FrameCheck::TemperatureOutOfRange { raw: 30080 })
+    );
+
+    // structural, not physical: a zero accelerometer with a sane temperature is a frame
+    let free_fall = encode_frame(&RawFrame {
+        acc: [0; 3],
+        temp: -2000,
+        gyro: [0; 3],
+    });
+    assert_eq!(check_frame(&free_fall, &model), None);
+    // one differing byte breaks the pattern
+    let mut almost = [0xff; 14];
+    almost[13] = 0xfe;
+    assert_eq!(check_frame(&almost, &model), None);
+
+    // the temperature goes through the model: a calibration moves the limits
+    let shifted = TempModel {
+        offset_c: 130.,
+        ..model
+    };
+    assert_eq!(
+        check_frame(&with_temp(0), &shifted),
+        Some(FrameCheck::TemperatureOutOfRange { raw: 0 })
+    );
+    let broken = TempModel {
+        sensitivity: 0.,
+        ..model
+    };
+    assert!(check_frame(&with_temp(0), &broken).is_some());
+
+    assert_eq!(FrameCheck::AllZero.to_string(), "all bytes zero");
+    assert_eq!(
+        FrameCheck::IdenticalBytes { byte: 0xff }.to_string(),
+        "all bytes 0xff"
+    );
+    assert_eq!(
+        FrameCheck::TemperatureOutOfRange { raw: -26021 }.to_string(),
+        "temperature out of range, -26021 counts"
+    );
+}
+
+#[test]
+fn strict_rejects_failing_frames() {
+    let (mut mpu, bus) = driver();
+    mpu.set_parse_policy(ParsePolicy::Strict);
+    assert_eq!(mpu.parse_policy(), ParsePolicy::Strict);
+    assert_eq!(mpu.debug_state().parse_policy, ParsePolicy::Strict);
+
+    for (bytes, check) in corrupt_frames() {
+        assert!(read_frames(&mut mpu, &bus, &[at_rest()]).is_ok());
+        match read_frames(&mut mpu, &bus, &[bytes]) {
+            Err(Mpu6050Error::ImplausibleFrame { reason }) => assert_eq!(reason, check),
+            other => panic!("{:02x?}: {:?}", bytes, other),
+        }
+    }
+    let error: Mpu6050Error<Infallible> = Mpu6050Error::ImplausibleFrame {
+        reason: FrameCheck::AllZero,
+    };
+    assert_eq!(error.recovery_hint(), RecoveryHint::RetryImmediately);
+    assert_eq!(error.to_string(), "implausible frame: all bytes zero");
+
+    // counted, not kept
+    let diagnostics = mpu.parse_diagnostics();
+    assert_eq!((diagnostics.checked(), diagnostics.failed()), (10, 5));
+    assert_eq!(diagnostics.recorded(), 0);
+    assert!(diagnostics.is_empty());
+}
+
+#[test]
+fn diagnose_accepts_and_records() {
+    let (mut lenient, lenient_bus) = driver();
+    let (mut mpu, bus) = driver();
+    mpu.set_parse_policy(ParsePolicy::Diagnose);
+
+    // two rounds of good, corrupt, good: ten failures, the last eight kept
+    let mut frames = Vec::new();
+    for _ in 0..2 {
+        for (bytes, _) in corrupt_frames() {
+            frames.push(at_rest());
+            frames.push(bytes);
+        }
+    }
+    let samples = read_frames(&mut mpu, &bus, &frames).unwrap();
+    let expected = read_frames(&mut lenient, &lenient_bus, &frames).unwrap();
+    assert_eq!(samples, expected);
+
+    let diagnostics = *mpu.parse_diagnostics();
+    assert_eq!(diagnostics.checked(), 20);
+    assert_eq!((diagnostics.failed(), diagnostics.recorded()), (10, 10));
+    assert_eq!(diagnostics.len(), PARSE_DIAGNOSTICS_LEN);
+    let checks: Vec<_> = corrupt_frames().iter().map(|(_, check)| *check).collect();
+    let kept: Vec<_> = diagnostics.iter().copied().collect();
+    for (i, diagnostic) in kept.iter().enumerate() {
+        // failures 2..10, at the odd frames
+        let failure = i + 2;
+        assert_eq!(diagnostic.frame as usize, 2 * failure + 1);
+        assert_eq!(diagnostic.bytes, frames[2 * failure + 1]);
+        assert_eq!(diagnostic.check, checks[failure % checks.len()]);
+    }
+    assert_eq!(diagnostics.latest(), kept.last());
+    assert_eq!(mpu.debug_state().parse_diagnostics, diagnostics);
+
+    // a policy change keeps them, clearing starts over
+    mpu.set_parse_policy(ParsePolicy::Lenient);
+    assert_eq!(mpu.parse_diagnostics().len(), PARSE_DIAGNOSTICS_LEN);
+    mpu.clear_parse_diagnostics();
+    assert!(mpu.parse_diagnostics().is_empty());
+    assert_eq!(mpu.parse_diagnostics().latest(), None);
+    assert_eq!(*mpu.parse_diagnostics(), ParseDiagnostics::default());
+}
+
+#[test]
+fn lenient_is_bit_identical() {
+    let (mut untouched, untouched_bus) = driver();
+    let (mut mpu, bus) = driver();
+    assert_eq!(mpu.parse_policy(), ParsePolicy::Lenient);
+    mpu.set_parse_policy(ParsePolicy::Strict);
+    mpu.set_parse_policy(ParsePolicy::Lenient);
+
+    let mut frames: Vec<[u8; 14]> = corrupt_frames().iter().map(|(bytes, _)| *bytes).collect();
+    frames.push(at_rest());
+    let samples = read_frames(&mut mpu, &bus, &frames).unwrap();
+    let expected = read_frames(&mut untouched, &untouched_bus, &frames).unwrap();
+    assert_eq!(samples.len(), expected.len());
+    for (sample, expected) in samples.iter().zip(&expected) {
+        let bits = |s: &MpuSample| {
+            let mut bits: Vec<u32> = s.acc().to_array().map(f32::to_bits).to_vec();
+            bits.extend(s.gyro().to_array().map(f32::to_bits));
+            bits.push(s.temp().to_bits());
+            bits
+        };
+        assert_eq!(bits(sample), bits(expected));
+    }
+    // the all-zero frame comes out as zeros at 36.53 °C
+    assert_eq!(samples[0].acc(), Vec3A::ZERO);
+    assert_eq!(samples[0].temp(), 36.53);
+    assert_eq!(mpu.parse_diagnostics().checked(), 0);
+}
+
+fn driver() -> (Mpu6050<SharedBus>, SharedBus) {
+    let bus = SharedBus::new(&[ADDR]);
+    let mut mpu = Mpu6050Builder::new().i2c(bus.clone()).build().unwrap();
+    mpu.init(&mut NoDelay).unwrap();
+    (mpu, bus)
+}
+
+/// one burst read per frame
+fn read_frames(
+    mpu: &mut Mpu6050<SharedBus>,
+    bus: &SharedBus,
+    frames: &[[u8; 14]],
+) -> Result<Vec<MpuSample>, Mpu6050Error<Infallible>> {
+    let mut out = Vec::new();
+    for frame in frames {
+        bus.device(ADDR, |m| m.set_frame(frame));
+        let mut sample = [MpuSample::default()];
+        mpu.read_batch(|| {}, &mut sample)?;
+        out.push(sample[0]);
+    }
+    Ok(out)
+}
@@ -0,0 +1,179 @@
+//! Calibration against a motion reference on a synthetic moving platform, see the
+//! `platform` module.
+
+mod common;
+
+use mpu6050::device::{AccelRange, GyroRange, DEFAULT_SLAVE_ADDR};
+use mpu6050::platform::ReferencedCalibration;
+use mpu6050::setup::{AutoSetupFailure, AutoSetupOptions};
+use mpu6050::*;
+
+use common::{NoDelay, SharedBus};
+
+const GYRO_BIAS: Vec3A = Vec3A::new(0.02, -0.01, 0.015);
+const ACC_BIAS: Vec3A = Vec3A::new(0.03, -0.02, 0.05);
+const DT: f32 = 0.002;
+
+/// a ship turning at 0.05 rad/s while rolling with 0.1 rad/s amplitude and a 6 s period
+fn ship_rate(i: u32) -> Vec3A {
+    let t = i as f32 * DT;
+    Vec3A::new(0.1 * (t * core::f32::consts::TAU / 6.).sin(), 0., 0.05)
+}
+
+/// heeled 25° and trimmed 10° nose down, surging with 0.05g at 0.7 Hz
+fn ship_specific_force(i: u32) -> Vec3A {
+    let t = i as f32 * DT;
+    synthetic::at_rest(25., 10.)
+        + Vec3A::new(0.05 * (t * core::f32::consts::TAU * 0.7).sin(), 0., 0.)
+}
+
+/// the sensor on the ship: readings are `truth + bias`
+struct Ship {
+    bus: SharedBus,
+    sample: u32,
+}
+
+impl Ship {
+    fn new() -> (Self, Mpu6050<SharedBus>) {
+        let bus = SharedBus::new(&[DEFAULT_SLAVE_ADDR]);
+        let mut mpu = Mpu6050Builder::new().i2c(bus.clone()).build().unwrap();
+        mpu.init(&mut NoDelay).unwrap();
+        let mut ship = Ship { bus, sample: 0 };
+        ship.show(0);
+        (ship, mpu)
+    }
+
+    fn show(&mut self, i: u32) {
+        let frame = synthetic::frame_bytes(
+            ship_specific_force(i) + ACC_BIAS,
+            (ship_rate(i) + GYRO_BIAS) * (180. / core::f32::consts::PI),
+            AccelRange::G2,
+            GyroRange::D250,
+        );
+        self.bus
+            .device(DEFAULT_SLAVE_ADDR, |mock| mock.set_frame(&frame));
+    }
+
+    /// truth of the sample just read, then moves on to the next one
+    fn advance(&mut self, truth: fn(u32) -> Vec3A) -> Vec3A {
+        let value = truth(self.sample);
+        self.sample += 1;
+        self.show(self.sample);
+        value
+    }
+}
+
+fn options() -> AutoSetupOptions {
+    AutoSetupOptions::default()
+}
+
+fn assert_close(actual: Vec3A, expected: Vec3A, tolerance: f32) {
+    assert!(
+        (actual - expected).abs().max_element() < tolerance,
+        "{actual:?} != {expected:?}"
+    );
+}
+
+#[test]
+fn naive_gyro_calibration_on_a_ship_absorbs_the_platform_rate() {
+    let (mut ship, mut mpu) = Ship::new();
+    let relaxed = ReferencedCalibration {
+        max_std_dev: 1.,
+        ..ReferencedCalibration::gyro(&options())
+    };
+    let still = |_| Vec3A::ZERO;
+    let naive = mpu
+        .calibrate_gyro_with_reference(&mut NoDelay, || ship.advance(still), relaxed)
+        .unwrap();
+
+    let mean_rate = (0..relaxed.samples as u32)
+        .map(ship_rate)
+        .fold(Vec3A::ZERO, |sum, rate| sum + rate)
+        / relaxed.samples as f32;
+    assert_close(naive.offset, GYRO_BIAS + mean_rate, 1e-3);
+    // 0.05 rad/s of turn rate taken for bias
+    assert!((naive.offset - GYRO_BIAS).z.abs() > 0.04);
+
+    // with the standard gate the roll motion rejects the run
+    let (mut ship, mut mpu) = Ship::new();
+    let gated = ReferencedCalibration::gyro(&options());
+    match mpu.calibrate_gyro_with_reference(&mut NoDelay, || ship.advance(still), gated) {
+        Err(AutoSetupFailure::CalibrationRejected { std_dev, .. }) => assert!(std_dev > 0.01),
+        other => panic!("{other:?}"),
+    }
+}
+
+#[test]
+fn referenced_gyro_calibration_recovers_the_bias() {
+    let (mut ship, mut mpu) = Ship::new();
+    let result = mpu
+        .calibrate_gyro_with_reference(
+            &mut NoDelay,
+            || ship.advance(ship_rate),
+            ReferencedCalibration::gyro(&options()),
+        )
+        .unwrap();
+
+    assert_close(result.offset, GYRO_BIAS, 1e-3);
+    assert_eq!(mpu.gyro_offset, result.offset);
+    assert_eq!(result.samples, options().gyro_samples as u32);
+    assert!(result.std_dev < options().max_gyro_std_dev);
+}
+
+#[test]
+fn referenced_accel_calibration_recovers_the_bias_off_level() {
+    let (mut ship, mut mpu) = Ship::new();
+    let relaxed = ReferencedCalibration {
+        max_std_dev: 1.,
+        ..ReferencedCalibration::accel(&options())
+    };
+    // the standard path assumes level: 1g on Z
+    let level = |_| Vec3A::Z;
+    let naive = mpu
+        .calibrate_accel_with_reference(&mut NoDelay, || ship.advance(level), relaxed)
+        .unwrap();
+    assert!(
+        (naive.offset - ACC_BIAS).abs().max_element() > 0.1,
+        "{naive:?}"
+    );
+
+    let (mut ship, mut mpu) = Ship::new();
+    let result = mpu
+        .calibrate_accel_with_reference(
+            &mut NoDelay,
+            || ship.advance(ship_specific_force),
+            ReferencedCalibration::accel(&options()),
+        )
+        .unwrap();
+    assert_close(result.offset, ACC_BIAS, 1e-3);
+    assert_eq!(mpu.acc_offset, result.offset);
+
+    // corrected readings now match the reference
+    let acc = mpu.get_acc().unwrap();
+    assert_close(acc, ship_specific_force(ship.sample), 1e-3);
+}
+
+#[test]
+fn constant_reference_matches_auto_setup() {
+    let (_, mut mpu) = Ship::new();
+    let setup = AutoSetupOptions {
+        actuation_check: false,
+        device_is_level: true,
+        max_gyro_std_dev: 1.,
+        max_acc_std_dev: 1.,
+        ..options()
+    };
+    let report = mpu.auto_setup(&mut NoDelay, setup, None).unwrap();
+
+    let (_, mut mpu) = Ship::new();
+    let gyro = ReferencedCalibration::gyro(&setup);
+    let acc = ReferencedCalibration::accel(&setup);
+    let gyro = mpu
+        .calibrate_gyro_with_reference(&mut NoDelay, || Vec3A::ZERO, gyro)
+        .unwrap();
+    let acc = mpu
+        .calibrate_accel_with_reference(&mut NoDelay, || Vec3A::Z, acc)
+        .unwrap();
+    assert_eq!(gyro.offset, report.gyro_offset);
+    assert_eq!(acc.offset, report.acc_offset);
+}
@@ -0,0 +1,287 @@
+mod common;
+
+use std::io;
+use std::path::PathBuf;
+
+use mpu6050::axis_map::AxisMap;
+use mpu6050::board::DeviceAddr;
+use mpu6050::device::{AccelRange, ChipVariant, WHOAMI};
+use mpu6050::packed::DecodeError;
+use mpu6050::platform_config::*;
+use mpu6050::Mpu6050;
+
+use common::{NoDelay, SharedBus};
+
+/// file in the temp dir, removed on drop
+struct TempFile(PathBuf);
+
+impl TempFile {
+    fn new(name: &str, contents: &[u8]) -> Self {
+        let path =
+            std::env::temp_dir().join(format!("mpu6050-platform-{}-{}", std::process::id(), name));
+        std::fs::write(&path, contents).unwrap();
+        Self(path)
+    }
+}
+
+impl Drop for TempFile {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.0);
+    }
+}
+
+fn description(toml: &str) -> PlatformDescription {
+    PlatformDescription::from_toml_str(toml).unwrap()
+}
+
+fn parse_error(toml: &str) -> String {
+    PlatformDescription::from_toml_str(toml)
+        .unwrap_err()
+        .to_string()
+}
+
+#[allow(clippy::result_large_err)]
+fn setup(
+    desc: &PlatformDescription,
+    bus: &SharedBus,
+) -> Result<(Mpu6050<SharedBus>, PlatformReport), PlatformError<std::convert::Infallible>> {
+    let bus = bus.clone();
+    Mpu6050::from_platform_description_with(desc, |_| Ok(bus), &mut NoDelay)
+}
+
+#[test]
+fn descriptions_read_from_toml_and_json() {
+    let toml = description(
+        r#"
+        bus = "/dev/i2c-3"
+        address = 0x69
+        init = "auto_setup"
+        settings = "Drone_Rate_Control"
+        orientation = [[0, -1, 0], [1, 0, 0], [0, 0, 1]]
+        owner = "another tool"
+
+        [newer]
+        field = 1
+        "#,
+    );
+    assert_eq!(
+        toml,
+        PlatformDescription {
+            bus: "/dev/i2c-3".into(),
+            address: AddressSpec::Fixed(DeviceAddr::Ad0High),
+            init: InitProfile::AutoSetup,
+            settings: Some("DRONE_RATE_CONTROL".into()),
+            orientation: AxisMap::from_name("-Y+X+Z").unwrap(),
+            calibration: None,
+        }
+    );
+
+    let json = PlatformDescription::from_json_str(
+        r#"{"bus": "/dev/i2c-3", "address": 105, "init": "auto_setup",
+            "settings": "DRONE_RATE_CONTROL", "orientation": "-y+x+z", "extra": [1, 2]}"#,
+    )
+    .unwrap();
+    assert_eq!(json, toml);
+
+    // everything but the bus has a default
+    let minimal = description("bus = \"/dev/i2c-1\"");
+    assert_eq!(minimal.address, AddressSpec::Fixed(DeviceAddr::Ad0Low));
+    assert_eq!(minimal.init, InitProfile::Init);
+    assert_eq!(minimal.settings, None);
+    assert_eq!(minimal.orientation, AxisMap::IDENTITY);
+
+    // the extension picks the format
+    let file = TempFile::new("desc.json", br#"{"bus": "/dev/i2c-1", "address": "probe"}"#);
+    let read = PlatformDescription::from_file(&file.0).unwrap();
+    assert_eq!(read.address, AddressSpec::Probe);
+}
+
+#[test]
+fn bad_descriptions_say_what_is_accepted() {
+    let message = parse_error("bus = \"/dev/i2c-1\"\norientation = \"+X+Q+Z\"");
+    assert!(message.contains("line 2"), "{}", message);
+    assert!(message.contains("orientation \"+X+Q+Z\""), "{}", message);
+    assert!(message.contains("like +X+Y+Z or -Y+X+Z"), "{}", message);
+
+    let message = parse_error("bus = \"/dev/i2c-1\"\norientation = \"-X+Y+Z\"");
+    assert!(message.contains("a mirror, not a mounting"), "{}", message);
+    let message = parse_error("bus = \"/dev/i2c-1\"\norientation = \"+Z+Y+Z\"");
+    assert!(message.contains("chip axis Z used twice"), "{}", message);
+    let message =
+        parse_error("bus = \"/dev/i2c-1\"\norientation = [[1, 0, 0], [0, 1, 1], [0, 0, 1]]");
+    assert!(message.contains("matrix row 1"), "{}", message);
+    let message = parse_error("bus = \"/dev/i2c-1\"\norientation = true");
+    assert!(
+        message.contains("a name like \"-Y+X+Z\" or a 3x3 matrix"),
+        "{}",
+        message
+    );
+
+    let message = parse_error("bus = \"/dev/i2c-1\"\nsettings = \"racing\"");
+    assert!(
+        message
+            .contains("unknown preset \"racing\", expected one of DRONE_RATE_CONTROL, HANDHELD_UI"),
+        "{}",
+        message
+    );
+    let message = parse_error("bus = \"/dev/i2c-1\"\naddress = 0x42");
+    assert!(
+        message.contains("address 0x42 is not an MPU-6050 address"),
+        "{}",
+        message
+    );
+    let message = parse_error("bus = \"/dev/i2c-1\"\ninit = \"warm\"");
+    assert!(
+        message.contains("expected `init` or `auto_setup`"),
+        "{}",
+        message
+    );
+    assert!(parse_error("address = 0x68").contains("missing field `bus`"));
+
+    let message =
+        parse_error("bus = \"/dev/i2c-1\"\ninit = \"auto_setup\"\ncalibration = \"cal.bin\"");
+    assert!(message.contains("calibrates itself"), "{}", message);
+
+    let file = TempFile::new("broken.toml", b"bus = ");
+    match PlatformDescription::from_file(&file.0) {
+        Err(DescriptionError::Parse {
+            path: Some(path), ..
+        }) => assert_eq!(path, file.0),
+        other => panic!("{:?}", other),
+    }
+    let missing = std::env::temp_dir().join("mpu6050-platform-missing.toml");
+    let err = PlatformDescription::from_file(&missing).unwrap_err();
+    assert!(matches!(err, DescriptionError::Read { .. }));
+    assert_eq!(PlatformError::<()>::from(err).stage(), PlatformStage::File);
+}
+
+#[test]
+fn description_sets_the_driver_up_and_reports_it() {
+    let calibration = OffsetCalibration {
+        gyro_offset: [0.01, -0.02, 0.03],
+        acc_offset: [0.0, 0.0, -0.05],
+    };
+    let blob = calibration.to_bytes();
+    assert_eq!(OffsetCalibration::from_bytes(&blob), Ok(calibration));
+    let file = TempFile::new("offsets.bin", &blob);
+
+    let mut desc = description(
+        r#"
+        bus = "/dev/i2c-7"
+        address = "probe"
+        settings = "drone_rate_control"
+        orientation = "+Y-X+Z"
+        "#,
+    );
+    desc.calibration = Some(file.0.clone());
+    let bus = SharedBus::new(&[0x68, 0x69]);
+    bus.device(0x68, |m| m.regs[WHOAMI as usize] = 0x00);
+    let mut opened = None;
+    let handle = bus.clone();
+    let (mut mpu, report) = Mpu6050::from_platform_description_with(
+        &desc,
+        |path| {
+            opened = Some(path.to_string());
+            Ok(handle)
+        },
+        &mut NoDelay,
+    )
+    .unwrap();
+    assert_eq!(opened.as_deref(), Some("/dev/i2c-7"));
+
+    assert_eq!((report.address, report.probed), (0x69, true));
+    assert_eq!(report.chip, ChipVariant::Mpu6050);
+    assert_eq!(report.settings.as_deref(), Some("DRONE_RATE_CONTROL"));
+    assert_eq!(report.orientation.to_string(), "+Y-X+Z");
+    assert_eq!(report.gyro_offset.to_array(), calibration.gyro_offset);
+    assert_eq!(OffsetCalibration::of(&mpu), calibration);
+    assert!(report.auto_setup.is_none());
+    assert_eq!(mpu.get_accel_range().unwrap(), AccelRange::G16);
+    let text = report.to_string();
+    assert!(text.contains("address: 0x69 (probed)"), "{}", text);
+    assert!(text.contains("settings: DRONE_RATE_CONTROL"), "{}", text);
+}
+
+#[test]
+fn failures_name_their_stage() {
+    let bus = SharedBus::new(&[0x68, 0x69]);
+
+    // calibration first: the bus is not opened for a missing file
+    let mut desc = description("bus = \"/dev/i2c-1\"");
+    desc.calibration = Some(std::env::temp_dir().join("mpu6050-platform-missing.bin"));
+    let mut opened = false;
+    let err = Mpu6050::<SharedBus>::from_platform_description_with(
+        &desc,
+        |_| {
+            opened = true;
+            Ok(bus.clone())
+        },
+        &mut NoDelay,
+    )
+    .err()
+    .unwrap();
+    assert!(!opened);
+    assert!(matches!(err, PlatformError::CalibrationRead { .. }));
+    assert_eq!(err.stage(), PlatformStage::Calibration);
+
+    let mut blob = OffsetCalibration::default().to_bytes();
+    blob[4] ^= 1;
+    let file = TempFile::new("corrupt.bin", &blob);
+    desc.calibration = Some(file.0.clone());
+    match setup(&desc, &bus).err().unwrap() {
+        PlatformError::CalibrationDecode { path, error } => {
+            assert_eq!((path, error), (file.0.clone(), DecodeError::CrcMismatch))
+        }
+        other => panic!("{:?}", other),
+    }
+
+    desc.calibration = None;
+    let err = Mpu6050::<SharedBus>::from_platform_description_with(
+        &desc,
+        |_| Err(io::Error::new(io::ErrorKind::NotFound, "no such bus")),
+        &mut NoDelay,
+    )
+    .err()
+    .unwrap();
+    assert_eq!(err.stage(), PlatformStage::BusOpen);
+    assert_eq!(
+        err.to_string(),
+        "bus open: cannot open /dev/i2c-1: no such bus"
+    );
+
+    // nothing known at the configured address, the other one answers
+    bus.device(0x68, |m| m.regs[WHOAMI as usize] = 0x00);
+    let err = setup(&desc, &bus).err().unwrap();
+    assert_eq!(err.stage(), PlatformStage::Probe);
+    assert_eq!(
+        err.to_string(),
+        "probe: no known chip at 0x68, one answers at 0x69"
+    );
+
+    // probing needs exactly one chip
+    bus.device(0x68, |m| m.regs[WHOAMI as usize] = 0x68);
+    let desc = description("bus = \"/dev/i2c-1\"\naddress = \"probe\"");
+    let err = setup(&desc, &bus).err().unwrap();
+    assert_eq!(
+        err.to_string(),
+        "probe: chips answer at 0x68 and 0x69, give the address"
+    );
+
+    // an MPU-6500 is a known chip but not the one the driver accepts
+    bus.device(0x68, |m| m.regs[WHOAMI as usize] = 0x70);
+    let err = setup(&description("bus = \"/dev/i2c-1\""), &bus)
+        .err()
+        .unwrap();
+    assert!(matches!(err, PlatformError::Init(_)), "{:?}", err);
+    assert_eq!(err.stage(), PlatformStage::Init);
+
+    // the actuation check of the auto setup sees no self-test response on the mock
+    bus.device(0x68, |m| m.regs[WHOAMI as usize] = 0x68);
+    let desc = description("bus = \"/dev/i2c-1\"\ninit = \"auto_setup\"");
+    let err = setup(&desc, &bus).err().unwrap();
+    assert!(matches!(err, PlatformError::AutoSetup(_)), "{:?}", err);
+    assert_eq!(err.stage(), PlatformStage::Init);
+    assert!(err
+        .to_string()
+        .starts_with("init: auto setup failed in phase ActuationCheck"));
+}
@@ -0,0 +1,304 @@
+//! Per-sample plausibility checks: each check and combinations through synthetic frames, the
+//! bitmask encoding, the driver without a config, and the complementary filter policy, see
+//! the `plausibility` module.
+
+mod common;
+
+use mpu6050::device::*;
+use mpu6050::orientation::*;
+use mpu6050::plausibility::*;
+use mpu6050::provenance::{FieldGroup, Provenance};
+use mpu6050::synthetic::frame_bytes;
+use mpu6050::*;
+
+use common::{NoDelay, SharedBus};
+
+const ADDR: u8 = DEFAULT_SLAVE_ADDR;
+
+/// frame at ±2 g and ±250 °/s with `temp` counts, 36.53 °C plus `temp / 340`
+fn frame(acc_g: Vec3A, gyro_dps: Vec3A, temp: i16) -> [u8; 14] {
+    let mut bytes = frame_bytes(acc_g, gyro_dps, AccelRange::G2, GyroRange::D250);
+    bytes[6..8].copy_from_slice(&temp.to_be_bytes());
+    bytes
+}
+
+/// at rest, the gyro at `k` °/s so consecutive frames differ
+fn resting(k: u32) -> [u8; 14] {
+    frame(Vec3A::Z, Vec3A::splat(k as f32), 0)
+}
+
+fn driver(config: Option<PlausibilityConfig>) -> (Mpu6050<SharedBus>, SharedBus) {
+    let bus = SharedBus::new(&[ADDR]);
+    let mut mpu = Mpu6050Builder::new().i2c(bus.clone()).build().unwrap();
+    mpu.init(&mut NoDelay).unwrap();
+    mpu.set_plausibility_config(config);
+    (mpu, bus)
+}
+
+/// scores of one burst read per frame
+fn scores(config: PlausibilityConfig, frames: &[[u8; 14]]) -> Vec<PlausibilityScore> {
+    let (mut mpu, bus) = driver(Some(config));
+    frames
+        .iter()
+        .map(|frame| {
+            bus.device(ADDR, |m| m.set_frame(frame));
+            let sample = mpu.read_into_views::<1>(&mut []).unwrap();
+            sample.plausibility()
+        })
+        .collect()
+}
+
+/// every check enabled, ±0.2 g around 1 g, 2 rad/s, -40..=85 °C and 5 °C per sample
+const ALL_CHECKS: PlausibilityConfig = PlausibilityConfig {
+    acc_magnitude_g: Some((0.8, 1.2)),
+    max_gyro_rad_s: Some(2.),
+    temp_c: Some((-40., 85.)),
+    max_temp_step_c: Some(5.),
+    stale: true,
+    clipped: true,
+    implausible: DEFAULT_IMPLAUSIBLE,
+};
+
+#[test]
+fn each_check_fails_alone() {
+    let only = |check| {
+        let config = PlausibilityConfig {
+            implausible: 0,
+            ..PlausibilityConfig::NONE
+        };
+        match check {
+            PlausibilityCheck::AccMagnitude => PlausibilityConfig {
+                acc_magnitude_g: ALL_CHECKS.acc_magnitude_g,
+                ..config
+            },
+            PlausibilityCheck::GyroRate => PlausibilityConfig {
+                max_gyro_rad_s: ALL_CHECKS.max_gyro_rad_s,
+                ..config
+            },
+            PlausibilityCheck::TempRange => PlausibilityConfig {
+                temp_c: ALL_CHECKS.temp_c,
+                ..config
+            },
+            PlausibilityCheck::TempRate => PlausibilityConfig {
+                max_temp_step_c: ALL_CHECKS.max_temp_step_c,
+                ..config
+            },
+            PlausibilityCheck::Stale => PlausibilityConfig {
+                stale: true,
+                ..config
+            },
+            PlausibilityCheck::Clipped => PlausibilityConfig {
+                clipped: true,
+                ..config
+            },
+        }
+    };
+    // a good frame, then one failing the check
+    let failing = [
+        (
+            PlausibilityCheck::AccMagnitude,
+            frame(Vec3A::Z * 1.5, Vec3A::ZERO, 0),
+        ),
+        (
+            PlausibilityCheck::GyroRate,
+            frame(Vec3A::Z, Vec3A::X * 200., 0),
+        ),
+        // 36.53 + 18_000 / 340 = 89.5 °C
+        (
+            PlausibilityCheck::TempRange,
+            frame(Vec3A::Z, Vec3A::ONE, 18_000),
+        ),
+        // 10 °C up in one sample
+        (
+            PlausibilityCheck::TempRate,
+            frame(Vec3A::Z, Vec3A::ONE, 3400),
+        ),
+        (PlausibilityCheck::Stale, resting(0)),
+        // 3 g saturates at ±2 g
+        (
+            PlausibilityCheck::Clipped,
+            frame(Vec3A::X * 3., Vec3A::ONE, 0),
+        ),
+    ];
+    for (check, failing) in failing {
+        let [good, bad, recovered] = scores(only(check), &[resting(0), failing, resting(2)])[..]
+        else {
+            unreachable!()
+        };
+        assert_eq!(good, PlausibilityScore::OK, "{:?}", check);
+        assert_eq!(bad.bits(), check.bit(), "{:?}", check);
+        assert_eq!(bad.class(), Plausibility::Suspect);
+        assert_eq!(bad.failed_checks().collect::<Vec<_>>(), [check]);
+        if check != PlausibilityCheck::TempRate {
+            assert_eq!(recovered, PlausibilityScore::OK, "{:?}", check);
+        }
+    }
+}
+
+#[test]
+fn combinations_and_the_implausible_mask() {
+    let frames = [
+        resting(0),
+        resting(1),
+        // 3 g on X: out of bounds and clipped
+        frame(Vec3A::X * 3., Vec3A::ONE, 0),
+        // hot and a jump: two temperature checks
+        frame(Vec3A::Z, Vec3A::splat(3.), 18_000),
+        // the same again: stale, no step
+        frame(Vec3A::Z, Vec3A::splat(3.), 18_000),
+        // spinning with a dropped accelerometer
+        frame(Vec3A::ZERO, Vec3A::Z * 200., 18_000),
+    ];
+    let bits = |checks: &[PlausibilityCheck]| checks.iter().fold(0, |b, c| b | c.bit());
+    use PlausibilityCheck::*;
+    let expected = [
+        (0, Plausibility::Ok),
+        (0, Plausibility::Ok),
+        (bits(&[AccMagnitude, Clipped]), Plausibility::Implausible),
+        (bits(&[TempRange, TempRate]), Plausibility::Implausible),
+        (bits(&[TempRange, Stale]), Plausibility::Implausible),
+        (
+            bits(&[AccMagnitude, GyroRate, TempRange]),
+            Plausibility::Implausible,
+        ),
+    ];
+    let actual = scores(ALL_CHECKS, &frames);
+    let actual: Vec<_> = actual.iter().map(|s| (s.bits(), s.class())).collect();
+    assert_eq!(actual, expected);
+
+    // stale alone: implausible by default, suspect without the mask
+    let stale = PlausibilityConfig {
+        stale: true,
+        ..PlausibilityConfig::NONE
+    };
+    let classes = |config| scores(config, &[resting(0), resting(0)])[1].class();
+    assert_eq!(classes(stale), Plausibility::Implausible);
+    let relaxed = PlausibilityConfig {
+        implausible: 0,
+        ..stale
+    };
+    assert_eq!(classes(relaxed), Plausibility::Suspect);
+}
+
+#[test]
+fn bitmask_encoding() {
+    let bits: Vec<u8> = PlausibilityCheck::ALL.iter().map(|c| c.bit()).collect();
+    assert_eq!(bits, [0x01, 0x02, 0x04, 0x08, 0x10, 0x20]);
+    assert_eq!(DEFAULT_IMPLAUSIBLE, 0x30);
+
+    assert_eq!(PlausibilityScore::new(0, 0xff), PlausibilityScore::OK);
+    assert_eq!(PlausibilityScore::default(), PlausibilityScore::OK);
+    assert_eq!(
+        PlausibilityScore::new(0x04, 0).class(),
+        Plausibility::Suspect
+    );
+    assert_eq!(
+        PlausibilityScore::new(0x04, 0x04).class(),
+        Plausibility::Implausible
+    );
+    assert_eq!(
+        PlausibilityScore::new(0x05, 0).class(),
+        Plausibility::Implausible
+    );
+    let score = PlausibilityScore::new(0x22, DEFAULT_IMPLAUSIBLE);
+    assert!(score.failed(PlausibilityCheck::GyroRate));
+    assert!(!score.failed(PlausibilityCheck::Stale));
+    assert_eq!(
+        score.failed_checks().collect::<Vec<_>>(),
+        [PlausibilityCheck::GyroRate, PlausibilityCheck::Clipped]
+    );
+    assert!(Plausibility::Ok < Plausibility::Suspect);
+    assert!(Plausibility::Suspect < Plausibility::Implausible);
+}
+
+#[test]
+fn unset_costs_nothing_and_partial_reads_skip_unread_groups() {
+    // a clipped frame read twice: flagged only with a config, in the same transactions
+    let clipped = frame(Vec3A::X * 3., Vec3A::ZERO, 0);
+    let mut transactions = Vec::new();
+    for config in [None, Some(ALL_CHECKS)] {
+        let (mut mpu, bus) = driver(config);
+        bus.device(ADDR, |m| m.set_frame(&clipped));
+        let before = mpu.io_stats().transactions;
+        let first = mpu.read_into_views::<1>(&mut []).unwrap();
+        let second = mpu.read_into_views::<1>(&mut []).unwrap();
+        transactions.push(mpu.io_stats().transactions - before);
+        assert_eq!(mpu.plausibility_config(), config);
+        assert_eq!(mpu.debug_state().plausibility, config);
+        match config {
+            None => assert_eq!(
+                [first, second].map(|s| s.plausibility()),
+                [PlausibilityScore::OK; 2]
+            ),
+            Some(_) => assert!(second.plausibility().failed(PlausibilityCheck::Stale)),
+        }
+    }
+    assert_eq!(transactions, [2, 2]);
+
+    // single sensor reads: the unread, NaN groups are not checked
+    let (mut mpu, bus) = driver(Some(ALL_CHECKS));
+    bus.device(ADDR, |m| m.set_frame(&resting(0)));
+    mpu.get_acc().unwrap();
+    mpu.set_sample_tap(Some(|sample: &MpuSample| {
+        assert!(FieldGroup::ALL
+            .iter()
+            .any(|g| sample.provenance().get(*g) == Provenance::Invalid));
+        assert_eq!(sample.plausibility(), PlausibilityScore::OK);
+    }));
+    mpu.get_acc().unwrap();
+    mpu.get_gyro().unwrap();
+    mpu.get_temp().unwrap();
+
+    // a clipped single read is scored with its own sample
+    mpu.set_sample_tap(None);
+    bus.device(ADDR, |m| m.set_frame(&clipped));
+    mpu.set_sample_hook(Some(|sample: &mut MpuSample| {
+        assert!(sample.plausibility().failed(PlausibilityCheck::Clipped));
+    }));
+    mpu.get_acc().unwrap();
+}
+
+#[test]
+fn the_filter_skips_or_deweights_flagged_samples() {
+    let suspect = PlausibilityScore::new(PlausibilityCheck::AccMagnitude.bit(), 0);
+    let implausible = PlausibilityScore::new(PlausibilityCheck::Stale.bit(), DEFAULT_IMPLAUSIBLE);
+    // tilted 90°: the full correction is 1 - α of it
+    let sample = MpuSample::new(Vec3A::X, Vec3A::ZERO, 25.);
+    let policy = PlausibilityPolicy {
+        suspect: SampleHandling::DeWeight(0.5),
+        implausible: SampleHandling::Skip,
+    };
+    let updated = |score, policy| {
+        let mut filter = ComplementaryFilter::new(0.9).with_plausibility_policy(policy);
+        filter.update_sample(&sample.with_plausibility(score), 0.01);
+        filter.estimate()
+    };
+
+    // the default uses everything
+    assert_eq!(
+        ComplementaryFilter::new(0.9).plausibility_policy(),
+        PlausibilityPolicy::IGNORE
+    );
+    let full = updated(implausible, PlausibilityPolicy::IGNORE);
+    assert_eq!(full.accel_weight, 1.);
+    assert_eq!(updated(PlausibilityScore::OK, policy), full);
+
+    let halved = updated(suspect, policy);
+    assert_eq!(halved.accel_weight, 0.5);
+    let angle = |e: OrientationEstimate| e.orientation.angle_between(Quat::IDENTITY);
+    assert!((angle(halved) - angle(full) / 2.).abs() < 1e-4);
+
+    let skipped = updated(implausible, policy);
+    assert_eq!(skipped.orientation, Quat::IDENTITY);
+    assert!(!skipped.accel_gated);
+
+    // weights outside 0..=1 are clamped, a weight of 0 gates
+    assert_eq!(policy.weight(Plausibility::Implausible), None);
+    let zero = PlausibilityPolicy {
+        suspect: SampleHandling::DeWeight(-1.),
+        implausible: SampleHandling::DeWeight(f32::NAN),
+    };
+    assert_eq!(zero.weight(Plausibility::Suspect), Some(0.));
+    assert_eq!(zero.weight(Plausibility::Implausible), Some(0.));
+    assert!(updated(suspect, zero).accel_gated);
+}
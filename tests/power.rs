@@ -0,0 +1,231 @@
+//! Current draw estimates against hand calculations from the datasheet tables, the state the
+//! driver tracks for them, and the estimates carried by governor transitions, see the
+//! `power` module.
+
+mod common;
+
+use mpu6050::bus::ReadPlan;
+use mpu6050::device::*;
+use mpu6050::governor::*;
+use mpu6050::power::*;
+use mpu6050::presets;
+use mpu6050::settings::Mpu6050Settings;
+use mpu6050::*;
+
+use common::{NoDelay, SharedBus};
+
+const ADDR: u8 = DEFAULT_SLAVE_ADDR;
+
+/// bus current of one burst read per sample at `rate_hz` on a 400 kHz bus
+fn burst_i2c_ua(rate_hz: f32) -> f32 {
+    rate_hz * 156. / 400_000. * I2C_ACTIVE_UA as f32
+}
+
+fn close(actual: f32, expected: f32) {
+    assert!(
+        (actual - expected).abs() < 1e-3,
+        "{} != {}",
+        actual,
+        expected
+    );
+}
+
+fn driver(bus: &SharedBus) -> Mpu6050<SharedBus> {
+    Mpu6050Builder::new().i2c(bus.clone()).build().unwrap()
+}
+
+#[test]
+fn tables_reproduce_the_datasheet_currents() {
+    assert_eq!(ReadPlan::BURST.bits_per_sample(), 156);
+    for (table, normal, gyro_only, accel_only, sleep) in [
+        (MPU6050_CURRENT, 3800, 3600, 500, 5),
+        (MPU6500_CURRENT, 3400, 3200, 450, 6),
+    ] {
+        let awake = |gyro: bool, accel: bool| {
+            let config = PowerConfig {
+                mode: PowerMode::Normal,
+                gyro_axes: [gyro; 3],
+                accel,
+                temp: true,
+                sample_rate_hz: 0.,
+                traffic: BusTraffic::DEFAULT,
+            };
+            config.estimate(&table)
+        };
+        close(awake(true, true).total_ua, normal as f32);
+        close(awake(true, false).total_ua, gyro_only as f32);
+        assert_eq!(awake(false, true).total_ua, accel_only as f32);
+        let asleep = PowerConfig {
+            mode: PowerMode::Sleep,
+            ..awake_config()
+        };
+        assert_eq!(asleep.estimate(&table).total_ua, sleep as f32);
+    }
+
+    // the cycle currents are the datasheet points, and in between for the 6500
+    let wakes = [
+        LP_WAKE_CTRL::_1P25,
+        LP_WAKE_CTRL::_2P5,
+        LP_WAKE_CTRL::_5,
+        LP_WAKE_CTRL::_10,
+    ];
+    let cycle = |table: &CurrentTable| wakes.map(|wake| table.cycle(wake));
+    assert_eq!(cycle(&MPU6050_CURRENT), [10, 13, 20, 36]);
+    assert_eq!(cycle(&MPU6500_CURRENT), [7, 8, 9, 11]);
+}
+
+fn awake_config() -> PowerConfig {
+    PowerConfig::from_settings(&Mpu6050Settings::new(), BusTraffic::DEFAULT)
+}
+
+#[test]
+fn estimates_itemize_the_configuration() {
+    // 1 kHz with the DLPF on, one burst read per sample
+    let settings = Mpu6050Settings::new().with_dlpf_cfg(1);
+    let estimate =
+        PowerConfig::from_settings(&settings, BusTraffic::DEFAULT).estimate(&MPU6050_CURRENT);
+    assert_eq!(estimate.mode, PowerMode::Normal);
+    assert_eq!(
+        (estimate.base_ua, estimate.gyro_ua, estimate.accel_ua),
+        (300., [1100.; 3], 200.)
+    );
+    assert_eq!(MPU6500_CURRENT.gyro_ua, 2950);
+    close(estimate.i2c_ua, burst_i2c_ua(1000.));
+    close(estimate.total_ua, 3800. + 136.5);
+    close(estimate.sensors_ua(), 3800.);
+
+    // the separate reads cost more bus time, the default 8 kHz a saturated bus
+    let separate = BusTraffic {
+        plan: ReadPlan::SEPARATE,
+        ..BusTraffic::DEFAULT
+    };
+    let bits = ReadPlan::SEPARATE.bits_per_sample() as f32;
+    close(separate.utilization(1000.), 1000. * bits / 400_000.);
+    assert_eq!(awake_config().estimate(&MPU6050_CURRENT).i2c_ua, 350.);
+    let idle = BusTraffic {
+        bus_hz: 0,
+        ..BusTraffic::DEFAULT
+    };
+    assert_eq!(idle.utilization(1000.), 0.);
+
+    // cycling at 5 Hz: the accel at the cycle current, no gyro, no temperature
+    let cycling = Mpu6050Settings::new().with_cycle(Some(LP_WAKE_CTRL::_5));
+    let estimate =
+        PowerConfig::from_settings(&cycling, BusTraffic::DEFAULT).estimate(&MPU6050_CURRENT);
+    assert_eq!(estimate.mode, PowerMode::Cycle(LP_WAKE_CTRL::_5));
+    assert_eq!(
+        (
+            estimate.base_ua,
+            estimate.gyro_ua,
+            estimate.accel_ua,
+            estimate.temp_ua
+        ),
+        (5., [0.; 3], 15., 0.)
+    );
+    close(estimate.total_ua, 20. + burst_i2c_ua(5.));
+    // a 220 mAh coin cell
+    close(estimate.battery_hours(220.), 220_000. / estimate.total_ua);
+
+    // gyro Z alone, accel in standby
+    let config = PowerConfig {
+        gyro_axes: [false, false, true],
+        accel: false,
+        sample_rate_hz: 100.,
+        ..awake_config()
+    };
+    let estimate = config.estimate(&MPU6050_CURRENT);
+    close(estimate.total_ua, 300. + 1100. + burst_i2c_ua(100.));
+}
+
+#[test]
+fn the_driver_tracks_what_it_configured() {
+    let bus = SharedBus::new(&[ADDR]);
+    let mut mpu = driver(&bus);
+    assert_eq!(mpu.power_estimate().mode, PowerMode::Sleep);
+    assert_eq!(mpu.power_estimate().total_ua, 5.);
+
+    mpu.init(&mut NoDelay).unwrap();
+    mpu.apply_settings(&presets::HANDHELD_UI).unwrap();
+    let estimate = mpu.power_estimate();
+    assert_eq!(estimate.mode, PowerMode::Normal);
+    close(estimate.total_ua, 3800. + burst_i2c_ua(100.));
+    assert_eq!(mpu.power_config().sample_rate_hz, 100.);
+
+    mpu.set_temp_enabled(false).unwrap();
+    assert!(!mpu.power_state().temp_enabled);
+    // the MPU6050 temperature sensor draws nothing measurable on its own
+    close(mpu.power_estimate().total_ua, 3800. + burst_i2c_ua(100.));
+
+    mpu.set_bus_traffic(BusTraffic {
+        bus_hz: 100_000,
+        plan: ReadPlan::BURST,
+    });
+    close(mpu.power_estimate().i2c_ua, 4. * burst_i2c_ua(100.));
+    assert_eq!(mpu.bus_traffic().bus_hz, 100_000);
+    assert_eq!(mpu.debug_state().power.traffic.bus_hz, 100_000);
+    mpu.set_bus_traffic(BusTraffic::default());
+
+    // projected before applying
+    let change = mpu.power_change(&presets::LOW_POWER_TILT);
+    assert_eq!(change.before, mpu.power_estimate());
+    mpu.apply_settings(&presets::LOW_POWER_TILT).unwrap();
+    assert_eq!(change.after, mpu.power_estimate());
+    close(change.saving_ua(), 3780. + burst_i2c_ua(95.));
+
+    mpu.set_sleep_enabled(true).unwrap();
+    assert_eq!(mpu.power_estimate().mode, PowerMode::Sleep);
+    mpu.set_sleep_enabled(false).unwrap();
+    assert_eq!(
+        mpu.power_estimate().mode,
+        PowerMode::Cycle(LP_WAKE_CTRL::_5)
+    );
+}
+
+#[test]
+fn an_mpu6500_uses_its_own_table() {
+    let bus = SharedBus::new(&[ADDR]);
+    let mut mpu = driver(&bus);
+    mpu.init(&mut NoDelay).unwrap();
+    assert_eq!(mpu.capabilities().supply_current, MPU6050_CURRENT);
+    bus.device(ADDR, |m| m.regs[WHOAMI as usize] = 0x70);
+    mpu.probe_capabilities().unwrap();
+    assert_eq!(mpu.capabilities().supply_current, MPU6500_CURRENT);
+    mpu.apply_settings(&presets::HANDHELD_UI).unwrap();
+    close(mpu.power_estimate().sensors_ua(), 3400.);
+}
+
+#[test]
+fn governor_transitions_carry_their_power_change() {
+    let rest = OperatingPoint::new("rest", presets::LOW_POWER_TILT, 0);
+    let active = OperatingPoint::new("active", presets::HANDHELD_UI, 2000);
+    let rule = GovernorRule {
+        up: ActivityLevel {
+            gyro_rms: 0.3,
+            acc_variance: 0.01,
+        },
+        down: ActivityLevel {
+            gyro_rms: 0.1,
+            acc_variance: 0.002,
+        },
+    };
+    let config = GovernorConfig::two(rest, active, rule);
+
+    // the pure governor does not know the chip
+    let governor = PowerGovernor::new(config, 0).unwrap();
+    assert_eq!(governor.motion_wake().unwrap().power, None);
+
+    let bus = SharedBus::new(&[ADDR]);
+    let mut mpu = driver(&bus);
+    mpu.init(&mut NoDelay).unwrap();
+    mpu.set_power_governor(PowerGovernor::new(config, 0).unwrap())
+        .unwrap();
+    let resting = mpu.power_estimate();
+    let wake = mpu.governor_motion_wake().unwrap().unwrap();
+    let change = wake.power.unwrap();
+    assert_eq!(change.before, resting);
+    assert_eq!(change.after, mpu.power_estimate());
+    close(
+        change.saving_ua(),
+        -(3800. + burst_i2c_ua(100.) - resting.total_ua),
+    );
+}
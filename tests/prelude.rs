@@ -0,0 +1,270 @@
+//! Public API snapshot: nothing but the prelude is imported, a type or call leaving it fails
+//! to compile here.
+
+mod common;
+
+use mpu6050::prelude::*;
+
+use common::{NoDelay as Delay, RegisterMock};
+
+/// every type of the prelude, generics filled in
+#[allow(dead_code)]
+type Surface = (
+    (
+        Mpu6050<RegisterMock>,
+        Mpu6050Builder<RegisterMock>,
+        Mpu6050BuilderError,
+        Mpu6050Error<()>,
+        NoDelay,
+        AbortProgress,
+        AccessViolation,
+        TimestampError,
+    ),
+    (
+        AccelRange,
+        GyroRange,
+        ACCEL_HPF,
+        CLKSEL,
+        EXT_SYNC,
+        LP_WAKE_CTRL,
+        Axis,
+        Capability,
+        ChipCapabilities,
+        ChipVariant,
+        BitBlock,
+        Register,
+    ),
+    (
+        Mpu6050Settings,
+        SettingsError,
+        Mpu6050Config,
+        DefaultsReport,
+        AutoSetupError<()>,
+        AutoSetupFailure<()>,
+        AutoSetupOptions,
+        AutoSetupPhase,
+        AutoSetupReport,
+        RegisterScript,
+        ScriptError<()>,
+        ScriptReport,
+    ),
+    (
+        MpuSample,
+        SampleMeta,
+        SampleControl,
+        SamplingError<(), ()>,
+        SampleHook,
+        SampleTap,
+        Provenance,
+        SampleProvenance,
+        FieldGroup,
+        FifoFrame,
+        FifoSchema,
+        FifoSources,
+        MixedReadPolicy,
+    ),
+    (
+        Quat,
+        Vec3A,
+        ComplementaryFilter,
+        OrientationEstimate,
+        Reacquisition,
+        ReacquisitionState,
+        TiltEstimate,
+        TiltThresholds,
+        InterpolatingBuffer<4>,
+        UniformResampler,
+        Pipeline,
+        ScaleModel,
+    ),
+    (
+        AliasingAssessment,
+        ExtDataSlot,
+        SlaveConfig,
+        SlaveSlot,
+        RateTooHigh,
+        BackgroundCalibrationStatus,
+        CalibrationConfig,
+        CalibrationResult,
+        ReferencedCalibration,
+        ConnectionMonitor,
+        ConnectionState,
+        ReconnectOutcome,
+    ),
+    (
+        ErrorBudget,
+        GovernorTransition,
+        PowerGovernor,
+        InterleaveRates,
+        InterleaveState,
+        RangeInterleave,
+        InterruptEdgeTracker,
+        InterruptEvents,
+        MotionEvent,
+        LogHeader,
+        GaugeLimiter,
+        IoStats,
+    ),
+    (
+        ClockErrorEstimate,
+        ResolutionInfo,
+        SettleCountdown,
+        SettlingPolicy,
+        SkewCorrection,
+        SkewReference,
+        DriverStateSnapshot,
+        SupervisorConfig,
+        SupervisorEvent,
+        TransferMode,
+    ),
+    (
+        BusErrorKind,
+        BusRecovery,
+        BusRecoveryOutcome,
+        BusRecoveryStats,
+        LockupDetection,
+        ResyncOutcome,
+        FindingAction,
+        FindingKind,
+        InitFinding,
+        InitFindings,
+        FilterSpec,
+        FilteredView,
+        Smoothing,
+    ),
+    (
+        BusTraffic,
+        PowerChange,
+        PowerConfig,
+        PowerEstimate,
+        PowerMode,
+        PowerState,
+        AddrConstraint,
+        BoardConstraints,
+        ConstraintViolation,
+        DeviceAddr,
+        IntPinConfig,
+        IntPinConstraint,
+    ),
+    (
+        PlausibilityPolicy,
+        SampleHandling,
+        Plausibility,
+        PlausibilityCheck,
+        PlausibilityConfig,
+        PlausibilityScore,
+        LatestSampleMailbox,
+        RoundingMode,
+        DrainBudget,
+        CalibrationKind,
+        CalibrationMeta,
+        CalibrationPolicy,
+        CalibrationStatus,
+        HintContext,
+        RecoveryHint,
+        ConsumerSpec,
+        OverflowPolicy,
+        StreamSplitter<2, 4>,
+        TempCalibration,
+        TempCalibrationError,
+        InitBurst,
+        InitCriteria,
+        InitQuality,
+        ChannelSummary,
+        LongTermStats,
+        SharedLongTermStats,
+        StatsSnapshot,
+        CloneAssessment,
+        CloneEvidence,
+        CloneSign,
+        DeniedRange,
+        ConfigChange,
+        Tracked<Pipeline>,
+        (
+            ChangeSource,
+            ConfigChangeEvent,
+            ConfigField,
+            ConfigObserver,
+            ConfigObserverStatus,
+            ConfigValue,
+        ),
+        BlackBox,
+        BlackBoxEvent,
+        FailureRecord,
+        SharedBlackBox,
+        ImpactPhase,
+        ImpactRangeConfig,
+        ImpactRanger,
+        ImpactStats,
+        RangeTransition,
+        RearmPolicy,
+        LeverArmCompensation,
+        LeverArmCompensator,
+        TraceLevel,
+        ContinuityBreak,
+        ReentryYaw,
+        FrameCheck,
+        ParseDiagnostic,
+        ParseDiagnostics,
+        ParsePolicy,
+        ShutdownCheck,
+        ShutdownEvidence,
+        ShutdownItem,
+    ),
+);
+
+/// the traits of the prelude, as bounds
+#[allow(dead_code)]
+fn traits<M: MetricsSink, T: TypedRegister, D: OwnedDelay>() {}
+
+#[test]
+fn one_call_per_feature() {
+    // driver and builder
+    let mut mpu = Mpu6050Builder::new()
+        .i2c(RegisterMock::new())
+        .build()
+        .unwrap();
+    mpu.init(&mut Delay).unwrap();
+
+    // settings and their errors
+    mpu.apply_settings(&Mpu6050Settings::new().with_accel_range(AccelRange::G4))
+        .unwrap();
+    assert!(matches!(
+        mpu.apply_settings(&Mpu6050Settings::new().with_dlpf_cfg(7)),
+        Err(Mpu6050Error::InvalidSettings(
+            SettingsError::ReservedDlpfCfg(7)
+        ))
+    ));
+
+    // scaled reads and tilt
+    let acc: Vec3A = mpu.get_acc().unwrap();
+    assert!((acc.z - 2.).abs() < 1e-3, "{}", acc);
+    let tilt: TiltEstimate = mpu.get_acc_angles_checked().unwrap();
+    assert!(tilt.roll.abs() < 1e-3);
+
+    // typed register access
+    assert_eq!(mpu.read_register(Register::WHO_AM_I).unwrap(), 0x68);
+
+    // FIFO layout
+    mpu.set_fifo_sources(FifoSources::NONE.with_accel(true))
+        .unwrap();
+    let schema: FifoSchema = mpu.fifo_schema();
+    let frame: FifoFrame = mpu
+        .parse_fifo_frame::<()>(&schema, &[0, 0, 0, 0, 0x20, 0])
+        .unwrap();
+    assert_eq!(frame.acc_g(), Some(Vec3A::Z));
+
+    // fusion with provenance
+    let sample = MpuSample::new(acc, Vec3A::ZERO, 25.);
+    assert!(sample.provenance().is_measured());
+    let mut filter = ComplementaryFilter::new(0.98).with_accel_gate(Some(0.5));
+    filter.update_sample(&sample, 0.01);
+    let estimate: OrientationEstimate = filter.estimate();
+    assert!(estimate.accel_gated);
+
+    // diagnostics
+    let state: DriverStateSnapshot = mpu.debug_state();
+    assert_eq!(state.accel_range, AccelRange::G4);
+    let stats: IoStats = mpu.io_stats();
+    assert!(stats.transactions > 0);
+}
@@ -0,0 +1,425 @@
+//! Provenance tags: encoding bounds, the complementary filter weighing tagged inputs, and a
+//! pipeline through every producing stage against what each stage did, see the `provenance`
+//! module.
+
+mod common;
+
+use std::cell::RefCell;
+
+use mpu6050::device::*;
+use mpu6050::interleave::{merge_interleaved, RangeInterleave};
+use mpu6050::interpolation::{InterpolatingBuffer, TimedSample};
+use mpu6050::orientation::ComplementaryFilter;
+use mpu6050::packed::*;
+use mpu6050::provenance::*;
+use mpu6050::resample::{resample, ResampledItem, UniformResampler};
+use mpu6050::sampling::SampleControl;
+use mpu6050::*;
+
+use common::{NoDelay, SharedBus};
+
+use Provenance::*;
+
+const ADDR: u8 = 0x68;
+
+fn tags(acc: Provenance, gyro: Provenance, temp: Provenance) -> SampleProvenance {
+    SampleProvenance::MEASURED
+        .with(FieldGroup::Acc, acc)
+        .with(FieldGroup::Gyro, gyro)
+        .with(FieldGroup::Temp, temp)
+}
+
+#[test]
+fn encoding_is_bounded_and_round_trips() {
+    assert_eq!(SampleProvenance::default(), SampleProvenance::MEASURED);
+    assert_eq!(SampleProvenance::MEASURED.to_bytes(), [0; PROVENANCE_LEN]);
+    assert!(core::mem::size_of::<SampleProvenance>() <= 2 * PROVENANCE_LEN);
+
+    let mut valid = 0;
+    for byte in 0..=u8::MAX {
+        if let Some(tag) = Provenance::from_bits(byte) {
+            assert_eq!(tag.to_bits(), byte);
+            assert_eq!(tag.to_string().parse(), Ok(tag));
+            valid += 1;
+        }
+    }
+    // 5 kinds plus 32 ages
+    assert_eq!(valid, 5 + MAX_HELD_AGE as usize + 1);
+    assert_eq!(Provenance::held(1000), Held { age: MAX_HELD_AGE });
+    assert_eq!(Provenance::from_bits(0x06), None);
+    assert_eq!(Provenance::from_bits(0x09), None);
+
+    let all = tags(Held { age: 31 }, Substituted, Synthesized);
+    assert_eq!(all.to_string(), "H31,S,Y");
+    assert_eq!(SampleProvenance::from_bytes(all.to_bytes()), Some(all));
+    for bad in ["", "M,M", "M,M,M,M", "H32,M,M", "Q,M,M", "h1,M,M"] {
+        assert_eq!(
+            bad.parse::<SampleProvenance>(),
+            Err(ParseProvenanceError),
+            "{}",
+            bad
+        );
+    }
+    assert_eq!(
+        " I , M ,X".parse(),
+        Ok(tags(Interpolated, Measured, Invalid))
+    );
+
+    // combining keeps the worst
+    assert_eq!(Measured.worst(Interpolated), Interpolated);
+    assert_eq!(Held { age: 3 }.worst(Held { age: 1 }), Held { age: 3 });
+    assert_eq!(Held { age: 31 }.worst(Substituted), Substituted);
+    assert_eq!(Invalid.worst(Synthesized), Invalid);
+    assert_eq!(
+        SampleProvenance::MEASURED.interpolated(tags(Held { age: 2 }, Measured, Invalid)),
+        tags(Held { age: 2 }, Interpolated, Invalid)
+    );
+}
+
+/// rotation angle of `q`, precise for small angles unlike `angle_between`
+fn angle(q: Quat) -> f32 {
+    2. * q.xyz().length().asin()
+}
+
+/// angle the filter moves from identity in one step
+fn step_angle(provenance: SampleProvenance, gyro: Vec3A) -> (f32, Vec3A) {
+    let mut filter = ComplementaryFilter::new(0.9);
+    let tilted = Vec3A::new(0.1f32.sin(), 0., 0.1f32.cos());
+    let sample = MpuSample::new(tilted, gyro, 25.).with_provenance(provenance);
+    let q = filter.update_sample(&sample, 0.01);
+    (angle(q), filter.bias_estimate())
+}
+
+#[test]
+fn filter_weighs_inputs_by_their_tags() {
+    let (measured, measured_bias) = step_angle(SampleProvenance::MEASURED, Vec3A::ZERO);
+    // the same step as the plain update
+    let mut plain = ComplementaryFilter::new(0.9);
+    let q = plain.update(
+        Vec3A::ZERO,
+        Vec3A::new(0.1f32.sin(), 0., 0.1f32.cos()),
+        0.01,
+    );
+    assert!((angle(q) - measured).abs() < 1e-7);
+    assert!((measured - 0.01).abs() < 1e-4);
+
+    for (acc, weight) in [
+        (Interpolated, 0.5),
+        (Held { age: 0 }, 0.5),
+        (Held { age: 3 }, 0.125),
+        (Substituted, 0.25),
+        (Synthesized, 0.),
+        (Invalid, 0.),
+    ] {
+        let provenance = tags(acc, Measured, Measured);
+        let (moved, bias) = step_angle(provenance, Vec3A::ZERO);
+        assert!(
+            (moved - measured * weight).abs() < 1e-6,
+            "{}: {}",
+            acc,
+            moved
+        );
+        assert!(
+            (bias.length() - measured_bias.length() * weight).abs() < 1e-6,
+            "{}",
+            acc
+        );
+    }
+
+    // the bias learns at both weights, the gyro is integrated anyway
+    let (_, held_gyro_bias) =
+        step_angle(tags(Interpolated, Held { age: 1 }, Measured), Vec3A::ZERO);
+    assert!((held_gyro_bias.length() - measured_bias.length() * 0.5 * 0.25).abs() < 1e-6);
+    let rate = Vec3A::new(0., 0., 1.);
+    let (turned, _) = step_angle(tags(Synthesized, Synthesized, Measured), rate);
+    assert!((turned - 0.01).abs() < 1e-5);
+
+    // an invalid gyro is NaN: no prediction, the filter stays finite
+    let mut filter = ComplementaryFilter::new(0.9);
+    let partial = MpuSample::new(Vec3A::Z, Vec3A::NAN, f32::NAN)
+        .with_provenance(tags(Measured, Invalid, Invalid));
+    let q = filter.update_sample(&partial, 0.01);
+    assert!(angle(q) < 1e-6);
+    assert!(filter.bias_estimate().is_finite());
+}
+
+thread_local! {
+    static TAPPED: RefCell<Vec<MpuSample>> = const { RefCell::new(Vec::new()) };
+}
+
+/// spike rejection: an X reading beyond 1.5 g is replaced by the last plausible one
+fn reject_spikes(sample: &mut MpuSample) {
+    if sample.acc().x.abs() > 1.5 {
+        let tags = sample.provenance().with(FieldGroup::Acc, Substituted);
+        *sample = sample.with_acc(Vec3A::Z).with_provenance(tags);
+    }
+}
+
+fn tap(sample: &MpuSample) {
+    TAPPED.with(|t| t.borrow_mut().push(*sample));
+}
+
+fn tapped() -> Vec<MpuSample> {
+    TAPPED.with(|t| t.take())
+}
+
+/// `n` samples through the sampling loop
+fn sample(mpu: &mut Mpu6050<SharedBus>, n: usize) -> Vec<MpuSample> {
+    let mut samples = Vec::new();
+    mpu.run_sampling_loop(
+        || Ok::<(), ()>(()),
+        |sample, _| {
+            samples.push(sample);
+            if samples.len() == n {
+                SampleControl::Stop
+            } else {
+                SampleControl::Continue
+            }
+        },
+    )
+    .unwrap();
+    samples
+}
+
+fn set_acc(bus: &SharedBus, acc_g: Vec3A) {
+    let frame = synthetic::frame_bytes(acc_g, Vec3A::ZERO, AccelRange::G2, GyroRange::D250);
+    bus.device(ADDR, |m| m.set_frame(&frame));
+}
+
+fn acc_tag(sample: &MpuSample) -> Provenance {
+    sample.provenance().get(FieldGroup::Acc)
+}
+
+#[test]
+fn pipeline_tags_match_what_each_stage_did() {
+    let bus = SharedBus::new(&[ADDR]);
+    let mut mpu = Mpu6050Builder::new().i2c(bus.clone()).build().unwrap();
+    mpu.init(&mut NoDelay).unwrap();
+    mpu.set_sample_hook(Some(reject_spikes));
+    mpu.set_sample_tap(Some(tap));
+    set_acc(&bus, Vec3A::Z);
+    // past the settling of the wake
+    sample(&mut mpu, 3);
+    tapped();
+
+    // ground truth in order: what the stage did to each output
+    let mut outputs: Vec<(MpuSample, SampleProvenance)> = Vec::new();
+
+    // sensor reads
+    for s in sample(&mut mpu, 2) {
+        outputs.push((s, SampleProvenance::MEASURED));
+    }
+    // a spike on X, replaced by the hook
+    set_acc(&bus, Vec3A::new(1.9, 0., 1.));
+    let spike = sample(&mut mpu, 1)[0];
+    assert_eq!(spike.acc(), Vec3A::Z);
+    outputs.push((spike, tags(Substituted, Measured, Measured)));
+    set_acc(&bus, Vec3A::Z);
+
+    // single sensor reads reach the tap as partial samples
+    mpu.get_acc().unwrap();
+    mpu.get_gyro().unwrap();
+    mpu.get_temp().unwrap();
+    let partial = tapped();
+    let partial_tags = [
+        tags(Measured, Invalid, Invalid),
+        tags(Invalid, Measured, Invalid),
+        tags(Invalid, Invalid, Measured),
+    ];
+    assert_eq!(partial.len(), 2 + 1 + 3);
+    for (s, truth) in partial[partial.len() - 3..].iter().zip(partial_tags) {
+        outputs.push((*s, truth));
+    }
+
+    // range interleaving, merged over windows of 2 * period + 1
+    let config = RangeInterleave {
+        low: AccelRange::G2,
+        high: AccelRange::G16,
+        period: 3,
+    };
+    mpu.enable_range_interleaving(config).unwrap();
+    let tagged = sample(&mut mpu, 13);
+    mpu.disable_range_interleaving(AccelRange::G2).unwrap();
+    // low settling, low, low, high settling, high, high, low settling, ...
+    for (start, truth) in [
+        // centre 3, high: only low samples 1 and 2 before it, 2 is one sample away
+        (0, Held { age: 1 }),
+        // centre 4, high: low samples 2 and 7 around it
+        (1, Interpolated),
+        // centre 8, low and usable
+        (5, Measured),
+    ] {
+        let merged = merge_interleaved(&tagged[start..start + 7]).unwrap();
+        assert_eq!(acc_tag(&merged.sample), truth, "window at {}", start);
+        assert_eq!(merged.sample.acc(), Vec3A::Z);
+        outputs.push((merged.sample, tags(truth, Measured, Measured)));
+    }
+
+    // interpolating buffer: exact, between a measured and a held sample, extrapolated
+    let measured = outputs[0].0;
+    let held = outputs[outputs.len() - 3].0;
+    let mut buffer = InterpolatingBuffer::<4>::new();
+    buffer.set_extrapolation_window(20_000);
+    buffer
+        .push(TimedSample {
+            t_us: 0,
+            sample: measured,
+            orientation: None,
+        })
+        .unwrap();
+    buffer
+        .push(TimedSample {
+            t_us: 10_000,
+            sample: held,
+            orientation: None,
+        })
+        .unwrap();
+    for (t_us, truth) in [
+        (0, SampleProvenance::MEASURED),
+        (10_000, tags(Held { age: 1 }, Measured, Measured)),
+        (5_000, tags(Held { age: 1 }, Interpolated, Interpolated)),
+        (15_000, SampleProvenance::uniform(Synthesized)),
+    ] {
+        let at = buffer.sample_at(t_us).unwrap();
+        assert_eq!(at.provenance, truth, "at {} µs", t_us);
+        let s = MpuSample::new(at.acc, at.gyro, at.temp).with_provenance(at.provenance);
+        outputs.push((s, truth));
+    }
+
+    // resampling 100 Hz of jittered inputs to 50 Hz: grid points between inputs
+    let inputs = (0..6u64).map(|k| TimedSample {
+        t_us: k * 10_000 + [0, 300, 400, 700, 0, 200][k as usize],
+        sample: measured,
+        orientation: None,
+    });
+    let mut grid = 0;
+    for item in resample(inputs, UniformResampler::new(50, 100., 100_000)) {
+        let Ok(ResampledItem::Sample { t_us, sample: at }) = item else {
+            panic!("no gap expected")
+        };
+        // 0 and 40 ms fall on inputs
+        let truth = match [0, 40_000].contains(&t_us) {
+            true => SampleProvenance::MEASURED,
+            false => SampleProvenance::uniform(Interpolated),
+        };
+        assert_eq!(at.provenance, truth, "grid point at {} µs", t_us);
+        grid += 1;
+    }
+    assert_eq!(grid, 3);
+
+    for (s, truth) in &outputs {
+        assert_eq!(s.provenance(), *truth);
+    }
+    let kinds = |p: Provenance| {
+        outputs
+            .iter()
+            .any(|(s, _)| FieldGroup::ALL.iter().any(|g| s.provenance().get(*g) == p))
+    };
+    for kind in [
+        Measured,
+        Held { age: 1 },
+        Interpolated,
+        Substituted,
+        Synthesized,
+        Invalid,
+    ] {
+        assert!(kinds(kind), "{} not exercised", kind);
+    }
+
+    // encoders: packed binary and the CSV columns keep every tag
+    let samples: Vec<_> = outputs.iter().map(|(s, _)| *s).collect();
+    let mut buf = vec![0; packed_len_with_provenance(samples.len(), PackedBits::B12)];
+    let len = encode_binary_packed(
+        &samples,
+        PackedBits::B12,
+        AccelRange::G2,
+        GyroRange::D250,
+        &mut buf,
+    )
+    .unwrap();
+    assert_eq!(len, buf.len());
+    let mut decoded = vec![MpuSample::default(); samples.len()];
+    let batch = decode_binary_packed(&buf, &mut decoded).unwrap();
+    assert!(batch.provenance);
+    for (d, (_, truth)) in decoded.iter().zip(&outputs) {
+        assert_eq!(d.provenance(), *truth);
+    }
+
+    let mut csv = format!("acc_x,{}\n", PROVENANCE_CSV_COLUMNS.join(","));
+    for s in &samples {
+        csv += &format!("{},{}\n", s.acc().x, s.provenance());
+    }
+    let parsed: Vec<SampleProvenance> = csv
+        .lines()
+        .skip(1)
+        .map(|line| line.split_once(',').unwrap().1.parse().unwrap())
+        .collect();
+    assert_eq!(parsed, outputs.iter().map(|(_, t)| *t).collect::<Vec<_>>());
+
+    // the filter stays finite on every output, invalid parts included
+    let mut filter = ComplementaryFilter::new(0.98);
+    for s in &samples {
+        filter.update_sample(s, 0.01);
+    }
+    assert!(filter.orientation().is_finite());
+}
+
+#[test]
+fn packed_batches_without_tags_keep_their_size() {
+    let samples = [MpuSample::new(Vec3A::Z, Vec3A::ZERO, 25.); 4];
+    let mut buf = [0; 64];
+    let len = encode_binary_packed(
+        &samples,
+        PackedBits::B10,
+        AccelRange::G2,
+        GyroRange::D250,
+        &mut buf,
+    )
+    .unwrap();
+    assert_eq!(len, packed_len(4, PackedBits::B10));
+    assert_eq!(buf[0], PACKED_VERSION);
+    assert_eq!(buf[3], 0);
+
+    // version 1 batch: same layout, no block
+    let mut v1 = buf[..len].to_vec();
+    v1[0] = 1;
+    let crc = crc16(&v1[..len - PACKED_CRC_LEN]);
+    v1[len - PACKED_CRC_LEN..].copy_from_slice(&crc.to_le_bytes());
+    let mut out = [MpuSample::default(); 4];
+    let batch = decode_binary_packed(&v1, &mut out).unwrap();
+    assert!(!batch.provenance);
+    assert!(out.iter().all(|s| s.provenance().is_measured()));
+
+    // a tagged batch needs the block, a reserved tag is refused
+    let tagged = [samples[0].with_provenance(SampleProvenance::uniform(Interpolated))];
+    assert_eq!(
+        encode_binary_packed(
+            &tagged,
+            PackedBits::B10,
+            AccelRange::G2,
+            GyroRange::D250,
+            &mut buf[..packed_len(1, PackedBits::B10)],
+        ),
+        Err(EncodeError::BufferTooSmall(packed_len_with_provenance(
+            1,
+            PackedBits::B10
+        )))
+    );
+    let len = encode_binary_packed(
+        &tagged,
+        PackedBits::B10,
+        AccelRange::G2,
+        GyroRange::D250,
+        &mut buf,
+    )
+    .unwrap();
+    let block = packed_len(1, PackedBits::B10) - PACKED_CRC_LEN;
+    assert_eq!(buf[block..block + PROVENANCE_LEN], [1, 1, 1]);
+    buf[block] = 0x07;
+    let crc = crc16(&buf[..len - PACKED_CRC_LEN]);
+    buf[len - PACKED_CRC_LEN..len].copy_from_slice(&crc.to_le_bytes());
+    assert_eq!(
+        decode_binary_packed(&buf[..len], &mut out),
+        Err(DecodeError::BadProvenance)
+    );
+}
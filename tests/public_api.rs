@@ -0,0 +1,603 @@
+//! Snapshot of the public API in `tests/golden/public_api.txt`, regenerated from the sources
+//! and compared on every test run: a public item added, removed or changed fails the suite
+//! until the snapshot is regenerated with `UPDATE_GOLDENS=1`, and the diff reviewed with the
+//! change. No feature or toolchain requirement, the sources are scanned as text.
+//!
+//! One line per item: module, then the item as declared with its `cfg`, `derive`, `repr`,
+//! `non_exhaustive` and `deprecated` attributes, whitespace normalized. Lines of inherent
+//! impls carry the impl header, fields of structs and struct variants their type, trait
+//! impls are listed by header. Items generated by macros are not seen.
+
+use std::collections::HashMap;
+use std::path::Path;
+
+const SNAPSHOT: &str = "tests/golden/public_api.txt";
+
+#[derive(Clone, Debug)]
+enum Block {
+    /// module body, recording `pub` items
+    Module(String),
+    /// inherent impl with its header, recording `pub` associated items
+    Impl(String),
+    /// trait, recording every associated item
+    Trait(String),
+    /// struct with named fields, recording `pub` fields
+    Struct(String),
+    /// enum, recording variants
+    Enum(String),
+    /// struct variant, recording fields
+    Variant(String),
+    /// function bodies, private items, macros
+    Skip,
+}
+
+/// Scanner of one file, see the module docs
+struct Scanner<'a> {
+    module: &'a str,
+    stack: Vec<Block>,
+    /// text of the statement since the last `;`, `,`, `{` or `}` of the current block
+    pending: String,
+    /// nesting of (), [] and <> within `pending`
+    nesting: i32,
+    /// braces within a use, const, static or type statement, part of the text
+    inline_braces: i32,
+    lines: Vec<String>,
+}
+
+impl<'a> Scanner<'a> {
+    fn new(module: &'a str) -> Self {
+        Self {
+            module,
+            stack: vec![Block::Module(module.into())],
+            pending: String::new(),
+            nesting: 0,
+            inline_braces: 0,
+            lines: Vec::new(),
+        }
+    }
+
+    fn scan(mut self, source: &str) -> Vec<String> {
+        let chars: Vec<char> = source.chars().collect();
+        let mut i = 0;
+        while i < chars.len() {
+            let c = chars[i];
+            let next = chars.get(i + 1).copied();
+            match c {
+                '/' if next == Some('/') => {
+                    while i < chars.len() && chars[i] != '\n' {
+                        i += 1;
+                    }
+                    continue;
+                }
+                '/' if next == Some('*') => {
+                    let mut depth = 0;
+                    while i < chars.len() {
+                        if chars[i] == '/' && chars.get(i + 1) == Some(&'*') {
+                            depth += 1;
+                            i += 1;
+                        } else if chars[i] == '*' && chars.get(i + 1) == Some(&'/') {
+                            depth -= 1;
+                            i += 1;
+                            if depth == 0 {
+                                i += 1;
+                                break;
+                            }
+                        }
+                        i += 1;
+                    }
+                    continue;
+                }
+                '"' => {
+                    let raw_hashes = raw_prefix(&chars, i);
+                    let start = i;
+                    i += 1;
+                    loop {
+                        match chars.get(i) {
+                            None => break,
+                            Some('\\') if raw_hashes.is_none() => i += 2,
+                            Some('"') => {
+                                let hashes = raw_hashes.unwrap_or(0);
+                                if (1..=hashes).all(|k| chars.get(i + k) == Some(&'#')) {
+                                    i += 1 + hashes;
+                                    break;
+                                }
+                                i += 1;
+                            }
+                            Some(_) => i += 1,
+                        }
+                    }
+                    let literal: String = chars[start..i.min(chars.len())].iter().collect();
+                    self.pending.push_str(&literal);
+                    continue;
+                }
+                '\'' => {
+                    // a char literal, or a lifetime kept as text
+                    let end = match (next, chars.get(i + 2)) {
+                        (Some('\\'), _) => (i + 2..chars.len()).find(|k| chars[*k] == '\''),
+                        (Some(_), Some('\'')) => Some(i + 2),
+                        _ => None,
+                    };
+                    if let Some(end) = end {
+                        let literal: String = chars[i..=end].iter().collect();
+                        self.pending.push_str(&literal);
+                        i = end + 1;
+                        continue;
+                    }
+                    self.pending.push(c);
+                }
+                '{' => self.open(),
+                '}' => self.close(),
+                ';' if self.nesting <= 0 => self.separator(c),
+                ',' if self.nesting == 0 && self.in_fields() => self.separator(c),
+                '(' | '[' => {
+                    self.nesting += 1;
+                    self.pending.push(c);
+                }
+                ')' | ']' => {
+                    self.nesting -= 1;
+                    self.pending.push(c);
+                }
+                '<' if self.in_type_context() => {
+                    self.nesting += 1;
+                    self.pending.push(c);
+                }
+                '>' if self.in_type_context() && !self.pending.ends_with('-') => {
+                    self.nesting -= 1;
+                    self.pending.push(c);
+                }
+                _ => self.pending.push(c),
+            }
+            i += 1;
+        }
+        self.lines
+    }
+
+    /// `<` and `>` are brackets in field types and signatures, operators in bodies
+    fn in_type_context(&self) -> bool {
+        !matches!(self.stack.last(), Some(Block::Skip)) && !self.in_value()
+    }
+
+    /// commas separate fields or variants, not generics and where clauses
+    fn in_fields(&self) -> bool {
+        self.inline_braces == 0
+            && matches!(
+                self.block(),
+                Block::Struct(_) | Block::Enum(_) | Block::Variant(_)
+            )
+    }
+
+    /// within the value of a const or static, after its `=`
+    fn in_value(&self) -> bool {
+        let statement = strip_attributes(&self.pending);
+        let statement = statement.trim_start();
+        let declaration = ["const ", "static ", "pub const ", "pub static "]
+            .iter()
+            .any(|prefix| statement.starts_with(prefix));
+        declaration && statement.contains('=')
+    }
+
+    fn block(&self) -> &Block {
+        self.stack.last().unwrap()
+    }
+
+    fn take_pending(&mut self) -> String {
+        self.nesting = 0;
+        std::mem::take(&mut self.pending)
+    }
+
+    fn open(&mut self) {
+        if self.inline_braces > 0 || is_inline_statement(&self.pending) {
+            self.inline_braces += 1;
+            self.pending.push('{');
+            return;
+        }
+        let statement = self.take_pending();
+        let (attributes, item) = split_attributes(&statement);
+        let item = normalize(&item);
+        let pushed = match self.block().clone() {
+            Block::Module(path) => self.open_in_module(&path, &attributes, &item),
+            Block::Impl(header) => {
+                if is_public(&item) {
+                    self.record_member(&header, &attributes, &item);
+                }
+                Block::Skip
+            }
+            Block::Trait(name) => {
+                self.record_member(&format!("trait {}", name), &attributes, &item);
+                Block::Skip
+            }
+            Block::Enum(name) => {
+                let variant = format!("{}::{}", name, item);
+                self.record(&format!("{} {{", variant), &attributes);
+                Block::Variant(variant)
+            }
+            Block::Struct(_) | Block::Variant(_) | Block::Skip => Block::Skip,
+        };
+        self.stack.push(pushed);
+    }
+
+    fn open_in_module(&mut self, path: &str, attributes: &str, item: &str) -> Block {
+        let keyword = item_keyword(item);
+        if let Some(rest) = item.strip_prefix("impl") {
+            if rest.contains(" for ") {
+                self.record(item, attributes);
+                return Block::Skip;
+            }
+            return Block::Impl(join_attributes(attributes, item));
+        }
+        if !is_public(item) {
+            return Block::Skip;
+        }
+        self.record(item, attributes);
+        let name = item_name(item);
+        match keyword {
+            "mod" => Block::Module(format!("{}::{}", path, name)),
+            "struct" => Block::Struct(name),
+            "enum" => Block::Enum(name),
+            "trait" => Block::Trait(name),
+            _ => Block::Skip,
+        }
+    }
+
+    fn close(&mut self) {
+        if self.inline_braces > 0 {
+            self.inline_braces -= 1;
+            self.pending.push('}');
+            return;
+        }
+        // the last field or variant without a trailing comma
+        if !self.pending.trim().is_empty() {
+            self.separator(',');
+        }
+        self.take_pending();
+        self.stack.pop();
+        if self.stack.is_empty() {
+            self.stack.push(Block::Module(self.module.into()));
+        }
+    }
+
+    fn separator(&mut self, c: char) {
+        if self.inline_braces > 0 {
+            self.pending.push(c);
+            return;
+        }
+        let statement = self.take_pending();
+        let (attributes, item) = split_attributes(&statement);
+        let item = normalize(&declaration_only(&item));
+        if item.is_empty() || item.starts_with("#!") {
+            return;
+        }
+        match self.block().clone() {
+            Block::Module(_) if is_public(&item) => self.record(&item, &attributes),
+            Block::Module(_) if item.starts_with("impl") => self.record(&item, &attributes),
+            Block::Impl(header) if is_public(&item) => {
+                self.record_member(&header, &attributes, &item)
+            }
+            Block::Trait(name) => {
+                self.record_member(&format!("trait {}", name), &attributes, &item)
+            }
+            Block::Struct(name) if is_public(&item) => {
+                self.record_member(&format!("struct {}", name), &attributes, &item)
+            }
+            Block::Enum(name) => self.record(&format!("{}::{}", name, item), &attributes),
+            Block::Variant(variant) => self.record_member(&variant, &attributes, &item),
+            _ => {}
+        }
+    }
+
+    /// an associated item, field or variant field of `parent`, its attributes inside the braces
+    fn record_member(&mut self, parent: &str, attributes: &str, item: &str) {
+        let member = join_attributes(attributes, item);
+        self.record(&format!("{} {{ {} }}", parent, member), "");
+    }
+
+    fn record(&mut self, item: &str, attributes: &str) {
+        let line = join_attributes(attributes, item);
+        self.lines.push(format!("{}: {}", self.module, line));
+    }
+}
+
+/// number of `#` of a raw string starting at the quote at `quote`, None for a plain string
+fn raw_prefix(chars: &[char], quote: usize) -> Option<usize> {
+    let mut k = quote;
+    let mut hashes = 0;
+    while k > 0 && chars[k - 1] == '#' {
+        hashes += 1;
+        k -= 1;
+    }
+    let is_raw = k > 0
+        && chars[k - 1] == 'r'
+        && (k < 2 || !chars[k - 2].is_alphanumeric() || chars[k - 2] == 'b');
+    is_raw.then_some(hashes)
+}
+
+/// a statement whose braces are part of its text: use trees, const and static values, type
+/// aliases
+fn is_inline_statement(pending: &str) -> bool {
+    let statement = strip_attributes(pending);
+    let statement = statement.trim_start();
+    let statement = statement.strip_prefix("pub ").unwrap_or(statement);
+    ["use ", "const ", "static ", "type ", "let "]
+        .iter()
+        .any(|prefix| statement.starts_with(prefix))
+        && !statement.starts_with("const fn")
+}
+
+/// the declaration of a const or static, without its value
+fn declaration_only(item: &str) -> String {
+    let bare = item.trim_start();
+    let bare = bare.strip_prefix("pub ").unwrap_or(bare);
+    if bare.starts_with("const ") || bare.starts_with("static ") {
+        if let Some(at) = item.find(" = ").or_else(|| item.find('=')) {
+            return item[..at].into();
+        }
+    }
+    item.into()
+}
+
+fn is_public(item: &str) -> bool {
+    item.starts_with("pub ")
+}
+
+/// the keyword after `pub` and qualifiers
+fn item_keyword(item: &str) -> &str {
+    item.split_whitespace()
+        .find(|word| !matches!(*word, "pub" | "const" | "unsafe" | "async" | "extern"))
+        .unwrap_or("")
+}
+
+/// name of a mod, struct, enum or trait item
+fn item_name(item: &str) -> String {
+    let mut words = item.split_whitespace();
+    words.find(|word| matches!(*word, "mod" | "struct" | "enum" | "trait"));
+    let name = words.next().unwrap_or("");
+    name.split(['<', '(', ':']).next().unwrap_or("").into()
+}
+
+/// attributes kept in the snapshot
+const KEPT_ATTRIBUTES: [&str; 5] = ["cfg", "derive", "repr", "non_exhaustive", "deprecated"];
+
+/// outer attributes and the rest of a statement
+fn split_attributes(statement: &str) -> (String, String) {
+    let mut attributes = Vec::new();
+    let mut rest = statement.trim_start();
+    while let Some(body) = rest.strip_prefix("#[").or_else(|| rest.strip_prefix("#![")) {
+        let inner = rest.starts_with("#!");
+        let mut depth = 1;
+        let mut end = body.len();
+        for (k, c) in body.char_indices() {
+            match c {
+                '[' => depth += 1,
+                ']' => {
+                    depth -= 1;
+                    if depth == 0 {
+                        end = k;
+                        break;
+                    }
+                }
+                _ => {}
+            }
+        }
+        let attribute = normalize(&body[..end]);
+        let name = attribute
+            .split(|c: char| !c.is_alphanumeric() && c != '_')
+            .next()
+            .unwrap_or("");
+        if !inner && KEPT_ATTRIBUTES.contains(&name) {
+            attributes.push(format!("#[{}]", attribute));
+        }
+        rest = body[(end + 1).min(body.len())..].trim_start();
+    }
+    (attributes.join(" "), rest.into())
+}
+
+fn strip_attributes(statement: &str) -> String {
+    split_attributes(statement).1
+}
+
+fn join_attributes(attributes: &str, item: &str) -> String {
+    match attributes.is_empty() {
+        true => item.into(),
+        false => format!("{} {}", attributes, item),
+    }
+}
+
+/// whitespace collapsed, none inside brackets, no trailing commas
+fn normalize(text: &str) -> String {
+    let collapsed = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    let mut out = String::new();
+    let chars: Vec<char> = collapsed.chars().collect();
+    for (k, c) in chars.iter().enumerate() {
+        let previous = out.chars().last();
+        match c {
+            ' ' if matches!(previous, Some('(' | '[')) => {}
+            ' ' if matches!(chars.get(k + 1), Some(')' | ']' | ',')) => {}
+            ')' | ']' | '>' if matches!(previous, Some(',')) => {
+                out.pop();
+                while out.ends_with(' ') {
+                    out.pop();
+                }
+                out.push(*c);
+            }
+            _ => out.push(*c),
+        }
+    }
+    out.trim().trim_end_matches(',').trim_end().into()
+}
+
+/// `pub mod` declarations of lib.rs, the files of the public modules
+fn public_modules(lib: &str) -> Vec<String> {
+    lib.lines()
+        .filter_map(|line| line.trim().strip_prefix("pub mod "))
+        .filter_map(|rest| rest.strip_suffix(';'))
+        .map(String::from)
+        .collect()
+}
+
+fn generate(root: &Path) -> String {
+    let lib = std::fs::read_to_string(root.join("src/lib.rs")).unwrap();
+    let mut lines = Scanner::new("crate").scan(&lib);
+    let mut modules = public_modules(&lib);
+    modules.sort();
+    for module in modules {
+        let path = root.join(format!("src/{}.rs", module));
+        let source = std::fs::read_to_string(&path)
+            .unwrap_or_else(|_| panic!("{} not found", path.display()));
+        lines.extend(Scanner::new(&format!("crate::{}", module)).scan(&source));
+    }
+    let mut out = lines.join("\n");
+    out.push('\n');
+    out
+}
+
+/// lines only in `expected` (-) and only in `actual` (+), counted as multisets
+fn diff(expected: &str, actual: &str) -> String {
+    let mut counts: HashMap<&str, i32> = HashMap::new();
+    for line in expected.lines() {
+        *counts.entry(line).or_default() += 1;
+    }
+    for line in actual.lines() {
+        *counts.entry(line).or_default() -= 1;
+    }
+    let mut out = String::new();
+    let mut removed = counts.clone();
+    for line in expected.lines() {
+        let count = removed.get_mut(line).unwrap();
+        if *count > 0 {
+            *count -= 1;
+            out.push_str(&format!("- {}\n", line));
+        }
+    }
+    let mut added = counts;
+    for line in actual.lines() {
+        let count = added.get_mut(line).unwrap();
+        if *count < 0 {
+            *count += 1;
+            out.push_str(&format!("+ {}\n", line));
+        }
+    }
+    out
+}
+
+#[test]
+fn public_api_matches_the_snapshot() {
+    let root = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let actual = generate(root);
+    let path = root.join(SNAPSHOT);
+    // as `trace::update_requested`, which needs the test-util feature
+    let update =
+        std::env::var_os("UPDATE_GOLDENS").is_some_and(|value| value != "0" && !value.is_empty());
+    if update {
+        std::fs::write(&path, &actual).unwrap();
+        return;
+    }
+    let expected = std::fs::read_to_string(&path)
+        .unwrap_or_default()
+        .replace("\r\n", "\n");
+    if expected != actual {
+        let changes = diff(&expected, &actual);
+        panic!(
+            "public API differs from {}, review and rerun with UPDATE_GOLDENS=1 if intended:\n{}",
+            SNAPSHOT,
+            match changes.is_empty() {
+                true => "order changed only\n",
+                false => &changes,
+            }
+        );
+    }
+}
+
+#[test]
+fn scanner_sees_items_fields_variants_and_impls() {
+    let source = r#"
+//! docs { not a block
+use crate::x::{A, B};
+/// doc with a brace {
+#[derive(Copy, Clone)]
+#[allow(dead_code)]
+pub struct Config {
+    /// field doc
+    pub rate: HashMap<u8, Vec<u16>>,
+    hidden: u8,
+    pub(crate) internal: u8,
+    pub callback: fn(u8, u8) -> bool,
+}
+struct Private {
+    pub seen: u8,
+}
+#[non_exhaustive]
+pub enum Event {
+    Plain,
+    Tuple(u8, u16),
+    Named {
+        /// at
+        at: u64,
+        value: Option<(u8, u8)>
+    },
+}
+pub const LIMIT: [u8; 2] = [1, 2];
+pub const TABLE: Config = Config { rate: 0, hidden: '}' as u8 };
+pub use crate::y::{C, D};
+#[cfg(feature = "driver")]
+impl<I, D> Driver<I, D>
+where
+    I: Write,
+{
+    /// method
+    pub fn read(
+        &mut self,
+        reg: u8,
+    ) -> Result<u8, Error> {
+        let text = "}{";
+        if a < b && c > d { return Ok(0) }
+        Ok(1)
+    }
+    fn private(&self) {}
+    pub(crate) fn internal(&self) {}
+    pub const MAX: u8 = 3;
+}
+impl Default for Config {
+    fn default() -> Self {
+        todo!()
+    }
+}
+pub trait Sink: Sync {
+    fn counter(&self, name: &'static str, delta: u32) {
+        let _ = (name, delta);
+    }
+    fn flush(&self);
+}
+#[deprecated(since = "0.2.0", note = "use `read`")]
+pub fn old(x: u8) -> u8 { x }
+impl Old {
+    #[deprecated]
+    pub fn older(&self) {}
+}
+"#;
+    let lines = Scanner::new("crate::m").scan(source);
+    assert_eq!(
+        lines,
+        [
+            "crate::m: #[derive(Copy, Clone)] pub struct Config",
+            "crate::m: struct Config { pub rate: HashMap<u8, Vec<u16>> }",
+            "crate::m: struct Config { pub callback: fn(u8, u8) -> bool }",
+            "crate::m: #[non_exhaustive] pub enum Event",
+            "crate::m: Event::Plain",
+            "crate::m: Event::Tuple(u8, u16)",
+            "crate::m: Event::Named {",
+            "crate::m: Event::Named { at: u64 }",
+            "crate::m: Event::Named { value: Option<(u8, u8)> }",
+            "crate::m: pub const LIMIT: [u8; 2]",
+            "crate::m: pub const TABLE: Config",
+            "crate::m: pub use crate::y::{C, D}",
+            "crate::m: #[cfg(feature = \"driver\")] impl<I, D> Driver<I, D> where I: Write { pub fn read(&mut self, reg: u8) -> Result<u8, Error> }",
+            "crate::m: #[cfg(feature = \"driver\")] impl<I, D> Driver<I, D> where I: Write { pub const MAX: u8 }",
+            "crate::m: impl Default for Config",
+            "crate::m: pub trait Sink: Sync",
+            "crate::m: trait Sink { fn counter(&self, name: &'static str, delta: u32) }",
+            "crate::m: trait Sink { fn flush(&self) }",
+            "crate::m: #[deprecated(since = \"0.2.0\", note = \"use `read`\")] pub fn old(x: u8) -> u8",
+            "crate::m: impl Old { #[deprecated] pub fn older(&self) }",
+        ]
+    );
+}
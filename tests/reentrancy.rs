@@ -0,0 +1,176 @@
+//! Callbacks calling back into their driver get `Reentrancy` instead of reaching its state,
+//! and the sample observer sees the driver's configuration, see the `reentrancy` module.
+
+mod common;
+
+use std::convert::Infallible;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::{Barrier, Mutex, OnceLock};
+
+use mpu6050::device::AccelRange;
+use mpu6050::metrics::{GaugeLimiter, MetricsSink};
+use mpu6050::reentrancy::{DriverInfo, SampleContext, SharedMpu6050};
+use mpu6050::supervisor::{SupervisorConfig, SupervisorEvent};
+use mpu6050::*;
+
+use common::{NoDelay, RegisterMock};
+
+type Shared = SharedMpu6050<RegisterMock>;
+type Error = Mpu6050Error<Infallible>;
+
+fn driver() -> Mpu6050<RegisterMock> {
+    let mut mpu = Mpu6050Builder::new()
+        .i2c(RegisterMock::new())
+        .build()
+        .unwrap();
+    mpu.init(&mut NoDelay).unwrap();
+    mpu
+}
+
+/// `with` from inside a callback of the driver `shared` holds: refused, nothing read
+fn reenter(shared: &OnceLock<Shared>, refused: &AtomicU32) {
+    let nested = shared.get().unwrap().with(|mpu| mpu.get_acc());
+    assert!(
+        matches!(nested, Err(Mpu6050Error::Reentrancy)),
+        "{:?}",
+        nested
+    );
+    refused.fetch_add(1, Ordering::Relaxed);
+}
+
+#[test]
+fn a_tap_calling_back_gets_reentrancy() {
+    static MPU: OnceLock<Shared> = OnceLock::new();
+    static REFUSED: AtomicU32 = AtomicU32::new(0);
+    fn tap(_: &MpuSample) {
+        reenter(&MPU, &REFUSED);
+    }
+
+    let mut mpu = driver();
+    mpu.set_sample_tap(Some(tap));
+    let shared = MPU.get_or_init(|| Shared::new(mpu));
+    let acc = shared.with(|mpu| mpu.get_acc()).unwrap();
+    assert_eq!(acc, Vec3A::Z);
+    let sample = shared.with(|mpu| mpu.get_all()).unwrap();
+    assert_eq!(sample.acc(), Vec3A::Z);
+    assert_eq!(REFUSED.load(Ordering::Relaxed), 2);
+    assert!(!shared.is_busy());
+}
+
+#[test]
+fn a_hook_supervisor_and_sink_calling_back_get_reentrancy() {
+    static MPU: OnceLock<Shared> = OnceLock::new();
+    static REFUSED: AtomicU32 = AtomicU32::new(0);
+    fn hook(_: &mut MpuSample) {
+        reenter(&MPU, &REFUSED);
+    }
+    fn tripped(_: SupervisorEvent) {
+        reenter(&MPU, &REFUSED);
+    }
+    struct Sink;
+    impl MetricsSink for Sink {
+        fn gauge(&self, _: &'static str, _: f32) {
+            reenter(&MPU, &REFUSED);
+        }
+    }
+    static SINK: Sink = Sink;
+
+    let mut mpu = driver();
+    mpu.set_sample_hook(Some(hook));
+    mpu.set_supervisor(SupervisorConfig {
+        max_gyro_rad_s: None,
+        max_acc_g: Some(0.5),
+        consecutive: 1,
+        callback: Some(tripped),
+    });
+    let shared = MPU.get_or_init(|| Shared::new(mpu));
+    shared
+        .with(|mpu| {
+            mpu.set_metrics_sink(Some(&SINK));
+            mpu.set_gauge_limiter(Some(GaugeLimiter::new(1)));
+            Ok::<_, Error>(())
+        })
+        .unwrap();
+    let before = REFUSED.load(Ordering::Relaxed);
+    let acc = shared.with(|mpu| mpu.get_acc()).unwrap();
+    assert_eq!(acc, Vec3A::Z);
+    // hook, supervisor once, at least one gauge
+    assert!(REFUSED.load(Ordering::Relaxed) - before >= 3);
+    let tripped = shared.with(|mpu| Ok::<_, Error>(mpu.supervisor_tripped()));
+    assert!(tripped.unwrap().is_some());
+}
+
+#[test]
+fn the_flag_outlives_a_panicking_callback_and_refuses_other_threads() {
+    static MPU: OnceLock<Shared> = OnceLock::new();
+    let shared = MPU.get_or_init(|| Shared::new(driver()));
+    let panicked = panic::catch_unwind(AssertUnwindSafe(|| {
+        shared.with(|_| -> Result<(), Error> { panic!("inside with") })
+    }));
+    assert!(panicked.is_err());
+    assert!(!shared.is_busy());
+    assert!(shared.with(|mpu| mpu.get_temp()).is_ok());
+
+    // another context finding the driver busy is refused, not blocked
+    let (entered, release) = (Barrier::new(2), Barrier::new(2));
+    std::thread::scope(|scope| {
+        let holder = scope.spawn(|| {
+            shared.with(|mpu| {
+                entered.wait();
+                release.wait();
+                mpu.get_acc()
+            })
+        });
+        entered.wait();
+        assert!(shared.is_busy());
+        assert!(matches!(
+            shared.with(|mpu| mpu.get_gyro()),
+            Err(Mpu6050Error::Reentrancy)
+        ));
+        release.wait();
+        assert!(holder.join().unwrap().is_ok());
+    });
+    assert!(!shared.is_busy());
+}
+
+#[test]
+fn the_observer_sees_the_configuration_of_the_read() {
+    static SEEN: Mutex<Vec<(Vec3A, DriverInfo)>> = Mutex::new(Vec::new());
+    fn observer(context: &SampleContext) {
+        let acc = context.sample().acc();
+        SEEN.lock().unwrap().push((acc, context.info()));
+    }
+
+    let mut mpu = driver();
+    mpu.set_sample_observer(Some(observer));
+    assert!(mpu.get_sample_observer().is_some());
+    mpu.get_acc().unwrap();
+    mpu.set_accel_range(AccelRange::G4).unwrap();
+    mpu.get_all().unwrap();
+    let seen = SEEN.lock().unwrap().clone();
+    assert_eq!(seen.len(), 2);
+    assert_eq!(seen[0].0, Vec3A::Z);
+    assert_eq!(seen[0].1.accel_range, AccelRange::G2);
+    assert_eq!(seen[1].0, Vec3A::Z * 2.);
+    assert_eq!(seen[1].1, mpu.driver_info());
+    assert_eq!(seen[1].1.accel_range, AccelRange::G4);
+    assert!(seen[1].1.config_epoch > seen[0].1.config_epoch);
+    assert_eq!(seen[1].1.slave_addr, 0x68);
+
+    // raw reads are not scaled output
+    mpu.get_acc_raw().unwrap();
+    assert_eq!(SEEN.lock().unwrap().len(), 2);
+    mpu.set_sample_observer(None);
+    mpu.get_acc().unwrap();
+    assert_eq!(SEEN.lock().unwrap().len(), 2);
+}
+
+#[test]
+fn shared_driver_hands_the_driver_back() {
+    let mut shared = Shared::new(driver());
+    shared.get_mut().set_sample_tap(None);
+    assert!(shared.with(|mpu| mpu.get_acc()).is_ok());
+    let mpu = shared.into_inner();
+    assert!(mpu.get_sample_tap().is_none());
+}
@@ -0,0 +1,413 @@
+//! Typed register access: read-modify-write semantics, typed value round trips, refused
+//! accesses and parity with the raw address calls, see the `register` module.
+
+mod common;
+
+use std::cell::Cell;
+use std::convert::Infallible;
+
+use mpu6050::chaos::{ChaosConfig, FaultKind, FlakyError, FlakyI2c};
+use mpu6050::device::*;
+use mpu6050::register::*;
+use mpu6050::script::{RegisterScript, ScriptStep};
+use mpu6050::trace::{TraceHandle, TracingI2c};
+use mpu6050::*;
+
+use common::{NoDelay, RegisterMock};
+
+type Mpu = Mpu6050<TracingI2c<RegisterMock>>;
+type FlakyMpu = Mpu6050<TracingI2c<FlakyI2c<RegisterMock>>>;
+
+fn traced() -> (Mpu, TraceHandle) {
+    let (i2c, trace) = TracingI2c::new(RegisterMock::new());
+    let mpu = Mpu6050Builder::new().i2c(i2c).build().unwrap();
+    (mpu, trace)
+}
+
+/// fails every `n`th transaction with a NAK
+fn flaky(n: u32) -> (FlakyMpu, TraceHandle) {
+    let config = ChaosConfig::NONE
+        .with_fail_every(n)
+        .with_fault(FaultKind::Nak);
+    let (flaky, _) = FlakyI2c::new(RegisterMock::new(), config);
+    let (i2c, trace) = TracingI2c::new(flaky);
+    let mpu = Mpu6050Builder::new().i2c(i2c).build().unwrap();
+    (mpu, trace)
+}
+
+fn is_injected<T>(res: Result<T, Mpu6050Error<FlakyError<Infallible>>>) -> bool {
+    matches!(
+        res,
+        Err(Mpu6050Error::I2c(FlakyError::Injected(FaultKind::Nak)))
+    )
+}
+
+#[test]
+fn modify_reads_once_and_writes_once() {
+    let (mut mpu, trace) = traced();
+    mpu.write_register(Register::MOT_THR, 0x12).unwrap();
+    trace.clear();
+
+    let calls = Cell::new(0);
+    let written = mpu
+        .modify_register(Register::MOT_THR, |byte| {
+            calls.set(calls.get() + 1);
+            assert_eq!(byte, 0x12);
+            byte | 0x80
+        })
+        .unwrap();
+    assert_eq!(written, 0x92);
+    assert_eq!(calls.get(), 1);
+    assert_eq!(trace.render(), "R 0x68 MOT_THR [12]\nW 0x68 MOT_THR [92]\n");
+    assert_eq!(mpu.read_register(Register::MOT_THR).unwrap(), 0x92);
+}
+
+#[test]
+fn failed_read_skips_closure_and_write() {
+    let (mut mpu, trace) = flaky(1);
+    let called = Cell::new(false);
+    let res = mpu.modify_register(Register::MOT_THR, |byte| {
+        called.set(true);
+        byte
+    });
+    assert!(is_injected(res));
+    assert!(!called.get());
+    assert_eq!(trace.transactions(), 1);
+    assert!(trace.render().starts_with("R 0x68 MOT_THR"));
+}
+
+#[test]
+fn failed_write_is_returned() {
+    // the read passes, the write is the second transaction
+    let (mut mpu, trace) = flaky(2);
+    let called = Cell::new(0);
+    let res = mpu.modify_register(Register::MOT_THR, |_| {
+        called.set(called.get() + 1);
+        0x55
+    });
+    assert!(is_injected(res));
+    assert_eq!(called.get(), 1);
+    assert_eq!(trace.transactions(), 2);
+    // nothing retried, the register keeps its value
+    assert_eq!(mpu.read_register(Register::MOT_THR).unwrap(), 0);
+}
+
+#[test]
+fn typed_values_round_trip() {
+    let (mut mpu, _) = traced();
+
+    for value in [
+        ConfigValue {
+            ext_sync: EXT_SYNC::DISABLED,
+            dlpf_cfg: 0,
+        },
+        ConfigValue {
+            ext_sync: EXT_SYNC::ACCEL_ZOUT_L,
+            dlpf_cfg: 6,
+        },
+    ] {
+        mpu.write_typed::<CONFIG>(value).unwrap();
+        assert_eq!(mpu.read_typed::<CONFIG>().unwrap(), value);
+        assert_eq!(mpu.read_byte(CONFIG::ADDR).unwrap(), CONFIG::to_byte(value));
+    }
+
+    for range in [GyroRange::D250, GyroRange::D2000] {
+        let value = GyroConfigValue {
+            self_test: [true, false, true],
+            range,
+        };
+        mpu.write_typed::<GYRO_CONFIG>(value).unwrap();
+        assert_eq!(mpu.read_typed::<GYRO_CONFIG>().unwrap(), value);
+        assert_eq!(
+            mpu.read_byte(GYRO_CONFIG::ADDR).unwrap(),
+            0b1010_0000 | (range as u8) << 3
+        );
+    }
+
+    for (range, hpf) in [
+        (AccelRange::G4, ACCEL_HPF::_HOLD),
+        (AccelRange::G16, ACCEL_HPF::_0P63),
+    ] {
+        let value = AccelConfigValue {
+            self_test: [false, true, false],
+            range,
+            hpf,
+        };
+        mpu.write_typed::<ACCEL_CONFIG>(value).unwrap();
+        assert_eq!(mpu.read_typed::<ACCEL_CONFIG>().unwrap(), value);
+    }
+
+    let value = PowerManagement1 {
+        device_reset: false,
+        sleep: true,
+        cycle: false,
+        temp_dis: true,
+        clksel: CLKSEL::GZAXIS,
+    };
+    mpu.write_typed::<PWR_MGMT_1>(value).unwrap();
+    assert_eq!(mpu.read_typed::<PWR_MGMT_1>().unwrap(), value);
+    assert_eq!(mpu.read_byte(PWR_MGMT_1::ADDR).unwrap(), 0b0100_1011);
+
+    let value = PowerManagement2 {
+        wake: LP_WAKE_CTRL::_5,
+        standby: [true, false, false, false, false, true],
+    };
+    mpu.write_typed::<PWR_MGMT_2>(value).unwrap();
+    assert_eq!(mpu.read_typed::<PWR_MGMT_2>().unwrap(), value);
+    assert_eq!(mpu.read_byte(PWR_MGMT_2::ADDR).unwrap(), 0b1010_0001);
+
+    // every byte decodes to a value that encodes back, reserved bits aside
+    for byte in 0..=255u8 {
+        assert_eq!(CONFIG::to_byte(CONFIG::from_byte(byte)), byte & 0x3f);
+        assert_eq!(
+            GYRO_CONFIG::to_byte(GYRO_CONFIG::from_byte(byte)),
+            byte & 0xf8
+        );
+        assert_eq!(
+            PWR_MGMT_1::to_byte(PWR_MGMT_1::from_byte(byte)),
+            byte & 0xef
+        );
+        assert_eq!(PWR_MGMT_2::to_byte(PWR_MGMT_2::from_byte(byte)), byte);
+    }
+}
+
+#[test]
+fn violations_never_reach_the_bus() {
+    let (mut mpu, trace) = traced();
+    let refused = |res: Result<(), Mpu6050Error<Infallible>>| match res {
+        Err(Mpu6050Error::InvalidRegisterAccess {
+            register,
+            violation,
+        }) => (register, violation),
+        other => panic!("expected a refused access, got {:?}", other),
+    };
+
+    assert_eq!(
+        refused(mpu.write_register(Register::WHO_AM_I, 0x68)),
+        (Register::WHO_AM_I, AccessViolation::ReadOnly)
+    );
+    assert_eq!(
+        refused(mpu.modify_register(Register::INT_STATUS, |b| b).map(drop)),
+        (Register::INT_STATUS, AccessViolation::ReadOnly)
+    );
+    assert_eq!(
+        refused(mpu.read_register(Register::TEMP_OUT_H).map(drop)),
+        (Register::TEMP_OUT_H, AccessViolation::SplitsPair)
+    );
+    assert_eq!(
+        refused(mpu.write_register_bit(Register::XG_OFFS_USRL, 0, true)),
+        (Register::XG_OFFS_USRL, AccessViolation::SplitsPair)
+    );
+    assert_eq!(
+        refused(mpu.read_register_word(Register::ACCEL_XOUT_L).map(drop)),
+        (Register::ACCEL_XOUT_L, AccessViolation::NotAPair)
+    );
+    assert_eq!(
+        refused(mpu.write_register_word(Register::PWR_MGMT_1, 0)),
+        (Register::PWR_MGMT_1, AccessViolation::NotAPair)
+    );
+    assert_eq!(
+        refused(mpu.write_register_word(Register::FIFO_COUNTH, 0)),
+        (Register::FIFO_COUNTH, AccessViolation::ReadOnly)
+    );
+    let mut buf = [0; 5];
+    assert_eq!(
+        refused(mpu.read_registers(Register::ACCEL_XOUT_H, &mut buf)),
+        (Register::ACCEL_XOUT_H, AccessViolation::SplitsPair)
+    );
+    assert_eq!(trace.transactions(), 0);
+
+    // what the checks allow
+    let mut buf = [0; 14];
+    mpu.read_registers(Register::ACCEL_XOUT_H, &mut buf)
+        .unwrap();
+    mpu.read_registers(Register::FIFO_R_W, &mut buf[..3])
+        .unwrap();
+    assert_eq!(trace.transactions(), 2);
+
+    let err: Mpu6050Error<Infallible> = Mpu6050Error::InvalidRegisterAccess {
+        register: Register::TEMP_OUT_H,
+        violation: AccessViolation::SplitsPair,
+    };
+    assert!(err
+        .to_string()
+        .starts_with("invalid access to TEMP_OUT_H (0x41): "));
+}
+
+#[test]
+fn register_map() {
+    assert!(Register::ALL.windows(2).all(|w| w[0].addr() < w[1].addr()));
+    for reg in Register::ALL {
+        assert_eq!(Register::from_addr(reg.addr()), Some(*reg));
+        assert_eq!(u8::from(*reg), reg.addr());
+        assert_eq!(reg.pair().is_some(), register_pair(reg.addr()).is_some());
+    }
+    assert_eq!(Register::from_addr(WHOAMI), Some(Register::WHO_AM_I));
+    assert_eq!(Register::from_addr(0xff), None);
+    assert_eq!(
+        Register::ext_sens_data(23),
+        Some(Register::EXT_SENS_DATA_23)
+    );
+    assert_eq!(Register::ext_sens_data(24), None);
+    assert_eq!(
+        Register::i2c_slv(3),
+        Some([
+            Register::I2C_SLV3_ADDR,
+            Register::I2C_SLV3_REG,
+            Register::I2C_SLV3_CTRL
+        ])
+    );
+    // slave 4 has its own layout
+    assert_eq!(Register::i2c_slv(4), None);
+    assert_eq!(Register::PWR_MGMT_1.to_string(), "PWR_MGMT_1 (0x6b)");
+}
+
+/// the same operations through the raw and the typed calls, the deprecated bit helpers
+/// included until their removal
+#[allow(deprecated)]
+fn raw_ops(mpu: &mut Mpu) {
+    mpu.write_byte(MOT_THR, 20).unwrap();
+    mpu.write_bits(
+        GYRO_CONFIG::ADDR,
+        GYRO_CONFIG::FS_SEL.bit,
+        GYRO_CONFIG::FS_SEL.length,
+        GyroRange::D1000 as u8,
+    )
+    .unwrap();
+    mpu.write_bit(PWR_MGMT_1::ADDR, PWR_MGMT_1::SLEEP, false)
+        .unwrap();
+    mpu.write_word(XG_OFFS_USRH, -300).unwrap();
+    assert_eq!(mpu.read_word(XG_OFFS_USRH).unwrap(), -300);
+    assert_eq!(mpu.read_byte(MOT_THR).unwrap(), 20);
+}
+
+fn typed_ops(mpu: &mut Mpu) {
+    mpu.write_register(Register::MOT_THR, 20).unwrap();
+    mpu.write_register_field(
+        Register::GYRO_CONFIG,
+        GYRO_CONFIG::FS_SEL,
+        GyroRange::D1000 as u8,
+    )
+    .unwrap();
+    mpu.write_register_bit(Register::PWR_MGMT_1, PWR_MGMT_1::SLEEP, false)
+        .unwrap();
+    mpu.write_register_word(Register::XG_OFFS_USRH, -300)
+        .unwrap();
+    assert_eq!(
+        mpu.read_register_word(Register::XG_OFFS_USRH).unwrap(),
+        -300
+    );
+    assert_eq!(mpu.read_register(Register::MOT_THR).unwrap(), 20);
+}
+
+#[test]
+fn typed_and_raw_calls_look_alike() {
+    let (mut raw, raw_trace) = traced();
+    let (mut typed, typed_trace) = traced();
+    raw.init(&mut NoDelay).unwrap();
+    typed.init(&mut NoDelay).unwrap();
+    raw_trace.clear();
+    typed_trace.clear();
+    let before = raw.io_stats();
+
+    raw_ops(&mut raw);
+    typed_ops(&mut typed);
+    assert_eq!(raw_trace.render(), typed_trace.render());
+    assert_eq!(raw.io_stats(), typed.io_stats());
+    assert_eq!(raw.io_stats().transactions - before.transactions, 8);
+    // both bypass the cached settings alike
+    assert_eq!(
+        raw.debug_state().to_string(),
+        typed.debug_state().to_string()
+    );
+
+    // scripts write through the typed calls
+    let script = RegisterScript::new()
+        .with_step(ScriptStep::WriteReg {
+            reg: MOT_THR,
+            value: 40,
+        })
+        .with_step(ScriptStep::WriteBits {
+            reg: MOT_DETECT_CONTROL::ADDR,
+            start_bit: 5,
+            length: 2,
+            value: 1,
+        });
+    raw_trace.clear();
+    raw.run_script(&script, &mut NoDelay).unwrap();
+    typed_trace.clear();
+    typed.write_register(Register::MOT_THR, 40).unwrap();
+    typed
+        .modify_register(Register::MOT_DETECT_CONTROL, |b| {
+            b & !0b0011_0000 | 0b0001_0000
+        })
+        .unwrap();
+    assert_eq!(raw_trace.render(), typed_trace.render());
+}
+
+#[test]
+fn fields_modify_only_their_bits() {
+    let (mut mpu, trace) = traced();
+    mpu.write_register(Register::ACCEL_CONFIG, 0b1111_1111)
+        .unwrap();
+    trace.clear();
+
+    mpu.write_field(ACCEL_CONFIG::ACCEL_HPF_FIELD, ACCEL_HPF::_5)
+        .unwrap();
+    assert_eq!(
+        trace.render(),
+        "R 0x68 ACCEL_CONFIG [ff]\nW 0x68 ACCEL_CONFIG [f9]\n"
+    );
+    assert_eq!(mpu.read_field(ACCEL_CONFIG::ACCEL_HPF_FIELD).unwrap(), 1);
+    assert_eq!(mpu.read_field(ACCEL_CONFIG::FS_SEL_FIELD).unwrap(), 0b11);
+    assert!(mpu.read_flag(ACCEL_CONFIG::XA_ST_FIELD).unwrap());
+
+    // the same writes as the raw escape hatch
+    let (mut raw, raw_trace) = traced();
+    raw.write_register(Register::ACCEL_CONFIG, 0b1111_1111)
+        .unwrap();
+    raw_trace.clear();
+    raw.write_register_field(Register::ACCEL_CONFIG, ACCEL_CONFIG::ACCEL_HPF, 1)
+        .unwrap();
+    mpu.write_register(Register::ACCEL_CONFIG, 0b1111_1111)
+        .unwrap();
+    trace.clear();
+    mpu.write_field(ACCEL_CONFIG::ACCEL_HPF_FIELD, ACCEL_HPF::_5)
+        .unwrap();
+    assert_eq!(raw_trace.render(), trace.render());
+}
+
+#[test]
+fn register_values_compose_fields() {
+    let value = RegisterValue::<PWR_MGMT_1>::ZERO
+        .with(PWR_MGMT_1::CLKSEL_FIELD, CLKSEL::GXAXIS)
+        .with(PWR_MGMT_1::TEMP_DIS_FIELD, true);
+    assert_eq!(value.byte(), 0b0000_1001);
+    assert_eq!(value.field(PWR_MGMT_1::CLKSEL_FIELD), 1);
+    assert!(value.flag(PWR_MGMT_1::TEMP_DIS_FIELD));
+    assert!(!value.flag(PWR_MGMT_1::SLEEP_FIELD));
+    // values wider than the field are truncated to it
+    assert_eq!(
+        RegisterValue::<CONFIG>::ZERO
+            .with(CONFIG::DLPF_CFG_FIELD, 0xff)
+            .byte(),
+        0b111
+    );
+    assert_eq!(RegisterValue::<PWR_MGMT_1>::reset().byte(), 0x40);
+    assert_eq!(PWR_MGMT_1::SLEEP_FIELD.register(), Register::PWR_MGMT_1);
+    assert_eq!(ACCEL_CONFIG::FS_SEL_FIELD.mask(), 0b0001_1000);
+}
+
+#[test]
+fn interrupt_sources_enable_their_own_bit() {
+    use mpu6050::interrupt::InterruptSource;
+
+    let mut mask = 0;
+    for source in InterruptSource::ALL {
+        let field = source.enable_field();
+        assert_eq!(field.register(), Register::INT_ENABLE);
+        assert_eq!(field.mask().count_ones(), 1);
+        mask |= field.mask();
+    }
+    assert_eq!(mask, 0b1111_1001);
+}
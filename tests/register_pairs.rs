@@ -0,0 +1,280 @@
+//! 16 bit register pairs are accessed in one transaction, see `REGISTER_PAIRS` in `device`.
+
+mod common;
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+use mpu6050::device::*;
+use mpu6050::register::Register;
+use mpu6050::trace::{Direction, TraceEntry, TraceHandle, TracingI2c};
+use mpu6050::*;
+
+use common::RegisterMock;
+
+type Mpu = Mpu6050<TracingI2c<RegisterMock>>;
+
+fn driver(who_am_i: u8) -> (Mpu, TraceHandle) {
+    let mut mock = RegisterMock::new();
+    mock.regs[WHOAMI as usize] = who_am_i;
+    let (i2c, trace) = TracingI2c::new(mock);
+    let mut mpu = Mpu6050Builder::new().i2c(i2c).build().unwrap();
+    mpu.probe_capabilities().unwrap();
+    trace.clear();
+    (mpu, trace)
+}
+
+/// writes touching a register pair with anything but exactly its two bytes
+fn split_pair_writes(trace: &TraceHandle) -> Vec<String> {
+    trace
+        .entries()
+        .iter()
+        .filter_map(|entry| match entry {
+            TraceEntry::Transaction {
+                direction: Direction::Write,
+                reg: Some(reg),
+                data,
+                ..
+            } => {
+                let touched = (0..data.len()).map(|i| reg.wrapping_add(i as u8));
+                let split = touched.filter_map(register_pair).any(|pair| {
+                    let start = pair.high as usize;
+                    let end = start + 2;
+                    let (first, last) = (*reg as usize, *reg as usize + data.len());
+                    first > start || last < end
+                });
+                split.then(|| entry.to_string())
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+const VALUES: [i16; 9] = [0, 1, -1, 0x00ff, -256, 0x1234, -0x1234, i16::MAX, i16::MIN];
+
+#[test]
+fn words_are_one_transaction_high_byte_first() {
+    let (mut mpu, trace) = driver(0x68);
+    for value in VALUES {
+        mpu.write_word(XG_OFFS_USRH, value).unwrap();
+        assert_eq!(mpu.read_word(XG_OFFS_USRH).unwrap(), value);
+        let [high, low] = value.to_be_bytes();
+        assert_eq!(
+            trace.render(),
+            format!(
+                "W 0x68 XG_OFFS_USRH [{:02x} {:02x}]\nR 0x68 XG_OFFS_USRH [{:02x} {:02x}]\n",
+                high, low, high, low
+            )
+        );
+        trace.clear();
+    }
+    // the low register follows the high one
+    mpu.write_word(ZG_OFFS_USRH, -0x1234).unwrap();
+    assert_eq!(mpu.read_byte(ZG_OFFS_USRH).unwrap(), 0xed);
+    assert_eq!(mpu.read_byte(ZG_OFFS_USRH + 1).unwrap(), 0xcc);
+}
+
+#[test]
+fn raw_counts_are_big_endian_pairs() {
+    let mut mock = RegisterMock::new();
+    let acc = [0x80, 0x00, 0xff, 0xff, 0x12, 0x34];
+    let temp = [0xaa, 0x55];
+    let gyro = [0x7f, 0xff, 0x00, 0x01, 0xed, 0xcc];
+    let frame: Vec<u8> = [&acc[..], &temp, &gyro].concat();
+    mock.set_frame(&frame.try_into().unwrap());
+    let (i2c, trace) = TracingI2c::new(mock);
+    let mut mpu = Mpu6050Builder::new().i2c(i2c).build().unwrap();
+
+    assert_eq!(mpu.get_acc_raw().unwrap(), [i16::MIN, -1, 0x1234]);
+    assert_eq!(mpu.get_gyro_raw().unwrap(), [i16::MAX, 1, -0x1234]);
+    // one read of the three pairs each, the temperature in between untouched
+    assert_eq!(
+        trace.render(),
+        "R 0x68 ACCEL_XOUT_H [80 00 ff ff 12 34]\nR 0x68 GYRO_XOUT_H [7f ff 00 01 ed cc]\n"
+    );
+}
+
+#[test]
+fn gyro_offsets() {
+    let (mut mpu, trace) = driver(0x68);
+    mpu.set_gyro_hw_offsets([i16::MIN, -1, i16::MAX]).unwrap();
+    assert_eq!(
+        trace.render(),
+        "W 0x68 XG_OFFS_USRH [80 00]\n\
+         W 0x68 YG_OFFS_USRH [ff ff]\n\
+         W 0x68 ZG_OFFS_USRH [7f ff]\n"
+    );
+    assert_eq!(mpu.get_gyro_hw_offsets().unwrap(), [i16::MIN, -1, i16::MAX]);
+}
+
+#[test]
+fn accel_offsets_keep_the_reserved_bit() {
+    for (who_am_i, registers) in [
+        (0x68, [XA_OFFS_H, YA_OFFS_H, ZA_OFFS_H]),
+        (0x70, [XA_OFFSET_H, YA_OFFSET_H, ZA_OFFSET_H]),
+    ] {
+        let (mut mpu, trace) = driver(who_am_i);
+        // factory trim with the reserved bit set on X only
+        mpu.write_word(registers[0], 0x0401).unwrap();
+        trace.clear();
+        mpu.set_accel_hw_offsets([-2, 0x0100, -4096]).unwrap();
+        assert_eq!(mpu.get_accel_hw_offsets().unwrap(), [-2 | 1, 0x0100, -4096]);
+        assert!(split_pair_writes(&trace).is_empty());
+        let writes = trace
+            .entries()
+            .into_iter()
+            .filter(|e| {
+                matches!(
+                    e,
+                    TraceEntry::Transaction {
+                        direction: Direction::Write,
+                        ..
+                    }
+                )
+            })
+            .count();
+        assert_eq!(writes, 3);
+    }
+
+    let (mut mpu, trace) = driver(0x98);
+    assert!(matches!(
+        mpu.set_accel_hw_offsets([0; 3]),
+        Err(Mpu6050Error::Unsupported(Capability::AccelOffsets))
+    ));
+    assert_eq!(trace.transactions(), 0);
+}
+
+#[test]
+fn calibration_to_hardware_never_splits_a_pair() {
+    let (mut mpu, trace) = driver(0x68);
+    mpu.set_gyro_hw_offsets([100, -100, 0]).unwrap();
+    trace.clear();
+
+    // a bias of -0.01 rad/s = -18.8 counts and 0.02 rad/s = 37.6 counts, corrected by 18.8 and
+    // -37.6 counts, the residue stays in software
+    mpu.gyro_offset = Vec3A::new(-0.01, 0.02, 0.);
+    let offsets = mpu.store_gyro_offset_in_hardware().unwrap();
+    assert_eq!(offsets, [119, -138, 0]);
+    assert_eq!(mpu.get_gyro_hw_offsets().unwrap(), offsets);
+    let lsb = GYRO_OFFSET_DPS_PER_LSB.to_radians();
+    assert!((mpu.gyro_offset.x - (19. * lsb - 0.01)).abs() < 1e-6);
+    assert!((mpu.gyro_offset.y - (0.02 - 38. * lsb)).abs() < 1e-6);
+    assert!(mpu.gyro_offset.abs().max_element() < lsb / 2.);
+
+    assert!(split_pair_writes(&trace).is_empty(), "{}", trace.render());
+    let writes: Vec<_> = trace
+        .render()
+        .lines()
+        .filter(|l| l.starts_with('W'))
+        .map(String::from)
+        .collect();
+    assert_eq!(
+        writes,
+        [
+            "W 0x68 XG_OFFS_USRH [00 77]",
+            "W 0x68 YG_OFFS_USRH [ff 76]",
+            "W 0x68 ZG_OFFS_USRH [00 00]",
+        ]
+    );
+
+    // clamped at the register range, the rest stays in software
+    mpu.set_gyro_hw_offsets([i16::MAX; 3]).unwrap();
+    mpu.gyro_offset = Vec3A::splat(-0.1);
+    assert_eq!(mpu.store_gyro_offset_in_hardware().unwrap(), [i16::MAX; 3]);
+    assert_eq!(mpu.gyro_offset, Vec3A::splat(-0.1));
+}
+
+#[test]
+fn pair_table() {
+    for pair in REGISTER_PAIRS {
+        assert_eq!(register_pair(pair.high), Some(&pair));
+        assert_eq!(register_pair(pair.low()), Some(&pair));
+    }
+    assert_eq!(register_pair(FIFO_COUNT_H).map(|p| p.writable), Some(false));
+    assert_eq!(register_pair(WHOAMI), None);
+}
+
+/// `pub const NAME: u8 = 0x..;` of registers.rs whose value is a pair register
+fn pair_constants(root: &Path) -> HashMap<String, u8> {
+    let registers = fs::read_to_string(root.join("src/registers.rs")).unwrap();
+    registers
+        .lines()
+        .filter_map(|line| {
+            let rest = line.trim().strip_prefix("pub const ")?;
+            let (name, value) = rest.split_once(": u8 = 0x")?;
+            let value = u8::from_str_radix(value.trim_end_matches(';'), 16).ok()?;
+            register_pair(value).map(|_| (name.to_string(), value))
+        })
+        .collect()
+}
+
+/// Lint: single byte accessors are never called on a pair register, by constant, constant
+/// plus one, literal or `Register` handle. The typed accessors also refuse pairs at runtime,
+/// see `register::AccessViolation::SplitsPair`
+#[test]
+fn no_single_byte_access_to_pairs() {
+    let root = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let constants = pair_constants(root);
+    assert!(constants.contains_key("XG_OFFS_USRH") && constants.contains_key("TEMP_OUT_H"));
+    let accessors = [
+        "write_byte(",
+        "write_bit(",
+        "write_bits(",
+        "read_byte(",
+        "read_bit(",
+        "read_bits(",
+        "read_register(",
+        "write_register(",
+        "modify_register(",
+        "read_register_bit(",
+        "write_register_bit(",
+        "read_register_field(",
+        "write_register_field(",
+    ];
+    let handles: HashMap<&str, u8> = Register::ALL
+        .iter()
+        .filter(|reg| register_pair(reg.addr()).is_some())
+        .map(|reg| (reg.name(), reg.addr()))
+        .collect();
+    assert!(handles.contains_key("FIFO_COUNTH") && handles.contains_key("ACCEL_XOUT_L"));
+    let mut findings = Vec::new();
+    for entry in fs::read_dir(root.join("src")).unwrap() {
+        let path = entry.unwrap().path();
+        if path.extension().is_none_or(|e| e != "rs") {
+            continue;
+        }
+        let source = fs::read_to_string(&path).unwrap();
+        for (number, line) in source.lines().enumerate() {
+            for accessor in accessors {
+                for (at, _) in line.match_indices(accessor) {
+                    let args = &line[at + accessor.len()..];
+                    let reg = args.split([',', ')']).next().unwrap_or("").trim();
+                    let base = reg.trim_end_matches("+ 1").trim();
+                    let literal = base
+                        .strip_prefix("0x")
+                        .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+                    let handle = base
+                        .strip_prefix("Register::")
+                        .is_some_and(|name| handles.contains_key(name));
+                    if constants.contains_key(base)
+                        || handle
+                        || literal.and_then(register_pair).is_some()
+                    {
+                        findings.push(format!(
+                            "{}:{}: {}",
+                            path.display(),
+                            number + 1,
+                            line.trim()
+                        ));
+                    }
+                }
+            }
+        }
+    }
+    assert!(
+        findings.is_empty(),
+        "split pair accesses:\n{}",
+        findings.join("\n")
+    );
+}
@@ -0,0 +1,446 @@
+//! The remote protocol over an in-memory loopback: every command, malformed frames and a
+//! transport failing mid-operation.
+
+mod common;
+
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::rc::Rc;
+
+use mpu6050::device::{
+    AccelRange, GyroRange, ACCEL_CONFIG, CONFIG, DEFAULT_SLAVE_ADDR, SMPLRT_DIV,
+};
+use mpu6050::log_header::LogHeader;
+use mpu6050::remote::*;
+use mpu6050::setup::AutoSetupPhase;
+use mpu6050::validity::CalibrationStatus;
+use mpu6050::{synthetic, Mpu6050, Mpu6050Builder, Vec3A};
+
+use common::{NoDelay, SharedBus};
+
+type Pipe = Rc<RefCell<VecDeque<u8>>>;
+
+#[derive(Debug, PartialEq)]
+struct LinkDown;
+
+/// One end of the loopback. The client's end runs the server whenever it has nothing to read
+struct End {
+    rx: Pipe,
+    tx: Pipe,
+    /// bytes readable before reads fail, None for no limit
+    reads_left: Rc<RefCell<Option<usize>>>,
+    /// frames writable before writes fail, None for no limit
+    writes_left: Rc<RefCell<Option<usize>>>,
+    pump: Option<Box<dyn FnMut()>>,
+}
+
+impl End {
+    fn new(rx: &Pipe, tx: &Pipe) -> Self {
+        Self {
+            rx: rx.clone(),
+            tx: tx.clone(),
+            reads_left: Rc::default(),
+            writes_left: Rc::default(),
+            pump: None,
+        }
+    }
+}
+
+impl Transport for End {
+    type Error = LinkDown;
+
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, LinkDown> {
+        if self.rx.borrow().is_empty() {
+            if let Some(pump) = &mut self.pump {
+                pump();
+            }
+        }
+        let mut rx = self.rx.borrow_mut();
+        let mut n = buf.len().min(rx.len());
+        if let Some(left) = &mut *self.reads_left.borrow_mut() {
+            if *left == 0 && n > 0 {
+                return Err(LinkDown);
+            }
+            n = n.min(*left);
+            *left -= n;
+        }
+        for byte in &mut buf[..n] {
+            *byte = rx.pop_front().unwrap();
+        }
+        Ok(n)
+    }
+
+    fn write(&mut self, bytes: &[u8]) -> Result<(), LinkDown> {
+        if let Some(left) = &mut *self.writes_left.borrow_mut() {
+            if *left == 0 {
+                return Err(LinkDown);
+            }
+            *left -= 1;
+        }
+        self.tx.borrow_mut().extend(bytes);
+        Ok(())
+    }
+}
+
+struct Server {
+    mpu: Mpu6050<SharedBus, NoDelay>,
+    link: RemoteLink<End>,
+    budget: ServeBudget,
+    results: Vec<Result<ServeStats, LinkDown>>,
+}
+
+struct Loopback {
+    bus: SharedBus,
+    server: Rc<RefCell<Server>>,
+    to_server: Pipe,
+    to_client: Pipe,
+    server_writes_left: Rc<RefCell<Option<usize>>>,
+    client_reads_left: Rc<RefCell<Option<usize>>>,
+}
+
+impl Loopback {
+    fn new() -> (RemoteClient<End>, Self) {
+        let bus = SharedBus::new(&[DEFAULT_SLAVE_ADDR]);
+        let mpu = Mpu6050Builder::new()
+            .i2c(bus.clone())
+            .delay(NoDelay)
+            .build()
+            .unwrap();
+        let (to_server, to_client) = (Pipe::default(), Pipe::default());
+        let server_end = End::new(&to_server, &to_client);
+        let server_writes_left = server_end.writes_left.clone();
+        let server = Rc::new(RefCell::new(Server {
+            mpu,
+            link: RemoteLink::new(server_end),
+            budget: ServeBudget {
+                commands: 1,
+                progress: mpu6050::cooperative::DrainBudget::Transactions(40),
+            },
+            results: Vec::new(),
+        }));
+        let mut client_end = End::new(&to_client, &to_server);
+        let client_reads_left = client_end.reads_left.clone();
+        let pumped = server.clone();
+        client_end.pump = Some(Box::new(move || {
+            let server = &mut *pumped.borrow_mut();
+            let result = server.mpu.serve(&mut server.link, server.budget);
+            server.results.push(result);
+        }));
+        let client = RemoteClient::new(client_end).with_max_idle_reads(5);
+        let loopback = Self {
+            bus,
+            server,
+            to_server,
+            to_client,
+            server_writes_left,
+            client_reads_left,
+        };
+        (client, loopback)
+    }
+
+    /// serves `wire` as received bytes, returns the responses
+    fn raw(&self, wire: &[u8]) -> (ServeStats, Vec<Frame>) {
+        self.to_server.borrow_mut().extend(wire);
+        let server = &mut *self.server.borrow_mut();
+        let budget = ServeBudget {
+            commands: 16,
+            ..server.budget
+        };
+        let stats = server.mpu.serve(&mut server.link, budget).unwrap();
+        (stats, received(&self.to_client))
+    }
+}
+
+fn received(pipe: &Pipe) -> Vec<Frame> {
+    let mut reader = RemoteLink::new(End::new(pipe, &Pipe::default()));
+    let mut frames = Vec::new();
+    while let Some(frame) = reader.receive().unwrap() {
+        frames.push(frame.unwrap());
+    }
+    frames
+}
+
+fn wire(frame: &Frame) -> Vec<u8> {
+    let mut buf = [0; MAX_WIRE];
+    let len = frame.encode(&mut buf);
+    buf[..len].to_vec()
+}
+
+fn rejected(frame: &Frame) -> (u8, RejectReason) {
+    match Response::from_frame(frame).unwrap() {
+        Response::Rejected { reason, version } => {
+            assert_eq!(version, REMOTE_VERSION);
+            (frame.seq, reason)
+        }
+        other => panic!("{:?}", other),
+    }
+}
+
+#[test]
+fn every_command_round_trips() {
+    let (mut client, lo) = Loopback::new();
+    let info = client.hello().unwrap();
+    assert_eq!(
+        info,
+        ServerInfo {
+            version: REMOTE_VERSION,
+            max_body: MAX_BODY as u8,
+            profiles: 4,
+        }
+    );
+
+    client.init(0).unwrap();
+    // HANDHELD_UI: ±2g, ±250°/s, DLPF 5, divider 9
+    client.init(2).unwrap();
+    lo.bus.device(DEFAULT_SLAVE_ADDR, |m| {
+        assert_eq!(m.regs[CONFIG::ADDR as usize] & 0x07, 5);
+        assert_eq!(m.regs[SMPLRT_DIV as usize], 9);
+    });
+
+    client.set_accel_range(AccelRange::G8).unwrap();
+    client.set_gyro_range(GyroRange::D1000).unwrap();
+    client.set_dlpf(3).unwrap();
+    client.set_sample_rate_divider(4).unwrap();
+    lo.bus.device(DEFAULT_SLAVE_ADDR, |m| {
+        assert_eq!(m.regs[ACCEL_CONFIG::ADDR as usize] >> 3 & 0x03, 2);
+        assert_eq!(m.regs[CONFIG::ADDR as usize] & 0x07, 3);
+        assert_eq!(m.regs[SMPLRT_DIV as usize], 4);
+    });
+    // the driver's validation answers as a failure with the error code
+    assert_eq!(
+        client.set_dlpf(7),
+        Err(ClientError::Failed(RemoteFailure::Driver(9)))
+    );
+
+    let acc = Vec3A::new(0.5, -0.25, 1.);
+    let gyro = Vec3A::new(10., 0., -20.);
+    let frame = synthetic::frame_bytes(acc, gyro, AccelRange::G8, GyroRange::D1000);
+    lo.bus.device(DEFAULT_SLAVE_ADDR, |m| m.set_frame(&frame));
+    let sample = client.get_all().unwrap();
+    assert!((sample.acc() - acc).length() < 0.01, "{:?}", sample.acc());
+    assert!((sample.gyro() - gyro * core::f32::consts::PI / 180.).length() < 0.01);
+    assert!(!sample.out_of_band());
+
+    // at rest again for the calibration, at the current ranges
+    let rest = synthetic::frame_bytes(Vec3A::Z, Vec3A::ZERO, AccelRange::G8, GyroRange::D1000);
+    lo.bus.device(DEFAULT_SLAVE_ADDR, |m| m.set_frame(&rest));
+    let mut progress = Vec::new();
+    let offsets = client
+        .calibrate(
+            RemoteCalibration {
+                gyro_samples: 60,
+                acc_samples: 60,
+                device_is_level: true,
+            },
+            |phase, part| progress.push((phase, part)),
+        )
+        .unwrap();
+    assert!(offsets.gyro.length() < 1e-3 && offsets.acc.length() < 1e-2);
+    assert!(progress
+        .iter()
+        .enumerate()
+        .all(|(i, (_, part))| *part as usize == i));
+    let mut phases: Vec<_> = progress.iter().map(|(phase, _)| *phase).collect();
+    phases.dedup();
+    assert_eq!(phases, AutoSetupPhase::ALL);
+    // yields within the sampling phases streamed progress of their own
+    assert!(progress.len() > AutoSetupPhase::COUNT + 2, "{:?}", progress);
+    assert_eq!(
+        lo.server.borrow_mut().mpu.get_accel_range().unwrap(),
+        AccelRange::G8
+    );
+
+    // the mock does not respond to self-test
+    assert_eq!(client.self_test().unwrap(), Vec3A::ZERO);
+
+    let blob = client.export_calibration().unwrap();
+    assert!(blob.len() > BLOB_CHUNK, "{}", blob.len());
+    let (header, len) = LogHeader::decode(&blob).unwrap();
+    assert_eq!(len, blob.len());
+    assert_eq!(header.who_am_i, 0x68);
+    assert_eq!(header.accel_range, AccelRange::G8);
+    assert_eq!(header.gyro_range, GyroRange::D1000);
+
+    let health = client.health().unwrap();
+    assert_eq!(health.who_am_i, Some(0x68));
+    assert!(health.connected && !health.asleep && !health.supervisor_tripped);
+    assert_eq!(health.calibration, CalibrationStatus::Valid);
+    assert_eq!(
+        health.transactions,
+        lo.server.borrow().mpu.io_stats().transactions
+    );
+
+    let results = &lo.server.borrow().results;
+    assert!(results
+        .iter()
+        .all(|r| r.as_ref().is_ok_and(|s| s.rejected == 0)));
+}
+
+#[test]
+fn frames_round_trip_unchanged() {
+    let commands = [
+        Command::Hello,
+        Command::Init { profile: 4 },
+        Command::SetAccelRange(AccelRange::G16),
+        Command::SetGyroRange(GyroRange::D500),
+        Command::Calibrate(RemoteCalibration::default()),
+        Command::HealthCheck,
+    ];
+    for (seq, command) in commands.iter().enumerate() {
+        let frame = command.to_frame(seq as u8);
+        let wire = wire(&frame);
+        assert_eq!(*wire.last().unwrap(), 0);
+        assert!(!wire[..wire.len() - 1].contains(&0));
+        let decoded = Frame::decode(&wire[..wire.len() - 1]).unwrap();
+        assert_eq!(decoded, frame);
+        assert_eq!(Command::from_frame(&decoded), Ok(*command));
+    }
+    let part = BlobChunk::new(96, &[0; BLOB_CHUNK]).unwrap();
+    for response in [
+        Response::BlobPart(part),
+        Response::Progress {
+            phase: AutoSetupPhase::AccelCalibration,
+            part: 300,
+        },
+        Response::Failed(RemoteFailure::Calibration {
+            phase: AutoSetupPhase::GyroCalibration,
+            driver: None,
+        }),
+    ] {
+        let frame = response.to_frame(7);
+        let wire = wire(&frame);
+        let decoded = Frame::decode(&wire[..wire.len() - 1]).unwrap();
+        assert_eq!(Response::from_frame(&decoded), Ok(response));
+    }
+    assert!(Frame::new(0, 0x01, &[0; MAX_BODY + 1]).is_none());
+
+    // the explicit delay flavour
+    let mut mpu = Mpu6050Builder::new()
+        .i2c(SharedBus::new(&[DEFAULT_SLAVE_ADDR]))
+        .build()
+        .unwrap();
+    let (rx, tx) = (Pipe::default(), Pipe::default());
+    rx.borrow_mut()
+        .extend(wire(&Command::Init { profile: 1 }.to_frame(9)));
+    let mut link = RemoteLink::new(End::new(&rx, &tx));
+    let stats = mpu.serve(&mut NoDelay, &mut link, ServeBudget::default());
+    assert_eq!(
+        stats,
+        Ok(ServeStats {
+            commands: 1,
+            rejected: 0
+        })
+    );
+    let frames = received(&tx);
+    assert_eq!(Response::from_frame(&frames[0]), Ok(Response::Ack));
+    assert_eq!(mpu.get_accel_range().unwrap(), AccelRange::G16);
+}
+
+#[test]
+fn malformed_frames_are_rejected() {
+    let (mut client, lo) = Loopback::new();
+
+    let hello = wire(&Command::Hello.to_frame(3));
+    let mut bad_crc = hello.clone();
+    bad_crc[2] ^= 0x40;
+    let mut version = Command::Hello.to_frame(4);
+    version.version = REMOTE_VERSION + 1;
+    let unknown = Frame::new(5, 0x7f, &[]).unwrap();
+    let bad_range = Frame::new(6, Command::SetAccelRange(AccelRange::G2).opcode(), &[4]).unwrap();
+    let bad_profile = Command::Init { profile: 5 }.to_frame(7);
+    let long_body = Frame::new(8, Command::GetAll.opcode(), &[1]).unwrap();
+
+    let mut stream = Vec::new();
+    stream.extend(&bad_crc);
+    // a code byte pointing past the end
+    stream.extend([9, 1, 1, 0]);
+    // too short for a header and CRC
+    stream.extend([3, 1, 1, 0]);
+    stream.extend(wire(&version));
+    stream.extend(wire(&unknown));
+    stream.extend(wire(&bad_range));
+    stream.extend(wire(&bad_profile));
+    stream.extend(wire(&long_body));
+    // no delimiter within MAX_WIRE bytes
+    stream.extend([0x55; 2 * MAX_WIRE]);
+    stream.push(0);
+    // idle delimiters, then a good frame
+    stream.extend([0, 0]);
+    stream.extend(&hello);
+    let (stats, frames) = lo.raw(&stream);
+
+    assert_eq!(
+        stats,
+        ServeStats {
+            commands: 10,
+            rejected: 9
+        }
+    );
+    let reasons: Vec<_> = frames[..9].iter().map(rejected).collect();
+    assert_eq!(
+        reasons,
+        [
+            (0, RejectReason::CrcMismatch),
+            (0, RejectReason::Malformed),
+            (0, RejectReason::Malformed),
+            (4, RejectReason::Version),
+            (5, RejectReason::UnknownCommand),
+            (6, RejectReason::BadArgument),
+            (7, RejectReason::BadArgument),
+            (8, RejectReason::BadArgument),
+            (0, RejectReason::TooLong),
+        ]
+    );
+    // resynchronised at the delimiters
+    assert_eq!(frames[9].seq, 3);
+    assert!(matches!(
+        Response::from_frame(&frames[9]),
+        Ok(Response::Hello(_))
+    ));
+    assert_eq!(frames.len(), 10);
+
+    // through the client
+    assert_eq!(
+        client.request(&Command::Init { profile: 9 }, |_| {}),
+        Err(ClientError::Rejected(RejectReason::BadArgument))
+    );
+    assert!(client.hello().is_ok());
+}
+
+#[test]
+fn transport_error_mid_calibration() {
+    let (mut client, lo) = Loopback::new();
+    client.init(0).unwrap();
+    let calibration = RemoteCalibration {
+        gyro_samples: 60,
+        acc_samples: 60,
+        device_is_level: true,
+    };
+
+    // the server's writes fail after three progress frames: the calibration is aborted,
+    // serve returns the error and the client hears nothing more
+    *lo.server_writes_left.borrow_mut() = Some(3);
+    let mut parts = 0;
+    assert_eq!(
+        client.calibrate(calibration, |_, _| parts += 1),
+        Err(ClientError::Timeout)
+    );
+    assert_eq!(parts, 3);
+    assert!(lo.server.borrow().results.contains(&Err(LinkDown)));
+    let transactions = lo.server.borrow().mpu.io_stats().transactions;
+    assert!(transactions < 100, "{}", transactions);
+
+    // recovered: the next request goes through
+    *lo.server_writes_left.borrow_mut() = None;
+    assert!(client.health().unwrap().connected);
+
+    // the client's reads fail mid-stream
+    *lo.client_reads_left.borrow_mut() = Some(3 * MAX_WIRE / 2);
+    let result = client.calibrate(calibration, |_, _| {});
+    assert_eq!(result, Err(ClientError::Transport(LinkDown)));
+    // the rest of that calibration is still pending, skipped by its sequence number
+    *lo.client_reads_left.borrow_mut() = None;
+    assert!(!lo.to_client.borrow().is_empty());
+    assert_eq!(client.init(0), Ok(()));
+    assert!(lo.to_client.borrow().is_empty());
+}
@@ -0,0 +1,244 @@
+//! `UniformResampler` on scripted timestamps: a skewed clock, outputs exactly on the grid with
+//! the analytic interpolation, the rate and skew estimates and the bridge limit, see the
+//! `resample` module.
+
+mod common;
+
+use mpu6050::device::DEFAULT_SLAVE_ADDR;
+use mpu6050::interpolation::{TimedSample, TimestampError};
+use mpu6050::provenance::{Provenance, SampleProvenance};
+use mpu6050::resample::{resample, ResampledItem, UniformResampler};
+use mpu6050::{Mpu6050Builder, MpuSample, Vec3A};
+
+use common::SharedBus;
+
+/// accel x the time in s, gyro y its negative
+fn ramp(t_us: u64) -> TimedSample {
+    let t = t_us as f32 * 1e-6;
+    TimedSample {
+        t_us,
+        sample: MpuSample::new(Vec3A::new(t, 0., 1.), Vec3A::new(0., -t, 0.), 25.),
+        orientation: None,
+    }
+}
+
+/// `n` inputs `interval_us` apart from `start_us`
+fn inputs(start_us: u64, interval_us: u64, n: u64) -> impl Iterator<Item = TimedSample> {
+    (0..n).map(move |i| ramp(start_us + i * interval_us))
+}
+
+fn samples(items: &[ResampledItem]) -> Vec<u64> {
+    items
+        .iter()
+        .filter_map(|item| match item {
+            ResampledItem::Sample { t_us, .. } => Some(*t_us),
+            ResampledItem::Gap { .. } => None,
+        })
+        .collect()
+}
+
+#[test]
+fn slow_clock_is_resampled_onto_the_exact_grid() {
+    // nominal 100 Hz arriving at 99.2 Hz: 10_081 µs between inputs
+    let start_us = 1_234_567;
+    let mut resampler = UniformResampler::new(100, 100., 50_000);
+    let mut out = Vec::new();
+    for input in inputs(start_us, 10_081, 101) {
+        out.extend(resampler.push(input).unwrap());
+    }
+    let last_us = start_us + 100 * 10_081;
+    let expected: Vec<_> = (0..)
+        .map(|k| start_us + k * 10_000)
+        .take_while(|t| *t <= last_us)
+        .collect();
+    assert_eq!(samples(&out), expected);
+    assert_eq!(expected.len(), 101);
+
+    for item in &out {
+        let ResampledItem::Sample { t_us, sample } = item else {
+            panic!("gap without a gap")
+        };
+        let truth = ramp(*t_us).sample;
+        assert!((sample.acc.x - truth.acc().x).abs() < 1e-5, "{t_us}");
+        assert!((sample.gyro.y - truth.gyro().y).abs() < 1e-5);
+        assert_eq!(sample.acc.z, 1.);
+        assert!(!sample.extrapolated);
+    }
+}
+
+#[test]
+fn rate_and_skew_are_measured_over_bridged_intervals() {
+    let mut resampler = UniformResampler::new(100, 100., 50_000);
+    assert_eq!(resampler.input_rate_hz(), None);
+    assert_eq!(resampler.grid_time(3), None);
+    for input in inputs(0, 10_081, 51) {
+        resampler.push(input).unwrap();
+    }
+    let rate = resampler.input_rate_hz().unwrap();
+    assert!((rate - 1e6 / 10_081.).abs() < 1e-3, "{rate}");
+    // 50 intervals, 81 µs long each: the sensor runs slow
+    assert!((resampler.clock_skew_us() - 50. * 81.).abs() < 1e-3);
+    assert_eq!(resampler.grid_time(3), Some(30_000));
+
+    let mut fast = UniformResampler::new(100, 100., 50_000);
+    for input in inputs(0, 9_930, 11) {
+        fast.push(input).unwrap();
+    }
+    assert!((fast.clock_skew_us() + 700.).abs() < 1e-3);
+}
+
+#[test]
+fn grid_points_on_inputs_are_the_inputs() {
+    // 333 Hz grid over 1 ms inputs, rounded down to whole µs
+    let mut resampler = UniformResampler::new(333, 1000., 5_000);
+    let mut out = Vec::new();
+    let odd = |t_us| TimedSample {
+        sample: ramp(t_us)
+            .sample
+            .with_temp(20. + t_us as f32 * 0.001_3)
+            .with_provenance(SampleProvenance::MEASURED),
+        ..ramp(t_us)
+    };
+    for t_us in (0..=9).map(|i| i * 1000) {
+        out.extend(resampler.push(odd(t_us)).unwrap());
+    }
+    // floor(k * 1e6 / 333): 0, 3003, 6006, 9009 is past the last input
+    assert_eq!(samples(&out), [0, 3003, 6006]);
+
+    let mut exact = UniformResampler::new(100, 100., 50_000);
+    let mut out = Vec::new();
+    for input in inputs(0, 10_000, 5).map(|t| TimedSample {
+        sample: odd(t.t_us).sample,
+        ..t
+    }) {
+        out.extend(exact.push(input).unwrap());
+    }
+    for item in out {
+        let ResampledItem::Sample { t_us, sample } = item else {
+            panic!()
+        };
+        let input = odd(t_us).sample;
+        assert_eq!(sample.temp.to_bits(), input.temp().to_bits());
+        assert_eq!(sample.acc, input.acc());
+        assert_eq!(sample.provenance, SampleProvenance::MEASURED);
+    }
+}
+
+#[test]
+fn off_grid_points_are_tagged_interpolated() {
+    let mut resampler = UniformResampler::new(100, 100., 50_000);
+    let mut out = Vec::new();
+    for input in inputs(0, 10_081, 3) {
+        out.extend(resampler.push(input).unwrap());
+    }
+    let tags: Vec<_> = out
+        .iter()
+        .map(|item| match item {
+            ResampledItem::Sample { sample, .. } => sample.provenance,
+            ResampledItem::Gap { .. } => panic!(),
+        })
+        .collect();
+    assert_eq!(
+        tags,
+        [
+            SampleProvenance::MEASURED,
+            SampleProvenance::uniform(Provenance::Interpolated),
+            SampleProvenance::uniform(Provenance::Interpolated),
+        ]
+    );
+}
+
+#[test]
+fn gaps_beyond_the_bridge_limit_are_marked() {
+    let mut resampler = UniformResampler::new(100, 100., 25_000);
+    let mut out = Vec::new();
+    let script = [
+        0, 10_000, 20_000, 30_000, 40_000, 100_000, 110_000, 135_000, 161_500,
+    ];
+    for t_us in script {
+        out.extend(resampler.push(ramp(t_us)).unwrap());
+    }
+    // 40 to 100 ms: 50 to 90 ms left out, the point on the new input kept. 135 to 161.5 ms
+    // too, 140 to 160 ms skipped. 110 to 135 ms is exactly the limit and bridged
+    let gaps: Vec<_> = out
+        .iter()
+        .filter(|item| matches!(item, ResampledItem::Gap { .. }))
+        .copied()
+        .collect();
+    assert_eq!(
+        gaps,
+        [
+            ResampledItem::Gap {
+                from_us: 40_000,
+                to_us: 100_000,
+                skipped: 5
+            },
+            ResampledItem::Gap {
+                from_us: 135_000,
+                to_us: 161_500,
+                skipped: 3
+            },
+        ]
+    );
+    assert_eq!(
+        samples(&out),
+        [0, 10_000, 20_000, 30_000, 40_000, 100_000, 110_000, 120_000, 130_000]
+    );
+    // the gap precedes the points after it
+    let gap_at = out
+        .iter()
+        .position(|item| matches!(item, ResampledItem::Gap { .. }))
+        .unwrap();
+    assert!(matches!(
+        out[gap_at + 1],
+        ResampledItem::Sample { t_us: 100_000, .. }
+    ));
+    // gaps do not count towards the rate: 6 bridged intervals, 25 ms being one
+    assert!((resampler.clock_skew_us() - 15_000.).abs() < 1e-3);
+}
+
+#[test]
+fn stalled_and_backwards_timestamps_change_nothing() {
+    let mut resampler = UniformResampler::new(100, 100., 50_000);
+    resampler.push(ramp(0)).unwrap();
+    resampler.push(ramp(10_000)).unwrap();
+    assert_eq!(
+        resampler.push(ramp(10_000)).unwrap_err(),
+        TimestampError::Duplicate { t_us: 10_000 }
+    );
+    assert_eq!(
+        resampler.push(ramp(5_000)).unwrap_err(),
+        TimestampError::OutOfOrder {
+            newest_us: 10_000,
+            t_us: 5_000
+        }
+    );
+    let next: Vec<_> = resampler.push(ramp(20_000)).unwrap().collect();
+    assert_eq!(samples(&next), [20_000]);
+    assert!(resampler.clock_skew_us().abs() < 1e-3);
+}
+
+#[test]
+fn iterator_combinator_passes_errors_on() {
+    let script = [0, 10_000, 10_000, 20_000, 15_000, 30_000].map(ramp);
+    let mut stream = resample(script, UniformResampler::new(100, 100., 50_000));
+    let items: Vec<_> = stream.by_ref().collect();
+    let errors = items.iter().filter(|item| item.is_err()).count();
+    assert_eq!(errors, 2);
+    let ok: Vec<_> = items.into_iter().filter_map(Result::ok).collect();
+    assert_eq!(samples(&ok), [0, 10_000, 20_000, 30_000]);
+    assert_eq!(stream.resampler().grid_time(1), Some(10_000));
+}
+
+#[test]
+fn driver_resampler_measures_against_the_corrected_rate() {
+    let bus = SharedBus::new(&[DEFAULT_SLAVE_ADDR]);
+    let mpu = Mpu6050Builder::new().i2c(bus).build().unwrap();
+    let interval_us = mpu.nominal_sample_interval_us() as u64;
+    let mut resampler = mpu.uniform_resampler(100, 50_000);
+    for input in inputs(0, interval_us, 21) {
+        resampler.push(input).unwrap();
+    }
+    let rate = resampler.input_rate_hz().unwrap();
+    assert!((rate - mpu.effective_odr_hz()).abs() / rate < 1e-3);
+}
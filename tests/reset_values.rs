@@ -0,0 +1,76 @@
+//! The documented power-on reset values: the table itself against the register map and
+//! `assert_device_at_defaults` on a mock register file.
+
+mod common;
+
+use mpu6050::device::{
+    reset_value, ACCEL_CONFIG, ACC_REGX_H, INT_STATUS, PWR_MGMT_1, RESET_VALUES, WHOAMI,
+};
+use mpu6050::Mpu6050Builder;
+
+use common::RegisterMock;
+
+#[test]
+fn table_is_sorted_without_duplicates() {
+    for pair in RESET_VALUES.windows(2) {
+        assert!(
+            pair[0].0 < pair[1].0,
+            "{:#04x} before {:#04x}",
+            pair[0].0,
+            pair[1].0
+        );
+    }
+}
+
+#[test]
+fn values_match_the_register_map() {
+    // register map rev 4.2 section 3
+    assert_eq!(reset_value(PWR_MGMT_1::ADDR), Some(0x40));
+    assert_eq!(reset_value(WHOAMI), Some(0x68));
+    for (addr, value) in RESET_VALUES {
+        if *addr == PWR_MGMT_1::ADDR || *addr == WHOAMI {
+            continue;
+        }
+        assert_eq!(*value, 0x00, "{addr:#04x}");
+        assert_eq!(reset_value(*addr), Some(0x00), "{addr:#04x}");
+    }
+}
+
+#[test]
+fn measurement_registers_have_no_reset_value() {
+    assert_eq!(reset_value(ACC_REGX_H), None);
+    assert_eq!(reset_value(0xff), None);
+}
+
+#[test]
+fn fresh_device_is_at_defaults() {
+    let mut mpu = Mpu6050Builder::new()
+        .i2c(RegisterMock::new())
+        .build()
+        .unwrap();
+    assert!(mpu.assert_device_at_defaults().unwrap().at_defaults());
+}
+
+#[test]
+fn changed_registers_are_reported_in_address_order() {
+    let mut mock = RegisterMock::new();
+    mock.regs[PWR_MGMT_1::ADDR as usize] = 0x01;
+    mock.regs[ACCEL_CONFIG::ADDR as usize] = 0x10;
+    // clear on read, never compared
+    mock.regs[INT_STATUS::ADDR as usize] = 0x01;
+    let mut mpu = Mpu6050Builder::new().i2c(mock).build().unwrap();
+
+    let report = mpu.assert_device_at_defaults().unwrap();
+    assert!(!report.at_defaults());
+    let mismatches: Vec<_> = report
+        .mismatches()
+        .map(|m| (m.addr, m.expected, m.actual))
+        .collect();
+    assert_eq!(
+        mismatches,
+        [
+            (ACCEL_CONFIG::ADDR, 0x00, 0x10),
+            (PWR_MGMT_1::ADDR, 0x40, 0x01)
+        ]
+    );
+}
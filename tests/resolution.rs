@@ -0,0 +1,98 @@
+//! Resolution, noise floor and range suggestions against values worked out by hand from the
+//! datasheet sensitivities and noise densities, see the `resolution` module.
+
+use mpu6050::device::{AccelRange, GyroRange};
+use mpu6050::resolution::{suggest_ranges, ResolutionInfo};
+
+fn assert_close(actual: f32, expected: f32) {
+    assert!(
+        (actual - expected).abs() <= expected.abs() * 1e-5,
+        "{actual} != {expected}"
+    );
+}
+
+#[test]
+fn accel_resolution_is_the_inverse_sensitivity() {
+    // 16384, 8192, 4096 and 2048 LSB/g
+    for (range, mg_per_lsb) in [
+        (AccelRange::G2, 0.061_035_156),
+        (AccelRange::G4, 0.122_070_31),
+        (AccelRange::G8, 0.244_140_63),
+        (AccelRange::G16, 0.488_281_25),
+    ] {
+        assert_close(range.resolution_mg_per_lsb(), mg_per_lsb);
+    }
+}
+
+#[test]
+fn gyro_resolution_is_the_inverse_sensitivity() {
+    // 131, 65.5, 32.8 and 16.4 LSB/(°/s)
+    for (range, dps_per_lsb) in [
+        (GyroRange::D250, 0.007_633_588),
+        (GyroRange::D500, 0.015_267_176),
+        (GyroRange::D1000, 0.030_487_805),
+        (GyroRange::D2000, 0.060_975_61),
+    ] {
+        assert_close(range.resolution_dps_per_lsb(), dps_per_lsb);
+    }
+}
+
+#[test]
+fn noise_is_density_times_root_bandwidth() {
+    // DLPF_CFG, accel mg rms (400 µg/√Hz), gyro °/s rms (0.005 °/s/√Hz)
+    for (cfg, accel_mg, gyro_dps) in [
+        // √260 = 16.1245, √256 = 16
+        (0, 6.449_806, 0.08),
+        // √94 = 9.6954, √98 = 9.8995
+        (2, 3.878_144, 0.049_497_475),
+        // √10
+        (5, 1.264_911, 0.015_811_388),
+        // √5
+        (6, 0.894_427_2, 0.011_180_34),
+        // reserved, like 0
+        (7, 6.449_806, 0.08),
+    ] {
+        let info = ResolutionInfo::new(AccelRange::G2, GyroRange::D250, cfg);
+        assert_close(info.accel_noise_mg_rms, accel_mg);
+        assert_close(info.gyro_noise_dps_rms, gyro_dps);
+    }
+}
+
+#[test]
+fn info_combines_ranges_and_dlpf() {
+    let info = ResolutionInfo::new(AccelRange::G8, GyroRange::D1000, 3);
+    assert_close(info.accel_mg_per_lsb, 0.244_140_63);
+    assert_close(info.gyro_dps_per_lsb, 0.030_487_805);
+    assert_eq!(info.accel_bandwidth_hz, 44.);
+    assert_eq!(info.gyro_bandwidth_hz, 42.);
+    assert_eq!(info.accel_delay_ms, 4.9);
+    assert_eq!(info.gyro_delay_ms, 4.8);
+    // upper DLPF_CFG bits are ignored
+    assert_eq!(
+        ResolutionInfo::new(AccelRange::G8, GyroRange::D1000, 0x0b),
+        info
+    );
+}
+
+#[test]
+fn suggested_ranges_are_the_smallest_covering_the_maximum() {
+    for (acc_g, rate_dps, headroom, expected) in [
+        (0.5, 100., 1., (AccelRange::G2, GyroRange::D250)),
+        // the full scale itself is covered
+        (2., 250., 1., (AccelRange::G2, GyroRange::D250)),
+        (2.01, 251., 1., (AccelRange::G4, GyroRange::D500)),
+        // 4.5g and 450°/s with the headroom
+        (3., 300., 1.5, (AccelRange::G8, GyroRange::D500)),
+        (8., 1000., 1., (AccelRange::G8, GyroRange::D1000)),
+        // only the magnitude counts
+        (-3., -600., 1., (AccelRange::G4, GyroRange::D1000)),
+        // beyond every range
+        (20., 3000., 1., (AccelRange::G16, GyroRange::D2000)),
+    ] {
+        assert_eq!(
+            suggest_ranges(acc_g, rate_dps, headroom),
+            expected,
+            "{acc_g} g, {rate_dps} °/s, ×{headroom}"
+        );
+    }
+}
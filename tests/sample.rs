@@ -0,0 +1,107 @@
+//! Constructing and reading `MpuSample` from outside the crate: the constructors, the `with_*`
+//! methods and accessors, the defaults and the documented comparison semantics, see the
+//! `sample` module.
+
+use mpu6050::device::AccelRange;
+use mpu6050::impact::RangeTransition;
+use mpu6050::plausibility::PlausibilityScore;
+use mpu6050::provenance::{Provenance, SampleProvenance};
+use mpu6050::skew::SkewReference;
+use mpu6050::{MpuSample, Vec3A};
+
+const ACC: Vec3A = Vec3A::new(0.1, -0.2, 0.98);
+const GYRO: Vec3A = Vec3A::new(0.01, 0.02, -0.03);
+
+#[test]
+fn new_sets_the_values_and_neutral_tags() {
+    let sample = MpuSample::new(ACC, GYRO, 25.5);
+    assert_eq!(sample.acc(), ACC);
+    assert_eq!(sample.gyro(), GYRO);
+    assert_eq!(sample.temp(), 25.5);
+    assert!(!sample.settling());
+    assert!(!sample.out_of_band());
+    assert_eq!(sample.accel_range(), None);
+    assert_eq!(sample.range_transition(), None);
+    assert_eq!(sample.provenance(), SampleProvenance::MEASURED);
+    assert_eq!(sample.time_reference(), None);
+    assert_eq!(sample.plausibility(), PlausibilityScore::OK);
+    assert_eq!(sample.lever_arm_correction(), None);
+    assert_eq!(sample.uncompensated_acc(), ACC);
+
+    assert_eq!(
+        MpuSample::from_arrays(ACC.to_array(), GYRO.to_array(), 25.5),
+        sample
+    );
+    assert_eq!(
+        MpuSample::default(),
+        MpuSample::new(Vec3A::ZERO, Vec3A::ZERO, 0.)
+    );
+}
+
+#[test]
+fn with_methods_replace_one_value() {
+    let sample = MpuSample::new(ACC, GYRO, 25.5);
+    let changed = sample.with_acc(Vec3A::Z).with_gyro(Vec3A::X).with_temp(30.);
+    assert_eq!(
+        (changed.acc(), changed.gyro(), changed.temp()),
+        (Vec3A::Z, Vec3A::X, 30.)
+    );
+    assert_eq!(
+        changed.with_acc(ACC).with_gyro(GYRO).with_temp(25.5),
+        sample
+    );
+    // a copy, the original is untouched
+    assert_eq!(sample.acc(), ACC);
+    let [x, y, z]: [f32; 3] = sample.acc_as();
+    assert_eq!(Vec3A::new(x, y, z), ACC);
+    let gyro: [f32; 3] = sample.gyro_as();
+    assert_eq!(gyro, GYRO.to_array());
+}
+
+#[cfg(not(feature = "minimal-pipeline"))]
+#[test]
+fn tags_round_trip_through_their_accessors() {
+    let provenance = SampleProvenance::uniform(Provenance::Interpolated);
+    let lever_arm = Vec3A::new(0.01, 0., -0.02);
+    let sample = MpuSample::new(ACC, GYRO, 25.5)
+        .with_settling(true)
+        .with_out_of_band(true)
+        .with_accel_range(Some(AccelRange::G16))
+        .with_range_transition(Some(RangeTransition::Switched))
+        .with_provenance(provenance)
+        .with_time_reference(Some(SkewReference::Gyro))
+        .with_lever_arm_correction(Some(lever_arm));
+
+    assert!(sample.settling());
+    assert!(sample.out_of_band());
+    assert_eq!(sample.accel_range(), Some(AccelRange::G16));
+    assert_eq!(sample.range_transition(), Some(RangeTransition::Switched));
+    assert_eq!(sample.provenance(), provenance);
+    assert_eq!(sample.time_reference(), Some(SkewReference::Gyro));
+    assert_eq!(sample.lever_arm_correction(), Some(lever_arm));
+    // the correction is added back
+    assert_eq!(sample.uncompensated_acc(), ACC + lever_arm);
+    // tags take part in the comparison
+    assert_ne!(sample, MpuSample::new(ACC, GYRO, 25.5));
+}
+
+#[cfg(feature = "minimal-pipeline")]
+#[test]
+fn tags_are_not_stored() {
+    let sample = MpuSample::new(ACC, GYRO, 25.5)
+        .with_settling(true)
+        .with_accel_range(Some(AccelRange::G16));
+    assert!(!sample.settling());
+    assert_eq!(sample.accel_range(), None);
+    assert_eq!(sample, MpuSample::new(ACC, GYRO, 25.5));
+}
+
+#[test]
+fn comparison_is_exact() {
+    let sample = MpuSample::new(ACC, GYRO, 25.5);
+    assert_ne!(sample, sample.with_temp(25.5 + 1e-5));
+    // NaN never equals itself, signed zeros do
+    let nan = sample.with_temp(f32::NAN);
+    assert_ne!(nan, nan);
+    assert_eq!(sample.with_temp(0.), sample.with_temp(-0.));
+}
@@ -0,0 +1,116 @@
+//! Scale model of the driver: unit per-axis factors scale exactly like the nominal
+//! sensitivity alone, other factors compose with the offsets in the documented order and
+//! survive range changes, see the `scale` module.
+
+mod common;
+
+use mpu6050::device::{AccelRange, GyroRange, DEFAULT_SLAVE_ADDR};
+use mpu6050::scale::ScaleModel;
+use mpu6050::{Mpu6050, Mpu6050Builder, Vec3A, PI_180};
+
+use common::SharedBus;
+
+const ACC: [i16; 3] = [1234, -16384, 32767];
+const GYRO: [i16; 3] = [-32768, 77, 4096];
+
+fn setup(builder: Mpu6050Builder<SharedBus>) -> (SharedBus, Mpu6050<SharedBus>) {
+    let bus = SharedBus::new(&[DEFAULT_SLAVE_ADDR]);
+    let mut frame = [0; 14];
+    for (i, value) in ACC.iter().enumerate() {
+        frame[2 * i..2 * i + 2].copy_from_slice(&value.to_be_bytes());
+    }
+    for (i, value) in GYRO.iter().enumerate() {
+        frame[8 + 2 * i..10 + 2 * i].copy_from_slice(&value.to_be_bytes());
+    }
+    bus.device(DEFAULT_SLAVE_ADDR, |mock| mock.set_frame(&frame));
+    let mpu = builder.i2c(bus.clone()).build().unwrap();
+    (bus, mpu)
+}
+
+fn counts([x, y, z]: [i16; 3]) -> Vec3A {
+    Vec3A::new(x as f32, y as f32, z as f32)
+}
+
+#[test]
+fn unit_factors_scale_with_the_nominal_sensitivity_alone() {
+    let (_, mut mpu) = setup(Mpu6050Builder::new());
+    for (accel, lsb_per_g) in [
+        (AccelRange::G2, 16384.),
+        (AccelRange::G4, 8192.),
+        (AccelRange::G8, 4096.),
+        (AccelRange::G16, 2048.),
+    ] {
+        mpu.set_accel_range(accel).unwrap();
+        assert_eq!(mpu.get_accel_scale(), ScaleModel::new(lsb_per_g));
+        // bit for bit
+        assert_eq!(mpu.get_acc().unwrap(), counts(ACC) / lsb_per_g, "{accel:?}");
+    }
+    for (gyro, lsb_per_dps) in [
+        (GyroRange::D250, 131.),
+        (GyroRange::D500, 65.5),
+        (GyroRange::D1000, 32.8),
+        (GyroRange::D2000, 16.4),
+    ] {
+        mpu.set_gyro_range(gyro).unwrap();
+        assert_eq!(mpu.get_gyro_scale(), ScaleModel::new(lsb_per_dps));
+        assert_eq!(
+            mpu.get_gyro().unwrap(),
+            counts(GYRO) * (PI_180 / lsb_per_dps),
+            "{gyro:?}"
+        );
+    }
+}
+
+#[test]
+fn factors_apply_after_the_offsets() {
+    let factors = Vec3A::new(1.02, 0.97, 1.005);
+    let offset = Vec3A::new(0.05, -0.02, 0.01);
+    let (_, mut mpu) = setup(Mpu6050Builder::new());
+    mpu.set_acc_offset(offset);
+    mpu.set_accel_scale_factors(factors);
+    mpu.set_gyro_offset(offset);
+    mpu.set_gyro_scale_factors(factors);
+
+    assert_eq!(
+        mpu.get_acc().unwrap(),
+        (counts(ACC) / 16384. - offset) * factors
+    );
+    assert_eq!(
+        mpu.get_gyro().unwrap(),
+        (counts(GYRO) * (PI_180 / 131.) - offset) * factors
+    );
+}
+
+#[test]
+fn range_changes_keep_the_factors() {
+    let factors = Vec3A::new(0.99, 1.01, 1.03);
+    let (_, mut mpu) = setup(Mpu6050Builder::new());
+    mpu.set_accel_scale_factors(factors);
+    mpu.set_gyro_scale_factors(factors * 2.);
+
+    mpu.set_accel_range(AccelRange::G8).unwrap();
+    mpu.set_gyro_range(GyroRange::D1000).unwrap();
+
+    assert_eq!(
+        mpu.get_accel_scale(),
+        ScaleModel::new(4096.).with_factors(factors)
+    );
+    assert_eq!(
+        mpu.get_gyro_scale(),
+        ScaleModel::new(32.8).with_factors(factors * 2.)
+    );
+    assert_eq!(mpu.get_acc().unwrap(), counts(ACC) / 4096. * factors);
+}
+
+#[test]
+fn builder_sets_the_factors() {
+    let acc = Vec3A::new(1.01, 1.0, 0.98);
+    let gyro = [1.0, 1.02, 0.99];
+    let (_, mpu) = setup(
+        Mpu6050Builder::new()
+            .acc_scale_factors(acc)
+            .gyro_scale_factors_array(gyro),
+    );
+    assert_eq!(mpu.get_accel_scale().per_axis, acc);
+    assert_eq!(mpu.get_gyro_scale().per_axis, Vec3A::from(gyro));
+}
@@ -0,0 +1,312 @@
+//! [`ScaleDriftMonitor`] on synthetic rests with injected scale errors, and its integration
+//! with the scale factors and the calibration policy.
+
+mod common;
+
+use mpu6050::device::DEFAULT_SLAVE_ADDR;
+use mpu6050::log_header::CalibrationMethod;
+use mpu6050::scale_drift::*;
+use mpu6050::validity::*;
+use mpu6050::*;
+
+use common::{NoDelay, SharedBus};
+
+const DT_S: f32 = 0.01;
+
+/// Deterministic noise in [-amplitude, amplitude]
+struct Noise(u64);
+
+impl Noise {
+    fn next(&mut self, amplitude: f32) -> f32 {
+        self.0 = self
+            .0
+            .wrapping_mul(6_364_136_223_846_793_005)
+            .wrapping_add(1_442_695_040_888_963_407);
+        ((self.0 >> 40) as f32 / (1u64 << 24) as f32 * 2. - 1.) * amplitude
+    }
+
+    fn vec(&mut self, amplitude: f32) -> Vec3A {
+        Vec3A::new(
+            self.next(amplitude),
+            self.next(amplitude),
+            self.next(amplitude),
+        )
+    }
+}
+
+/// Three directions of gravity in every octant, off its center
+fn orientations() -> Vec<Vec3A> {
+    let mut directions = Vec::new();
+    for x in [-1., 1.] {
+        for y in [-1., 1.] {
+            for z in [-1., 1.] {
+                directions.push(Vec3A::new(0.3 * x, 0.5 * y, z));
+                directions.push(Vec3A::new(x, 0.4 * y, 0.2 * z));
+                directions.push(Vec3A::new(0.2 * x, y, 0.6 * z));
+            }
+        }
+    }
+    directions
+}
+
+/// A chip reading gravity with per-axis `scale` and `offset`
+struct Chip {
+    scale: Vec3A,
+    offset: Vec3A,
+    noise: Noise,
+}
+
+impl Chip {
+    fn new(scale: Vec3A, offset: Vec3A) -> Self {
+        Self {
+            scale,
+            offset,
+            noise: Noise(3),
+        }
+    }
+
+    fn reading(&mut self, gravity: Vec3A) -> Vec3A {
+        gravity.normalize() * self.scale + self.offset + self.noise.vec(0.003)
+    }
+
+    /// `seconds` at rest along each direction, turned in between
+    fn rest(
+        &mut self,
+        monitor: &mut ScaleDriftMonitor,
+        directions: &[Vec3A],
+        seconds: f32,
+        factors: Vec3A,
+    ) {
+        for direction in directions {
+            for _ in 0..(seconds / DT_S) as usize {
+                let gyro = self.noise.vec(0.005);
+                monitor.feed_readings(self.reading(*direction) * factors, gyro, DT_S);
+            }
+            turn(monitor, *direction);
+        }
+    }
+}
+
+/// a second of being turned
+fn turn(monitor: &mut ScaleDriftMonitor, direction: Vec3A) {
+    for _ in 0..(1. / DT_S) as usize {
+        assert!(!monitor.feed_readings(direction, Vec3A::new(0.5, -0.3, 0.2), DT_S));
+    }
+}
+
+fn new_monitor() -> ScaleDriftMonitor {
+    ScaleDriftMonitor::new(ScaleDriftConfig::default())
+}
+
+#[test]
+fn injected_scale_errors_are_recovered() {
+    let cases = [
+        (Vec3A::ONE, Vec3A::ZERO),
+        (Vec3A::new(1.02, 0.985, 1.01), Vec3A::ZERO),
+        (
+            Vec3A::new(0.97, 1.015, 1.03),
+            Vec3A::new(0.02, -0.01, 0.015),
+        ),
+        (Vec3A::new(1.005, 1.005, 0.99), Vec3A::new(-0.03, 0.02, 0.)),
+    ];
+    for (scale, offset) in cases {
+        let mut monitor = new_monitor();
+        Chip::new(scale, offset).rest(&mut monitor, &orientations(), 8., Vec3A::ONE);
+        let estimate = monitor.estimate().unwrap();
+        let error = estimate.scale_correction * scale - Vec3A::ONE;
+        // within 0.1% per axis
+        assert!(error.abs().max_element() < 1e-3, "{:?}: {:?}", scale, error);
+        assert!((estimate.offset_g - offset).abs().max_element() < 2e-3);
+        assert_eq!(estimate.populated, 8);
+        // conservative, the error is well within
+        assert!(estimate.std_dev.max_element() < 0.002);
+        assert!(estimate.residual_rms_g < 2e-3);
+        // a second of each rest settles the stillness
+        let held = monitor.progress().octant_s;
+        assert!(held.iter().all(|s| (s - 21.).abs() < 0.1), "{:?}", held);
+    }
+}
+
+#[test]
+fn every_axis_needs_varied_orientations() {
+    let chip = || Chip::new(Vec3A::new(1.02, 0.985, 1.01), Vec3A::ZERO);
+    // flat on a table, upside down and on its sides: four octants
+    let few = [
+        Vec3A::new(0.1, 0.1, 1.),
+        Vec3A::new(0.1, 0.1, -1.),
+        Vec3A::new(-1., 0.1, 0.1),
+        Vec3A::new(0.1, -1., 0.1),
+    ];
+    let mut monitor = new_monitor();
+    chip().rest(&mut monitor, &few, 20., Vec3A::ONE);
+    match monitor.estimate() {
+        Err(ScaleDriftError::LowConfidence(progress)) => {
+            assert_eq!(progress.populated, 4);
+            assert!(progress.std_dev.max_element() > 0.002);
+        }
+        other => panic!("{:?}", other),
+    }
+
+    // the centers of all octants do not separate the axes
+    let centers: Vec<_> = orientations().iter().map(|d| d.signum()).collect();
+    let mut monitor = new_monitor();
+    chip().rest(&mut monitor, &centers, 8., Vec3A::ONE);
+    match monitor.estimate() {
+        Err(ScaleDriftError::LowConfidence(progress)) => {
+            assert_eq!(progress.populated, 8);
+            assert!(progress.std_dev.max_element() > 0.002);
+        }
+        other => panic!("{:?}", other),
+    }
+
+    // too short to populate an octant
+    let mut monitor = new_monitor();
+    chip().rest(&mut monitor, &orientations(), 2., Vec3A::ONE);
+    let error = monitor.estimate().unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "low confidence, 0 of 8 octants populated, standard deviation inf"
+    );
+}
+
+#[test]
+fn moving_device_accumulates_nothing() {
+    let mut monitor = new_monitor();
+    let mut noise = Noise(5);
+    for i in 0..10_000 {
+        // turning, or accelerating while held straight
+        let (acc, gyro) = match i % 2 {
+            0 => (Vec3A::Z + noise.vec(0.003), Vec3A::new(0., 0.2, 0.)),
+            _ => (Vec3A::new(0.3, 0., 1.), Vec3A::ZERO),
+        };
+        assert!(!monitor.feed_readings(acc, gyro, DT_S));
+    }
+    assert_eq!(monitor.progress().octant_s, [0.; 8]);
+    assert!(matches!(
+        monitor.estimate(),
+        Err(ScaleDriftError::LowConfidence(_))
+    ));
+    assert!(!monitor.feed_readings(Vec3A::Z, Vec3A::ZERO, f32::NAN));
+}
+
+#[test]
+fn octants_hold_a_bounded_window() {
+    let config = ScaleDriftConfig {
+        octant_window_s: 10.,
+        ..ScaleDriftConfig::default()
+    };
+    let mut monitor = ScaleDriftMonitor::new(config);
+    let mut chip = Chip::new(Vec3A::ONE, Vec3A::ZERO);
+    chip.rest(&mut monitor, &[Vec3A::new(0.2, 0.3, 1.)], 100., Vec3A::ONE);
+    let held = monitor.progress().octant_s;
+    assert!((held[0] - 10.).abs() < 1e-3);
+    assert_eq!(held[1..], [0.; 7]);
+
+    monitor.reset();
+    assert_eq!(monitor.progress().octant_s, [0.; 8]);
+}
+
+fn driver() -> Mpu6050<SharedBus> {
+    let bus = SharedBus::new(&[DEFAULT_SLAVE_ADDR]);
+    let mut mpu = Mpu6050Builder::new().i2c(bus).build().unwrap();
+    mpu.init(&mut NoDelay).unwrap();
+    mpu
+}
+
+#[test]
+fn corrections_apply_in_bounded_steps() {
+    let scale = Vec3A::new(1.012, 0.994, 1.);
+    let mut mpu = driver();
+    let mut monitor = new_monitor();
+    let mut chip = Chip::new(scale, Vec3A::ZERO);
+    let epoch = mpu.config_epoch();
+
+    // the statistics follow the factors applied, without a reset
+    chip.rest(&mut monitor, &orientations(), 8., Vec3A::ONE);
+    let mut steps = Vec::new();
+    for _ in 0..4 {
+        let step = mpu.apply_scale_correction(&mut monitor).unwrap();
+        assert!((step - Vec3A::ONE).abs().max_element() <= 0.005 + 1e-6);
+        steps.push(step);
+    }
+    assert_eq!(steps[0].x, 1. - 0.005);
+    assert_eq!(steps[0].y, 1. + 0.005);
+    let factors = mpu.get_accel_scale().per_axis;
+    assert!((factors * scale - Vec3A::ONE).abs().max_element() < 1e-3);
+    assert!(mpu.config_epoch() > epoch);
+
+    // new readings through the new factors agree
+    chip.rest(&mut monitor, &orientations(), 8., factors);
+    let estimate = monitor.estimate().unwrap();
+    assert!(estimate.drift() < 1e-3);
+
+    // refused estimates change nothing
+    let mut empty = ScaleDriftMonitor::new(ScaleDriftConfig::default());
+    assert!(mpu.apply_scale_correction(&mut empty).is_err());
+    assert_eq!(mpu.get_accel_scale().per_axis, factors);
+}
+
+#[test]
+fn scale_drift_degrades_the_scale_factors() {
+    let mut mpu = driver();
+    mpu.set_calibration_policy(Some(CalibrationPolicy {
+        max_scale_drift: Some(0.002),
+        ..CalibrationPolicy::NONE
+    }));
+    let kind = CalibrationKind::ScaleFactors;
+    mpu.set_calibration_meta(kind, Some(CalibrationMeta::new(CalibrationMethod::Static)));
+
+    let mut monitor = new_monitor();
+    Chip::new(Vec3A::new(1.008, 1., 1.), Vec3A::ZERO).rest(
+        &mut monitor,
+        &orientations(),
+        8.,
+        Vec3A::ONE,
+    );
+    let estimate = mpu.check_scale_drift(&monitor).unwrap();
+    assert!(estimate.drift() > 0.007);
+    let reasons = InvalidationReasons::EMPTY.with(InvalidationReason::ScaleDrift);
+    assert_eq!(
+        mpu.calibration_status_of(kind),
+        CalibrationStatus::Degraded { reasons }
+    );
+    assert_eq!(
+        mpu.calibration_status(),
+        CalibrationStatus::Degraded { reasons }
+    );
+    // the offsets are not affected
+    assert_eq!(
+        mpu.calibration_status_of(CalibrationKind::SoftwareOffsets),
+        CalibrationStatus::Valid
+    );
+
+    // two steps take the drift within the limit
+    mpu.apply_scale_correction(&mut monitor).unwrap();
+    assert!(!mpu.calibration_status().reasons().is_empty());
+    mpu.apply_scale_correction(&mut monitor).unwrap();
+    assert_eq!(mpu.calibration_status(), CalibrationStatus::Valid);
+
+    // not latched, and not stored with the metadata
+    let meta = mpu.calibration_meta(kind).unwrap();
+    assert!(meta.latched.is_empty());
+}
+
+#[test]
+fn drift_is_not_evaluated_without_the_rule() {
+    let mut validity = CalibrationMonitor::new();
+    let kind = CalibrationKind::ScaleFactors;
+    validity.set_meta(
+        kind,
+        Some(CalibrationMeta::new(CalibrationMethod::External)),
+    );
+    validity.set_scale_drift(0.1);
+    assert_eq!(validity.status(), CalibrationStatus::Valid);
+    validity.set_policy(Some(CalibrationPolicy {
+        max_scale_drift: Some(0.01),
+        ..CalibrationPolicy::NONE
+    }));
+    assert_eq!(validity.status_of(kind).reasons().bits(), 1 << 3);
+    validity.set_scale_drift(0.005);
+    assert_eq!(validity.status(), CalibrationStatus::Valid);
+    assert_eq!(validity.scale_drift(), 0.005);
+}
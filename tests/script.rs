@@ -0,0 +1,411 @@
+//! Register scripts: format, whitelist, expectations and the register sequence of a quirk
+//! workaround against `tests/golden`, see the `script` module.
+
+mod common;
+
+use embedded_hal::blocking::delay::DelayMs;
+use mpu6050::device::*;
+use mpu6050::packed::crc16;
+use mpu6050::script::*;
+use mpu6050::trace::{check_golden, TraceHandle, TracingI2c};
+use mpu6050::*;
+
+use common::{NoDelay, RegisterMock};
+
+type Mpu = Mpu6050<TracingI2c<RegisterMock>>;
+
+/// delay marking the trace
+struct TracedDelay(TraceHandle);
+
+impl DelayMs<u8> for TracedDelay {
+    fn delay_ms(&mut self, ms: u8) {
+        self.0.mark(&format!("delay {} ms", ms));
+    }
+}
+
+fn initialized(strict: bool) -> (Mpu, TraceHandle) {
+    let (i2c, trace) = TracingI2c::new(RegisterMock::new());
+    let mut mpu = Mpu6050Builder::new()
+        .i2c(i2c)
+        .strict_configuration(strict)
+        .build()
+        .unwrap();
+    mpu.init(&mut NoDelay).unwrap();
+    trace.clear();
+    (mpu, trace)
+}
+
+const fn bits(reg: u8, start_bit: u8, length: u8, value: u8) -> ScriptStep {
+    ScriptStep::WriteBits {
+        reg,
+        start_bit,
+        length,
+        value,
+    }
+}
+
+const fn expect(reg: u8, start_bit: u8, length: u8, value: u8, on_fail: OnFail) -> ScriptStep {
+    ScriptStep::ExpectBits {
+        reg,
+        start_bit,
+        length,
+        value,
+        on_fail,
+    }
+}
+
+/// Batch with a slow oscillator start and a gyro offset after wake: wait longer after
+/// clearing SLEEP, reset the signal paths once more, then the usual configuration
+const SLOW_WAKE_QUIRK: RegisterScript = RegisterScript::new()
+    .with_step(expect(WHOAMI, 7, 8, 0x68, OnFail::Abort))
+    .with_step(bits(PWR_MGMT_1::ADDR, PWR_MGMT_1::SLEEP, 1, 0))
+    .with_step(ScriptStep::DelayMs(50))
+    .with_step(ScriptStep::WriteReg {
+        reg: SIGNAL_PATH_RESET::ADDR,
+        value: 0x07,
+    })
+    .with_step(ScriptStep::DelayMs(100))
+    .with_step(bits(USER_CTRL::ADDR, USER_CTRL::SIG_COND_RESET, 1, 1))
+    .with_step(bits(CONFIG::ADDR, 2, 3, 3))
+    .with_step(ScriptStep::WriteReg {
+        reg: SMPLRT_DIV,
+        value: 4,
+    })
+    .with_step(bits(GYRO_CONFIG::ADDR, 4, 2, GyroRange::D500 as u8))
+    .with_step(expect(
+        PWR_MGMT_1::ADDR,
+        PWR_MGMT_1::SLEEP,
+        1,
+        0,
+        OnFail::Continue,
+    ));
+
+#[test]
+fn round_trip() {
+    let mut buf = [0; MAX_SCRIPT_LEN];
+    let len = SLOW_WAKE_QUIRK.encode(&mut buf).unwrap();
+    assert_eq!(len, SLOW_WAKE_QUIRK.encoded_len());
+    assert_eq!(RegisterScript::decode(&buf[..len]), Ok(SLOW_WAKE_QUIRK));
+    assert_eq!(
+        &buf[..12],
+        &[1, 10, 4, WHOAMI, 7, 8, 0x68, 0, 2, PWR_MGMT_1::ADDR, 6, 1]
+    );
+
+    assert_eq!(
+        SLOW_WAKE_QUIRK.encode(&mut buf[..len - 1]),
+        Err(EncodeError::BufferTooSmall(len))
+    );
+    assert_eq!(
+        RegisterScript::decode(&buf[..len - 1]),
+        Err(DecodeError::Truncated)
+    );
+    let mut corrupted = buf;
+    corrupted[5] ^= 0x10;
+    assert_eq!(
+        RegisterScript::decode(&corrupted),
+        Err(DecodeError::CrcMismatch)
+    );
+    corrupted[0] = 2;
+    assert_eq!(
+        RegisterScript::decode(&corrupted),
+        Err(DecodeError::BadHeader)
+    );
+
+    // a full script, and the empty one
+    let mut full = RegisterScript::new();
+    for i in 0..MAX_SCRIPT_STEPS {
+        full.push(expect(WHOAMI, 7, 8, i as u8, OnFail::Continue))
+            .unwrap();
+    }
+    assert_eq!(
+        full.push(ScriptStep::DelayMs(1)),
+        Err(ScriptViolation::TooManySteps)
+    );
+    assert_eq!(full.encode(&mut buf), Ok(MAX_SCRIPT_LEN));
+    assert_eq!(RegisterScript::decode(&buf), Ok(full));
+    let len = RegisterScript::new().encode(&mut buf).unwrap();
+    assert_eq!(len, 4);
+    assert!(RegisterScript::decode(&buf[..len]).unwrap().is_empty());
+}
+
+/// bytes with a valid CRC
+fn framed(body: &[u8]) -> Vec<u8> {
+    let mut bytes = body.to_vec();
+    bytes.extend_from_slice(&crc16(body).to_le_bytes());
+    bytes
+}
+
+#[test]
+fn malformed_input() {
+    assert_eq!(
+        RegisterScript::decode(&framed(&[1, 1, 9, 0])),
+        Err(DecodeError::UnknownOpcode { step: 0, opcode: 9 })
+    );
+    assert_eq!(
+        RegisterScript::decode(&framed(&[1, 2, 3, 10, 4, WHOAMI, 7, 8, 0x68, 2])),
+        Err(DecodeError::UnknownOpcode { step: 1, opcode: 4 })
+    );
+    assert_eq!(
+        RegisterScript::decode(&framed(&[1, 33])),
+        Err(DecodeError::Invalid(ScriptViolation::TooManySteps))
+    );
+    // well formed, but writing WHO_AM_I
+    assert_eq!(
+        RegisterScript::decode(&framed(&[1, 1, 1, WHOAMI, 0])),
+        Err(DecodeError::Invalid(ScriptViolation::NotWritable {
+            step: 0,
+            reg: WHOAMI
+        }))
+    );
+    assert_eq!(RegisterScript::decode(&[]), Err(DecodeError::Truncated));
+}
+
+#[test]
+fn whitelist_rejection() {
+    let refused = [
+        // read only, device reset, FIFO, offsets
+        (
+            ScriptStep::WriteReg {
+                reg: WHOAMI,
+                value: 0,
+            },
+            ScriptViolation::NotWritable {
+                step: 0,
+                reg: WHOAMI,
+            },
+        ),
+        (
+            bits(PWR_MGMT_1::ADDR, PWR_MGMT_1::DEVICE_RESET, 1, 1),
+            ScriptViolation::NotWritable {
+                step: 0,
+                reg: PWR_MGMT_1::ADDR,
+            },
+        ),
+        (
+            ScriptStep::WriteReg {
+                reg: FIFO_EN,
+                value: 0xf8,
+            },
+            ScriptViolation::NotWritable {
+                step: 0,
+                reg: FIFO_EN,
+            },
+        ),
+        (
+            ScriptStep::WriteReg {
+                reg: XG_OFFS_USRH,
+                value: 1,
+            },
+            ScriptViolation::NotWritable {
+                step: 0,
+                reg: XG_OFFS_USRH,
+            },
+        ),
+        // partly writable register, whole byte
+        (
+            ScriptStep::WriteReg {
+                reg: CONFIG::ADDR,
+                value: 3,
+            },
+            ScriptViolation::NotWritable {
+                step: 0,
+                reg: CONFIG::ADDR,
+            },
+        ),
+        // range and HPF together
+        (
+            ScriptStep::WriteReg {
+                reg: ACCEL_CONFIG::ADDR,
+                value: 0x08,
+            },
+            ScriptViolation::SplitsCachedField {
+                step: 0,
+                reg: ACCEL_CONFIG::ADDR,
+            },
+        ),
+        (
+            bits(ACCEL_CONFIG::ADDR, 5, 3, 1),
+            ScriptViolation::SplitsCachedField {
+                step: 0,
+                reg: ACCEL_CONFIG::ADDR,
+            },
+        ),
+        (
+            bits(GYRO_CONFIG::ADDR, 2, 4, 0),
+            ScriptViolation::InvalidField { step: 0 },
+        ),
+        (
+            bits(GYRO_CONFIG::ADDR, 4, 2, 4),
+            ScriptViolation::InvalidField { step: 0 },
+        ),
+        (
+            expect(WHOAMI, 8, 1, 0, OnFail::Abort),
+            ScriptViolation::InvalidField { step: 0 },
+        ),
+        // half of a pair, outside the register map
+        (
+            expect(TEMP_OUT_H, 7, 1, 0, OnFail::Abort),
+            ScriptViolation::NotReadable {
+                step: 0,
+                reg: TEMP_OUT_H,
+            },
+        ),
+        (
+            expect(0xff, 7, 1, 0, OnFail::Abort),
+            ScriptViolation::NotReadable { step: 0, reg: 0xff },
+        ),
+    ];
+
+    let (mut mpu, trace) = initialized(false);
+    for (step, violation) in refused {
+        // the refused step last, after an allowed write
+        let allowed = ScriptStep::WriteReg {
+            reg: MOT_THR,
+            value: 20,
+        };
+        let script = RegisterScript::new().with_step(step);
+        assert_eq!(script.validate(), Err(violation), "{:?}", step);
+        let script = RegisterScript::new().with_step(allowed).with_step(step);
+        let shifted = match violation {
+            ScriptViolation::NotWritable { reg, .. } => {
+                ScriptViolation::NotWritable { step: 1, reg }
+            }
+            ScriptViolation::SplitsCachedField { reg, .. } => {
+                ScriptViolation::SplitsCachedField { step: 1, reg }
+            }
+            ScriptViolation::InvalidField { .. } => ScriptViolation::InvalidField { step: 1 },
+            ScriptViolation::NotReadable { reg, .. } => {
+                ScriptViolation::NotReadable { step: 1, reg }
+            }
+            other => other,
+        };
+        match mpu.run_script(&script, &mut NoDelay) {
+            Err(ScriptError::Rejected(v)) => assert_eq!(v, shifted),
+            other => panic!("{:?}: {:?}", step, other),
+        }
+        assert_eq!(RegisterScript::from_steps(script.steps()), Err(shifted));
+    }
+    assert_eq!(trace.transactions(), 0);
+
+    let slow = [ScriptStep::DelayMs(250); 5];
+    assert_eq!(
+        RegisterScript::from_steps(&slow),
+        Err(ScriptViolation::DelayBudget(1250))
+    );
+    assert!(RegisterScript::from_steps(&slow[..4]).is_ok());
+}
+
+#[test]
+fn failed_expectation_aborts() {
+    let (mut mpu, trace) = initialized(false);
+    let script = RegisterScript::new()
+        .with_step(ScriptStep::WriteReg {
+            reg: MOT_THR,
+            value: 20,
+        })
+        .with_step(expect(WHOAMI, 7, 8, 0x70, OnFail::Abort))
+        .with_step(ScriptStep::WriteReg {
+            reg: MOT_DUR,
+            value: 40,
+        });
+    match mpu.run_script(&script, &mut NoDelay) {
+        Err(ScriptError::ExpectationFailed { step, report }) => {
+            assert_eq!(step, 1);
+            assert_eq!(
+                report.outcomes(),
+                &[
+                    StepOutcome::Written,
+                    StepOutcome::Mismatch(0x68),
+                    StepOutcome::NotRun
+                ]
+            );
+        }
+        other => panic!("{:?}", other),
+    }
+    assert_eq!(
+        trace.render(),
+        "W 0x68 MOT_THR [14]\nR 0x68 WHO_AM_I [68]\n"
+    );
+}
+
+#[test]
+fn failed_expectation_continues() {
+    let (mut mpu, trace) = initialized(false);
+    let script = RegisterScript::new()
+        .with_step(expect(WHOAMI, 6, 6, 0x38, OnFail::Continue))
+        .with_step(expect(WHOAMI, 6, 6, 0x34, OnFail::Continue))
+        .with_step(ScriptStep::WriteReg {
+            reg: MOT_DUR,
+            value: 40,
+        });
+    let report = mpu.run_script(&script, &mut NoDelay).unwrap();
+    assert_eq!(
+        report.outcomes(),
+        &[
+            StepOutcome::Mismatch(0x34),
+            StepOutcome::Matched,
+            StepOutcome::Written
+        ]
+    );
+    assert_eq!(report.mismatches(), 1);
+    assert_eq!(mpu.read_byte(MOT_DUR).unwrap(), 40);
+    assert_eq!(trace.transactions(), 4);
+}
+
+#[test]
+fn cached_fields_go_through_the_setters() {
+    let (mut mpu, _) = initialized(false);
+    let script = RegisterScript::new()
+        .with_step(bits(ACCEL_CONFIG::ADDR, 4, 2, AccelRange::G8 as u8))
+        // self test bits of the same register, not cached
+        .with_step(bits(ACCEL_CONFIG::ADDR, 7, 1, 1))
+        // one bit of the range field
+        .with_step(bits(GYRO_CONFIG::ADDR, 4, 1, 1))
+        .with_step(bits(CONFIG::ADDR, 1, 2, 2))
+        .with_step(ScriptStep::WriteReg {
+            reg: SMPLRT_DIV,
+            value: 9,
+        });
+    mpu.run_script(&script, &mut NoDelay).unwrap();
+    let state = mpu.debug_state();
+    assert_eq!(state.accel_range, AccelRange::G8);
+    assert_eq!(state.gyro_range, GyroRange::D1000);
+    assert_eq!(state.dlpf_cfg, 2);
+    assert_eq!(state.sample_rate_div, 9);
+    assert_eq!(mpu.read_byte(ACCEL_CONFIG::ADDR).unwrap(), 0x90);
+    assert_eq!(mpu.read_byte(GYRO_CONFIG::ADDR).unwrap(), 0x10);
+    assert_eq!(mpu.read_byte(CONFIG::ADDR).unwrap(), 0x02);
+
+    // strict configuration applies to scripts too
+    let (mut mpu, _) = initialized(true);
+    let aliasing = RegisterScript::new().with_step(ScriptStep::WriteReg {
+        reg: SMPLRT_DIV,
+        value: 19,
+    });
+    assert!(matches!(
+        mpu.run_script(&aliasing, &mut NoDelay),
+        Err(ScriptError::Driver {
+            step: 0,
+            error: Mpu6050Error::AliasingLikely(_)
+        })
+    ));
+    assert_eq!(mpu.debug_state().sample_rate_div, 0);
+}
+
+#[test]
+fn slow_wake_quirk_golden() {
+    let (mut mpu, trace) = initialized(false);
+    let report = mpu
+        .run_script(&SLOW_WAKE_QUIRK, &mut TracedDelay(trace.clone()))
+        .unwrap();
+    assert_eq!(report.mismatches(), 0);
+    assert_eq!(report.delay_ms(), 150);
+    assert_eq!(mpu.debug_state().gyro_range, GyroRange::D500);
+    let path = format!(
+        "{}/tests/golden/script_slow_wake_quirk.trace",
+        env!("CARGO_MANIFEST_DIR")
+    );
+    if let Err(mismatch) = check_golden(path, &trace.render()) {
+        panic!("{}", mismatch);
+    }
+}
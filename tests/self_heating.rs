@@ -0,0 +1,380 @@
+//! Self-heating characterization: the settling fit on synthetic curves with known
+//! parameters, the bounded fitter, the routine over a mock whose bias follows a curve in the
+//! time the delay waits, its aborts, and the boot-transient corrector, see the
+//! `self_heating` module.
+
+mod common;
+
+use embedded_hal::blocking::delay::DelayMs;
+use mpu6050::config_epoch::ConfigChange;
+use mpu6050::device::*;
+use mpu6050::packed::DecodeError;
+use mpu6050::self_heating::*;
+use mpu6050::settling::SettlingPolicy;
+use mpu6050::*;
+
+use common::{NoDelay, SharedBus};
+
+const ADDR: u8 = DEFAULT_SLAVE_ADDR;
+/// gyro counts per rad/s at ±250 °/s
+const COUNTS_PER_RAD_S: f32 = 131. / PI_180;
+
+/// Deterministic noise in [-amplitude, amplitude]
+struct Noise(u64);
+
+impl Noise {
+    fn next(&mut self, amplitude: f32) -> f32 {
+        self.0 = self
+            .0
+            .wrapping_mul(6_364_136_223_846_793_005)
+            .wrapping_add(1_442_695_040_888_963_407);
+        ((self.0 >> 40) as f32 / (1u64 << 24) as f32 * 2. - 1.) * amplitude
+    }
+}
+
+fn curve(asymptote: f32, amplitude: f32, tau: f32, t: f32) -> f32 {
+    asymptote + amplitude * (-t / tau).exp()
+}
+
+#[test]
+fn fit_recovers_known_curves() {
+    for (asymptote, amplitude, tau) in [
+        (0.005, 0.02, 120.),
+        (-0.01, -0.03, 40.),
+        (0.002, 0.008, 300.),
+        (33., -8., 90.),
+    ] {
+        let t: Vec<f32> = (0..=120).map(|i| i as f32 * 5.).collect();
+        let y: Vec<f32> = t
+            .iter()
+            .map(|&t| curve(asymptote, amplitude, tau, t))
+            .collect();
+        let fit = fit_exponential_settling(&t, &y).unwrap();
+        assert!((fit.time_constant_s - tau).abs() < tau * 0.01, "{:?}", fit);
+        assert!(
+            (fit.amplitude - amplitude).abs() < amplitude.abs() * 0.01,
+            "{:?}",
+            fit
+        );
+        assert!(
+            (fit.asymptote - asymptote).abs() < amplitude.abs() * 0.01,
+            "{:?}",
+            fit
+        );
+        assert!(fit.r_squared > 0.9999, "{:?}", fit);
+        assert!(!fit.at_bound);
+        assert_eq!(fit.points, 121);
+        assert!((fit.value_at(200.) - curve(asymptote, amplitude, tau, 200.)).abs() < 1e-3);
+    }
+}
+
+#[test]
+fn fit_recovers_noisy_curves_within_tolerance() {
+    let mut noise = Noise(7);
+    let (asymptote, amplitude, tau) = (0.004, 0.02, 150.);
+    let t: Vec<f32> = (0..=120).map(|i| i as f32 * 5.).collect();
+    let y: Vec<f32> = t
+        .iter()
+        .map(|&t| curve(asymptote, amplitude, tau, t) + noise.next(0.001))
+        .collect();
+    let fit = fit_exponential_settling(&t, &y).unwrap();
+    assert!((fit.time_constant_s - tau).abs() < tau * 0.1, "{:?}", fit);
+    assert!(
+        (fit.amplitude - amplitude).abs() < amplitude * 0.1,
+        "{:?}",
+        fit
+    );
+    assert!((fit.asymptote - asymptote).abs() < 0.001, "{:?}", fit);
+    // uniform noise of ±0.001 has an RMS of 0.00058
+    assert!(
+        fit.rms_residual > 0.0004 && fit.rms_residual < 0.0008,
+        "{:?}",
+        fit
+    );
+    assert!(fit.r_squared > 0.95 && fit.r_squared < 1.);
+}
+
+#[test]
+fn unresolved_and_degenerate_records() {
+    // too few points, no span, not finite
+    assert_eq!(fit_exponential_settling(&[0., 1., 2., 3.], &[1.; 4]), None);
+    assert_eq!(fit_exponential_settling(&[5.; 8], &[1.; 8]), None);
+    let mut y = [1.; 8];
+    y[3] = f32::NAN;
+    assert_eq!(
+        fit_exponential_settling(&[0., 1., 2., 3., 4., 5., 6., 7.], &y),
+        None
+    );
+
+    // a record far shorter than the time constant: a straight line, the time constant at
+    // the upper end of the search
+    let t: Vec<f32> = (0..20).map(|i| i as f32).collect();
+    let y: Vec<f32> = t.iter().map(|&t| 0.01 - 0.0001 * t).collect();
+    let fit = fit_exponential_settling(&t, &y).unwrap();
+    assert!(fit.at_bound, "{:?}", fit);
+
+    // a constant: no amplitude, a perfect fit
+    let fit = fit_exponential_settling(&t, &[0.003; 20]).unwrap();
+    assert!(fit.amplitude.abs() < 1e-6);
+    assert!((fit.asymptote - 0.003).abs() < 1e-6);
+    assert_eq!(fit.r_squared, 1.);
+}
+
+fn point(elapsed_s: f32) -> SelfHeatingPoint {
+    SelfHeatingPoint {
+        elapsed_s,
+        temp_c: curve(33., -8., 100., elapsed_s),
+        gyro_bias: Vec3A::new(
+            curve(0.005, 0.02, 120., elapsed_s),
+            curve(-0.003, -0.015, 120., elapsed_s),
+            curve(0.001, 0.01, 120., elapsed_s),
+        ),
+        gyro_std_dev: 0.,
+    }
+}
+
+#[test]
+fn fitter_memory_stays_bounded_and_spread() {
+    let mut fitter = SelfHeatingFitter::new();
+    for i in 0..1000 {
+        fitter.push(point(i as f32));
+        assert!(fitter.points().len() <= FIT_CAPACITY);
+    }
+    let kept = fitter.points();
+    assert_eq!(kept[0].elapsed_s, 0.);
+    // evenly spaced, covering the record
+    let step = kept[1].elapsed_s - kept[0].elapsed_s;
+    assert!(kept
+        .windows(2)
+        .all(|w| w[1].elapsed_s - w[0].elapsed_s == step));
+    assert!(kept.last().unwrap().elapsed_s > 900.);
+
+    let summary = fitter.summary();
+    assert_eq!(summary.points, 1000);
+    assert!(summary.cold_start);
+    assert_eq!(summary.start_temp_c, 25.);
+    let [x, y, z] = summary.gyro.map(Option::unwrap);
+    for (fit, amplitude) in [(x, 0.02), (y, -0.015), (z, 0.01)] {
+        assert!((fit.time_constant_s - 120.).abs() < 1., "{:?}", fit);
+        assert!((fit.amplitude - amplitude).abs() < 1e-4, "{:?}", fit);
+    }
+    let model = summary.model().unwrap();
+    assert!((model.amplitude - Vec3A::new(0.02, -0.015, 0.01)).length() < 1e-3);
+    assert!((model.active_for_s - 600.).abs() < 5.);
+}
+
+/// Delay moving the mock along a settling curve: every wait advances the clock and writes the
+/// bias and temperature of the new instant, with a count of noise
+struct Warmup {
+    bus: SharedBus,
+    now_ms: u32,
+    noise: Noise,
+    /// die temperature at power-up, rising 8 °C with a 100 s time constant
+    start_c: f32,
+    /// bias shakes by ±0.1 rad/s from this time on
+    motion_from_ms: Option<u32>,
+}
+
+impl Warmup {
+    fn new(bus: SharedBus, start_c: f32) -> Self {
+        let mut warmup = Self {
+            bus,
+            now_ms: 0,
+            noise: Noise(3),
+            start_c,
+            motion_from_ms: None,
+        };
+        warmup.write();
+        warmup
+    }
+
+    fn write(&mut self) {
+        let mut bias = point(self.now_ms as f32 / 1000.).gyro_bias;
+        if self.motion_from_ms.is_some_and(|from| self.now_ms >= from) {
+            bias += Vec3A::splat(self.noise.next(0.1));
+        }
+        let counts = bias * COUNTS_PER_RAD_S + Vec3A::splat(self.noise.next(1.));
+        let temp_c = if self.start_c < 30. {
+            curve(self.start_c + 8., -8., 100., self.now_ms as f32 / 1000.)
+        } else {
+            self.start_c
+        };
+        let temp = ((temp_c - 36.53) * 340.) as i16;
+        self.bus.device(ADDR, |mock| {
+            let at = TEMP_OUT_H as usize;
+            mock.regs[at..at + 2].copy_from_slice(&temp.to_be_bytes());
+            let at = GYRO_REGX_H as usize;
+            for (i, count) in counts.to_array().into_iter().enumerate() {
+                let count = count.round() as i16;
+                mock.regs[at + 2 * i..at + 2 * i + 2].copy_from_slice(&count.to_be_bytes());
+            }
+        });
+    }
+}
+
+impl DelayMs<u8> for Warmup {
+    fn delay_ms(&mut self, ms: u8) {
+        self.now_ms += u32::from(ms);
+        self.write();
+    }
+}
+
+fn driver() -> (Mpu6050<SharedBus>, SharedBus) {
+    let bus = SharedBus::new(&[ADDR]);
+    let mut mpu = Mpu6050Builder::new().i2c(bus.clone()).build().unwrap();
+    mpu.init(&mut NoDelay).unwrap();
+    mpu.set_settling_policy(SettlingPolicy::Flag);
+    (mpu, bus)
+}
+
+#[test]
+fn characterization_streams_points_and_fits_the_curve() {
+    let (mut mpu, bus) = driver();
+    let mut warmup = Warmup::new(bus, 25.);
+    let mut points = Vec::new();
+    let summary = mpu
+        .characterize_self_heating(&mut warmup, 600, 5000, &mut |point| points.push(point))
+        .unwrap();
+
+    assert_eq!(points.len(), 121);
+    assert_eq!(summary.points, 121);
+    assert!(points
+        .windows(2)
+        .all(|w| (w[1].elapsed_s - w[0].elapsed_s - 5.).abs() < 1e-3));
+    // the window of 20 bursts 2 ms apart is centered 19 ms after the point's start
+    assert_eq!(points[0].elapsed_s, 0.019);
+    assert!(points
+        .iter()
+        .all(|point| point.gyro_std_dev < MAX_GYRO_STD_DEV));
+    assert!(warmup.now_ms >= 600_000);
+
+    assert!(summary.cold_start);
+    assert!((summary.start_temp_c - 25.).abs() < 0.1);
+    let temperature = summary.temperature.unwrap();
+    assert!(
+        (temperature.asymptote - 33.).abs() < 0.1,
+        "{:?}",
+        temperature
+    );
+    assert!(
+        (temperature.time_constant_s - 100.).abs() < 5.,
+        "{:?}",
+        temperature
+    );
+    let expected = [(0.005, 0.02), (-0.003, -0.015), (0.001, 0.01)];
+    for (fit, (asymptote, amplitude)) in summary.gyro.map(Option::unwrap).iter().zip(expected) {
+        assert!((fit.time_constant_s - 120.).abs() < 12., "{:?}", fit);
+        assert!((fit.amplitude - amplitude).abs() < 0.001, "{:?}", fit);
+        assert!((fit.asymptote - asymptote).abs() < 0.0005, "{:?}", fit);
+        assert!(fit.r_squared > 0.99, "{:?}", fit);
+    }
+}
+
+#[test]
+fn warm_die_is_reported() {
+    let (mut mpu, bus) = driver();
+    let mut warmup = Warmup::new(bus, 40.);
+    let summary = mpu
+        .characterize_self_heating(&mut warmup, 60, 2000, &mut |_| {})
+        .unwrap();
+    assert!(!summary.cold_start);
+    assert!((summary.start_temp_c - 40.).abs() < 0.1);
+}
+
+#[test]
+fn motion_aborts_cleanly() {
+    let (mut mpu, bus) = driver();
+    let mut warmup = Warmup::new(bus, 25.);
+    warmup.motion_from_ms = Some(30_000);
+    let mut points = Vec::new();
+    let epoch = mpu.config_epoch();
+    let offset = mpu.get_gyro_offset();
+    let error = mpu
+        .characterize_self_heating(&mut warmup, 600, 5000, &mut |point| points.push(point))
+        .unwrap_err();
+    let Mpu6050Error::Aborted { phase, progress } = error else {
+        panic!("{:?}", error);
+    };
+    assert_eq!(phase, MOTION_PHASE);
+    // the still windows before the motion reached the sink
+    assert_eq!(points.len(), 6);
+    assert_eq!(progress.samples, 6);
+    assert_eq!(progress.partial_mean, Some(points[5].gyro_bias));
+    assert!(progress.elapsed_ms >= 30_000 && progress.elapsed_ms < 30_100);
+    // a window is 20 bursts
+    assert_eq!(progress.transactions, 7 * 20);
+    assert_eq!(mpu.config_epoch(), epoch);
+    assert_eq!(mpu.get_gyro_offset(), offset);
+}
+
+#[test]
+fn deadline_aborts_in_the_self_heating_phase() {
+    let (mut mpu, bus) = driver();
+    let mut warmup = Warmup::new(bus, 25.);
+    let mut checks = 0;
+    let error = mpu
+        .characterize_self_heating_with_deadline(&mut warmup, 600, 5000, &mut |_| {}, || {
+            checks += 1;
+            checks > 100
+        })
+        .unwrap_err();
+    let Mpu6050Error::Aborted { phase, progress } = error else {
+        panic!("{:?}", error);
+    };
+    assert_eq!(phase, PHASE);
+    assert!(progress.samples > 0);
+    assert!(progress.elapsed_ms < 600_000);
+}
+
+#[test]
+fn boot_transient_corrects_direct_gyro_reads() {
+    let (mut mpu, _bus) = driver();
+    assert_eq!(mpu.get_gyro().unwrap(), Vec3A::ZERO);
+    let model = BootTransientModel::new(Vec3A::new(0.02, 0., -0.01), Vec3A::new(100., 50., 100.));
+    assert_eq!(model.active_for_s, 500.);
+    let epoch = mpu.config_epoch();
+    assert!(mpu.set_boot_transient_correction(Some(model)));
+    assert_eq!(mpu.config_epoch(), epoch + 1);
+    assert_eq!(mpu.last_config_change(), Some(ConfigChange::Offsets));
+    assert_eq!(mpu.boot_transient_correction(), Some(model));
+    assert_eq!(mpu.boot_transient_elapsed_s(), Some(0.));
+
+    // a still sensor reading the bias curve above its asymptote reads zero
+    let gyro = mpu.get_gyro().unwrap();
+    assert!((gyro - Vec3A::new(-0.02, 0., 0.01)).length() < 1e-6);
+    mpu.advance_boot_transient(100_000);
+    let gyro = mpu.get_all().unwrap().gyro();
+    assert!((gyro.x + 0.02 * (-1f32).exp()).abs() < 1e-6);
+    assert_eq!(mpu.debug_state().boot_transient_elapsed_s, Some(100.));
+    mpu.advance_boot_transient(400_001);
+    assert_eq!(mpu.get_gyro().unwrap(), Vec3A::ZERO);
+
+    let invalid = BootTransientModel::new(Vec3A::ONE, Vec3A::ZERO);
+    assert!(!mpu.set_boot_transient_correction(Some(invalid)));
+    assert_eq!(mpu.boot_transient_correction(), Some(model));
+    assert!(mpu.set_boot_transient_correction(None));
+    assert_eq!(mpu.boot_transient_elapsed_s(), None);
+}
+
+#[test]
+fn boot_transient_round_trips_through_bytes() {
+    let model = BootTransientModel::new(Vec3A::new(0.02, -0.015, 0.01), Vec3A::splat(120.));
+    let bytes = model.to_bytes();
+    assert_eq!(bytes[0], BOOT_TRANSIENT_VERSION);
+    assert_eq!(BootTransientModel::from_bytes(&bytes), Ok(model));
+    assert_eq!(
+        BootTransientModel::from_bytes(&bytes[..10]),
+        Err(DecodeError::Truncated(BOOT_TRANSIENT_LEN))
+    );
+    let mut corrupt = bytes;
+    corrupt[5] ^= 1;
+    assert_eq!(
+        BootTransientModel::from_bytes(&corrupt),
+        Err(DecodeError::CrcMismatch)
+    );
+    let invalid = BootTransientModel::new(Vec3A::ONE, Vec3A::new(1., -1., 1.)).to_bytes();
+    assert_eq!(
+        BootTransientModel::from_bytes(&invalid),
+        Err(DecodeError::BadHeader)
+    );
+}
@@ -0,0 +1,395 @@
+//! Randomized round trips of valid [`Mpu6050Settings`] through the driver and the register
+//! mock.
+//!
+//! Every case generates settings from a seeded generator, applies them after `init` and
+//! checks the invariants in [`check`]. A failing case is shrunk field by field to the
+//! simplest settings that still fail before it is reported. Pairs of settings check the
+//! configuration epoch of the diffs between them, see [`check_diff`].
+//!
+//! 256 cases run by default. For a soak run raise the count and vary the seed:
+//! `SETTINGS_FUZZ_CASES=100000 SETTINGS_FUZZ_SEED=7 cargo test --features test-util --test settings_fuzz`
+
+mod common;
+
+use mpu6050::config_epoch::ConfigChange;
+use mpu6050::device::*;
+use mpu6050::settings::Mpu6050Settings;
+use mpu6050::trace::{Direction, TraceEntry, TracingI2c};
+use mpu6050::*;
+
+use common::{NoDelay, SharedBus};
+
+const DEFAULT_CASES: u32 = 256;
+const DEFAULT_SEED: u64 = 0x5e77_1265;
+
+const ACCEL_RANGES: [AccelRange; 4] = [
+    AccelRange::G2,
+    AccelRange::G4,
+    AccelRange::G8,
+    AccelRange::G16,
+];
+const GYRO_RANGES: [GyroRange; 4] = [
+    GyroRange::D250,
+    GyroRange::D500,
+    GyroRange::D1000,
+    GyroRange::D2000,
+];
+const HPFS: [ACCEL_HPF; 6] = [
+    ACCEL_HPF::_RESET,
+    ACCEL_HPF::_5,
+    ACCEL_HPF::_2P5,
+    ACCEL_HPF::_1P25,
+    ACCEL_HPF::_0P63,
+    ACCEL_HPF::_HOLD,
+];
+/// usable clocks, simplest first
+const CLOCKS: [CLKSEL; 6] = [
+    CLKSEL::GXAXIS,
+    CLKSEL::OSCILL,
+    CLKSEL::GYAXIS,
+    CLKSEL::GZAXIS,
+    CLKSEL::EXT_32p7,
+    CLKSEL::EXT_19P2,
+];
+/// None first
+const CYCLES: [Option<LP_WAKE_CTRL>; 5] = [
+    None,
+    Some(LP_WAKE_CTRL::_1P25),
+    Some(LP_WAKE_CTRL::_2P5),
+    Some(LP_WAKE_CTRL::_5),
+    Some(LP_WAKE_CTRL::_10),
+];
+
+/// raw frame behind the scaled output check: accel, temp, gyro counts
+const ACC_COUNTS: [i16; 3] = [4096, -2048, 1000];
+const GYRO_COUNTS: [i16; 3] = [131, -262, 3000];
+
+fn env_or<T: std::str::FromStr>(name: &str, default: T) -> T {
+    std::env::var(name)
+        .ok()
+        .and_then(|value| value.parse().ok())
+        .unwrap_or(default)
+}
+
+/// xorshift64*
+struct Rng(u64);
+
+impl Rng {
+    fn below(&mut self, n: usize) -> usize {
+        self.0 ^= self.0 >> 12;
+        self.0 ^= self.0 << 25;
+        self.0 ^= self.0 >> 27;
+        (self.0.wrapping_mul(0x2545_f491_4f6c_dd1d) >> 32) as usize % n
+    }
+
+    fn pick<T: Copy>(&mut self, values: &[T]) -> T {
+        values[self.below(values.len())]
+    }
+}
+
+/// Valid settings, drawn until `validate` accepts them
+fn arbitrary(rng: &mut Rng) -> Mpu6050Settings {
+    loop {
+        // dividers at the edges are drawn more often than their share
+        let div = match rng.below(4) {
+            0 => rng.pick(&[0, 1, 254, 255]),
+            _ => rng.below(256) as u8,
+        };
+        let settings = Mpu6050Settings::new()
+            .with_accel_range(rng.pick(&ACCEL_RANGES))
+            .with_gyro_range(rng.pick(&GYRO_RANGES))
+            .with_dlpf_cfg(rng.below(7) as u8)
+            .with_sample_rate_div(div)
+            .with_accel_hpf(rng.pick(&HPFS))
+            .with_clock_source(rng.pick(&CLOCKS))
+            .with_cycle(rng.pick(&CYCLES));
+        if settings.validate().is_ok() {
+            return settings;
+        }
+    }
+}
+
+/// values before `current` in `values`, simplest first
+fn simpler<T: Copy + PartialEq>(values: &[T], current: T) -> Vec<T> {
+    let index = values.iter().position(|v| *v == current).unwrap_or(0);
+    values[..index].to_vec()
+}
+
+/// Valid settings one step simpler than `s`, in one field each
+fn shrink_candidates(s: &Mpu6050Settings) -> Vec<Mpu6050Settings> {
+    let mut out = Vec::new();
+    out.extend(
+        simpler(&CYCLES, s.cycle)
+            .into_iter()
+            .map(|v| s.with_cycle(v)),
+    );
+    out.extend(
+        simpler(&ACCEL_RANGES, s.accel_range)
+            .into_iter()
+            .map(|v| s.with_accel_range(v)),
+    );
+    out.extend(
+        simpler(&GYRO_RANGES, s.gyro_range)
+            .into_iter()
+            .map(|v| s.with_gyro_range(v)),
+    );
+    out.extend((0..s.dlpf_cfg).map(|v| s.with_dlpf_cfg(v)));
+    let div = s.sample_rate_div;
+    if div > 0 {
+        out.extend([0, div / 2, div - 1].map(|v| s.with_sample_rate_div(v)));
+    }
+    out.extend(
+        simpler(&HPFS, s.accel_hpf)
+            .into_iter()
+            .map(|v| s.with_accel_hpf(v)),
+    );
+    out.extend(
+        simpler(&CLOCKS, s.clock_source)
+            .into_iter()
+            .map(|v| s.with_clock_source(v)),
+    );
+    out.retain(|candidate| candidate != s && candidate.validate().is_ok());
+    out
+}
+
+/// Greedy shrinking: take the first simpler candidate that still fails until none does
+fn shrink<F>(mut failing: Mpu6050Settings, fails: F) -> Mpu6050Settings
+where
+    F: Fn(&Mpu6050Settings) -> bool,
+{
+    while let Some(simpler) = shrink_candidates(&failing)
+        .into_iter()
+        .find(|candidate| fails(candidate))
+    {
+        failing = simpler;
+    }
+    failing
+}
+
+/// sensitivities from the datasheet, independent of the driver's tables
+fn acc_lsb_per_g(range: AccelRange) -> f32 {
+    match range {
+        AccelRange::G2 => 16384.,
+        AccelRange::G4 => 8192.,
+        AccelRange::G8 => 4096.,
+        AccelRange::G16 => 2048.,
+    }
+}
+
+fn gyro_lsb_per_dps(range: GyroRange) -> f32 {
+    match range {
+        GyroRange::D250 => 131.,
+        GyroRange::D500 => 65.5,
+        GyroRange::D1000 => 32.8,
+        GyroRange::D2000 => 16.4,
+    }
+}
+
+fn frame() -> [u8; 14] {
+    let mut frame = [0; 14];
+    for (i, count) in ACC_COUNTS.iter().enumerate() {
+        frame[2 * i..2 * i + 2].copy_from_slice(&count.to_be_bytes());
+    }
+    for (i, count) in GYRO_COUNTS.iter().enumerate() {
+        frame[8 + 2 * i..10 + 2 * i].copy_from_slice(&count.to_be_bytes());
+    }
+    frame
+}
+
+/// Applies `settings` to a freshly initialized driver and checks:
+/// * every write lands in a register described in [`REGISTERS`]
+/// * reading the settings back from the device gives `settings`
+/// * settings reconstructed from a register snapshot give `settings`
+/// * the cached resolution follows the applied ranges and DLPF
+/// * values derived before are stale and refused, derived after they are current
+/// * scaled output of a fixed raw frame follows the applied sensitivities
+fn check(settings: &Mpu6050Settings) -> Result<(), String> {
+    let bus = SharedBus::new(&[DEFAULT_SLAVE_ADDR]);
+    let (i2c, trace) = TracingI2c::new(bus.clone());
+    let mut mpu = Mpu6050Builder::new().i2c(i2c).build().unwrap();
+    mpu.init(&mut NoDelay)
+        .map_err(|e| format!("init: {:?}", e))?;
+    trace.clear();
+    let epoch = mpu.config_epoch();
+    let resolution = mpu.tracked(Mpu6050::current_resolution);
+    let schema = mpu.fifo_schema();
+    mpu.apply_settings(settings)
+        .map_err(|e| format!("apply_settings: {:?}", e))?;
+
+    for entry in trace.entries() {
+        if let TraceEntry::Transaction {
+            direction: Direction::Write,
+            reg,
+            ..
+        } = &entry
+        {
+            if reg.and_then(register_info).is_none() {
+                return Err(format!("write outside the described registers: {}", entry));
+            }
+        }
+    }
+
+    let read_back = mpu
+        .read_settings()
+        .map_err(|e| format!("read_settings: {:?}", e))?;
+    if read_back != *settings {
+        return Err(format!("read back {:?}", read_back));
+    }
+    let config = mpu
+        .read_config()
+        .map_err(|e| format!("read_config: {:?}", e))?;
+    match Mpu6050Settings::from_config(&config) {
+        Ok(adopted) if adopted == *settings => {}
+        other => return Err(format!("adopted from snapshot: {:?}", other)),
+    }
+    if mpu.current_resolution() != settings.resolution() {
+        return Err(format!("cached resolution {:?}", mpu.current_resolution()));
+    }
+    if schema.is_current(&mpu) {
+        return Err("FIFO schema of the init configuration still current".into());
+    }
+    match resolution.get::<_, _, ()>(&mpu) {
+        Err(Mpu6050Error::StaleConfiguration { captured, current })
+            if captured == epoch && current == mpu.config_epoch() => {}
+        other => return Err(format!("resolution of the init configuration: {:?}", other)),
+    }
+    let resolution = mpu.tracked(Mpu6050::current_resolution);
+    if resolution.get::<_, _, ()>(&mpu).ok() != Some(&settings.resolution()) {
+        return Err(format!("tracked resolution {:?}", resolution));
+    }
+
+    bus.device(DEFAULT_SLAVE_ADDR, |device| device.set_frame(&frame()));
+    let acc = mpu.get_acc().map_err(|e| format!("get_acc: {:?}", e))?;
+    let gyro = mpu.get_gyro().map_err(|e| format!("get_gyro: {:?}", e))?;
+    let expected_acc = Vec3A::from(ACC_COUNTS.map(f32::from)) / acc_lsb_per_g(settings.accel_range);
+    let expected_gyro =
+        Vec3A::from(GYRO_COUNTS.map(f32::from)) / gyro_lsb_per_dps(settings.gyro_range) * PI_180;
+    if (acc - expected_acc).abs().max_element() > 1e-5 * expected_acc.abs().max_element() {
+        return Err(format!("acc {} expected {}", acc, expected_acc));
+    }
+    if (gyro - expected_gyro).abs().max_element() > 1e-5 * expected_gyro.abs().max_element() {
+        return Err(format!("gyro {} expected {}", gyro, expected_gyro));
+    }
+    Ok(())
+}
+
+/// Applies `from`, then the diff to `to`, and checks that the epoch advances once per
+/// changed range, DLPF and divider, in the order written, and not for the other fields
+fn check_diff(from: &Mpu6050Settings, to: &Mpu6050Settings) -> Result<(), String> {
+    let bus = SharedBus::new(&[DEFAULT_SLAVE_ADDR]);
+    let mut mpu = Mpu6050Builder::new().i2c(bus).build().unwrap();
+    mpu.init(&mut NoDelay)
+        .map_err(|e| format!("init: {:?}", e))?;
+    mpu.apply_settings(from)
+        .map_err(|e| format!("apply_settings: {:?}", e))?;
+    let epoch = mpu.config_epoch();
+    let last = mpu.last_config_change();
+    mpu.apply_settings_diff(from, to)
+        .map_err(|e| format!("apply_settings_diff: {:?}", e))?;
+
+    let changes: Vec<ConfigChange> = [
+        (from.accel_range != to.accel_range, ConfigChange::AccelRange),
+        (from.gyro_range != to.gyro_range, ConfigChange::GyroRange),
+        (from.dlpf_cfg != to.dlpf_cfg, ConfigChange::Dlpf),
+        (
+            from.sample_rate_div != to.sample_rate_div,
+            ConfigChange::SampleRateDivider,
+        ),
+    ]
+    .into_iter()
+    .filter_map(|(changed, change)| changed.then_some(change))
+    .collect();
+    let advanced = mpu.config_epoch().wrapping_sub(epoch);
+    if advanced != changes.len() as u32 {
+        return Err(format!("epoch advanced {} for {:?}", advanced, changes));
+    }
+    let expected = changes.last().copied().or(last);
+    if mpu.last_config_change() != expected {
+        return Err(format!("last change {:?}", mpu.last_config_change()));
+    }
+    Ok(())
+}
+
+#[test]
+fn random_valid_settings_round_trip() {
+    let cases = env_or("SETTINGS_FUZZ_CASES", DEFAULT_CASES);
+    let seed = env_or("SETTINGS_FUZZ_SEED", DEFAULT_SEED);
+    let mut rng = Rng(seed | 1);
+    for case in 0..cases {
+        let settings = arbitrary(&mut rng);
+        if let Err(error) = check(&settings) {
+            let minimal = shrink(settings, |s| check(s).is_err());
+            panic!(
+                "case {} (seed {:#x}): {}\noriginal {:?}\nminimal {:?}: {}",
+                case,
+                seed,
+                error,
+                settings,
+                minimal,
+                check(&minimal).unwrap_err()
+            );
+        }
+    }
+}
+
+#[test]
+fn settings_diffs_advance_the_epoch() {
+    let cases = env_or("SETTINGS_FUZZ_CASES", DEFAULT_CASES);
+    let seed = env_or("SETTINGS_FUZZ_SEED", DEFAULT_SEED);
+    let mut rng = Rng(seed.rotate_left(17) | 1);
+    for case in 0..cases {
+        let from = arbitrary(&mut rng);
+        let to = arbitrary(&mut rng);
+        for to in [to, from, from.with_accel_hpf(to.accel_hpf)] {
+            if let Err(error) = check_diff(&from, &to) {
+                let minimal = shrink(to, |t| check_diff(&from, t).is_err());
+                panic!(
+                    "case {} (seed {:#x}): {}\nfrom {:?}\nto {:?}\nminimal {:?}: {}",
+                    case,
+                    seed,
+                    error,
+                    from,
+                    to,
+                    minimal,
+                    check_diff(&from, &minimal).unwrap_err()
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn presets_round_trip() {
+    use mpu6050::presets::*;
+    for preset in [
+        DRONE_RATE_CONTROL,
+        HANDHELD_UI,
+        VIBRATION_LOGGING,
+        LOW_POWER_TILT,
+    ] {
+        check(&preset).unwrap();
+    }
+}
+
+#[test]
+fn shrinking_finds_the_minimal_failing_settings() {
+    // stand-in bug: cycle mode combined with a divider of 100 or more
+    let fails = |s: &Mpu6050Settings| s.cycle.is_some() && s.sample_rate_div >= 100;
+    let failing = Mpu6050Settings::new()
+        .with_accel_range(AccelRange::G16)
+        .with_gyro_range(GyroRange::D1000)
+        .with_dlpf_cfg(5)
+        .with_sample_rate_div(231)
+        .with_accel_hpf(ACCEL_HPF::_HOLD)
+        .with_clock_source(CLKSEL::EXT_19P2)
+        .with_cycle(Some(LP_WAKE_CTRL::_10));
+
+    let minimal = shrink(failing, fails);
+    assert_eq!(
+        minimal,
+        Mpu6050Settings::new()
+            .with_sample_rate_div(100)
+            .with_clock_source(CLKSEL::OSCILL)
+            .with_cycle(Some(LP_WAKE_CTRL::_1P25))
+    );
+}
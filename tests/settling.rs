@@ -0,0 +1,179 @@
+//! Settling countdowns after the operations disturbing the signal path, and what the policies
+//! do with the samples: discarding costs one transaction per retry, flagging none, steady
+//! state reads are untouched. See the `settling` module, the driver reads a mock register
+//! file.
+
+mod common;
+
+use mpu6050::device::{
+    AccelRange, GyroRange, SettleTrigger, ACCEL_HPF, DEFAULT_SLAVE_ADDR, SETTLE_SAMPLES,
+};
+use mpu6050::settling::{SettleCountdown, SettlingPolicy};
+use mpu6050::{Mpu6050, Mpu6050Builder};
+
+use common::{NoDelay, SharedBus};
+
+fn settled(policy: SettlingPolicy) -> (SharedBus, Mpu6050<SharedBus>) {
+    let bus = SharedBus::new(&[DEFAULT_SLAVE_ADDR]);
+    let mut mpu = Mpu6050Builder::new().i2c(bus.clone()).build().unwrap();
+    mpu.set_settling_policy(SettlingPolicy::Ignore);
+    while mpu.settle_countdown().is_settling() {
+        mpu.get_acc().unwrap();
+        mpu.get_gyro().unwrap();
+    }
+    mpu.set_settling_policy(policy);
+    (bus, mpu)
+}
+
+fn countdown((acc, gyro): (u8, u8)) -> SettleCountdown {
+    SettleCountdown { acc, gyro }
+}
+
+#[test]
+fn table_is_indexed_by_trigger() {
+    for (trigger, samples) in [
+        (SettleTrigger::Wake, (2, 3)),
+        (SettleTrigger::Reset, (2, 3)),
+        (SettleTrigger::Dlpf, (2, 2)),
+        (SettleTrigger::AccelHpf, (1, 0)),
+        (SettleTrigger::Range, (1, 1)),
+        (SettleTrigger::SelfTest, (1, 0)),
+        (SettleTrigger::CycleExit, (0, 3)),
+    ] {
+        assert_eq!(trigger.samples(), samples, "{trigger:?}");
+        assert_eq!(SETTLE_SAMPLES[trigger as usize], samples);
+    }
+}
+
+#[test]
+fn countdown_never_shortens() {
+    let mut countdown = SettleCountdown::default();
+    countdown.trigger(SettleTrigger::Wake);
+    countdown.trigger(SettleTrigger::Range);
+    assert_eq!(countdown, SettleCountdown { acc: 2, gyro: 3 });
+    assert!(countdown.consume_acc());
+    assert!(countdown.consume_acc());
+    assert!(!countdown.consume_acc());
+    assert!(countdown.is_settling());
+}
+
+#[test]
+fn every_trigger_operation_arms_its_countdown() {
+    type Op = fn(&mut Mpu6050<SharedBus>);
+    let ops: [(&str, Op, SettleTrigger); 9] = [
+        (
+            "init",
+            |mpu| mpu.init(&mut NoDelay).unwrap(),
+            SettleTrigger::Wake,
+        ),
+        (
+            "wake",
+            |mpu| mpu.set_sleep_enabled(false).unwrap(),
+            SettleTrigger::Wake,
+        ),
+        (
+            "reset",
+            |mpu| mpu.reset_device(&mut NoDelay).unwrap(),
+            SettleTrigger::Reset,
+        ),
+        ("dlpf", |mpu| mpu.set_dlpf(3).unwrap(), SettleTrigger::Dlpf),
+        (
+            "hpf",
+            |mpu| mpu.set_accel_hpf(ACCEL_HPF::_5).unwrap(),
+            SettleTrigger::AccelHpf,
+        ),
+        (
+            "accel range",
+            |mpu| mpu.set_accel_range(AccelRange::G8).unwrap(),
+            SettleTrigger::Range,
+        ),
+        (
+            "gyro range",
+            |mpu| mpu.set_gyro_range(GyroRange::D1000).unwrap(),
+            SettleTrigger::Range,
+        ),
+        (
+            "self-test",
+            |mpu| mpu.set_accel_z_self_test(true).unwrap(),
+            SettleTrigger::SelfTest,
+        ),
+        (
+            "cycle exit",
+            |mpu| {
+                mpu.set_cycle_enabled(false).unwrap();
+            },
+            SettleTrigger::CycleExit,
+        ),
+    ];
+    for (name, op, trigger) in ops {
+        let (_, mut mpu) = settled(SettlingPolicy::Ignore);
+        if name == "cycle exit" {
+            mpu.set_cycle_enabled(true).unwrap();
+            assert!(!mpu.settle_countdown().is_settling());
+        }
+        op(&mut mpu);
+        assert_eq!(
+            mpu.settle_countdown(),
+            countdown(trigger.samples()),
+            "{name}"
+        );
+    }
+}
+
+#[test]
+fn discard_rereads_one_transaction_per_retry() {
+    let (bus, mut mpu) = settled(SettlingPolicy::Discard { max_retries: 3 });
+    mpu.set_dlpf(0).unwrap();
+    bus.take_log();
+    // two settling accel samples: the read and two retries
+    mpu.get_acc().unwrap();
+    assert_eq!(bus.take_log().len(), 3);
+    assert_eq!(mpu.settle_countdown(), SettleCountdown { acc: 0, gyro: 2 });
+
+    // retries run out before the countdown, the last settling sample is returned
+    let (bus, mut mpu) = settled(SettlingPolicy::Discard { max_retries: 1 });
+    mpu.set_sleep_enabled(false).unwrap();
+    bus.take_log();
+    mpu.get_gyro().unwrap();
+    assert_eq!(bus.take_log().len(), 2);
+    assert_eq!(mpu.settle_countdown(), SettleCountdown { acc: 2, gyro: 1 });
+}
+
+#[test]
+fn full_samples_are_flagged_not_reread() {
+    // one burst holds both sensors, get_all flags under every policy
+    let (bus, mut mpu) = settled(SettlingPolicy::Discard { max_retries: 3 });
+    mpu.set_dlpf(0).unwrap();
+    bus.take_log();
+    let flags: Vec<_> = (0..3).map(|_| mpu.get_all().unwrap().settling()).collect();
+    assert_eq!(flags, [true, true, false]);
+    assert_eq!(bus.take_log().len(), 3);
+}
+
+#[test]
+fn flag_marks_samples_without_rereading() {
+    let (bus, mut mpu) = settled(SettlingPolicy::Flag);
+    mpu.set_gyro_range(GyroRange::D500).unwrap();
+    bus.take_log();
+    let flags: Vec<_> = (0..3).map(|_| mpu.get_all().unwrap().settling()).collect();
+    assert_eq!(flags, [true, false, false]);
+    assert_eq!(bus.take_log().len(), 3);
+}
+
+#[test]
+fn steady_state_reads_cost_one_transaction_under_every_policy() {
+    for policy in [
+        SettlingPolicy::Ignore,
+        SettlingPolicy::Flag,
+        SettlingPolicy::Discard { max_retries: 3 },
+    ] {
+        let (bus, mut mpu) = settled(policy);
+        bus.take_log();
+        for _ in 0..4 {
+            mpu.get_acc().unwrap();
+            mpu.get_gyro().unwrap();
+            assert!(!mpu.get_all().unwrap().settling());
+        }
+        assert_eq!(bus.take_log().len(), 12, "{policy:?}");
+    }
+}
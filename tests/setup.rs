@@ -0,0 +1,228 @@
+//! `auto_setup` against a register mock: a clean run, a rejected calibration and bus failures
+//! attributed to the phase they happened in.
+
+mod common;
+
+use embedded_hal::blocking::i2c::{Write, WriteRead};
+use mpu6050::device::{AccelRange, GyroRange, ACCEL_CONFIG, ACC_REGX_H, GYRO_REGX_H};
+use mpu6050::setup::{AutoSetupFailure, AutoSetupOptions, AutoSetupPhase, PhaseStatus};
+use mpu6050::*;
+
+use common::{NoDelay, RegisterMock};
+
+/// A [`RegisterMock`] at rest in the configured accel range, answering 0.5g more per axis
+/// while the self-test bits are set, gyro X alternating between
+/// `±gyro_noise` on every read and every transaction from `fail_from` on failing
+struct SetupChip {
+    mock: RegisterMock,
+    gyro_noise: i16,
+    reads: u32,
+    transactions: usize,
+    fail_from: Option<usize>,
+}
+
+impl SetupChip {
+    fn new() -> Self {
+        Self {
+            mock: RegisterMock::new(),
+            gyro_noise: 0,
+            reads: 0,
+            transactions: 0,
+            fail_from: None,
+        }
+    }
+
+    fn transaction(&mut self) -> Result<(), ()> {
+        self.transactions += 1;
+        match self.fail_from {
+            Some(from) if self.transactions > from => Err(()),
+            _ => Ok(()),
+        }
+    }
+
+    fn refresh(&mut self) {
+        let config = self.mock.regs[ACCEL_CONFIG::ADDR as usize];
+        let lsb_per_g: i16 = 16384 >> ((config >> 3) & 0b11);
+        let offset = if config & 0xe0 == 0xe0 { lsb_per_g / 2 } else { 0 };
+        let acc = [offset, offset, lsb_per_g + offset];
+        for (i, value) in acc.iter().enumerate() {
+            let at = ACC_REGX_H as usize + 2 * i;
+            self.mock.regs[at..at + 2].copy_from_slice(&value.to_be_bytes());
+        }
+        self.reads += 1;
+        let gyro = if self.reads.is_multiple_of(2) {
+            self.gyro_noise
+        } else {
+            -self.gyro_noise
+        };
+        let at = GYRO_REGX_H as usize;
+        self.mock.regs[at..at + 2].copy_from_slice(&gyro.to_be_bytes());
+    }
+}
+
+impl Write for SetupChip {
+    type Error = ();
+
+    fn write(&mut self, address: u8, bytes: &[u8]) -> Result<(), ()> {
+        self.transaction()?;
+        self.mock.write(address, bytes).ok();
+        Ok(())
+    }
+}
+
+impl WriteRead for SetupChip {
+    type Error = ();
+
+    fn write_read(&mut self, address: u8, bytes: &[u8], buffer: &mut [u8]) -> Result<(), ()> {
+        self.transaction()?;
+        self.refresh();
+        self.mock.write_read(address, bytes, buffer).ok();
+        Ok(())
+    }
+}
+
+fn options() -> AutoSetupOptions {
+    AutoSetupOptions {
+        accel_range: AccelRange::G8,
+        gyro_range: GyroRange::D500,
+        device_is_level: true,
+        gyro_samples: 50,
+        acc_samples: 50,
+        ..AutoSetupOptions::default()
+    }
+}
+
+fn mpu(chip: SetupChip) -> Mpu6050<SetupChip> {
+    Mpu6050Builder::new().i2c(chip).build().unwrap()
+}
+
+#[test]
+fn clean_run_completes_every_phase_and_applies_the_ranges() {
+    let mut mpu = mpu(SetupChip::new());
+    let mut phases = Vec::new();
+    let mut progress = |phase| phases.push(phase);
+    let report = mpu
+        .auto_setup(&mut NoDelay, options(), Some(&mut progress))
+        .unwrap();
+
+    assert_eq!(phases, AutoSetupPhase::ALL);
+    assert_eq!(
+        report.phase(AutoSetupPhase::ThermalWait).status,
+        PhaseStatus::Skipped
+    );
+    for phase in [
+        AutoSetupPhase::Init,
+        AutoSetupPhase::ActuationCheck,
+        AutoSetupPhase::GyroCalibration,
+        AutoSetupPhase::AccelCalibration,
+        AutoSetupPhase::ApplyConfiguration,
+    ] {
+        assert_eq!(
+            report.phase(phase).status,
+            PhaseStatus::Completed,
+            "{phase:?}"
+        );
+    }
+    assert_eq!(report.gyro_std_dev, Some(0.));
+    assert_eq!(report.acc_std_dev, Some(0.));
+    assert_eq!(report.gyro_offset, Vec3A::ZERO);
+    assert!(
+        report.acc_offset.abs().max_element() < 1e-4,
+        "{:?}",
+        report.acc_offset
+    );
+
+    assert_eq!(mpu.get_accel_range().unwrap(), AccelRange::G8);
+    assert_eq!(mpu.get_gyro_range().unwrap(), GyroRange::D500);
+    // self-test bits left cleared
+    assert_eq!(mpu.read_byte(ACCEL_CONFIG::ADDR).unwrap() & 0xe0, 0);
+}
+
+#[test]
+fn noisy_gyro_rejects_the_calibration() {
+    let mut chip = SetupChip::new();
+    // ±200 LSB at ±500°/s is about 0.053 rad/s against the 0.01 allowed
+    chip.gyro_noise = 200;
+    let error = mpu(chip)
+        .auto_setup(&mut NoDelay, options(), None)
+        .unwrap_err();
+
+    assert_eq!(error.phase, AutoSetupPhase::GyroCalibration);
+    let AutoSetupFailure::CalibrationRejected { std_dev, limit } = error.failure else {
+        panic!("{:?}", error.failure);
+    };
+    assert_eq!(limit, options().max_gyro_std_dev);
+    assert!(std_dev > limit, "{std_dev}");
+    assert_eq!(error.report.gyro_std_dev, Some(std_dev));
+    assert_eq!(
+        error.report.phase(AutoSetupPhase::GyroCalibration).status,
+        PhaseStatus::Failed
+    );
+    assert_eq!(
+        error.report.phase(AutoSetupPhase::ActuationCheck).status,
+        PhaseStatus::Completed
+    );
+    assert_eq!(
+        error.report.phase(AutoSetupPhase::AccelCalibration).status,
+        PhaseStatus::NotRun
+    );
+}
+
+#[test]
+fn bus_failure_on_the_first_transaction_fails_init() {
+    let mut chip = SetupChip::new();
+    chip.fail_from = Some(0);
+    let error = mpu(chip)
+        .auto_setup(&mut NoDelay, options(), None)
+        .unwrap_err();
+
+    assert_eq!(error.phase, AutoSetupPhase::Init);
+    assert!(
+        matches!(
+            error.failure,
+            AutoSetupFailure::Device(Mpu6050Error::I2c(()))
+        ),
+        "{:?}",
+        error.failure
+    );
+    assert_eq!(
+        error.report.phase(AutoSetupPhase::Init).status,
+        PhaseStatus::Failed
+    );
+    for phase in &AutoSetupPhase::ALL[1..] {
+        assert_eq!(
+            error.report.phase(*phase).status,
+            PhaseStatus::NotRun,
+            "{phase:?}"
+        );
+    }
+}
+
+#[test]
+fn bus_failure_after_init_is_attributed_to_the_running_phase() {
+    // count the transactions of a clean init and range setup, then fail right after them
+    let mut init = mpu(SetupChip::new());
+    init.init(&mut NoDelay).unwrap();
+    init.set_accel_range(options().accel_range).unwrap();
+    init.set_gyro_range(options().gyro_range).unwrap();
+    let mut chip = SetupChip::new();
+    chip.fail_from = Some(init.io_stats().transactions as usize);
+
+    let error = mpu(chip)
+        .auto_setup(&mut NoDelay, options(), None)
+        .unwrap_err();
+
+    assert_eq!(error.phase, AutoSetupPhase::ActuationCheck);
+    assert!(matches!(
+        error.failure,
+        AutoSetupFailure::Device(Mpu6050Error::I2c(()))
+    ));
+    assert_eq!(
+        error.report.phase(AutoSetupPhase::Init).status,
+        PhaseStatus::Completed
+    );
+    assert_eq!(
+        error.report.phase(AutoSetupPhase::ActuationCheck).status,
+        PhaseStatus::Failed
+    );
+}
@@ -0,0 +1,246 @@
+//! Coordinated shutdown: the read-backs against the mock's registers, a stuck register and a
+//! full FIFO failing verification, the best-effort mode past bus errors, and the later
+//! re-check, see the `shutdown` module. The sequence itself is a golden trace in `trace`.
+
+mod common;
+
+use embedded_hal::blocking::delay::DelayMs;
+
+use mpu6050::chaos::{ChaosConfig, ChaosHandle, FaultKind, FlakyError, FlakyI2c};
+use mpu6050::device::DEFAULT_SLAVE_ADDR;
+use mpu6050::fifo::FifoSources;
+use mpu6050::register::Register;
+use mpu6050::shutdown::*;
+use mpu6050::*;
+
+use common::{Access, NoDelay, SharedBus};
+
+const ADDR: u8 = DEFAULT_SLAVE_ADDR;
+
+type FlakyMpu = Mpu6050<FlakyI2c<SharedBus>>;
+
+/// delay recording the waits
+#[derive(Default)]
+struct Waits(Vec<u8>);
+
+impl DelayMs<u8> for Waits {
+    fn delay_ms(&mut self, ms: u8) {
+        self.0.push(ms);
+    }
+}
+
+fn driver() -> (Mpu6050<SharedBus>, SharedBus) {
+    let bus = SharedBus::new(&[ADDR]);
+    let mut mpu = Mpu6050Builder::new().i2c(bus.clone()).build().unwrap();
+    mpu.init(&mut NoDelay).unwrap();
+    (mpu, bus)
+}
+
+fn flaky() -> (FlakyMpu, SharedBus, ChaosHandle) {
+    let bus = SharedBus::new(&[ADDR]);
+    let (i2c, chaos) = FlakyI2c::new(bus.clone(), ChaosConfig::NONE);
+    let mut mpu = Mpu6050Builder::new().i2c(i2c).build().unwrap();
+    mpu.init(&mut NoDelay).unwrap();
+    bus.take_log();
+    (mpu, bus, chaos)
+}
+
+/// a running driver: interrupts, FIFO, aux master and bypass on
+fn streaming(mpu: &mut Mpu6050<SharedBus>, bus: &SharedBus) {
+    mpu.write_register(Register::INT_ENABLE, 0x51).unwrap();
+    mpu.set_fifo_sources(FifoSources::NONE.with_accel(true).with_gyro(true))
+        .unwrap();
+    mpu.set_fifo_enabled(true).unwrap();
+    mpu.set_i2c_master_enabled(true).unwrap();
+    mpu.write_register_bit(Register::INT_PIN_CFG, 1, true)
+        .unwrap();
+    mpu.write_register(Register::PWR_MGMT_1, 0x21).unwrap();
+    bus.take_log();
+}
+
+fn reg(bus: &SharedBus, reg: Register) -> u8 {
+    bus.device(ADDR, |m| m.regs[reg.addr() as usize])
+}
+
+fn failed(evidence: &ShutdownEvidence) -> Vec<ShutdownItem> {
+    evidence.failures().map(|check| check.item).collect()
+}
+
+#[test]
+fn sequence_is_certified_by_reads_after_the_writes() {
+    let (mut mpu, bus) = driver();
+    streaming(&mut mpu, &bus);
+    let epoch = mpu.config_epoch();
+    let mut waits = Waits::default();
+    let evidence = mpu.shutdown(&mut waits).unwrap();
+    assert!(evidence.verified(), "{}", evidence);
+    assert_eq!(failed(&evidence), []);
+    assert_eq!(waits.0, [SHUTDOWN_SETTLE_MS]);
+
+    // the chip's registers, not the driver's idea of them
+    assert_eq!(reg(&bus, Register::INT_ENABLE), 0);
+    assert_eq!(reg(&bus, Register::FIFO_EN), 0);
+    assert_eq!(reg(&bus, Register::USER_CTRL) & 0x60, 0);
+    assert_eq!(reg(&bus, Register::INT_PIN_CFG) & 0x02, 0);
+    assert_eq!(reg(&bus, Register::PWR_MGMT_2) & 0x3f, 0x3f);
+    // SLEEP set, CYCLE cleared, the clock source kept
+    assert_eq!(reg(&bus, Register::PWR_MGMT_1), 0x41);
+
+    // the read-backs are the last transactions, one read per item, after every write
+    let log = bus.take_log();
+    let (writes, reads) = log.split_at(log.len() - SHUTDOWN_STEPS);
+    let expected: Vec<Access> = ShutdownItem::ALL
+        .iter()
+        .map(|item| Access {
+            address: ADDR,
+            reg: item.register().addr(),
+            read: true,
+        })
+        .collect();
+    assert_eq!(reads, expected.as_slice());
+    assert!(writes.iter().any(|access| !access.read));
+    for (check, item) in evidence.checks().iter().zip(ShutdownItem::ALL) {
+        assert_eq!(check.item, item);
+        assert_eq!(evidence.check(item), check);
+        assert_eq!(check.register, item.register());
+        assert_eq!(check.written, Some(true));
+        assert!(check.passed());
+    }
+    assert_eq!(evidence.check(ShutdownItem::FifoEmpty).observed, Some(0));
+    assert_eq!(evidence.check(ShutdownItem::Asleep).observed, Some(0x41));
+
+    // the driver follows
+    assert!(mpu.power_state().asleep);
+    assert!(!mpu.fifo_streaming());
+    assert_eq!(mpu.get_fifo_sources(), FifoSources::NONE);
+    assert!(mpu.config_epoch() > epoch);
+
+    let report = evidence.to_string();
+    assert!(report.starts_with("shutdown verified\n"), "{}", report);
+    assert_eq!(report.lines().count(), 1 + SHUTDOWN_STEPS);
+    assert!(report.contains("asleep, PWR_MGMT_1 (0x6b): expected 0x40 of 0x60, observed 0x41, ok"));
+}
+
+#[test]
+fn stuck_register_and_full_fifo_fail_verification() {
+    let (mut mpu, bus) = driver();
+    streaming(&mut mpu, &bus);
+    // PWR_MGMT_1 drops writes, and the mock does not empty the FIFO on FIFO_RESET
+    bus.device(ADDR, |m| {
+        m.read_only.push(Register::PWR_MGMT_1.addr());
+        m.fifo.extend([0; 12]);
+    });
+    let evidence = mpu.shutdown(&mut NoDelay).unwrap();
+    assert!(!evidence.verified());
+    assert_eq!(
+        failed(&evidence),
+        [ShutdownItem::FifoEmpty, ShutdownItem::Asleep]
+    );
+    let asleep = evidence.check(ShutdownItem::Asleep);
+    // written as far as the bus knows, observed as the chip has it
+    assert_eq!(asleep.written, Some(true));
+    assert_eq!(asleep.observed, Some(0x21));
+    assert_eq!(evidence.check(ShutdownItem::FifoEmpty).observed, Some(12));
+
+    let report = evidence.to_string();
+    assert!(report.starts_with("shutdown not verified, 2 of 7 failed\n"));
+    assert!(
+        report.contains("asleep, PWR_MGMT_1 (0x6b): expected 0x40 of 0x60, observed 0x21, FAILED")
+    );
+    assert!(report.contains(
+        "FIFO empty, FIFO_COUNTH (0x72): expected 0x0000 of 0xffff, observed 0x000c, FAILED"
+    ));
+}
+
+#[test]
+fn best_effort_completes_past_bus_errors() {
+    // the strict sequence stops at the first error
+    let (mut mpu, bus, chaos) = flaky();
+    chaos.set_config(ChaosConfig::NONE.with_fault(FaultKind::Nak));
+    chaos.fail_next(1);
+    assert!(matches!(
+        mpu.shutdown(&mut NoDelay),
+        Err(Mpu6050Error::I2c(FlakyError::Injected(FaultKind::Nak)))
+    ));
+    assert_eq!(bus.take_log(), []);
+    assert_eq!(reg(&bus, Register::PWR_MGMT_1) & 0x40, 0);
+
+    // the interrupt write fails, everything else is done and verified
+    chaos.fail_next(1);
+    let evidence = mpu.shutdown_best_effort(&mut NoDelay);
+    assert_eq!(failed(&evidence), [ShutdownItem::InterruptsDisabled]);
+    let interrupts = evidence.check(ShutdownItem::InterruptsDisabled);
+    assert_eq!(interrupts.written, Some(false));
+    // INT_ENABLE is clear after init, the write failing makes the check fail all the same
+    assert_eq!(interrupts.observed, Some(0));
+    assert!(evidence.to_string().contains("write failed, FAILED"));
+    assert_eq!(reg(&bus, Register::PWR_MGMT_1) & 0x40, 0x40);
+
+    // the read-back of the last item fails: everything written, one check not observed
+    let (mut mpu, _bus, chaos) = flaky();
+    // twelve transactions for the writes, the seventh read is the nineteenth
+    chaos.reset_stats();
+    chaos.set_config(ChaosConfig::NONE.with_fail_every(19));
+    let evidence = mpu.shutdown_best_effort(&mut NoDelay);
+    let asleep = evidence.check(ShutdownItem::Asleep);
+    assert_eq!((asleep.written, asleep.observed), (Some(true), None));
+    assert_eq!(failed(&evidence), [ShutdownItem::Asleep]);
+    assert!(evidence.to_string().contains("not read, FAILED"));
+
+    // an unplugged chip: nothing written, nothing observed, no error
+    let (mut mpu, _bus, chaos) = flaky();
+    chaos.fail_next(100);
+    let evidence = mpu.shutdown_best_effort(&mut NoDelay);
+    assert_eq!(failed(&evidence), ShutdownItem::ALL);
+    for check in evidence.checks() {
+        assert_eq!((check.written, check.observed), (Some(false), None));
+    }
+    assert!(evidence
+        .to_string()
+        .starts_with("shutdown not verified, 7 of 7 failed"));
+}
+
+#[test]
+fn verify_rechecks_without_writing() {
+    let (mut mpu, bus) = driver();
+    streaming(&mut mpu, &bus);
+    mpu.shutdown(&mut NoDelay).unwrap();
+    bus.take_log();
+
+    let evidence = mpu.verify_shutdown_state().unwrap();
+    assert!(evidence.verified());
+    assert!(evidence
+        .checks()
+        .iter()
+        .all(|check| check.written.is_none()));
+    let log = bus.take_log();
+    assert_eq!(log.len(), SHUTDOWN_STEPS);
+    assert!(log.iter().all(|access| access.read));
+
+    // something woke the chip behind the driver's back
+    bus.device(ADDR, |m| {
+        m.regs[Register::PWR_MGMT_1.addr() as usize] &= !0x40;
+        m.regs[Register::INT_ENABLE.addr() as usize] = 0x01;
+    });
+    let evidence = mpu.verify_shutdown_state().unwrap();
+    assert_eq!(
+        failed(&evidence),
+        [ShutdownItem::InterruptsDisabled, ShutdownItem::Asleep]
+    );
+    assert!(mpu.power_state().asleep);
+
+    // the owned delay flavours
+    let bus = SharedBus::new(&[ADDR]);
+    let mut owning = Mpu6050Builder::new()
+        .i2c(bus.clone())
+        .delay(Waits::default())
+        .build()
+        .unwrap();
+    owning.init().unwrap();
+    assert!(owning.shutdown().unwrap().verified());
+    assert!(owning.shutdown_best_effort().verified());
+    assert_eq!(
+        owning.delay_mut().unwrap().0,
+        [100, SHUTDOWN_SETTLE_MS, SHUTDOWN_SETTLE_MS]
+    );
+}
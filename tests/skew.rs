@@ -0,0 +1,281 @@
+//! Intra-sample skew: the extrapolation against sinusoids with known answers, the corrector's
+//! tagging, and the driver's sample path with its rate limit, see the `skew` module.
+
+mod common;
+
+use mpu6050::device::*;
+use mpu6050::provenance::{FieldGroup, Provenance, SampleProvenance};
+use mpu6050::sampling::SampleControl;
+use mpu6050::settings::Mpu6050Settings;
+use mpu6050::skew::*;
+use mpu6050::synthetic::frame_bytes;
+use mpu6050::*;
+
+use common::{NoDelay, SharedBus};
+
+/// A sin(ω t) at `t_us`
+fn sine(amplitude: f32, omega: f32, t_us: f32) -> f32 {
+    amplitude * (omega * t_us * 1e-6).sin()
+}
+
+#[test]
+fn extrapolation_matches_sinusoids() {
+    let interval_us = 1000.;
+    for (hz, shift_us) in [(5., 480.), (40., -200.), (120., 980.), (120., -400.)] {
+        let omega = 2. * PI * hz;
+        let amplitude = 3.;
+        for k in 1..50 {
+            let t = k as f32 * interval_us;
+            // y in antiphase, z like x
+            let at = |t: f32| {
+                let value = sine(amplitude, omega, t);
+                Vec3A::new(value, -value, value)
+            };
+            let (previous, current) = (at(t - interval_us), at(t));
+            let shifted = extrapolate(previous, current, interval_us, shift_us);
+
+            // the line through both samples
+            let slope =
+                (sine(amplitude, omega, t) - sine(amplitude, omega, t - interval_us)) / interval_us;
+            let line = sine(amplitude, omega, t) + slope * shift_us;
+            assert!((shifted.x - line).abs() < 1e-5, "{} Hz, k {}", hz, k);
+            assert!((shifted.y + line).abs() < 1e-5);
+            assert_eq!(shifted.x, shifted.z);
+
+            // and close to the true value: the remainder of a linear fit is below
+            // A ω² |s| (|s| + dt) / 2
+            let truth = sine(amplitude, omega, t + shift_us);
+            let s = shift_us.abs() * 1e-6;
+            let bound = amplitude * omega * omega * s * (s + interval_us * 1e-6) / 2.;
+            assert!(
+                (shifted.x - truth).abs() <= bound * 1.01 + 1e-6,
+                "{} Hz, k {}: {} vs {}, bound {}",
+                hz,
+                k,
+                shifted.x,
+                truth,
+                bound
+            );
+        }
+    }
+
+    // the shift never exceeds one interval
+    let far = extrapolate(Vec3A::ZERO, Vec3A::ONE, 100., 5000.);
+    assert_eq!(far, Vec3A::splat(2.));
+    assert_eq!(
+        extrapolate(Vec3A::ZERO, Vec3A::ONE, 100., -5000.),
+        Vec3A::ZERO
+    );
+}
+
+#[test]
+fn defaults_and_rate_limit() {
+    assert_eq!(default_skew_us(0, 1000.), 980.);
+    assert_eq!(default_skew_us(0, 8000.), 480.);
+    assert_eq!(default_skew_us(7, 8000.), 480.);
+    assert_eq!(default_skew_us(1, 1000.), -100.);
+    assert_eq!(default_skew_us(3, 1000.), -100.);
+    assert_eq!(default_skew_us(6, 50.), -400.);
+
+    // four samples per period of the gyro passband edge
+    assert!(correction_sensible(0, 8000.));
+    assert!(!correction_sensible(0, 1000.));
+    assert!(correction_sensible(1, 1000.));
+    assert!(!correction_sensible(1, 500.));
+    assert!(correction_sensible(6, 20.));
+    assert!(!correction_sensible(6, 19.));
+
+    let characterized = SkewCorrection::new(SkewReference::Gyro).with_skew_us(250.);
+    assert_eq!(characterized.skew_us, Some(250.));
+    assert_eq!(characterized.with_skew_us(f32::NAN).skew_us, Some(250.));
+}
+
+#[test]
+fn corrector_shifts_one_group_and_tags_it() {
+    let interval_us = 1250.;
+    let skew_us = 480.;
+    let omega = 2. * PI * 30.;
+    let at = |k: u32| {
+        let t = k as f32 * interval_us;
+        MpuSample::new(
+            Vec3A::splat(sine(1., omega, t)),
+            Vec3A::splat(sine(2., omega, t)),
+            25.,
+        )
+    };
+    for reference in [SkewReference::Accel, SkewReference::Gyro] {
+        let mut corrector = SkewCorrector::new(SkewCorrection::new(reference));
+        let first = corrector.correct(at(0), skew_us, interval_us);
+        assert_eq!(first, at(0));
+        for k in 1..20 {
+            let corrected = corrector.correct(at(k), skew_us, interval_us);
+            assert_eq!(corrected.time_reference(), Some(reference));
+            let (previous, current) = (at(k - 1), at(k));
+            match reference {
+                SkewReference::Accel => {
+                    let gyro = extrapolate(previous.gyro(), current.gyro(), interval_us, skew_us);
+                    assert_eq!(corrected.gyro(), gyro);
+                    assert_eq!(corrected.acc(), current.acc());
+                    assert_eq!(
+                        corrected.provenance(),
+                        SampleProvenance::MEASURED.with(FieldGroup::Gyro, Provenance::Interpolated)
+                    );
+                }
+                SkewReference::Gyro => {
+                    let acc = extrapolate(previous.acc(), current.acc(), interval_us, -skew_us);
+                    assert_eq!(corrected.acc(), acc);
+                    assert_eq!(corrected.gyro(), current.gyro());
+                    assert_eq!(
+                        corrected.provenance(),
+                        SampleProvenance::MEASURED.with(FieldGroup::Acc, Provenance::Interpolated)
+                    );
+                }
+            }
+        }
+    }
+
+    // worse tags of either sample survive, the previous one counts too
+    let mut corrector = SkewCorrector::new(SkewCorrection::new(SkewReference::Accel));
+    let held = SampleProvenance::MEASURED.with(FieldGroup::Gyro, Provenance::held(2));
+    corrector.correct(at(0).with_provenance(held), skew_us, interval_us);
+    let corrected = corrector.correct(at(1), skew_us, interval_us);
+    assert_eq!(
+        corrected.provenance().get(FieldGroup::Gyro),
+        Provenance::held(2)
+    );
+
+    corrector.reset();
+    assert_eq!(corrector.correct(at(2), skew_us, interval_us), at(2));
+}
+
+const ADDR: u8 = DEFAULT_SLAVE_ADDR;
+
+/// gyro ramp of 2 °/s per sample on every axis, exact in LSB at ±250 °/s
+fn ramp_frame(k: u32) -> [u8; 14] {
+    frame_bytes(
+        Vec3A::Z,
+        Vec3A::splat(2. * k as f32),
+        AccelRange::G2,
+        GyroRange::D250,
+    )
+}
+
+/// `samples` samples of the sampling loop, the ramp advancing per sample. INT_STATUS reports
+/// an overrun after sample `overrun_at`
+fn sample_ramp(
+    mpu: &mut Mpu6050<SharedBus>,
+    bus: &SharedBus,
+    samples: u32,
+    overrun_at: Option<u32>,
+) -> Vec<MpuSample> {
+    bus.device(ADDR, |m| m.set_frame(&ramp_frame(0)));
+    let mut out = Vec::new();
+    mpu.run_sampling_loop(
+        || Ok::<(), ()>(()),
+        |sample, _| {
+            out.push(sample);
+            let k = out.len() as u32;
+            bus.device(ADDR, |m| {
+                m.set_frame(&ramp_frame(k));
+                let overrun = Some(k - 1) == overrun_at;
+                m.regs[INT_STATUS::ADDR as usize] = (overrun as u8) << INT_STATUS::DATA_RDY_INT;
+            });
+            match k < samples {
+                true => SampleControl::Continue,
+                false => SampleControl::Stop,
+            }
+        },
+    )
+    .unwrap();
+    out
+}
+
+fn driver() -> (Mpu6050<SharedBus>, SharedBus) {
+    let bus = SharedBus::new(&[ADDR]);
+    let mut mpu = Mpu6050Builder::new().i2c(bus.clone()).build().unwrap();
+    mpu.init(&mut NoDelay).unwrap();
+    (mpu, bus)
+}
+
+#[test]
+fn sample_path_corrects_above_the_rate_limit() {
+    let (mut mpu, bus) = driver();
+    let step = 2. * PI_180;
+
+    // 1 kHz, DLPF_CFG 1: skew -100 µs, a tenth of a sample back
+    mpu.set_dlpf(1).unwrap();
+    mpu.set_sample_rate_divider(0).unwrap();
+    assert_eq!(mpu.intra_sample_skew_us(), -100.);
+    assert!(!mpu.skew_correction_active());
+    mpu.set_skew_correction(Some(SkewCorrection::new(SkewReference::Accel)));
+    assert!(mpu.skew_correction_active());
+    assert_eq!(
+        mpu.debug_state().skew_correction,
+        Some(SkewCorrection::new(SkewReference::Accel))
+    );
+
+    let samples = sample_ramp(&mut mpu, &bus, 6, Some(3));
+    for (k, sample) in samples.iter().enumerate() {
+        let measured = step * k as f32;
+        // the first sample and the one after the overrun have no previous sample
+        if k == 0 || k == 4 {
+            assert_eq!(sample.time_reference(), None, "sample {}", k);
+            assert!((sample.gyro().x - measured).abs() < 1e-5);
+            assert!(sample.provenance().is_measured());
+        } else {
+            assert_eq!(sample.time_reference(), Some(SkewReference::Accel));
+            assert!(
+                (sample.gyro().x - (measured - 0.1 * step)).abs() < 1e-5,
+                "sample {}: {}",
+                k,
+                sample.gyro()
+            );
+            assert_eq!(
+                sample.provenance().get(FieldGroup::Gyro),
+                Provenance::Interpolated
+            );
+            assert_eq!(sample.acc(), Vec3A::Z);
+        }
+    }
+
+    // a characterized skew replaces the table
+    mpu.set_skew_correction(Some(
+        SkewCorrection::new(SkewReference::Accel).with_skew_us(500.),
+    ));
+    assert_eq!(mpu.intra_sample_skew_us(), 500.);
+    let samples = sample_ramp(&mut mpu, &bus, 3, None);
+    assert!((samples[2].gyro().x - 2.5 * step).abs() < 1e-5);
+}
+
+#[test]
+fn sample_path_is_uncorrected_below_the_rate_limit_and_in_cycle_mode() {
+    let (mut mpu, bus) = driver();
+    mpu.set_skew_correction(Some(SkewCorrection::new(SkewReference::Accel)));
+
+    // DLPF off at 800 Hz: below four times 256 Hz
+    mpu.set_dlpf(0).unwrap();
+    mpu.set_sample_rate_divider(9).unwrap();
+    assert!(!mpu.skew_correction_active());
+    for (k, sample) in sample_ramp(&mut mpu, &bus, 4, None).iter().enumerate() {
+        assert_eq!(sample.time_reference(), None);
+        assert!((sample.gyro().x - 2. * PI_180 * k as f32).abs() < 1e-5);
+        assert!(sample.provenance().is_measured());
+    }
+
+    // the same at 8 kHz is corrected, 480 µs forward
+    mpu.set_sample_rate_divider(0).unwrap();
+    assert!(mpu.skew_correction_active());
+    assert_eq!(mpu.intra_sample_skew_us(), 480.);
+
+    mpu.apply_settings(
+        &Mpu6050Settings::new()
+            .with_cycle(Some(LP_WAKE_CTRL::_10))
+            .with_clock_source(CLKSEL::OSCILL),
+    )
+    .unwrap();
+    assert!(!mpu.skew_correction_active());
+
+    mpu.set_skew_correction(None);
+    assert_eq!(mpu.skew_correction(), None);
+    assert_eq!(mpu.debug_state().skew_correction, None);
+}
@@ -0,0 +1,175 @@
+//! Per-consumer smoothing: the documented filter responses, views with different specs fed
+//! the same stream, and the single burst read feeding several views, see the `smoothing`
+//! module.
+
+mod common;
+
+use mpu6050::device::*;
+use mpu6050::smoothing::*;
+use mpu6050::synthetic::frame_bytes;
+use mpu6050::*;
+
+use common::{NoDelay, SharedBus};
+
+fn sample(acc: f32, gyro: f32, temp: f32) -> MpuSample {
+    MpuSample::new(Vec3A::splat(acc), Vec3A::splat(gyro), temp)
+}
+
+#[test]
+fn filters_follow_their_documented_responses() {
+    // step of 2 after a start at 0
+    let mut low_pass = LowPassFilter::new(0.25);
+    assert_eq!(low_pass.update(0.), 0.);
+    for k in 1..20 {
+        let expected = 2. * (1. - 0.75f32.powi(k));
+        assert!(
+            (low_pass.update(2.) - expected).abs() < 1e-6,
+            "sample {}",
+            k
+        );
+    }
+    low_pass.reset();
+    assert_eq!(low_pass.value(), None);
+    assert_eq!(low_pass.update(5.), 5.);
+    assert_eq!(LowPassFilter::new(0.).alpha(), f32::EPSILON);
+    assert_eq!(LowPassFilter::new(3.).alpha(), 1.);
+    assert_eq!(LowPassFilter::new(f32::NAN).alpha(), 1.);
+
+    // spikes of 1 and 2 samples through a window of 5
+    let mut median = MedianFilter::<5>::new(5);
+    let inputs = [1., 1., 9., 1., 1., 1., 1., 9., 9., 1., 1., 1.];
+    let outputs: Vec<f32> = inputs.iter().map(|x| median.update(*x)).collect();
+    assert_eq!(outputs, [1.; 12]);
+    // three of five are no spike
+    assert_eq!(median.update(9.), 1.);
+    assert_eq!(median.update(9.), 1.);
+    assert_eq!(median.update(9.), 9.);
+    // the mean of the middle two while filling up
+    let mut median = MedianFilter::<4>::new(4);
+    assert_eq!(median.update(1.), 1.);
+    assert_eq!(median.update(3.), 2.);
+    assert_eq!(median.update(2.), 2.);
+    assert_eq!(median.update(10.), 2.5);
+    assert_eq!(median.update(10.), 6.5);
+    assert_eq!(MedianFilter::<4>::new(9).window(), 4);
+    assert_eq!(MedianFilter::<4>::new(0).window(), 1);
+
+    // NaN passes through and leaves the state alone
+    let mut low_pass = LowPassFilter::new(0.5);
+    low_pass.update(4.);
+    assert!(low_pass.update(f32::NAN).is_nan());
+    assert_eq!(low_pass.update(0.), 2.);
+    let mut median = MedianFilter::<3>::new(3);
+    median.update(4.);
+    assert!(median.update(f32::NAN).is_nan());
+    assert_eq!(median.update(6.), 5.);
+}
+
+#[test]
+fn views_with_different_specs_are_independent() {
+    let display_spec = FilterSpec {
+        acc: Smoothing::LowPass { alpha: 0.5 },
+        gyro: Smoothing::Median { window: 3 },
+        temp: Smoothing::LowPass { alpha: 0.1 },
+    };
+    let mut display = FilteredView::<3>::new(display_spec);
+    let mut control = FilteredView::<3>::new(FilterSpec::RAW);
+    let mut spike_free = FilteredView::<3>::new(FilterSpec {
+        acc: Smoothing::Median { window: 3 },
+        ..FilterSpec::RAW
+    });
+    assert_eq!(display.latest(), None);
+
+    // accel step, a one sample gyro spike, a temperature step
+    let stream = [
+        sample(0., 0., 20.),
+        sample(1., 0., 30.),
+        sample(1., 5., 30.),
+        sample(1., 0., 30.),
+        sample(1., 0., 30.),
+    ];
+    for (k, input) in stream.iter().enumerate() {
+        let smoothed = display.ingest(input);
+        assert_eq!(control.ingest(input), *input);
+        spike_free.ingest(input);
+
+        let k = k as i32;
+        let acc = match k {
+            0 => 0.,
+            _ => 1. - 0.5f32.powi(k),
+        };
+        let temp = match k {
+            0 => 20.,
+            _ => 30. - 10. * 0.9f32.powi(k),
+        };
+        assert!(
+            (smoothed.acc() - Vec3A::splat(acc)).length() < 1e-6,
+            "k {}",
+            k
+        );
+        assert_eq!(smoothed.gyro(), Vec3A::ZERO, "k {}", k);
+        assert!((smoothed.temp() - temp).abs() < 1e-4, "k {}", k);
+    }
+    assert_eq!(display.ingested(), 5);
+    assert_eq!(control.latest(), Some(stream[4]));
+    // the median takes the step two samples late: 0, mean(0, 1), then 1
+    assert_eq!(spike_free.latest().unwrap().acc(), Vec3A::ONE);
+    assert_eq!(spike_free.latest().unwrap().gyro(), Vec3A::ZERO);
+
+    // flags and provenance survive the smoothing
+    let settling = stream[4].with_settling(true);
+    assert!(display.ingest(&settling).settling());
+    assert_eq!(
+        display.latest().unwrap().provenance(),
+        settling.provenance()
+    );
+
+    display.reset();
+    assert_eq!((display.latest(), display.ingested()), (None, 0));
+    assert_eq!(display.spec(), display_spec);
+    assert_eq!(display.ingest(&stream[2]), stream[2]);
+}
+
+#[test]
+fn one_burst_read_feeds_every_view() {
+    let bus = SharedBus::new(&[DEFAULT_SLAVE_ADDR]);
+    let mut mpu = Mpu6050Builder::new().i2c(bus.clone()).build().unwrap();
+    mpu.init(&mut NoDelay).unwrap();
+
+    let mut raw = mpu.filtered_view(FilterSpec::RAW);
+    let mut smooth = mpu.filtered_view(FilterSpec {
+        gyro: Smoothing::LowPass { alpha: 0.5 },
+        ..FilterSpec::RAW
+    });
+    let mut median = mpu.filtered_view(FilterSpec {
+        gyro: Smoothing::Median { window: 3 },
+        ..FilterSpec::RAW
+    });
+    let rates = [0., 100., 100.];
+    for (k, rate) in rates.iter().enumerate() {
+        let bytes = frame_bytes(
+            Vec3A::Z,
+            Vec3A::splat(*rate),
+            AccelRange::G2,
+            GyroRange::D250,
+        );
+        bus.device(DEFAULT_SLAVE_ADDR, |m| m.set_frame(&bytes));
+        let before = mpu.io_stats().transactions;
+        let sample = mpu
+            .read_into_views(&mut [&mut raw, &mut smooth, &mut median])
+            .unwrap();
+        assert_eq!(mpu.io_stats().transactions - before, 1, "read {}", k);
+        // the samples after init settle, flagged instead of read again
+        assert!(sample.settling());
+        assert_eq!(raw.latest(), Some(sample));
+        assert!((sample.acc() - Vec3A::Z).length() < 1e-3);
+    }
+    let rate = 100. * PI_180;
+    assert!((raw.latest().unwrap().gyro().x - rate).abs() < 1e-3);
+    assert!((smooth.latest().unwrap().gyro().x - 0.75 * rate).abs() < 1e-3);
+    assert!((median.latest().unwrap().gyro().x - rate).abs() < 1e-3);
+    assert_eq!(smooth.ingested(), 3);
+
+    // the driver's own reads stay raw
+    assert!((mpu.get_gyro().unwrap().x - rate).abs() < 1e-3);
+}
@@ -0,0 +1,237 @@
+//! Spectrum analyzer against a reference DFT, see the `spectrum` module.
+//!
+//! The reference is a direct DFT in f64 of the same mean removed, Hann windowed samples with
+//! the same amplitude scaling. The FFT runs in f32: every bin agrees within 1e-5 g plus 1e-5
+//! of the total input amplitude.
+
+mod common;
+
+use std::f64::consts::PI;
+
+use mpu6050::device::{ACC_REGX_H, DEFAULT_SLAVE_ADDR};
+use mpu6050::spectrum::{Peak, SpectrumAnalyzer, SpectrumSource};
+use mpu6050::*;
+
+use common::{NoDelay, SharedBus};
+
+const RATE: f32 = 1000.;
+
+/// `(amplitude, frequency in bins)` tones plus `offset`
+fn tones<const N: usize>(offset: f64, tones: &[(f64, f64)]) -> [f32; N] {
+    let mut samples = [0.; N];
+    for (n, sample) in samples.iter_mut().enumerate() {
+        let t = n as f64 / N as f64;
+        let tone: f64 = tones
+            .iter()
+            .map(|(a, bins)| a * (2. * PI * bins * t + 0.3).cos())
+            .sum();
+        *sample = (offset + tone) as f32;
+    }
+    samples
+}
+
+fn reference_dft(samples: &[f32]) -> Vec<f64> {
+    let n = samples.len();
+    let mean = samples.iter().map(|s| *s as f64).sum::<f64>() / n as f64;
+    let windowed: Vec<f64> = samples
+        .iter()
+        .enumerate()
+        .map(|(i, s)| (*s as f64 - mean) * (0.5 - 0.5 * (2. * PI * i as f64 / n as f64).cos()))
+        .collect();
+    (0..=n / 2)
+        .map(|k| {
+            let (re, im) = windowed
+                .iter()
+                .enumerate()
+                .fold((0., 0.), |(re, im), (i, x)| {
+                    let phase = -2. * PI * (k * i) as f64 / n as f64;
+                    (re + x * phase.cos(), im + x * phase.sin())
+                });
+            let scale = if k == 0 || k == n / 2 { 2. } else { 4. };
+            (re * re + im * im).sqrt() * scale / n as f64
+        })
+        .collect()
+}
+
+fn analyze<const N: usize>(samples: &[f32; N]) -> SpectrumAnalyzer<N> {
+    let mut analyzer = SpectrumAnalyzer::<N>::new(SpectrumSource::AccelX);
+    for sample in samples {
+        analyzer.push(*sample);
+    }
+    assert!(analyzer.compute().is_some());
+    analyzer
+}
+
+fn check_reference<const N: usize>(samples: &[f32; N], total_amplitude: f64) {
+    let analyzer = analyze(samples);
+    let reference = reference_dft(samples);
+    let magnitudes = analyzer.magnitudes();
+    assert_eq!(magnitudes.len(), N / 2 + 1);
+    let tolerance = 1e-5 + 1e-5 * total_amplitude;
+    for (bin, (fft, dft)) in magnitudes.iter().zip(&reference).enumerate() {
+        assert!(
+            (*fft as f64 - dft).abs() < tolerance,
+            "N {} bin {}: {} vs {}",
+            N,
+            bin,
+            fft,
+            dft
+        );
+    }
+}
+
+#[test]
+fn single_tones_match_the_reference() {
+    for bins in [1., 7., 20., 33.3, 63., 64.] {
+        check_reference(&tones::<128>(1., &[(0.5, bins)]), 0.5);
+        check_reference(&tones::<256>(1., &[(0.5, bins * 2.)]), 0.5);
+        check_reference(&tones::<512>(-0.3, &[(2., bins * 4.)]), 2.);
+    }
+    check_reference(&tones::<4>(0., &[(1., 1.)]), 1.);
+    check_reference(&tones::<8>(0., &[(1., 3.)]), 1.);
+}
+
+#[test]
+fn multi_tones_match_the_reference() {
+    let mix = [(1., 10.), (0.5, 37.5), (0.25, 90.), (0.05, 127.)];
+    check_reference(&tones::<256>(1., &mix), 1.8);
+    check_reference(&tones::<512>(1., &mix), 1.8);
+
+    let analyzer = analyze(&tones::<256>(1., &mix));
+    let peaks: [Option<Peak>; 4] = analyzer.peaks(RATE);
+    let bins: Vec<_> = peaks.iter().map(|p| p.unwrap().bin).collect();
+    // the tone between 37 and 38 counts at the lower bin
+    assert_eq!(bins, [10, 37, 90, 127]);
+    assert!((peaks[0].unwrap().magnitude - 1.).abs() < 1e-4);
+    assert_eq!(peaks[0].unwrap().frequency_hz, 10. * RATE / 256.);
+    assert!((peaks[2].unwrap().magnitude - 0.25).abs() < 1e-4);
+    // further maxima are leakage of the tone between bins and rounding
+    let more: [Option<Peak>; 8] = analyzer.peaks(RATE);
+    assert_eq!(more[..4], peaks);
+    assert!(more[4..].iter().flatten().all(|p| p.magnitude < 0.005));
+}
+
+#[test]
+fn hann_window_on_a_bin() {
+    // A on the bin, A / 2 in the neighbours, nothing elsewhere, the offset removed
+    let analyzer = analyze(&tones::<256>(1., &[(0.4, 50.)]));
+    let magnitudes = analyzer.magnitudes();
+    assert!((magnitudes[50] - 0.4).abs() < 1e-5);
+    assert!((magnitudes[49] - 0.2).abs() < 1e-5);
+    assert!((magnitudes[51] - 0.2).abs() < 1e-5);
+    for (bin, magnitude) in magnitudes.iter().enumerate() {
+        if !(49..=51).contains(&bin) {
+            assert!(*magnitude < 1e-5, "bin {}: {}", bin, magnitude);
+        }
+    }
+    assert_eq!(
+        analyzer.dominant_frequency(RATE),
+        (50. * RATE / 256., magnitudes[50])
+    );
+}
+
+#[test]
+fn hann_window_between_bins() {
+    let analyzer = analyze(&tones::<256>(0., &[(0.4, 50.5)]));
+    let magnitudes = analyzer.magnitudes();
+    // equal neighbours, scalloping loss 1.42 dB
+    assert!((magnitudes[50] - magnitudes[51]).abs() < 1e-5);
+    assert!((magnitudes[50] / 0.4 - 0.8488).abs() < 1e-3);
+    // leakage falls off quickly
+    assert!(magnitudes[45] < 0.4 * 1e-2);
+    assert!(magnitudes[30] < 0.4 * 1e-3);
+    let (hz, magnitude) = analyzer.dominant_frequency(RATE);
+    assert_eq!(hz, 50. * RATE / 256.);
+    assert_eq!(magnitude, magnitudes[50]);
+}
+
+#[test]
+fn bin_frequencies() {
+    let analyzer = SpectrumAnalyzer::<512>::new(SpectrumSource::AccelZ);
+    assert_eq!(analyzer.bin_frequency(0, RATE), 0.);
+    assert_eq!(analyzer.bin_frequency(1, RATE), 1000. / 512.);
+    assert_eq!(analyzer.bin_frequency(256, RATE), 500.);
+    // a tone at a quarter of the sample rate lands in bin N / 4
+    let mut quarter = [0.; 512];
+    for (n, sample) in quarter.iter_mut().enumerate() {
+        *sample = [0., 0.3, 0., -0.3][n % 4];
+    }
+    let analyzer = analyze(&quarter);
+    let (hz, magnitude) = analyzer.dominant_frequency(RATE);
+    assert_eq!(hz, 250.);
+    assert!((magnitude - 0.3).abs() < 1e-5);
+}
+
+#[test]
+fn window_slides_over_the_latest_samples() {
+    let old = tones::<64>(0., &[(1., 5.)]);
+    let new = tones::<64>(0., &[(1., 12.)]);
+    let mut analyzer = SpectrumAnalyzer::<64>::new(SpectrumSource::AccelX);
+    assert!(analyzer.is_empty());
+    assert!(analyzer.compute().is_none());
+    assert!(analyzer.magnitudes().is_empty());
+    assert_eq!(analyzer.dominant_frequency(RATE), (0., 0.));
+    for sample in old.iter().chain(&new[..32]) {
+        analyzer.push(*sample);
+    }
+    assert!(analyzer.is_full());
+    for sample in &new[32..] {
+        analyzer.push(*sample);
+    }
+    analyzer.compute().unwrap();
+    assert_eq!(analyzer.magnitudes(), analyze(&new).magnitudes());
+
+    analyzer.clear();
+    assert_eq!(analyzer.len(), 0);
+    assert!(analyzer.magnitudes().is_empty());
+}
+
+#[test]
+fn magnitude_source() {
+    let source = SpectrumSource::AccelMagnitude;
+    assert_eq!(source.select([3., 4., 0.]), 5.);
+    assert_eq!(source.select([0., 0., -1.]), 1.);
+    assert_eq!(source.select([0.; 3]), 0.);
+    assert_eq!(SpectrumSource::AccelY.select([1., 2., 3.]), 2.);
+}
+
+#[test]
+fn memory_footprint() {
+    assert!(std::mem::size_of::<SpectrumAnalyzer<256>>() <= 2 * 1024 + 32);
+    assert!(std::mem::size_of::<SpectrumAnalyzer<512>>() <= 4 * 1024 + 32);
+}
+
+#[test]
+#[should_panic]
+fn power_of_two_windows_only() {
+    SpectrumAnalyzer::<100>::new(SpectrumSource::AccelX);
+}
+
+#[test]
+fn fed_from_the_accelerometer() {
+    let bus = SharedBus::new(&[DEFAULT_SLAVE_ADDR]);
+    let mut mpu = Mpu6050Builder::new().i2c(bus.clone()).build().unwrap();
+    mpu.init(&mut NoDelay).unwrap();
+    mpu.set_settling_policy(settling::SettlingPolicy::Ignore);
+
+    // X at 0, 0.5g, 0, -0.5g: a tone at a quarter of the sample rate
+    let mut analyzer = SpectrumAnalyzer::<16>::new(SpectrumSource::AccelX);
+    for n in 0..16 {
+        let x: i16 = [0, 8192, 0, -8192][n % 4];
+        bus.device(DEFAULT_SLAVE_ADDR, |mock| {
+            let at = ACC_REGX_H as usize;
+            mock.regs[at..at + 2].copy_from_slice(&x.to_be_bytes());
+        });
+        bus.take_log();
+        let full = mpu.feed_spectrum(&mut analyzer).unwrap();
+        assert_eq!(full, n == 15);
+        // one burst per sample
+        let log = bus.take_log();
+        assert_eq!(log.len(), 1);
+        assert_eq!((log[0].reg, log[0].read), (ACC_REGX_H, true));
+    }
+    analyzer.compute().unwrap();
+    let (hz, magnitude) = analyzer.dominant_frequency(RATE);
+    assert_eq!(hz, 250.);
+    assert!((magnitude - 0.5).abs() < 1e-3);
+}
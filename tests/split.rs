@@ -0,0 +1,191 @@
+//! Stream splitting: distribution ratios over long runs, overflow accounting per policy, the
+//! undecimated consumer bit for bit, and a FIFO drain fanned out, see the `split` module.
+
+mod common;
+
+use mpu6050::device::DEFAULT_SLAVE_ADDR;
+use mpu6050::fifo::FifoSources;
+use mpu6050::provenance::{FieldGroup, Provenance};
+use mpu6050::split::*;
+use mpu6050::*;
+
+use common::{NoDelay, SharedBus};
+
+use OverflowPolicy::*;
+
+/// distinct values per index, gyro and temperature irregular
+fn sample(i: u32) -> MpuSample {
+    let t = i as f32;
+    MpuSample::new(
+        Vec3A::new(t * 1e-3, -t, 1. / (t + 1.)),
+        Vec3A::new((t * 0.37).sin(), (t * 0.11).cos(), t.sqrt()),
+        25. + (t * 0.01).sin(),
+    )
+}
+
+fn firmware() -> StreamSplitter<3, 64> {
+    StreamSplitter::new([
+        ConsumerSpec::new(1, DropOldest),
+        ConsumerSpec::new(10, DropOldest),
+        ConsumerSpec::new(100, DropNewest),
+    ])
+}
+
+#[test]
+fn decimator_keeps_every_nth_from_the_first() {
+    let mut decimator = Decimator::new(3);
+    let kept: Vec<bool> = (0..7).map(|_| decimator.accept()).collect();
+    assert_eq!(kept, [true, false, false, true, false, false, true]);
+    decimator.reset();
+    assert!(decimator.accept());
+    assert_eq!(Decimator::new(0).factor(), 1);
+}
+
+#[test]
+fn distribution_follows_the_ratios_over_long_runs() {
+    let mut splitter = firmware();
+    let mut seen = [Vec::new(), Vec::new(), Vec::new()];
+    let drain = |splitter: &mut StreamSplitter<3, 64>, seen: &mut [Vec<MpuSample>; 3]| {
+        for (idx, samples) in seen.iter_mut().enumerate().skip(1) {
+            samples.extend(splitter.consumer(idx));
+        }
+    };
+    for i in 0..100_000 {
+        splitter.ingest(&sample(i));
+        // the control loop drains every sample, the others every 32 ms
+        seen[0].extend(splitter.consumer(0));
+        if i % 32 == 31 {
+            drain(&mut splitter, &mut seen);
+        }
+    }
+    drain(&mut splitter, &mut seen);
+    assert_eq!(splitter.ingested(), 100_000);
+    for (idx, factor) in [(0, 1), (1, 10), (2, 100)] {
+        let samples = &seen[idx];
+        assert_eq!(samples.len(), 100_000 / factor, "consumer {}", idx);
+        // the first input and every factor-th after it
+        for (k, s) in samples.iter().enumerate() {
+            assert_eq!(
+                *s,
+                sample((k * factor) as u32),
+                "consumer {} sample {}",
+                idx,
+                k
+            );
+        }
+        let stats = splitter.stats(idx);
+        assert_eq!(stats.accepted, samples.len() as u64);
+        assert_eq!((stats.dropped, stats.queued), (0, 0));
+    }
+}
+
+#[test]
+fn attitude_consumer_is_the_input_bit_for_bit() {
+    let mut splitter = firmware();
+    let mut passed = Vec::new();
+    let mut inputs = Vec::new();
+    for i in 0..5_000 {
+        let input = sample(i).with_settling(i % 7 == 0);
+        inputs.push(input);
+        splitter.ingest(&input);
+        passed.extend(splitter.consumer(0));
+    }
+    assert_eq!(passed.len(), inputs.len());
+    for (out, input) in passed.iter().zip(&inputs) {
+        let bits = |v: Vec3A| v.to_array().map(f32::to_bits);
+        assert_eq!(bits(out.acc()), bits(input.acc()));
+        assert_eq!(bits(out.gyro()), bits(input.gyro()));
+        assert_eq!(out.temp().to_bits(), input.temp().to_bits());
+        assert_eq!(out, input);
+    }
+}
+
+#[test]
+fn overflow_is_counted_per_consumer_and_policy() {
+    let mut splitter = StreamSplitter::<3, 4>::new([
+        ConsumerSpec::new(1, DropOldest),
+        ConsumerSpec::new(1, DropNewest),
+        ConsumerSpec::new(2, DropOldest),
+    ]);
+    for i in 0..10 {
+        splitter.ingest(&sample(i));
+    }
+    let stats: Vec<ConsumerStats> = (0..3).map(|idx| splitter.stats(idx)).collect();
+    assert_eq!(
+        stats,
+        [
+            ConsumerStats {
+                accepted: 10,
+                dropped: 6,
+                queued: 4
+            },
+            ConsumerStats {
+                accepted: 10,
+                dropped: 6,
+                queued: 4
+            },
+            ConsumerStats {
+                accepted: 5,
+                dropped: 1,
+                queued: 4
+            },
+        ]
+    );
+    // the latest against the earliest
+    let latest: Vec<_> = splitter.consumer(0).collect();
+    assert_eq!(latest, (6..10).map(sample).collect::<Vec<_>>());
+    let earliest: Vec<_> = splitter.consumer(1).collect();
+    assert_eq!(earliest, (0..4).map(sample).collect::<Vec<_>>());
+    let decimated: Vec<_> = splitter.consumer(2).collect();
+    assert_eq!(decimated, [2, 4, 6, 8].map(sample));
+
+    // a partial drain leaves the rest queued, drained queues take samples again
+    splitter.ingest(&sample(10));
+    splitter.ingest(&sample(11));
+    assert_eq!(splitter.consumer(1).next(), Some(sample(10)));
+    assert_eq!(splitter.stats(1).queued, 1);
+    assert_eq!(splitter.stats(1).dropped, 6);
+
+    splitter.reset();
+    assert_eq!(splitter.ingested(), 0);
+    assert_eq!(splitter.stats(0), ConsumerStats::default());
+    assert_eq!(splitter.spec(2), ConsumerSpec::new(2, DropOldest));
+}
+
+#[test]
+fn fifo_drain_fans_out() {
+    let bus = SharedBus::new(&[DEFAULT_SLAVE_ADDR]);
+    let mut mpu = Mpu6050Builder::new().i2c(bus.clone()).build().unwrap();
+    mpu.init(&mut NoDelay).unwrap();
+    let schema = mpu
+        .set_fifo_sources(FifoSources::NONE.with_accel(true))
+        .unwrap();
+    for i in 0..20i16 {
+        let frame = [0, 0, 0, 0, 0x40, 0].map(|b: u8| b.wrapping_add(i as u8));
+        bus.device(DEFAULT_SLAVE_ADDR, |mock| mock.fifo.extend(frame));
+    }
+    let mut splitter = StreamSplitter::<2, 32>::new([
+        ConsumerSpec::new(1, DropOldest),
+        ConsumerSpec::new(5, DropOldest),
+    ]);
+    let frames = mpu
+        .drain_fifo_into(&schema, &mut [0; 256], &mut splitter)
+        .unwrap();
+    assert_eq!(frames, 20);
+    let all: Vec<_> = splitter.consumer(0).collect();
+    assert_eq!(all.len(), 20);
+    let first = all[0];
+    assert_eq!(first.acc(), Vec3A::new(0., 0., 1.));
+    assert!(first.gyro().is_nan() && first.temp().is_nan());
+    assert_eq!(
+        first.provenance().get(FieldGroup::Acc),
+        Provenance::Measured
+    );
+    assert_eq!(
+        first.provenance().get(FieldGroup::Gyro),
+        Provenance::Invalid
+    );
+    let decimated: Vec<_> = splitter.consumer(1).collect();
+    assert_eq!(decimated.len(), 4);
+    assert_eq!(decimated[1].acc(), all[5].acc());
+}
@@ -0,0 +1,177 @@
+//! Soft limits on scaled reads: debounce, per-axis gyro and magnitude accel limits, the
+//! latch and the callback, see the `supervisor` module. Over-limit streams are fed through a
+//! mock register file.
+
+mod common;
+
+use std::sync::atomic::{AtomicU32, Ordering};
+
+use mpu6050::device::{AccelRange, Axis, GyroRange, DEFAULT_SLAVE_ADDR};
+use mpu6050::supervisor::{SupervisorConfig, SupervisorEvent, SupervisorLimit};
+use mpu6050::{synthetic, Mpu6050, Mpu6050Builder, Vec3A};
+
+use common::SharedBus;
+
+const LIMITS: SupervisorConfig = SupervisorConfig {
+    max_gyro_rad_s: Some(Vec3A::new(1.0, 2.0, 0.5)),
+    max_acc_g: Some(1.5),
+    consecutive: 3,
+    callback: None,
+};
+
+struct Stream {
+    bus: SharedBus,
+    mpu: Mpu6050<SharedBus>,
+}
+
+impl Stream {
+    fn new(config: SupervisorConfig) -> Self {
+        let bus = SharedBus::new(&[DEFAULT_SLAVE_ADDR]);
+        let mut mpu = Mpu6050Builder::new().i2c(bus.clone()).build().unwrap();
+        mpu.set_accel_range(AccelRange::G4).unwrap();
+        mpu.set_gyro_range(GyroRange::D500).unwrap();
+        let mut stream = Self { bus, mpu };
+        stream.show(Vec3A::Z, Vec3A::ZERO);
+        // settling samples are not checked
+        while stream.mpu.settle_countdown().is_settling() {
+            stream.mpu.get_all().unwrap();
+        }
+        stream.mpu.set_supervisor(config);
+        stream
+    }
+
+    /// acc in g, gyro in rad/s
+    fn show(&mut self, acc: Vec3A, gyro: Vec3A) {
+        let frame = synthetic::frame_bytes(
+            acc,
+            gyro * (180. / core::f32::consts::PI),
+            AccelRange::G4,
+            GyroRange::D500,
+        );
+        self.bus
+            .device(DEFAULT_SLAVE_ADDR, |mock| mock.set_frame(&frame));
+    }
+
+    /// `count` full samples of `acc` and `gyro`
+    fn feed(&mut self, acc: Vec3A, gyro: Vec3A, count: usize) {
+        self.show(acc, gyro);
+        for _ in 0..count {
+            self.mpu.get_all().unwrap();
+        }
+    }
+}
+
+#[test]
+fn trips_only_after_consecutive_violations() {
+    let mut s = Stream::new(LIMITS);
+    let fast = Vec3A::new(1.2, 0., 0.);
+    s.feed(Vec3A::Z, fast, 2);
+    assert_eq!(s.mpu.supervisor_tripped(), None);
+    // a reading within the limits restarts the count
+    s.feed(Vec3A::Z, Vec3A::ZERO, 1);
+    s.feed(Vec3A::Z, fast, 2);
+    assert_eq!(s.mpu.supervisor_tripped(), None);
+    s.feed(Vec3A::Z, fast, 1);
+    let event = s.mpu.supervisor_tripped().unwrap();
+    assert_eq!(event.limit, SupervisorLimit::GyroRate(Axis::X));
+    assert!((event.value - 1.2).abs() < 1e-2, "{}", event.value);
+}
+
+#[test]
+fn gyro_limits_are_per_axis() {
+    // 1.5 rad/s on Y is within its 2 rad/s, 0.6 on Z beyond its 0.5
+    let mut s = Stream::new(LIMITS);
+    s.feed(Vec3A::Z, Vec3A::new(0., 1.5, 0.), 5);
+    assert_eq!(s.mpu.supervisor_tripped(), None);
+    s.feed(Vec3A::Z, Vec3A::new(0., 0., -0.6), 3);
+    let event = s.mpu.supervisor_tripped().unwrap();
+    assert_eq!(event.limit, SupervisorLimit::GyroRate(Axis::Z));
+    // the signed rate
+    assert!((event.value + 0.6).abs() < 1e-2, "{}", event.value);
+}
+
+#[test]
+fn accel_limit_is_on_the_magnitude() {
+    // no axis beyond 1.5g, the magnitude of about 1.68g is
+    let mut s = Stream::new(LIMITS);
+    let acc = Vec3A::new(1.0, 1.0, 0.9);
+    s.feed(Vec3A::new(0., 0., 1.4), Vec3A::ZERO, 5);
+    assert_eq!(s.mpu.supervisor_tripped(), None);
+    s.feed(acc, Vec3A::ZERO, 3);
+    let event = s.mpu.supervisor_tripped().unwrap();
+    assert_eq!(event.limit, SupervisorLimit::AccelMagnitude);
+    assert!((event.value - acc.length()).abs() < 1e-3, "{}", event.value);
+}
+
+#[test]
+fn single_sensor_reads_are_checked() {
+    let mut s = Stream::new(SupervisorConfig {
+        consecutive: 1,
+        ..LIMITS
+    });
+    s.show(Vec3A::new(0., 0., 2.), Vec3A::ZERO);
+    s.mpu.get_gyro().unwrap();
+    assert_eq!(s.mpu.supervisor_tripped(), None);
+    s.mpu.get_acc().unwrap();
+    assert_eq!(
+        s.mpu.supervisor_tripped().unwrap().limit,
+        SupervisorLimit::AccelMagnitude
+    );
+}
+
+#[test]
+fn latch_keeps_the_first_event_until_cleared() {
+    let mut s = Stream::new(LIMITS);
+    s.feed(Vec3A::Z, Vec3A::new(1.2, 0., 0.), 3);
+    let first = s.mpu.supervisor_tripped().unwrap();
+
+    // back within the limits, then another limit exceeded: still the first event
+    s.feed(Vec3A::Z, Vec3A::ZERO, 10);
+    s.feed(Vec3A::new(0., 0., 3.), Vec3A::ZERO, 5);
+    assert_eq!(s.mpu.supervisor_tripped(), Some(first));
+
+    // clearing resets the debounce too
+    s.mpu.clear_supervisor();
+    assert_eq!(s.mpu.supervisor_tripped(), None);
+    s.feed(Vec3A::new(0., 0., 3.), Vec3A::ZERO, 2);
+    assert_eq!(s.mpu.supervisor_tripped(), None);
+    s.feed(Vec3A::new(0., 0., 3.), Vec3A::ZERO, 1);
+    assert_eq!(
+        s.mpu.supervisor_tripped().unwrap().limit,
+        SupervisorLimit::AccelMagnitude
+    );
+
+    s.mpu.remove_supervisor();
+    assert_eq!(s.mpu.supervisor_tripped(), None);
+}
+
+static CALLBACKS: AtomicU32 = AtomicU32::new(0);
+
+fn count_trip(event: SupervisorEvent) {
+    assert_eq!(event.limit, SupervisorLimit::GyroRate(Axis::Y));
+    CALLBACKS.fetch_add(1, Ordering::Relaxed);
+}
+
+#[test]
+fn callback_runs_once_per_latch() {
+    let mut s = Stream::new(SupervisorConfig {
+        callback: Some(count_trip),
+        ..LIMITS
+    });
+    s.feed(Vec3A::Z, Vec3A::new(0., 2.5, 0.), 10);
+    assert_eq!(CALLBACKS.load(Ordering::Relaxed), 1);
+
+    s.mpu.clear_supervisor();
+    s.feed(Vec3A::Z, Vec3A::new(0., 2.5, 0.), 3);
+    assert_eq!(CALLBACKS.load(Ordering::Relaxed), 2);
+}
+
+#[test]
+fn unconfigured_limits_are_not_checked() {
+    let mut s = Stream::new(SupervisorConfig {
+        max_gyro_rad_s: None,
+        ..LIMITS
+    });
+    s.feed(Vec3A::Z, Vec3A::splat(5.), 10);
+    assert_eq!(s.mpu.supervisor_tripped(), None);
+}
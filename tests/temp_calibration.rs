@@ -0,0 +1,281 @@
+//! Temperature calibration: conversions of each model against hand computed values, rejected
+//! reference points, the serialized forms, and one model change seen by every consumer, see
+//! the `temp_calibration` module.
+
+mod common;
+
+use mpu6050::device::*;
+use mpu6050::fifo::{FifoSchema, FifoSources, FIFO_SCHEMA_VERSION};
+use mpu6050::log_header::{CalibrationMethod, LogHeader};
+use mpu6050::packed::DecodeError;
+use mpu6050::plausibility::{PlausibilityCheck, PlausibilityConfig};
+use mpu6050::scale::TempModel;
+use mpu6050::settling::SettlingPolicy;
+use mpu6050::temp_calibration::*;
+use mpu6050::validity::*;
+use mpu6050::*;
+
+use common::{NoDelay, SharedBus};
+
+const ADDR: u8 = DEFAULT_SLAVE_ADDR;
+
+/// the two-point model of -3000 counts at 20 °C and 3000 at 40 °C: 300 LSB/°C, 30 °C at 0
+fn two_point() -> TempCalibration {
+    TempCalibration::two_point(-3000, 20., 3000, 40.).unwrap()
+}
+
+fn driver() -> (Mpu6050<SharedBus>, SharedBus) {
+    let bus = SharedBus::new(&[ADDR]);
+    let mut mpu = Mpu6050Builder::new().i2c(bus.clone()).build().unwrap();
+    mpu.init(&mut NoDelay).unwrap();
+    mpu.set_settling_policy(SettlingPolicy::Flag);
+    (mpu, bus)
+}
+
+fn set_temp_raw(bus: &SharedBus, raw: i16) {
+    bus.device(ADDR, |mock| {
+        let at = TEMP_OUT_H as usize;
+        mock.regs[at..at + 2].copy_from_slice(&raw.to_be_bytes());
+    });
+}
+
+#[test]
+fn each_model_converts_like_its_formula() {
+    let datasheet = TempCalibration::DatasheetDefault {
+        variant: TempFormula::Mpu6050,
+    };
+    assert_eq!(datasheet.celsius(340), 37.53);
+    assert_eq!(datasheet.celsius(-3400), -10. + 36.53);
+    assert_eq!(datasheet.model(), TempModel::NATIVE);
+    let mpu6500 = TempCalibration::DatasheetDefault {
+        variant: TempFormula::Mpu6500,
+    };
+    assert_eq!(mpu6500.celsius(0), 21.);
+    assert_eq!(mpu6500.celsius(-3339), -3339. / 333.87 + 21.);
+    // the model is the formula bit for bit
+    for raw in [i16::MIN, -12345, -1, 0, 1, 777, i16::MAX] {
+        for variant in [TempFormula::Mpu6050, TempFormula::Mpu6500] {
+            let calibration = TempCalibration::DatasheetDefault { variant };
+            assert_eq!(calibration.celsius(raw), variant.celsius(raw));
+        }
+    }
+
+    // 0 counts read at 30 °C, the datasheet 340 LSB/°C kept
+    let single = TempCalibration::single_point(TempFormula::Mpu6050, 0, 30.).unwrap();
+    assert_eq!(
+        single,
+        TempCalibration::SinglePoint {
+            variant: TempFormula::Mpu6050,
+            offset_c: 30.
+        }
+    );
+    assert_eq!(single.celsius(340), 31.);
+    assert_eq!(single.celsius(-680), 28.);
+
+    let two = two_point();
+    assert_eq!(
+        two,
+        TempCalibration::TwoPoint {
+            sensitivity: 300.,
+            offset_c: 30.
+        }
+    );
+    assert_eq!(two.celsius(1500), 35.);
+    assert_eq!(two.celsius(-3000), 20.);
+    assert_eq!(two.celsius(3000), 40.);
+    assert_eq!(two.to_string(), "two point: raw / 300 + 30 °C");
+}
+
+#[test]
+fn degenerate_reference_points_are_rejected() {
+    assert_eq!(
+        TempCalibration::two_point(-300, 20., 1200, 25.),
+        Err(TempCalibrationError::PointsTooClose { span_c: 5. })
+    );
+    // the same counts at both temperatures
+    assert_eq!(
+        TempCalibration::two_point(500, 20., 500, 40.),
+        Err(TempCalibrationError::SensitivityOutOfRange { lsb_per_c: 0. })
+    );
+    // swapped counts
+    assert_eq!(
+        TempCalibration::two_point(3000, 20., -3000, 40.),
+        Err(TempCalibrationError::SensitivityOutOfRange { lsb_per_c: -300. })
+    );
+    assert_eq!(
+        TempCalibration::two_point(-3000, f32::NAN, 3000, 40.),
+        Err(TempCalibrationError::NotFinite)
+    );
+    assert_eq!(
+        TempCalibration::single_point(TempFormula::Mpu6050, 0, f32::INFINITY),
+        Err(TempCalibrationError::NotFinite)
+    );
+    // points given high first are fine
+    assert_eq!(
+        TempCalibration::two_point(3000, 40., -3000, 20.),
+        Ok(two_point())
+    );
+
+    let (mut mpu, _) = driver();
+    let before = mpu.temp_calibration();
+    assert!(mpu.calibrate_temp_two_point(0, 20., 100, 22.).is_err());
+    let out_of_range = TempCalibration::TwoPoint {
+        sensitivity: 1000.,
+        offset_c: 0.,
+    };
+    assert!(mpu.set_temp_calibration(Some(out_of_range)).is_err());
+    assert_eq!(mpu.temp_calibration(), before);
+    assert!(matches!(
+        Mpu6050Builder::new()
+            .i2c(SharedBus::new(&[ADDR]))
+            .temp_calibration(out_of_range)
+            .build(),
+        Err(Mpu6050BuilderError::TempCalibration(
+            TempCalibrationError::SensitivityOutOfRange { .. }
+        ))
+    ));
+}
+
+#[test]
+fn serialized_forms_round_trip() {
+    let models = [
+        TempCalibration::DatasheetDefault {
+            variant: TempFormula::Mpu6500,
+        },
+        TempCalibration::single_point(TempFormula::Mpu6050, -2220, 31.25).unwrap(),
+        two_point(),
+    ];
+    for calibration in models {
+        let bytes = calibration.to_bytes();
+        assert_eq!(bytes[0], TEMP_CALIBRATION_VERSION);
+        assert_eq!(TempCalibration::from_bytes(&bytes), Ok(calibration));
+
+        let mut corrupted = bytes;
+        corrupted[9] ^= 0x01;
+        assert_eq!(
+            TempCalibration::from_bytes(&corrupted),
+            Err(DecodeError::CrcMismatch)
+        );
+        assert_eq!(
+            TempCalibration::from_bytes(&bytes[..TEMP_CALIBRATION_LEN - 1]),
+            Err(DecodeError::Truncated(TEMP_CALIBRATION_LEN))
+        );
+
+        let schema = FifoSchema::new(
+            FifoSources::NONE.with_temp(true),
+            [0; 4],
+            AccelRange::G2,
+            GyroRange::D250,
+        )
+        .with_temp_model(calibration.model());
+        assert_eq!(FifoSchema::from_bytes(&schema.to_bytes()), Ok(schema));
+
+        let header = LogHeader::new(0x68, AccelRange::G2, GyroRange::D250)
+            .with_temp_calibration(calibration);
+        assert_eq!(header.pipeline.temp(1500), calibration.celsius(1500));
+        let mut buf = [0; 256];
+        let len = header.encode(&mut buf).unwrap();
+        assert_eq!(len, header.encoded_len());
+        assert_eq!(LogHeader::decode(&buf[..len]), Ok((header, len)));
+    }
+
+    // version 1 schemas have no temperature model
+    let v1 = FifoSchema::new(
+        FifoSources::NONE.with_temp(true),
+        [0; 4],
+        AccelRange::G2,
+        GyroRange::D250,
+    )
+    .with_temp_model(two_point().model());
+    let mut bytes = v1.to_bytes();
+    bytes[0] = 1;
+    assert_eq!(FIFO_SCHEMA_VERSION, 2);
+    assert_eq!(
+        FifoSchema::from_bytes(&bytes[..20]).map(|schema| schema.temp),
+        Ok(TempModel::NATIVE)
+    );
+
+    // a header of a recording with the driver carries its model
+    let (mut mpu, _) = driver();
+    mpu.calibrate_temp_two_point(-3000, 20., 3000, 40.).unwrap();
+    let header = mpu.build_log_header().unwrap();
+    assert_eq!(header.temp_calibration, Some(two_point()));
+    assert!(header.to_string().contains("temperature: two point"));
+    let mut buf = [0; 512];
+    let len = header.encode(&mut buf).unwrap();
+    let (decoded, _) = LogHeader::decode(&buf[..len]).unwrap();
+    assert_eq!(decoded.pipeline, mpu.pipeline());
+}
+
+#[test]
+fn every_consumer_follows_the_model() {
+    let (mut mpu, bus) = driver();
+    // 30 °C by the datasheet formula
+    let raw = -2220;
+    set_temp_raw(&bus, raw);
+    mpu.set_plausibility_config(Some(PlausibilityConfig {
+        temp_c: Some((-40., 40.)),
+        ..PlausibilityConfig::NONE
+    }));
+    mpu.set_calibration_policy(Some(CalibrationPolicy {
+        max_temp_delta_c: Some(10.),
+        ..CalibrationPolicy::NONE
+    }));
+    let mut meta = CalibrationMeta::new(CalibrationMethod::Static);
+    meta.temperature_c = Some(25.);
+    mpu.set_calibration_meta(CalibrationKind::SoftwareOffsets, Some(meta));
+    mpu.set_fifo_sources(FifoSources::NONE.with_temp(true))
+        .unwrap();
+    let stale = mpu.fifo_schema();
+
+    assert_eq!(
+        mpu.temp_calibration(),
+        TempCalibration::DatasheetDefault {
+            variant: TempFormula::Mpu6050
+        }
+    );
+    assert_eq!(mpu.get_temp_raw().unwrap(), raw);
+    assert!((mpu.get_temp().unwrap() - 30.).abs() < 1e-2);
+    let sample = mpu.read_into_views::<1>(&mut []).unwrap();
+    assert!(!sample.plausibility().failed(PlausibilityCheck::TempRange));
+    assert_eq!(mpu.calibration_status(), CalibrationStatus::Valid);
+
+    // the part reads 15 °C low
+    let calibration = mpu.calibrate_temp_single_point(raw, 45.).unwrap();
+    assert_eq!(mpu.temp_calibration(), calibration);
+    assert_eq!(mpu.debug_state().temp_calibration, Some(calibration));
+    assert!((calibration.celsius(raw) - 45.).abs() < 1e-2);
+
+    let temp = mpu.get_temp().unwrap();
+    assert_eq!(temp, calibration.celsius(raw));
+    let sample = mpu.read_into_views::<1>(&mut []).unwrap();
+    assert_eq!(sample.temp(), temp);
+    // the alarms and the compensation rules see the calibrated temperature
+    assert!(sample.plausibility().failed(PlausibilityCheck::TempRange));
+    let reasons = InvalidationReasons::EMPTY.with(InvalidationReason::Temperature);
+    assert_eq!(
+        mpu.calibration_status(),
+        CalibrationStatus::Degraded { reasons }
+    );
+    assert_eq!(mpu.pipeline().temp(raw), temp);
+
+    // the FIFO schema carries the model, the one from before is stale
+    assert!(matches!(
+        mpu.drain_fifo(&stale, &mut [0; 16], |_| {}),
+        Err(Mpu6050Error::StaleConfiguration { .. })
+    ));
+    let schema = mpu.fifo_schema();
+    bus.device(ADDR, |mock| mock.fifo.extend(raw.to_be_bytes()));
+    let mut fifo_temps = Vec::new();
+    mpu.drain_fifo(&schema, &mut [0; 16], |frame| {
+        fifo_temps.push(frame.temp_c())
+    })
+    .unwrap();
+    assert_eq!(fifo_temps, [Some(temp)]);
+
+    // back to the datasheet formula
+    mpu.set_temp_calibration(None).unwrap();
+    assert!((mpu.get_temp().unwrap() - 30.).abs() < 1e-2);
+    mpu.read_into_views::<1>(&mut []).unwrap();
+    assert_eq!(mpu.calibration_status(), CalibrationStatus::Valid);
+}
@@ -0,0 +1,232 @@
+//! Golden sample corpus: the bytes and expected outputs of every fixture against
+//! `tests/golden/fixtures.txt`, generator to parser round trips at every range combination,
+//! and the FIFO layouts, see the `test_fixtures` module. After extending the corpus, rerun
+//! with `UPDATE_GOLDENS=1` and review the golden diff.
+
+use std::fmt::Write;
+
+use mpu6050::conversion::*;
+use mpu6050::device::{AccelRange, GyroRange, ACCEL_SENS, GYRO_SENS};
+use mpu6050::fifo::{parse_fifo_frame, FifoSchema, FifoSources};
+use mpu6050::frame::{encode_frame, parse_frame};
+use mpu6050::test_fixtures::*;
+use mpu6050::trace::check_golden;
+use mpu6050::*;
+
+fn accel_sensitivity(range: AccelRange) -> f32 {
+    let (a, b, c, d) = ACCEL_SENS;
+    [a, b, c, d][range as usize]
+}
+
+fn gyro_sensitivity(range: GyroRange) -> f32 {
+    let (a, b, c, d) = GYRO_SENS;
+    [a, b, c, d][range as usize]
+}
+
+fn render() -> String {
+    let mut out = String::new();
+    for fixture in ALL {
+        writeln!(
+            out,
+            "{}: {}, {:?} {:?}, {} samples every {} s",
+            fixture.name,
+            fixture.description,
+            fixture.accel_range,
+            fixture.gyro_range,
+            fixture.len,
+            fixture.interval_s
+        )
+        .unwrap();
+        for idx in 0..fixture.len {
+            let words: Vec<String> = fixture
+                .bytes(idx)
+                .chunks_exact(2)
+                .map(|word| format!("{:02x}{:02x}", word[0], word[1]))
+                .collect();
+            let expected = fixture.expected(idx);
+            let saturated = fixture.saturates(idx, fixture.accel_range, fixture.gyro_range);
+            writeln!(
+                out,
+                "{:3} {} acc {:?} gyro {:?} temp {:?}{}",
+                idx,
+                words.join(" "),
+                expected.acc().to_array(),
+                expected.gyro().to_array(),
+                expected.temp(),
+                if saturated { " saturated" } else { "" }
+            )
+            .unwrap();
+        }
+    }
+    out
+}
+
+#[test]
+fn corpus_matches_the_golden() {
+    let path = format!("{}/tests/golden/fixtures.txt", env!("CARGO_MANIFEST_DIR"));
+    if let Err(mismatch) = check_golden(path, &render()) {
+        panic!("{}", mismatch);
+    }
+}
+
+/// `scaled` is within half an LSB of `truth`, or the range limit toward it if `count`
+/// saturated
+fn within_half_lsb(scaled: f32, truth: f32, count: i16, lsb: f32, what: &str) {
+    if count == i16::MAX || count == i16::MIN {
+        assert!(
+            truth.abs() >= scaled.abs(),
+            "{}: {} {}",
+            what,
+            scaled,
+            truth
+        );
+        assert_eq!(scaled.signum(), truth.signum(), "{}", what);
+    } else {
+        assert!(
+            (scaled - truth).abs() <= 0.5 * lsb * (1. + 1e-5),
+            "{}: {} {}",
+            what,
+            scaled,
+            truth
+        );
+    }
+}
+
+#[test]
+fn every_fixture_round_trips_at_every_range() {
+    let mode = RoundingMode::NearestEven;
+    for fixture in ALL {
+        for accel_range in AccelRange::ALL {
+            for gyro_range in GyroRange::ALL {
+                let (acc_lsb, gyro_lsb) =
+                    (accel_sensitivity(accel_range), gyro_sensitivity(gyro_range));
+                for idx in 0..fixture.len {
+                    let what = format!(
+                        "{} {} {:?} {:?}",
+                        fixture.name, idx, accel_range, gyro_range
+                    );
+                    let frame = fixture.frame_at(idx, accel_range, gyro_range);
+                    let bytes = fixture.bytes_at(idx, accel_range, gyro_range);
+                    assert_eq!(parse_frame(&bytes), frame, "{}", what);
+                    assert_eq!(encode_frame(&frame), bytes, "{}", what);
+
+                    // the expected output scales back to the frame, exactly
+                    let expected = fixture.expected_at(idx, accel_range, gyro_range);
+                    assert_eq!(
+                        units_to_counts(expected.acc(), acc_lsb, mode),
+                        (frame.acc, false),
+                        "{}",
+                        what
+                    );
+                    assert_eq!(
+                        rad_s_to_counts(expected.gyro(), gyro_lsb, mode),
+                        (frame.gyro, false),
+                        "{}",
+                        what
+                    );
+                    assert_eq!(
+                        celsius_to_counts(expected.temp(), mode),
+                        (frame.temp, false),
+                        "{}",
+                        what
+                    );
+
+                    // and is the truth to half an LSB
+                    let truth = fixture.truth(idx);
+                    let gyro_dps = expected.gyro() / PI_180;
+                    for axis in 0..3 {
+                        within_half_lsb(
+                            expected.acc()[axis],
+                            truth.acc_g[axis],
+                            frame.acc[axis],
+                            1. / acc_lsb,
+                            &what,
+                        );
+                        within_half_lsb(
+                            gyro_dps[axis],
+                            truth.gyro_dps[axis],
+                            frame.gyro[axis],
+                            1. / gyro_lsb,
+                            &what,
+                        );
+                    }
+                    within_half_lsb(expected.temp(), truth.temp_c, frame.temp, 1. / 340., &what);
+                    let saturated = [frame.acc, frame.gyro]
+                        .iter()
+                        .flatten()
+                        .chain([&frame.temp])
+                        .any(|count| *count == i16::MAX || *count == i16::MIN);
+                    assert_eq!(
+                        fixture.saturates(idx, accel_range, gyro_range),
+                        saturated,
+                        "{}",
+                        what
+                    );
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn documented_clipping_and_ground_truth() {
+    let clipped: Vec<usize> = (0..IMPACT.len)
+        .filter(|&idx| IMPACT.saturates(idx, IMPACT.accel_range, IMPACT.gyro_range))
+        .collect();
+    assert_eq!(clipped, [7, 8]);
+    assert_eq!(IMPACT.frame(7).acc[0], i16::MAX);
+    // within ±2 g a resting device saturates nothing, the shock everywhere it is beyond 2 g
+    assert!((0..STATIONARY.len).all(|idx| !STATIONARY.saturates(
+        idx,
+        AccelRange::G2,
+        GyroRange::D250
+    )));
+    assert_eq!(
+        (0..IMPACT.len)
+            .filter(|&idx| IMPACT.saturates(idx, AccelRange::G2, GyroRange::D2000))
+            .count(),
+        4
+    );
+
+    assert_eq!(TEMP_RAMP.truth(40).temp_c, 40.);
+    assert_eq!(TEMP_RAMP.time_s(40), 40.);
+    assert_eq!(ROTATING_Z.time_s(100), 1.);
+    for fixture in ALL {
+        assert_eq!(fixture.truths().count(), fixture.len, "{}", fixture.name);
+    }
+}
+
+#[test]
+fn fifo_sequences_parse_back_to_the_frames() {
+    let source_sets = [
+        FifoSources::NONE
+            .with_accel(true)
+            .with_temp(true)
+            .with_gyro(true),
+        FifoSources::NONE.with_accel(true).with_gyro(true),
+        FifoSources::NONE.with_gyro_axes([true, false, true]),
+    ];
+    for fixture in ALL {
+        for sources in source_sets {
+            let schema = FifoSchema::new(sources, [0; 4], fixture.accel_range, fixture.gyro_range);
+            let bytes = fixture.fifo_bytes(sources);
+            assert_eq!(bytes.len(), schema.frame_len() * fixture.len);
+            for (idx, chunk) in bytes.chunks_exact(schema.frame_len()).enumerate() {
+                let parsed = parse_fifo_frame(&schema, chunk).unwrap();
+                let frame = fixture.frame(idx);
+                if sources.accel {
+                    assert_eq!(parsed.acc, Some(frame.acc));
+                    assert_eq!(parsed.acc_g(), Some(fixture.expected(idx).acc()));
+                }
+                if parsed.gyro.iter().all(Option::is_some) {
+                    assert_eq!(parsed.gyro_rad_s(), Some(fixture.expected(idx).gyro()));
+                }
+                for axis in 0..3 {
+                    let count = sources.gyro[axis].then_some(frame.gyro[axis]);
+                    assert_eq!(parsed.gyro[axis], count, "{} {}", fixture.name, idx);
+                }
+                assert_eq!(parsed.temp, sources.temp.then_some(frame.temp));
+            }
+        }
+    }
+}
@@ -0,0 +1,117 @@
+//! Trust classes of accel-only tilt around the ±2% and ±10% boundaries and near zero g, see
+//! the `tilt` module. The driver reads a mock register file.
+
+mod common;
+
+use mpu6050::device::{AccelRange, GyroRange, DEFAULT_SLAVE_ADDR};
+use mpu6050::tilt::{classify, estimate, TiltThresholds, TiltTrust};
+use mpu6050::{synthetic, Mpu6050Builder, Vec3A};
+
+use common::SharedBus;
+
+fn trust(magnitude_g: f32) -> TiltTrust {
+    classify(magnitude_g, &TiltThresholds::default())
+}
+
+#[test]
+fn two_percent_splits_high_from_medium() {
+    for magnitude in [1.0, 1.019, 0.981] {
+        assert_eq!(trust(magnitude), TiltTrust::High, "{magnitude}");
+    }
+    for magnitude in [1.021, 0.979] {
+        assert_eq!(trust(magnitude), TiltTrust::Medium, "{magnitude}");
+    }
+}
+
+#[test]
+fn ten_percent_splits_medium_from_low() {
+    for magnitude in [1.099, 0.901] {
+        assert_eq!(trust(magnitude), TiltTrust::Medium, "{magnitude}");
+    }
+    for magnitude in [1.101, 0.899, 2.0] {
+        assert_eq!(trust(magnitude), TiltTrust::Low, "{magnitude}");
+    }
+}
+
+#[test]
+fn free_fall_is_invalid() {
+    for magnitude in [0.0, 0.05, 0.099, f32::NAN] {
+        assert_eq!(trust(magnitude), TiltTrust::Invalid, "{magnitude}");
+    }
+    // at the cutoff the direction is usable, but far from 1g
+    assert_eq!(trust(0.1), TiltTrust::Low);
+}
+
+#[test]
+fn custom_thresholds_move_the_boundaries() {
+    let strict = TiltThresholds {
+        high: 0.005,
+        medium: 0.03,
+        invalid_below: 0.5,
+    };
+    assert_eq!(classify(1.004, &strict), TiltTrust::High);
+    assert_eq!(classify(1.006, &strict), TiltTrust::Medium);
+    assert_eq!(classify(1.031, &strict), TiltTrust::Low);
+    assert_eq!(classify(0.49, &strict), TiltTrust::Invalid);
+}
+
+#[test]
+fn estimate_classifies_the_vector_magnitude() {
+    // 30° right, 1.5% heavy: the angles are unaffected by the scale
+    let tilt = estimate(
+        synthetic::at_rest(30., 0.) * 1.015,
+        &TiltThresholds::default(),
+    );
+    assert_eq!(tilt.trust, TiltTrust::High);
+    assert!((tilt.roll.to_degrees() - 30.).abs() < 1e-3);
+    assert!(tilt.pitch.abs() < 1e-3);
+
+    let tilt = estimate(
+        synthetic::at_rest(0., 20.) * 1.05,
+        &TiltThresholds::default(),
+    );
+    assert_eq!(tilt.trust, TiltTrust::Medium);
+
+    let tilt = estimate(Vec3A::new(0.01, -0.02, 0.03), &TiltThresholds::default());
+    assert_eq!(tilt.trust, TiltTrust::Invalid);
+}
+
+#[test]
+fn driver_scores_the_measured_magnitude() {
+    let bus = SharedBus::new(&[DEFAULT_SLAVE_ADDR]);
+    let mut mpu = Mpu6050Builder::new().i2c(bus.clone()).build().unwrap();
+    let show = |acc: Vec3A| {
+        let frame = synthetic::frame_bytes(acc, Vec3A::ZERO, AccelRange::G2, GyroRange::D250);
+        bus.device(DEFAULT_SLAVE_ADDR, |mock| mock.set_frame(&frame));
+    };
+
+    show(synthetic::at_rest(10., -5.));
+    assert_eq!(mpu.get_acc_angles_checked().unwrap().trust, TiltTrust::High);
+
+    // accelerating at 0.3g on top of gravity
+    show(synthetic::at_rest(0., 0.) + Vec3A::new(0.3, 0., 0.));
+    assert_eq!(
+        mpu.get_acc_angles_checked().unwrap().trust,
+        TiltTrust::Medium
+    );
+
+    show(synthetic::at_rest(0., 0.) * 1.2);
+    assert_eq!(mpu.get_acc_angles_checked().unwrap().trust, TiltTrust::Low);
+
+    // falling
+    show(Vec3A::ZERO);
+    assert_eq!(
+        mpu.get_acc_angles_checked().unwrap().trust,
+        TiltTrust::Invalid
+    );
+
+    mpu.set_tilt_thresholds(TiltThresholds {
+        medium: 0.25,
+        ..TiltThresholds::default()
+    });
+    show(synthetic::at_rest(0., 0.) * 1.2);
+    assert_eq!(
+        mpu.get_acc_angles_checked().unwrap().trust,
+        TiltTrust::Medium
+    );
+}
@@ -0,0 +1,274 @@
+//! Wrap-aware time arithmetic at the boundaries, the wrap counting of narrow clocks, and a
+//! scripted 32 bit clock driving the timestamp consumers across several wraps, see the
+//! `time_math` module.
+
+use mpu6050::interpolation::{InterpolatingBuffer, TimedSample};
+use mpu6050::resample::{ResampledItem, UniformResampler};
+use mpu6050::time_math::*;
+use mpu6050::*;
+
+const PERIOD_32: u64 = 1 << 32;
+
+#[test]
+fn masks_and_raw_differences_at_the_boundaries() {
+    assert_eq!(width_mask(0), 1);
+    assert_eq!(width_mask(1), 1);
+    assert_eq!(width_mask(16), 0xffff);
+    assert_eq!(width_mask(32), u32::MAX as u64);
+    assert_eq!(width_mask(64), u64::MAX);
+    assert_eq!(width_mask(100), u64::MAX);
+
+    assert_eq!(wrapping_elapsed(5, 5, 32), 0);
+    assert_eq!(wrapping_elapsed(u32::MAX as u64, 0, 32), 1);
+    assert_eq!(wrapping_elapsed(0, u32::MAX as u64, 32), u32::MAX as u64);
+    assert_eq!(wrapping_elapsed(1, 0, 32), u32::MAX as u64);
+    assert_eq!(wrapping_elapsed(u64::MAX, 0, 64), 1);
+    assert_eq!(wrapping_elapsed(1, 0, 1), 1);
+    // bits above the width do not matter
+    assert_eq!(wrapping_elapsed(PERIOD_32 + 10, 3 * PERIOD_32 + 25, 32), 15);
+
+    // every pair of 8 bit readings
+    for earlier in 0..256u64 {
+        for delta in 0..256u64 {
+            let later = (earlier + delta) % 256;
+            assert_eq!(wrapping_elapsed(earlier, later, 8), delta);
+        }
+    }
+}
+
+#[test]
+fn extended_differences_saturate() {
+    assert_eq!(elapsed_us(3, 10), 7);
+    assert_eq!(elapsed_us(10, 3), 0);
+    assert_eq!(elapsed_us(0, u64::MAX), u64::MAX);
+
+    assert_eq!(offset_us(10, 3), -7);
+    assert_eq!(offset_us(3, 10), 7);
+    assert_eq!(offset_us(0, u64::MAX), i64::MAX);
+    assert_eq!(offset_us(u64::MAX, 0), i64::MIN);
+
+    assert_eq!(before_us(10, 3), 7);
+    assert_eq!(before_us(3, 10), 0);
+
+    assert_eq!(dt_s(1_000_000, 1_010_000), 0.01);
+    assert_eq!(dt_s(1_010_000, 1_000_000), 0.);
+
+    assert_eq!(grid_time_us(1000, 3, 4), 751_000);
+    assert_eq!(grid_time_us(1000, 1, 3), 334_333);
+    assert_eq!(grid_time_us(1000, 2, 0), 2_001_000);
+    assert_eq!(grid_time_us(u64::MAX - 1, 10, 1), u64::MAX);
+}
+
+#[test]
+fn extender_counts_single_wraps() {
+    let mut extender = ClockExtender::new(32);
+    assert_eq!(extender.last_us(), None);
+    let max = u32::MAX as u64;
+    assert_eq!(extender.extend(max), max);
+    assert_eq!(extender.extend(0), PERIOD_32);
+    assert_eq!(extender.extend(max), 2 * PERIOD_32 - 1);
+    assert_eq!(extender.extend(max), 2 * PERIOD_32 - 1);
+    assert_eq!(extender.extend(0), 2 * PERIOD_32);
+    assert_eq!(extender.last_us(), Some(2 * PERIOD_32));
+    // bits above the width are ignored
+    assert_eq!(extender.extend(5 * PERIOD_32 + 7), 2 * PERIOD_32 + 7);
+
+    // every step of an 8 bit counter from every start
+    for start in 0..256u64 {
+        let mut extender = ClockExtender::new(8);
+        let mut t = start;
+        extender.extend(start);
+        for step in [0, 1, 17, 128, 255, 3] {
+            t += step;
+            assert_eq!(extender.extend(t % 256), t, "start {} step {}", start, step);
+        }
+    }
+
+    // a 64 bit counter saturates instead of wrapping
+    let mut extender = ClockExtender::new(64);
+    extender.extend(u64::MAX);
+    assert_eq!(extender.extend(0), u64::MAX);
+    assert_eq!(ClockExtender::new(0).width_bits(), 1);
+    assert_eq!(ClockExtender::new(65).width_bits(), 64);
+}
+
+#[test]
+fn multiple_wraps_are_counted_from_the_expectation_and_flagged() {
+    for start in [0, 1, PERIOD_32 / 2, PERIOD_32 - 1] {
+        for skipped in 0..4u64 {
+            for jitter in [-2_000i64, 0, 2_000] {
+                let mut extender = ClockExtender::new(32);
+                extender.extend(start);
+                let step = 12_345;
+                let actual = skipped * PERIOD_32 + step;
+                let expected = (actual as i64 + jitter) as u64;
+                let extension = extender.extend_expecting((start + actual) % PERIOD_32, expected);
+                assert_eq!(
+                    extension,
+                    Extension {
+                        t_us: start + actual,
+                        skipped_wraps: skipped,
+                        ambiguous: false,
+                    },
+                    "start {} skipped {} jitter {}",
+                    start,
+                    skipped,
+                    jitter
+                );
+                assert_eq!(extension.is_flagged(), skipped > 0);
+            }
+        }
+    }
+
+    // an expectation below the readings changes nothing
+    let mut extender = ClockExtender::new(32);
+    extender.extend(1000);
+    let extension = extender.extend_expecting(500, 10);
+    assert_eq!(extension.t_us, PERIOD_32 + 500);
+    assert!(!extension.is_flagged());
+
+    // halfway between two wrap counts: rounded, and flagged as a guess
+    let mut extender = ClockExtender::new(32);
+    extender.extend(0);
+    let below = extender.extend_expecting(10, 10 + PERIOD_32 / 2 - 1);
+    assert_eq!((below.skipped_wraps, below.ambiguous), (0, true));
+    assert!(below.is_flagged());
+    let above = extender.extend_expecting(20, 10 + PERIOD_32 / 2);
+    assert_eq!((above.skipped_wraps, above.ambiguous), (1, true));
+    assert_eq!(above.t_us, PERIOD_32 + 20);
+    // a quarter period off is still decided
+    let mut extender = ClockExtender::new(32);
+    extender.extend(0);
+    let quarter = extender.extend_expecting(10, 10 + PERIOD_32 / 4);
+    assert_eq!((quarter.skipped_wraps, quarter.ambiguous), (0, false));
+}
+
+/// 32 bit µs hardware timer read every `step_us`, `jumps` maps reading indices to extra µs
+/// the timer advances before them
+struct Timer32 {
+    raw: u32,
+    step_us: u32,
+    readings: u64,
+    jumps: &'static [(u64, u64)],
+}
+
+impl Clock for Timer32 {
+    const WIDTH_BITS: u32 = 32;
+
+    fn now_us(&mut self) -> u64 {
+        let jump = self
+            .jumps
+            .iter()
+            .find(|(at, _)| *at == self.readings)
+            .map_or(0, |(_, us)| *us);
+        self.readings += 1;
+        self.raw = self
+            .raw
+            .wrapping_add(self.step_us)
+            .wrapping_add(jump as u32);
+        self.raw as u64
+    }
+}
+
+#[test]
+fn scripted_32_bit_clock_stays_continuous_across_wraps() {
+    // 0.997 s per sample, five wraps in about 21600 samples, starting just before a wrap
+    let step_us = 997_123;
+    let timer = Timer32 {
+        raw: u32::MAX - 2 * step_us,
+        step_us,
+        readings: 0,
+        jumps: &[],
+    };
+    let mut clock = ExtendedClock::new(timer);
+    let mut buffer = InterpolatingBuffer::<4>::new();
+    let mut resampler = UniformResampler::new(1, 1e6 / step_us as f32, 2_000_000);
+
+    let mut previous: Option<u64> = None;
+    let mut first = None;
+    let mut grid = Vec::new();
+    let samples = 5 * PERIOD_32 / step_us as u64 + 10;
+    for k in 0..samples {
+        let t_us = clock.now_us();
+        if let Some(previous) = previous {
+            assert_eq!(elapsed_us(previous, t_us), step_us as u64, "sample {}", k);
+            assert!(t_us > previous);
+        }
+        previous = Some(t_us);
+        let first_us = *first.get_or_insert(t_us);
+
+        // value: seconds since the first sample, a ramp the resampler reproduces
+        let seconds = dt_s(first_us, t_us);
+        let sample = MpuSample::new(Vec3A::splat(seconds), Vec3A::ZERO, 25.);
+        let entry = TimedSample {
+            t_us,
+            sample,
+            orientation: None,
+        };
+        buffer.push(entry).unwrap();
+        for item in resampler.push(entry).unwrap() {
+            match item {
+                ResampledItem::Sample { t_us, sample } => grid.push((t_us, sample.acc.x)),
+                gap => panic!("gap at sample {}: {:?}", k, gap),
+            }
+        }
+    }
+    let last_us = previous.unwrap();
+    assert!(last_us - first.unwrap() > 5 * PERIOD_32);
+
+    // the grid runs on at exactly 1 s through every wrap
+    for pair in grid.windows(2) {
+        assert_eq!(elapsed_us(pair[0].0, pair[1].0), 1_000_000);
+    }
+    for (k, (t_us, value)) in grid.iter().enumerate() {
+        assert_eq!(*t_us, grid_time_us(first.unwrap(), k as u64, 1));
+        assert!(
+            (value - k as f32).abs() < 1e-2 * (1. + k as f32 * 1e-4),
+            "grid point {}",
+            k
+        );
+    }
+    // the buffer spans the last samples across the extension
+    let (oldest, newest) = buffer.span().unwrap();
+    assert_eq!(
+        (newest, elapsed_us(oldest, newest)),
+        (last_us, 3 * step_us as u64)
+    );
+    assert!(buffer.sample_at(newest - step_us as u64 / 2).is_some());
+}
+
+#[test]
+fn a_clock_stalled_for_several_wraps_is_flagged() {
+    // the host slept for three full timer periods before reading 3
+    let step_us = 1_000;
+    let timer = Timer32 {
+        raw: 0,
+        step_us,
+        readings: 0,
+        jumps: &[(3, 3 * PERIOD_32)],
+    };
+    let mut clock = ExtendedClock::new(timer);
+    let mut timestamps = Vec::new();
+    for k in 0..6u64 {
+        // the expectation: samples counted by the sensor since the previous reading
+        let expected = match k {
+            3 => 3 * PERIOD_32 + step_us as u64,
+            _ => step_us as u64,
+        };
+        let extension = clock.now_expecting(expected);
+        assert_eq!(extension.is_flagged(), k == 3, "reading {}", k);
+        timestamps.push(extension.t_us);
+    }
+    let lost = 3 * PERIOD_32;
+    assert_eq!(
+        timestamps,
+        [
+            1_000,
+            2_000,
+            3_000,
+            lost + 4_000,
+            lost + 5_000,
+            lost + 6_000
+        ]
+    );
+}
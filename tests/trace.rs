@@ -0,0 +1,200 @@
+//! Register access order of sequence-sensitive operations against golden traces in
+//! `tests/golden`, see the `trace` module. After an intentional change of a sequence, rerun
+//! with `UPDATE_GOLDENS=1` and review the golden diff.
+
+mod common;
+
+use mpu6050::aux_i2c::{SlaveConfig, SlaveSlot};
+use mpu6050::device::WHOAMI;
+use mpu6050::fifo::FifoSources;
+use mpu6050::interrupt::MotionDetectionConfig;
+use mpu6050::presets;
+use mpu6050::settings::Mpu6050Settings;
+use mpu6050::trace::{check_golden, line_diff, GoldenMismatch, TraceHandle, TracingI2c};
+use mpu6050::*;
+
+use common::{chip_time_us, FifoChip, NoDelay, RegisterMock};
+
+type Mpu = Mpu6050<TracingI2c<RegisterMock>>;
+
+fn traced() -> (Mpu, TraceHandle) {
+    let (i2c, trace) = TracingI2c::new(RegisterMock::new());
+    let mpu = Mpu6050Builder::new().i2c(i2c).build().unwrap();
+    (mpu, trace)
+}
+
+/// driver after init, with the init transactions removed from the trace
+fn initialized() -> (Mpu, TraceHandle) {
+    let (mut mpu, trace) = traced();
+    mpu.init(&mut NoDelay).unwrap();
+    trace.clear();
+    (mpu, trace)
+}
+
+fn assert_golden(name: &str, trace: &TraceHandle) {
+    let path = format!("{}/tests/golden/{}.trace", env!("CARGO_MANIFEST_DIR"), name);
+    if let Err(mismatch) = check_golden(path, &trace.render()) {
+        panic!("{}", mismatch);
+    }
+}
+
+#[test]
+fn init() {
+    let (mut mpu, trace) = traced();
+    mpu.init(&mut NoDelay).unwrap();
+    assert_golden("init", &trace);
+}
+
+fn init_with(name: &str, settings: &Mpu6050Settings) {
+    let (mut mpu, trace) = traced();
+    trace.mark("init");
+    mpu.init(&mut NoDelay).unwrap();
+    trace.mark(name);
+    mpu.apply_settings(settings).unwrap();
+    assert_golden(&format!("init_{}", name), &trace);
+}
+
+#[test]
+fn init_with_each_preset() {
+    init_with("drone_rate_control", &presets::DRONE_RATE_CONTROL);
+    init_with("handheld_ui", &presets::HANDHELD_UI);
+    init_with("vibration_logging", &presets::VIBRATION_LOGGING);
+    init_with("low_power_tilt", &presets::LOW_POWER_TILT);
+}
+
+#[test]
+fn preset_transitions() {
+    let (mut mpu, trace) = initialized();
+    for (name, settings) in [
+        ("drone_rate_control", &presets::DRONE_RATE_CONTROL),
+        ("low_power_tilt", &presets::LOW_POWER_TILT),
+        ("handheld_ui", &presets::HANDHELD_UI),
+    ] {
+        trace.mark(name);
+        mpu.apply_settings(settings).unwrap();
+    }
+    assert_golden("preset_transitions", &trace);
+}
+
+#[test]
+fn reset_device() {
+    let (mut mpu, trace) = initialized();
+    mpu.reset_device(&mut NoDelay).unwrap();
+    assert_golden("reset_device", &trace);
+}
+
+#[test]
+fn sleep_and_wake() {
+    let (mut mpu, trace) = initialized();
+    trace.mark("power down");
+    mpu.set_sleep_enabled(true).unwrap();
+    trace.mark("power up");
+    mpu.set_sleep_enabled(false).unwrap();
+    assert_golden("sleep_and_wake", &trace);
+}
+
+#[test]
+fn shutdown() {
+    let (mut mpu, trace) = initialized();
+    trace.mark("sequence");
+    let evidence = mpu.shutdown(&mut NoDelay).unwrap();
+    assert!(evidence.verified());
+    trace.mark("verify");
+    assert!(mpu.verify_shutdown_state().unwrap().verified());
+    assert_golden("shutdown", &trace);
+}
+
+#[test]
+fn setup_motion_detection() {
+    let (mut mpu, trace) = initialized();
+    mpu.setup_motion_detection(MotionDetectionConfig::DEFAULT)
+        .unwrap();
+    assert_golden("setup_motion_detection", &trace);
+}
+
+#[test]
+fn try_reconnect() {
+    let (mut mpu, trace) = initialized();
+    mpu.try_reconnect(&mut NoDelay).unwrap();
+    assert_golden("try_reconnect", &trace);
+}
+
+#[test]
+fn aux_i2c_master() {
+    let (mut mpu, trace) = initialized();
+    let magnetometer = SlaveConfig {
+        address: 0x0c,
+        register: 0x03,
+        len: 6,
+        read: true,
+        byte_swap: false,
+    };
+    trace.mark("enable");
+    mpu.set_i2c_master_enabled(true).unwrap();
+    mpu.configure_i2c_slave(SlaveSlot::Slv0, magnetometer)
+        .unwrap();
+    trace.mark("disable");
+    mpu.disable_i2c_slave(SlaveSlot::Slv0).unwrap();
+    mpu.set_i2c_master_enabled(false).unwrap();
+    assert_golden("aux_i2c_master", &trace);
+}
+
+#[test]
+fn fifo_overflow_recovery() {
+    let chip = FifoChip::new(6, 1000);
+    let (i2c, trace) = TracingI2c::new(chip.clone());
+    let mut mpu = Mpu6050Builder::new().i2c(i2c).build().unwrap();
+    mpu.set_fifo_clock(Some(chip_time_us));
+    let schema = mpu
+        .set_fifo_sources(FifoSources::NONE.with_accel(true))
+        .unwrap();
+    mpu.set_fifo_enabled(true).unwrap();
+    let mut buf = [0; 64];
+    trace.clear();
+    trace.mark("drain");
+    chip.write_frames(2);
+    mpu.drain_fifo(&schema, &mut buf, |_| {}).unwrap();
+    trace.mark("overflow");
+    chip.write_frames(200);
+    mpu.drain_fifo(&schema, &mut buf, |_| {}).unwrap();
+    trace.mark("resume");
+    chip.write_frames(2);
+    mpu.drain_fifo(&schema, &mut buf, |_| {}).unwrap();
+    assert_golden("fifo_overflow_recovery", &trace);
+}
+
+#[test]
+fn mismatch_points_at_the_reordered_lines() {
+    let (mut mpu, trace) = traced();
+    mpu.init(&mut NoDelay).unwrap();
+    let golden = trace.render();
+    let mut lines: Vec<&str> = golden.lines().collect();
+    // WHO_AM_I before the wake write
+    lines.swap(0, 1);
+    let reordered = lines.join("\n") + "\n";
+
+    let diff = line_diff(&golden, &reordered);
+    assert!(diff.starts_with('-') || diff.starts_with('+'), "{}", diff);
+    assert_eq!(diff.lines().filter(|l| l.starts_with('-')).count(), 1);
+    assert_eq!(diff.lines().filter(|l| l.starts_with('+')).count(), 1);
+    assert_eq!(line_diff(&golden, &golden), "");
+
+    let mismatch = GoldenMismatch {
+        path: "init.trace".into(),
+        expected: Some(golden),
+        actual: reordered,
+    };
+    let message = mismatch.to_string();
+    assert!(message.contains("trace differs from init.trace"));
+    assert!(message.contains("UPDATE_GOLDENS=1"));
+}
+
+#[test]
+fn failed_transactions_are_recorded() {
+    let (mut i2c, trace) = TracingI2c::new(RegisterMock::new());
+    let mut buf = [0; 1];
+    embedded_hal::blocking::i2c::WriteRead::write_read(&mut i2c, 0x68, &[WHOAMI], &mut buf)
+        .unwrap();
+    assert_eq!(trace.render(), "R 0x68 WHO_AM_I [68]\n");
+    assert_eq!(trace.transactions(), 1);
+}
@@ -0,0 +1,124 @@
+//! Guarded sections: the allowed ops pass, a hidden extra read is a violation, panicking with
+//! debug assertions and counted otherwise, see the `traffic_guard` module.
+#![cfg(not(feature = "no-guards"))]
+
+mod common;
+
+use mpu6050::bus::DriverOp;
+use mpu6050::metrics::{MemorySink, TRAFFIC_VIOLATIONS};
+use mpu6050::register::Register;
+use mpu6050::settling::SettlingPolicy;
+use mpu6050::traffic_guard::ViolationAction;
+use mpu6050::*;
+
+use common::{NoDelay, RegisterMock};
+
+fn driver() -> (Mpu6050<RegisterMock>, &'static MemorySink) {
+    let mut mpu = Mpu6050Builder::new()
+        .i2c(RegisterMock::new())
+        .build()
+        .unwrap();
+    mpu.init(&mut NoDelay).unwrap();
+    // settling reads right after init are read again, traffic of its own
+    mpu.set_settling_policy(SettlingPolicy::Flag);
+    let sink: &'static MemorySink = Box::leak(Box::new(MemorySink::new()));
+    mpu.set_metrics_sink(Some(sink));
+    (mpu, sink)
+}
+
+#[test]
+fn allowed_ops_pass() {
+    let (mut mpu, sink) = driver();
+    let mut guard = mpu.traffic_guard();
+    guard
+        .allow(DriverOp::AccelRead, 1)
+        .allow(DriverOp::GyroRead, 1)
+        .allow(DriverOp::ConfigUpdate, 1);
+    guard.get_acc().unwrap();
+    guard.get_gyro().unwrap();
+    // read and write of the update charged once
+    guard
+        .write_register_bit(Register::PWR_MGMT_1, 5, false)
+        .unwrap();
+    assert_eq!(guard.violations(), 0);
+    for op in [
+        DriverOp::AccelRead,
+        DriverOp::GyroRead,
+        DriverOp::ConfigUpdate,
+    ] {
+        assert_eq!(guard.remaining(op), 0, "{:?}", op);
+    }
+    drop(guard);
+    // outside a section nothing is charged
+    mpu.get_temp().unwrap();
+    assert_eq!(mpu.traffic_violations(), 0);
+    assert_eq!(mpu.last_traffic_violation(), None);
+    assert_eq!(sink.counter_value(TRAFFIC_VIOLATIONS), 0);
+}
+
+#[cfg(debug_assertions)]
+#[test]
+#[should_panic(expected = "TempRead")]
+fn hidden_read_panics_in_debug_builds() {
+    let (mut mpu, _) = driver();
+    let mut guard = mpu.traffic_guard();
+    guard.allow(DriverOp::AccelRead, 1);
+    guard.get_acc().unwrap();
+    let _ = guard.get_temp();
+}
+
+#[test]
+fn counted_violations_let_the_transaction_run() {
+    let (mut mpu, sink) = driver();
+    let mut guard = mpu.traffic_guard();
+    guard
+        .on_violation(ViolationAction::Count)
+        .allow(DriverOp::AccelRead, 1);
+    guard.get_acc().unwrap();
+    assert!(guard.get_temp().unwrap().is_finite());
+    // the permission is used up
+    guard.get_acc().unwrap();
+    assert_eq!(guard.violations(), 2);
+    drop(guard);
+    assert_eq!(mpu.traffic_violations(), 2);
+    assert_eq!(mpu.last_traffic_violation(), Some(DriverOp::AccelRead));
+    assert_eq!(mpu.debug_state().traffic_violations, 2);
+    assert_eq!(sink.counter_value(TRAFFIC_VIOLATIONS), 2);
+    // a new section starts over, the driver's count does not
+    let guard = mpu.traffic_guard();
+    assert_eq!(guard.violations(), 0);
+    assert_eq!(guard.traffic_violations(), 2);
+}
+
+#[test]
+fn sized_ops_and_nested_sections() {
+    let (mut mpu, _) = driver();
+    let mut outer = mpu.traffic_guard();
+    outer
+        .on_violation(ViolationAction::Count)
+        .allow(DriverOp::RegisterRead(2), 2)
+        .allow(DriverOp::TempRead, 1);
+    outer.read_register(Register::WHO_AM_I).unwrap();
+    assert_eq!(outer.violations(), 0);
+    let mut buf = [0; 3];
+    outer
+        .read_registers(Register::SMPLRT_DIV, &mut buf)
+        .unwrap();
+    assert_eq!(outer.violations(), 1);
+    assert_eq!(
+        outer.last_traffic_violation(),
+        Some(DriverOp::RegisterRead(3))
+    );
+    {
+        let mut inner = outer.traffic_guard();
+        inner.on_violation(ViolationAction::Count);
+        inner.get_temp().unwrap();
+        assert_eq!(inner.violations(), 1);
+        assert_eq!(inner.remaining(DriverOp::TempRead), 0);
+    }
+    assert_eq!(outer.violations(), 1);
+    assert_eq!(outer.remaining(DriverOp::TempRead), 1);
+    outer.get_temp().unwrap();
+    assert_eq!(outer.violations(), 1);
+    assert_eq!(outer.traffic_violations(), 2);
+}
@@ -0,0 +1,196 @@
+//! Transaction shapes of register reads with and without repeated start, see the `transfer`
+//! module.
+
+mod common;
+
+use mpu6050::device::{ACC_REGX_H, DEFAULT_SLAVE_ADDR, FIFO_COUNT_H, FIFO_R_W, WHOAMI};
+use mpu6050::fifo::FifoSources;
+use mpu6050::trace::{TraceHandle, TracingI2c};
+use mpu6050::transfer::{TransferI2c, TransferMode};
+use mpu6050::*;
+
+use embedded_hal::blocking::i2c::{Read, Write};
+
+use common::{NoDelay, RegisterMock, SharedBus};
+
+type Mpu = Mpu6050<TransferI2c<TracingI2c<SharedBus>>>;
+
+/// driver after init in `mode`, with the init transactions removed from the trace
+fn driver(mode: TransferMode) -> (SharedBus, Mpu, TraceHandle) {
+    let bus = SharedBus::new(&[DEFAULT_SLAVE_ADDR]);
+    let (i2c, trace) = TracingI2c::new(bus.clone());
+    let mut mpu = Mpu6050Builder::new()
+        .i2c(TransferI2c::new(i2c, mode))
+        .build()
+        .unwrap();
+    mpu.init(&mut NoDelay).unwrap();
+    trace.clear();
+    (bus, mpu, trace)
+}
+
+const FRAME: [u8; 14] = [
+    0x01, 0x02, 0x03, 0x04, 0x40, 0x00, 0xf0, 0x00, 0x00, 0x10, 0x00, 0x20, 0xff, 0xd0,
+];
+
+#[test]
+fn default_is_the_repeated_start() {
+    assert_eq!(TransferMode::default(), TransferMode::RepeatedStart);
+    let (_, mut mpu, trace) = driver(TransferMode::RepeatedStart);
+    assert_eq!(mpu.get_transfer_mode(), TransferMode::RepeatedStart);
+    assert_eq!(mpu.read_byte(WHOAMI).unwrap(), 0x68);
+    mpu.read_bytes(ACC_REGX_H, &mut [0; 6]).unwrap();
+    assert_eq!(
+        trace.render(),
+        "R 0x68 WHO_AM_I [68]\n\
+         R 0x68 ACCEL_XOUT_H [00 00 00 00 40 00]\n"
+    );
+}
+
+#[test]
+fn stop_start_writes_the_pointer_before_a_bare_read() {
+    let (_, mut mpu, trace) = driver(TransferMode::StopStart);
+    assert_eq!(mpu.read_byte(WHOAMI).unwrap(), 0x68);
+    mpu.read_bytes(ACC_REGX_H, &mut [0; 6]).unwrap();
+    assert_eq!(
+        trace.render(),
+        "W 0x68 WHO_AM_I []\n\
+         R 0x68 - [68]\n\
+         W 0x68 ACCEL_XOUT_H []\n\
+         R 0x68 - [00 00 00 00 40 00]\n"
+    );
+}
+
+#[test]
+fn burst_reads_match_in_both_modes() {
+    let mut samples = Vec::new();
+    let mut traces = Vec::new();
+    for mode in [TransferMode::RepeatedStart, TransferMode::StopStart] {
+        let (bus, mut mpu, trace) = driver(mode);
+        push_frame(&bus, &FRAME);
+        samples.push(burst(&mut mpu));
+        traces.push(trace.render());
+    }
+    assert_eq!(samples, [FRAME; 2]);
+    let payload = "[01 02 03 04 40 00 f0 00 00 10 00 20 ff d0]";
+    assert_eq!(traces[0], format!("R 0x68 ACCEL_XOUT_H {}\n", payload));
+    assert_eq!(
+        traces[1],
+        format!("W 0x68 ACCEL_XOUT_H []\nR 0x68 - {}\n", payload)
+    );
+}
+
+/// ACCEL_XOUT_H..GYRO_ZOUT_H in one read
+fn burst(mpu: &mut Mpu) -> [u8; 14] {
+    let mut buf = [0; 14];
+    mpu.read_bytes(ACC_REGX_H, &mut buf).unwrap();
+    buf
+}
+
+fn push_frame(bus: &SharedBus, frame: &[u8; 14]) {
+    bus.device(DEFAULT_SLAVE_ADDR, |mock| mock.set_frame(frame));
+}
+
+fn push_fifo(bus: &SharedBus, bytes: &[u8]) {
+    bus.device(DEFAULT_SLAVE_ADDR, |mock| mock.fifo.extend(bytes));
+}
+
+fn fifo_len(bus: &SharedBus) -> usize {
+    bus.device(DEFAULT_SLAVE_ADDR, |mock| mock.fifo.len())
+}
+
+#[test]
+fn fifo_pointer_does_not_advance() {
+    // the mock follows the datasheet: a bare read continues at the pointer, which advances
+    // except at FIFO_R_W
+    let mut mock = RegisterMock::new();
+    mock.fifo.extend([1, 2, 3, 4, 5]);
+    let mut buf = [0; 2];
+    mock.write(DEFAULT_SLAVE_ADDR, &[FIFO_COUNT_H]).unwrap();
+    mock.read(DEFAULT_SLAVE_ADDR, &mut buf).unwrap();
+    assert_eq!(buf, [0, 5]);
+    // the count leaves the pointer at FIFO_R_W
+    assert_eq!(mock.pointer, FIFO_R_W);
+    mock.read(DEFAULT_SLAVE_ADDR, &mut buf).unwrap();
+    assert_eq!(buf, [1, 2]);
+    mock.read(DEFAULT_SLAVE_ADDR, &mut buf).unwrap();
+    assert_eq!(buf, [3, 4]);
+    assert_eq!(mock.pointer, FIFO_R_W);
+}
+
+#[test]
+fn stop_start_fifo_drain_resends_the_pointer() {
+    let (bus, mut mpu, trace) = driver(TransferMode::StopStart);
+    let schema = mpu
+        .set_fifo_sources(FifoSources::NONE.with_accel(true))
+        .unwrap();
+    let frame = [0x40, 0x00, 0x00, 0x00, 0xc0, 0x00];
+    for _ in 0..2 {
+        push_fifo(&bus, &frame);
+    }
+    trace.clear();
+
+    let mut buf = [0; 32];
+    let mut frames = Vec::new();
+    assert_eq!(
+        mpu.drain_fifo(&schema, &mut buf, |f| frames.push(f.acc))
+            .unwrap(),
+        2
+    );
+    assert_eq!(frames, [Some([0x4000, 0, -0x4000]); 2]);
+    // the count leaves the pointer at FIFO_R_W, the drain writes it anyway and reads both
+    // frames in one bare read
+    assert_eq!(
+        trace.render(),
+        "W 0x68 FIFO_COUNTH []\n\
+         R 0x68 - [00 0c]\n\
+         W 0x68 FIFO_R_W []\n\
+         R 0x68 - [40 00 00 00 c0 00 40 00 00 00 c0 00]\n"
+    );
+    assert_eq!(fifo_len(&bus), 0);
+}
+
+#[test]
+fn switching_modes_between_operations() {
+    let (bus, mut mpu, trace) = driver(TransferMode::RepeatedStart);
+    push_frame(&bus, &FRAME);
+    let schema = mpu
+        .set_fifo_sources(FifoSources::NONE.with_gyro(true))
+        .unwrap();
+    let expected = burst(&mut mpu);
+    trace.clear();
+
+    let mut shapes = Vec::new();
+    for mode in [
+        TransferMode::StopStart,
+        TransferMode::RepeatedStart,
+        TransferMode::StopStart,
+        TransferMode::StopStart,
+        TransferMode::RepeatedStart,
+    ] {
+        mpu.set_transfer_mode(mode);
+        assert_eq!(mpu.get_transfer_mode(), mode);
+        assert_eq!(burst(&mut mpu), expected);
+        push_fifo(&bus, &[0, 1, 0, 2, 0, 3]);
+        let mut gyro = None;
+        let mut buf = [0; 6];
+        assert_eq!(
+            mpu.drain_fifo(&schema, &mut buf, |f| gyro = Some(f.gyro))
+                .unwrap(),
+            1
+        );
+        assert_eq!(gyro, Some([Some(1), Some(2), Some(3)]));
+        let entries = trace.entries();
+        trace.clear();
+        shapes.push((mode, entries.len()));
+    }
+    // sample, count and drain: three reads, each one or two transactions
+    let expected_shapes: Vec<_> = shapes
+        .iter()
+        .map(|(mode, _)| match mode {
+            TransferMode::RepeatedStart => (*mode, 3),
+            TransferMode::StopStart => (*mode, 6),
+        })
+        .collect();
+    assert_eq!(shapes, expected_shapes);
+    assert_eq!(fifo_len(&bus), 0);
+}
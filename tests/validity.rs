@@ -0,0 +1,517 @@
+//! Calibration validity: synthetic streams crossing each rule of the policy alone and
+//! together, the status hook, the metadata blob, and the metadata recorded by the driver, see
+//! the `validity` module.
+
+mod common;
+
+use std::sync::Mutex;
+
+use mpu6050::calibration::CalibrationConfig;
+use mpu6050::device::*;
+use mpu6050::log_header::CalibrationMethod;
+use mpu6050::packed::DecodeError;
+use mpu6050::provenance::{FieldGroup, Provenance, SampleProvenance};
+use mpu6050::setup::{AutoSetupOptions, ThermalWait};
+use mpu6050::synthetic::frame_bytes;
+use mpu6050::validity::*;
+use mpu6050::*;
+
+use common::{NoDelay, SharedBus};
+
+const ADDR: u8 = DEFAULT_SLAVE_ADDR;
+
+const SOFTWARE: CalibrationKind = CalibrationKind::SoftwareOffsets;
+
+/// 10 °C from the calibration, shocks above 3 g, 100 time units
+const POLICY: CalibrationPolicy = CalibrationPolicy {
+    max_age: Some(100),
+    max_temp_delta_c: Some(10.),
+    invalidate_on_shock_g: Some(3.),
+    max_scale_drift: None,
+    on_change: None,
+};
+
+fn at(temp: f32) -> MpuSample {
+    MpuSample::new(Vec3A::Z, Vec3A::ZERO, temp)
+}
+
+fn shock(g: f32) -> MpuSample {
+    MpuSample::new(Vec3A::new(0., 0., g), Vec3A::ZERO, 25.)
+}
+
+fn reasons(reasons: &[InvalidationReason]) -> InvalidationReasons {
+    reasons
+        .iter()
+        .fold(InvalidationReasons::EMPTY, |set, reason| set.with(*reason))
+}
+
+fn degraded(list: &[InvalidationReason]) -> CalibrationStatus {
+    CalibrationStatus::Degraded {
+        reasons: reasons(list),
+    }
+}
+
+fn expired(list: &[InvalidationReason]) -> CalibrationStatus {
+    CalibrationStatus::Expired {
+        reasons: reasons(list),
+    }
+}
+
+/// software offsets calibrated at 25 °C at time 0
+fn calibrated(policy: CalibrationPolicy) -> CalibrationMonitor {
+    let mut monitor = CalibrationMonitor::new();
+    monitor.set_policy(Some(policy));
+    let meta = CalibrationMeta {
+        timestamp: Some(0),
+        temperature_c: Some(25.),
+        ..CalibrationMeta::new(CalibrationMethod::Static)
+    };
+    monitor.set_meta(SOFTWARE, Some(meta));
+    monitor
+}
+
+/// statuses after each sample
+fn statuses(monitor: &mut CalibrationMonitor, samples: &[MpuSample]) -> Vec<CalibrationStatus> {
+    samples
+        .iter()
+        .map(|sample| {
+            monitor.observe(sample);
+            monitor.status()
+        })
+        .collect()
+}
+
+#[test]
+fn temperature_degrades_and_recovers() {
+    use InvalidationReason::Temperature;
+    let policy = CalibrationPolicy {
+        max_temp_delta_c: Some(10.),
+        ..CalibrationPolicy::NONE
+    };
+    let mut monitor = calibrated(policy);
+    let stream = [at(25.), at(35.), at(35.5), at(14.), at(15.), at(30.)];
+    assert_eq!(
+        statuses(&mut monitor, &stream),
+        [
+            CalibrationStatus::Valid,
+            CalibrationStatus::Valid,
+            degraded(&[Temperature]),
+            degraded(&[Temperature]),
+            CalibrationStatus::Valid,
+            CalibrationStatus::Valid,
+        ]
+    );
+    assert!(monitor.meta(SOFTWARE).unwrap().latched.is_empty());
+}
+
+#[test]
+fn shock_expires_until_replaced() {
+    use InvalidationReason::Shock;
+    let policy = CalibrationPolicy {
+        invalidate_on_shock_g: Some(3.),
+        ..CalibrationPolicy::NONE
+    };
+    let mut monitor = calibrated(policy);
+    let stream = [shock(1.), shock(3.), shock(-3.1), shock(1.), at(80.)];
+    assert_eq!(
+        statuses(&mut monitor, &stream),
+        [
+            CalibrationStatus::Valid,
+            CalibrationStatus::Valid,
+            expired(&[Shock]),
+            expired(&[Shock]),
+            expired(&[Shock]),
+        ]
+    );
+    // on any axis
+    let mut monitor = calibrated(policy);
+    monitor.observe(&MpuSample::new(Vec3A::new(2., 2., 2.), Vec3A::ZERO, 25.));
+    assert_eq!(monitor.status(), expired(&[Shock]));
+
+    // a new calibration clears it
+    monitor.set_meta(
+        SOFTWARE,
+        Some(CalibrationMeta::new(CalibrationMethod::Static)),
+    );
+    assert_eq!(monitor.status(), CalibrationStatus::Valid);
+}
+
+#[test]
+fn age_expires_from_the_application_time() {
+    use InvalidationReason::Age;
+    let policy = CalibrationPolicy {
+        max_age: Some(100),
+        ..CalibrationPolicy::NONE
+    };
+    let mut monitor = calibrated(policy);
+    monitor.set_time(100);
+    assert_eq!(monitor.status(), CalibrationStatus::Valid);
+    monitor.set_time(101);
+    assert_eq!(monitor.status(), expired(&[Age]));
+    // time going backwards does not revive it
+    monitor.set_time(0);
+    assert_eq!(monitor.status(), expired(&[Age]));
+
+    // stamped by the next time, then aged from there
+    monitor.set_meta(
+        SOFTWARE,
+        Some(CalibrationMeta::new(CalibrationMethod::External)),
+    );
+    monitor.set_time(500);
+    assert_eq!(monitor.meta(SOFTWARE).unwrap().timestamp, Some(500));
+    monitor.set_time(600);
+    assert_eq!(monitor.status(), CalibrationStatus::Valid);
+    monitor.set_time(601);
+    assert_eq!(monitor.status(), expired(&[Age]));
+}
+
+#[test]
+fn rules_combine() {
+    use InvalidationReason::*;
+    let mut monitor = calibrated(POLICY);
+    let stream = [at(40.), shock(4.), at(45.), at(25.)];
+    assert_eq!(
+        statuses(&mut monitor, &stream),
+        [
+            degraded(&[Temperature]),
+            // latched, the temperature of the shock sample is within the limit again
+            expired(&[Shock]),
+            expired(&[Temperature, Shock]),
+            expired(&[Shock]),
+        ]
+    );
+    monitor.set_time(1000);
+    assert_eq!(monitor.status(), expired(&[Age, Shock]));
+    monitor.observe(&at(-20.));
+    assert_eq!(monitor.status(), expired(&[Age, Temperature, Shock]));
+    let reasons: Vec<_> = monitor.status().reasons().iter().collect();
+    assert_eq!(reasons, [Age, Temperature, Shock]);
+}
+
+#[test]
+fn kinds_are_tracked_separately() {
+    use InvalidationReason::*;
+    let mut monitor = calibrated(POLICY);
+    let scale = CalibrationMeta {
+        temperature_c: Some(50.),
+        ..CalibrationMeta::new(CalibrationMethod::Referenced)
+    };
+    monitor.set_meta(CalibrationKind::ScaleFactors, Some(scale));
+
+    monitor.observe(&at(45.));
+    assert_eq!(monitor.status_of(SOFTWARE), degraded(&[Temperature]));
+    assert_eq!(
+        monitor.status_of(CalibrationKind::ScaleFactors),
+        CalibrationStatus::Valid
+    );
+    assert_eq!(
+        monitor.status_of(CalibrationKind::HardwareOffsets),
+        CalibrationStatus::Valid
+    );
+    assert_eq!(monitor.status(), degraded(&[Temperature]));
+
+    // the scale factors have no timestamp: stamped at 200, the offsets are from 0
+    monitor.set_time(200);
+    assert_eq!(monitor.status_of(SOFTWARE), expired(&[Temperature, Age]));
+    assert_eq!(
+        monitor.status_of(CalibrationKind::ScaleFactors),
+        CalibrationStatus::Valid
+    );
+    // the worst of both, the reasons of all
+    monitor.observe(&at(80.));
+    assert_eq!(
+        monitor.status_of(CalibrationKind::ScaleFactors),
+        degraded(&[Temperature])
+    );
+    assert_eq!(monitor.status(), expired(&[Age, Temperature]));
+
+    // untracked again
+    monitor.set_meta(SOFTWARE, None);
+    assert_eq!(monitor.status(), degraded(&[Temperature]));
+}
+
+#[test]
+fn first_valid_temperature_is_the_reference() {
+    let mut monitor = CalibrationMonitor::new();
+    monitor.set_policy(Some(POLICY));
+    monitor.set_meta(
+        SOFTWARE,
+        Some(CalibrationMeta::new(CalibrationMethod::Background)),
+    );
+
+    // a partial read without temperature is skipped
+    let partial = at(f32::NAN)
+        .with_provenance(SampleProvenance::MEASURED.with(FieldGroup::Temp, Provenance::Invalid));
+    monitor.observe(&partial);
+    assert_eq!(monitor.meta(SOFTWARE).unwrap().temperature_c, None);
+    monitor.observe(&at(50.));
+    assert_eq!(monitor.meta(SOFTWARE).unwrap().temperature_c, Some(50.));
+    monitor.observe(&at(40.));
+    assert_eq!(monitor.status(), CalibrationStatus::Valid);
+    monitor.observe(&at(39.));
+    assert!(matches!(
+        monitor.status(),
+        CalibrationStatus::Degraded { .. }
+    ));
+
+    // nor is an invalid accel group a shock
+    let partial = shock(10.)
+        .with_provenance(SampleProvenance::MEASURED.with(FieldGroup::Acc, Provenance::Invalid));
+    monitor.observe(&partial);
+    assert!(!monitor.status().is_expired());
+}
+
+#[test]
+fn off_without_a_policy() {
+    let mut monitor = CalibrationMonitor::new();
+    monitor.set_meta(
+        SOFTWARE,
+        Some(CalibrationMeta::new(CalibrationMethod::Static)),
+    );
+    let stream = [shock(16.), at(-40.), at(85.)];
+    assert!(statuses(&mut monitor, &stream)
+        .iter()
+        .all(|status| *status == CalibrationStatus::Valid));
+    monitor.set_time(u64::MAX);
+    assert_eq!(monitor.status(), CalibrationStatus::Valid);
+    let meta = monitor.meta(SOFTWARE).unwrap();
+    assert_eq!(meta.temperature_c, None);
+    assert!(meta.latched.is_empty());
+
+    // removing the policy keeps what was latched, drops the temperature
+    use InvalidationReason::*;
+    let mut monitor = calibrated(POLICY);
+    statuses(&mut monitor, &[shock(4.), at(50.)]);
+    monitor.set_policy(None);
+    assert_eq!(monitor.status(), expired(&[Shock]));
+}
+
+static TRANSITIONS: Mutex<Vec<CalibrationStatus>> = Mutex::new(Vec::new());
+
+fn record(status: CalibrationStatus) {
+    TRANSITIONS.lock().unwrap().push(status);
+}
+
+#[test]
+fn hook_runs_on_transitions_only() {
+    use InvalidationReason::*;
+    let mut monitor = calibrated(CalibrationPolicy {
+        on_change: Some(record),
+        ..POLICY
+    });
+    let stream = [
+        at(25.),
+        at(40.),
+        at(41.),
+        at(30.),
+        at(20.),
+        shock(5.),
+        shock(5.),
+        at(60.),
+    ];
+    statuses(&mut monitor, &stream);
+    monitor.set_time(1000);
+    assert_eq!(
+        *TRANSITIONS.lock().unwrap(),
+        [
+            degraded(&[Temperature]),
+            CalibrationStatus::Valid,
+            expired(&[Shock]),
+            expired(&[Temperature, Shock]),
+            expired(&[Age, Temperature, Shock]),
+        ]
+    );
+}
+
+#[test]
+fn meta_round_trips() {
+    let full = CalibrationMeta {
+        method: CalibrationMethod::Referenced,
+        timestamp: Some(u64::MAX - 1),
+        temperature_c: Some(-12.25),
+        gyro_std_dev: Some(0.002),
+        acc_std_dev: Some(0.),
+        latched: reasons(&[InvalidationReason::Age, InvalidationReason::Shock]),
+    };
+    let bare = CalibrationMeta::new(CalibrationMethod::External);
+    for meta in [
+        full,
+        bare,
+        CalibrationMeta {
+            timestamp: Some(0),
+            ..bare
+        },
+        CalibrationMeta {
+            acc_std_dev: None,
+            latched: InvalidationReasons::EMPTY,
+            ..full
+        },
+    ] {
+        let bytes = meta.to_bytes();
+        assert_eq!(bytes.len(), CALIBRATION_META_LEN);
+        assert_eq!(CalibrationMeta::from_bytes(&bytes), Ok(meta));
+    }
+
+    let bytes = full.to_bytes();
+    assert_eq!(bytes[0], CALIBRATION_META_VERSION);
+    assert_eq!(
+        CalibrationMeta::from_bytes(&bytes[..CALIBRATION_META_LEN - 1]),
+        Err(DecodeError::Truncated(CALIBRATION_META_LEN))
+    );
+    let mut corrupt = bytes;
+    corrupt[8] ^= 1;
+    assert_eq!(
+        CalibrationMeta::from_bytes(&corrupt),
+        Err(DecodeError::CrcMismatch)
+    );
+    let mut newer = bytes;
+    newer[0] = CALIBRATION_META_VERSION + 1;
+    assert_eq!(
+        CalibrationMeta::from_bytes(&newer),
+        Err(DecodeError::BadHeader)
+    );
+}
+
+#[test]
+fn restored_meta_keeps_its_state() {
+    let mut monitor = calibrated(POLICY);
+    monitor.observe(&shock(4.));
+    let stored = monitor.meta(SOFTWARE).unwrap().to_bytes();
+
+    // after a reboot
+    let mut monitor = CalibrationMonitor::new();
+    monitor.set_policy(Some(POLICY));
+    monitor.set_meta(
+        SOFTWARE,
+        Some(CalibrationMeta::from_bytes(&stored).unwrap()),
+    );
+    assert_eq!(monitor.status(), expired(&[InvalidationReason::Shock]));
+    monitor.set_time(101);
+    assert_eq!(
+        monitor.status(),
+        expired(&[InvalidationReason::Age, InvalidationReason::Shock])
+    );
+}
+
+fn driver() -> (Mpu6050<SharedBus>, SharedBus) {
+    let bus = SharedBus::new(&[ADDR]);
+    let mut mpu = Mpu6050Builder::new().i2c(bus.clone()).build().unwrap();
+    mpu.init(&mut NoDelay).unwrap();
+    (mpu, bus)
+}
+
+/// frame at ±2 g and ±250 °/s, 36.53 °C
+fn frame(acc_g: Vec3A) -> [u8; 14] {
+    frame_bytes(acc_g, Vec3A::ZERO, AccelRange::G2, GyroRange::D250)
+}
+
+#[test]
+fn driver_records_and_evaluates() {
+    let (mut mpu, bus) = driver();
+    assert_eq!(mpu.calibration_policy().map(|p| p.max_age), None);
+    assert_eq!(mpu.calibration_meta(SOFTWARE), None);
+
+    let options = AutoSetupOptions {
+        thermal_wait: Some(ThermalWait {
+            max_ms: 200,
+            interval_ms: 10,
+            tolerance_c: 0.05,
+            stable_readings: 4,
+        }),
+        // the mock does not respond to self-test
+        min_actuation_g: 0.0,
+        gyro_samples: 20,
+        device_is_level: true,
+        acc_samples: 20,
+        ..AutoSetupOptions::default()
+    };
+    bus.device(ADDR, |m| m.set_frame(&frame(Vec3A::Z)));
+    let report = mpu.auto_setup(&mut NoDelay, options, None).unwrap();
+    let meta = mpu.calibration_meta(SOFTWARE).unwrap();
+    assert_eq!(meta.method, CalibrationMethod::Static);
+    assert_eq!(meta.temperature_c, report.temperature);
+    assert!(meta.temperature_c.is_some());
+    assert_eq!(meta.gyro_std_dev, report.gyro_std_dev);
+    assert_eq!(meta.acc_std_dev, report.acc_std_dev);
+    assert_eq!(meta.timestamp, None);
+    assert_eq!(mpu.calibration_status(), CalibrationStatus::Valid);
+
+    mpu.store_gyro_offset_in_hardware().unwrap();
+    assert_eq!(
+        mpu.calibration_meta(CalibrationKind::HardwareOffsets),
+        Some(meta)
+    );
+
+    // a shock in the sample stream expires both
+    mpu.set_calibration_policy(Some(CalibrationPolicy {
+        invalidate_on_shock_g: Some(1.5),
+        ..CalibrationPolicy::NONE
+    }));
+    mpu.get_acc().unwrap();
+    assert_eq!(mpu.calibration_status(), CalibrationStatus::Valid);
+    bus.device(ADDR, |m| m.set_frame(&frame(Vec3A::splat(1.))));
+    mpu.read_into_views::<1>(&mut []).unwrap();
+    let shock = expired(&[InvalidationReason::Shock]);
+    assert_eq!(mpu.calibration_status(), shock);
+    assert_eq!(mpu.calibration_status_of(SOFTWARE), shock);
+    assert_eq!(
+        mpu.calibration_status_of(CalibrationKind::HardwareOffsets),
+        shock
+    );
+    assert_eq!(mpu.debug_state().calibration_validity.status(), shock);
+
+    // the application's own scale factors, aged by its clock
+    mpu.set_calibration_policy(Some(CalibrationPolicy {
+        max_age: Some(10),
+        ..CalibrationPolicy::NONE
+    }));
+    mpu.set_calibration_meta(SOFTWARE, None);
+    mpu.set_calibration_meta(CalibrationKind::HardwareOffsets, None);
+    let factors = CalibrationMeta {
+        timestamp: Some(5),
+        ..CalibrationMeta::new(CalibrationMethod::External)
+    };
+    mpu.set_calibration_meta(CalibrationKind::ScaleFactors, Some(factors));
+    mpu.set_calibration_time(15);
+    assert_eq!(mpu.calibration_status(), CalibrationStatus::Valid);
+    mpu.set_calibration_time(16);
+    assert_eq!(
+        mpu.calibration_status(),
+        expired(&[InvalidationReason::Age])
+    );
+}
+
+#[test]
+fn background_calibration_records_its_method() {
+    let (mut mpu, bus) = driver();
+    bus.device(ADDR, |m| m.set_frame(&frame(Vec3A::Z)));
+    mpu.start_background_calibration(CalibrationConfig {
+        samples: 10,
+        ..CalibrationConfig::default()
+    });
+    for _ in 0..10 {
+        mpu.get_gyro().unwrap();
+    }
+    assert_eq!(mpu.background_calibration_status(), None);
+    let meta = mpu.calibration_meta(SOFTWARE).unwrap();
+    assert_eq!(meta.method, CalibrationMethod::Background);
+    assert_eq!(meta.gyro_std_dev, Some(0.));
+    assert_eq!(meta.acc_std_dev, None);
+
+    // staged results are recorded when committed
+    mpu.set_calibration_meta(SOFTWARE, None);
+    mpu.start_background_calibration(CalibrationConfig {
+        samples: 10,
+        manual_commit: true,
+        ..CalibrationConfig::default()
+    });
+    for _ in 0..10 {
+        mpu.get_gyro().unwrap();
+    }
+    assert_eq!(mpu.calibration_meta(SOFTWARE), None);
+    mpu.commit_background_calibration().unwrap();
+    assert_eq!(
+        mpu.calibration_meta(SOFTWARE).map(|meta| meta.method),
+        Some(CalibrationMethod::Background)
+    );
+}
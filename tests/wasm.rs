@@ -0,0 +1,59 @@
+//! The `fusion` layer builds for `wasm32-unknown-unknown` without `embedded-hal`, the
+//! `log_analysis` example with it. Runs cargo on this package in a separate target directory.
+
+use std::process::{Command, Output};
+
+fn cargo(args: &[&str]) -> Output {
+    let cargo = std::env::var("CARGO").unwrap_or_else(|_| "cargo".into());
+    let manifest = concat!(env!("CARGO_MANIFEST_DIR"), "/Cargo.toml");
+    Command::new(cargo)
+        .args(args)
+        .args(["--manifest-path", manifest])
+        .env(
+            "CARGO_TARGET_DIR",
+            concat!(env!("CARGO_MANIFEST_DIR"), "/target/wasm-check"),
+        )
+        .output()
+        .expect("cargo runs")
+}
+
+fn stderr(output: &Output) -> String {
+    String::from_utf8_lossy(&output.stderr).into_owned()
+}
+
+const FUSION: [&str; 3] = ["--no-default-features", "--features", "fusion,std"];
+const WASM: [&str; 2] = ["--target", "wasm32-unknown-unknown"];
+
+/// runs `cargo <args>` for wasm32-unknown-unknown, skipped where its std is not installed
+fn check_wasm(args: &[&str]) {
+    let output = cargo(&[args, &FUSION[..], &WASM[..]].concat());
+    if stderr(&output).contains("target may not be installed") {
+        eprintln!("wasm32-unknown-unknown not installed, skipped");
+        return;
+    }
+    assert!(output.status.success(), "{}", stderr(&output));
+}
+
+#[test]
+fn fusion_has_no_embedded_hal() {
+    let output = cargo(
+        &[
+            &["tree", "--edges", "normal", "--prefix", "none"],
+            &FUSION[..],
+        ]
+        .concat(),
+    );
+    assert!(output.status.success(), "{}", stderr(&output));
+    let tree = String::from_utf8_lossy(&output.stdout);
+    assert!(!tree.contains("embedded-hal"), "{tree}");
+}
+
+#[test]
+fn fusion_builds_for_wasm() {
+    check_wasm(&["check", "--lib"]);
+}
+
+#[test]
+fn log_analysis_builds_for_wasm() {
+    check_wasm(&["build", "--example", "log_analysis"]);
+}